@@ -23,6 +23,7 @@ type HandlerOptions struct {
 	appCodec              codec.Codec
 	govkeeper             govkeeper.Keeper // You'll need the keeper to access stored mrenclave hash
 	IBCKeeper             *keeper.Keeper
+	ComputeKeeper         *compute.Keeper
 	WasmConfig            *compute.WasmConfig
 	TXCounterStoreService store.KVStoreService
 }
@@ -40,6 +41,10 @@ func NewAnteHandler(options HandlerOptions) (sdk.AnteHandler, error) {
 		return nil, sdkerrors.ErrLogic.Wrap("sign mode handler is required for ante builder")
 	}
 
+	if options.ComputeKeeper == nil || options.WasmConfig == nil {
+		return nil, sdkerrors.ErrLogic.Wrap("compute keeper and wasm config are required for ante builder")
+	}
+
 	sigGasConsumer := options.HandlerOptions.SigGasConsumer
 	if sigGasConsumer == nil {
 		sigGasConsumer = ante.DefaultSigVerificationGasConsumer
@@ -60,6 +65,7 @@ func NewAnteHandler(options HandlerOptions) (sdk.AnteHandler, error) {
 		ante.NewSigGasConsumeDecorator(options.HandlerOptions.AccountKeeper, sigGasConsumer),
 		ante.NewSigVerificationDecorator(options.HandlerOptions.AccountKeeper, options.HandlerOptions.SignModeHandler),
 		ante.NewIncrementSequenceDecorator(options.HandlerOptions.AccountKeeper),
+		compute.NewPrewarmDecorator(options.ComputeKeeper, options.WasmConfig.EnclaveCacheSize), // after signature verification, so only valid txs are hinted
 	}
 
 	return sdk.ChainAnteDecorators(anteDecorators...), nil
@@ -1,6 +1,7 @@
 package app
 
 import (
+	"encoding/hex"
 	"fmt"
 	"io"
 	"net/http"
@@ -377,6 +378,7 @@ func NewSecretNetworkApp(
 		appCodec:              app.appCodec,
 		govkeeper:             *app.AppKeepers.GovKeeper,
 		IBCKeeper:             app.AppKeepers.IbcKeeper,
+		ComputeKeeper:         app.AppKeepers.ComputeKeeper,
 		WasmConfig:            computeConfig,
 		TXCounterStoreService: app.AppKeepers.ComputeKeeper.GetStoreService(),
 	})
@@ -499,6 +501,8 @@ func (app *SecretNetworkApp) RegisterAPIRoutes(apiSvr *api.Server, apiConfig con
 	// Register legacy and grpc-gateway routes for all modules.
 	ModuleBasics().RegisterGRPCGatewayRoutes(clientCtx, apiSvr.GRPCGatewayRouter)
 
+	app.registerCodeAttestationRoute(apiSvr.Router)
+
 	// register swagger API from root so that other applications can override easily
 	if apiConfig.Swagger {
 		RegisterSwaggerAPI(clientCtx, apiSvr.Router)
@@ -518,6 +522,33 @@ func RegisterSwaggerAPI(_ client.Context, rtr *mux.Router) {
 	rtr.PathPrefix("/openapi/").Handler(statikServer)
 }
 
+// registerCodeAttestationRoute serves the enclave's attestations of code hashes. They're local to
+// the node, so they're served by the node's API server instead of through the gRPC queries.
+func (app *SecretNetworkApp) registerCodeAttestationRoute(rtr *mux.Router) {
+	rtr.HandleFunc("/compute/v1beta1/code_attestation/{code_hash}", func(w http.ResponseWriter, r *http.Request) {
+		codeHash, err := hex.DecodeString(mux.Vars(r)["code_hash"])
+		if err != nil {
+			http.Error(w, "invalid code hash", http.StatusBadRequest)
+			return
+		}
+
+		ctx, err := app.BaseApp.CreateQueryContext(0, false)
+		if err != nil {
+			http.Error(w, err.Error(), http.StatusServiceUnavailable)
+			return
+		}
+
+		res, err := app.AppKeepers.ComputeKeeper.AttestCodeHash(ctx, codeHash)
+		if err != nil {
+			http.Error(w, err.Error(), http.StatusBadRequest)
+			return
+		}
+
+		w.Header().Set("Content-Type", "application/json")
+		_, _ = w.Write(res)
+	}).Methods(http.MethodGet)
+}
+
 // BlockedAddrs returns all the app's module account addresses that are not
 // allowed to receive external tokens.
 func (app *SecretNetworkApp) BlockedAddrs() map[string]bool {
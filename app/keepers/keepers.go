@@ -366,6 +366,7 @@ func (ak *SecretAppKeepers) InitCustomKeepers(
 		regRouter,
 		reg.EnclaveApi{},
 		homePath,
+		app.ChainID(),
 		bootstrap,
 	)
 	ak.RegKeeper = &regKeeper
@@ -524,6 +525,7 @@ func (ak *SecretAppKeepers) InitCustomKeepers(
 		ak.TransferKeeper,
 		ak.IbcKeeper.ChannelKeeper,
 		ak.IbcSwitchKeeper,
+		ak.ICAHostKeeper,
 		app.MsgServiceRouter(),
 		app.GRPCQueryRouter(),
 		computeDir,
@@ -534,6 +536,7 @@ func (ak *SecretAppKeepers) InitCustomKeepers(
 		&app.LastTxManager,
 		authtypes.NewModuleAddress(govtypes.ModuleName).String(),
 	)
+	computeKeeper.SetCommitMultiStore(app.CommitMultiStore())
 	ak.ComputeKeeper = &computeKeeper
 	wasmHooks.ContractKeeper = ak.ComputeKeeper
 
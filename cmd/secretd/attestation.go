@@ -5,6 +5,8 @@ package main
 
 import (
 	"bytes"
+	"context"
+	"crypto/sha256"
 	"encoding/base64"
 	"encoding/hex"
 	"encoding/json"
@@ -15,7 +17,10 @@ import (
 	"os"
 	"path/filepath"
 	"strconv"
+	"time"
 
+	"github.com/cometbft/cometbft/crypto/ed25519"
+	"github.com/cometbft/cometbft/privval"
 	"github.com/cosmos/cosmos-sdk/client"
 	"github.com/cosmos/cosmos-sdk/client/flags"
 	"github.com/cosmos/cosmos-sdk/server"
@@ -35,6 +40,8 @@ const (
 	flag_no_epid                  = "no-epid"
 	flag_no_dcap                  = "no-dcap"
 	flag_is_migration_report      = "migration"
+	flag_ed25519                  = "ed25519"
+	flagCertOut                   = "cert-out"
 )
 
 const (
@@ -103,8 +110,9 @@ blockchain. Writes the certificate in DER format to ~/attestation_cert
 			no_epid, _ := cmd.Flags().GetBool(flag_no_epid)
 			no_dcap, _ := cmd.Flags().GetBool(flag_no_dcap)
 			is_migration_report, _ := cmd.Flags().GetBool(flag_is_migration_report)
+			ed25519, _ := cmd.Flags().GetBool(flag_ed25519)
 
-			_, err = api.CreateAttestationReport(apiKeyFile, no_epid, no_dcap, is_migration_report)
+			_, err = api.CreateAttestationReport(apiKeyFile, no_epid, no_dcap, is_migration_report, ed25519)
 			if err != nil {
 				return fmt.Errorf("failed to create attestation report: %w", err)
 			}
@@ -115,6 +123,7 @@ blockchain. Writes the certificate in DER format to ~/attestation_cert
 	cmd.Flags().Bool(flag_no_epid, false, "Optional flag to disable EPID attestation")
 	cmd.Flags().Bool(flag_no_dcap, false, "Optional flag to disable DCAP attestation")
 	cmd.Flags().Bool(flag_is_migration_report, false, "Create migration report rather then attestation")
+	cmd.Flags().Bool(flag_ed25519, false, "Optional flag to attest an Ed25519 key along with the registration key. Only DCAP attests it")
 
 	return cmd
 }
@@ -154,7 +163,7 @@ blockchain. Writes the certificate in DER format to ~/attestation_cert
 			}
 
 			// the master key of the generated certificate is returned here
-			masterKey, err := api.InitBootstrap(spidFile, apiKeyFile)
+			masterKey, err := api.InitBootstrap(spidFile, apiKeyFile, genDoc.ChainID)
 			if err != nil {
 				return fmt.Errorf("failed to initialize enclave: %w", err)
 			}
@@ -284,7 +293,7 @@ func MigrationOp() *cobra.Command {
 	cmd := &cobra.Command{
 		Use:   "migrate_op [opcode]",
 		Short: "Migration operation",
-		Long:  "0: migrate from SGX 2.17 format, 1: create migration report, 2: export sealing key for the new enclave, 3: import sealing data, 4: import legacy data, 5: self target info",
+		Long:  "0: migrate from SGX 2.17 format, 1: create migration report, 2: export sealing key for the new enclave, 3: import sealing data, 4: import legacy data, 5: self target info, 6: back up registration key to escrow, 7: release backup to restored enclave (escrow), 8: restore registration key from escrow",
 		Args:  cobra.ExactArgs(1),
 		RunE: func(_ *cobra.Command, args []string) error {
 			op_num, err := strconv.ParseUint(args[0], 10, 32)
@@ -305,6 +314,380 @@ func MigrationOp() *cobra.Command {
 	return cmd
 }
 
+func RotateNodeKey() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "rotate-node-key",
+		Short: "Replace the enclave registration key",
+		Long: `Generate a new enclave registration key, for when the current one may be exposed. Writes a
+DCAP attestation of the new key to the sgx secrets folder. Registering it with "tx register auth"
+revokes the previous key. The node should be stopped while the key is rotated.
+`,
+		Args: cobra.ExactArgs(0),
+		RunE: func(_ *cobra.Command, _ []string) error {
+			sgxSecretsFolder := os.Getenv("SCRT_SGX_STORAGE")
+			if sgxSecretsFolder == "" {
+				sgxSecretsFolder = os.ExpandEnv("/opt/secret/.sgx_secrets")
+			}
+
+			pubKey, err := api.RotateNodeKeys()
+			if err != nil {
+				return fmt.Errorf("failed to rotate the registration key: %w", err)
+			}
+
+			fmt.Printf("New registration key: %s\n", hex.EncodeToString(pubKey))
+			fmt.Printf("Register it with: secretd tx register auth %s\n", filepath.Join(sgxSecretsFolder, reg.AttestationCombinedPath))
+			fmt.Println("Then configure the node with the seed registered for the new key, using configure-secret")
+			return nil
+		},
+	}
+
+	return cmd
+}
+
+func ProveKeyPossession() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "prove-key-possession [master-key-file]",
+		Short: "Prove the enclave holds its registration key",
+		Long: `Append a proof that the enclave holds its registration key to the attestation certificate
+init-enclave wrote, keyed with the node exchange key of the network in [master-key-file], as
+"query register secret-network-params" writes it. The proof is made for the latest block of the
+node in --node, and carries its header and commit. It's only accepted for 100 blocks after it, and
+while the same validators sign blocks, so register right after. Networks can require the proof
+from every registration, or from keys that already registered.
+`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			clientCtx, err := client.GetClientQueryContext(cmd)
+			if err != nil {
+				return err
+			}
+
+			masterKeyB64, err := os.ReadFile(args[0])
+			if err != nil {
+				return err
+			}
+
+			masterKey, err := base64.StdEncoding.DecodeString(string(masterKeyB64))
+			if err != nil {
+				return fmt.Errorf("invalid master key: %w", err)
+			}
+
+			node, err := clientCtx.GetNode()
+			if err != nil {
+				return err
+			}
+
+			commit, err := node.Commit(context.Background(), nil)
+			if err != nil {
+				return fmt.Errorf("failed to query the latest block: %w", err)
+			}
+			header := commit.SignedHeader.Header
+
+			headerBz, err := header.ToProto().Marshal()
+			if err != nil {
+				return fmt.Errorf("failed to encode the header: %w", err)
+			}
+			commitBz, err := commit.SignedHeader.Commit.ToProto().Marshal()
+			if err != nil {
+				return fmt.Errorf("failed to encode the commit: %w", err)
+			}
+
+			err = api.ProveKeyPossession(masterKey, headerBz, commitBz)
+			if err != nil {
+				return fmt.Errorf("failed to prove key possession: %w", err)
+			}
+
+			fmt.Printf("Appended the proof for block %d of %s to the attestation certificate\n", header.Height, header.ChainID)
+			return nil
+		},
+	}
+
+	flags.AddQueryFlagsToCmd(cmd)
+
+	return cmd
+}
+
+func BindOperator() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "bind-operator",
+		Short: "Bind the enclave to the validator it serves",
+		Long: `Append the binding of the node to the validator to the attestation certificate
+init-enclave wrote, signed with the consensus key of the validator in priv_validator_key.json.
+The binding is made for the chain of the node in --node. Networks can restrict the seeds to nodes
+bound to a bonded validator. Bind the node again after init-enclave or rotate-node-key, which
+write a new certificate.
+`,
+		Args: cobra.NoArgs,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			clientCtx, err := client.GetClientQueryContext(cmd)
+			if err != nil {
+				return err
+			}
+
+			node, err := clientCtx.GetNode()
+			if err != nil {
+				return err
+			}
+
+			status, err := node.Status(context.Background())
+			if err != nil {
+				return fmt.Errorf("failed to query the node status: %w", err)
+			}
+			chainID := status.NodeInfo.Network
+
+			sgxSecretsFolder := os.Getenv("SCRT_SGX_STORAGE")
+			if sgxSecretsFolder == "" {
+				sgxSecretsFolder = os.ExpandEnv("/opt/secret/.sgx_secrets")
+			}
+
+			cert, err := os.ReadFile(filepath.Join(sgxSecretsFolder, reg.AttestationCombinedPath))
+			if err != nil {
+				return fmt.Errorf("failed to read the attestation certificate, create it with init-enclave: %w", err)
+			}
+
+			nodeKey, err := ra.VerifyCombinedCert(cert)
+			if err != nil {
+				return fmt.Errorf("invalid attestation certificate: %w", err)
+			}
+
+			config := server.GetServerContextFromCmd(cmd).Config
+			keyFile := config.PrivValidatorKeyFile()
+			if _, err := os.Stat(keyFile); err != nil {
+				return fmt.Errorf("failed to find the consensus key: %w", err)
+			}
+
+			pv := privval.LoadFilePVEmptyState(keyFile, config.PrivValidatorStateFile())
+			if pv.Key.PubKey.Type() != ed25519.KeyType {
+				return fmt.Errorf("the consensus key is a %s key, only %s keys can bind a node", pv.Key.PubKey.Type(), ed25519.KeyType)
+			}
+
+			signature, err := pv.Key.PrivKey.Sign(operatorBindingMessage(nodeKey, chainID))
+			if err != nil {
+				return fmt.Errorf("failed to sign the binding: %w", err)
+			}
+
+			err = api.BindOperator(pv.Key.PubKey.Bytes(), signature, chainID)
+			if err != nil {
+				return fmt.Errorf("failed to bind the node: %w", err)
+			}
+
+			fmt.Printf("Bound the node to the validator with consensus address %s on %s\n", pv.Key.PubKey.Address(), chainID)
+			return nil
+		},
+	}
+
+	flags.AddQueryFlagsToCmd(cmd)
+
+	return cmd
+}
+
+// operatorBindingMessage is what the consensus key of a validator signs to bind the node with
+// nodeKey to it, as the enclave verifies it
+func operatorBindingMessage(nodeKey []byte, chainID string) []byte {
+	chainIDHash := sha256.Sum256([]byte(chainID))
+
+	msg := []byte("secret-registration-operator-v1")
+	msg = append(msg, nodeKey...)
+	return append(msg, chainIDHash[:]...)
+}
+
+func CreateRegistrationPackage() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "create-registration-package [output-file]",
+		Short: "Write the attestation of the enclave to a single file, for nodes that attest offline",
+		Long: `Write a registration package to [output-file]: the combined certificate that
+"tx register auth" takes, with a DCAP quote and its collateral, the registration key it attests and
+what the enclave read from its report, as json. Nothing is sent over the network but the requests
+for the collateral, which a local PCCS can serve. Check the package with
+verify-registration-package before it's registered.
+`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			// Creates the registration key if the node has none
+			_, err := api.KeyGen()
+			if err != nil {
+				return fmt.Errorf("failed to initialize enclave: %w", err)
+			}
+
+			ed25519, _ := cmd.Flags().GetBool(flag_ed25519)
+			res, err := api.CreateRegistrationPackage(ed25519)
+			if err != nil {
+				return fmt.Errorf("failed to create the registration package: %w", err)
+			}
+
+			var pkg registrationPackage
+			if err := json.Unmarshal(res, &pkg); err != nil {
+				return fmt.Errorf("failed to decode the registration package: %w", err)
+			}
+
+			if err := os.WriteFile(args[0], res, 0o644); err != nil {
+				return err
+			}
+
+			fmt.Printf("Wrote the registration package of %s to %s\n", pkg.PublicKey, args[0])
+			fmt.Printf("Its collateral expires at %s\n", time.Unix(pkg.Metadata.CollateralExpiration, 0).UTC())
+			return nil
+		},
+	}
+	cmd.Flags().Bool(flag_ed25519, false, "Optional flag to attest an Ed25519 key along with the registration key")
+
+	return cmd
+}
+
+func VerifyRegistrationPackage() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "verify-registration-package [package-file]",
+		Short: "Check a registration package before it's registered",
+		Long: `Verify the registration package in [package-file] the way the chain verifies a registration
+of its certificate, with this node's enclave and at its time, without registering it. With
+--cert-out, the certificate in the package is written to a file for "tx register auth". Proofs of
+possession and operator bindings can't be made offline, so a package has neither.
+`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			res, err := os.ReadFile(args[0])
+			if err != nil {
+				return err
+			}
+
+			var pkg registrationPackage
+			if err := json.Unmarshal(res, &pkg); err != nil {
+				return fmt.Errorf("failed to decode the registration package: %w", err)
+			}
+
+			collateralExpiration, err := api.VerifyRegistrationPackage(res)
+			if err != nil {
+				return fmt.Errorf("invalid registration package: %w", err)
+			}
+
+			metadata := pkg.Metadata
+			fmt.Printf("Registration key: %s\n", pkg.PublicKey)
+			fmt.Printf("Key scheme:       %d\n", metadata.KeyScheme)
+			fmt.Printf("Node role:        %s\n", ra.NodeRole(metadata.IsvProdID))
+			fmt.Printf("MRENCLAVE:        %s\n", metadata.MrEnclave)
+			fmt.Printf("MRSIGNER:         %s\n", metadata.MrSigner)
+			fmt.Printf("ISV SVN:          %d\n", metadata.IsvSvn)
+			fmt.Printf("Enclave version:  %s\n", metadata.EnclaveVersion)
+			fmt.Printf("Collateral until: %s\n", time.Unix(collateralExpiration, 0).UTC())
+
+			certOut, _ := cmd.Flags().GetString(flagCertOut)
+			if certOut != "" {
+				if err := os.WriteFile(certOut, pkg.Cert, 0o644); err != nil {
+					return err
+				}
+				fmt.Printf("Wrote the certificate to %s\n", certOut)
+			}
+			return nil
+		},
+	}
+	cmd.Flags().String(flagCertOut, "", "Optional file to write the certificate in the package to")
+
+	return cmd
+}
+
+// registrationPackage is the json of a registration package, as the enclave writes it
+type registrationPackage struct {
+	Version   uint32 `json:"version"`
+	Cert      []byte `json:"cert"`
+	PublicKey string `json:"public_key"`
+	Metadata  struct {
+		KeyScheme            uint8  `json:"key_scheme"`
+		MrEnclave            string `json:"mr_enclave"`
+		MrSigner             string `json:"mr_signer"`
+		IsvSvn               uint16 `json:"isv_svn"`
+		IsvProdID            uint16 `json:"isv_prod_id"`
+		CollateralExpiration int64  `json:"collateral_expiration"`
+		CreatedAt            int64  `json:"created_at"`
+		EnclaveVersion       string `json:"enclave_version"`
+	} `json:"metadata"`
+}
+
+func SeedHeartbeat() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "seed-heartbeat [height]",
+		Short: "Sign a heartbeat proving the enclave holds the consensus seed",
+		Long: `Sign a heartbeat of the node's registration key at a block height, with a key the enclave
+derives from the consensus seed. Any node with the seed can verify it, and an enclave without the
+seed can't sign one. The height must be one of the last 100 blocks when the heartbeat is recorded.
+`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(_ *cobra.Command, args []string) error {
+			height, err := strconv.ParseUint(args[0], 10, 64)
+			if err != nil {
+				return fmt.Errorf("invalid height: %w", err)
+			}
+
+			nodeKey, signature, err := api.SeedHeartbeat(height)
+			if err != nil {
+				return fmt.Errorf("failed to sign a seed heartbeat: %w", err)
+			}
+
+			fmt.Printf("Node key: %s\n", hex.EncodeToString(nodeKey))
+			fmt.Printf("Height: %d\n", height)
+			fmt.Printf("Signature: %s\n", hex.EncodeToString(signature))
+			return nil
+		},
+	}
+
+	return cmd
+}
+
+func PlatformCensus() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "platform-census",
+		Short: "Print the TCB levels of the SGX platforms that registered",
+		Long: `Print how many of the platforms that registered through this node are at each TCB status,
+TCB date and advisory, signed by the enclave. Platforms are counted by a salted hash of their
+attestation key, so the census doesn't tell which node runs on which platform.
+`,
+		Args: cobra.ExactArgs(0),
+		RunE: func(_ *cobra.Command, _ []string) error {
+			res, err := api.PlatformCensus()
+			if err != nil {
+				return fmt.Errorf("failed to export the platform census: %w", err)
+			}
+
+			var census bytes.Buffer
+			if err := json.Indent(&census, res, "", "  "); err != nil {
+				return fmt.Errorf("failed to decode the platform census: %w", err)
+			}
+
+			fmt.Println(census.String())
+			return nil
+		},
+	}
+
+	return cmd
+}
+
+func CrashBreadcrumbs() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "crash-breadcrumbs",
+		Short: "Print the last panics and fatal errors of the enclave",
+		Long: `Print the breadcrumbs the enclave sealed when it last panicked or hit a fatal error, oldest
+first: the subsystem, the ecall that was running, the height of its block and a sanitized message.
+They're kept across restarts, so run this after the node crashed.
+`,
+		Args: cobra.ExactArgs(0),
+		RunE: func(_ *cobra.Command, _ []string) error {
+			res, err := api.GetCrashBreadcrumbs()
+			if err != nil {
+				return fmt.Errorf("failed to read the crash breadcrumbs: %w", err)
+			}
+
+			var breadcrumbs bytes.Buffer
+			if err := json.Indent(&breadcrumbs, res, "", "  "); err != nil {
+				return fmt.Errorf("failed to decode the crash breadcrumbs: %w", err)
+			}
+
+			fmt.Println(breadcrumbs.String())
+			return nil
+		},
+	}
+
+	return cmd
+}
+
 func EmergencyApproveUpgrade() *cobra.Command {
 	cmd := &cobra.Command{
 		Use:   "emergency_approve_upgrade [mr_enclave]",
@@ -515,7 +898,7 @@ Please report any issues with this command
 			no_epid, _ := cmd.Flags().GetBool(flag_no_epid)
 			no_dcap, _ := cmd.Flags().GetBool(flag_no_dcap)
 
-			_, err = api.CreateAttestationReport(apiKeyFile, no_epid, no_dcap, false)
+			_, err = api.CreateAttestationReport(apiKeyFile, no_epid, no_dcap, false, false)
 			if err != nil {
 				return fmt.Errorf("failed to create attestation report: %w", err)
 			}
@@ -82,7 +82,7 @@ func MigrationOp() *cobra.Command {
 	cmd := &cobra.Command{
 		Use:   "migrate_op [opcode]",
 		Short: "Migration operation",
-		Long:  "0: migrate from SGX 2.17 format, 1: create migration report, 2: export sealing key for the new enclave, 3: import sealing data, 4: import legacy data, 5: self target info",
+		Long:  "0: migrate from SGX 2.17 format, 1: create migration report, 2: export sealing key for the new enclave, 3: import sealing data, 4: import legacy data, 5: self target info, 6: back up registration key to escrow, 7: release backup to restored enclave (escrow), 8: restore registration key from escrow",
 		Args:  cobra.ExactArgs(1),
 		RunE: func(cmd *cobra.Command, args []string) error {
 			println("This is a secretd only function, yo")
@@ -93,6 +93,176 @@ func MigrationOp() *cobra.Command {
 	return cmd
 }
 
+func RotateNodeKey() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "rotate-node-key",
+		Short: "Replace the enclave registration key",
+		Long: `Generate a new enclave registration key, for when the current one may be exposed. Writes a
+DCAP attestation of the new key to the sgx secrets folder. Registering it with "tx register auth"
+revokes the previous key. The node should be stopped while the key is rotated.
+`,
+		Args: cobra.ExactArgs(0),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			println("This is a secretd only function, yo")
+			return nil
+		},
+	}
+
+	return cmd
+}
+
+func ConsensusSigner() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "consensus-signer [priv_validator_laddr]",
+		Short: "Sign the node's votes and proposals with the consensus key held by the enclave",
+		Long: `Connect to the priv_validator_laddr of a node, e.g. tcp://127.0.0.1:26659, and answer its
+remote signer requests with the consensus key held by the enclave, which is generated on first
+use. The enclave refuses to double sign. See docs/enclave-consensus-key.md.
+`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			println("This is a secretd only function, yo")
+			return nil
+		},
+	}
+
+	return cmd
+}
+
+func ProveKeyPossession() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "prove-key-possession [master-key-file]",
+		Short: "Prove the enclave holds its registration key",
+		Long: `Append a proof that the enclave holds its registration key to the attestation certificate
+init-enclave wrote, keyed with the node exchange key of the network in [master-key-file], as
+"query register secret-network-params" writes it. The proof is made for the latest block of the
+node in --node, and carries its header and commit. It's only accepted for 100 blocks after it, and
+while the same validators sign blocks, so register right after. Networks can require the proof
+from every registration, or from keys that already registered.
+`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			println("This is a secretd only function, yo")
+			return nil
+		},
+	}
+
+	return cmd
+}
+
+func BindOperator() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "bind-operator",
+		Short: "Bind the enclave to the validator it serves",
+		Long: `Append the binding of the node to the validator to the attestation certificate
+init-enclave wrote, signed with the consensus key of the validator in priv_validator_key.json.
+The binding is made for the chain of the node in --node. Networks can restrict the seeds to nodes
+bound to a bonded validator. Bind the node again after init-enclave or rotate-node-key, which
+write a new certificate.
+`,
+		Args: cobra.NoArgs,
+		RunE: func(cmd *cobra.Command, args []string) error {
+			println("This is a secretd only function, yo")
+			return nil
+		},
+	}
+
+	return cmd
+}
+
+func CreateRegistrationPackage() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "create-registration-package [output-file]",
+		Short: "Write the attestation of the enclave to a single file, for nodes that attest offline",
+		Long: `Write a registration package to [output-file]: the combined certificate that
+"tx register auth" takes, with a DCAP quote and its collateral, the registration key it attests and
+what the enclave read from its report, as json. Nothing is sent over the network but the requests
+for the collateral, which a local PCCS can serve. Check the package with
+verify-registration-package before it's registered.
+`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			println("This is a secretd only function, yo")
+			return nil
+		},
+	}
+
+	return cmd
+}
+
+func VerifyRegistrationPackage() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "verify-registration-package [package-file]",
+		Short: "Check a registration package before it's registered",
+		Long: `Verify the registration package in [package-file] the way the chain verifies a registration
+of its certificate, with this node's enclave and at its time, without registering it. With
+--cert-out, the certificate in the package is written to a file for "tx register auth". Proofs of
+possession and operator bindings can't be made offline, so a package has neither.
+`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			println("This is a secretd only function, yo")
+			return nil
+		},
+	}
+
+	return cmd
+}
+
+func SeedHeartbeat() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "seed-heartbeat [height]",
+		Short: "Sign a heartbeat proving the enclave holds the consensus seed",
+		Long: `Sign a heartbeat of the node's registration key at a block height, with a key the enclave
+derives from the consensus seed. Any node with the seed can verify it, and an enclave without the
+seed can't sign one. The height must be one of the last 100 blocks when the heartbeat is recorded.
+`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			println("This is a secretd only function, yo")
+			return nil
+		},
+	}
+
+	return cmd
+}
+
+func PlatformCensus() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "platform-census",
+		Short: "Print the TCB levels of the SGX platforms that registered",
+		Long: `Print how many of the platforms that registered through this node are at each TCB status,
+TCB date and advisory, signed by the enclave. Platforms are counted by a salted hash of their
+attestation key, so the census doesn't tell which node runs on which platform.
+`,
+		Args: cobra.ExactArgs(0),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			println("This is a secretd only function, yo")
+			return nil
+		},
+	}
+
+	return cmd
+}
+
+func CrashBreadcrumbs() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "crash-breadcrumbs",
+		Short: "Print the last panics and fatal errors of the enclave",
+		Long: `Print the breadcrumbs the enclave sealed when it last panicked or hit a fatal error, oldest
+first: the subsystem, the ecall that was running, the height of its block and a sanitized message.
+They're kept across restarts, so run this after the node crashed.
+`,
+		Args: cobra.ExactArgs(0),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			println("This is a secretd only function, yo")
+			return nil
+		},
+	}
+
+	return cmd
+}
+
 func EmergencyApproveUpgrade() *cobra.Command {
 	cmd := &cobra.Command{
 		Use:   "emergency_approve_upgrade",
@@ -0,0 +1,96 @@
+//go:build !secretcli
+// +build !secretcli
+
+package main
+
+import (
+	"fmt"
+	"os"
+	"os/signal"
+	"syscall"
+	"time"
+
+	"github.com/cometbft/cometbft/crypto/ed25519"
+	cmtlog "github.com/cometbft/cometbft/libs/log"
+	cmtnet "github.com/cometbft/cometbft/libs/net"
+	"github.com/cometbft/cometbft/privval"
+	privvalproto "github.com/cometbft/cometbft/proto/tendermint/privval"
+	cmttypes "github.com/cometbft/cometbft/types"
+	"github.com/cosmos/cosmos-sdk/client/flags"
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	"github.com/spf13/cobra"
+)
+
+const consensusSignerTimeout = 3 * time.Second
+
+func ConsensusSigner() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "consensus-signer [priv_validator_laddr]",
+		Short: "Sign the node's votes and proposals with the consensus key held by the enclave",
+		Long: `Connect to the priv_validator_laddr of a node, e.g. tcp://127.0.0.1:26659, and answer its
+remote signer requests with the consensus key held by the enclave, which is generated on first
+use. The enclave refuses to double sign. See docs/enclave-consensus-key.md.
+`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			chainID, err := cmd.Flags().GetString(flags.FlagChainID)
+			if err != nil {
+				return err
+			}
+
+			pubKey, err := api.ConsensusKeyInit()
+			if err != nil {
+				return fmt.Errorf("failed to load the consensus key: %w", err)
+			}
+			fmt.Printf("consensus key: %X\n", pubKey)
+
+			logger := cmtlog.NewTMLogger(cmtlog.NewSyncWriter(os.Stdout))
+
+			var dialer privval.SocketDialer
+			protocol, address := cmtnet.ProtocolAndAddress(args[0])
+			switch protocol {
+			case "unix":
+				dialer = privval.DialUnixFn(address)
+			case "tcp":
+				dialer = privval.DialTCPFn(address, consensusSignerTimeout, ed25519.GenPrivKey())
+			default:
+				return fmt.Errorf("unsupported protocol %q, expected tcp or unix", protocol)
+			}
+
+			endpoint := privval.NewSignerDialerEndpoint(logger, dialer)
+			server := privval.NewSignerServer(endpoint, chainID, nil)
+			server.SetRequestHandler(enclaveSignerRequestHandler)
+			if err := server.Start(); err != nil {
+				return err
+			}
+			defer server.Stop() //nolint:errcheck
+
+			signals := make(chan os.Signal, 1)
+			signal.Notify(signals, syscall.SIGINT, syscall.SIGTERM)
+			<-signals
+			return nil
+		},
+	}
+	cmd.Flags().String(flags.FlagChainID, "", "The chain id of the node")
+
+	return cmd
+}
+
+// enclaveSignerRequestHandler hands a request of the node to the enclave, which holds the key
+// and the last signed state, so privVal is never set.
+func enclaveSignerRequestHandler(_ cmttypes.PrivValidator, request privvalproto.Message, _ string) (privvalproto.Message, error) {
+	var response privvalproto.Message
+
+	bz, err := request.Marshal()
+	if err != nil {
+		return response, err
+	}
+	res, err := api.ConsensusSign(bz)
+	if err != nil {
+		return response, err
+	}
+	if err := response.Unmarshal(res); err != nil {
+		return response, err
+	}
+	return response, nil
+}
@@ -202,6 +202,15 @@ func initRootCmd(rootCmd *cobra.Command, encodingConfig app.EncodingConfig, basi
 		ParseCert(),
 		DumpBin(),
 		MigrationOp(),
+		RotateNodeKey(),
+		ConsensusSigner(),
+		ProveKeyPossession(),
+		BindOperator(),
+		CreateRegistrationPackage(),
+		VerifyRegistrationPackage(),
+		SeedHeartbeat(),
+		PlatformCensus(),
+		CrashBreadcrumbs(),
 		EmergencyApproveUpgrade(),
 		ConfigureSecret(),
 		HealthCheck(),
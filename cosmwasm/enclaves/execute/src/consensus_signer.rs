@@ -0,0 +1,586 @@
+//! Tendermint consensus key held by the enclave.
+//!
+//! Validators usually keep their consensus key in a file next to the node, where anyone with
+//! access to the machine can copy it. As an alternative, the enclave can generate the key and
+//! never let it leave: the node sends it the same `privval.Message` requests a remote signer
+//! gets, and the enclave answers with the signed vote or proposal.
+//!
+//! Double signing is prevented the way `FilePV` does it. The height, round and step of the last
+//! signature are sealed together with the key before the signature is released, and requests
+//! that go back in height, round or step are refused. A request at the same height, round and
+//! step gets the previous signature if it signs the same bytes, or the same bytes up to the
+//! timestamp, and is refused otherwise.
+//!
+//! On top of that, the enclave never signs at or below the height of the last block its light
+//! client verified. Those heights are already committed, so a request for one can only come
+//! from a misconfigured or compromised node, e.g. a second validator instance catching up with
+//! the same key. The watermark is sealed with the rest of the state, so it only moves forward.
+
+use std::convert::TryFrom;
+use std::path;
+use std::sync::SgxMutex;
+use std::untrusted::path::PathEx;
+
+use ed25519_consensus::SigningKey;
+use lazy_static::lazy_static;
+use log::*;
+use prost::Message as _;
+use serde::{Deserialize, Serialize};
+use sgx_types::{sgx_status_t, SgxResult};
+
+use enclave_crypto::consts::{make_sgx_secret_path, SEALED_FILE_CONSENSUS_KEY};
+use enclave_crypto::rand_slice;
+use enclave_utils::storage::{seal, unseal};
+use tendermint_proto::v0_38::crypto::{public_key, PublicKey};
+use tendermint_proto::v0_38::privval::message::Sum;
+use tendermint_proto::v0_38::privval::{
+    Message, PingResponse, PubKeyResponse, RemoteSignerError, SignedProposalResponse,
+    SignedVoteResponse,
+};
+use tendermint_proto::v0_38::types::{
+    BlockId, CanonicalBlockId, CanonicalPartSetHeader, CanonicalProposal, CanonicalVote,
+    CanonicalVoteExtension, Proposal, SignedMsgType, Vote,
+};
+
+const STEP_PROPOSE: u8 = 1;
+const STEP_PREVOTE: u8 = 2;
+const STEP_PRECOMMIT: u8 = 3;
+
+/// The code of every `RemoteSignerError` the enclave returns
+const REMOTE_SIGNER_ERROR_CODE: i32 = 1;
+
+lazy_static! {
+    static ref CONSENSUS_KEY_SEALING_PATH: String = make_sgx_secret_path(SEALED_FILE_CONSENSUS_KEY);
+    static ref CONSENSUS_SIGNER: SgxMutex<Option<ConsensusSigner>> = SgxMutex::new(None);
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct LastSigned {
+    height: i64,
+    round: i32,
+    step: u8,
+    sign_bytes: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConsensusSigner {
+    private_key: [u8; 32],
+    last_signed: Option<LastSigned>,
+    /// The highest block height the light client verified
+    #[serde(default)]
+    verified_height: i64,
+}
+
+impl ConsensusSigner {
+    fn unseal() -> SgxResult<Self> {
+        let signer: Self = serde_json::from_slice(unseal(&CONSENSUS_KEY_SEALING_PATH)?.as_slice())
+            .map_err(|e| {
+                error!("Error decoding consensus signer state from json {:?}", e);
+                sgx_status_t::SGX_ERROR_UNEXPECTED
+            })?;
+
+        Ok(signer)
+    }
+
+    fn seal(&self) -> SgxResult<()> {
+        let encoded = serde_json::to_vec(&self).map_err(|e| {
+            error!("Error encoding consensus signer state to json: {:?}", e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })?;
+
+        seal(encoded.as_slice(), &CONSENSUS_KEY_SEALING_PATH)
+    }
+
+    fn public_key(&self) -> [u8; 32] {
+        SigningKey::from(self.private_key)
+            .verification_key()
+            .to_bytes()
+    }
+
+    fn sign_bytes(&self, bytes: &[u8]) -> Vec<u8> {
+        SigningKey::from(self.private_key)
+            .sign(bytes)
+            .to_bytes()
+            .to_vec()
+    }
+
+    /// Raises the watermark to the height of the last block the light client verified
+    fn observe_verified_height(&mut self, height: i64) {
+        self.verified_height = self.verified_height.max(height);
+    }
+
+    /// Returns the previous signature if the request is at the same height, round and step as
+    /// the last one, and an error if it goes back
+    fn check_hrs(&self, height: i64, round: i32, step: u8) -> Result<Option<&LastSigned>, String> {
+        if height <= self.verified_height {
+            return Err(format!(
+                "refusing to sign at height {}, the light client already verified height {}",
+                height, self.verified_height
+            ));
+        }
+
+        let last = match &self.last_signed {
+            Some(last) => last,
+            None => return Ok(None),
+        };
+
+        match (height, round, step).cmp(&(last.height, last.round, last.step)) {
+            std::cmp::Ordering::Greater => Ok(None),
+            std::cmp::Ordering::Equal => Ok(Some(last)),
+            std::cmp::Ordering::Less => Err(format!(
+                "refusing to sign {}/{}/{}, already signed {}/{}/{}",
+                height, round, step, last.height, last.round, last.step
+            )),
+        }
+    }
+
+    fn record(&mut self, height: i64, round: i32, step: u8, sign_bytes: Vec<u8>) -> Vec<u8> {
+        let signature = self.sign_bytes(&sign_bytes);
+        self.last_signed = Some(LastSigned {
+            height,
+            round,
+            step,
+            sign_bytes,
+            signature: signature.clone(),
+        });
+        signature
+    }
+
+    fn sign_vote(
+        &mut self,
+        chain_id: &str,
+        vote: &mut Vote,
+        skip_extension_signing: bool,
+    ) -> Result<(), String> {
+        let step = match SignedMsgType::try_from(vote.r#type) {
+            Ok(SignedMsgType::Prevote) => STEP_PREVOTE,
+            Ok(SignedMsgType::Precommit) => STEP_PRECOMMIT,
+            _ => return Err(format!("unexpected vote type {}", vote.r#type)),
+        };
+
+        let sign_bytes = vote_sign_bytes(chain_id, vote);
+        let previous = self.check_hrs(vote.height, vote.round, step)?.cloned();
+        vote.signature = match previous {
+            Some(last) if last.sign_bytes == sign_bytes => last.signature,
+            Some(last) => {
+                let previous_timestamp =
+                    CanonicalVote::decode_length_delimited(last.sign_bytes.as_slice())
+                        .map_err(|_| "failed to decode the last signed vote".to_string())?
+                        .timestamp;
+
+                let mut retimed = vote.clone();
+                retimed.timestamp = previous_timestamp;
+                if vote_sign_bytes(chain_id, &retimed) != last.sign_bytes {
+                    return Err(format!(
+                        "refusing to sign a conflicting vote at {}/{}/{}",
+                        vote.height, vote.round, step
+                    ));
+                }
+
+                vote.timestamp = retimed.timestamp;
+                last.signature
+            }
+            None => self.record(vote.height, vote.round, step, sign_bytes),
+        };
+
+        // Extensions aren't slashable, so they're signed every time
+        let is_nil = canonical_block_id(vote.block_id.clone()).is_none();
+        if step == STEP_PRECOMMIT && !is_nil && !skip_extension_signing {
+            vote.extension_signature = self.sign_bytes(&vote_extension_sign_bytes(chain_id, vote));
+        }
+
+        Ok(())
+    }
+
+    fn sign_proposal(&mut self, chain_id: &str, proposal: &mut Proposal) -> Result<(), String> {
+        let sign_bytes = proposal_sign_bytes(chain_id, proposal);
+        let previous = self
+            .check_hrs(proposal.height, proposal.round, STEP_PROPOSE)?
+            .cloned();
+        proposal.signature = match previous {
+            Some(last) if last.sign_bytes == sign_bytes => last.signature,
+            Some(last) => {
+                let previous_timestamp =
+                    CanonicalProposal::decode_length_delimited(last.sign_bytes.as_slice())
+                        .map_err(|_| "failed to decode the last signed proposal".to_string())?
+                        .timestamp;
+
+                let mut retimed = proposal.clone();
+                retimed.timestamp = previous_timestamp;
+                if proposal_sign_bytes(chain_id, &retimed) != last.sign_bytes {
+                    return Err(format!(
+                        "refusing to sign a conflicting proposal at {}/{}",
+                        proposal.height, proposal.round
+                    ));
+                }
+
+                proposal.timestamp = retimed.timestamp;
+                last.signature
+            }
+            None => self.record(proposal.height, proposal.round, STEP_PROPOSE, sign_bytes),
+        };
+
+        Ok(())
+    }
+
+    fn public_key_proto(&self) -> PublicKey {
+        PublicKey {
+            sum: Some(public_key::Sum::Ed25519(self.public_key().to_vec())),
+        }
+    }
+
+    /// Answers a single privval request
+    fn handle(&mut self, request: Sum) -> Result<Sum, String> {
+        let response = match request {
+            Sum::PubKeyRequest(_) => Sum::PubKeyResponse(PubKeyResponse {
+                pub_key: Some(self.public_key_proto()),
+                error: None,
+            }),
+            Sum::SignVoteRequest(request) => {
+                let mut vote = request.vote.ok_or("missing vote")?;
+                let response = match self.sign_vote(
+                    &request.chain_id,
+                    &mut vote,
+                    request.skip_extension_signing,
+                ) {
+                    Ok(()) => SignedVoteResponse {
+                        vote: Some(vote),
+                        error: None,
+                    },
+                    Err(description) => SignedVoteResponse {
+                        vote: None,
+                        error: Some(remote_signer_error(description)),
+                    },
+                };
+                Sum::SignedVoteResponse(response)
+            }
+            Sum::SignProposalRequest(request) => {
+                let mut proposal = request.proposal.ok_or("missing proposal")?;
+                let response = match self.sign_proposal(&request.chain_id, &mut proposal) {
+                    Ok(()) => SignedProposalResponse {
+                        proposal: Some(proposal),
+                        error: None,
+                    },
+                    Err(description) => SignedProposalResponse {
+                        proposal: None,
+                        error: Some(remote_signer_error(description)),
+                    },
+                };
+                Sum::SignedProposalResponse(response)
+            }
+            Sum::PingRequest(_) => Sum::PingResponse(PingResponse {}),
+            _ => return Err("unexpected privval message".to_string()),
+        };
+
+        Ok(response)
+    }
+}
+
+fn remote_signer_error(description: String) -> RemoteSignerError {
+    warn!("consensus signer: {}", description);
+    RemoteSignerError {
+        code: REMOTE_SIGNER_ERROR_CODE,
+        description,
+    }
+}
+
+/// The height of the last block the light client verified
+#[cfg(feature = "light-client-validation")]
+fn light_client_height() -> i64 {
+    block_verifier::VERIFIED_BLOCK_MESSAGES
+        .lock()
+        .unwrap()
+        .height() as i64
+}
+
+#[cfg(not(feature = "light-client-validation"))]
+fn light_client_height() -> i64 {
+    0
+}
+
+/// A nil block id is left out of the sign bytes
+fn canonical_block_id(block_id: Option<BlockId>) -> Option<CanonicalBlockId> {
+    let block_id = block_id?;
+    let part_set_header = block_id.part_set_header.unwrap_or_default();
+    if block_id.hash.is_empty() && part_set_header.total == 0 && part_set_header.hash.is_empty() {
+        return None;
+    }
+
+    Some(CanonicalBlockId {
+        hash: block_id.hash,
+        part_set_header: Some(CanonicalPartSetHeader {
+            total: part_set_header.total,
+            hash: part_set_header.hash,
+        }),
+    })
+}
+
+fn vote_sign_bytes(chain_id: &str, vote: &Vote) -> Vec<u8> {
+    CanonicalVote {
+        r#type: vote.r#type,
+        height: vote.height,
+        round: vote.round as i64,
+        block_id: canonical_block_id(vote.block_id.clone()),
+        timestamp: vote.timestamp.clone(),
+        chain_id: chain_id.to_string(),
+    }
+    .encode_length_delimited_to_vec()
+}
+
+fn vote_extension_sign_bytes(chain_id: &str, vote: &Vote) -> Vec<u8> {
+    CanonicalVoteExtension {
+        extension: vote.extension.clone(),
+        height: vote.height,
+        round: vote.round as i64,
+        chain_id: chain_id.to_string(),
+    }
+    .encode_length_delimited_to_vec()
+}
+
+fn proposal_sign_bytes(chain_id: &str, proposal: &Proposal) -> Vec<u8> {
+    CanonicalProposal {
+        r#type: SignedMsgType::Proposal as i32,
+        height: proposal.height,
+        round: proposal.round as i64,
+        pol_round: proposal.pol_round as i64,
+        block_id: canonical_block_id(proposal.block_id.clone()),
+        timestamp: proposal.timestamp.clone(),
+        chain_id: chain_id.to_string(),
+    }
+    .encode_length_delimited_to_vec()
+}
+
+/// Loads the consensus key, generating and sealing a new one on first use, and returns its
+/// public key
+pub fn init_consensus_key() -> SgxResult<[u8; 32]> {
+    let mut signer = CONSENSUS_SIGNER.lock().unwrap();
+    if let Some(signer) = signer.as_ref() {
+        return Ok(signer.public_key());
+    }
+
+    let loaded = if path::Path::new(CONSENSUS_KEY_SEALING_PATH.as_str()).exists() {
+        ConsensusSigner::unseal()?
+    } else {
+        let mut private_key = [0u8; 32];
+        rand_slice(&mut private_key).map_err(|e| {
+            error!("Failed to generate a consensus key: {:?}", e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })?;
+
+        let generated = ConsensusSigner {
+            private_key,
+            last_signed: None,
+            verified_height: 0,
+        };
+        generated.seal()?;
+        info!("Generated a new consensus key");
+        generated
+    };
+
+    let public_key = loaded.public_key();
+    *signer = Some(loaded);
+    Ok(public_key)
+}
+
+/// Answers a protobuf encoded `privval.Message`. The state is sealed before a new signature
+/// is returned.
+pub fn handle_privval_request(request: &[u8]) -> SgxResult<Vec<u8>> {
+    let request = Message::decode(request).map_err(|e| {
+        warn!("Failed to decode privval request: {:?}", e);
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    })?;
+    let request = request.sum.ok_or_else(|| {
+        warn!("Got an empty privval request");
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    })?;
+
+    let mut guard = CONSENSUS_SIGNER.lock().unwrap();
+    let signer = guard.as_mut().ok_or_else(|| {
+        error!("The consensus key wasn't initialized");
+        sgx_status_t::SGX_ERROR_INVALID_STATE
+    })?;
+
+    let (last_signed, verified_height) = (signer.last_signed.clone(), signer.verified_height);
+    signer.observe_verified_height(light_client_height());
+    let response = signer.handle(request).map_err(|e| {
+        warn!("Failed to handle privval request: {}", e);
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    })?;
+
+    if signer.last_signed != last_signed || signer.verified_height != verified_height {
+        signer.seal()?;
+    }
+
+    Ok(Message {
+        sum: Some(response),
+    }
+    .encode_to_vec())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    use crate::count_failures;
+
+    use ed25519_consensus::{Signature, VerificationKey};
+    use tendermint_proto::google::protobuf::Timestamp;
+    use tendermint_proto::v0_38::types::PartSetHeader;
+
+    const CHAIN_ID: &str = "secret-4";
+
+    fn signer() -> ConsensusSigner {
+        ConsensusSigner {
+            private_key: [7u8; 32],
+            last_signed: None,
+            verified_height: 0,
+        }
+    }
+
+    fn vote(r#type: SignedMsgType, height: i64, round: i32, hash: u8, seconds: i64) -> Vote {
+        Vote {
+            r#type: r#type as i32,
+            height,
+            round,
+            block_id: Some(BlockId {
+                hash: vec![hash; 32],
+                part_set_header: Some(PartSetHeader {
+                    total: 1,
+                    hash: vec![hash; 32],
+                }),
+            }),
+            timestamp: Some(Timestamp { seconds, nanos: 0 }),
+            extension: b"extension".to_vec(),
+            ..Default::default()
+        }
+    }
+
+    pub fn run_tests() {
+        println!();
+        let mut failures = 0;
+
+        count_failures!(failures, {
+            test_consensus_signer_signs_vote();
+            test_consensus_signer_refuses_regression();
+            test_consensus_signer_same_hrs();
+            test_consensus_signer_nil_block_id();
+            test_consensus_signer_light_client_watermark();
+        });
+
+        if failures != 0 {
+            panic!("{}: {} tests failed", file!(), failures);
+        }
+    }
+
+    fn test_consensus_signer_signs_vote() {
+        let mut signer = signer();
+        let mut precommit = vote(SignedMsgType::Precommit, 10, 0, 1, 100);
+        signer.sign_vote(CHAIN_ID, &mut precommit, false).unwrap();
+
+        let public_key = VerificationKey::try_from(signer.public_key()).unwrap();
+        let signature = |bytes: &[u8]| {
+            let mut signature = [0u8; 64];
+            signature.copy_from_slice(bytes);
+            Signature::from(signature)
+        };
+
+        assert!(public_key
+            .verify(
+                &signature(&precommit.signature),
+                &vote_sign_bytes(CHAIN_ID, &precommit)
+            )
+            .is_ok());
+        assert!(public_key
+            .verify(
+                &signature(&precommit.extension_signature),
+                &vote_extension_sign_bytes(CHAIN_ID, &precommit)
+            )
+            .is_ok());
+    }
+
+    fn test_consensus_signer_refuses_regression() {
+        let mut signer = signer();
+        let mut precommit = vote(SignedMsgType::Precommit, 10, 1, 1, 100);
+        signer.sign_vote(CHAIN_ID, &mut precommit, false).unwrap();
+
+        // lower height, lower round, lower step
+        let mut old_height = vote(SignedMsgType::Precommit, 9, 5, 1, 100);
+        let mut old_round = vote(SignedMsgType::Precommit, 10, 0, 1, 100);
+        let mut old_step = vote(SignedMsgType::Prevote, 10, 1, 1, 100);
+        assert!(signer.sign_vote(CHAIN_ID, &mut old_height, false).is_err());
+        assert!(signer.sign_vote(CHAIN_ID, &mut old_round, false).is_err());
+        assert!(signer.sign_vote(CHAIN_ID, &mut old_step, false).is_err());
+
+        let mut proposal = Proposal {
+            r#type: SignedMsgType::Proposal as i32,
+            height: 10,
+            round: 1,
+            ..Default::default()
+        };
+        assert!(signer.sign_proposal(CHAIN_ID, &mut proposal).is_err());
+
+        let mut next_height = vote(SignedMsgType::Prevote, 11, 0, 1, 100);
+        assert!(signer.sign_vote(CHAIN_ID, &mut next_height, false).is_ok());
+    }
+
+    fn test_consensus_signer_same_hrs() {
+        let mut signer = signer();
+        let mut prevote = vote(SignedMsgType::Prevote, 10, 0, 1, 100);
+        signer.sign_vote(CHAIN_ID, &mut prevote, false).unwrap();
+
+        // the same vote gets the same signature
+        let mut again = vote(SignedMsgType::Prevote, 10, 0, 1, 100);
+        signer.sign_vote(CHAIN_ID, &mut again, false).unwrap();
+        assert_eq!(again.signature, prevote.signature);
+
+        // only the timestamp changed, so the previous timestamp and signature are returned
+        let mut retimed = vote(SignedMsgType::Prevote, 10, 0, 1, 200);
+        signer.sign_vote(CHAIN_ID, &mut retimed, false).unwrap();
+        assert_eq!(retimed.signature, prevote.signature);
+        assert_eq!(retimed.timestamp, prevote.timestamp);
+
+        // a different block is a double sign
+        let mut conflicting = vote(SignedMsgType::Prevote, 10, 0, 2, 100);
+        assert!(signer.sign_vote(CHAIN_ID, &mut conflicting, false).is_err());
+    }
+
+    fn test_consensus_signer_nil_block_id() {
+        let mut signer = signer();
+        let mut nil_precommit = vote(SignedMsgType::Precommit, 10, 0, 1, 100);
+        nil_precommit.block_id = Some(BlockId::default());
+        let mut without_block_id = nil_precommit.clone();
+        without_block_id.block_id = None;
+
+        assert_eq!(
+            vote_sign_bytes(CHAIN_ID, &nil_precommit),
+            vote_sign_bytes(CHAIN_ID, &without_block_id)
+        );
+
+        // nil precommits have no extension signature
+        signer
+            .sign_vote(CHAIN_ID, &mut nil_precommit, false)
+            .unwrap();
+        assert!(nil_precommit.extension_signature.is_empty());
+    }
+
+    fn test_consensus_signer_light_client_watermark() {
+        let mut signer = signer();
+        signer.observe_verified_height(10);
+
+        let mut committed = vote(SignedMsgType::Prevote, 10, 0, 1, 100);
+        assert!(signer.sign_vote(CHAIN_ID, &mut committed, false).is_err());
+        let mut proposal = Proposal {
+            r#type: SignedMsgType::Proposal as i32,
+            height: 9,
+            ..Default::default()
+        };
+        assert!(signer.sign_proposal(CHAIN_ID, &mut proposal).is_err());
+
+        let mut next = vote(SignedMsgType::Prevote, 11, 0, 1, 100);
+        assert!(signer.sign_vote(CHAIN_ID, &mut next, false).is_ok());
+
+        // the watermark never goes back
+        signer.observe_verified_height(5);
+        assert_eq!(signer.verified_height, 10);
+    }
+}
@@ -1,5 +1,15 @@
+use std::panic;
+
+use log::*;
 use sgx_types::sgx_status_t;
 
+use enclave_ffi_types::{NodeAuthResult, MAX_REGISTRATION_PACKAGE_LENGTH};
+use enclave_utils::output_buffer::{write_output, BufferTooSmall};
+use enclave_utils::{validate_const_ptr, validate_input_length, validate_mut_ptr};
+
+/// Vote extensions can be large, so allow up to the maximum block size
+const MAX_PRIVVAL_REQUEST_LENGTH: u32 = 22_020_096;
+
 /// # Safety
 ///  This function reads buffers which must be correctly initialized by the caller,
 /// see safety section of slice::[from_raw_parts](https://doc.rust-lang.org/std/slice/fn.from_raw_parts.html#safety)
@@ -18,6 +28,13 @@ pub unsafe extern "C" fn ecall_submit_block_signatures(
     decrypted_random: &mut [u8; 32],
     next_validator_set_evidence: &mut [u8; 32],
 ) -> sgx_status_t {
+    let _breadcrumbs =
+        enclave_utils::crash_breadcrumbs::enter_ecall("ecall_submit_block_signatures");
+
+    if !enclave_utils::self_test::self_test_passed() {
+        return sgx_status_t::SGX_ERROR_INVALID_STATE;
+    }
+
     #[cfg(feature = "light-client-validation")]
     {
         block_verifier::submit_block_signatures::submit_block_signatures_impl(
@@ -47,3 +64,489 @@ pub unsafe extern "C" fn ecall_submit_block_signatures(
         sgx_status_t::SGX_ERROR_ECALL_NOT_ALLOWED
     }
 }
+
+/// Runs the known-answer tests of the enclave's crypto, and returns a bit in `failures` for each
+/// test that failed. Consensus ecalls are refused until every test passed.
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_self_test(failures: &mut u32) -> sgx_status_t {
+    validate_mut_ptr!(
+        failures as *mut u32 as _,
+        std::mem::size_of::<u32>(),
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+
+    match panic::catch_unwind(crate::self_test::run_self_test) {
+        Ok(failed) => {
+            *failures = failed;
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(_) => {
+            error!("Call ecall_self_test panicked unexpectedly!");
+            enclave_utils::self_test::record_self_test(false);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// Hands the enclave the params of the compute module, with a proof of them against the app hash
+/// of the block it last verified, see `enclave_utils::compute_params`
+///
+/// # Safety
+///  This function reads buffers which must be correctly initialized by the caller,
+/// see safety section of slice::[from_raw_parts](https://doc.rust-lang.org/std/slice/fn.from_raw_parts.html#safety)
+///
+#[no_mangle]
+#[allow(unused_variables)]
+pub unsafe extern "C" fn ecall_submit_compute_params(
+    in_params: *const u8,
+    in_params_len: u32,
+    in_proof: *const u8,
+    in_proof_len: u32,
+) -> sgx_status_t {
+    #[cfg(feature = "light-client-validation")]
+    {
+        #[cfg(feature = "go-tests")]
+        {
+            // go-tests don't submit blocks, so there's no app hash to prove the params against
+            // if the env variable SKIP_LIGHT_CLIENT_VALIDATION is set to TRUE
+            let is_skip_light_client_validation = std::env::var("SKIP_LIGHT_CLIENT_VALIDATION");
+
+            if is_skip_light_client_validation
+                .unwrap_or_default()
+                .to_uppercase()
+                == "TRUE"
+            {
+                return enclave_utils::compute_params::submit_unproven_compute_params(
+                    in_params,
+                    in_params_len,
+                );
+            }
+        }
+
+        block_verifier::submit_compute_params::submit_compute_params_impl(
+            in_params,
+            in_params_len,
+            in_proof,
+            in_proof_len,
+        )
+    }
+
+    #[cfg(all(not(feature = "light-client-validation"), not(feature = "SGX_MODE_HW")))]
+    {
+        // there's no verified block to prove the params against, so tests take them as they are
+        enclave_utils::compute_params::submit_unproven_compute_params(in_params, in_params_len)
+    }
+
+    #[cfg(all(not(feature = "light-client-validation"), feature = "SGX_MODE_HW"))]
+    {
+        // this is an error so that if we're compiling in HW mode we don't forget to enable this feature
+        sgx_status_t::SGX_ERROR_ECALL_NOT_ALLOWED
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_consensus_key_init(public_key: &mut [u8; 32]) -> sgx_status_t {
+    validate_mut_ptr!(
+        public_key.as_mut_ptr(),
+        public_key.len(),
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+
+    let result = panic::catch_unwind(crate::consensus_signer::init_consensus_key);
+
+    match result {
+        Ok(Ok(key)) => {
+            public_key.copy_from_slice(&key);
+            sgx_status_t::SGX_SUCCESS
+        }
+        Ok(Err(status)) => status,
+        Err(_) => {
+            error!("Call ecall_consensus_key_init panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_consensus_sign(
+    request: *const u8,
+    request_len: u32,
+    response: *mut u8,
+    response_capacity: u32,
+    response_len: &mut u32,
+) -> sgx_status_t {
+    if !enclave_utils::self_test::self_test_passed() {
+        return sgx_status_t::SGX_ERROR_INVALID_STATE;
+    }
+
+    validate_input_length!(
+        request_len,
+        "request",
+        MAX_PRIVVAL_REQUEST_LENGTH,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    validate_const_ptr!(
+        request,
+        request_len as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+    validate_mut_ptr!(
+        response,
+        response_capacity as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+    let request_slice = std::slice::from_raw_parts(request, request_len as usize);
+
+    let result =
+        panic::catch_unwind(|| crate::consensus_signer::handle_privval_request(request_slice));
+
+    match result {
+        // Signing again at the same height, round and step returns the same signature, so the
+        // caller can retry with a larger buffer
+        Ok(Ok(encoded)) => {
+            match write_output(&encoded, response, response_capacity, response_len) {
+                Ok(()) => sgx_status_t::SGX_SUCCESS,
+                Err(BufferTooSmall { needed }) => {
+                    debug!(
+                        "privval response ({}) is larger than the buffer ({})",
+                        needed, response_capacity
+                    );
+                    sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+                }
+            }
+        }
+        Ok(Err(status)) => status,
+        Err(_) => {
+            error!("Call ecall_consensus_sign panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_get_seed_heartbeat_key(public_key: &mut [u8; 32]) -> sgx_status_t {
+    validate_mut_ptr!(
+        public_key.as_mut_ptr(),
+        public_key.len(),
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+
+    let result = panic::catch_unwind(crate::seed_heartbeat::heartbeat_public_key);
+
+    match result {
+        Ok(Ok(key)) => {
+            public_key.copy_from_slice(&key);
+            sgx_status_t::SGX_SUCCESS
+        }
+        Ok(Err(status)) => status,
+        Err(_) => {
+            error!("Call ecall_get_seed_heartbeat_key panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_seed_heartbeat(
+    height: u64,
+    node_public_key: &mut [u8; 32],
+    signature: &mut [u8; crate::seed_heartbeat::SIGNATURE_LENGTH],
+) -> sgx_status_t {
+    validate_mut_ptr!(
+        node_public_key.as_mut_ptr(),
+        node_public_key.len(),
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+    validate_mut_ptr!(
+        signature.as_mut_ptr(),
+        signature.len(),
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+
+    let result = panic::catch_unwind(|| crate::seed_heartbeat::sign_heartbeat(height));
+
+    match result {
+        Ok(Ok(heartbeat)) => {
+            node_public_key.copy_from_slice(&heartbeat.node_public_key);
+            signature.copy_from_slice(&heartbeat.signature);
+            sgx_status_t::SGX_SUCCESS
+        }
+        Ok(Err(status)) => status,
+        Err(_) => {
+            error!("Call ecall_seed_heartbeat panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// Returns how many of the platforms that registered are at each TCB status, TCB date and
+/// advisory, signed by the enclave, as json
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_platform_census(
+    census: *mut u8,
+    census_capacity: u32,
+    census_len: &mut u32,
+) -> sgx_status_t {
+    validate_mut_ptr!(
+        census,
+        census_capacity as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+
+    let result = panic::catch_unwind(crate::registration::platform_census::encoded_census);
+
+    match result {
+        Ok(Ok(encoded)) => match write_output(&encoded, census, census_capacity, census_len) {
+            Ok(()) => sgx_status_t::SGX_SUCCESS,
+            Err(BufferTooSmall { needed }) => {
+                debug!(
+                    "platform census ({}) is larger than the buffer ({})",
+                    needed, census_capacity
+                );
+                sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+            }
+        },
+        Ok(Err(status)) => status,
+        Err(_) => {
+            error!("Call ecall_platform_census panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// Returns a DCAP quote that attests the code with `code_hash` runs under this enclave on
+/// `chain_id` at `height`, with the statement it attests, as json
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_attest_code_hash(
+    code_hash: &[u8; 32],
+    chain_id: *const u8,
+    chain_id_len: usize,
+    height: u64,
+    attestation: *mut u8,
+    attestation_capacity: u32,
+    attestation_len: &mut u32,
+) -> sgx_status_t {
+    validate_input_length!(
+        chain_id_len,
+        "chain_id",
+        crate::registration::code_attestation::MAX_CHAIN_ID_LENGTH,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    validate_const_ptr!(
+        code_hash.as_ptr(),
+        code_hash.len(),
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+    validate_const_ptr!(chain_id, chain_id_len, sgx_status_t::SGX_ERROR_UNEXPECTED);
+    validate_mut_ptr!(
+        attestation,
+        attestation_capacity as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+    let chain_id = match std::str::from_utf8(std::slice::from_raw_parts(chain_id, chain_id_len)) {
+        Ok(chain_id) => chain_id,
+        Err(_) => return sgx_status_t::SGX_ERROR_INVALID_PARAMETER,
+    };
+
+    let result = panic::catch_unwind(|| {
+        crate::registration::code_attestation::encoded_code_attestation(code_hash, chain_id, height)
+    });
+
+    match result {
+        Ok(Ok(encoded)) => {
+            match write_output(&encoded, attestation, attestation_capacity, attestation_len) {
+                Ok(()) => sgx_status_t::SGX_SUCCESS,
+                Err(BufferTooSmall { needed }) => {
+                    debug!(
+                        "code attestation ({}) is larger than the buffer ({})",
+                        needed, attestation_capacity
+                    );
+                    sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+                }
+            }
+        }
+        Ok(Err(status)) => status,
+        Err(_) => {
+            error!("Call ecall_attest_code_hash panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// Returns a registration package of the node's registration key, attested in the key scheme
+/// with the id `key_scheme`: the combined cert with a DCAP quote and its collateral, the key and
+/// what the enclave read from its report, as json. The combined cert file isn't written.
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_create_registration_package(
+    key_scheme: u8,
+    package: *mut u8,
+    package_capacity: u32,
+    package_len: &mut u32,
+) -> sgx_status_t {
+    validate_mut_ptr!(
+        package,
+        package_capacity as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+    let scheme = match enclave_wire::KeyScheme::from_id(key_scheme) {
+        Some(scheme) => scheme,
+        None => {
+            error!("Unknown key scheme: {}", key_scheme);
+            return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        }
+    };
+
+    let result = panic::catch_unwind(|| crate::registration::package::encoded_package(scheme));
+
+    match result {
+        Ok(Ok(encoded)) => match write_output(&encoded, package, package_capacity, package_len) {
+            Ok(()) => sgx_status_t::SGX_SUCCESS,
+            Err(BufferTooSmall { needed }) => {
+                debug!(
+                    "registration package ({}) is larger than the buffer ({})",
+                    needed, package_capacity
+                );
+                sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+            }
+        },
+        Ok(Err(status)) => status,
+        Err(_) => {
+            error!("Call ecall_create_registration_package panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// Verifies a registration package the way `ecall_authenticate_new_node` verifies a cert, at the
+/// enclave's time, without issuing anything. `collateral_expiration` is set to the time at which
+/// the earliest of its collateral expires.
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_verify_registration_package(
+    package: *const u8,
+    package_len: u32,
+    collateral_expiration: &mut i64,
+) -> NodeAuthResult {
+    *collateral_expiration = 0;
+
+    validate_input_length!(
+        package_len as usize,
+        "package",
+        MAX_REGISTRATION_PACKAGE_LENGTH,
+        NodeAuthResult::InputTooLarge
+    );
+    validate_const_ptr!(package, package_len as usize, NodeAuthResult::InvalidInput);
+    let package = std::slice::from_raw_parts(package, package_len as usize);
+
+    match panic::catch_unwind(|| crate::registration::package::verify_package(package)) {
+        Ok(Ok(expiration)) => {
+            *collateral_expiration = expiration;
+            NodeAuthResult::Success
+        }
+        Ok(Err(e)) => e,
+        Err(_) => {
+            error!("Call ecall_verify_registration_package panicked unexpectedly!");
+            NodeAuthResult::Panic
+        }
+    }
+}
+
+/// Returns how often the enclave made each ocall, and how many of those calls were rejected or
+/// failed, as json
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_get_ocall_stats(
+    stats: *mut u8,
+    stats_capacity: u32,
+    stats_len: &mut u32,
+) -> sgx_status_t {
+    validate_mut_ptr!(
+        stats,
+        stats_capacity as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+
+    let result = panic::catch_unwind(crate::ocall_stats::encoded_ocall_stats);
+
+    match result {
+        Ok(Ok(encoded)) => match write_output(&encoded, stats, stats_capacity, stats_len) {
+            Ok(()) => sgx_status_t::SGX_SUCCESS,
+            Err(BufferTooSmall { needed }) => {
+                debug!(
+                    "ocall stats ({}) are larger than the buffer ({})",
+                    needed, stats_capacity
+                );
+                sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+            }
+        },
+        Ok(Err(status)) => status,
+        Err(_) => {
+            error!("Call ecall_get_ocall_stats panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// Returns the breadcrumbs of the last panics and fatal errors of the enclave, which are kept
+/// across restarts, as json
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_get_crash_breadcrumbs(
+    breadcrumbs: *mut u8,
+    breadcrumbs_capacity: u32,
+    breadcrumbs_len: &mut u32,
+) -> sgx_status_t {
+    validate_mut_ptr!(
+        breadcrumbs,
+        breadcrumbs_capacity as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+
+    let result = panic::catch_unwind(enclave_utils::crash_breadcrumbs::encoded_crash_breadcrumbs);
+
+    match result {
+        Ok(Ok(encoded)) => {
+            match write_output(&encoded, breadcrumbs, breadcrumbs_capacity, breadcrumbs_len) {
+                Ok(()) => sgx_status_t::SGX_SUCCESS,
+                Err(BufferTooSmall { needed }) => {
+                    debug!(
+                        "crash breadcrumbs ({}) are larger than the buffer ({})",
+                        needed, breadcrumbs_capacity
+                    );
+                    sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+                }
+            }
+        }
+        Ok(Err(status)) => status,
+        Err(_) => {
+            error!("Call ecall_get_crash_breadcrumbs panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
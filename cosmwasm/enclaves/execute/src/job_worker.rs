@@ -0,0 +1,190 @@
+//! The worker side of off-chain jobs: runs a job's handler and attests its result.
+//!
+//! The result is signed with a secp256k1 key the worker generates for the job and never keeps.
+//! The report data of a DCAP quote commits to the compressed public key, followed by
+//! `JOB_WORKER_REPORT_DOMAIN`, so the chain can tell the result came from an instance of this
+//! enclave, and that the quote was made for a job's result, see `job_message`.
+
+use std::panic;
+use std::vec::Vec;
+
+use log::*;
+use sgx_types::sgx_status_t;
+
+use enclave_contract_engine::{run_job, worker_report_data, SignedJobResult, WORKER_PUBKEY_SIZE};
+use enclave_crypto::{rand_slice, HASH_SIZE};
+use enclave_ffi_types::{Ctx, EnclaveError, MAX_ENV_LENGTH};
+use enclave_utils::input_limits::{max_msg_length, max_wasm_length};
+use enclave_utils::{oom_handler, validate_const_ptr, validate_input_length, validate_mut_ptr};
+
+use crate::registration::get_quote_ecdsa;
+
+/// Runs the handler of an off-chain job, and writes the signed and attested result, as json.
+/// `env` is the env of the contract that enqueued the job.
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn ecall_run_job(
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    handler: *const u8,
+    handler_len: usize,
+    contract_code_hash: &[u8; HASH_SIZE],
+    env: *const u8,
+    env_len: usize,
+    job: *const u8,
+    job_len: usize,
+    result: *mut u8,
+    result_capacity: u32,
+    result_len: &mut u32,
+) -> sgx_status_t {
+    validate_input_length!(
+        handler_len,
+        "handler",
+        max_wasm_length(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    validate_input_length!(
+        env_len,
+        "env",
+        MAX_ENV_LENGTH,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    validate_input_length!(
+        job_len,
+        "job",
+        max_msg_length(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    validate_mut_ptr!(
+        used_gas as _,
+        std::mem::size_of::<u64>(),
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+    validate_const_ptr!(handler, handler_len, sgx_status_t::SGX_ERROR_UNEXPECTED);
+    validate_const_ptr!(
+        contract_code_hash.as_ptr(),
+        contract_code_hash.len(),
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+    validate_const_ptr!(env, env_len, sgx_status_t::SGX_ERROR_UNEXPECTED);
+    validate_const_ptr!(job, job_len, sgx_status_t::SGX_ERROR_UNEXPECTED);
+    validate_mut_ptr!(
+        result,
+        result_capacity as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+
+    if oom_handler::register_oom_handler().is_err() {
+        error!("Could not register OOM handler!");
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    let handler = std::slice::from_raw_parts(handler, handler_len);
+    let env = std::slice::from_raw_parts(env, env_len);
+    let job = std::slice::from_raw_parts(job, job_len);
+    let encoded = panic::catch_unwind(|| {
+        let mut local_used_gas = *used_gas;
+        let encoded = encoded_job_result(
+            context,
+            gas_limit,
+            &mut local_used_gas,
+            handler,
+            contract_code_hash,
+            env,
+            job,
+        );
+        *used_gas = local_used_gas;
+        encoded
+    });
+
+    if oom_handler::restore_safety_buffer().is_err() {
+        error!("Could not restore OOM safety buffer!");
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    match encoded {
+        Ok(Ok(encoded)) => {
+            // Tell the caller how large a buffer the result needs, even when it doesn't fit
+            *result_len = encoded.len() as u32;
+            if encoded.len() > result_capacity as usize {
+                debug!(
+                    "job result ({}) is larger than the buffer ({})",
+                    encoded.len(),
+                    result_capacity
+                );
+                return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+            }
+            std::ptr::copy_nonoverlapping(encoded.as_ptr(), result, encoded.len());
+            sgx_status_t::SGX_SUCCESS
+        }
+        Ok(Err(err)) => {
+            warn!("Failed to run job: {}", err);
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        }
+        Err(_) => {
+            *used_gas = gas_limit / 2;
+            if oom_handler::get_then_clear_oom_happened() {
+                error!("Call ecall_run_job failed because the enclave ran out of memory!");
+                sgx_status_t::SGX_ERROR_OUT_OF_MEMORY
+            } else {
+                error!("Call ecall_run_job panicked unexpectedly!");
+                sgx_status_t::SGX_ERROR_UNEXPECTED
+            }
+        }
+    }
+}
+
+/// A result for `job` that's signed and attested, as json
+#[allow(clippy::too_many_arguments)]
+fn encoded_job_result(
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: &mut u64,
+    handler: &[u8],
+    contract_code_hash: &[u8; HASH_SIZE],
+    env: &[u8],
+    job: &[u8],
+) -> Result<Vec<u8>, EnclaveError> {
+    let mut result = run_job(
+        context,
+        gas_limit,
+        used_gas,
+        handler,
+        contract_code_hash,
+        env,
+        job,
+    )?;
+
+    let worker_pubkey = sign_with_fresh_key(&mut result)?;
+    let (quote, collateral) =
+        get_quote_ecdsa(&worker_report_data(&worker_pubkey)).map_err(|status| {
+            error!("Failed to attest the job worker's key: {}", status);
+            EnclaveError::FailedFunctionCall
+        })?;
+    result.attest(quote, collateral);
+
+    serde_json::to_vec(&result).map_err(|err| {
+        error!("Failed to encode the job result: {}", err);
+        EnclaveError::FailedSeal
+    })
+}
+
+fn sign_with_fresh_key(
+    result: &mut SignedJobResult,
+) -> Result<[u8; WORKER_PUBKEY_SIZE], EnclaveError> {
+    // a random 32 bytes is a valid key but for a negligible fraction, so retrying is enough
+    for _ in 0..8 {
+        let mut secret_key = [0u8; 32];
+        rand_slice(&mut secret_key).map_err(|_| EnclaveError::FailedSeal)?;
+        if let Ok(worker_pubkey) = result.sign(&secret_key) {
+            return Ok(worker_pubkey);
+        }
+    }
+
+    error!("Failed to generate a job worker key");
+    Err(EnclaveError::FailedSeal)
+}
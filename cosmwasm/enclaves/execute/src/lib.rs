@@ -13,8 +13,14 @@ use enclave_utils::logger::get_log_level;
 
 // Force linking to all the ecalls/ocalls in this package
 pub use enclave_contract_engine;
+mod consensus_signer;
 mod ecalls;
+mod job_worker;
+mod ocall_stats;
 pub mod registration;
+mod secure_time;
+mod seed_heartbeat;
+mod self_test;
 mod tests;
 
 #[allow(unused_imports)]
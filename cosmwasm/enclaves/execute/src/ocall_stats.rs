@@ -0,0 +1,33 @@
+//! Counters of the calls the enclave made to the host, for the node's metrics.
+
+use log::*;
+use serde::Serialize;
+use sgx_types::sgx_status_t;
+
+use enclave_crypto::ocalls::ocall_stats;
+
+#[derive(Serialize)]
+struct EncodedOcallStats {
+    name: &'static str,
+    calls: u64,
+    rejected: u64,
+    failed: u64,
+}
+
+/// The counters of every ocall, as a json array
+pub fn encoded_ocall_stats() -> Result<Vec<u8>, sgx_status_t> {
+    let stats: Vec<EncodedOcallStats> = ocall_stats()
+        .into_iter()
+        .map(|stats| EncodedOcallStats {
+            name: stats.name,
+            calls: stats.calls,
+            rejected: stats.rejected,
+            failed: stats.failed,
+        })
+        .collect();
+
+    serde_json::to_vec(&stats).map_err(|err| {
+        error!("Error encoding ocall stats to json: {:?}", err);
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })
+}
@@ -1,6 +1,5 @@
 use core::mem;
 
-use enclave_crypto::dcap::verify_quote_any;
 use enclave_crypto::KeyPair;
 use std::vec::Vec;
 
@@ -50,8 +49,13 @@ use enclave_crypto::consts::*;
 #[cfg(all(feature = "SGX_MODE_HW", feature = "production"))]
 use std::sgxfs::remove as SgxFsRemove;
 
+use super::collateral_cache::verify_quote_cached;
+
+#[cfg(feature = "SGX_MODE_HW")]
+use super::collateral_cache::{own_collateral, set_own_collateral};
+
 #[cfg(feature = "SGX_MODE_HW")]
-use super::ocalls::{
+use enclave_crypto::ocalls::{
     ocall_get_ias_socket, ocall_get_quote, ocall_get_quote_ecdsa, ocall_get_quote_ecdsa_collateral,
     ocall_get_quote_ecdsa_params, ocall_sgx_init_quote,
 };
@@ -233,12 +237,15 @@ pub fn in_grace_period(timestamp: u64) -> bool {
     timestamp < 1692626400_u64
 }
 
+/// Verify a DCAP quote and return its report body, the verification result, and the time (unix
+/// seconds) at which the earliest of its collateral expires. A quote already verified against the
+/// same collateral isn't sent to the QvE again, see `collateral_cache`.
 pub fn verify_quote_sgx(
     vec_quote: &[u8],
     vec_coll: &[u8],
     time_s: i64,
-) -> Result<(sgx_report_body_t, sgx_ql_qv_result_t), sgx_status_t> {
-    let qv_result = verify_quote_any(vec_quote, vec_coll, time_s)?;
+) -> Result<(sgx_report_body_t, sgx_ql_qv_result_t, i64), sgx_status_t> {
+    let (qv_result, collateral_expiration) = verify_quote_cached(vec_quote, vec_coll, time_s)?;
 
     if vec_quote.len() < mem::size_of::<sgx_quote_t>() {
         trace!("Quote too small");
@@ -254,7 +261,7 @@ pub fn verify_quote_sgx(
             Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
         } else {
             let report_body = (*my_p_quote).report_body;
-            Ok((report_body, qv_result))
+            Ok((report_body, qv_result, collateral_expiration))
         }
     }
 }
@@ -328,10 +335,38 @@ pub fn get_quote_ecdsa_untested(pub_k: &[u8]) -> Result<(Vec<u8>, Vec<u8>), sgx_
         return Err(e);
     }
 
+    let vec_coll = match own_collateral() {
+        Some(vec_coll) => {
+            trace!("Reusing the cached collateral");
+            vec_coll
+        }
+        None => fetch_quote_collateral(&vec_quote)?,
+    };
+
+    println!(
+        "mr_signer = {}",
+        orig_hex::encode(my_report.body.mr_signer.m)
+    );
+    println!(
+        "mr_enclave = {}",
+        orig_hex::encode(my_report.body.mr_enclave.m)
+    );
+    println!(
+        "report_data = {}",
+        orig_hex::encode(my_report.body.report_data.d)
+    );
+
+    Ok((vec_quote, vec_coll))
+}
+
+/// Fetch the collateral of a quote from the PCCS, through the host
+#[cfg(feature = "SGX_MODE_HW")]
+fn fetch_quote_collateral(vec_quote: &[u8]) -> Result<Vec<u8>, sgx_status_t> {
+    let mut rt: sgx_status_t = sgx_status_t::default();
     let mut vec_coll: Vec<u8> = vec![0; 0x4000];
     let mut size_coll: u32 = 0;
 
-    res = unsafe {
+    let mut res = unsafe {
         ocall_get_quote_ecdsa_collateral(
             &mut rt as *mut sgx_status_t,
             vec_quote.as_ptr(),
@@ -370,20 +405,9 @@ pub fn get_quote_ecdsa_untested(pub_k: &[u8]) -> Result<(Vec<u8>, Vec<u8>), sgx_
         }
     }
 
-    println!(
-        "mr_signer = {}",
-        orig_hex::encode(my_report.body.mr_signer.m)
-    );
-    println!(
-        "mr_enclave = {}",
-        orig_hex::encode(my_report.body.mr_enclave.m)
-    );
-    println!(
-        "report_data = {}",
-        orig_hex::encode(my_report.body.report_data.d)
-    );
+    set_own_collateral(&vec_coll);
 
-    Ok((vec_quote, vec_coll))
+    Ok(vec_coll)
 }
 
 #[cfg(feature = "SGX_MODE_HW")]
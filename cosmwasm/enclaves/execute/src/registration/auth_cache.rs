@@ -0,0 +1,146 @@
+//! A cache of the DCAP attestations of registrations the enclave verified.
+//!
+//! A registration tx is authenticated in CheckTx, again when it's simulated, and again in
+//! DeliverTx, and verifying its DCAP quote takes hundreds of milliseconds each time. Only the
+//! quote is cached, by the hash of the cert's quote and collateral: the enclave and report data it
+//! attests, the block time it was verified at and the expiry of its collateral. Like the
+//! collateral cache, an entry is only used while the block time is between the two, in which
+//! verifying the quote again returns the same, so it doesn't matter which verification filled it.
+//!
+//! Whatever else decides a registration, the checks against governance and chain state, the
+//! attestation policy among them, and the encryption of the seeds, isn't cached and runs every
+//! time.
+//! Entries are kept in memory, and failed verifications aren't cached.
+
+use std::collections::HashMap;
+use std::sync::SgxMutex;
+
+use lazy_static::lazy_static;
+use log::*;
+
+use enclave_crypto::{sha_256, HASH_SIZE};
+
+use super::cert::EnclaveIdentity;
+
+/// Cap on the cached attestations, the ones that expired are dropped when it's reached
+const MAX_ENTRIES: usize = 256;
+
+lazy_static! {
+    static ref AUTH_CACHE: SgxMutex<HashMap<[u8; HASH_SIZE], VerifiedAttestation>> =
+        SgxMutex::new(HashMap::new());
+}
+
+/// What verifying a DCAP quote showed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerifiedAttestation {
+    pub identity: EnclaveIdentity,
+    /// The report data of the quote, which binds the node's keys
+    pub report_data: [u8; 64],
+    /// When the earliest of the collateral expires, in unix seconds
+    pub collateral_expiration: i64,
+    /// The block time it was verified at, in unix seconds
+    pub verified_at: i64,
+}
+
+impl VerifiedAttestation {
+    /// Whether verifying the quote at `time_s` returns the same
+    fn holds_at(&self, time_s: i64) -> bool {
+        self.verified_at <= time_s && time_s < self.collateral_expiration
+    }
+}
+
+/// Hashes the quote and the collateral apart, so moving bytes from one to the other is another
+/// attestation
+fn attestation_hash(quote: &[u8], collateral: &[u8]) -> [u8; HASH_SIZE] {
+    sha_256(&[sha_256(quote), sha_256(collateral)].concat())
+}
+
+/// The verified quote of a cert, if it was verified at an earlier time that still holds at
+/// `time_s`
+pub fn cached_attestation(
+    quote: &[u8],
+    collateral: &[u8],
+    time_s: i64,
+) -> Option<VerifiedAttestation> {
+    let cache = AUTH_CACHE.lock().unwrap();
+    let verified = cache
+        .get(&attestation_hash(quote, collateral))
+        .copied()
+        .filter(|verified| verified.holds_at(time_s));
+    if verified.is_some() {
+        trace!("Attestation was already verified");
+    }
+    verified
+}
+
+pub fn cache_attestation(quote: &[u8], collateral: &[u8], verified: VerifiedAttestation) {
+    let mut cache = AUTH_CACHE.lock().unwrap();
+    if cache.len() >= MAX_ENTRIES {
+        cache.retain(|_, cached| cached.collateral_expiration > verified.verified_at);
+        if cache.len() >= MAX_ENTRIES {
+            debug!("The attestation cache is full, not caching the attestation");
+            return;
+        }
+    }
+
+    cache.insert(attestation_hash(quote, collateral), verified);
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn verified(verified_at: i64, collateral_expiration: i64) -> VerifiedAttestation {
+        VerifiedAttestation {
+            identity: EnclaveIdentity {
+                mr_enclave: [2; 32],
+                mr_signer: [3; 32],
+                isv_prod_id: 0,
+                isv_svn: 1,
+            },
+            report_data: [1; 64],
+            collateral_expiration,
+            verified_at,
+        }
+    }
+
+    pub fn test_auth_cache_holds_until_expiry() {
+        assert_eq!(cached_attestation(b"quote", b"collateral", 1_000), None);
+
+        cache_attestation(b"quote", b"collateral", verified(1_000, 2_000));
+        assert_eq!(
+            cached_attestation(b"quote", b"collateral", 1_000),
+            Some(verified(1_000, 2_000))
+        );
+        assert_eq!(
+            cached_attestation(b"quote", b"collateral", 1_999),
+            Some(verified(1_000, 2_000))
+        );
+        assert_eq!(
+            cached_attestation(b"other quote", b"collateral", 1_000),
+            None
+        );
+        assert_eq!(cached_attestation(b"quotec", b"ollateral", 1_000), None);
+
+        // not before it was verified, nor once its collateral expired
+        assert_eq!(cached_attestation(b"quote", b"collateral", 999), None);
+        assert_eq!(cached_attestation(b"quote", b"collateral", 2_000), None);
+    }
+
+    pub fn test_auth_cache_drops_expired_entries() {
+        for i in 0..MAX_ENTRIES as u32 {
+            cache_attestation(&i.to_le_bytes(), b"collateral", verified(1_000, 1_500));
+        }
+
+        // a full cache makes room by dropping the attestations that expired
+        cache_attestation(b"later", b"collateral", verified(1_500, 3_000));
+        assert_eq!(
+            cached_attestation(b"later", b"collateral", 1_500),
+            Some(verified(1_500, 3_000))
+        );
+        assert_eq!(
+            cached_attestation(&0u32.to_le_bytes(), b"collateral", 1_000),
+            None
+        );
+    }
+}
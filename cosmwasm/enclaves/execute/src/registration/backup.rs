@@ -0,0 +1,318 @@
+//! Backup of the registration key to an escrow enclave, run by the operator on another machine.
+//!
+//! The consensus seeds are never backed up: a restored node gets them again from the network with
+//! its registration key, the same way it got them when it registered. The registration key only
+//! ever leaves the enclave encrypted to another enclave with the same MRSIGNER:
+//! * op 6 (node): encrypts the registration key to the escrow enclave's migration key, taken from
+//!   its verified report
+//! * op 7 (escrow): re-encrypts the backup to the migration key of the restored enclave, taken from
+//!   its verified report, if it's at least the security version that made the backup
+//! * op 8 (restored node): decrypts the backup and saves the registration key
+//!
+//! Neither enclave may be below the minimum security version governance scheduled, see
+//! `svn_ratchet`.
+//!
+use core::convert::TryInto;
+use enclave_crypto::consts::{
+    make_sgx_secret_path, FILE_BACKUP_DATA, FILE_BACKUP_ESCROW_CERT_LOCAL,
+    FILE_BACKUP_ESCROW_CERT_REMOTE, FILE_BACKUP_RESTORE_CERT_LOCAL,
+    FILE_BACKUP_RESTORE_CERT_REMOTE, FILE_BACKUP_RESTORE_DATA, SELF_REPORT_BODY,
+};
+use enclave_crypto::ed25519::Ed25519PrivateKey;
+use enclave_crypto::{AESKey, Ed25519PublicKey, KeyPair, SIVEncryptable};
+use enclave_utils::{Keychain, KEY_MANAGER};
+use log::*;
+use sgx_types::{sgx_report_body_t, sgx_status_t, SgxResult};
+use std::fs::File;
+use std::io::prelude::*;
+
+use super::offchain::get_verified_report_body;
+use super::svn_ratchet::is_peer_isv_svn_allowed;
+
+const BACKUP_DATA_VER: u32 = 1;
+
+struct RegistrationBackup {
+    /// The ISV SVN of the enclave that made the backup
+    isv_svn: u16,
+    registration_key: KeyPair,
+}
+
+impl RegistrationBackup {
+    fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&BACKUP_DATA_VER.to_le_bytes());
+        data.extend_from_slice(&self.isv_svn.to_le_bytes());
+        data.extend_from_slice(self.registration_key.get_privkey());
+        data
+    }
+
+    fn deserialize(data: &[u8]) -> SgxResult<Self> {
+        if data.len() != 4 + 2 + 32 {
+            error!("Invalid backup size: {}", data.len());
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let ver = u32::from_le_bytes(data[..4].try_into().unwrap());
+        if ver != BACKUP_DATA_VER {
+            error!("Unsupported backup version: {}", ver);
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let isv_svn = u16::from_le_bytes(data[4..6].try_into().unwrap());
+
+        let mut sk = Ed25519PrivateKey::default();
+        sk.get_mut().copy_from_slice(&data[6..]);
+
+        Ok(Self {
+            isv_svn,
+            registration_key: KeyPair::from_sk(sk),
+        })
+    }
+}
+
+/// Encrypts `data` to `other_pub_k` the same way sealed data is exported for a migration: with a
+/// fresh key pair, whose public key is prepended to the result
+fn encrypt_to(other_pub_k: &Ed25519PublicKey, data: &[u8]) -> SgxResult<Vec<u8>> {
+    let kp = KeyPair::new().map_err(|e| {
+        error!("Failed to generate key pair: {}", e);
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })?;
+    let aes_key = AESKey::new_from_slice(&kp.diffie_hellman(other_pub_k));
+
+    let data_encrypted = aes_key.encrypt_siv(data, None).map_err(|e| {
+        error!("Failed to encrypt backup: {}", e);
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })?;
+
+    let mut res = kp.get_pubkey().to_vec();
+    res.extend_from_slice(&data_encrypted);
+    Ok(res)
+}
+
+fn decrypt_with_migration_keys(data: &[u8]) -> SgxResult<Vec<u8>> {
+    if data.len() < 32 {
+        error!("Backup too short");
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let other_pub_k: Ed25519PublicKey = data[..32].try_into().unwrap();
+
+    let kp = Keychain::get_migration_keys();
+    let aes_key = AESKey::new_from_slice(&kp.diffie_hellman(&other_pub_k));
+
+    aes_key.decrypt_siv(&data[32..], None).map_err(|e| {
+        error!("Can't decrypt backup: {}", e);
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })
+}
+
+fn read_file(file_name: &str) -> SgxResult<Vec<u8>> {
+    let mut data = Vec::new();
+    File::open(make_sgx_secret_path(file_name))
+        .and_then(|mut f_in| f_in.read_to_end(&mut data))
+        .map_err(|e| {
+            error!("failed to read file {}: {}", file_name, e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })?;
+    Ok(data)
+}
+
+fn write_file(file_name: &str, data: &[u8]) -> SgxResult<()> {
+    File::create(make_sgx_secret_path(file_name))
+        .and_then(|mut f_out| f_out.write_all(data))
+        .map_err(|e| {
+            error!("failed to write file {}: {}", file_name, e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })
+}
+
+fn is_escrow_approved(report: &sgx_report_body_t) -> bool {
+    if report.mr_signer.m != SELF_REPORT_BODY.mr_signer.m {
+        println!("Escrow enclave uses different signer");
+        return false;
+    }
+
+    if !is_peer_isv_svn_allowed(report.isv_svn) {
+        println!(
+            "Escrow enclave security version {} is below the scheduled minimum",
+            report.isv_svn
+        );
+        return false;
+    }
+
+    true
+}
+
+fn is_restore_approved(report: &sgx_report_body_t, backup: &RegistrationBackup) -> bool {
+    if report.mr_signer.m != SELF_REPORT_BODY.mr_signer.m {
+        println!("Restored enclave uses different signer");
+        return false;
+    }
+
+    if report.isv_svn < backup.isv_svn {
+        println!(
+            "Restored enclave security version {} is lower than the backup's {}",
+            report.isv_svn, backup.isv_svn
+        );
+        return false;
+    }
+
+    if !is_peer_isv_svn_allowed(report.isv_svn) {
+        println!(
+            "Restored enclave security version {} is below the scheduled minimum",
+            report.isv_svn
+        );
+        return false;
+    }
+
+    true
+}
+
+fn try_export_registration_backup() -> SgxResult<()> {
+    let registration_key = KEY_MANAGER.get_registration_key().map_err(|_| {
+        error!("No registration key to back up");
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })?;
+
+    let escrow = get_verified_report_body(
+        FILE_BACKUP_ESCROW_CERT_LOCAL,
+        FILE_BACKUP_ESCROW_CERT_REMOTE,
+    )?;
+
+    if !is_escrow_approved(&escrow) {
+        return Err(sgx_status_t::SGX_ERROR_NO_PRIVILEGE);
+    }
+
+    let backup = RegistrationBackup {
+        isv_svn: SELF_REPORT_BODY.isv_svn,
+        registration_key,
+    };
+
+    let escrow_pub_k = &escrow.report_data.d[0..32].try_into().unwrap();
+    let data = encrypt_to(escrow_pub_k, &backup.serialize())?;
+
+    write_file(FILE_BACKUP_DATA, &data)
+}
+
+fn try_release_registration_backup() -> SgxResult<()> {
+    let backup = RegistrationBackup::deserialize(&decrypt_with_migration_keys(&read_file(
+        FILE_BACKUP_DATA,
+    )?)?)?;
+
+    let target = get_verified_report_body(
+        FILE_BACKUP_RESTORE_CERT_LOCAL,
+        FILE_BACKUP_RESTORE_CERT_REMOTE,
+    )?;
+
+    if !is_restore_approved(&target, &backup) {
+        return Err(sgx_status_t::SGX_ERROR_NO_PRIVILEGE);
+    }
+
+    let target_pub_k = &target.report_data.d[0..32].try_into().unwrap();
+    let data = encrypt_to(target_pub_k, &backup.serialize())?;
+
+    write_file(FILE_BACKUP_RESTORE_DATA, &data)
+}
+
+fn try_import_registration_backup() -> SgxResult<()> {
+    if KEY_MANAGER.is_consensus_seed_set() {
+        error!("The node is already initialized, refusing to replace its registration key");
+        return Err(sgx_status_t::SGX_ERROR_NO_PRIVILEGE);
+    }
+
+    let backup = RegistrationBackup::deserialize(&decrypt_with_migration_keys(&read_file(
+        FILE_BACKUP_RESTORE_DATA,
+    )?)?)?;
+
+    let mut key_manager = Keychain::new_empty();
+    key_manager.set_registration_key(backup.registration_key);
+
+    println!(
+        "Registration key restored: {}",
+        hex::encode(backup.registration_key.get_pubkey())
+    );
+    Ok(())
+}
+
+pub fn export_registration_backup() -> sgx_status_t {
+    match try_export_registration_backup() {
+        Ok(()) => {
+            println!("Registration key backup successfully exported");
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+pub fn release_registration_backup() -> sgx_status_t {
+    match try_release_registration_backup() {
+        Ok(()) => {
+            println!("Registration key backup successfully released");
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+pub fn import_registration_backup() -> sgx_status_t {
+    match try_import_registration_backup() {
+        Ok(()) => sgx_status_t::SGX_SUCCESS,
+        Err(e) => e,
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_registration_backup_roundtrip() {
+        let backup = RegistrationBackup {
+            isv_svn: 3,
+            registration_key: KeyPair::new().unwrap(),
+        };
+
+        let encrypted = encrypt_to(
+            &Keychain::get_migration_keys().get_pubkey(),
+            &backup.serialize(),
+        )
+        .unwrap();
+        let decrypted =
+            RegistrationBackup::deserialize(&decrypt_with_migration_keys(&encrypted).unwrap())
+                .unwrap();
+
+        assert_eq!(decrypted.isv_svn, 3);
+        assert_eq!(
+            decrypted.registration_key.get_pubkey(),
+            backup.registration_key.get_pubkey()
+        );
+
+        let mut tampered = encrypted;
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(decrypt_with_migration_keys(&tampered).is_err());
+    }
+
+    pub fn test_registration_backup_restore_policy() {
+        let backup = RegistrationBackup {
+            isv_svn: SELF_REPORT_BODY.isv_svn,
+            registration_key: KeyPair::new().unwrap(),
+        };
+
+        let mut target = *SELF_REPORT_BODY;
+        assert!(is_restore_approved(&target, &backup));
+
+        target.isv_svn = backup.isv_svn + 1;
+        assert!(is_restore_approved(&target, &backup));
+
+        // a backup made by a newer enclave
+        let newer = RegistrationBackup {
+            isv_svn: backup.isv_svn + 2,
+            registration_key: backup.registration_key,
+        };
+        assert!(!is_restore_approved(&target, &newer));
+
+        target.mr_signer.m[0] ^= 1;
+        assert!(!is_restore_approved(&target, &backup));
+        assert!(!is_escrow_approved(&target));
+    }
+}
@@ -6,10 +6,7 @@ use chrono::{Duration, TimeZone};
 use log::*;
 use num_bigint::BigUint;
 use sgx_tcrypto::SgxEccHandle;
-use sgx_types::{
-    sgx_ec256_private_t, sgx_ec256_public_t, sgx_platform_info_t, sgx_status_t,
-    sgx_update_info_bit_t, SgxResult,
-};
+use sgx_types::{sgx_ec256_private_t, sgx_ec256_public_t, sgx_report_body_t, SgxResult};
 
 use std::io::BufReader;
 use std::str;
@@ -18,21 +15,14 @@ use yasna::models::ObjectIdentifier;
 
 use enclave_crypto::consts::{SigningMethod, CERTEXPIRYDAYS, SELF_REPORT_BODY, SIGNING_METHOD};
 use enclave_ffi_types::NodeAuthResult;
+use enclave_utils::verified_params::{AttestationPolicy, ATTESTATION_POLICY};
 
 use crate::registration::report::AdvisoryIDs;
+use crate::registration::svn_ratchet::{check_isv_svn, required_isv_svn};
 
 #[cfg(feature = "SGX_MODE_HW")]
 use super::report::{AttestationReport, SgxQuoteStatus};
 
-extern "C" {
-    pub fn ocall_get_update_info(
-        ret_val: *mut sgx_status_t,
-        platformBlob: *const sgx_platform_info_t,
-        enclaveTrusted: i32,
-        update_info: *mut sgx_update_info_bit_t,
-    ) -> sgx_status_t;
-}
-
 pub const IAS_REPORT_CA: &[u8] = include_bytes!("../../Intel_SGX_Attestation_RootCA.pem");
 
 const ISSUER: &str = "SecretTEE";
@@ -279,50 +269,174 @@ pub fn verify_ra_cert(
     Ok(pk)
 }
 
-pub fn verify_ra_report(
-    report_mr_signer: &[u8; 32],
-    report_mr_enclave: &[u8; 32],
-    override_verify_type: Option<SigningMethod>,
-) -> NodeAuthResult {
-    let signing_method: SigningMethod = match override_verify_type {
-        Some(method) => method,
-        None => SIGNING_METHOD,
-    };
+/// What registration checks of the enclave an attestation is from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnclaveIdentity {
+    pub mr_enclave: [u8; 32],
+    pub mr_signer: [u8; 32],
+    pub isv_prod_id: u16,
+    pub isv_svn: u16,
+}
 
-    // verify certificate
+impl EnclaveIdentity {
+    pub fn from_report_body(report_body: &sgx_report_body_t) -> Self {
+        Self {
+            mr_enclave: report_body.mr_enclave.m,
+            mr_signer: report_body.mr_signer.m,
+            isv_prod_id: report_body.isv_prod_id,
+            isv_svn: report_body.isv_svn,
+        }
+    }
+}
+
+/// The rule of the attestation policy that accepted an enclave
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchedRule {
+    /// It's this enclave
+    SelfMrEnclave,
+    /// It has the signer of this enclave
+    SelfMrSigner,
+    /// Its measurements weren't checked, with `SigningMethod::NONE`
+    Unchecked,
+    /// Its MRENCLAVE is the one at this index of the policy
+    PolicyMrEnclave(usize),
+    /// Its MRSIGNER is the one at this index of the policy
+    PolicyMrSigner(usize),
+}
+
+/// Matches `identity` against the enclave it's verified by, `own`, with `signing_method`
+fn match_own(
+    identity: &EnclaveIdentity,
+    own: &EnclaveIdentity,
+    signing_method: SigningMethod,
+) -> Result<MatchedRule, NodeAuthResult> {
     match signing_method {
         SigningMethod::MRENCLAVE => {
-            if (*report_mr_enclave) != SELF_REPORT_BODY.mr_enclave.m
-                || (*report_mr_signer) != SELF_REPORT_BODY.mr_signer.m
-            {
+            if identity.mr_enclave != own.mr_enclave || identity.mr_signer != own.mr_signer {
                 error!(
                     "Got a different mr_enclave or mr_signer than expected. Invalid certificate"
                 );
                 warn!(
                     "mr_enclave: received: {:?} \n expected: {:?}",
-                    report_mr_enclave, SELF_REPORT_BODY.mr_enclave.m
+                    identity.mr_enclave, own.mr_enclave
                 );
                 warn!(
                     "mr_signer: received: {:?} \n expected: {:?}",
-                    report_mr_signer, SELF_REPORT_BODY.mr_signer.m
+                    identity.mr_signer, own.mr_signer
                 );
-                return NodeAuthResult::MrEnclaveMismatch;
+                return Err(NodeAuthResult::MrEnclaveMismatch);
             }
+            Ok(MatchedRule::SelfMrEnclave)
         }
         SigningMethod::MRSIGNER => {
-            if (*report_mr_signer) != SELF_REPORT_BODY.mr_signer.m {
+            if identity.mr_signer != own.mr_signer {
                 error!("Got a different mrsigner than expected. Invalid certificate");
                 warn!(
                     "received: {:?} \n expected: {:?}",
-                    report_mr_signer, SELF_REPORT_BODY.mr_signer.m
+                    identity.mr_signer, own.mr_signer
                 );
-                return NodeAuthResult::MrSignerMismatch;
+                return Err(NodeAuthResult::MrSignerMismatch);
             }
+            Ok(MatchedRule::SelfMrSigner)
         }
-        SigningMethod::NONE => {}
+        SigningMethod::NONE => Ok(MatchedRule::Unchecked),
+    }
+}
+
+/// Matches `identity` against the measurements `policy` lists
+fn match_listed(
+    identity: &EnclaveIdentity,
+    policy: &AttestationPolicy,
+) -> Result<MatchedRule, NodeAuthResult> {
+    if let Some(index) = policy
+        .mr_enclave
+        .iter()
+        .position(|mr_enclave| *mr_enclave == identity.mr_enclave)
+    {
+        return Ok(MatchedRule::PolicyMrEnclave(index));
+    }
+    if let Some(index) = policy
+        .mr_signer
+        .iter()
+        .position(|mr_signer| *mr_signer == identity.mr_signer)
+    {
+        return Ok(MatchedRule::PolicyMrSigner(index));
     }
 
-    NodeAuthResult::Success
+    error!(
+        "The attestation policy doesn't list mr_enclave {} or mr_signer {}. Invalid certificate",
+        hex::encode(identity.mr_enclave),
+        hex::encode(identity.mr_signer)
+    );
+    if policy.mr_signer.is_empty() {
+        Err(NodeAuthResult::MrEnclaveMismatch)
+    } else {
+        Err(NodeAuthResult::MrSignerMismatch)
+    }
+}
+
+/// Checks `identity` against `policy`. A policy that names no measurements leaves them to
+/// `match_own`.
+fn check_policy(
+    identity: &EnclaveIdentity,
+    policy: &AttestationPolicy,
+    own: &EnclaveIdentity,
+    signing_method: SigningMethod,
+) -> Result<MatchedRule, NodeAuthResult> {
+    let rule = if policy.names_measurements() {
+        match_listed(identity, policy)?
+    } else {
+        match_own(identity, own, signing_method)?
+    };
+
+    if !policy.isv_prod_id.is_empty() && !policy.isv_prod_id.contains(&identity.isv_prod_id) {
+        warn!(
+            "The attestation policy doesn't allow product ID {}",
+            identity.isv_prod_id
+        );
+        return Err(NodeAuthResult::ProductNotAllowed);
+    }
+    if identity.isv_svn < policy.min_isv_svn {
+        warn!(
+            "ISV SVN {} is below the minimum of the attestation policy, {}",
+            identity.isv_svn, policy.min_isv_svn
+        );
+        return Err(NodeAuthResult::IsvSvnTooLow);
+    }
+
+    Ok(rule)
+}
+
+/// The policy registration reads until the params are proven: only `own`'s MRENCLAVE, at its
+/// ISV SVN or above, so withholding the params can't let in an enclave governance didn't
+fn strict_policy(own: &EnclaveIdentity) -> AttestationPolicy {
+    AttestationPolicy {
+        mr_enclave: vec![own.mr_enclave],
+        min_isv_svn: own.isv_svn,
+        ..AttestationPolicy::default()
+    }
+}
+
+/// Whether the enclave of an attestation may register, by the policy governance set as
+/// `ATTESTATION_POLICY`. Without one, or for the measurements when it names none, the enclave
+/// must be this one or have its signer, with `override_verify_type` or `SIGNING_METHOD`. Its ISV
+/// SVN must also be at the minimum of `svn_ratchet`. Returns the rule that accepted it.
+pub fn verify_ra_report(
+    identity: &EnclaveIdentity,
+    override_verify_type: Option<SigningMethod>,
+) -> Result<MatchedRule, NodeAuthResult> {
+    let signing_method: SigningMethod = match override_verify_type {
+        Some(method) => method,
+        None => SIGNING_METHOD,
+    };
+    let own = EnclaveIdentity::from_report_body(&SELF_REPORT_BODY);
+    // Until the params are proven, only enclaves of this build and version are accepted
+    let policy = ATTESTATION_POLICY.get_or(AttestationPolicy::default(), strict_policy(&own));
+
+    let rule = check_policy(identity, &policy, &own, signing_method)?;
+    check_isv_svn(identity.isv_svn, required_isv_svn())?;
+    debug!("Enclave accepted by rule {:?}", rule);
+    Ok(rule)
 }
 
 /// # Verifies remote attestation cert
@@ -349,15 +463,14 @@ pub fn verify_ra_cert(
         verify_quote_status(&report, &report.advisory_ids)?;
     }
 
-    let res = verify_ra_report(
-        &report.sgx_quote_body.isv_enclave_report.mr_signer,
-        &report.sgx_quote_body.isv_enclave_report.mr_enclave,
-        override_verify_type,
-    );
-
-    if res != NodeAuthResult::Success {
-        return Err(res);
-    }
+    let enclave_report = &report.sgx_quote_body.isv_enclave_report;
+    let identity = EnclaveIdentity {
+        mr_enclave: enclave_report.mr_enclave,
+        mr_signer: enclave_report.mr_signer,
+        isv_prod_id: enclave_report.isv_prod_id,
+        isv_svn: enclave_report.isv_svn,
+    };
+    verify_ra_report(&identity, override_verify_type)?;
 
     if check_tcb_version {
         // todo: change this to a parameters or const when we migrate the code to main
@@ -499,7 +612,10 @@ pub mod tests {
 
     use crate::registration::report::AttestationReport;
 
-    use super::verify_ra_cert;
+    use enclave_crypto::consts::SigningMethod;
+    use enclave_utils::verified_params::{AttestationPolicy, ParamValue};
+
+    use super::{check_policy, strict_policy, verify_ra_cert, EnclaveIdentity, MatchedRule};
 
     // #[cfg(feature = "SGX_MODE_HW")]
     // fn tls_ra_cert_der_out_of_date() -> Vec<u8> {
@@ -601,4 +717,118 @@ pub mod tests {
         let tls_ra_cert = tls_ra_cert_der_valid();
         let _ = verify_ra_cert(&tls_ra_cert, None, false).unwrap();
     }
+
+    fn identity(mr_enclave: u8, mr_signer: u8) -> EnclaveIdentity {
+        EnclaveIdentity {
+            mr_enclave: [mr_enclave; 32],
+            mr_signer: [mr_signer; 32],
+            isv_prod_id: 0,
+            isv_svn: 3,
+        }
+    }
+
+    pub fn test_attestation_policy() {
+        let own = identity(1, 2);
+        let unset = AttestationPolicy::default();
+
+        // without measurements in the policy, the enclave's own rule applies
+        assert_eq!(
+            check_policy(&identity(9, 2), &unset, &own, SigningMethod::MRSIGNER),
+            Ok(MatchedRule::SelfMrSigner)
+        );
+        assert_eq!(
+            check_policy(&identity(9, 2), &unset, &own, SigningMethod::MRENCLAVE),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+        assert_eq!(
+            check_policy(&own, &unset, &own, SigningMethod::MRENCLAVE),
+            Ok(MatchedRule::SelfMrEnclave)
+        );
+        assert_eq!(
+            check_policy(&identity(9, 9), &unset, &own, SigningMethod::MRSIGNER),
+            Err(NodeAuthResult::MrSignerMismatch)
+        );
+
+        // listed measurements replace it, each one a rule of its own
+        let listed = AttestationPolicy {
+            mr_enclave: vec![[7; 32], [8; 32]],
+            mr_signer: vec![[5; 32]],
+            ..Default::default()
+        };
+        assert_eq!(
+            check_policy(&identity(8, 9), &listed, &own, SigningMethod::MRSIGNER),
+            Ok(MatchedRule::PolicyMrEnclave(1))
+        );
+        assert_eq!(
+            check_policy(&identity(9, 5), &listed, &own, SigningMethod::MRSIGNER),
+            Ok(MatchedRule::PolicyMrSigner(0))
+        );
+        assert_eq!(
+            check_policy(&own, &listed, &own, SigningMethod::MRSIGNER),
+            Err(NodeAuthResult::MrSignerMismatch)
+        );
+
+        // product IDs and the minimum ISV SVN apply to either
+        let restricted = AttestationPolicy {
+            isv_prod_id: vec![1],
+            ..Default::default()
+        };
+        assert_eq!(
+            check_policy(&own, &restricted, &own, SigningMethod::MRSIGNER),
+            Err(NodeAuthResult::ProductNotAllowed)
+        );
+        let min_svn = AttestationPolicy {
+            min_isv_svn: 4,
+            ..listed
+        };
+        assert_eq!(
+            check_policy(&identity(7, 9), &min_svn, &own, SigningMethod::MRSIGNER),
+            Err(NodeAuthResult::IsvSvnTooLow)
+        );
+
+        // until the params are proven, only this build at its version or above
+        let strict = strict_policy(&own);
+        assert_eq!(
+            check_policy(&own, &strict, &own, SigningMethod::MRSIGNER),
+            Ok(MatchedRule::PolicyMrEnclave(0))
+        );
+        assert_eq!(
+            check_policy(&identity(9, 2), &strict, &own, SigningMethod::MRSIGNER),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+        let older = EnclaveIdentity { isv_svn: 2, ..own };
+        assert_eq!(
+            check_policy(&older, &strict, &own, SigningMethod::MRSIGNER),
+            Err(NodeAuthResult::IsvSvnTooLow)
+        );
+    }
+
+    pub fn test_attestation_policy_decode() {
+        let signer = hex::encode([5u8; 32]);
+        let json = format!(
+            r#"{{"mr_signer":["{}"],"isv_prod_id":[0,1],"min_isv_svn":2}}"#,
+            signer
+        );
+        assert_eq!(
+            AttestationPolicy::decode(json.as_bytes()),
+            Some(AttestationPolicy {
+                mr_enclave: vec![],
+                mr_signer: vec![[5; 32]],
+                isv_prod_id: vec![0, 1],
+                min_isv_svn: 2,
+            })
+        );
+        assert_eq!(
+            AttestationPolicy::decode(b"{}"),
+            Some(AttestationPolicy::default())
+        );
+
+        // typos and short measurements are rejected
+        assert_eq!(AttestationPolicy::decode(br#"{"min_isvsvn":2}"#), None);
+        assert_eq!(
+            AttestationPolicy::decode(br#"{"mr_signer":["0505"]}"#),
+            None
+        );
+        assert_eq!(AttestationPolicy::decode(b"not json"), None);
+    }
 }
@@ -0,0 +1,188 @@
+//! Proofs that a code hash runs under a genuine enclave of the network, for clients that want to
+//! check what processes their data before they encrypt it to a contract.
+//!
+//! The enclave only attests a code hash whose module it has in its cache, i.e. code it compiled
+//! to run, on the chain its consensus keys are bound to, at a height it has verified. The
+//! statement is bound to the report data of a DCAP quote:
+//!
+//! ```text
+//! report_data[..32] = sha256("secret-code-attestation-v1" || code hash (32) ||
+//!                            chain id length (4) || chain id || height (8))
+//! report_data[32..] = 0
+//! ```
+//!
+//! with all integers big endian, so any standard DCAP verification library can check the quote,
+//! and the client recomputes the report data from the statement it was given.
+
+use std::string::String;
+use std::vec::Vec;
+
+use log::*;
+use serde::Serialize;
+use sgx_types::sgx_status_t;
+
+use enclave_contract_engine::wasm3::module_cache::is_code_hash_cached;
+use enclave_crypto::{sha_256, HASH_SIZE};
+use enclave_utils::KEY_MANAGER;
+
+use super::attestation::get_quote_ecdsa;
+
+const STATEMENT_DOMAIN: &[u8] = b"secret-code-attestation-v1";
+
+/// Longer than any chain id tendermint accepts
+pub const MAX_CHAIN_ID_LENGTH: usize = 64;
+
+/// What the enclave attests, with the quote it's bound to
+#[derive(Serialize, Debug)]
+pub struct CodeAttestation {
+    pub code_hash: String,
+    pub chain_id: String,
+    pub height: u64,
+    pub report_data: String,
+    /// The DCAP quote, in base64
+    pub quote: String,
+}
+
+/// The report data of the quote that attests `code_hash` on `chain_id` at `height`
+pub fn statement_report_data(code_hash: &[u8; HASH_SIZE], chain_id: &str, height: u64) -> [u8; 64] {
+    let mut statement = STATEMENT_DOMAIN.to_vec();
+    statement.extend_from_slice(code_hash);
+    statement.extend_from_slice(&(chain_id.len() as u32).to_be_bytes());
+    statement.extend_from_slice(chain_id.as_bytes());
+    statement.extend_from_slice(&height.to_be_bytes());
+
+    let mut report_data = [0u8; 64];
+    report_data[..HASH_SIZE].copy_from_slice(&sha_256(&statement));
+    report_data
+}
+
+/// The height of the latest block the light client verified
+#[cfg(feature = "light-client-validation")]
+fn verified_height() -> Option<u64> {
+    Some(
+        block_verifier::VERIFIED_BLOCK_MESSAGES
+            .lock()
+            .unwrap()
+            .height(),
+    )
+}
+
+// Without the light client any height the node reports is taken as is
+#[cfg(not(feature = "light-client-validation"))]
+fn verified_height() -> Option<u64> {
+    None
+}
+
+fn check_statement(
+    code_hash: &[u8; HASH_SIZE],
+    chain_id: &str,
+    height: u64,
+) -> Result<(), sgx_status_t> {
+    if chain_id.is_empty() || chain_id.len() > MAX_CHAIN_ID_LENGTH {
+        warn!("Refusing to attest code for an invalid chain id");
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    if let Some(context) = KEY_MANAGER.get_consensus_seed_context().current {
+        if context.chain_id != chain_id {
+            warn!(
+                "Refusing to attest code on chain {}, the consensus keys are bound to {}",
+                chain_id, context.chain_id
+            );
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+    }
+
+    if let Some(verified) = verified_height() {
+        if height > verified {
+            warn!(
+                "Refusing to attest code at height {}, the latest verified block is {}",
+                height, verified
+            );
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+    }
+
+    if !is_code_hash_cached(code_hash) {
+        warn!(
+            "Refusing to attest code {}, its module isn't loaded",
+            hex::encode(code_hash)
+        );
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    Ok(())
+}
+
+/// A DCAP quote that attests `code_hash` runs under this enclave on `chain_id` at `height`, with
+/// the statement it attests, as json
+pub fn encoded_code_attestation(
+    code_hash: &[u8; HASH_SIZE],
+    chain_id: &str,
+    height: u64,
+) -> Result<Vec<u8>, sgx_status_t> {
+    check_statement(code_hash, chain_id, height)?;
+
+    let report_data = statement_report_data(code_hash, chain_id, height);
+    let (quote, _) = get_quote_ecdsa(&report_data)?;
+
+    let attestation = CodeAttestation {
+        code_hash: hex::encode(code_hash),
+        chain_id: chain_id.to_string(),
+        height,
+        report_data: hex::encode(&report_data[..]),
+        quote: base64::encode(&quote),
+    };
+
+    serde_json::to_vec(&attestation).map_err(|e| {
+        error!("Failed to encode the code attestation: {}", e);
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_code_attestation_report_data() {
+        let code_hash = [7u8; HASH_SIZE];
+        let report_data = statement_report_data(&code_hash, "secret-4", 1000);
+
+        let mut statement = b"secret-code-attestation-v1".to_vec();
+        statement.extend_from_slice(&code_hash);
+        statement.extend_from_slice(&[0, 0, 0, 8]);
+        statement.extend_from_slice(b"secret-4");
+        statement.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0x03, 0xe8]);
+        assert_eq!(&report_data[..HASH_SIZE], &sha_256(&statement)[..]);
+        assert_eq!(&report_data[HASH_SIZE..], &[0u8; 32][..]);
+
+        // every part of the statement is bound
+        assert_ne!(
+            statement_report_data(&[8u8; HASH_SIZE], "secret-4", 1000),
+            report_data
+        );
+        assert_ne!(
+            statement_report_data(&code_hash, "pulsar-3", 1000),
+            report_data
+        );
+        assert_ne!(
+            statement_report_data(&code_hash, "secret-4", 1001),
+            report_data
+        );
+    }
+
+    pub fn test_code_attestation_check_statement() {
+        assert_eq!(
+            check_statement(&[9u8; HASH_SIZE], "secret-4", 0),
+            Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+        );
+        assert_eq!(
+            check_statement(&[9u8; HASH_SIZE], "", 0),
+            Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+        );
+        assert_eq!(
+            check_statement(&[9u8; HASH_SIZE], &"a".repeat(MAX_CHAIN_ID_LENGTH + 1), 0),
+            Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+        );
+    }
+}
@@ -0,0 +1,530 @@
+//! A sealed cache of the DCAP collateral the enclave verified quotes against.
+//!
+//! Collateral is large, and the same few chains are verified over and over: a registration is
+//! verified in CheckTx and again in DeliverTx, nodes on the same platform register with the same
+//! collateral, and the node fetches its own collateral from the PCCS for every self-attestation.
+//!
+//! Entries are keyed by the identity of the collateral's TCB info: the TEE type, the FMSPC of the
+//! platforms it applies to, and the CA that issued their PCK certificates. An entry holds the hash
+//! of the collateral and the quotes already verified against it, each with the time it was
+//! verified at and the expiry the QvE reported. A quote that comes with byte-identical collateral
+//! isn't sent to the QvE again while the time it's checked at is between those two. The QvE
+//! returns the same result for any time in that window, so the cache doesn't change the result
+//! of on-chain verification, which checks at the verified block time, it only saves the work.
+//!
+//! Collateral with the same or a newer TCB evaluation data number replaces the entry of its
+//! identity once a quote verifies against it, so a TCB recovery takes effect without a restart.
+//! Older collateral is still verified, but isn't cached.
+
+use std::path;
+use std::string::String;
+use std::sync::SgxMutex;
+use std::untrusted::path::PathEx;
+use std::vec::Vec;
+
+use lazy_static::lazy_static;
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use sgx_types::{sgx_ql_qv_result_t, sgx_status_t};
+
+use enclave_crypto::consts::{make_sgx_secret_path, SEALED_FILE_COLLATERAL_CACHE};
+use enclave_crypto::dcap::verify_quote_collateral;
+use enclave_crypto::sha_256;
+use enclave_utils::storage::{seal, unseal};
+
+use crate::secure_time::enclave_now;
+
+/// Size of the `QlQveCollateral` header, 8 little endian u32: the TEE type, then the sizes of
+/// the PCK CRL issuer chain, root CA CRL, PCK CRL, TCB info issuer chain, TCB info, QE identity
+/// issuer chain and QE identity, which follow it in that order
+const COLLATERAL_HEADER_SIZE: usize = 8 * 4;
+
+/// Cap on the identities the cache holds, the one that expires first is evicted beyond it
+const MAX_ENTRIES: usize = 32;
+
+/// Cap on the quotes remembered per identity, the oldest is forgotten beyond it
+const MAX_QUOTES_PER_ENTRY: usize = 256;
+
+/// The node's own collateral is only reused for self-attestation while it's valid for this much
+/// longer, so the quote it's sent with doesn't expire right after
+const OWN_COLLATERAL_MIN_VALIDITY_S: i64 = 24 * 60 * 60;
+
+lazy_static! {
+    static ref COLLATERAL_CACHE_SEALING_PATH: String =
+        make_sgx_secret_path(SEALED_FILE_COLLATERAL_CACHE);
+    static ref COLLATERAL_CACHE: SgxMutex<Option<CollateralCache>> = SgxMutex::new(None);
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CollateralId {
+    pub tee_type: u32,
+    /// Hex encoded, lower case
+    pub fmspc: String,
+    /// SHA-256 of the PCK CRL issuer chain, hex encoded
+    pub pck_crl_issuer: String,
+}
+
+#[derive(Deserialize)]
+struct TcbInfoJson {
+    #[serde(rename = "tcbInfo")]
+    tcb_info: TcbInfoBody,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TcbInfoBody {
+    fmspc: String,
+    tcb_evaluation_data_number: u32,
+}
+
+/// Serialized collateral, as the QvE takes it, and what the cache keys it by
+struct Collateral<'a> {
+    id: CollateralId,
+    tcb_evaluation_data_number: u32,
+    /// SHA-256 of the serialized collateral, hex encoded
+    hash: String,
+    bytes: &'a [u8],
+}
+
+/// Reads the little endian u32 at `index` of the collateral header
+fn header_field(header: &[u8], index: usize) -> u32 {
+    let mut value = [0u8; 4];
+    value.copy_from_slice(&header[index * 4..index * 4 + 4]);
+    u32::from_le_bytes(value)
+}
+
+/// The PCK CRL issuer chain and the TCB info json of serialized collateral, without the NUL
+/// terminator of the json
+pub fn collateral_tcb_info(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let header = bytes.get(..COLLATERAL_HEADER_SIZE)?;
+    let size = |index: usize| header_field(header, index) as usize;
+
+    let pck_crl_issuer_start = COLLATERAL_HEADER_SIZE;
+    let pck_crl_issuer = bytes.get(pck_crl_issuer_start..pck_crl_issuer_start + size(1))?;
+
+    let tcb_info_start = pck_crl_issuer_start + size(1) + size(2) + size(3) + size(4);
+    let tcb_info = bytes.get(tcb_info_start..tcb_info_start + size(5))?;
+
+    // The blobs are NUL terminated
+    let end = tcb_info
+        .iter()
+        .rposition(|b| *b != 0)
+        .map_or(0, |last| last + 1);
+
+    Some((pck_crl_issuer, &tcb_info[..end]))
+}
+
+impl<'a> Collateral<'a> {
+    fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let (pck_crl_issuer, tcb_info) = collateral_tcb_info(bytes)?;
+        let tcb_info = match serde_json::from_slice::<TcbInfoJson>(tcb_info) {
+            Ok(json) => json.tcb_info,
+            Err(e) => {
+                trace!("Failed to parse the TCB info of the collateral: {:?}", e);
+                return None;
+            }
+        };
+
+        Some(Self {
+            id: CollateralId {
+                tee_type: header_field(bytes, 0),
+                fmspc: tcb_info.fmspc.to_lowercase(),
+                pck_crl_issuer: hex::encode(sha_256(pck_crl_issuer)),
+            },
+            tcb_evaluation_data_number: tcb_info.tcb_evaluation_data_number,
+            hash: hex::encode(sha_256(bytes)),
+            bytes,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+struct VerifiedQuote {
+    /// SHA-256 of the quote, hex encoded
+    hash: String,
+    /// The time the quote was verified at, unix seconds
+    verified_at: i64,
+    /// When the earliest of the collateral expires, as the QvE reported it, unix seconds
+    expiration: i64,
+    /// Whether the QvE returned `SGX_QL_QV_RESULT_SW_HARDENING_NEEDED` rather than
+    /// `SGX_QL_QV_RESULT_OK`, the only other result it accepts
+    sw_hardening_needed: bool,
+}
+
+impl VerifiedQuote {
+    fn qv_result(&self) -> sgx_ql_qv_result_t {
+        if self.sw_hardening_needed {
+            sgx_ql_qv_result_t::SGX_QL_QV_RESULT_SW_HARDENING_NEEDED
+        } else {
+            sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OK
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct CachedCollateral {
+    id: CollateralId,
+    tcb_evaluation_data_number: u32,
+    /// SHA-256 of the serialized collateral, hex encoded
+    hash: String,
+    quotes: Vec<VerifiedQuote>,
+    /// The collateral itself, only kept for the node's own platform
+    #[serde(default)]
+    bytes: Vec<u8>,
+}
+
+impl CachedCollateral {
+    /// The latest expiry any quote reported. The QvE checks the quote's PCK certificates as well,
+    /// so each quote's expiry is a lower bound on the expiry of the collateral.
+    fn expiration(&self) -> i64 {
+        self.quotes.iter().map(|q| q.expiration).max().unwrap_or(0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct CollateralCache {
+    entries: Vec<CachedCollateral>,
+    /// The identity of the node's own collateral, set when it fetches it for a quote
+    own: Option<CollateralId>,
+}
+
+impl CollateralCache {
+    fn load() -> Self {
+        if !path::Path::new(COLLATERAL_CACHE_SEALING_PATH.as_str()).exists() {
+            return Self::default();
+        }
+
+        let decoded = unseal(&COLLATERAL_CACHE_SEALING_PATH)
+            .ok()
+            .and_then(|sealed| serde_json::from_slice::<Self>(sealed.as_slice()).ok());
+
+        decoded.unwrap_or_else(|| {
+            // An empty cache only costs the verifications it would have saved
+            warn!("Failed to load the collateral cache, starting empty");
+            Self::default()
+        })
+    }
+
+    fn seal(&self) {
+        let result = serde_json::to_vec(&self)
+            .map_err(|e| error!("Error encoding collateral cache to json: {:?}", e))
+            .and_then(|encoded| {
+                seal(encoded.as_slice(), &COLLATERAL_CACHE_SEALING_PATH)
+                    .map_err(|e| error!("Error sealing collateral cache: {}", e))
+            });
+
+        if result.is_err() {
+            warn!("The collateral cache wasn't sealed, and will be empty after a restart");
+        }
+    }
+
+    /// An earlier verification of the quote against the same collateral, if its result still
+    /// holds at `time_s`
+    fn lookup(
+        &self,
+        collateral: &Collateral,
+        quote_hash: &str,
+        time_s: i64,
+    ) -> Option<&VerifiedQuote> {
+        self.entries
+            .iter()
+            .find(|entry| entry.id == collateral.id && entry.hash == collateral.hash)?
+            .quotes
+            .iter()
+            .find(|q| q.hash == quote_hash && q.verified_at <= time_s && time_s < q.expiration)
+    }
+
+    fn record(&mut self, collateral: &Collateral, quote: VerifiedQuote) {
+        self.entries
+            .retain(|entry| entry.expiration() > quote.verified_at);
+
+        let index = match self
+            .entries
+            .iter()
+            .position(|entry| entry.id == collateral.id)
+        {
+            Some(index) if self.entries[index].hash == collateral.hash => index,
+            Some(index)
+                if self.entries[index].tcb_evaluation_data_number
+                    > collateral.tcb_evaluation_data_number =>
+            {
+                debug!(
+                    "Not caching collateral of fmspc {} older than the cached one",
+                    collateral.id.fmspc
+                );
+                return;
+            }
+            Some(index) => {
+                debug!(
+                    "Reloading the cached collateral of fmspc {}",
+                    collateral.id.fmspc
+                );
+                self.entries[index] = Self::new_entry(collateral);
+                index
+            }
+            None => {
+                if self.entries.len() >= MAX_ENTRIES {
+                    let first_to_expire = (0..self.entries.len())
+                        .min_by_key(|index| self.entries[*index].expiration())
+                        .unwrap_or(0);
+                    self.entries.remove(first_to_expire);
+                }
+                self.entries.push(Self::new_entry(collateral));
+                self.entries.len() - 1
+            }
+        };
+
+        let keep_bytes = self.own.as_ref() == Some(&collateral.id);
+        let entry = &mut self.entries[index];
+        if keep_bytes && entry.bytes.is_empty() {
+            entry.bytes = collateral.bytes.to_vec();
+        }
+
+        entry.quotes.retain(|q| q.hash != quote.hash);
+        if entry.quotes.len() >= MAX_QUOTES_PER_ENTRY {
+            entry.quotes.remove(0);
+        }
+        entry.quotes.push(quote);
+    }
+
+    fn new_entry(collateral: &Collateral) -> CachedCollateral {
+        CachedCollateral {
+            id: collateral.id.clone(),
+            tcb_evaluation_data_number: collateral.tcb_evaluation_data_number,
+            hash: collateral.hash.clone(),
+            quotes: vec![],
+            bytes: vec![],
+        }
+    }
+
+    /// Returns whether the identity changed
+    fn set_own(&mut self, id: &CollateralId) -> bool {
+        if self.own.as_ref() == Some(id) {
+            return false;
+        }
+
+        for entry in self.entries.iter_mut() {
+            entry.bytes.clear();
+        }
+        self.own = Some(id.clone());
+        true
+    }
+
+    fn own_collateral(&self, time_s: i64) -> Option<Vec<u8>> {
+        let own = self.own.as_ref()?;
+        let entry = self.entries.iter().find(|entry| &entry.id == own)?;
+
+        if entry.bytes.is_empty() || entry.expiration() < time_s + OWN_COLLATERAL_MIN_VALIDITY_S {
+            return None;
+        }
+
+        Some(entry.bytes.clone())
+    }
+}
+
+/// Verify a DCAP quote like `verify_quote_collateral` does, unless it was verified against the
+/// same collateral before and the result still holds at `time_s`. When `time_s` is 0, the QvE
+/// uses the host's time, and the cache is checked at `enclave_now`.
+pub fn verify_quote_cached(
+    vec_quote: &[u8],
+    vec_coll: &[u8],
+    time_s: i64,
+) -> Result<(sgx_ql_qv_result_t, i64), sgx_status_t> {
+    let now_s = if time_s != 0 {
+        time_s
+    } else {
+        enclave_now().seconds
+    };
+
+    let collateral = match Collateral::parse(vec_coll) {
+        Some(collateral) if now_s != 0 => collateral,
+        _ => return verify_quote_collateral(vec_quote, vec_coll, time_s),
+    };
+    let quote_hash = hex::encode(sha_256(vec_quote));
+
+    {
+        let mut guard = COLLATERAL_CACHE.lock().unwrap();
+        let cache = guard.get_or_insert_with(CollateralCache::load);
+        if let Some(quote) = cache.lookup(&collateral, &quote_hash, now_s) {
+            trace!("Quote was verified against the same collateral before");
+            return Ok((quote.qv_result(), quote.expiration));
+        }
+    }
+
+    // Not holding the lock while the QvE runs
+    let (qv_result, expiration) = verify_quote_collateral(vec_quote, vec_coll, time_s)?;
+
+    let mut guard = COLLATERAL_CACHE.lock().unwrap();
+    let cache = guard.get_or_insert_with(CollateralCache::load);
+    cache.record(
+        &collateral,
+        VerifiedQuote {
+            hash: quote_hash,
+            verified_at: now_s,
+            expiration,
+            sw_hardening_needed: qv_result
+                == sgx_ql_qv_result_t::SGX_QL_QV_RESULT_SW_HARDENING_NEEDED,
+        },
+    );
+    cache.seal();
+
+    Ok((qv_result, expiration))
+}
+
+/// The node's own collateral, if it was verified before and is still valid for a while, so a
+/// self-attestation doesn't have to fetch it again
+#[cfg(feature = "SGX_MODE_HW")]
+pub fn own_collateral() -> Option<Vec<u8>> {
+    let now_s = enclave_now().seconds;
+    if now_s == 0 {
+        return None;
+    }
+
+    let mut guard = COLLATERAL_CACHE.lock().unwrap();
+    guard
+        .get_or_insert_with(CollateralCache::load)
+        .own_collateral(now_s)
+}
+
+/// Mark freshly fetched collateral as the node's own, so it's kept once a quote verifies against
+/// it
+#[cfg(feature = "SGX_MODE_HW")]
+pub fn set_own_collateral(vec_coll: &[u8]) {
+    let collateral = match Collateral::parse(vec_coll) {
+        Some(collateral) => collateral,
+        None => return,
+    };
+
+    let mut guard = COLLATERAL_CACHE.lock().unwrap();
+    let cache = guard.get_or_insert_with(CollateralCache::load);
+    if cache.set_own(&collateral.id) {
+        cache.seal();
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    const DCAP_COLLATERAL: &[u8] = include_bytes!("fixtures/attestation_dcap.collateral");
+
+    fn replaced(from: &[u8], to: &[u8]) -> Vec<u8> {
+        assert_eq!(from.len(), to.len());
+        let start = DCAP_COLLATERAL
+            .windows(from.len())
+            .position(|window| window == from)
+            .unwrap();
+
+        let mut bytes = DCAP_COLLATERAL.to_vec();
+        bytes[start..start + to.len()].copy_from_slice(to);
+        bytes
+    }
+
+    fn verified(hash: &str, verified_at: i64, expiration: i64) -> VerifiedQuote {
+        VerifiedQuote {
+            hash: hash.to_string(),
+            verified_at,
+            expiration,
+            sw_hardening_needed: false,
+        }
+    }
+
+    pub fn test_collateral_parse() {
+        let collateral = Collateral::parse(DCAP_COLLATERAL).unwrap();
+        assert_eq!(collateral.id.tee_type, 0);
+        assert_eq!(collateral.id.fmspc, "00906ed50000");
+        assert_eq!(
+            collateral.id.pck_crl_issuer,
+            "134d9f70fc55ac99df8bcd0c688038e84026f2e181b41d213ec7c03305b8c8cb"
+        );
+        assert_eq!(collateral.tcb_evaluation_data_number, 10);
+        assert_eq!(
+            collateral.hash,
+            "be3f25b58e348932a618cad010b63d11174a0d2eddf8168d3ed0e9af801717c0"
+        );
+
+        assert!(Collateral::parse(&DCAP_COLLATERAL[..COLLATERAL_HEADER_SIZE]).is_none());
+        assert!(Collateral::parse(&DCAP_COLLATERAL[..DCAP_COLLATERAL.len() / 2]).is_none());
+
+        let mut oversized = DCAP_COLLATERAL.to_vec();
+        oversized[20..24].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Collateral::parse(&oversized).is_none());
+    }
+
+    pub fn test_collateral_cache_lookup() {
+        let collateral = Collateral::parse(DCAP_COLLATERAL).unwrap();
+        let mut cache = CollateralCache::default();
+
+        assert!(cache.lookup(&collateral, "aa", 100).is_none());
+        cache.record(&collateral, verified("aa", 100, 1000));
+
+        assert!(cache.lookup(&collateral, "aa", 100).is_some());
+        assert_eq!(
+            cache.lookup(&collateral, "aa", 999).unwrap().expiration,
+            1000
+        );
+        // before it was verified, or after the collateral expired
+        assert!(cache.lookup(&collateral, "aa", 99).is_none());
+        assert!(cache.lookup(&collateral, "aa", 1000).is_none());
+        // another quote
+        assert!(cache.lookup(&collateral, "bb", 500).is_none());
+
+        // other collateral of the same identity
+        let reissued_bytes = replaced(b"b92b9df2184fc1d092f", b"b92b9df2184fc1d092e");
+        let reissued = Collateral::parse(&reissued_bytes).unwrap();
+        assert_eq!(reissued.id, collateral.id);
+        assert!(cache.lookup(&reissued, "aa", 500).is_none());
+    }
+
+    pub fn test_collateral_cache_reload() {
+        let collateral = Collateral::parse(DCAP_COLLATERAL).unwrap();
+        let newer_bytes = replaced(
+            b"\"tcbEvaluationDataNumber\":10",
+            b"\"tcbEvaluationDataNumber\":11",
+        );
+        let newer = Collateral::parse(&newer_bytes).unwrap();
+        assert_eq!(newer.tcb_evaluation_data_number, 11);
+
+        let mut cache = CollateralCache::default();
+        cache.record(&collateral, verified("aa", 100, 1000));
+        cache.record(&newer, verified("bb", 200, 2000));
+
+        // the newer collateral replaced the entry
+        assert_eq!(cache.entries.len(), 1);
+        assert!(cache.lookup(&collateral, "aa", 500).is_none());
+        assert!(cache.lookup(&newer, "bb", 500).is_some());
+
+        // older collateral isn't cached
+        cache.record(&collateral, verified("cc", 300, 3000));
+        assert!(cache.lookup(&collateral, "cc", 500).is_none());
+        assert!(cache.lookup(&newer, "bb", 500).is_some());
+
+        // expired entries are dropped
+        let other_bytes = replaced(b"\"fmspc\":\"00906ed50000\"", b"\"fmspc\":\"00906ed50001\"");
+        let other = Collateral::parse(&other_bytes).unwrap();
+        cache.record(&other, verified("dd", 2500, 5000));
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.entries[0].id.fmspc, "00906ed50001");
+    }
+
+    pub fn test_collateral_cache_own() {
+        let collateral = Collateral::parse(DCAP_COLLATERAL).unwrap();
+        let now = 1_700_000_000;
+
+        let mut cache = CollateralCache::default();
+        cache.record(&collateral, verified("aa", now, now + 7 * 24 * 60 * 60));
+        // not the node's own collateral, so its bytes weren't kept
+        assert!(cache.own_collateral(now).is_none());
+
+        assert!(cache.set_own(&collateral.id));
+        assert!(!cache.set_own(&collateral.id));
+        cache.record(&collateral, verified("bb", now, now + 7 * 24 * 60 * 60));
+        assert_eq!(cache.own_collateral(now).unwrap(), DCAP_COLLATERAL);
+
+        // about to expire
+        assert!(cache
+            .own_collateral(now + 7 * 24 * 60 * 60 - OWN_COLLATERAL_MIN_VALIDITY_S + 1)
+            .is_none());
+    }
+}
@@ -0,0 +1,121 @@
+//! What the enclave does when a public key that was issued seeds registers again.
+//!
+//! The chain doesn't ask the enclave about a key it already registered, but the host can, and
+//! an attested key whose cert was copied, or whose enclave was cloned, would be issued the seeds
+//! again. x/registration keeps the height it first issued seeds to each key at in its state, and
+//! passes it to `ecall_authenticate_new_node`, or 0 for a key it never issued seeds to. It only
+//! records a key in DeliverTx, so CheckTx and simulation see the same heights as every node. A
+//! key it has a height for is a duplicate, also when an earlier registration in the same block
+//! issued the seeds to it, and governance picks what happens to it with
+//! `DUPLICATE_REGISTRATION_POLICY`: the seeds are issued again, the registration fails with
+//! `AlreadyRegistered`, or it only succeeds if the cert carries a proof of possession, see
+//! `possession`.
+//!
+//! Without the light client there's no verified height, and nothing is a duplicate.
+
+use log::*;
+
+use enclave_ffi_types::NodeAuthResult;
+use enclave_utils::verified_params::{DuplicateRegistrationPolicy, DUPLICATE_REGISTRATION_POLICY};
+use enclave_wire::CombinedCert;
+
+use super::key_scheme::AttestedKeys;
+use super::possession::verify_recent_possession;
+
+/// What the policy says about a key that was first issued seeds at `first_issued`, and registers
+/// again at `height`. `has_proof` is only called when the policy needs it.
+fn duplicate_outcome<F: FnOnce() -> bool>(
+    policy: DuplicateRegistrationPolicy,
+    first_issued: Option<u64>,
+    height: u64,
+    has_proof: F,
+) -> Result<(), NodeAuthResult> {
+    match first_issued {
+        Some(_) if height != 0 => {}
+        _ => return Ok(()),
+    }
+
+    match policy {
+        DuplicateRegistrationPolicy::Allow => Ok(()),
+        DuplicateRegistrationPolicy::Deny => Err(NodeAuthResult::AlreadyRegistered),
+        DuplicateRegistrationPolicy::RequireProof if has_proof() => Ok(()),
+        DuplicateRegistrationPolicy::RequireProof => Err(NodeAuthResult::AlreadyRegistered),
+    }
+}
+
+/// Checks a registration of `keys` at `height` against the height x/registration first issued
+/// seeds to their X25519 key at, 0 if it never did
+pub fn check_duplicate(
+    keys: &AttestedKeys,
+    cert: &CombinedCert,
+    issued_height: u64,
+    height: u64,
+) -> Result<(), NodeAuthResult> {
+    let public_key = &keys.exchange_key;
+    let first_issued = Some(issued_height).filter(|issued_height| *issued_height != 0);
+
+    // Until the params are proven, duplicates are denied
+    let outcome = duplicate_outcome(
+        DUPLICATE_REGISTRATION_POLICY.get_or(
+            DuplicateRegistrationPolicy::Allow,
+            DuplicateRegistrationPolicy::Deny,
+        ),
+        first_issued,
+        height,
+        || verify_recent_possession(keys, cert),
+    );
+
+    if let Some(first_issued) = first_issued {
+        if outcome.is_err() {
+            warn!(
+                "Key {} was already issued seeds at height {}, rejecting the registration",
+                hex::encode(public_key),
+                first_issued
+            );
+        } else {
+            info!(
+                "Key {} was already issued seeds at height {}, issuing them again",
+                hex::encode(public_key),
+                first_issued
+            );
+        }
+    }
+
+    outcome
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_duplicate_registration_policy() {
+        use DuplicateRegistrationPolicy::*;
+
+        let no_proof = || false;
+        let proof = || true;
+
+        // a key that wasn't issued seeds
+        assert_eq!(duplicate_outcome(Deny, None, 100, no_proof), Ok(()));
+        // without a verified height nothing is a duplicate
+        assert_eq!(duplicate_outcome(Deny, Some(100), 0, no_proof), Ok(()));
+
+        assert_eq!(duplicate_outcome(Allow, Some(100), 101, no_proof), Ok(()));
+        // issued to by an earlier registration in the same block
+        assert_eq!(
+            duplicate_outcome(Deny, Some(100), 100, no_proof),
+            Err(NodeAuthResult::AlreadyRegistered)
+        );
+        assert_eq!(
+            duplicate_outcome(Deny, Some(100), 101, proof),
+            Err(NodeAuthResult::AlreadyRegistered)
+        );
+        assert_eq!(
+            duplicate_outcome(RequireProof, Some(100), 101, no_proof),
+            Err(NodeAuthResult::AlreadyRegistered)
+        );
+        assert_eq!(
+            duplicate_outcome(RequireProof, Some(100), 101, proof),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! The keys a node registers with, as the `KeyScheme` in its cert lays them out.
+//!
+//! The attestation of a node binds its keys with the report data of its quote. Nodes always had a
+//! single X25519 key in its first 32 bytes, which the seeds are encrypted to, and which can't
+//! sign. A cert can now name another scheme, so a new kind of key registers through the same
+//! ecall, and nodes that don't know about it keep registering as they did:
+//!
+//! ```text
+//! X25519:        X25519 key (32) || zeros (32)
+//! Ed25519X25519: X25519 key (32) || Ed25519 key (32)
+//! ```
+//!
+//! The seeds are still encrypted to the X25519 key in either scheme. The Ed25519 key is derived
+//! from the registration key, so a node's keychain doesn't change, and it signs the node's proofs
+//! of possession, see `possession`.
+
+use ed25519_consensus::SigningKey;
+
+use enclave_crypto::{hkdf_sha_256, KeyPair, PUBLIC_KEY_SIZE};
+use enclave_ffi_types::NodeAuthResult;
+
+pub use enclave_wire::KeyScheme;
+
+const SIGNING_KEY_DOMAIN: &[u8] = b"secret-registration-signing-key";
+
+/// The report data an attestation binds the keys with
+pub const REPORT_DATA_SIZE: usize = 64;
+
+/// The keys of a registering node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttestedKeys {
+    pub scheme: KeyScheme,
+    /// The X25519 key the seeds are encrypted to
+    pub exchange_key: [u8; PUBLIC_KEY_SIZE],
+    /// The Ed25519 key that signs for the node, if its scheme has one
+    pub signing_key: Option<[u8; 32]>,
+}
+
+impl AttestedKeys {
+    /// The keys in the report data of an attestation, as `scheme` lays them out. An Ed25519 key of
+    /// zeros is rejected: enclaves never attest one, but it's what a quote of the `X25519` scheme
+    /// would read as.
+    pub fn from_report_data(
+        scheme: KeyScheme,
+        report_data: &[u8; REPORT_DATA_SIZE],
+    ) -> Result<Self, NodeAuthResult> {
+        let mut exchange_key = [0u8; PUBLIC_KEY_SIZE];
+        exchange_key.copy_from_slice(&report_data[..PUBLIC_KEY_SIZE]);
+
+        let signing_key = match scheme {
+            KeyScheme::X25519 => None,
+            KeyScheme::Ed25519X25519 => {
+                let mut signing_key = [0u8; 32];
+                signing_key.copy_from_slice(&report_data[PUBLIC_KEY_SIZE..]);
+                if signing_key == [0u8; 32] {
+                    return Err(NodeAuthResult::UnsupportedKeyScheme);
+                }
+                Some(signing_key)
+            }
+        };
+
+        Ok(Self {
+            scheme,
+            exchange_key,
+            signing_key,
+        })
+    }
+
+    pub fn report_data(&self) -> [u8; REPORT_DATA_SIZE] {
+        let mut report_data = [0u8; REPORT_DATA_SIZE];
+        report_data[..PUBLIC_KEY_SIZE].copy_from_slice(&self.exchange_key);
+        if let Some(signing_key) = self.signing_key {
+            report_data[PUBLIC_KEY_SIZE..].copy_from_slice(&signing_key);
+        }
+        report_data
+    }
+}
+
+/// The Ed25519 key of a node, derived from its registration key
+pub fn registration_signing_key(registration_key: &KeyPair) -> SigningKey {
+    SigningKey::from(*hkdf_sha_256(registration_key.get_privkey(), &[SIGNING_KEY_DOMAIN]).get())
+}
+
+/// The keys a node with `registration_key` attests in `scheme`
+pub fn registration_keys(registration_key: &KeyPair, scheme: KeyScheme) -> AttestedKeys {
+    let signing_key = match scheme {
+        KeyScheme::X25519 => None,
+        KeyScheme::Ed25519X25519 => Some(
+            registration_signing_key(registration_key)
+                .verification_key()
+                .to_bytes(),
+        ),
+    };
+
+    AttestedKeys {
+        scheme,
+        exchange_key: registration_key.get_pubkey(),
+        signing_key,
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_attested_keys_report_data() {
+        let registration_key = KeyPair::new().unwrap();
+
+        let legacy = registration_keys(&registration_key, KeyScheme::X25519);
+        let report_data = legacy.report_data();
+        assert_eq!(&report_data[..32], &registration_key.get_pubkey());
+        assert_eq!(&report_data[32..], &[0u8; 32]);
+        assert_eq!(
+            AttestedKeys::from_report_data(KeyScheme::X25519, &report_data),
+            Ok(legacy)
+        );
+
+        let hybrid = registration_keys(&registration_key, KeyScheme::Ed25519X25519);
+        assert_eq!(hybrid.exchange_key, registration_key.get_pubkey());
+        assert_eq!(
+            AttestedKeys::from_report_data(KeyScheme::Ed25519X25519, &hybrid.report_data()),
+            Ok(hybrid)
+        );
+        // the same key every time
+        assert_eq!(
+            registration_keys(&registration_key, KeyScheme::Ed25519X25519),
+            hybrid
+        );
+
+        // a quote of a single X25519 key doesn't pass for one of both
+        assert_eq!(
+            AttestedKeys::from_report_data(KeyScheme::Ed25519X25519, &report_data),
+            Err(NodeAuthResult::UnsupportedKeyScheme)
+        );
+    }
+}
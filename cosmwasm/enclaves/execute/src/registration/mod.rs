@@ -1,18 +1,28 @@
-pub use attestation::create_attestation_certificate;
+pub use attestation::{create_attestation_certificate, get_quote_ecdsa};
 pub use offchain::{ecall_get_attestation_report, ecall_init_bootstrap, ecall_init_node};
 pub use onchain::ecall_authenticate_new_node;
 
 mod attestation;
+mod auth_cache;
+mod backup;
 mod cert;
+pub mod code_attestation;
+mod collateral_cache;
 mod hex;
+mod issued_keys;
+mod key_scheme;
+mod node_role;
 mod offchain;
 mod onchain;
+mod operator;
+pub mod package;
 mod persistency;
+pub mod platform_census;
+mod possession;
 mod report;
+mod seed_ceremony;
 mod seed_exchange;
-
-#[cfg(feature = "SGX_MODE_HW")]
-mod ocalls;
+mod svn_ratchet;
 
 #[cfg(feature = "SGX_MODE_HW")]
 pub mod print_report;
@@ -39,6 +49,44 @@ pub mod tests {
             report::tests::test_attestation_dcap_temper();
             cert::tests::test_certificate_valid();
             cert::tests::test_certificate_invalid_configuration_needed();
+            cert::tests::test_attestation_policy();
+            cert::tests::test_attestation_policy_decode();
+            code_attestation::tests::test_code_attestation_report_data();
+            code_attestation::tests::test_code_attestation_check_statement();
+            collateral_cache::tests::test_collateral_parse();
+            collateral_cache::tests::test_collateral_cache_lookup();
+            collateral_cache::tests::test_collateral_cache_reload();
+            collateral_cache::tests::test_collateral_cache_own();
+            backup::tests::test_registration_backup_roundtrip();
+            backup::tests::test_registration_backup_restore_policy();
+            seed_ceremony::tests::test_seed_ceremony_transcript();
+            seed_ceremony::tests::test_seed_ceremony_shares();
+            seed_ceremony::tests::test_seed_ceremony_participant_policy();
+            seed_exchange::tests::test_read_seed_blob();
+            node_role::tests::test_node_role_from_isv_prod_id();
+            node_role::tests::test_node_role_secret_to_share();
+            seed_exchange::tests::test_decode_seed_blob();
+            seed_exchange::tests::test_decode_seed_blob_mismatch();
+            onchain::tests::test_epid_status();
+            onchain::tests::test_issues_genesis_seed();
+            onchain::tests::test_split_combined_cert();
+            auth_cache::tests::test_auth_cache_holds_until_expiry();
+            auth_cache::tests::test_auth_cache_drops_expired_entries();
+            key_scheme::tests::test_attested_keys_report_data();
+            possession::tests::test_possession_proof();
+            possession::tests::test_possession_proof_ed25519();
+            possession::tests::test_possession_challenge_age();
+            operator::tests::test_operator_binding();
+            operator::tests::test_operator_binding_height();
+            package::tests::test_registration_package_decode();
+            package::tests::test_registration_package_attested();
+            svn_ratchet::tests::test_isv_svn_ratchet();
+            svn_ratchet::tests::test_isv_svn_ratchet_decode();
+            issued_keys::tests::test_duplicate_registration_policy();
+            platform_census::tests::test_platform_census_tcb_level();
+            platform_census::tests::test_platform_census_quote_platform();
+            platform_census::tests::test_platform_census_aggregates();
+            platform_census::tests::test_platform_census_signature();
         });
 
         if failures != 0 {
@@ -0,0 +1,121 @@
+//! Roles of the enclaves that register with the network.
+//!
+//! Besides validators, attested service enclaves (oracles, job workers and escrows) register the
+//! same way, but only receive the capability key of their role, never the consensus seed. The
+//! role of an enclave is the ISV product ID of its DCAP quote, which is set when the enclave is
+//! signed, so an enclave can only claim a role it was signed for. Validators are product 0, like
+//! every enclave that registered before roles existed.
+//!
+use enclave_crypto::consts::{
+    ESCROW_CAPABILITY_KEY_DERIVE_ORDER, JOB_WORKER_CAPABILITY_KEY_DERIVE_ORDER,
+    ORACLE_CAPABILITY_KEY_DERIVE_ORDER,
+};
+use enclave_crypto::{derive_data, Kdf, KdfAlgorithm, KeyContext, Seed};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
+pub enum NodeRole {
+    Validator = 0,
+    Oracle = 1,
+    JobWorker = 2,
+    Escrow = 3,
+}
+
+impl Default for NodeRole {
+    fn default() -> Self {
+        NodeRole::Validator
+    }
+}
+
+impl NodeRole {
+    pub fn from_isv_prod_id(isv_prod_id: u16) -> Option<Self> {
+        match isv_prod_id {
+            0 => Some(NodeRole::Validator),
+            1 => Some(NodeRole::Oracle),
+            2 => Some(NodeRole::JobWorker),
+            3 => Some(NodeRole::Escrow),
+            _ => None,
+        }
+    }
+
+    /// The derivation order of the role's capability key, or None for validators, which receive
+    /// the consensus seed itself
+    fn capability_key_derive_order(self) -> Option<u32> {
+        match self {
+            NodeRole::Validator => None,
+            NodeRole::Oracle => Some(ORACLE_CAPABILITY_KEY_DERIVE_ORDER),
+            NodeRole::JobWorker => Some(JOB_WORKER_CAPABILITY_KEY_DERIVE_ORDER),
+            NodeRole::Escrow => Some(ESCROW_CAPABILITY_KEY_DERIVE_ORDER),
+        }
+    }
+
+    /// The secret an enclave of the role receives for a consensus seed. Capability keys are the
+    /// same length as seeds, so the encrypted secrets have the same layout for every role.
+    pub fn secret_to_share(
+        self,
+        seed: &Seed,
+        kdf: KdfAlgorithm,
+        context: Option<&KeyContext>,
+    ) -> Vec<u8> {
+        match self.capability_key_derive_order() {
+            None => seed.as_slice().to_vec(),
+            Some(order) => seed
+                .derive_key_with(kdf, &derive_data(context, order))
+                .get()
+                .to_vec(),
+        }
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    use enclave_crypto::SEED_KEY_SIZE;
+
+    pub fn test_node_role_from_isv_prod_id() {
+        assert_eq!(NodeRole::from_isv_prod_id(0), Some(NodeRole::Validator));
+        assert_eq!(NodeRole::from_isv_prod_id(3), Some(NodeRole::Escrow));
+        assert_eq!(NodeRole::from_isv_prod_id(4), None);
+        assert_eq!(NodeRole::default(), NodeRole::Validator);
+    }
+
+    pub fn test_node_role_secret_to_share() {
+        let mut seed = Seed::default();
+        seed.as_mut().copy_from_slice(&[7u8; SEED_KEY_SIZE]);
+
+        let validator = NodeRole::Validator.secret_to_share(&seed, KdfAlgorithm::HkdfSha256, None);
+        assert_eq!(validator, seed.as_slice().to_vec());
+
+        let roles = [NodeRole::Oracle, NodeRole::JobWorker, NodeRole::Escrow];
+        let secrets: Vec<Vec<u8>> = roles
+            .iter()
+            .map(|role| role.secret_to_share(&seed, KdfAlgorithm::HkdfSha256, None))
+            .collect();
+        for (i, secret) in secrets.iter().enumerate() {
+            assert_eq!(secret.len(), SEED_KEY_SIZE);
+            // Service enclaves never receive the seed, or the key of another role
+            assert_ne!(secret, &validator);
+            assert!(secrets[i + 1..].iter().all(|other| other != secret));
+        }
+
+        // Keys are derived like the other keys of the seed, so the algorithm matters
+        assert_ne!(
+            NodeRole::Oracle.secret_to_share(&seed, KdfAlgorithm::HkdfSha512, None),
+            secrets[0]
+        );
+
+        // and so does the network the seed is bound to
+        let context = KeyContext {
+            chain_id: "secret-4".to_string(),
+            network_version: 1,
+        };
+        assert_ne!(
+            NodeRole::Oracle.secret_to_share(&seed, KdfAlgorithm::HkdfSha256, Some(&context)),
+            secrets[0]
+        );
+        assert_eq!(
+            NodeRole::Validator.secret_to_share(&seed, KdfAlgorithm::HkdfSha256, Some(&context)),
+            validator
+        );
+    }
+}
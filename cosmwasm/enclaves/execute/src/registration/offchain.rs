@@ -1,8 +1,15 @@
 //!
 use super::attestation::{create_attestation_certificate, get_quote_ecdsa};
+use super::backup::{
+    export_registration_backup, import_registration_backup, release_registration_backup,
+};
+use super::seed_ceremony::{
+    ceremony_seeds, combine_seed_shares, commit_seed_share, reveal_seed_share,
+};
 use super::seed_service::get_next_consensus_seed_from_service;
 use crate::registration::attestation::verify_quote_sgx;
 use crate::registration::onchain::split_combined_cert;
+use crate::secure_time::enclave_now;
 #[cfg(feature = "verify-validator-whitelist")]
 use block_verifier::validator_whitelist;
 use core::convert::TryInto;
@@ -12,19 +19,22 @@ use enclave_crypto::consts::{
     make_sgx_secret_path, ATTESTATION_CERT_PATH, ATTESTATION_DCAP_PATH, COLLATERAL_DCAP_PATH,
     CONSENSUS_SEED_VERSION, FILE_CERT_COMBINED, FILE_MIGRATION_CERT_LOCAL,
     FILE_MIGRATION_CERT_REMOTE, FILE_MIGRATION_DATA, FILE_MIGRATION_TARGET_INFO,
-    INPUT_ENCRYPTED_SEED_SIZE, MIGRATION_CONSENSUS_PATH, PUBKEY_PATH, SEED_UPDATE_SAVE_PATH,
-    SIGNATURE_TYPE,
+    MIGRATION_CONSENSUS_PATH, PUBKEY_PATH, SEED_UPDATE_SAVE_PATH, SIGNATURE_TYPE,
 };
+use enclave_crypto::KeyContext;
 #[cfg(feature = "random")]
 use enclave_crypto::{
     consts::SELF_REPORT_BODY, sha_256, AESKey, Ed25519PublicKey, KeyPair, SIVEncryptable,
     PUBLIC_KEY_SIZE,
 };
-use enclave_ffi_types::SINGLE_ENCRYPTED_SEED_SIZE;
+use enclave_ffi_types::{SeedBlobStatus, MAX_MSG_LENGTH};
+use enclave_utils::input_limits::max_cert_length;
 use enclave_utils::key_manager::KeychainMutableData;
+use enclave_utils::output_buffer::{write_output, BufferTooSmall};
 use enclave_utils::pointers::validate_mut_slice;
 use enclave_utils::storage::migrate_all_from_2_17;
 use enclave_utils::{validate_const_ptr, validate_mut_ptr, Keychain, KEY_MANAGER};
+use enclave_wire::{CombinedCert, CombinedCertBuilder, OperatorBinding};
 /// These functions run off chain, and so are not limited by deterministic limitations. Feel free
 /// to go crazy with random generation entropy, time requirements, or whatever else
 ///
@@ -40,10 +50,19 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::panic;
 use std::slice;
+use tendermint::block::Header;
 use tendermint::Hash::Sha256 as tm_Sha256;
+use tendermint_proto::v0_38::types::Header as RawHeader;
+use tendermint_proto::Protobuf;
 
+use super::code_attestation::MAX_CHAIN_ID_LENGTH;
+use super::key_scheme::{registration_keys, KeyScheme};
+use super::node_role::NodeRole;
+use super::operator::verify_binding;
 use super::persistency::{write_master_pub_keys, write_seed};
-use super::seed_exchange::{decrypt_seed, encrypt_seed, SeedType};
+use super::possession::{prove_possession, Challenge, ProofBlock};
+use super::seed_exchange::{decode_seed_blob, encrypt_seed, SeedBlob, SeedType};
+use super::svn_ratchet::is_peer_isv_svn_allowed;
 
 #[cfg(feature = "light-client-validation")]
 use block_verifier::VERIFIED_BLOCK_MESSAGES;
@@ -66,12 +85,18 @@ pub unsafe extern "C" fn ecall_init_bootstrap(
     spid_len: u32,
     api_key: *const u8,
     api_key_len: u32,
+    chain_id: *const u8,
+    chain_id_len: usize,
 ) -> sgx_status_t {
     validate_mut_ptr!(
         public_key.as_mut_ptr(),
         public_key.len(),
         sgx_status_t::SGX_ERROR_UNEXPECTED,
     );
+    let key_context = match read_key_context(chain_id, chain_id_len) {
+        Ok(key_context) => key_context,
+        Err(status) => return status,
+    };
 
     validate_const_ptr!(spid, spid_len as usize, sgx_status_t::SGX_ERROR_UNEXPECTED);
 
@@ -83,8 +108,14 @@ pub unsafe extern "C" fn ecall_init_bootstrap(
 
     let mut key_manager = Keychain::new_empty();
 
-    if let Err(_e) = key_manager.create_consensus_seed() {
-        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    match ceremony_seeds() {
+        Ok(Some((genesis, current))) => key_manager.set_consensus_seed(genesis, current),
+        Ok(None) => {
+            if let Err(_e) = key_manager.create_consensus_seed() {
+                return sgx_status_t::SGX_ERROR_UNEXPECTED;
+            }
+        }
+        Err(status) => return status,
     }
 
     #[cfg(feature = "use_seed_service_on_bootstrap")]
@@ -118,6 +149,11 @@ pub unsafe extern "C" fn ecall_init_bootstrap(
         key_manager.set_consensus_seed(genesis_seed, new_consensus_seed);
     }
 
+    // No keys were derived from the seeds of a new network yet, so they can be bound to it
+    if let Err(_e) = key_manager.bind_consensus_seed_context(key_context) {
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
     if let Err(_e) = key_manager.generate_consensus_master_keys() {
         return sgx_status_t::SGX_ERROR_UNEXPECTED;
     }
@@ -146,6 +182,28 @@ pub unsafe extern "C" fn ecall_init_bootstrap(
     sgx_status_t::SGX_SUCCESS
 }
 
+/// The context of the chain id the node passed, which the consensus seeds of a new network are
+/// bound to
+unsafe fn read_key_context(chain_id: *const u8, chain_id_len: usize) -> SgxResult<KeyContext> {
+    if chain_id_len == 0 || chain_id_len > MAX_CHAIN_ID_LENGTH {
+        error!(
+            "chain_id ({}) must be between 1 and {} bytes",
+            chain_id_len, MAX_CHAIN_ID_LENGTH
+        );
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    validate_const_ptr!(
+        chain_id,
+        chain_id_len,
+        Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+    );
+
+    match std::str::from_utf8(slice::from_raw_parts(chain_id, chain_id_len)) {
+        Ok(chain_id) => Ok(KeyContext::new(chain_id)),
+        Err(_) => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+    }
+}
+
 ///
 ///  `ecall_init_node`
 ///
@@ -156,6 +214,10 @@ pub unsafe extern "C" fn ecall_init_bootstrap(
 ///
 /// The seed was encrypted using Diffie-Hellman in the function [ecall_get_encrypted_seed]
 ///
+/// `seed_status` says whether the seed had only the genesis seed or both seeds, or why it couldn't
+/// be loaded. A length byte that disagrees with the seeds after it is rejected, and so are seeds
+/// that don't decrypt, each with their own status, so the node can tell the operator which.
+///
 /// This function happens off-chain, so if we panic for some reason it _can_ be acceptable,
 ///  though probably not recommended
 ///
@@ -168,12 +230,22 @@ pub unsafe extern "C" fn ecall_init_bootstrap(
 pub unsafe extern "C" fn ecall_init_node(
     master_key: *const u8,
     master_key_len: u32,
+    // seed structure 1 byte - length (96 or 48) | genesis seed bytes | current seed bytes (optional)
     encrypted_seed: *const u8,
     encrypted_seed_len: u32,
     api_key: *const u8,
     api_key_len: u32,
-    // seed structure 1 byte - length (96 or 48) | genesis seed bytes | current seed bytes (optional)
+    chain_id: *const u8,
+    chain_id_len: usize,
+    seed_status: &mut SeedBlobStatus,
 ) -> sgx_status_t {
+    *seed_status = SeedBlobStatus::NotDecoded;
+
+    let key_context = match read_key_context(chain_id, chain_id_len) {
+        Ok(key_context) => key_context,
+        Err(status) => return status,
+    };
+
     validate_const_ptr!(
         master_key,
         master_key_len as usize,
@@ -196,10 +268,15 @@ pub unsafe extern "C" fn ecall_init_node(
 
     let key_slice = slice::from_raw_parts(master_key, master_key_len as usize);
 
-    if encrypted_seed_len != INPUT_ENCRYPTED_SEED_SIZE {
-        error!("Encrypted seed bad length");
-        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
-    }
+    let encrypted_seed_slice = slice::from_raw_parts(encrypted_seed, encrypted_seed_len as usize);
+    let seed_blob = match decode_seed_blob(encrypted_seed_slice) {
+        Ok(seed_blob) => seed_blob,
+        Err(status) => {
+            error!("Failed to decode the encrypted seed: {}", status);
+            *seed_status = status;
+            return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        }
+    };
 
     // validate this node is patched and updated
 
@@ -219,8 +296,6 @@ pub unsafe extern "C" fn ecall_init_node(
     //     return sgx_status_t::SGX_ERROR_UNEXPECTED;
     // }
 
-    let encrypted_seed_slice = slice::from_raw_parts(encrypted_seed, encrypted_seed_len as usize);
-
     // validate this node is patched and updated
 
     // generate temporary key for attestation
@@ -267,70 +342,76 @@ pub unsafe extern "C" fn ecall_init_node(
 
     key_manager.delete_consensus_seed();
 
-    // Skip the first byte which is the length of the seed
-    let mut single_seed_bytes = [0u8; SINGLE_ENCRYPTED_SEED_SIZE];
-    single_seed_bytes.copy_from_slice(&encrypted_seed_slice[1..(SINGLE_ENCRYPTED_SEED_SIZE + 1)]);
-
     trace!("Target public key is: {:?}", target_public_key);
-    let genesis_seed = match decrypt_seed(&key_manager, target_public_key, single_seed_bytes) {
-        Ok(result) => result,
-        Err(status) => return status,
+    let seeds = match seed_blob.decrypt(&key_manager, target_public_key) {
+        Ok(seeds) => seeds,
+        Err(status) => {
+            error!("Failed to decrypt the encrypted seed: {}", status);
+            *seed_status = status;
+            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        }
     };
+    *seed_status = seeds.status();
 
-    let encrypted_seed_len = encrypted_seed_slice[0] as u32;
-    let new_consensus_seed;
-
-    if encrypted_seed_len as usize == 2 * SINGLE_ENCRYPTED_SEED_SIZE {
-        debug!("Got both keys from registration");
-
-        single_seed_bytes.copy_from_slice(
-            &encrypted_seed_slice
-                [(SINGLE_ENCRYPTED_SEED_SIZE + 1)..(SINGLE_ENCRYPTED_SEED_SIZE * 2 + 1)],
-        );
-        new_consensus_seed = match decrypt_seed(&key_manager, target_public_key, single_seed_bytes)
-        {
-            Ok(result) => result,
-            Err(status) => return status,
-        };
+    match seeds {
+        SeedBlob::Dual { genesis, current } => {
+            debug!("Got both keys from registration");
 
-        key_manager.set_consensus_seed(genesis_seed, new_consensus_seed);
-    } else {
-        let reg_key = key_manager.get_registration_key().unwrap();
-        let my_pub_key = reg_key.get_pubkey();
-
-        debug!("New consensus seed not found! Need to get it from service");
-        if key_manager.get_consensus_seed().is_err() {
-            new_consensus_seed = match get_next_consensus_seed_from_service(
-                &mut key_manager,
-                1,
-                genesis_seed,
-                api_key_slice,
-                reg_key,
-                CONSENSUS_SEED_VERSION,
-            ) {
-                Ok(s) => s,
-                Err(e) => {
-                    error!("Consensus seed failure: {}", e as u64);
-                    return sgx_status_t::SGX_ERROR_UNEXPECTED;
-                }
-            };
+            key_manager.set_consensus_seed(genesis, current);
+        }
+        SeedBlob::CurrentOnly(current) => {
+            debug!("Got only the current seed from registration");
 
-            key_manager.set_consensus_seed(genesis_seed, new_consensus_seed);
-        } else {
-            debug!("New consensus seed already exists, no need to get it from service");
+            // There's no genesis seed to derive the genesis keys from. Taking the current seed's
+            // place keeps the keychain whole, and nothing that's still under the genesis seed
+            // decrypts.
+            key_manager.set_consensus_seed(current, current);
         }
+        SeedBlob::GenesisOnly(genesis_seed) => {
+            let reg_key = key_manager.get_registration_key().unwrap();
+            let my_pub_key = reg_key.get_pubkey();
+
+            debug!("New consensus seed not found! Need to get it from service");
+            if key_manager.get_consensus_seed().is_err() {
+                let new_consensus_seed = match get_next_consensus_seed_from_service(
+                    &mut key_manager,
+                    1,
+                    genesis_seed,
+                    api_key_slice,
+                    reg_key,
+                    CONSENSUS_SEED_VERSION,
+                ) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        error!("Consensus seed failure: {}", e as u64);
+                        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+                    }
+                };
+
+                key_manager.set_consensus_seed(genesis_seed, new_consensus_seed);
+            } else {
+                debug!("New consensus seed already exists, no need to get it from service");
+            }
 
-        let mut res: Vec<u8> = encrypt_seed(my_pub_key, SeedType::Genesis, false).unwrap();
-        let res_current: Vec<u8> = encrypt_seed(my_pub_key, SeedType::Current, false).unwrap();
-        res.extend(&res_current);
+            let mut res: Vec<u8> =
+                encrypt_seed(my_pub_key, SeedType::Genesis, false, NodeRole::Validator).unwrap();
+            let res_current: Vec<u8> =
+                encrypt_seed(my_pub_key, SeedType::Current, false, NodeRole::Validator).unwrap();
+            res.extend(&res_current);
 
-        trace!("Done encrypting seed, got {:?}, {:?}", res.len(), res);
+            trace!("Done encrypting seed, got {:?}, {:?}", res.len(), res);
 
-        if let Err(_e) = write_seed(&res, SEED_UPDATE_SAVE_PATH) {
-            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+            if let Err(_e) = write_seed(&res, SEED_UPDATE_SAVE_PATH) {
+                return sgx_status_t::SGX_ERROR_UNEXPECTED;
+            }
         }
     }
 
+    // The seeds have to derive the same keys as on the rest of the network
+    if let Err(_e) = key_manager.detect_consensus_seed_context(&target_public_key, key_context) {
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
     // this initializes the key manager with all the keys we need for computations
     if let Err(_e) = key_manager.generate_consensus_master_keys() {
         return sgx_status_t::SGX_ERROR_UNEXPECTED;
@@ -393,23 +474,15 @@ pub fn save_attestation_combined(
     res_dcap: &Result<(Vec<u8>, Vec<u8>), sgx_status_t>,
     res_epid: &Result<Vec<u8>, sgx_status_t>,
     is_migration_report: bool,
+    scheme: KeyScheme,
 ) -> sgx_status_t {
-    let mut size_epid: u32 = 0;
-    let mut size_dcap_q: u32 = 0;
-    let mut size_dcap_c: u32 = 0;
-
     if let Ok(ref vec_cert) = res_epid {
-        size_epid = vec_cert.len() as u32;
-
         if !is_migration_report {
             write_to_untrusted(vec_cert.as_slice(), ATTESTATION_CERT_PATH.as_str()).unwrap();
         }
     }
 
     if let Ok((ref vec_quote, ref vec_coll)) = res_dcap {
-        size_dcap_q = vec_quote.len() as u32;
-        size_dcap_c = vec_coll.len() as u32;
-
         if !is_migration_report {
             write_to_untrusted(vec_quote, ATTESTATION_DCAP_PATH.as_str()).unwrap();
             write_to_untrusted(vec_coll, COLLATERAL_DCAP_PATH.as_str()).unwrap();
@@ -430,20 +503,20 @@ pub fn save_attestation_combined(
         }
     };
 
-    f_out.write_all(&size_epid.to_le_bytes()).unwrap();
-    f_out.write_all(&size_dcap_q.to_le_bytes()).unwrap();
-    f_out.write_all(&size_dcap_c.to_le_bytes()).unwrap();
-
-    if let Ok(ref vec_cert) = res_epid {
-        f_out.write_all(vec_cert.as_slice()).unwrap();
-    }
-
-    if let Ok((vec_quote, vec_coll)) = res_dcap {
-        f_out.write_all(vec_quote.as_slice()).unwrap();
-        f_out.write_all(vec_coll.as_slice()).unwrap();
-    }
-
-    if (size_epid == 0) && (size_dcap_q == 0) {
+    let none = vec![];
+    let vec_cert = res_epid.as_ref().unwrap_or(&none);
+    let (vec_quote, vec_coll) = match res_dcap {
+        Ok((vec_quote, vec_coll)) => (vec_quote, vec_coll),
+        Err(_) => (&none, &none),
+    };
+    let combined = CombinedCertBuilder::new()
+        .epid_cert(vec_cert)
+        .dcap(vec_quote, vec_coll)
+        .key_scheme(scheme)
+        .build();
+    f_out.write_all(&combined).unwrap();
+
+    if vec_cert.is_empty() && vec_quote.is_empty() {
         if let Err(status) = res_epid {
             return *status;
         }
@@ -454,10 +527,19 @@ pub fn save_attestation_combined(
 }
 
 fn get_verified_migration_report_body() -> SgxResult<sgx_report_body_t> {
-    if let Ok(mut f_in) = File::open(make_sgx_secret_path(FILE_MIGRATION_CERT_LOCAL)) {
+    get_verified_report_body(FILE_MIGRATION_CERT_LOCAL, FILE_MIGRATION_CERT_REMOTE)
+}
+
+/// Verifies the report of another enclave, created with migration op 1: a local report if
+/// `local_file` exists, or a DCAP quote in `remote_file`
+pub(super) fn get_verified_report_body(
+    local_file: &str,
+    remote_file: &str,
+) -> SgxResult<sgx_report_body_t> {
+    if let Ok(mut f_in) = File::open(make_sgx_secret_path(local_file)) {
         let mut buffer = vec![0u8; std::mem::size_of::<sgx_report_t>()];
         if f_in.read_exact(&mut buffer).is_ok() {
-            println!("Found local migration report");
+            println!("Found local report");
             let report: sgx_report_t =
                 unsafe { std::ptr::read(buffer.as_ptr() as *const sgx_report_t) };
 
@@ -472,16 +554,20 @@ fn get_verified_migration_report_body() -> SgxResult<sgx_report_body_t> {
         }
     }
 
-    if let Ok(mut f_in) = File::open(make_sgx_secret_path(FILE_MIGRATION_CERT_REMOTE)) {
-        println!("Found remote migration report");
+    if let Ok(mut f_in) = File::open(make_sgx_secret_path(remote_file)) {
+        println!("Found remote report");
 
         let mut cert = vec![];
         f_in.read_to_end(&mut cert).unwrap();
 
-        let (_, vec_quote, vec_coll) = split_combined_cert(cert.as_ptr(), cert.len() as u32);
+        let (_, vec_quote, vec_coll) = split_combined_cert(&cert);
 
-        match verify_quote_sgx(vec_quote.as_slice(), vec_coll.as_slice(), 0) {
-            Ok((body, _)) => {
+        match verify_quote_sgx(
+            vec_quote.as_slice(),
+            vec_coll.as_slice(),
+            enclave_now().seconds,
+        ) {
+            Ok((body, _, _)) => {
                 return Ok(body);
             }
             Err(e) => {
@@ -505,6 +591,10 @@ fn get_verified_migration_report_body() -> SgxResult<sgx_report_body_t> {
  *
  * This x509 certificate can be used in the future for mutual-RA cross-enclave TLS channels, or for
  * other creative usages.
+ *
+ * With the flag 0x20, the DCAP quote attests an Ed25519 key next to the registration key, and the
+ * cert names the `Ed25519X25519` key scheme, see `key_scheme`. There's no EPID report then, it has
+ * no room for the second key.
  * # Safety
  * Something should go here
 */
@@ -513,8 +603,6 @@ pub unsafe extern "C" fn ecall_get_attestation_report(
     api_key_len: u32,
     flags: u32,
 ) -> sgx_status_t {
-    let mut report_data: [u8; 48] = [0; 48];
-
     let (kp, is_migration_report) = match 0x10 & flags {
         0x10 => {
             // migration report
@@ -528,34 +616,226 @@ pub unsafe extern "C" fn ecall_get_attestation_report(
                 hex::encode(kp.get_pubkey())
             );
 
-            let mut f_out = match File::create(PUBKEY_PATH.as_str()) {
-                Ok(f) => f,
-                Err(e) => {
-                    error!("failed to create file {}", e);
-                    return sgx_status_t::SGX_ERROR_UNEXPECTED;
-                }
-            };
-
-            f_out.write_all(kp.get_pubkey().as_ref()).unwrap();
+            if let Err(e) = write_registration_pubkey(&kp) {
+                return e;
+            }
 
             (kp, false)
         }
     };
 
-    let res_epid = match 1 & flags {
-        0 => get_attestation_report_epid(api_key, api_key_len, &kp),
+    // Migration reports attest the migration key alone
+    let scheme = match 0x20 & flags {
+        0x20 if !is_migration_report => KeyScheme::Ed25519X25519,
+        _ => KeyScheme::X25519,
+    };
+
+    let res_epid = match (1 & flags, scheme) {
+        (0, KeyScheme::X25519) => get_attestation_report_epid(api_key, api_key_len, &kp),
         _ => Err(sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED),
     };
 
     let res_dcap = match 2 & flags {
-        0 => {
-            report_data[0..32].copy_from_slice(&kp.get_pubkey());
-            get_attestation_report_dcap(&report_data)
-        }
+        0 => get_attestation_report_dcap(&registration_keys(&kp, scheme).report_data()),
         _ => Err(sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED),
     };
 
-    save_attestation_combined(&res_dcap, &res_epid, is_migration_report)
+    save_attestation_combined(&res_dcap, &res_epid, is_migration_report, scheme)
+}
+
+/// Appends a proof that the node holds its registration key to the combined cert that
+/// `ecall_get_attestation_report` wrote, see `possession`. `master_key` is the node exchange key
+/// of the network, as `secretd query register secret-network-params` writes it, and the proof is
+/// made for the block with the protobuf encoded `header` and `commit`, which it carries.
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_prove_key_possession(
+    master_key: &[u8; 32],
+    header: *const u8,
+    header_len: usize,
+    commit: *const u8,
+    commit_len: usize,
+) -> sgx_status_t {
+    if header_len > max_cert_length() || commit_len > max_cert_length() {
+        error!(
+            "header ({}) or commit ({}) is longer than {} bytes",
+            header_len,
+            commit_len,
+            max_cert_length()
+        );
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    validate_const_ptr!(header, header_len, sgx_status_t::SGX_ERROR_UNEXPECTED);
+    validate_const_ptr!(commit, commit_len, sgx_status_t::SGX_ERROR_UNEXPECTED);
+    let block = ProofBlock {
+        header: slice::from_raw_parts(header, header_len),
+        commit: slice::from_raw_parts(commit, commit_len),
+    };
+
+    // The enclave that authenticates the registration verifies the header, this one only reads
+    // what the proof is made for
+    let header = match <Header as Protobuf<RawHeader>>::decode(block.header) {
+        Ok(header) => header,
+        Err(e) => {
+            error!("Error parsing header from proto: {:?}", e);
+            return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        }
+    };
+    let app_hash: [u8; 32] = match header.app_hash.as_bytes().try_into() {
+        Ok(app_hash) => app_hash,
+        Err(_) => {
+            error!("A proof of possession needs a block with a 32 byte app hash");
+            return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        }
+    };
+    if block.commit.is_empty() || header.height.value() == 0 {
+        error!("A proof of possession needs a block and its commit");
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+
+    let registration_key = match KEY_MANAGER.get_registration_key() {
+        Ok(key) => key,
+        Err(_) => {
+            error!("Failed to unlock node key. Please make sure the file is accessible or reinitialize the node");
+            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        }
+    };
+
+    let cert = match read_combined_cert() {
+        Ok(cert) => cert,
+        Err(e) => return e,
+    };
+    let attestation = match CombinedCert::parse(&cert) {
+        Ok(parsed) if parsed.has_epid() || parsed.has_dcap() => parsed,
+        _ => {
+            error!("The attestation certificate has no attestation, create it with init-enclave");
+            return sgx_status_t::SGX_ERROR_INVALID_STATE;
+        }
+    };
+    let scheme = match attestation.scheme() {
+        Some(scheme) => scheme,
+        None => {
+            error!("The attestation certificate names an unknown key scheme");
+            return sgx_status_t::SGX_ERROR_INVALID_STATE;
+        }
+    };
+
+    let challenge = Challenge {
+        chain_id: header.chain_id.as_str(),
+        height: header.height.value(),
+        app_hash,
+    };
+    let proof = prove_possession(
+        &registration_key,
+        scheme,
+        master_key,
+        &attestation,
+        &challenge,
+        &block,
+    );
+    let proven = CombinedCert {
+        proof: &proof,
+        ..attestation
+    }
+    .encode();
+
+    match write_combined_cert(&proven) {
+        Ok(()) => sgx_status_t::SGX_SUCCESS,
+        Err(e) => e,
+    }
+}
+
+/// Appends the binding of the node to the validator it serves to the combined cert that
+/// `ecall_get_attestation_report` wrote, see `operator`. `signature` is the signature of the
+/// validator's `consensus_key` over the `operator_message` of the node's registration key and
+/// `chain_id`, and is verified before the cert is written.
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_bind_operator(
+    consensus_key: &[u8; 32],
+    signature: &[u8; 64],
+    chain_id: *const u8,
+    chain_id_len: usize,
+) -> sgx_status_t {
+    if chain_id_len > MAX_CHAIN_ID_LENGTH {
+        error!(
+            "chain_id ({}) is longer than {} bytes",
+            chain_id_len, MAX_CHAIN_ID_LENGTH
+        );
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    validate_const_ptr!(chain_id, chain_id_len, sgx_status_t::SGX_ERROR_UNEXPECTED);
+    let chain_id = match std::str::from_utf8(slice::from_raw_parts(chain_id, chain_id_len)) {
+        Ok(chain_id) => chain_id,
+        Err(_) => return sgx_status_t::SGX_ERROR_INVALID_PARAMETER,
+    };
+
+    let registration_key = match KEY_MANAGER.get_registration_key() {
+        Ok(key) => key,
+        Err(_) => {
+            error!("Failed to unlock node key. Please make sure the file is accessible or reinitialize the node");
+            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        }
+    };
+
+    let binding = OperatorBinding {
+        consensus_key,
+        signature,
+    };
+    if verify_binding(&registration_key.get_pubkey(), &binding, chain_id).is_none() {
+        error!("The consensus key didn't sign the binding of this node");
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+
+    let cert = match read_combined_cert() {
+        Ok(cert) => cert,
+        Err(e) => return e,
+    };
+    let attestation = match CombinedCert::parse(&cert) {
+        Ok(parsed) if parsed.has_epid() || parsed.has_dcap() => parsed,
+        _ => {
+            error!("The attestation certificate has no attestation, create it with init-enclave");
+            return sgx_status_t::SGX_ERROR_INVALID_STATE;
+        }
+    };
+
+    let binding = binding.encode();
+    let bound = CombinedCert {
+        operator: &binding,
+        ..attestation
+    }
+    .encode();
+
+    match write_combined_cert(&bound) {
+        Ok(()) => sgx_status_t::SGX_SUCCESS,
+        Err(e) => e,
+    }
+}
+
+/// The combined cert `ecall_get_attestation_report` wrote
+fn read_combined_cert() -> Result<Vec<u8>, sgx_status_t> {
+    let mut cert = vec![];
+    if let Err(e) = File::open(make_sgx_secret_path(FILE_CERT_COMBINED))
+        .and_then(|mut f| f.read_to_end(&mut cert))
+    {
+        error!("Failed to read the attestation certificate: {}", e);
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+    Ok(cert)
+}
+
+fn write_combined_cert(cert: &[u8]) -> Result<(), sgx_status_t> {
+    if let Err(e) =
+        File::create(make_sgx_secret_path(FILE_CERT_COMBINED)).and_then(|mut f| f.write_all(cert))
+    {
+        error!("Failed to write the attestation certificate: {}", e);
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+    Ok(())
 }
 
 ///
@@ -589,6 +869,92 @@ pub unsafe extern "C" fn ecall_key_gen(
     sgx_status_t::SGX_SUCCESS
 }
 
+fn write_registration_pubkey(kp: &KeyPair) -> Result<(), sgx_status_t> {
+    let mut f_out = match File::create(PUBKEY_PATH.as_str()) {
+        Ok(f) => f,
+        Err(e) => {
+            error!("failed to create file {}", e);
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+        }
+    };
+
+    f_out.write_all(kp.get_pubkey().as_ref()).unwrap();
+    Ok(())
+}
+
+///
+/// `ecall_rotate_node_keys`
+///
+/// Replaces the registration key, for operators who suspect it was exposed. A DCAP attestation is
+/// created for the new key, with the previous public key in the second half of the report data,
+/// so that registering it on-chain revokes the previous key. The attestation is saved the same way
+/// `ecall_get_attestation_report` saves it, and the new key is only sealed once it's attested.
+///
+/// The sealed consensus seeds are kept, but the encrypted seed the node starts with is encrypted to
+/// the previous key, and has to be replaced with the one registered for the new key. An enclave
+/// that is already running keeps using the previous key until it's restarted.
+///
+#[no_mangle]
+pub unsafe extern "C" fn ecall_rotate_node_keys(
+    public_key: &mut [u8; PUBLIC_KEY_SIZE],
+) -> sgx_types::sgx_status_t {
+    if let Err(_e) = validate_mut_slice(public_key) {
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    let mut key_manager = Keychain::new();
+
+    let prev_kp = match key_manager.get_registration_key() {
+        Ok(kp) => kp,
+        Err(_e) => {
+            error!("No registration key to rotate");
+            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        }
+    };
+
+    let new_kp = match KeyPair::new() {
+        Ok(kp) => kp,
+        Err(_e) => {
+            error!("Failed to create registration key");
+            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        }
+    };
+
+    let mut report_data = [0u8; 64];
+    report_data[..32].copy_from_slice(&new_kp.get_pubkey());
+    report_data[32..].copy_from_slice(&prev_kp.get_pubkey());
+
+    let res_dcap = get_attestation_report_dcap(&report_data);
+    if let Err(e) = res_dcap {
+        error!("Failed to attest the new registration key: {}", e);
+        return e;
+    }
+
+    let res = save_attestation_combined(
+        &res_dcap,
+        &Err(sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED),
+        false,
+        KeyScheme::X25519,
+    );
+    if res != sgx_status_t::SGX_SUCCESS {
+        return res;
+    }
+
+    key_manager.set_registration_key(new_kp);
+
+    if let Err(e) = write_registration_pubkey(&new_kp) {
+        return e;
+    }
+
+    public_key.copy_from_slice(&new_kp.get_pubkey());
+    info!(
+        "Registration key rotated from {} to {}",
+        hex::encode(prev_kp.get_pubkey()),
+        hex::encode(new_kp.get_pubkey())
+    );
+    sgx_status_t::SGX_SUCCESS
+}
+
 ///
 /// `ecall_get_genesis_seed
 ///
@@ -598,17 +964,22 @@ pub unsafe extern "C" fn ecall_key_gen(
 /// The seed is encrypted with a key derived from the secret master key of the chain, and the public
 /// key of the requesting chain
 ///
+/// When the seed doesn't fit in `seed_capacity`, `SGX_ERROR_INVALID_PARAMETER` is returned, and
+/// `seed_len` says how large it is.
+///
 /// This function happens off-chain
 ///
 #[no_mangle]
 pub unsafe extern "C" fn ecall_get_genesis_seed(
     pk: *const u8,
     pk_len: u32,
-    seed: &mut [u8; SINGLE_ENCRYPTED_SEED_SIZE],
+    seed: *mut u8,
+    seed_capacity: u32,
+    seed_len: &mut u32,
 ) -> sgx_types::sgx_status_t {
     validate_mut_ptr!(
-        seed.as_mut_ptr(),
-        seed.len(),
+        seed,
+        seed_capacity as usize,
         sgx_status_t::SGX_ERROR_UNEXPECTED
     );
 
@@ -631,8 +1002,13 @@ pub unsafe extern "C" fn ecall_get_genesis_seed(
             &target_public_key.to_vec()
         );
 
-        let res: Vec<u8> = encrypt_seed(target_public_key, SeedType::Genesis, true)
-            .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+        let res: Vec<u8> = encrypt_seed(
+            target_public_key,
+            SeedType::Genesis,
+            true,
+            NodeRole::Validator,
+        )
+        .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?;
 
         Ok(res)
     });
@@ -642,9 +1018,16 @@ pub unsafe extern "C" fn ecall_get_genesis_seed(
             Ok(res) => {
                 trace!("Done encrypting seed, got {:?}, {:?}", res.len(), res);
 
-                seed.copy_from_slice(&res);
-                trace!("returning with seed: {:?}, {:?}", seed.len(), seed);
-                sgx_status_t::SGX_SUCCESS
+                match write_output(&res, seed, seed_capacity, seed_len) {
+                    Ok(()) => sgx_status_t::SGX_SUCCESS,
+                    Err(BufferTooSmall { needed }) => {
+                        debug!(
+                            "encrypted genesis seed ({}) is larger than the buffer ({})",
+                            needed, seed_capacity
+                        );
+                        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+                    }
+                }
             }
             Err(e) => {
                 trace!("error encrypting seed {:?}", e);
@@ -686,6 +1069,30 @@ pub unsafe extern "C" fn ecall_migration_op(opcode: u32) -> sgx_types::sgx_statu
             println!("Export self target info");
             export_self_target_info()
         }
+        6 => {
+            println!("Back up the registration key to the escrow enclave");
+            export_registration_backup()
+        }
+        7 => {
+            println!("Release a registration key backup to the restored enclave");
+            release_registration_backup()
+        }
+        8 => {
+            println!("Restore the registration key from the escrow enclave");
+            import_registration_backup()
+        }
+        9 => {
+            println!("Commit to a share of the genesis seed");
+            commit_seed_share()
+        }
+        10 => {
+            println!("Reveal the seed share to the other participants of the ceremony");
+            reveal_seed_share()
+        }
+        11 => {
+            println!("Combine the seed shares of the ceremony");
+            combine_seed_shares()
+        }
         _ => sgx_status_t::SGX_ERROR_UNEXPECTED,
     }
 }
@@ -743,6 +1150,10 @@ pub unsafe extern "C" fn ecall_onchain_approve_upgrade(
     msg: *const u8,
     msg_len: u32,
 ) -> sgx_types::sgx_status_t {
+    if msg_len as usize > MAX_MSG_LENGTH {
+        error!("upgrade msg ({}) is larger than the maximum", msg_len);
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
     validate_const_ptr!(msg, msg_len as usize, sgx_status_t::SGX_ERROR_UNEXPECTED);
     let msg_slice = slice::from_raw_parts(msg, msg_len as usize);
 
@@ -927,6 +1338,14 @@ fn is_export_approved(report: &sgx_report_body_t) -> bool {
         return false;
     }
 
+    if !is_peer_isv_svn_allowed(report.isv_svn) {
+        println!(
+            "Migration target security version {} is below the scheduled minimum",
+            report.isv_svn
+        );
+        return false;
+    }
+
     {
         let extra = KEY_MANAGER.extra_data.lock().unwrap();
         if let Some(val) = extra.next_mr_enclave {
@@ -1166,6 +1585,10 @@ pub unsafe extern "C" fn ecall_generate_random(
     random: &mut [u8; ENCRYPTED_RANDOM_LENGTH as usize],
     _proof: &mut [u8; PROOF_LENGTH as usize],
 ) -> sgx_status_t {
+    if !enclave_utils::self_test::self_test_passed() {
+        return sgx_status_t::SGX_ERROR_INVALID_STATE;
+    }
+
     validate_const_ptr!(
         block_hash,
         block_hash_len as usize,
@@ -1239,6 +1662,10 @@ pub unsafe extern "C" fn ecall_submit_validator_set(
     val_set_len: u32,
     height: u64,
 ) -> sgx_status_t {
+    if !enclave_utils::self_test::self_test_passed() {
+        return sgx_status_t::SGX_ERROR_INVALID_STATE;
+    }
+
     validate_input_length!(val_set_len, "validator set length", MAX_VARIABLE_LENGTH);
     validate_const_ptr!(
         val_set,
@@ -1337,6 +1764,10 @@ pub unsafe extern "C" fn ecall_validate_random(
     block_hash_len: u32,
     _height: u64,
 ) -> sgx_status_t {
+    if !enclave_utils::self_test::self_test_passed() {
+        return sgx_status_t::SGX_ERROR_INVALID_STATE;
+    }
+
     validate_input_length!(random_len, "encrypted_random", ENCRYPTED_RANDOM_LENGTH);
     validate_input_length!(proof_len, "proof", PROOF_LENGTH);
     if block_hash_len != BLOCK_HASH_LENGTH {
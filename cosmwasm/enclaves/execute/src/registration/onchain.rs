@@ -4,74 +4,123 @@
 use log::*;
 use std::panic;
 
-use enclave_ffi_types::NodeAuthResult;
+use enclave_ffi_types::{
+    NodeAuthResult, AUTH_FLAG_CURRENT_SEED_ONLY, AUTH_FLAG_EPID_DEPRECATED,
+    SINGLE_ENCRYPTED_SEED_SIZE,
+};
 
 use crate::registration::attestation::verify_quote_sgx;
-use crate::registration::cert::verify_ra_report;
+use crate::registration::cert::{verify_ra_report, EnclaveIdentity};
 use crate::registration::seed_exchange::SeedType;
 
-use enclave_crypto::consts::OUTPUT_ENCRYPTED_SEED_SIZE;
 use enclave_crypto::PUBLIC_KEY_SIZE;
+use enclave_utils::input_limits::max_cert_length;
+use enclave_utils::output_buffer::{write_output, BufferTooSmall};
+use enclave_utils::verified_params::{
+    EPID_CUTOFF_HEIGHT, ISSUE_GENESIS_SEED, OPERATOR_BINDING_HEIGHT, POSSESSION_PROOF_HEIGHT,
+    SEED_ROTATION_HEIGHT,
+};
 use enclave_utils::{
     oom_handler::{self, get_then_clear_oom_happened},
-    validate_const_ptr, validate_mut_ptr,
+    validate_const_ptr, validate_input_length, validate_mut_ptr,
 };
 
-use sgx_types::sgx_ql_qv_result_t;
+use sgx_types::{sgx_ql_qv_result_t, sgx_report_body_t};
 
 use enclave_crypto::consts::SigningMethod;
+use enclave_wire::CombinedCert;
 
+use super::auth_cache::{cache_attestation, cached_attestation, VerifiedAttestation};
 use super::cert::verify_ra_cert;
+use super::issued_keys::check_duplicate;
+use super::key_scheme::{AttestedKeys, KeyScheme, REPORT_DATA_SIZE};
+use super::node_role::NodeRole;
+use super::operator::check_operator;
+use super::platform_census::record_platform;
+use super::possession::check_possession;
 use super::seed_exchange::encrypt_seed;
-use core::mem;
-use std::slice;
 
 #[cfg(feature = "light-client-validation")]
 use enclave_contract_engine::check_cert_in_current_block;
 
-#[cfg(feature = "light-client-validation")]
-use block_verifier::VERIFIED_BLOCK_MESSAGES;
+use crate::secure_time::verified_block_time_s;
+
+/// Splits a combined cert into its EPID certificate, DCAP quote and DCAP collateral. A cert that's
+/// shorter than its lengths say has none of them, and whatever comes after them is ignored.
+pub fn split_combined_cert(cert: &[u8]) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    match CombinedCert::parse(cert) {
+        Ok(cert) => (
+            cert.epid_cert.to_vec(),
+            cert.dcap_quote.to_vec(),
+            cert.dcap_collateral.to_vec(),
+        ),
+        Err(err) => {
+            trace!("malformed combined cert: {}", err);
+            (vec![], vec![], vec![])
+        }
+    }
+}
 
-#[cfg(feature = "light-client-validation")]
-fn get_current_block_time_s() -> i64 {
-    let verified_msgs = VERIFIED_BLOCK_MESSAGES.lock().unwrap();
-    let tm_ns = verified_msgs.time();
-    (tm_ns / 1000000000) as i64
+/// Where EPID attestations are on their way out, which governance schedules with
+/// `EPID_CUTOFF_HEIGHT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EpidStatus {
+    Accepted,
+    /// A cutoff is scheduled, registrations are flagged with `AUTH_FLAG_EPID_DEPRECATED`
+    Deprecated,
+    Retired,
 }
 
-#[cfg(not(feature = "light-client-validation"))]
-fn get_current_block_time_s() -> i64 {
-    return 0 as i64;
+/// No cutoff is scheduled
+const NO_EPID_CUTOFF: u64 = u64::MAX;
+/// Until the params are proven, EPID is retired from the first block
+const STRICT_EPID_CUTOFF: u64 = 1;
+
+fn epid_status(cutoff_height: u64, height: u64) -> EpidStatus {
+    if cutoff_height == NO_EPID_CUTOFF {
+        EpidStatus::Accepted
+    } else if height >= cutoff_height {
+        EpidStatus::Retired
+    } else {
+        EpidStatus::Deprecated
+    }
 }
 
-pub fn split_combined_cert(cert: *const u8, cert_len: u32) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
-    let mut vec_cert: Vec<u8> = Vec::new();
-    let mut vec_quote: Vec<u8> = Vec::new();
-    let mut vec_coll: Vec<u8> = Vec::new();
+/// The seed rotation isn't scheduled
+const NO_SEED_ROTATION: u64 = u64::MAX;
+/// Until the params are proven, the rotation is active from the first block
+const STRICT_SEED_ROTATION: u64 = 1;
 
-    let n0 = mem::size_of::<u32>() as u32 * 3;
+/// Whether new nodes receive the genesis seed along with the current one, which stops once the seed
+/// rotation is active. Governance can override it either way with `issue_genesis_seed`.
+fn issues_genesis_seed(rotation_height: u64, height: u64) -> bool {
+    height < rotation_height
+}
 
-    if cert_len >= n0 {
-        let p_cert = cert as *const u32;
-        let s0 = u32::from_le(unsafe { *p_cert });
-        let s1 = u32::from_le(unsafe { *(p_cert.offset(1)) });
-        let s2 = u32::from_le(unsafe { *(p_cert.offset(2)) });
+/// Possession proofs aren't required of every registration
+const NO_POSSESSION_PROOF: u64 = u64::MAX;
+/// Until the params are proven, possession is required from the first block
+const STRICT_POSSESSION_PROOF: u64 = 1;
 
-        let size_total = (n0 as u64) + (s0 as u64) + (s1 as u64) + (s2 as u64);
+/// Registration doesn't require an operator binding
+const NO_OPERATOR_BINDING: u64 = u64::MAX;
+/// Until the params are proven, the binding is required from the first block
+const STRICT_OPERATOR_BINDING: u64 = 1;
 
-        if size_total <= cert_len as u64 {
-            vec_cert =
-                unsafe { slice::from_raw_parts(cert.offset(n0 as isize), s0 as usize).to_vec() };
-            vec_quote = unsafe {
-                slice::from_raw_parts(cert.offset((n0 + s0) as isize), s1 as usize).to_vec()
-            };
-            vec_coll = unsafe {
-                slice::from_raw_parts(cert.offset((n0 + s0 + s1) as isize), s2 as usize).to_vec()
-            };
-        }
-    }
+/// The height of the block being executed, which the node can't choose
+#[cfg(feature = "light-client-validation")]
+fn verified_height() -> u64 {
+    block_verifier::VERIFIED_BLOCK_MESSAGES
+        .lock()
+        .unwrap()
+        .height()
+}
 
-    (vec_cert, vec_quote, vec_coll)
+// Without the light client there's no height the node can't choose, and the cutoff is never
+// reached
+#[cfg(not(feature = "light-client-validation"))]
+fn verified_height() -> u64 {
+    0
 }
 
 fn verify_attestation_epid(cert_slice: &[u8], pub_key: &mut [u8; 32]) -> NodeAuthResult {
@@ -96,39 +145,92 @@ fn verify_attestation_epid(cert_slice: &[u8], pub_key: &mut [u8; 32]) -> NodeAut
     NodeAuthResult::Success
 }
 
-fn verify_attestation_dcap(
+/// Verifies a DCAP quote with its collateral at `time_s`. Returns the report body of the quote,
+/// and the time at which the earliest of the collateral expires.
+fn verify_quote_at(
     vec_quote: &[u8],
     vec_coll: &[u8],
-    pub_key: &mut [u8; 32],
-) -> NodeAuthResult {
-    let tm_s = get_current_block_time_s();
-    trace!("Current block time: {}", tm_s);
-
-    // test self
-    let report_body = match verify_quote_sgx(vec_quote, vec_coll, tm_s) {
+    time_s: i64,
+) -> Result<(sgx_report_body_t, i64), NodeAuthResult> {
+    match verify_quote_sgx(vec_quote, vec_coll, time_s) {
         Ok(r) => {
             trace!("Remote quote verified ok");
             if r.1 != sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OK {
                 trace!("WARNING: {}", r.1);
             }
-            r.0
+            Ok((r.0, r.2))
         }
         Err(e) => {
             trace!("Remote quote verification failed: {}", e);
-            return NodeAuthResult::InvalidCert;
+            Err(NodeAuthResult::InvalidCert)
         }
-    };
+    }
+}
+
+/// Verifies a DCAP quote with its collateral at `time_s`, and that the enclave it quotes may
+/// register. Returns the report body of the quote, and the time at which the earliest of the
+/// collateral expires.
+pub(super) fn verify_dcap_quote(
+    vec_quote: &[u8],
+    vec_coll: &[u8],
+    time_s: i64,
+) -> Result<(sgx_report_body_t, i64), NodeAuthResult> {
+    let (report_body, collateral_expiration) = verify_quote_at(vec_quote, vec_coll, time_s)?;
 
-    let veritication_res = verify_ra_report(
-        &report_body.mr_signer.m,
-        &report_body.mr_enclave.m,
+    verify_ra_report(
+        &EnclaveIdentity::from_report_body(&report_body),
         Some(SigningMethod::MRSIGNER),
-    );
-    if NodeAuthResult::Success != veritication_res {
-        return veritication_res;
+    )?;
+
+    Ok((report_body, collateral_expiration))
+}
+
+fn verify_attestation_dcap(
+    vec_quote: &[u8],
+    vec_coll: &[u8],
+    report_data: &mut [u8; REPORT_DATA_SIZE],
+    collateral_expiration: &mut i64,
+    role: &mut NodeRole,
+) -> NodeAuthResult {
+    // Not enclave_now, since this has to be the same on every node
+    let tm_s = verified_block_time_s();
+    trace!("Current block time: {}", tm_s);
+
+    let verified = match cached_attestation(vec_quote, vec_coll, tm_s) {
+        Some(verified) => verified,
+        None => {
+            let (report_body, expiration) = match verify_quote_at(vec_quote, vec_coll, tm_s) {
+                Ok(verified) => verified,
+                Err(e) => return e,
+            };
+            record_platform(vec_quote, vec_coll, &report_body.cpu_svn.svn);
+
+            let verified = VerifiedAttestation {
+                identity: EnclaveIdentity::from_report_body(&report_body),
+                report_data: report_body.report_data.d,
+                collateral_expiration: expiration,
+                verified_at: tm_s,
+            };
+            cache_attestation(vec_quote, vec_coll, verified);
+            verified
+        }
+    };
+
+    // The policy may have changed since the quote was verified
+    if let Err(e) = verify_ra_report(&verified.identity, Some(SigningMethod::MRSIGNER)) {
+        return e;
     }
+    *collateral_expiration = verified.collateral_expiration;
 
-    pub_key.copy_from_slice(&report_body.report_data.d[..32]);
+    *role = match NodeRole::from_isv_prod_id(verified.identity.isv_prod_id) {
+        Some(role) => role,
+        None => {
+            warn!("Unknown node role: {}", verified.identity.isv_prod_id);
+            return NodeAuthResult::UnknownNodeRole;
+        }
+    };
+
+    *report_data = verified.report_data;
 
     NodeAuthResult::Success
 }
@@ -143,6 +245,49 @@ fn verify_attestation_dcap(
 /// The seed is encrypted with a key derived from the secret master key of the chain, and the public
 /// key of the requesting chain
 ///
+/// For DCAP attestations, `collateral_expiration` is set to the time (unix seconds) at which the
+/// earliest of the quote's collateral expires, so the node can flag registrations that rely on
+/// soon to expire collateral. It's left as 0 for EPID attestations.
+///
+/// EPID attestations are rejected with `AttestationMethodRetired` from the height governance set
+/// as `EPID_CUTOFF_HEIGHT`. Before it, they're accepted with `AUTH_FLAG_EPID_DEPRECATED` set in
+/// `auth_flags`, so the chain can warn the operator.
+///
+/// Once the seed rotation is active, from the verified param `seed_rotation_height`, new nodes only
+/// receive the current seed, and the genesis seed is all zeros, with `AUTH_FLAG_CURRENT_SEED_ONLY`
+/// set. Governance can override it either way with `issue_genesis_seed`. Service enclaves get the
+/// capability key of the current seed alone the same way.
+///
+/// `issued_height` is the height x/registration first issued seeds to the node's public key at,
+/// from its state, or 0 if it never did. A key that was already issued seeds is issued them again,
+/// or rejected with `AlreadyRegistered`, as governance set `duplicate_registration_policy`, see
+/// `issued_keys`.
+///
+/// From the verified param `possession_proof_height`, the cert must also carry a proof that the
+/// node holds its registration key, made for a block of this chain at most `MAX_CHALLENGE_AGE`
+/// blocks old, or the registration fails with `PossessionNotProven` before any seed is encrypted,
+/// see `possession`.
+///
+/// The cert may name the scheme of the node's keys, see `key_scheme`. Nodes of the
+/// `Ed25519X25519` scheme attest an Ed25519 key next to their X25519 key, which only DCAP quotes
+/// have room for, and sign their proofs with it. The seeds are encrypted to the X25519 key in
+/// every scheme. Schemes the enclave doesn't know fail with `UnsupportedKeyScheme`.
+///
+/// From the verified param `operator_binding_height`, seeds are only issued to nodes that the
+/// consensus key of a validator in the verified validator set bound itself to, and the rest fail
+/// with `OperatorNotBonded`, see `operator`.
+///
+/// A DCAP attestation is only verified once while its collateral is valid, so the same
+/// registration in CheckTx, simulation and DeliverTx doesn't verify its quote again, see
+/// `auth_cache`. The checks above and the encryption of the seeds run every time.
+///
+/// Service enclaves register the same way, with their role as the ISV product ID of their DCAP
+/// quote. They receive the capability keys of their role instead of the seeds, in the same
+/// layout. EPID attestations are always of validators.
+///
+/// The seeds are written to `seed`, and their length to `seed_len`. When they don't fit in
+/// `seed_capacity`, nothing is written and `BufferTooSmall` is returned, with the length they need.
+///
 /// This function happens on-chain, so any panic here might cause the chain to go boom
 ///
 /// # Safety
@@ -151,15 +296,29 @@ fn verify_attestation_dcap(
 pub unsafe extern "C" fn ecall_authenticate_new_node(
     cert: *const u8,
     cert_len: u32,
-    // seed structure 1 byte - length (96 or 48) | genesis seed bytes | current seed bytes (optional)
-    seed: &mut [u8; OUTPUT_ENCRYPTED_SEED_SIZE as usize],
+    issued_height: u64,
+    // genesis seed bytes | current seed bytes
+    seed: *mut u8,
+    seed_capacity: u32,
+    seed_len: &mut u32,
+    collateral_expiration: &mut i64,
+    auth_flags: &mut u32,
 ) -> NodeAuthResult {
+    *auth_flags = 0;
+
+    validate_input_length!(
+        cert_len as usize,
+        "cert",
+        max_cert_length(),
+        NodeAuthResult::InputTooLarge
+    );
+
     if let Err(_err) = oom_handler::register_oom_handler() {
         error!("Could not register OOM handler!");
         return NodeAuthResult::MemorySafetyAllocationError;
     }
 
-    validate_mut_ptr!(seed.as_mut_ptr(), seed.len(), NodeAuthResult::InvalidInput);
+    validate_mut_ptr!(seed, seed_capacity as usize, NodeAuthResult::InvalidInput);
     validate_const_ptr!(cert, cert_len as usize, NodeAuthResult::InvalidInput);
 
     let cert_slice = std::slice::from_raw_parts(cert, cert_len as usize);
@@ -169,41 +328,161 @@ pub unsafe extern "C" fn ecall_authenticate_new_node(
         return NodeAuthResult::SignatureInvalid;
     }
 
-    let mut target_public_key: [u8; 32] = [0u8; 32];
+    let outcome = authenticate_node(cert_slice, issued_height);
+
+    if let Err(_err) = oom_handler::restore_safety_buffer() {
+        error!("Could not restore OOM safety buffer!");
+        return NodeAuthResult::MemorySafetyAllocationError;
+    }
+
+    let auth = match outcome {
+        Ok(auth) => auth,
+        Err(e) => return e,
+    };
+
+    *collateral_expiration = auth.collateral_expiration;
+    *auth_flags = auth.auth_flags;
+
+    trace!(
+        "Done encrypting seed, got {:?}, {:?}",
+        auth.seed.len(),
+        auth.seed
+    );
+
+    match write_output(&auth.seed, seed, seed_capacity, seed_len) {
+        Ok(()) => NodeAuthResult::Success,
+        Err(BufferTooSmall { needed }) => {
+            debug!(
+                "encrypted seed ({}) is larger than the buffer ({})",
+                needed, seed_capacity
+            );
+            NodeAuthResult::BufferTooSmall
+        }
+    }
+}
+
+/// What `ecall_authenticate_new_node` returns for an authenticated node
+struct NodeAuth {
+    /// The genesis seed, then the current seed, encrypted to the node
+    seed: Vec<u8>,
+    collateral_expiration: i64,
+    auth_flags: u32,
+}
 
-    let (vec_cert, vec_quote, vec_coll) = split_combined_cert(cert, cert_len);
+/// Verifies the attestation of a combined cert, and encrypts the seeds to the key it attests
+fn authenticate_node(cert_slice: &[u8], issued_height: u64) -> Result<NodeAuth, NodeAuthResult> {
+    let mut report_data = [0u8; REPORT_DATA_SIZE];
+    let mut role = NodeRole::default();
+    let mut collateral_expiration: i64 = 0;
+    let mut auth_flags: u32 = 0;
+
+    let (vec_cert, vec_quote, vec_coll) = split_combined_cert(cert_slice);
+
+    // A cert that doesn't parse has no attestation, and is rejected below
+    let cert = CombinedCert::parse(cert_slice).unwrap_or_default();
+    let scheme = match cert.scheme() {
+        Some(scheme) => scheme,
+        None => {
+            warn!("Unknown key scheme: {:?}", cert.key_scheme);
+            return Err(NodeAuthResult::UnsupportedKeyScheme);
+        }
+    };
 
     if vec_quote.is_empty() || vec_coll.is_empty() {
         if vec_cert.is_empty() {
             warn!("No valid attestation method provided");
-            return NodeAuthResult::InvalidCert;
+            return Err(NodeAuthResult::InvalidCert);
         }
 
         trace!("EPID attestation");
 
-        let res = verify_attestation_epid(vec_cert.as_slice(), &mut target_public_key);
+        // An EPID report only has room for the X25519 key
+        if scheme != KeyScheme::X25519 {
+            warn!("EPID attestations can't attest the {:?} key scheme", scheme);
+            return Err(NodeAuthResult::UnsupportedKeyScheme);
+        }
+
+        match epid_status(
+            EPID_CUTOFF_HEIGHT.get_or(NO_EPID_CUTOFF, STRICT_EPID_CUTOFF),
+            verified_height(),
+        ) {
+            EpidStatus::Accepted => {}
+            EpidStatus::Deprecated => auth_flags |= AUTH_FLAG_EPID_DEPRECATED,
+            EpidStatus::Retired => {
+                warn!("EPID attestations were retired, rejecting the registration");
+                return Err(NodeAuthResult::AttestationMethodRetired);
+            }
+        }
+
+        let mut public_key = [0u8; PUBLIC_KEY_SIZE];
+        let res = verify_attestation_epid(vec_cert.as_slice(), &mut public_key);
         if NodeAuthResult::Success != res {
-            return res;
+            return Err(res);
         }
+        report_data[..PUBLIC_KEY_SIZE].copy_from_slice(&public_key);
     } else {
         trace!("DCAP attestation");
 
-        let res = verify_attestation_dcap(&vec_quote, &vec_coll, &mut target_public_key);
+        let res = verify_attestation_dcap(
+            &vec_quote,
+            &vec_coll,
+            &mut report_data,
+            &mut collateral_expiration,
+            &mut role,
+        );
         if NodeAuthResult::Success != res {
-            return res;
+            return Err(res);
         }
     }
 
+    let keys = AttestedKeys::from_report_data(scheme, &report_data)?;
+    let target_public_key = keys.exchange_key;
+    let height = verified_height();
+
+    // Until the params are proven, every registration must prove possession
+    check_possession(
+        POSSESSION_PROOF_HEIGHT.get_or(NO_POSSESSION_PROOF, STRICT_POSSESSION_PROOF),
+        &keys,
+        &cert,
+        height,
+    )?;
+    // Until the params are proven, only nodes bound to a validator are issued seeds
+    check_operator(
+        OPERATOR_BINDING_HEIGHT.get_or(NO_OPERATOR_BINDING, STRICT_OPERATOR_BINDING),
+        &keys.exchange_key,
+        &cert,
+        height,
+    )?;
+    check_duplicate(&keys, &cert, issued_height, height)?;
+
+    // Until the params are proven, only the current seed is issued
+    let issue_genesis = ISSUE_GENESIS_SEED.get_or(
+        issues_genesis_seed(
+            SEED_ROTATION_HEIGHT.get_or(NO_SEED_ROTATION, STRICT_SEED_ROTATION),
+            height,
+        ),
+        false,
+    );
+    if !issue_genesis {
+        auth_flags |= AUTH_FLAG_CURRENT_SEED_ONLY;
+    }
+
     let result = panic::catch_unwind(|| -> Result<Vec<u8>, NodeAuthResult> {
         trace!(
-            "ecall_get_encrypted_seed target_public_key key pk: {:?}",
-            &target_public_key.to_vec()
+            "ecall_get_encrypted_seed target_public_key key pk: {:?}, role: {:?}",
+            &target_public_key.to_vec(),
+            role
         );
 
-        let mut res: Vec<u8> = encrypt_seed(target_public_key, SeedType::Genesis, false)
-            .map_err(|_| NodeAuthResult::SeedEncryptionFailed)?;
+        // A seed that isn't issued is all zeros, so the layout stays the same
+        let mut res: Vec<u8> = if issue_genesis {
+            encrypt_seed(target_public_key, SeedType::Genesis, false, role)
+                .map_err(|_| NodeAuthResult::SeedEncryptionFailed)?
+        } else {
+            vec![0u8; SINGLE_ENCRYPTED_SEED_SIZE]
+        };
 
-        let res_current: Vec<u8> = encrypt_seed(target_public_key, SeedType::Current, false)
+        let res_current: Vec<u8> = encrypt_seed(target_public_key, SeedType::Current, false, role)
             .map_err(|_| NodeAuthResult::SeedEncryptionFailed)?;
 
         res.extend(&res_current);
@@ -211,29 +490,74 @@ pub unsafe extern "C" fn ecall_authenticate_new_node(
         Ok(res)
     });
 
-    if let Err(_err) = oom_handler::restore_safety_buffer() {
-        error!("Could not restore OOM safety buffer!");
-        return NodeAuthResult::MemorySafetyAllocationError;
+    match result {
+        Ok(Ok(seed)) => Ok(NodeAuth {
+            seed,
+            collateral_expiration,
+            auth_flags,
+        }),
+        Ok(Err(e)) => {
+            trace!("error encrypting seed {:?}", e);
+            Err(e)
+        }
+        Err(_err) => {
+            // There's no real need here to test if oom happened
+            get_then_clear_oom_happened();
+            warn!("Enclave call ecall_authenticate_new_node panic!");
+            Err(NodeAuthResult::Panic)
+        }
     }
+}
 
-    if let Ok(res) = result {
-        match res {
-            Ok(res) => {
-                trace!("Done encrypting seed, got {:?}, {:?}", res.len(), res);
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
 
-                seed.copy_from_slice(&res);
-                trace!("returning with seed: {:?}, {:?}", seed.len(), seed);
-                NodeAuthResult::Success
-            }
-            Err(e) => {
-                trace!("error encrypting seed {:?}", e);
-                e
-            }
-        }
-    } else {
-        // There's no real need here to test if oom happened
-        get_then_clear_oom_happened();
-        warn!("Enclave call ecall_authenticate_new_node panic!");
-        NodeAuthResult::Panic
+    pub fn test_epid_status() {
+        assert_eq!(epid_status(NO_EPID_CUTOFF, 0), EpidStatus::Accepted);
+        assert_eq!(epid_status(NO_EPID_CUTOFF, 1_000_000), EpidStatus::Accepted);
+
+        assert_eq!(epid_status(100, 0), EpidStatus::Deprecated);
+        assert_eq!(epid_status(100, 99), EpidStatus::Deprecated);
+        assert_eq!(epid_status(100, 100), EpidStatus::Retired);
+        assert_eq!(epid_status(100, 101), EpidStatus::Retired);
+
+        assert_eq!(epid_status(STRICT_EPID_CUTOFF, 1), EpidStatus::Retired);
+    }
+
+    pub fn test_issues_genesis_seed() {
+        assert!(issues_genesis_seed(NO_SEED_ROTATION, 0));
+        assert!(issues_genesis_seed(NO_SEED_ROTATION, 1_000_000));
+
+        assert!(issues_genesis_seed(100, 99));
+        assert!(!issues_genesis_seed(100, 100));
+        assert!(!issues_genesis_seed(100, 101));
+
+        assert!(!issues_genesis_seed(STRICT_SEED_ROTATION, 1));
+    }
+
+    pub fn test_split_combined_cert() {
+        use enclave_wire::CombinedCertBuilder;
+
+        let cert = CombinedCertBuilder::new()
+            .epid_cert(b"epid")
+            .dcap(b"quote", b"collateral")
+            .build();
+        assert_eq!(
+            split_combined_cert(&cert),
+            (b"epid".to_vec(), b"quote".to_vec(), b"collateral".to_vec())
+        );
+
+        let dcap_only = CombinedCertBuilder::new().dcap(b"quote", b"").build();
+        assert_eq!(
+            split_combined_cert(&dcap_only),
+            (vec![], b"quote".to_vec(), vec![])
+        );
+
+        // a truncated cert has no fields at all
+        assert_eq!(
+            split_combined_cert(&cert[..cert.len() - 1]),
+            (vec![], vec![], vec![])
+        );
     }
 }
@@ -0,0 +1,239 @@
+//! Binding of a registering node to the validator it serves.
+//!
+//! An attestation says nothing about whose node it is, so seeds are issued to any attested enclave.
+//! A node can bind itself to a validator: its cert carries the validator's consensus key and its
+//! signature over the node's X25519 key and the chain id. The consensus key is the only key of a
+//! validator the light client knows, so the enclave checks the signature, and that the key is in
+//! the validator set it verified, i.e. that the validator is bonded.
+//!
+//! The binding doesn't name the validator's operator address, which the enclave has no way to
+//! look up. The chain maps the consensus key to the operator itself.
+//!
+//! Governance restricts the seeds to bonded validators with `OPERATOR_BINDING_HEIGHT`. Bindings
+//! aren't checked before that height, or when it's not set, and are checked until the params are
+//! proven.
+
+use std::convert::TryFrom;
+use std::vec::Vec;
+
+use ed25519_consensus::{Signature, VerificationKey};
+use log::*;
+
+use enclave_crypto::{sha_256, PUBLIC_KEY_SIZE};
+use enclave_ffi_types::NodeAuthResult;
+use enclave_wire::{CombinedCert, OperatorBinding};
+
+#[cfg(feature = "light-client-validation")]
+use enclave_utils::KEY_MANAGER;
+
+const OPERATOR_DOMAIN: &[u8] = b"secret-registration-operator-v1";
+
+/// `"secret-registration-operator-v1" || X25519 key (32) || sha256(chain id)`, which the
+/// consensus key of the validator signs
+pub fn operator_message(public_key: &[u8; PUBLIC_KEY_SIZE], chain_id: &str) -> Vec<u8> {
+    let mut encoded = OPERATOR_DOMAIN.to_vec();
+    encoded.extend_from_slice(public_key);
+    encoded.extend_from_slice(&sha_256(chain_id.as_bytes()));
+    encoded
+}
+
+/// The address Tendermint gives a validator with `consensus_key`
+pub fn consensus_address(consensus_key: &[u8; 32]) -> [u8; 20] {
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&sha_256(consensus_key)[..20]);
+    address
+}
+
+/// Whether `binding` is signed by its consensus key for the node with `public_key` on `chain_id`.
+/// Returns the consensus key if it is.
+pub fn verify_binding(
+    public_key: &[u8; PUBLIC_KEY_SIZE],
+    binding: &OperatorBinding,
+    chain_id: &str,
+) -> Option<[u8; 32]> {
+    let mut consensus_key = [0u8; 32];
+    let mut signature = [0u8; 64];
+    if binding.consensus_key.len() != consensus_key.len()
+        || binding.signature.len() != signature.len()
+    {
+        debug!("The operator binding isn't signed by an Ed25519 key");
+        return None;
+    }
+    consensus_key.copy_from_slice(binding.consensus_key);
+    signature.copy_from_slice(binding.signature);
+
+    let message = operator_message(public_key, chain_id);
+    VerificationKey::try_from(consensus_key)
+        .and_then(|key| key.verify(&Signature::from(signature), &message))
+        .ok()?;
+
+    Some(consensus_key)
+}
+
+/// Whether the cert of the node with `public_key` binds it to a validator of `chain_id` that
+/// `is_bonded`, which gets the consensus address of the validator
+pub fn is_bound_to_bonded<F: FnOnce(&[u8; 20]) -> bool>(
+    public_key: &[u8; PUBLIC_KEY_SIZE],
+    cert: &CombinedCert,
+    chain_id: &str,
+    is_bonded: F,
+) -> bool {
+    let binding = match cert.operator_binding() {
+        Some(binding) => binding,
+        None => {
+            debug!("The cert has no operator binding");
+            return false;
+        }
+    };
+    let consensus_key = match verify_binding(public_key, &binding, chain_id) {
+        Some(consensus_key) => consensus_key,
+        None => {
+            debug!("The operator binding isn't signed for this node");
+            return false;
+        }
+    };
+
+    let address = consensus_address(&consensus_key);
+    if !is_bonded(&address) {
+        debug!(
+            "Validator {} isn't in the verified validator set",
+            hex::encode(address)
+        );
+        return false;
+    }
+    true
+}
+
+/// Checks the binding of `cert` against the chain, and the validator set the light client
+/// verified
+#[cfg(feature = "light-client-validation")]
+pub fn verify_bonded_operator(public_key: &[u8; PUBLIC_KEY_SIZE], cert: &CombinedCert) -> bool {
+    let chain_id = {
+        let block = block_verifier::VERIFIED_BLOCK_MESSAGES.lock().unwrap();
+        block.chain_id().to_string()
+    };
+    let validator_set = {
+        let extra = KEY_MANAGER.extra_data.lock().unwrap();
+        match extra.decode_validator_set() {
+            Some(validator_set) => validator_set,
+            None => return false,
+        }
+    };
+
+    is_bound_to_bonded(public_key, cert, &chain_id, |address| {
+        validator_set
+            .validators()
+            .iter()
+            .any(|validator| validator.address.as_bytes() == address && validator.power.value() > 0)
+    })
+}
+
+// Without the light client there's no verified validator set to check the validator against
+#[cfg(not(feature = "light-client-validation"))]
+pub fn verify_bonded_operator(_public_key: &[u8; PUBLIC_KEY_SIZE], _cert: &CombinedCert) -> bool {
+    false
+}
+
+/// Whether registrations at `height` must be bound to a bonded validator, from the height
+/// governance set
+fn requires_binding(required_from: u64, height: u64) -> bool {
+    height != 0 && height >= required_from
+}
+
+/// Fails a registration at `height` that must be bound to a bonded validator, and isn't
+pub fn check_operator(
+    required_from: u64,
+    public_key: &[u8; PUBLIC_KEY_SIZE],
+    cert: &CombinedCert,
+    height: u64,
+) -> Result<(), NodeAuthResult> {
+    if !requires_binding(required_from, height) || verify_bonded_operator(public_key, cert) {
+        return Ok(());
+    }
+
+    warn!(
+        "Key {} isn't bound to a bonded validator, rejecting the registration",
+        hex::encode(public_key)
+    );
+    Err(NodeAuthResult::OperatorNotBonded)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use ed25519_consensus::SigningKey;
+
+    use enclave_wire::CombinedCertBuilder;
+
+    const CHAIN_ID: &str = "secret-4";
+
+    fn bound_cert(consensus_key: &SigningKey, public_key: &[u8; PUBLIC_KEY_SIZE]) -> Vec<u8> {
+        let signature = consensus_key
+            .sign(&operator_message(public_key, CHAIN_ID))
+            .to_bytes();
+        let binding = OperatorBinding {
+            consensus_key: &consensus_key.verification_key().to_bytes(),
+            signature: &signature,
+        }
+        .encode();
+
+        CombinedCertBuilder::new()
+            .dcap(b"quote", b"collateral")
+            .operator(&binding)
+            .build()
+    }
+
+    pub fn test_operator_binding() {
+        let consensus_key = SigningKey::from([3; 32]);
+        let address = consensus_address(&consensus_key.verification_key().to_bytes());
+        let bonded = |a: &[u8; 20]| *a == address;
+        let public_key = [1; PUBLIC_KEY_SIZE];
+
+        let cert = bound_cert(&consensus_key, &public_key);
+        let cert = CombinedCert::parse(&cert).unwrap();
+        assert!(is_bound_to_bonded(&public_key, &cert, CHAIN_ID, bonded));
+
+        // not for another node, another chain, or a validator that isn't bonded
+        assert!(!is_bound_to_bonded(&[2; 32], &cert, CHAIN_ID, bonded));
+        assert!(!is_bound_to_bonded(&public_key, &cert, "pulsar-3", bonded));
+        assert!(!is_bound_to_bonded(&public_key, &cert, CHAIN_ID, |_| false));
+
+        // the signature is of the consensus key in the binding
+        let binding = cert.operator_binding().unwrap();
+        let other_key = SigningKey::from([4; 32]).verification_key().to_bytes();
+        let claimed = OperatorBinding {
+            consensus_key: &other_key,
+            ..binding
+        };
+        assert_eq!(verify_binding(&public_key, &claimed, CHAIN_ID), None);
+        assert_eq!(
+            verify_binding(&public_key, &binding, CHAIN_ID),
+            Some(consensus_key.verification_key().to_bytes())
+        );
+
+        // a cert without a binding, or a signature that isn't Ed25519
+        let bare = CombinedCertBuilder::new()
+            .dcap(b"quote", b"collateral")
+            .build();
+        let bare = CombinedCert::parse(&bare).unwrap();
+        assert!(!is_bound_to_bonded(&public_key, &bare, CHAIN_ID, bonded));
+        let short = OperatorBinding {
+            signature: &binding.signature[..32],
+            ..binding
+        };
+        assert_eq!(verify_binding(&public_key, &short, CHAIN_ID), None);
+    }
+
+    pub fn test_operator_binding_height() {
+        assert!(!requires_binding(u64::MAX, 1_000_000));
+        assert!(!requires_binding(100, 99));
+        assert!(requires_binding(100, 100));
+        // without a verified height nothing is required
+        assert!(!requires_binding(100, 0));
+        assert_eq!(
+            check_operator(u64::MAX, &[1; 32], &CombinedCert::default(), 100),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! Registration packages, for nodes that attest on a machine that's kept offline.
+//!
+//! `tx register auth` only needs the combined cert, but operators who attest on an air-gapped
+//! machine want to know the cert will register before they carry it to one that's online. A
+//! package is the cert, with the DCAP quote and its collateral, the key it attests and what the
+//! enclave read from its report, in one json file:
+//!
+//! ```text
+//! { "version": 1, "cert": base64, "public_key": hex, "metadata": { ... } }
+//! ```
+//!
+//! Any enclave can verify a package the way `ecall_authenticate_new_node` verifies the cert in it,
+//! at its own time instead of the block time. Verifying has no side effects: the platform isn't
+//! counted in the census, and the attestation isn't cached.
+
+use std::string::String;
+use std::vec::Vec;
+
+use log::*;
+use serde::{Deserialize, Serialize};
+use sgx_types::{sgx_report_body_t, sgx_status_t};
+
+use enclave_ffi_types::NodeAuthResult;
+use enclave_utils::input_limits::max_cert_length;
+use enclave_utils::KEY_MANAGER;
+use enclave_wire::{CombinedCert, CombinedCertBuilder};
+
+use crate::secure_time::enclave_now;
+
+use super::attestation::get_quote_ecdsa;
+use super::key_scheme::{registration_keys, AttestedKeys, KeyScheme};
+use super::node_role::NodeRole;
+use super::onchain::verify_dcap_quote;
+
+pub const PACKAGE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RegistrationPackage {
+    pub version: u32,
+    /// The combined cert to register with, in base64
+    pub cert: String,
+    /// The X25519 key the seeds are encrypted to, hex encoded
+    pub public_key: String,
+    pub metadata: PackageMetadata,
+}
+
+/// What the enclave that created the package read from its report. Everything but `created_at`
+/// and `enclave_version` is checked against the quote when the package is verified.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PackageMetadata {
+    /// The id of the `KeyScheme` of the cert
+    pub key_scheme: u8,
+    /// Hex encoded
+    pub mr_enclave: String,
+    /// Hex encoded
+    pub mr_signer: String,
+    pub isv_svn: u16,
+    /// The role of the node, see `NodeRole`
+    pub isv_prod_id: u16,
+    /// When the earliest of the collateral expires, in unix seconds
+    pub collateral_expiration: i64,
+    /// The trusted time of the enclave in unix seconds, 0 if it had none
+    pub created_at: i64,
+    pub enclave_version: String,
+}
+
+impl PackageMetadata {
+    fn attested(
+        scheme: KeyScheme,
+        report_body: &sgx_report_body_t,
+        collateral_expiration: i64,
+    ) -> Self {
+        Self {
+            key_scheme: scheme.id(),
+            mr_enclave: hex::encode(report_body.mr_enclave.m),
+            mr_signer: hex::encode(report_body.mr_signer.m),
+            isv_svn: report_body.isv_svn,
+            isv_prod_id: report_body.isv_prod_id,
+            collateral_expiration,
+            created_at: enclave_now().seconds,
+            enclave_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// A package of this node's registration key in `scheme`, as json. The quote is verified before
+/// it's packaged, so a package that this enclave wouldn't accept is never written.
+pub fn encoded_package(scheme: KeyScheme) -> Result<Vec<u8>, sgx_status_t> {
+    let registration_key = KEY_MANAGER.get_registration_key().map_err(|_| {
+        error!("Failed to unlock node key. Please make sure the file is accessible or reinitialize the node");
+        sgx_status_t::SGX_ERROR_INVALID_STATE
+    })?;
+    let keys = registration_keys(&registration_key, scheme);
+
+    let (quote, collateral) = get_quote_ecdsa(&keys.report_data())?;
+    let (report_body, collateral_expiration) =
+        verify_dcap_quote(&quote, &collateral, enclave_now().seconds).map_err(|e| {
+            error!("The quote of the package doesn't verify: {}", e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })?;
+
+    let cert = CombinedCertBuilder::new()
+        .dcap(&quote, &collateral)
+        .key_scheme(scheme)
+        .build();
+    let package = RegistrationPackage {
+        version: PACKAGE_VERSION,
+        cert: base64::encode(&cert),
+        public_key: hex::encode(keys.exchange_key),
+        metadata: PackageMetadata::attested(scheme, &report_body, collateral_expiration),
+    };
+
+    serde_json::to_vec(&package).map_err(|e| {
+        error!("Failed to encode the registration package: {}", e);
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })
+}
+
+/// The package in `encoded`, and the cert in it. Packages only carry DCAP attestations.
+fn decode_package(encoded: &[u8]) -> Result<(RegistrationPackage, Vec<u8>), NodeAuthResult> {
+    let package: RegistrationPackage = serde_json::from_slice(encoded).map_err(|e| {
+        warn!("Malformed registration package: {}", e);
+        NodeAuthResult::InvalidInput
+    })?;
+    if package.version != PACKAGE_VERSION {
+        warn!("Unknown registration package version: {}", package.version);
+        return Err(NodeAuthResult::InvalidInput);
+    }
+
+    let cert = base64::decode(&package.cert).map_err(|_| NodeAuthResult::InvalidCert)?;
+    if cert.len() > max_cert_length() {
+        return Err(NodeAuthResult::InputTooLarge);
+    }
+    match CombinedCert::parse(&cert) {
+        Ok(parsed) if parsed.has_dcap() => {}
+        _ => {
+            warn!("The registration package has no DCAP attestation");
+            return Err(NodeAuthResult::InvalidCert);
+        }
+    }
+
+    Ok((package, cert))
+}
+
+/// Checks that the key and metadata of `package` are the ones its quote attests
+fn check_attested(
+    package: &RegistrationPackage,
+    keys: &AttestedKeys,
+    report_body: &sgx_report_body_t,
+    collateral_expiration: i64,
+) -> Result<(), NodeAuthResult> {
+    if package.public_key != hex::encode(keys.exchange_key) {
+        warn!("The registration package names a key its quote doesn't attest");
+        return Err(NodeAuthResult::MalformedPublicKey);
+    }
+
+    let attested = PackageMetadata {
+        created_at: package.metadata.created_at,
+        enclave_version: package.metadata.enclave_version.clone(),
+        ..PackageMetadata::attested(keys.scheme, report_body, collateral_expiration)
+    };
+    if package.metadata != attested {
+        warn!(
+            "The metadata of the registration package doesn't match its quote: {:?}",
+            attested
+        );
+        return Err(NodeAuthResult::InvalidCert);
+    }
+
+    Ok(())
+}
+
+/// Verifies a package the way a registration of its cert would be, at the enclave's time. Returns
+/// when the earliest of its collateral expires.
+pub fn verify_package(encoded: &[u8]) -> Result<i64, NodeAuthResult> {
+    let (package, cert) = decode_package(encoded)?;
+    let cert = CombinedCert::parse(&cert).map_err(|_| NodeAuthResult::InvalidCert)?;
+    let scheme = match cert.scheme() {
+        Some(scheme) => scheme,
+        None => {
+            warn!("Unknown key scheme: {:?}", cert.key_scheme);
+            return Err(NodeAuthResult::UnsupportedKeyScheme);
+        }
+    };
+
+    let (report_body, collateral_expiration) =
+        verify_dcap_quote(cert.dcap_quote, cert.dcap_collateral, enclave_now().seconds)?;
+    if NodeRole::from_isv_prod_id(report_body.isv_prod_id).is_none() {
+        warn!("Unknown node role: {}", report_body.isv_prod_id);
+        return Err(NodeAuthResult::UnknownNodeRole);
+    }
+
+    let keys = AttestedKeys::from_report_data(scheme, &report_body.report_data.d)?;
+    check_attested(&package, &keys, &report_body, collateral_expiration)?;
+
+    Ok(collateral_expiration)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use enclave_crypto::KeyPair;
+
+    fn package(cert: &[u8]) -> Vec<u8> {
+        let package = RegistrationPackage {
+            version: PACKAGE_VERSION,
+            cert: base64::encode(cert),
+            public_key: hex::encode([1u8; 32]),
+            metadata: PackageMetadata::attested(
+                KeyScheme::X25519,
+                &sgx_report_body_t::default(),
+                0,
+            ),
+        };
+        serde_json::to_vec(&package).unwrap()
+    }
+
+    pub fn test_registration_package_decode() {
+        let dcap = CombinedCertBuilder::new()
+            .dcap(b"quote", b"collateral")
+            .build();
+        let (decoded, cert) = decode_package(&package(&dcap)).unwrap();
+        assert_eq!(cert, dcap);
+        assert_eq!(decoded.version, PACKAGE_VERSION);
+
+        assert_eq!(
+            decode_package(b"{}").unwrap_err(),
+            NodeAuthResult::InvalidInput
+        );
+        let mut future: RegistrationPackage = serde_json::from_slice(&package(&dcap)).unwrap();
+        future.version = PACKAGE_VERSION + 1;
+        assert_eq!(
+            decode_package(&serde_json::to_vec(&future).unwrap()).unwrap_err(),
+            NodeAuthResult::InvalidInput
+        );
+
+        // an EPID cert can't be packaged
+        let epid = CombinedCertBuilder::new().epid_cert(b"cert").build();
+        assert_eq!(
+            decode_package(&package(&epid)).unwrap_err(),
+            NodeAuthResult::InvalidCert
+        );
+    }
+
+    pub fn test_registration_package_attested() {
+        let registration_key = KeyPair::new().unwrap();
+        let keys = registration_keys(&registration_key, KeyScheme::Ed25519X25519);
+
+        let mut report_body = sgx_report_body_t::default();
+        report_body.mr_enclave.m = [2; 32];
+        report_body.mr_signer.m = [3; 32];
+        report_body.isv_svn = 4;
+        report_body.report_data.d = keys.report_data();
+
+        let package = RegistrationPackage {
+            version: PACKAGE_VERSION,
+            cert: String::new(),
+            public_key: hex::encode(keys.exchange_key),
+            metadata: PackageMetadata {
+                created_at: 1_700_000_000,
+                enclave_version: "1.0.0".to_string(),
+                ..PackageMetadata::attested(keys.scheme, &report_body, 1_800_000_000)
+            },
+        };
+        assert_eq!(
+            check_attested(&package, &keys, &report_body, 1_800_000_000),
+            Ok(())
+        );
+
+        // the metadata is the quote's
+        let mut other = report_body;
+        other.isv_svn = 5;
+        assert_eq!(
+            check_attested(&package, &keys, &other, 1_800_000_000),
+            Err(NodeAuthResult::InvalidCert)
+        );
+        assert_eq!(
+            check_attested(&package, &keys, &report_body, 1_700_000_000),
+            Err(NodeAuthResult::InvalidCert)
+        );
+        let legacy = registration_keys(&registration_key, KeyScheme::X25519);
+        assert_eq!(
+            check_attested(&package, &legacy, &report_body, 1_800_000_000),
+            Err(NodeAuthResult::InvalidCert)
+        );
+
+        // and so is the key
+        let renamed = RegistrationPackage {
+            public_key: hex::encode([9u8; 32]),
+            ..package
+        };
+        assert_eq!(
+            check_attested(&renamed, &keys, &report_body, 1_800_000_000),
+            Err(NodeAuthResult::MalformedPublicKey)
+        );
+    }
+}
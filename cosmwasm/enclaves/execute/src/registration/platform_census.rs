@@ -0,0 +1,514 @@
+//! A census of the SGX platforms that registered, for governance to weigh a stricter TCB policy.
+//!
+//! Before the network stops accepting a TCB level, or a platform affected by an advisory, it has
+//! to know how many registered nodes that would lock out. When a DCAP registration verifies, the
+//! enclave matches the platform's CPUSVN and PCESVN against the TCB info of the collateral, the
+//! same way the QvE does, and records the status, date and advisories of the level it's at.
+//!
+//! Platforms are identified by the ECDSA attestation key of their quote, hashed with a salt
+//! derived from the consensus seed. Quotes are public on chain, so an unsalted hash would link a
+//! census entry to the node that registered with it. Every node derives the same salt, so a
+//! platform that registers twice is counted once, and nodes that saw the same registrations have
+//! the same census.
+//!
+//! `ecall_platform_census` exports the census as counts only, the number of platforms at each
+//! TCB status, TCB date and advisory, signed like the other reports of the enclave.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::path;
+use std::string::String;
+use std::sync::SgxMutex;
+use std::untrusted::path::PathEx;
+use std::vec::Vec;
+
+use ed25519_consensus::SigningKey;
+use lazy_static::lazy_static;
+use log::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sgx_types::sgx_status_t;
+
+use enclave_crypto::consts::{make_sgx_secret_path, SEALED_FILE_PLATFORM_CENSUS};
+use enclave_crypto::{sha_256, Kdf, HASH_SIZE};
+use enclave_utils::storage::{seal, unseal};
+use enclave_utils::KEY_MANAGER;
+
+use super::collateral_cache::collateral_tcb_info;
+
+const CENSUS_DOMAIN: &[u8] = b"secret-platform-census-v1";
+const PLATFORM_ID_SALT_DOMAIN: &[u8] = b"platform_census_platform_id";
+const SIGNING_KEY_DOMAIN: &[u8] = b"platform_census_signing";
+
+/// Offset of the PCE SVN in the header of a v3 quote
+const QUOTE_PCE_SVN_OFFSET: usize = 10;
+/// Offset of the ECDSA attestation key in a v3 quote: after the header (48), the report body
+/// (384), the length of the signature data (4) and the signature of the quote (64)
+const QUOTE_ATTESTATION_KEY_OFFSET: usize = 48 + 384 + 4 + 64;
+const ATTESTATION_KEY_SIZE: usize = 64;
+
+/// Recorded for a platform that's at none of the TCB levels of its collateral
+const UNKNOWN_TCB_STATUS: &str = "Unknown";
+
+/// Cap on the platforms the census holds, new platforms aren't recorded beyond it
+const MAX_PLATFORMS: usize = 16_384;
+
+lazy_static! {
+    static ref PLATFORM_CENSUS_SEALING_PATH: String =
+        make_sgx_secret_path(SEALED_FILE_PLATFORM_CENSUS);
+    /// Unsealed the first time it's needed
+    static ref PLATFORM_CENSUS: SgxMutex<Option<PlatformCensus>> = SgxMutex::new(None);
+}
+
+/// The TCB level a platform was at when it last registered
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PlatformRecord {
+    /// As Intel names it, e.g. `UpToDate` or `SWHardeningNeeded`
+    pub tcb_status: String,
+    /// Empty if the level is unknown
+    pub tcb_date: String,
+    pub advisories: Vec<String>,
+}
+
+impl PlatformRecord {
+    fn unknown() -> Self {
+        Self {
+            tcb_status: UNKNOWN_TCB_STATUS.to_string(),
+            tcb_date: String::new(),
+            advisories: vec![],
+        }
+    }
+}
+
+/// The CPUSVN and PCESVN of a TCB level, from TCB info v3 or v2
+fn level_svns(tcb: &Value) -> Option<([u8; 16], u16)> {
+    let mut cpu_svn = [0u8; 16];
+    match tcb.get("sgxtcbcomponents") {
+        Some(components) => {
+            let components = components.as_array()?;
+            if components.len() != cpu_svn.len() {
+                return None;
+            }
+            for (svn, component) in cpu_svn.iter_mut().zip(components) {
+                *svn = u8::try_from(component.get("svn")?.as_u64()?).ok()?;
+            }
+        }
+        None => {
+            for (index, svn) in cpu_svn.iter_mut().enumerate() {
+                let field = format!("sgxtcbcomp{:02}svn", index + 1);
+                *svn = u8::try_from(tcb.get(field.as_str())?.as_u64()?).ok()?;
+            }
+        }
+    }
+    let pce_svn = u16::try_from(tcb.get("pcesvn")?.as_u64()?).ok()?;
+
+    Some((cpu_svn, pce_svn))
+}
+
+/// The first TCB level of the TCB info json that the platform is at. Intel sorts the levels from
+/// the newest, so that's the highest level the platform reached.
+fn platform_tcb_level(tcb_info: &[u8], cpu_svn: &[u8; 16], pce_svn: u16) -> Option<PlatformRecord> {
+    let json: Value = serde_json::from_slice(tcb_info).ok()?;
+    let levels = json.get("tcbInfo")?.get("tcbLevels")?.as_array()?;
+
+    for level in levels {
+        let (level_cpu_svn, level_pce_svn) = level_svns(level.get("tcb")?)?;
+        let below = pce_svn < level_pce_svn
+            || cpu_svn
+                .iter()
+                .zip(level_cpu_svn.iter())
+                .any(|(svn, level_svn)| svn < level_svn);
+        if below {
+            continue;
+        }
+
+        let advisories = match level.get("advisoryIDs").and_then(Value::as_array) {
+            Some(ids) => ids
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect(),
+            None => vec![],
+        };
+
+        return Some(PlatformRecord {
+            tcb_status: level.get("tcbStatus")?.as_str()?.to_string(),
+            tcb_date: level
+                .get("tcbDate")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            advisories,
+        });
+    }
+
+    None
+}
+
+/// The PCE SVN and the ECDSA attestation key of a v3 quote
+fn quote_platform(quote: &[u8]) -> Option<(u16, &[u8])> {
+    let pce_svn = quote.get(QUOTE_PCE_SVN_OFFSET..QUOTE_PCE_SVN_OFFSET + 2)?;
+    let attestation_key = quote
+        .get(QUOTE_ATTESTATION_KEY_OFFSET..QUOTE_ATTESTATION_KEY_OFFSET + ATTESTATION_KEY_SIZE)?;
+
+    Some((
+        u16::from_le_bytes([pce_svn[0], pce_svn[1]]),
+        attestation_key,
+    ))
+}
+
+fn platform_id(salt: &[u8], attestation_key: &[u8]) -> [u8; HASH_SIZE] {
+    let mut preimage = salt.to_vec();
+    preimage.extend_from_slice(attestation_key);
+    sha_256(&preimage)
+}
+
+/// How many platforms are at each TCB status, TCB date and advisory
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CensusAggregates {
+    pub platforms: u32,
+    pub tcb_status: BTreeMap<String, u32>,
+    pub tcb_date: BTreeMap<String, u32>,
+    pub advisories: BTreeMap<String, u32>,
+}
+
+impl CensusAggregates {
+    /// sha256 of `platforms (4)`, then for each of `tcb_status`, `tcb_date` and `advisories`,
+    /// `entries (4)` and every entry in order as `name length (2) || name || count (4)`, all big
+    /// endian
+    pub fn digest(&self) -> [u8; HASH_SIZE] {
+        let mut encoded = self.platforms.to_be_bytes().to_vec();
+        for counts in [&self.tcb_status, &self.tcb_date, &self.advisories].iter() {
+            encoded.extend_from_slice(&(counts.len() as u32).to_be_bytes());
+            for (name, count) in counts.iter() {
+                encoded.extend_from_slice(&(name.len() as u16).to_be_bytes());
+                encoded.extend_from_slice(name.as_bytes());
+                encoded.extend_from_slice(&count.to_be_bytes());
+            }
+        }
+        sha_256(&encoded)
+    }
+}
+
+/// The latest TCB level of every platform that registered
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlatformCensus {
+    /// By platform id, hex encoded
+    platforms: BTreeMap<String, PlatformRecord>,
+}
+
+impl PlatformCensus {
+    /// Records the level of a platform, replacing the one it registered with before. Returns
+    /// whether the census changed.
+    pub fn record(&mut self, platform_id: &[u8; HASH_SIZE], record: PlatformRecord) -> bool {
+        let id = hex::encode(platform_id);
+        if !self.platforms.contains_key(&id) && self.platforms.len() >= MAX_PLATFORMS {
+            warn!("The platform census is full, not recording a new platform");
+            return false;
+        }
+
+        self.platforms.insert(id, record.clone()) != Some(record)
+    }
+
+    pub fn aggregates(&self) -> CensusAggregates {
+        let mut aggregates = CensusAggregates {
+            platforms: self.platforms.len() as u32,
+            ..Default::default()
+        };
+
+        for record in self.platforms.values() {
+            *aggregates
+                .tcb_status
+                .entry(record.tcb_status.clone())
+                .or_insert(0) += 1;
+            if !record.tcb_date.is_empty() {
+                *aggregates
+                    .tcb_date
+                    .entry(record.tcb_date.clone())
+                    .or_insert(0) += 1;
+            }
+            for advisory in &record.advisories {
+                *aggregates.advisories.entry(advisory.clone()).or_insert(0) += 1;
+            }
+        }
+
+        aggregates
+    }
+
+    fn load() -> Self {
+        if !path::Path::new(PLATFORM_CENSUS_SEALING_PATH.as_str()).exists() {
+            return Self::default();
+        }
+
+        match unseal(&PLATFORM_CENSUS_SEALING_PATH)
+            .ok()
+            .and_then(|sealed| serde_json::from_slice(&sealed).ok())
+        {
+            Some(census) => census,
+            None => {
+                warn!("Failed to load the platform census, starting from an empty one");
+                Self::default()
+            }
+        }
+    }
+
+    fn seal(&self) {
+        let encoded = match serde_json::to_vec(self) {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                error!("Failed to encode the platform census: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = seal(&encoded, &PLATFORM_CENSUS_SEALING_PATH) {
+            error!("Error sealing the platform census: {}", e);
+        }
+    }
+}
+
+/// Records the platform of a DCAP registration, after its quote verified against `collateral`.
+/// `cpu_svn` is the CPUSVN of the quote's report body.
+pub fn record_platform(quote: &[u8], collateral: &[u8], cpu_svn: &[u8; 16]) {
+    let salt = match KEY_MANAGER.get_consensus_state_ikm() {
+        Ok(consensus_state_ikm) => consensus_state_ikm
+            .genesis
+            .derive_key_from_this(PLATFORM_ID_SALT_DOMAIN),
+        Err(_) => {
+            debug!("Not recording the platform of a registration without the consensus seed");
+            return;
+        }
+    };
+
+    let (pce_svn, attestation_key) = match quote_platform(quote) {
+        Some(platform) => platform,
+        None => {
+            warn!("Not recording the platform of a quote that's too short");
+            return;
+        }
+    };
+
+    let record = collateral_tcb_info(collateral)
+        .and_then(|(_, tcb_info)| platform_tcb_level(tcb_info, cpu_svn, pce_svn))
+        .unwrap_or_else(PlatformRecord::unknown);
+    trace!("Registered platform is at TCB level {:?}", record);
+
+    let mut cached = PLATFORM_CENSUS.lock().unwrap();
+    let census = cached.get_or_insert_with(PlatformCensus::load);
+    if census.record(&platform_id(salt.get(), attestation_key), record) {
+        census.seal();
+    }
+}
+
+/// The aggregates of the census, signed by the enclave. Keys and the signature are base64.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SignedCensus {
+    pub census: CensusAggregates,
+    pub digest: String,
+    /// The registration key of the node whose enclave exported the census
+    pub node_public_key: String,
+    /// The ed25519 key the census is signed with, the same on every node
+    pub public_key: String,
+    /// Over `sign_bytes`
+    pub signature: String,
+}
+
+/// `"secret-platform-census-v1" || node public key (32) || digest (32)`
+pub fn census_sign_bytes(node_public_key: &[u8; 32], digest: &[u8; HASH_SIZE]) -> Vec<u8> {
+    let mut sign_bytes = CENSUS_DOMAIN.to_vec();
+    sign_bytes.extend_from_slice(node_public_key);
+    sign_bytes.extend_from_slice(digest);
+    sign_bytes
+}
+
+impl SignedCensus {
+    pub fn sign(census: CensusAggregates, node_public_key: &[u8; 32], key: &SigningKey) -> Self {
+        let digest = census.digest();
+        let signature = key.sign(&census_sign_bytes(node_public_key, &digest));
+
+        Self {
+            census,
+            digest: base64::encode(digest),
+            node_public_key: base64::encode(node_public_key),
+            public_key: base64::encode(key.verification_key().to_bytes()),
+            signature: base64::encode(signature.to_bytes()),
+        }
+    }
+}
+
+fn signing_key() -> Result<SigningKey, sgx_status_t> {
+    let consensus_state_ikm = KEY_MANAGER.get_consensus_state_ikm().map_err(|_| {
+        warn!("can't sign the platform census without the consensus seed");
+        sgx_status_t::SGX_ERROR_INVALID_STATE
+    })?;
+
+    Ok(SigningKey::from(
+        *consensus_state_ikm
+            .genesis
+            .derive_key_from_this(SIGNING_KEY_DOMAIN)
+            .get(),
+    ))
+}
+
+/// The signed aggregates of the census, as json
+pub fn encoded_census() -> Result<Vec<u8>, sgx_status_t> {
+    let key = signing_key()?;
+    let node_public_key = KEY_MANAGER
+        .get_registration_key()
+        .map_err(|_| sgx_status_t::SGX_ERROR_INVALID_STATE)?
+        .get_pubkey();
+
+    let aggregates = {
+        let mut cached = PLATFORM_CENSUS.lock().unwrap();
+        cached.get_or_insert_with(PlatformCensus::load).aggregates()
+    };
+
+    serde_json::to_vec(&SignedCensus::sign(aggregates, &node_public_key, &key)).map_err(|e| {
+        error!("Failed to encode the platform census: {}", e);
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use ed25519_consensus::{Signature, VerificationKey};
+
+    const DCAP_QUOTE: &[u8] = include_bytes!("fixtures/attestation_dcap.quote");
+    const DCAP_COLLATERAL: &[u8] = include_bytes!("fixtures/attestation_dcap.collateral");
+
+    fn cpu_svn(first: &[u8]) -> [u8; 16] {
+        let mut svn = [0u8; 16];
+        svn[..first.len()].copy_from_slice(first);
+        svn
+    }
+
+    fn record(tcb_status: &str, tcb_date: &str, advisories: &[&str]) -> PlatformRecord {
+        PlatformRecord {
+            tcb_status: tcb_status.to_string(),
+            tcb_date: tcb_date.to_string(),
+            advisories: advisories.iter().map(|id| id.to_string()).collect(),
+        }
+    }
+
+    pub fn test_platform_census_tcb_level() {
+        // The fixture's TCB info is v2
+        let (_, tcb_info) = collateral_tcb_info(DCAP_COLLATERAL).unwrap();
+
+        let level = |svn: &[u8], pce_svn| platform_tcb_level(tcb_info, &cpu_svn(svn), pce_svn);
+        assert_eq!(
+            level(&[17, 17, 2, 4, 1, 128, 6], 10),
+            Some(record("SWHardeningNeeded", "2020-11-11T00:00:00Z", &[]))
+        );
+        assert_eq!(
+            level(&[17, 17, 2, 4, 1, 128, 0], 10).unwrap().tcb_status,
+            "ConfigurationAndSWHardeningNeeded"
+        );
+        // a newer CPUSVN with an older PCESVN is at the level of the PCESVN
+        assert_eq!(
+            level(&[17, 17, 2, 4, 1, 128, 6], 9).unwrap().tcb_date,
+            "2019-11-13T00:00:00Z"
+        );
+        assert_eq!(level(&[0; 16], 10), None);
+
+        let v3 = br#"{"tcbInfo":{"version":3,"tcbLevels":[{"tcb":{"sgxtcbcomponents":[
+            {"svn":3},{"svn":3},{"svn":0},{"svn":0},{"svn":0},{"svn":0},{"svn":0},{"svn":0},
+            {"svn":0},{"svn":0},{"svn":0},{"svn":0},{"svn":0},{"svn":0},{"svn":0},{"svn":0}],
+            "pcesvn":13},"tcbDate":"2024-03-13T00:00:00Z","tcbStatus":"SWHardeningNeeded",
+            "advisoryIDs":["INTEL-SA-00615","INTEL-SA-00828"]}]}}"#;
+        assert_eq!(
+            platform_tcb_level(v3, &cpu_svn(&[3, 4]), 13),
+            Some(record(
+                "SWHardeningNeeded",
+                "2024-03-13T00:00:00Z",
+                &["INTEL-SA-00615", "INTEL-SA-00828"]
+            ))
+        );
+        assert_eq!(platform_tcb_level(v3, &cpu_svn(&[3, 2]), 13), None);
+    }
+
+    pub fn test_platform_census_quote_platform() {
+        let (pce_svn, attestation_key) = quote_platform(DCAP_QUOTE).unwrap();
+        assert_eq!(pce_svn, 15);
+        assert_eq!(attestation_key.len(), ATTESTATION_KEY_SIZE);
+        assert!(quote_platform(&DCAP_QUOTE[..QUOTE_ATTESTATION_KEY_OFFSET]).is_none());
+
+        // the id only links registrations of the same platform under the same salt
+        let id = platform_id(&[1u8; 32], attestation_key);
+        assert_eq!(id, platform_id(&[1u8; 32], attestation_key));
+        assert_ne!(id, platform_id(&[2u8; 32], attestation_key));
+    }
+
+    pub fn test_platform_census_aggregates() {
+        let mut census = PlatformCensus::default();
+        let up_to_date = record("UpToDate", "2024-03-13T00:00:00Z", &[]);
+        let hardening = record(
+            "SWHardeningNeeded",
+            "2024-03-13T00:00:00Z",
+            &["INTEL-SA-00615"],
+        );
+
+        assert!(census.record(&[1u8; HASH_SIZE], hardening.clone()));
+        assert!(census.record(&[2u8; HASH_SIZE], hardening.clone()));
+        assert!(census.record(
+            &[3u8; HASH_SIZE],
+            record("OutOfDate", "2020-06-10T00:00:00Z", &[])
+        ));
+        assert!(census.record(&[4u8; HASH_SIZE], PlatformRecord::unknown()));
+        // registering again at the same level doesn't change the census
+        assert!(!census.record(&[2u8; HASH_SIZE], hardening));
+        // and a platform that updated replaces its level
+        assert!(census.record(&[1u8; HASH_SIZE], up_to_date));
+
+        let aggregates = census.aggregates();
+        assert_eq!(aggregates.platforms, 4);
+        assert_eq!(aggregates.tcb_status["UpToDate"], 1);
+        assert_eq!(aggregates.tcb_status["SWHardeningNeeded"], 1);
+        assert_eq!(aggregates.tcb_status[UNKNOWN_TCB_STATUS], 1);
+        assert_eq!(aggregates.tcb_date["2024-03-13T00:00:00Z"], 2);
+        assert_eq!(aggregates.tcb_date.len(), 2);
+        assert_eq!(aggregates.advisories["INTEL-SA-00615"], 1);
+
+        assert_ne!(aggregates.digest(), CensusAggregates::default().digest());
+        let mut one_less = aggregates.clone();
+        one_less.platforms -= 1;
+        assert_ne!(aggregates.digest(), one_less.digest());
+
+        let encoded = serde_json::to_vec(&census).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<PlatformCensus>(&encoded).unwrap(),
+            census
+        );
+    }
+
+    pub fn test_platform_census_signature() {
+        let key = SigningKey::from([7u8; 32]);
+        let node = [9u8; 32];
+        let mut tcb_status = BTreeMap::new();
+        tcb_status.insert("UpToDate".to_string(), 1);
+        let aggregates = CensusAggregates {
+            platforms: 1,
+            tcb_status,
+            ..Default::default()
+        };
+
+        let signed = SignedCensus::sign(aggregates.clone(), &node, &key);
+        assert_eq!(signed.digest, base64::encode(aggregates.digest()));
+
+        let public_key = VerificationKey::try_from(key.verification_key().to_bytes()).unwrap();
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(&base64::decode(&signed.signature).unwrap());
+        let signature = Signature::from(signature);
+
+        assert!(public_key
+            .verify(&signature, &census_sign_bytes(&node, &aggregates.digest()))
+            .is_ok());
+        // the signature binds the node
+        assert!(public_key
+            .verify(
+                &signature,
+                &census_sign_bytes(&[8u8; 32], &aggregates.digest())
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,535 @@
+//! Proof that a registering node holds the private half of the key it attests.
+//!
+//! An attestation shows that an enclave created the registration key, but a cert is public once
+//! it's on chain, and anyone can submit it again. A node proves it holds the key with a MAC over
+//! its public key, its attestation, the chain id and the app hash of a recent block, keyed by the
+//! Diffie-Hellman of its registration key and the node exchange key of the network. That's the
+//! secret its seeds are encrypted with, so only the node and the enclaves that authenticate
+//! registrations can compute it, and the block makes it a response to a challenge nobody could
+//! know before the block was produced.
+//!
+//! The proof carries the header of the block with its commit. The enclave verifies them against
+//! the validator set its light client verified the current block with, so every node accepts or
+//! rejects the proof the same way, and a proof whose block an earlier validator set signed is
+//! rejected.
+//!
+//! The registration key is an X25519 key, which can't sign, hence a MAC rather than a signature.
+//! Nodes whose cert names the `Ed25519X25519` key scheme sign the same challenge with their
+//! Ed25519 key instead, which anyone can verify, see `key_scheme`.
+//!
+//! Governance requires the proof from every registration with `POSSESSION_PROOF_HEIGHT`, and the
+//! duplicate registration policy may require it from keys that were issued seeds before, see
+//! `issued_keys`.
+
+use std::convert::TryFrom;
+use std::string::String;
+use std::vec::Vec;
+
+use ed25519_consensus::{Signature, VerificationKey};
+use log::*;
+
+use enclave_crypto::{hkdf_sha_256, sha_256, Hmac, KeyPair, HMAC_SIGNATURE_SIZE, PUBLIC_KEY_SIZE};
+use enclave_ffi_types::NodeAuthResult;
+use enclave_wire::{CombinedCert, Endian, Headed, Reader};
+
+use super::key_scheme::{registration_signing_key, AttestedKeys, KeyScheme};
+
+#[cfg(feature = "light-client-validation")]
+use enclave_utils::KEY_MANAGER;
+
+const POSSESSION_DOMAIN: &[u8] = b"secret-registration-possession-v2";
+
+/// How many blocks before the current one the block a proof refers to may be
+pub const MAX_CHALLENGE_AGE: u64 = 100;
+
+const ED25519_SIGNATURE_SIZE: usize = 64;
+
+/// `len(header) || len(commit) || header || commit`, the protobuf encoded header of the block a
+/// proof refers to and its commit, after the MAC or signature of the proof
+pub const PROOF_BLOCK: Headed<2> = Headed(Endian::Little);
+
+/// The MAC, or the Ed25519 signature in the `Ed25519X25519` key scheme
+fn signature_size(scheme: KeyScheme) -> usize {
+    match scheme {
+        KeyScheme::X25519 => HMAC_SIGNATURE_SIZE,
+        KeyScheme::Ed25519X25519 => ED25519_SIGNATURE_SIZE,
+    }
+}
+
+/// The block a proof refers to, as its header has it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Challenge<'a> {
+    pub chain_id: &'a str,
+    pub height: u64,
+    /// The app hash in the header of the block at `height`
+    pub app_hash: [u8; 32],
+}
+
+/// `"secret-registration-possession-v2" || X25519 key (32) || sha256(attestation) ||
+/// sha256(chain id) || height (8, little endian) || app hash (32)`, where the attestation is the
+/// fields of the cert without the proof
+pub fn possession_challenge(
+    public_key: &[u8; PUBLIC_KEY_SIZE],
+    cert: &CombinedCert,
+    challenge: &Challenge,
+) -> Vec<u8> {
+    let mut encoded = POSSESSION_DOMAIN.to_vec();
+    encoded.extend_from_slice(public_key);
+    encoded.extend_from_slice(&sha_256(&cert.attestation()));
+    encoded.extend_from_slice(&sha_256(challenge.chain_id.as_bytes()));
+    encoded.extend_from_slice(&challenge.height.to_le_bytes());
+    encoded.extend_from_slice(&challenge.app_hash);
+    encoded
+}
+
+/// The protobuf encoded header of the block a proof refers to, and its commit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofBlock<'a> {
+    pub header: &'a [u8],
+    pub commit: &'a [u8],
+}
+
+/// A block's header, once the light client verified it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedHeader {
+    pub chain_id: String,
+    pub height: u64,
+    pub app_hash: Vec<u8>,
+}
+
+/// `height (8, little endian) || MAC or signature || PROOF_BLOCK`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Proof<'a> {
+    height: u64,
+    signature: &'a [u8],
+    block: ProofBlock<'a>,
+}
+
+impl<'a> Proof<'a> {
+    fn parse(proof: &'a [u8], scheme: KeyScheme) -> Option<Self> {
+        let mut reader = Reader::new(proof);
+        let height = u64::from_le_bytes(reader.array().ok()?);
+        let signature = reader.take(signature_size(scheme)).ok()?;
+        let [header, commit] = PROOF_BLOCK.read(&mut reader).ok()?;
+        reader.finish().ok()?;
+
+        Some(Self {
+            height,
+            signature,
+            block: ProofBlock { header, commit },
+        })
+    }
+}
+
+fn possession_mac(shared_secret: &[u8], challenge: &[u8]) -> [u8; HMAC_SIGNATURE_SIZE] {
+    hkdf_sha_256(shared_secret, &[POSSESSION_DOMAIN]).sign_sha_256(challenge)
+}
+
+/// The proof a node appends to its cert, in the key scheme it attested with. `master_pk` is the
+/// node exchange key of the network, as the node's seed config has it, and `challenge` is what
+/// the header of `block` says.
+pub fn prove_possession(
+    registration_key: &KeyPair,
+    scheme: KeyScheme,
+    master_pk: &[u8; PUBLIC_KEY_SIZE],
+    cert: &CombinedCert,
+    challenge: &Challenge,
+    block: &ProofBlock,
+) -> Vec<u8> {
+    let encoded = possession_challenge(&registration_key.get_pubkey(), cert, challenge);
+
+    let mut proof = challenge.height.to_le_bytes().to_vec();
+    match scheme {
+        KeyScheme::X25519 => proof.extend_from_slice(&possession_mac(
+            &registration_key.diffie_hellman(master_pk),
+            &encoded,
+        )),
+        KeyScheme::Ed25519X25519 => proof.extend_from_slice(
+            &registration_signing_key(registration_key)
+                .sign(&encoded)
+                .to_bytes(),
+        ),
+    }
+    proof.extend_from_slice(&PROOF_BLOCK.encode([block.header, block.commit]));
+    proof
+}
+
+/// The height of the block the proof of `cert` refers to, if it has one in the cert's key scheme
+pub fn proof_height(cert: &CombinedCert) -> Option<u64> {
+    Proof::parse(cert.proof, cert.scheme()?).map(|proof| proof.height)
+}
+
+/// Whether a proof that refers to the block at `proof_height` is recent enough at `height`
+fn is_recent(proof_height: u64, height: u64) -> bool {
+    proof_height != 0 && proof_height <= height && height - proof_height <= MAX_CHALLENGE_AGE
+}
+
+/// Whether the proof of `cert` shows its submitter holds the private keys of `keys`, and was made
+/// for a recent block of `chain_id`. `verify_header` verifies the header and commit the proof
+/// carries.
+pub fn verify_possession<F: FnOnce(&ProofBlock) -> Option<VerifiedHeader>>(
+    exchange_key: &KeyPair,
+    keys: &AttestedKeys,
+    cert: &CombinedCert,
+    chain_id: &str,
+    height: u64,
+    verify_header: F,
+) -> bool {
+    // The proof parses in the scheme of `keys` only if the cert names that scheme
+    let proof = match Proof::parse(cert.proof, keys.scheme)
+        .filter(|_| cert.scheme() == Some(keys.scheme))
+    {
+        Some(proof) => proof,
+        None => {
+            debug!("The cert has no proof of possession");
+            return false;
+        }
+    };
+    if !is_recent(proof.height, height) {
+        debug!(
+            "The proof of possession refers to height {}, at height {}",
+            proof.height, height
+        );
+        return false;
+    }
+    let header = match verify_header(&proof.block) {
+        Some(header) => header,
+        None => {
+            debug!(
+                "The header the proof of possession carries for height {} doesn't verify",
+                proof.height
+            );
+            return false;
+        }
+    };
+    let app_hash = match <[u8; 32]>::try_from(header.app_hash.as_slice()) {
+        Ok(app_hash) if header.chain_id == chain_id && header.height == proof.height => app_hash,
+        _ => {
+            debug!(
+                "The proof of possession carries the header of height {} of {}",
+                header.height, header.chain_id
+            );
+            return false;
+        }
+    };
+
+    let challenge = Challenge {
+        chain_id,
+        height: proof.height,
+        app_hash,
+    };
+    let encoded = possession_challenge(&keys.exchange_key, cert, &challenge);
+    let signature = proof.signature;
+    match (keys.scheme, keys.signing_key) {
+        (KeyScheme::X25519, _) => {
+            let expected =
+                possession_mac(&exchange_key.diffie_hellman(&keys.exchange_key), &encoded);
+            constant_time_eq(&expected, signature)
+        }
+        (KeyScheme::Ed25519X25519, Some(signing_key)) => {
+            let mut bytes = [0u8; ED25519_SIGNATURE_SIZE];
+            bytes.copy_from_slice(signature);
+            VerificationKey::try_from(signing_key)
+                .and_then(|key| key.verify(&Signature::from(bytes), &encoded))
+                .is_ok()
+        }
+        (KeyScheme::Ed25519X25519, None) => false,
+    }
+}
+
+/// Verifies the proof of `cert` against the chain and the validator set the light client verified
+/// the current block with
+#[cfg(feature = "light-client-validation")]
+pub fn verify_recent_possession(keys: &AttestedKeys, cert: &CombinedCert) -> bool {
+    let (chain_id, height) = {
+        let block = block_verifier::VERIFIED_BLOCK_MESSAGES.lock().unwrap();
+        (block.chain_id().to_string(), block.height())
+    };
+    let exchange_key = match KEY_MANAGER.seed_exchange_key() {
+        Ok(exchange_key) => exchange_key,
+        Err(_) => return false,
+    };
+
+    verify_possession(
+        &exchange_key.current,
+        keys,
+        cert,
+        &chain_id,
+        height,
+        |block| {
+            block_verifier::past_headers::verify_past_header(block.header, block.commit).map(
+                |header| VerifiedHeader {
+                    chain_id: header.chain_id,
+                    height: header.height,
+                    app_hash: header.app_hash,
+                },
+            )
+        },
+    )
+}
+
+// Without the light client there's no validator set to verify the block of a proof with
+#[cfg(not(feature = "light-client-validation"))]
+pub fn verify_recent_possession(_keys: &AttestedKeys, _cert: &CombinedCert) -> bool {
+    false
+}
+
+/// Whether registrations at `height` must prove possession, from the height governance set
+fn requires_proof(required_from: u64, height: u64) -> bool {
+    height != 0 && height >= required_from
+}
+
+/// Fails a registration at `height` that must prove possession of `keys`, and doesn't
+pub fn check_possession(
+    required_from: u64,
+    keys: &AttestedKeys,
+    cert: &CombinedCert,
+    height: u64,
+) -> Result<(), NodeAuthResult> {
+    if !requires_proof(required_from, height) || verify_recent_possession(keys, cert) {
+        return Ok(());
+    }
+
+    warn!(
+        "Key {} didn't prove possession of its private key, rejecting the registration",
+        hex::encode(keys.exchange_key)
+    );
+    Err(NodeAuthResult::PossessionNotProven)
+}
+
+/// Compares two byte strings in time that only depends on their lengths
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use enclave_wire::CombinedCertBuilder;
+
+    use crate::registration::key_scheme::registration_keys;
+
+    const CHAIN_ID: &str = "secret-4";
+
+    const CHALLENGE: Challenge = Challenge {
+        chain_id: CHAIN_ID,
+        height: 100,
+        app_hash: [7; 32],
+    };
+
+    const BLOCK: ProofBlock = ProofBlock {
+        header: b"header",
+        commit: b"commit",
+    };
+
+    fn header() -> VerifiedHeader {
+        VerifiedHeader {
+            chain_id: CHAIN_ID.to_string(),
+            height: 100,
+            app_hash: vec![7; 32],
+        }
+    }
+
+    /// A light client that only signed `BLOCK`
+    fn verify_header(block: &ProofBlock) -> Option<VerifiedHeader> {
+        if *block == BLOCK {
+            Some(header())
+        } else {
+            None
+        }
+    }
+
+    /// Checks the proof of a node registering in `scheme`
+    fn check_proof(scheme: KeyScheme) {
+        let node_key = KeyPair::new().unwrap();
+        let exchange_key = KeyPair::new().unwrap();
+        let keys = registration_keys(&node_key, scheme);
+
+        let attestation = CombinedCertBuilder::new()
+            .dcap(b"quote", b"collateral")
+            .key_scheme(scheme);
+        let proof = prove_possession(
+            &node_key,
+            scheme,
+            &exchange_key.get_pubkey(),
+            &CombinedCert::parse(&attestation.build()).unwrap(),
+            &CHALLENGE,
+            &BLOCK,
+        );
+        assert_eq!(
+            proof.len(),
+            8 + signature_size(scheme) + PROOF_BLOCK.encode([BLOCK.header, BLOCK.commit]).len()
+        );
+        let cert = attestation.proof(&proof).build();
+        let cert = CombinedCert::parse(&cert).unwrap();
+        assert_eq!(proof_height(&cert), Some(100));
+        assert!(verify_possession(
+            &exchange_key,
+            &keys,
+            &cert,
+            CHAIN_ID,
+            110,
+            verify_header
+        ));
+
+        // not of another key, another attestation, or another chain
+        let other_keys = registration_keys(&KeyPair::new().unwrap(), scheme);
+        assert!(!verify_possession(
+            &exchange_key,
+            &other_keys,
+            &cert,
+            CHAIN_ID,
+            110,
+            verify_header
+        ));
+        let moved = CombinedCertBuilder::new()
+            .dcap(b"other quote", b"collateral")
+            .key_scheme(scheme)
+            .proof(&proof)
+            .build();
+        assert!(!verify_possession(
+            &exchange_key,
+            &keys,
+            &CombinedCert::parse(&moved).unwrap(),
+            CHAIN_ID,
+            110,
+            verify_header
+        ));
+        assert!(!verify_possession(
+            &exchange_key,
+            &keys,
+            &cert,
+            "pulsar-3",
+            110,
+            verify_header
+        ));
+
+        // the header must verify, and be the block the proof was made for
+        assert!(!verify_possession(
+            &exchange_key,
+            &keys,
+            &cert,
+            CHAIN_ID,
+            110,
+            |_| Some(VerifiedHeader {
+                app_hash: vec![8; 32],
+                ..header()
+            })
+        ));
+        assert!(!verify_possession(
+            &exchange_key,
+            &keys,
+            &cert,
+            CHAIN_ID,
+            110,
+            |_| Some(VerifiedHeader {
+                height: 101,
+                ..header()
+            })
+        ));
+        assert!(!verify_possession(
+            &exchange_key,
+            &keys,
+            &cert,
+            CHAIN_ID,
+            110,
+            |_| Some(VerifiedHeader {
+                chain_id: "pulsar-3".to_string(),
+                ..header()
+            })
+        ));
+        assert!(!verify_possession(
+            &exchange_key,
+            &keys,
+            &cert,
+            CHAIN_ID,
+            110,
+            |_| None
+        ));
+
+        // a cert without a proof proves nothing
+        let bare = attestation.build();
+        let bare = CombinedCert::parse(&bare).unwrap();
+        assert_eq!(proof_height(&bare), None);
+        assert!(!verify_possession(
+            &exchange_key,
+            &keys,
+            &bare,
+            CHAIN_ID,
+            110,
+            verify_header
+        ));
+    }
+
+    pub fn test_possession_proof() {
+        check_proof(KeyScheme::X25519);
+    }
+
+    pub fn test_possession_proof_ed25519() {
+        check_proof(KeyScheme::Ed25519X25519);
+
+        // the signature verifies without the node exchange key, and only in its own scheme
+        let node_key = KeyPair::new().unwrap();
+        let keys = registration_keys(&node_key, KeyScheme::Ed25519X25519);
+        let attestation = CombinedCertBuilder::new()
+            .dcap(b"quote", b"collateral")
+            .key_scheme(KeyScheme::Ed25519X25519);
+        let proof = prove_possession(
+            &node_key,
+            KeyScheme::Ed25519X25519,
+            &[0; 32],
+            &CombinedCert::parse(&attestation.build()).unwrap(),
+            &CHALLENGE,
+            &BLOCK,
+        );
+        let cert = attestation.proof(&proof).build();
+        let cert = CombinedCert::parse(&cert).unwrap();
+        let unrelated = KeyPair::new().unwrap();
+        assert!(verify_possession(
+            &unrelated,
+            &keys,
+            &cert,
+            CHAIN_ID,
+            110,
+            verify_header
+        ));
+
+        let legacy = registration_keys(&node_key, KeyScheme::X25519);
+        assert!(!verify_possession(
+            &unrelated,
+            &legacy,
+            &cert,
+            CHAIN_ID,
+            110,
+            verify_header
+        ));
+        let stripped = CombinedCert {
+            key_scheme: &[],
+            ..cert
+        };
+        assert!(!verify_possession(
+            &unrelated,
+            &keys,
+            &stripped,
+            CHAIN_ID,
+            110,
+            verify_header
+        ));
+    }
+
+    pub fn test_possession_challenge_age() {
+        assert!(is_recent(100, 100));
+        assert!(is_recent(100, 100 + MAX_CHALLENGE_AGE));
+        assert!(!is_recent(100, 101 + MAX_CHALLENGE_AGE));
+        // a block that wasn't produced yet, or no block at all
+        assert!(!is_recent(101, 100));
+        assert!(!is_recent(0, 10));
+
+        assert!(!requires_proof(u64::MAX, 1_000));
+        assert!(!requires_proof(100, 99));
+        assert!(requires_proof(100, 100));
+        // without a verified height nothing can be proven
+        assert!(!requires_proof(0, 0));
+    }
+}
@@ -1,7 +1,6 @@
-use crate::registration::{
-    cert::{ocall_get_update_info, verify_quote_status},
-    report::AttestationReport,
-};
+use crate::registration::{cert::verify_quote_status, report::AttestationReport};
+
+use enclave_crypto::ocalls::ocall_get_update_info;
 
 use enclave_ffi_types::NodeAuthResult;
 use log::{error, warn};
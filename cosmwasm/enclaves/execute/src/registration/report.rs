@@ -18,6 +18,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 use uuid::Uuid;
 
+use enclave_crypto::x509::SUPPORTED_SIG_ALGS;
 use enclave_ffi_types::NodeAuthResult;
 
 use super::cert::{get_ias_auth_config, get_netscape_comment};
@@ -89,21 +90,6 @@ where
     deserializer.deserialize_str(Base64Visitor)
 }
 
-type SignatureAlgorithms = &'static [&'static webpki::SignatureAlgorithm];
-static SUPPORTED_SIG_ALGS: SignatureAlgorithms = &[
-    &webpki::ECDSA_P256_SHA256,
-    &webpki::ECDSA_P256_SHA384,
-    &webpki::ECDSA_P384_SHA256,
-    &webpki::ECDSA_P384_SHA384,
-    &webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY,
-    &webpki::RSA_PSS_2048_8192_SHA384_LEGACY_KEY,
-    &webpki::RSA_PSS_2048_8192_SHA512_LEGACY_KEY,
-    &webpki::RSA_PKCS1_2048_8192_SHA256,
-    &webpki::RSA_PKCS1_2048_8192_SHA384,
-    &webpki::RSA_PKCS1_2048_8192_SHA512,
-    &webpki::RSA_PKCS1_3072_8192_SHA384,
-];
-
 /// A report generated by an enclave that contains measurement, identity and
 /// other data related to enclave.
 ///
@@ -962,6 +948,10 @@ pub mod tests {
 
         let res = verify_quote_sgx(&vec_quote, &vec_coll, time_s);
         assert!(res.is_ok());
+
+        // collateral that verified at time_s can't have expired by then
+        let (_, _, collateral_expiration) = res.unwrap();
+        assert!(collateral_expiration > time_s);
     }
 
     pub fn test_attestation_dcap_temper() {
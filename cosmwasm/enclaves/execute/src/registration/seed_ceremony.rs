@@ -0,0 +1,610 @@
+//! Generation of the genesis seed in a ceremony between several enclaves, so that no single
+//! party, or single machine's RNG, knows or biases it.
+//!
+//! Every participant runs the same enclave, and the operators pass the files between them:
+//! * op 9 (commit): the enclave draws a random share and a ceremony key, seals them, and attests
+//!   to the key and a commitment to the share in a DCAP quote
+//! * op 10 (reveal): given the commitments of all the participants, the enclave verifies their
+//!   quotes, fixes the transcript of the ceremony, and encrypts its share to every other
+//!   participant's ceremony key
+//! * op 11 (combine): given the reveals of the other participants, the enclave decrypts their
+//!   shares, checks them against their commitments, and hashes all the shares into the seeds
+//!
+//! `ecall_init_bootstrap` then uses the combined seeds instead of drawing new ones. Shares are
+//! fixed by their commitments before any of them is revealed, and they are only ever revealed to
+//! the attested enclaves of the transcript, so every share bar one can be known to an attacker
+//! and the seeds are still random and secret.
+//!
+use core::convert::TryInto;
+use enclave_crypto::consts::{
+    make_sgx_secret_path, FILE_CEREMONY_COMMIT, FILE_CEREMONY_COMMITS, FILE_CEREMONY_REVEAL,
+    FILE_CEREMONY_REVEALS, FILE_CEREMONY_TRANSCRIPT, SEALED_FILE_CEREMONY_SEED,
+    SEALED_FILE_CEREMONY_STATE, SELF_REPORT_BODY,
+};
+use enclave_crypto::ed25519::Ed25519PrivateKey;
+use enclave_crypto::{rand_slice, sha_256, AESKey, KeyPair, SIVEncryptable, Seed};
+use enclave_utils::storage::{seal, unseal};
+use enclave_wire::{CombinedCertBuilder, Endian, Framed};
+use log::*;
+use serde::{Deserialize, Serialize};
+use sgx_types::{sgx_report_body_t, sgx_status_t, SgxResult};
+use std::fs::File;
+use std::io::prelude::*;
+use std::path;
+use std::untrusted::path::PathEx;
+
+use super::attestation::{get_quote_ecdsa, verify_quote_sgx};
+use super::onchain::split_combined_cert;
+use crate::secure_time::enclave_now;
+
+const COMMITMENT_DOMAIN: &[u8] = b"secret seed ceremony commitment";
+const TRANSCRIPT_DOMAIN: &[u8] = b"secret seed ceremony transcript";
+const GENESIS_SEED_DOMAIN: &[u8] = b"secret seed ceremony genesis seed";
+const CURRENT_SEED_DOMAIN: &[u8] = b"secret seed ceremony current seed";
+const FINGERPRINT_DOMAIN: &[u8] = b"secret seed ceremony fingerprint";
+
+const MIN_PARTICIPANTS: usize = 2;
+
+/// A share encrypted with AES-SIV
+const ENCRYPTED_SHARE_SIZE: usize = 32 + 16;
+
+/// How the operator concatenates the files of the participants
+const CEREMONY_FILES: Framed = Framed(Endian::Little);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Participant {
+    public_key: [u8; 32],
+    commitment: [u8; 32],
+    mr_enclave: [u8; 32],
+}
+
+/// The participants of a ceremony, in the order of their ceremony keys
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Transcript {
+    participants: Vec<Participant>,
+    /// A hash of the combined seeds, which all the participants should agree on
+    #[serde(default)]
+    seed_fingerprint: Option<[u8; 32]>,
+}
+
+impl Transcript {
+    fn new(mut participants: Vec<Participant>) -> SgxResult<Self> {
+        if participants.len() < MIN_PARTICIPANTS {
+            error!(
+                "A ceremony needs at least {} participants, got {}",
+                MIN_PARTICIPANTS,
+                participants.len()
+            );
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        participants.sort_by(|a, b| a.public_key.cmp(&b.public_key));
+        if participants
+            .windows(2)
+            .any(|pair| pair[0].public_key == pair[1].public_key)
+        {
+            error!("A participant committed more than once");
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        Ok(Self {
+            participants,
+            seed_fingerprint: None,
+        })
+    }
+
+    /// The hash every share is bound to, so that a share revealed to one set of participants
+    /// can't be used with another
+    fn hash(&self) -> [u8; 32] {
+        let mut data = TRANSCRIPT_DOMAIN.to_vec();
+        for participant in &self.participants {
+            data.extend_from_slice(&participant.public_key);
+            data.extend_from_slice(&participant.commitment);
+            data.extend_from_slice(&participant.mr_enclave);
+        }
+        sha_256(&data)
+    }
+}
+
+/// What a participant keeps sealed between the ops
+#[derive(Serialize, Deserialize)]
+struct CeremonyState {
+    private_key: [u8; 32],
+    share: [u8; 32],
+    /// Set once the share was revealed, after which it is never revealed to other participants
+    transcript: Option<Transcript>,
+}
+
+impl CeremonyState {
+    fn new() -> SgxResult<Self> {
+        let kp = KeyPair::new().map_err(|e| {
+            error!("Failed to generate key pair: {}", e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })?;
+
+        let mut share = [0u8; 32];
+        rand_slice(&mut share).map_err(|e| {
+            error!("Failed to generate share: {}", e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })?;
+
+        Ok(Self {
+            private_key: *kp.get_privkey(),
+            share,
+            transcript: None,
+        })
+    }
+
+    fn key_pair(&self) -> KeyPair {
+        let mut sk = Ed25519PrivateKey::default();
+        sk.get_mut().copy_from_slice(&self.private_key);
+        KeyPair::from_sk(sk)
+    }
+
+    fn unseal() -> SgxResult<Self> {
+        serde_json::from_slice(&unseal(&make_sgx_secret_path(SEALED_FILE_CEREMONY_STATE))?).map_err(
+            |e| {
+                error!("Error decoding ceremony state from json {:?}", e);
+                sgx_status_t::SGX_ERROR_UNEXPECTED
+            },
+        )
+    }
+
+    fn seal(&self) -> SgxResult<()> {
+        let encoded = serde_json::to_vec(&self).map_err(|e| {
+            error!("Error encoding ceremony state to json: {:?}", e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })?;
+
+        seal(&encoded, &make_sgx_secret_path(SEALED_FILE_CEREMONY_STATE))
+    }
+}
+
+fn commitment(public_key: &[u8; 32], share: &[u8; 32]) -> [u8; 32] {
+    let mut data = COMMITMENT_DOMAIN.to_vec();
+    data.extend_from_slice(public_key);
+    data.extend_from_slice(share);
+    sha_256(&data)
+}
+
+fn share_key(kp: &KeyPair, other_public_key: &[u8; 32]) -> AESKey {
+    AESKey::new_from_slice(&kp.diffie_hellman(other_public_key))
+}
+
+/// The genesis and current seeds of the shares of all the participants, in transcript order
+fn combine_shares(transcript_hash: &[u8; 32], shares: &[[u8; 32]]) -> (Seed, Seed) {
+    let hash_shares = |domain: &[u8]| {
+        let mut data = domain.to_vec();
+        data.extend_from_slice(transcript_hash);
+        for share in shares {
+            data.extend_from_slice(share);
+        }
+
+        let mut seed = Seed::default();
+        seed.as_mut().copy_from_slice(&sha_256(&data));
+        seed
+    };
+
+    (
+        hash_shares(GENESIS_SEED_DOMAIN),
+        hash_shares(CURRENT_SEED_DOMAIN),
+    )
+}
+
+fn seed_fingerprint(genesis: &Seed, current: &Seed) -> [u8; 32] {
+    let mut data = FINGERPRINT_DOMAIN.to_vec();
+    data.extend_from_slice(genesis.as_slice());
+    data.extend_from_slice(current.as_slice());
+    sha_256(&data)
+}
+
+fn read_file(file_name: &str) -> SgxResult<Vec<u8>> {
+    let mut data = Vec::new();
+    File::open(make_sgx_secret_path(file_name))
+        .and_then(|mut f_in| f_in.read_to_end(&mut data))
+        .map_err(|e| {
+            error!("failed to read file {}: {}", file_name, e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })?;
+    Ok(data)
+}
+
+fn write_file(file_name: &str, data: &[u8]) -> SgxResult<()> {
+    File::create(make_sgx_secret_path(file_name))
+        .and_then(|mut f_out| f_out.write_all(data))
+        .map_err(|e| {
+            error!("failed to write file {}: {}", file_name, e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })
+}
+
+/// Splits the files of all the participants that the operator concatenated into one: each is
+/// prefixed with its size, as a little endian u32
+fn split_files(data: &[u8]) -> SgxResult<Vec<&[u8]>> {
+    CEREMONY_FILES.decode(data).map_err(|err| {
+        error!("Truncated ceremony file: {}", err);
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    })
+}
+
+fn is_participant_approved(report: &sgx_report_body_t) -> bool {
+    // all the participants have to run the same code, or any of them could leak its share
+    if report.mr_enclave.m != SELF_REPORT_BODY.mr_enclave.m {
+        println!("Participant runs a different enclave");
+        return false;
+    }
+    true
+}
+
+fn verify_commitment(cert: &[u8]) -> SgxResult<Participant> {
+    let (_, vec_quote, vec_coll) = split_combined_cert(cert);
+
+    let report = match verify_quote_sgx(
+        vec_quote.as_slice(),
+        vec_coll.as_slice(),
+        enclave_now().seconds,
+    ) {
+        Ok((body, _, _)) => body,
+        Err(e) => {
+            error!("Can't verify participant quote: {}", e);
+            return Err(sgx_status_t::SGX_ERROR_NO_PRIVILEGE);
+        }
+    };
+
+    if !is_participant_approved(&report) {
+        return Err(sgx_status_t::SGX_ERROR_NO_PRIVILEGE);
+    }
+
+    Ok(Participant {
+        public_key: report.report_data.d[0..32].try_into().unwrap(),
+        commitment: report.report_data.d[32..64].try_into().unwrap(),
+        mr_enclave: report.mr_enclave.m,
+    })
+}
+
+fn try_commit_seed_share() -> SgxResult<()> {
+    if path::Path::new(&make_sgx_secret_path(SEALED_FILE_CEREMONY_STATE)).exists() {
+        error!("Already committed to a share, refusing to draw another");
+        return Err(sgx_status_t::SGX_ERROR_NO_PRIVILEGE);
+    }
+
+    let state = CeremonyState::new()?;
+    let public_key = state.key_pair().get_pubkey();
+
+    let mut report_data = [0u8; 64];
+    report_data[..32].copy_from_slice(&public_key);
+    report_data[32..].copy_from_slice(&commitment(&public_key, &state.share));
+
+    let (vec_quote, vec_coll) = get_quote_ecdsa(&report_data)?;
+
+    state.seal()?;
+
+    // the same layout as the combined attestation, without an EPID certificate
+    let cert = CombinedCertBuilder::new()
+        .dcap(&vec_quote, &vec_coll)
+        .build();
+
+    write_file(FILE_CEREMONY_COMMIT, &cert)?;
+
+    println!("Ceremony key: {}", hex::encode(public_key));
+    Ok(())
+}
+
+fn try_reveal_seed_share() -> SgxResult<()> {
+    let mut state = CeremonyState::unseal()?;
+    let kp = state.key_pair();
+
+    let participants = split_files(&read_file(FILE_CEREMONY_COMMITS)?)?
+        .into_iter()
+        .map(verify_commitment)
+        .collect::<SgxResult<Vec<_>>>()?;
+    let transcript = Transcript::new(participants)?;
+
+    let own_commitment = commitment(&kp.get_pubkey(), &state.share);
+    if !transcript
+        .participants
+        .iter()
+        .any(|p| p.public_key == kp.get_pubkey() && p.commitment == own_commitment)
+    {
+        error!("Own commitment is missing from the ceremony");
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    if let Some(ref revealed) = state.transcript {
+        if revealed.hash() != transcript.hash() {
+            error!("The share was already revealed to other participants");
+            return Err(sgx_status_t::SGX_ERROR_NO_PRIVILEGE);
+        }
+    }
+
+    let transcript_hash = transcript.hash();
+    let mut reveal = kp.get_pubkey().to_vec();
+    for participant in &transcript.participants {
+        if participant.public_key == kp.get_pubkey() {
+            continue;
+        }
+
+        let encrypted = share_key(&kp, &participant.public_key)
+            .encrypt_siv(&state.share, Some(&[&transcript_hash[..]]))
+            .map_err(|e| {
+                error!("Failed to encrypt share: {}", e);
+                sgx_status_t::SGX_ERROR_UNEXPECTED
+            })?;
+
+        reveal.extend_from_slice(&participant.public_key);
+        reveal.extend_from_slice(&encrypted);
+    }
+
+    state.transcript = Some(transcript);
+    state.seal()?;
+
+    write_file(FILE_CEREMONY_REVEAL, &reveal)?;
+
+    println!("Ceremony transcript: {}", hex::encode(transcript_hash));
+    Ok(())
+}
+
+/// The share `participant` revealed to `kp` in one of the `reveals`
+fn find_share(
+    kp: &KeyPair,
+    participant: &Participant,
+    transcript_hash: &[u8; 32],
+    reveals: &[&[u8]],
+) -> SgxResult<[u8; 32]> {
+    let reveal = reveals
+        .iter()
+        .find(|reveal| reveal.len() >= 32 && reveal[..32] == participant.public_key)
+        .ok_or_else(|| {
+            error!(
+                "No reveal from participant {}",
+                hex::encode(participant.public_key)
+            );
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        })?;
+
+    let encrypted = reveal[32..]
+        .chunks(32 + ENCRYPTED_SHARE_SIZE)
+        .find(|entry| entry.len() == 32 + ENCRYPTED_SHARE_SIZE && entry[..32] == kp.get_pubkey())
+        .map(|entry| &entry[32..])
+        .ok_or_else(|| {
+            error!(
+                "Participant {} didn't reveal its share to us",
+                hex::encode(participant.public_key)
+            );
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        })?;
+
+    let share: [u8; 32] = share_key(kp, &participant.public_key)
+        .decrypt_siv(encrypted, Some(&[&transcript_hash[..]]))
+        .map_err(|e| {
+            error!("Can't decrypt share: {}", e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })?
+        .as_slice()
+        .try_into()
+        .map_err(|_| sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+
+    if commitment(&participant.public_key, &share) != participant.commitment {
+        error!(
+            "Participant {} revealed a share it didn't commit to",
+            hex::encode(participant.public_key)
+        );
+        return Err(sgx_status_t::SGX_ERROR_NO_PRIVILEGE);
+    }
+
+    Ok(share)
+}
+
+fn try_combine_seed_shares() -> SgxResult<()> {
+    let state = CeremonyState::unseal()?;
+    let kp = state.key_pair();
+
+    let mut transcript = state.transcript.clone().ok_or_else(|| {
+        error!("The share wasn't revealed yet");
+        sgx_status_t::SGX_ERROR_INVALID_STATE
+    })?;
+    let transcript_hash = transcript.hash();
+
+    let reveals_file = read_file(FILE_CEREMONY_REVEALS)?;
+    let reveals = split_files(&reveals_file)?;
+
+    let shares = transcript
+        .participants
+        .iter()
+        .map(|participant| {
+            if participant.public_key == kp.get_pubkey() {
+                Ok(state.share)
+            } else {
+                find_share(&kp, participant, &transcript_hash, &reveals)
+            }
+        })
+        .collect::<SgxResult<Vec<_>>>()?;
+
+    let (genesis, current) = combine_shares(&transcript_hash, &shares);
+
+    let mut seeds = genesis.as_slice().to_vec();
+    seeds.extend_from_slice(current.as_slice());
+    seal(&seeds, &make_sgx_secret_path(SEALED_FILE_CEREMONY_SEED))?;
+
+    transcript.seed_fingerprint = Some(seed_fingerprint(&genesis, &current));
+    let encoded = serde_json::to_vec(&transcript).map_err(|e| {
+        error!("Error encoding ceremony transcript to json: {:?}", e);
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })?;
+    write_file(FILE_CEREMONY_TRANSCRIPT, &encoded)?;
+
+    println!(
+        "Seed fingerprint: {}",
+        hex::encode(transcript.seed_fingerprint.unwrap())
+    );
+    Ok(())
+}
+
+/// The seeds of a finished ceremony, if this enclave took part in one
+pub fn ceremony_seeds() -> SgxResult<Option<(Seed, Seed)>> {
+    let sealed_path = make_sgx_secret_path(SEALED_FILE_CEREMONY_SEED);
+    if !path::Path::new(&sealed_path).exists() {
+        return Ok(None);
+    }
+
+    let seeds = unseal(&sealed_path)?;
+    if seeds.len() != 64 {
+        error!("Invalid ceremony seeds size: {}", seeds.len());
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    let mut genesis = Seed::default();
+    genesis.as_mut().copy_from_slice(&seeds[..32]);
+    let mut current = Seed::default();
+    current.as_mut().copy_from_slice(&seeds[32..]);
+
+    println!("Using the seeds of the seed ceremony");
+    Ok(Some((genesis, current)))
+}
+
+pub fn commit_seed_share() -> sgx_status_t {
+    match try_commit_seed_share() {
+        Ok(()) => {
+            println!("Seed share commitment successfully exported");
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+pub fn reveal_seed_share() -> sgx_status_t {
+    match try_reveal_seed_share() {
+        Ok(()) => {
+            println!("Seed share successfully revealed");
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+pub fn combine_seed_shares() -> sgx_status_t {
+    match try_combine_seed_shares() {
+        Ok(()) => {
+            println!("Seed shares successfully combined");
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn participant(state: &CeremonyState) -> Participant {
+        let public_key = state.key_pair().get_pubkey();
+        Participant {
+            public_key,
+            commitment: commitment(&public_key, &state.share),
+            mr_enclave: SELF_REPORT_BODY.mr_enclave.m,
+        }
+    }
+
+    fn reveal(state: &CeremonyState, transcript: &Transcript) -> Vec<u8> {
+        let kp = state.key_pair();
+        let mut reveal = kp.get_pubkey().to_vec();
+        for p in &transcript.participants {
+            if p.public_key != kp.get_pubkey() {
+                reveal.extend_from_slice(&p.public_key);
+                reveal.extend_from_slice(
+                    &share_key(&kp, &p.public_key)
+                        .encrypt_siv(&state.share, Some(&[&transcript.hash()[..]]))
+                        .unwrap(),
+                );
+            }
+        }
+        reveal
+    }
+
+    pub fn test_seed_ceremony_transcript() {
+        let a = participant(&CeremonyState::new().unwrap());
+        let b = participant(&CeremonyState::new().unwrap());
+
+        assert!(Transcript::new(vec![a.clone()]).is_err());
+        assert!(Transcript::new(vec![a.clone(), a.clone()]).is_err());
+
+        // the order the operators concatenated the commitments in doesn't matter
+        let ab = Transcript::new(vec![a.clone(), b.clone()]).unwrap();
+        let ba = Transcript::new(vec![b.clone(), a.clone()]).unwrap();
+        assert_eq!(ab, ba);
+        assert_eq!(ab.hash(), ba.hash());
+
+        let mut other = b;
+        other.commitment[0] ^= 1;
+        assert_ne!(Transcript::new(vec![a, other]).unwrap().hash(), ab.hash());
+
+        let decoded: Transcript =
+            serde_json::from_slice(&serde_json::to_vec(&ab).unwrap()).unwrap();
+        assert_eq!(decoded, ab);
+
+        let files = [&[1u8, 2][..], &[], &[3]]
+            .iter()
+            .fold(vec![], |mut data, f| {
+                data.extend_from_slice(&(f.len() as u32).to_le_bytes());
+                data.extend_from_slice(f);
+                data
+            });
+        assert_eq!(split_files(&files).unwrap(), vec![&[1u8, 2][..], &[], &[3]]);
+        assert!(split_files(&files[..files.len() - 1]).is_err());
+    }
+
+    pub fn test_seed_ceremony_shares() {
+        let states: Vec<_> = (0..3).map(|_| CeremonyState::new().unwrap()).collect();
+        let transcript = Transcript::new(states.iter().map(participant).collect()).unwrap();
+        let hash = transcript.hash();
+        let reveals: Vec<_> = states.iter().map(|s| reveal(s, &transcript)).collect();
+        let reveals: Vec<&[u8]> = reveals.iter().map(|r| r.as_slice()).collect();
+
+        // every participant ends up with the same seeds
+        let mut fingerprints = vec![];
+        for state in &states {
+            let kp = state.key_pair();
+            let shares: Vec<_> = transcript
+                .participants
+                .iter()
+                .map(|p| {
+                    if p.public_key == kp.get_pubkey() {
+                        state.share
+                    } else {
+                        find_share(&kp, p, &hash, &reveals).unwrap()
+                    }
+                })
+                .collect();
+            let (genesis, current) = combine_shares(&hash, &shares);
+            assert_ne!(genesis.as_slice(), current.as_slice());
+            fingerprints.push(seed_fingerprint(&genesis, &current));
+        }
+        assert!(fingerprints.windows(2).all(|pair| pair[0] == pair[1]));
+
+        let kp = states[0].key_pair();
+        let other = transcript
+            .participants
+            .iter()
+            .find(|p| p.public_key != kp.get_pubkey())
+            .unwrap();
+
+        // a share revealed for another transcript
+        assert!(find_share(&kp, other, &[0u8; 32], &reveals).is_err());
+
+        // a share that doesn't match its commitment
+        let mut cheater = other.clone();
+        cheater.commitment[0] ^= 1;
+        assert!(find_share(&kp, &cheater, &hash, &reveals).is_err());
+
+        // a missing reveal
+        assert!(find_share(&kp, other, &hash, &[]).is_err());
+    }
+
+    pub fn test_seed_ceremony_participant_policy() {
+        let mut report = *SELF_REPORT_BODY;
+        assert!(is_participant_approved(&report));
+
+        report.mr_enclave.m[0] ^= 1;
+        assert!(!is_participant_approved(&report));
+    }
+}
@@ -3,18 +3,122 @@ use log::*;
 use sgx_types::{sgx_status_t, SgxResult};
 
 use enclave_crypto::{AESKey, SIVEncryptable, Seed, PUBLIC_KEY_SIZE, SEED_KEY_SIZE};
-use enclave_ffi_types::SINGLE_ENCRYPTED_SEED_SIZE;
+use enclave_ffi_types::{SeedBlobStatus, SINGLE_ENCRYPTED_SEED_SIZE};
 use enclave_utils::{Keychain, KEY_MANAGER};
+use enclave_wire::{Reader, WireResult};
+
+use super::node_role::NodeRole;
 
 pub enum SeedType {
     Genesis,
     Current,
 }
 
+pub type EncryptedSeed = [u8; SINGLE_ENCRYPTED_SEED_SIZE];
+
+/// The seeds a node loads on startup, as its registration returned them, encrypted or not
+#[derive(Debug, PartialEq, Eq)]
+pub enum SeedBlob<T = EncryptedSeed> {
+    /// Nodes that registered before the seed rotation only received the genesis seed, and get the
+    /// current one from the seed service
+    GenesisOnly(T),
+    Dual {
+        genesis: T,
+        current: T,
+    },
+    /// Nodes that registered once the network stopped issuing the genesis seed, see
+    /// `issues_genesis_seed`. The genesis seed is all zeros.
+    CurrentOnly(T),
+}
+
+impl<T> SeedBlob<T> {
+    pub fn status(&self) -> SeedBlobStatus {
+        match self {
+            SeedBlob::GenesisOnly(_) => SeedBlobStatus::GenesisOnly,
+            SeedBlob::Dual { .. } => SeedBlobStatus::Dual,
+            SeedBlob::CurrentOnly(_) => SeedBlobStatus::CurrentOnly,
+        }
+    }
+}
+
+/// Reads `length byte || genesis seed || current seed`, the seeds a node loads on startup as its
+/// registration returned them. A seed that wasn't issued is all zeros.
+fn read_seed_blob(blob: &[u8]) -> WireResult<(u8, EncryptedSeed, EncryptedSeed)> {
+    let mut reader = Reader::new(blob);
+    let len_byte = reader.array::<1>()?[0];
+    let genesis = reader.array()?;
+    let current = reader.array()?;
+    reader.finish()?;
+    Ok((len_byte, genesis, current))
+}
+
+impl SeedBlob<EncryptedSeed> {
+    /// Decrypts the seeds with the node's registration key. Fails with the status of the first
+    /// seed that doesn't decrypt.
+    pub fn decrypt(
+        self,
+        key_manager: &Keychain,
+        master_pk: [u8; PUBLIC_KEY_SIZE],
+    ) -> Result<SeedBlob<Seed>, SeedBlobStatus> {
+        let genesis = |seed| {
+            decrypt_seed(key_manager, master_pk, seed)
+                .map_err(|_| SeedBlobStatus::GenesisSeedUndecryptable)
+        };
+        let current = |seed| {
+            decrypt_seed(key_manager, master_pk, seed)
+                .map_err(|_| SeedBlobStatus::CurrentSeedUndecryptable)
+        };
+
+        Ok(match self {
+            SeedBlob::GenesisOnly(seed) => SeedBlob::GenesisOnly(genesis(seed)?),
+            SeedBlob::Dual {
+                genesis: genesis_seed,
+                current: current_seed,
+            } => SeedBlob::Dual {
+                genesis: genesis(genesis_seed)?,
+                current: current(current_seed)?,
+            },
+            SeedBlob::CurrentOnly(seed) => SeedBlob::CurrentOnly(current(seed)?),
+        })
+    }
+}
+
+/// Decodes `length byte || genesis seed || current seed`, where the length byte is 48 or 96 and a
+/// seed that wasn't issued is all zeros. A length byte that disagrees with what follows it is
+/// rejected rather than guessed at.
+pub fn decode_seed_blob(blob: &[u8]) -> Result<SeedBlob, SeedBlobStatus> {
+    let (len_byte, genesis, current) = read_seed_blob(blob).map_err(|err| {
+        warn!("Encrypted seed of {} bytes: {}", blob.len(), err);
+        SeedBlobStatus::BadLength
+    })?;
+
+    let has_genesis = genesis.iter().any(|b| *b != 0);
+    let has_current = current.iter().any(|b| *b != 0);
+
+    match len_byte as usize {
+        SINGLE_ENCRYPTED_SEED_SIZE if has_current => Err(SeedBlobStatus::UnexpectedCurrentSeed),
+        SINGLE_ENCRYPTED_SEED_SIZE => Ok(SeedBlob::GenesisOnly(genesis)),
+        len if len == 2 * SINGLE_ENCRYPTED_SEED_SIZE && !has_current => {
+            Err(SeedBlobStatus::MissingCurrentSeed)
+        }
+        len if len == 2 * SINGLE_ENCRYPTED_SEED_SIZE && !has_genesis => {
+            Ok(SeedBlob::CurrentOnly(current))
+        }
+        len if len == 2 * SINGLE_ENCRYPTED_SEED_SIZE => Ok(SeedBlob::Dual { genesis, current }),
+        len => {
+            warn!("Encrypted seed has a length byte of {}", len);
+            Err(SeedBlobStatus::BadLength)
+        }
+    }
+}
+
+/// Encrypts the consensus seed for a registering node, or the capability key of its role if it's a
+/// service enclave
 pub fn encrypt_seed(
     new_node_pk: [u8; PUBLIC_KEY_SIZE],
     seed_type: SeedType,
     is_legacy: bool,
+    role: NodeRole,
 ) -> SgxResult<Vec<u8>> {
     let base_seed = if is_legacy {
         KEY_MANAGER.seed_exchange_key().unwrap().genesis
@@ -22,9 +126,19 @@ pub fn encrypt_seed(
         KEY_MANAGER.seed_exchange_key().unwrap().current
     };
 
+    let kdf = KEY_MANAGER.get_consensus_seed_kdf();
+    let context = KEY_MANAGER.get_consensus_seed_context();
     let seed_to_share = match seed_type {
-        SeedType::Genesis => KEY_MANAGER.get_consensus_seed().unwrap().genesis,
-        SeedType::Current => KEY_MANAGER.get_consensus_seed().unwrap().current,
+        SeedType::Genesis => role.secret_to_share(
+            &KEY_MANAGER.get_consensus_seed().unwrap().genesis,
+            kdf.genesis,
+            context.genesis.as_ref(),
+        ),
+        SeedType::Current => role.secret_to_share(
+            &KEY_MANAGER.get_consensus_seed().unwrap().current,
+            kdf.current,
+            context.current.as_ref(),
+        ),
     };
 
     let shared_enc_key = base_seed.diffie_hellman(&new_node_pk);
@@ -107,3 +221,86 @@ pub fn decrypt_seed(
         });
     Ok(genesis_seed)
 }
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use enclave_wire::WireError;
+
+    fn blob(len: u8, genesis: u8, current: u8) -> Vec<u8> {
+        let mut blob = vec![len];
+        blob.extend_from_slice(&[genesis; SINGLE_ENCRYPTED_SEED_SIZE]);
+        blob.extend_from_slice(&[current; SINGLE_ENCRYPTED_SEED_SIZE]);
+        blob
+    }
+
+    pub fn test_read_seed_blob() {
+        assert_eq!(
+            read_seed_blob(&blob(96, 1, 2)),
+            Ok((
+                96,
+                [1; SINGLE_ENCRYPTED_SEED_SIZE],
+                [2; SINGLE_ENCRYPTED_SEED_SIZE]
+            ))
+        );
+
+        assert_eq!(
+            read_seed_blob(&blob(48, 1, 0)[..49]),
+            Err(WireError::Truncated {
+                needed: SINGLE_ENCRYPTED_SEED_SIZE
+            })
+        );
+        assert_eq!(
+            read_seed_blob(&[blob(96, 1, 2), vec![0]].concat()),
+            Err(WireError::TrailingBytes { len: 1 })
+        );
+        assert!(read_seed_blob(&[]).is_err());
+    }
+
+    pub fn test_decode_seed_blob() {
+        assert_eq!(
+            decode_seed_blob(&blob(48, 1, 0)),
+            Ok(SeedBlob::GenesisOnly([1; SINGLE_ENCRYPTED_SEED_SIZE]))
+        );
+
+        let dual = decode_seed_blob(&blob(96, 1, 2)).unwrap();
+        assert_eq!(dual.status(), SeedBlobStatus::Dual);
+        assert_eq!(
+            dual,
+            SeedBlob::Dual {
+                genesis: [1; SINGLE_ENCRYPTED_SEED_SIZE],
+                current: [2; SINGLE_ENCRYPTED_SEED_SIZE],
+            }
+        );
+
+        // issued once the network stopped issuing the genesis seed
+        assert_eq!(
+            decode_seed_blob(&blob(96, 0, 2)),
+            Ok(SeedBlob::CurrentOnly([2; SINGLE_ENCRYPTED_SEED_SIZE]))
+        );
+    }
+
+    pub fn test_decode_seed_blob_mismatch() {
+        assert_eq!(
+            decode_seed_blob(&blob(48, 1, 2)),
+            Err(SeedBlobStatus::UnexpectedCurrentSeed)
+        );
+        assert_eq!(
+            decode_seed_blob(&blob(96, 1, 0)),
+            Err(SeedBlobStatus::MissingCurrentSeed)
+        );
+        assert_eq!(
+            decode_seed_blob(&blob(97, 1, 2)),
+            Err(SeedBlobStatus::BadLength)
+        );
+        assert_eq!(
+            decode_seed_blob(&blob(0, 0, 0)),
+            Err(SeedBlobStatus::BadLength)
+        );
+        assert_eq!(
+            decode_seed_blob(&blob(48, 1, 0)[..49]),
+            Err(SeedBlobStatus::BadLength)
+        );
+    }
+}
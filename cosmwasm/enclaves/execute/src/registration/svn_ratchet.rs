@@ -0,0 +1,142 @@
+//! Minimum ISV SVNs that governance schedules, so builds of the enclave that are too old are
+//! locked out once the network has moved past them.
+//!
+//! `ISV_SVN_RATCHET` is a list of steps, each a height and the minimum ISV SVN from that height
+//! on, and every step raises the minimum. Enclaves below the minimum aren't issued seeds by
+//! registration, and peer enclaves don't hand them secrets: the sealed data of a migration, or a
+//! backup of a registration key.
+//!
+//! Registration checks the minimum at the height of the block it's executed in, so every node
+//! agrees. Peer interactions run outside of blocks, and check it at the last block the enclave
+//! verified, with the params proven for it. Until the enclave verified a block and its params,
+//! e.g. after a restart or without the light client, no enclave older than this one is given
+//! secrets.
+
+use log::*;
+
+use enclave_crypto::consts::SELF_REPORT_BODY;
+use enclave_ffi_types::NodeAuthResult;
+use enclave_utils::verified_params::{IsvSvnRatchet, IsvSvnStep, ISV_SVN_RATCHET};
+
+/// The height of the block being executed, or outside of one the last block the enclave verified.
+/// 0 until it verified one.
+#[cfg(feature = "light-client-validation")]
+fn ratchet_height() -> u64 {
+    block_verifier::VERIFIED_BLOCK_MESSAGES
+        .lock()
+        .unwrap()
+        .height()
+}
+
+#[cfg(not(feature = "light-client-validation"))]
+fn ratchet_height() -> u64 {
+    0
+}
+
+/// The ratchet until the params are proven: from the first block, no enclave older than this one
+fn strict_ratchet(own_isv_svn: u16) -> IsvSvnRatchet {
+    IsvSvnRatchet {
+        steps: vec![IsvSvnStep {
+            height: 1,
+            min_isv_svn: own_isv_svn,
+        }],
+    }
+}
+
+/// The lowest ISV SVN an enclave that's given secrets may have now
+pub fn required_isv_svn() -> u16 {
+    let own_isv_svn = SELF_REPORT_BODY.isv_svn;
+    let height = ratchet_height();
+    if height == 0 {
+        // Without a verified block, there are no proven params either
+        return own_isv_svn;
+    }
+
+    // Until the params are proven, no enclave older than this one is given secrets
+    ISV_SVN_RATCHET
+        .get_or(IsvSvnRatchet::default(), strict_ratchet(own_isv_svn))
+        .min_isv_svn_at(height)
+}
+
+/// Fails for an enclave with `isv_svn` below `required`
+pub fn check_isv_svn(isv_svn: u16, required: u16) -> Result<(), NodeAuthResult> {
+    if isv_svn < required {
+        warn!(
+            "ISV SVN {} is below the minimum governance scheduled, {}",
+            isv_svn, required
+        );
+        return Err(NodeAuthResult::IsvSvnTooLow);
+    }
+    Ok(())
+}
+
+/// Whether a peer enclave with `isv_svn` may be handed secrets
+pub fn is_peer_isv_svn_allowed(isv_svn: u16) -> bool {
+    check_isv_svn(isv_svn, required_isv_svn()).is_ok()
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use enclave_utils::verified_params::ParamValue;
+
+    pub fn test_isv_svn_ratchet() {
+        let ratchet = IsvSvnRatchet::decode(
+            br#"[{"height":100,"min_isv_svn":2},{"height":200,"min_isv_svn":5}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ratchet.steps,
+            vec![
+                IsvSvnStep {
+                    height: 100,
+                    min_isv_svn: 2
+                },
+                IsvSvnStep {
+                    height: 200,
+                    min_isv_svn: 5
+                },
+            ]
+        );
+
+        assert_eq!(ratchet.min_isv_svn_at(99), 0);
+        assert_eq!(ratchet.min_isv_svn_at(100), 2);
+        assert_eq!(ratchet.min_isv_svn_at(199), 2);
+        assert_eq!(ratchet.min_isv_svn_at(1_000), 5);
+        // without a verified height nothing is required
+        assert_eq!(ratchet.min_isv_svn_at(0), 0);
+
+        // until the params are proven, nothing older than this enclave
+        assert_eq!(strict_ratchet(3).min_isv_svn_at(1), 3);
+        assert_eq!(strict_ratchet(3).min_isv_svn_at(1_000), 3);
+
+        assert_eq!(check_isv_svn(5, 5), Ok(()));
+        assert_eq!(check_isv_svn(4, 5), Err(NodeAuthResult::IsvSvnTooLow));
+    }
+
+    pub fn test_isv_svn_ratchet_decode() {
+        // every step must raise the minimum, at a later height
+        assert_eq!(
+            IsvSvnRatchet::decode(
+                br#"[{"height":100,"min_isv_svn":5},{"height":200,"min_isv_svn":2}]"#
+            ),
+            None
+        );
+        assert_eq!(
+            IsvSvnRatchet::decode(
+                br#"[{"height":200,"min_isv_svn":2},{"height":100,"min_isv_svn":5}]"#
+            ),
+            None
+        );
+        assert_eq!(IsvSvnRatchet::decode(b"[]"), None);
+        assert_eq!(
+            IsvSvnRatchet::decode(br#"[{"height":0,"min_isv_svn":2}]"#),
+            None
+        );
+        assert_eq!(
+            IsvSvnRatchet::decode(br#"[{"height":100,"min_svn":2}]"#),
+            None
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Trusted time for the enclave.
+//!
+//! The host controls the system clock, so the enclave can't take the time from it. Instead,
+//! `enclave_now` combines the sources the enclave can trust:
+//! * The time of the last block verified by the light client. It's signed by the validators, so
+//!   the host can't make it run ahead, but it only advances as blocks are verified, and it's 0
+//!   in enclaves built without light client validation.
+//! * A sealed watermark of the latest time seen, so the time doesn't go back to 0 after a
+//!   restart, before the first block is verified. It's sealed at most once per
+//!   `WATERMARK_SEAL_INTERVAL_S`, so it can trail the last verified block by that much.
+//!
+//! SGX on Linux doesn't offer platform trusted time: the platform services that provided it
+//! were never available there, and have been removed from the SDK, so it isn't a source.
+//!
+//! The result is a lower bound on the real time: it may lag by however far the node is behind
+//! the chain, but it can't be ahead of the last verified block. That's what time-locked checks
+//! ("not before T") need, since once `enclave_now` passes T, T has passed. Checks that something
+//! hasn't expired yet, like attestation freshness, accept anything that was still valid at the
+//! lagging time.
+//!
+//! Different nodes get different results, since the watermark is local, so on-chain code must
+//! use `verified_block_time_s` instead.
+
+use std::path;
+use std::sync::SgxMutex;
+use std::untrusted::path::PathEx;
+
+use lazy_static::lazy_static;
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use enclave_crypto::consts::{make_sgx_secret_path, SEALED_FILE_TIME_WATERMARK};
+use enclave_utils::storage::{seal, unseal};
+
+/// How far the watermark advances before it's sealed again
+const WATERMARK_SEAL_INTERVAL_S: i64 = 60 * 60;
+
+lazy_static! {
+    static ref TIME_WATERMARK_SEALING_PATH: String =
+        make_sgx_secret_path(SEALED_FILE_TIME_WATERMARK);
+    static ref TIME_WATERMARK: SgxMutex<Option<TimeWatermark>> = SgxMutex::new(None);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeSource {
+    /// The time of the last block verified by the light client
+    VerifiedBlock,
+    /// The sealed watermark, ahead of any block verified since the enclave started
+    Watermark,
+    /// No trusted time is known, and `seconds` is 0
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnclaveTime {
+    /// Unix seconds
+    pub seconds: i64,
+    pub source: TimeSource,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct TimeWatermark {
+    seconds: i64,
+    #[serde(skip)]
+    sealed_seconds: i64,
+}
+
+impl TimeWatermark {
+    fn load() -> Self {
+        if !path::Path::new(TIME_WATERMARK_SEALING_PATH.as_str()).exists() {
+            return Self::default();
+        }
+
+        let decoded = unseal(&TIME_WATERMARK_SEALING_PATH)
+            .ok()
+            .and_then(|sealed| serde_json::from_slice::<Self>(sealed.as_slice()).ok());
+
+        match decoded {
+            Some(mut watermark) => {
+                watermark.sealed_seconds = watermark.seconds;
+                watermark
+            }
+            None => {
+                // Starting from 0 only makes the time lag, it can't make it run ahead
+                warn!("Failed to load the time watermark, starting from 0");
+                Self::default()
+            }
+        }
+    }
+
+    fn seal(&self) {
+        let result = serde_json::to_vec(&self)
+            .map_err(|e| error!("Error encoding time watermark to json: {:?}", e))
+            .and_then(|encoded| {
+                seal(encoded.as_slice(), &TIME_WATERMARK_SEALING_PATH)
+                    .map_err(|e| error!("Error sealing time watermark: {}", e))
+            });
+
+        if result.is_err() {
+            warn!("The time watermark wasn't sealed, and will lag after a restart");
+        }
+    }
+
+    /// Moves the watermark forward to `block_s`, and returns the combined time
+    fn observe(&mut self, block_s: i64) -> EnclaveTime {
+        let now = combine(block_s, self.seconds);
+        self.seconds = now.seconds;
+        now
+    }
+
+    fn should_seal(&self) -> bool {
+        self.seconds - self.sealed_seconds >= WATERMARK_SEAL_INTERVAL_S
+    }
+}
+
+fn combine(block_s: i64, watermark_s: i64) -> EnclaveTime {
+    if block_s > 0 && block_s >= watermark_s {
+        EnclaveTime {
+            seconds: block_s,
+            source: TimeSource::VerifiedBlock,
+        }
+    } else if watermark_s > 0 {
+        EnclaveTime {
+            seconds: watermark_s,
+            source: TimeSource::Watermark,
+        }
+    } else {
+        EnclaveTime {
+            seconds: 0,
+            source: TimeSource::Unknown,
+        }
+    }
+}
+
+/// The time of the last block verified by the light client, in unix seconds, or 0 if none was.
+/// The same on every node that verified the same block.
+#[cfg(feature = "light-client-validation")]
+pub fn verified_block_time_s() -> i64 {
+    let verified_msgs = block_verifier::VERIFIED_BLOCK_MESSAGES.lock().unwrap();
+    let tm_ns = verified_msgs.time();
+    (tm_ns / 1000000000) as i64
+}
+
+#[cfg(not(feature = "light-client-validation"))]
+pub fn verified_block_time_s() -> i64 {
+    0
+}
+
+/// The current time as far as the enclave can trust it, see the module docs for what that
+/// guarantees. When no trusted time is known, `seconds` is 0, which quote verification takes as
+/// "use the host's time".
+pub fn enclave_now() -> EnclaveTime {
+    let block_s = verified_block_time_s();
+
+    let mut guard = TIME_WATERMARK.lock().unwrap();
+    let watermark = guard.get_or_insert_with(TimeWatermark::load);
+
+    let now = watermark.observe(block_s);
+    if watermark.should_seal() {
+        watermark.seal();
+        watermark.sealed_seconds = watermark.seconds;
+    }
+
+    trace!("enclave_now: {:?}", now);
+    now
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    use crate::count_failures;
+
+    pub fn run_tests() {
+        println!();
+        let mut failures = 0;
+
+        count_failures!(failures, {
+            test_enclave_time_combine();
+            test_time_watermark_monotonic();
+        });
+
+        if failures != 0 {
+            panic!("{}: {} tests failed", file!(), failures);
+        }
+    }
+
+    fn test_enclave_time_combine() {
+        assert_eq!(
+            combine(0, 0),
+            EnclaveTime {
+                seconds: 0,
+                source: TimeSource::Unknown
+            }
+        );
+        assert_eq!(
+            combine(1_700_000_000, 1_600_000_000),
+            EnclaveTime {
+                seconds: 1_700_000_000,
+                source: TimeSource::VerifiedBlock
+            }
+        );
+        // before the first block after a restart
+        assert_eq!(
+            combine(0, 1_600_000_000),
+            EnclaveTime {
+                seconds: 1_600_000_000,
+                source: TimeSource::Watermark
+            }
+        );
+        // a node replaying blocks it already saw
+        assert_eq!(
+            combine(1_500_000_000, 1_600_000_000),
+            EnclaveTime {
+                seconds: 1_600_000_000,
+                source: TimeSource::Watermark
+            }
+        );
+    }
+
+    fn test_time_watermark_monotonic() {
+        let mut watermark = TimeWatermark {
+            seconds: 1_600_000_000,
+            sealed_seconds: 1_600_000_000,
+        };
+
+        assert_eq!(watermark.observe(1_500_000_000).seconds, 1_600_000_000);
+        assert_eq!(watermark.seconds, 1_600_000_000);
+        assert!(!watermark.should_seal());
+
+        assert_eq!(watermark.observe(1_600_000_060).seconds, 1_600_000_060);
+        assert!(!watermark.should_seal());
+
+        watermark.observe(1_600_000_000 + WATERMARK_SEAL_INTERVAL_S);
+        assert!(watermark.should_seal());
+
+        assert_eq!(
+            watermark.observe(0).seconds,
+            1_600_000_000 + WATERMARK_SEAL_INTERVAL_S
+        );
+    }
+}
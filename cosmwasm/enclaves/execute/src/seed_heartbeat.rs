@@ -0,0 +1,133 @@
+//! Heartbeats that prove a registered node still holds the consensus seed.
+//!
+//! A node restored from a stale backup, or one that lost its sealed seed, stays registered
+//! forever, and nothing on chain tells it apart from a node that works. The enclave of a node
+//! that holds the seed can sign a heartbeat: the height it was asked for, and the registration
+//! key of the node, signed with an ed25519 key derived from the current consensus seed:
+//!
+//! ```text
+//! "secret-seed-heartbeat" | height (8, big endian) | registration key (32)
+//! ```
+//!
+//! Every enclave with the seed derives the same key, so the chain verifies a heartbeat against
+//! the public key its own enclave derives, and a node without the seed can't produce one. The
+//! enclave only signs for its own registration key, so a node can't vouch for another.
+
+use ed25519_consensus::SigningKey;
+use log::*;
+use sgx_types::sgx_status_t;
+
+use enclave_utils::KEY_MANAGER;
+
+const HEARTBEAT_DOMAIN: &[u8] = b"secret-seed-heartbeat";
+
+pub const SIGNATURE_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedHeartbeat {
+    pub node_public_key: [u8; 32],
+    pub signature: [u8; SIGNATURE_LENGTH],
+}
+
+/// The bytes a heartbeat of `node_public_key` at `height` signs
+pub fn heartbeat_sign_bytes(height: u64, node_public_key: &[u8; 32]) -> Vec<u8> {
+    let mut sign_bytes = HEARTBEAT_DOMAIN.to_vec();
+    sign_bytes.extend_from_slice(&height.to_be_bytes());
+    sign_bytes.extend_from_slice(node_public_key);
+    sign_bytes
+}
+
+fn heartbeat_key() -> Result<SigningKey, sgx_status_t> {
+    let secret = KEY_MANAGER.get_seed_heartbeat_key_secret().map_err(|_| {
+        warn!("can't sign a seed heartbeat without the consensus seed");
+        sgx_status_t::SGX_ERROR_INVALID_STATE
+    })?;
+
+    Ok(SigningKey::from(*secret.get()))
+}
+
+/// The public key heartbeats are verified against
+pub fn heartbeat_public_key() -> Result<[u8; 32], sgx_status_t> {
+    Ok(heartbeat_key()?.verification_key().to_bytes())
+}
+
+/// Sign a heartbeat of the node's registration key at `height`
+pub fn sign_heartbeat(height: u64) -> Result<SeedHeartbeat, sgx_status_t> {
+    let key = heartbeat_key()?;
+    let node_public_key = KEY_MANAGER
+        .get_registration_key()
+        .map_err(|_| sgx_status_t::SGX_ERROR_INVALID_STATE)?
+        .get_pubkey();
+
+    let signature = key.sign(&heartbeat_sign_bytes(height, &node_public_key));
+    trace!("signed a seed heartbeat at height {}", height);
+
+    Ok(SeedHeartbeat {
+        node_public_key,
+        signature: signature.to_bytes(),
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    use crate::count_failures;
+
+    use ed25519_consensus::{Signature, VerificationKey};
+    use std::convert::TryFrom;
+
+    fn verify_heartbeat(
+        heartbeat_public_key: &[u8; 32],
+        height: u64,
+        node_public_key: &[u8; 32],
+        signature: &[u8; SIGNATURE_LENGTH],
+    ) -> bool {
+        VerificationKey::try_from(*heartbeat_public_key)
+            .unwrap()
+            .verify(
+                &Signature::from(*signature),
+                &heartbeat_sign_bytes(height, node_public_key),
+            )
+            .is_ok()
+    }
+
+    pub fn run_tests() {
+        println!();
+        let mut failures = 0;
+
+        count_failures!(failures, {
+            test_heartbeat_sign_bytes();
+            test_heartbeat_verify();
+        });
+
+        if failures != 0 {
+            panic!("{}: {} tests failed", file!(), failures);
+        }
+    }
+
+    fn test_heartbeat_sign_bytes() {
+        let sign_bytes = heartbeat_sign_bytes(0x0102, &[9u8; 32]);
+        assert_eq!(sign_bytes.len(), HEARTBEAT_DOMAIN.len() + 8 + 32);
+        assert!(sign_bytes.starts_with(HEARTBEAT_DOMAIN));
+        assert_eq!(
+            &sign_bytes[HEARTBEAT_DOMAIN.len()..HEARTBEAT_DOMAIN.len() + 8],
+            &[0, 0, 0, 0, 0, 0, 1, 2]
+        );
+    }
+
+    fn test_heartbeat_verify() {
+        let key = SigningKey::from([7u8; 32]);
+        let public_key = key.verification_key().to_bytes();
+        let node = [9u8; 32];
+        let signature = key.sign(&heartbeat_sign_bytes(100, &node)).to_bytes();
+
+        assert!(verify_heartbeat(&public_key, 100, &node, &signature));
+        // A heartbeat only holds for its height and node
+        assert!(!verify_heartbeat(&public_key, 101, &node, &signature));
+        assert!(!verify_heartbeat(&public_key, 100, &[8u8; 32], &signature));
+
+        // A key derived from another seed doesn't verify
+        let other = SigningKey::from([6u8; 32]).verification_key().to_bytes();
+        assert!(!verify_heartbeat(&other, 100, &node, &signature));
+    }
+}
@@ -0,0 +1,177 @@
+//! The startup self-test of the enclave, which the node runs through `ecall_self_test`.
+//!
+//! Runs known-answer tests of every primitive the state and the enclave's signatures depend on,
+//! and records the outcome with `enclave_utils::self_test`, so consensus ecalls are refused
+//! unless all of them passed. The tests of the crypto crate's primitives live in
+//! `enclave_crypto::self_test`, the tests here use what only the enclave has: sealing, the
+//! consensus key's Ed25519 signatures and the quotes of its attestation.
+
+use std::convert::TryFrom;
+use std::panic;
+use std::untrusted::fs;
+
+use ed25519_consensus::{Signature, SigningKey, VerificationKey};
+use log::*;
+
+use enclave_crypto::consts::{make_sgx_secret_path, SEALED_FILE_SELF_TEST};
+use enclave_crypto::dcap::quote_report_body;
+use enclave_utils::crash_breadcrumbs;
+use enclave_utils::self_test::record_self_test;
+use enclave_utils::storage::{seal, unseal};
+
+/// The tests in the order of the bits of the failures `ecall_self_test` returns. The node has the
+/// same list, so tests can only be appended.
+const SELF_TESTS: &[(&str, fn() -> bool)] = &[
+    ("aes_siv", enclave_crypto::self_test::aes_siv),
+    ("hkdf", enclave_crypto::self_test::hkdf),
+    ("secp256k1", enclave_crypto::self_test::secp256k1),
+    ("ed25519", ed25519),
+    ("x25519", enclave_crypto::self_test::x25519),
+    ("sealing", sealing),
+    ("quote_parsing", quote_parsing),
+];
+
+/// RFC 8032, 7.1 test 1, which signs the empty message
+const ED25519_PRIVKEY: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+const ED25519_PUBKEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+const ED25519_SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555\
+                                 fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+const SEALING_PLAINTEXT: &[u8] = b"secret network enclave self-test";
+
+const DCAP_QUOTE: &[u8] = include_bytes!("registration/fixtures/attestation_dcap.quote");
+const DCAP_QUOTE_MR_ENCLAVE: &str =
+    "15abbb64470ce2f74791479a8121a08b2fb146b9467a190d3e495925dcdd1cf7";
+const DCAP_QUOTE_MR_SIGNER: &str =
+    "83d719e77deaca1470f6baf62a4d774303c899db69020f9c70ee1dfc08c7ce9e";
+
+/// Runs every test, and returns a bit for each one that failed
+pub fn run_self_test() -> u32 {
+    let mut failures = 0u32;
+
+    for (index, (name, test)) in SELF_TESTS.iter().enumerate() {
+        // A test that panics failed as well
+        let passed = panic::catch_unwind(*test).unwrap_or(false);
+        if passed {
+            debug!("self-test {} passed", name);
+        } else {
+            error!("self-test {} failed", name);
+            crash_breadcrumbs::record_fatal("self-test", &format!("self-test {} failed", name));
+            failures |= 1 << index;
+        }
+    }
+
+    record_self_test(failures == 0);
+
+    failures
+}
+
+fn ed25519() -> bool {
+    let mut privkey = [0u8; 32];
+    privkey.copy_from_slice(&hex::decode(ED25519_PRIVKEY).unwrap());
+    let signing_key = SigningKey::from(privkey);
+
+    let pubkey = signing_key.verification_key().to_bytes();
+    if hex::encode(pubkey) != ED25519_PUBKEY {
+        error!("ed25519 derived the public key {}", hex::encode(pubkey));
+        return false;
+    }
+
+    let signature = signing_key.sign(b"").to_bytes();
+    if hex::encode(signature) != ED25519_SIGNATURE {
+        error!("ed25519 signed {}", hex::encode(signature));
+        return false;
+    }
+
+    let verification_key = VerificationKey::try_from(pubkey).unwrap();
+    if verification_key
+        .verify(&Signature::from(signature), b"")
+        .is_err()
+    {
+        error!("ed25519 rejected a valid signature");
+        return false;
+    }
+
+    match verification_key.verify(&Signature::from(signature), b"tampered") {
+        Err(_) => true,
+        Ok(_) => {
+            error!("ed25519 accepted the signature of another message");
+            false
+        }
+    }
+}
+
+/// Sealing uses a key only this CPU can derive, so there is no known answer. Seals a file and
+/// checks that it unseals, and that the plaintext doesn't appear in it.
+fn sealing() -> bool {
+    let path = make_sgx_secret_path(SEALED_FILE_SELF_TEST);
+
+    let passed = seal(SEALING_PLAINTEXT, &path).is_ok()
+        && match unseal(&path) {
+            Ok(unsealed) => unsealed == SEALING_PLAINTEXT,
+            Err(_) => false,
+        }
+        && match fs::read(&path) {
+            Ok(sealed) => !sealed
+                .windows(SEALING_PLAINTEXT.len())
+                .any(|window| window == SEALING_PLAINTEXT),
+            Err(_) => false,
+        };
+
+    if let Err(err) = fs::remove_file(&path) {
+        warn!("failed to remove {}: {:?}", path, err);
+    }
+
+    passed
+}
+
+fn quote_parsing() -> bool {
+    let report_body = match quote_report_body(DCAP_QUOTE) {
+        Ok(report_body) => report_body,
+        Err(err) => {
+            error!("failed to parse the quote: {:?}", err);
+            return false;
+        }
+    };
+
+    if hex::encode(report_body.mr_enclave.m) != DCAP_QUOTE_MR_ENCLAVE
+        || hex::encode(report_body.mr_signer.m) != DCAP_QUOTE_MR_SIGNER
+    {
+        error!(
+            "parsed the quote of mr_enclave {} and mr_signer {}",
+            hex::encode(report_body.mr_enclave.m),
+            hex::encode(report_body.mr_signer.m)
+        );
+        return false;
+    }
+
+    // A quote too short to hold a report body
+    quote_report_body(&DCAP_QUOTE[..100]).is_err()
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    use crate::count_failures;
+
+    pub fn run_tests() {
+        println!();
+        let mut failures = 0;
+
+        count_failures!(failures, {
+            test_self_test_passes();
+        });
+
+        if failures != 0 {
+            panic!("{}: {} tests failed", file!(), failures);
+        }
+    }
+
+    fn test_self_test_passes() {
+        for (name, test) in SELF_TESTS {
+            assert!(test(), "self-test {} failed", name);
+        }
+        assert_eq!(run_self_test(), 0);
+        assert!(enclave_utils::self_test::self_test_passed());
+    }
+}
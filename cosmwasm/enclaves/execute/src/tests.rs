@@ -36,8 +36,16 @@ mod test {
             enclave_contract_engine::wasm3::tests::run_tests();
             enclave_cosmos_types::tests::run_tests();
             crate::registration::tests::run_tests();
+            crate::consensus_signer::tests::run_tests();
+            crate::secure_time::tests::run_tests();
+            crate::seed_heartbeat::tests::run_tests();
+            crate::self_test::tests::run_tests();
             block_verifier::tests::run_tests();
 
+            enclave_utils::crash_breadcrumbs::tests::test_crash_breadcrumbs_ecall_scope();
+            enclave_utils::crash_breadcrumbs::tests::test_crash_breadcrumbs_ring_buffer();
+            enclave_utils::crash_breadcrumbs::tests::test_crash_breadcrumbs_sanitize_message();
+
             enclave_crypto::tests::run_tests();
 
             // example failing tests:
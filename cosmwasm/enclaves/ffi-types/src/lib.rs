@@ -5,8 +5,8 @@ mod types;
 
 pub use types::{
     Ctx, EnclaveBuffer, EnclaveError, HandleResult, HealthCheckResult, InitResult, MigrateResult,
-    NodeAuthResult, OcallReturn, QueryResult, RuntimeConfiguration, UntrustedVmError,
-    UpdateAdminResult, UserSpaceBuffer,
+    NodeAuthResult, OcallReturn, QueryResult, RuntimeConfiguration, SeedBlobStatus,
+    UntrustedVmError, UpdateAdminResult, UserSpaceBuffer,
 };
 
 // On input, the encrypted seed is expected to contain 3 values:
@@ -14,6 +14,8 @@ pub use types::{
 //  The next 48 bytes are the first seed
 //  The next 48 bytes represent an optional second seed
 // On output (When authenticating a node or retreiving the seed) we ALWAYS return 96 bytes that represent both of the seeds (Without the size indicator)
+// The output sizes are only the initial capacity of the caller's buffer. The enclave reports the
+// length it needs when its output doesn't fit (see `enclave_utils::output_buffer`)
 pub const INPUT_ENCRYPTED_SEED_SIZE: u32 = 97;
 pub const OUTPUT_ENCRYPTED_SEED_SIZE: u32 = 96;
 
@@ -21,3 +23,21 @@ pub const SINGLE_ENCRYPTED_SEED_SIZE: usize = 48;
 pub const NEWLY_FORMED_SINGLE_ENCRYPTED_SEED_SIZE: usize = SINGLE_ENCRYPTED_SEED_SIZE + 1;
 pub const NEWLY_FORMED_DOUBLE_ENCRYPTED_SEED_SIZE: usize = (2 * SINGLE_ENCRYPTED_SEED_SIZE) + 1;
 pub const PUBLIC_KEY_SIZE: usize = 32;
+
+// Maximum sizes of ecall inputs. Larger inputs are rejected with `InputTooLarge` before the
+// enclave does any work, and the untrusted side checks them again before calling the enclave.
+// These are consensus critical: nodes with different limits would disagree about which txs fail.
+// The msg, wasm and cert limits can be lowered by the compute params, see `input_limits`.
+pub const MAX_ENV_LENGTH: usize = 10_240; // 10 KiB
+pub const MAX_SIG_INFO_LENGTH: usize = 5_120_000; // 5 MiB, includes tx_bytes and sign_bytes
+pub const MAX_MSG_LENGTH: usize = 2_048_000; // 2 MiB
+pub const MAX_ADDRESS_LENGTH: usize = 65; // canonical can be 20 or 32 bytes, humanized can be 45 or 65
+pub const MAX_PROOF_LENGTH: usize = 32; // output of sha256
+pub const MAX_WASM_LENGTH: usize = 3_145_728; // 3 MiB, larger Wasm ATM is 1,990,361 bytes (1.6 MiB)
+pub const MAX_STATE_CHUNK_LENGTH: usize = 2_048_000; // 2 MiB, one chunk of a state export or import
+pub const MAX_CERT_LENGTH: usize = 131_072; // 128 KiB, a DCAP quote with its collateral is ~20 KiB
+pub const MAX_REGISTRATION_PACKAGE_LENGTH: usize = 2 * MAX_CERT_LENGTH; // a cert in base64, with its metadata
+
+// Flags `ecall_authenticate_new_node` sets on a successful registration
+pub const AUTH_FLAG_EPID_DEPRECATED: u32 = 1; // EPID is accepted, until a cutoff governance set
+pub const AUTH_FLAG_CURRENT_SEED_ONLY: u32 = 2; // the genesis seed wasn't issued, it's all zeros
@@ -41,6 +41,14 @@ pub struct RuntimeConfiguration {
     /// This speeds up the execution of recently used modules, but has a significant
     /// memory overhead.
     pub module_cache_size: u32,
+    /// The most wasm pages a query from outside the chain can grow the contract's memory to,
+    /// or 0 for the default that applies to every contract call.
+    pub query_memory_limit_pages: u32,
+    /// The most gas a query from outside the chain can use, or 0 for no limit besides the one
+    /// the query comes with.
+    pub query_gas_limit: u64,
+    /// How long a query from outside the chain can run, in milliseconds, or 0 for no limit.
+    pub query_time_limit_ms: u64,
 }
 
 /// This struct holds a pointer to memory in userspace, that contains the storage
@@ -168,6 +176,27 @@ pub enum EnclaveError {
     OutOfMemory,
     #[display(fmt = "depth of nested contract calls exceeded")]
     ExceededRecursionLimit,
+    /// An input was larger than the maximum allowed for it
+    #[display(fmt = "input is larger than the allowed maximum")]
+    InputTooLarge,
+    /// Governance restricted instantiating this code
+    #[display(fmt = "instantiating this code hash is not allowed")]
+    CodeHashNotAllowed,
+    /// A contract that opted in to the reentrancy guard was called while it was already executing
+    #[display(fmt = "the contract doesn't allow reentrant calls")]
+    ReentrancyNotAllowed,
+    /// A query from outside the chain ran longer than the node allows
+    #[display(fmt = "query exceeded the time limit")]
+    QueryTimeLimitExceeded,
+    /// The contract used up the execution ticks of a single execution
+    #[display(fmt = "execution exceeded the execution ticks limit")]
+    ExecutionTicksExhausted,
+    /// The enclave refuses consensus ecalls until it passed its self-test
+    #[display(fmt = "the enclave didn't pass its self-test")]
+    SelfTestFailed,
+    /// The node cancelled a query from outside the chain
+    #[display(fmt = "query was cancelled by the node")]
+    QueryCancelled,
     /// Unexpected Error happened, no more details available
     #[display(fmt = "unknown error")]
     Unknown,
@@ -177,7 +206,7 @@ pub enum EnclaveError {
 /// enclave while authenticating a new node in the network.
 /// cbindgen:prefix-with-name
 #[repr(C)]
-#[derive(Debug, Display, PartialEq, Eq)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
 pub enum NodeAuthResult {
     #[display(fmt = "Enclave quote is valid")]
     Success,
@@ -223,6 +252,76 @@ pub enum NodeAuthResult {
         fmt = "Unexpected panic during node authentication. Certificate may be malformed or invalid"
     )]
     Panic,
+    #[display(fmt = "The provided certificate is larger than the allowed maximum")]
+    InputTooLarge,
+    #[display(fmt = "The registering enclave was signed with an unknown node role")]
+    UnknownNodeRole,
+    #[display(fmt = "The output buffer is too small for the encrypted seed")]
+    BufferTooSmall,
+    #[display(fmt = "EPID attestations were retired by governance, register with DCAP")]
+    AttestationMethodRetired,
+    #[display(
+        fmt = "The public key was already issued seeds, and the duplicate registration policy doesn't allow it again"
+    )]
+    AlreadyRegistered,
+    #[display(
+        fmt = "The certificate doesn't prove possession of its key for a recent block of the chain"
+    )]
+    PossessionNotProven,
+    #[display(fmt = "The certificate names a key scheme this enclave doesn't support")]
+    UnsupportedKeyScheme,
+    #[display(fmt = "The certificate doesn't bind the node to a bonded validator")]
+    OperatorNotBonded,
+    #[display(fmt = "The attestation policy doesn't allow the product ID of the enclave")]
+    ProductNotAllowed,
+    #[display(fmt = "The ISV SVN of the enclave is below the policy's minimum")]
+    IsvSvnTooLow,
+}
+
+/// What `ecall_init_node` made of the encrypted seed it was given: a length byte, then one or two
+/// encrypted seeds, padded with zeros
+/// cbindgen:prefix-with-name
+#[repr(C)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeedBlobStatus {
+    /// The ecall failed before it got to the seed
+    #[default]
+    #[display(fmt = "the encrypted seed was not decoded")]
+    NotDecoded,
+    #[display(fmt = "only the genesis seed, as registered before the seed rotation")]
+    GenesisOnly,
+    #[display(fmt = "the genesis and the current seed")]
+    Dual,
+    #[display(fmt = "only the current seed, as registered once the genesis seed was retired")]
+    CurrentOnly,
+    #[display(fmt = "corrupt encrypted seed: its length byte is neither 48 nor 96")]
+    BadLength,
+    #[display(
+        fmt = "corrupt encrypted seed: its length byte says genesis only, but a current seed follows"
+    )]
+    UnexpectedCurrentSeed,
+    #[display(
+        fmt = "corrupt encrypted seed: its length byte says genesis and current, but the current seed is missing"
+    )]
+    MissingCurrentSeed,
+    #[display(
+        fmt = "the genesis seed doesn't decrypt with this node's registration key and the master key"
+    )]
+    GenesisSeedUndecryptable,
+    #[display(
+        fmt = "the current seed doesn't decrypt with this node's registration key and the master key"
+    )]
+    CurrentSeedUndecryptable,
+}
+
+impl SeedBlobStatus {
+    /// Whether the seeds were decoded and decrypted
+    pub fn is_loaded(self) -> bool {
+        matches!(
+            self,
+            SeedBlobStatus::GenesisOnly | SeedBlobStatus::Dual | SeedBlobStatus::CurrentOnly
+        )
+    }
 }
 
 /// This type represents the possible error conditions that can be encountered in the
@@ -344,13 +443,17 @@ pub enum UpdateAdminResult {
     },
 }
 
-/// This struct is returned from ecall_query.
+/// This struct is returned from ecall_query and ecall_query_chunk.
 /// cbindgen:prefix-with-name
 #[repr(C)]
 pub enum QueryResult {
     Success {
-        /// A pointer to the output of the calculation
+        /// A pointer to the output of the calculation, or to a chunk of it
         output: UserSpaceBuffer,
+        /// The size of the whole output
+        total_size: usize,
+        /// Pass to ecall_query_chunk to get the next chunk. All zeros when there are no more chunks
+        continuation_token: [u8; 32],
     },
     Failure {
         /// The error that happened in the enclave
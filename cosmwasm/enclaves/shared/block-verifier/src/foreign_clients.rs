@@ -0,0 +1,454 @@
+//! Light clients of other chains, hosted inside the enclave so contracts can check that a
+//! header or a state commitment of another chain is final, without trusting the relayer that
+//! brought it.
+//!
+//! A client is created by governance with a trusted header of the other chain, and is then
+//! updated by anyone with newer headers, which are verified against it the same way a light
+//! client node verifies them. The enclave doesn't keep the clients itself: each creation or update
+//! returns the client, which x/compute stores in its state. At the start of every block the node
+//! passes the stored clients back with proofs of them against the app hash of the block, and
+//! contracts are answered from the clients that were proven, until the next block is verified.
+//!
+//! Only Tendermint clients are supported for now. `ForeignClient` is an enum so clients of
+//! other kinds (e.g. an Ethereum sync committee) can be added without changing how clients are
+//! stored or queried.
+
+use core::convert::{TryFrom, TryInto};
+use core::time::Duration;
+use std::collections::BTreeMap;
+use std::sync::SgxRwLock;
+
+use lazy_static::lazy_static;
+use log::*;
+use sgx_types::sgx_status_t;
+
+use tendermint::block::signed_header::SignedHeader;
+use tendermint::block::{Header, Height};
+use tendermint::chain::Id as ChainId;
+use tendermint::validator::Set;
+use tendermint::Time;
+use tendermint_light_client_verifier::options::Options;
+use tendermint_light_client_verifier::types::{
+    TrustThreshold, TrustedBlockState, UntrustedBlockState,
+};
+use tendermint_light_client_verifier::{ProdVerifier, Verdict, Verifier};
+use tendermint_proto::v0_38::types::Header as RawHeader;
+use tendermint_proto::Protobuf;
+
+use enclave_utils::key_manager::KeychainMutableData;
+
+use crate::wasm_messages::VERIFIED_BLOCK_MESSAGES;
+
+/// How many chains can have a client at once
+pub const MAX_FOREIGN_CLIENTS: usize = 32;
+/// How many of the latest app hashes each client keeps
+pub const MAX_APP_HASHES: usize = 1000;
+
+/// Allowed drift between the clocks of the two chains
+const CLOCK_DRIFT: Duration = Duration::from_secs(10);
+
+const KIND_TENDERMINT: u8 = 1;
+
+lazy_static! {
+    static ref VERIFIER: ProdVerifier = ProdVerifier::default();
+    /// The clients proven at the start of the current block
+    static ref FOREIGN_CLIENTS: SgxRwLock<BTreeMap<String, ForeignClient>> =
+        SgxRwLock::new(BTreeMap::new());
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TendermintClient {
+    pub trusting_period_secs: u64,
+    pub trust_threshold: (u64, u64),
+    /// The latest trusted header
+    pub height: u64,
+    pub time: i128,
+    /// The validator set of the next height, as a `tendermint.types.ValidatorSet`
+    pub next_validators: Vec<u8>,
+    /// The app hash in the header of each trusted height
+    pub app_hashes: BTreeMap<u64, Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ForeignClient {
+    Tendermint(TendermintClient),
+}
+
+impl ForeignClient {
+    pub fn app_hash_at(&self, height: u64) -> Option<&[u8]> {
+        match self {
+            ForeignClient::Tendermint(client) => client.app_hashes.get(&height).map(Vec::as_slice),
+        }
+    }
+}
+
+fn decode_header(header: &[u8]) -> Result<Header, sgx_status_t> {
+    <Header as Protobuf<RawHeader>>::decode(header).map_err(|e| {
+        error!("Error parsing foreign header from proto: {:?}", e);
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    })
+}
+
+fn decode_validators(validators: &[u8]) -> Result<Set, sgx_status_t> {
+    KeychainMutableData::decode_validator_set_ex(validators)
+        .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+}
+
+fn time_from_nanos(nanos: i128) -> Result<Time, sgx_status_t> {
+    let secs = nanos.div_euclid(1_000_000_000) as i64;
+    let subsec_nanos = nanos.rem_euclid(1_000_000_000) as u32;
+    Time::from_unix_timestamp(secs, subsec_nanos).map_err(|e| {
+        error!("Invalid time {}: {:?}", nanos, e);
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    })
+}
+
+impl TendermintClient {
+    /// A client that trusts `header`. `next_validators` must be the validator set its
+    /// `next_validators_hash` commits to.
+    pub fn new(
+        chain_id: &str,
+        trusting_period_secs: u64,
+        trust_threshold: (u64, u64),
+        header: &[u8],
+        next_validators: &[u8],
+    ) -> Result<Self, sgx_status_t> {
+        TrustThreshold::new(trust_threshold.0, trust_threshold.1).map_err(|e| {
+            error!("Invalid trust threshold {:?}: {:?}", trust_threshold, e);
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        })?;
+
+        let header = decode_header(header)?;
+        if header.chain_id.as_str() != chain_id {
+            error!(
+                "Trusted header is of chain {}, not {}",
+                header.chain_id, chain_id
+            );
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        if decode_validators(next_validators)?.hash() != header.next_validators_hash {
+            error!("Next validators don't match the trusted header");
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut app_hashes = BTreeMap::new();
+        app_hashes.insert(header.height.value(), header.app_hash.as_bytes().to_vec());
+
+        Ok(Self {
+            trusting_period_secs,
+            trust_threshold,
+            height: header.height.value(),
+            time: header.time.unix_timestamp_nanos(),
+            next_validators: next_validators.to_vec(),
+            app_hashes,
+        })
+    }
+
+    /// Verify a newer header of the chain against the latest trusted one, and trust it.
+    /// `now` is the time of the current block, in nanoseconds.
+    pub fn update(
+        &mut self,
+        chain_id: &str,
+        header: &[u8],
+        commit: &[u8],
+        validators: &[u8],
+        next_validators: &[u8],
+        now: i128,
+    ) -> Result<(), sgx_status_t> {
+        let chain_id = ChainId::try_from(chain_id.to_string()).map_err(|e| {
+            error!("Invalid chain id {}: {:?}", chain_id, e);
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        })?;
+
+        let header = decode_header(header)?;
+        let commit = crate::verify::commit::decode(commit)?;
+        let signed_header = SignedHeader::new(header, commit).map_err(|e| {
+            error!("Error creating foreign signed header: {:?}", e);
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        })?;
+        let untrusted_validators = decode_validators(validators)?;
+        let untrusted_next_validators = decode_validators(next_validators)?;
+        let trusted_next_validators = decode_validators(&self.next_validators)?;
+
+        let untrusted = UntrustedBlockState {
+            signed_header: &signed_header,
+            validators: &untrusted_validators,
+            next_validators: Some(&untrusted_next_validators),
+        };
+        let trusted = TrustedBlockState {
+            chain_id: &chain_id,
+            header_time: time_from_nanos(self.time)?,
+            height: Height::try_from(self.height)
+                .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?,
+            next_validators: &trusted_next_validators,
+            next_validators_hash: trusted_next_validators.hash(),
+        };
+        let options = Options {
+            // checked when the client was created
+            trust_threshold: TrustThreshold::new(self.trust_threshold.0, self.trust_threshold.1)
+                .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?,
+            trusting_period: Duration::from_secs(self.trusting_period_secs),
+            clock_drift: CLOCK_DRIFT,
+        };
+
+        match VERIFIER.verify_update_header(untrusted, trusted, &options, time_from_nanos(now)?) {
+            Verdict::Success => {}
+            Verdict::NotEnoughTrust(_) => {
+                debug!("Error verifying foreign header - not enough trust");
+                return Err(sgx_status_t::SGX_ERROR_INVALID_SIGNATURE);
+            }
+            Verdict::Invalid(e) => {
+                debug!("Error verifying foreign header - invalid header: {:?}", e);
+                return Err(sgx_status_t::SGX_ERROR_INVALID_SIGNATURE);
+            }
+        }
+
+        let header = &signed_header.header;
+        self.height = header.height.value();
+        self.time = header.time.unix_timestamp_nanos();
+        self.next_validators = next_validators.to_vec();
+        self.app_hashes
+            .insert(self.height, header.app_hash.as_bytes().to_vec());
+        while self.app_hashes.len() > MAX_APP_HASHES {
+            self.app_hashes.pop_first();
+        }
+
+        Ok(())
+    }
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn read_array<const N: usize>(bytes: &mut &[u8]) -> Option<[u8; N]> {
+    if bytes.len() < N {
+        return None;
+    }
+    let (value, rest) = bytes.split_at(N);
+    *bytes = rest;
+    value.try_into().ok()
+}
+
+fn read_u64(bytes: &mut &[u8]) -> Option<u64> {
+    read_array(bytes).map(u64::from_le_bytes)
+}
+
+fn read_bytes(bytes: &mut &[u8]) -> Option<Vec<u8>> {
+    let len = read_u64(bytes)? as usize;
+    if bytes.len() < len {
+        return None;
+    }
+    let (value, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Some(value.to_vec())
+}
+
+/// All u64s are little endian, and byte strings are prefixed by their length as a u64:
+/// `kind || client`
+pub fn serialize_client(client: &ForeignClient) -> Vec<u8> {
+    let mut out = vec![];
+
+    match client {
+        ForeignClient::Tendermint(client) => {
+            out.push(KIND_TENDERMINT);
+            out.extend_from_slice(&client.trusting_period_secs.to_le_bytes());
+            out.extend_from_slice(&client.trust_threshold.0.to_le_bytes());
+            out.extend_from_slice(&client.trust_threshold.1.to_le_bytes());
+            out.extend_from_slice(&client.height.to_le_bytes());
+            out.extend_from_slice(&client.time.to_le_bytes());
+            write_bytes(&mut out, &client.next_validators);
+            out.extend_from_slice(&(client.app_hashes.len() as u64).to_le_bytes());
+            for (height, app_hash) in &client.app_hashes {
+                out.extend_from_slice(&height.to_le_bytes());
+                write_bytes(&mut out, app_hash);
+            }
+        }
+    }
+
+    out
+}
+
+pub fn deserialize_client(mut bytes: &[u8]) -> Option<ForeignClient> {
+    let bytes = &mut bytes;
+    let [kind] = read_array(bytes)?;
+    let client = match kind {
+        KIND_TENDERMINT => {
+            let trusting_period_secs = read_u64(bytes)?;
+            let trust_threshold = (read_u64(bytes)?, read_u64(bytes)?);
+            let height = read_u64(bytes)?;
+            let time = i128::from_le_bytes(read_array(bytes)?);
+            let next_validators = read_bytes(bytes)?;
+            let mut app_hashes = BTreeMap::new();
+            for _ in 0..read_u64(bytes)? {
+                let height = read_u64(bytes)?;
+                app_hashes.insert(height, read_bytes(bytes)?);
+            }
+
+            ForeignClient::Tendermint(TendermintClient {
+                trusting_period_secs,
+                trust_threshold,
+                height,
+                time,
+                next_validators,
+                app_hashes,
+            })
+        }
+        _ => {
+            error!("Unknown kind {} of a foreign client", kind);
+            return None;
+        }
+    };
+
+    if !bytes.is_empty() {
+        error!("Trailing bytes after a foreign client");
+        return None;
+    }
+    Some(client)
+}
+
+/// Answer contracts from `client` of `chain_id` until the next block. The caller must have
+/// verified that the client is in x/compute's state.
+pub fn add_client(chain_id: &str, client: ForeignClient) -> Result<(), sgx_status_t> {
+    let mut clients = FOREIGN_CLIENTS.write().unwrap();
+    if clients.len() >= MAX_FOREIGN_CLIENTS && !clients.contains_key(chain_id) {
+        warn!("Too many foreign clients, dropping the one of {}", chain_id);
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    clients.insert(chain_id.to_string(), client);
+    Ok(())
+}
+
+/// Forgets the clients, when a new block is verified
+pub fn clear_clients() {
+    FOREIGN_CLIENTS.write().unwrap().clear();
+}
+
+/// A client of `chain_id` that trusts `header`. The caller must have verified that governance
+/// created it.
+pub fn create_tendermint_client(
+    chain_id: &str,
+    trusting_period_secs: u64,
+    trust_threshold: (u64, u64),
+    header: &[u8],
+    next_validators: &[u8],
+) -> Result<ForeignClient, sgx_status_t> {
+    let client = TendermintClient::new(
+        chain_id,
+        trusting_period_secs,
+        trust_threshold,
+        header,
+        next_validators,
+    )?;
+
+    info!("Created the foreign client of {}", chain_id);
+    Ok(ForeignClient::Tendermint(client))
+}
+
+/// `client` of `chain_id`, updated with a newer header verified at the time of the current block
+pub fn update_tendermint_client(
+    client: &ForeignClient,
+    chain_id: &str,
+    header: &[u8],
+    commit: &[u8],
+    validators: &[u8],
+    next_validators: &[u8],
+) -> Result<ForeignClient, sgx_status_t> {
+    let now = VERIFIED_BLOCK_MESSAGES.lock().unwrap().time();
+
+    let ForeignClient::Tendermint(client) = client;
+    let mut updated = client.clone();
+    updated.update(chain_id, header, commit, validators, next_validators, now)?;
+
+    Ok(ForeignClient::Tendermint(updated))
+}
+
+/// The app hash in the header of `chain_id` at `height`, if the header is trusted
+pub fn app_hash_at(chain_id: &str, height: u64) -> Option<Vec<u8>> {
+    FOREIGN_CLIENTS
+        .read()
+        .unwrap()
+        .get(chain_id)?
+        .app_hash_at(height)
+        .map(<[u8]>::to_vec)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn tendermint_client(height: u64) -> TendermintClient {
+        let mut app_hashes = BTreeMap::new();
+        app_hashes.insert(height, vec![7u8; 32]);
+
+        TendermintClient {
+            trusting_period_secs: 14 * 24 * 60 * 60,
+            trust_threshold: (1, 3),
+            height,
+            time: 1_700_000_000_000_000_000,
+            next_validators: vec![1, 2, 3],
+            app_hashes,
+        }
+    }
+
+    pub fn test_foreign_clients_serialization() {
+        let client = ForeignClient::Tendermint(tendermint_client(100));
+
+        let serialized = serialize_client(&client);
+        assert_eq!(deserialize_client(&serialized), Some(client));
+
+        // truncated
+        assert_eq!(
+            deserialize_client(&serialized[..serialized.len() - 1]),
+            None
+        );
+        // trailing bytes
+        let mut trailing = serialized.clone();
+        trailing.push(0);
+        assert_eq!(deserialize_client(&trailing), None);
+        // unknown kind
+        let mut unknown = serialized;
+        unknown[0] = 0xff;
+        assert_eq!(deserialize_client(&unknown), None);
+    }
+
+    pub fn test_foreign_clients_app_hash_at() {
+        let client = ForeignClient::Tendermint(tendermint_client(100));
+        assert_eq!(client.app_hash_at(100), Some(&[7u8; 32][..]));
+        assert_eq!(client.app_hash_at(99), None);
+        assert_eq!(client.app_hash_at(101), None);
+
+        add_client("cosmoshub-4", client).unwrap();
+        assert_eq!(app_hash_at("cosmoshub-4", 100), Some(vec![7u8; 32]));
+        assert_eq!(app_hash_at("osmosis-1", 100), None);
+
+        clear_clients();
+        assert_eq!(app_hash_at("cosmoshub-4", 100), None);
+    }
+
+    pub fn test_foreign_clients_limit() {
+        for i in 0..MAX_FOREIGN_CLIENTS {
+            let client = ForeignClient::Tendermint(tendermint_client(100));
+            add_client(&i.to_string(), client).unwrap();
+        }
+
+        let client = ForeignClient::Tendermint(tendermint_client(101));
+        assert!(add_client("osmosis-1", client.clone()).is_err());
+        // a chain that has a client can replace it
+        add_client("0", client).unwrap();
+        assert_eq!(app_hash_at("0", 101), Some(vec![7u8; 32]));
+
+        clear_clients();
+    }
+
+    pub fn test_foreign_clients_reject_invalid_trusted_state() {
+        // not a header
+        assert!(TendermintClient::new("cosmoshub-4", 100, (1, 3), &[0xff], &[]).is_err());
+        // the trust threshold must be between 1/3 and 1
+        assert!(TendermintClient::new("cosmoshub-4", 100, (1, 4), &[], &[]).is_err());
+        assert!(TendermintClient::new("cosmoshub-4", 100, (2, 1), &[], &[]).is_err());
+    }
+}
@@ -4,6 +4,8 @@ extern crate sgx_tstd as std;
 extern crate alloc;
 extern crate sgx_types;
 
+pub mod foreign_clients;
+pub mod past_headers;
 pub mod wasm_messages;
 
 pub use wasm_messages::VERIFIED_BLOCK_MESSAGES;
@@ -13,7 +15,9 @@ mod txs;
 #[cfg(any(feature = "verify-validator-whitelist", feature = "test"))]
 pub mod validator_whitelist;
 
+pub mod state_proof;
 pub mod submit_block_signatures;
+pub mod submit_compute_params;
 mod verify;
 
 #[cfg(feature = "test")]
@@ -52,7 +56,14 @@ pub mod tests {
             crate::wasm_messages::tests::check_parse_reg_from_tx();
             crate::wasm_messages::tests::test_wasm_msg_tracker();
             crate::wasm_messages::tests::test_mix_wasm_bank_msg_tracker_multiple_msgs();
+            crate::wasm_messages::tests::test_resubmitted_block_keeps_order();
             crate::validator_whitelist::tests::test_parse_validators();
+            crate::state_proof::tests::test_state_proof();
+            crate::state_proof::tests::test_state_proof_specs();
+            crate::foreign_clients::tests::test_foreign_clients_serialization();
+            crate::foreign_clients::tests::test_foreign_clients_app_hash_at();
+            crate::foreign_clients::tests::test_foreign_clients_limit();
+            crate::foreign_clients::tests::test_foreign_clients_reject_invalid_trusted_state();
         });
 
         if failures != 0 {
@@ -0,0 +1,58 @@
+//! Headers of earlier blocks of this chain, which a tx carries and the enclave can't have verified
+//! itself, e.g. the block a proof of possession was made for.
+//!
+//! A header is verified with its commit against the validator set the light client verified the
+//! current block with, so every node's enclave accepts or rejects it the same way. Headers that
+//! an earlier validator set signed are rejected, and the tx has to carry a newer one.
+
+use tendermint::block::signed_header::SignedHeader;
+use tendermint::block::Header;
+use tendermint_proto::v0_38::types::Header as RawHeader;
+use tendermint_proto::Protobuf;
+
+use enclave_utils::KEY_MANAGER;
+use log::*;
+
+use crate::verify::commit;
+use crate::verify::header::verify_signed_header;
+
+/// What the enclave needs of the header of an earlier block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PastHeader {
+    pub chain_id: String,
+    pub height: u64,
+    /// The app hash in the header, which commits to the state after the block before it
+    pub app_hash: Vec<u8>,
+}
+
+impl From<&Header> for PastHeader {
+    fn from(header: &Header) -> Self {
+        Self {
+            chain_id: header.chain_id.as_str().to_string(),
+            height: header.height.value(),
+            app_hash: header.app_hash.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Verifies a protobuf encoded header and commit of this chain against the current validator
+/// set. `None` if they don't decode, or the current validators didn't sign them.
+pub fn verify_past_header(header_slice: &[u8], commit_slice: &[u8]) -> Option<PastHeader> {
+    let validator_set = KEY_MANAGER
+        .extra_data
+        .lock()
+        .unwrap()
+        .decode_validator_set()?;
+
+    let commit = commit::decode(commit_slice).ok()?;
+    let header = <Header as Protobuf<RawHeader>>::decode(header_slice)
+        .map_err(|e| debug!("Error parsing header from proto: {:?}", e))
+        .ok()?;
+    let signed_header = SignedHeader::new(header, commit)
+        .map_err(|e| debug!("Error creating signed header: {:?}", e))
+        .ok()?;
+
+    verify_signed_header(signed_header, &validator_set)
+        .ok()
+        .map(|signed_header| PastHeader::from(&signed_header.header))
+}
@@ -0,0 +1,495 @@
+//! Proofs of this chain's state, checked against the app hash of a verified header.
+//!
+//! A proof is an ICS-23 `MerkleProof`, as the IBC module builds it from the proof of an ABCI
+//! query: an IAVL existence proof of the value in its module's store, and a Tendermint existence
+//! proof of the store's root in the multistore, whose root is the app hash. The header of a
+//! block has the app hash of the state after the block before it, so the state a node queried at
+//! `height` is proven with the header of `height + 1`.
+//!
+//! Only existence proofs are accepted.
+
+use log::*;
+
+use enclave_crypto::sha_256;
+use enclave_utils::proto_fields::{fields, varint, varint_value, WIRE_TYPE_LEN, WIRE_TYPE_VARINT};
+
+use crate::wasm_messages::VERIFIED_BLOCK_MESSAGES;
+
+// ics23 `HashOp` and `LengthOp`
+const NO_HASH: u64 = 0;
+const SHA256: u64 = 1;
+const VAR_PROTO: u64 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateProofError {
+    /// The proof can't be decoded
+    Malformed,
+    /// The proof doesn't prove the value
+    InvalidProof,
+}
+
+fn malformed<T>(_: T) -> StateProofError {
+    StateProofError::Malformed
+}
+
+fn proof_fields(msg: &[u8]) -> Result<Vec<(u64, u64, &[u8])>, StateProofError> {
+    fields(msg).map_err(malformed)
+}
+
+fn proof_varint(value: &[u8]) -> Result<u64, StateProofError> {
+    varint_value(value).map_err(malformed)
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct LeafOp {
+    hash: u64,
+    prehash_key: u64,
+    prehash_value: u64,
+    length: u64,
+    prefix: Vec<u8>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct InnerOp {
+    hash: u64,
+    prefix: Vec<u8>,
+    suffix: Vec<u8>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct ExistenceProof {
+    key: Vec<u8>,
+    value: Vec<u8>,
+    leaf: LeafOp,
+    path: Vec<InnerOp>,
+}
+
+impl LeafOp {
+    fn parse(msg: &[u8]) -> Result<Self, StateProofError> {
+        let mut leaf = Self::default();
+        for (field, wire_type, value) in proof_fields(msg)? {
+            match (field, wire_type) {
+                (1, WIRE_TYPE_VARINT) => leaf.hash = proof_varint(value)?,
+                (2, WIRE_TYPE_VARINT) => leaf.prehash_key = proof_varint(value)?,
+                (3, WIRE_TYPE_VARINT) => leaf.prehash_value = proof_varint(value)?,
+                (4, WIRE_TYPE_VARINT) => leaf.length = proof_varint(value)?,
+                (5, WIRE_TYPE_LEN) => leaf.prefix = value.to_vec(),
+                _ => return Err(StateProofError::Malformed),
+            }
+        }
+        Ok(leaf)
+    }
+}
+
+impl InnerOp {
+    fn parse(msg: &[u8]) -> Result<Self, StateProofError> {
+        let mut inner = Self::default();
+        for (field, wire_type, value) in proof_fields(msg)? {
+            match (field, wire_type) {
+                (1, WIRE_TYPE_VARINT) => inner.hash = proof_varint(value)?,
+                (2, WIRE_TYPE_LEN) => inner.prefix = value.to_vec(),
+                (3, WIRE_TYPE_LEN) => inner.suffix = value.to_vec(),
+                _ => return Err(StateProofError::Malformed),
+            }
+        }
+        Ok(inner)
+    }
+}
+
+impl ExistenceProof {
+    fn parse(msg: &[u8]) -> Result<Self, StateProofError> {
+        let mut proof = Self::default();
+        for (field, wire_type, value) in proof_fields(msg)? {
+            match (field, wire_type) {
+                (1, WIRE_TYPE_LEN) => proof.key = value.to_vec(),
+                (2, WIRE_TYPE_LEN) => proof.value = value.to_vec(),
+                (3, WIRE_TYPE_LEN) => proof.leaf = LeafOp::parse(value)?,
+                (4, WIRE_TYPE_LEN) => proof.path.push(InnerOp::parse(value)?),
+                _ => return Err(StateProofError::Malformed),
+            }
+        }
+        Ok(proof)
+    }
+}
+
+/// The existence proofs of a `MerkleProof`, each a `CommitmentProof`. Other kinds of commitment
+/// proofs, e.g. batches, are rejected.
+fn parse_merkle_proof(msg: &[u8]) -> Result<Vec<ExistenceProof>, StateProofError> {
+    let mut proofs = vec![];
+    for (field, wire_type, commitment_proof) in proof_fields(msg)? {
+        if (field, wire_type) != (1, WIRE_TYPE_LEN) {
+            return Err(StateProofError::Malformed);
+        }
+        match proof_fields(commitment_proof)?.as_slice() {
+            [(1, WIRE_TYPE_LEN, exist)] => proofs.push(ExistenceProof::parse(exist)?),
+            _ => return Err(StateProofError::InvalidProof),
+        }
+    }
+    Ok(proofs)
+}
+
+/// The ics23 spec of the trees of a store, which keeps a proof from passing off an inner node as
+/// a leaf or the other way around
+struct ProofSpec {
+    min_prefix_length: usize,
+    max_prefix_length: usize,
+    child_size: usize,
+    iavl: bool,
+}
+
+const IAVL_SPEC: ProofSpec = ProofSpec {
+    min_prefix_length: 4,
+    max_prefix_length: 12,
+    child_size: 33,
+    iavl: true,
+};
+
+const TENDERMINT_SPEC: ProofSpec = ProofSpec {
+    min_prefix_length: 1,
+    max_prefix_length: 1,
+    child_size: 32,
+    iavl: false,
+};
+
+/// Both specs hash leaves with this prefix
+const LEAF_PREFIX: u8 = 0;
+
+/// A zigzag encoded varint, as IAVL writes the height, size and version of its nodes
+fn read_signed_varint(bytes: &mut &[u8]) -> Result<i64, StateProofError> {
+    let value = enclave_utils::proto_fields::read_varint(bytes).map_err(malformed)?;
+    Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+}
+
+/// The IAVL node header of a prefix, and what follows it
+fn iavl_node_header(mut prefix: &[u8]) -> Result<((i64, i64, i64), &[u8]), StateProofError> {
+    let height = read_signed_varint(&mut prefix)?;
+    let size = read_signed_varint(&mut prefix)?;
+    let version = read_signed_varint(&mut prefix)?;
+    Ok(((height, size, version), prefix))
+}
+
+impl ProofSpec {
+    fn check_leaf(&self, leaf: &LeafOp) -> Result<(), StateProofError> {
+        if leaf.hash != SHA256
+            || leaf.prehash_key != NO_HASH
+            || leaf.prehash_value != SHA256
+            || leaf.length != VAR_PROTO
+            || leaf.prefix.first() != Some(&LEAF_PREFIX)
+        {
+            return Err(StateProofError::InvalidProof);
+        }
+
+        if self.iavl {
+            // a leaf is at height 0, and has a size of 1
+            match iavl_node_header(&leaf.prefix)? {
+                ((0, 1, version), []) if version >= 0 => {}
+                _ => return Err(StateProofError::InvalidProof),
+            }
+        } else if leaf.prefix.len() != 1 {
+            return Err(StateProofError::InvalidProof);
+        }
+
+        Ok(())
+    }
+
+    /// Checks the inner node at `depth` above the leaf
+    fn check_inner(&self, inner: &InnerOp, depth: usize) -> Result<(), StateProofError> {
+        let prefix_len = inner.prefix.len();
+        if inner.hash != SHA256
+            || inner.prefix.first() == Some(&LEAF_PREFIX)
+            || prefix_len < self.min_prefix_length
+            || prefix_len > self.max_prefix_length + self.child_size
+        {
+            return Err(StateProofError::InvalidProof);
+        }
+
+        // The child is either on the left, with its sibling in the suffix, or on the right, with
+        // its sibling at the end of the prefix
+        let sibling_in_suffix = if self.iavl {
+            match iavl_node_header(&inner.prefix)? {
+                ((height, size, version), rest)
+                    if height > depth as i64 && size >= 0 && version >= 0 =>
+                {
+                    match rest {
+                        [32] => true,
+                        [32, .., 32] if rest.len() == 34 => false,
+                        _ => return Err(StateProofError::InvalidProof),
+                    }
+                }
+                _ => return Err(StateProofError::InvalidProof),
+            }
+        } else if prefix_len == self.min_prefix_length {
+            true
+        } else if prefix_len == self.min_prefix_length + self.child_size {
+            false
+        } else {
+            return Err(StateProofError::InvalidProof);
+        };
+
+        let expected_suffix_len = if sibling_in_suffix {
+            self.child_size
+        } else {
+            0
+        };
+        if inner.suffix.len() != expected_suffix_len
+            || (self.iavl && sibling_in_suffix && inner.suffix[0] != 32)
+        {
+            return Err(StateProofError::InvalidProof);
+        }
+
+        Ok(())
+    }
+
+    /// The root `proof` proves `key` and `value` in
+    fn calculate_root(
+        &self,
+        proof: &ExistenceProof,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<[u8; 32], StateProofError> {
+        if proof.key != key || proof.value != value {
+            return Err(StateProofError::InvalidProof);
+        }
+        self.check_leaf(&proof.leaf)?;
+
+        let mut node = proof.leaf.prefix.clone();
+        node.extend_from_slice(&varint(key.len() as u64));
+        node.extend_from_slice(key);
+        node.extend_from_slice(&varint(32));
+        node.extend_from_slice(&sha_256(value));
+        let mut hash = sha_256(&node);
+
+        for (depth, inner) in proof.path.iter().enumerate() {
+            self.check_inner(inner, depth)?;
+            hash = sha_256(&[&inner.prefix[..], &hash[..], &inner.suffix[..]].concat());
+        }
+
+        Ok(hash)
+    }
+}
+
+/// The app hash `proof` proves that `value` is at `key` of the module store `store_key` under
+pub fn prove_state(
+    store_key: &[u8],
+    key: &[u8],
+    value: &[u8],
+    proof: &[u8],
+) -> Result<[u8; 32], StateProofError> {
+    let proofs = parse_merkle_proof(proof)?;
+    let (store_proof, multistore_proof) = match proofs.as_slice() {
+        [store_proof, multistore_proof] => (store_proof, multistore_proof),
+        _ => return Err(StateProofError::InvalidProof),
+    };
+
+    let store_root = IAVL_SPEC.calculate_root(store_proof, key, value)?;
+    TENDERMINT_SPEC.calculate_root(multistore_proof, store_key, &store_root)
+}
+
+/// Checks that `proof` proves `value` at `key` of the module store `store_key`, in the state the
+/// current block was executed on
+pub fn verify_current_state(
+    store_key: &[u8],
+    key: &[u8],
+    value: &[u8],
+    proof: &[u8],
+) -> Result<(), StateProofError> {
+    let proven = prove_state(store_key, key, value, proof)?;
+
+    let verified_msgs = VERIFIED_BLOCK_MESSAGES.lock().unwrap();
+    if proven[..] != verified_msgs.app_hash()[..] {
+        debug!(
+            "state proof doesn't match the app hash of block {}",
+            verified_msgs.height()
+        );
+        return Err(StateProofError::InvalidProof);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn len_field(number: u64, value: &[u8]) -> Vec<u8> {
+        let mut encoded = varint(number << 3 | WIRE_TYPE_LEN);
+        encoded.extend(varint(value.len() as u64));
+        encoded.extend_from_slice(value);
+        encoded
+    }
+
+    pub fn varint_field(number: u64, value: u64) -> Vec<u8> {
+        let mut encoded = varint(number << 3 | WIRE_TYPE_VARINT);
+        encoded.extend(varint(value));
+        encoded
+    }
+
+    fn existence_proof(
+        key: &[u8],
+        value: &[u8],
+        leaf_prefix: &[u8],
+        path: &[(Vec<u8>, Vec<u8>)],
+    ) -> Vec<u8> {
+        let leaf = [
+            varint_field(1, SHA256),
+            varint_field(3, SHA256),
+            varint_field(4, VAR_PROTO),
+            len_field(5, leaf_prefix),
+        ]
+        .concat();
+
+        let mut proof = [len_field(1, key), len_field(2, value), len_field(3, &leaf)].concat();
+        for (prefix, suffix) in path {
+            let inner = [
+                varint_field(1, SHA256),
+                len_field(2, prefix),
+                len_field(3, suffix),
+            ]
+            .concat();
+            proof.extend(len_field(4, &inner));
+        }
+
+        proof
+    }
+
+    /// A `CommitmentProof` with an existence proof
+    fn commitment_proof(existence_proof: Vec<u8>) -> Vec<u8> {
+        len_field(1, &existence_proof)
+    }
+
+    fn leaf_hash(prefix: &[u8], key: &[u8], value: &[u8]) -> [u8; 32] {
+        sha_256(
+            &[
+                prefix,
+                &[key.len() as u8][..],
+                key,
+                &[32u8][..],
+                &sha_256(value)[..],
+            ]
+            .concat(),
+        )
+    }
+
+    /// A proof of `value` at `key` of the store `store_key`, and the app hash it proves
+    pub fn proven_state(store_key: &[u8], key: &[u8], value: &[u8]) -> (Vec<u8>, [u8; 32]) {
+        // height 0, size 1, version 5
+        let leaf_prefix = [0, 2, 10];
+        // height 1, size 2, version 5, with the sibling on the right
+        let inner_prefix = vec![2, 4, 10, 32];
+        let inner_suffix = [&[32u8][..], &[9u8; 32][..]].concat();
+        let store_root = sha_256(
+            &[
+                &inner_prefix[..],
+                &leaf_hash(&leaf_prefix, key, value)[..],
+                &inner_suffix[..],
+            ]
+            .concat(),
+        );
+
+        // the sibling on the left
+        let multistore_prefix = [&[1u8][..], &[8u8; 32][..]].concat();
+        let app_hash = sha_256(
+            &[
+                &multistore_prefix[..],
+                &leaf_hash(&[0], store_key, &store_root)[..],
+            ]
+            .concat(),
+        );
+
+        let store_proof =
+            existence_proof(key, value, &leaf_prefix, &[(inner_prefix, inner_suffix)]);
+        let multistore_proof =
+            existence_proof(store_key, &store_root, &[0], &[(multistore_prefix, vec![])]);
+        let proof = [
+            len_field(1, &commitment_proof(store_proof)),
+            len_field(1, &commitment_proof(multistore_proof)),
+        ]
+        .concat();
+
+        (proof, app_hash)
+    }
+
+    pub fn test_state_proof() {
+        let (proof, app_hash) = proven_state(b"compute", &[0x0B], b"params");
+        assert_eq!(
+            prove_state(b"compute", &[0x0B], b"params", &proof),
+            Ok(app_hash)
+        );
+
+        // another store
+        assert_eq!(
+            prove_state(b"bank", &[0x0B], b"params", &proof),
+            Err(StateProofError::InvalidProof)
+        );
+        // another key
+        assert_eq!(
+            prove_state(b"compute", &[0x0C], b"params", &proof),
+            Err(StateProofError::InvalidProof)
+        );
+        // another value
+        assert_eq!(
+            prove_state(b"compute", &[0x0B], b"other params", &proof),
+            Err(StateProofError::InvalidProof)
+        );
+        // only the store proof
+        let (store_proof, _) = proof.split_at(proof.len() / 2);
+        assert!(prove_state(b"compute", &[0x0B], b"params", store_proof).is_err());
+    }
+
+    pub fn test_state_proof_specs() {
+        let key = b"key";
+        let leaf = ExistenceProof::parse(&existence_proof(key, b"v", &[0, 2, 10], &[])).unwrap();
+        assert!(IAVL_SPEC.calculate_root(&leaf, key, b"v").is_ok());
+        assert_eq!(
+            IAVL_SPEC.calculate_root(&leaf, key, b"w"),
+            Err(StateProofError::InvalidProof)
+        );
+
+        // a leaf with more than the node header in its prefix
+        let proof =
+            ExistenceProof::parse(&existence_proof(key, b"v", &[0, 2, 2, 10], &[])).unwrap();
+        assert_eq!(
+            IAVL_SPEC.calculate_root(&proof, key, b"v"),
+            Err(StateProofError::InvalidProof)
+        );
+
+        // an inner node passing as a leaf
+        let proof =
+            ExistenceProof::parse(&existence_proof(key, b"v", &[2, 4, 10, 32], &[])).unwrap();
+        assert_eq!(
+            IAVL_SPEC.calculate_root(&proof, key, b"v"),
+            Err(StateProofError::InvalidProof)
+        );
+
+        // an inner node with the sibling both in its prefix and its suffix
+        let prefix = [&[2u8, 4, 10, 32][..], &[8u8; 32][..], &[32u8][..]].concat();
+        let suffix = [&[32u8][..], &[9u8; 32][..]].concat();
+        let proof = ExistenceProof::parse(&existence_proof(
+            key,
+            b"v",
+            &[0, 2, 10],
+            &[(prefix, suffix)],
+        ))
+        .unwrap();
+        assert_eq!(
+            IAVL_SPEC.calculate_root(&proof, key, b"v"),
+            Err(StateProofError::InvalidProof)
+        );
+
+        // a multistore node with a prefix of neither length
+        let proof =
+            ExistenceProof::parse(&existence_proof(key, b"v", &[0], &[(vec![1; 5], vec![])]))
+                .unwrap();
+        assert_eq!(
+            TENDERMINT_SPEC.calculate_root(&proof, key, b"v"),
+            Err(StateProofError::InvalidProof)
+        );
+
+        // proofs of absence aren't existence proofs
+        let absence = len_field(1, &len_field(2, &len_field(1, key)));
+        assert_eq!(
+            parse_merkle_proof(&absence).unwrap_err(),
+            StateProofError::InvalidProof
+        );
+    }
+}
@@ -2,6 +2,7 @@ use std::slice;
 
 use sgx_types::sgx_status_t;
 
+use enclave_utils::compute_params::clear_compute_params;
 use enclave_utils::{validate_const_ptr, validate_input_length, validate_mut_ptr, KEY_MANAGER};
 use log::debug;
 use log::error;
@@ -15,6 +16,7 @@ macro_rules! unwrap_or_return {
     };
 }
 
+use crate::foreign_clients::clear_clients;
 use crate::txs::tx_from_bytes;
 use crate::wasm_messages::VERIFIED_BLOCK_MESSAGES;
 
@@ -84,14 +86,24 @@ pub unsafe fn submit_block_signatures_impl(
         commit,
     ));
 
+    // Keys bound to a network are only used for the blocks of its chain
+    if let Some(context) = KEY_MANAGER.get_consensus_seed_context().current {
+        if header.header.chain_id.as_str() != context.chain_id {
+            error!(
+                "Block of chain {} but the consensus keys are bound to {}",
+                header.header.chain_id, context.chain_id
+            );
+            return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        }
+    }
+
     let txs = unwrap_or_return!(crate::verify::txs::validate_txs(txs_slice, &header));
 
     let mut message_verifier = VERIFIED_BLOCK_MESSAGES.lock().unwrap();
 
-    if message_verifier.remaining() != 0 {
-        // new block, clear messages
-        message_verifier.clear();
-    }
+    // new block, clear messages. If it's the same block again, the messages that were already
+    // consumed aren't restored, so its messages can't be run in another order
+    message_verifier.start_block(header.header.height.value());
 
     for tx in txs.iter() {
         // doing this a different way makes the code unreadable or requires creating a copy of
@@ -108,6 +120,13 @@ pub unsafe fn submit_block_signatures_impl(
         header.header.height.value(),
         header.header.time.unix_timestamp_nanos(),
     );
+    message_verifier.set_chain_id(header.header.chain_id.as_str());
+    message_verifier.set_app_hash(header.header.app_hash.as_bytes());
+
+    // the params and foreign clients of the last block don't hold for this one, until they're
+    // proven again
+    clear_compute_params();
+    clear_clients();
 
     #[cfg(feature = "random")]
     {
@@ -0,0 +1,71 @@
+use std::slice;
+
+use log::*;
+use sgx_types::sgx_status_t;
+
+use enclave_utils::compute_params::{
+    set_compute_params, ComputeParams, COMPUTE_STORE_KEY, MAX_PARAMS_LENGTH, PARAMS_KEY,
+};
+use enclave_utils::{validate_const_ptr, validate_input_length};
+
+use crate::state_proof::verify_current_state;
+
+const MAX_PROOF_LENGTH: u32 = 100_000;
+
+/// Sets the params of the compute module, if `in_proof` proves them in the state the current
+/// block is executed on
+///
+/// # Safety
+/// `in_params` and `in_proof` must point to `in_params_len` and `in_proof_len` readable bytes
+pub unsafe fn submit_compute_params_impl(
+    in_params: *const u8,
+    in_params_len: u32,
+    in_proof: *const u8,
+    in_proof_len: u32,
+) -> sgx_status_t {
+    validate_input_length!(
+        in_params_len,
+        "params",
+        MAX_PARAMS_LENGTH,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    validate_input_length!(
+        in_proof_len,
+        "proof",
+        MAX_PROOF_LENGTH,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    if in_params_len == 0 || in_proof_len == 0 {
+        error!("Compute params must come with a proof of them");
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    validate_const_ptr!(
+        in_params,
+        in_params_len as usize,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    validate_const_ptr!(
+        in_proof,
+        in_proof_len as usize,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+
+    let params = slice::from_raw_parts(in_params, in_params_len as usize);
+    let proof = slice::from_raw_parts(in_proof, in_proof_len as usize);
+
+    if let Err(e) = verify_current_state(COMPUTE_STORE_KEY, PARAMS_KEY, params, proof) {
+        error!("Failed to verify the compute params: {:?}", e);
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+
+    match ComputeParams::parse(params) {
+        Ok(params) => {
+            set_compute_params(params);
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(_) => {
+            error!("Error parsing the compute params from proto");
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        }
+    }
+}
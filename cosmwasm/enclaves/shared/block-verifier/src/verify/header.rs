@@ -32,6 +32,14 @@ pub fn validate_block_header(
         return Err(sgx_status_t::SGX_ERROR_FILE_RECOVERY_NEEDED);
     }
 
+    verify_signed_header(signed_header, validator_set)
+}
+
+/// Verifies that `validator_set` signed the header, at whatever height it is
+pub fn verify_signed_header(
+    signed_header: SignedHeader,
+    validator_set: &Set,
+) -> Result<SignedHeader, sgx_status_t> {
     let untrusted_block = UntrustedBlockState {
         signed_header: &signed_header,
         validators: validator_set,
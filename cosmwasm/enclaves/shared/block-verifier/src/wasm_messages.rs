@@ -11,26 +11,55 @@ pub fn message_is_reg(msg: &protobuf::well_known_types::Any) -> bool {
     )
 }
 
+/// The messages of the last verified block, which the enclave hands out in the order of the block.
+///
+/// Every message has a position in the block, and once a message was handed out, neither it nor
+/// the messages before it are handed out again at the same height, even if the host submits the
+/// block again. Otherwise the host could run the messages of a block in any order it likes, and
+/// probe how the state depends on that order.
 #[derive(Debug, Clone, Default)]
 pub struct VerifiedBlockMessages {
-    messages: VecDeque<Vec<u8>>,
+    messages: VecDeque<(u64, Vec<u8>)>,
+    /// The position of the next message of the block to be appended
+    next_position: u64,
+    /// The number of messages of the block that were handed out or skipped
+    consumed: u64,
     height: u64,
     time: i128,
+    chain_id: String,
+    /// The app hash of the block, which commits to the state it's executed on
+    app_hash: Vec<u8>,
     pub next_validators_evidence: [u8; 32],
 }
 
 impl VerifiedBlockMessages {
     pub fn get_next(&mut self) -> Option<Vec<u8>> {
-        self.messages.pop_front()
+        let (position, msg) = self.messages.pop_front()?;
+        self.consumed = position + 1;
+        Some(msg)
     }
 
     pub fn remaining(&self) -> usize {
         self.messages.len()
     }
 
+    /// Start over with the messages of the block at `height`. If it's the block that was already
+    /// verified, the messages that were handed out or skipped stay consumed.
+    pub fn start_block(&mut self, height: u64) {
+        self.messages.clear();
+        self.next_position = 0;
+        if height != self.height {
+            self.consumed = 0;
+        }
+    }
+
     pub fn append_msg_from_tx(&mut self, mut tx: Tx) {
         for msg in tx.take_body().messages {
-            self.messages.push_back(msg.value);
+            let position = self.next_position;
+            self.next_position += 1;
+            if position >= self.consumed {
+                self.messages.push_back((position, msg.value));
+            }
         }
     }
 
@@ -39,15 +68,31 @@ impl VerifiedBlockMessages {
         self.time = time;
     }
 
+    pub fn set_chain_id(&mut self, chain_id: &str) {
+        self.chain_id = chain_id.to_string();
+    }
+
+    pub fn set_app_hash(&mut self, app_hash: &[u8]) {
+        self.app_hash = app_hash.to_vec();
+    }
+
     pub fn height(&self) -> u64 {
         self.height
     }
     pub fn time(&self) -> i128 {
         self.time
     }
+    /// The chain id in the header of the block
+    pub fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
+    pub fn app_hash(&self) -> &[u8] {
+        &self.app_hash
+    }
 
     pub fn clear(&mut self) {
-        self.messages.clear()
+        self.messages.clear();
+        self.consumed = self.next_position;
     }
 }
 
@@ -218,5 +263,37 @@ pub mod tests {
             1 as usize
         );
     }
-    //
+
+    pub fn test_resubmitted_block_keeps_order() {
+        let tx_bytes = hex::decode(TX_RAW_2_WASM_1_BANK_MSG).unwrap();
+        let tx = protoTx::tx::Tx::parse_from_bytes(tx_bytes.as_slice()).unwrap();
+        let ref_msgs = tx.body.clone().unwrap().messages;
+
+        let mut verified_msgs = super::VerifiedBlockMessages::default();
+        verified_msgs.start_block(10);
+        verified_msgs.append_msg_from_tx(tx.clone());
+        verified_msgs.set_block_info(10, 0);
+
+        // Skip the first message, and hand out the second
+        verified_msgs.get_next();
+        assert_eq!(verified_msgs.get_next().unwrap(), ref_msgs[1].value);
+
+        // Submitting the block again doesn't give back the messages before the third
+        verified_msgs.start_block(10);
+        verified_msgs.append_msg_from_tx(tx.clone());
+        assert_eq!(verified_msgs.remaining(), 1);
+
+        // A failure consumes the rest of the block
+        verified_msgs.clear();
+        verified_msgs.start_block(10);
+        verified_msgs.append_msg_from_tx(tx.clone());
+        assert_eq!(verified_msgs.remaining(), 0);
+
+        // The next block starts over
+        verified_msgs.start_block(11);
+        verified_msgs.append_msg_from_tx(tx);
+        verified_msgs.set_block_info(11, 0);
+        assert_eq!(verified_msgs.remaining(), 3);
+        assert_eq!(verified_msgs.get_next().unwrap(), ref_msgs[0].value);
+    }
 }
@@ -0,0 +1,207 @@
+//! Execution transcripts of audit builds.
+//!
+//! Enclaves built with the `audit` feature run with well-known keys (see
+//! `enclave_utils::key_manager::audit_seeds`), and record everything each contract execution does:
+//! when it starts, with the plaintext msg, every host function it calls, every storage access with
+//! the plaintext key and value, and how it ends, with the gas used and the plaintext output. The
+//! transcript is handed out with `ecall_take_audit_transcript`, which also clears it.
+//!
+//! Nothing in the transcript depends on the machine or the time it ran at, so two runs of the same
+//! txs give the same transcript, and the outputs and gas can be compared with those of a
+//! production node. Executions a contract triggers from a host function, like queries of other
+//! contracts, are recorded in between the steps of the execution that triggered them.
+//!
+//! The transcript holds plaintext contract state, so the feature can't be combined with
+//! `production`.
+
+use std::sync::SgxMutex;
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+
+use enclave_ffi_types::EnclaveError;
+
+use crate::errors::WasmEngineError;
+
+/// Steps past this are dropped, and the transcript is marked as truncated
+const MAX_TRANSCRIPT_LEN: usize = 100_000;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum AuditStep {
+    Start {
+        operation: String,
+        msg: String,
+    },
+    HostCall {
+        name: String,
+        gas_remaining: u64,
+    },
+    HostError {
+        name: String,
+        error: String,
+    },
+    Read {
+        key: String,
+        value: Option<String>,
+    },
+    Write {
+        key: String,
+        value: String,
+    },
+    Remove {
+        key: String,
+    },
+    End {
+        gas_used: u64,
+        output: Option<String>,
+        error: Option<String>,
+    },
+}
+
+#[derive(Serialize, Debug, Default)]
+struct Transcript {
+    steps: Vec<AuditStep>,
+    truncated: bool,
+}
+
+lazy_static! {
+    static ref TRANSCRIPT: SgxMutex<Transcript> = SgxMutex::new(Transcript::default());
+}
+
+fn push(step: AuditStep) {
+    let mut transcript = TRANSCRIPT.lock().unwrap();
+    if transcript.steps.len() < MAX_TRANSCRIPT_LEN {
+        transcript.steps.push(step);
+    } else {
+        transcript.truncated = true;
+    }
+}
+
+pub fn record_start(operation: &str, msg: &[u8]) {
+    push(AuditStep::Start {
+        operation: operation.to_string(),
+        msg: hex::encode(msg),
+    });
+}
+
+pub fn record_host_call(name: &str, gas_remaining: u64) {
+    push(AuditStep::HostCall {
+        name: name.to_string(),
+        gas_remaining,
+    });
+}
+
+pub fn record_host_error(name: &str, error: &WasmEngineError) {
+    push(AuditStep::HostError {
+        name: name.to_string(),
+        error: error.to_string(),
+    });
+}
+
+pub fn record_read(key: &[u8], value: Option<&[u8]>) {
+    push(AuditStep::Read {
+        key: hex::encode(key),
+        value: value.map(hex::encode),
+    });
+}
+
+pub fn record_write(key: &[u8], value: &[u8]) {
+    push(AuditStep::Write {
+        key: hex::encode(key),
+        value: hex::encode(value),
+    });
+}
+
+pub fn record_remove(key: &[u8]) {
+    push(AuditStep::Remove {
+        key: hex::encode(key),
+    });
+}
+
+pub fn record_end(gas_used: u64, result: &Result<Vec<u8>, EnclaveError>) {
+    push(AuditStep::End {
+        gas_used,
+        output: result.as_ref().ok().map(hex::encode),
+        error: result.as_ref().err().map(|err| err.to_string()),
+    });
+}
+
+/// The transcript recorded since it was last taken, as json
+pub fn encoded_transcript() -> Vec<u8> {
+    let transcript = TRANSCRIPT.lock().unwrap();
+    serde_json::to_vec(&*transcript).unwrap_or_default()
+}
+
+pub fn clear_transcript() {
+    *TRANSCRIPT.lock().unwrap() = Transcript::default();
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_audit_transcript_encoding() {
+        clear_transcript();
+
+        record_start("handle", b"{}");
+        record_host_call("db_read", 100);
+        record_read(b"a", None);
+        record_host_call("db_write", 90);
+        record_write(b"a", b"1");
+        record_host_call("db_remove", 80);
+        record_remove(b"a");
+        record_host_error("db_remove", &WasmEngineError::UnauthorizedWrite);
+        record_end(20, &Err(EnclaveError::OutOfGas));
+        record_end(10, &Ok(b"ok".to_vec()));
+
+        let encoded: serde_json::Value = serde_json::from_slice(&encoded_transcript()).unwrap();
+        assert_eq!(encoded["truncated"], false);
+
+        let steps = encoded["steps"].as_array().unwrap();
+        assert_eq!(steps.len(), 10);
+        assert_eq!(
+            steps[0],
+            serde_json::json!({"op": "start", "operation": "handle", "msg": "7b7d"})
+        );
+        assert_eq!(
+            steps[1],
+            serde_json::json!({"op": "host_call", "name": "db_read", "gas_remaining": 100})
+        );
+        assert_eq!(
+            steps[2],
+            serde_json::json!({"op": "read", "key": "61", "value": null})
+        );
+        assert_eq!(steps[7]["op"], "host_error");
+        assert_eq!(steps[8]["output"], serde_json::Value::Null);
+        assert!(steps[8]["error"].is_string());
+        assert_eq!(
+            steps[9],
+            serde_json::json!({"op": "end", "gas_used": 10, "output": "6f6b", "error": null})
+        );
+
+        // taking the transcript clears it
+        clear_transcript();
+        assert_eq!(
+            encoded_transcript(),
+            br#"{"steps":[],"truncated":false}"#.to_vec()
+        );
+    }
+
+    pub fn test_audit_transcript_truncation() {
+        clear_transcript();
+
+        for _ in 0..MAX_TRANSCRIPT_LEN + 1 {
+            record_remove(b"a");
+        }
+
+        let encoded: serde_json::Value = serde_json::from_slice(&encoded_transcript()).unwrap();
+        assert_eq!(encoded["truncated"], true);
+        assert_eq!(
+            encoded["steps"].as_array().unwrap().len(),
+            MAX_TRANSCRIPT_LEN
+        );
+
+        clear_transcript();
+    }
+}
@@ -0,0 +1,282 @@
+//! Wide integer arithmetic and modular exponentiation, which RSA verification, BLS utilities and
+//! bridges need, but which cost a lot of gas when implemented in wasm.
+//!
+//! * 512-bit integers are 64 byte big-endian numbers, either unsigned or two's complement.
+//!   Operations fail rather than wrap when their result doesn't fit.
+//! * `modexp` has the semantics of the EVM's MODEXP precompile (EIP-198), and is priced like it
+//!   (EIP-2565), so contracts ported from Solidity get the same results for the same relative
+//!   cost.
+
+use std::cmp::max;
+
+use num_bigint::{BigInt, BigUint, Sign};
+
+/// Error codes returned to contracts by the big integer host functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum BigIntError {
+    /// An operand has the wrong length, or the operation is unknown
+    InvalidInput = 1,
+    /// The result doesn't fit in 512 bits
+    Overflow = 2,
+    DivisionByZero = 3,
+}
+
+/// The operations of `uint512_op` and `int512_op`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    /// Rounded towards zero
+    Div,
+    /// With the sign of the dividend
+    Rem,
+}
+
+impl Op {
+    fn from_u32(op: u32) -> Result<Self, BigIntError> {
+        match op {
+            0 => Ok(Op::Add),
+            1 => Ok(Op::Sub),
+            2 => Ok(Op::Mul),
+            3 => Ok(Op::Div),
+            4 => Ok(Op::Rem),
+            _ => Err(BigIntError::InvalidInput),
+        }
+    }
+}
+
+const INT512_BYTES: usize = 64;
+
+/// The longest base, exponent or modulus of `modexp`, i.e. 8192 bits
+pub const MAX_MODEXP_OPERAND_BYTES: usize = 1024;
+
+/// The minimal price of `modexp`, in EVM gas
+const MIN_MODEXP_EVM_GAS: u64 = 200;
+
+fn decode(bytes: &[u8], signed: bool) -> Result<BigInt, BigIntError> {
+    if bytes.len() != INT512_BYTES {
+        return Err(BigIntError::InvalidInput);
+    }
+
+    if signed {
+        Ok(BigInt::from_signed_bytes_be(bytes))
+    } else {
+        Ok(BigInt::from_bytes_be(Sign::Plus, bytes))
+    }
+}
+
+fn encode(value: &BigInt, signed: bool) -> Result<Vec<u8>, BigIntError> {
+    let (fill, bytes) = match (signed, value.sign()) {
+        (false, Sign::Minus) => return Err(BigIntError::Overflow),
+        (false, _) => (0, value.to_bytes_be().1),
+        (true, Sign::Minus) => (0xff, value.to_signed_bytes_be()),
+        (true, _) => (0, value.to_signed_bytes_be()),
+    };
+    if bytes.len() > INT512_BYTES {
+        return Err(BigIntError::Overflow);
+    }
+
+    let mut result = vec![fill; INT512_BYTES - bytes.len()];
+    result.extend_from_slice(&bytes);
+    Ok(result)
+}
+
+fn int512_op_impl(op: u32, a: &[u8], b: &[u8], signed: bool) -> Result<Vec<u8>, BigIntError> {
+    let op = Op::from_u32(op)?;
+    let (a, b) = (decode(a, signed)?, decode(b, signed)?);
+    if (op == Op::Div || op == Op::Rem) && b.sign() == Sign::NoSign {
+        return Err(BigIntError::DivisionByZero);
+    }
+
+    let result = match op {
+        Op::Add => a + b,
+        Op::Sub => a - b,
+        Op::Mul => a * b,
+        Op::Div => a / b,
+        Op::Rem => a % b,
+    };
+    encode(&result, signed)
+}
+
+/// An operation on two unsigned 512-bit integers
+pub fn uint512_op(op: u32, a: &[u8], b: &[u8]) -> Result<Vec<u8>, BigIntError> {
+    int512_op_impl(op, a, b, false)
+}
+
+/// An operation on two signed 512-bit integers
+pub fn int512_op(op: u32, a: &[u8], b: &[u8]) -> Result<Vec<u8>, BigIntError> {
+    int512_op_impl(op, a, b, true)
+}
+
+/// The price of `modexp` in EVM gas, as defined by EIP-2565
+pub fn modexp_evm_gas(base_len: usize, exponent: &[u8], modulus_len: usize) -> u64 {
+    let words = (max(base_len, modulus_len) as u64 + 7) / 8;
+    let multiplication_complexity = words.saturating_mul(words);
+
+    // The bit length of the exponent, of which only the first 32 bytes are actually counted
+    let head = &exponent[..exponent.len().min(32)];
+    let head_bits = BigUint::from_bytes_be(head).bits() as u64;
+    let tail_bits = 8u64.saturating_mul(exponent.len().saturating_sub(32) as u64);
+    let iteration_count = max(tail_bits.saturating_add(head_bits).saturating_sub(1), 1);
+
+    max(
+        MIN_MODEXP_EVM_GAS,
+        multiplication_complexity.saturating_mul(iteration_count) / 3,
+    )
+}
+
+/// base^exponent % modulus, as big-endian numbers of any length up to
+/// `MAX_MODEXP_OPERAND_BYTES`. The result is as long as the modulus, and is 0 if the modulus is.
+pub fn modexp(base: &[u8], exponent: &[u8], modulus: &[u8]) -> Result<Vec<u8>, BigIntError> {
+    if [base, exponent, modulus]
+        .iter()
+        .any(|operand| operand.len() > MAX_MODEXP_OPERAND_BYTES)
+    {
+        return Err(BigIntError::InvalidInput);
+    }
+
+    let mut result = vec![0u8; modulus.len()];
+
+    // Everything is 0 modulo 1, and there's nothing to compute modulo 0
+    let modulus = BigUint::from_bytes_be(modulus);
+    if modulus.bits() <= 1 {
+        return Ok(result);
+    }
+
+    let value = BigUint::from_bytes_be(base)
+        .modpow(&BigUint::from_bytes_be(exponent), &modulus)
+        .to_bytes_be();
+
+    // The value is smaller than the modulus, so it fits
+    let offset = result.len() - value.len();
+    result[offset..].copy_from_slice(&value);
+    Ok(result)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    const ADD: u32 = 0;
+    const SUB: u32 = 1;
+    const MUL: u32 = 2;
+    const DIV: u32 = 3;
+    const REM: u32 = 4;
+
+    fn int512(value: i128) -> Vec<u8> {
+        let fill = if value < 0 { 0xff } else { 0 };
+        let mut bytes = vec![fill; INT512_BYTES - 16];
+        bytes.extend_from_slice(&value.to_be_bytes());
+        bytes
+    }
+
+    pub fn test_big_int_uint512() {
+        let max = vec![0xff; INT512_BYTES];
+        let mut max_minus_one = max.clone();
+        max_minus_one[INT512_BYTES - 1] = 0xfe;
+
+        assert_eq!(uint512_op(ADD, &int512(2), &int512(3)).unwrap(), int512(5));
+        assert_eq!(uint512_op(SUB, &max, &int512(1)).unwrap(), max_minus_one);
+        assert_eq!(uint512_op(DIV, &max, &max).unwrap(), int512(1));
+        assert_eq!(uint512_op(REM, &int512(17), &int512(5)).unwrap(), int512(2));
+
+        // 2^128 * 2^128 doesn't fit in an i128, but does fit in 512 bits
+        let mut two_to_256 = vec![0; INT512_BYTES];
+        two_to_256[INT512_BYTES - 33] = 1;
+        let mut two_to_128 = vec![0; INT512_BYTES];
+        two_to_128[INT512_BYTES - 17] = 1;
+        assert_eq!(
+            uint512_op(MUL, &two_to_128, &two_to_128).unwrap(),
+            two_to_256
+        );
+
+        assert_eq!(
+            uint512_op(ADD, &max, &int512(1)),
+            Err(BigIntError::Overflow)
+        );
+        assert_eq!(
+            uint512_op(SUB, &int512(1), &int512(2)),
+            Err(BigIntError::Overflow)
+        );
+        assert_eq!(
+            uint512_op(MUL, &two_to_256, &two_to_256),
+            Err(BigIntError::Overflow)
+        );
+        assert_eq!(
+            uint512_op(DIV, &int512(1), &int512(0)),
+            Err(BigIntError::DivisionByZero)
+        );
+        assert_eq!(
+            uint512_op(5, &int512(1), &int512(1)),
+            Err(BigIntError::InvalidInput)
+        );
+        assert_eq!(
+            uint512_op(ADD, &int512(1)[1..], &int512(1)),
+            Err(BigIntError::InvalidInput)
+        );
+    }
+
+    pub fn test_big_int_int512() {
+        assert_eq!(int512_op(SUB, &int512(2), &int512(3)).unwrap(), int512(-1));
+        assert_eq!(
+            int512_op(MUL, &int512(-4), &int512(3)).unwrap(),
+            int512(-12)
+        );
+        // Division rounds towards zero, and the remainder has the sign of the dividend
+        assert_eq!(int512_op(DIV, &int512(-7), &int512(2)).unwrap(), int512(-3));
+        assert_eq!(int512_op(REM, &int512(-7), &int512(2)).unwrap(), int512(-1));
+        assert_eq!(int512_op(REM, &int512(7), &int512(-2)).unwrap(), int512(1));
+
+        let mut min = vec![0; INT512_BYTES];
+        min[0] = 0x80;
+        let mut max = vec![0xff; INT512_BYTES];
+        max[0] = 0x7f;
+        assert_eq!(int512_op(SUB, &min, &int512(-1)).unwrap(), {
+            let mut min_plus_one = min.clone();
+            min_plus_one[INT512_BYTES - 1] = 1;
+            min_plus_one
+        });
+        assert_eq!(int512_op(ADD, &max, &int512(1)), Err(BigIntError::Overflow));
+        assert_eq!(int512_op(SUB, &min, &int512(1)), Err(BigIntError::Overflow));
+        assert_eq!(
+            int512_op(DIV, &min, &int512(-1)),
+            Err(BigIntError::Overflow)
+        );
+        assert_eq!(
+            int512_op(REM, &min, &int512(0)),
+            Err(BigIntError::DivisionByZero)
+        );
+    }
+
+    pub fn test_big_int_modexp() {
+        assert_eq!(
+            modexp(&[4], &[13], &[0x01, 0xf1]).unwrap(),
+            vec![0x01, 0xbd]
+        );
+
+        // EIP-198 example 1: 3^(p - 1) % p == 1, with p the secp256k1 field prime
+        let mut exponent = vec![0xff; 32];
+        exponent[27] = 0xfe;
+        exponent[28..].copy_from_slice(&[0xff, 0xff, 0xfc, 0x2e]);
+        let mut modulus = exponent.clone();
+        modulus[31] = 0x2f;
+        let mut one = vec![0; 32];
+        one[31] = 1;
+        assert_eq!(modexp(&[3], &exponent, &modulus).unwrap(), one);
+        assert_eq!(modexp_evm_gas(1, &exponent, 32), 1360);
+
+        // The result is as long as the modulus, even when it's 0
+        assert_eq!(modexp(&[3], &[1], &[0, 0, 0]).unwrap(), vec![0, 0, 0]);
+        assert_eq!(modexp(&[3], &[1], &[0, 1]).unwrap(), vec![0, 0]);
+        assert_eq!(modexp(&[3], &[], &[0, 7]).unwrap(), vec![0, 1]);
+        assert_eq!(modexp(&[], &[], &[]).unwrap(), Vec::<u8>::new());
+        assert_eq!(modexp_evm_gas(1, &[2], 1), 200);
+
+        assert_eq!(
+            modexp(&[3], &[1], &vec![1; MAX_MODEXP_OPERAND_BYTES + 1]),
+            Err(BigIntError::InvalidInput)
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Deterministic calendar arithmetic on timestamps, for vesting and streaming contracts that
+//! schedule things monthly or weekly, and keep getting month lengths and leap years wrong.
+//!
+//! Timestamps are nanoseconds since 1970-01-01T00:00:00Z, like cosmwasm's `Timestamp`. Dates
+//! are in the proleptic Gregorian calendar, in UTC, and days are always 86400 seconds long, i.e.
+//! leap seconds don't exist, like in Unix time.
+//!
+//! * `add_months` moves a timestamp by whole calendar months, keeping its time of day. A day that
+//!   doesn't exist in the target month is clamped to the month's last day, so Jan 31 + 1 month is
+//!   Feb 28, or Feb 29 in a leap year.
+//! * `truncate` rounds a timestamp down to the start of its day, ISO week (Monday), month or year.
+//! * `weekday` is the ISO weekday of a timestamp, 1 for Monday to 7 for Sunday.
+
+/// Error codes returned to contracts by the calendar host functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum CalendarError {
+    /// The unit to truncate to is unknown
+    InvalidInput = 1,
+    /// The result is before 1970-01-01, or doesn't fit in 64 bits of nanoseconds
+    OutOfRange = 2,
+}
+
+pub const NANOS_PER_DAY: u64 = 86_400 * 1_000_000_000;
+
+/// 1970-01-01 was a Thursday
+const EPOCH_ISO_WEEKDAY: u64 = 4;
+
+/// What `truncate` rounds down to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Day,
+    /// Monday of the ISO week
+    Week,
+    Month,
+    Year,
+}
+
+impl Unit {
+    fn from_u32(unit: u32) -> Result<Self, CalendarError> {
+        match unit {
+            0 => Ok(Unit::Day),
+            1 => Ok(Unit::Week),
+            2 => Ok(Unit::Month),
+            3 => Ok(Unit::Year),
+            _ => Err(CalendarError::InvalidInput),
+        }
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a date, with `month` in 1..=12 and `day` in 1..=31
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Howard Hinnant's algorithm: years start in March, so the leap day is the last day of the
+    // year, and are grouped in eras of 400 years, which all have the same number of days
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The year, month and day of a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+fn to_timestamp(days: i64, nanos_of_day: u64) -> Result<u64, CalendarError> {
+    let nanos = days as i128 * NANOS_PER_DAY as i128 + nanos_of_day as i128;
+    if nanos < 0 || nanos > u64::MAX as i128 {
+        return Err(CalendarError::OutOfRange);
+    }
+
+    Ok(nanos as u64)
+}
+
+/// `timestamp` moved by `months` calendar months, which may be negative
+pub fn add_months(timestamp: u64, months: i32) -> Result<u64, CalendarError> {
+    let days = (timestamp / NANOS_PER_DAY) as i64;
+    let (year, month, day) = civil_from_days(days);
+
+    let months_since_year_0 = year * 12 + (month as i64 - 1) + months as i64;
+    let year = months_since_year_0.div_euclid(12);
+    let month = months_since_year_0.rem_euclid(12) as u32 + 1;
+    let day = day.min(days_in_month(year, month));
+
+    to_timestamp(days_from_civil(year, month, day), timestamp % NANOS_PER_DAY)
+}
+
+/// `timestamp` rounded down to the start of its day (0), ISO week (1), month (2) or year (3)
+pub fn truncate(timestamp: u64, unit: u32) -> Result<u64, CalendarError> {
+    let days = (timestamp / NANOS_PER_DAY) as i64;
+
+    let start = match Unit::from_u32(unit)? {
+        Unit::Day => days,
+        Unit::Week => days - (weekday(timestamp) as i64 - 1),
+        Unit::Month => {
+            let (year, month, _) = civil_from_days(days);
+            days_from_civil(year, month, 1)
+        }
+        Unit::Year => {
+            let (year, _, _) = civil_from_days(days);
+            days_from_civil(year, 1, 1)
+        }
+    };
+
+    to_timestamp(start, 0)
+}
+
+/// The ISO weekday of `timestamp`, 1 for Monday to 7 for Sunday
+pub fn weekday(timestamp: u64) -> u32 {
+    let days = timestamp / NANOS_PER_DAY;
+    ((days + EPOCH_ISO_WEEKDAY - 1) % 7 + 1) as u32
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn timestamp(year: i64, month: u32, day: u32, seconds_of_day: u64) -> u64 {
+        to_timestamp(
+            days_from_civil(year, month, day),
+            seconds_of_day * 1_000_000_000,
+        )
+        .unwrap()
+    }
+
+    pub fn test_calendar_civil_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+
+        // every day of four centuries round-trips, and the days are contiguous
+        let mut expected = days_from_civil(1900, 1, 1);
+        for year in 1900..2300 {
+            for month in 1..=12 {
+                for day in 1..=days_in_month(year, month) {
+                    assert_eq!(days_from_civil(year, month, day), expected);
+                    assert_eq!(civil_from_days(expected), (year, month, day));
+                    expected += 1;
+                }
+            }
+        }
+
+        assert!(is_leap_year(2000));
+        assert!(!is_leap_year(2100));
+        assert!(is_leap_year(2024));
+        assert!(!is_leap_year(2023));
+    }
+
+    pub fn test_calendar_add_months() {
+        let noon = 12 * 3600;
+
+        // the time of day is kept
+        assert_eq!(
+            add_months(timestamp(2024, 1, 15, noon) + 7, 1),
+            Ok(timestamp(2024, 2, 15, noon) + 7)
+        );
+
+        // days that don't exist in the target month are clamped
+        assert_eq!(
+            add_months(timestamp(2024, 1, 31, noon), 1),
+            Ok(timestamp(2024, 2, 29, noon))
+        );
+        assert_eq!(
+            add_months(timestamp(2023, 1, 31, 0), 1),
+            Ok(timestamp(2023, 2, 28, 0))
+        );
+        assert_eq!(
+            add_months(timestamp(2024, 2, 29, 0), 12),
+            Ok(timestamp(2025, 2, 28, 0))
+        );
+        assert_eq!(
+            add_months(timestamp(2024, 5, 31, 0), -3),
+            Ok(timestamp(2024, 2, 29, 0))
+        );
+
+        // across years, both ways
+        assert_eq!(
+            add_months(timestamp(2023, 11, 30, 0), 3),
+            Ok(timestamp(2024, 2, 29, 0))
+        );
+        assert_eq!(
+            add_months(timestamp(2024, 1, 10, 0), -13),
+            Ok(timestamp(2022, 12, 10, 0))
+        );
+        assert_eq!(add_months(12345, 0), Ok(12345));
+
+        assert_eq!(add_months(0, -1), Err(CalendarError::OutOfRange));
+        assert_eq!(add_months(u64::MAX, 1), Err(CalendarError::OutOfRange));
+        assert_eq!(add_months(0, i32::MAX), Err(CalendarError::OutOfRange));
+        assert_eq!(add_months(0, i32::MIN), Err(CalendarError::OutOfRange));
+    }
+
+    pub fn test_calendar_truncate_weekday() {
+        // 2024-02-29 was a Thursday
+        let leap_day = timestamp(2024, 2, 29, 13 * 3600) + 5;
+        assert_eq!(weekday(leap_day), 4);
+        assert_eq!(weekday(timestamp(2024, 2, 26, 0)), 1);
+        assert_eq!(weekday(timestamp(2024, 3, 3, 86_399)), 7);
+        assert_eq!(weekday(0), 4);
+
+        assert_eq!(truncate(leap_day, 0), Ok(timestamp(2024, 2, 29, 0)));
+        assert_eq!(truncate(leap_day, 1), Ok(timestamp(2024, 2, 26, 0)));
+        assert_eq!(truncate(leap_day, 2), Ok(timestamp(2024, 2, 1, 0)));
+        assert_eq!(truncate(leap_day, 3), Ok(timestamp(2024, 1, 1, 0)));
+
+        // a week that starts in the previous year
+        assert_eq!(
+            truncate(timestamp(2025, 1, 1, 0), 1),
+            Ok(timestamp(2024, 12, 30, 0))
+        );
+        // truncating a Monday keeps it
+        assert_eq!(
+            truncate(timestamp(2024, 2, 26, 60), 1),
+            Ok(timestamp(2024, 2, 26, 0))
+        );
+
+        // the week of the epoch started on Monday 1969-12-29
+        assert_eq!(truncate(0, 1), Err(CalendarError::OutOfRange));
+        assert_eq!(truncate(leap_day, 4), Err(CalendarError::InvalidInput));
+    }
+}
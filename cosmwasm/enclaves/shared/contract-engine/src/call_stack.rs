@@ -0,0 +1,424 @@
+//! Call stacks of contract executions, for contracts that opt in to the reentrancy guard.
+//!
+//! Contracts call each other through messages that the node dispatches after the caller
+//! returned, so the enclave never sees a call stack of its own. Instead, every callback signature
+//! the enclave creates carries the stack of the execution it's for, i.e. the contracts that are
+//! waiting for it to return:
+//!
+//! ```text
+//! callback_sig || flags: u8 || count: u8 || (len: u8 || address)* || HMAC(key, all of the above)
+//! ```
+//!
+//! A callback signature without a stack, e.g. one created before the guard existed, is an empty
+//! stack. The node can't change a stack without the HMAC failing, and can't move it to another
+//! message, since the HMAC covers the callback signature of the message.
+//!
+//! A contract opts in by exporting `requires_reentrancy_guard`, like contracts opt in to random.
+//! It then can't be executed by a message whose stack contains it, which fails with
+//! `EnclaveError::ReentrancyNotAllowed`. Replies to a guarded contract are still allowed, since
+//! they return to the contract rather than entering it again.
+
+use log::*;
+
+use cw_types_v010::encoding::Binary;
+use cw_types_v010::types::CanonicalAddr;
+use enclave_cosmos_types::types::{HandleType, SigInfo};
+use enclave_crypto::{AESKey, Hmac, Kdf, HASH_SIZE};
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::KEY_MANAGER;
+
+use crate::io::WasmOutput;
+
+const CALL_STACK_KEY_DOMAIN: &[u8] = b"call_stack";
+
+/// The stack lost the callers of a plaintext reply, which isn't signed by the enclave
+const FLAG_INCOMPLETE: u8 = 0x01;
+
+const MAX_CALL_STACK_DEPTH: usize = u8::MAX as usize;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CallStack {
+    /// The contracts waiting for the execution to return, the outermost first
+    pub callers: Vec<CanonicalAddr>,
+    /// Whether callers may be missing
+    pub incomplete: bool,
+}
+
+pub fn call_stack_key() -> AESKey {
+    KEY_MANAGER
+        .get_consensus_callback_secret()
+        .unwrap()
+        .current
+        .derive_key_from_this(CALL_STACK_KEY_DOMAIN)
+}
+
+impl CallStack {
+    /// The stack of an execution, from the callback signature it was verified with
+    pub fn of(sig_info: &SigInfo, is_reply: bool) -> Result<Self, EnclaveError> {
+        match &sig_info.callback_sig {
+            Some(callback_sig) => {
+                let (_, stack) = split_callback_sig(callback_sig.as_slice(), &call_stack_key())?;
+                Ok(stack)
+            }
+            // Plaintext replies are sent by the node, which doesn't know the callers
+            None if is_reply => Ok(Self {
+                callers: vec![],
+                incomplete: true,
+            }),
+            // Txs, IBC packets and their callbacks aren't called by any contract
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Fails when a guarded contract is executed while it's already on the stack, or might be
+    pub fn check_reentrancy(
+        &self,
+        contract: &CanonicalAddr,
+        handle_type: HandleType,
+    ) -> Result<(), EnclaveError> {
+        if handle_type != HandleType::HANDLE_TYPE_EXECUTE {
+            return Ok(());
+        }
+
+        if self.incomplete || self.callers.contains(contract) {
+            warn!("guarded contract {:?} was called reentrantly", contract);
+            return Err(EnclaveError::ReentrancyNotAllowed);
+        }
+
+        Ok(())
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, EnclaveError> {
+        if self.callers.len() > MAX_CALL_STACK_DEPTH {
+            warn!("call stack is deeper than {}", MAX_CALL_STACK_DEPTH);
+            return Err(EnclaveError::ExceededRecursionLimit);
+        }
+
+        let mut bytes = vec![
+            if self.incomplete { FLAG_INCOMPLETE } else { 0 },
+            self.callers.len() as u8,
+        ];
+        for caller in &self.callers {
+            bytes.push(caller.len() as u8);
+            bytes.extend_from_slice(caller.as_slice());
+        }
+
+        Ok(bytes)
+    }
+
+    fn decode(mut bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 2 || bytes[0] & !FLAG_INCOMPLETE != 0 {
+            return None;
+        }
+
+        let incomplete = bytes[0] & FLAG_INCOMPLETE != 0;
+        let count = bytes[1] as usize;
+        bytes = &bytes[2..];
+
+        let mut callers = Vec::with_capacity(count);
+        for _ in 0..count {
+            let len = *bytes.first()? as usize;
+            let caller = bytes.get(1..1 + len)?;
+            callers.push(CanonicalAddr(Binary(caller.to_vec())));
+            bytes = &bytes[1 + len..];
+        }
+
+        if !bytes.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            callers,
+            incomplete,
+        })
+    }
+
+    /// The stack of the messages an execution of `contract` sends
+    fn push(&self, contract: &CanonicalAddr) -> Self {
+        let mut stack = self.clone();
+        stack.callers.push(contract.clone());
+        stack
+    }
+
+    /// The stack of the reply to the contract that sent the execution's message
+    fn pop(&self) -> Self {
+        let mut stack = self.clone();
+        stack.callers.pop();
+        stack
+    }
+}
+
+/// Append `stack` to a callback signature
+fn extend_callback_sig(
+    callback_sig: &[u8],
+    stack: &CallStack,
+    key: &AESKey,
+) -> Result<Vec<u8>, EnclaveError> {
+    if stack == &CallStack::default() {
+        return Ok(callback_sig.to_vec());
+    }
+
+    let mut extended = callback_sig.to_vec();
+    extended.extend(stack.encode()?);
+    let tag = key.sign_sha_256(&extended);
+    extended.extend_from_slice(&tag);
+
+    Ok(extended)
+}
+
+/// Split a callback signature into the signature itself and its stack
+pub fn split_callback_sig<'a>(
+    callback_sig: &'a [u8],
+    key: &AESKey,
+) -> Result<(&'a [u8], CallStack), EnclaveError> {
+    if callback_sig.len() <= HASH_SIZE {
+        return Ok((callback_sig, CallStack::default()));
+    }
+
+    let invalid = || {
+        warn!("callback signature has an invalid call stack");
+        EnclaveError::FailedTxVerification
+    };
+
+    if callback_sig.len() < 2 * HASH_SIZE + 2 {
+        return Err(invalid());
+    }
+
+    let (signed, tag) = callback_sig.split_at(callback_sig.len() - HASH_SIZE);
+    if key.sign_sha_256(signed) != tag {
+        return Err(invalid());
+    }
+
+    let (sig, stack) = signed.split_at(HASH_SIZE);
+    let stack = CallStack::decode(stack).ok_or_else(invalid)?;
+
+    Ok((sig, stack))
+}
+
+fn extend_v010_msgs(
+    msgs: &mut [cw_types_v010::types::CosmosMsg],
+    stack: &CallStack,
+    key: &AESKey,
+) -> Result<(), EnclaveError> {
+    use cw_types_v010::types::{CosmosMsg, WasmMsg};
+
+    for msg in msgs {
+        if let CosmosMsg::Wasm(wasm_msg) = msg {
+            let callback_sig = match wasm_msg {
+                WasmMsg::Execute { callback_sig, .. }
+                | WasmMsg::Instantiate { callback_sig, .. }
+                | WasmMsg::Migrate { callback_sig, .. }
+                | WasmMsg::UpdateAdmin { callback_sig, .. }
+                | WasmMsg::ClearAdmin { callback_sig, .. } => callback_sig,
+            };
+            if let Some(sig) = callback_sig {
+                *sig = extend_callback_sig(sig, stack, key)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn extend_v1_msgs<T>(
+    msgs: &mut [cw_types_v1::results::SubMsg<T>],
+    stack: &CallStack,
+    key: &AESKey,
+) -> Result<(), EnclaveError>
+where
+    T: Clone + core::fmt::Debug + PartialEq,
+{
+    use cw_types_v1::results::{CosmosMsg, WasmMsg};
+
+    for sub_msg in msgs {
+        if let CosmosMsg::Wasm(wasm_msg) = &mut sub_msg.msg {
+            let callback_sig = match wasm_msg {
+                WasmMsg::Execute { callback_sig, .. }
+                | WasmMsg::Instantiate { callback_sig, .. }
+                | WasmMsg::Migrate { callback_sig, .. }
+                | WasmMsg::UpdateAdmin { callback_sig, .. }
+                | WasmMsg::ClearAdmin { callback_sig, .. } => callback_sig,
+            };
+            if let Some(sig) = callback_sig {
+                *sig = extend_callback_sig(sig, stack, key)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn attach_call_stack_with_key(
+    output: Vec<u8>,
+    stack: &CallStack,
+    contract: &CanonicalAddr,
+    key: &AESKey,
+) -> Result<Vec<u8>, EnclaveError> {
+    let mut wasm_output: WasmOutput = serde_json::from_slice(&output).map_err(|err| {
+        error!(
+            "failed to decode the output to attach a call stack: {:?}",
+            err
+        );
+        EnclaveError::FailedToDeserialize
+    })?;
+
+    let msgs_stack = stack.push(contract);
+    if let Some(ok) = wasm_output.v010.as_mut().and_then(|v010| v010.ok.as_mut()) {
+        extend_v010_msgs(&mut ok.messages, &msgs_stack, key)?;
+    }
+    if let Some(ok) = wasm_output.v1.as_mut().and_then(|v1| v1.ok.as_mut()) {
+        extend_v1_msgs(&mut ok.messages, &msgs_stack, key)?;
+    }
+    if let Some(ok) = wasm_output
+        .ibc_basic
+        .as_mut()
+        .and_then(|ibc| ibc.ok.as_mut())
+    {
+        extend_v1_msgs(&mut ok.messages, &msgs_stack, key)?;
+    }
+    if let Some(ok) = wasm_output
+        .ibc_packet_receive
+        .as_mut()
+        .and_then(|ibc| ibc.ok.as_mut())
+    {
+        extend_v1_msgs(&mut ok.messages, &msgs_stack, key)?;
+    }
+
+    if let Some(reply_sig) = wasm_output.internal_reply_enclave_sig.as_mut() {
+        *reply_sig = Binary(extend_callback_sig(
+            reply_sig.as_slice(),
+            &stack.pop(),
+            key,
+        )?);
+    }
+
+    serde_json::to_vec(&wasm_output).map_err(|err| {
+        error!("failed to encode the output with a call stack: {:?}", err);
+        EnclaveError::FailedToSerialize
+    })
+}
+
+/// Add the stacks of the executions that the final output of an execution of `contract`
+/// triggers to their callback signatures: its messages are called by `contract`, and its reply
+/// returns to the contract that called it.
+pub fn attach_call_stack(
+    output: Vec<u8>,
+    stack: &CallStack,
+    contract: &CanonicalAddr,
+) -> Result<Vec<u8>, EnclaveError> {
+    attach_call_stack_with_key(output, stack, contract, &call_stack_key())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn addr(byte: u8) -> CanonicalAddr {
+        CanonicalAddr(Binary(vec![byte; 20]))
+    }
+
+    pub fn test_call_stack_callback_sig() {
+        let key = AESKey::new_from_slice(&[1u8; 32]);
+        let sig = [7u8; HASH_SIZE];
+
+        // no stack, as before
+        let (split, stack) = split_callback_sig(&sig, &key).unwrap();
+        assert_eq!(split, &sig[..]);
+        assert_eq!(stack, CallStack::default());
+
+        let stack = CallStack {
+            callers: vec![addr(2), addr(3)],
+            incomplete: false,
+        };
+        let extended = extend_callback_sig(&sig, &stack, &key).unwrap();
+        let (split, split_stack) = split_callback_sig(&extended, &key).unwrap();
+        assert_eq!(split, &sig[..]);
+        assert_eq!(split_stack, stack);
+
+        // the stack can't be changed, or moved to another signature
+        let mut tampered = extended.clone();
+        tampered[HASH_SIZE + 3] ^= 1;
+        assert!(split_callback_sig(&tampered, &key).is_err());
+        let mut moved = extended.clone();
+        moved[0] ^= 1;
+        assert!(split_callback_sig(&moved, &key).is_err());
+        assert!(split_callback_sig(&extended, &AESKey::new_from_slice(&[2u8; 32])).is_err());
+
+        let incomplete = CallStack {
+            callers: vec![],
+            incomplete: true,
+        };
+        let extended = extend_callback_sig(&sig, &incomplete, &key).unwrap();
+        assert_eq!(split_callback_sig(&extended, &key).unwrap().1, incomplete);
+    }
+
+    pub fn test_call_stack_reentrancy() {
+        let stack = CallStack {
+            callers: vec![addr(2), addr(3)],
+            incomplete: false,
+        };
+
+        assert!(stack
+            .check_reentrancy(&addr(4), HandleType::HANDLE_TYPE_EXECUTE)
+            .is_ok());
+        assert!(matches!(
+            stack.check_reentrancy(&addr(2), HandleType::HANDLE_TYPE_EXECUTE),
+            Err(EnclaveError::ReentrancyNotAllowed)
+        ));
+        // replies return to the contract
+        assert!(stack
+            .check_reentrancy(&addr(2), HandleType::HANDLE_TYPE_REPLY)
+            .is_ok());
+
+        let incomplete = CallStack {
+            callers: vec![],
+            incomplete: true,
+        };
+        assert!(matches!(
+            incomplete.check_reentrancy(&addr(4), HandleType::HANDLE_TYPE_EXECUTE),
+            Err(EnclaveError::ReentrancyNotAllowed)
+        ));
+    }
+
+    pub fn test_call_stack_attach() {
+        let key = AESKey::new_from_slice(&[1u8; 32]);
+        let sig = base64::encode([7u8; HASH_SIZE]);
+        let stack = CallStack {
+            callers: vec![addr(2)],
+            incomplete: false,
+        };
+
+        let output = format!(
+            r#"{{"v010":null,"v1":{{"Ok":{{"messages":[{{"id":1,"msg":{{"wasm":{{"execute":{{"contract_addr":"secret1b","code_hash":"","msg":"","send":[],"callback_sig":[{}]}}}}}},"gas_limit":null,"reply_on":"never"}}],"attributes":[],"events":[],"data":null}},"Err":null}},"ibc_basic":null,"ibc_packet_receive":null,"ibc_open_channel":null,"query":null,"internal_reply_enclave_sig":"{}","internal_msg_id":null}}"#,
+            [7u8; HASH_SIZE]
+                .iter()
+                .map(|b| b.to_string())
+                .collect::<Vec<String>>()
+                .join(","),
+            sig
+        );
+
+        let attached: WasmOutput = serde_json::from_slice(
+            &attach_call_stack_with_key(output.into_bytes(), &stack, &addr(3), &key).unwrap(),
+        )
+        .unwrap();
+
+        let msg = &attached.v1.unwrap().ok.unwrap().messages[0].msg;
+        let callback_sig = match msg {
+            cw_types_v1::results::CosmosMsg::Wasm(cw_types_v1::results::WasmMsg::Execute {
+                callback_sig: Some(callback_sig),
+                ..
+            }) => callback_sig.clone(),
+            _ => panic!("unexpected msg {:?}", msg),
+        };
+        // the message is called by the contract
+        assert_eq!(
+            split_callback_sig(&callback_sig, &key).unwrap().1.callers,
+            vec![addr(2), addr(3)]
+        );
+
+        // the reply returns to the caller, which has no callers of its own
+        let reply_sig = attached.internal_reply_enclave_sig.unwrap();
+        assert_eq!(reply_sig.as_slice(), &[7u8; HASH_SIZE][..]);
+    }
+}
@@ -0,0 +1,463 @@
+//! JSON canonicalization, as defined by RFC 8785 (JCS).
+//!
+//! Hashing or comparing JSON byte for byte breaks as soon as two clients serialize the same
+//! document differently, e.g. with other whitespace, key order or number formatting. The
+//! canonical form of a document is unique:
+//!
+//! * No whitespace.
+//! * Object keys are sorted by their UTF-16 code units.
+//! * Numbers are parsed as IEEE 754 doubles and written the way ECMAScript's `Number.toString`
+//!   writes them, so `1.0`, `1` and `1e0` are all written `1`.
+//! * Strings only escape what they must, using the short escapes where there are any.
+//!
+//! Invalid JSON, duplicate object keys, lone surrogates and numbers outside the range of a
+//! double are rejected, as RFC 8785 requires.
+
+use std::cmp::Ordering;
+
+/// Error codes returned to contracts by the `canonicalize_json` host function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum CanonicalJsonError {
+    InvalidJson = 1,
+    DuplicateKey = 2,
+    InvalidNumber = 3,
+    TooDeep = 4,
+}
+
+const MAX_DEPTH: usize = 128;
+
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<u8, CanonicalJsonError> {
+        let byte = self.peek().ok_or(CanonicalJsonError::InvalidJson)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), CanonicalJsonError> {
+        if self.next()? != expected {
+            return Err(CanonicalJsonError::InvalidJson);
+        }
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &[u8], value: Value) -> Result<Value, CanonicalJsonError> {
+        if !self.input[self.pos..].starts_with(literal) {
+            return Err(CanonicalJsonError::InvalidJson);
+        }
+        self.pos += literal.len();
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> Result<Value, CanonicalJsonError> {
+        self.skip_whitespace();
+        match self.peek().ok_or(CanonicalJsonError::InvalidJson)? {
+            b'n' => self.parse_literal(b"null", Value::Null),
+            b't' => self.parse_literal(b"true", Value::Bool(true)),
+            b'f' => self.parse_literal(b"false", Value::Bool(false)),
+            b'"' => Ok(Value::String(self.parse_string()?)),
+            b'[' => self.nested(Self::parse_array),
+            b'{' => self.nested(Self::parse_object),
+            b'-' | b'0'..=b'9' => Ok(Value::Number(self.parse_number()?)),
+            _ => Err(CanonicalJsonError::InvalidJson),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Value, CanonicalJsonError>,
+    ) -> Result<Value, CanonicalJsonError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(CanonicalJsonError::TooDeep);
+        }
+        let value = parse(self)?;
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    fn parse_array(&mut self) -> Result<Value, CanonicalJsonError> {
+        self.expect(b'[')?;
+        let mut items = vec![];
+
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => continue,
+                b']' => return Ok(Value::Array(items)),
+                _ => return Err(CanonicalJsonError::InvalidJson),
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Value, CanonicalJsonError> {
+        self.expect(b'{')?;
+        let mut entries: Vec<(String, Value)> = vec![];
+
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            let value = self.parse_value()?;
+
+            if entries.iter().any(|(existing, _)| *existing == key) {
+                return Err(CanonicalJsonError::DuplicateKey);
+            }
+            entries.push((key, value));
+
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => continue,
+                b'}' => break,
+                _ => return Err(CanonicalJsonError::InvalidJson),
+            }
+        }
+
+        entries.sort_by(|(a, _), (b, _)| compare_utf16(a, b));
+        Ok(Value::Object(entries))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u16, CanonicalJsonError> {
+        let mut value: u16 = 0;
+        for _ in 0..4 {
+            let digit = (self.next()? as char)
+                .to_digit(16)
+                .ok_or(CanonicalJsonError::InvalidJson)?;
+            value = value << 4 | digit as u16;
+        }
+        Ok(value)
+    }
+
+    fn parse_string(&mut self) -> Result<String, CanonicalJsonError> {
+        self.expect(b'"')?;
+        let mut out = String::new();
+
+        loop {
+            let start = self.pos;
+            while let Some(byte) = self.peek() {
+                if byte == b'"' || byte == b'\\' || byte < 0x20 {
+                    break;
+                }
+                self.pos += 1;
+            }
+            // The input was checked to be UTF-8, and we only stop at ASCII characters
+            out.push_str(std::str::from_utf8(&self.input[start..self.pos]).unwrap());
+
+            match self.next()? {
+                b'"' => return Ok(out),
+                b'\\' => {}
+                _ => return Err(CanonicalJsonError::InvalidJson),
+            }
+
+            let unit = match self.next()? {
+                b'"' => '"',
+                b'\\' => '\\',
+                b'/' => '/',
+                b'b' => '\u{8}',
+                b'f' => '\u{c}',
+                b'n' => '\n',
+                b'r' => '\r',
+                b't' => '\t',
+                b'u' => {
+                    let high = self.parse_hex4()?;
+                    let code_point = match high {
+                        0xd800..=0xdbff => {
+                            self.expect(b'\\')?;
+                            self.expect(b'u')?;
+                            let low = self.parse_hex4()?;
+                            if !(0xdc00..=0xdfff).contains(&low) {
+                                return Err(CanonicalJsonError::InvalidJson);
+                            }
+                            0x10000 + ((high as u32 - 0xd800) << 10) + (low as u32 - 0xdc00)
+                        }
+                        0xdc00..=0xdfff => return Err(CanonicalJsonError::InvalidJson),
+                        _ => high as u32,
+                    };
+                    std::char::from_u32(code_point).ok_or(CanonicalJsonError::InvalidJson)?
+                }
+                _ => return Err(CanonicalJsonError::InvalidJson),
+            };
+            out.push(unit);
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<f64, CanonicalJsonError> {
+        let start = self.pos;
+        let digits = |parser: &mut Self| {
+            let digits_start = parser.pos;
+            while let Some(b'0'..=b'9') = parser.peek() {
+                parser.pos += 1;
+            }
+            parser.pos - digits_start
+        };
+
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                digits(self);
+            }
+            _ => return Err(CanonicalJsonError::InvalidJson),
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if digits(self) == 0 {
+                return Err(CanonicalJsonError::InvalidJson);
+            }
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if digits(self) == 0 {
+                return Err(CanonicalJsonError::InvalidJson);
+            }
+        }
+
+        let number: f64 = std::str::from_utf8(&self.input[start..self.pos])
+            .unwrap()
+            .parse()
+            .map_err(|_| CanonicalJsonError::InvalidNumber)?;
+        if !number.is_finite() {
+            return Err(CanonicalJsonError::InvalidNumber);
+        }
+
+        Ok(number)
+    }
+}
+
+fn compare_utf16(a: &str, b: &str) -> Ordering {
+    a.encode_utf16().cmp(b.encode_utf16())
+}
+
+pub(crate) fn write_string(value: &str, out: &mut String) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// ECMAScript's `Number.prototype.toString`, for finite numbers
+pub(crate) fn write_number(value: f64, out: &mut String) {
+    if value == 0.0 {
+        // including -0
+        out.push('0');
+        return;
+    }
+    if value < 0.0 {
+        out.push('-');
+    }
+
+    // `{:e}` gives the shortest digits that round trip, e.g. "1.2345e-7"
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().unwrap() + 1;
+
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.push_str(&"0".repeat(-n as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push('e');
+        out.push(if n > 0 { '+' } else { '-' });
+        out.push_str(&(n - 1).abs().to_string());
+    }
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(true) => out.push_str("true"),
+        Value::Bool(false) => out.push_str("false"),
+        Value::Number(number) => write_number(*number, out),
+        Value::String(string) => write_string(string, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(entries) => {
+            out.push('{');
+            for (i, (key, item)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_value(item, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// Returns the canonical form of the JSON document in `input`
+pub fn canonicalize(input: &[u8]) -> Result<Vec<u8>, CanonicalJsonError> {
+    std::str::from_utf8(input).map_err(|_| CanonicalJsonError::InvalidJson)?;
+
+    let mut parser = Parser {
+        input,
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.pos != input.len() {
+        return Err(CanonicalJsonError::InvalidJson);
+    }
+
+    let mut out = String::with_capacity(input.len());
+    write_value(&value, &mut out);
+    Ok(out.into_bytes())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn canonical(input: &str) -> String {
+        String::from_utf8(canonicalize(input.as_bytes()).unwrap()).unwrap()
+    }
+
+    pub fn test_canonical_json_rfc8785_sample() {
+        // RFC 8785 section 3.2.3
+        let input = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        assert_eq!(
+            canonical(input),
+            "{\"literals\":[null,true,false],\"numbers\":[333333333.3333333,1e+30,4.5,0.002,1e-27],\"string\":\"\u{20ac}$\\u000f\\nA'B\\\"\\\\\\\\\\\"/\"}"
+        );
+    }
+
+    pub fn test_canonical_json_key_order() {
+        // RFC 8785 section 3.2.3, sorted by UTF-16 code units rather than by code points
+        let input =
+            r#"{"\u20ac":1,"\r":2,"\ufb33":3,"1":4,"\ud83d\ude00":5,"\u0080":6,"\u00f6":7}"#;
+        assert_eq!(
+            canonical(input),
+            "{\"\\r\":2,\"1\":4,\"\u{80}\":6,\"\u{f6}\":7,\"\u{20ac}\":1,\"\u{1f600}\":5,\"\u{fb33}\":3}"
+        );
+    }
+
+    pub fn test_canonical_json_numbers() {
+        let cases = [
+            ("0", "0"),
+            ("-0", "0"),
+            ("1.0", "1"),
+            ("1e0", "1"),
+            ("-1.5", "-1.5"),
+            ("1e20", "100000000000000000000"),
+            ("1e21", "1e+21"),
+            ("0.000001", "0.000001"),
+            ("1e-7", "1e-7"),
+            ("123456789012345680000", "123456789012345680000"),
+            ("9007199254740993", "9007199254740992"),
+            ("5e-324", "5e-324"),
+            ("1.7976931348623157e308", "1.7976931348623157e+308"),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(canonical(input), *expected, "{}", input);
+        }
+    }
+
+    pub fn test_canonical_json_rejects() {
+        let cases = [
+            ("", CanonicalJsonError::InvalidJson),
+            ("{\"a\":1,}", CanonicalJsonError::InvalidJson),
+            ("[01]", CanonicalJsonError::InvalidJson),
+            ("\"\\ud800\"", CanonicalJsonError::InvalidJson),
+            ("{\"a\":1} x", CanonicalJsonError::InvalidJson),
+            ("{\"a\":1,\"a\":2}", CanonicalJsonError::DuplicateKey),
+            ("1e400", CanonicalJsonError::InvalidNumber),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(
+                canonicalize(input.as_bytes()).err(),
+                Some(*expected),
+                "{}",
+                input
+            );
+        }
+
+        let deep = "[".repeat(MAX_DEPTH + 1) + &"]".repeat(MAX_DEPTH + 1);
+        assert_eq!(
+            canonicalize(deep.as_bytes()).err(),
+            Some(CanonicalJsonError::TooDeep)
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! CBOR encoded contract messages.
+//!
+//! JSON is a poor fit for large binary payloads such as bridged proofs: `Binary` fields are
+//! base64 encoded, which makes transactions a third larger, and the size is paid for in gas. A
+//! sender can instead encode its message as CBOR (RFC 8949), flagged by starting the plaintext,
+//! right after the contract code hash, with the self-described CBOR tag `0xd9d9f7`:
+//!
+//! ```text
+//! code_hash (64 hex chars) || 0xd9 0xd9 0xf7 || cbor message
+//! ```
+//!
+//! A JSON message can never start with `0xd9`, so existing messages are unaffected. The enclave
+//! transcodes CBOR messages to the JSON cosmwasm-std expects before passing them to the contract,
+//! with byte strings written as base64 strings, so contracts don't need to change and `Binary`
+//! fields deserialize as usual.
+//!
+//! Only the subset of CBOR that maps to JSON is accepted: definite length items, text map keys,
+//! finite floats and the simple values `false`, `true` and `null`. Tags other than the leading
+//! self-described tag are rejected.
+//!
+//! CBOR messages can end in bytes that look like padding, e.g. `0x80` for an empty array. They're
+//! decoded from the plaintext `padding::decrypt_message` returns, which only strips padding from
+//! messages encrypted with `PADDED_MESSAGE_AD`, so their last bytes are never mistaken for it.
+
+use log::*;
+
+use enclave_ffi_types::EnclaveError;
+
+use crate::canonical_json::{write_number, write_string};
+
+/// The self-described CBOR tag, 55799
+pub const CBOR_ENVELOPE_MAGIC_BYTES: &[u8] = &[0xd9, 0xd9, 0xf7];
+
+const MAX_DEPTH: usize = 128;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_SIMPLE: u8 = 7;
+
+const SIMPLE_FALSE: u8 = 20;
+const SIMPLE_TRUE: u8 = 21;
+const SIMPLE_NULL: u8 = 22;
+const FLOAT_16: u8 = 25;
+const FLOAT_32: u8 = 26;
+const FLOAT_64: u8 = 27;
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    out: String,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: u64) -> Result<&'a [u8], EnclaveError> {
+        if (self.input.len() as u64) < len {
+            warn!("cbor message is truncated");
+            return Err(EnclaveError::FailedToDeserialize);
+        }
+
+        let (taken, rest) = self.input.split_at(len as usize);
+        self.input = rest;
+        Ok(taken)
+    }
+
+    /// Reads the initial byte of an item and its argument, returning the major type, the
+    /// additional info and the argument
+    fn header(&mut self) -> Result<(u8, u8, u64), EnclaveError> {
+        let initial = self.take(1)?[0];
+        let major = initial >> 5;
+        let info = initial & 0x1f;
+
+        let argument = match info {
+            0..=23 => info as u64,
+            24..=27 => {
+                let bytes = self.take(1 << (info - 24))?;
+                bytes
+                    .iter()
+                    .fold(0u64, |value, byte| value << 8 | *byte as u64)
+            }
+            _ => {
+                warn!("unsupported cbor additional info {}", info);
+                return Err(EnclaveError::FailedToDeserialize);
+            }
+        };
+
+        Ok((major, info, argument))
+    }
+
+    fn text(&mut self, len: u64) -> Result<&'a str, EnclaveError> {
+        std::str::from_utf8(self.take(len)?).map_err(|_| {
+            warn!("cbor text string is not valid utf8");
+            EnclaveError::FailedToDeserialize
+        })
+    }
+
+    fn item(&mut self, depth: usize) -> Result<(), EnclaveError> {
+        if depth > MAX_DEPTH {
+            warn!("cbor message is nested too deep");
+            return Err(EnclaveError::FailedToDeserialize);
+        }
+
+        let (major, info, argument) = self.header()?;
+        match major {
+            MAJOR_UNSIGNED => self.out.push_str(&argument.to_string()),
+            MAJOR_NEGATIVE => self.out.push_str(&(-1 - argument as i128).to_string()),
+            MAJOR_BYTES => {
+                let bytes = self.take(argument)?;
+                write_string(&base64::encode(bytes), &mut self.out);
+            }
+            MAJOR_TEXT => {
+                let text = self.text(argument)?;
+                write_string(text, &mut self.out);
+            }
+            MAJOR_ARRAY => {
+                self.out.push('[');
+                for i in 0..argument {
+                    if i > 0 {
+                        self.out.push(',');
+                    }
+                    self.item(depth + 1)?;
+                }
+                self.out.push(']');
+            }
+            MAJOR_MAP => {
+                self.out.push('{');
+                for i in 0..argument {
+                    if i > 0 {
+                        self.out.push(',');
+                    }
+                    let (key_major, _, key_len) = self.header()?;
+                    if key_major != MAJOR_TEXT {
+                        warn!("cbor map key of major type {} is not a string", key_major);
+                        return Err(EnclaveError::FailedToDeserialize);
+                    }
+                    let key = self.text(key_len)?;
+                    write_string(key, &mut self.out);
+                    self.out.push(':');
+                    self.item(depth + 1)?;
+                }
+                self.out.push('}');
+            }
+            MAJOR_SIMPLE => match info {
+                SIMPLE_FALSE => self.out.push_str("false"),
+                SIMPLE_TRUE => self.out.push_str("true"),
+                SIMPLE_NULL => self.out.push_str("null"),
+                FLOAT_16 | FLOAT_32 | FLOAT_64 => {
+                    let float = match info {
+                        FLOAT_16 => half_to_f64(argument as u16),
+                        FLOAT_32 => f32::from_bits(argument as u32) as f64,
+                        _ => f64::from_bits(argument),
+                    };
+                    if !float.is_finite() {
+                        warn!("cbor float {} can't be represented in json", float);
+                        return Err(EnclaveError::FailedToDeserialize);
+                    }
+                    write_number(float, &mut self.out);
+                }
+                _ => {
+                    warn!("unsupported cbor simple value {}", info);
+                    return Err(EnclaveError::FailedToDeserialize);
+                }
+            },
+            _ => {
+                warn!("unsupported cbor major type {}", major);
+                return Err(EnclaveError::FailedToDeserialize);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn half_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let fraction = (bits & 0x3ff) as f64;
+
+    sign * match exponent {
+        0 => fraction * 2f64.powi(-24),
+        31 if fraction == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1.0 + fraction / 1024.0) * 2f64.powi(exponent - 15),
+    }
+}
+
+/// Transcodes `msg` to JSON if it's flagged as CBOR, and returns it as is otherwise
+pub fn decode_envelope(msg: Vec<u8>) -> Result<Vec<u8>, EnclaveError> {
+    if !msg.starts_with(CBOR_ENVELOPE_MAGIC_BYTES) {
+        return Ok(msg);
+    }
+
+    let mut decoder = Decoder {
+        input: &msg[CBOR_ENVELOPE_MAGIC_BYTES.len()..],
+        out: String::with_capacity(msg.len() * 4 / 3),
+    };
+    decoder.item(0)?;
+
+    if !decoder.input.is_empty() {
+        warn!("cbor message has {} trailing bytes", decoder.input.len());
+        return Err(EnclaveError::FailedToDeserialize);
+    }
+
+    trace!("transcoded cbor message to json: {}", decoder.out);
+    Ok(decoder.out.into_bytes())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use enclave_crypto::AESKey;
+
+    use crate::padding::{decrypt_message, encrypt_message};
+
+    use super::*;
+
+    fn decode(cbor: &[u8]) -> Result<String, EnclaveError> {
+        let mut msg = CBOR_ENVELOPE_MAGIC_BYTES.to_vec();
+        msg.extend_from_slice(cbor);
+        decode_envelope(msg).map(|json| String::from_utf8(json).unwrap())
+    }
+
+    pub fn test_cbor_envelope_transcode() {
+        // {"submit": {"proof": h'00ff10', "height": 1000, "delta": -5, "final": true, "memo": null}}
+        let cbor = [
+            0xa1, 0x66, b's', b'u', b'b', b'm', b'i', b't', 0xa5, 0x65, b'p', b'r', b'o', b'o',
+            b'f', 0x43, 0x00, 0xff, 0x10, 0x66, b'h', b'e', b'i', b'g', b'h', b't', 0x19, 0x03,
+            0xe8, 0x65, b'd', b'e', b'l', b't', b'a', 0x24, 0x65, b'f', b'i', b'n', b'a', b'l',
+            0xf5, 0x64, b'm', b'e', b'm', b'o', 0xf6,
+        ];
+        assert_eq!(
+            decode(&cbor).unwrap(),
+            r#"{"submit":{"proof":"AP8Q","height":1000,"delta":-5,"final":true,"memo":null}}"#
+        );
+
+        // [1.5 as f16, 0.1 as f64, "\"quoted\""]
+        let cbor = [
+            0x83, 0xf9, 0x3e, 0x00, 0xfb, 0x3f, 0xb9, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a, 0x68,
+            b'"', b'q', b'u', b'o', b't', b'e', b'd', b'"',
+        ];
+        assert_eq!(decode(&cbor).unwrap(), r#"[1.5,0.1,"\"quoted\""]"#);
+    }
+
+    pub fn test_cbor_envelope_json_passthrough() {
+        let json = b"{\"transfer\":{\"amount\":\"10\"}}".to_vec();
+        assert_eq!(decode_envelope(json.clone()).unwrap(), json);
+    }
+
+    pub fn test_cbor_envelope_rejects() {
+        let cases: [&[u8]; 6] = [
+            // truncated byte string
+            &[0x43, 0x00],
+            // integer map key
+            &[0xa1, 0x01, 0x02],
+            // indefinite length array
+            &[0x9f, 0x01, 0xff],
+            // bignum tag
+            &[0xc2, 0x41, 0x01],
+            // NaN
+            &[0xf9, 0x7e, 0x00],
+            // trailing bytes
+            &[0x01, 0x02],
+        ];
+        for cbor in cases.iter() {
+            assert!(decode(cbor).is_err(), "{:?}", cbor);
+        }
+    }
+
+    pub fn test_cbor_envelope_trailing_empty_array() {
+        let key = AESKey::new_from_slice(&[3; 32]);
+        // {"a": []}, which ends with 0x80 like padding does
+        let mut msg = CBOR_ENVELOPE_MAGIC_BYTES.to_vec();
+        msg.extend_from_slice(&[0xa1, 0x61, b'a', 0x80]);
+
+        for should_pad in [false, true].iter() {
+            let ciphertext = encrypt_message(&key, &msg, *should_pad).unwrap();
+            let (plaintext, is_padded) = decrypt_message(&key, &ciphertext).unwrap();
+            assert_eq!(is_padded, *should_pad);
+            assert_eq!(plaintext, msg);
+            assert_eq!(decode_envelope(plaintext).unwrap(), br#"{"a":[]}"#.to_vec());
+        }
+    }
+}
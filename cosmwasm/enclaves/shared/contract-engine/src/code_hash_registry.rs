@@ -0,0 +1,316 @@
+//! A mirror of the code every contract runs, kept inside the enclave.
+//!
+//! The node passes the code of a contract to every call, along with the contract key the enclave
+//! generated for it. Keys stay valid after a contract is migrated, so a node could keep running
+//! a contract with the code it had before a migration, and the key of that code. The enclave
+//! records the code hash of every contract it instantiates or migrates in a light client
+//! verified block, and reports calls of the contract with other code.
+//!
+//! A tx can still fail after its instantiate or migrate succeeded, which reverts them, and the
+//! enclave doesn't learn the results of txs. A recorded code hash is pending until a call in a
+//! later block runs the contract with it, which an honest node only does if the chain kept it.
+//! Until then, the code the contract was confirmed to run before is accepted as well.
+//!
+//! The registry is sealed by every node for itself, and a node that state-synced or deleted its
+//! file has another registry than the rest of the chain. Since the outcome of a call mustn't
+//! depend on it, a call with other code is only logged, and runs like before.
+
+use std::collections::HashMap;
+use std::path;
+use std::sync::SgxMutex;
+use std::untrusted::path::PathEx;
+
+use lazy_static::lazy_static;
+use log::*;
+
+use enclave_crypto::consts::{make_sgx_secret_path, SEALED_FILE_CODE_HASH_REGISTRY};
+use enclave_crypto::HASH_SIZE;
+use enclave_utils::storage::{seal, unseal};
+
+#[cfg(feature = "light-client-validation")]
+use block_verifier::VERIFIED_BLOCK_MESSAGES;
+
+type CodeHash = [u8; HASH_SIZE];
+
+const CONFIRMED: u8 = 1;
+const PENDING: u8 = 2;
+
+/// `flags || confirmed || pending || pending height`, after the length and the address
+const ENTRY_LEN: usize = 1 + HASH_SIZE + HASH_SIZE + 8;
+
+lazy_static! {
+    static ref CODE_HASH_REGISTRY_SEALING_PATH: String =
+        make_sgx_secret_path(SEALED_FILE_CODE_HASH_REGISTRY);
+    /// Unsealed the first time it's needed
+    static ref CODE_HASH_REGISTRY: SgxMutex<Option<CodeHashRegistry>> = SgxMutex::new(None);
+}
+
+/// What a call of a contract with a code hash is, to the registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeHashCheck {
+    /// The contract runs the code, or the registry doesn't know which code it runs
+    Accepted,
+    /// The call confirmed the pending code hash of the contract
+    Confirmed,
+    /// The contract was confirmed to run other code
+    Mismatch(CodeHash),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Entry {
+    /// The code hash that a call in a later block than it was recorded in ran the contract with
+    confirmed: Option<CodeHash>,
+    /// The code hash of the latest instantiate or migrate, and the height of its block
+    pending: Option<(CodeHash, u64)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeHashRegistry {
+    entries: HashMap<Vec<u8>, Entry>,
+}
+
+impl CodeHashRegistry {
+    /// Records that the contract was instantiated or migrated with `code_hash` at `height`
+    pub fn record(&mut self, contract_address: &[u8], code_hash: &CodeHash, height: u64) {
+        let entry = self.entries.entry(contract_address.to_vec()).or_default();
+        entry.pending = Some((*code_hash, height));
+    }
+
+    /// Checks a call of the contract with `code_hash` at `height`
+    pub fn check(
+        &mut self,
+        contract_address: &[u8],
+        code_hash: &CodeHash,
+        height: u64,
+    ) -> CodeHashCheck {
+        let entry = match self.entries.get_mut(contract_address) {
+            Some(entry) => entry,
+            // Contracts instantiated before the enclave kept the registry
+            None => return CodeHashCheck::Accepted,
+        };
+
+        if let Some((pending, recorded_at)) = entry.pending {
+            if pending == *code_hash {
+                if height <= recorded_at {
+                    return CodeHashCheck::Accepted;
+                }
+                entry.confirmed = Some(pending);
+                entry.pending = None;
+                return CodeHashCheck::Confirmed;
+            }
+        }
+
+        match entry.confirmed {
+            Some(confirmed) if confirmed != *code_hash => CodeHashCheck::Mismatch(confirmed),
+            _ => CodeHashCheck::Accepted,
+        }
+    }
+
+    /// The entries, each as `address length (1) || address || flags (1) || confirmed (32) ||
+    /// pending (32) || pending height (8, little endian)`, zeroed where it's not set. Entries are
+    /// sorted by address.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut addresses: Vec<&Vec<u8>> = self.entries.keys().collect();
+        addresses.sort();
+
+        let mut out = vec![];
+        for address in addresses {
+            let entry = &self.entries[address];
+            let (pending, pending_height) = entry.pending.unwrap_or(([0u8; HASH_SIZE], 0));
+
+            let mut flags = 0u8;
+            if entry.confirmed.is_some() {
+                flags |= CONFIRMED;
+            }
+            if entry.pending.is_some() {
+                flags |= PENDING;
+            }
+
+            out.push(address.len() as u8);
+            out.extend_from_slice(address);
+            out.push(flags);
+            out.extend_from_slice(&entry.confirmed.unwrap_or([0u8; HASH_SIZE]));
+            out.extend_from_slice(&pending);
+            out.extend_from_slice(&pending_height.to_le_bytes());
+        }
+        out
+    }
+
+    pub fn deserialize(mut bytes: &[u8]) -> Option<Self> {
+        let mut registry = Self::default();
+
+        while let Some((&address_len, rest)) = bytes.split_first() {
+            let address_len = address_len as usize;
+            if rest.len() < address_len + ENTRY_LEN {
+                return None;
+            }
+            let (address, rest) = rest.split_at(address_len);
+            let (entry, rest) = rest.split_at(ENTRY_LEN);
+            bytes = rest;
+
+            let flags = entry[0];
+            let mut confirmed = [0u8; HASH_SIZE];
+            confirmed.copy_from_slice(&entry[1..1 + HASH_SIZE]);
+            let mut pending = [0u8; HASH_SIZE];
+            pending.copy_from_slice(&entry[1 + HASH_SIZE..1 + 2 * HASH_SIZE]);
+            let mut pending_height = [0u8; 8];
+            pending_height.copy_from_slice(&entry[1 + 2 * HASH_SIZE..]);
+
+            registry.entries.insert(
+                address.to_vec(),
+                Entry {
+                    confirmed: Some(confirmed).filter(|_| flags & CONFIRMED != 0),
+                    pending: Some((pending, u64::from_le_bytes(pending_height)))
+                        .filter(|_| flags & PENDING != 0),
+                },
+            );
+        }
+
+        Some(registry)
+    }
+
+    fn load() -> Self {
+        if !path::Path::new(CODE_HASH_REGISTRY_SEALING_PATH.as_str()).exists() {
+            return Self::default();
+        }
+
+        match unseal(&CODE_HASH_REGISTRY_SEALING_PATH)
+            .ok()
+            .and_then(|sealed| Self::deserialize(&sealed))
+        {
+            Some(registry) => registry,
+            None => {
+                warn!("Failed to load the code hash registry, starting from an empty one");
+                Self::default()
+            }
+        }
+    }
+
+    fn seal(&self) {
+        if let Err(e) = seal(&self.serialize(), &CODE_HASH_REGISTRY_SEALING_PATH) {
+            error!("Error sealing the code hash registry: {}", e);
+            warn!(
+                "The code hash registry wasn't sealed, and will miss this change after a restart"
+            );
+        }
+    }
+}
+
+#[cfg(feature = "light-client-validation")]
+fn verified_height() -> Option<u64> {
+    Some(VERIFIED_BLOCK_MESSAGES.lock().unwrap().height())
+}
+
+// Without the light client, instantiates and migrates aren't verified against the block
+#[cfg(not(feature = "light-client-validation"))]
+fn verified_height() -> Option<u64> {
+    None
+}
+
+/// Records the code hash of a contract that was instantiated or migrated successfully
+pub fn record_code_hash(contract_address: &[u8], code_hash: &CodeHash) {
+    let height = match verified_height() {
+        Some(height) => height,
+        None => return,
+    };
+
+    let mut cached = CODE_HASH_REGISTRY.lock().unwrap();
+    let registry = cached.get_or_insert_with(CodeHashRegistry::load);
+    registry.record(contract_address, code_hash, height);
+    registry.seal();
+}
+
+/// Logs a call of a contract with code other than the code it runs. The call isn't rejected,
+/// since other nodes may not have the same registry.
+pub fn check_code_hash(contract_address: &[u8], code_hash: &CodeHash) {
+    let height = match verified_height() {
+        Some(height) => height,
+        None => return,
+    };
+
+    let mut cached = CODE_HASH_REGISTRY.lock().unwrap();
+    let registry = cached.get_or_insert_with(CodeHashRegistry::load);
+    match registry.check(contract_address, code_hash, height) {
+        CodeHashCheck::Accepted => {}
+        CodeHashCheck::Confirmed => registry.seal(),
+        CodeHashCheck::Mismatch(confirmed) => error!(
+            "contract {} was called with code {}, but it runs {}",
+            hex::encode(contract_address),
+            hex::encode(code_hash),
+            hex::encode(confirmed)
+        ),
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    const CONTRACT: &[u8] = &[7u8; 20];
+
+    pub fn test_code_hash_registry_migrations() {
+        let mut registry = CodeHashRegistry::default();
+        let (v1, v2) = ([1u8; HASH_SIZE], [2u8; HASH_SIZE]);
+
+        // unknown contracts are accepted with any code
+        assert_eq!(registry.check(CONTRACT, &v1, 10), CodeHashCheck::Accepted);
+
+        registry.record(CONTRACT, &v1, 10);
+        // the same block doesn't confirm it
+        assert_eq!(registry.check(CONTRACT, &v1, 10), CodeHashCheck::Accepted);
+        assert_eq!(registry.check(CONTRACT, &v1, 11), CodeHashCheck::Confirmed);
+        assert_eq!(registry.check(CONTRACT, &v1, 12), CodeHashCheck::Accepted);
+        assert_eq!(
+            registry.check(CONTRACT, &v2, 12),
+            CodeHashCheck::Mismatch(v1)
+        );
+
+        // both codes are accepted until the migration is confirmed
+        registry.record(CONTRACT, &v2, 20);
+        assert_eq!(registry.check(CONTRACT, &v1, 21), CodeHashCheck::Accepted);
+        assert_eq!(registry.check(CONTRACT, &v2, 22), CodeHashCheck::Confirmed);
+        assert_eq!(
+            registry.check(CONTRACT, &v1, 23),
+            CodeHashCheck::Mismatch(v2)
+        );
+    }
+
+    pub fn test_code_hash_registry_reverted_instantiate() {
+        let mut registry = CodeHashRegistry::default();
+        let (reverted, kept) = ([1u8; HASH_SIZE], [2u8; HASH_SIZE]);
+
+        // the instantiate was reverted, and another contract got the address later
+        registry.record(CONTRACT, &reverted, 10);
+        assert_eq!(registry.check(CONTRACT, &kept, 11), CodeHashCheck::Accepted);
+        registry.record(CONTRACT, &kept, 15);
+        assert_eq!(
+            registry.check(CONTRACT, &kept, 16),
+            CodeHashCheck::Confirmed
+        );
+        assert_eq!(
+            registry.check(CONTRACT, &reverted, 17),
+            CodeHashCheck::Mismatch(kept)
+        );
+    }
+
+    pub fn test_code_hash_registry_serialization() {
+        let mut registry = CodeHashRegistry::default();
+        assert_eq!(registry.serialize(), Vec::<u8>::new());
+        assert_eq!(CodeHashRegistry::deserialize(&[]), Some(registry.clone()));
+
+        registry.record(CONTRACT, &[1u8; HASH_SIZE], 10);
+        registry.check(CONTRACT, &[1u8; HASH_SIZE], 11);
+        registry.record(CONTRACT, &[2u8; HASH_SIZE], 12);
+        registry.record(&[8u8; 32], &[3u8; HASH_SIZE], 13);
+
+        let serialized = registry.serialize();
+        assert_eq!(serialized.len(), 1 + 20 + ENTRY_LEN + 1 + 32 + ENTRY_LEN);
+        assert_eq!(CodeHashRegistry::deserialize(&serialized), Some(registry));
+
+        // truncated
+        assert_eq!(
+            CodeHashRegistry::deserialize(&serialized[..serialized.len() - 1]),
+            None
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Deterministic compression and decompression for contracts that accept large payloads, e.g.
+//! bridged proofs and batch submissions.
+//!
+//! Decompression is where payloads can hurt: a few bytes can expand to gigabytes. So the contract
+//! gives the most it's willing to accept, under a global cap, and decompression stops with an
+//! error as soon as the output would be longer. zstd frames are decoded block by block for the
+//! same reason, since a frame can declare a window far larger than its content.
+//!
+//! * Deflate (RFC 1951) and zlib (RFC 1950) are handled by miniz_oxide, and zstd (RFC 8878)
+//!   by ruzstd, which only decodes. Both are pure Rust, and their versions are pinned, so every
+//!   node compresses the same input to the same output.
+//! * zstd payloads are a single frame without a dictionary, and its checksum is checked if it has
+//!   one. Bytes after the end of the compressed data are rejected in every format.
+
+use miniz_oxide::inflate::TINFLStatus;
+use ruzstd::frame_decoder::{BlockDecodingStrategy, FrameDecoder};
+
+/// The longest input of the host functions
+pub const MAX_INPUT_LENGTH: usize = 1024 * 1024;
+/// The longest output of decompression, whatever the contract accepts
+pub const MAX_DECOMPRESSED_LENGTH: usize = 4 * 1024 * 1024;
+
+/// miniz_oxide's default level
+const DEFLATE_LEVEL: u8 = 6;
+
+/// Error codes returned to contracts by `compress` and `decompress`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum CompressionError {
+    /// The format is unknown or can't be compressed to, the input is too long, or the maximum
+    /// output length is 0 or above the cap
+    InvalidInput = 1,
+    /// The input isn't valid compressed data of the format
+    InvalidData = 2,
+    /// The output would be longer than the maximum
+    OutputTooLong = 3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionFormat {
+    Deflate,
+    Zlib,
+    Zstd,
+}
+
+impl CompressionFormat {
+    pub fn from_u32(format: u32) -> Result<Self, CompressionError> {
+        match format {
+            0 => Ok(CompressionFormat::Deflate),
+            1 => Ok(CompressionFormat::Zlib),
+            2 => Ok(CompressionFormat::Zstd),
+            _ => Err(CompressionError::InvalidInput),
+        }
+    }
+}
+
+pub fn compress(format: CompressionFormat, input: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    if input.len() > MAX_INPUT_LENGTH {
+        return Err(CompressionError::InvalidInput);
+    }
+
+    match format {
+        CompressionFormat::Deflate => {
+            Ok(miniz_oxide::deflate::compress_to_vec(input, DEFLATE_LEVEL))
+        }
+        CompressionFormat::Zlib => Ok(miniz_oxide::deflate::compress_to_vec_zlib(
+            input,
+            DEFLATE_LEVEL,
+        )),
+        CompressionFormat::Zstd => Err(CompressionError::InvalidInput),
+    }
+}
+
+pub fn decompress(
+    format: CompressionFormat,
+    input: &[u8],
+    max_output_length: usize,
+) -> Result<Vec<u8>, CompressionError> {
+    if input.len() > MAX_INPUT_LENGTH
+        || max_output_length == 0
+        || max_output_length > MAX_DECOMPRESSED_LENGTH
+    {
+        return Err(CompressionError::InvalidInput);
+    }
+
+    match format {
+        CompressionFormat::Deflate | CompressionFormat::Zlib => {
+            inflate(format == CompressionFormat::Zlib, input, max_output_length)
+        }
+        CompressionFormat::Zstd => decode_zstd(input, max_output_length),
+    }
+}
+
+fn inflate(
+    zlib: bool,
+    input: &[u8],
+    max_output_length: usize,
+) -> Result<Vec<u8>, CompressionError> {
+    use miniz_oxide::inflate::core::{decompress, inflate_flags, DecompressorOxide};
+
+    let mut flags = inflate_flags::TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF;
+    if zlib {
+        flags |=
+            inflate_flags::TINFL_FLAG_PARSE_ZLIB_HEADER | inflate_flags::TINFL_FLAG_COMPUTE_ADLER32;
+    }
+
+    // One byte more than the maximum, to tell output that fits from output that doesn't
+    let mut output = vec![0u8; max_output_length + 1];
+    let mut decompressor = DecompressorOxide::new();
+    let (status, read, written) = decompress(&mut decompressor, input, &mut output, 0, flags);
+
+    match status {
+        TINFLStatus::Done if read == input.len() && written <= max_output_length => {
+            output.truncate(written);
+            Ok(output)
+        }
+        TINFLStatus::Done if read != input.len() => Err(CompressionError::InvalidData),
+        TINFLStatus::Done | TINFLStatus::HasMoreOutput => Err(CompressionError::OutputTooLong),
+        _ => Err(CompressionError::InvalidData),
+    }
+}
+
+fn decode_zstd(input: &[u8], max_output_length: usize) -> Result<Vec<u8>, CompressionError> {
+    let mut source = input;
+    let mut decoder = FrameDecoder::new();
+    // `init` doesn't reserve the window up front, unlike `reset`
+    decoder
+        .init(&mut source)
+        .map_err(|_| CompressionError::InvalidData)?;
+
+    let mut output = vec![];
+    loop {
+        // Blocks are at most 128 KiB, so the output is checked before it can grow much further
+        let finished = decoder
+            .decode_blocks(&mut source, BlockDecodingStrategy::UptoBlocks(1))
+            .map_err(|_| CompressionError::InvalidData)?;
+        if let Some(collected) = decoder.collect() {
+            output.extend_from_slice(&collected);
+        }
+        if output.len() + decoder.can_collect() > max_output_length {
+            return Err(CompressionError::OutputTooLong);
+        }
+        if finished {
+            break;
+        }
+    }
+    if let Some(collected) = decoder.collect() {
+        output.extend_from_slice(&collected);
+    }
+
+    if !source.is_empty() {
+        return Err(CompressionError::InvalidData);
+    }
+    if let Some(checksum) = decoder.get_checksum_from_data() {
+        if decoder.get_calculated_checksum() != Some(checksum) {
+            return Err(CompressionError::InvalidData);
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    /// "hello hello hello hello", compressed by libzstd at level 3 with a checksum
+    const HELLO_ZSTD: &str = "28b52ffd04586500003068656c6c6f200100994b11175eae0d";
+
+    fn sample() -> Vec<u8> {
+        (0..10000u32)
+            .flat_map(|i| (i % 100).to_be_bytes().to_vec())
+            .collect()
+    }
+
+    pub fn test_compression_roundtrip() {
+        let input = sample();
+        for &format in [CompressionFormat::Deflate, CompressionFormat::Zlib].iter() {
+            let compressed = compress(format, &input).unwrap();
+            assert!(compressed.len() < input.len() / 10);
+            // Compression is deterministic
+            assert_eq!(compress(format, &input).unwrap(), compressed);
+            assert_eq!(decompress(format, &compressed, input.len()).unwrap(), input);
+        }
+
+        let hello = decompress(
+            CompressionFormat::Zstd,
+            &hex::decode(HELLO_ZSTD).unwrap(),
+            100,
+        )
+        .unwrap();
+        assert_eq!(hello, b"hello hello hello hello");
+        assert_eq!(
+            compress(CompressionFormat::Zstd, &input),
+            Err(CompressionError::InvalidInput)
+        );
+    }
+
+    pub fn test_decompression_output_cap() {
+        let input = sample();
+        for &format in [CompressionFormat::Deflate, CompressionFormat::Zlib].iter() {
+            let compressed = compress(format, &input).unwrap();
+            assert_eq!(
+                decompress(format, &compressed, input.len() - 1),
+                Err(CompressionError::OutputTooLong)
+            );
+        }
+        assert_eq!(
+            decompress(
+                CompressionFormat::Zstd,
+                &hex::decode(HELLO_ZSTD).unwrap(),
+                22
+            ),
+            Err(CompressionError::OutputTooLong)
+        );
+
+        assert_eq!(
+            decompress(CompressionFormat::Deflate, &[], 0),
+            Err(CompressionError::InvalidInput)
+        );
+        assert_eq!(
+            decompress(CompressionFormat::Deflate, &[], MAX_DECOMPRESSED_LENGTH + 1),
+            Err(CompressionError::InvalidInput)
+        );
+    }
+
+    pub fn test_decompression_rejects_invalid_data() {
+        let zlib = compress(CompressionFormat::Zlib, b"payload").unwrap();
+        let mut corrupted = zlib.clone();
+        // The Adler-32 checksum at the end
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            decompress(CompressionFormat::Zlib, &corrupted, 100),
+            Err(CompressionError::InvalidData)
+        );
+
+        let mut trailing = zlib;
+        trailing.push(0);
+        assert_eq!(
+            decompress(CompressionFormat::Zlib, &trailing, 100),
+            Err(CompressionError::InvalidData)
+        );
+
+        let mut zstd = hex::decode(HELLO_ZSTD).unwrap();
+        *zstd.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            decompress(CompressionFormat::Zstd, &zstd, 100),
+            Err(CompressionError::InvalidData)
+        );
+        assert_eq!(
+            decompress(CompressionFormat::Zstd, b"not zstd", 100),
+            Err(CompressionError::InvalidData)
+        );
+        assert_eq!(
+            CompressionFormat::from_u32(3),
+            Err(CompressionError::InvalidInput)
+        );
+    }
+}
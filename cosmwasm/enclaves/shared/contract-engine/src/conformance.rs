@@ -0,0 +1,90 @@
+//! Storage traces for the gas and behavior conformance vectors.
+//!
+//! In enclaves built with the `conformance` feature, every storage access a contract makes is
+//! recorded here, in the order the contract made it, with the plaintext key and value. The test
+//! harness executes a corpus of reference contracts, takes the trace after each call with
+//! `ecall_take_conformance_trace`, and compares it, together with the gas used and the output,
+//! against vectors that were generated earlier, so that changes to the engine that alter gas or
+//! behavior are caught in both SW and HW mode.
+//!
+//! The trace holds plaintext contract state, so the feature can't be combined with `production`.
+
+use std::sync::SgxMutex;
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+
+/// Calls that access more than this are truncated, the trace is only meant for small test
+/// contracts
+const MAX_TRACE_LEN: usize = 10_000;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum StorageOp {
+    Read { key: String, value: Option<String> },
+    Write { key: String, value: String },
+    Remove { key: String },
+}
+
+lazy_static! {
+    static ref TRACE: SgxMutex<Vec<StorageOp>> = SgxMutex::new(Vec::new());
+}
+
+fn push(op: StorageOp) {
+    let mut trace = TRACE.lock().unwrap();
+    if trace.len() < MAX_TRACE_LEN {
+        trace.push(op);
+    }
+}
+
+pub fn record_read(key: &[u8], value: Option<&[u8]>) {
+    push(StorageOp::Read {
+        key: hex::encode(key),
+        value: value.map(hex::encode),
+    });
+}
+
+pub fn record_write(key: &[u8], value: &[u8]) {
+    push(StorageOp::Write {
+        key: hex::encode(key),
+        value: hex::encode(value),
+    });
+}
+
+pub fn record_remove(key: &[u8]) {
+    push(StorageOp::Remove {
+        key: hex::encode(key),
+    });
+}
+
+/// The trace recorded since it was last taken, as a json array
+pub fn encoded_trace() -> Vec<u8> {
+    let trace = TRACE.lock().unwrap();
+    serde_json::to_vec(&*trace).unwrap_or_default()
+}
+
+pub fn clear_trace() {
+    TRACE.lock().unwrap().clear();
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_conformance_trace_encoding() {
+        clear_trace();
+
+        record_write(b"a", b"1");
+        record_read(b"a", Some(b"1"));
+        record_read(b"b", None);
+        record_remove(b"a");
+
+        assert_eq!(
+            String::from_utf8(encoded_trace()).unwrap(),
+            r#"[{"op":"write","key":"61","value":"31"},{"op":"read","key":"61","value":"31"},{"op":"read","key":"62","value":null},{"op":"remove","key":"61"}]"#
+        );
+
+        clear_trace();
+        assert_eq!(encoded_trace(), b"[]");
+    }
+}
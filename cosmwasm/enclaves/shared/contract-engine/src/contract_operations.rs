@@ -1,44 +1,85 @@
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "random")]
-use cw_types_generic::{ContractFeature, CwEnv};
+use cw_types_generic::CwEnv;
 
-use cw_types_generic::{BaseAddr, BaseEnv};
+use cw_types_generic::{BaseAddr, BaseEnv, ContractFeature, CosmWasmApiVersion};
 
 use cw_types_v010::encoding::Binary;
 use cw_types_v010::types::CanonicalAddr;
+use cw_types_v1::math::Uint64;
 
 use enclave_cosmos_types::types::{ContractCode, HandleType, SigInfo, VerifyParamsType};
-use enclave_crypto::Ed25519PublicKey;
+use enclave_crypto::{rand_slice, sha_256, Ed25519PublicKey, HASH_SIZE};
 use enclave_ffi_types::{Ctx, EnclaveError};
+use enclave_utils::audit_log::{AuditEntry, AuditLog};
+use enclave_utils::crash_breadcrumbs;
+use enclave_utils::KEY_MANAGER;
 use log::*;
 
+use crate::call_stack::{attach_call_stack, CallStack};
+use crate::code_hash_registry::record_code_hash;
 use crate::cosmwasm_config::ContractOperation;
+use crate::cron::verify_cron_callback;
 
 #[cfg(feature = "light-client-validation")]
-use crate::contract_validation::verify_block_info;
+use crate::contract_validation::{
+    set_verified_block_info, verify_block_info, verify_compute_state,
+};
 
 use crate::contract_validation::{
     generate_admin_proof, generate_contract_key_proof, ReplyParams, ValidatedMessage,
 };
+use crate::db::{decrypt_stored_pair, encrypt_stored_pair, get_symmetrical_key_new};
+#[cfg(feature = "differential-execution")]
+use crate::differential::{self, RunSummary};
+use crate::execution_receipt::{attach_execution_receipt, execution_receipt};
+use crate::execution_report::attach_execution_report;
 use crate::external::results::{
     HandleSuccess, InitSuccess, MigrateSuccess, QuerySuccess, UpdateAdminSuccess,
 };
+use crate::gov_messages::authority_address;
+use crate::ibc_sender_context::recover_sender_context;
+use crate::instantiate_restrictions::check_instantiate_allowed;
+use crate::job_message::{
+    job_result_id, open_job_input, EnqueuedJob, JobHandlerOutput, SignedJobResult,
+};
+use crate::key_disclosure::{
+    disclosure_key, encrypt_to_auditor, KeyDisclosureRequest, ENCRYPTED_CONTRACT_KEY_LENGTH,
+};
 use crate::message::{is_ibc_msg, parse_message};
 use crate::message_utils::try_get_decrypted_secret_msg;
+use crate::native_snip20::{is_native_snip20, try_native_snip20_execute};
+use crate::public_state::PUBLIC_KEY_PREFIX;
+use crate::query_cache;
+use crate::query_cancellation::QueryCancellation;
+use crate::query_limits::query_limits;
+use crate::query_replay::check_query_replay;
+use crate::query_session::resolve_session_message;
+use crate::state_backup::{
+    backup_key, decode_pairs, encode_pairs, unwrap_chunk, wrap_chunk, BackupOperation,
+    StateBackupAuthorization, StatePairs,
+};
+use crate::state_commitment::{
+    leaf_hash, leaf_key, signing_key as commitment_signing_key, CommitmentProgress,
+    SignedStateCommitment,
+};
+use crate::state_reencryption::{encode_output, reencrypt_chunk, ReencryptionProgress};
 use crate::types::ParsedMessage;
+use crate::wasm3::{get_encryption_salt, EngineBackend};
 
 use crate::random::update_msg_counter;
 
 #[cfg(feature = "random")]
-use crate::random::derive_random;
+use crate::random::{derive_random, derive_simulation_random};
 #[cfg(feature = "random")]
 use crate::wasm3::Engine;
 
 use crate::hardcoded_admins::is_hardcoded_contract_admin;
 
 use super::contract_validation::{
-    generate_contract_key, validate_contract_key, validate_msg, verify_params, ContractKey,
+    generate_contract_key, validate_contract_key, validate_msg, verify_params,
+    verify_simulation_params, ContractKey,
 };
 use super::gas::WasmCosts;
 use super::io::{
@@ -74,6 +115,7 @@ pub fn init(
     admin: &[u8],       // admin's canonical address or null if no admin
 ) -> Result<InitSuccess, EnclaveError> {
     trace!("Starting init");
+    query_cache::invalidate();
 
     //let start = Instant::now();
     let contract_code = ContractCode::new(contract);
@@ -85,6 +127,8 @@ pub fn init(
         contract_hash
     );
 
+    check_instantiate_allowed(&contract_hash)?;
+
     //let start = Instant::now();
     let base_env: BaseEnv = extract_base_env(env)?;
 
@@ -117,12 +161,13 @@ pub fn init(
 
     let parsed_sig_info: SigInfo = extract_sig_info(sig_info)?;
 
-    let (secret_msg, decrypted_msg) =
+    let (secret_msg, decrypted_msg, was_msg_encrypted) =
         if let Some(decrypted_secret_msg) = try_get_decrypted_secret_msg(msg) {
             trace!("init input before decryption: {:?}", base64::encode(msg));
             (
                 decrypted_secret_msg.secret_msg,
                 decrypted_secret_msg.decrypted_msg,
+                true,
             )
         } else {
             trace!("init input was plaintext: {:?}", base64::encode(msg));
@@ -133,7 +178,7 @@ pub fn init(
             };
 
             let decrypted_msg = secret_msg.msg.clone();
-            (secret_msg, decrypted_msg)
+            (secret_msg, decrypted_msg, false)
         };
 
     //let start = Instant::now();
@@ -152,6 +197,8 @@ pub fn init(
     // let duration = start.elapsed();
     // trace!("Time elapsed in verify_params: {:?}", duration);
 
+    let call_stack = CallStack::of(&parsed_sig_info, false)?;
+
     //let start = Instant::now();
     // let decrypted_msg = secret_msg.decrypt()?;
     // let duration = start.elapsed();
@@ -171,6 +218,11 @@ pub fn init(
     // let duration = start.elapsed();
     // trace!("Time elapsed in validate_msg: {:?}", duration);
 
+    #[cfg(feature = "differential-execution")]
+    let candidate_context = unsafe { context.unsafe_clone() };
+    #[cfg(feature = "differential-execution")]
+    let candidate_msg = validated_msg.clone();
+
     //let start = Instant::now();
     let mut engine = start_engine(
         context,
@@ -182,6 +234,7 @@ pub fn init(
         secret_msg.nonce,
         secret_msg.user_public_key,
         base_env.0.block.time,
+        EngineBackend::Current,
     )?;
     // let duration = start.elapsed();
     // trace!("Time elapsed in start_engine: {:?}", duration);
@@ -202,13 +255,48 @@ pub fn init(
 
     update_msg_counter(block_height);
     //let start = Instant::now();
+    #[cfg(feature = "differential-execution")]
+    let recording = differential::Recording::start();
     let result = engine.init(&versioned_env, validated_msg);
     // let duration = start.elapsed();
     // trace!("Time elapsed in engine.init: {:?}", duration);
 
     *used_gas = engine.gas_used();
 
-    let output = result?;
+    #[cfg(feature = "differential-execution")]
+    differential::compare_candidate(
+        recording,
+        "init",
+        &contract_hash,
+        &result,
+        *used_gas,
+        engine.write_set(),
+        || {
+            let mut candidate = start_engine(
+                candidate_context,
+                gas_limit,
+                &contract_code,
+                &og_contract_key,
+                ContractOperation::Init,
+                query_depth,
+                secret_msg.nonce,
+                secret_msg.user_public_key,
+                base_env.0.block.time,
+                EngineBackend::Candidate,
+            )?;
+            let result = candidate.init(&versioned_env, candidate_msg);
+            Ok(RunSummary::new(
+                &result,
+                candidate.gas_used(),
+                candidate.write_set(),
+            ))
+        },
+    );
+
+    let output = result.map_err(|err| {
+        engine.apply_uniform_gas_on_failure(used_gas);
+        err
+    })?;
 
     #[cfg(not(feature = "random"))]
     let random: Option<Binary> = None;
@@ -218,6 +306,21 @@ pub fn init(
     engine
         .flush_cache(random)
         .map_err(|_| EnclaveError::FailedFunctionCall)?;
+    engine.apply_uniform_gas(used_gas)?;
+
+    let receipt = if was_msg_encrypted {
+        execution_receipt(
+            contract_address.as_str(),
+            &contract_hash,
+            &secret_msg,
+            &decrypted_msg,
+            &output,
+            *used_gas,
+            block_height,
+        )?
+    } else {
+        None
+    };
 
     // TODO: copy cosmwasm's structures to enclave
     // TODO: ref: https://github.com/CosmWasm/cosmwasm/blob/b971c037a773bf6a5f5d08a88485113d9b9e8e7b/packages/std/src/init_handle.rs#L129
@@ -231,9 +334,15 @@ pub fn init(
         versioned_env.get_contract_hash(),
         reply_params,
         &canonical_sender_address,
+        &og_contract_key,
         false,
         false,
     )?;
+    let output = attach_call_stack(output, &call_stack, &canonical_contract_address)?;
+    let output = match receipt {
+        Some(receipt) => attach_execution_receipt(output, &receipt)?,
+        None => output,
+    };
 
     // let duration = start.elapsed();
     // trace!("Time elapsed in encrypt_output: {:?}", duration);
@@ -242,6 +351,8 @@ pub fn init(
 
     let admin_proof = generate_admin_proof(&canonical_admin_address.0 .0, &og_contract_key);
 
+    record_code_hash(canonical_contract_address.as_slice(), &contract_hash);
+
     Ok(InitSuccess {
         output,
         contract_key: og_contract_key,
@@ -289,6 +400,7 @@ pub fn migrate(
     admin_proof: &[u8],
 ) -> Result<MigrateSuccess, EnclaveError> {
     debug!("Starting migrate");
+    query_cache::invalidate();
 
     //let start = Instant::now();
     let contract_code = ContractCode::new(contract);
@@ -358,6 +470,8 @@ pub fn migrate(
     // let duration = start.elapsed();
     // trace!("Time elapsed in verify_params: {:?}", duration);
 
+    let call_stack = CallStack::of(&parsed_sig_info, false)?;
+
     //let start = Instant::now();
     let decrypted_msg = secret_msg.decrypt()?;
     // let duration = start.elapsed();
@@ -377,6 +491,13 @@ pub fn migrate(
     // let duration = start.elapsed();
     // trace!("Time elapsed in validate_msg: {:?}", duration);
 
+    #[cfg(feature = "differential-execution")]
+    let candidate_context = unsafe { context.unsafe_clone() };
+    #[cfg(feature = "differential-execution")]
+    let candidate_msg = validated_msg.clone();
+    #[cfg(feature = "differential-execution")]
+    let candidate_timestamp = base_env.0.block.time;
+
     //let start = Instant::now();
     let mut engine = start_engine(
         context,
@@ -388,6 +509,7 @@ pub fn migrate(
         secret_msg.nonce,
         secret_msg.user_public_key,
         base_env.0.block.time,
+        EngineBackend::Current,
     )?;
     // let duration = start.elapsed();
     // trace!("Time elapsed in start_engine: {:?}", duration);
@@ -413,17 +535,53 @@ pub fn migrate(
     );
 
     update_msg_counter(block_height);
+    #[cfg(feature = "differential-execution")]
+    let recording = differential::Recording::start();
     let result = engine.migrate(&versioned_env, validated_msg);
 
     *used_gas = engine.gas_used();
 
-    let output = result?;
+    #[cfg(feature = "differential-execution")]
+    differential::compare_candidate(
+        recording,
+        "migrate",
+        &contract_hash,
+        &result,
+        *used_gas,
+        engine.write_set(),
+        || {
+            let mut candidate = start_engine(
+                candidate_context,
+                gas_limit,
+                &contract_code,
+                &og_contract_key,
+                ContractOperation::Migrate,
+                query_depth,
+                secret_msg.nonce,
+                secret_msg.user_public_key,
+                candidate_timestamp,
+                EngineBackend::Candidate,
+            )?;
+            let result = candidate.migrate(&versioned_env, candidate_msg);
+            Ok(RunSummary::new(
+                &result,
+                candidate.gas_used(),
+                candidate.write_set(),
+            ))
+        },
+    );
+
+    let output = result.map_err(|err| {
+        engine.apply_uniform_gas_on_failure(used_gas);
+        err
+    })?;
 
     let random = versioned_env.get_random();
 
     engine
         .flush_cache(random)
         .map_err(|_| EnclaveError::FailedFunctionCall)?;
+    engine.apply_uniform_gas(used_gas)?;
 
     let output = post_process_output(
         output,
@@ -432,9 +590,11 @@ pub fn migrate(
         versioned_env.get_contract_hash(),
         reply_params,
         &canonical_sender_address,
+        &og_contract_key,
         false,
         false,
     )?;
+    let output = attach_call_stack(output, &call_stack, &canonical_contract_address)?;
 
     // let duration = start.elapsed();
     // trace!("Time elapsed in encrypt_output: {:?}", duration);
@@ -453,6 +613,8 @@ pub fn migrate(
         new_contract_key, new_contract_key_proof
     );
 
+    record_code_hash(canonical_contract_address.as_slice(), &contract_hash);
+
     Ok(MigrateSuccess {
         output,
         new_contract_key,
@@ -529,6 +691,351 @@ pub fn update_admin(
     Ok(UpdateAdminSuccess { new_admin_proof })
 }
 
+/// Re-encrypt the state key of a contract to an auditor, see `key_disclosure`
+#[cfg_attr(not(feature = "light-client-validation"), allow(unused_variables))]
+pub fn disclose_contract_key(
+    env: &[u8],
+    code_hash: &[u8; HASH_SIZE],
+    admin: &[u8],
+    admin_proof: &[u8],
+    msg: &[u8],
+    msg_proof: &[u8],
+) -> Result<[u8; ENCRYPTED_CONTRACT_KEY_LENGTH], EnclaveError> {
+    debug!("Starting disclose_contract_key");
+
+    let request = KeyDisclosureRequest::parse(msg)?;
+
+    if request.authority != authority_address() {
+        warn!(
+            "key disclosure was requested by {}, not by governance",
+            request.authority
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    let base_env: BaseEnv = extract_base_env(env)?;
+
+    #[cfg(feature = "light-client-validation")]
+    verify_block_info(&base_env)?;
+
+    let (_sender, contract_address, block_height, _sent_funds) = base_env.get_verification_params();
+
+    if contract_address.as_str() != request.contract {
+        warn!(
+            "key disclosure for {} was passed the env of {}",
+            request.contract, contract_address
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    let canonical_contract_address = to_canonical(contract_address)?;
+    validate_contract_key(&base_env, &canonical_contract_address, code_hash)?;
+
+    // x/compute only stores the msg once governance passed it
+    #[cfg(feature = "light-client-validation")]
+    verify_compute_state(&disclosure_key(&canonical_contract_address), msg, msg_proof)?;
+
+    let og_contract_key = base_env.get_og_contract_key()?;
+    let canonical_admin_address = CanonicalAddr::from_vec(admin.to_vec());
+
+    if generate_admin_proof(&canonical_admin_address.0 .0, &og_contract_key) != admin_proof {
+        error!("Failed to validate the admin of a key disclosure");
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    request.verify_admin_signature(&canonical_admin_address, &base_env.0.block.chain_id)?;
+
+    let encrypted_key = encrypt_to_auditor(
+        &get_symmetrical_key_new(&og_contract_key),
+        &request.auditor_public_key,
+        &request.contract,
+        request.nonce,
+    )?;
+
+    AuditLog::append(AuditEntry::ContractKeyDisclosed {
+        height: block_height,
+        contract_address: request.contract.clone(),
+        admin: hex::encode(admin),
+        auditor_public_key: hex::encode(request.auditor_public_key),
+        authorization_hash: hex::encode(sha_256(msg)),
+    })
+    .map_err(|err| {
+        error!(
+            "Failed to record a key disclosure in the audit log: {}",
+            err
+        );
+        EnclaveError::InternalError
+    })?;
+
+    info!(
+        "disclosed the key of contract {} to auditor {}",
+        request.contract,
+        hex::encode(request.auditor_public_key)
+    );
+
+    Ok(encrypted_key)
+}
+
+/// Decrypt a chunk of a contract's stored pairs and wrap them for a backup, see `state_backup`
+pub fn export_state(
+    env: &[u8],
+    code_hash: &[u8; HASH_SIZE],
+    admin: &[u8],
+    admin_proof: &[u8],
+    authorization: &[u8],
+    stored_pairs: &[u8],
+) -> Result<Vec<u8>, EnclaveError> {
+    debug!("Starting export_state");
+
+    let base_env: BaseEnv = extract_base_env(env)?;
+    let og_contract_key = validate_state_backup(
+        &base_env,
+        code_hash,
+        admin,
+        admin_proof,
+        authorization,
+        BackupOperation::Export,
+    )?;
+
+    let pairs = decrypt_stored_pairs(stored_pairs, &og_contract_key)?;
+
+    let admin = CanonicalAddr::from_vec(admin.to_vec());
+    wrap_chunk(&pairs, &backup_key(code_hash, &admin))
+}
+
+/// Unwrap a chunk of a backup and encrypt its pairs for the contract, returns the pairs to store,
+/// see `state_backup`
+pub fn import_state(
+    env: &[u8],
+    code_hash: &[u8; HASH_SIZE],
+    admin: &[u8],
+    admin_proof: &[u8],
+    authorization: &[u8],
+    backup_chunk: &[u8],
+) -> Result<Vec<u8>, EnclaveError> {
+    debug!("Starting import_state");
+
+    let base_env: BaseEnv = extract_base_env(env)?;
+
+    #[cfg(feature = "light-client-validation")]
+    verify_block_info(&base_env)?;
+
+    let og_contract_key = validate_state_backup(
+        &base_env,
+        code_hash,
+        admin,
+        admin_proof,
+        authorization,
+        BackupOperation::Import,
+    )?;
+
+    let admin = CanonicalAddr::from_vec(admin.to_vec());
+    let encryption_salt = get_encryption_salt(base_env.0.block.time);
+
+    let mut stored_pairs = vec![];
+    for (key, value) in unwrap_chunk(backup_chunk, &backup_key(code_hash, &admin))? {
+        stored_pairs.push(encrypt_stored_pair(
+            &key,
+            &value,
+            &og_contract_key,
+            &encryption_salt,
+        )?);
+    }
+
+    Ok(encode_pairs(&stored_pairs))
+}
+
+/// Checks the contract key and the admin of a state backup operation, and that the admin signed
+/// it. Returns the contract's state key.
+fn validate_state_backup(
+    base_env: &BaseEnv,
+    code_hash: &[u8; HASH_SIZE],
+    admin: &[u8],
+    admin_proof: &[u8],
+    authorization: &[u8],
+    operation: BackupOperation,
+) -> Result<ContractKey, EnclaveError> {
+    let authorization = StateBackupAuthorization::parse(authorization)?;
+
+    let (_sender, contract_address, block_height, _sent_funds) = base_env.get_verification_params();
+
+    if contract_address.as_str() != authorization.contract {
+        warn!(
+            "state backup authorization for {} was passed the env of {}",
+            authorization.contract, contract_address
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    let og_contract_key = validate_contract_of_env(base_env, code_hash)?;
+    let canonical_admin_address = CanonicalAddr::from_vec(admin.to_vec());
+
+    if generate_admin_proof(&canonical_admin_address.0 .0, &og_contract_key) != admin_proof {
+        error!("Failed to validate the admin of a state backup");
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    authorization.verify(operation, &canonical_admin_address, block_height)?;
+
+    Ok(og_contract_key)
+}
+
+/// Returns the leaves of a chunk of a contract's stored pairs, in the order of the pairs, see
+/// `state_commitment`
+pub fn state_commitment_leaves(
+    env: &[u8],
+    code_hash: &[u8; HASH_SIZE],
+    stored_pairs: &[u8],
+) -> Result<Vec<u8>, EnclaveError> {
+    debug!("Starting state_commitment_leaves");
+
+    let base_env: BaseEnv = extract_base_env(env)?;
+    let og_contract_key = validate_contract_of_env(&base_env, code_hash)?;
+    let leaf_key = leaf_key(&get_symmetrical_key_new(&og_contract_key));
+
+    let mut leaves = vec![];
+    for (key, value) in decrypt_stored_pairs(stored_pairs, &og_contract_key)? {
+        leaves.extend_from_slice(&leaf_hash(&leaf_key, &key, &value));
+    }
+
+    Ok(leaves)
+}
+
+/// Fold a chunk of a contract's stored pairs, sorted by their leaves, into a state commitment.
+/// Returns the sealed progress to pass with the next chunk, or the signed commitment after the
+/// last one, see `state_commitment`
+pub fn fold_state_commitment(
+    env: &[u8],
+    code_hash: &[u8; HASH_SIZE],
+    sealed_progress: &[u8],
+    stored_pairs: &[u8],
+    last_chunk: bool,
+) -> Result<Vec<u8>, EnclaveError> {
+    debug!("Starting fold_state_commitment");
+
+    let base_env: BaseEnv = extract_base_env(env)?;
+    let og_contract_key = validate_contract_of_env(&base_env, code_hash)?;
+    let state_key = get_symmetrical_key_new(&og_contract_key);
+
+    let (_sender, contract_address, block_height, _sent_funds) = base_env.get_verification_params();
+
+    let mut progress = if sealed_progress.is_empty() {
+        CommitmentProgress::new(block_height)
+    } else {
+        CommitmentProgress::unseal(sealed_progress, &state_key)?
+    };
+
+    if progress.height != block_height {
+        warn!(
+            "state commitment started at height {} was continued at height {}",
+            progress.height, block_height
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    let leaf_key = leaf_key(&state_key);
+    for (key, value) in decrypt_stored_pairs(stored_pairs, &og_contract_key)? {
+        progress.push(leaf_hash(&leaf_key, &key, &value))?;
+    }
+
+    if !last_chunk {
+        return progress.seal(&state_key);
+    }
+
+    let commitment = SignedStateCommitment::sign(
+        contract_address.as_str(),
+        code_hash,
+        &progress,
+        &commitment_signing_key(),
+    )?;
+
+    serde_json::to_vec(&commitment).map_err(|err| {
+        error!("failed to encode a state commitment: {:?}", err);
+        EnclaveError::InternalError
+    })
+}
+
+/// Re-encrypt the pairs of a chunk of a contract's stored pairs that are still under the genesis
+/// seed. Returns them to be stored, with the sealed progress to pass with the next chunk, see
+/// `state_reencryption`
+pub fn reencrypt_state(
+    env: &[u8],
+    code_hash: &[u8; HASH_SIZE],
+    sealed_progress: &[u8],
+    stored_pairs: &[u8],
+    last_chunk: bool,
+) -> Result<Vec<u8>, EnclaveError> {
+    debug!("Starting reencrypt_state");
+
+    let base_env: BaseEnv = extract_base_env(env)?;
+    let og_contract_key = validate_contract_of_env(&base_env, code_hash)?;
+    let state_key = get_symmetrical_key_new(&og_contract_key);
+
+    let mut progress = if sealed_progress.is_empty() {
+        ReencryptionProgress::default()
+    } else {
+        ReencryptionProgress::unseal(sealed_progress, &state_key)?
+    };
+
+    let reencrypted = reencrypt_chunk(
+        &mut progress,
+        &decode_pairs(stored_pairs)?,
+        &og_contract_key,
+        last_chunk,
+    )?;
+
+    if progress.complete {
+        let (_sender, contract_address, _block_height, _sent_funds) =
+            base_env.get_verification_params();
+        info!(
+            "contract {} re-encrypted {} pairs, {} are under retired seeds",
+            contract_address, progress.reencrypted, progress.retired
+        );
+    }
+
+    encode_output(&progress, &reencrypted, &state_key)
+}
+
+/// Checks that `code_hash` is the code of the contract in the env, returns its key
+fn validate_contract_of_env(
+    base_env: &BaseEnv,
+    code_hash: &[u8; HASH_SIZE],
+) -> Result<ContractKey, EnclaveError> {
+    let (_sender, contract_address, _block_height, _sent_funds) =
+        base_env.get_verification_params();
+
+    let canonical_contract_address = to_canonical(contract_address)?;
+    validate_contract_key(base_env, &canonical_contract_address, code_hash)?;
+
+    base_env.get_og_contract_key()
+}
+
+/// Decrypt a chunk of a contract's pairs as they're stored, without its public values. Values
+/// still stored in the legacy format can't be decrypted, since their keys are digests.
+fn decrypt_stored_pairs(
+    stored_pairs: &[u8],
+    og_contract_key: &ContractKey,
+) -> Result<StatePairs, EnclaveError> {
+    let mut pairs = vec![];
+    for (key, value) in decode_pairs(stored_pairs)? {
+        // Public values are in the clear in the chain's state already
+        if key.starts_with(PUBLIC_KEY_PREFIX) {
+            continue;
+        }
+
+        match decrypt_stored_pair(&key, &value, og_contract_key)? {
+            Some(pair) => pairs.push(pair),
+            None => {
+                // Skipping the pair would pass an incomplete state off as a complete one
+                warn!("the pair {:?} is stored in the legacy format", key);
+                return Err(EnclaveError::ValidationFailure);
+            }
+        }
+    }
+
+    Ok(pairs)
+}
+
 #[cfg_attr(feature = "cargo-clippy", allow(clippy::too_many_arguments))]
 pub fn handle(
     context: Ctx,
@@ -541,7 +1048,68 @@ pub fn handle(
     handle_type: u8,
 ) -> Result<HandleSuccess, EnclaveError> {
     trace!("Starting handle");
+    query_cache::invalidate();
+
+    handle_impl(
+        context,
+        gas_limit,
+        used_gas,
+        contract,
+        env,
+        msg,
+        sig_info,
+        HandleType::try_from(handle_type)?,
+        false,
+    )
+}
+
+/// Execute a tx that isn't part of any block, to know its gas and response. State writes
+/// still go through the node, which must run the simulation on a branch of its state that it
+/// discards, so they're metered exactly like in a real execution. The enclave doesn't change any
+/// of its own state. See `verify_simulation_params` for the msgs that can be simulated.
+#[cfg_attr(feature = "cargo-clippy", allow(clippy::too_many_arguments))]
+pub fn simulate(
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: &mut u64,
+    contract: &[u8],
+    env: &[u8],
+    msg: &[u8],
+    sig_info: &[u8],
+) -> Result<HandleSuccess, EnclaveError> {
+    trace!("Starting simulate");
+
+    // Queries inside the simulation see its writes, so neither the results cached before it nor
+    // the ones cached during it can be trusted
+    query_cache::invalidate();
+    let result = handle_impl(
+        context,
+        gas_limit,
+        used_gas,
+        contract,
+        env,
+        msg,
+        sig_info,
+        HandleType::HANDLE_TYPE_EXECUTE,
+        true,
+    );
+    query_cache::invalidate();
+
+    result
+}
 
+#[cfg_attr(feature = "cargo-clippy", allow(clippy::too_many_arguments))]
+fn handle_impl(
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: &mut u64,
+    contract: &[u8],
+    env: &[u8],
+    msg: &[u8],
+    sig_info: &[u8],
+    parsed_handle_type: HandleType,
+    is_simulation: bool,
+) -> Result<HandleSuccess, EnclaveError> {
     let contract_code = ContractCode::new(contract);
     let contract_hash = contract_code.hash();
 
@@ -552,8 +1120,11 @@ pub fn handle(
 
     let base_env: BaseEnv = extract_base_env(env)?;
 
+    // Simulations run on top of the last block, not in a verified one
     #[cfg(feature = "light-client-validation")]
-    verify_block_info(&base_env)?;
+    if !is_simulation {
+        verify_block_info(&base_env)?;
+    }
 
     let query_depth = extract_query_depth(env)?;
 
@@ -561,14 +1132,12 @@ pub fn handle(
 
     let canonical_contract_address = to_canonical(contract_address)?;
 
-    validate_contract_key(&base_env, &canonical_contract_address, &contract_code)?;
+    validate_contract_key(&base_env, &canonical_contract_address, &contract_hash)?;
 
     let parsed_sig_info: SigInfo = extract_sig_info(sig_info)?;
 
     // The flow of handle is now used for multiple messages (such ash Handle, Reply, IBC)
     // When the message is handle, we expect it always to be encrypted while in Reply & IBC it might be plaintext
-    let parsed_handle_type = HandleType::try_from(handle_type)?;
-
     trace!("Handle type is {:?}", parsed_handle_type);
 
     let ParsedMessage {
@@ -579,7 +1148,12 @@ pub fn handle(
         secret_msg,
         decrypted_msg,
         data_for_validation,
-    } = parse_message(msg, &parsed_handle_type)?;
+    } = parse_message(
+        msg,
+        &parsed_handle_type,
+        contract_address,
+        base_env.0.block.time,
+    )?;
 
     let canonical_sender_address = match to_canonical(sender) {
         Ok(can) => can,
@@ -592,19 +1166,54 @@ pub fn handle(
     // - Plaintext replies (resulting from an IBC call)
     // - IBC WASM Hooks
     // - (In the future:) ICA
-    verify_params(
+    let decrypted_msg = if is_simulation {
+        verify_simulation_params(&parsed_sig_info, was_msg_encrypted, &decrypted_msg)?
+    } else {
+        verify_params(
+            &parsed_sig_info,
+            sent_funds,
+            &canonical_sender_address,
+            contract_address,
+            &secret_msg,
+            should_verify_sig_info,
+            should_verify_input,
+            VerifyParamsType::HandleType(parsed_handle_type),
+            None,
+            None,
+        )?;
+        decrypted_msg
+    };
+
+    // Cron callbacks aren't signed by anyone, the contract's schedule in the chain state
+    // authorizes them instead, and the contract only receives the height
+    let decrypted_msg = if parsed_handle_type == HandleType::HANDLE_TYPE_CRON {
+        verify_cron_callback(
+            &canonical_contract_address,
+            contract_address,
+            &decrypted_msg,
+            block_height,
+            gas_limit,
+        )?
+    } else {
+        decrypted_msg
+    };
+
+    let call_stack = CallStack::of(
         &parsed_sig_info,
-        sent_funds,
-        &canonical_sender_address,
-        contract_address,
-        &secret_msg,
-        should_verify_sig_info,
-        should_verify_input,
-        VerifyParamsType::HandleType(parsed_handle_type),
-        None,
-        None,
+        parsed_handle_type == HandleType::HANDLE_TYPE_REPLY,
     )?;
 
+    // Acks and timeouts of transfers are encrypted to whoever made the contract send them
+    let (secret_msg, should_encrypt_output) = match recover_sender_context(
+        &parsed_sig_info,
+        &parsed_handle_type,
+        msg,
+        &canonical_contract_address,
+    )? {
+        Some(sender_context) => (sender_context, true),
+        None => (secret_msg, should_encrypt_output),
+    };
+
     let mut validated_msg = decrypted_msg.clone();
     let mut reply_params: Option<Vec<ReplyParams>> = None;
     if was_msg_encrypted {
@@ -621,9 +1230,60 @@ pub fn handle(
 
     let og_contract_key = base_env.get_og_contract_key()?;
 
+    if parsed_handle_type == HandleType::HANDLE_TYPE_EXECUTE && is_native_snip20(&contract_hash) {
+        if let Some(output) = try_native_snip20_execute(
+            &context,
+            gas_limit,
+            used_gas,
+            &og_contract_key,
+            &canonical_sender_address,
+            block_height,
+            base_env.0.block.time,
+            &validated_msg,
+        )? {
+            let receipt = if was_msg_encrypted && !is_simulation {
+                execution_receipt(
+                    contract_address.as_str(),
+                    &contract_hash,
+                    &secret_msg,
+                    &decrypted_msg,
+                    &output,
+                    *used_gas,
+                    block_height,
+                )?
+            } else {
+                None
+            };
+
+            let output = post_process_output(
+                output,
+                &secret_msg,
+                &canonical_contract_address,
+                &hex::encode(contract_hash),
+                reply_params,
+                &canonical_sender_address,
+                &og_contract_key,
+                false,
+                false,
+            )?;
+            let output = attach_call_stack(output, &call_stack, &canonical_contract_address)?;
+            let output = match receipt {
+                Some(receipt) => attach_execution_receipt(output, &receipt)?,
+                None => output,
+            };
+
+            return Ok(HandleSuccess { output });
+        }
+    }
+
     // Although the operation here is not always handle it is irrelevant in this case
     // because it only helps to decide whether to check floating points or not
     // In this case we want to do the same as in Handle both for Reply and for others so we can always pass "Handle".
+    #[cfg(feature = "differential-execution")]
+    let candidate_context = unsafe { context.unsafe_clone() };
+    #[cfg(feature = "differential-execution")]
+    let candidate_msg = validated_msg.clone();
+
     let mut engine = start_engine(
         context,
         gas_limit,
@@ -634,8 +1294,28 @@ pub fn handle(
         secret_msg.nonce,
         secret_msg.user_public_key,
         base_env.0.block.time,
+        EngineBackend::Current,
     )?;
 
+    if let HandleType::HANDLE_TYPE_JOB_RESULT = parsed_handle_type {
+        engine.record_delivered_job(job_result_id(&decrypted_msg)?)?;
+    }
+
+    if engine
+        .supported_features()
+        .contains(&ContractFeature::ReentrancyGuard)
+    {
+        call_stack.check_reentrancy(&canonical_contract_address, parsed_handle_type)?;
+    }
+
+    // v0.10 contracts only export `handle`, which would take the callback for a tx
+    if parsed_handle_type == HandleType::HANDLE_TYPE_CRON
+        && engine.get_api_version() == CosmWasmApiVersion::V010
+    {
+        warn!("v0.10 contracts don't have cron callbacks");
+        return Err(EnclaveError::ValidationFailure);
+    }
+
     let mut versioned_env = base_env
         .clone()
         .into_versioned_env(&engine.get_api_version());
@@ -649,6 +1329,8 @@ pub fn handle(
         HandleType::HANDLE_TYPE_EXECUTE => {}
         // Reply & IBC stuff: no msg.sender, set it to null just in case
         // WASM Hooks: cannot verify sender, set it to null
+        // Job results: authenticated by the worker enclave, not by the tx sender
+        // Cron callbacks: called by the chain at the end of the block
         HandleType::HANDLE_TYPE_REPLY
         | HandleType::HANDLE_TYPE_IBC_CHANNEL_OPEN
         | HandleType::HANDLE_TYPE_IBC_CHANNEL_CONNECT
@@ -658,31 +1340,77 @@ pub fn handle(
         | HandleType::HANDLE_TYPE_IBC_PACKET_TIMEOUT
         | HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_INCOMING_TRANSFER
         | HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_ACK
-        | HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_TIMEOUT => {
-            versioned_env.set_msg_sender("")
-        }
+        | HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_TIMEOUT
+        | HandleType::HANDLE_TYPE_JOB_RESULT
+        | HandleType::HANDLE_TYPE_CRON => versioned_env.set_msg_sender(""),
     }
 
     #[cfg(feature = "random")]
     {
         let contract_key_for_random = base_env.get_latest_contract_key()?;
-        set_random_in_env(
-            block_height,
-            &contract_key_for_random,
-            &mut engine,
-            &mut versioned_env,
-        );
+        if is_simulation {
+            set_simulation_random_in_env(
+                block_height,
+                &contract_key_for_random,
+                &engine,
+                &mut versioned_env,
+            );
+        } else {
+            set_random_in_env(
+                block_height,
+                &contract_key_for_random,
+                &mut engine,
+                &mut versioned_env,
+            );
+        }
     }
 
     versioned_env.set_contract_hash(&contract_hash);
 
-    update_msg_counter(block_height);
+    if !is_simulation {
+        update_msg_counter(block_height);
+    }
 
+    #[cfg(feature = "differential-execution")]
+    let recording = differential::Recording::start();
     let result = engine.handle(&versioned_env, validated_msg, &parsed_handle_type);
 
     *used_gas = engine.gas_used();
 
-    let mut output = result?;
+    #[cfg(feature = "differential-execution")]
+    differential::compare_candidate(
+        recording,
+        HandleType::get_export_name(&parsed_handle_type),
+        &contract_hash,
+        &result,
+        *used_gas,
+        engine.write_set(),
+        || {
+            let mut candidate = start_engine(
+                candidate_context,
+                gas_limit,
+                &contract_code,
+                &og_contract_key,
+                ContractOperation::Handle,
+                query_depth,
+                secret_msg.nonce,
+                secret_msg.user_public_key,
+                base_env.0.block.time,
+                EngineBackend::Candidate,
+            )?;
+            let result = candidate.handle(&versioned_env, candidate_msg, &parsed_handle_type);
+            Ok(RunSummary::new(
+                &result,
+                candidate.gas_used(),
+                candidate.write_set(),
+            ))
+        },
+    );
+
+    let mut output = result.map_err(|err| {
+        engine.apply_uniform_gas_on_failure(used_gas);
+        err
+    })?;
 
     let random = versioned_env.get_random();
 
@@ -691,6 +1419,25 @@ pub fn handle(
         .flush_cache(random)
         .map_err(|_| EnclaveError::FailedFunctionCall)?;
     *used_gas = used_gas.saturating_sub(refund_cache_gas);
+    engine.apply_uniform_gas(used_gas)?;
+    let report = engine.execution_report(call_stack.callers.len() as u32);
+
+    let receipt = if parsed_handle_type == HandleType::HANDLE_TYPE_EXECUTE
+        && was_msg_encrypted
+        && !is_simulation
+    {
+        execution_receipt(
+            contract_address.as_str(),
+            &contract_hash,
+            &secret_msg,
+            &decrypted_msg,
+            &output,
+            *used_gas,
+            block_height,
+        )?
+    } else {
+        None
+    };
 
     debug!(
         "(2) nonce just before encrypt_output: nonce = {:x?} pubkey = {:x?}",
@@ -704,6 +1451,7 @@ pub fn handle(
             versioned_env.get_contract_hash(),
             reply_params,
             &canonical_sender_address,
+            &og_contract_key,
             false,
             is_ibc_msg(parsed_handle_type),
         )?;
@@ -715,6 +1463,16 @@ pub fn handle(
         output = finalize_raw_output(raw_output, false, is_ibc_msg(parsed_handle_type), false)?;
     }
 
+    output = attach_call_stack(output, &call_stack, &canonical_contract_address)?;
+
+    if let Some(receipt) = receipt {
+        output = attach_execution_receipt(output, &receipt)?;
+    }
+
+    if let Some(report) = report {
+        output = attach_execution_report(output, report)?;
+    }
+
     Ok(HandleSuccess { output })
 }
 
@@ -738,6 +1496,27 @@ fn set_random_in_env(
     }
 }
 
+#[cfg(feature = "random")]
+fn set_simulation_random_in_env(
+    block_height: u64,
+    contract_key: &[u8; 64],
+    engine: &Engine,
+    versioned_env: &mut CwEnv,
+) {
+    let random = match versioned_env.get_random() {
+        Some(rand)
+            if engine
+                .supported_features()
+                .contains(&ContractFeature::Random) =>
+        {
+            Some(derive_simulation_random(&rand, contract_key, block_height))
+        }
+        _ => None,
+    };
+
+    versioned_env.set_random(random);
+}
+
 fn extract_sig_info(sig_info: &[u8]) -> Result<SigInfo, EnclaveError> {
     serde_json::from_slice(sig_info).map_err(|err| {
         warn!(
@@ -765,13 +1544,27 @@ pub fn query(
     let base_env: BaseEnv = extract_base_env(env)?;
     let query_depth = extract_query_depth(env)?;
 
-    let (_, contract_address, _, _) = base_env.get_verification_params();
+    let (_, contract_address, block_height, _) = base_env.get_verification_params();
 
     let canonical_contract_address = to_canonical(contract_address)?;
 
-    validate_contract_key(&base_env, &canonical_contract_address, &contract_code)?;
+    validate_contract_key(&base_env, &canonical_contract_address, &contract_hash)?;
 
-    let secret_msg = SecretMessage::from_slice(msg)?;
+    // Queries from contracts see the state of the block being executed, which changes within it
+    let cacheable = query_depth == 1 && extract_query_cacheable(env)?;
+    if cacheable {
+        if let Some((response, cached_gas)) =
+            query_cache::get(canonical_contract_address.as_slice(), msg, block_height)
+        {
+            *used_gas = cached_gas;
+            return Ok(response);
+        }
+    }
+
+    let mut secret_msg = SecretMessage::from_slice(msg)?;
+    if let Some(session_msg) = resolve_session_message(&secret_msg, block_height)? {
+        secret_msg = session_msg;
+    }
     let decrypted_msg = secret_msg.decrypt()?;
 
     let ValidatedMessage { validated_msg, .. } = validate_msg(
@@ -794,7 +1587,26 @@ pub fn query(
         secret_msg.nonce,
         secret_msg.user_public_key,
         base_env.0.block.time,
+        EngineBackend::Current,
     )?;
+    // The node's query limits only apply to queries from outside the chain, like caching
+    engine.limit_query(&query_limits(cacheable));
+    if cacheable {
+        if let Some(id) = extract_query_cancellation_id(env)? {
+            let cancellation =
+                QueryCancellation::register(id).ok_or(EnclaveError::ValidationFailure)?;
+            engine.allow_cancellation(cancellation);
+        }
+    }
+
+    // Queries from contracts are sent again by every node that executes the block
+    let replay_protected = cacheable
+        && engine
+            .supported_features()
+            .contains(&ContractFeature::QueryReplayProtection);
+    if replay_protected {
+        check_query_replay(canonical_contract_address.as_slice(), msg)?;
+    }
 
     let mut versioned_env = base_env
         .clone()
@@ -804,7 +1616,11 @@ pub fn query(
 
     let result = engine.query(&versioned_env, validated_msg);
     *used_gas = engine.gas_used();
-    let output = result?;
+    let output = result.map_err(|err| {
+        engine.apply_uniform_gas_on_failure(used_gas);
+        err
+    })?;
+    engine.apply_uniform_gas(used_gas)?;
 
     let output = post_process_output(
         output,
@@ -813,11 +1629,144 @@ pub fn query(
         "",   // Not used for queries (can't call a sub-message from a query),
         None, // Not used for queries (Query response is not replied to the caller),
         &CanonicalAddr(Binary(Vec::new())), // Not used for queries (used only for replies)
+        &og_contract_key,
         true,
         false,
     )?;
 
-    Ok(QuerySuccess { output })
+    let response = QuerySuccess { output };
+    // A cached response would answer a replay without checking it
+    if cacheable && !replay_protected {
+        query_cache::insert(
+            canonical_contract_address.as_slice(),
+            msg,
+            block_height,
+            &response,
+            *used_gas,
+        );
+    }
+
+    Ok(response)
+}
+
+/// Runs an off-chain job: opens the input the contract in `env` sealed for `handler`, and calls
+/// the handler's `query` entry point with it. The handler's result is encrypted for the
+/// contract, as a user would encrypt a msg to it, and returned for the worker to attest and sign.
+/// The handler is isolated from the node, see `job_message`, and only the contract of `env` is
+/// taken from it, after checking its contract key.
+#[allow(clippy::too_many_arguments)]
+pub fn run_job(
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: &mut u64,
+    handler: &[u8],
+    contract_code_hash: &[u8; HASH_SIZE],
+    env: &[u8],
+    job: &[u8],
+) -> Result<SignedJobResult, EnclaveError> {
+    trace!("Entered run_job");
+
+    let handler_code = ContractCode::new(handler);
+    let handler_code_hash = handler_code.hash();
+
+    let job: EnqueuedJob = serde_json::from_slice(job).map_err(|err| {
+        warn!(
+            "got an error while trying to deserialize job into EnqueuedJob: {}",
+            err
+        );
+        EnclaveError::FailedToDeserialize
+    })?;
+    if job.handler_code_hash.as_slice() != handler_code_hash {
+        warn!(
+            "job {} is for handler {}, not {}",
+            job.job_id,
+            hex::encode(job.handler_code_hash.as_slice()),
+            hex::encode(handler_code_hash)
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    #[allow(unused_mut)]
+    let mut base_env: BaseEnv = extract_base_env(env)?;
+
+    #[cfg(feature = "light-client-validation")]
+    set_verified_block_info(&mut base_env);
+
+    let (_, contract_address, _, _) = base_env.get_verification_params();
+    if contract_address != &job.contract_address {
+        warn!(
+            "job {} was enqueued by {:?}, not {:?}",
+            job.job_id, job.contract_address, contract_address
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    let canonical_contract_address = to_canonical(contract_address)?;
+    validate_contract_key(&base_env, &canonical_contract_address, contract_code_hash)?;
+
+    let og_contract_key = base_env.get_og_contract_key()?;
+    let state_ikm = KEY_MANAGER
+        .get_consensus_state_ikm()
+        .map_err(|_| EnclaveError::DecryptionError)?;
+    let input = open_job_input(&state_ikm.genesis, &og_contract_key, &job)?;
+
+    let mut engine = start_engine(
+        context,
+        gas_limit,
+        &handler_code,
+        &og_contract_key,
+        ContractOperation::Query,
+        1,
+        [0u8; 32],
+        [0u8; 32],
+        base_env.0.block.time,
+    )?;
+    if engine.get_api_version() != CosmWasmApiVersion::V1 {
+        warn!("job handlers must use CosmWasm v1");
+        return Err(EnclaveError::ValidationFailure);
+    }
+    engine.isolate();
+
+    let mut versioned_env = base_env.into_versioned_env(&engine.get_api_version());
+    versioned_env.set_msg_sender("");
+    versioned_env.set_contract_hash(&handler_code_hash);
+
+    let result = engine.query(&versioned_env, input.clone());
+    *used_gas = engine.gas_used();
+    let output = result?;
+
+    let result = match serde_json::from_slice(&output) {
+        Ok(JobHandlerOutput::Ok(result)) => result,
+        Ok(JobHandlerOutput::Error(err)) => {
+            debug!("handler of job {} failed: {}", job.job_id, err);
+            return Err(EnclaveError::FailedFunctionCall);
+        }
+        Err(err) => {
+            warn!(
+                "handler of job {} returned malformed output: {}",
+                job.job_id, err
+            );
+            return Err(EnclaveError::FailedToDeserialize);
+        }
+    };
+
+    // prefixed with the contract's code hash, like a msg a user encrypts to it
+    let mut msg = hex::encode(contract_code_hash).into_bytes();
+    msg.extend_from_slice(result.as_slice());
+    let mut secret_result = SecretMessage {
+        nonce: [0u8; 32],
+        user_public_key: [0u8; 32],
+        msg,
+    };
+    rand_slice(&mut secret_result.nonce).map_err(|_| EnclaveError::FailedSeal)?;
+    rand_slice(&mut secret_result.user_public_key).map_err(|_| EnclaveError::FailedSeal)?;
+    secret_result.encrypt_in_place()?;
+
+    Ok(SignedJobResult::unsigned(
+        &job,
+        &input,
+        secret_result.to_vec(),
+    ))
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -831,6 +1780,7 @@ fn start_engine(
     nonce: IoNonce,
     user_public_key: Ed25519PublicKey,
     timestamp: u64,
+    backend: EngineBackend,
 ) -> Result<crate::wasm3::Engine, EnclaveError> {
     crate::wasm3::Engine::new(
         context,
@@ -843,6 +1793,7 @@ fn start_engine(
         user_public_key,
         query_depth,
         timestamp,
+        backend,
     )
 }
 
@@ -858,6 +1809,7 @@ fn extract_base_env(env: &[u8]) -> Result<BaseEnv, EnclaveError> {
         })
         .map(|base_env| {
             trace!("base env: {:?}", base_env);
+            crash_breadcrumbs::set_height(base_env.0.block.height);
             base_env
         })
 }
@@ -867,6 +1819,18 @@ struct EnvWithQD {
     query_depth: u32,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct EnvWithQueryCacheable {
+    #[serde(default)]
+    query_cacheable: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EnvWithQueryCancellationId {
+    #[serde(default)]
+    query_cancellation_id: Option<Uint64>,
+}
+
 /// Extract the query_depth from the env parameter.
 ///
 /// This is done in a separate method and type definition in order
@@ -887,3 +1851,32 @@ fn extract_query_depth(env: &[u8]) -> Result<u32, EnclaveError> {
             env.query_depth
         })
 }
+
+/// Whether the untrusted side marked the query as coming from outside the chain, see
+/// `query_cache` for why only those are cached
+fn extract_query_cacheable(env: &[u8]) -> Result<bool, EnclaveError> {
+    serde_json::from_slice::<EnvWithQueryCacheable>(env)
+        .map_err(|err| {
+            warn!(
+                "error while deserializing env into json {:?}: {}",
+                String::from_utf8_lossy(env),
+                err
+            );
+            EnclaveError::FailedToDeserialize
+        })
+        .map(|env| env.query_cacheable)
+}
+
+/// The id the untrusted side can cancel the query with, see `query_cancellation`
+fn extract_query_cancellation_id(env: &[u8]) -> Result<Option<u64>, EnclaveError> {
+    serde_json::from_slice::<EnvWithQueryCancellationId>(env)
+        .map_err(|err| {
+            warn!(
+                "error while deserializing env into json {:?}: {}",
+                String::from_utf8_lossy(env),
+                err
+            );
+            EnclaveError::FailedToDeserialize
+        })
+        .map(|env| env.query_cancellation_id.map(|id| id.u64()))
+}
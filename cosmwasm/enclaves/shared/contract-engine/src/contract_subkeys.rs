@@ -0,0 +1,214 @@
+//! x25519 keys that belong to a contract, so dApp backends can encrypt data that only the
+//! contract can decrypt, inside the enclave.
+//!
+//! A contract derives a key pair for any path it likes, e.g. one per user or per purpose. The
+//! secret key is derived from the genesis state key, the og contract key and the path:
+//!
+//! ```text
+//! hkdf_sha256(genesis state key, "contract_subkey" || og contract key || path)
+//! ```
+//!
+//! so it survives seed rotations and migrations, and no two contracts can derive the same key.
+//! The contract only ever sees the public key. Ciphertexts use the format of encrypted contract
+//! msgs, with the contract's public key instead of the consensus IO key:
+//!
+//! ```text
+//! nonce (32) || sender public key (32) || AES-SIV ciphertext
+//! ```
+//!
+//! with the key derived from the x25519 key exchange and the nonce, and no associated data.
+
+use log::*;
+
+use enclave_crypto::{
+    hkdf_sha_256, AESKey, Ed25519PublicKey, Kdf, KeyPair, SIVEncryptable, PUBLIC_KEY_SIZE,
+};
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::KEY_MANAGER;
+
+use crate::contract_validation::ContractKey;
+
+pub const MAX_SUBKEY_PATH_LENGTH: usize = 256;
+pub const MAX_SUBKEY_PLAINTEXT_LENGTH: usize = 64 * 1024;
+
+const SUBKEY_DOMAIN: &[u8] = b"contract_subkey";
+const NONCE_LENGTH: usize = 32;
+const CIPHERTEXT_HEADER_LENGTH: usize = NONCE_LENGTH + PUBLIC_KEY_SIZE;
+const SIV_TAG_LENGTH: usize = 16;
+const MAX_CIPHERTEXT_LENGTH: usize =
+    CIPHERTEXT_HEADER_LENGTH + MAX_SUBKEY_PLAINTEXT_LENGTH + SIV_TAG_LENGTH;
+
+/// Error codes returned to contracts by `derive_contract_keypair` and `contract_decrypt`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ContractSubkeyError {
+    /// The path is longer than `MAX_SUBKEY_PATH_LENGTH`, or the ciphertext is shorter than its
+    /// header or longer than a `MAX_SUBKEY_PLAINTEXT_LENGTH` plaintext
+    InvalidInput = 1,
+    /// The ciphertext wasn't encrypted to the key of the path, or was tampered with
+    DecryptionFailed = 2,
+}
+
+/// The key every contract sub-key, token metadata key and public state tag is derived from
+pub fn genesis_state_key() -> Result<AESKey, EnclaveError> {
+    let state_ikm = KEY_MANAGER.get_consensus_state_ikm().map_err(|err| {
+        error!("failed to get the genesis state key: {:?}", err);
+        EnclaveError::InternalError
+    })?;
+
+    Ok(state_ikm.genesis)
+}
+
+fn subkey(
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    path: &[u8],
+) -> Result<KeyPair, ContractSubkeyError> {
+    if path.len() > MAX_SUBKEY_PATH_LENGTH {
+        return Err(ContractSubkeyError::InvalidInput);
+    }
+
+    let info: Vec<&[u8]> = vec![SUBKEY_DOMAIN, og_contract_key.as_slice(), path];
+
+    Ok(KeyPair::from(hkdf_sha_256(
+        genesis_state_key.get(),
+        info.as_slice(),
+    )))
+}
+
+/// The public key of the contract's sub-key at `path`
+pub fn subkey_public_key(
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    path: &[u8],
+) -> Result<Ed25519PublicKey, ContractSubkeyError> {
+    Ok(subkey(genesis_state_key, og_contract_key, path)?.get_pubkey())
+}
+
+/// Decrypt a ciphertext that was encrypted to the contract's sub-key at `path`
+pub fn subkey_decrypt(
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    path: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, ContractSubkeyError> {
+    let keypair = subkey(genesis_state_key, og_contract_key, path)?;
+
+    if ciphertext.len() < CIPHERTEXT_HEADER_LENGTH || ciphertext.len() > MAX_CIPHERTEXT_LENGTH {
+        return Err(ContractSubkeyError::InvalidInput);
+    }
+    let (nonce, rest) = ciphertext.split_at(NONCE_LENGTH);
+    let (sender_public_key, ciphertext) = rest.split_at(PUBLIC_KEY_SIZE);
+
+    let mut public_key = [0u8; PUBLIC_KEY_SIZE];
+    public_key.copy_from_slice(sender_public_key);
+    let shared_secret = keypair.diffie_hellman(&public_key);
+
+    AESKey::new_from_slice(&shared_secret)
+        .derive_key_from_this(nonce)
+        .decrypt_siv(ciphertext, None)
+        .map_err(|err| {
+            debug!("failed to decrypt with a contract sub-key: {:?}", err);
+            ContractSubkeyError::DecryptionFailed
+        })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use enclave_crypto::sha_256;
+
+    fn encrypt(public_key: &Ed25519PublicKey, plaintext: &[u8]) -> Vec<u8> {
+        let sender = KeyPair::new().unwrap();
+        let nonce = [7u8; NONCE_LENGTH];
+        let ciphertext = AESKey::new_from_slice(&sender.diffie_hellman(public_key))
+            .derive_key_from_this(&nonce)
+            .encrypt_siv(plaintext, None)
+            .unwrap();
+
+        let mut encrypted = nonce.to_vec();
+        encrypted.extend_from_slice(&sender.get_pubkey());
+        encrypted.extend_from_slice(&ciphertext);
+        encrypted
+    }
+
+    pub fn test_contract_subkey_derivation() {
+        let state_key = AESKey::new_from_slice(&sha_256(b"state key"));
+        let contract = [1u8; 64];
+        let other_contract = [2u8; 64];
+
+        let public_key = subkey_public_key(&state_key, &contract, b"users/alice").unwrap();
+        assert_eq!(
+            subkey_public_key(&state_key, &contract, b"users/alice").unwrap(),
+            public_key
+        );
+
+        // separated by path and by contract
+        assert_ne!(
+            subkey_public_key(&state_key, &contract, b"users/bob").unwrap(),
+            public_key
+        );
+        assert_ne!(
+            subkey_public_key(&state_key, &other_contract, b"users/alice").unwrap(),
+            public_key
+        );
+
+        assert!(subkey_public_key(&state_key, &contract, b"").is_ok());
+        assert!(subkey_public_key(&state_key, &contract, &[0u8; MAX_SUBKEY_PATH_LENGTH]).is_ok());
+        assert_eq!(
+            subkey_public_key(&state_key, &contract, &[0u8; MAX_SUBKEY_PATH_LENGTH + 1]),
+            Err(ContractSubkeyError::InvalidInput)
+        );
+    }
+
+    pub fn test_contract_subkey_decrypt() {
+        let state_key = AESKey::new_from_slice(&sha_256(b"state key"));
+        let contract = [1u8; 64];
+        let path: &[u8] = b"users/alice";
+
+        let public_key = subkey_public_key(&state_key, &contract, path).unwrap();
+        let encrypted = encrypt(&public_key, b"secret data");
+
+        assert_eq!(
+            subkey_decrypt(&state_key, &contract, path, &encrypted).unwrap(),
+            b"secret data"
+        );
+
+        // only the contract's key of the same path can decrypt it
+        assert_eq!(
+            subkey_decrypt(&state_key, &contract, b"users/bob", &encrypted),
+            Err(ContractSubkeyError::DecryptionFailed)
+        );
+        assert_eq!(
+            subkey_decrypt(&state_key, &[2u8; 64], path, &encrypted),
+            Err(ContractSubkeyError::DecryptionFailed)
+        );
+
+        let mut tampered = encrypted.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            subkey_decrypt(&state_key, &contract, path, &tampered),
+            Err(ContractSubkeyError::DecryptionFailed)
+        );
+
+        assert_eq!(
+            subkey_decrypt(
+                &state_key,
+                &contract,
+                path,
+                &encrypted[..CIPHERTEXT_HEADER_LENGTH - 1]
+            ),
+            Err(ContractSubkeyError::InvalidInput)
+        );
+
+        let largest = encrypt(&public_key, &[1u8; MAX_SUBKEY_PLAINTEXT_LENGTH]);
+        assert!(subkey_decrypt(&state_key, &contract, path, &largest).is_ok());
+        let mut too_long = largest;
+        too_long.push(0);
+        assert_eq!(
+            subkey_decrypt(&state_key, &contract, path, &too_long),
+            Err(ContractSubkeyError::InvalidInput)
+        );
+    }
+}
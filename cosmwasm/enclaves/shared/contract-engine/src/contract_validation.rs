@@ -7,8 +7,7 @@ use cw_types_generic::BaseEnv;
 use cw_types_v010::types::{CanonicalAddr, Coin, HumanAddr};
 use enclave_cosmos_types::traits::CosmosAminoPubkey;
 use enclave_cosmos_types::types::{
-    ContractCode, CosmosPubKey, DirectSdkMsg, HandleType, SigInfo, SignDoc, StdSignDoc, TxBody,
-    VerifyParamsType,
+    CosmosPubKey, DirectSdkMsg, HandleType, SigInfo, SignDoc, StdSignDoc, TxBody, VerifyParamsType,
 };
 use enclave_crypto::traits::VerifyingKey;
 use enclave_crypto::{sha_256, AESKey, Hmac, Kdf, HASH_SIZE};
@@ -16,17 +15,26 @@ use enclave_ffi_types::EnclaveError;
 use enclave_utils::KEY_MANAGER;
 use protobuf::Message;
 
+use crate::call_stack::{call_stack_key, split_callback_sig};
+use crate::cbor_envelope::decode_envelope;
+use crate::code_hash_registry::check_code_hash;
 use crate::hardcoded_admins::is_code_hash_allowed;
 use crate::input_validation::contract_address_validation::verify_contract_address;
 use crate::input_validation::msg_validation::verify_and_get_sdk_msg;
 use crate::input_validation::send_funds_validations::verify_sent_funds;
 use crate::input_validation::sender_validation::verify_sender;
+use crate::interchain_accounts::{is_interchain_account_tx, verify_interchain_account_input};
 use crate::io::create_callback_signature;
+use crate::job_message::JobResultMsg;
 use crate::message::is_ibc_msg;
 use crate::types::SecretMessage;
 
+#[cfg(feature = "light-client-validation")]
+use block_verifier::state_proof::verify_current_state;
 #[cfg(feature = "light-client-validation")]
 use block_verifier::VERIFIED_BLOCK_MESSAGES;
+#[cfg(feature = "light-client-validation")]
+use enclave_utils::compute_params::COMPUTE_STORE_KEY;
 
 extern crate hex;
 
@@ -84,6 +92,55 @@ pub fn verify_block_info(base_env: &BaseEnv) -> Result<(), EnclaveError> {
     Ok(())
 }
 
+/// Replace the block in `base_env` with the last block the enclave verified, for code that runs
+/// outside of a block, like job handlers
+#[cfg(feature = "light-client-validation")]
+pub fn set_verified_block_info(base_env: &mut BaseEnv) {
+    #[cfg(feature = "go-tests")]
+    {
+        // allow skipping light client validation in go-tests
+        // if the env variable SKIP_LIGHT_CLIENT_VALIDATION is set to TRUE
+        let is_skip_light_client_validation = std::env::var("SKIP_LIGHT_CLIENT_VALIDATION");
+
+        if is_skip_light_client_validation
+            .unwrap_or_default()
+            .to_uppercase()
+            == "TRUE"
+        {
+            return;
+        }
+    }
+
+    let verified_msgs = VERIFIED_BLOCK_MESSAGES.lock().unwrap();
+    base_env.0.block.height = verified_msgs.height();
+    base_env.0.block.time = verified_msgs.time() as u64;
+}
+
+/// Checks that x/compute's store has `value` at `key`, in the state the verified block is
+/// executed on
+#[cfg(feature = "light-client-validation")]
+pub fn verify_compute_state(key: &[u8], value: &[u8], proof: &[u8]) -> Result<(), EnclaveError> {
+    #[cfg(feature = "go-tests")]
+    {
+        // allow skipping light client validation in go-tests
+        // if the env variable SKIP_LIGHT_CLIENT_VALIDATION is set to TRUE
+        let is_skip_light_client_validation = std::env::var("SKIP_LIGHT_CLIENT_VALIDATION");
+
+        if is_skip_light_client_validation
+            .unwrap_or_default()
+            .to_uppercase()
+            == "TRUE"
+        {
+            return Ok(());
+        }
+    }
+
+    verify_current_state(COMPUTE_STORE_KEY, key, value, proof).map_err(|err| {
+        warn!("failed to verify a value of the compute store: {:?}", err);
+        EnclaveError::ValidationFailure
+    })
+}
+
 #[cfg(feature = "light-client-validation")]
 /// WARNING: this function must be called at most once per message!
 /// Checks if there's a msg in the light client that's contained in tx_sign_bytes
@@ -247,7 +304,7 @@ pub fn generate_contract_id(
 pub fn validate_current_contract_key(
     contract_key: &[u8; CONTRACT_KEY_LENGTH],
     contract_address: &CanonicalAddr,
-    contract_code: &ContractCode,
+    code_hash: &[u8; HASH_SIZE],
     og_contract_key: Option<&[u8; CONTRACT_KEY_LENGTH]>,
 ) -> Result<(), EnclaveError> {
     // parse contract key -> < signer_id || authentication_code >
@@ -271,7 +328,7 @@ pub fn validate_current_contract_key(
     let calculated_authentication_id = generate_contract_id(
         &enclave_key,
         &signer_id,
-        &contract_code.hash(),
+        code_hash,
         contract_address.as_slice(),
         og_contract_key,
     );
@@ -289,7 +346,7 @@ pub fn validate_current_contract_key(
 pub fn validate_contract_key(
     base_env: &BaseEnv,
     canonical_contract_address: &CanonicalAddr,
-    contract_code: &ContractCode,
+    code_hash: &[u8; HASH_SIZE],
 ) -> Result<(), EnclaveError> {
     let og_contract_key: [u8; CONTRACT_KEY_LENGTH] = base_env.get_og_contract_key()?;
 
@@ -302,7 +359,7 @@ pub fn validate_contract_key(
         validate_current_contract_key(
             &current_contract_key,
             canonical_contract_address,
-            contract_code,
+            code_hash,
             Some(&og_contract_key),
         )?;
 
@@ -310,7 +367,7 @@ pub fn validate_contract_key(
 
         let contract_key_proof = generate_contract_key_proof(
             &canonical_contract_address.0 .0,
-            &contract_code.hash(),
+            code_hash,
             &og_contract_key,
             &current_contract_key, // this is already validated
         );
@@ -319,20 +376,21 @@ pub fn validate_contract_key(
             error!("Failed to validate contract key proof for a migrated contract");
             return Err(EnclaveError::ValidationFailure);
         }
-
-        Ok(())
     } else {
         trace!("Contract still has original code, validating contract_key");
 
         validate_current_contract_key(
             &og_contract_key,
             canonical_contract_address,
-            contract_code,
+            code_hash,
             None,
         )?;
-
-        Ok(())
     }
+
+    // The keys of the code a contract ran before it was migrated are still valid
+    check_code_hash(canonical_contract_address.as_slice(), code_hash);
+
+    Ok(())
 }
 
 pub fn generate_admin_proof(admin: &[u8], contract_key: &[u8]) -> [u8; enclave_crypto::HASH_SIZE] {
@@ -383,6 +441,9 @@ pub fn validate_msg(
 ) -> Result<ValidatedMessage, EnclaveError> {
     match handle_type {
         None => validate_basic_msg(contract_address, msg, contract_hash, data_for_validation),
+        Some(HandleType::HANDLE_TYPE_JOB_RESULT) => {
+            validate_job_result_msg(contract_address, msg, contract_hash)
+        }
         Some(h) => match is_ibc_msg(h) {
             false => validate_basic_msg(contract_address, msg, contract_hash, data_for_validation),
             true => validate_ibc_msg(contract_address, msg, contract_hash, data_for_validation, h),
@@ -435,6 +496,42 @@ pub fn validate_ibc_msg(
     }
 }
 
+/// An encrypted job result carries the contract hash inside the encrypted `result`, same as an
+/// encrypted execute message.
+pub fn validate_job_result_msg(
+    contract_address: &CanonicalAddr,
+    msg: &[u8],
+    contract_hash: &[u8; HASH_SIZE],
+) -> Result<ValidatedMessage, EnclaveError> {
+    let mut job_result: JobResultMsg = serde_json::from_slice(msg).map_err(|err| {
+        warn!(
+            "JobResult msg got an error while trying to deserialize msg input bytes into json {:?}: {}",
+            String::from_utf8_lossy(msg),
+            err
+        );
+        EnclaveError::FailedToDeserialize
+    })?;
+
+    let validated_msg = validate_basic_msg(
+        contract_address,
+        job_result.result.as_slice(),
+        contract_hash,
+        None,
+    )?;
+    job_result.result = validated_msg.validated_msg.as_slice().into();
+
+    Ok(ValidatedMessage {
+        validated_msg: serde_json::to_vec(&job_result).map_err(|err| {
+            warn!(
+                "got an error while trying to serialize job result msg into bytes {:?}: {}",
+                job_result, err
+            );
+            EnclaveError::FailedToSerialize
+        })?,
+        reply_params: validated_msg.reply_params,
+    })
+}
+
 pub fn validate_basic_msg(
     contract_address: &CanonicalAddr,
     msg: &[u8],
@@ -534,7 +631,7 @@ pub fn validate_basic_msg(
     }
 
     Ok(ValidatedMessage {
-        validated_msg,
+        validated_msg: decode_envelope(validated_msg)?,
         reply_params,
     })
 }
@@ -561,7 +658,22 @@ pub fn verify_params(
             return verify_callback_sig(callback_sig.as_slice(), sender, secret_msg, sent_funds);
         }
 
-        verify_signature(sig_info, sender)?;
+        if let Err(err) = verify_signature(sig_info, sender) {
+            // Interchain accounts don't sign, their controller chain sends their txs in IBC packets
+            if verify_params_type == VerifyParamsType::HandleType(HandleType::HANDLE_TYPE_EXECUTE)
+                && is_interchain_account_tx(sig_info)
+            {
+                return verify_interchain_account_input(
+                    sig_info,
+                    sender,
+                    contract_address,
+                    secret_msg,
+                    sent_funds,
+                );
+            }
+
+            return Err(err);
+        }
     }
 
     if should_verify_input {
@@ -582,6 +694,39 @@ pub fn verify_params(
     Ok(())
 }
 
+/// The plaintext of a simulated encrypted msg starts with this, so only whoever encrypted it can
+/// simulate it, and it can't be executed in a real tx.
+pub const SIMULATION_MSG_PREFIX: &[u8] = b"simulate:";
+
+/// Like `verify_params` for an execute, but for a simulated tx, which isn't part of any block and
+/// isn't signed. Plaintext msgs can be simulated by anyone. An encrypted msg must have been
+/// encrypted for the simulation, with `SIMULATION_MSG_PREFIX`, which proves the simulator holds
+/// its key, so a simulation can't reveal the gas or the branches of the msg of someone else's tx.
+///
+/// Returns the decrypted msg without the prefix.
+pub fn verify_simulation_params(
+    sig_info: &SigInfo,
+    was_msg_encrypted: bool,
+    decrypted_msg: &[u8],
+) -> Result<Vec<u8>, EnclaveError> {
+    if sig_info.callback_sig.is_some() {
+        warn!("a simulation can't be a callback of another contract");
+        return Err(EnclaveError::FailedTxVerification);
+    }
+
+    if !was_msg_encrypted {
+        return Ok(decrypted_msg.to_vec());
+    }
+
+    match decrypted_msg.strip_prefix(SIMULATION_MSG_PREFIX) {
+        Some(msg) => Ok(msg.to_vec()),
+        None => {
+            warn!("simulated msg wasn't encrypted for a simulation");
+            Err(EnclaveError::FailedTxVerification)
+        }
+    }
+}
+
 fn verify_signature(sig_info: &SigInfo, sender: &CanonicalAddr) -> Result<(), EnclaveError> {
     let sender_public_key = get_signer(sig_info, sender)?;
 
@@ -621,9 +766,7 @@ fn verify_input(
     current_admin: Option<&CanonicalAddr>,
     new_admin: Option<&CanonicalAddr>,
 ) -> Result<(), EnclaveError> {
-    let sdk_messages = get_sdk_messages_from_sign_bytes(sig_info)?;
-
-    verify_tx_bytes(sig_info, &sdk_messages)?;
+    let sdk_messages = get_verified_sdk_messages(sig_info)?;
 
     let is_verified = verify_input_params(
         #[cfg(feature = "light-client-validation")]
@@ -646,6 +789,15 @@ fn verify_input(
     Ok(())
 }
 
+/// The messages of the tx in `sig_info`, checked against its tx_bytes
+pub fn get_verified_sdk_messages(sig_info: &SigInfo) -> Result<Vec<DirectSdkMsg>, EnclaveError> {
+    let sdk_messages = get_sdk_messages_from_sign_bytes(sig_info)?;
+
+    verify_tx_bytes(sig_info, &sdk_messages)?;
+
+    Ok(sdk_messages)
+}
+
 fn get_signer(sign_info: &SigInfo, sender: &CanonicalAddr) -> Result<CosmosPubKey, EnclaveError> {
     use cosmos_proto::tx::signing::SignMode::*;
     use protobuf::well_known_types::Any as AnyProto;
@@ -842,6 +994,12 @@ fn verify_callback_sig_impl(
         return false;
     }
 
+    // The call stack of the execution is checked for reentrancy separately
+    let callback_signature = match split_callback_sig(callback_signature, &call_stack_key()) {
+        Ok((callback_signature, _)) => callback_signature,
+        Err(_) => return false,
+    };
+
     let callback_sig = create_callback_signature(sender, &secret_msg.msg, sent_funds);
 
     if callback_signature != callback_sig {
@@ -6,7 +6,10 @@ pub mod api_marker {
 }
 
 pub mod features {
+    pub const QUERY_REPLAY_PROTECTION: &str = "requires_query_replay_protection";
     pub const RANDOM: &str = "requires_random";
+    pub const REENTRANCY_GUARD: &str = "requires_reentrancy_guard";
+    pub const STATE_COMMITMENT: &str = "requires_state_commitment";
 }
 
 /// Right now ContractOperation is used to detect queris and prevent state changes
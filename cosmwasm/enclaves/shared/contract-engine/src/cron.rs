@@ -0,0 +1,239 @@
+//! Block callbacks of contracts, so contracts can run logic every few blocks without a keeper.
+//!
+//! A contract's admin schedules its callbacks with a `MsgSetContractCron`, and x/compute stores
+//! the schedule at `cron_key` of the contract, as the big endian interval, gas budget and height
+//! of the msg. A callback carries the schedule with a proof of it against the app hash of the
+//! block, and the enclave only runs the contract's `cron` entry point if the proven schedule is
+//! due at the verified height, with no more gas than its budget. The enclave doesn't keep the
+//! schedules itself, so a node can't make it run a schedule that was removed or replaced.
+
+use std::convert::TryInto;
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use cw_types_v010::encoding::Binary;
+use cw_types_v010::types::{CanonicalAddr, HumanAddr};
+use cw_types_v1::math::Uint64;
+use enclave_ffi_types::EnclaveError;
+
+#[cfg(feature = "light-client-validation")]
+use crate::contract_validation::verify_compute_state;
+use crate::types::{ParsedMessage, SecretMessage};
+
+const CONTRACT_CRON_PREFIX: u8 = 0x0C;
+
+/// About a month of 6 second blocks
+const MAX_CRON_INTERVAL: u64 = 500_000;
+pub const MAX_CRON_GAS_BUDGET: u64 = 10_000_000;
+const GAS_MULTIPLIER: u64 = 1000; // (cosmwasm gas : sdk gas)
+
+/// What the contract receives in its `cron` entry point
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CronMsg {
+    pub height: Uint64,
+}
+
+/// What the node sends for a callback, the contract's `CronMsg` and its proven schedule
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CronCallbackMsg {
+    pub height: Uint64,
+    pub schedule: Binary,
+    #[serde(default)]
+    pub proof: Binary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    pub interval: u64,
+    pub gas_budget: u64,
+    /// The height of the `MsgSetContractCron`, the callbacks are every `interval` blocks after it
+    pub start_height: u64,
+}
+
+impl CronSchedule {
+    /// Parses a schedule as x/compute stores it
+    pub fn parse(bytes: &[u8]) -> Result<Self, EnclaveError> {
+        if bytes.len() != 24 {
+            warn!("stored contract cron is {} bytes", bytes.len());
+            return Err(EnclaveError::FailedToDeserialize);
+        }
+
+        let read_u64 =
+            |offset: usize| u64::from_be_bytes(bytes[offset..offset + 8].try_into().unwrap());
+        let schedule = Self {
+            interval: read_u64(0),
+            gas_budget: read_u64(8),
+            start_height: read_u64(16),
+        };
+
+        if schedule.interval == 0
+            || schedule.interval > MAX_CRON_INTERVAL
+            || schedule.gas_budget == 0
+            || schedule.gas_budget > MAX_CRON_GAS_BUDGET
+        {
+            warn!(
+                "invalid contract cron with an interval of {} and a gas budget of {}",
+                schedule.interval, schedule.gas_budget
+            );
+            return Err(EnclaveError::ValidationFailure);
+        }
+
+        Ok(schedule)
+    }
+
+    pub fn is_due(&self, height: u64) -> bool {
+        height > self.start_height && (height - self.start_height) % self.interval == 0
+    }
+}
+
+/// The key of the schedule of `contract` in x/compute's store
+pub fn cron_key(contract: &CanonicalAddr) -> Vec<u8> {
+    [&[CONTRACT_CRON_PREFIX][..], contract.as_slice()].concat()
+}
+
+pub fn parse_cron_message(message: &[u8]) -> Result<ParsedMessage, EnclaveError> {
+    Ok(ParsedMessage {
+        should_verify_sig_info: false,
+        should_verify_input: false,
+        was_msg_encrypted: false,
+        should_encrypt_output: false,
+        secret_msg: SecretMessage {
+            nonce: [0; 32],
+            user_public_key: [0; 32],
+            msg: message.into(),
+        },
+        decrypted_msg: message.into(),
+        data_for_validation: None,
+    })
+}
+
+/// Checks that the schedule in the callback of `contract` is its schedule in the chain state, that
+/// it's due at the verified height, and that the node didn't give it more gas than its budget.
+/// Returns the `CronMsg` the contract receives, without the schedule.
+#[cfg_attr(not(feature = "light-client-validation"), allow(unused_variables))]
+pub fn verify_cron_callback(
+    contract: &CanonicalAddr,
+    contract_address: &HumanAddr,
+    msg: &[u8],
+    block_height: u64,
+    gas_limit: u64,
+) -> Result<Vec<u8>, EnclaveError> {
+    let callback: CronCallbackMsg = serde_json::from_slice(msg).map_err(|err| {
+        warn!(
+            "got an error while trying to deserialize input bytes into CronCallbackMsg {:?}: {}",
+            String::from_utf8_lossy(msg),
+            err
+        );
+        EnclaveError::FailedToDeserialize
+    })?;
+    if callback.height.u64() != block_height {
+        warn!(
+            "cron callback of {} for height {} was run at height {}",
+            contract_address, callback.height, block_height
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    // x/compute only stores the schedules contract admins set
+    #[cfg(feature = "light-client-validation")]
+    verify_compute_state(
+        &cron_key(contract),
+        callback.schedule.as_slice(),
+        callback.proof.as_slice(),
+    )?;
+
+    let schedule = CronSchedule::parse(callback.schedule.as_slice())?;
+    if !schedule.is_due(block_height) {
+        warn!(
+            "no cron callback of {} is due at {}",
+            contract_address, block_height
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+    if gas_limit > schedule.gas_budget.saturating_mul(GAS_MULTIPLIER) {
+        warn!(
+            "cron callback of {} got {} gas, more than its budget of {}",
+            contract_address, gas_limit, schedule.gas_budget
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    serde_json::to_vec(&CronMsg {
+        height: callback.height,
+    })
+    .map_err(|err| {
+        error!("failed to serialize a CronMsg: {}", err);
+        EnclaveError::FailedToSerialize
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn schedule_bytes(interval: u64, gas_budget: u64, start_height: u64) -> Vec<u8> {
+        let mut bytes = interval.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&gas_budget.to_be_bytes());
+        bytes.extend_from_slice(&start_height.to_be_bytes());
+        bytes
+    }
+
+    pub fn test_contract_cron_schedule() {
+        let schedule = CronSchedule::parse(&schedule_bytes(10, 1000, 100)).unwrap();
+        assert_eq!(
+            schedule,
+            CronSchedule {
+                interval: 10,
+                gas_budget: 1000,
+                start_height: 100,
+            }
+        );
+        assert!(!schedule.is_due(100));
+        assert!(!schedule.is_due(105));
+        assert!(schedule.is_due(110));
+        assert!(!schedule.is_due(111));
+        assert!(schedule.is_due(120));
+
+        assert!(CronSchedule::parse(&schedule_bytes(10, 1000, 100)[..23]).is_err());
+        assert!(CronSchedule::parse(&schedule_bytes(0, 1000, 100)).is_err());
+        assert!(CronSchedule::parse(&schedule_bytes(10, 0, 100)).is_err());
+        assert!(CronSchedule::parse(&schedule_bytes(10, MAX_CRON_GAS_BUDGET + 1, 100)).is_err());
+        assert!(CronSchedule::parse(&schedule_bytes(MAX_CRON_INTERVAL + 1, 1000, 100)).is_err());
+
+        assert_eq!(
+            cron_key(&CanonicalAddr(Binary(vec![4u8; 20]))),
+            [&[0x0Cu8][..], &[4u8; 20]].concat()
+        );
+    }
+
+    pub fn test_contract_cron_callbacks() {
+        let contract = CanonicalAddr(Binary(vec![1u8; 20]));
+        let contract_address = HumanAddr("contract".to_string());
+        let callback = |height: u64, schedule: Vec<u8>| {
+            serde_json::to_vec(&CronCallbackMsg {
+                height: Uint64::new(height),
+                schedule: Binary(schedule),
+                proof: Binary(vec![]),
+            })
+            .unwrap()
+        };
+
+        let msg = callback(105, schedule_bytes(5, 2000, 100));
+        // the schedule has no proof
+        #[cfg(not(feature = "light-client-validation"))]
+        assert_eq!(
+            verify_cron_callback(&contract, &contract_address, &msg, 105, 2_000_000).unwrap(),
+            br#"{"height":"105"}"#.to_vec()
+        );
+
+        // not the verified height
+        assert!(verify_cron_callback(&contract, &contract_address, &msg, 110, 2_000_000).is_err());
+        // not due
+        let msg = callback(106, schedule_bytes(5, 2000, 100));
+        assert!(verify_cron_callback(&contract, &contract_address, &msg, 106, 2_000_000).is_err());
+        // more gas than the budget
+        let msg = callback(110, schedule_bytes(5, 2000, 100));
+        assert!(verify_cron_callback(&contract, &contract_address, &msg, 110, 2_000_001).is_err());
+    }
+}
@@ -5,17 +5,20 @@ use log::*;
 
 use sgx_types::sgx_status_t;
 
-use enclave_crypto::{sha_256, AESKey, Kdf, SIVEncryptable};
+use enclave_crypto::{ocalls, sha_256, AESKey, Kdf, SIVEncryptable};
 use enclave_ffi_types::{Ctx, EnclaveBuffer, OcallReturn, UntrustedVmError};
 use enclave_utils::key_manager::SeedsHolder;
 use enclave_utils::KEY_MANAGER;
 
-use crate::external::{ecalls, ocalls};
+#[cfg(feature = "differential-execution")]
+use crate::differential::{self, HostRequest};
+use crate::external::ecalls;
 
 use enclave_utils::kv_cache::KvCache;
 
 use super::contract_validation::ContractKey;
 use super::errors::WasmEngineError;
+use super::padding::{pad, state_value_buckets, unpad, PADDED_VALUE_AD};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -29,6 +32,25 @@ struct EncryptedKey {
     pub data: Vec<u8>,
 }
 
+/// Starts the values stored under the digest of their field name that were re-encrypted under the
+/// current seed, see `state_reencryption`. It's followed by the version of the seed.
+const REKEYED_VALUE_MAGIC_BYTES: &[u8] = b"secret-rekeyed";
+const REKEYED_VALUE_HEADER_LENGTH: usize = REKEYED_VALUE_MAGIC_BYTES.len() + 2;
+
+/// The length of the legacy keys, which are digests of the field name
+const FIELD_NAME_DIGEST_LENGTH: usize = 32;
+
+/// The seed a stored pair of a contract is encrypted with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoredPairEpoch {
+    /// The current seed
+    Current,
+    /// The genesis seed, in the legacy per-field format
+    Genesis,
+    /// A seed older than the current one, which the enclave doesn't have
+    Retired,
+}
+
 #[derive(Serialize, Deserialize)]
 struct EncryptedValue {
     // header
@@ -49,6 +71,14 @@ pub fn write_multiple_keys(
     }
 
     let x = serde_json::to_vec(&keys).unwrap();
+
+    #[cfg(feature = "differential-execution")]
+    let request = HostRequest::WriteMultiple { pairs: x.clone() };
+    #[cfg(feature = "differential-execution")]
+    if let Some(response) = differential::replay(&request) {
+        return response.map(|response| response.gas_used);
+    }
+
     let len = x.len();
     let ptr = x.as_ptr();
 
@@ -69,7 +99,11 @@ pub fn write_multiple_keys(
     }
 
     match ocall_return {
-        OcallReturn::Success => Ok(gas_used),
+        OcallReturn::Success => {
+            #[cfg(feature = "differential-execution")]
+            differential::record(request, None, gas_used);
+            Ok(gas_used)
+        }
         OcallReturn::Failure => Err(WasmEngineError::FailedOcall(vm_err)),
         OcallReturn::Panic => Err(WasmEngineError::Panic),
     }
@@ -122,6 +156,28 @@ pub fn create_encrypted_key_value(
         err
     })?;
 
+    let (encrypted_key_bytes, encrypted_value_bytes) = encrypt_stored_pair(
+        plaintext_key,
+        plaintext_value,
+        contract_key,
+        encryption_salt,
+    )?;
+
+    debug!(
+        "Removed old field name: {:?} and created new field name: {:?}",
+        scrambled_field_name, encrypted_key_bytes
+    );
+
+    Ok((encrypted_key_bytes, gas_used_remove, encrypted_value_bytes))
+}
+
+/// Encrypt a key-value pair the way it's stored, without touching the storage
+pub fn encrypt_stored_pair(
+    plaintext_key: &[u8],
+    plaintext_value: &[u8],
+    contract_key: &ContractKey,
+    encryption_salt: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>), WasmEngineError> {
     let encrypted_key = EncryptedKey {
         magic_bytes: ENCRYPTED_KEY_MAGIC_BYTES.to_vec(),
         consensus_seed_version: CONSENSUS_SEED_VERSION,
@@ -141,12 +197,173 @@ pub fn create_encrypted_key_value(
     };
     let encrypted_value_bytes = bincode2::serialize(&encrypted_value).unwrap();
 
-    debug!(
-        "Removed old field name: {:?} and created new field name: {:?}",
-        scrambled_field_name, encrypted_key_bytes
-    );
+    Ok((encrypted_key_bytes, encrypted_value_bytes))
+}
 
-    Ok((encrypted_key_bytes, gas_used_remove, encrypted_value_bytes))
+/// Decrypt a key-value pair as it's stored. Returns `None` for pairs stored in the legacy per-field
+/// format, whose keys are digests that the plaintext key can't be recovered from.
+pub fn decrypt_stored_pair(
+    encrypted_key_bytes: &[u8],
+    encrypted_value_bytes: &[u8],
+    contract_key: &ContractKey,
+) -> Result<Option<(Vec<u8>, Vec<u8>)>, WasmEngineError> {
+    let encrypted_key: EncryptedKey = match bincode2::deserialize(encrypted_key_bytes) {
+        Ok(encrypted_key) => encrypted_key,
+        Err(_) => return Ok(None),
+    };
+    if encrypted_key.magic_bytes != ENCRYPTED_KEY_MAGIC_BYTES {
+        return Ok(None);
+    }
+
+    let plaintext_key = get_symmetrical_key_new(contract_key)
+        .decrypt_siv(&encrypted_key.data, Some(&[]))
+        .map_err(|err| {
+            warn!(
+                "got an error while trying to decrypt the stored key {:?}: {:?}",
+                encrypted_key_bytes, err
+            );
+            WasmEngineError::DecryptionError
+        })?;
+
+    let encrypted_value: EncryptedValue =
+        bincode2::deserialize(encrypted_value_bytes).map_err(|err| {
+            warn!(
+                "got an error while trying to deserialize the stored value for key {:?}: {}",
+                encrypted_key_bytes, err
+            );
+            WasmEngineError::DecryptionError
+        })?;
+
+    let plaintext_value = decrypt_value_new(
+        &encrypted_key.data,
+        &encrypted_value.data,
+        contract_key,
+        &encrypted_value.salt,
+    )?;
+
+    Ok(Some((plaintext_key, plaintext_value)))
+}
+
+/// The seed a pair is encrypted with, from its header. Doesn't decrypt the pair.
+pub fn stored_pair_epoch(
+    encrypted_key_bytes: &[u8],
+    encrypted_value_bytes: &[u8],
+) -> StoredPairEpoch {
+    if encrypted_key_bytes.len() == FIELD_NAME_DIGEST_LENGTH {
+        return match rekeyed_value_version(encrypted_value_bytes) {
+            Some(CONSENSUS_SEED_VERSION) => StoredPairEpoch::Current,
+            Some(_) => StoredPairEpoch::Retired,
+            None => StoredPairEpoch::Genesis,
+        };
+    }
+
+    match bincode2::deserialize::<EncryptedKey>(encrypted_key_bytes) {
+        Ok(encrypted_key)
+            if encrypted_key.magic_bytes == ENCRYPTED_KEY_MAGIC_BYTES
+                && encrypted_key.consensus_seed_version == CONSENSUS_SEED_VERSION =>
+        {
+            StoredPairEpoch::Current
+        }
+        _ => StoredPairEpoch::Retired,
+    }
+}
+
+/// Re-encrypt a value stored with the genesis seed under the digest of its field name, with the
+/// current seed. It stays under the same digest, since its field name can't be recovered from it.
+pub fn rekey_legacy_value(
+    scrambled_field_name: &[u8],
+    encrypted_value: &[u8],
+    contract_key: &ContractKey,
+) -> Result<Vec<u8>, WasmEngineError> {
+    if encrypted_value.len() < 32 {
+        warn!(
+            "the legacy value of {:?} is too short to be re-encrypted",
+            scrambled_field_name
+        );
+        return Err(WasmEngineError::DecryptionError);
+    }
+    let plaintext_value = decrypt_value_old(scrambled_field_name, encrypted_value, contract_key)?;
+    let padded_value = pad(&plaintext_value, &STATE_VALUE_BUCKETS);
+
+    let ciphertext = get_symmetrical_key_new(contract_key)
+        .encrypt_siv(
+            &padded_value,
+            Some(&[
+                scrambled_field_name,
+                REKEYED_VALUE_MAGIC_BYTES,
+                PADDED_VALUE_AD,
+            ]),
+        )
+        .map_err(|err| {
+            warn!(
+                "got an error while trying to re-encrypt the legacy value of {:?}: {:?}",
+                scrambled_field_name, err
+            );
+            WasmEngineError::EncryptionError
+        })?;
+
+    let mut rekeyed_value = REKEYED_VALUE_MAGIC_BYTES.to_vec();
+    rekeyed_value.extend_from_slice(&CONSENSUS_SEED_VERSION.to_be_bytes());
+    rekeyed_value.extend_from_slice(&ciphertext);
+    Ok(rekeyed_value)
+}
+
+fn rekeyed_value_version(encrypted_value: &[u8]) -> Option<u16> {
+    if encrypted_value.len() < REKEYED_VALUE_HEADER_LENGTH
+        || !encrypted_value.starts_with(REKEYED_VALUE_MAGIC_BYTES)
+    {
+        return None;
+    }
+
+    let version = &encrypted_value[REKEYED_VALUE_MAGIC_BYTES.len()..REKEYED_VALUE_HEADER_LENGTH];
+    Some(u16::from_be_bytes([version[0], version[1]]))
+}
+
+/// Decrypt a value stored under the digest of its field name, whether it's still encrypted with
+/// the genesis seed or was re-encrypted with the current one
+fn decrypt_legacy_value(
+    scrambled_field_name: &[u8],
+    encrypted_value: &[u8],
+    contract_key: &ContractKey,
+) -> Result<Vec<u8>, WasmEngineError> {
+    match rekeyed_value_version(encrypted_value) {
+        None => decrypt_value_old(scrambled_field_name, encrypted_value, contract_key),
+        Some(CONSENSUS_SEED_VERSION) => {
+            let padded_value = get_symmetrical_key_new(contract_key)
+                .decrypt_siv(
+                    &encrypted_value[REKEYED_VALUE_HEADER_LENGTH..],
+                    Some(&[
+                        scrambled_field_name,
+                        REKEYED_VALUE_MAGIC_BYTES,
+                        PADDED_VALUE_AD,
+                    ]),
+                )
+                .map_err(|err| {
+                    warn!(
+                        "got an error while trying to decrypt the re-encrypted value of {:?}: {:?}",
+                        scrambled_field_name, err
+                    );
+                    WasmEngineError::DecryptionError
+                })?;
+
+            unpad(&padded_value)
+                .map(|value| value.to_vec())
+                .ok_or_else(|| {
+                    warn!(
+                        "the re-encrypted value of {:?} has invalid padding",
+                        scrambled_field_name
+                    );
+                    WasmEngineError::DecryptionError
+                })
+        }
+        Some(version) => {
+            warn!(
+                "the value of {:?} was re-encrypted with seed version {}, which isn't current",
+                scrambled_field_name, version
+            );
+            Err(WasmEngineError::DecryptionError)
+        }
+    }
 }
 
 pub fn read_from_encrypted_state(
@@ -166,43 +383,7 @@ pub fn read_from_encrypted_state(
     };
     let encrypted_key_bytes = bincode2::serialize(&encrypted_key).unwrap();
 
-    let mut maybe_plaintext_value: Option<Vec<u8>>;
-    let gas_used_first_read: u64;
-    (maybe_plaintext_value, gas_used_first_read) = match read_db(context, &encrypted_key_bytes) {
-        Ok((maybe_encrypted_value_bytes, gas_used)) => match maybe_encrypted_value_bytes {
-            Some(encrypted_value_bytes) => {
-                let encrypted_value: EncryptedValue = bincode2::deserialize(&encrypted_value_bytes).map_err(|err| {
-                    warn!(
-                        "read_db() got an error while trying to read_from_encrypted_state the value {:?} for key {:?}, stopping wasm: {:?}",
-                        encrypted_value_bytes,
-                        encrypted_key_bytes,
-                        err.to_string()
-                    );
-                    WasmEngineError::DecryptionError
-                })?;
-
-                match decrypt_value_new(
-                    &encrypted_key.data,
-                    &encrypted_value.data,
-                    contract_key,
-                    &encrypted_value.salt,
-                ) {
-                    Ok(plaintext_value) => Ok((Some(plaintext_value), gas_used)),
-                    // This error case is why we have all the matches here.
-                    // If we successfully collected a value, but failed to decrypt it, then we propagate that error.
-                    Err(err) => Err(err),
-                }
-            }
-            None => Ok((None, gas_used)),
-        },
-        Err(err) => Err(err),
-    }?;
-
-    if let Some(plaintext_value) = maybe_plaintext_value {
-        return Ok((Some(plaintext_value), gas_used_first_read));
-    }
-
-    // Key doesn't exist, try reading with the old encryption format
+    // Key may not exist in the new format yet, so both formats are read in one ocall
     let scrambled_field_name = field_name_digest(plaintext_key, contract_key);
 
     trace!(
@@ -210,24 +391,39 @@ pub fn read_from_encrypted_state(
         scrambled_field_name
     );
 
-    let gas_used_second_read: u64;
-    (maybe_plaintext_value, gas_used_second_read) = match read_db(context, &scrambled_field_name) {
-        Ok((encrypted_value, gas_used)) => match encrypted_value {
-            Some(plaintext_value) => {
-                match decrypt_value_old(&scrambled_field_name, &plaintext_value, contract_key) {
-                    Ok(plaintext_value) => {
-                        let _ = kv_cache.store_in_ro_cache(plaintext_key, &plaintext_value);
-                        Ok((Some(plaintext_value), gas_used))
-                    }
-                    // This error case is why we have all the matches here.
-                    // If we successfully collected a value, but failed to decrypt it, then we propagate that error.
-                    Err(err) => Err(err),
-                }
-            }
-            None => Ok((None, gas_used)),
-        },
-        Err(err) => Err(err),
-    }?;
+    let (found, gas_used_read) =
+        read_db_first(context, &[&encrypted_key_bytes, &scrambled_field_name])?;
+
+    let maybe_plaintext_value = match found {
+        Some((0, encrypted_value_bytes)) => {
+            let encrypted_value: EncryptedValue = bincode2::deserialize(&encrypted_value_bytes).map_err(|err| {
+                warn!(
+                    "read_db() got an error while trying to read_from_encrypted_state the value {:?} for key {:?}, stopping wasm: {:?}",
+                    encrypted_value_bytes,
+                    encrypted_key_bytes,
+                    err.to_string()
+                );
+                WasmEngineError::DecryptionError
+            })?;
+
+            // If we successfully collected a value, but failed to decrypt it, then we propagate that error.
+            let plaintext_value = decrypt_value_new(
+                &encrypted_key.data,
+                &encrypted_value.data,
+                contract_key,
+                &encrypted_value.salt,
+            )?;
+            return Ok((Some(plaintext_value), gas_used_read));
+        }
+        Some((_, encrypted_value)) => {
+            // Key exists with the old format
+            let plaintext_value =
+                decrypt_legacy_value(&scrambled_field_name, &encrypted_value, contract_key)?;
+            let _ = kv_cache.store_in_ro_cache(plaintext_key, &plaintext_value);
+            Some(plaintext_value)
+        }
+        None => None,
+    };
 
     let mut gas_used_write: u64 = 0;
     if has_write_permissions {
@@ -243,10 +439,7 @@ pub fn read_from_encrypted_state(
         }
     }
 
-    Ok((
-        maybe_plaintext_value,
-        gas_used_first_read + gas_used_second_read + gas_used_write,
-    ))
+    Ok((maybe_plaintext_value, gas_used_read + gas_used_write))
 }
 
 pub fn remove_from_encrypted_state(
@@ -298,7 +491,17 @@ fn field_name_digest(field_name: &[u8], contract_key: &ContractKey) -> [u8; 32]
 }
 
 /// Safe wrapper around reads from the contract storage
-fn read_db(context: &Ctx, key: &[u8]) -> Result<(Option<Vec<u8>>, u64), WasmEngineError> {
+pub(crate) fn read_db(
+    context: &Ctx,
+    key: &[u8],
+) -> Result<(Option<Vec<u8>>, u64), WasmEngineError> {
+    #[cfg(feature = "differential-execution")]
+    let request = HostRequest::Read { key: key.to_vec() };
+    #[cfg(feature = "differential-execution")]
+    if let Some(response) = differential::replay(&request) {
+        return response.map(|response| (response.value, response.gas_used));
+    }
+
     let mut ocall_return = OcallReturn::Success;
     let mut enclave_buffer = std::mem::MaybeUninit::<EnclaveBuffer>::uninit();
     let mut vm_err = UntrustedVmError::default();
@@ -337,11 +540,82 @@ fn read_db(context: &Ctx, key: &[u8]) -> Result<(Option<Vec<u8>>, u64), WasmEngi
         }
     };
 
+    #[cfg(feature = "differential-execution")]
+    differential::record(request, value.as_deref(), gas_used);
+
     Ok((value, gas_used))
 }
 
+/// Reads `keys` in order until one exists, in a single ocall. Returns the index of the key that
+/// exists with its value, and the gas of all the reads, which is what reading them one by one
+/// would cost.
+fn read_db_first(
+    context: &Ctx,
+    keys: &[&[u8]],
+) -> Result<(Option<(usize, Vec<u8>)>, u64), WasmEngineError> {
+    let mut ocall_return = OcallReturn::Success;
+    let mut enclave_buffer = std::mem::MaybeUninit::<EnclaveBuffer>::uninit();
+    let mut vm_err = UntrustedVmError::default();
+    let mut gas_used = 0_u64;
+    let mut key_index = 0_u32;
+
+    let serialized_keys = serde_json::to_vec(keys).unwrap();
+
+    let value = unsafe {
+        let status = ocalls::ocall_read_db_first(
+            (&mut ocall_return) as *mut _,
+            context.unsafe_clone(),
+            (&mut vm_err) as *mut _,
+            (&mut gas_used) as *mut _,
+            enclave_buffer.as_mut_ptr(),
+            (&mut key_index) as *mut _,
+            serialized_keys.as_ptr(),
+            serialized_keys.len(),
+        );
+        match status {
+            sgx_status_t::SGX_SUCCESS => { /* continue */ }
+            error_status => {
+                warn!(
+                    "read_db() got an error from ocall_read_db_first, stopping wasm: {:?}",
+                    error_status
+                );
+                return Err(WasmEngineError::FailedOcall(vm_err));
+            }
+        }
+
+        match ocall_return {
+            OcallReturn::Success => {
+                let enclave_buffer = enclave_buffer.assume_init();
+                ecalls::recover_buffer(enclave_buffer)?
+            }
+            OcallReturn::Failure => {
+                return Err(WasmEngineError::FailedOcall(vm_err));
+            }
+            OcallReturn::Panic => return Err(WasmEngineError::Panic),
+        }
+    };
+
+    match value {
+        Some(value) if (key_index as usize) < keys.len() => {
+            Ok((Some((key_index as usize, value)), gas_used))
+        }
+        Some(_) => {
+            warn!("ocall_read_db_first returned a value of key {}", key_index);
+            Err(WasmEngineError::HostMisbehavior)
+        }
+        None => Ok((None, gas_used)),
+    }
+}
+
 /// Safe wrapper around reads from the contract storage
-fn remove_db(context: &Ctx, key: &[u8]) -> Result<u64, WasmEngineError> {
+pub(crate) fn remove_db(context: &Ctx, key: &[u8]) -> Result<u64, WasmEngineError> {
+    #[cfg(feature = "differential-execution")]
+    let request = HostRequest::Remove { key: key.to_vec() };
+    #[cfg(feature = "differential-execution")]
+    if let Some(response) = differential::replay(&request) {
+        return response.map(|response| response.gas_used);
+    }
+
     let mut ocall_return = OcallReturn::Success;
     let mut vm_err = UntrustedVmError::default();
     let mut gas_used = 0_u64;
@@ -360,15 +634,28 @@ fn remove_db(context: &Ctx, key: &[u8]) -> Result<u64, WasmEngineError> {
     }
 
     match ocall_return {
-        OcallReturn::Success => Ok(gas_used),
+        OcallReturn::Success => {
+            #[cfg(feature = "differential-execution")]
+            differential::record(request, None, gas_used);
+            Ok(gas_used)
+        }
         OcallReturn::Failure => Err(WasmEngineError::FailedOcall(vm_err)),
         OcallReturn::Panic => Err(WasmEngineError::Panic),
     }
 }
 
 /// Safe wrapper around writes to the contract storage
-#[allow(dead_code)]
-fn write_db(context: &Ctx, key: &[u8], value: &[u8]) -> Result<u64, WasmEngineError> {
+pub(crate) fn write_db(context: &Ctx, key: &[u8], value: &[u8]) -> Result<u64, WasmEngineError> {
+    #[cfg(feature = "differential-execution")]
+    let request = HostRequest::Write {
+        key: key.to_vec(),
+        value: value.to_vec(),
+    };
+    #[cfg(feature = "differential-execution")]
+    if let Some(response) = differential::replay(&request) {
+        return response.map(|response| response.gas_used);
+    }
+
     let mut ocall_return = OcallReturn::Success;
     let mut vm_err = UntrustedVmError::default();
     let mut gas_used = 0_u64;
@@ -389,7 +676,11 @@ fn write_db(context: &Ctx, key: &[u8], value: &[u8]) -> Result<u64, WasmEngineEr
     }
 
     match ocall_return {
-        OcallReturn::Success => Ok(gas_used),
+        OcallReturn::Success => {
+            #[cfg(feature = "differential-execution")]
+            differential::record(request, None, gas_used);
+            Ok(gas_used)
+        }
         OcallReturn::Failure => Err(WasmEngineError::FailedOcall(vm_err)),
         OcallReturn::Panic => Err(WasmEngineError::Panic),
     }
@@ -426,7 +717,7 @@ fn get_symmetrical_key_old(field_name: &[u8], contract_key: &ContractKey) -> AES
         .derive_key_from_this(&derivation_data)
 }
 
-fn get_symmetrical_key_new(contract_key: &ContractKey) -> AESKey {
+pub fn get_symmetrical_key_new(contract_key: &ContractKey) -> AESKey {
     let consensus_state_ikm: SeedsHolder<AESKey> = KEY_MANAGER.get_consensus_state_ikm().unwrap();
     consensus_state_ikm
         .current
@@ -440,9 +731,13 @@ fn encrypt_value_new(
     encryption_salt: &[u8],
 ) -> Result<Vec<u8>, WasmEngineError> {
     let encryption_key = get_symmetrical_key_new(contract_key);
+    let padded_value = pad(plaintext_state_value, &state_value_buckets());
 
     encryption_key
-        .encrypt_siv(plaintext_state_value, Some(&[encrypted_state_key, encryption_salt]))
+        .encrypt_siv(
+            &padded_value,
+            Some(&[encrypted_state_key, encryption_salt, PADDED_VALUE_AD]),
+        )
         .map_err(|err| {
             warn!(
                 "write_db() got an error while trying to encrypt_value_new the value '{:?}', stopping wasm: {:?}",
@@ -462,6 +757,23 @@ fn decrypt_value_new(
 ) -> Result<Vec<u8>, WasmEngineError> {
     let decryption_key = get_symmetrical_key_new(contract_key);
 
+    if let Ok(padded_value) = decryption_key.decrypt_siv(
+        encrypted_value,
+        Some(&[encrypted_key, encryption_salt, PADDED_VALUE_AD]),
+    ) {
+        return match unpad(&padded_value) {
+            Some(value) => Ok(value.to_vec()),
+            None => {
+                warn!(
+                    "read_db() got a padded value with invalid padding for key {:?}",
+                    encrypted_key
+                );
+                Err(WasmEngineError::DecryptionError)
+            }
+        };
+    }
+
+    // Values written before padding was added
     decryption_key.decrypt_siv(encrypted_value, Some(&[encrypted_key, encryption_salt])).map_err(|err| {
         warn!(
             "read_db() got an error while trying to decrypt_value_new the value {:?} for key {:?}, stopping wasm: {:?}",
@@ -0,0 +1,457 @@
+//! Differential execution of two engine backends, for validating engine upgrades on testnets.
+//!
+//! In enclaves built with the `differential-execution` feature, every init, execute and migrate
+//! runs twice: through the current backend, whose result the chain uses, and through the
+//! candidate backend, where an upgrade of the engine stages its changes (see
+//! `wasm3::EngineBackend`). The two runs are compared on their output or error, the gas they
+//! used, the writes they left in the cache, and the calls they made to the host. Every mismatch
+//! is reported to the node with `ocall_report_engine_divergence`.
+//!
+//! The candidate never reaches the host. Each storage access and query it makes is answered from
+//! a recording of the current run, in order, so it doesn't charge the node's gas meter or change
+//! the state. A call that differs from the recorded one fails the candidate run with
+//! `HostMisbehavior`, and is reported as the first diverging host call.
+//!
+//! The reports hold hashes of plaintext outputs and storage, so the feature can't be combined
+//! with `production`.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use log::*;
+use serde::Serialize;
+
+use enclave_crypto::sha_256;
+use enclave_ffi_types::EnclaveError;
+
+use crate::errors::WasmEngineError;
+
+/// Runs that make more host calls than this aren't compared
+const MAX_RECORDING_LEN: usize = 100_000;
+
+/// A call the engine made to the host
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HostRequest {
+    Read {
+        key: Vec<u8>,
+    },
+    Write {
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    Remove {
+        key: Vec<u8>,
+    },
+    WriteMultiple {
+        pairs: Vec<u8>,
+    },
+    Query {
+        query: Vec<u8>,
+        query_depth: u32,
+        gas_limit: u64,
+    },
+}
+
+/// What the host answered a call with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostResponse {
+    pub value: Option<Vec<u8>>,
+    pub gas_used: u64,
+}
+
+enum Mode {
+    Off,
+    Recording {
+        calls: Vec<(HostRequest, HostResponse)>,
+        truncated: bool,
+        /// Set while a query runs, so the storage accesses of the queried contract, which run
+        /// on this thread as well, aren't recorded as the calls of this one
+        suspended: u32,
+    },
+    Replaying {
+        calls: Vec<(HostRequest, HostResponse)>,
+        next: usize,
+        diverged_at: Option<usize>,
+    },
+}
+
+thread_local! {
+    static MODE: RefCell<Mode> = RefCell::new(Mode::Off);
+}
+
+/// Records the host calls of the current run until it's finished or dropped
+pub struct Recording {
+    _private: (),
+}
+
+impl Recording {
+    pub fn start() -> Self {
+        MODE.with(|mode| {
+            *mode.borrow_mut() = Mode::Recording {
+                calls: vec![],
+                truncated: false,
+                suspended: 0,
+            }
+        });
+        Self { _private: () }
+    }
+
+    /// The calls recorded, or None if there were too many of them
+    fn finish(self) -> Option<Vec<(HostRequest, HostResponse)>> {
+        MODE.with(|mode| match mode.replace(Mode::Off) {
+            Mode::Recording {
+                calls,
+                truncated: false,
+                ..
+            } => Some(calls),
+            _ => None,
+        })
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        MODE.with(|mode| {
+            let recording = matches!(*mode.borrow(), Mode::Recording { .. });
+            if recording {
+                *mode.borrow_mut() = Mode::Off;
+            }
+        })
+    }
+}
+
+/// Stops recording until dropped
+pub struct Suspended {
+    _private: (),
+}
+
+pub fn suspend() -> Suspended {
+    MODE.with(|mode| {
+        if let Mode::Recording { suspended, .. } = &mut *mode.borrow_mut() {
+            *suspended += 1;
+        }
+    });
+    Suspended { _private: () }
+}
+
+impl Drop for Suspended {
+    fn drop(&mut self) {
+        MODE.with(|mode| {
+            if let Mode::Recording { suspended, .. } = &mut *mode.borrow_mut() {
+                *suspended = suspended.saturating_sub(1);
+            }
+        })
+    }
+}
+
+/// Record a host call of the current run, if one is being recorded
+pub fn record(request: HostRequest, value: Option<&[u8]>, gas_used: u64) {
+    MODE.with(|mode| {
+        if let Mode::Recording {
+            calls,
+            truncated,
+            suspended: 0,
+        } = &mut *mode.borrow_mut()
+        {
+            if calls.len() < MAX_RECORDING_LEN {
+                let response = HostResponse {
+                    value: value.map(|value| value.to_vec()),
+                    gas_used,
+                };
+                calls.push((request, response));
+            } else {
+                *truncated = true;
+            }
+        }
+    })
+}
+
+/// While the candidate runs, answer its host call from the recording instead of the host. Returns
+/// None when the calls should go to the host.
+pub fn replay(request: &HostRequest) -> Option<Result<HostResponse, WasmEngineError>> {
+    MODE.with(|mode| match &mut *mode.borrow_mut() {
+        Mode::Replaying {
+            calls,
+            next,
+            diverged_at,
+        } => {
+            if diverged_at.is_some() {
+                return Some(Err(WasmEngineError::HostMisbehavior));
+            }
+
+            match calls.get(*next) {
+                Some((recorded, response)) if recorded == request => {
+                    *next += 1;
+                    Some(Ok(response.clone()))
+                }
+                _ => {
+                    *diverged_at = Some(*next);
+                    Some(Err(WasmEngineError::HostMisbehavior))
+                }
+            }
+        }
+        _ => None,
+    })
+}
+
+/// Run `f` while replaying `calls`, and return the index of the first call that diverged, if any
+fn replaying<T>(
+    calls: Vec<(HostRequest, HostResponse)>,
+    f: impl FnOnce() -> T,
+) -> (T, Option<usize>) {
+    let recorded = calls.len();
+    MODE.with(|mode| {
+        *mode.borrow_mut() = Mode::Replaying {
+            calls,
+            next: 0,
+            diverged_at: None,
+        }
+    });
+
+    let result = f();
+
+    let diverged_at = MODE.with(|mode| match mode.replace(Mode::Off) {
+        // A run that stopped short of the recorded calls diverged where it stopped
+        Mode::Replaying {
+            next, diverged_at, ..
+        } => diverged_at.or(if next < recorded { Some(next) } else { None }),
+        _ => None,
+    });
+
+    (result, diverged_at)
+}
+
+/// A run of one backend, as far as the comparison goes
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RunSummary {
+    gas_used: u64,
+    /// SHA-256 of the output, hex encoded
+    output: Option<String>,
+    error: Option<String>,
+    /// SHA-256 of the writes left in the cache, in key order, hex encoded
+    write_set: String,
+    writes: usize,
+}
+
+impl RunSummary {
+    pub fn new(
+        result: &Result<Vec<u8>, EnclaveError>,
+        gas_used: u64,
+        write_set: &BTreeMap<Vec<u8>, Vec<u8>>,
+    ) -> Self {
+        let mut encoded = vec![];
+        for (key, value) in write_set {
+            encoded.extend_from_slice(&(key.len() as u32).to_be_bytes());
+            encoded.extend_from_slice(key);
+            encoded.extend_from_slice(&(value.len() as u32).to_be_bytes());
+            encoded.extend_from_slice(value);
+        }
+
+        Self {
+            gas_used,
+            output: result
+                .as_ref()
+                .ok()
+                .map(|output| hex::encode(sha_256(output))),
+            error: result.as_ref().err().map(|err| err.to_string()),
+            write_set: hex::encode(sha_256(&encoded)),
+            writes: write_set.len(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct EngineDivergence<'a> {
+    operation: &'a str,
+    code_hash: String,
+    mismatches: Vec<&'static str>,
+    /// The index of the first host call the candidate made differently
+    first_diverging_host_call: Option<usize>,
+    current: RunSummary,
+    /// None if the candidate engine couldn't be started
+    candidate: Option<RunSummary>,
+}
+
+fn mismatches(
+    current: &RunSummary,
+    candidate: Option<&RunSummary>,
+    host_call_diverged: bool,
+) -> Vec<&'static str> {
+    let candidate = match candidate {
+        Some(candidate) => candidate,
+        None => return vec!["start"],
+    };
+
+    let mut mismatches = vec![];
+    if current.output != candidate.output {
+        mismatches.push("output");
+    }
+    if current.error != candidate.error {
+        mismatches.push("error");
+    }
+    if current.gas_used != candidate.gas_used {
+        mismatches.push("gas_used");
+    }
+    if current.write_set != candidate.write_set {
+        mismatches.push("write_set");
+    }
+    if host_call_diverged {
+        mismatches.push("host_calls");
+    }
+    mismatches
+}
+
+/// Run the candidate backend over the host calls recorded for the current one, and report how
+/// they differ. `run_candidate` returns the summary of the candidate run, or an error if the
+/// candidate engine couldn't be started.
+pub fn compare_candidate<F>(
+    recording: Recording,
+    operation: &str,
+    code_hash: &[u8],
+    result: &Result<Vec<u8>, EnclaveError>,
+    gas_used: u64,
+    write_set: &BTreeMap<Vec<u8>, Vec<u8>>,
+    run_candidate: F,
+) where
+    F: FnOnce() -> Result<RunSummary, EnclaveError>,
+{
+    let calls = match recording.finish() {
+        Some(calls) => calls,
+        None => {
+            warn!(
+                "Not comparing the engines on {}, too many host calls",
+                operation
+            );
+            return;
+        }
+    };
+
+    // The host failed the current run, which says nothing about the engine
+    if let Err(EnclaveError::FailedOcall { .. }) = result {
+        trace!(
+            "Not comparing the engines on {}, the host failed",
+            operation
+        );
+        return;
+    }
+
+    let current = RunSummary::new(result, gas_used, write_set);
+    let (candidate, diverged_at) = replaying(calls, run_candidate);
+    let candidate = candidate
+        .map_err(|err| warn!("Failed to start the candidate engine: {}", err))
+        .ok();
+
+    let mismatches = mismatches(&current, candidate.as_ref(), diverged_at.is_some());
+    if mismatches.is_empty() {
+        trace!("The engines agree on {}", operation);
+        return;
+    }
+
+    let divergence = EngineDivergence {
+        operation,
+        code_hash: hex::encode(code_hash),
+        mismatches,
+        first_diverging_host_call: diverged_at,
+        current,
+        candidate,
+    };
+    warn!("The engines diverged: {:?}", divergence);
+    report(&divergence);
+}
+
+fn report(divergence: &EngineDivergence) {
+    let encoded = match serde_json::to_vec(divergence) {
+        Ok(encoded) => encoded,
+        Err(err) => {
+            error!("Failed to encode an engine divergence: {:?}", err);
+            return;
+        }
+    };
+
+    let status = unsafe {
+        enclave_crypto::ocalls::ocall_report_engine_divergence(encoded.as_ptr(), encoded.len())
+    };
+    if status != sgx_types::sgx_status_t::SGX_SUCCESS {
+        warn!("Failed to report an engine divergence: {}", status);
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn read(key: &[u8]) -> HostRequest {
+        HostRequest::Read { key: key.to_vec() }
+    }
+
+    pub fn test_differential_replay() {
+        let recording = Recording::start();
+        record(read(b"a"), Some(b"1"), 10);
+        {
+            let _suspended = suspend();
+            // a queried contract's read
+            record(read(b"q"), None, 5);
+        }
+        record(
+            HostRequest::Write {
+                key: b"b".to_vec(),
+                value: b"2".to_vec(),
+            },
+            None,
+            20,
+        );
+        let calls = recording.finish().unwrap();
+        assert_eq!(calls.len(), 2);
+
+        // not replaying, so the call goes to the host
+        assert!(replay(&read(b"a")).is_none());
+
+        let (value, diverged_at) = replaying(calls.clone(), || replay(&read(b"a")));
+        assert_eq!(
+            value.unwrap().unwrap(),
+            HostResponse {
+                value: Some(b"1".to_vec()),
+                gas_used: 10
+            }
+        );
+        // stopped before the write
+        assert_eq!(diverged_at, Some(1));
+
+        let (value, diverged_at) = replaying(calls, || replay(&read(b"b")));
+        assert!(value.unwrap().is_err());
+        assert_eq!(diverged_at, Some(0));
+        assert!(replay(&read(b"a")).is_none());
+    }
+
+    pub fn test_differential_recording_dropped() {
+        {
+            let _recording = Recording::start();
+            record(read(b"a"), None, 0);
+        }
+        // an early return from the run doesn't leave the recording on
+        let recording = Recording::start();
+        assert_eq!(recording.finish().unwrap(), vec![]);
+    }
+
+    pub fn test_differential_mismatches() {
+        let mut writes = BTreeMap::new();
+        writes.insert(b"k".to_vec(), b"v".to_vec());
+        let current = RunSummary::new(&Ok(b"out".to_vec()), 100, &writes);
+
+        assert!(mismatches(&current, Some(&current.clone()), false).is_empty());
+        assert_eq!(mismatches(&current, None, false), vec!["start"]);
+
+        let candidate = RunSummary::new(&Err(EnclaveError::OutOfGas), 120, &BTreeMap::new());
+        assert_eq!(
+            mismatches(&current, Some(&candidate), true),
+            vec!["output", "error", "gas_used", "write_set", "host_calls"]
+        );
+
+        writes.insert(b"k".to_vec(), b"w".to_vec());
+        let candidate = RunSummary::new(&Ok(b"out".to_vec()), 100, &writes);
+        assert_eq!(
+            mismatches(&current, Some(&candidate), false),
+            vec!["write_set"]
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! The features this enclave offers contracts, so contracts and factories can check what's
+//! available at runtime and degrade gracefully, instead of failing on a missing host function
+//! after an upgrade, or on nodes built without an optional feature.
+//!
+//! `query_enclave_features` returns them as json:
+//!
+//! ```json
+//! {"version":1,"features":["aead","big_integers",...]}
+//! ```
+//!
+//! A feature's name never changes meaning. New features get new names, and `version` only
+//! changes if the format of the document does.
+
+use serde::Serialize;
+
+pub const ENCLAVE_FEATURES_VERSION: u32 = 1;
+
+/// Every feature a contract can ask about, and whether this build has it
+const FEATURES: &[(&str, bool)] = &[
+    ("aead", true),
+    ("big_integers", true),
+    ("calendar_math", true),
+    ("canonical_json", true),
+    ("cbor_messages", true),
+    ("compression", true),
+    ("contract_subkeys", true),
+    ("dcap_quotes", true),
+    ("drand_bls", true),
+    ("ed25519_batch_verify", true),
+    ("fixed_point_math", true),
+    (
+        "foreign_light_clients",
+        cfg!(feature = "light-client-validation"),
+    ),
+    ("hkdf_hmac", true),
+    (
+        "ibc_client_status",
+        cfg!(feature = "light-client-validation"),
+    ),
+    ("integrity_storage", true),
+    ("jwt_verification", true),
+    ("merkle_accumulators", true),
+    ("musig2", true),
+    ("oblivious_storage", true),
+    ("oracle_attestations", true),
+    (
+        "past_random",
+        cfg!(all(feature = "random", feature = "light-client-validation")),
+    ),
+    ("poseidon_hash", true),
+    ("private_jobs", true),
+    ("public_state", true),
+    ("random", cfg!(feature = "random")),
+    ("rsa_signatures", true),
+    ("signing", true),
+    ("state_commitment", true),
+    ("storage_iterators", true),
+    ("token_metadata_acl", true),
+    ("uniform_gas", true),
+    ("x509_certificates", true),
+];
+
+#[derive(Serialize, Debug)]
+struct EnclaveFeatures<'a> {
+    version: u32,
+    features: Vec<&'a str>,
+}
+
+/// The features of this build, sorted by name
+pub fn enabled_features() -> Vec<&'static str> {
+    let mut features: Vec<&str> = FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name)
+        .collect();
+    features.sort_unstable();
+    features
+}
+
+/// Every feature the enclave knows, whether this build has it or not
+pub fn known_features() -> Vec<&'static str> {
+    FEATURES.iter().map(|(name, _)| *name).collect()
+}
+
+/// Whether this build has `name`, or None if the enclave doesn't know the feature at all
+pub fn has_feature(name: &str) -> Option<bool> {
+    FEATURES
+        .iter()
+        .find(|(feature, _)| *feature == name)
+        .map(|(_, enabled)| *enabled)
+}
+
+pub fn encoded_enclave_features() -> Vec<u8> {
+    serde_json::to_vec(&EnclaveFeatures {
+        version: ENCLAVE_FEATURES_VERSION,
+        features: enabled_features(),
+    })
+    .unwrap_or_default()
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_enclave_features_encoding() {
+        let encoded: serde_json::Value =
+            serde_json::from_slice(&encoded_enclave_features()).unwrap();
+        assert_eq!(encoded["version"], ENCLAVE_FEATURES_VERSION);
+
+        let features: Vec<&str> = encoded["features"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|feature| feature.as_str().unwrap())
+            .collect();
+        assert_eq!(features, enabled_features());
+        assert!(features.contains(&"storage_iterators"));
+        assert_eq!(features.contains(&"random"), cfg!(feature = "random"));
+
+        assert_eq!(has_feature("storage_iterators"), Some(true));
+        assert_eq!(has_feature("random"), Some(cfg!(feature = "random")));
+        assert_eq!(has_feature("teleportation"), None);
+        assert!(known_features().contains(&"random"));
+        assert!(!known_features().contains(&"teleportation"));
+
+        // sorted, and without duplicates
+        assert!(features.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
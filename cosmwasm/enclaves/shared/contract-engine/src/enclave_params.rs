@@ -0,0 +1,98 @@
+//! The checks of the params in `enclave_utils::verified_params` that governance sets.
+//!
+//! x/compute passes the params of every `MsgUpdateParams` to `validate_enclave_params` before it
+//! stores them, so a proposal can't set a param the enclave doesn't know, or a value of the wrong
+//! type, which would leave the param at its strict value.
+
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::compute_params::ComputeParams;
+use enclave_utils::verified_params::{registered_params, validate_params};
+
+/// Check the enclave params of the encoded compute module `params`
+pub fn validate_enclave_params(params: &[u8]) -> Result<(), EnclaveError> {
+    let params = ComputeParams::parse(params)?;
+    validate_params(&params, &registered_params())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use enclave_utils::verified_params::VerifiedParam;
+
+    use super::*;
+
+    const FLAG: VerifiedParam<bool> = VerifiedParam::new("flag");
+    const LIMIT: VerifiedParam<u64> = VerifiedParam::new("limit");
+
+    fn encode_field(out: &mut Vec<u8>, field: u8, value: &[u8]) {
+        out.push(field << 3 | 2);
+        out.push(value.len() as u8);
+        out.extend_from_slice(value);
+    }
+
+    fn encode_params(enclave_params: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        // max_contract_size, then the enclave params
+        let mut params = vec![0x10, 0x80, 0x80, 0x80, 0x01];
+        for (name, value) in enclave_params {
+            let mut param = vec![];
+            encode_field(&mut param, 1, name.as_bytes());
+            encode_field(&mut param, 2, value);
+            encode_field(&mut params, 12, &param);
+        }
+        params
+    }
+
+    pub fn test_enclave_params_parse() {
+        let limit = 7u64.to_be_bytes().to_vec();
+        let params = encode_params(&[
+            ("flag", vec![1]),
+            ("limit", limit.clone()),
+            ("flag", vec![0]),
+        ]);
+        let params = ComputeParams::parse(&params).unwrap();
+
+        // a name that's set twice has its last value
+        assert_eq!(params.enclave_params.len(), 2);
+        assert_eq!(params.enclave_params["flag"], vec![0]);
+        assert_eq!(params.enclave_params["limit"], limit);
+
+        // names are UTF-8
+        let mut bad = vec![];
+        let mut param = vec![];
+        encode_field(&mut param, 1, &[0xff]);
+        encode_field(&mut bad, 12, &param);
+        assert!(ComputeParams::parse(&bad).is_err());
+    }
+
+    pub fn test_enclave_params_values() {
+        let params = encode_params(&[("flag", vec![1]), ("limit", vec![7])]);
+        let params = ComputeParams::parse(&params).unwrap();
+
+        assert!(FLAG.value_in(&params, false, false));
+        // a value that doesn't decode is strict
+        assert_eq!(LIMIT.value_in(&params, 10, 1), 1);
+        // a param that isn't set has its default
+        assert_eq!(LIMIT.value_in(&ComputeParams::default(), 10, 1), 10);
+    }
+
+    pub fn test_enclave_params_validate() {
+        let specs = [FLAG.spec(), LIMIT.spec()];
+        let valid = encode_params(&[("flag", vec![1]), ("limit", 7u64.to_be_bytes().to_vec())]);
+        let valid = ComputeParams::parse(&valid).unwrap();
+        assert!(validate_params(&valid, &specs).is_ok());
+        assert!(validate_params(&ComputeParams::default(), &specs).is_ok());
+
+        for invalid in [
+            encode_params(&[("other", vec![1])]),
+            encode_params(&[("flag", vec![2])]),
+            encode_params(&[("limit", vec![7])]),
+        ]
+        .iter()
+        {
+            let invalid = ComputeParams::parse(invalid).unwrap();
+            assert!(validate_params(&invalid, &specs).is_err());
+        }
+
+        assert!(validate_enclave_params(&encode_params(&[])).is_ok());
+        assert!(validate_enclave_params(&encode_params(&[("unknown", vec![1])])).is_err());
+    }
+}
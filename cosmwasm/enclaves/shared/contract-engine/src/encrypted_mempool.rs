@@ -0,0 +1,269 @@
+//! Txs that stay encrypted until the block that includes them is final, so nobody can front-run
+//! them from the mempool.
+//!
+//! Every block height has its own key, derived from the consensus seed. Users encrypt a tx to the
+//! public key of a height, and wrap it in a `MsgExecuteEncryptedTx`:
+//!
+//! ```text
+//! message MsgExecuteEncryptedTx {
+//!   string sender = 1;
+//!   bytes encrypted_tx = 2;  // height (8, big endian) | public key (32) | nonce (32) | ciphertext
+//!   uint64 gas_limit = 3;
+//! }
+//! ```
+//!
+//! The ciphertext is AES-SIV, with the key derived from the x25519 key exchange and the nonce,
+//! like the encryption of contract msgs, and the sender as associated data. x/compute stores the
+//! msg at `encrypted_tx_key` when it's executed, and has the enclave decrypt it in the next block,
+//! with a proof of it against the app hash of that block. The enclave only decrypts msgs that were
+//! included no more than `MAX_INCLUSION_DELAY` blocks after the height they were encrypted to. By
+//! then the block that included the msg is committed, along with the order of its txs, so the
+//! plaintext can't change what comes before it.
+
+use log::*;
+
+use enclave_crypto::{sha_256, AESKey, Ed25519PublicKey, Kdf, KeyPair, SIVEncryptable};
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::proto_fields::{fields, varint_value, WIRE_TYPE_LEN, WIRE_TYPE_VARINT};
+use enclave_utils::KEY_MANAGER;
+
+#[cfg(feature = "light-client-validation")]
+use block_verifier::VERIFIED_BLOCK_MESSAGES;
+
+#[cfg(feature = "light-client-validation")]
+use crate::contract_validation::verify_compute_state;
+use crate::gov_messages::utf8_string;
+use crate::types::IoNonce;
+
+/// The blocks after its height in which an encrypted tx can still be included
+pub const MAX_INCLUSION_DELAY: u64 = 10;
+
+const ENCRYPTED_TX_HEADER_LENGTH: usize = 8 + 32 + 32;
+
+const ENCRYPTED_TX_PREFIX: u8 = 0x0F;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ExecuteEncryptedTx {
+    pub sender: String,
+    pub encrypted_tx: Vec<u8>,
+    pub gas_limit: u64,
+}
+
+impl ExecuteEncryptedTx {
+    pub fn parse(msg: &[u8]) -> Result<Self, EnclaveError> {
+        let mut request = Self::default();
+
+        for (field, wire_type, value) in fields(msg)? {
+            match (field, wire_type) {
+                (1, WIRE_TYPE_LEN) => request.sender = utf8_string(value)?,
+                (2, WIRE_TYPE_LEN) => request.encrypted_tx = value.to_vec(),
+                (3, WIRE_TYPE_VARINT) => request.gas_limit = varint_value(value)?,
+                (field, wire_type) => {
+                    warn!(
+                        "unexpected field {} of wire type {} in encrypted tx msg",
+                        field, wire_type
+                    );
+                    return Err(EnclaveError::FailedToDeserialize);
+                }
+            }
+        }
+
+        Ok(request)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct EncryptedTx<'a> {
+    pub height: u64,
+    pub user_public_key: Ed25519PublicKey,
+    pub nonce: IoNonce,
+    pub ciphertext: &'a [u8],
+}
+
+impl<'a> EncryptedTx<'a> {
+    pub fn parse(encrypted_tx: &'a [u8]) -> Result<Self, EnclaveError> {
+        if encrypted_tx.len() <= ENCRYPTED_TX_HEADER_LENGTH {
+            warn!("encrypted tx of {} bytes is too short", encrypted_tx.len());
+            return Err(EnclaveError::FailedToDeserialize);
+        }
+
+        let mut height = [0u8; 8];
+        height.copy_from_slice(&encrypted_tx[..8]);
+        let mut user_public_key = [0u8; 32];
+        user_public_key.copy_from_slice(&encrypted_tx[8..40]);
+        let mut nonce = [0u8; 32];
+        nonce.copy_from_slice(&encrypted_tx[40..72]);
+
+        Ok(Self {
+            height: u64::from_be_bytes(height),
+            user_public_key,
+            nonce,
+            ciphertext: &encrypted_tx[ENCRYPTED_TX_HEADER_LENGTH..],
+        })
+    }
+
+    fn encryption_key(&self, block_key: &KeyPair) -> AESKey {
+        let ikm = block_key.diffie_hellman(&self.user_public_key);
+        AESKey::new_from_slice(&ikm).derive_key_from_this(&self.nonce)
+    }
+
+    /// Decrypt the tx with the key of its height, checking that it was encrypted for `sender`
+    pub fn decrypt(&self, block_key: &KeyPair, sender: &str) -> Result<Vec<u8>, EnclaveError> {
+        self.encryption_key(block_key)
+            .decrypt_siv(self.ciphertext, Some(&[sender.as_bytes()]))
+            .map_err(|err| {
+                warn!("failed to decrypt an encrypted tx: {:?}", err);
+                EnclaveError::DecryptionError
+            })
+    }
+}
+
+/// The key of the block at `height`
+fn block_key(height: u64) -> Result<KeyPair, EnclaveError> {
+    let secret = KEY_MANAGER.get_mempool_key_secret().map_err(|_| {
+        error!("no mempool key secret, is the node registered?");
+        EnclaveError::InternalError
+    })?;
+
+    Ok(KeyPair::from(
+        secret.derive_key_from_this(&height.to_be_bytes()),
+    ))
+}
+
+/// The public key users encrypt the txs they want included at `height` to
+pub fn mempool_public_key(height: u64) -> Result<Ed25519PublicKey, EnclaveError> {
+    Ok(block_key(height)?.get_pubkey())
+}
+
+/// Whether a tx encrypted to the key of `key_height` can be included in the block at
+/// `block_height`
+pub fn is_in_inclusion_window(key_height: u64, block_height: u64) -> bool {
+    block_height >= key_height && block_height - key_height < MAX_INCLUSION_DELAY
+}
+
+/// The key of an encrypted tx `msg` in x/compute's store, until it's decrypted in the next block
+pub fn encrypted_tx_key(msg: &[u8]) -> Vec<u8> {
+    [&[ENCRYPTED_TX_PREFIX][..], &sha_256(msg)[..]].concat()
+}
+
+/// The height of the block the proven state is of, which included the msgs being decrypted
+#[cfg(feature = "light-client-validation")]
+fn inclusion_height() -> Result<u64, EnclaveError> {
+    Ok(VERIFIED_BLOCK_MESSAGES
+        .lock()
+        .unwrap()
+        .height()
+        .saturating_sub(1))
+}
+
+// Without the light client, the enclave can't tell whether the block is final
+#[cfg(not(feature = "light-client-validation"))]
+fn inclusion_height() -> Result<u64, EnclaveError> {
+    warn!("can't decrypt encrypted txs without light client validation");
+    Err(EnclaveError::ValidationFailure)
+}
+
+/// Decrypt the tx of a `MsgExecuteEncryptedTx` that was included in the last block, which
+/// `msg_proof` proves x/compute stored
+#[cfg_attr(not(feature = "light-client-validation"), allow(unused_variables))]
+pub fn decrypt_encrypted_tx(msg: &[u8], msg_proof: &[u8]) -> Result<Vec<u8>, EnclaveError> {
+    let request = ExecuteEncryptedTx::parse(msg)?;
+    let encrypted_tx = EncryptedTx::parse(&request.encrypted_tx)?;
+
+    let height = inclusion_height()?;
+    if !is_in_inclusion_window(encrypted_tx.height, height) {
+        warn!(
+            "tx encrypted to height {} can't be included at height {}",
+            encrypted_tx.height, height
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    // x/compute only stores the msg once its block is executed
+    #[cfg(feature = "light-client-validation")]
+    verify_compute_state(&encrypted_tx_key(msg), msg, msg_proof)?;
+
+    encrypted_tx.decrypt(&block_key(encrypted_tx.height)?, &request.sender)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn encrypt(block_key: &KeyPair, height: u64, sender: &str, tx: &[u8]) -> Vec<u8> {
+        let user_key = KeyPair::new().unwrap();
+        let nonce = [7u8; 32];
+        let ikm = user_key.diffie_hellman(&block_key.get_pubkey());
+        let ciphertext = AESKey::new_from_slice(&ikm)
+            .derive_key_from_this(&nonce)
+            .encrypt_siv(tx, Some(&[sender.as_bytes()]))
+            .unwrap();
+
+        let mut encrypted_tx = height.to_be_bytes().to_vec();
+        encrypted_tx.extend_from_slice(&user_key.get_pubkey());
+        encrypted_tx.extend_from_slice(&nonce);
+        encrypted_tx.extend_from_slice(&ciphertext);
+        encrypted_tx
+    }
+
+    pub fn test_encrypted_tx_parse() {
+        let mut msg = vec![1 << 3 | 2, 6];
+        msg.extend_from_slice(b"sender");
+        msg.extend_from_slice(&[2 << 3 | 2, 3, 1, 2, 3]);
+        // gas limit 300
+        msg.extend_from_slice(&[3 << 3, 0xac, 0x02]);
+
+        assert_eq!(
+            ExecuteEncryptedTx::parse(&msg).unwrap(),
+            ExecuteEncryptedTx {
+                sender: "sender".to_string(),
+                encrypted_tx: vec![1, 2, 3],
+                gas_limit: 300,
+            }
+        );
+
+        msg.extend_from_slice(&[4 << 3, 1]);
+        assert!(ExecuteEncryptedTx::parse(&msg).is_err());
+
+        let mut encrypted_tx = 42u64.to_be_bytes().to_vec();
+        encrypted_tx.extend_from_slice(&[1; 32]);
+        encrypted_tx.extend_from_slice(&[2; 32]);
+        assert!(EncryptedTx::parse(&encrypted_tx).is_err());
+
+        encrypted_tx.push(3);
+        assert_eq!(
+            EncryptedTx::parse(&encrypted_tx).unwrap(),
+            EncryptedTx {
+                height: 42,
+                user_public_key: [1; 32],
+                nonce: [2; 32],
+                ciphertext: &[3],
+            }
+        );
+    }
+
+    pub fn test_encrypted_tx_decrypt() {
+        let block_key = KeyPair::new().unwrap();
+        let encrypted_tx = encrypt(&block_key, 100, "sender", b"tx");
+
+        let parsed = EncryptedTx::parse(&encrypted_tx).unwrap();
+        assert_eq!(parsed.decrypt(&block_key, "sender").unwrap(), b"tx");
+
+        // Copying the tx into another sender's msg doesn't decrypt it
+        assert!(parsed.decrypt(&block_key, "front-runner").is_err());
+        // Nor does the key of another block
+        assert!(parsed.decrypt(&KeyPair::new().unwrap(), "sender").is_err());
+    }
+
+    pub fn test_encrypted_tx_inclusion_window() {
+        assert!(!is_in_inclusion_window(100, 99));
+        assert!(is_in_inclusion_window(100, 100));
+        assert!(is_in_inclusion_window(100, 100 + MAX_INCLUSION_DELAY - 1));
+        assert!(!is_in_inclusion_window(100, 100 + MAX_INCLUSION_DELAY));
+
+        assert_eq!(
+            encrypted_tx_key(b"msg"),
+            [&[0x0Fu8][..], &sha_256(b"msg")[..]].concat()
+        );
+    }
+}
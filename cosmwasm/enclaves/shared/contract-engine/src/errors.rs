@@ -34,9 +34,17 @@ pub enum WasmEngineError {
     MemoryWriteError,
     /// The contract attempted to write to storage during a query
     UnauthorizedWrite,
+    /// The contract passed an invalid storage namespace
+    InvalidNamespace,
+    /// The contract wrote to a public namespace after it read secret data, see `public_state`
+    TaintedPublicWrite,
 
     /// The contract tried calling an unrecognized function
     NonExistentImportFunction,
+    /// A query from outside the chain ran longer than the node allows
+    QueryTimeLimitExceeded,
+    /// The node cancelled a query from outside the chain, see `query_cancellation`
+    QueryCancelled,
 }
 
 pub type WasmEngineResult<T> = Result<T, WasmEngineError>;
@@ -58,7 +66,10 @@ impl From<WasmEngineError> for EnclaveError {
             MemoryReadError => EnclaveError::MemoryReadError,
             MemoryWriteError => EnclaveError::MemoryWriteError,
             UnauthorizedWrite => EnclaveError::UnauthorizedWrite,
+            TaintedPublicWrite => EnclaveError::UnauthorizedWrite,
             HostMisbehavior => EnclaveError::HostMisbehavior,
+            QueryTimeLimitExceeded => EnclaveError::QueryTimeLimitExceeded,
+            QueryCancelled => EnclaveError::QueryCancelled,
             // Unexpected WasmEngineError variant
             _other => EnclaveError::Unknown,
         }
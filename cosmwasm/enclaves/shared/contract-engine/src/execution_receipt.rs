@@ -0,0 +1,282 @@
+//! Signed receipts of encrypted executions, e.g. for a rollup, an auditor or a bridge to check
+//! that a confidential execution happened without seeing its input.
+//!
+//! When governance enables `EXECUTION_RECEIPTS`, every successful encrypted init and execute
+//! gets a receipt, emitted as the plaintext `execution_receipt` attribute of its `wasm` event:
+//!
+//! ```json
+//! {"contract_address":"secret1...","code_hash":"<base64>","input_commitment":"<base64>",
+//!  "output_commitment":"<base64>","gas_used":"123","height":"456","public_key":"<base64>",
+//!  "signature":"<base64>"}
+//! ```
+//!
+//! The commitments are HMACs of the decrypted input and of the decrypted `data` of the output,
+//! under keys derived from the tx encryption key. Only the sender and the enclave can compute
+//! them, so they don't reveal inputs that could be guessed, and the sender can open either one
+//! to a verifier by disclosing its key, without disclosing the other one.
+//!
+//! Receipts are signed with a secp256k1 key derived from the consensus seed, so every node signs
+//! the same receipt for the same execution.
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use cw_types_v010::encoding::Binary;
+use cw_types_v010::types::{HumanAddr, LogAttribute};
+use cw_types_v1::math::Uint64;
+use enclave_crypto::{sha_256, AESKey, Hmac, Kdf, HASH_SIZE};
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::verified_params::EXECUTION_RECEIPTS;
+use enclave_utils::KEY_MANAGER;
+
+use crate::io::{calc_encryption_key, RawWasmOutput, WasmOutput};
+use crate::types::SecretMessage;
+
+pub const EXECUTION_RECEIPT_DOMAIN: &[u8] = b"secret-execution-receipt-v1";
+const INPUT_COMMITMENT_KEY_DOMAIN: &[u8] = b"execution_receipt_input";
+const OUTPUT_COMMITMENT_KEY_DOMAIN: &[u8] = b"execution_receipt_output";
+const SIGNING_KEY_DOMAIN: &[u8] = b"execution_receipt_signing";
+
+pub const EXECUTION_RECEIPT_ATTRIBUTE: &str = "execution_receipt";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ExecutionReceipt {
+    pub contract_address: HumanAddr,
+    pub code_hash: Binary,
+    pub input_commitment: Binary,
+    pub output_commitment: Binary,
+    pub gas_used: Uint64,
+    pub height: Uint64,
+    /// Compressed secp256k1 public key, the same on every node
+    pub public_key: Binary,
+    /// Compact secp256k1 signature over `sha256(sign_bytes)`
+    pub signature: Binary,
+}
+
+impl ExecutionReceipt {
+    pub fn sign_bytes(&self) -> Vec<u8> {
+        let contract_address = self.contract_address.0.as_bytes();
+
+        let mut bytes = vec![];
+        bytes.extend_from_slice(EXECUTION_RECEIPT_DOMAIN);
+        bytes.extend_from_slice(&(contract_address.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(contract_address);
+        bytes.extend_from_slice(self.code_hash.as_slice());
+        bytes.extend_from_slice(self.input_commitment.as_slice());
+        bytes.extend_from_slice(self.output_commitment.as_slice());
+        bytes.extend_from_slice(&self.gas_used.u64().to_be_bytes());
+        bytes.extend_from_slice(&self.height.u64().to_be_bytes());
+
+        bytes
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn sign(
+        contract_address: &str,
+        code_hash: &[u8; HASH_SIZE],
+        input_commitment: [u8; HASH_SIZE],
+        output_commitment: [u8; HASH_SIZE],
+        gas_used: u64,
+        height: u64,
+        signing_key: &[u8],
+    ) -> Result<Self, EnclaveError> {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let sk = secp256k1::SecretKey::from_slice(signing_key).map_err(|err| {
+            error!("execution receipt signing key is invalid: {:?}", err);
+            EnclaveError::InternalError
+        })?;
+
+        let mut receipt = Self {
+            contract_address: HumanAddr(contract_address.to_string()),
+            code_hash: Binary(code_hash.to_vec()),
+            input_commitment: Binary(input_commitment.to_vec()),
+            output_commitment: Binary(output_commitment.to_vec()),
+            gas_used: Uint64::new(gas_used),
+            height: Uint64::new(height),
+            public_key: Binary(
+                secp256k1::PublicKey::from_secret_key(&secp, &sk)
+                    .serialize()
+                    .to_vec(),
+            ),
+            signature: Binary(vec![]),
+        };
+
+        let msg = secp256k1::Message::from_slice(&sha_256(&receipt.sign_bytes()))
+            .map_err(|_| EnclaveError::InternalError)?;
+        receipt.signature = Binary(secp.sign_ecdsa(&msg, &sk).serialize_compact().to_vec());
+
+        Ok(receipt)
+    }
+}
+
+/// The key receipts are signed with, derived from the genesis consensus seed
+pub fn signing_key() -> [u8; 32] {
+    let consensus_state_ikm = KEY_MANAGER.get_consensus_state_ikm().unwrap();
+    *consensus_state_ikm
+        .genesis
+        .derive_key_from_this(SIGNING_KEY_DOMAIN)
+        .get()
+}
+
+/// The keys of the input and output commitments of an execution. The sender derives them from
+/// the tx encryption key the same way.
+pub fn commitment_keys(tx_key: &AESKey) -> (AESKey, AESKey) {
+    (
+        tx_key.derive_key_from_this(INPUT_COMMITMENT_KEY_DOMAIN),
+        tx_key.derive_key_from_this(OUTPUT_COMMITMENT_KEY_DOMAIN),
+    )
+}
+
+/// The `data` of the contract's output, before it's encrypted. Failed executions have none.
+fn output_data(output: &[u8]) -> Option<Vec<u8>> {
+    let data = match serde_json::from_slice(output).ok()? {
+        RawWasmOutput::OkV010 { ok, .. } => ok.data,
+        RawWasmOutput::OkV1 { ok, .. } => ok.data,
+        _ => return None,
+    };
+
+    Some(data.map(|data| data.0).unwrap_or_default())
+}
+
+/// The receipt of an encrypted execution, if receipts are enabled and the execution succeeded.
+/// `output` is the output of the contract, before it's encrypted, and `gas_used` the gas the
+/// enclave reports for the execution.
+#[allow(clippy::too_many_arguments)]
+pub fn execution_receipt(
+    contract_address: &str,
+    code_hash: &[u8; HASH_SIZE],
+    secret_msg: &SecretMessage,
+    decrypted_msg: &[u8],
+    output: &[u8],
+    gas_used: u64,
+    height: u64,
+) -> Result<Option<ExecutionReceipt>, EnclaveError> {
+    if !EXECUTION_RECEIPTS.get_or(false, true) {
+        return Ok(None);
+    }
+
+    let data = match output_data(output) {
+        Some(data) => data,
+        None => return Ok(None),
+    };
+
+    let tx_key = calc_encryption_key(&secret_msg.nonce, &secret_msg.user_public_key);
+    let (input_key, output_key) = commitment_keys(&tx_key);
+
+    ExecutionReceipt::sign(
+        contract_address,
+        code_hash,
+        input_key.sign_sha_256(decrypted_msg),
+        output_key.sign_sha_256(&data),
+        gas_used,
+        height,
+        &signing_key(),
+    )
+    .map(Some)
+}
+
+/// Add `receipt` to the final output of the execution, as a plaintext attribute
+pub fn attach_execution_receipt(
+    output: Vec<u8>,
+    receipt: &ExecutionReceipt,
+) -> Result<Vec<u8>, EnclaveError> {
+    let mut wasm_output: WasmOutput = serde_json::from_slice(&output).map_err(|err| {
+        error!("failed to decode the output to attach a receipt: {:?}", err);
+        EnclaveError::FailedToDeserialize
+    })?;
+
+    let attribute = LogAttribute {
+        key: EXECUTION_RECEIPT_ATTRIBUTE.to_string(),
+        value: serde_json::to_string(receipt).map_err(|err| {
+            error!("failed to encode an execution receipt: {:?}", err);
+            EnclaveError::FailedToSerialize
+        })?,
+        encrypted: false,
+        ..Default::default()
+    };
+
+    if let Some(ok) = wasm_output.v010.as_mut().and_then(|v010| v010.ok.as_mut()) {
+        ok.log.push(attribute);
+    } else if let Some(ok) = wasm_output.v1.as_mut().and_then(|v1| v1.ok.as_mut()) {
+        ok.attributes.push(attribute);
+    } else {
+        return Ok(output);
+    }
+
+    serde_json::to_vec(&wasm_output).map_err(|err| {
+        error!("failed to encode the output with a receipt: {:?}", err);
+        EnclaveError::FailedToSerialize
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_execution_receipt_signature() {
+        let receipt = ExecutionReceipt::sign(
+            "secret1contract",
+            &[7u8; HASH_SIZE],
+            [1u8; HASH_SIZE],
+            [2u8; HASH_SIZE],
+            1000,
+            100,
+            &sha_256(b"signing key"),
+        )
+        .unwrap();
+        assert_eq!(receipt.gas_used.u64(), 1000);
+        assert_eq!(receipt.height.u64(), 100);
+
+        let secp = secp256k1::Secp256k1::verification_only();
+        let signature =
+            secp256k1::ecdsa::Signature::from_compact(receipt.signature.as_slice()).unwrap();
+        let public_key = secp256k1::PublicKey::from_slice(receipt.public_key.as_slice()).unwrap();
+        let msg = secp256k1::Message::from_slice(&sha_256(&receipt.sign_bytes())).unwrap();
+        assert!(secp.verify_ecdsa(&msg, &signature, &public_key).is_ok());
+
+        // the signature binds every field
+        let mut tampered = receipt.clone();
+        tampered.gas_used = Uint64::new(999);
+        let msg = secp256k1::Message::from_slice(&sha_256(&tampered.sign_bytes())).unwrap();
+        assert!(secp.verify_ecdsa(&msg, &signature, &public_key).is_err());
+
+        let (input_key, output_key) = commitment_keys(&AESKey::new_from_slice(&[3u8; 32]));
+        assert_ne!(
+            input_key.sign_sha_256(b"msg"),
+            output_key.sign_sha_256(b"msg")
+        );
+    }
+
+    pub fn test_execution_receipt_attach() {
+        let receipt = ExecutionReceipt::sign(
+            "secret1contract",
+            &[7u8; HASH_SIZE],
+            [1u8; HASH_SIZE],
+            [2u8; HASH_SIZE],
+            1000,
+            100,
+            &sha_256(b"signing key"),
+        )
+        .unwrap();
+
+        let output = br#"{"v010":null,"v1":{"Ok":{"messages":[],"attributes":[{"key":"a","value":"b"}],"events":[],"data":null},"Err":null},"ibc_basic":null,"ibc_packet_receive":null,"ibc_open_channel":null,"query":null,"internal_reply_enclave_sig":null,"internal_msg_id":null}"#;
+        let attached: WasmOutput =
+            serde_json::from_slice(&attach_execution_receipt(output.to_vec(), &receipt).unwrap())
+                .unwrap();
+
+        let attributes = attached.v1.unwrap().ok.unwrap().attributes;
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(attributes[0].key, "a");
+        assert_eq!(attributes[1].key, EXECUTION_RECEIPT_ATTRIBUTE);
+        let attached_receipt: ExecutionReceipt =
+            serde_json::from_str(&attributes[1].value).unwrap();
+        assert_eq!(attached_receipt, receipt);
+
+        // errors have no attributes
+        let err = br#"{"v010":{"Ok":null,"Err":{"generic_err":{"msg":"x"}}},"v1":null,"ibc_basic":null,"ibc_packet_receive":null,"ibc_open_channel":null,"query":null,"internal_reply_enclave_sig":null,"internal_msg_id":null}"#;
+        assert_eq!(
+            attach_execution_receipt(err.to_vec(), &receipt).unwrap(),
+            err.to_vec()
+        );
+    }
+}
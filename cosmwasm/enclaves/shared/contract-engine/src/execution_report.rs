@@ -0,0 +1,154 @@
+//! Reports of what an execution spent its gas on, for the node to tune fees and for chain
+//! analytics. Every execution that runs the contract returns one to the node, next to its output:
+//!
+//! ```json
+//! {"execution_report":{"wasm_gas":81234,"host_functions":[{"name":"db_read","calls":3,"gas":3300}],
+//!  "storage":{"reads":3,"cache_hits":1,"writes":2,"removes":0},"module_cache_hit":true,"call_depth":1}}
+//! ```
+//!
+//! The report isn't part of consensus. `module_cache_hit` depends on the node's cache, and the
+//! node may ignore the report altogether.
+//!
+//! The breakdown shows which host functions an execution called, which its total gas only hints
+//! at. Contracts that hide their gas with `gas_uniform` don't get a report.
+
+use std::collections::BTreeMap;
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use enclave_ffi_types::EnclaveError;
+
+use crate::io::WasmOutput;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct ExecutionReport {
+    /// The gas of the contract's own instructions
+    pub wasm_gas: u64,
+    /// Sorted by name
+    pub host_functions: Vec<HostFunctionGas>,
+    pub storage: StorageOps,
+    /// Whether the contract's module was already compiled
+    pub module_cache_hit: bool,
+    /// How many contracts are waiting for this execution to return, see `call_stack`
+    pub call_depth: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct HostFunctionGas {
+    pub name: String,
+    pub calls: u64,
+    /// Both the gas charged inside the enclave and the gas of the ocalls it made
+    pub gas: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct StorageOps {
+    pub reads: u64,
+    /// Reads served from the values the execution already read or wrote
+    pub cache_hits: u64,
+    /// Keys written to the state when the execution finished
+    pub writes: u64,
+    pub removes: u64,
+}
+
+/// The gas each host function used during an execution
+#[derive(Debug, Default)]
+pub struct HostGasMeter {
+    functions: BTreeMap<&'static str, (u64, u64)>,
+    /// The part of the gas that was charged from the wasm instance's gas counter
+    instance_gas: u64,
+}
+
+impl HostGasMeter {
+    /// Records a call that charged `instance_gas` inside the enclave, and `external_gas` for its
+    /// ocalls
+    pub fn record(&mut self, name: &'static str, instance_gas: u64, external_gas: u64) {
+        let (calls, total) = self.functions.entry(name).or_default();
+        *calls += 1;
+        *total = total.saturating_add(instance_gas.saturating_add(external_gas));
+        self.instance_gas = self.instance_gas.saturating_add(instance_gas);
+    }
+
+    pub fn instance_gas(&self) -> u64 {
+        self.instance_gas
+    }
+
+    pub fn report(&self) -> Vec<HostFunctionGas> {
+        self.functions
+            .iter()
+            .map(|(name, (calls, gas))| HostFunctionGas {
+                name: name.to_string(),
+                calls: *calls,
+                gas: *gas,
+            })
+            .collect()
+    }
+}
+
+pub fn attach_execution_report(
+    output: Vec<u8>,
+    report: ExecutionReport,
+) -> Result<Vec<u8>, EnclaveError> {
+    let mut wasm_output: WasmOutput = serde_json::from_slice(&output).map_err(|err| {
+        error!("failed to decode the output to attach a report: {:?}", err);
+        EnclaveError::FailedToDeserialize
+    })?;
+
+    wasm_output.execution_report = Some(report);
+
+    serde_json::to_vec(&wasm_output).map_err(|err| {
+        error!("failed to encode the output with a report: {:?}", err);
+        EnclaveError::FailedToSerialize
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_host_gas_meter() {
+        let mut meter = HostGasMeter::default();
+        assert_eq!(meter.report(), vec![]);
+
+        meter.record("db_write", 2000, 0);
+        meter.record("db_read", 1000, 0);
+        meter.record("db_read", 1000, 500);
+        assert_eq!(meter.instance_gas(), 4000);
+
+        assert_eq!(
+            meter.report(),
+            vec![
+                HostFunctionGas {
+                    name: "db_read".to_string(),
+                    calls: 2,
+                    gas: 2500,
+                },
+                HostFunctionGas {
+                    name: "db_write".to_string(),
+                    calls: 1,
+                    gas: 2000,
+                },
+            ]
+        );
+    }
+
+    pub fn test_attach_execution_report() {
+        let output = serde_json::to_vec(&WasmOutput::default()).unwrap();
+        let report = ExecutionReport {
+            wasm_gas: 100,
+            storage: StorageOps {
+                reads: 1,
+                ..Default::default()
+            },
+            call_depth: 2,
+            ..Default::default()
+        };
+
+        let attached = attach_execution_report(output, report.clone()).unwrap();
+        let wasm_output: WasmOutput = serde_json::from_slice(&attached).unwrap();
+        assert_eq!(wasm_output.execution_report, Some(report));
+
+        assert!(attach_execution_report(b"not json".to_vec(), ExecutionReport::default()).is_err());
+    }
+}
@@ -9,28 +9,34 @@ use sgx_types::sgx_status_t;
 
 use enclave_ffi_types::{
     Ctx, EnclaveBuffer, EnclaveError, HandleResult, HealthCheckResult, InitResult, MigrateResult,
-    QueryResult, RuntimeConfiguration, UpdateAdminResult,
+    QueryResult, RuntimeConfiguration, UpdateAdminResult, MAX_ADDRESS_LENGTH, MAX_ENV_LENGTH,
+    MAX_PROOF_LENGTH, MAX_SIG_INFO_LENGTH, MAX_STATE_CHUNK_LENGTH,
 };
 
-use enclave_utils::{oom_handler, validate_const_ptr, validate_input_length, validate_mut_ptr};
+use enclave_cosmos_types::types::ContractCode;
+use enclave_utils::compute_params::MAX_PARAMS_LENGTH;
+use enclave_utils::input_limits::{max_msg_length, max_wasm_length};
+use enclave_utils::output_buffer::{write_output, BufferTooSmall};
+use enclave_utils::{
+    crash_breadcrumbs, oom_handler, validate_const_ptr, validate_input_length, validate_mut_ptr,
+};
 
 use crate::external::results::{
     result_handle_success_to_handleresult, result_init_success_to_initresult,
-    result_migrate_success_to_result, result_query_success_to_queryresult,
-    result_update_admin_success_to_result,
+    query_chunk_to_queryresult, result_migrate_success_to_result,
+    result_query_success_to_queryresult, result_update_admin_success_to_result,
 };
+use crate::gas::WasmCosts;
+
+/// An ICS-23 proof of a value of this chain's state, see `block_verifier::state_proof`
+const MAX_STATE_PROOF_LENGTH: usize = 100_000;
+/// The longest chain id Tendermint accepts
+const MAX_CHAIN_ID_LENGTH: usize = 50;
 
 lazy_static! {
     static ref ECALL_ALLOCATE_STACK: SgxMutex<Vec<EnclaveBuffer>> = SgxMutex::new(Vec::new());
 }
 
-const MAX_ENV_LENGTH: usize = 10_240; // 10 KiB
-const MAX_SIG_INFO_LENGTH: usize = 5_120_000; // 5 MiB, includes tx_bytes and sign_bytes
-const MAX_MSG_LENGTH: usize = 2_048_000; // 2 MiB
-const MAX_ADDRESS_LENGTH: usize = 65; // canonical can be 20 or 32 bytes, humanized can be 45 or 65
-const MAX_PROOF_LENGTH: usize = 32; // output of sha256
-const MAX_WASM_LENGHT: usize = 3_145_728; // 3 MiB, larger Wasm ATM is 1,990,361 bytes (1.6 MiB)
-
 /// # Safety
 /// Always use protection
 #[no_mangle]
@@ -104,7 +110,13 @@ fn ecall_configure_runtime_impl(config: RuntimeConfiguration) -> sgx_status_t {
         "inside ecall_configure_runtime: {}",
         config.module_cache_size
     );
+    crash_breadcrumbs::install_panic_hook();
     crate::wasm3::module_cache::configure_module_cache(config.module_cache_size as usize);
+    crate::query_limits::configure_query_limits(crate::query_limits::QueryLimits {
+        memory_pages: config.query_memory_limit_pages,
+        gas: config.query_gas_limit,
+        time_ms: config.query_time_limit_ms,
+    });
     sgx_status_t::SGX_SUCCESS
 }
 
@@ -153,6 +165,31 @@ pub unsafe extern "C" fn ecall_init(
     admin: *const u8,
     admin_len: usize,
 ) -> InitResult {
+    let _breadcrumbs = crash_breadcrumbs::enter_ecall("ecall_init");
+
+    if !enclave_utils::self_test::self_test_passed() {
+        return InitResult::Failure {
+            err: EnclaveError::SelfTestFailed,
+        };
+    }
+
+    let input_too_large = || result_init_success_to_initresult(Err(EnclaveError::InputTooLarge));
+    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, input_too_large());
+    validate_input_length!(msg_len, "msg", max_msg_length(), input_too_large());
+    validate_input_length!(
+        contract_len,
+        "contract",
+        max_wasm_length(),
+        input_too_large()
+    );
+    validate_input_length!(
+        sig_info_len,
+        "sig_info",
+        MAX_SIG_INFO_LENGTH,
+        input_too_large()
+    );
+    validate_input_length!(admin_len, "admin", MAX_ADDRESS_LENGTH, input_too_large());
+
     if let Err(err) = oom_handler::register_oom_handler() {
         error!("Could not register OOM handler!");
         return InitResult::Failure { err };
@@ -166,12 +203,6 @@ pub unsafe extern "C" fn ecall_init(
     validate_const_ptr!(sig_info, sig_info_len, failed_call());
     // admin can be null (checked later), so admin_len is allowed to be 0
 
-    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, failed_call());
-    validate_input_length!(msg_len, "msg", MAX_MSG_LENGTH, failed_call());
-    validate_input_length!(contract_len, "contract", MAX_WASM_LENGHT, failed_call());
-    validate_input_length!(sig_info_len, "sig_info", MAX_SIG_INFO_LENGTH, failed_call());
-    validate_input_length!(admin_len, "admin", MAX_ADDRESS_LENGTH, failed_call());
-
     let contract = std::slice::from_raw_parts(contract, contract_len);
     let env = std::slice::from_raw_parts(env, env_len);
     let msg = std::slice::from_raw_parts(msg, msg_len);
@@ -234,6 +265,31 @@ pub unsafe extern "C" fn ecall_handle(
     sig_info_len: usize,
     handle_type: u8,
 ) -> HandleResult {
+    let _breadcrumbs = crash_breadcrumbs::enter_ecall("ecall_handle");
+
+    if !enclave_utils::self_test::self_test_passed() {
+        return HandleResult::Failure {
+            err: EnclaveError::SelfTestFailed,
+        };
+    }
+
+    let input_too_large =
+        || result_handle_success_to_handleresult(Err(EnclaveError::InputTooLarge));
+    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, input_too_large());
+    validate_input_length!(msg_len, "msg", max_msg_length(), input_too_large());
+    validate_input_length!(
+        contract_len,
+        "contract",
+        max_wasm_length(),
+        input_too_large()
+    );
+    validate_input_length!(
+        sig_info_len,
+        "sig_info",
+        MAX_SIG_INFO_LENGTH,
+        input_too_large()
+    );
+
     if let Err(err) = oom_handler::register_oom_handler() {
         error!("Could not register OOM handler!");
         return HandleResult::Failure { err };
@@ -247,11 +303,6 @@ pub unsafe extern "C" fn ecall_handle(
     validate_const_ptr!(contract, contract_len, failed_call());
     validate_const_ptr!(sig_info, sig_info_len, failed_call());
 
-    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, failed_call());
-    validate_input_length!(msg_len, "msg", MAX_MSG_LENGTH, failed_call());
-    validate_input_length!(contract_len, "contract", MAX_WASM_LENGHT, failed_call());
-    validate_input_length!(sig_info_len, "sig_info", MAX_SIG_INFO_LENGTH, failed_call());
-
     let contract = std::slice::from_raw_parts(contract, contract_len);
     let env = std::slice::from_raw_parts(env, env_len);
     let msg = std::slice::from_raw_parts(msg, msg_len);
@@ -296,6 +347,97 @@ pub unsafe extern "C" fn ecall_handle(
     }
 }
 
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_simulate(
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    contract: *const u8,
+    contract_len: usize,
+    env: *const u8,
+    env_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    sig_info: *const u8,
+    sig_info_len: usize,
+) -> HandleResult {
+    let _breadcrumbs = crash_breadcrumbs::enter_ecall("ecall_simulate");
+
+    let input_too_large =
+        || result_handle_success_to_handleresult(Err(EnclaveError::InputTooLarge));
+    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, input_too_large());
+    validate_input_length!(msg_len, "msg", max_msg_length(), input_too_large());
+    validate_input_length!(
+        contract_len,
+        "contract",
+        max_wasm_length(),
+        input_too_large()
+    );
+    validate_input_length!(
+        sig_info_len,
+        "sig_info",
+        MAX_SIG_INFO_LENGTH,
+        input_too_large()
+    );
+
+    if let Err(err) = oom_handler::register_oom_handler() {
+        error!("Could not register OOM handler!");
+        return HandleResult::Failure { err };
+    }
+
+    let failed_call =
+        || result_handle_success_to_handleresult(Err(EnclaveError::FailedFunctionCall));
+    validate_mut_ptr!(used_gas as _, std::mem::size_of::<u64>(), failed_call());
+    validate_const_ptr!(env, env_len, failed_call());
+    validate_const_ptr!(msg, msg_len, failed_call());
+    validate_const_ptr!(contract, contract_len, failed_call());
+    validate_const_ptr!(sig_info, sig_info_len, failed_call());
+
+    let contract = std::slice::from_raw_parts(contract, contract_len);
+    let env = std::slice::from_raw_parts(env, env_len);
+    let msg = std::slice::from_raw_parts(msg, msg_len);
+    let sig_info = std::slice::from_raw_parts(sig_info, sig_info_len);
+    let result = panic::catch_unwind(|| {
+        let mut local_used_gas = *used_gas;
+        let result = crate::contract_operations::simulate(
+            context,
+            gas_limit,
+            &mut local_used_gas,
+            contract,
+            env,
+            msg,
+            sig_info,
+        );
+        *used_gas = local_used_gas;
+        result_handle_success_to_handleresult(result)
+    });
+
+    if let Err(err) = oom_handler::restore_safety_buffer() {
+        error!("Could not restore OOM safety buffer!");
+        return HandleResult::Failure { err };
+    }
+
+    if let Ok(res) = result {
+        res
+    } else {
+        *used_gas = gas_limit / 2;
+
+        if oom_handler::get_then_clear_oom_happened() {
+            error!("Call ecall_simulate failed because the enclave ran out of memory!");
+            HandleResult::Failure {
+                err: EnclaveError::OutOfMemory,
+            }
+        } else {
+            error!("Call ecall_simulate panicked unexpectedly!");
+            HandleResult::Failure {
+                err: EnclaveError::Panic,
+            }
+        }
+    }
+}
+
 /// # Safety
 /// Always use protection
 #[no_mangle]
@@ -310,6 +452,8 @@ pub unsafe extern "C" fn ecall_query(
     msg: *const u8,
     msg_len: usize,
 ) -> QueryResult {
+    let _breadcrumbs = crash_breadcrumbs::enter_ecall("ecall_query");
+
     ecall_query_impl(
         context,
         gas_limit,
@@ -337,6 +481,16 @@ unsafe fn ecall_query_impl(
     msg: *const u8,
     msg_len: usize,
 ) -> QueryResult {
+    let input_too_large = || result_query_success_to_queryresult(Err(EnclaveError::InputTooLarge));
+    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, input_too_large());
+    validate_input_length!(msg_len, "msg", max_msg_length(), input_too_large());
+    validate_input_length!(
+        contract_len,
+        "contract",
+        max_wasm_length(),
+        input_too_large()
+    );
+
     if let Err(err) = oom_handler::register_oom_handler() {
         error!("Could not register OOM handler!");
         return QueryResult::Failure { err };
@@ -348,10 +502,6 @@ unsafe fn ecall_query_impl(
     validate_const_ptr!(msg, msg_len, failed_call());
     validate_const_ptr!(contract, contract_len, failed_call());
 
-    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, failed_call());
-    validate_input_length!(msg_len, "msg", MAX_MSG_LENGTH, failed_call());
-    validate_input_length!(contract_len, "contract", MAX_WASM_LENGHT, failed_call());
-
     let contract = std::slice::from_raw_parts(contract, contract_len);
     let env = std::slice::from_raw_parts(env, env_len);
     let msg = std::slice::from_raw_parts(msg, msg_len);
@@ -393,6 +543,49 @@ unsafe fn ecall_query_impl(
     }
 }
 
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_query_chunk(continuation_token: &[u8; 32]) -> QueryResult {
+    let _breadcrumbs = crash_breadcrumbs::enter_ecall("ecall_query_chunk");
+
+    validate_const_ptr!(
+        continuation_token.as_ptr(),
+        continuation_token.len(),
+        QueryResult::Failure {
+            err: EnclaveError::FailedFunctionCall
+        }
+    );
+
+    let result =
+        panic::catch_unwind(
+            || match crate::query_chunks::next_chunk(continuation_token) {
+                Ok(chunk) => query_chunk_to_queryresult(chunk),
+                Err(err) => QueryResult::Failure { err },
+            },
+        );
+
+    result.unwrap_or_else(|_| {
+        error!("Call ecall_query_chunk panicked unexpectedly!");
+        QueryResult::Failure {
+            err: EnclaveError::Panic,
+        }
+    })
+}
+
+/// Cancels the query from outside the chain that was given `cancellation_id` in its env, see
+/// `query_cancellation`. Cancelling a query that already returned does nothing.
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_cancel_query(cancellation_id: u64) -> sgx_status_t {
+    if !crate::query_cancellation::cancel_query(cancellation_id) {
+        debug!("query {} to cancel isn't running", cancellation_id);
+    }
+    sgx_status_t::SGX_SUCCESS
+}
+
 /// # Safety
 /// Always use protection
 #[no_mangle]
@@ -413,6 +606,37 @@ pub unsafe extern "C" fn ecall_migrate(
     admin_proof: *const u8,
     admin_proof_len: usize,
 ) -> MigrateResult {
+    let _breadcrumbs = crash_breadcrumbs::enter_ecall("ecall_migrate");
+
+    if !enclave_utils::self_test::self_test_passed() {
+        return MigrateResult::Failure {
+            err: EnclaveError::SelfTestFailed,
+        };
+    }
+
+    let input_too_large = || result_migrate_success_to_result(Err(EnclaveError::InputTooLarge));
+    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, input_too_large());
+    validate_input_length!(msg_len, "msg", max_msg_length(), input_too_large());
+    validate_input_length!(
+        contract_len,
+        "contract",
+        max_wasm_length(),
+        input_too_large()
+    );
+    validate_input_length!(
+        sig_info_len,
+        "sig_info",
+        MAX_SIG_INFO_LENGTH,
+        input_too_large()
+    );
+    validate_input_length!(admin_len, "admin", MAX_ADDRESS_LENGTH, input_too_large());
+    validate_input_length!(
+        admin_proof_len,
+        "admin_proof",
+        MAX_ENV_LENGTH,
+        input_too_large()
+    );
+
     if let Err(err) = oom_handler::register_oom_handler() {
         error!("Could not register OOM handler!");
         return MigrateResult::Failure { err };
@@ -428,18 +652,6 @@ pub unsafe extern "C" fn ecall_migrate(
     validate_const_ptr!(admin, admin_len, failed_call());
     validate_const_ptr!(admin_proof, admin_proof_len, failed_call());
 
-    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, failed_call());
-    validate_input_length!(msg_len, "msg", MAX_MSG_LENGTH, failed_call());
-    validate_input_length!(contract_len, "contract", MAX_WASM_LENGHT, failed_call());
-    validate_input_length!(sig_info_len, "sig_info", MAX_SIG_INFO_LENGTH, failed_call());
-    validate_input_length!(admin_len, "admin", MAX_ADDRESS_LENGTH, failed_call());
-    validate_input_length!(
-        admin_proof_len,
-        "admin_proof",
-        MAX_ENV_LENGTH,
-        failed_call()
-    );
-
     let contract = std::slice::from_raw_parts(contract, contract_len);
     let env = std::slice::from_raw_parts(env, env_len);
     let msg = std::slice::from_raw_parts(msg, msg_len);
@@ -503,6 +715,42 @@ pub unsafe extern "C" fn ecall_update_admin(
     new_admin: *const u8,
     new_admin_len: usize,
 ) -> UpdateAdminResult {
+    let _breadcrumbs = crash_breadcrumbs::enter_ecall("ecall_update_admin");
+
+    if !enclave_utils::self_test::self_test_passed() {
+        return UpdateAdminResult::UpdateAdminFailure {
+            err: EnclaveError::SelfTestFailed,
+        };
+    }
+
+    let input_too_large =
+        || result_update_admin_success_to_result(Err(EnclaveError::InputTooLarge));
+    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, input_too_large());
+    validate_input_length!(
+        sig_info_len,
+        "sig_info",
+        MAX_SIG_INFO_LENGTH,
+        input_too_large()
+    );
+    validate_input_length!(
+        current_admin_len,
+        "current_admin",
+        MAX_ADDRESS_LENGTH,
+        input_too_large()
+    );
+    validate_input_length!(
+        current_admin_proof_len,
+        "current_admin_proof",
+        MAX_PROOF_LENGTH,
+        input_too_large()
+    );
+    validate_input_length!(
+        new_admin_len,
+        "new_admin",
+        MAX_ADDRESS_LENGTH,
+        input_too_large()
+    );
+
     if let Err(err) = oom_handler::register_oom_handler() {
         error!("Could not register OOM handler!");
         return UpdateAdminResult::UpdateAdminFailure { err };
@@ -520,21 +768,6 @@ pub unsafe extern "C" fn ecall_update_admin(
     );
     // new_admin can be null (checked later), so new_admin_len is allowed to be 0
 
-    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, failed_call());
-    validate_input_length!(sig_info_len, "sig_info", MAX_SIG_INFO_LENGTH, failed_call());
-    validate_input_length!(
-        current_admin_len,
-        "current_admin",
-        MAX_ADDRESS_LENGTH,
-        failed_call()
-    );
-    validate_input_length!(
-        current_admin_proof_len,
-        "current_admin_proof",
-        MAX_PROOF_LENGTH,
-        failed_call()
-    );
-
     let env = std::slice::from_raw_parts(env, env_len);
     let sig_info = std::slice::from_raw_parts(sig_info, sig_info_len);
     let current_admin = std::slice::from_raw_parts(current_admin, current_admin_len);
@@ -580,6 +813,1018 @@ pub unsafe extern "C" fn ecall_health_check() -> HealthCheckResult {
     HealthCheckResult::Success
 }
 
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_open_query_session(
+    nonce: &[u8; 32],
+    user_public_key: &[u8; 32],
+    current_height: u64,
+    expiry_height: u64,
+    session_id: &mut [u8; 32],
+) -> sgx_status_t {
+    validate_const_ptr!(
+        nonce.as_ptr(),
+        nonce.len(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    validate_const_ptr!(
+        user_public_key.as_ptr(),
+        user_public_key.len(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    validate_mut_ptr!(
+        session_id.as_mut_ptr(),
+        session_id.len(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+
+    let result = panic::catch_unwind(|| {
+        crate::query_session::open_session(nonce, user_public_key, current_height, expiry_height)
+    });
+
+    match result {
+        Ok(Ok(id)) => {
+            session_id.copy_from_slice(&id);
+            sgx_status_t::SGX_SUCCESS
+        }
+        Ok(Err(err)) => {
+            debug!("failed to open a query session: {}", err);
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        }
+        Err(_) => {
+            error!("Call ecall_open_query_session panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_prewarm_module(
+    contract: *const u8,
+    contract_len: u32,
+) -> sgx_status_t {
+    validate_input_length!(
+        contract_len as usize,
+        "contract",
+        max_wasm_length(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    validate_const_ptr!(
+        contract,
+        contract_len as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+    let contract = std::slice::from_raw_parts(contract, contract_len as usize);
+
+    if let Err(err) = oom_handler::register_oom_handler() {
+        error!("Could not register OOM handler: {}", err);
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    let result = panic::catch_unwind(|| {
+        crate::wasm3::module_cache::prewarm_module(
+            &ContractCode::new(contract),
+            &WasmCosts::default(),
+        )
+    });
+
+    if let Err(err) = oom_handler::restore_safety_buffer() {
+        error!("Could not restore OOM safety buffer: {}", err);
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    match result {
+        Ok(Ok(_)) => sgx_status_t::SGX_SUCCESS,
+        Ok(Err(err)) => {
+            debug!("failed to prewarm a module: {}", err);
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        }
+        Err(_) => {
+            if oom_handler::get_then_clear_oom_happened() {
+                error!("Call ecall_prewarm_module failed because the enclave ran out of memory!");
+            } else {
+                error!("Call ecall_prewarm_module panicked unexpectedly!");
+            }
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_validate_code(
+    contract: *const u8,
+    contract_len: u32,
+    report: *mut u8,
+    report_capacity: u32,
+    report_len: &mut u32,
+) -> sgx_status_t {
+    // Modules of any size get a report, `validate_code` doesn't parse those that are too large
+    validate_const_ptr!(
+        contract,
+        contract_len as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+    validate_mut_ptr!(
+        report,
+        report_capacity as usize,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    let contract = std::slice::from_raw_parts(contract, contract_len as usize);
+
+    if let Err(err) = oom_handler::register_oom_handler() {
+        error!("Could not register OOM handler: {}", err);
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    let result =
+        panic::catch_unwind(|| crate::wasm3::code_limits::encoded_validation_report(contract));
+
+    if let Err(err) = oom_handler::restore_safety_buffer() {
+        error!("Could not restore OOM safety buffer: {}", err);
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    write_ecall_output(
+        "ecall_validate_code",
+        result,
+        report,
+        report_capacity,
+        report_len,
+    )
+}
+
+/// Checks the enclave params of compute module params governance is about to set, see
+/// `enclave_params`
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_validate_enclave_params(
+    params: *const u8,
+    params_len: u32,
+) -> sgx_status_t {
+    validate_input_length!(
+        params_len,
+        "params",
+        MAX_PARAMS_LENGTH,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    validate_const_ptr!(
+        params,
+        params_len as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+    let params = std::slice::from_raw_parts(params, params_len as usize);
+
+    let result = panic::catch_unwind(|| crate::enclave_params::validate_enclave_params(params));
+
+    match result {
+        Ok(Ok(())) => sgx_status_t::SGX_SUCCESS,
+        Ok(Err(err)) => {
+            debug!("invalid enclave params: {}", err);
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        }
+        Err(_) => {
+            error!("Call ecall_validate_enclave_params panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_module_cache_snapshot(
+    snapshot: *mut u8,
+    snapshot_capacity: u32,
+    snapshot_len: &mut u32,
+) -> sgx_status_t {
+    validate_mut_ptr!(
+        snapshot,
+        snapshot_capacity as usize,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+
+    if let Err(err) = oom_handler::register_oom_handler() {
+        error!("Could not register OOM handler: {}", err);
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    let result = panic::catch_unwind(crate::module_cache_snapshot::encoded_snapshot);
+
+    if let Err(err) = oom_handler::restore_safety_buffer() {
+        error!("Could not restore OOM safety buffer: {}", err);
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    write_ecall_output(
+        "ecall_module_cache_snapshot",
+        result,
+        snapshot,
+        snapshot_capacity,
+        snapshot_len,
+    )
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn ecall_disclose_contract_key(
+    env: *const u8,
+    env_len: usize,
+    code_hash: &[u8; 32],
+    admin: *const u8,
+    admin_len: usize,
+    admin_proof: *const u8,
+    admin_proof_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    msg_proof: *const u8,
+    msg_proof_len: usize,
+    encrypted_key: *mut u8,
+    encrypted_key_capacity: u32,
+    encrypted_key_len: &mut u32,
+) -> sgx_status_t {
+    let invalid_parameter = || sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, invalid_parameter());
+    validate_input_length!(admin_len, "admin", MAX_ADDRESS_LENGTH, invalid_parameter());
+    validate_input_length!(
+        admin_proof_len,
+        "admin_proof",
+        MAX_PROOF_LENGTH,
+        invalid_parameter()
+    );
+    validate_input_length!(msg_len, "msg", max_msg_length(), invalid_parameter());
+    validate_input_length!(
+        msg_proof_len,
+        "msg_proof",
+        MAX_STATE_PROOF_LENGTH,
+        invalid_parameter()
+    );
+
+    validate_const_ptr!(env, env_len, invalid_parameter());
+    validate_const_ptr!(code_hash.as_ptr(), code_hash.len(), invalid_parameter());
+    validate_const_ptr!(admin, admin_len, invalid_parameter());
+    validate_const_ptr!(admin_proof, admin_proof_len, invalid_parameter());
+    validate_const_ptr!(msg, msg_len, invalid_parameter());
+    validate_const_ptr!(msg_proof, msg_proof_len, invalid_parameter());
+    validate_mut_ptr!(
+        encrypted_key,
+        encrypted_key_capacity as usize,
+        invalid_parameter()
+    );
+
+    let env = std::slice::from_raw_parts(env, env_len);
+    let admin = std::slice::from_raw_parts(admin, admin_len);
+    let admin_proof = std::slice::from_raw_parts(admin_proof, admin_proof_len);
+    let msg = std::slice::from_raw_parts(msg, msg_len);
+    let msg_proof = std::slice::from_raw_parts(msg_proof, msg_proof_len);
+
+    let result = panic::catch_unwind(|| {
+        crate::contract_operations::disclose_contract_key(
+            env,
+            code_hash,
+            admin,
+            admin_proof,
+            msg,
+            msg_proof,
+        )
+        .map(|key| key.to_vec())
+    });
+
+    write_ecall_output(
+        "ecall_disclose_contract_key",
+        result,
+        encrypted_key,
+        encrypted_key_capacity,
+        encrypted_key_len,
+    )
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_create_foreign_client(
+    msg: *const u8,
+    msg_len: usize,
+    msg_proof: *const u8,
+    msg_proof_len: usize,
+    output: *mut u8,
+    output_capacity: u32,
+    output_len: &mut u32,
+) -> sgx_status_t {
+    let invalid_parameter = || sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    validate_input_length!(msg_len, "msg", max_msg_length(), invalid_parameter());
+    validate_input_length!(
+        msg_proof_len,
+        "msg_proof",
+        MAX_STATE_PROOF_LENGTH,
+        invalid_parameter()
+    );
+    validate_const_ptr!(msg, msg_len, invalid_parameter());
+    validate_const_ptr!(msg_proof, msg_proof_len, invalid_parameter());
+    validate_mut_ptr!(output, output_capacity as usize, invalid_parameter());
+
+    let msg = std::slice::from_raw_parts(msg, msg_len);
+    let msg_proof = std::slice::from_raw_parts(msg_proof, msg_proof_len);
+
+    let result =
+        panic::catch_unwind(|| crate::foreign_clients::create_foreign_client(msg, msg_proof));
+
+    write_ecall_output(
+        "ecall_create_foreign_client",
+        result,
+        output,
+        output_capacity,
+        output_len,
+    )
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn ecall_update_foreign_client(
+    client: *const u8,
+    client_len: usize,
+    client_proof: *const u8,
+    client_proof_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    output: *mut u8,
+    output_capacity: u32,
+    output_len: &mut u32,
+) -> sgx_status_t {
+    let invalid_parameter = || sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    validate_input_length!(
+        client_len,
+        "client",
+        MAX_STATE_CHUNK_LENGTH,
+        invalid_parameter()
+    );
+    validate_input_length!(
+        client_proof_len,
+        "client_proof",
+        MAX_STATE_PROOF_LENGTH,
+        invalid_parameter()
+    );
+    validate_input_length!(msg_len, "msg", max_msg_length(), invalid_parameter());
+    validate_const_ptr!(client, client_len, invalid_parameter());
+    validate_const_ptr!(client_proof, client_proof_len, invalid_parameter());
+    validate_const_ptr!(msg, msg_len, invalid_parameter());
+    validate_mut_ptr!(output, output_capacity as usize, invalid_parameter());
+
+    let client = std::slice::from_raw_parts(client, client_len);
+    let client_proof = std::slice::from_raw_parts(client_proof, client_proof_len);
+    let msg = std::slice::from_raw_parts(msg, msg_len);
+
+    let result = panic::catch_unwind(|| {
+        crate::foreign_clients::update_foreign_client(client, client_proof, msg)
+    });
+
+    write_ecall_output(
+        "ecall_update_foreign_client",
+        result,
+        output,
+        output_capacity,
+        output_len,
+    )
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_submit_foreign_client(
+    chain_id: *const u8,
+    chain_id_len: usize,
+    client: *const u8,
+    client_len: usize,
+    proof: *const u8,
+    proof_len: usize,
+) -> sgx_status_t {
+    let invalid_parameter = || sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    validate_input_length!(
+        chain_id_len,
+        "chain_id",
+        MAX_CHAIN_ID_LENGTH,
+        invalid_parameter()
+    );
+    validate_input_length!(
+        client_len,
+        "client",
+        MAX_STATE_CHUNK_LENGTH,
+        invalid_parameter()
+    );
+    validate_input_length!(
+        proof_len,
+        "proof",
+        MAX_STATE_PROOF_LENGTH,
+        invalid_parameter()
+    );
+    validate_const_ptr!(chain_id, chain_id_len, invalid_parameter());
+    validate_const_ptr!(client, client_len, invalid_parameter());
+    validate_const_ptr!(proof, proof_len, invalid_parameter());
+
+    let chain_id = std::slice::from_raw_parts(chain_id, chain_id_len);
+    let client = std::slice::from_raw_parts(client, client_len);
+    let proof = std::slice::from_raw_parts(proof, proof_len);
+
+    let result = panic::catch_unwind(|| {
+        crate::foreign_clients::submit_foreign_client(chain_id, client, proof)
+    });
+
+    match result {
+        Ok(Ok(())) => sgx_status_t::SGX_SUCCESS,
+        Ok(Err(err)) => {
+            warn!("failed to submit a foreign client: {}", err);
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        }
+        Err(_) => {
+            error!("Call ecall_submit_foreign_client panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_get_mempool_key(
+    height: u64,
+    public_key: &mut [u8; 32],
+) -> sgx_status_t {
+    validate_mut_ptr!(
+        public_key.as_mut_ptr(),
+        public_key.len(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+
+    let result = panic::catch_unwind(|| crate::encrypted_mempool::mempool_public_key(height));
+
+    match result {
+        Ok(Ok(key)) => {
+            public_key.copy_from_slice(&key);
+            sgx_status_t::SGX_SUCCESS
+        }
+        Ok(Err(err)) => {
+            error!(
+                "failed to get the mempool key of height {}: {}",
+                height, err
+            );
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+        Err(_) => {
+            error!("Call ecall_get_mempool_key panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_decrypt_encrypted_tx(
+    msg: *const u8,
+    msg_len: usize,
+    msg_proof: *const u8,
+    msg_proof_len: usize,
+    output: *mut u8,
+    output_capacity: u32,
+    output_len: &mut u32,
+) -> sgx_status_t {
+    let invalid_parameter = || sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    validate_input_length!(msg_len, "msg", max_msg_length(), invalid_parameter());
+    validate_input_length!(
+        msg_proof_len,
+        "msg_proof",
+        MAX_STATE_PROOF_LENGTH,
+        invalid_parameter()
+    );
+    validate_const_ptr!(msg, msg_len, invalid_parameter());
+    validate_const_ptr!(msg_proof, msg_proof_len, invalid_parameter());
+    validate_mut_ptr!(output, output_capacity as usize, invalid_parameter());
+
+    let msg = std::slice::from_raw_parts(msg, msg_len);
+    let msg_proof = std::slice::from_raw_parts(msg_proof, msg_proof_len);
+
+    let result =
+        panic::catch_unwind(|| crate::encrypted_mempool::decrypt_encrypted_tx(msg, msg_proof));
+
+    write_ecall_output(
+        "ecall_decrypt_encrypted_tx",
+        result,
+        output,
+        output_capacity,
+        output_len,
+    )
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn ecall_export_state(
+    env: *const u8,
+    env_len: usize,
+    code_hash: &[u8; 32],
+    admin: *const u8,
+    admin_len: usize,
+    admin_proof: *const u8,
+    admin_proof_len: usize,
+    authorization: *const u8,
+    authorization_len: usize,
+    pairs: *const u8,
+    pairs_len: usize,
+    output: *mut u8,
+    output_capacity: u32,
+    output_len: &mut u32,
+) -> sgx_status_t {
+    state_backup_ecall_impl(
+        "ecall_export_state",
+        crate::contract_operations::export_state,
+        env,
+        env_len,
+        code_hash,
+        admin,
+        admin_len,
+        admin_proof,
+        admin_proof_len,
+        authorization,
+        authorization_len,
+        pairs,
+        pairs_len,
+        output,
+        output_capacity,
+        output_len,
+    )
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn ecall_import_state(
+    env: *const u8,
+    env_len: usize,
+    code_hash: &[u8; 32],
+    admin: *const u8,
+    admin_len: usize,
+    admin_proof: *const u8,
+    admin_proof_len: usize,
+    authorization: *const u8,
+    authorization_len: usize,
+    backup_chunk: *const u8,
+    backup_chunk_len: usize,
+    output: *mut u8,
+    output_capacity: u32,
+    output_len: &mut u32,
+) -> sgx_status_t {
+    state_backup_ecall_impl(
+        "ecall_import_state",
+        crate::contract_operations::import_state,
+        env,
+        env_len,
+        code_hash,
+        admin,
+        admin_len,
+        admin_proof,
+        admin_proof_len,
+        authorization,
+        authorization_len,
+        backup_chunk,
+        backup_chunk_len,
+        output,
+        output_capacity,
+        output_len,
+    )
+}
+
+type StateBackupOperation =
+    fn(&[u8], &[u8; 32], &[u8], &[u8], &[u8], &[u8]) -> Result<Vec<u8>, EnclaveError>;
+
+/// Shared by the state export and import ecalls, see `write_ecall_output`
+/// # Safety
+/// Always use protection
+#[allow(clippy::too_many_arguments)]
+unsafe fn state_backup_ecall_impl(
+    name: &str,
+    operation: StateBackupOperation,
+    env: *const u8,
+    env_len: usize,
+    code_hash: &[u8; 32],
+    admin: *const u8,
+    admin_len: usize,
+    admin_proof: *const u8,
+    admin_proof_len: usize,
+    authorization: *const u8,
+    authorization_len: usize,
+    chunk: *const u8,
+    chunk_len: usize,
+    output: *mut u8,
+    output_capacity: u32,
+    output_len: &mut u32,
+) -> sgx_status_t {
+    let invalid_parameter = || sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, invalid_parameter());
+    validate_input_length!(admin_len, "admin", MAX_ADDRESS_LENGTH, invalid_parameter());
+    validate_input_length!(
+        admin_proof_len,
+        "admin_proof",
+        MAX_PROOF_LENGTH,
+        invalid_parameter()
+    );
+    validate_input_length!(
+        authorization_len,
+        "authorization",
+        max_msg_length(),
+        invalid_parameter()
+    );
+    validate_input_length!(
+        chunk_len,
+        "chunk",
+        MAX_STATE_CHUNK_LENGTH,
+        invalid_parameter()
+    );
+
+    validate_const_ptr!(env, env_len, invalid_parameter());
+    validate_const_ptr!(code_hash.as_ptr(), code_hash.len(), invalid_parameter());
+    validate_const_ptr!(admin, admin_len, invalid_parameter());
+    validate_const_ptr!(admin_proof, admin_proof_len, invalid_parameter());
+    validate_const_ptr!(authorization, authorization_len, invalid_parameter());
+    validate_const_ptr!(chunk, chunk_len, invalid_parameter());
+    validate_mut_ptr!(output, output_capacity as usize, invalid_parameter());
+
+    let env = std::slice::from_raw_parts(env, env_len);
+    let admin = std::slice::from_raw_parts(admin, admin_len);
+    let admin_proof = std::slice::from_raw_parts(admin_proof, admin_proof_len);
+    let authorization = std::slice::from_raw_parts(authorization, authorization_len);
+    let chunk = std::slice::from_raw_parts(chunk, chunk_len);
+
+    if let Err(_err) = oom_handler::register_oom_handler() {
+        error!("Could not register OOM handler!");
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    let result =
+        panic::catch_unwind(|| operation(env, code_hash, admin, admin_proof, authorization, chunk));
+
+    if let Err(_err) = oom_handler::restore_safety_buffer() {
+        error!("Could not restore OOM safety buffer!");
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    write_ecall_output(name, result, output, output_capacity, output_len)
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_state_commitment_leaves(
+    env: *const u8,
+    env_len: usize,
+    code_hash: &[u8; 32],
+    pairs: *const u8,
+    pairs_len: usize,
+    output: *mut u8,
+    output_capacity: u32,
+    output_len: &mut u32,
+) -> sgx_status_t {
+    let invalid_parameter = || sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, invalid_parameter());
+    validate_input_length!(
+        pairs_len,
+        "pairs",
+        MAX_STATE_CHUNK_LENGTH,
+        invalid_parameter()
+    );
+
+    validate_const_ptr!(env, env_len, invalid_parameter());
+    validate_const_ptr!(code_hash.as_ptr(), code_hash.len(), invalid_parameter());
+    validate_const_ptr!(pairs, pairs_len, invalid_parameter());
+    validate_mut_ptr!(output, output_capacity as usize, invalid_parameter());
+
+    let env = std::slice::from_raw_parts(env, env_len);
+    let pairs = std::slice::from_raw_parts(pairs, pairs_len);
+
+    if let Err(_err) = oom_handler::register_oom_handler() {
+        error!("Could not register OOM handler!");
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    let result = panic::catch_unwind(|| {
+        crate::contract_operations::state_commitment_leaves(env, code_hash, pairs)
+    });
+
+    if let Err(_err) = oom_handler::restore_safety_buffer() {
+        error!("Could not restore OOM safety buffer!");
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    write_ecall_output(
+        "ecall_state_commitment_leaves",
+        result,
+        output,
+        output_capacity,
+        output_len,
+    )
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn ecall_fold_state_commitment(
+    env: *const u8,
+    env_len: usize,
+    code_hash: &[u8; 32],
+    progress: *const u8,
+    progress_len: usize,
+    pairs: *const u8,
+    pairs_len: usize,
+    last_chunk: u8,
+    output: *mut u8,
+    output_capacity: u32,
+    output_len: &mut u32,
+) -> sgx_status_t {
+    let invalid_parameter = || sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, invalid_parameter());
+    validate_input_length!(
+        progress_len,
+        "progress",
+        max_msg_length(),
+        invalid_parameter()
+    );
+    validate_input_length!(
+        pairs_len,
+        "pairs",
+        MAX_STATE_CHUNK_LENGTH,
+        invalid_parameter()
+    );
+
+    validate_const_ptr!(env, env_len, invalid_parameter());
+    validate_const_ptr!(code_hash.as_ptr(), code_hash.len(), invalid_parameter());
+    validate_const_ptr!(progress, progress_len, invalid_parameter());
+    validate_const_ptr!(pairs, pairs_len, invalid_parameter());
+    validate_mut_ptr!(output, output_capacity as usize, invalid_parameter());
+
+    let env = std::slice::from_raw_parts(env, env_len);
+    let progress = std::slice::from_raw_parts(progress, progress_len);
+    let pairs = std::slice::from_raw_parts(pairs, pairs_len);
+
+    if let Err(_err) = oom_handler::register_oom_handler() {
+        error!("Could not register OOM handler!");
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    let result = panic::catch_unwind(|| {
+        crate::contract_operations::fold_state_commitment(
+            env,
+            code_hash,
+            progress,
+            pairs,
+            last_chunk != 0,
+        )
+    });
+
+    if let Err(_err) = oom_handler::restore_safety_buffer() {
+        error!("Could not restore OOM safety buffer!");
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    write_ecall_output(
+        "ecall_fold_state_commitment",
+        result,
+        output,
+        output_capacity,
+        output_len,
+    )
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn ecall_reencrypt_state(
+    env: *const u8,
+    env_len: usize,
+    code_hash: &[u8; 32],
+    progress: *const u8,
+    progress_len: usize,
+    pairs: *const u8,
+    pairs_len: usize,
+    last_chunk: u8,
+    output: *mut u8,
+    output_capacity: u32,
+    output_len: &mut u32,
+) -> sgx_status_t {
+    let invalid_parameter = || sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    validate_input_length!(env_len, "env", MAX_ENV_LENGTH, invalid_parameter());
+    validate_input_length!(
+        progress_len,
+        "progress",
+        MAX_MSG_LENGTH,
+        invalid_parameter()
+    );
+    validate_input_length!(
+        pairs_len,
+        "pairs",
+        MAX_STATE_CHUNK_LENGTH,
+        invalid_parameter()
+    );
+
+    validate_const_ptr!(env, env_len, invalid_parameter());
+    validate_const_ptr!(code_hash.as_ptr(), code_hash.len(), invalid_parameter());
+    validate_const_ptr!(progress, progress_len, invalid_parameter());
+    validate_const_ptr!(pairs, pairs_len, invalid_parameter());
+    validate_mut_ptr!(output, output_capacity as usize, invalid_parameter());
+
+    let env = std::slice::from_raw_parts(env, env_len);
+    let progress = std::slice::from_raw_parts(progress, progress_len);
+    let pairs = std::slice::from_raw_parts(pairs, pairs_len);
+
+    if let Err(_err) = oom_handler::register_oom_handler() {
+        error!("Could not register OOM handler!");
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    let result = panic::catch_unwind(|| {
+        crate::contract_operations::reencrypt_state(
+            env,
+            code_hash,
+            progress,
+            pairs,
+            last_chunk != 0,
+        )
+    });
+
+    if let Err(_err) = oom_handler::restore_safety_buffer() {
+        error!("Could not restore OOM safety buffer!");
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    write_ecall_output(
+        "ecall_reencrypt_state",
+        result,
+        output,
+        output_capacity,
+        output_len,
+    )
+}
+
+/// The output is only written when it fits in `output_capacity`, otherwise `output_len` says how
+/// large it is, so the caller can retry.
+/// # Safety
+/// Always use protection
+unsafe fn write_ecall_output(
+    name: &str,
+    result: std::thread::Result<Result<Vec<u8>, EnclaveError>>,
+    output: *mut u8,
+    output_capacity: u32,
+    output_len: &mut u32,
+) -> sgx_status_t {
+    match result {
+        Ok(Ok(encoded)) => match write_output(&encoded, output, output_capacity, output_len) {
+            Ok(()) => sgx_status_t::SGX_SUCCESS,
+            Err(BufferTooSmall { needed }) => {
+                debug!(
+                    "{} output ({}) is larger than the buffer ({})",
+                    name, needed, output_capacity
+                );
+                sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+            }
+        },
+        Ok(Err(err)) => {
+            error!("{} failed: {}", name, err);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+        Err(_) => {
+            if oom_handler::get_then_clear_oom_happened() {
+                error!(
+                    "Call {} failed because the enclave ran out of memory!",
+                    name
+                );
+            } else {
+                error!("Call {} panicked unexpectedly!", name);
+            }
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+#[cfg(feature = "conformance")]
+pub unsafe extern "C" fn ecall_take_conformance_trace(
+    trace: *mut u8,
+    trace_capacity: u32,
+    trace_len: &mut u32,
+) -> sgx_status_t {
+    validate_mut_ptr!(
+        trace,
+        trace_capacity as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+
+    let result = panic::catch_unwind(crate::conformance::encoded_trace);
+
+    match result {
+        Ok(encoded) => {
+            *trace_len = encoded.len() as u32;
+            // The trace is kept, so the caller can retry with a buffer of `trace_len`
+            if encoded.len() > trace_capacity as usize {
+                debug!(
+                    "conformance trace ({}) is larger than the buffer ({})",
+                    encoded.len(),
+                    trace_capacity
+                );
+                return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+            }
+
+            let trace_slice = std::slice::from_raw_parts_mut(trace, encoded.len());
+            trace_slice.copy_from_slice(&encoded);
+            crate::conformance::clear_trace();
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(_) => {
+            error!("Call ecall_take_conformance_trace panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+#[cfg(not(feature = "conformance"))]
+pub unsafe extern "C" fn ecall_take_conformance_trace(
+    _trace: *mut u8,
+    _trace_capacity: u32,
+    _trace_len: &mut u32,
+) -> sgx_status_t {
+    sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+#[cfg(feature = "audit")]
+pub unsafe extern "C" fn ecall_take_audit_transcript(
+    transcript: *mut u8,
+    transcript_capacity: u32,
+    transcript_len: &mut u32,
+) -> sgx_status_t {
+    validate_mut_ptr!(
+        transcript,
+        transcript_capacity as usize,
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+
+    let result = panic::catch_unwind(crate::audit::encoded_transcript);
+
+    match result {
+        Ok(encoded) => {
+            *transcript_len = encoded.len() as u32;
+            // The transcript is kept, so the caller can retry with a buffer of `transcript_len`
+            if encoded.len() > transcript_capacity as usize {
+                debug!(
+                    "audit transcript ({}) is larger than the buffer ({})",
+                    encoded.len(),
+                    transcript_capacity
+                );
+                return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+            }
+
+            let transcript_slice = std::slice::from_raw_parts_mut(transcript, encoded.len());
+            transcript_slice.copy_from_slice(&encoded);
+            crate::audit::clear_transcript();
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(_) => {
+            error!("Call ecall_take_audit_transcript panicked unexpectedly!");
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+#[cfg(not(feature = "audit"))]
+pub unsafe extern "C" fn ecall_take_audit_transcript(
+    _transcript: *mut u8,
+    _transcript_capacity: u32,
+    _transcript_len: &mut u32,
+) -> sgx_status_t {
+    sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED
+}
+
 #[cfg(feature = "test")]
 pub mod tests {
     use super::*;
@@ -1,3 +1,2 @@
 pub mod ecalls;
-pub mod ocalls;
 pub mod results;
@@ -1,11 +1,12 @@
 use sgx_types::sgx_status_t;
 
+use enclave_crypto::ocalls::ocall_allocate;
 use enclave_ffi_types::{
     EnclaveError, HandleResult, InitResult, MigrateResult, QueryResult, UntrustedVmError,
     UpdateAdminResult, UserSpaceBuffer,
 };
 
-use crate::external::ocalls::ocall_allocate;
+use crate::query_chunks::{first_chunk, QueryChunk};
 
 /// This struct is returned from module initialization.
 pub struct InitSuccess {
@@ -147,25 +148,38 @@ pub fn result_query_success_to_queryresult(
     result: Result<QuerySuccess, EnclaveError>,
 ) -> QueryResult {
     match result {
-        Ok(QuerySuccess { output }) => {
-            let user_buffer = unsafe {
-                let mut user_buffer = std::mem::MaybeUninit::<UserSpaceBuffer>::uninit();
-                match ocall_allocate(user_buffer.as_mut_ptr(), output.as_ptr(), output.len()) {
-                    sgx_status_t::SGX_SUCCESS => { /* continue */ }
-                    _ => {
-                        return QueryResult::Failure {
-                            err: EnclaveError::FailedOcall {
-                                vm_error: UntrustedVmError::default(),
-                            },
-                        }
-                    }
+        Ok(QuerySuccess { output }) => match first_chunk(output) {
+            Ok(chunk) => query_chunk_to_queryresult(chunk),
+            Err(err) => QueryResult::Failure { err },
+        },
+        Err(err) => QueryResult::Failure { err },
+    }
+}
+
+pub fn query_chunk_to_queryresult(chunk: QueryChunk) -> QueryResult {
+    let QueryChunk {
+        data,
+        total_size,
+        continuation_token,
+    } = chunk;
+
+    let user_buffer = unsafe {
+        let mut user_buffer = std::mem::MaybeUninit::<UserSpaceBuffer>::uninit();
+        match ocall_allocate(user_buffer.as_mut_ptr(), data.as_ptr(), data.len()) {
+            sgx_status_t::SGX_SUCCESS => { /* continue */ }
+            _ => {
+                return QueryResult::Failure {
+                    err: EnclaveError::FailedOcall {
+                        vm_error: UntrustedVmError::default(),
+                    },
                 }
-                user_buffer.assume_init()
-            };
-            QueryResult::Success {
-                output: user_buffer,
             }
         }
-        Err(err) => QueryResult::Failure { err },
+        user_buffer.assume_init()
+    };
+    QueryResult::Success {
+        output: user_buffer,
+        total_size,
+        continuation_token,
     }
 }
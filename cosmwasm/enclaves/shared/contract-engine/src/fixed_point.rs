@@ -0,0 +1,621 @@
+//! Deterministic 256-bit fixed-point math, for contracts that need more precision than they can
+//! get from integers, since floats are banned from contracts.
+//!
+//! Numbers are 32 byte big-endian two's complement integers, scaled by 10^18, i.e. they have 18
+//! decimals. Non-negative numbers are encoded like the atomics of cosmwasm's `Decimal256`.
+//!
+//! * `mul` and `div` are exact, except for the last decimal, which is rounded the way the caller
+//!   asks for.
+//! * `ln`, `exp` and `pow` are computed with 36 decimals, and then rounded to the nearest number.
+//!
+//! Everything is computed with integers, so the results are the same on every node.
+
+use std::cmp::Ordering;
+
+/// Error codes returned to contracts by the fixed-point host functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum FixedPointError {
+    /// An operand isn't 32 bytes long, or the rounding mode is unknown
+    InvalidInput = 1,
+    /// The result doesn't fit in 256 bits
+    Overflow = 2,
+    DivisionByZero = 3,
+    /// The logarithm of a number that isn't positive, or a power of a negative number
+    OutOfDomain = 4,
+}
+
+/// How `mul` and `div` round their result. When rounding a magnitude, `Floor` is towards zero
+/// and `Ceil` is away from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rounding {
+    /// Towards negative infinity
+    Floor,
+    /// Towards positive infinity
+    Ceil,
+    /// To the nearest number, and away from zero if there are two
+    Nearest,
+}
+
+impl Rounding {
+    fn from_u32(mode: u32) -> Result<Self, FixedPointError> {
+        match mode {
+            0 => Ok(Rounding::Floor),
+            1 => Ok(Rounding::Ceil),
+            2 => Ok(Rounding::Nearest),
+            _ => Err(FixedPointError::InvalidInput),
+        }
+    }
+
+    /// How to round the magnitude of a result with the given sign
+    fn of_magnitude(self, negative: bool) -> Self {
+        match (self, negative) {
+            (Rounding::Floor, true) => Rounding::Ceil,
+            (Rounding::Ceil, true) => Rounding::Floor,
+            (rounding, _) => rounding,
+        }
+    }
+}
+
+/// An unsigned 256-bit integer, least significant limb first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct U256([u64; 4]);
+
+impl U256 {
+    const ZERO: Self = U256([0; 4]);
+    const ONE: Self = U256([1, 0, 0, 0]);
+
+    const fn from_u128(value: u128) -> Self {
+        U256([value as u64, (value >> 64) as u64, 0, 0])
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    fn bits(&self) -> u32 {
+        match self.0.iter().rposition(|limb| *limb != 0) {
+            Some(index) => 64 * index as u32 + 64 - self.0[index].leading_zeros(),
+            None => 0,
+        }
+    }
+
+    fn bit(&self, index: u32) -> bool {
+        (self.0[index as usize / 64] >> (index % 64)) & 1 == 1
+    }
+
+    fn overflowing_add(self, other: Self) -> (Self, bool) {
+        let mut result = [0u64; 4];
+        let mut carry = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (sum, carry1) = self.0[i].overflowing_add(other.0[i]);
+            let (sum, carry2) = sum.overflowing_add(carry as u64);
+            *limb = sum;
+            carry = carry1 || carry2;
+        }
+        (U256(result), carry)
+    }
+
+    fn overflowing_sub(self, other: Self) -> (Self, bool) {
+        let mut result = [0u64; 4];
+        let mut borrow = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (difference, borrow1) = self.0[i].overflowing_sub(other.0[i]);
+            let (difference, borrow2) = difference.overflowing_sub(borrow as u64);
+            *limb = difference;
+            borrow = borrow1 || borrow2;
+        }
+        (U256(result), borrow)
+    }
+
+    fn checked_add(self, other: Self) -> Result<Self, FixedPointError> {
+        match self.overflowing_add(other) {
+            (sum, false) => Ok(sum),
+            (_, true) => Err(FixedPointError::Overflow),
+        }
+    }
+
+    /// Only used where `other` is known to be smaller
+    fn wrapping_sub(self, other: Self) -> Self {
+        self.overflowing_sub(other).0
+    }
+
+    /// Shifts left by less than 256 bits, dropping the bits that are shifted out
+    fn shl(self, shift: u32) -> Self {
+        let limbs = (shift / 64) as usize;
+        let bits = shift % 64;
+        let mut result = [0u64; 4];
+        for (i, limb) in result.iter_mut().enumerate().skip(limbs) {
+            *limb = self.0[i - limbs] << bits;
+            if bits > 0 && i > limbs {
+                *limb |= self.0[i - limbs - 1] >> (64 - bits);
+            }
+        }
+        U256(result)
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// 10^18, the scale of the numbers contracts pass
+const SCALE: U256 = U256::from_u128(1_000_000_000_000_000_000);
+/// 10^36, the scale of the intermediate results of `ln`, `exp` and `pow`
+const WIDE_SCALE: U256 = U256::from_u128(1_000_000_000_000_000_000_000_000_000_000_000_000);
+/// ln(2), with 36 decimals
+const LN_2: U256 = U256::from_u128(693_147_180_559_945_309_417_232_121_458_176_568);
+/// 2^255, the magnitude of the smallest number
+const MIN_MAGNITUDE: U256 = U256([0, 0, 0, 1 << 63]);
+/// 1000, beyond which `exp` overflows, or is 0 for negative numbers
+const MAX_EXP_MAGNITUDE: U256 = U256::from_u128(1_000_000_000_000_000_000_000);
+
+/// The 512-bit product of two numbers, least significant limb first
+fn full_mul(a: U256, b: U256) -> [u64; 8] {
+    let mut result = [0u64; 8];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let current = result[i + j] as u128 + a.0[i] as u128 * b.0[j] as u128 + carry;
+            result[i + j] = current as u64;
+            carry = current >> 64;
+        }
+        result[i + 4] = carry as u64;
+    }
+    result
+}
+
+/// `a * b / divisor`, without overflowing in between
+fn mul_div(a: U256, b: U256, divisor: U256, rounding: Rounding) -> Result<U256, FixedPointError> {
+    if divisor.is_zero() {
+        return Err(FixedPointError::DivisionByZero);
+    }
+
+    let product = full_mul(a, b);
+    let product_bits = match product.iter().rposition(|limb| *limb != 0) {
+        Some(index) => 64 * index + 64 - product[index].leading_zeros() as usize,
+        None => 0,
+    };
+    let mut quotient = U256::ZERO;
+    let mut remainder = U256::ZERO;
+    for index in (0..product_bits).rev() {
+        // The remainder is smaller than the divisor, so it only needs one more bit when shifted
+        let carry = remainder.bit(255);
+        remainder = remainder.shl(1);
+        remainder.0[0] |= (product[index / 64] >> (index % 64)) & 1;
+        if carry || remainder >= divisor {
+            if index >= 256 {
+                return Err(FixedPointError::Overflow);
+            }
+            remainder = remainder.wrapping_sub(divisor);
+            quotient.0[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    let round_up = match rounding {
+        Rounding::Floor => false,
+        Rounding::Ceil => !remainder.is_zero(),
+        Rounding::Nearest => remainder >= divisor.wrapping_sub(remainder),
+    };
+    if round_up {
+        quotient.checked_add(U256::ONE)
+    } else {
+        Ok(quotient)
+    }
+}
+
+fn mul_small(a: U256, b: u128) -> Result<U256, FixedPointError> {
+    mul_div(a, U256::from_u128(b), U256::ONE, Rounding::Floor)
+}
+
+/// The sum of two signed numbers, as a sign and a magnitude
+fn signed_add(
+    (a_negative, a): (bool, U256),
+    (b_negative, b): (bool, U256),
+) -> Result<(bool, U256), FixedPointError> {
+    if a_negative == b_negative {
+        Ok((a_negative, a.checked_add(b)?))
+    } else if a >= b {
+        Ok((a_negative, a.wrapping_sub(b)))
+    } else {
+        Ok((b_negative, b.wrapping_sub(a)))
+    }
+}
+
+/// A number passed by a contract, as a sign and a magnitude
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fixed {
+    negative: bool,
+    magnitude: U256,
+}
+
+impl Fixed {
+    const ZERO: Self = Fixed {
+        negative: false,
+        magnitude: U256::ZERO,
+    };
+    const ONE: Self = Fixed {
+        negative: false,
+        magnitude: SCALE,
+    };
+
+    fn new(negative: bool, magnitude: U256) -> Result<Self, FixedPointError> {
+        if magnitude > MIN_MAGNITUDE || (magnitude == MIN_MAGNITUDE && !negative) {
+            return Err(FixedPointError::Overflow);
+        }
+
+        Ok(Fixed {
+            negative: negative && !magnitude.is_zero(),
+            magnitude,
+        })
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, FixedPointError> {
+        if bytes.len() != 32 {
+            return Err(FixedPointError::InvalidInput);
+        }
+
+        let mut value = U256::ZERO;
+        for (i, chunk) in bytes.chunks(8).enumerate() {
+            let mut limb = [0u8; 8];
+            limb.copy_from_slice(chunk);
+            value.0[3 - i] = u64::from_be_bytes(limb);
+        }
+
+        if value.bit(255) {
+            Ok(Fixed {
+                negative: true,
+                magnitude: U256::ZERO.wrapping_sub(value),
+            })
+        } else {
+            Ok(Fixed {
+                negative: false,
+                magnitude: value,
+            })
+        }
+    }
+
+    fn encode(&self) -> [u8; 32] {
+        let value = if self.negative {
+            U256::ZERO.wrapping_sub(self.magnitude)
+        } else {
+            self.magnitude
+        };
+
+        let mut bytes = [0u8; 32];
+        for (i, chunk) in bytes.chunks_mut(8).enumerate() {
+            chunk.copy_from_slice(&value.0[3 - i].to_be_bytes());
+        }
+        bytes
+    }
+}
+
+/// The natural logarithm of a positive number, with 36 decimals
+fn ln_wide(x: U256) -> Result<(bool, U256), FixedPointError> {
+    // x = m * 2^k, with 1 <= m < 2
+    let scaled_mantissa = |k: i32| {
+        if k >= 0 {
+            mul_div(x, SCALE, U256::ONE.shl(k as u32), Rounding::Nearest)
+        } else {
+            mul_div(x.shl(-k as u32), SCALE, U256::ONE, Rounding::Floor)
+        }
+    };
+    let mut k = x.bits() as i32 - SCALE.bits() as i32;
+    let mut m = scaled_mantissa(k)?;
+    if m < WIDE_SCALE {
+        k -= 1;
+        m = scaled_mantissa(k)?;
+    }
+
+    // ln(m) = 2 * atanh(z) = 2 * (z + z^3 / 3 + z^5 / 5 + ...), with z = (m - 1) / (m + 1) < 1/3
+    let z = mul_div(
+        m.wrapping_sub(WIDE_SCALE),
+        WIDE_SCALE,
+        m.checked_add(WIDE_SCALE)?,
+        Rounding::Nearest,
+    )?;
+    let z_squared = mul_div(z, z, WIDE_SCALE, Rounding::Nearest)?;
+    let mut sum = z;
+    let mut power = z;
+    let mut n = 1;
+    loop {
+        power = mul_div(power, z_squared, WIDE_SCALE, Rounding::Nearest)?;
+        if power.is_zero() {
+            break;
+        }
+        n += 2;
+        sum = sum.checked_add(mul_div(
+            power,
+            U256::ONE,
+            U256::from_u128(n),
+            Rounding::Nearest,
+        )?)?;
+    }
+    let ln_m = sum.checked_add(sum)?;
+
+    let k_ln_2 = mul_small(LN_2, k.unsigned_abs() as u128)?;
+    signed_add((k < 0, k_ln_2), (false, ln_m))
+}
+
+/// e^x, for x with 36 decimals. Returns the result with 18 decimals.
+fn exp_wide(negative: bool, x: U256) -> Result<U256, FixedPointError> {
+    // x = k * ln(2) + r, with |r| <= ln(2) / 2
+    let k = mul_div(x, U256::ONE, LN_2, Rounding::Floor)?;
+    if k > U256::from_u128(200) {
+        // Far beyond 2^255 or below 10^-18
+        return if negative {
+            Ok(U256::ZERO)
+        } else {
+            Err(FixedPointError::Overflow)
+        };
+    }
+    let mut k = k.0[0] as u32;
+    let mut r = x.wrapping_sub(mul_small(LN_2, k as u128)?);
+    let mut r_negative = negative;
+    if r.checked_add(r)? > LN_2 {
+        k += 1;
+        r = LN_2.wrapping_sub(r);
+        r_negative = !negative;
+    }
+
+    // e^|r| = 1 + |r| + |r|^2 / 2! + ...
+    let mut sum = WIDE_SCALE;
+    let mut term = WIDE_SCALE;
+    let mut n = 0;
+    loop {
+        n += 1;
+        term = mul_div(term, r, mul_small(WIDE_SCALE, n)?, Rounding::Nearest)?;
+        if term.is_zero() {
+            break;
+        }
+        sum = sum.checked_add(term)?;
+    }
+    if r_negative {
+        sum = mul_div(WIDE_SCALE, WIDE_SCALE, sum, Rounding::Nearest)?;
+    }
+
+    // e^x = 2^k * e^r
+    if !negative {
+        mul_div(sum, U256::ONE.shl(k), SCALE, Rounding::Nearest)
+    } else if k <= 190 {
+        mul_div(sum, U256::ONE, SCALE.shl(k), Rounding::Nearest)
+    } else {
+        Ok(U256::ZERO)
+    }
+}
+
+/// a * b, rounded with the given rounding mode
+pub fn mul(a: &[u8], b: &[u8], rounding: u32) -> Result<[u8; 32], FixedPointError> {
+    let (a, b) = (Fixed::decode(a)?, Fixed::decode(b)?);
+    let negative = a.negative != b.negative;
+    let rounding = Rounding::from_u32(rounding)?.of_magnitude(negative);
+
+    let magnitude = mul_div(a.magnitude, b.magnitude, SCALE, rounding)?;
+    Ok(Fixed::new(negative, magnitude)?.encode())
+}
+
+/// a / b, rounded with the given rounding mode
+pub fn div(a: &[u8], b: &[u8], rounding: u32) -> Result<[u8; 32], FixedPointError> {
+    let (a, b) = (Fixed::decode(a)?, Fixed::decode(b)?);
+    let negative = a.negative != b.negative;
+    let rounding = Rounding::from_u32(rounding)?.of_magnitude(negative);
+
+    let magnitude = mul_div(a.magnitude, SCALE, b.magnitude, rounding)?;
+    Ok(Fixed::new(negative, magnitude)?.encode())
+}
+
+/// The natural logarithm of x, rounded to the nearest number
+pub fn ln(x: &[u8]) -> Result<[u8; 32], FixedPointError> {
+    let x = Fixed::decode(x)?;
+    if x.negative || x.magnitude.is_zero() {
+        return Err(FixedPointError::OutOfDomain);
+    }
+
+    let (negative, ln) = ln_wide(x.magnitude)?;
+    let magnitude = mul_div(ln, U256::ONE, SCALE, Rounding::Nearest)?;
+    Ok(Fixed::new(negative, magnitude)?.encode())
+}
+
+/// e^x, rounded to the nearest number
+pub fn exp(x: &[u8]) -> Result<[u8; 32], FixedPointError> {
+    let x = Fixed::decode(x)?;
+    if x.magnitude > MAX_EXP_MAGNITUDE {
+        return if x.negative {
+            Ok(Fixed::ZERO.encode())
+        } else {
+            Err(FixedPointError::Overflow)
+        };
+    }
+
+    let magnitude = exp_wide(
+        x.negative,
+        mul_div(x.magnitude, SCALE, U256::ONE, Rounding::Floor)?,
+    )?;
+    Ok(Fixed::new(false, magnitude)?.encode())
+}
+
+/// base^exponent, computed as e^(exponent * ln(base)) and rounded to the nearest number
+pub fn pow(base: &[u8], exponent: &[u8]) -> Result<[u8; 32], FixedPointError> {
+    let (base, exponent) = (Fixed::decode(base)?, Fixed::decode(exponent)?);
+    if exponent.magnitude.is_zero() {
+        return Ok(Fixed::ONE.encode());
+    }
+    if base.negative {
+        return Err(FixedPointError::OutOfDomain);
+    }
+    if base.magnitude.is_zero() {
+        return if exponent.negative {
+            Err(FixedPointError::DivisionByZero)
+        } else {
+            Ok(Fixed::ZERO.encode())
+        };
+    }
+
+    let (ln_negative, ln) = ln_wide(base.magnitude)?;
+    let negative = ln_negative != exponent.negative;
+    let product = match mul_div(ln, exponent.magnitude, SCALE, Rounding::Nearest) {
+        Ok(product) => product,
+        Err(_) if negative => return Ok(Fixed::ZERO.encode()),
+        Err(err) => return Err(err),
+    };
+
+    let magnitude = exp_wide(negative, product)?;
+    Ok(Fixed::new(false, magnitude)?.encode())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn fixed(value: i128) -> [u8; 32] {
+        let magnitude = U256::from_u128(value.unsigned_abs());
+        Fixed::new(value < 0, magnitude).unwrap().encode()
+    }
+
+    fn value(bytes: [u8; 32]) -> i128 {
+        let fixed = Fixed::decode(&bytes).unwrap();
+        assert!(fixed.magnitude.0[2] == 0 && fixed.magnitude.0[3] == 0);
+        let magnitude = fixed.magnitude.0[0] as i128 | (fixed.magnitude.0[1] as i128) << 64;
+        if fixed.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    const ONE: i128 = 1_000_000_000_000_000_000;
+
+    pub fn test_fixed_point_mul_div_rounding() {
+        const FLOOR: u32 = 0;
+        const CEIL: u32 = 1;
+        const NEAREST: u32 = 2;
+
+        let third = value(div(&fixed(ONE), &fixed(3 * ONE), FLOOR).unwrap());
+        assert_eq!(third, 333_333_333_333_333_333);
+        assert_eq!(
+            value(div(&fixed(ONE), &fixed(3 * ONE), CEIL).unwrap()),
+            third + 1
+        );
+        assert_eq!(
+            value(div(&fixed(2 * ONE), &fixed(3 * ONE), NEAREST).unwrap()),
+            666_666_666_666_666_667
+        );
+
+        // Floor and ceil are towards negative and positive infinity, not towards zero
+        assert_eq!(
+            value(div(&fixed(-ONE), &fixed(3 * ONE), FLOOR).unwrap()),
+            -third - 1
+        );
+        assert_eq!(
+            value(div(&fixed(-ONE), &fixed(3 * ONE), CEIL).unwrap()),
+            -third
+        );
+
+        // Ties are rounded away from zero
+        assert_eq!(
+            value(mul(&fixed(15), &fixed(ONE / 10), NEAREST).unwrap()),
+            2
+        );
+        assert_eq!(
+            value(mul(&fixed(-15), &fixed(ONE / 10), NEAREST).unwrap()),
+            -2
+        );
+        assert_eq!(value(mul(&fixed(15), &fixed(ONE / 10), FLOOR).unwrap()), 1);
+
+        // The product isn't truncated before it's divided
+        let big = Fixed::new(false, MIN_MAGNITUDE.wrapping_sub(U256::ONE))
+            .unwrap()
+            .encode();
+        assert_eq!(mul(&big, &fixed(ONE), FLOOR).unwrap(), big);
+        assert_eq!(div(&big, &fixed(ONE), FLOOR).unwrap(), big);
+        assert_eq!(
+            mul(&big, &fixed(2 * ONE), FLOOR),
+            Err(FixedPointError::Overflow)
+        );
+
+        assert_eq!(
+            div(&fixed(ONE), &fixed(0), FLOOR),
+            Err(FixedPointError::DivisionByZero)
+        );
+        assert_eq!(
+            mul(&fixed(ONE), &fixed(ONE), 3),
+            Err(FixedPointError::InvalidInput)
+        );
+        assert_eq!(
+            mul(&fixed(ONE)[1..], &fixed(ONE), FLOOR),
+            Err(FixedPointError::InvalidInput)
+        );
+    }
+
+    pub fn test_fixed_point_ln_exp() {
+        assert_eq!(value(ln(&fixed(ONE)).unwrap()), 0);
+        assert_eq!(value(exp(&fixed(0)).unwrap()), ONE);
+
+        // e = 2.718281828459045235|36..., ln(2) = 0.693147180559945309|41...
+        assert_eq!(value(exp(&fixed(ONE)).unwrap()), 2_718_281_828_459_045_235);
+        assert_eq!(value(ln(&fixed(2 * ONE)).unwrap()), 693_147_180_559_945_309);
+        // ln(0.5) = -0.693147180559945309|41..., e^-1 = 0.367879441171442321|59...
+        assert_eq!(
+            value(ln(&fixed(ONE / 2)).unwrap()),
+            -693_147_180_559_945_309
+        );
+        assert_eq!(value(exp(&fixed(-ONE)).unwrap()), 367_879_441_171_442_322);
+        // ln(10^-18) = -41.446531673892822312|35...
+        assert_eq!(value(ln(&fixed(1)).unwrap()), -41_446_531_673_892_822_312);
+        // e^20 = 485165195.409790277969106830|54...
+        assert_eq!(
+            value(exp(&fixed(20 * ONE)).unwrap()),
+            485_165_195_409_790_277_969_106_831
+        );
+
+        assert_eq!(ln(&fixed(0)), Err(FixedPointError::OutOfDomain));
+        assert_eq!(ln(&fixed(-ONE)), Err(FixedPointError::OutOfDomain));
+        assert_eq!(exp(&fixed(136 * ONE)), Err(FixedPointError::Overflow));
+        assert_eq!(value(exp(&fixed(-100 * ONE)).unwrap()), 0);
+        assert_eq!(value(exp(&fixed(i128::MIN)).unwrap()), 0);
+    }
+
+    pub fn test_fixed_point_pow() {
+        assert_eq!(
+            value(pow(&fixed(2 * ONE), &fixed(10 * ONE)).unwrap()),
+            1024 * ONE
+        );
+        assert_eq!(
+            value(pow(&fixed(4 * ONE), &fixed(ONE / 2)).unwrap()),
+            2 * ONE
+        );
+        assert_eq!(
+            value(pow(&fixed(4 * ONE), &fixed(-ONE / 2)).unwrap()),
+            ONE / 2
+        );
+        // 1.05^10 = 1.628894626777441406|25
+        assert_eq!(
+            value(pow(&fixed(105 * ONE / 100), &fixed(10 * ONE)).unwrap()),
+            1_628_894_626_777_441_406
+        );
+
+        assert_eq!(value(pow(&fixed(0), &fixed(0)).unwrap()), ONE);
+        assert_eq!(value(pow(&fixed(0), &fixed(ONE)).unwrap()), 0);
+        assert_eq!(
+            pow(&fixed(0), &fixed(-ONE)),
+            Err(FixedPointError::DivisionByZero)
+        );
+        assert_eq!(
+            pow(&fixed(-2 * ONE), &fixed(2 * ONE)),
+            Err(FixedPointError::OutOfDomain)
+        );
+        assert_eq!(
+            pow(&fixed(10 * ONE), &fixed(60 * ONE)),
+            Err(FixedPointError::Overflow)
+        );
+        assert_eq!(value(pow(&fixed(10 * ONE), &fixed(-60 * ONE)).unwrap()), 0);
+    }
+}
@@ -0,0 +1,344 @@
+//! Messages creating and updating the light clients of other chains in
+//! `block_verifier::foreign_clients`.
+//!
+//! A client is created by governance, which chooses the header it starts from:
+//!
+//! ```text
+//! message MsgCreateForeignClient {
+//!   string authority = 1;                   // the gov module account
+//!   string chain_id = 2;
+//!   uint64 trusting_period = 3;             // in seconds
+//!   uint64 trust_threshold_numerator = 4;
+//!   uint64 trust_threshold_denominator = 5;
+//!   bytes header = 6;                       // tendermint.types.Header
+//!   bytes next_validators = 7;              // tendermint.types.ValidatorSet
+//! }
+//! ```
+//!
+//! and is then updated by anyone relaying newer headers of the chain:
+//!
+//! ```text
+//! message MsgUpdateForeignClient {
+//!   string signer = 1;
+//!   string chain_id = 2;
+//!   bytes header = 3;                       // tendermint.types.Header
+//!   bytes commit = 4;                       // tendermint.types.Commit
+//!   bytes validators = 5;                   // tendermint.types.ValidatorSet
+//!   bytes next_validators = 6;              // tendermint.types.ValidatorSet
+//! }
+//! ```
+//!
+//! The enclave doesn't take the node's word for any of it. x/compute only stores a
+//! `MsgCreateForeignClient` once governance passed it, at `creation_key` of the chain, and creates
+//! the client in the next block, with a proof of the msg against the app hash of that block. The
+//! client is stored at `client_key` of the chain, and an update is only applied to the client with
+//! a proof that it's the one in that state, so a node can't update a client governance didn't
+//! create, or an older version of it. At the start of every block the node passes the stored
+//! clients with proofs of them, and contracts are answered from the clients that were proven.
+
+use log::*;
+
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::proto_fields::{fields, varint_value, WIRE_TYPE_LEN, WIRE_TYPE_VARINT};
+
+#[cfg(feature = "light-client-validation")]
+use crate::contract_validation::verify_compute_state;
+#[cfg(feature = "light-client-validation")]
+use crate::gov_messages::authority_address;
+use crate::gov_messages::utf8_string;
+
+/// Where x/compute stores the light client of every chain
+#[cfg(any(feature = "light-client-validation", feature = "test"))]
+const FOREIGN_CLIENT_PREFIX: u8 = 0x12;
+/// Where x/compute stores the last `MsgCreateForeignClient` of every chain
+#[cfg(any(feature = "light-client-validation", feature = "test"))]
+const FOREIGN_CLIENT_CREATION_PREFIX: u8 = 0x13;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct CreateForeignClient {
+    pub authority: String,
+    pub chain_id: String,
+    pub trusting_period: u64,
+    pub trust_threshold: (u64, u64),
+    pub header: Vec<u8>,
+    pub next_validators: Vec<u8>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct UpdateForeignClient {
+    pub signer: String,
+    pub chain_id: String,
+    pub header: Vec<u8>,
+    pub commit: Vec<u8>,
+    pub validators: Vec<u8>,
+    pub next_validators: Vec<u8>,
+}
+
+fn unexpected_field(field: u64, wire_type: u64, msg_name: &str) -> EnclaveError {
+    warn!(
+        "unexpected field {} of wire type {} in {}",
+        field, wire_type, msg_name
+    );
+    EnclaveError::FailedToDeserialize
+}
+
+impl CreateForeignClient {
+    pub fn parse(msg: &[u8]) -> Result<Self, EnclaveError> {
+        let mut create = Self::default();
+
+        for (field, wire_type, value) in fields(msg)? {
+            match (field, wire_type) {
+                (1, WIRE_TYPE_LEN) => create.authority = utf8_string(value)?,
+                (2, WIRE_TYPE_LEN) => create.chain_id = utf8_string(value)?,
+                (3, WIRE_TYPE_VARINT) => create.trusting_period = varint_value(value)?,
+                (4, WIRE_TYPE_VARINT) => create.trust_threshold.0 = varint_value(value)?,
+                (5, WIRE_TYPE_VARINT) => create.trust_threshold.1 = varint_value(value)?,
+                (6, WIRE_TYPE_LEN) => create.header = value.to_vec(),
+                (7, WIRE_TYPE_LEN) => create.next_validators = value.to_vec(),
+                (field, wire_type) => {
+                    return Err(unexpected_field(
+                        field,
+                        wire_type,
+                        "foreign client creation",
+                    ))
+                }
+            }
+        }
+
+        Ok(create)
+    }
+}
+
+impl UpdateForeignClient {
+    pub fn parse(msg: &[u8]) -> Result<Self, EnclaveError> {
+        let mut update = Self::default();
+
+        for (field, wire_type, value) in fields(msg)? {
+            match (field, wire_type) {
+                (1, WIRE_TYPE_LEN) => update.signer = utf8_string(value)?,
+                (2, WIRE_TYPE_LEN) => update.chain_id = utf8_string(value)?,
+                (3, WIRE_TYPE_LEN) => update.header = value.to_vec(),
+                (4, WIRE_TYPE_LEN) => update.commit = value.to_vec(),
+                (5, WIRE_TYPE_LEN) => update.validators = value.to_vec(),
+                (6, WIRE_TYPE_LEN) => update.next_validators = value.to_vec(),
+                (field, wire_type) => {
+                    return Err(unexpected_field(field, wire_type, "foreign client update"))
+                }
+            }
+        }
+
+        Ok(update)
+    }
+}
+
+/// The key of the client of `chain_id` in x/compute's store
+#[cfg(any(feature = "light-client-validation", feature = "test"))]
+pub fn client_key(chain_id: &str) -> Vec<u8> {
+    let mut key = vec![FOREIGN_CLIENT_PREFIX];
+    key.extend_from_slice(chain_id.as_bytes());
+    key
+}
+
+/// The key of the last `MsgCreateForeignClient` of `chain_id` in x/compute's store
+#[cfg(any(feature = "light-client-validation", feature = "test"))]
+pub fn creation_key(chain_id: &str) -> Vec<u8> {
+    let mut key = vec![FOREIGN_CLIENT_CREATION_PREFIX];
+    key.extend_from_slice(chain_id.as_bytes());
+    key
+}
+
+/// Create the light client in a `MsgCreateForeignClient`, which `msg_proof` proves x/compute
+/// stored once governance passed it, and return the client for x/compute to store
+#[cfg(feature = "light-client-validation")]
+pub fn create_foreign_client(msg: &[u8], msg_proof: &[u8]) -> Result<Vec<u8>, EnclaveError> {
+    let create = CreateForeignClient::parse(msg)?;
+    if create.authority != authority_address() {
+        warn!(
+            "the foreign client of {} wasn't created by governance",
+            create.chain_id
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+    verify_compute_state(&creation_key(&create.chain_id), msg, msg_proof)?;
+
+    let client = block_verifier::foreign_clients::create_tendermint_client(
+        &create.chain_id,
+        create.trusting_period,
+        create.trust_threshold,
+        &create.header,
+        &create.next_validators,
+    )
+    .map_err(|_| EnclaveError::ValidationFailure)?;
+
+    Ok(block_verifier::foreign_clients::serialize_client(&client))
+}
+
+/// Update `client` with the header in a `MsgUpdateForeignClient`, and return the updated client
+/// for x/compute to store. `client_proof` must prove that `client` is the client of the chain in
+/// x/compute's state.
+#[cfg(feature = "light-client-validation")]
+pub fn update_foreign_client(
+    client: &[u8],
+    client_proof: &[u8],
+    msg: &[u8],
+) -> Result<Vec<u8>, EnclaveError> {
+    let update = UpdateForeignClient::parse(msg)?;
+    verify_compute_state(&client_key(&update.chain_id), client, client_proof)?;
+
+    let client = block_verifier::foreign_clients::deserialize_client(client)
+        .ok_or(EnclaveError::FailedToDeserialize)?;
+    let client = block_verifier::foreign_clients::update_tendermint_client(
+        &client,
+        &update.chain_id,
+        &update.header,
+        &update.commit,
+        &update.validators,
+        &update.next_validators,
+    )
+    .map_err(|_| EnclaveError::ValidationFailure)?;
+
+    Ok(block_verifier::foreign_clients::serialize_client(&client))
+}
+
+/// Answer contracts from the client of `chain_id` in this block, once `proof` proves it's the
+/// client in x/compute's state
+#[cfg(feature = "light-client-validation")]
+pub fn submit_foreign_client(
+    chain_id: &[u8],
+    client: &[u8],
+    proof: &[u8],
+) -> Result<(), EnclaveError> {
+    let chain_id = utf8_string(chain_id)?;
+    verify_compute_state(&client_key(&chain_id), client, proof)?;
+
+    let client = block_verifier::foreign_clients::deserialize_client(client)
+        .ok_or(EnclaveError::FailedToDeserialize)?;
+    block_verifier::foreign_clients::add_client(&chain_id, client)
+        .map_err(|_| EnclaveError::ValidationFailure)
+}
+
+// Headers of other chains can only be verified by the block verifier
+#[cfg(not(feature = "light-client-validation"))]
+pub fn create_foreign_client(msg: &[u8], _msg_proof: &[u8]) -> Result<Vec<u8>, EnclaveError> {
+    let create = CreateForeignClient::parse(msg)?;
+    warn!(
+        "can't create the foreign client of {} without light client validation",
+        create.chain_id
+    );
+    Err(EnclaveError::ValidationFailure)
+}
+
+#[cfg(not(feature = "light-client-validation"))]
+pub fn update_foreign_client(
+    _client: &[u8],
+    _client_proof: &[u8],
+    msg: &[u8],
+) -> Result<Vec<u8>, EnclaveError> {
+    let update = UpdateForeignClient::parse(msg)?;
+    warn!(
+        "can't update the foreign client of {} without light client validation",
+        update.chain_id
+    );
+    Err(EnclaveError::ValidationFailure)
+}
+
+// Without light client validation no client can be created, so there are none to answer from
+#[cfg(not(feature = "light-client-validation"))]
+pub fn submit_foreign_client(
+    _chain_id: &[u8],
+    _client: &[u8],
+    _proof: &[u8],
+) -> Result<(), EnclaveError> {
+    Ok(())
+}
+
+/// The app hash in the trusted header of `chain_id` at `height`
+#[cfg(feature = "light-client-validation")]
+pub fn foreign_app_hash_at(chain_id: &str, height: u64) -> Option<Vec<u8>> {
+    block_verifier::foreign_clients::app_hash_at(chain_id, height)
+}
+
+#[cfg(not(feature = "light-client-validation"))]
+pub fn foreign_app_hash_at(_chain_id: &str, _height: u64) -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    #[cfg(not(feature = "light-client-validation"))]
+    use crate::gov_messages::authority_address;
+
+    fn encode_field(out: &mut Vec<u8>, field: u8, value: &[u8]) {
+        out.push(field << 3 | WIRE_TYPE_LEN as u8);
+        out.push(value.len() as u8);
+        out.extend_from_slice(value);
+    }
+
+    fn encode_varint_field(out: &mut Vec<u8>, field: u8, value: u8) {
+        out.push(field << 3 | WIRE_TYPE_VARINT as u8);
+        out.push(value);
+    }
+
+    pub fn test_foreign_client_create_parse() {
+        let mut msg = vec![];
+        encode_field(&mut msg, 1, authority_address().as_bytes());
+        encode_field(&mut msg, 2, b"cosmoshub-4");
+        encode_varint_field(&mut msg, 3, 100);
+        encode_varint_field(&mut msg, 4, 1);
+        encode_varint_field(&mut msg, 5, 3);
+        encode_field(&mut msg, 6, &[1, 2]);
+        encode_field(&mut msg, 7, &[3]);
+
+        assert_eq!(
+            CreateForeignClient::parse(&msg).unwrap(),
+            CreateForeignClient {
+                authority: authority_address(),
+                chain_id: "cosmoshub-4".to_string(),
+                trusting_period: 100,
+                trust_threshold: (1, 3),
+                header: vec![1, 2],
+                next_validators: vec![3],
+            }
+        );
+
+        // truncated
+        assert!(CreateForeignClient::parse(&msg[..msg.len() - 1]).is_err());
+        // the trusting period with the wrong wire type
+        let mut bad = vec![];
+        encode_field(&mut bad, 3, &[100]);
+        assert!(CreateForeignClient::parse(&bad).is_err());
+    }
+
+    pub fn test_foreign_client_update_parse() {
+        let mut msg = vec![];
+        encode_field(&mut msg, 1, b"secret1relayer");
+        encode_field(&mut msg, 2, b"cosmoshub-4");
+        encode_field(&mut msg, 3, &[1]);
+        encode_field(&mut msg, 4, &[2]);
+        encode_field(&mut msg, 5, &[3]);
+        encode_field(&mut msg, 6, &[4]);
+
+        assert_eq!(
+            UpdateForeignClient::parse(&msg).unwrap(),
+            UpdateForeignClient {
+                signer: "secret1relayer".to_string(),
+                chain_id: "cosmoshub-4".to_string(),
+                header: vec![1],
+                commit: vec![2],
+                validators: vec![3],
+                next_validators: vec![4],
+            }
+        );
+
+        // unknown field
+        let mut bad = msg.clone();
+        encode_field(&mut bad, 7, &[]);
+        assert!(UpdateForeignClient::parse(&bad).is_err());
+    }
+
+    pub fn test_foreign_client_keys() {
+        assert_eq!(client_key("cosmoshub-4"), b"\x12cosmoshub-4".to_vec());
+        assert_eq!(creation_key("cosmoshub-4"), b"\x13cosmoshub-4".to_vec());
+    }
+}
@@ -51,10 +51,79 @@ pub struct WasmCosts {
     pub external_secp256k1_sign: u32,
     /// Cost invoking ed25519_sign from WASM
     pub external_ed25519_sign: u32,
+    /// Cost invoking rsa_pkcs1v15_verify or rsa_pss_verify from WASM with a 2048-bit key
+    pub external_rsa_verify_2048: u32,
+    /// Cost invoking rsa_pkcs1v15_verify or rsa_pss_verify from WASM with a 3072-bit key
+    pub external_rsa_verify_3072: u32,
+    /// Cost invoking rsa_pkcs1v15_verify or rsa_pss_verify from WASM with a 4096-bit key
+    pub external_rsa_verify_4096: u32,
+    /// Cost invoking musig2_key_agg from WASM, per key
+    pub external_musig2_key_agg_per_key: u32,
+    /// Cost invoking secp256k1_schnorr_verify from WASM
+    pub external_secp256k1_schnorr_verify: u32,
     pub external_check_gas_used: u32,
     pub external_minimum_gas_evaporate: u32,
     /// Cost invoking dcap_quote_verify from WASM
     pub external_dcap_quote_verify: u32,
+    /// Cost invoking drand_verify or drand_randomness from WASM
+    pub external_drand_verify: u32,
+    /// Cost invoking oracle_attestation_verify from WASM
+    pub external_oracle_attestation_verify: u32,
+    /// Cost invoking job_seal_input from WASM
+    pub external_job_seal_input_base: u32,
+    /// Cost of job_seal_input per byte of the input
+    pub external_job_seal_input_per_byte: u32,
+    /// Cost invoking decrypt_disclosed_attribute from WASM
+    pub external_decrypt_disclosed_attribute: u32,
+    /// Cost invoking derive_contract_keypair or contract_decrypt from WASM, on top of
+    /// external_symmetric_crypto_per_byte for the ciphertext of contract_decrypt
+    pub external_contract_subkey: u32,
+    /// Cost invoking token_metadata_seal or token_metadata_open from WASM, on top of
+    /// external_symmetric_crypto_per_byte for their inputs. token_metadata_open also costs
+    /// external_secp256k1_verify, for checking a permit.
+    pub external_token_metadata_base: u32,
+    /// Cost invoking foreign_app_hash_verify from WASM
+    pub external_foreign_app_hash_verify: u32,
+    /// Cost invoking block_random_at from WASM
+    pub external_block_random_at: u32,
+    /// Cost invoking query_enclave_features from WASM
+    pub external_query_enclave_features: u32,
+    /// Cost invoking gas_uniform from WASM
+    pub external_gas_uniform: u32,
+    /// Cost invoking canonicalize_json from WASM
+    pub external_canonicalize_json_base: u32,
+    /// Cost invoking canonicalize_json from WASM
+    pub external_canonicalize_json_per_byte: u32,
+    /// Cost invoking fixed_point_mul or fixed_point_div from WASM
+    pub external_fixed_point_arithmetic: u32,
+    /// Cost invoking fixed_point_ln, fixed_point_exp or fixed_point_pow from WASM
+    pub external_fixed_point_transcendental: u32,
+    /// Cost invoking timestamp_add_months, timestamp_truncate or timestamp_weekday from WASM
+    pub external_timestamp_arithmetic: u32,
+    /// Cost invoking uint512_op or int512_op from WASM
+    pub external_int512_op: u32,
+    /// Cost invoking modexp from WASM, per gas the EVM charges for it
+    pub external_modexp_per_evm_gas: u32,
+    /// Cost invoking x509_verify_chain from WASM, per certificate in the chain
+    pub external_x509_verify_chain_per_cert: u32,
+    /// Cost invoking poseidon_hash from WASM, per field multiplication it takes
+    pub external_poseidon_hash_per_multiplication: u32,
+    /// Cost of hashing a node of a SHA-256 accumulator in merkle_append or merkle_verify, or of a
+    /// state commitment when it's updated or verified
+    pub external_merkle_sha256_per_node: u32,
+    /// Cost invoking hkdf_sha256, hmac_sha256, aead_seal or aead_open from WASM
+    pub external_symmetric_crypto_base: u32,
+    /// Cost invoking hkdf_sha256, hmac_sha256, aead_seal or aead_open from WASM, per byte of the
+    /// inputs, and of the output of hkdf_sha256
+    pub external_symmetric_crypto_per_byte: u32,
+    /// Cost invoking compress or decompress from WASM
+    pub external_compression_base: u32,
+    /// Cost invoking compress or decompress from WASM, per byte of the input and of the output
+    pub external_compression_per_byte: u32,
+    /// Cost invoking verify_sgx_quote from WASM
+    pub external_verify_sgx_quote: u32,
+    /// Cost invoking ibc_client_status from WASM
+    pub external_ibc_client_status: u32,
 }
 
 impl Default for WasmCosts {
@@ -82,9 +151,43 @@ impl Default for WasmCosts {
             external_ed25519_batch_verify_each: 70000,
             external_secp256k1_sign: 100000,
             external_ed25519_sign: 75000,
+            external_rsa_verify_2048: 400000,
+            external_rsa_verify_3072: 800000,
+            external_rsa_verify_4096: 1000000,
+            external_musig2_key_agg_per_key: 40000,
+            external_secp256k1_schnorr_verify: 98304,
             external_check_gas_used: 8192,
             external_minimum_gas_evaporate: 8000,
             external_dcap_quote_verify: 100000,
+            external_drand_verify: 400000,
+            external_oracle_attestation_verify: 150000,
+            external_job_seal_input_base: 20000,
+            external_job_seal_input_per_byte: 30,
+            external_decrypt_disclosed_attribute: 20000,
+            external_contract_subkey: 20000,
+            external_token_metadata_base: 20000,
+            external_foreign_app_hash_verify: 20000,
+            external_block_random_at: 10000,
+            external_query_enclave_features: 8192,
+            external_gas_uniform: 8192,
+            external_canonicalize_json_base: 8192,
+            external_canonicalize_json_per_byte: 20,
+            external_fixed_point_arithmetic: 8192,
+            external_fixed_point_transcendental: 100000,
+            external_timestamp_arithmetic: 8192,
+            external_int512_op: 8192,
+            // The EVM charges 3000 gas for ecrecover, for which we charge as much as for
+            // secp256k1_verify
+            external_modexp_per_evm_gas: 32,
+            external_x509_verify_chain_per_cert: 1000000,
+            external_poseidon_hash_per_multiplication: 100,
+            external_merkle_sha256_per_node: 1000,
+            external_symmetric_crypto_base: 8192,
+            external_symmetric_crypto_per_byte: 20,
+            external_compression_base: 8192,
+            external_compression_per_byte: 10,
+            external_verify_sgx_quote: 100000,
+            external_ibc_client_status: 30000,
         }
     }
 }
@@ -0,0 +1,36 @@
+//! Helpers for messages governance passes to the enclave.
+//!
+//! These messages are executed by the gov module and handed to the enclave as raw protobuf, with
+//! a proof that x/compute stored them, so the enclave doesn't have to trust the node that
+//! governance passed them.
+
+use bech32::ToBase32;
+
+use cw_types_v010::consts::BECH32_PREFIX_ACC_ADDR;
+use enclave_crypto::sha_256;
+use enclave_ffi_types::EnclaveError;
+
+const AUTHORITY_MODULE: &[u8] = b"gov";
+
+pub fn utf8_string(value: &[u8]) -> Result<String, EnclaveError> {
+    String::from_utf8(value.to_vec()).map_err(|_| EnclaveError::FailedToDeserialize)
+}
+
+/// The address of the gov module account, `sha256("gov")[..20]`
+pub fn authority_address() -> String {
+    let address = &sha_256(AUTHORITY_MODULE)[..20];
+    bech32::encode(BECH32_PREFIX_ACC_ADDR, address.to_base32()).unwrap_or_default()
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_gov_authority_address() {
+        // sha256("gov")[..20], the gov module account on every cosmos chain
+        assert_eq!(
+            authority_address(),
+            "secret10d07y265gmmuvt4z0w9aw880jnsr700jc88vt0"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! The status of this chain's IBC clients, verified against the chain's own state, so contracts
+//! that bridge to another chain can e.g. pause when the chain's client is frozen, without
+//! trusting the host to tell them.
+//!
+//! A contract only names the client. The enclave asks the node for the client's state, as the IBC
+//! module stores it at `clients/<client id>/clientState`, as of the last block, with an ICS-23
+//! proof of it, and checks the proof against the app hash of the block it's executing, which the
+//! light client verified. Every node proves the same state against the same app hash, so every
+//! node returns the same status, also after a state sync.
+//!
+//! Only Tendermint client states are accepted.
+
+use log::*;
+use serde::Serialize;
+
+use enclave_ffi_types::Ctx;
+use enclave_utils::proto_fields::{fields, varint_value, WIRE_TYPE_LEN, WIRE_TYPE_VARINT};
+
+#[cfg(feature = "light-client-validation")]
+use crate::query_chain::query_ibc_client_state;
+
+const TENDERMINT_CLIENT_STATE: &str = "/ibc.lightclients.tendermint.v1.ClientState";
+#[cfg(feature = "light-client-validation")]
+const IBC_STORE_KEY: &[u8] = b"ibc";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IbcClientStatusError {
+    /// The client id or the client state can't be decoded
+    Malformed = 1,
+    /// The client isn't a Tendermint client
+    UnsupportedClient = 2,
+    /// The node's proof doesn't prove the client state
+    InvalidProof = 3,
+    /// There's no such client, or the node couldn't prove its state
+    Unavailable = 4,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IbcHeight {
+    pub revision_number: u64,
+    pub revision_height: u64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct IbcClientStatus {
+    pub client_id: String,
+    /// The chain the client follows
+    pub chain_id: String,
+    pub latest_height: IbcHeight,
+    pub frozen: bool,
+}
+
+fn malformed<T>(_: T) -> IbcClientStatusError {
+    IbcClientStatusError::Malformed
+}
+
+/// The key the IBC module stores the state of `client_id` at
+pub fn client_state_key(client_id: &str) -> Vec<u8> {
+    format!("clients/{}/clientState", client_id).into_bytes()
+}
+
+fn parse_height(msg: &[u8]) -> Result<IbcHeight, IbcClientStatusError> {
+    let mut height = IbcHeight::default();
+    for (field, wire_type, value) in fields(msg).map_err(malformed)? {
+        match (field, wire_type) {
+            (1, WIRE_TYPE_VARINT) => {
+                height.revision_number = varint_value(value).map_err(malformed)?
+            }
+            (2, WIRE_TYPE_VARINT) => {
+                height.revision_height = varint_value(value).map_err(malformed)?
+            }
+            _ => return Err(IbcClientStatusError::Malformed),
+        }
+    }
+    Ok(height)
+}
+
+/// The chain id, the latest height and the frozen height of a Tendermint client state, as an
+/// `Any`
+fn parse_client_state(
+    client_state: &[u8],
+) -> Result<(String, IbcHeight, IbcHeight), IbcClientStatusError> {
+    let mut type_url = String::new();
+    let mut value: &[u8] = &[];
+    for (field, wire_type, field_value) in fields(client_state).map_err(malformed)? {
+        match (field, wire_type) {
+            (1, WIRE_TYPE_LEN) => {
+                type_url = String::from_utf8(field_value.to_vec()).map_err(malformed)?
+            }
+            (2, WIRE_TYPE_LEN) => value = field_value,
+            _ => return Err(IbcClientStatusError::Malformed),
+        }
+    }
+    if type_url != TENDERMINT_CLIENT_STATE {
+        debug!("ibc client status of a client of type {}", type_url);
+        return Err(IbcClientStatusError::UnsupportedClient);
+    }
+
+    let mut chain_id = String::new();
+    let mut latest_height = IbcHeight::default();
+    let mut frozen_height = IbcHeight::default();
+    for (field, wire_type, field_value) in fields(value).map_err(malformed)? {
+        match (field, wire_type) {
+            (1, WIRE_TYPE_LEN) => {
+                chain_id = String::from_utf8(field_value.to_vec()).map_err(malformed)?
+            }
+            (6, WIRE_TYPE_LEN) => frozen_height = parse_height(field_value)?,
+            (7, WIRE_TYPE_LEN) => latest_height = parse_height(field_value)?,
+            _ => {}
+        }
+    }
+
+    Ok((chain_id, latest_height, frozen_height))
+}
+
+/// The status of the IBC client `client_id`, from the state the node returned for it and its
+/// proof, if `verify_state` accepts the proof of the state at the client's key
+pub fn client_status(
+    client_id: &str,
+    proven_state: Option<(&[u8], &[u8])>,
+    verify_state: impl FnOnce(&[u8], &[u8], &[u8]) -> bool,
+) -> Result<IbcClientStatus, IbcClientStatusError> {
+    let (client_state, proof) = proven_state.ok_or(IbcClientStatusError::Unavailable)?;
+    if !verify_state(&client_state_key(client_id), client_state, proof) {
+        debug!(
+            "ibc client status proof of {} doesn't match the app hash of the block",
+            client_id
+        );
+        return Err(IbcClientStatusError::InvalidProof);
+    }
+
+    let (chain_id, latest_height, frozen_height) = parse_client_state(client_state)?;
+    Ok(IbcClientStatus {
+        client_id: client_id.to_string(),
+        chain_id,
+        latest_height,
+        frozen: frozen_height != IbcHeight::default(),
+    })
+}
+
+/// Whether `proof` proves `value` at `key` of the IBC store, in the state the current block was
+/// executed on
+#[cfg(feature = "light-client-validation")]
+fn verify_ibc_state(key: &[u8], value: &[u8], proof: &[u8]) -> bool {
+    block_verifier::state_proof::verify_current_state(IBC_STORE_KEY, key, value, proof).is_ok()
+}
+
+/// The status of the IBC client `client_id` in the state the current block was executed on, with
+/// the state and proof the enclave queried the node for
+#[cfg(feature = "light-client-validation")]
+pub fn verified_client_status(
+    context: &Ctx,
+    client_id: &str,
+    query_depth: u32,
+    gas_used: &mut u64,
+    gas_limit: u64,
+) -> Result<IbcClientStatus, IbcClientStatusError> {
+    let state = query_ibc_client_state(context, client_id, query_depth, gas_used, gas_limit)
+        .ok()
+        .flatten();
+    client_status(
+        client_id,
+        state
+            .as_ref()
+            .map(|state| (state.value.as_slice(), state.proof.as_slice())),
+        verify_ibc_state,
+    )
+}
+
+// Without the light client there's no app hash to check the proof against
+#[cfg(not(feature = "light-client-validation"))]
+pub fn verified_client_status(
+    _context: &Ctx,
+    _client_id: &str,
+    _query_depth: u32,
+    _gas_used: &mut u64,
+    _gas_limit: u64,
+) -> Result<IbcClientStatus, IbcClientStatusError> {
+    Err(IbcClientStatusError::Unavailable)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use enclave_utils::proto_fields::varint;
+
+    fn len_field(number: u64, value: &[u8]) -> Vec<u8> {
+        let mut encoded = varint(number << 3 | WIRE_TYPE_LEN);
+        encoded.extend(varint(value.len() as u64));
+        encoded.extend_from_slice(value);
+        encoded
+    }
+
+    fn varint_field(number: u64, value: u64) -> Vec<u8> {
+        let mut encoded = varint(number << 3 | WIRE_TYPE_VARINT);
+        encoded.extend(varint(value));
+        encoded
+    }
+
+    fn height(revision_number: u64, revision_height: u64) -> Vec<u8> {
+        [
+            varint_field(1, revision_number),
+            varint_field(2, revision_height),
+        ]
+        .concat()
+    }
+
+    fn client_state(type_url: &str, frozen_height: Option<Vec<u8>>) -> Vec<u8> {
+        let mut state = len_field(1, b"cosmoshub-4");
+        // the trust level, which isn't read
+        state.extend(len_field(
+            2,
+            &[varint_field(1, 1), varint_field(2, 3)].concat(),
+        ));
+        if let Some(frozen_height) = frozen_height {
+            state.extend(len_field(6, &frozen_height));
+        }
+        state.extend(len_field(7, &height(4, 100)));
+
+        [len_field(1, type_url.as_bytes()), len_field(2, &state)].concat()
+    }
+
+    pub fn test_ibc_client_status() {
+        let state = client_state(TENDERMINT_CLIENT_STATE, None);
+        let proof: &[u8] = b"proof";
+
+        let status = client_status(
+            "07-tendermint-0",
+            Some((&state[..], proof)),
+            |key, value, proof| {
+                assert_eq!(key, b"clients/07-tendermint-0/clientState");
+                value == state.as_slice() && proof == b"proof"
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            status,
+            IbcClientStatus {
+                client_id: "07-tendermint-0".to_string(),
+                chain_id: "cosmoshub-4".to_string(),
+                latest_height: IbcHeight {
+                    revision_number: 4,
+                    revision_height: 100
+                },
+                frozen: false,
+            }
+        );
+
+        // the proof doesn't match the app hash
+        assert_eq!(
+            client_status("07-tendermint-0", Some((&state[..], proof)), |_, _, _| {
+                false
+            }),
+            Err(IbcClientStatusError::InvalidProof)
+        );
+        // no such client, or no proof
+        assert_eq!(
+            client_status("07-tendermint-0", None, |_, _, _| true),
+            Err(IbcClientStatusError::Unavailable)
+        );
+        // an unproven state isn't decoded
+        assert_eq!(
+            client_status(
+                "07-tendermint-0",
+                Some((&[0x0a, 0xff][..], proof)),
+                |_, _, _| false
+            ),
+            Err(IbcClientStatusError::InvalidProof)
+        );
+
+        let frozen = client_state(TENDERMINT_CLIENT_STATE, Some(height(4, 90)));
+        let status = client_status("07-tendermint-0", Some((&frozen[..], proof)), |_, _, _| {
+            true
+        })
+        .unwrap();
+        assert!(status.frozen);
+    }
+
+    pub fn test_ibc_client_status_client_state() {
+        let (chain_id, latest_height, frozen_height) =
+            parse_client_state(&client_state(TENDERMINT_CLIENT_STATE, Some(height(0, 7)))).unwrap();
+        assert_eq!(chain_id, "cosmoshub-4");
+        assert_eq!(latest_height.revision_height, 100);
+        assert_eq!(frozen_height.revision_height, 7);
+
+        assert_eq!(
+            parse_client_state(&client_state("/ibc.lightclients.wasm.v1.ClientState", None)),
+            Err(IbcClientStatusError::UnsupportedClient)
+        );
+        assert_eq!(
+            parse_client_state(&[0x0a, 0xff]),
+            Err(IbcClientStatusError::Malformed)
+        );
+    }
+}
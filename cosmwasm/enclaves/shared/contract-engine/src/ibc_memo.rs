@@ -0,0 +1,135 @@
+//! Encrypted memos of ibc-hooks incoming transfers.
+//!
+//! ibc-hooks calls a contract with the `msg` in a transfer's memo, which is public on both
+//! chains. To keep the msg confidential, the sender on the other chain can instead set it to an
+//! envelope addressed to the enclave:
+//!
+//! ```json
+//! {"wasm":{"contract":"secret1...","msg":{"encrypted_memo":"<base64>"}}}
+//! ```
+//!
+//! where `encrypted_memo` is `nonce || user_public_key || ciphertext`, like the input of a tx.
+//! The ciphertext is the contract's code hash followed by the execute msg, encrypted with the
+//! IBC memo key (see `calc_ibc_memo_key`). The enclave decrypts it and executes the contract
+//! with the embedded msg, and encrypts the output to `user_public_key` like a tx's.
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use enclave_crypto::{AESKey, Ed25519PublicKey, Kdf};
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::KEY_MANAGER;
+
+use crate::ibc_message::parse_plaintext_ibc_validated_message;
+use crate::padding::decrypt_message;
+use crate::types::{IoNonce, ParsedMessage, SecretMessage};
+
+const IBC_MEMO_KEY_DOMAIN: &[u8] = b"ibc_memo";
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedMemo {
+    pub encrypted_memo: String,
+}
+
+/// The key of an encrypted memo. It's exchanged with the chain's io key like the key of a tx
+/// input, but is derived under its own domain, so a memo can't be replayed as a tx input, or a
+/// tx input as a memo.
+pub fn calc_ibc_memo_key(nonce: &IoNonce, user_public_key: &Ed25519PublicKey) -> AESKey {
+    let enclave_io_key = KEY_MANAGER.get_consensus_io_exchange_keypair().unwrap();
+
+    let memo_ikm = enclave_io_key.current.diffie_hellman(user_public_key);
+
+    AESKey::new_from_slice(&memo_ikm).derive_key_from_this(&[IBC_MEMO_KEY_DOMAIN, nonce].concat())
+}
+
+/// The envelope in `message`, if it is one
+pub fn parse_encrypted_memo(message: &[u8]) -> Result<Option<SecretMessage>, EnclaveError> {
+    let envelope: EncryptedMemo = match serde_json::from_slice(message) {
+        Ok(envelope) => envelope,
+        Err(_) => return Ok(None),
+    };
+
+    let envelope = base64::decode(&envelope.encrypted_memo).map_err(|err| {
+        warn!("encrypted memo is not valid base64: {:?}", err);
+        EnclaveError::FailedToDeserialize
+    })?;
+
+    SecretMessage::from_slice(&envelope).map(Some)
+}
+
+/// Parse the msg of an ibc-hooks incoming transfer, which is either plaintext or an encrypted
+/// memo. The msg is checked against the memo in the packet as it was sent, before decryption.
+pub fn parse_ibc_hooks_incoming_transfer_message(
+    message: &[u8],
+) -> Result<ParsedMessage, EnclaveError> {
+    let envelope = match parse_encrypted_memo(message)? {
+        Some(envelope) => envelope,
+        None => return parse_plaintext_ibc_validated_message(message),
+    };
+
+    trace!(
+        "ibc-hooks memo before decryption: {:?}",
+        base64::encode(message)
+    );
+
+    let (decrypted_msg, _) = decrypt_message(
+        &calc_ibc_memo_key(&envelope.nonce, &envelope.user_public_key),
+        &envelope.msg,
+    )
+    .ok_or_else(|| {
+        warn!("failed to decrypt encrypted memo");
+        EnclaveError::DecryptionError
+    })?;
+
+    Ok(ParsedMessage {
+        should_verify_sig_info: false,
+        should_verify_input: true,
+        was_msg_encrypted: true,
+        should_encrypt_output: true,
+        secret_msg: SecretMessage {
+            nonce: envelope.nonce,
+            user_public_key: envelope.user_public_key,
+            msg: message.into(),
+        },
+        decrypted_msg,
+        data_for_validation: None,
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_ibc_memo_envelope_parse() {
+        let mut envelope = vec![1u8; 32];
+        envelope.extend_from_slice(&[2u8; 32]);
+        envelope.extend_from_slice(&[3u8; 20]);
+        let memo = format!(r#"{{"encrypted_memo":"{}"}}"#, base64::encode(&envelope));
+
+        let secret_msg = parse_encrypted_memo(memo.as_bytes()).unwrap().unwrap();
+        assert_eq!(secret_msg.nonce, [1u8; 32]);
+        assert_eq!(secret_msg.user_public_key, [2u8; 32]);
+        assert_eq!(secret_msg.msg, vec![3u8; 20]);
+
+        // msgs with other fields are regular msgs
+        let plaintext = br#"{"encrypted_memo":"AAAA","amount":"1"}"#;
+        assert_eq!(parse_encrypted_memo(plaintext).unwrap(), None);
+        assert_eq!(parse_encrypted_memo(br#"{"transfer":{}}"#).unwrap(), None);
+
+        // an envelope that is too short to hold a ciphertext
+        let short = format!(r#"{{"encrypted_memo":"{}"}}"#, base64::encode([1u8; 64]));
+        assert!(parse_encrypted_memo(short.as_bytes()).is_err());
+        assert!(parse_encrypted_memo(br#"{"encrypted_memo":"not base64!"}"#).is_err());
+    }
+
+    pub fn test_ibc_memo_plaintext_passthrough() {
+        let msg = br#"{"transfer":{"recipient":"secret1abc"}}"#;
+
+        let parsed = parse_ibc_hooks_incoming_transfer_message(msg).unwrap();
+        assert!(!parsed.was_msg_encrypted);
+        assert!(!parsed.should_encrypt_output);
+        assert!(parsed.should_verify_input);
+        assert_eq!(parsed.decrypted_msg, msg.to_vec());
+    }
+}
@@ -0,0 +1,263 @@
+//! Sender contexts of outgoing transfers.
+//!
+//! The ack or timeout of a transfer is delivered to the contract that sent it in a new tx, by a
+//! relayer, so its output can't be encrypted to the user whose encrypted tx (or encrypted IBC
+//! packet) made the contract send the transfer. With packet forwarding the ack may only come
+//! back after several hops, long after that tx.
+//!
+//! When an encrypted execution sends a transfer with an ibc-hooks callback to itself, the
+//! enclave seals the nonce and public key of the execution into the transfer's memo:
+//!
+//! ```json
+//! {"ibc_callback":"secret1...","secret_sender_context":"<base64>"}
+//! ```
+//!
+//! The memo is part of the packet, which the ack or timeout carries back. There the enclave
+//! unseals the context and encrypts the callback's output to the same public key, with a nonce
+//! scoped to the packet's channel and sequence (see `scoped_nonce`), so every transfer of a tx
+//! has its own key.
+
+use log::*;
+use serde_json::Value;
+
+use cw_types_v010::types::{CanonicalAddr, HumanAddr};
+use cw_types_v1::results::{CosmosMsg, IbcMsg, SubMsg};
+use enclave_cosmos_types::types::{
+    DirectSdkMsg, FungibleTokenPacketData, HandleType, IBCLifecycleComplete,
+    IBCLifecycleCompleteOptions, SigInfo,
+};
+use enclave_crypto::{sha_256, AESKey, Ed25519PublicKey, Kdf, SIVEncryptable};
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::KEY_MANAGER;
+
+use crate::contract_validation::get_verified_sdk_messages;
+use crate::types::{IoNonce, SecretMessage};
+
+const SENDER_CONTEXT_KEY_DOMAIN: &[u8] = b"ibc_sender_context";
+const SCOPED_NONCE_DOMAIN: &[u8] = b"ibc_sender_context_nonce";
+const SENDER_CONTEXT_MEMO_FIELD: &str = "secret_sender_context";
+const IBC_CALLBACK_MEMO_FIELD: &str = "ibc_callback";
+
+// Contexts are only ever unsealed by the enclave, so they're sealed with a key of its own
+fn sender_context_key() -> AESKey {
+    KEY_MANAGER
+        .get_consensus_callback_secret()
+        .unwrap()
+        .current
+        .derive_key_from_this(SENDER_CONTEXT_KEY_DOMAIN)
+}
+
+/// Seal the nonce and public key of `secret_msg`, so that only the acks and timeouts delivered to
+/// `contract_addr` can unseal them
+pub fn seal_sender_context(
+    secret_msg: &SecretMessage,
+    contract_addr: &CanonicalAddr,
+) -> Result<String, EnclaveError> {
+    let context = [secret_msg.nonce, secret_msg.user_public_key].concat();
+
+    let sealed = sender_context_key()
+        .encrypt_siv(&context, Some(&[contract_addr.as_slice()]))
+        .map_err(|err| {
+            warn!("failed to seal an ibc sender context: {:?}", err);
+            EnclaveError::EncryptionError
+        })?;
+
+    Ok(base64::encode(sealed))
+}
+
+pub fn unseal_sender_context(
+    sealed: &str,
+    contract_addr: &CanonicalAddr,
+) -> Option<(IoNonce, Ed25519PublicKey)> {
+    let sealed = base64::decode(sealed).ok()?;
+    let context = sender_context_key()
+        .decrypt_siv(&sealed, Some(&[contract_addr.as_slice()]))
+        .ok()?;
+
+    if context.len() != 64 {
+        return None;
+    }
+
+    let mut nonce = [0u8; 32];
+    let mut user_public_key = [0u8; 32];
+    nonce.copy_from_slice(&context[..32]);
+    user_public_key.copy_from_slice(&context[32..]);
+
+    Some((nonce, user_public_key))
+}
+
+/// The nonce the output of an ack or timeout is encrypted with. The sender knows the nonce of
+/// their tx, and finds the channel and sequence of the transfer in its `send_packet` event.
+pub fn scoped_nonce(nonce: &IoNonce, channel: &str, sequence: u64) -> IoNonce {
+    let mut data = SCOPED_NONCE_DOMAIN.to_vec();
+    data.extend_from_slice(nonce);
+    data.extend_from_slice(channel.as_bytes());
+    data.extend_from_slice(&sequence.to_be_bytes());
+
+    sha_256(&data)
+}
+
+/// Seal the sender context into `sub_msg` if it's a transfer whose ack and timeout will be
+/// delivered back to `contract_addr` by ibc-hooks. A context the contract put in the memo
+/// itself is replaced.
+pub fn attach_sender_context<T: Clone + std::fmt::Debug + PartialEq>(
+    sub_msg: &mut SubMsg<T>,
+    secret_msg: &SecretMessage,
+    contract_addr: &CanonicalAddr,
+) -> Result<(), EnclaveError> {
+    let memo = match &mut sub_msg.msg {
+        CosmosMsg::Ibc(IbcMsg::Transfer {
+            memo: Some(memo), ..
+        }) => memo,
+        _ => return Ok(()),
+    };
+
+    let mut memo_json = match serde_json::from_str::<Value>(memo) {
+        Ok(Value::Object(memo_json)) => memo_json,
+        _ => return Ok(()),
+    };
+
+    let contract_human = HumanAddr::from_canonical(contract_addr).map_err(|err| {
+        warn!("failed to convert the contract address to human: {:?}", err);
+        EnclaveError::FailedToSerialize
+    })?;
+    if memo_json.get(IBC_CALLBACK_MEMO_FIELD) != Some(&Value::String(contract_human.0)) {
+        return Ok(());
+    }
+
+    memo_json.insert(
+        SENDER_CONTEXT_MEMO_FIELD.to_string(),
+        Value::String(seal_sender_context(secret_msg, contract_addr)?),
+    );
+    *memo = Value::Object(memo_json).to_string();
+
+    Ok(())
+}
+
+/// The sender context of an ibc-hooks ack or timeout, with its nonce scoped to the packet.
+/// `msg` must already have been verified against the packet in the tx.
+pub fn recover_sender_context(
+    sig_info: &SigInfo,
+    handle_type: &HandleType,
+    msg: &[u8],
+    contract_addr: &CanonicalAddr,
+) -> Result<Option<SecretMessage>, EnclaveError> {
+    if !matches!(
+        handle_type,
+        HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_ACK
+            | HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_TIMEOUT
+    ) {
+        return Ok(None);
+    }
+
+    let (channel, sequence) = match serde_json::from_slice::<IBCLifecycleComplete>(msg) {
+        Ok(IBCLifecycleComplete::IBCLifecycleComplete(IBCLifecycleCompleteOptions::IBCAck {
+            channel,
+            sequence,
+            ..
+        }))
+        | Ok(IBCLifecycleComplete::IBCLifecycleComplete(
+            IBCLifecycleCompleteOptions::IBCTimeout { channel, sequence },
+        )) => (channel, sequence),
+        _ => return Ok(None),
+    };
+
+    let sdk_messages = get_verified_sdk_messages(sig_info)?;
+    let packet = sdk_messages.iter().find_map(|m| match m {
+        DirectSdkMsg::MsgAcknowledgement { packet, .. }
+        | DirectSdkMsg::MsgTimeout { packet, .. }
+            if packet.source_channel == channel && packet.sequence == sequence =>
+        {
+            Some(packet)
+        }
+        _ => None,
+    });
+
+    let sealed = packet
+        .and_then(|packet| serde_json::from_slice::<FungibleTokenPacketData>(&packet.data).ok())
+        .and_then(|packet_data| packet_data.memo)
+        .and_then(|memo| serde_json::from_str::<Value>(&memo).ok())
+        .and_then(|memo| {
+            memo.get(SENDER_CONTEXT_MEMO_FIELD)
+                .and_then(|sealed| sealed.as_str().map(String::from))
+        });
+    let sealed = match sealed {
+        Some(sealed) => sealed,
+        None => return Ok(None),
+    };
+
+    match unseal_sender_context(&sealed, contract_addr) {
+        Some((nonce, user_public_key)) => Ok(Some(SecretMessage {
+            nonce: scoped_nonce(&nonce, &channel, sequence),
+            user_public_key,
+            msg: msg.to_vec(),
+        })),
+        None => {
+            // e.g. sealed before the consensus seed was rotated
+            warn!(
+                "failed to unseal the sender context of packet {} on {}, output stays plaintext",
+                sequence, channel
+            );
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use cw_types_v1::results::{BankMsg, ReplyOn};
+
+    fn sub_msg(msg: CosmosMsg) -> SubMsg {
+        SubMsg {
+            id: 0,
+            msg,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+            was_msg_encrypted: false,
+        }
+    }
+
+    pub fn test_ibc_sender_context_scoped_nonce() {
+        let nonce = [1u8; 32];
+
+        let first = scoped_nonce(&nonce, "channel-0", 1);
+        assert_ne!(first, nonce);
+        assert_eq!(first, scoped_nonce(&nonce, "channel-0", 1));
+        assert_ne!(first, scoped_nonce(&nonce, "channel-0", 2));
+        assert_ne!(first, scoped_nonce(&nonce, "channel-1", 1));
+        assert_ne!(first, scoped_nonce(&[2u8; 32], "channel-0", 1));
+    }
+
+    pub fn test_ibc_sender_context_ignores_other_msgs() {
+        let contract_addr = CanonicalAddr::from_vec(vec![7u8; 20]);
+        let secret_msg = SecretMessage {
+            nonce: [1u8; 32],
+            user_public_key: [2u8; 32],
+            msg: vec![],
+        };
+
+        // a callback to another contract
+        let memo = r#"{"ibc_callback":"secret1other"}"#.to_string();
+        let mut transfer = sub_msg(CosmosMsg::Ibc(IbcMsg::Transfer {
+            channel_id: "channel-0".to_string(),
+            to_address: "cosmos1abc".to_string(),
+            amount: cw_types_v1::coins::Coin::new(1, "uscrt"),
+            timeout: Default::default(),
+            memo: Some(memo.clone()),
+        }));
+        attach_sender_context(&mut transfer, &secret_msg, &contract_addr).unwrap();
+        match &transfer.msg {
+            CosmosMsg::Ibc(IbcMsg::Transfer { memo: attached, .. }) => {
+                assert_eq!(attached, &Some(memo))
+            }
+            _ => panic!("transfer was replaced"),
+        }
+
+        let mut bank = sub_msg(CosmosMsg::Bank(BankMsg::Burn { amount: vec![] }));
+        let before = bank.clone();
+        attach_sender_context(&mut bank, &secret_msg, &contract_addr).unwrap();
+        assert_eq!(bank, before);
+    }
+}
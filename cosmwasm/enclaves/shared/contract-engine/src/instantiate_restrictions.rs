@@ -0,0 +1,116 @@
+//! Governance restrictions on which code hashes can be instantiated.
+//!
+//! During incident response governance may want to stop new instances of a vulnerable contract,
+//! or only allow a vetted set of codes. The restriction is enforced here rather than in the Go
+//! layer, so a node operator can't bypass it by patching their node. It's set with the compute
+//! module params, which the enclave only takes once they're proven against the app hash of the
+//! block it's executing, see `enclave_utils::compute_params`:
+//!
+//! ```text
+//! message Params {
+//!   ...
+//!   bool instantiate_allow_list_enabled = 9;
+//!   repeated bytes instantiate_allowed_code_hashes = 10;  // 32 bytes each
+//!   repeated bytes instantiate_denied_code_hashes = 11;   // 32 bytes each
+//! }
+//! ```
+//!
+//! A code hash in the deny list can't be instantiated. When the allow list is enabled, only code
+//! hashes in it can be. Until the params of the block are proven nothing can be instantiated, so
+//! a node can't lift the restrictions by withholding them. Existing instances are not affected.
+
+use std::collections::HashSet;
+
+use log::*;
+
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::compute_params::{compute_params, ComputeParams};
+
+const CODE_HASH_SIZE: usize = 32;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct InstantiateRestrictions {
+    pub allow_list_enabled: bool,
+    pub allowed_code_hashes: HashSet<[u8; CODE_HASH_SIZE]>,
+    pub denied_code_hashes: HashSet<[u8; CODE_HASH_SIZE]>,
+}
+
+impl InstantiateRestrictions {
+    pub fn from_params(params: &ComputeParams) -> Self {
+        Self {
+            allow_list_enabled: params.instantiate_allow_list_enabled,
+            allowed_code_hashes: params
+                .instantiate_allowed_code_hashes
+                .iter()
+                .cloned()
+                .collect(),
+            denied_code_hashes: params
+                .instantiate_denied_code_hashes
+                .iter()
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn is_allowed(&self, code_hash: &[u8; CODE_HASH_SIZE]) -> bool {
+        if self.denied_code_hashes.contains(code_hash) {
+            return false;
+        }
+
+        !self.allow_list_enabled || self.allowed_code_hashes.contains(code_hash)
+    }
+}
+
+pub fn check_instantiate_allowed(code_hash: &[u8; CODE_HASH_SIZE]) -> Result<(), EnclaveError> {
+    let params = match compute_params() {
+        Some(params) => params,
+        None => {
+            warn!("instantiate restrictions are unknown until the compute params are proven");
+            return Err(EnclaveError::CodeHashNotAllowed);
+        }
+    };
+
+    if !InstantiateRestrictions::from_params(&params).is_allowed(code_hash) {
+        warn!(
+            "instantiating code hash {} is restricted by governance",
+            hex::encode(code_hash)
+        );
+        return Err(EnclaveError::CodeHashNotAllowed);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_instantiate_restrictions_from_params() {
+        let params = ComputeParams {
+            instantiate_allow_list_enabled: true,
+            instantiate_allowed_code_hashes: vec![[1u8; 32]],
+            instantiate_denied_code_hashes: vec![[2u8; 32]],
+            ..Default::default()
+        };
+
+        let restrictions = InstantiateRestrictions::from_params(&params);
+        assert!(restrictions.allow_list_enabled);
+        assert!(restrictions.is_allowed(&[1u8; 32]));
+        assert!(!restrictions.is_allowed(&[2u8; 32]));
+        assert!(!restrictions.is_allowed(&[3u8; 32]));
+
+        // no restrictions in the params allow everything
+        let restrictions = InstantiateRestrictions::from_params(&ComputeParams::default());
+        assert!(restrictions.is_allowed(&[3u8; 32]));
+    }
+
+    pub fn test_instantiate_restrictions_deny_only() {
+        let restrictions = InstantiateRestrictions {
+            denied_code_hashes: [[2u8; 32]].iter().cloned().collect(),
+            ..Default::default()
+        };
+
+        assert!(restrictions.is_allowed(&[1u8; 32]));
+        assert!(!restrictions.is_allowed(&[2u8; 32]));
+    }
+}
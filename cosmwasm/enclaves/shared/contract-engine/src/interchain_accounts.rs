@@ -0,0 +1,205 @@
+//! Contract executions by interchain accounts (ICA) hosted on this chain.
+//!
+//! An interchain account doesn't sign its txs. Its controller chain sends them in an IBC packet
+//! to the `icahost` port, and the host module executes them as the account. The enclave
+//! verifies such an execution against the packet in the relayer's tx, and against the
+//! controller of the account, instead of against a signature.
+//!
+//! The node reads the controller's port and the account's channel from the state of the host
+//! module, which every node has the same, and passes them in the `SigInfo` of the execution.
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use cw_types_v010::encoding::Binary;
+use cw_types_v010::types::{CanonicalAddr, Coin, HumanAddr};
+use enclave_cosmos_types::types::{DirectSdkMsg, InterchainAccountInfo, Packet, SigInfo};
+use enclave_ffi_types::EnclaveError;
+
+#[cfg(feature = "light-client-validation")]
+use crate::contract_validation::check_tx_in_current_block;
+use crate::contract_validation::get_verified_sdk_messages;
+use crate::gov_messages::{read_len_delimited, read_string, read_varint, WIRE_TYPE_LEN};
+use crate::types::SecretMessage;
+
+pub const ICA_HOST_PORT: &str = "icahost";
+const ICA_CONTROLLER_PORT_PREFIX: &str = "icacontroller-";
+const ICA_EXECUTE_TX: &str = "TYPE_EXECUTE_TX";
+
+/// `InterchainAccountPacketData`, as the host receives it
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct IcaPacketData {
+    #[serde(rename = "type")]
+    packet_type: String,
+    data: Binary,
+}
+
+/// The msgs in the `CosmosTx` of an ICA packet that executes a tx
+fn packet_msgs(packet: &Packet) -> Option<Vec<DirectSdkMsg>> {
+    let packet_data: IcaPacketData = serde_json::from_slice(&packet.data).ok()?;
+    if packet_data.packet_type != ICA_EXECUTE_TX {
+        return None;
+    }
+
+    let mut cosmos_tx = packet_data.data.as_slice();
+    let mut msgs = vec![];
+    while !cosmos_tx.is_empty() {
+        let key = read_varint(&mut cosmos_tx).ok()?;
+        if key != (1 << 3 | WIRE_TYPE_LEN) {
+            return None;
+        }
+
+        let mut any = read_len_delimited(&mut cosmos_tx).ok()?;
+        let mut type_url = String::new();
+        let mut value: &[u8] = &[];
+        while !any.is_empty() {
+            let key = read_varint(&mut any).ok()?;
+            match (key >> 3, key & 0x7) {
+                (1, WIRE_TYPE_LEN) => type_url = read_string(&mut any).ok()?,
+                (2, WIRE_TYPE_LEN) => value = read_len_delimited(&mut any).ok()?,
+                _ => return None,
+            }
+        }
+
+        msgs.push(DirectSdkMsg::from_bytes(&type_url, value).ok()?);
+    }
+
+    Some(msgs)
+}
+
+/// Whether `packet` comes from the controller of `account`, over the account's channel
+fn is_from_controller(account: &InterchainAccountInfo, packet: &Packet) -> bool {
+    account
+        .controller_port
+        .starts_with(ICA_CONTROLLER_PORT_PREFIX)
+        && packet.source_port == account.controller_port
+        && packet.destination_channel == account.host_channel
+}
+
+fn is_ica_packet(msg: &DirectSdkMsg) -> Option<&Packet> {
+    match msg {
+        DirectSdkMsg::MsgRecvPacket { packet, .. } if packet.destination_port == ICA_HOST_PORT => {
+            Some(packet)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the tx in `sig_info` delivers packets to interchain accounts
+pub fn is_interchain_account_tx(sig_info: &SigInfo) -> bool {
+    get_verified_sdk_messages(sig_info)
+        .map(|msgs| msgs.iter().any(|msg| is_ica_packet(msg).is_some()))
+        .unwrap_or(false)
+}
+
+/// Verify an execution by the interchain account `sender` against the ICA packet in the tx, and
+/// the controller of the account in `sig_info`
+pub fn verify_interchain_account_input(
+    sig_info: &SigInfo,
+    sender: &CanonicalAddr,
+    contract_address: &HumanAddr,
+    secret_msg: &SecretMessage,
+    sent_funds: &[Coin],
+) -> Result<(), EnclaveError> {
+    let account = match &sig_info.interchain_account {
+        Some(account) => account,
+        None => {
+            warn!("the sender of an ica packet's execution is not an interchain account");
+            return Err(EnclaveError::FailedTxVerification);
+        }
+    };
+
+    let sdk_messages = get_verified_sdk_messages(sig_info)?;
+    let sent_msg = secret_msg.to_vec();
+
+    let packet = sdk_messages
+        .iter()
+        .filter_map(is_ica_packet)
+        .find(|packet| {
+            packet_msgs(packet).map_or(false, |msgs| {
+                msgs.iter().any(|msg| match msg {
+                    DirectSdkMsg::MsgExecuteContract {
+                        sender: msg_sender,
+                        contract,
+                        msg,
+                        sent_funds: msg_funds,
+                    } => {
+                        msg_sender == sender
+                            && contract == contract_address
+                            && msg == &sent_msg
+                            && msg_funds.as_slice() == sent_funds
+                    }
+                    _ => false,
+                })
+            })
+        });
+    let packet = match packet {
+        Some(packet) => packet,
+        None => {
+            warn!("no ica packet in the tx executes the contract as its sender");
+            return Err(EnclaveError::FailedTxVerification);
+        }
+    };
+
+    if !is_from_controller(account, packet) {
+        warn!(
+            "{}/{} is not the controller of interchain account {:?}",
+            packet.source_port, packet.destination_channel, sender
+        );
+        return Err(EnclaveError::FailedTxVerification);
+    }
+
+    #[cfg(feature = "light-client-validation")]
+    if !check_tx_in_current_block(sig_info.tx_bytes.as_slice()) {
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn packet(source_port: &str, destination_channel: &str) -> Packet {
+        Packet {
+            sequence: 1,
+            source_port: source_port.to_string(),
+            source_channel: "channel-7".to_string(),
+            destination_port: ICA_HOST_PORT.to_string(),
+            destination_channel: destination_channel.to_string(),
+            data: vec![],
+        }
+    }
+
+    pub fn test_interchain_account_controller() {
+        let account = InterchainAccountInfo {
+            controller_port: "icacontroller-dao".to_string(),
+            host_channel: "channel-3".to_string(),
+        };
+
+        assert!(is_from_controller(
+            &account,
+            &packet("icacontroller-dao", "channel-3")
+        ));
+        // another controller
+        assert!(!is_from_controller(
+            &account,
+            &packet("icacontroller-other", "channel-3")
+        ));
+        // another account of the same controller port, on another connection
+        assert!(!is_from_controller(
+            &account,
+            &packet("icacontroller-dao", "channel-4")
+        ));
+        // not a controller port
+        let account = InterchainAccountInfo {
+            controller_port: "transfer".to_string(),
+            host_channel: "channel-3".to_string(),
+        };
+        assert!(!is_from_controller(
+            &account,
+            &packet("transfer", "channel-3")
+        ));
+    }
+}
@@ -1,18 +1,24 @@
-use crate::contract_validation::ReplyParams;
+use crate::contract_validation::{ContractKey, ReplyParams};
 use core::fmt;
 
 /// This contains all the user-facing functions. In these functions we will be using
 /// the consensus_io_exchange_keypair and a user-generated key to create a symmetric key
 /// that is unique to the user and the enclave
 ///
+use super::execution_report::ExecutionReport;
+use super::ibc_sender_context::attach_sender_context;
+use super::padding::encrypt_message;
+use super::query_session;
+use super::random::MSG_COUNTER;
 use super::types::{IoNonce, SecretMessage};
 use cw_types_v010::encoding::Binary;
 use cw_types_v010::types::{CanonicalAddr, Coin, LogAttribute};
 use cw_types_v1::results::{Event, Reply, ReplyOn, SubMsg, SubMsgResponse, SubMsgResult};
 
-use enclave_crypto::{AESKey, Ed25519PublicKey, Kdf, SIVEncryptable};
+use enclave_crypto::{sha_256, AESKey, Ed25519PublicKey, Kdf, SIVEncryptable, HASH_SIZE};
 use enclave_ffi_types::EnclaveError;
 use enclave_utils::KEY_MANAGER;
+use enclave_wire::Reader;
 
 use log::*;
 use serde::{Deserialize, Serialize};
@@ -121,9 +127,20 @@ pub struct WasmOutput {
     pub query: Option<QueryOutput>,
     pub internal_reply_enclave_sig: Option<Binary>,
     pub internal_msg_id: Option<Binary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub execution_report: Option<ExecutionReport>,
 }
 
 pub fn calc_encryption_key(nonce: &IoNonce, user_public_key: &Ed25519PublicKey) -> AESKey {
+    if let Some(key) = query_session::cached_key(nonce, user_public_key) {
+        return key;
+    }
+
+    derive_encryption_key(nonce, user_public_key)
+}
+
+/// Always does the key exchange, see `calc_encryption_key`
+pub fn derive_encryption_key(nonce: &IoNonce, user_public_key: &Ed25519PublicKey) -> AESKey {
     let enclave_io_key = KEY_MANAGER.get_consensus_io_exchange_keypair().unwrap();
 
     let tx_encryption_ikm = enclave_io_key.current.diffie_hellman(user_public_key);
@@ -131,11 +148,170 @@ pub fn calc_encryption_key(nonce: &IoNonce, user_public_key: &Ed25519PublicKey)
     AESKey::new_from_slice(&tx_encryption_ikm).derive_key_from_this(nonce)
 }
 
+const DISCLOSURE_KEY_DOMAIN: &[u8] = b"event_disclosure";
+
+/// Key used for attributes a contract emitted to a disclosure group.
+/// It's derived from the genesis state key so that historical events stay decryptable after
+/// a seed rotation, and from the og contract key so that it survives migrations.
+pub fn calc_disclosure_key(
+    og_contract_key: &ContractKey,
+    group: &str,
+) -> Result<AESKey, EnclaveError> {
+    let state_ikm = KEY_MANAGER.get_consensus_state_ikm().map_err(|err| {
+        error!(
+            "failed to get the state key for event disclosure: {:?}",
+            err
+        );
+        EnclaveError::InternalError
+    })?;
+
+    let info: Vec<&[u8]> = vec![
+        DISCLOSURE_KEY_DOMAIN,
+        og_contract_key.as_slice(),
+        group.as_bytes(),
+    ];
+
+    Ok(enclave_crypto::hkdf_sha_256(
+        state_ikm.genesis.get(),
+        info.as_slice(),
+    ))
+}
+
+/// Decrypt an attribute key or value that was emitted to a disclosure group, as it appears in
+/// the tx events (base64)
+pub fn decrypt_disclosed_attribute(
+    og_contract_key: &ContractKey,
+    group: &str,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, EnclaveError> {
+    let ciphertext = base64::decode(ciphertext).map_err(|err| {
+        debug!("disclosed attribute is not valid base64: {:?}", err);
+        EnclaveError::FailedToDeserialize
+    })?;
+
+    open_disclosed(&calc_disclosure_key(og_contract_key, group)?, &ciphertext)
+}
+
+const DISCLOSURE_NONCE_DOMAIN: &[u8] = b"event_disclosure_nonce";
+const DISCLOSURE_NONCE_SIZE: usize = 32;
+
+/// The nonces of the disclosed attributes of an output. The disclosure key of a group is the
+/// same for every tx, so each attribute is encrypted with a nonce of its own as the associated
+/// data, or equal values would encrypt to equal ciphertexts. The nonces are derived from the tx's
+/// nonce and public key, the msg's position in the block and the attribute's position in the
+/// output, so every node derives the same ones.
+struct DisclosureNonces {
+    seed: [u8; HASH_SIZE],
+    next: u32,
+}
+
+impl DisclosureNonces {
+    fn new(secret_msg: &SecretMessage) -> Self {
+        let msg_counter = *MSG_COUNTER.lock().unwrap();
+        Self::from_parts(
+            &secret_msg.nonce,
+            &secret_msg.user_public_key,
+            msg_counter.height,
+            msg_counter.counter,
+        )
+    }
+
+    fn from_parts(
+        nonce: &IoNonce,
+        user_public_key: &Ed25519PublicKey,
+        height: u64,
+        msg_counter: u64,
+    ) -> Self {
+        let mut data = DISCLOSURE_NONCE_DOMAIN.to_vec();
+        data.extend_from_slice(nonce);
+        data.extend_from_slice(user_public_key);
+        data.extend_from_slice(&height.to_be_bytes());
+        data.extend_from_slice(&msg_counter.to_be_bytes());
+
+        DisclosureNonces {
+            seed: sha_256(&data),
+            next: 0,
+        }
+    }
+
+    fn next(&mut self) -> [u8; DISCLOSURE_NONCE_SIZE] {
+        let mut data = self.seed.to_vec();
+        data.extend_from_slice(&self.next.to_be_bytes());
+        self.next += 1;
+
+        sha_256(&data)
+    }
+}
+
+/// `nonce || ciphertext`, with the nonce as the associated data, in base64
+fn seal_disclosed(
+    key: &AESKey,
+    nonce: &[u8; DISCLOSURE_NONCE_SIZE],
+    plaintext: &[u8],
+) -> Result<String, EnclaveError> {
+    let ciphertext = key
+        .encrypt_siv(plaintext, Some(&[nonce.as_slice()]))
+        .map_err(|err| {
+            debug!("failed to encrypt disclosed attribute: {:?}", err);
+            EnclaveError::EncryptionError
+        })?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(b64_encode(&sealed))
+}
+
+fn open_disclosed(key: &AESKey, sealed: &[u8]) -> Result<Vec<u8>, EnclaveError> {
+    let mut reader = Reader::new(sealed);
+    let nonce = reader.array::<DISCLOSURE_NONCE_SIZE>().map_err(|err| {
+        debug!("disclosed attribute is too short to have a nonce: {}", err);
+        EnclaveError::DecryptionError
+    })?;
+    let ciphertext = reader.rest();
+
+    key.decrypt_siv(ciphertext, Some(&[nonce.as_slice()]))
+        .map_err(|err| {
+            debug!("failed to decrypt disclosed attribute: {:?}", err);
+            EnclaveError::DecryptionError
+        })
+}
+
+fn encrypt_attribute(
+    attr: &mut LogAttribute,
+    sender_key: &AESKey,
+    og_contract_key: &ContractKey,
+    disclosure_nonces: &mut DisclosureNonces,
+) -> Result<(), EnclaveError> {
+    match &attr.disclose_to {
+        Some(group) => {
+            let disclosure_key = calc_disclosure_key(og_contract_key, group)?;
+            attr.key = seal_disclosed(
+                &disclosure_key,
+                &disclosure_nonces.next(),
+                attr.key.as_bytes(),
+            )?;
+            attr.value = seal_disclosed(
+                &disclosure_key,
+                &disclosure_nonces.next(),
+                attr.value.as_bytes(),
+            )?;
+        }
+        None => {
+            attr.key = encrypt_preserialized_string(sender_key, &attr.key, &None, false, false)?;
+            attr.value =
+                encrypt_preserialized_string(sender_key, &attr.value, &None, false, false)?;
+        }
+    }
+
+    Ok(())
+}
+
 fn encrypt_serializable<T>(
     key: &AESKey,
     val: &T,
     reply_params: &Option<Vec<ReplyParams>>,
     should_append_all_reply_params: bool,
+    should_pad: bool,
 ) -> Result<String, EnclaveError>
 where
     T: ?Sized + Serialize,
@@ -147,7 +323,13 @@ where
 
     let trimmed = serialized.trim_start_matches('"').trim_end_matches('"');
 
-    encrypt_preserialized_string(key, trimmed, reply_params, should_append_all_reply_params)
+    encrypt_preserialized_string(
+        key,
+        trimmed,
+        reply_params,
+        should_append_all_reply_params,
+        should_pad,
+    )
 }
 
 // use this to encrypt a String that has already been serialized.  When that is the case, if
@@ -158,6 +340,7 @@ fn encrypt_preserialized_string(
     val: &str,
     reply_params: &Option<Vec<ReplyParams>>,
     should_append_all_reply_params: bool,
+    should_pad: bool,
 ) -> Result<String, EnclaveError> {
     let serialized = match reply_params {
         Some(v) => {
@@ -175,9 +358,8 @@ fn encrypt_preserialized_string(
         }
         None => val.as_bytes().to_vec(),
     };
-    let encrypted_data = key
-        .encrypt_siv(serialized.as_slice(), None)
-        .map_err(|err| {
+    let encrypted_data =
+        encrypt_message(key, serialized.as_slice(), should_pad).map_err(|err| {
             debug!(
                 "got an error while trying to encrypt output error {:?}: {}",
                 err, err
@@ -200,6 +382,7 @@ pub fn post_process_output(
     contract_hash: &str,
     reply_params: Option<Vec<ReplyParams>>,
     sender_addr: &CanonicalAddr,
+    og_contract_key: &ContractKey,
     is_query_output: bool,
     is_ibc_output: bool,
 ) -> Result<Vec<u8>, EnclaveError> {
@@ -209,6 +392,7 @@ pub fn post_process_output(
         raw_output,
         secret_msg,
         contract_addr,
+        og_contract_key,
         &reply_params,
         is_ibc_output,
     )?;
@@ -503,6 +687,7 @@ fn encrypt_output(
     mut output: RawWasmOutput,
     secret_msg: &SecretMessage,
     contract_addr: &CanonicalAddr,
+    og_contract_key: &ContractKey,
     reply_params: &Option<Vec<ReplyParams>>,
     is_ibc_output: bool,
 ) -> Result<RawWasmOutput, EnclaveError> {
@@ -515,14 +700,18 @@ fn encrypt_output(
         secret_msg.nonce,
         secret_msg.user_public_key
     );
+    // Errors aren't padded, since they can reach clients that don't know about padding
+    let should_pad = secret_msg.is_padded();
+    let mut disclosure_nonces = DisclosureNonces::new(secret_msg);
 
     match &mut output {
         RawWasmOutput::Err { err, .. } => {
-            let encrypted_err = encrypt_serializable(&encryption_key, err, reply_params, false)?;
+            let encrypted_err =
+                encrypt_serializable(&encryption_key, err, reply_params, false, false)?;
             *err = format_generic_error_message(Value::String(encrypted_err));
         }
         RawWasmOutput::QueryOkV010 { ok } | RawWasmOutput::QueryOkV1 { ok } => {
-            *ok = encrypt_serializable(&encryption_key, ok, reply_params, false)?;
+            *ok = encrypt_serializable(&encryption_key, ok, reply_params, false, should_pad)?;
         }
         RawWasmOutput::OkV010 { ok, .. } => {
             for msg in &mut ok.messages {
@@ -539,9 +728,12 @@ fn encrypt_output(
 
             // v0.10: The logs that will be emitted as part of a "wasm" event.
             for log in ok.log.iter_mut().filter(|log| log.encrypted) {
-                log.key = encrypt_preserialized_string(&encryption_key, &log.key, &None, false)?;
-                log.value =
-                    encrypt_preserialized_string(&encryption_key, &log.value, &None, false)?;
+                encrypt_attribute(
+                    log,
+                    &encryption_key,
+                    og_contract_key,
+                    &mut disclosure_nonces,
+                )?;
             }
 
             if let Some(data) = &mut ok.data {
@@ -550,6 +742,7 @@ fn encrypt_output(
                     data,
                     reply_params,
                     false,
+                    should_pad,
                 )?)?;
             }
         }
@@ -559,6 +752,9 @@ fn encrypt_output(
                 &mut ok.attributes,
                 &mut ok.events,
                 secret_msg,
+                contract_addr,
+                og_contract_key,
+                &mut disclosure_nonces,
             )?;
             if let Some(data) = &mut ok.data {
                 if is_ibc_output {
@@ -571,6 +767,7 @@ fn encrypt_output(
                     data,
                     reply_params,
                     false,
+                    should_pad,
                 )?)?;
             }
         }
@@ -580,6 +777,9 @@ fn encrypt_output(
                 &mut ok.attributes,
                 &mut ok.events,
                 secret_msg,
+                contract_addr,
+                og_contract_key,
+                &mut disclosure_nonces,
             )?;
 
             ok.acknowledgement = Binary::from_base64(&encrypt_serializable(
@@ -587,6 +787,7 @@ fn encrypt_output(
                 &ok.acknowledgement,
                 reply_params,
                 false,
+                should_pad,
             )?)?;
         }
         RawWasmOutput::OkIBCOpenChannel { ok: _ } => {}
@@ -600,24 +801,26 @@ fn encrypt_v1_non_result_fields<T: Clone + fmt::Debug + PartialEq>(
     attributes: &mut [LogAttribute],
     events: &mut [Event],
     secret_msg: &SecretMessage,
+    contract_addr: &CanonicalAddr,
+    og_contract_key: &ContractKey,
+    disclosure_nonces: &mut DisclosureNonces,
 ) -> Result<(), EnclaveError> {
     let encryption_key = calc_encryption_key(&secret_msg.nonce, &secret_msg.user_public_key);
 
     for sub_msg in messages.iter_mut() {
         encrypt_wasm_submsg(sub_msg, secret_msg)?;
+        attach_sender_context(sub_msg, secret_msg, contract_addr)?;
     }
 
     // v1: The attributes that will be emitted as part of a "wasm" event.
     for attr in attributes.iter_mut().filter(|attr| attr.encrypted) {
-        attr.key = encrypt_preserialized_string(&encryption_key, &attr.key, &None, false)?;
-        attr.value = encrypt_preserialized_string(&encryption_key, &attr.value, &None, false)?;
+        encrypt_attribute(attr, &encryption_key, og_contract_key, disclosure_nonces)?;
     }
 
     // v1: Extra, custom events separate from the main wasm one. These will have "wasm-"" prepended to the type.
     for event in events.iter_mut() {
         for attr in event.attributes.iter_mut().filter(|attr| attr.encrypted) {
-            attr.key = encrypt_preserialized_string(&encryption_key, &attr.key, &None, false)?;
-            attr.value = encrypt_preserialized_string(&encryption_key, &attr.value, &None, false)?;
+            encrypt_attribute(attr, &encryption_key, og_contract_key, disclosure_nonces)?;
         }
     }
 
@@ -863,6 +1066,7 @@ fn get_reply_info_for_output(
         &reply_params.as_ref().unwrap()[0].sub_msg_id.to_string(),
         reply_params,
         should_append_all_reply_params,
+        false,
     )?)?;
 
     let reply = Reply {
@@ -1035,3 +1239,36 @@ pub fn create_callback_signature(
 pub fn format_generic_error_message(encrypted_err: Value) -> Value {
     json!({"generic_err":{"msg":encrypted_err}})
 }
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_disclosed_attribute_nonces() {
+        let key = AESKey::new_from_slice(&[1; 32]);
+        let mut nonces = DisclosureNonces::from_parts(&[2; 32], &[3; 32], 10, 0);
+        let first = nonces.next();
+        let second = nonces.next();
+        assert_ne!(first, second);
+
+        // equal values don't encrypt equally, within an output or across msgs
+        let sealed = seal_disclosed(&key, &first, b"1000").unwrap();
+        assert_ne!(sealed, seal_disclosed(&key, &second, b"1000").unwrap());
+        let mut next_msg = DisclosureNonces::from_parts(&[2; 32], &[3; 32], 10, 1);
+        assert_ne!(first, next_msg.next());
+        // but every node derives the same nonces
+        assert_eq!(
+            first,
+            DisclosureNonces::from_parts(&[2; 32], &[3; 32], 10, 0).next()
+        );
+
+        let sealed = base64::decode(sealed).unwrap();
+        assert_eq!(open_disclosed(&key, &sealed).unwrap(), b"1000".to_vec());
+
+        // the nonce is bound as associated data
+        let mut tampered = sealed.clone();
+        tampered[0] ^= 1;
+        assert!(open_disclosed(&key, &tampered).is_err());
+        assert!(open_disclosed(&key, &sealed[..DISCLOSURE_NONCE_SIZE - 1]).is_err());
+    }
+}
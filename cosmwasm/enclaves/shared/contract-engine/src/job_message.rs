@@ -0,0 +1,508 @@
+//! Off-chain private computation jobs, and their results.
+//!
+//! A contract enqueues a job by sealing its input with `job_seal_input`, and sending the sealed
+//! input in a `MsgEnqueueJob`, which the node stores with the job's `EnqueuedJob`. The input is
+//! sealed with a key derived from the genesis state key, the contract's og key and the code hash
+//! of the job's handler, so only an enclave of the network can open it, and only to run that
+//! handler for that contract.
+//!
+//! A worker, which is an instance of this enclave, picks the job up, opens its input and runs the
+//! handler's `query` entry point off-chain, see `run_job`. The handler is isolated from the
+//! worker's node: it reads nothing from storage, can't query the chain, and sees the height and
+//! time of the last block the enclave verified. The worker signs a `SignedJobResult` with a fresh
+//! key that its quote commits to, which is then submitted back to the chain. Before the result is
+//! delivered to the contract's `job_result` entry point we check that:
+//! 1. The worker's quote verifies at the current block time and has our MRSIGNER and MRENCLAVE
+//! 2. The quote's report data is the worker's key followed by `JOB_WORKER_REPORT_DOMAIN`, so the
+//!    quote can't be one the enclave made for another purpose
+//! 3. The worker's key signed the result
+//! 4. The result is addressed to the contract that's being called
+//! 5. No result for the job was delivered to the contract before, as recorded in its state
+//!
+//! The node checks that the job is waiting for its result, and that the result is for the
+//! handler and the sealed input of the job. The contract is responsible for checking
+//! `handler_code_hash` and `input_hash` against the job it enqueued.
+
+use log::{error, trace, warn};
+use serde::{Deserialize, Serialize};
+
+use cw_types_v010::encoding::Binary;
+use cw_types_v010::types::HumanAddr;
+use cw_types_v1::math::Uint64;
+use enclave_crypto::dcap::verify_quote_same_enclave;
+use enclave_crypto::{hkdf_sha_256, sha_256, AESKey, SIVEncryptable};
+use enclave_ffi_types::EnclaveError;
+use sgx_types::sgx_report_body_t;
+
+use crate::contract_validation::ContractKey;
+use crate::message_utils::try_get_decrypted_secret_msg;
+use crate::types::{ParsedMessage, SecretMessage};
+
+pub const JOB_RESULT_DOMAIN: &[u8] = b"secret-job-result-v1";
+pub const JOB_WORKER_REPORT_DOMAIN: &[u8] = b"secret-job-worker-v1";
+pub const WORKER_PUBKEY_SIZE: usize = 33;
+const HASH_SIZE: usize = 32;
+
+const JOB_INPUT_KEY_DOMAIN: &[u8] = b"job_input";
+/// Prefix of the keys the enclave writes to a contract's state for the jobs it delivered. A
+/// contract that writes keys with this prefix can only break the replay protection of its own
+/// jobs.
+const DELIVERED_JOB_KEY_PREFIX: &[u8] = b"\x00secret_delivered_job\x00";
+pub const MAX_JOB_INPUT_LENGTH: usize = 256 * 1024;
+
+/// Error codes returned to contracts by `job_seal_input`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum JobSealError {
+    /// The handler code hash isn't 32 bytes, or the input is over its limit
+    InvalidInput = 1,
+}
+
+/// A job as the node stores it, and hands it to a worker
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct EnqueuedJob {
+    pub job_id: Uint64,
+    pub contract_address: HumanAddr,
+    pub handler_code_hash: Binary,
+    /// The input, as `job_seal_input` sealed it
+    pub sealed_input: Binary,
+}
+
+/// What a handler's `query` entry point returns
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum JobHandlerOutput {
+    Ok(Binary),
+    Error(String),
+}
+
+fn job_input_key(
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    handler_code_hash: &[u8],
+) -> Result<AESKey, JobSealError> {
+    if handler_code_hash.len() != HASH_SIZE {
+        return Err(JobSealError::InvalidInput);
+    }
+
+    let info: Vec<&[u8]> = vec![
+        JOB_INPUT_KEY_DOMAIN,
+        og_contract_key.as_slice(),
+        handler_code_hash,
+    ];
+
+    Ok(hkdf_sha_256(genesis_state_key.get(), info.as_slice()))
+}
+
+/// Encrypt the input of a job for the enclave that runs its handler. AES-SIV is deterministic,
+/// so every node sends the same `MsgEnqueueJob`.
+pub fn seal_job_input(
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    handler_code_hash: &[u8],
+    input: &[u8],
+) -> Result<Vec<u8>, JobSealError> {
+    let key = job_input_key(genesis_state_key, og_contract_key, handler_code_hash)?;
+    if input.len() > MAX_JOB_INPUT_LENGTH {
+        return Err(JobSealError::InvalidInput);
+    }
+
+    key.encrypt_siv(input, Some(&[handler_code_hash]))
+        .map_err(|err| {
+            error!("failed to seal job input: {:?}", err);
+            JobSealError::InvalidInput
+        })
+}
+
+/// Decrypt the input of a job, for the worker that runs its handler
+pub fn open_job_input(
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    job: &EnqueuedJob,
+) -> Result<Vec<u8>, EnclaveError> {
+    let handler_code_hash = job.handler_code_hash.as_slice();
+    let key = job_input_key(genesis_state_key, og_contract_key, handler_code_hash)
+        .map_err(|_| EnclaveError::FailedToDeserialize)?;
+
+    key.decrypt_siv(job.sealed_input.as_slice(), Some(&[handler_code_hash]))
+        .map_err(|err| {
+            warn!("failed to open the input of job {}: {:?}", job.job_id, err);
+            EnclaveError::DecryptionError
+        })
+}
+
+/// What a worker enclave submits to the chain
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct SignedJobResult {
+    pub job_id: Uint64,
+    pub contract_address: HumanAddr,
+    pub handler_code_hash: Binary,
+    pub input_hash: Binary,
+    /// Hash of the job's input as `job_seal_input` sealed it, for the node to match the job
+    pub sealed_input_hash: Binary,
+    /// The handler's output, usually encrypted as a `SecretMessage` for the contract
+    pub result: Binary,
+    pub worker_quote: Binary,
+    pub worker_collateral: Binary,
+    /// Compact secp256k1 signature over `sha256(sign_bytes)`
+    pub signature: Binary,
+}
+
+/// What the contract receives in its `job_result` entry point
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct JobResultMsg {
+    pub job_id: Uint64,
+    pub handler_code_hash: Binary,
+    pub input_hash: Binary,
+    pub result: Binary,
+}
+
+impl SignedJobResult {
+    /// The result of `job` that a worker still has to attest and sign
+    pub fn unsigned(job: &EnqueuedJob, input: &[u8], result: Vec<u8>) -> Self {
+        SignedJobResult {
+            job_id: job.job_id,
+            contract_address: job.contract_address.clone(),
+            handler_code_hash: job.handler_code_hash.clone(),
+            input_hash: Binary(sha_256(input).to_vec()),
+            sealed_input_hash: Binary(sha_256(job.sealed_input.as_slice()).to_vec()),
+            result: Binary(result),
+            worker_quote: Binary(vec![]),
+            worker_collateral: Binary(vec![]),
+            signature: Binary(vec![]),
+        }
+    }
+
+    pub fn sign_bytes(&self) -> Vec<u8> {
+        let contract_address = self.contract_address.0.as_bytes();
+
+        let mut bytes = vec![];
+        bytes.extend_from_slice(JOB_RESULT_DOMAIN);
+        bytes.extend_from_slice(&self.job_id.u64().to_be_bytes());
+        bytes.extend_from_slice(&(contract_address.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(contract_address);
+        bytes.extend_from_slice(self.handler_code_hash.as_slice());
+        bytes.extend_from_slice(self.input_hash.as_slice());
+        bytes.extend_from_slice(self.sealed_input_hash.as_slice());
+        bytes.extend_from_slice(&sha_256(self.result.as_slice()));
+
+        bytes
+    }
+
+    /// Sign the result with a worker's `secret_key`, and return its compressed public key, which
+    /// the worker's quote must commit to
+    pub fn sign(
+        &mut self,
+        secret_key: &[u8; 32],
+    ) -> Result<[u8; WORKER_PUBKEY_SIZE], EnclaveError> {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let secret_key = secp256k1::SecretKey::from_slice(secret_key).map_err(|err| {
+            warn!("job worker secret key is invalid: {:?}", err);
+            EnclaveError::FailedTxVerification
+        })?;
+        let msg = secp256k1::Message::from_slice(&sha_256(&self.sign_bytes()))
+            .map_err(|_| EnclaveError::FailedTxVerification)?;
+
+        self.signature = Binary(
+            secp.sign_ecdsa(&msg, &secret_key)
+                .serialize_compact()
+                .to_vec(),
+        );
+
+        Ok(secp256k1::PublicKey::from_secret_key(&secp, &secret_key).serialize())
+    }
+
+    /// Attach the worker's quote, which commits to the key `sign` returned, and its collateral
+    pub fn attest(&mut self, quote: Vec<u8>, collateral: Vec<u8>) {
+        self.worker_quote = Binary(quote);
+        self.worker_collateral = Binary(collateral);
+    }
+
+    fn verify_signature(&self, worker_pubkey: &[u8]) -> Result<(), EnclaveError> {
+        if self.handler_code_hash.len() != HASH_SIZE
+            || self.input_hash.len() != HASH_SIZE
+            || self.sealed_input_hash.len() != HASH_SIZE
+        {
+            warn!("job result has malformed hashes");
+            return Err(EnclaveError::FailedToDeserialize);
+        }
+
+        let secp = secp256k1::Secp256k1::verification_only();
+        let pubkey = secp256k1::PublicKey::from_slice(worker_pubkey).map_err(|err| {
+            warn!("job worker public key is invalid: {:?}", err);
+            EnclaveError::FailedTxVerification
+        })?;
+        let signature = secp256k1::ecdsa::Signature::from_compact(self.signature.as_slice())
+            .map_err(|err| {
+                warn!("job result signature is malformed: {:?}", err);
+                EnclaveError::FailedTxVerification
+            })?;
+        let msg = secp256k1::Message::from_slice(&sha_256(&self.sign_bytes()))
+            .map_err(|_| EnclaveError::FailedTxVerification)?;
+
+        secp.verify_ecdsa(&msg, &signature, &pubkey).map_err(|_| {
+            warn!("job result signature failed to verify");
+            EnclaveError::FailedTxVerification
+        })
+    }
+}
+
+/// The report data a worker's quote commits to for its key
+pub fn worker_report_data(worker_pubkey: &[u8; WORKER_PUBKEY_SIZE]) -> Vec<u8> {
+    let mut report_data = worker_pubkey.to_vec();
+    report_data.extend_from_slice(JOB_WORKER_REPORT_DOMAIN);
+    report_data
+}
+
+/// The key in the report of a worker, if the report is one
+fn worker_pubkey(report_body: &sgx_report_body_t) -> Result<&[u8], EnclaveError> {
+    let (pubkey, domain) = report_body.report_data.d.split_at(WORKER_PUBKEY_SIZE);
+    let (domain, padding) = domain.split_at(JOB_WORKER_REPORT_DOMAIN.len());
+    if domain != JOB_WORKER_REPORT_DOMAIN || padding.iter().any(|byte| *byte != 0) {
+        warn!("job worker quote isn't a quote of a worker key");
+        return Err(EnclaveError::FailedTxVerification);
+    }
+
+    Ok(pubkey)
+}
+
+/// The key under which the enclave records that it delivered the result of `job_id`
+pub fn delivered_job_key(job_id: u64) -> Vec<u8> {
+    let mut key = DELIVERED_JOB_KEY_PREFIX.to_vec();
+    key.extend_from_slice(&job_id.to_be_bytes());
+    key
+}
+
+/// The id of the job of a `JobResultMsg`, as `parse_job_result_message` returned it
+pub fn job_result_id(decrypted_msg: &[u8]) -> Result<u64, EnclaveError> {
+    let job_result: JobResultMsg = serde_json::from_slice(decrypted_msg).map_err(|err| {
+        warn!(
+            "got an error while trying to deserialize JobResultMsg from bytes: {}",
+            err
+        );
+        EnclaveError::FailedToDeserialize
+    })?;
+
+    Ok(job_result.job_id.u64())
+}
+
+pub fn parse_job_result_message(
+    message: &[u8],
+    contract_address: &HumanAddr,
+    block_time_ns: u64,
+) -> Result<ParsedMessage, EnclaveError> {
+    let signed: SignedJobResult = serde_json::from_slice(message).map_err(|err| {
+        warn!(
+            "got an error while trying to deserialize input bytes into SignedJobResult {:?}: {}",
+            String::from_utf8_lossy(message),
+            err
+        );
+        EnclaveError::FailedToDeserialize
+    })?;
+
+    if &signed.contract_address != contract_address {
+        warn!(
+            "job result is addressed to {:?} but was sent to {:?}",
+            signed.contract_address, contract_address
+        );
+        return Err(EnclaveError::FailedTxVerification);
+    }
+
+    let time_s = (block_time_ns / 1_000_000_000) as i64;
+    let report_body = verify_quote_same_enclave(
+        signed.worker_quote.as_slice(),
+        signed.worker_collateral.as_slice(),
+        time_s,
+    )
+    .map_err(|err| {
+        warn!("job worker attestation failed: {:?}", err);
+        EnclaveError::FailedTxVerification
+    })?;
+
+    signed.verify_signature(worker_pubkey(&report_body)?)?;
+
+    let (was_msg_encrypted, secret_msg, result) =
+        match try_get_decrypted_secret_msg(signed.result.as_slice()) {
+            Some(decrypted) => {
+                trace!("job result {} was encrypted", signed.job_id);
+                (true, decrypted.secret_msg, decrypted.decrypted_msg)
+            }
+            None => {
+                trace!("job result {} was plaintext", signed.job_id);
+                (
+                    false,
+                    SecretMessage {
+                        nonce: [0; 32],
+                        user_public_key: [0; 32],
+                        msg: signed.result.as_slice().to_vec(),
+                    },
+                    signed.result.as_slice().to_vec(),
+                )
+            }
+        };
+
+    let job_result = JobResultMsg {
+        job_id: signed.job_id,
+        handler_code_hash: signed.handler_code_hash,
+        input_hash: signed.input_hash,
+        result: Binary(result),
+    };
+
+    Ok(ParsedMessage {
+        // The worker's signature and quote replace the tx signature
+        should_verify_sig_info: false,
+        should_verify_input: false,
+        was_msg_encrypted,
+        should_encrypt_output: was_msg_encrypted,
+        secret_msg,
+        decrypted_msg: serde_json::to_vec(&job_result).map_err(|err| {
+            warn!(
+                "got an error while trying to serialize JobResultMsg into bytes: {}",
+                err
+            );
+            EnclaveError::FailedToSerialize
+        })?,
+        data_for_validation: None,
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    const TEST_SECRET_KEY: [u8; 32] = [0x22; 32];
+
+    fn signed_result() -> (SignedJobResult, Vec<u8>) {
+        let secp = secp256k1::Secp256k1::new();
+        let sk = secp256k1::SecretKey::from_slice(&TEST_SECRET_KEY).unwrap();
+        let pk = secp256k1::PublicKey::from_secret_key(&secp, &sk)
+            .serialize()
+            .to_vec();
+
+        let mut job = SignedJobResult {
+            job_id: Uint64::new(7),
+            contract_address: HumanAddr("secret1contract".to_string()),
+            handler_code_hash: Binary(vec![1; HASH_SIZE]),
+            input_hash: Binary(vec![2; HASH_SIZE]),
+            sealed_input_hash: Binary(vec![3; HASH_SIZE]),
+            result: Binary(b"{\"score\":99}".to_vec()),
+            worker_quote: Binary(vec![]),
+            worker_collateral: Binary(vec![]),
+            signature: Binary(vec![]),
+        };
+
+        let msg = secp256k1::Message::from_slice(&sha_256(&job.sign_bytes())).unwrap();
+        job.signature = Binary(secp.sign_ecdsa(&msg, &sk).serialize_compact().to_vec());
+
+        (job, pk)
+    }
+
+    pub fn test_job_result_signature() {
+        let (job, pk) = signed_result();
+        assert!(job.verify_signature(&pk).is_ok());
+
+        let mut signed = job.clone();
+        signed.signature = Binary(vec![]);
+        let worker_pubkey = signed.sign(&TEST_SECRET_KEY).unwrap();
+        assert_eq!(worker_pubkey.to_vec(), pk);
+        assert!(signed.verify_signature(&worker_pubkey).is_ok());
+    }
+
+    pub fn test_job_result_signature_binds_fields() {
+        let (job, pk) = signed_result();
+
+        let mut other = job.clone();
+        other.job_id = Uint64::new(8);
+        assert!(other.verify_signature(&pk).is_err());
+
+        let mut other = job.clone();
+        other.contract_address = HumanAddr("secret1other".to_string());
+        assert!(other.verify_signature(&pk).is_err());
+
+        let mut other = job.clone();
+        other.sealed_input_hash = Binary(vec![4; HASH_SIZE]);
+        assert!(other.verify_signature(&pk).is_err());
+
+        let mut other = job;
+        other.result = Binary(b"{\"score\":100}".to_vec());
+        assert!(other.verify_signature(&pk).is_err());
+    }
+
+    pub fn test_job_worker_report() {
+        let pubkey = [2u8; WORKER_PUBKEY_SIZE];
+        let mut report_body = sgx_report_body_t::default();
+        report_body.report_data.d[..WORKER_PUBKEY_SIZE + JOB_WORKER_REPORT_DOMAIN.len()]
+            .copy_from_slice(&worker_report_data(&pubkey));
+        assert_eq!(worker_pubkey(&report_body).unwrap(), &pubkey[..]);
+
+        // e.g. a registration quote, which commits to a 32 byte key
+        let mut other_data = report_body;
+        other_data.report_data.d[WORKER_PUBKEY_SIZE] ^= 1;
+        assert!(worker_pubkey(&other_data).is_err());
+        let mut trailing_data = report_body;
+        trailing_data.report_data.d[63] = 1;
+        assert!(worker_pubkey(&trailing_data).is_err());
+    }
+
+    pub fn test_delivered_job_key() {
+        assert_ne!(delivered_job_key(1), delivered_job_key(2));
+        assert!(delivered_job_key(1).starts_with(DELIVERED_JOB_KEY_PREFIX));
+
+        let msg = serde_json::to_vec(&JobResultMsg {
+            job_id: Uint64::new(9),
+            handler_code_hash: Binary(vec![1; HASH_SIZE]),
+            input_hash: Binary(vec![2; HASH_SIZE]),
+            result: Binary(vec![]),
+        })
+        .unwrap();
+        assert_eq!(job_result_id(&msg).unwrap(), 9);
+        assert!(job_result_id(b"{}").is_err());
+    }
+
+    pub fn test_job_input_seal() {
+        let state_key = AESKey::new_from_slice(&[3; 32]);
+        let og_contract_key = [4u8; 64];
+        let handler_code_hash = [5u8; HASH_SIZE];
+
+        let sealed =
+            seal_job_input(&state_key, &og_contract_key, &handler_code_hash, b"input").unwrap();
+        let mut job = EnqueuedJob {
+            job_id: Uint64::new(1),
+            contract_address: HumanAddr("secret1contract".to_string()),
+            handler_code_hash: Binary(handler_code_hash.to_vec()),
+            sealed_input: Binary(sealed),
+        };
+        assert_eq!(
+            open_job_input(&state_key, &og_contract_key, &job).unwrap(),
+            b"input".to_vec()
+        );
+
+        // sealed for another contract
+        assert!(open_job_input(&state_key, &[6u8; 64], &job).is_err());
+        // or another handler
+        job.handler_code_hash = Binary(vec![7; HASH_SIZE]);
+        assert!(open_job_input(&state_key, &og_contract_key, &job).is_err());
+
+        assert_eq!(
+            seal_job_input(&state_key, &og_contract_key, &[5u8; 31], b"input"),
+            Err(JobSealError::InvalidInput)
+        );
+        assert_eq!(
+            seal_job_input(
+                &state_key,
+                &og_contract_key,
+                &handler_code_hash,
+                &vec![0; MAX_JOB_INPUT_LENGTH + 1]
+            ),
+            Err(JobSealError::InvalidInput)
+        );
+    }
+
+    pub fn test_job_result_wrong_contract() {
+        let (job, _) = signed_result();
+        let message = serde_json::to_vec(&job).unwrap();
+
+        let res = parse_job_result_message(&message, &HumanAddr("secret1other".to_string()), 0);
+        assert!(res.is_err());
+    }
+}
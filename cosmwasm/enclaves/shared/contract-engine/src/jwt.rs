@@ -0,0 +1,395 @@
+//! JWT verification, so contracts can gate actions on OIDC identities without shipping a JOSE
+//! stack in wasm.
+//!
+//! Only compact tokens signed with `ES256` or `RS256` are accepted, and the algorithm of a token
+//! must match the type of the JWK it's verified with, so a token can't choose how it's verified,
+//! e.g. with `none` or with the public key as an HMAC secret. The header, claims and JWK are
+//! rejected if they have duplicate keys, so the contract can't read other claims than the ones
+//! that were checked.
+//!
+//! The claims are returned as they were signed once the signature, `exp` and `nbf` check out.
+//! Checking `iss`, `aud` and the rest is up to the contract.
+
+use serde_json::{Map, Value};
+
+use enclave_crypto::{secp256r1, sha_256};
+
+use crate::canonical_json::canonicalize;
+use crate::rsa::{self, RsaError};
+
+/// Error codes returned to contracts by the `jwt_verify` host function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum JwtError {
+    /// The token isn't three base64url segments, its header or claims aren't JSON objects, it
+    /// has no integer `exp`, or it has a `crit` header
+    MalformedToken = 1,
+    /// The token isn't signed with `ES256` or `RS256`, or its algorithm doesn't match the JWK
+    UnsupportedAlgorithm = 2,
+    /// The JWK isn't a P-256 or RSA public key for signatures, or its `kid` doesn't match the
+    /// token's
+    InvalidKey = 3,
+    InvalidSignature = 4,
+    /// The block time is at or after `exp`
+    Expired = 5,
+    /// The block time is before `nbf`
+    NotYetValid = 6,
+}
+
+/// Tokens are passed around in headers, which servers usually limit to 8 or 16 KiB
+pub const MAX_JWT_LENGTH: usize = 16 * 1024;
+
+const P256_COORDINATE_SIZE: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JwkKey {
+    /// An uncompressed SEC1 public key
+    P256(Vec<u8>),
+    Rsa {
+        modulus: Vec<u8>,
+        exponent: Vec<u8>,
+    },
+}
+
+impl JwkKey {
+    fn algorithm(&self) -> &'static str {
+        match self {
+            JwkKey::P256(_) => "ES256",
+            JwkKey::Rsa { .. } => "RS256",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Jwk {
+    pub key: JwkKey,
+    pub kid: Option<String>,
+}
+
+/// Parses a JSON document that must be an object without duplicate keys
+fn parse_object(json: &[u8]) -> Option<Map<String, Value>> {
+    canonicalize(json).ok()?;
+
+    match serde_json::from_slice(json) {
+        Ok(Value::Object(object)) => Some(object),
+        _ => None,
+    }
+}
+
+/// Decodes unpadded base64url, as JOSE encodes everything
+fn decode_segment(segment: &[u8]) -> Option<Vec<u8>> {
+    if segment.contains(&b'=') {
+        return None;
+    }
+    base64::decode_config(segment, base64::URL_SAFE_NO_PAD).ok()
+}
+
+fn string_member<'a>(object: &'a Map<String, Value>, key: &str) -> Result<Option<&'a str>, ()> {
+    match object.get(key) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(()),
+    }
+}
+
+fn integer_member(object: &Map<String, Value>, key: &str) -> Result<Option<u64>, ()> {
+    match object.get(key) {
+        None => Ok(None),
+        Some(value) => value.as_u64().map(Some).ok_or(()),
+    }
+}
+
+impl Jwk {
+    pub fn parse(jwk: &[u8]) -> Result<Self, JwtError> {
+        let jwk = parse_object(jwk).ok_or(JwtError::InvalidKey)?;
+        let member = |key| string_member(&jwk, key).map_err(|_| JwtError::InvalidKey);
+        let bytes_member = |key| {
+            member(key)?
+                .and_then(|value| decode_segment(value.as_bytes()))
+                .ok_or(JwtError::InvalidKey)
+        };
+
+        let key = match member("kty")? {
+            Some("EC") => {
+                if member("crv")? != Some("P-256") {
+                    return Err(JwtError::InvalidKey);
+                }
+                let (x, y) = (bytes_member("x")?, bytes_member("y")?);
+                if x.len() != P256_COORDINATE_SIZE || y.len() != P256_COORDINATE_SIZE {
+                    return Err(JwtError::InvalidKey);
+                }
+
+                let mut public_key = vec![0x04];
+                public_key.extend_from_slice(&x);
+                public_key.extend_from_slice(&y);
+                JwkKey::P256(public_key)
+            }
+            Some("RSA") => JwkKey::Rsa {
+                modulus: bytes_member("n")?,
+                exponent: bytes_member("e")?,
+            },
+            _ => return Err(JwtError::InvalidKey),
+        };
+
+        // A key that is restricted to another algorithm can't be used for this one, and a key
+        // meant for encryption can't be used for signatures
+        if member("alg")?.map_or(false, |alg| alg != key.algorithm()) {
+            return Err(JwtError::UnsupportedAlgorithm);
+        }
+        if member("use")?.map_or(false, |usage| usage != "sig") {
+            return Err(JwtError::InvalidKey);
+        }
+        if let Some(key_ops) = jwk.get("key_ops") {
+            let ops = key_ops.as_array().ok_or(JwtError::InvalidKey)?;
+            if !ops.iter().any(|op| op == "verify") {
+                return Err(JwtError::InvalidKey);
+            }
+        }
+
+        Ok(Jwk {
+            key,
+            kid: member("kid")?.map(str::to_string),
+        })
+    }
+}
+
+/// Verifies a compact JWT with `jwk` at `now` (unix seconds), and returns its claims as they were
+/// signed
+pub fn verify_jwt(token: &[u8], jwk: &Jwk, now: u64) -> Result<Vec<u8>, JwtError> {
+    if token.len() > MAX_JWT_LENGTH {
+        return Err(JwtError::MalformedToken);
+    }
+
+    let segments: Vec<&[u8]> = token.split(|&byte| byte == b'.').collect();
+    let (header, claims, signature) = match segments[..] {
+        [header, claims, signature] => (header, claims, signature),
+        _ => return Err(JwtError::MalformedToken),
+    };
+    let signing_input = &token[..header.len() + 1 + claims.len()];
+
+    let header = decode_segment(header)
+        .and_then(|header| parse_object(&header))
+        .ok_or(JwtError::MalformedToken)?;
+    let claims = decode_segment(claims).ok_or(JwtError::MalformedToken)?;
+    let signature = decode_segment(signature).ok_or(JwtError::MalformedToken)?;
+
+    // None of the extensions a critical header could require are supported
+    if header.contains_key("crit") {
+        return Err(JwtError::MalformedToken);
+    }
+    let alg = string_member(&header, "alg").map_err(|_| JwtError::MalformedToken)?;
+    if alg != Some(jwk.key.algorithm()) {
+        return Err(JwtError::UnsupportedAlgorithm);
+    }
+    let kid = string_member(&header, "kid").map_err(|_| JwtError::MalformedToken)?;
+    if let (Some(kid), Some(jwk_kid)) = (kid, &jwk.kid) {
+        if kid != jwk_kid {
+            return Err(JwtError::InvalidKey);
+        }
+    }
+
+    match &jwk.key {
+        JwkKey::P256(public_key) => {
+            secp256r1::verify_sha256(public_key, signing_input, &signature)
+                .map_err(|_| JwtError::InvalidSignature)?;
+        }
+        JwkKey::Rsa { modulus, exponent } => {
+            rsa::verify_pkcs1v15(&sha_256(signing_input), &signature, modulus, exponent).map_err(
+                |err| match err {
+                    RsaError::InvalidPubkeyFormat => JwtError::InvalidKey,
+                    _ => JwtError::InvalidSignature,
+                },
+            )?;
+        }
+    }
+
+    let claims_object = parse_object(&claims).ok_or(JwtError::MalformedToken)?;
+    let exp = integer_member(&claims_object, "exp")
+        .ok()
+        .flatten()
+        .ok_or(JwtError::MalformedToken)?;
+    let nbf = integer_member(&claims_object, "nbf").map_err(|_| JwtError::MalformedToken)?;
+    if now >= exp {
+        return Err(JwtError::Expired);
+    }
+    if nbf.map_or(false, |nbf| now < nbf) {
+        return Err(JwtError::NotYetValid);
+    }
+
+    Ok(claims)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    const ES256_TOKEN: &str = concat!(
+        "eyJhbGciOiJFUzI1NiIsInR5cCI6IkpXVCIsImtpZCI6ImVjLTEifQ.",
+        "eyJpc3MiOiJodHRwczovL2FjY291bnRzLmV4YW1wbGUuY29tIiwic3ViIjoiMTIzNDU2Nzg5MCIsImF1ZCI6InNl",
+        "Y3JldDFhcHAiLCJuYmYiOjE3MzU2ODk2MDAsImV4cCI6MTc2NzIyNTYwMH0.",
+        "DG62Vfx-b65AewClBcyDCS8u8B3gnX3prI32LrNzj0M_Ud4rjpa4Lya6HAVxHQeFDKFSYGEIVpPbppS0Z2-1mQ",
+    );
+    const EC_JWK: &str = concat!(
+        r#"{"kty":"EC","crv":"P-256","kid":"ec-1","#,
+        r#""x":"wdLSgF2kTlSRTftI6UuGKKzt45az5uA-lNNiioQNu3A","#,
+        r#""y":"w6dwfwLETmwEuXpt2unnV-UfeDTGeXELqTHeBFsbQpc"}"#,
+    );
+
+    const RS256_TOKEN: &str = concat!(
+        "eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCIsImtpZCI6InJzYS0xIn0.",
+        "eyJpc3MiOiJodHRwczovL2FjY291bnRzLmV4YW1wbGUuY29tIiwic3ViIjoiMTIzNDU2Nzg5MCIsImF1ZCI6InNl",
+        "Y3JldDFhcHAiLCJuYmYiOjE3MzU2ODk2MDAsImV4cCI6MTc2NzIyNTYwMH0.",
+        "ZlyPhxMxcHEeJU00hSE1XV9CcH3sHQSqbTWOyxi95ms_9Nw9DfViTqF7_OSIjMsKzxcc4AUW2uEX7hD-PULZgB-C",
+        "BUGiXSgLLoQ-irU10C9belEbvUFBuofu2j6zhnjtNSobip3Xp511Pv0GHirW89kTQXOBn0-pcyTGTU_wPvexkdh2",
+        "U6jEiTqwj-bHvfCf61uOEnaPBaFVTgDLtjpgT-iADQBaNUdLLIxa-BVe0mFMdAwx1HsuCkOxt1b_fl5ZqajU_FbO",
+        "BNDNtyodv3IMs0OlDQvFLYcSlzXwWIoZf_Zlj85uKYQB0zVdVJJCuudV3Kj4GB11AZe93LOv4Z9yQA",
+    );
+    const RSA_JWK: &str = concat!(
+        r#"{"kty":"RSA","kid":"rsa-1","alg":"RS256","use":"sig","#,
+        r#""n":"iJaQvMLy3aOmrqw8UDcriJIzjcfZK93TAUsARDykJ2JKIdwdgYPkD6YGiz47RjXdAaOXB4keHkeejnFgM"#,
+        r#"enRfWNIiNZcJ5TK1zXkqa_JIutxFzGO2tF-WGb_u9TtI-pdmLCwFzupYoqrzDylIJpR2-KAxrJPyInV_pA31fz"#,
+        r#"avNt5rEhBzHmoaMk-BYQPWaod8HQ2-h60_1ywzeIgEpxU1PbxnMTV95IJ0CfV8DoMQ5Ux1Ns8e7BnKNiH9hOBD"#,
+        r#"NBxtRpizXJAmZBtowjHV3wmvJL1eXsWACKODdlbpbau2F4s0MUZKd9isfjD_g8rcwVWMIVl_RvLdhT99f-J7rL"#,
+        r#"OFQ","e":"AQAB"}"#,
+    );
+
+    /// Signed with the EC key, with `sub` twice
+    const DUPLICATE_CLAIMS_TOKEN: &str = concat!(
+        "eyJhbGciOiJFUzI1NiIsInR5cCI6IkpXVCIsImtpZCI6ImVjLTEifQ.",
+        "eyJzdWIiOiJhbGljZSIsImV4cCI6MTc2NzIyNTYwMCwic3ViIjoiYWRtaW4ifQ.",
+        "peVRcahXRcEF5JeB7KfOtc8FS0S3CBhhs1YMbYqPuw_w3bEHGMxR7H9okFIy3jMim5C9TVXdcvEp3cBDMMMUGw",
+    );
+
+    const CLAIMS: &str = concat!(
+        r#"{"iss":"https://accounts.example.com","sub":"1234567890","aud":"secret1app","#,
+        r#""nbf":1735689600,"exp":1767225600}"#,
+    );
+    const NBF: u64 = 1_735_689_600;
+    const EXP: u64 = 1_767_225_600;
+
+    fn jwk(jwk: &str) -> Jwk {
+        Jwk::parse(jwk.as_bytes()).unwrap()
+    }
+
+    fn encode(segment: &str) -> String {
+        base64::encode_config(segment, base64::URL_SAFE_NO_PAD)
+    }
+
+    /// A token with the given header and claims, and the signature of the ES256 token
+    fn forge(header: &str, claims: &str) -> Vec<u8> {
+        let signature = ES256_TOKEN.rsplit('.').next().unwrap();
+        format!("{}.{}.{}", encode(header), encode(claims), signature).into_bytes()
+    }
+
+    pub fn test_jwt_verify() {
+        assert_eq!(
+            verify_jwt(ES256_TOKEN.as_bytes(), &jwk(EC_JWK), NBF).unwrap(),
+            CLAIMS.as_bytes()
+        );
+        assert_eq!(
+            verify_jwt(RS256_TOKEN.as_bytes(), &jwk(RSA_JWK), EXP - 1).unwrap(),
+            CLAIMS.as_bytes()
+        );
+
+        assert_eq!(
+            verify_jwt(ES256_TOKEN.as_bytes(), &jwk(EC_JWK), EXP),
+            Err(JwtError::Expired)
+        );
+        assert_eq!(
+            verify_jwt(RS256_TOKEN.as_bytes(), &jwk(RSA_JWK), NBF - 1),
+            Err(JwtError::NotYetValid)
+        );
+
+        // A token without a kid can be verified with any key
+        let rsa_without_kid = RSA_JWK.replace(r#""kid":"rsa-1","#, "");
+        assert!(verify_jwt(RS256_TOKEN.as_bytes(), &jwk(&rsa_without_kid), NBF).is_ok());
+    }
+
+    pub fn test_jwt_rejects_forgeries() {
+        let ec = jwk(EC_JWK);
+
+        // The signature covers the header and the claims
+        let tampered = ES256_TOKEN.replacen("eyJpc3Mi", "eyJpc3Ni", 1);
+        assert_eq!(
+            verify_jwt(tampered.as_bytes(), &ec, NBF),
+            Err(JwtError::InvalidSignature)
+        );
+        let mut tampered = RS256_TOKEN.as_bytes().to_vec();
+        let last = tampered.len() - 2;
+        tampered[last] = b'A';
+        assert_eq!(
+            verify_jwt(&tampered, &jwk(RSA_JWK), NBF),
+            Err(JwtError::InvalidSignature)
+        );
+
+        // The token can't choose how it's verified
+        assert_eq!(
+            verify_jwt(ES256_TOKEN.as_bytes(), &jwk(RSA_JWK), NBF),
+            Err(JwtError::UnsupportedAlgorithm)
+        );
+        for alg in &["none", "HS256", "ES384"] {
+            let header = format!(r#"{{"alg":"{}"}}"#, alg);
+            assert_eq!(
+                verify_jwt(&forge(&header, CLAIMS), &ec, NBF),
+                Err(JwtError::UnsupportedAlgorithm)
+            );
+        }
+        assert_eq!(
+            Jwk::parse(
+                EC_JWK
+                    .replace(r#""kid""#, r#""alg":"ES384","kid""#)
+                    .as_bytes()
+            ),
+            Err(JwtError::UnsupportedAlgorithm)
+        );
+
+        assert_eq!(
+            verify_jwt(&forge(r#"{"alg":"ES256","kid":"ec-2"}"#, CLAIMS), &ec, NBF),
+            Err(JwtError::InvalidKey)
+        );
+
+        // Duplicate keys could make the contract read other claims than the ones checked here
+        assert_eq!(
+            verify_jwt(DUPLICATE_CLAIMS_TOKEN.as_bytes(), &ec, NBF),
+            Err(JwtError::MalformedToken)
+        );
+        for (header, claims) in &[
+            (r#"{"alg":"ES256","alg":"none"}"#, CLAIMS),
+            (r#"{"alg":"ES256","crit":["exp"]}"#, CLAIMS),
+            (r#"["ES256"]"#, CLAIMS),
+        ] {
+            assert_eq!(
+                verify_jwt(&forge(header, claims), &ec, NBF),
+                Err(JwtError::MalformedToken)
+            );
+        }
+        for token in &[
+            ES256_TOKEN.replacen('.', "=.", 1),
+            ES256_TOKEN.replacen('.', "..", 1),
+            ES256_TOKEN.replacen('-', "+", 1),
+            format!("{}.", ES256_TOKEN),
+        ] {
+            assert_eq!(
+                verify_jwt(token.as_bytes(), &ec, NBF),
+                Err(JwtError::MalformedToken)
+            );
+        }
+    }
+
+    pub fn test_jwt_parse_jwk() {
+        assert!(matches!(jwk(EC_JWK).key, JwkKey::P256(ref key) if key.len() == 65));
+        assert_eq!(jwk(RSA_JWK).kid.as_deref(), Some("rsa-1"));
+
+        for invalid in &[
+            EC_JWK.replace("P-256", "P-384"),
+            EC_JWK.replace(r#""x":""#, r#""x":"AAAA"#),
+            EC_JWK.replace(r#""kty":"EC""#, r#""kty":"oct""#),
+            RSA_JWK.replace(r#""use":"sig""#, r#""use":"enc""#),
+            RSA_JWK.replace(r#""use":"sig""#, r#""key_ops":["encrypt"]"#),
+            RSA_JWK.replace(r#""e":"AQAB""#, r#""e":"AQAB","e":"Aw""#),
+            RSA_JWK.replace(r#""e":"AQAB""#, r#""e":65537"#),
+        ] {
+            assert_eq!(Jwk::parse(invalid.as_bytes()), Err(JwtError::InvalidKey));
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Disclosure of a contract's state key to an auditor.
+//!
+//! Regulated deployments may need to give an auditor access to the state of a single contract.
+//! The key is only disclosed when both the contract's admin and governance agree: governance
+//! passes a `MsgDiscloseContractKey` of x/compute, and the message carries a signature of the
+//! admin over the chain id, the contract address, the auditor's key and a nonce:
+//!
+//! ```text
+//! message MsgDiscloseContractKey {
+//!   string authority = 1;          // the gov module account
+//!   string contract = 2;
+//!   bytes auditor_public_key = 3;  // x25519, 32 bytes
+//!   bytes admin_public_key = 4;    // secp256k1, compressed
+//!   bytes admin_signature = 5;     // over `disclosure_sign_bytes`
+//!   uint64 nonce = 6;              // above the last nonce of the contract's disclosures
+//! }
+//! ```
+//!
+//! x/compute only stores the message once governance passed it, at `disclosure_key` of the
+//! contract, and discloses the key in the next block. The node then hands the enclave the message
+//! with a proof of it against the app hash of that block, so the enclave doesn't take the node's
+//! word for governance's approval. The state key is re-encrypted to the auditor with an x25519
+//! key derived from the state key and the request, so every node returns the same output, and
+//! every disclosure is recorded in the sealed audit log. Only the key of the current consensus
+//! seed is disclosed, so values still stored in the legacy per-field format stay private.
+
+use log::*;
+
+use cosmos_proto::tx::signing::SignMode;
+use cw_types_v010::types::CanonicalAddr;
+use enclave_cosmos_types::traits::CosmosAminoPubkey;
+use enclave_crypto::ed25519::Ed25519PrivateKey;
+use enclave_crypto::secp256k1::Secp256k1PubKey;
+use enclave_crypto::traits::VerifyingKey;
+use enclave_crypto::{AESKey, Ed25519PublicKey, Kdf, KeyPair, SIVEncryptable, PUBLIC_KEY_SIZE};
+use enclave_ffi_types::EnclaveError;
+
+use enclave_utils::proto_fields::{fields, varint_value, WIRE_TYPE_LEN, WIRE_TYPE_VARINT};
+
+use crate::gov_messages::utf8_string;
+
+/// Where x/compute stores the last `MsgDiscloseContractKey` of every contract
+const CONTRACT_DISCLOSURE_PREFIX: u8 = 0x10;
+
+const DISCLOSURE_SIGN_PREFIX: &[u8] = b"secret_contract_key_disclosure";
+const DISCLOSURE_WRAPPING_DOMAIN: &[u8] = b"contract_key_disclosure";
+const DISCLOSURE_EPHEMERAL_DOMAIN: &[u8] = b"contract_key_disclosure_ephemeral";
+
+/// The ephemeral public key, followed by the wrapped 32 byte key and its 16 byte SIV tag
+pub const ENCRYPTED_CONTRACT_KEY_LENGTH: usize = PUBLIC_KEY_SIZE + 32 + 16;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct KeyDisclosureRequest {
+    pub authority: String,
+    pub contract: String,
+    pub auditor_public_key: Ed25519PublicKey,
+    pub admin_public_key: Vec<u8>,
+    pub admin_signature: Vec<u8>,
+    pub nonce: u64,
+}
+
+impl KeyDisclosureRequest {
+    pub fn parse(msg: &[u8]) -> Result<Self, EnclaveError> {
+        let mut request = Self::default();
+
+        for (field, wire_type, value) in fields(msg)? {
+            match (field, wire_type) {
+                (1, WIRE_TYPE_LEN) => request.authority = utf8_string(value)?,
+                (2, WIRE_TYPE_LEN) => request.contract = utf8_string(value)?,
+                (3, WIRE_TYPE_LEN) => {
+                    if value.len() != PUBLIC_KEY_SIZE {
+                        warn!("got an auditor public key of {} bytes", value.len());
+                        return Err(EnclaveError::FailedToDeserialize);
+                    }
+                    request.auditor_public_key.copy_from_slice(value);
+                }
+                (4, WIRE_TYPE_LEN) => request.admin_public_key = value.to_vec(),
+                (5, WIRE_TYPE_LEN) => request.admin_signature = value.to_vec(),
+                (6, WIRE_TYPE_VARINT) => request.nonce = varint_value(value)?,
+                (field, wire_type) => {
+                    warn!(
+                        "unexpected field {} of wire type {} in key disclosure request",
+                        field, wire_type
+                    );
+                    return Err(EnclaveError::FailedToDeserialize);
+                }
+            }
+        }
+
+        Ok(request)
+    }
+
+    /// The bytes the admin signs to authorize the disclosure on the chain `chain_id`
+    pub fn disclosure_sign_bytes(&self, chain_id: &str) -> Vec<u8> {
+        let mut bytes = DISCLOSURE_SIGN_PREFIX.to_vec();
+        bytes.extend_from_slice(&(chain_id.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(chain_id.as_bytes());
+        bytes.extend_from_slice(&(self.contract.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(self.contract.as_bytes());
+        bytes.extend_from_slice(&self.auditor_public_key);
+        bytes.extend_from_slice(&self.nonce.to_be_bytes());
+        bytes
+    }
+
+    /// Checks that the request was signed by `admin` for the chain `chain_id`
+    pub fn verify_admin_signature(
+        &self,
+        admin: &CanonicalAddr,
+        chain_id: &str,
+    ) -> Result<(), EnclaveError> {
+        verify_admin_signature(
+            &self.admin_public_key,
+            &self.admin_signature,
+            &self.disclosure_sign_bytes(chain_id),
+            admin,
+        )
+    }
+}
+
+/// The key of x/compute's store that has the last `MsgDiscloseContractKey` of `contract`
+pub fn disclosure_key(contract: &CanonicalAddr) -> Vec<u8> {
+    [&[CONTRACT_DISCLOSURE_PREFIX][..], contract.as_slice()].concat()
+}
+
+/// Checks that `sign_bytes` were signed by `admin`, with the secp256k1 key `admin_public_key`
+pub fn verify_admin_signature(
+    admin_public_key: &[u8],
+    admin_signature: &[u8],
+    sign_bytes: &[u8],
+    admin: &CanonicalAddr,
+) -> Result<(), EnclaveError> {
+    let admin_public_key = Secp256k1PubKey::new(admin_public_key.to_vec());
+
+    if &admin_public_key.get_address() != admin {
+        warn!("got a signature of a key that doesn't belong to the admin");
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    admin_public_key
+        .verify_bytes(sign_bytes, admin_signature, SignMode::SIGN_MODE_DIRECT)
+        .map_err(|err| {
+            warn!("admin signature verification failed: {:?}", err);
+            EnclaveError::ValidationFailure
+        })
+}
+
+/// Wrap `state_key` with a key only the holder of `auditor_public_key` can derive. The ephemeral
+/// key is derived from `state_key`, the auditor's key and `nonce`, so every node wraps it the same
+/// way.
+pub fn encrypt_to_auditor(
+    state_key: &AESKey,
+    auditor_public_key: &Ed25519PublicKey,
+    contract_address: &str,
+    nonce: u64,
+) -> Result<[u8; ENCRYPTED_CONTRACT_KEY_LENGTH], EnclaveError> {
+    let ephemeral_seed = state_key.derive_key_from_this(
+        &[
+            DISCLOSURE_EPHEMERAL_DOMAIN,
+            auditor_public_key,
+            &nonce.to_be_bytes(),
+        ]
+        .concat(),
+    );
+    let mut ephemeral_secret = Ed25519PrivateKey::default();
+    ephemeral_secret
+        .get_mut()
+        .copy_from_slice(ephemeral_seed.get());
+    let ephemeral_key = KeyPair::from_sk(ephemeral_secret);
+
+    let shared_secret = ephemeral_key.diffie_hellman(auditor_public_key);
+    let wrapping_key =
+        AESKey::new_from_slice(&shared_secret).derive_key_from_this(DISCLOSURE_WRAPPING_DOMAIN);
+
+    let wrapped_key = wrapping_key
+        .encrypt_siv(state_key.get(), Some(&[contract_address.as_bytes()]))
+        .map_err(|err| {
+            error!("failed to wrap the contract key for disclosure: {:?}", err);
+            EnclaveError::EncryptionError
+        })?;
+
+    let mut output = [0u8; ENCRYPTED_CONTRACT_KEY_LENGTH];
+    output[..PUBLIC_KEY_SIZE].copy_from_slice(&ephemeral_key.get_pubkey());
+    output[PUBLIC_KEY_SIZE..].copy_from_slice(&wrapped_key);
+
+    Ok(output)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use cw_types_v010::encoding::Binary;
+    use enclave_crypto::sha_256;
+
+    fn encode_field(out: &mut Vec<u8>, field: u8, value: &[u8]) {
+        out.push(field << 3 | WIRE_TYPE_LEN as u8);
+        out.push(value.len() as u8);
+        out.extend_from_slice(value);
+    }
+
+    pub fn test_key_disclosure_request_parse() {
+        let mut msg = vec![];
+        encode_field(&mut msg, 1, b"gov");
+        encode_field(&mut msg, 2, b"secret1contract");
+        encode_field(&mut msg, 3, &[7u8; 32]);
+        encode_field(&mut msg, 4, &[2u8; 33]);
+        encode_field(&mut msg, 5, &[9u8; 64]);
+        msg.extend_from_slice(&[6 << 3 | WIRE_TYPE_VARINT as u8, 3]);
+
+        let request = KeyDisclosureRequest::parse(&msg).unwrap();
+        assert_eq!(request.authority, "gov");
+        assert_eq!(request.contract, "secret1contract");
+        assert_eq!(request.auditor_public_key, [7u8; 32]);
+        assert_eq!(request.admin_public_key, vec![2u8; 33]);
+        assert_eq!(request.admin_signature, vec![9u8; 64]);
+        assert_eq!(request.nonce, 3);
+
+        let mut bad = vec![];
+        encode_field(&mut bad, 3, &[7u8; 31]);
+        assert!(KeyDisclosureRequest::parse(&bad).is_err());
+
+        // must match GetContractDisclosureKey in x/compute
+        assert_eq!(
+            disclosure_key(&CanonicalAddr(Binary(vec![4u8; 20]))),
+            [&[0x10u8][..], &[4u8; 20]].concat()
+        );
+    }
+
+    pub fn test_key_disclosure_sign_bytes() {
+        let request = KeyDisclosureRequest {
+            contract: "secret1a".to_string(),
+            auditor_public_key: [1u8; 32],
+            nonce: 1,
+            ..Default::default()
+        };
+        let other_auditor = KeyDisclosureRequest {
+            auditor_public_key: [2u8; 32],
+            ..request.clone()
+        };
+        let other_nonce = KeyDisclosureRequest {
+            nonce: 2,
+            ..request.clone()
+        };
+
+        let sign_bytes = request.disclosure_sign_bytes("secret-4");
+        assert!(sign_bytes.starts_with(DISCLOSURE_SIGN_PREFIX));
+        assert_ne!(sign_bytes, other_auditor.disclosure_sign_bytes("secret-4"));
+        assert_ne!(sign_bytes, other_nonce.disclosure_sign_bytes("secret-4"));
+        assert_ne!(sign_bytes, request.disclosure_sign_bytes("pulsar-3"));
+    }
+
+    pub fn test_key_disclosure_auditor_can_unwrap() {
+        let contract_address: &[u8] = b"secret1contract";
+        let other_address: &[u8] = b"secret1other";
+
+        let auditor = KeyPair::new().unwrap();
+        let state_key = AESKey::new_from_slice(&sha_256(b"state key"));
+
+        let output =
+            encrypt_to_auditor(&state_key, &auditor.get_pubkey(), "secret1contract", 1).unwrap();
+
+        // every node wraps the key the same way, and a new nonce with a new ephemeral key
+        assert_eq!(
+            output,
+            encrypt_to_auditor(&state_key, &auditor.get_pubkey(), "secret1contract", 1).unwrap()
+        );
+        assert_ne!(
+            output[..PUBLIC_KEY_SIZE],
+            encrypt_to_auditor(&state_key, &auditor.get_pubkey(), "secret1contract", 2).unwrap()
+                [..PUBLIC_KEY_SIZE]
+        );
+
+        let mut ephemeral_public_key = [0u8; PUBLIC_KEY_SIZE];
+        ephemeral_public_key.copy_from_slice(&output[..PUBLIC_KEY_SIZE]);
+        let shared_secret = auditor.diffie_hellman(&ephemeral_public_key);
+        let wrapping_key =
+            AESKey::new_from_slice(&shared_secret).derive_key_from_this(DISCLOSURE_WRAPPING_DOMAIN);
+
+        let unwrapped = wrapping_key
+            .decrypt_siv(&output[PUBLIC_KEY_SIZE..], Some(&[contract_address]))
+            .unwrap();
+        assert_eq!(unwrapped.as_slice(), state_key.get());
+
+        // bound to the contract address
+        assert!(wrapping_key
+            .decrypt_siv(&output[PUBLIC_KEY_SIZE..], Some(&[other_address]))
+            .is_err());
+    }
+}
@@ -8,34 +8,139 @@ extern crate sgx_tstd as std;
 extern crate sgx_rand;
 extern crate sgx_types;
 
+#[cfg(any(feature = "audit", feature = "test"))]
+mod audit;
+mod big_int;
+mod calendar;
+mod call_stack;
+mod canonical_json;
+mod cbor_envelope;
+mod code_hash_registry;
+mod compression;
+#[cfg(any(feature = "conformance", feature = "test"))]
+mod conformance;
 mod contract_operations;
+mod contract_subkeys;
 mod contract_validation;
 mod cosmwasm_config;
+mod cron;
 mod db;
+#[cfg(any(feature = "differential-execution", feature = "test"))]
+#[cfg_attr(not(feature = "differential-execution"), allow(dead_code))]
+mod differential;
+mod enclave_features;
+mod enclave_params;
+mod encrypted_mempool;
 mod errors;
 mod execute_message;
+mod execution_receipt;
+mod execution_report;
 pub mod external;
+mod fixed_point;
+mod foreign_clients;
 mod gas;
+mod gov_messages;
+mod ibc_client_status;
 mod ibc_denom_utils;
+mod ibc_memo;
 mod ibc_message;
+mod ibc_sender_context;
 mod input_validation;
+mod instantiate_restrictions;
+mod interchain_accounts;
 mod io;
+mod job_message;
+mod jwt;
+mod key_disclosure;
+mod merkle_accumulator;
 mod message;
 mod message_utils;
+mod module_cache_snapshot;
+mod musig2;
+mod native_snip20;
+mod oblivious_storage;
+mod padding;
+mod poseidon;
+mod public_state;
+mod query_cache;
+mod query_cancellation;
 mod query_chain;
+mod query_chunks;
+mod query_limits;
+mod query_replay;
+mod query_session;
+mod quote_policy;
 mod random;
 mod reply_message;
+mod rsa;
+mod state_backup;
+mod state_commitment;
+mod state_reencryption;
+mod state_tree;
+mod submsg_errors;
+mod token_metadata;
 mod hardcoded_admins;
 pub(crate) mod types;
 #[cfg(feature = "wasm3")]
 pub mod wasm3;
 
-pub use contract_operations::{handle, init, query};
+pub use contract_operations::{handle, init, query, run_job};
 #[cfg(feature = "light-client-validation")]
 pub use contract_validation::{check_cert_in_current_block, check_tx_in_current_block};
+pub use job_message::{worker_report_data, SignedJobResult, WORKER_PUBKEY_SIZE};
 
 #[cfg(feature = "test")]
 pub mod tests {
+    use crate::audit;
+    use crate::big_int;
+    use crate::calendar;
+    use crate::call_stack;
+    use crate::canonical_json;
+    use crate::cbor_envelope;
+    use crate::code_hash_registry;
+    use crate::compression;
+    use crate::conformance;
+    use crate::contract_subkeys;
+    use crate::cron;
+    use crate::differential;
+    use crate::enclave_features;
+    use crate::enclave_params;
+    use crate::encrypted_mempool;
+    use crate::execution_receipt;
+    use crate::execution_report;
+    use crate::fixed_point;
+    use crate::foreign_clients;
+    use crate::gov_messages;
+    use crate::ibc_client_status;
+    use crate::ibc_memo;
+    use crate::ibc_sender_context;
+    use crate::instantiate_restrictions;
+    use crate::interchain_accounts;
+    use crate::job_message;
+    use crate::jwt;
+    use crate::key_disclosure;
+    use crate::merkle_accumulator;
+    use crate::module_cache_snapshot;
+    use crate::musig2;
+    use crate::native_snip20;
+    use crate::oblivious_storage;
+    use crate::padding;
+    use crate::poseidon;
+    use crate::public_state;
+    use crate::query_cache;
+    use crate::query_chunks;
+    use crate::query_limits;
+    use crate::query_replay;
+    use crate::query_session;
+    use crate::quote_policy;
+    use crate::random;
+    use crate::rsa;
+    use crate::state_backup;
+    use crate::state_commitment;
+    use crate::state_reencryption;
+    use crate::state_tree;
+    use crate::submsg_errors;
+    use crate::token_metadata;
     use crate::types;
 
     /// Catch failures like the standard test runner, and print similar information per test.
@@ -62,6 +167,146 @@ pub mod tests {
 
         count_failures!(failures, {
             types::tests::test_new_from_slice();
+            audit::tests::test_audit_transcript_encoding();
+            audit::tests::test_audit_transcript_truncation();
+            big_int::tests::test_big_int_uint512();
+            big_int::tests::test_big_int_int512();
+            big_int::tests::test_big_int_modexp();
+            calendar::tests::test_calendar_civil_days();
+            calendar::tests::test_calendar_add_months();
+            calendar::tests::test_calendar_truncate_weekday();
+            call_stack::tests::test_call_stack_callback_sig();
+            call_stack::tests::test_call_stack_reentrancy();
+            call_stack::tests::test_call_stack_attach();
+            canonical_json::tests::test_canonical_json_rfc8785_sample();
+            canonical_json::tests::test_canonical_json_key_order();
+            canonical_json::tests::test_canonical_json_numbers();
+            canonical_json::tests::test_canonical_json_rejects();
+            cbor_envelope::tests::test_cbor_envelope_transcode();
+            cbor_envelope::tests::test_cbor_envelope_json_passthrough();
+            cbor_envelope::tests::test_cbor_envelope_rejects();
+            cbor_envelope::tests::test_cbor_envelope_trailing_empty_array();
+            code_hash_registry::tests::test_code_hash_registry_migrations();
+            code_hash_registry::tests::test_code_hash_registry_reverted_instantiate();
+            code_hash_registry::tests::test_code_hash_registry_serialization();
+            compression::tests::test_compression_roundtrip();
+            compression::tests::test_decompression_output_cap();
+            compression::tests::test_decompression_rejects_invalid_data();
+            conformance::tests::test_conformance_trace_encoding();
+            contract_subkeys::tests::test_contract_subkey_derivation();
+            contract_subkeys::tests::test_contract_subkey_decrypt();
+            cron::tests::test_contract_cron_schedule();
+            cron::tests::test_contract_cron_callbacks();
+            differential::tests::test_differential_replay();
+            differential::tests::test_differential_recording_dropped();
+            differential::tests::test_differential_mismatches();
+            enclave_features::tests::test_enclave_features_encoding();
+            enclave_params::tests::test_enclave_params_parse();
+            enclave_params::tests::test_enclave_params_values();
+            enclave_params::tests::test_enclave_params_validate();
+            encrypted_mempool::tests::test_encrypted_tx_parse();
+            encrypted_mempool::tests::test_encrypted_tx_decrypt();
+            encrypted_mempool::tests::test_encrypted_tx_inclusion_window();
+            execution_receipt::tests::test_execution_receipt_signature();
+            execution_receipt::tests::test_execution_receipt_attach();
+            execution_report::tests::test_host_gas_meter();
+            execution_report::tests::test_attach_execution_report();
+            fixed_point::tests::test_fixed_point_mul_div_rounding();
+            fixed_point::tests::test_fixed_point_ln_exp();
+            fixed_point::tests::test_fixed_point_pow();
+            foreign_clients::tests::test_foreign_client_create_parse();
+            foreign_clients::tests::test_foreign_client_update_parse();
+            foreign_clients::tests::test_foreign_client_keys();
+            gov_messages::tests::test_gov_authority_address();
+            ibc_client_status::tests::test_ibc_client_status();
+            ibc_client_status::tests::test_ibc_client_status_client_state();
+            ibc_memo::tests::test_ibc_memo_envelope_parse();
+            ibc_memo::tests::test_ibc_memo_plaintext_passthrough();
+            ibc_sender_context::tests::test_ibc_sender_context_scoped_nonce();
+            ibc_sender_context::tests::test_ibc_sender_context_ignores_other_msgs();
+            instantiate_restrictions::tests::test_instantiate_restrictions_from_params();
+            instantiate_restrictions::tests::test_instantiate_restrictions_deny_only();
+            interchain_accounts::tests::test_interchain_account_controller();
+            io::tests::test_disclosed_attribute_nonces();
+            job_message::tests::test_job_result_signature();
+            job_message::tests::test_job_result_signature_binds_fields();
+            job_message::tests::test_job_result_wrong_contract();
+            job_message::tests::test_job_input_seal();
+            job_message::tests::test_job_worker_report();
+            job_message::tests::test_delivered_job_key();
+            jwt::tests::test_jwt_verify();
+            jwt::tests::test_jwt_rejects_forgeries();
+            jwt::tests::test_jwt_parse_jwk();
+            key_disclosure::tests::test_key_disclosure_request_parse();
+            key_disclosure::tests::test_key_disclosure_sign_bytes();
+            key_disclosure::tests::test_key_disclosure_auditor_can_unwrap();
+            merkle_accumulator::tests::test_merkle_accumulator_append_and_prove();
+            merkle_accumulator::tests::test_merkle_accumulator_logarithmic_accesses();
+            merkle_accumulator::tests::test_merkle_accumulator_rejects_invalid_inputs();
+            module_cache_snapshot::tests::test_module_cache_snapshot_signature();
+            module_cache_snapshot::tests::test_module_cache_snapshot_digest();
+            musig2::tests::test_musig2_key_agg_vectors();
+            musig2::tests::test_musig2_key_agg_rejects_keys();
+            musig2::tests::test_schnorr_verify();
+            native_snip20::tests::test_native_snip20_parse();
+            native_snip20::tests::test_native_snip20_approved_code_hashes();
+            native_snip20::tests::test_native_snip20_response_padding();
+            native_snip20::tests::test_native_snip20_balance_layout();
+            oblivious_storage::tests::test_oblivious_bucket_serialization();
+            oblivious_storage::tests::test_oblivious_decoys();
+            oblivious_storage::tests::test_oblivious_namespaces();
+            padding::tests::test_padding_buckets();
+            padding::tests::test_padding_roundtrip();
+            padding::tests::test_padded_message_marker();
+            padding::tests::test_padding_bucket_params();
+            padding::tests::test_unpad_plain_messages();
+            poseidon::tests::test_poseidon_bn254_matches_circomlib();
+            poseidon::tests::test_poseidon_bls12_381_matches_reference();
+            poseidon::tests::test_poseidon_rejects_invalid_inputs();
+            public_state::tests::test_integrity_only_namespaces();
+            public_state::tests::test_public_namespaces_taint();
+            public_state::tests::test_public_value_integrity();
+            query_cache::tests::test_query_cache_hit();
+            query_cache::tests::test_query_cache_block_boundary();
+            query_cache::tests::test_query_cache_limits();
+            query_cancellation::tests::test_query_cancellation();
+            query_cancellation::tests::test_query_cancellation_after_return();
+            query_chunks::tests::test_query_chunks_small_response();
+            query_chunks::tests::test_query_chunks_reassemble();
+            query_chunks::tests::test_query_chunks_eviction();
+            query_limits::tests::test_query_limits_defaults();
+            query_limits::tests::test_query_limits_only_off_chain();
+            query_limits::tests::test_query_deadline();
+            query_replay::tests::test_query_replay_rejects_replays();
+            query_replay::tests::test_query_replay_window_slides();
+            query_replay::tests::test_query_replay_window_serialization();
+            query_session::tests::test_query_session_resolve();
+            query_session::tests::test_query_session_regular_message();
+            query_session::tests::test_query_session_expiry_bounds();
+            quote_policy::tests::test_quote_policy_parse();
+            quote_policy::tests::test_quote_policy_check();
+            quote_policy::tests::test_quote_summary();
+            random::tests::test_past_random_window();
+            rsa::tests::test_rsa_pkcs1v15_verify();
+            rsa::tests::test_rsa_pss_verify();
+            rsa::tests::test_rsa_rejects_encodings();
+            state_backup::tests::test_state_backup_authorization_parse();
+            state_backup::tests::test_state_backup_pairs_encoding();
+            state_backup::tests::test_state_backup_chunk_bound_to_key();
+            state_commitment::tests::test_state_commitment_root();
+            state_commitment::tests::test_state_commitment_leaves_ascending();
+            state_commitment::tests::test_state_commitment_progress_sealing();
+            state_reencryption::tests::test_state_reencryption_progress();
+            state_reencryption::tests::test_state_reencryption_progress_sealing();
+            state_tree::tests::test_state_tree_updates_and_proofs();
+            state_tree::tests::test_state_tree_rejects_invalid_proofs();
+            state_tree::tests::test_state_tree_restarts_after_migrations();
+            submsg_errors::tests::test_submsg_error_classification();
+            submsg_errors::tests::test_submsg_error_encoding();
+            token_metadata::tests::test_token_metadata_permit_sign_bytes();
+            token_metadata::tests::test_token_metadata_viewing_key();
+            token_metadata::tests::test_token_metadata_permit();
+            token_metadata::tests::test_token_metadata_constant_time_eq();
         });
 
         if failures != 0 {
@@ -72,3 +317,12 @@ pub mod tests {
 
 #[cfg(all(feature = "go-tests", feature = "production"))]
 compile_error!("Cannot use 'go-tests' & 'production' features together.");
+
+#[cfg(all(feature = "conformance", feature = "production"))]
+compile_error!("Cannot use 'conformance' & 'production' features together.");
+
+#[cfg(all(feature = "audit", feature = "production"))]
+compile_error!("Cannot use 'audit' & 'production' features together.");
+
+#[cfg(all(feature = "differential-execution", feature = "production"))]
+compile_error!("Cannot use 'differential-execution' & 'production' features together.");
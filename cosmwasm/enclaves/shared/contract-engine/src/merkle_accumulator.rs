@@ -0,0 +1,577 @@
+//! Incremental Merkle accumulators managed by the engine.
+//!
+//! Contracts that keep Merkle trees of commitments in wasm pay for every node they hash and
+//! every read and write of encrypted storage. An accumulator is an append-only tree of fixed
+//! depth, stored under a name in the contract's own encrypted state:
+//!
+//! * The tree itself is stored under `name || ACCUMULATOR_KEY_MARKER`, and every node under that
+//!   key followed by its level and index. Leaves are level 0.
+//! * Appending a leaf reads its left siblings and rewrites its path to the root, so it takes at
+//!   most `depth + 1` reads and `depth + 2` writes. Nodes that were never written are the roots of
+//!   empty subtrees, of which the leaves are 0.
+//! * Nodes are hashed with SHA-256 of the concatenated children, or with Poseidon over BN254, so
+//!   the roots match the ones zk circuits compute.
+//!
+//! Failures the contract can handle are returned as `MerkleError`s, while failures of storage stop
+//! the contract like they do for `db_read` and `db_write`.
+
+use std::convert::TryInto;
+
+use lazy_static::lazy_static;
+
+use enclave_crypto::sha_256;
+use enclave_ffi_types::Ctx;
+use enclave_utils::kv_cache::KvCache;
+
+use crate::contract_validation::ContractKey;
+use crate::db::{read_from_encrypted_state, remove_from_encrypted_state};
+use crate::errors::{WasmEngineError, WasmEngineResult};
+use crate::gas::{READ_BASE_GAS, WRITE_BASE_GAS};
+use crate::poseidon::{self, Curve};
+
+/// Error codes returned to contracts by the accumulator host functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum MerkleError {
+    /// The name is empty, the depth or hash is unknown, or a leaf or proof has the wrong length or
+    /// isn't a field element
+    InvalidInput = 1,
+    AlreadyExists = 2,
+    NotFound = 3,
+    /// The tree has `2^depth` leaves
+    TreeFull = 4,
+    /// The index isn't the index of a leaf that was appended
+    IndexOutOfRange = 5,
+    InvalidProof = 6,
+}
+
+/// Leaves and nodes are 32 bytes
+pub const NODE_SIZE: usize = 32;
+
+pub const MAX_TREE_DEPTH: u32 = 32;
+
+/// Separates accumulator keys from the keys a contract writes itself under the same name
+const ACCUMULATOR_KEY_MARKER: &[u8] = b"\xffmerkle";
+
+/// The length of a stored tree: its hash, its depth and its number of leaves
+const TREE_SIZE: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MerkleHash {
+    Sha256,
+    PoseidonBn254,
+}
+
+lazy_static! {
+    /// The roots of empty subtrees of each height, which are computed once since there are only
+    /// `MAX_TREE_DEPTH + 1` of them
+    static ref SHA_256_EMPTY_ROOTS: Vec<Vec<u8>> = MerkleHash::Sha256.empty_roots();
+    static ref POSEIDON_BN254_EMPTY_ROOTS: Vec<Vec<u8>> = MerkleHash::PoseidonBn254.empty_roots();
+}
+
+impl MerkleHash {
+    pub fn from_u32(hash: u32) -> Result<Self, MerkleError> {
+        match hash {
+            0 => Ok(MerkleHash::Sha256),
+            1 => Ok(MerkleHash::PoseidonBn254),
+            _ => Err(MerkleError::InvalidInput),
+        }
+    }
+
+    fn is_valid_node(self, node: &[u8]) -> bool {
+        match self {
+            MerkleHash::Sha256 => node.len() == NODE_SIZE,
+            MerkleHash::PoseidonBn254 => poseidon::is_field_element(Curve::Bn254, node),
+        }
+    }
+
+    /// The children are valid nodes
+    fn hash_children(self, left: &[u8], right: &[u8]) -> Vec<u8> {
+        match self {
+            MerkleHash::Sha256 => sha_256(&[left, right].concat()).to_vec(),
+            MerkleHash::PoseidonBn254 => {
+                poseidon::poseidon_hash(Curve::Bn254, &[left.to_vec(), right.to_vec()]).unwrap()
+            }
+        }
+    }
+
+    fn empty_roots(self) -> Vec<Vec<u8>> {
+        let mut roots = vec![vec![0u8; NODE_SIZE]];
+        for height in 0..MAX_TREE_DEPTH as usize {
+            roots.push(self.hash_children(&roots[height], &roots[height]));
+        }
+        roots
+    }
+
+    fn empty_root(self, height: u32) -> &'static [u8] {
+        match self {
+            MerkleHash::Sha256 => &SHA_256_EMPTY_ROOTS[height as usize],
+            MerkleHash::PoseidonBn254 => &POSEIDON_BN254_EMPTY_ROOTS[height as usize],
+        }
+    }
+}
+
+/// The storage accumulators, and the trees of `state_tree`, are kept in
+pub trait MerkleStorage {
+    fn read(&mut self, key: &[u8]) -> WasmEngineResult<Option<Vec<u8>>>;
+    fn write(&mut self, key: &[u8], value: &[u8]);
+    fn remove(&mut self, key: &[u8]) -> WasmEngineResult<()>;
+}
+
+fn tree_key(name: &[u8]) -> Vec<u8> {
+    let mut key = name.to_vec();
+    key.extend_from_slice(ACCUMULATOR_KEY_MARKER);
+    key
+}
+
+fn node_key(name: &[u8], level: u32, index: u64) -> Vec<u8> {
+    let mut key = tree_key(name);
+    key.push(level as u8);
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MerkleTree {
+    pub hash: MerkleHash,
+    pub depth: u32,
+    pub leaves: u64,
+}
+
+impl MerkleTree {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = vec![self.hash as u8, self.depth as u8];
+        bytes.extend_from_slice(&self.leaves.to_be_bytes());
+        bytes
+    }
+
+    fn deserialize(bytes: &[u8]) -> WasmEngineResult<Self> {
+        if bytes.len() != TREE_SIZE {
+            return Err(WasmEngineError::DeserializationError);
+        }
+        let tree = MerkleTree {
+            hash: MerkleHash::from_u32(bytes[0] as u32)
+                .map_err(|_| WasmEngineError::DeserializationError)?,
+            depth: bytes[1] as u32,
+            leaves: u64::from_be_bytes(bytes[2..].try_into().unwrap()),
+        };
+        if tree.depth == 0 || tree.depth > MAX_TREE_DEPTH || tree.leaves > tree.capacity() {
+            return Err(WasmEngineError::DeserializationError);
+        }
+        Ok(tree)
+    }
+
+    fn capacity(&self) -> u64 {
+        1 << self.depth
+    }
+
+    /// Creates an empty tree of depth 1 to `MAX_TREE_DEPTH`
+    pub fn create<S: MerkleStorage>(
+        storage: &mut S,
+        name: &[u8],
+        depth: u32,
+        hash: u32,
+    ) -> WasmEngineResult<Result<Self, MerkleError>> {
+        let hash = match MerkleHash::from_u32(hash) {
+            Ok(hash) if !name.is_empty() && depth != 0 && depth <= MAX_TREE_DEPTH => hash,
+            _ => return Ok(Err(MerkleError::InvalidInput)),
+        };
+        if storage.read(&tree_key(name))?.is_some() {
+            return Ok(Err(MerkleError::AlreadyExists));
+        }
+
+        let tree = MerkleTree {
+            hash,
+            depth,
+            leaves: 0,
+        };
+        storage.write(&tree_key(name), &tree.serialize());
+        Ok(Ok(tree))
+    }
+
+    pub fn load<S: MerkleStorage>(
+        storage: &mut S,
+        name: &[u8],
+    ) -> WasmEngineResult<Result<Self, MerkleError>> {
+        if name.is_empty() {
+            return Ok(Err(MerkleError::InvalidInput));
+        }
+        match storage.read(&tree_key(name))? {
+            Some(bytes) => Ok(Ok(MerkleTree::deserialize(&bytes)?)),
+            None => Ok(Err(MerkleError::NotFound)),
+        }
+    }
+
+    fn read_node<S: MerkleStorage>(
+        &self,
+        storage: &mut S,
+        name: &[u8],
+        level: u32,
+        index: u64,
+    ) -> WasmEngineResult<Vec<u8>> {
+        // Nodes right of the last leaf were never written
+        if index >= (self.leaves + (1 << level) - 1) >> level {
+            return Ok(self.hash.empty_root(level).to_vec());
+        }
+        match storage.read(&node_key(name, level, index))? {
+            Some(node) if self.hash.is_valid_node(&node) => Ok(node),
+            _ => Err(WasmEngineError::DeserializationError),
+        }
+    }
+
+    /// Appends a leaf, and returns its index
+    pub fn append<S: MerkleStorage>(
+        &mut self,
+        storage: &mut S,
+        name: &[u8],
+        leaf: &[u8],
+    ) -> WasmEngineResult<Result<u64, MerkleError>> {
+        if !self.hash.is_valid_node(leaf) {
+            return Ok(Err(MerkleError::InvalidInput));
+        }
+        if self.leaves == self.capacity() {
+            return Ok(Err(MerkleError::TreeFull));
+        }
+
+        // The path is computed before anything is written, so a failed read leaves no half
+        // updated tree behind
+        let index = self.leaves;
+        let mut path = vec![leaf.to_vec()];
+        for level in 0..self.depth {
+            let position = index >> level;
+            let node = &path[level as usize];
+            let parent = if position & 1 == 1 {
+                let sibling = self.read_node(storage, name, level, position - 1)?;
+                self.hash.hash_children(&sibling, node)
+            } else {
+                self.hash.hash_children(node, self.hash.empty_root(level))
+            };
+            path.push(parent);
+        }
+
+        for (level, node) in path.iter().enumerate() {
+            storage.write(&node_key(name, level as u32, index >> level), node);
+        }
+        self.leaves += 1;
+        storage.write(&tree_key(name), &self.serialize());
+
+        Ok(Ok(index))
+    }
+
+    pub fn root<S: MerkleStorage>(
+        &self,
+        storage: &mut S,
+        name: &[u8],
+    ) -> WasmEngineResult<Vec<u8>> {
+        self.read_node(storage, name, self.depth, 0)
+    }
+
+    /// Returns the siblings of the path from a leaf to the root, from the leaf up
+    pub fn prove<S: MerkleStorage>(
+        &self,
+        storage: &mut S,
+        name: &[u8],
+        index: u64,
+    ) -> WasmEngineResult<Result<Vec<u8>, MerkleError>> {
+        if index >= self.leaves {
+            return Ok(Err(MerkleError::IndexOutOfRange));
+        }
+
+        let mut proof = Vec::with_capacity(self.depth as usize * NODE_SIZE);
+        for level in 0..self.depth {
+            proof.extend(self.read_node(storage, name, level, (index >> level) ^ 1)?);
+        }
+        Ok(Ok(proof))
+    }
+
+    /// Checks that `leaf` is the leaf at `index`, with a proof as `prove` returns it
+    pub fn verify<S: MerkleStorage>(
+        &self,
+        storage: &mut S,
+        name: &[u8],
+        leaf: &[u8],
+        index: u64,
+        proof: &[u8],
+    ) -> WasmEngineResult<Result<(), MerkleError>> {
+        let siblings: Vec<&[u8]> = proof.chunks(NODE_SIZE).collect();
+        if proof.len() != self.depth as usize * NODE_SIZE
+            || !self.hash.is_valid_node(leaf)
+            || !siblings
+                .iter()
+                .all(|sibling| self.hash.is_valid_node(sibling))
+        {
+            return Ok(Err(MerkleError::InvalidInput));
+        }
+        if index >= self.leaves {
+            return Ok(Err(MerkleError::IndexOutOfRange));
+        }
+
+        let mut node = leaf.to_vec();
+        for (level, sibling) in siblings.iter().enumerate() {
+            node = if (index >> level) & 1 == 1 {
+                self.hash.hash_children(sibling, &node)
+            } else {
+                self.hash.hash_children(&node, sibling)
+            };
+        }
+
+        if node == self.root(storage, name)? {
+            Ok(Ok(()))
+        } else {
+            Ok(Err(MerkleError::InvalidProof))
+        }
+    }
+}
+
+/// Access to the contract's encrypted state during a single call. Reads are cached, and the gas
+/// of every access is summed up for the host function to charge.
+pub struct MerkleStore<'a> {
+    pub context: &'a Ctx,
+    pub contract_key: &'a ContractKey,
+    pub kv_cache: &'a mut KvCache,
+    pub has_write_permissions: bool,
+    pub encryption_salt: &'a [u8],
+    /// The base gas of the accesses, and the pseudo gas of the writes, which is refunded when the
+    /// cache is flushed
+    pub gas_used: u64,
+    /// The storage gas of the reads and removes
+    pub gas_used_externally: u64,
+}
+
+impl<'a> MerkleStorage for MerkleStore<'a> {
+    fn read(&mut self, key: &[u8]) -> WasmEngineResult<Option<Vec<u8>>> {
+        self.gas_used = self.gas_used.saturating_add(READ_BASE_GAS);
+        if let Some(value) = self.kv_cache.read(key) {
+            return Ok(Some(value));
+        }
+
+        let (value, gas_used) = read_from_encrypted_state(
+            key,
+            self.context,
+            self.contract_key,
+            self.has_write_permissions,
+            self.kv_cache,
+            self.encryption_salt,
+        )?;
+        self.gas_used_externally = self.gas_used_externally.saturating_add(gas_used);
+        if let Some(value) = &value {
+            self.kv_cache.store_in_ro_cache(key, value);
+        }
+
+        Ok(value)
+    }
+
+    fn write(&mut self, key: &[u8], value: &[u8]) {
+        let (_, pseudo_gas) = self.kv_cache.write(key, value);
+        self.gas_used = self
+            .gas_used
+            .saturating_add(WRITE_BASE_GAS)
+            .saturating_add(pseudo_gas);
+    }
+
+    fn remove(&mut self, key: &[u8]) -> WasmEngineResult<()> {
+        self.gas_used = self.gas_used.saturating_add(WRITE_BASE_GAS);
+        // Also remove the key from the cache to avoid rewriting it
+        self.kv_cache.remove(key);
+
+        let gas_used = remove_from_encrypted_state(key, self.context, self.contract_key)?;
+        self.gas_used_externally = self.gas_used_externally.saturating_add(gas_used);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    /// Storage for tests, which counts the accesses
+    #[derive(Default)]
+    pub struct TestStorage {
+        pub values: BTreeMap<Vec<u8>, Vec<u8>>,
+        pub reads: usize,
+        pub writes: usize,
+    }
+
+    impl MerkleStorage for TestStorage {
+        fn read(&mut self, key: &[u8]) -> WasmEngineResult<Option<Vec<u8>>> {
+            self.reads += 1;
+            Ok(self.values.get(key).cloned())
+        }
+
+        fn write(&mut self, key: &[u8], value: &[u8]) {
+            self.writes += 1;
+            self.values.insert(key.to_vec(), value.to_vec());
+        }
+
+        fn remove(&mut self, key: &[u8]) -> WasmEngineResult<()> {
+            self.writes += 1;
+            self.values.remove(key);
+            Ok(())
+        }
+    }
+
+    fn leaf(value: u8) -> Vec<u8> {
+        let mut leaf = vec![0u8; NODE_SIZE];
+        leaf[NODE_SIZE - 1] = value;
+        leaf
+    }
+
+    /// The root of a full tree of `leaves`, computed from scratch
+    fn naive_root(hash: MerkleHash, depth: u32, leaves: &[Vec<u8>]) -> Vec<u8> {
+        let mut level = leaves.to_vec();
+        level.resize(1 << depth, vec![0u8; NODE_SIZE]);
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| hash.hash_children(&pair[0], &pair[1]))
+                .collect();
+        }
+        level.swap_remove(0)
+    }
+
+    pub fn test_merkle_accumulator_append_and_prove() {
+        for &hash in [MerkleHash::Sha256, MerkleHash::PoseidonBn254].iter() {
+            let mut storage = TestStorage::default();
+            let mut tree = MerkleTree::create(&mut storage, b"notes", 3, hash as u32)
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                tree.root(&mut storage, b"notes").unwrap(),
+                naive_root(hash, 3, &[])
+            );
+
+            let mut leaves = vec![];
+            for value in 1..=8 {
+                let index = tree.append(&mut storage, b"notes", &leaf(value)).unwrap();
+                assert_eq!(index, Ok(leaves.len() as u64));
+                leaves.push(leaf(value));
+
+                let tree = MerkleTree::load(&mut storage, b"notes").unwrap().unwrap();
+                assert_eq!(tree.leaves, leaves.len() as u64);
+                assert_eq!(
+                    tree.root(&mut storage, b"notes").unwrap(),
+                    naive_root(hash, 3, &leaves)
+                );
+                for (index, leaf) in leaves.iter().enumerate() {
+                    let proof = tree
+                        .prove(&mut storage, b"notes", index as u64)
+                        .unwrap()
+                        .unwrap();
+                    assert_eq!(proof.len(), 3 * NODE_SIZE);
+                    assert_eq!(
+                        tree.verify(&mut storage, b"notes", leaf, index as u64, &proof)
+                            .unwrap(),
+                        Ok(())
+                    );
+                }
+            }
+
+            assert_eq!(
+                tree.append(&mut storage, b"notes", &leaf(9)).unwrap(),
+                Err(MerkleError::TreeFull)
+            );
+        }
+
+        // SHA-256 of two empty nodes, 32 times
+        let mut storage = TestStorage::default();
+        let tree = MerkleTree::create(&mut storage, b"deposits", 32, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            hex::encode(tree.root(&mut storage, b"deposits").unwrap()),
+            "c6f67e02e6e4e1bdefb994c6098953f34636ba2b6ca20a4721d2b26a886722ff"
+        );
+    }
+
+    pub fn test_merkle_accumulator_logarithmic_accesses() {
+        let mut storage = TestStorage::default();
+        let mut tree = MerkleTree::create(&mut storage, b"notes", 20, 0)
+            .unwrap()
+            .unwrap();
+        for value in 0..100 {
+            let (reads, writes) = (storage.reads, storage.writes);
+            tree.append(&mut storage, b"notes", &leaf(value))
+                .unwrap()
+                .unwrap();
+            assert!(storage.reads - reads <= 20);
+            assert_eq!(storage.writes - writes, 22);
+        }
+    }
+
+    pub fn test_merkle_accumulator_rejects_invalid_inputs() {
+        let mut storage = TestStorage::default();
+        for &(name, depth, hash) in [
+            (&b""[..], 3, 0),
+            (&b"notes"[..], 0, 0),
+            (&b"notes"[..], MAX_TREE_DEPTH + 1, 0),
+            (&b"notes"[..], 3, 2),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                MerkleTree::create(&mut storage, name, depth, hash).unwrap(),
+                Err(MerkleError::InvalidInput)
+            );
+        }
+        assert_eq!(
+            MerkleTree::load(&mut storage, b"notes").unwrap(),
+            Err(MerkleError::NotFound)
+        );
+
+        let mut tree = MerkleTree::create(&mut storage, b"notes", 2, 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            MerkleTree::create(&mut storage, b"notes", 2, 1).unwrap(),
+            Err(MerkleError::AlreadyExists)
+        );
+        // Not a field element of BN254
+        assert_eq!(
+            tree.append(&mut storage, b"notes", &[0xff; NODE_SIZE])
+                .unwrap(),
+            Err(MerkleError::InvalidInput)
+        );
+        assert_eq!(
+            tree.append(&mut storage, b"notes", &[1; 31]).unwrap(),
+            Err(MerkleError::InvalidInput)
+        );
+        assert_eq!(
+            tree.prove(&mut storage, b"notes", 0).unwrap(),
+            Err(MerkleError::IndexOutOfRange)
+        );
+
+        tree.append(&mut storage, b"notes", &leaf(1))
+            .unwrap()
+            .unwrap();
+        tree.append(&mut storage, b"notes", &leaf(2))
+            .unwrap()
+            .unwrap();
+        let proof = tree.prove(&mut storage, b"notes", 1).unwrap().unwrap();
+        assert_eq!(
+            tree.verify(&mut storage, b"notes", &leaf(3), 1, &proof)
+                .unwrap(),
+            Err(MerkleError::InvalidProof)
+        );
+        assert_eq!(
+            tree.verify(&mut storage, b"notes", &leaf(2), 0, &proof)
+                .unwrap(),
+            Err(MerkleError::InvalidProof)
+        );
+        assert_eq!(
+            tree.verify(&mut storage, b"notes", &leaf(2), 2, &proof)
+                .unwrap(),
+            Err(MerkleError::IndexOutOfRange)
+        );
+        assert_eq!(
+            tree.verify(&mut storage, b"notes", &leaf(2), 1, &proof[NODE_SIZE..])
+                .unwrap(),
+            Err(MerkleError::InvalidInput)
+        );
+
+        // A tree the contract overwrote is a storage failure
+        storage.write(&tree_key(b"notes"), b"garbage");
+        assert!(MerkleTree::load(&mut storage, b"notes").is_err());
+    }
+}
@@ -1,13 +1,17 @@
 use log::trace;
 
+use cw_types_v010::types::HumanAddr;
 use enclave_cosmos_types::types::HandleType;
 use enclave_ffi_types::EnclaveError;
 
+use crate::cron::parse_cron_message;
 use crate::execute_message::parse_execute_message;
+use crate::ibc_memo::parse_ibc_hooks_incoming_transfer_message;
 use crate::ibc_message::{
     parse_ibc_receive_message, parse_plaintext_ibc_protocol_message,
     parse_plaintext_ibc_validated_message,
 };
+use crate::job_message::parse_job_result_message;
 use crate::reply_message::parse_reply_message;
 use crate::types::ParsedMessage;
 
@@ -15,6 +19,8 @@ use crate::types::ParsedMessage;
 pub fn parse_message(
     message: &[u8],
     handle_type: &HandleType,
+    contract_address: &HumanAddr,
+    block_time_ns: u64,
 ) -> Result<ParsedMessage, EnclaveError> {
     match handle_type {
         HandleType::HANDLE_TYPE_EXECUTE => parse_execute_message(message),
@@ -31,13 +37,19 @@ pub fn parse_message(
             parse_plaintext_ibc_protocol_message(message)
         }
         HandleType::HANDLE_TYPE_IBC_PACKET_RECEIVE => parse_ibc_receive_message(message),
-        HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_INCOMING_TRANSFER
-        | HandleType::HANDLE_TYPE_IBC_PACKET_ACK
+        HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_INCOMING_TRANSFER => {
+            parse_ibc_hooks_incoming_transfer_message(message)
+        }
+        HandleType::HANDLE_TYPE_IBC_PACKET_ACK
         | HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_ACK
         | HandleType::HANDLE_TYPE_IBC_PACKET_TIMEOUT
         | HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_TIMEOUT => {
             parse_plaintext_ibc_validated_message(message)
         }
+        HandleType::HANDLE_TYPE_JOB_RESULT => {
+            parse_job_result_message(message, contract_address, block_time_ns)
+        }
+        HandleType::HANDLE_TYPE_CRON => parse_cron_message(message),
     }
 }
 
@@ -0,0 +1,194 @@
+//! Signed snapshots of the module cache, for comparing the artifacts that nodes run contracts with.
+//!
+//! When nodes disagree on the result of a block, one of the first questions is whether they ran
+//! the same instrumented code. `ecall_module_cache_snapshot` lists every module in the enclave's
+//! cache, sorted by code hash, with the hash of its instrumented artifact and the
+//! `ENGINE_VERSION` that built it:
+//!
+//! ```json
+//! {"entries":[{"code_hash":"<base64>","artifact_hash":"<base64>","engine_version":1}],
+//!  "digest":"<base64>","node_public_key":"<base64>","public_key":"<base64>",
+//!  "signature":"<base64>"}
+//! ```
+//!
+//! The digest is the sha256 of the entries, so two nodes with the same modules have the same
+//! digest. Snapshots are signed with a secp256k1 key derived from the consensus seed, over the
+//! digest and the registration key of the node, so an operator can check that a snapshot they
+//! were sent came from the enclave of that node, and not from the host.
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use cw_types_v010::encoding::Binary;
+use enclave_crypto::{sha_256, Kdf, HASH_SIZE};
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::KEY_MANAGER;
+
+use crate::wasm3::module_cache::{cached_artifacts, ENGINE_VERSION};
+
+pub const MODULE_CACHE_SNAPSHOT_DOMAIN: &[u8] = b"secret-module-cache-snapshot-v1";
+const SIGNING_KEY_DOMAIN: &[u8] = b"module_cache_snapshot_signing";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CachedModule {
+    pub code_hash: Binary,
+    /// The sha256 of the module after it was validated and instrumented
+    pub artifact_hash: Binary,
+    pub engine_version: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ModuleCacheSnapshot {
+    /// Sorted by code hash
+    pub entries: Vec<CachedModule>,
+    /// sha256 of `code hash (32) || artifact hash (32) || engine version (4, big endian)` of every
+    /// entry, in order
+    pub digest: Binary,
+    /// The registration key of the node whose enclave took the snapshot
+    pub node_public_key: Binary,
+    /// Compressed secp256k1 public key, the same on every node
+    pub public_key: Binary,
+    /// Compact secp256k1 signature over `sha256(sign_bytes)`
+    pub signature: Binary,
+}
+
+fn digest(entries: &[CachedModule]) -> [u8; HASH_SIZE] {
+    let mut encoded = Vec::with_capacity(entries.len() * (2 * HASH_SIZE + 4));
+    for entry in entries {
+        encoded.extend_from_slice(entry.code_hash.as_slice());
+        encoded.extend_from_slice(entry.artifact_hash.as_slice());
+        encoded.extend_from_slice(&entry.engine_version.to_be_bytes());
+    }
+    sha_256(&encoded)
+}
+
+impl ModuleCacheSnapshot {
+    pub fn sign_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(MODULE_CACHE_SNAPSHOT_DOMAIN);
+        bytes.extend_from_slice(self.node_public_key.as_slice());
+        bytes.extend_from_slice(self.digest.as_slice());
+
+        bytes
+    }
+
+    pub fn sign(
+        artifacts: &[([u8; HASH_SIZE], [u8; HASH_SIZE])],
+        node_public_key: &[u8; 32],
+        signing_key: &[u8],
+    ) -> Result<Self, EnclaveError> {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let sk = secp256k1::SecretKey::from_slice(signing_key).map_err(|err| {
+            error!("module cache snapshot signing key is invalid: {:?}", err);
+            EnclaveError::InternalError
+        })?;
+
+        let entries: Vec<CachedModule> = artifacts
+            .iter()
+            .map(|(code_hash, artifact_hash)| CachedModule {
+                code_hash: Binary(code_hash.to_vec()),
+                artifact_hash: Binary(artifact_hash.to_vec()),
+                engine_version: ENGINE_VERSION,
+            })
+            .collect();
+
+        let mut snapshot = Self {
+            digest: Binary(digest(&entries).to_vec()),
+            entries,
+            node_public_key: Binary(node_public_key.to_vec()),
+            public_key: Binary(
+                secp256k1::PublicKey::from_secret_key(&secp, &sk)
+                    .serialize()
+                    .to_vec(),
+            ),
+            signature: Binary(vec![]),
+        };
+
+        let msg = secp256k1::Message::from_slice(&sha_256(&snapshot.sign_bytes()))
+            .map_err(|_| EnclaveError::InternalError)?;
+        snapshot.signature = Binary(secp.sign_ecdsa(&msg, &sk).serialize_compact().to_vec());
+
+        Ok(snapshot)
+    }
+}
+
+/// The key snapshots are signed with, derived from the genesis consensus seed
+fn signing_key() -> Result<[u8; 32], EnclaveError> {
+    let consensus_state_ikm = KEY_MANAGER.get_consensus_state_ikm().map_err(|_| {
+        warn!("can't sign a module cache snapshot without the consensus seed");
+        EnclaveError::InternalError
+    })?;
+
+    Ok(*consensus_state_ikm
+        .genesis
+        .derive_key_from_this(SIGNING_KEY_DOMAIN)
+        .get())
+}
+
+/// A signed snapshot of the module cache, as json
+pub fn encoded_snapshot() -> Result<Vec<u8>, EnclaveError> {
+    let node_public_key = KEY_MANAGER
+        .get_registration_key()
+        .map_err(|_| EnclaveError::InternalError)?
+        .get_pubkey();
+
+    let snapshot =
+        ModuleCacheSnapshot::sign(&cached_artifacts(), &node_public_key, &signing_key()?)?;
+
+    serde_json::to_vec(&snapshot).map_err(|err| {
+        error!("Failed to encode a module cache snapshot: {}", err);
+        EnclaveError::FailedToSerialize
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_module_cache_snapshot_signature() {
+        let artifacts = [
+            ([1u8; HASH_SIZE], [2u8; HASH_SIZE]),
+            ([3u8; HASH_SIZE], [4u8; HASH_SIZE]),
+        ];
+        let snapshot =
+            ModuleCacheSnapshot::sign(&artifacts, &[9u8; 32], &sha_256(b"signing key")).unwrap();
+        assert_eq!(snapshot.entries.len(), 2);
+        assert_eq!(
+            snapshot.entries[1].artifact_hash.as_slice(),
+            &[4u8; HASH_SIZE]
+        );
+        assert_eq!(snapshot.entries[0].engine_version, ENGINE_VERSION);
+
+        let secp = secp256k1::Secp256k1::verification_only();
+        let signature =
+            secp256k1::ecdsa::Signature::from_compact(snapshot.signature.as_slice()).unwrap();
+        let public_key = secp256k1::PublicKey::from_slice(snapshot.public_key.as_slice()).unwrap();
+        let msg = secp256k1::Message::from_slice(&sha_256(&snapshot.sign_bytes())).unwrap();
+        assert!(secp.verify_ecdsa(&msg, &signature, &public_key).is_ok());
+
+        // the signature binds the node
+        let mut tampered = snapshot.clone();
+        tampered.node_public_key = Binary(vec![8u8; 32]);
+        let msg = secp256k1::Message::from_slice(&sha_256(&tampered.sign_bytes())).unwrap();
+        assert!(secp.verify_ecdsa(&msg, &signature, &public_key).is_err());
+    }
+
+    pub fn test_module_cache_snapshot_digest() {
+        let key = sha_256(b"signing key");
+        let empty = ModuleCacheSnapshot::sign(&[], &[9u8; 32], &key).unwrap();
+        assert_eq!(empty.digest.as_slice(), &sha_256(&[]));
+
+        let one = [([1u8; HASH_SIZE], [2u8; HASH_SIZE])];
+        let other_artifact = [([1u8; HASH_SIZE], [5u8; HASH_SIZE])];
+
+        // the same modules give the same digest on any node
+        let snapshot = ModuleCacheSnapshot::sign(&one, &[9u8; 32], &key).unwrap();
+        let other_node = ModuleCacheSnapshot::sign(&one, &[8u8; 32], &key).unwrap();
+        assert_eq!(snapshot.digest, other_node.digest);
+
+        // and a different artifact of the same code doesn't
+        let diverged = ModuleCacheSnapshot::sign(&other_artifact, &[9u8; 32], &key).unwrap();
+        assert_ne!(snapshot.digest, diverged.digest);
+        assert_ne!(snapshot.digest, empty.digest);
+    }
+}
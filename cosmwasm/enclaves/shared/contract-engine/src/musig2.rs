@@ -0,0 +1,262 @@
+//! MuSig2 key aggregation and BIP-340 Schnorr verification, for contracts that verify a single
+//! signature of a group of signers instead of one signature per signer.
+//!
+//! Keys are aggregated as BIP-327 `KeyAgg` does, without tweaks: every key is weighted by a
+//! coefficient committing to the whole list, so a signer can't choose its key to cancel the
+//! others out. The list is ordered, since the aggregate key depends on the order of the keys;
+//! signers that don't agree on an order sort their keys first. The aggregate is returned as an
+//! x-only key, the way BIP-340 signatures are verified against it.
+//!
+//! The hashes of both steps live in their own BIP-340 tagged-hash domains, so a signature over
+//! one of them can never be mistaken for anything else the same keys sign.
+
+use secp256k1::{schnorr, Message, PublicKey, Scalar, Secp256k1, XOnlyPublicKey};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MuSig2Error {
+    /// The message hash isn't 32 bytes long
+    InvalidHashFormat,
+    /// The signature isn't 64 bytes long
+    InvalidSignatureFormat,
+    /// A key isn't a compressed secp256k1 point, or there are no keys or too many of them
+    InvalidPubkeyFormat,
+    /// The keys aggregate to the point at infinity
+    InfiniteAggregateKey,
+    /// The signature is well-formed, but isn't a signature of the message by the key
+    VerificationFailed,
+}
+
+/// The most keys that can be aggregated in one call
+pub const MAX_MUSIG2_KEYS: usize = 100;
+
+const COMPRESSED_PUBKEY_LENGTH: usize = 33;
+
+/// The order of the secp256k1 group, big-endian
+const CURVE_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// `SHA256(SHA256(tag) || SHA256(tag) || data)`, as defined by BIP-340
+fn tagged_hash(tag: &str, data: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = Sha256::digest(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag_hash);
+    hasher.update(tag_hash);
+    for chunk in data {
+        hasher.update(chunk);
+    }
+    hasher.finalize().into()
+}
+
+/// Reduces a hash modulo the group order. A 32-byte value is less than twice the order, so one
+/// subtraction is enough.
+fn reduce(hash: [u8; 32]) -> Scalar {
+    if let Ok(scalar) = Scalar::from_be_bytes(hash) {
+        return scalar;
+    }
+
+    let mut reduced = [0u8; 32];
+    let mut borrow = false;
+    for i in (0..32).rev() {
+        let (byte, borrowed_order) = hash[i].overflowing_sub(CURVE_ORDER[i]);
+        let (byte, borrowed_carry) = byte.overflowing_sub(borrow as u8);
+        reduced[i] = byte;
+        borrow = borrowed_order || borrowed_carry;
+    }
+    // Can't fail, since the hash was at least the order
+    Scalar::from_be_bytes(reduced).unwrap_or(Scalar::ZERO)
+}
+
+/// Aggregates compressed public keys as BIP-327 `KeyAgg` does, and returns the x-only aggregate
+pub fn key_agg(public_keys: &[&[u8]]) -> Result<[u8; 32], MuSig2Error> {
+    if public_keys.is_empty() || public_keys.len() > MAX_MUSIG2_KEYS {
+        return Err(MuSig2Error::InvalidPubkeyFormat);
+    }
+
+    let points = public_keys
+        .iter()
+        .map(|key| {
+            if key.len() != COMPRESSED_PUBKEY_LENGTH {
+                return Err(MuSig2Error::InvalidPubkeyFormat);
+            }
+            PublicKey::from_slice(key).map_err(|_| MuSig2Error::InvalidPubkeyFormat)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let list_hash = tagged_hash("KeyAgg list", public_keys);
+    // The first key that differs from the first one gets a coefficient of 1, which lets the
+    // signing protocol skip a multiplication for it
+    let second_key = public_keys.iter().find(|key| **key != public_keys[0]);
+
+    let secp = Secp256k1::verification_only();
+    let weighted = points
+        .into_iter()
+        .zip(public_keys)
+        .map(|(point, key)| {
+            if Some(key) == second_key {
+                return Ok(point);
+            }
+            let coefficient = reduce(tagged_hash("KeyAgg coefficient", &[&list_hash[..], *key]));
+            point
+                .mul_tweak(&secp, &coefficient)
+                .map_err(|_| MuSig2Error::InfiniteAggregateKey)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let aggregate = PublicKey::combine_keys(&weighted.iter().collect::<Vec<_>>())
+        .map_err(|_| MuSig2Error::InfiniteAggregateKey)?;
+
+    let mut x_only = [0u8; 32];
+    x_only.copy_from_slice(&aggregate.serialize()[1..]);
+    Ok(x_only)
+}
+
+/// Verifies a BIP-340 Schnorr signature of a 32-byte message by an x-only public key
+pub fn schnorr_verify(
+    message_hash: &[u8],
+    signature: &[u8],
+    public_key: &[u8],
+) -> Result<(), MuSig2Error> {
+    let message = Message::from_slice(message_hash).map_err(|_| MuSig2Error::InvalidHashFormat)?;
+    let signature = schnorr::Signature::from_slice(signature)
+        .map_err(|_| MuSig2Error::InvalidSignatureFormat)?;
+    let public_key =
+        XOnlyPublicKey::from_slice(public_key).map_err(|_| MuSig2Error::InvalidPubkeyFormat)?;
+
+    Secp256k1::verification_only()
+        .verify_schnorr(&signature, &message, &public_key)
+        .map_err(|_| MuSig2Error::VerificationFailed)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    /// The public keys of the BIP-327 `KeyAgg` test vectors
+    const BIP327_KEYS: [&str; 3] = [
+        "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+        "03dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+        "023590a94e768f8e1815c2f24b4d80a8e3149316c3518ce7b7ad338368d038ca66",
+    ];
+
+    /// The keys of secret keys 0x11111111, 0x22222222 and 0x33333333, and a signature of
+    /// `SHA256("musig2 test message")` by their aggregate
+    const SIGNER_KEYS: [&str; 3] = [
+        "031db231e09e5db79f2293128737d4175891eb656a1b004dbd0c41090464738114",
+        "02007f8862c874397e594cc81c35d206766bdc3a2cdba368530718b210c61fe691",
+        "034284a8b793e323145805cf313e3b559331149010fcec92558012949b3edcd346",
+    ];
+    const SIGNERS_AGGREGATE_KEY: &str =
+        "af17bf176a6727699e8b033a03acb1d4c767186c5c24d820c99330591065c31b";
+    const SIGNERS_MESSAGE_HASH: &str =
+        "a0815bda05c7982c23dfa2874ac986c05e7418350257efee6df0f7e18b3f2d96";
+    const SIGNERS_SIGNATURE: &str = concat!(
+        "b88f0c2ccd4fe1ea1aadf668fa448e32276c07f7f782fb29771253aa40a842b5",
+        "938678a810e92e658f5176b3762adcc54b0ec99314de114008fc1e8a9a79e134",
+    );
+
+    fn hex(value: &str) -> Vec<u8> {
+        hex::decode(value).unwrap()
+    }
+
+    fn aggregate(keys: &[&str], indices: &[usize]) -> Result<String, MuSig2Error> {
+        let keys: Vec<Vec<u8>> = indices.iter().map(|i| hex(keys[*i])).collect();
+        let keys: Vec<&[u8]> = keys.iter().map(Vec::as_slice).collect();
+        key_agg(&keys).map(hex::encode)
+    }
+
+    pub fn test_musig2_key_agg_vectors() {
+        for (indices, expected) in [
+            (
+                &[0, 1, 2][..],
+                "90539eede565f5d054f32cc0c220126889ed1e5d193baf15aef344fe59d4610c",
+            ),
+            (
+                &[2, 1, 0],
+                "6204de8b083426dc6eaf9502d27024d53fc826bf7d2012148a0575435df54b2b",
+            ),
+            (
+                &[0, 0, 0],
+                "b436e3bad62b8cd409969a224731c193d051162d8c5ae8b109306127da3aa935",
+            ),
+            (
+                &[0, 0, 1, 1],
+                "69bc22bfa5d106306e48a20679de1d7389386124d07571d0d872686028c26a3e",
+            ),
+        ] {
+            assert_eq!(aggregate(&BIP327_KEYS, indices), Ok(expected.to_string()));
+        }
+
+        assert_eq!(
+            aggregate(&SIGNER_KEYS, &[0, 1, 2]),
+            Ok(SIGNERS_AGGREGATE_KEY.to_string())
+        );
+    }
+
+    pub fn test_musig2_key_agg_rejects_keys() {
+        assert_eq!(key_agg(&[]), Err(MuSig2Error::InvalidPubkeyFormat));
+
+        let key = hex(BIP327_KEYS[0]);
+        let too_many = vec![key.as_slice(); MAX_MUSIG2_KEYS + 1];
+        assert_eq!(key_agg(&too_many), Err(MuSig2Error::InvalidPubkeyFormat));
+
+        // x = 5 isn't on the curve, 0x04 isn't a compressed prefix, and x-only keys aren't
+        // accepted either
+        let mut off_curve = vec![0x02];
+        off_curve.extend_from_slice(&[0u8; 31]);
+        off_curve.push(5);
+        let mut bad_prefix = key.clone();
+        bad_prefix[0] = 0x04;
+        for invalid in [off_curve, bad_prefix, key[1..].to_vec()] {
+            assert_eq!(
+                key_agg(&[&key, &invalid]),
+                Err(MuSig2Error::InvalidPubkeyFormat)
+            );
+        }
+    }
+
+    pub fn test_schnorr_verify() {
+        // BIP-340 test vector 0
+        let public_key = hex("f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9");
+        let signature = hex(concat!(
+            "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca8215",
+            "25f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0",
+        ));
+        assert_eq!(schnorr_verify(&[0u8; 32], &signature, &public_key), Ok(()));
+
+        let (message_hash, signature) = (hex(SIGNERS_MESSAGE_HASH), hex(SIGNERS_SIGNATURE));
+        let aggregate_key = hex(SIGNERS_AGGREGATE_KEY);
+        assert_eq!(
+            schnorr_verify(&message_hash, &signature, &aggregate_key),
+            Ok(())
+        );
+
+        let mut other_hash = message_hash.clone();
+        other_hash[0] ^= 1;
+        assert_eq!(
+            schnorr_verify(&other_hash, &signature, &aggregate_key),
+            Err(MuSig2Error::VerificationFailed)
+        );
+        // Signed by the aggregate in another order
+        let reordered_key = hex(&aggregate(&SIGNER_KEYS, &[2, 1, 0]).unwrap());
+        assert_eq!(
+            schnorr_verify(&message_hash, &signature, &reordered_key),
+            Err(MuSig2Error::VerificationFailed)
+        );
+
+        assert_eq!(
+            schnorr_verify(&message_hash[1..], &signature, &aggregate_key),
+            Err(MuSig2Error::InvalidHashFormat)
+        );
+        assert_eq!(
+            schnorr_verify(&message_hash, &signature[1..], &aggregate_key),
+            Err(MuSig2Error::InvalidSignatureFormat)
+        );
+        assert_eq!(
+            schnorr_verify(&message_hash, &signature, &hex(SIGNER_KEYS[0])),
+            Err(MuSig2Error::InvalidPubkeyFormat)
+        );
+    }
+}
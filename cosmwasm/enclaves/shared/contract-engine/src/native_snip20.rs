@@ -0,0 +1,345 @@
+//! Native fast-path for SNIP-20 `transfer` and `send`.
+//!
+//! Token transfers are the bulk of the chain's traffic, and each one pays for a full wasm
+//! instantiation. For contracts whose code hash was approved by governance as a SNIP-20
+//! reference implementation, simple transfers are executed here instead, directly on the
+//! contract's encrypted state. The code hashes are the compute param `native_snip20_code_hashes`,
+//! and the fast-path is off for a block until the enclave verified its params. The approved code
+//! must:
+//! * Be a CosmWasm v1 contract
+//! * Store balances as `length_prefixed("balances") || canonical_address -> u128 big endian`
+//! * Store its status as `length_prefixed("config") || "contract_status" -> u8`, 0 being normal
+//! * Store registered receivers under `length_prefixed("receivers") || canonical_address`
+//! * Not keep a transfer history, since it isn't updated here
+//!
+//! Anything the fast-path doesn't fully understand (memos, `send` with a `msg` or to a
+//! registered receiver, a paused contract, insufficient funds, ...) falls through to wasm, so
+//! errors always come from the contract itself.
+
+use log::*;
+use serde::Deserialize;
+
+use cw_types_v010::encoding::Binary;
+use cw_types_v010::math::Uint128;
+use cw_types_v010::types::{CanonicalAddr, HumanAddr};
+use enclave_ffi_types::{Ctx, EnclaveError};
+use enclave_utils::compute_params::compute_params;
+use enclave_utils::kv_cache::KvCache;
+
+use crate::contract_validation::ContractKey;
+use crate::db::{create_encrypted_key_value, read_from_encrypted_state, write_multiple_keys};
+use crate::random::update_msg_counter;
+use crate::wasm3::get_encryption_salt;
+
+/// Flat cost of a native transfer, on top of the storage access costs
+pub const NATIVE_SNIP20_TRANSFER_GAS: u64 = 30_000;
+
+/// SNIP-20 reference implementations pad responses to blocks of this size
+const RESPONSE_BLOCK_SIZE: usize = 256;
+
+const BALANCES_NAMESPACE: &[u8] = b"balances";
+const CONFIG_NAMESPACE: &[u8] = b"config";
+const RECEIVERS_NAMESPACE: &[u8] = b"receivers";
+const CONTRACT_STATUS_KEY: &[u8] = b"contract_status";
+const CONTRACT_STATUS_NORMAL: u8 = 0;
+
+fn is_approved(code_hash: &[u8; 32], approved: &[[u8; 32]]) -> bool {
+    approved.iter().any(|approved| approved == code_hash)
+}
+
+/// Whether governance approved `code_hash` for the fast-path
+pub fn is_native_snip20(code_hash: &[u8; 32]) -> bool {
+    compute_params().map_or(false, |params| {
+        is_approved(code_hash, &params.native_snip20_code_hashes)
+    })
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum FastPathMsg {
+    Transfer {
+        recipient: HumanAddr,
+        amount: Uint128,
+        memo: Option<String>,
+        #[allow(dead_code)]
+        padding: Option<String>,
+    },
+    Send {
+        recipient: HumanAddr,
+        recipient_code_hash: Option<String>,
+        amount: Uint128,
+        msg: Option<Binary>,
+        memo: Option<String>,
+        #[allow(dead_code)]
+        padding: Option<String>,
+    },
+}
+
+struct Transfer {
+    recipient: HumanAddr,
+    amount: u128,
+    /// "transfer" or "send", used to build the response
+    kind: &'static str,
+}
+
+/// Parse the message, returning `None` if it's anything but a plain transfer
+fn parse_fast_path_msg(msg: &[u8]) -> Option<Transfer> {
+    match serde_json::from_slice(msg).ok()? {
+        FastPathMsg::Transfer {
+            recipient,
+            amount,
+            memo: None,
+            ..
+        } => Some(Transfer {
+            recipient,
+            amount: amount.u128(),
+            kind: "transfer",
+        }),
+        FastPathMsg::Send {
+            recipient,
+            recipient_code_hash: None,
+            amount,
+            msg: None,
+            memo: None,
+            ..
+        } => Some(Transfer {
+            recipient,
+            amount: amount.u128(),
+            kind: "send",
+        }),
+        _ => None,
+    }
+}
+
+fn length_prefixed(namespace: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(2 + namespace.len() + key.len());
+    out.extend_from_slice(&(namespace.len() as u16).to_be_bytes());
+    out.extend_from_slice(namespace);
+    out.extend_from_slice(key);
+    out
+}
+
+fn parse_balance(value: Option<Vec<u8>>) -> Option<u128> {
+    match value {
+        None => Some(0),
+        Some(bytes) if bytes.len() == 16 => {
+            let mut be = [0u8; 16];
+            be.copy_from_slice(&bytes);
+            Some(u128::from_be_bytes(be))
+        }
+        Some(bytes) => {
+            warn!("native snip20: malformed balance of length {}", bytes.len());
+            None
+        }
+    }
+}
+
+/// The response of a SNIP-20 reference implementation, padded the same way
+pub fn build_response(kind: &str) -> Vec<u8> {
+    let mut data = format!("{{\"{}\":{{\"status\":\"success\"}}}}", kind).into_bytes();
+    let padding = (RESPONSE_BLOCK_SIZE - data.len() % RESPONSE_BLOCK_SIZE) % RESPONSE_BLOCK_SIZE;
+    data.extend(std::iter::repeat(b' ').take(padding));
+
+    serde_json::json!({
+        "Ok": {
+            "messages": [],
+            "attributes": [],
+            "events": [],
+            "data": Binary(data),
+        }
+    })
+    .to_string()
+    .into_bytes()
+}
+
+struct NativeState<'a> {
+    context: &'a Ctx,
+    contract_key: &'a ContractKey,
+    kv_cache: KvCache,
+    gas_used: u64,
+}
+
+impl<'a> NativeState<'a> {
+    /// Reads never rewrite keys in the old encryption format, so nothing is written to state
+    /// before we know we're not falling back to wasm
+    fn read(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>, EnclaveError> {
+        let (value, gas_used) = read_from_encrypted_state(
+            key,
+            self.context,
+            self.contract_key,
+            false,
+            &mut self.kv_cache,
+            &[],
+        )?;
+        self.gas_used = self.gas_used.saturating_add(gas_used);
+
+        Ok(value)
+    }
+
+    fn write_all(
+        &mut self,
+        writes: Vec<(Vec<u8>, Vec<u8>)>,
+        salt: &[u8],
+    ) -> Result<(), EnclaveError> {
+        let mut encrypted = Vec::with_capacity(writes.len());
+        for (key, value) in writes {
+            let (enc_key, gas_used, enc_value) =
+                create_encrypted_key_value(&key, &value, self.context, self.contract_key, salt)?;
+            self.gas_used = self.gas_used.saturating_add(gas_used);
+            encrypted.push((enc_key, enc_value));
+        }
+
+        let gas_used = write_multiple_keys(self.context, encrypted)?;
+        self.gas_used = self.gas_used.saturating_add(gas_used);
+
+        Ok(())
+    }
+}
+
+/// Try to execute `msg` natively. Returns `Ok(None)` if the message must go through wasm.
+pub fn try_native_snip20_execute(
+    context: &Ctx,
+    gas_limit: u64,
+    used_gas: &mut u64,
+    contract_key: &ContractKey,
+    sender: &CanonicalAddr,
+    block_height: u64,
+    block_time: u64,
+    msg: &[u8],
+) -> Result<Option<Vec<u8>>, EnclaveError> {
+    let transfer = match parse_fast_path_msg(msg) {
+        Some(transfer) => transfer,
+        None => return Ok(None),
+    };
+
+    let recipient = match CanonicalAddr::from_human(&transfer.recipient) {
+        Ok(recipient) => recipient,
+        // let the contract produce the error
+        Err(_) => return Ok(None),
+    };
+
+    let mut state = NativeState {
+        context,
+        contract_key,
+        kv_cache: KvCache::new(),
+        gas_used: NATIVE_SNIP20_TRANSFER_GAS,
+    };
+
+    let status = state.read(&length_prefixed(CONFIG_NAMESPACE, CONTRACT_STATUS_KEY))?;
+    if status.as_deref() != Some(&[CONTRACT_STATUS_NORMAL][..]) {
+        trace!("native snip20: contract status is not normal");
+        return Ok(None);
+    }
+
+    if transfer.kind == "send"
+        && state
+            .read(&length_prefixed(RECEIVERS_NAMESPACE, recipient.as_slice()))?
+            .is_some()
+    {
+        trace!("native snip20: recipient has a registered receiver");
+        return Ok(None);
+    }
+
+    let sender_key = length_prefixed(BALANCES_NAMESPACE, sender.as_slice());
+    let recipient_key = length_prefixed(BALANCES_NAMESPACE, recipient.as_slice());
+
+    let sender_value = state.read(&sender_key)?;
+    let sender_balance = match parse_balance(sender_value) {
+        Some(balance) if balance >= transfer.amount => balance,
+        _ => return Ok(None),
+    };
+
+    let mut writes = vec![];
+    // a transfer to self doesn't change any balance
+    if recipient_key != sender_key {
+        let recipient_value = state.read(&recipient_key)?;
+        let recipient_balance = match parse_balance(recipient_value)
+            .and_then(|balance| balance.checked_add(transfer.amount))
+        {
+            Some(balance) => balance,
+            None => return Ok(None),
+        };
+
+        writes.push((
+            sender_key,
+            (sender_balance - transfer.amount).to_be_bytes().to_vec(),
+        ));
+        writes.push((recipient_key, recipient_balance.to_be_bytes().to_vec()));
+    }
+
+    // From here on we're committed to the native path
+    update_msg_counter(block_height);
+    state.write_all(writes, &get_encryption_salt(block_time))?;
+
+    *used_gas = state.gas_used;
+    if *used_gas > gas_limit {
+        *used_gas = gas_limit;
+        return Err(EnclaveError::OutOfGas);
+    }
+
+    debug!("native snip20 {} executed", transfer.kind);
+
+    Ok(Some(build_response(transfer.kind)))
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use enclave_utils::compute_params::ComputeParams;
+
+    pub fn test_native_snip20_parse() {
+        let plain = br#"{"transfer":{"recipient":"secret1abc","amount":"100","padding":"xx"}}"#;
+        let parsed = parse_fast_path_msg(plain).unwrap();
+        assert_eq!(parsed.amount, 100);
+        assert_eq!(parsed.kind, "transfer");
+
+        let send = br#"{"send":{"recipient":"secret1abc","amount":"5"}}"#;
+        assert_eq!(parse_fast_path_msg(send).unwrap().kind, "send");
+
+        // anything with side effects beyond balances goes through wasm
+        let memo = br#"{"transfer":{"recipient":"secret1abc","amount":"100","memo":"hi"}}"#;
+        assert!(parse_fast_path_msg(memo).is_none());
+        let callback = br#"{"send":{"recipient":"secret1abc","amount":"5","msg":"e30="}}"#;
+        assert!(parse_fast_path_msg(callback).is_none());
+        let other = br#"{"burn":{"amount":"5"}}"#;
+        assert!(parse_fast_path_msg(other).is_none());
+        let unknown_field = br#"{"transfer":{"recipient":"secret1abc","amount":"1","x":1}}"#;
+        assert!(parse_fast_path_msg(unknown_field).is_none());
+    }
+
+    pub fn test_native_snip20_approved_code_hashes() {
+        // max_contract_size, then the code hashes
+        let mut params = vec![0x10, 0x80, 0x80, 0x80, 0x01];
+        for code_hash in [[1u8; 32], [2u8; 32]].iter() {
+            params.extend_from_slice(&[0x1a, 32]);
+            params.extend_from_slice(code_hash);
+        }
+        let decoded = ComputeParams::parse(&params).unwrap();
+
+        assert!(is_approved(&[2u8; 32], &decoded.native_snip20_code_hashes));
+        assert!(!is_approved(&[3u8; 32], &decoded.native_snip20_code_hashes));
+        assert!(!is_approved(&[1u8; 32], &[]));
+        // only whole hashes are a valid value
+        assert!(ComputeParams::parse(&[0x1a, 1, 1]).is_err());
+    }
+
+    pub fn test_native_snip20_response_padding() {
+        let response: serde_json::Value =
+            serde_json::from_slice(&build_response("transfer")).unwrap();
+        let data = base64::decode(response["Ok"]["data"].as_str().unwrap()).unwrap();
+
+        assert_eq!(data.len() % RESPONSE_BLOCK_SIZE, 0);
+        assert!(data.starts_with(br#"{"transfer":{"status":"success"}}"#));
+    }
+
+    pub fn test_native_snip20_balance_layout() {
+        assert_eq!(
+            length_prefixed(BALANCES_NAMESPACE, &[1, 2]),
+            b"\x00\x08balances\x01\x02".to_vec()
+        );
+        assert_eq!(parse_balance(None), Some(0));
+        assert_eq!(parse_balance(Some(7u128.to_be_bytes().to_vec())), Some(7));
+        assert_eq!(parse_balance(Some(vec![1, 2, 3])), None);
+    }
+}
@@ -0,0 +1,340 @@
+//! Oblivious storage for high-sensitivity namespaces.
+//!
+//! Keys and values in contract state are encrypted, but the host still sees which encrypted key
+//! every `db_read` and `db_write` touches, which is enough to link e.g. a vote or an order to the
+//! account it belongs to. A contract can call `db_oblivious_namespace(prefix)` at the start of an
+//! entry point to opt a namespace into a read-decoy scheme for the rest of that call:
+//!
+//! * Keys under the prefix are not stored individually. They are spread over
+//!   `OBLIVIOUS_BUCKETS` buckets, picked with a hash keyed by the contract key, so the host can't
+//!   tell which bucket a given key lives in.
+//! * Every access, read or write, touches the real bucket and `OBLIVIOUS_DECOYS` other buckets,
+//!   always in ascending order. Buckets that were touched by a write are all written back,
+//!   re-encrypted with a fresh salt, so the host can't tell which one changed.
+//! * The decoys are never drawn from the enclave's RNG, since the buckets a write touches and the
+//!   gas of every access must be the same on every node. They're derived with HKDF from the
+//!   contract key, the namespace, the block height, the position of the msg in the block and the
+//!   position of the access in the call, which the host can't compute without the contract key.
+//!
+//! Each access costs `OBLIVIOUS_GAS_MULTIPLIER` times the base gas of a regular access, plus the
+//! storage gas of every bucket it touches. Bucket sizes aren't padded, so contracts should keep
+//! the values they store under an oblivious namespace the same size.
+
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+
+use log::*;
+
+use enclave_crypto::sha_256;
+use enclave_ffi_types::Ctx;
+use enclave_utils::kv_cache::KvCache;
+use enclave_wire::{Endian, Framed};
+
+use crate::contract_validation::ContractKey;
+use crate::db::read_from_encrypted_state;
+use crate::errors::{WasmEngineError, WasmEngineResult};
+
+pub const OBLIVIOUS_BUCKETS: u32 = 64;
+pub const OBLIVIOUS_DECOYS: usize = 3;
+/// Every access touches the real bucket and all the decoys
+pub const OBLIVIOUS_GAS_MULTIPLIER: u64 = OBLIVIOUS_DECOYS as u64 + 1;
+pub const MAX_OBLIVIOUS_NAMESPACES: usize = 8;
+
+/// Separates bucket keys from the keys a contract writes itself under the same prefix
+const BUCKET_KEY_MARKER: &[u8] = b"\xffoblivious";
+
+const DECOY_DOMAIN: &[u8] = b"oblivious_storage_decoys";
+
+type Bucket = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// A bucket is stored as its pairs, in the order of their keys
+const BUCKET_PAIRS: Framed = Framed(Endian::Big);
+
+/// The namespaces a contract opted into during the current call
+#[derive(Default)]
+pub struct ObliviousNamespaces(Vec<Vec<u8>>);
+
+impl ObliviousNamespaces {
+    pub fn register(&mut self, namespace: &[u8]) -> WasmEngineResult<()> {
+        if namespace.is_empty() {
+            debug!("db_oblivious_namespace was called with an empty namespace");
+            return Err(WasmEngineError::InvalidNamespace);
+        }
+        if self.0.iter().any(|ns| ns.as_slice() == namespace) {
+            return Ok(());
+        }
+        if self.0.len() >= MAX_OBLIVIOUS_NAMESPACES {
+            debug!("too many oblivious namespaces");
+            return Err(WasmEngineError::InvalidNamespace);
+        }
+
+        self.0.push(namespace.to_vec());
+        Ok(())
+    }
+
+    /// The oblivious namespace `key` belongs to, if any
+    pub fn namespace_of(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.0
+            .iter()
+            .find(|ns| key.starts_with(ns) && !is_bucket_key(ns, key))
+            .cloned()
+    }
+}
+
+fn is_bucket_key(namespace: &[u8], key: &[u8]) -> bool {
+    key[namespace.len()..].starts_with(BUCKET_KEY_MARKER)
+}
+
+fn bucket_key(namespace: &[u8], index: u32) -> Vec<u8> {
+    let mut key = namespace.to_vec();
+    key.extend_from_slice(BUCKET_KEY_MARKER);
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+fn bucket_of(contract_key: &ContractKey, namespace: &[u8], key: &[u8]) -> u32 {
+    let mut data = contract_key.to_vec();
+    data.extend_from_slice(&(namespace.len() as u32).to_be_bytes());
+    data.extend_from_slice(namespace);
+    data.extend_from_slice(key);
+
+    let hash = sha_256(&data);
+    u32::from_be_bytes(hash[..4].try_into().unwrap()) % OBLIVIOUS_BUCKETS
+}
+
+/// Where an access happens, which the decoys of the access are derived from
+#[derive(Clone, Copy)]
+struct AccessPosition {
+    height: u64,
+    /// The position of the msg in the block
+    msg_counter: u64,
+    /// The position of the access in the call
+    access: u32,
+}
+
+/// The real bucket and the decoys, in ascending order so the real one can't be told apart
+fn buckets_to_touch(
+    contract_key: &ContractKey,
+    namespace: &[u8],
+    position: AccessPosition,
+    real: u32,
+) -> Vec<u32> {
+    let namespace_len = (namespace.len() as u32).to_be_bytes();
+    let height = position.height.to_be_bytes();
+    let msg_counter = position.msg_counter.to_be_bytes();
+    let access = position.access.to_be_bytes();
+    let info: [&[u8]; 6] = [
+        DECOY_DOMAIN,
+        &namespace_len,
+        namespace,
+        &height,
+        &msg_counter,
+        &access,
+    ];
+    let seed = enclave_crypto::hkdf_sha_256(contract_key, &info);
+
+    let mut indexes = vec![real];
+    let mut round: u32 = 0;
+    while indexes.len() < OBLIVIOUS_DECOYS + 1 {
+        let mut data = seed.get().to_vec();
+        data.extend_from_slice(&round.to_be_bytes());
+        round += 1;
+
+        for candidate in sha_256(&data).chunks_exact(4) {
+            let decoy = u32::from_be_bytes(candidate.try_into().unwrap()) % OBLIVIOUS_BUCKETS;
+            if indexes.len() < OBLIVIOUS_DECOYS + 1 && !indexes.contains(&decoy) {
+                indexes.push(decoy);
+            }
+        }
+    }
+
+    indexes.sort_unstable();
+    indexes
+}
+
+fn serialize_bucket(bucket: &Bucket) -> Vec<u8> {
+    BUCKET_PAIRS.encode_pairs(bucket)
+}
+
+fn deserialize_bucket(bytes: &[u8]) -> WasmEngineResult<Bucket> {
+    let pairs = BUCKET_PAIRS.decode_pairs(bytes).map_err(|err| {
+        warn!("failed to decode an oblivious bucket: {}", err);
+        WasmEngineError::DeserializationError
+    })?;
+
+    Ok(pairs
+        .into_iter()
+        .map(|(key, value)| (key.to_vec(), value.to_vec()))
+        .collect())
+}
+
+/// Access to the oblivious namespaces of a contract during a single call
+pub struct ObliviousStore<'a> {
+    pub context: &'a Ctx,
+    pub contract_key: &'a ContractKey,
+    pub kv_cache: &'a mut KvCache,
+    pub has_write_permissions: bool,
+    pub encryption_salt: &'a [u8],
+    pub height: u64,
+    /// The position of the msg in the block
+    pub msg_counter: u64,
+    /// The accesses the call made so far, which is advanced on every access
+    pub accesses: &'a mut u32,
+}
+
+impl<'a> ObliviousStore<'a> {
+    fn next_buckets(&mut self, namespace: &[u8], real: u32) -> Vec<u32> {
+        let position = AccessPosition {
+            height: self.height,
+            msg_counter: self.msg_counter,
+            access: *self.accesses,
+        };
+        *self.accesses = self.accesses.wrapping_add(1);
+
+        buckets_to_touch(self.contract_key, namespace, position, real)
+    }
+
+    /// Returns the bucket and the storage gas used to read it
+    fn load_bucket(&mut self, namespace: &[u8], index: u32) -> WasmEngineResult<(Bucket, u64)> {
+        let key = bucket_key(namespace, index);
+        if let Some(bytes) = self.kv_cache.read(&key) {
+            return Ok((deserialize_bucket(&bytes)?, 0));
+        }
+
+        let (value, gas_used) = read_from_encrypted_state(
+            &key,
+            self.context,
+            self.contract_key,
+            self.has_write_permissions,
+            self.kv_cache,
+            self.encryption_salt,
+        )?;
+
+        let bytes = value.unwrap_or_default();
+        // Later accesses to this bucket in the same call are served from the cache
+        self.kv_cache.store_in_ro_cache(&key, &bytes);
+
+        Ok((deserialize_bucket(&bytes)?, gas_used))
+    }
+
+    /// Returns the value and the storage gas used
+    pub fn read(
+        &mut self,
+        namespace: &[u8],
+        key: &[u8],
+    ) -> WasmEngineResult<(Option<Vec<u8>>, u64)> {
+        let real = bucket_of(self.contract_key, namespace, key);
+
+        let mut value = None;
+        let mut gas_used: u64 = 0;
+        for index in self.next_buckets(namespace, real) {
+            let (bucket, gas) = self.load_bucket(namespace, index)?;
+            gas_used = gas_used.saturating_add(gas);
+            if index == real {
+                value = bucket.get(key).cloned();
+            }
+        }
+
+        Ok((value, gas_used))
+    }
+
+    /// Sets or removes (`value == None`) a key. Returns the storage gas used to read the buckets
+    /// and the pseudo gas of the writes, which is refunded when the cache is flushed.
+    pub fn write(
+        &mut self,
+        namespace: &[u8],
+        key: &[u8],
+        value: Option<&[u8]>,
+    ) -> WasmEngineResult<(u64, u64)> {
+        let real = bucket_of(self.contract_key, namespace, key);
+
+        let mut gas_used: u64 = 0;
+        let mut pseudo_gas: u64 = 0;
+        for index in self.next_buckets(namespace, real) {
+            let (mut bucket, gas) = self.load_bucket(namespace, index)?;
+            gas_used = gas_used.saturating_add(gas);
+            if index == real {
+                match value {
+                    Some(value) => bucket.insert(key.to_vec(), value.to_vec()),
+                    None => bucket.remove(key),
+                };
+            }
+
+            // Decoys are rewritten as well, and get a fresh salt when the cache is flushed
+            let (_, pseudo) = self
+                .kv_cache
+                .write(&bucket_key(namespace, index), &serialize_bucket(&bucket));
+            pseudo_gas = pseudo_gas.saturating_add(pseudo);
+        }
+
+        Ok((gas_used, pseudo_gas))
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_oblivious_bucket_serialization() {
+        let mut bucket = Bucket::new();
+        bucket.insert(b"voter1".to_vec(), b"yes".to_vec());
+        bucket.insert(b"voter2".to_vec(), vec![]);
+
+        let bytes = serialize_bucket(&bucket);
+        assert_eq!(deserialize_bucket(&bytes).unwrap(), bucket);
+        assert!(deserialize_bucket(&[]).unwrap().is_empty());
+        assert!(deserialize_bucket(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    pub fn test_oblivious_decoys() {
+        let position = |access| AccessPosition {
+            height: 100,
+            msg_counter: 2,
+            access,
+        };
+
+        for real in [0, 17, OBLIVIOUS_BUCKETS - 1] {
+            let indexes = buckets_to_touch(&[1; 64], b"votes", position(0), real);
+            assert_eq!(indexes.len(), OBLIVIOUS_DECOYS + 1);
+            assert!(indexes.contains(&real));
+            assert!(indexes.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!(indexes.iter().all(|index| *index < OBLIVIOUS_BUCKETS));
+
+            // every node touches the same buckets
+            assert_eq!(
+                indexes,
+                buckets_to_touch(&[1; 64], b"votes", position(0), real)
+            );
+        }
+
+        // but the decoys change from access to access, and between contracts
+        let decoys = (0..8)
+            .map(|access| buckets_to_touch(&[1; 64], b"votes", position(access), 17))
+            .collect::<std::collections::HashSet<_>>();
+        assert_ne!(decoys.len(), 1);
+        assert_ne!(
+            buckets_to_touch(&[1; 64], b"votes", position(0), 17),
+            buckets_to_touch(&[2; 64], b"votes", position(0), 17)
+        );
+    }
+
+    pub fn test_oblivious_namespaces() {
+        let mut namespaces = ObliviousNamespaces::default();
+        assert!(namespaces.register(b"").is_err());
+        namespaces.register(b"votes").unwrap();
+
+        assert_eq!(namespaces.namespace_of(b"votes/1"), Some(b"votes".to_vec()));
+        assert_eq!(namespaces.namespace_of(b"config"), None);
+        assert_eq!(namespaces.namespace_of(&bucket_key(b"votes", 3)), None);
+
+        // the bucket depends on the contract key
+        let key = b"votes/alice";
+        assert_ne!(
+            (0..8u8)
+                .map(|i| bucket_of(&[i; 64], b"votes", key))
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            1
+        );
+    }
+}
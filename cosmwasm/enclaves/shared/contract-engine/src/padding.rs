@@ -0,0 +1,195 @@
+//! Padding of plaintexts to size buckets before encryption.
+//!
+//! Ciphertext lengths leak plaintext lengths, which is often enough to tell e.g. a vote for from
+//! a vote against, or a small transfer from a large one. Plaintexts are padded ISO/IEC 7816-4
+//! style, `data || 0x80 || 0x00..`, up to the smallest bucket that fits them. Anything larger
+//! than the largest bucket is padded to a multiple of it.
+//!
+//! The padding is deterministic, so all nodes produce the same state. The buckets are the compute
+//! params `state_value_buckets` and `io_message_buckets` when they're proven for the current
+//! block, so governance changes them for every node at the same block, and they only change the
+//! state that's written from then on.
+//!
+//! * State values are always padded with the state value buckets. Padded values are encrypted
+//!   with `PADDED_VALUE_AD` as extra associated data, so values written before padding was added
+//!   are still read as they are.
+//! * Messages the enclave encrypts, such as submessages and queries to other contracts, are
+//!   always padded with the message buckets. Clients can pad their own messages the same way.
+//! * Responses a client decrypts are padded only when the client padded its message, so
+//!   existing clients keep working. Errors and event attributes aren't padded.
+//!
+//! A padded message is encrypted with `PADDED_MESSAGE_AD` as associated data, and padding is only
+//! stripped from messages that decrypt with it, so a plaintext that happens to end with
+//! `0x80 0x00..`, e.g. CBOR, is never truncated.
+
+use log::*;
+
+use enclave_crypto::{AESKey, CryptoError, SIVEncryptable};
+use enclave_utils::compute_params::{compute_params, ComputeParams};
+
+pub struct SizeBuckets(Vec<usize>);
+
+pub const DEFAULT_STATE_VALUE_BUCKETS: &[usize] = &[32, 64, 128, 256, 512, 1024, 4096];
+pub const DEFAULT_IO_MESSAGE_BUCKETS: &[usize] = &[256, 512, 1024, 2048, 4096, 8192, 16384];
+
+/// Associated data that marks a state value as padded
+pub const PADDED_VALUE_AD: &[u8] = b"padded";
+/// Associated data that marks a message as padded
+pub const PADDED_MESSAGE_AD: &[u8] = b"padded_msg";
+
+const PADDING_START: u8 = 0x80;
+
+/// The buckets state values are padded to
+pub fn state_value_buckets() -> SizeBuckets {
+    buckets(
+        |params| params.state_value_buckets,
+        DEFAULT_STATE_VALUE_BUCKETS,
+    )
+}
+
+/// The buckets messages and responses are padded to
+pub fn io_message_buckets() -> SizeBuckets {
+    buckets(
+        |params| params.io_message_buckets,
+        DEFAULT_IO_MESSAGE_BUCKETS,
+    )
+}
+
+fn buckets(param: impl Fn(ComputeParams) -> Vec<usize>, default: &[usize]) -> SizeBuckets {
+    match compute_params().map(param) {
+        Some(buckets) if !buckets.is_empty() => SizeBuckets(buckets),
+        _ => SizeBuckets(default.to_vec()),
+    }
+}
+
+impl SizeBuckets {
+    /// The length `len` bytes are padded to, always leaving room for at least one padding byte
+    pub fn padded_len(&self, len: usize) -> usize {
+        let min_len = len + 1;
+        if let Some(bucket) = self.0.iter().find(|bucket| **bucket >= min_len) {
+            return *bucket;
+        }
+
+        let largest = self.0[self.0.len() - 1];
+        ((min_len + largest - 1) / largest) * largest
+    }
+}
+
+pub fn pad(data: &[u8], buckets: &SizeBuckets) -> Vec<u8> {
+    let mut padded = Vec::with_capacity(buckets.padded_len(data.len()));
+    padded.extend_from_slice(data);
+    padded.push(PADDING_START);
+    padded.resize(buckets.padded_len(data.len()), 0);
+    padded
+}
+
+/// Strips the padding, returning `None` if `data` isn't padded
+pub fn unpad(data: &[u8]) -> Option<&[u8]> {
+    let end = data.iter().rposition(|byte| *byte != 0)?;
+    if data[end] != PADDING_START {
+        return None;
+    }
+
+    Some(&data[..end])
+}
+
+/// Encrypt a message, padding it and marking it as padded if `should_pad`
+pub fn encrypt_message(key: &AESKey, msg: &[u8], should_pad: bool) -> Result<Vec<u8>, CryptoError> {
+    if should_pad {
+        key.encrypt_siv(&pad(msg, &io_message_buckets()), Some(&[PADDED_MESSAGE_AD]))
+    } else {
+        key.encrypt_siv(msg, None)
+    }
+}
+
+/// Decrypt a message, stripping its padding if it's marked as padded. Returns whether it was.
+pub fn decrypt_message(key: &AESKey, ciphertext: &[u8]) -> Option<(Vec<u8>, bool)> {
+    if let Ok(padded) = key.decrypt_siv(ciphertext, Some(&[PADDED_MESSAGE_AD])) {
+        return match unpad(&padded) {
+            Some(msg) => Some((msg.to_vec(), true)),
+            None => {
+                warn!("got a msg marked as padded with invalid padding");
+                None
+            }
+        };
+    }
+
+    key.decrypt_siv(ciphertext, None)
+        .ok()
+        .map(|msg| (msg, false))
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_padding_buckets() {
+        let state_buckets = SizeBuckets(DEFAULT_STATE_VALUE_BUCKETS.to_vec());
+        assert_eq!(state_buckets.padded_len(0), 32);
+        assert_eq!(state_buckets.padded_len(31), 32);
+        assert_eq!(state_buckets.padded_len(32), 64);
+        assert_eq!(state_buckets.padded_len(4095), 4096);
+        assert_eq!(state_buckets.padded_len(4096), 8192);
+        assert_eq!(state_buckets.padded_len(10_000), 12_288);
+
+        let single = SizeBuckets(vec![100]);
+        assert_eq!(single.padded_len(99), 100);
+        assert_eq!(single.padded_len(100), 200);
+    }
+
+    pub fn test_padding_roundtrip() {
+        let cases: [&[u8]; 4] = [b"", b"{\"vote\":\"yes\"}", &[0u8; 40], &[0x80, 0, 0x80]];
+        let state_buckets = SizeBuckets(DEFAULT_STATE_VALUE_BUCKETS.to_vec());
+        for data in cases.iter() {
+            let padded = pad(data, &state_buckets);
+            assert_eq!(padded.len(), state_buckets.padded_len(data.len()));
+            assert_eq!(unpad(&padded), Some(*data));
+        }
+
+        // same bucket, same length
+        let io_buckets = SizeBuckets(DEFAULT_IO_MESSAGE_BUCKETS.to_vec());
+        assert_eq!(
+            pad(b"{\"vote\":\"yes\"}", &io_buckets).len(),
+            pad(b"{\"vote\":\"abstain\"}", &io_buckets).len()
+        );
+    }
+
+    pub fn test_padded_message_marker() {
+        let key = AESKey::new_from_slice(&[7; 32]);
+        // e.g. CBOR, ending with what looks like padding
+        let msg: &[u8] = &[0xa1, 0x61, 0x61, 0x80, 0x00];
+
+        let unpadded = encrypt_message(&key, msg, false).unwrap();
+        assert_eq!(
+            decrypt_message(&key, &unpadded),
+            Some((msg.to_vec(), false))
+        );
+
+        let padded = encrypt_message(&key, msg, true).unwrap();
+        assert_eq!(decrypt_message(&key, &padded), Some((msg.to_vec(), true)));
+
+        // a marked msg without valid padding is rejected
+        let invalid = key
+            .encrypt_siv(b"no padding", Some(&[PADDED_MESSAGE_AD]))
+            .unwrap();
+        assert_eq!(decrypt_message(&key, &invalid), None);
+    }
+
+    pub fn test_padding_bucket_params() {
+        // state value buckets packed, message buckets not
+        let params = [0x3a, 3, 100, 0xc8, 0x01, 0x40, 100, 0x40, 0xc8, 0x01];
+        let params = ComputeParams::parse(&params).unwrap();
+        assert_eq!(params.state_value_buckets, vec![100, 200]);
+        assert_eq!(params.io_message_buckets, vec![100, 200]);
+
+        // buckets must be ascending
+        assert!(ComputeParams::parse(&[0x3a, 3, 0xc8, 0x01, 100]).is_err());
+        assert!(ComputeParams::parse(&[0x3a, 1, 0]).is_err());
+    }
+
+    pub fn test_unpad_plain_messages() {
+        assert_eq!(unpad(b"{\"transfer\":{}}"), None);
+        assert_eq!(unpad(b""), None);
+        assert_eq!(unpad(&[0, 0, 0]), None);
+    }
+}
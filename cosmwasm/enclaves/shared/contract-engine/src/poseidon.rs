@@ -0,0 +1,360 @@
+//! The Poseidon hash over the scalar fields of BN254 and BLS12-381, so commitments computed by
+//! contracts match the ones zk circuits compute.
+//!
+//! The permutation uses the x^5 S-box and 8 full rounds, and its round constants and MDS matrix
+//! are generated like the reference implementation does, with the Grain LFSR. Over BN254, the
+//! hash of n inputs is the one circomlib's `Poseidon(n)` computes: the inputs follow a 0 capacity
+//! element in the state, and the hash is the first element of the permuted state. BLS12-381 uses
+//! the same construction, with the reference implementation's parameters for its 255-bit field.
+
+use std::collections::HashMap;
+use std::sync::{Arc, SgxMutex};
+
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+
+/// Error codes returned to contracts by the `poseidon_hash` host function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum PoseidonError {
+    /// The curve is unknown, there are no inputs or too many of them, or an input isn't 32 bytes
+    InvalidInput = 1,
+    /// An input isn't smaller than the modulus of the field
+    NonCanonicalInput = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Curve {
+    Bn254,
+    Bls12_381,
+}
+
+impl Curve {
+    pub fn from_u32(curve: u32) -> Result<Self, PoseidonError> {
+        match curve {
+            0 => Ok(Curve::Bn254),
+            1 => Ok(Curve::Bls12_381),
+            _ => Err(PoseidonError::InvalidInput),
+        }
+    }
+
+    fn modulus(self) -> BigUint {
+        let modulus: &[u8] = match self {
+            Curve::Bn254 => b"30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001",
+            Curve::Bls12_381 => b"73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001",
+        };
+        BigUint::parse_bytes(modulus, 16).unwrap()
+    }
+}
+
+/// circomlib's limit, which its constants were checked for
+pub const MAX_POSEIDON_INPUTS: usize = 12;
+
+/// Field elements are 32 byte big-endian numbers
+const FIELD_ELEMENT_BYTES: usize = 32;
+
+const FULL_ROUNDS: usize = 8;
+
+/// The partial rounds of the permutation of each width from 2, as circomlib and the reference
+/// implementation chose them for 128-bit security
+const PARTIAL_ROUNDS: [usize; MAX_POSEIDON_INPUTS] =
+    [56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65];
+
+struct Parameters {
+    modulus: BigUint,
+    partial_rounds: usize,
+    /// The constants of each round, one per element of the state
+    round_constants: Vec<BigUint>,
+    mds: Vec<Vec<BigUint>>,
+}
+
+lazy_static! {
+    /// Generating the constants takes longer than hashing, so they're generated once per width
+    static ref PARAMETERS: SgxMutex<HashMap<(Curve, usize), Arc<Parameters>>> =
+        SgxMutex::new(HashMap::new());
+}
+
+/// The Grain LFSR, in self-shrinking mode, of which bit i of the 80-bit state is the i-th oldest
+struct Grain(u128);
+
+impl Grain {
+    fn new(field_bits: usize, width: usize, partial_rounds: usize) -> Self {
+        // A prime field, the x^alpha S-box, the sizes, and 30 set bits
+        let fields = [
+            (1, 2),
+            (0, 4),
+            (field_bits, 12),
+            (width, 12),
+            (FULL_ROUNDS, 10),
+            (partial_rounds, 10),
+            ((1 << 30) - 1, 30),
+        ];
+        let mut state = 0u128;
+        let mut index = 0;
+        for &(value, bits) in fields.iter() {
+            for bit in (0..bits).rev() {
+                state |= (((value >> bit) & 1) as u128) << index;
+                index += 1;
+            }
+        }
+
+        let mut grain = Grain(state);
+        for _ in 0..160 {
+            grain.step();
+        }
+        grain
+    }
+
+    fn step(&mut self) -> bool {
+        let state = self.0;
+        let bit =
+            (state ^ (state >> 13) ^ (state >> 23) ^ (state >> 38) ^ (state >> 51) ^ (state >> 62))
+                & 1;
+        self.0 = (state >> 1) | (bit << 79);
+        bit == 1
+    }
+
+    /// Bits are generated in pairs, and the second one is output only if the first one is set
+    fn next_bit(&mut self) -> bool {
+        loop {
+            if self.step() {
+                return self.step();
+            }
+            self.step();
+        }
+    }
+
+    /// The next `bits` bits, as a big-endian number
+    fn next_integer(&mut self, bits: usize) -> BigUint {
+        let mut bytes = vec![0u8; (bits + 7) / 8];
+        let length = bytes.len();
+        for position in (0..bits).rev() {
+            if self.next_bit() {
+                bytes[length - 1 - position / 8] |= 1 << (position % 8);
+            }
+        }
+        BigUint::from_bytes_be(&bytes)
+    }
+}
+
+impl Parameters {
+    fn generate(curve: Curve, width: usize) -> Self {
+        let modulus = curve.modulus();
+        let field_bits = modulus.bits() as usize;
+        let partial_rounds = PARTIAL_ROUNDS[width - 2];
+        let mut grain = Grain::new(field_bits, width, partial_rounds);
+
+        let round_constants = (0..(FULL_ROUNDS + partial_rounds) * width)
+            .map(|_| loop {
+                let constant = grain.next_integer(field_bits);
+                if constant < modulus {
+                    break constant;
+                }
+            })
+            .collect();
+
+        // A Cauchy matrix, of which the first candidate passes the reference implementation's
+        // security checks for every width here
+        let zero = BigUint::from(0u32);
+        let exponent = &modulus - BigUint::from(2u32);
+        let mds = loop {
+            let values: Vec<BigUint> = (0..2 * width)
+                .map(|_| grain.next_integer(field_bits) % &modulus)
+                .collect();
+            let (xs, ys) = values.split_at(width);
+            let distinct = values
+                .iter()
+                .enumerate()
+                .all(|(i, value)| !values[..i].contains(value));
+            let sums: Vec<Vec<BigUint>> = xs
+                .iter()
+                .map(|x| ys.iter().map(|y| (x + y) % &modulus).collect())
+                .collect();
+            if distinct && sums.iter().flatten().all(|sum| *sum != zero) {
+                break sums
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|sum| sum.modpow(&exponent, &modulus))
+                            .collect()
+                    })
+                    .collect();
+            }
+        };
+
+        Parameters {
+            modulus,
+            partial_rounds,
+            round_constants,
+            mds,
+        }
+    }
+
+    fn get(curve: Curve, width: usize) -> Arc<Self> {
+        let mut parameters = PARAMETERS.lock().unwrap();
+        parameters
+            .entry((curve, width))
+            .or_insert_with(|| Arc::new(Parameters::generate(curve, width)))
+            .clone()
+    }
+
+    fn sbox(&self, element: &BigUint) -> BigUint {
+        let square = element * element % &self.modulus;
+        let fourth = &square * &square % &self.modulus;
+        fourth * element % &self.modulus
+    }
+
+    fn permute(&self, mut state: Vec<BigUint>) -> Vec<BigUint> {
+        let width = state.len();
+        let first_partial_round = FULL_ROUNDS / 2;
+        let last_partial_round = first_partial_round + self.partial_rounds;
+
+        for (round, constants) in self.round_constants.chunks(width).enumerate() {
+            for (element, constant) in state.iter_mut().zip(constants) {
+                *element = (&*element + constant) % &self.modulus;
+            }
+
+            if round < first_partial_round || round >= last_partial_round {
+                state = state.iter().map(|element| self.sbox(element)).collect();
+            } else {
+                state[0] = self.sbox(&state[0]);
+            }
+
+            state = self
+                .mds
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .zip(&state)
+                        .fold(BigUint::from(0u32), |sum, (entry, element)| {
+                            sum + entry * element
+                        })
+                        % &self.modulus
+                })
+                .collect();
+        }
+
+        state
+    }
+}
+
+/// The number of field multiplications hashing `inputs` takes, which is what it's priced by
+pub fn multiplications(inputs: usize) -> u64 {
+    let width = inputs + 1;
+    let partial_rounds = PARTIAL_ROUNDS[inputs.clamp(1, MAX_POSEIDON_INPUTS) - 1];
+    let sboxes = FULL_ROUNDS * width + partial_rounds;
+    // Every S-box takes 3 multiplications, and every round multiplies the state by the MDS matrix
+    (3 * sboxes + (FULL_ROUNDS + partial_rounds) * width * width) as u64
+}
+
+/// Whether `element` is a 32 byte big-endian number smaller than the modulus of the field
+pub fn is_field_element(curve: Curve, element: &[u8]) -> bool {
+    element.len() == FIELD_ELEMENT_BYTES && BigUint::from_bytes_be(element) < curve.modulus()
+}
+
+/// Hashes 1 to `MAX_POSEIDON_INPUTS` field elements, and returns the hash as a field element
+pub fn poseidon_hash(curve: Curve, inputs: &[Vec<u8>]) -> Result<Vec<u8>, PoseidonError> {
+    if inputs.is_empty()
+        || inputs.len() > MAX_POSEIDON_INPUTS
+        || inputs
+            .iter()
+            .any(|input| input.len() != FIELD_ELEMENT_BYTES)
+    {
+        return Err(PoseidonError::InvalidInput);
+    }
+
+    let parameters = Parameters::get(curve, inputs.len() + 1);
+    let mut state = vec![BigUint::from(0u32)];
+    for input in inputs {
+        let element = BigUint::from_bytes_be(input);
+        if element >= parameters.modulus {
+            return Err(PoseidonError::NonCanonicalInput);
+        }
+        state.push(element);
+    }
+
+    let hash = parameters.permute(state).swap_remove(0).to_bytes_be();
+    let mut result = vec![0u8; FIELD_ELEMENT_BYTES];
+    result[FIELD_ELEMENT_BYTES - hash.len()..].copy_from_slice(&hash);
+    Ok(result)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn element(value: u64) -> Vec<u8> {
+        let mut element = vec![0u8; FIELD_ELEMENT_BYTES];
+        element[24..].copy_from_slice(&value.to_be_bytes());
+        element
+    }
+
+    fn hash(curve: Curve, inputs: u64) -> String {
+        let inputs: Vec<Vec<u8>> = (1..=inputs).map(element).collect();
+        hex::encode(poseidon_hash(curve, &inputs).unwrap())
+    }
+
+    pub fn test_poseidon_bn254_matches_circomlib() {
+        // circomlib's Poseidon(n) of 1, 2, ..., n
+        assert_eq!(
+            hash(Curve::Bn254, 1),
+            "29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133"
+        );
+        assert_eq!(
+            hash(Curve::Bn254, 2),
+            "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+        );
+        assert_eq!(
+            hash(Curve::Bn254, 5),
+            "0dab9449e4a1398a15224c0b15a49d598b2174d305a316c918125f8feeb123c0"
+        );
+        assert_eq!(
+            hash(Curve::Bn254, 12),
+            "058814945232937db248a01e7cc55b3d681cc08702c8168494e856c1ef7693b5"
+        );
+    }
+
+    pub fn test_poseidon_bls12_381_matches_reference() {
+        // The first element of the reference implementation's poseidonperm_x5_255_3 of [0, 1, 2]
+        assert_eq!(
+            hash(Curve::Bls12_381, 2),
+            "28ce19420fc246a05553ad1e8c98f5c9d67166be2c18e9e4cb4b4e317dd2a78a"
+        );
+        assert_eq!(
+            hash(Curve::Bls12_381, 4),
+            "2a918b9c9f9bd7bb509331c81e297b5707f6fc7393dcee1b13901a0b22202e18"
+        );
+        assert_ne!(hash(Curve::Bls12_381, 1), hash(Curve::Bn254, 1));
+    }
+
+    pub fn test_poseidon_rejects_invalid_inputs() {
+        assert_eq!(Curve::from_u32(2), Err(PoseidonError::InvalidInput));
+        assert_eq!(
+            poseidon_hash(Curve::Bn254, &[]),
+            Err(PoseidonError::InvalidInput)
+        );
+        assert_eq!(
+            poseidon_hash(Curve::Bn254, &vec![element(1); MAX_POSEIDON_INPUTS + 1]),
+            Err(PoseidonError::InvalidInput)
+        );
+        assert_eq!(
+            poseidon_hash(Curve::Bn254, &[vec![1u8; 31]]),
+            Err(PoseidonError::InvalidInput)
+        );
+
+        // The modulus of BN254 is a valid element of BLS12-381, but not of BN254
+        let modulus = Curve::Bn254.modulus().to_bytes_be();
+        assert_eq!(
+            poseidon_hash(Curve::Bn254, std::slice::from_ref(&modulus)),
+            Err(PoseidonError::NonCanonicalInput)
+        );
+        assert!(poseidon_hash(Curve::Bls12_381, &[modulus]).is_ok());
+
+        // Too many inputs are priced like the most that are allowed, before they're rejected
+        assert!(multiplications(2) < multiplications(3));
+        assert_eq!(
+            multiplications(MAX_POSEIDON_INPUTS + 1) - multiplications(MAX_POSEIDON_INPUTS),
+            (3 * FULL_ROUNDS + (FULL_ROUNDS + 65) * 27) as u64
+        );
+    }
+}
@@ -0,0 +1,314 @@
+//! Public namespaces of contract state, for values anyone should be able to read straight from
+//! the chain's state, e.g. the total supply of a token, without a query to the enclave.
+//!
+//! A contract calls `db_public_namespace(prefix)` at the start of an entry point to store the keys
+//! under the prefix in the clear for the rest of that call:
+//!
+//! ```text
+//! key   = "\x00public\x00" || plaintext key
+//! value = plaintext value || tag (16)
+//! ```
+//!
+//! The tag is an AES-SIV tag over the stored key and value, with a key derived from the genesis
+//! state key and the og contract key, so the host can read public values but can't change them
+//! unnoticed. A value whose tag doesn't verify stops the contract.
+//!
+//! Secret data mustn't end up in a public namespace by accident. A call is tainted once it reads
+//! a value of its encrypted state, the answer of a query, or anything the enclave decrypted for
+//! it, and a tainted call can't write to a public namespace until it calls `db_declassify()`,
+//! which states that what it writes next is meant to be public. Reads of public values, the
+//! message and the env don't taint the call.
+//!
+//! A contract that publishes data it derives from its secret state on purpose, e.g. an order book
+//! for indexers, can declare an integrity-only namespace with `db_integrity_namespace(prefix)`
+//! instead. Its values are stored and tagged like public values, but the call's taint isn't
+//! checked when it writes them, so the contract alone decides what goes there.
+
+use log::*;
+
+use enclave_crypto::{hkdf_sha_256, AESKey, SIVEncryptable};
+use enclave_ffi_types::Ctx;
+
+use crate::contract_validation::ContractKey;
+use crate::db::{read_db, remove_db, write_db};
+use crate::errors::{WasmEngineError, WasmEngineResult};
+
+pub const MAX_PUBLIC_NAMESPACES: usize = 8;
+
+/// Can't start an encrypted key, whose first byte is the length of its magic bytes
+pub const PUBLIC_KEY_PREFIX: &[u8] = b"\x00public\x00";
+
+const TAG_DOMAIN: &[u8] = b"public_state";
+const TAG_LENGTH: usize = 16;
+
+/// How the values of a namespace stored in the clear are guarded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamespaceMode {
+    /// Tainted calls can't write to it
+    Public,
+    /// Only the tag guards it, any call can write to it
+    IntegrityOnly,
+}
+
+/// The public and integrity-only namespaces a contract declared during the current call, and
+/// whether the call read secret data since it last declassified
+#[derive(Default)]
+pub struct PublicNamespaces {
+    namespaces: Vec<(Vec<u8>, NamespaceMode)>,
+    tainted: bool,
+}
+
+impl PublicNamespaces {
+    pub fn register(&mut self, namespace: &[u8], mode: NamespaceMode) -> WasmEngineResult<()> {
+        if namespace.is_empty() {
+            debug!("a {:?} namespace can't be empty", mode);
+            return Err(WasmEngineError::InvalidNamespace);
+        }
+        if let Some((_, registered)) = self
+            .namespaces
+            .iter()
+            .find(|(ns, _)| ns.as_slice() == namespace)
+        {
+            if *registered != mode {
+                debug!("namespace was already declared as {:?}", registered);
+                return Err(WasmEngineError::InvalidNamespace);
+            }
+            return Ok(());
+        }
+        if self.namespaces.len() >= MAX_PUBLIC_NAMESPACES {
+            debug!("too many public namespaces");
+            return Err(WasmEngineError::InvalidNamespace);
+        }
+
+        self.namespaces.push((namespace.to_vec(), mode));
+        Ok(())
+    }
+
+    /// The mode of the namespaces `key` belongs to. A key in both a public and an integrity-only
+    /// namespace is public.
+    pub fn mode(&self, key: &[u8]) -> Option<NamespaceMode> {
+        let mut mode = None;
+        for (namespace, namespace_mode) in &self.namespaces {
+            if key.starts_with(namespace) {
+                if *namespace_mode == NamespaceMode::Public {
+                    return Some(NamespaceMode::Public);
+                }
+                mode = Some(*namespace_mode);
+            }
+        }
+
+        mode
+    }
+
+    /// Whether `key` is stored in the clear
+    pub fn is_public(&self, key: &[u8]) -> bool {
+        self.mode(key).is_some()
+    }
+
+    /// Marks the call as having read secret data
+    pub fn taint(&mut self) {
+        self.tainted = true;
+    }
+
+    pub fn declassify(&mut self) {
+        self.tainted = false;
+    }
+
+    /// Fails if `key` is public and the call read secret data since it last declassified
+    pub fn check_write(&self, key: &[u8]) -> WasmEngineResult<()> {
+        if self.tainted && self.mode(key) == Some(NamespaceMode::Public) {
+            debug!("a call that read secret data tried to write to a public namespace");
+            return Err(WasmEngineError::TaintedPublicWrite);
+        }
+
+        Ok(())
+    }
+}
+
+/// The key a public value is stored under
+pub fn public_key(key: &[u8]) -> Vec<u8> {
+    let mut stored_key = PUBLIC_KEY_PREFIX.to_vec();
+    stored_key.extend_from_slice(key);
+    stored_key
+}
+
+fn tag_key(genesis_state_key: &AESKey, og_contract_key: &ContractKey) -> AESKey {
+    let info: Vec<&[u8]> = vec![TAG_DOMAIN, og_contract_key.as_slice()];
+    AESKey::new_from_slice(&hkdf_sha_256(genesis_state_key.get(), info.as_slice()))
+}
+
+/// `value || tag`, as `value` is stored under `stored_key`
+pub fn seal_public_value(
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    stored_key: &[u8],
+    value: &[u8],
+) -> WasmEngineResult<Vec<u8>> {
+    let tag = tag_key(genesis_state_key, og_contract_key)
+        .encrypt_siv(&[], Some(&[stored_key, value]))
+        .map_err(|err| {
+            warn!("failed to tag a public value: {:?}", err);
+            WasmEngineError::EncryptionError
+        })?;
+
+    let mut sealed = value.to_vec();
+    sealed.extend_from_slice(&tag);
+    Ok(sealed)
+}
+
+/// The value of a stored public value, if its tag verifies
+pub fn open_public_value(
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    stored_key: &[u8],
+    sealed: &[u8],
+) -> WasmEngineResult<Vec<u8>> {
+    if sealed.len() < TAG_LENGTH {
+        warn!("public value of key {:?} is too short", stored_key);
+        return Err(WasmEngineError::HostMisbehavior);
+    }
+    let (value, tag) = sealed.split_at(sealed.len() - TAG_LENGTH);
+
+    tag_key(genesis_state_key, og_contract_key)
+        .decrypt_siv(tag, Some(&[stored_key, value]))
+        .map_err(|_| {
+            warn!("public value of key {:?} was tampered with", stored_key);
+            WasmEngineError::HostMisbehavior
+        })?;
+
+    Ok(value.to_vec())
+}
+
+pub fn read_public_state(
+    context: &Ctx,
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    key: &[u8],
+) -> WasmEngineResult<(Option<Vec<u8>>, u64)> {
+    let stored_key = public_key(key);
+    let (sealed, gas_used) = read_db(context, &stored_key)?;
+
+    let value = match sealed {
+        Some(sealed) => Some(open_public_value(
+            genesis_state_key,
+            og_contract_key,
+            &stored_key,
+            &sealed,
+        )?),
+        None => None,
+    };
+
+    Ok((value, gas_used))
+}
+
+pub fn write_public_state(
+    context: &Ctx,
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    key: &[u8],
+    value: &[u8],
+) -> WasmEngineResult<u64> {
+    let stored_key = public_key(key);
+    let sealed = seal_public_value(genesis_state_key, og_contract_key, &stored_key, value)?;
+
+    write_db(context, &stored_key, &sealed)
+}
+
+pub fn remove_public_state(context: &Ctx, key: &[u8]) -> WasmEngineResult<u64> {
+    remove_db(context, &public_key(key))
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use enclave_crypto::sha_256;
+
+    pub fn test_public_namespaces_taint() {
+        let mut namespaces = PublicNamespaces::default();
+        assert!(namespaces.register(b"", NamespaceMode::Public).is_err());
+        namespaces
+            .register(b"public/", NamespaceMode::Public)
+            .unwrap();
+        namespaces
+            .register(b"public/", NamespaceMode::Public)
+            .unwrap();
+        assert!(namespaces
+            .register(b"public/", NamespaceMode::IntegrityOnly)
+            .is_err());
+
+        assert!(namespaces.is_public(b"public/total_supply"));
+        assert!(!namespaces.is_public(b"balances/alice"));
+
+        for i in 1..MAX_PUBLIC_NAMESPACES {
+            namespaces
+                .register(&[i as u8], NamespaceMode::Public)
+                .unwrap();
+        }
+        assert!(namespaces
+            .register(b"one too many", NamespaceMode::Public)
+            .is_err());
+
+        assert!(namespaces.check_write(b"public/total_supply").is_ok());
+        namespaces.taint();
+        assert!(namespaces.check_write(b"public/total_supply").is_err());
+        namespaces.declassify();
+        assert!(namespaces.check_write(b"public/total_supply").is_ok());
+    }
+
+    pub fn test_integrity_only_namespaces() {
+        let mut namespaces = PublicNamespaces::default();
+        namespaces
+            .register(b"book/", NamespaceMode::IntegrityOnly)
+            .unwrap();
+        namespaces
+            .register(b"book/totals/", NamespaceMode::Public)
+            .unwrap();
+
+        assert_eq!(
+            namespaces.mode(b"book/bids/1"),
+            Some(NamespaceMode::IntegrityOnly)
+        );
+        assert_eq!(
+            namespaces.mode(b"book/totals/bids"),
+            Some(NamespaceMode::Public)
+        );
+        assert_eq!(namespaces.mode(b"balances/alice"), None);
+
+        // a tainted call can still write integrity-only values, but not public ones
+        namespaces.taint();
+        assert!(namespaces.check_write(b"book/bids/1").is_ok());
+        assert!(namespaces.check_write(b"book/totals/bids").is_err());
+        assert!(namespaces.check_write(b"balances/alice").is_ok());
+    }
+
+    pub fn test_public_value_integrity() {
+        let state_key = AESKey::new_from_slice(&sha_256(b"state key"));
+        let contract = [1u8; 64];
+        let stored_key = public_key(b"public/total_supply");
+        assert!(stored_key.starts_with(PUBLIC_KEY_PREFIX));
+
+        let sealed = seal_public_value(&state_key, &contract, &stored_key, b"1000000").unwrap();
+        assert!(sealed.starts_with(b"1000000"));
+        assert_eq!(sealed.len(), 7 + TAG_LENGTH);
+        assert_eq!(
+            open_public_value(&state_key, &contract, &stored_key, &sealed).unwrap(),
+            b"1000000"
+        );
+
+        // the tag binds the value, the key and the contract
+        let mut tampered = sealed.clone();
+        tampered[0] = b'9';
+        assert!(open_public_value(&state_key, &contract, &stored_key, &tampered).is_err());
+        let other_key = public_key(b"public/burned");
+        assert!(open_public_value(&state_key, &contract, &other_key, &sealed).is_err());
+        assert!(open_public_value(&state_key, &[2u8; 64], &stored_key, &sealed).is_err());
+        assert!(open_public_value(&state_key, &contract, &stored_key, &sealed[..3]).is_err());
+
+        let empty = seal_public_value(&state_key, &contract, &stored_key, b"").unwrap();
+        assert_eq!(
+            open_public_value(&state_key, &contract, &stored_key, &empty).unwrap(),
+            b""
+        );
+    }
+}
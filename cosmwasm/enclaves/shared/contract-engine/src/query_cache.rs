@@ -0,0 +1,180 @@
+//! Cache of query responses, for query farms that get the same query over and over in a block.
+//!
+//! Responses are keyed by the contract, the hash of the encrypted query and the block height, so
+//! a hit is only possible for the exact same ciphertext, from the same querier, and the cached
+//! response is already encrypted to that querier. The state can't change within a height for
+//! queries from outside the chain, which are the only ones the untrusted side marks as
+//! cacheable, so the cache is kept until the first query at a newer height. It's also dropped
+//! whenever a contract is executed, so a query that was wrongly marked as cacheable can't
+//! outlive a state change.
+
+use std::collections::HashMap;
+use std::sync::SgxMutex;
+
+use lazy_static::lazy_static;
+use log::*;
+
+use enclave_crypto::{sha_256, HASH_SIZE};
+
+use crate::external::results::QuerySuccess;
+
+const MAX_CACHED_RESPONSES: usize = 1024;
+/// Larger responses are delivered in chunks, and aren't worth keeping around
+const MAX_CACHED_RESPONSE_SIZE: usize = 64 * 1024; // 64 KiB
+
+#[derive(Hash, PartialEq, Eq)]
+struct QueryCacheKey {
+    contract: Vec<u8>,
+    query_hash: [u8; HASH_SIZE],
+}
+
+struct CachedResponse {
+    output: Vec<u8>,
+    used_gas: u64,
+}
+
+#[derive(Default)]
+struct QueryCache {
+    height: u64,
+    responses: HashMap<QueryCacheKey, CachedResponse>,
+}
+
+lazy_static! {
+    static ref QUERY_CACHE: SgxMutex<QueryCache> = SgxMutex::new(QueryCache::default());
+}
+
+impl QueryCache {
+    /// Drops the responses of older heights. Returns false for queries of a height older than
+    /// the cached one, which are neither looked up nor cached.
+    fn advance_to(&mut self, height: u64) -> bool {
+        if height < self.height {
+            return false;
+        }
+
+        if height > self.height {
+            self.responses.clear();
+            self.height = height;
+        }
+
+        true
+    }
+}
+
+fn cache_key(contract: &[u8], encrypted_query: &[u8]) -> QueryCacheKey {
+    QueryCacheKey {
+        contract: contract.to_vec(),
+        query_hash: sha_256(encrypted_query),
+    }
+}
+
+/// The cached response to `encrypted_query`, and the gas it used, if it was answered at `height`
+pub fn get(contract: &[u8], encrypted_query: &[u8], height: u64) -> Option<(QuerySuccess, u64)> {
+    let mut cache = QUERY_CACHE.lock().unwrap();
+    if !cache.advance_to(height) {
+        return None;
+    }
+
+    cache
+        .responses
+        .get(&cache_key(contract, encrypted_query))
+        .map(|cached| {
+            trace!("query answered from the cache");
+            (
+                QuerySuccess {
+                    output: cached.output.clone(),
+                },
+                cached.used_gas,
+            )
+        })
+}
+
+pub fn insert(
+    contract: &[u8],
+    encrypted_query: &[u8],
+    height: u64,
+    response: &QuerySuccess,
+    used_gas: u64,
+) {
+    if response.output.len() > MAX_CACHED_RESPONSE_SIZE {
+        return;
+    }
+
+    let mut cache = QUERY_CACHE.lock().unwrap();
+    if !cache.advance_to(height) {
+        return;
+    }
+
+    if cache.responses.len() >= MAX_CACHED_RESPONSES {
+        debug!("too many cached query responses, dropping them");
+        cache.responses.clear();
+    }
+
+    cache.responses.insert(
+        cache_key(contract, encrypted_query),
+        CachedResponse {
+            output: response.output.clone(),
+            used_gas,
+        },
+    );
+}
+
+/// Drops every cached response, called whenever contract state may change
+pub fn invalidate() {
+    QUERY_CACHE.lock().unwrap().responses.clear();
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn response(output: &[u8]) -> QuerySuccess {
+        QuerySuccess {
+            output: output.to_vec(),
+        }
+    }
+
+    pub fn test_query_cache_hit() {
+        invalidate();
+
+        insert(b"contract", b"query", 100, &response(b"answer"), 1234);
+
+        let (cached, used_gas) = get(b"contract", b"query", 100).unwrap();
+        assert_eq!(cached.output, b"answer");
+        assert_eq!(used_gas, 1234);
+
+        assert!(get(b"contract", b"other query", 100).is_none());
+        assert!(get(b"other contract", b"query", 100).is_none());
+
+        invalidate();
+        assert!(get(b"contract", b"query", 100).is_none());
+    }
+
+    pub fn test_query_cache_block_boundary() {
+        invalidate();
+
+        insert(b"contract", b"query", 200, &response(b"answer"), 1);
+
+        // a query of an older height neither sees nor drops the cached responses
+        assert!(get(b"contract", b"query", 199).is_none());
+        insert(b"contract", b"query", 199, &response(b"old answer"), 1);
+        assert_eq!(get(b"contract", b"query", 200).unwrap().0.output, b"answer");
+
+        assert!(get(b"contract", b"query", 201).is_none());
+        assert!(get(b"contract", b"query", 200).is_none());
+    }
+
+    pub fn test_query_cache_limits() {
+        invalidate();
+
+        let large = vec![0u8; MAX_CACHED_RESPONSE_SIZE + 1];
+        insert(b"contract", b"query", 300, &response(&large), 1);
+        assert!(get(b"contract", b"query", 300).is_none());
+
+        for i in 0..MAX_CACHED_RESPONSES {
+            insert(b"contract", &i.to_le_bytes(), 300, &response(b"answer"), 1);
+        }
+        insert(b"contract", b"query", 300, &response(b"answer"), 1);
+        assert!(get(b"contract", &0usize.to_le_bytes(), 300).is_none());
+        assert!(get(b"contract", b"query", 300).is_some());
+    }
+}
@@ -0,0 +1,93 @@
+//! Cancellation of stuck queries from outside the chain.
+//!
+//! A query that runs into a slow path of a contract holds one of the enclave's threads until it
+//! returns, and a handful of them is enough to starve the node. The untrusted side can pass a
+//! `query_cancellation_id` in the env of a query it may want to give up on, e.g. because the
+//! client's deadline passed, and later call `ecall_cancel_query` with the same id. The query is
+//! stopped the next time it calls into the enclave, like a query past its time limit.
+//!
+//! Only queries the untrusted side marks as coming from outside the chain can be cancelled.
+//! Queries that contracts make while a tx executes must give the same result on every node, so
+//! their id is ignored.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, SgxMutex};
+
+use lazy_static::lazy_static;
+use log::*;
+
+lazy_static! {
+    static ref RUNNING_QUERIES: SgxMutex<HashMap<u64, Arc<AtomicBool>>> =
+        SgxMutex::new(HashMap::new());
+}
+
+/// The flag of a running query, which is unregistered once the query returns
+pub struct QueryCancellation {
+    id: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl QueryCancellation {
+    /// Registers a query under `id`, unless a running query already has it
+    pub fn register(id: u64) -> Option<Self> {
+        let mut running = RUNNING_QUERIES.lock().unwrap();
+        if running.contains_key(&id) {
+            warn!("query cancellation id {} is already in use", id);
+            return None;
+        }
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        running.insert(id, cancelled.clone());
+        Some(Self { id, cancelled })
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for QueryCancellation {
+    fn drop(&mut self) {
+        RUNNING_QUERIES.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Cancels the running query registered under `id`. Returns false if there's none, e.g. because
+/// the query already returned.
+pub fn cancel_query(id: u64) -> bool {
+    match RUNNING_QUERIES.lock().unwrap().get(&id) {
+        Some(cancelled) => {
+            debug!("cancelling query {}", id);
+            cancelled.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_query_cancellation() {
+        let query = QueryCancellation::register(7).unwrap();
+        assert!(!query.is_cancelled());
+        // a running query's id can't be reused
+        assert!(QueryCancellation::register(7).is_none());
+
+        let other = QueryCancellation::register(8).unwrap();
+        assert!(cancel_query(7));
+        assert!(query.is_cancelled());
+        assert!(!other.is_cancelled());
+    }
+
+    pub fn test_query_cancellation_after_return() {
+        drop(QueryCancellation::register(9).unwrap());
+        assert!(!cancel_query(9));
+
+        // the id is free again once the query returned
+        let query = QueryCancellation::register(9).unwrap();
+        assert!(!query.is_cancelled());
+    }
+}
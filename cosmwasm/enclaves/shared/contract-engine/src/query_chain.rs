@@ -4,11 +4,13 @@ use sgx_types::sgx_status_t;
 
 use enclave_ffi_types::{Ctx, EnclaveBuffer, OcallReturn, UntrustedVmError};
 
-use enclave_crypto::Ed25519PublicKey;
+use enclave_crypto::{ocalls, Ed25519PublicKey};
 use enclave_utils::recursion_depth;
 
 use super::errors::WasmEngineError;
-use crate::external::{ecalls, ocalls};
+#[cfg(feature = "differential-execution")]
+use crate::differential::{self, HostRequest};
+use crate::external::ecalls;
 use crate::types::{IoNonce, SecretMessage};
 
 use cw_types_v010::{
@@ -144,6 +146,76 @@ pub fn encrypt_and_query_chain(
     Ok(answer_as_vec)
 }
 
+/// Queries x/compute for the random it stored for the block at `height`. Contracts can't send
+/// this query, because their queries have to parse as a `QueryRequest` first.
+#[cfg(all(feature = "random", feature = "light-client-validation"))]
+pub fn query_block_random(
+    context: &Ctx,
+    height: u64,
+    query_depth: u32,
+    gas_used: &mut u64,
+    gas_limit: u64,
+) -> Result<Option<Binary>, WasmEngineError> {
+    let query = serde_json::to_vec(&serde_json::json!({
+        "block_random": { "height": height.to_string() }
+    }))
+    .map_err(|_| WasmEngineError::SerializationError)?;
+
+    let (result, query_used_gas) = query_chain(context, &query, query_depth, gas_limit);
+    *gas_used = query_used_gas;
+
+    match serde_json::from_slice::<SystemResult<StdResult<Binary>>>(&result?) {
+        Ok(Ok(Ok(random))) if random.0.len() == 32 => Ok(Some(random)),
+        other => {
+            debug!(
+                "query_block_random() got no random for height {}: {:?}",
+                height, other
+            );
+            Ok(None)
+        }
+    }
+}
+
+/// A value of this chain's state as of the last block, with an ICS-23 proof of it against the app
+/// hash of the current block
+#[cfg(feature = "light-client-validation")]
+#[derive(serde::Deserialize, Debug)]
+pub struct ProvenState {
+    pub value: Binary,
+    pub proof: Binary,
+}
+
+/// Queries the node for the state the IBC module stores for the client `client_id`, with its
+/// proof. Like `query_block_random`, contracts can't send this query.
+#[cfg(feature = "light-client-validation")]
+pub fn query_ibc_client_state(
+    context: &Ctx,
+    client_id: &str,
+    query_depth: u32,
+    gas_used: &mut u64,
+    gas_limit: u64,
+) -> Result<Option<ProvenState>, WasmEngineError> {
+    let query = serde_json::to_vec(&serde_json::json!({
+        "ibc_client_state": { "client_id": client_id }
+    }))
+    .map_err(|_| WasmEngineError::SerializationError)?;
+
+    let (result, query_used_gas) = query_chain(context, &query, query_depth, gas_limit);
+    *gas_used = query_used_gas;
+
+    let state = match serde_json::from_slice::<SystemResult<StdResult<Binary>>>(&result?) {
+        Ok(Ok(Ok(state))) if !state.0.is_empty() => serde_json::from_slice(state.as_slice()).ok(),
+        _ => None,
+    };
+    if state.is_none() {
+        debug!(
+            "query_ibc_client_state() got no proven state of client {}",
+            client_id
+        );
+    }
+    Ok(state)
+}
+
 /// Safe wrapper around quering other contracts and modules
 fn query_chain(
     context: &Ctx,
@@ -155,6 +227,24 @@ fn query_chain(
     let mut enclave_buffer = std::mem::MaybeUninit::<EnclaveBuffer>::uninit();
     let mut vm_err = UntrustedVmError::default();
     let mut gas_used = 0_u64;
+
+    #[cfg(feature = "differential-execution")]
+    let request = HostRequest::Query {
+        query: query.to_vec(),
+        query_depth,
+        gas_limit,
+    };
+    #[cfg(feature = "differential-execution")]
+    if let Some(response) = differential::replay(&request) {
+        return match response {
+            Ok(response) => (Ok(response.value.unwrap_or_default()), response.gas_used),
+            Err(err) => (Err(err), 0),
+        };
+    }
+    // The queried contract runs on this thread, and its storage accesses aren't ours
+    #[cfg(feature = "differential-execution")]
+    let suspended = differential::suspend();
+
     let value = unsafe {
         let status = ocalls::ocall_query_chain(
             &mut ocall_return,
@@ -194,6 +284,12 @@ fn query_chain(
         }
     };
 
+    #[cfg(feature = "differential-execution")]
+    {
+        drop(suspended);
+        differential::record(request, Some(&value), gas_used);
+    }
+
     (Ok(value), gas_used)
 }
 
@@ -212,6 +308,16 @@ fn check_recursion_limit(query_depth: u32) -> Option<SystemResult<StdResult<Bina
     }
 }
 
+/// The answer to a query of a contract that can't query the chain, like a job handler that runs
+/// off-chain
+pub fn queries_unavailable_response() -> Result<Vec<u8>, WasmEngineError> {
+    let answer: SystemResult<StdResult<Binary>> = Err(SystemError::UnsupportedRequest {
+        kind: "queries from job handlers".to_string(),
+    });
+
+    serialize_error_response(&answer)
+}
+
 fn system_error_invalid_request<T>(request: &[u8], err: T) -> Result<Vec<u8>, WasmEngineError>
 where
     T: std::fmt::Debug + ToString,
@@ -0,0 +1,224 @@
+//! Chunked delivery of large query responses.
+//!
+//! Query outputs are handed to the untrusted side in a single buffer, which fails opaquely for
+//! megabyte-scale responses. Responses larger than `QUERY_CHUNK_SIZE` are instead kept in the
+//! enclave: `ecall_query` returns the first chunk, the total size, and a continuation token, and
+//! the untrusted side fetches the rest with `ecall_query_chunk` until it gets an all-zero token.
+//! The response is already encrypted to the querier at that point, so every chunk is as well.
+
+use std::collections::HashMap;
+use std::sync::SgxMutex;
+
+use lazy_static::lazy_static;
+use log::*;
+
+use enclave_ffi_types::EnclaveError;
+
+pub type ContinuationToken = [u8; 32];
+
+/// Returned when there are no more chunks to fetch
+pub const NO_CONTINUATION: ContinuationToken = [0u8; 32];
+
+pub const QUERY_CHUNK_SIZE: usize = 256 * 1024; // 256 KiB
+const MAX_CHUNKED_RESPONSE_SIZE: usize = 16 * 1024 * 1024; // 16 MiB
+/// The most the pending responses of all queriers may hold in the enclave's heap together. The
+/// least recently fetched responses are dropped to make room for new ones.
+const MAX_PENDING_BYTES: usize = 2 * MAX_CHUNKED_RESPONSE_SIZE; // 32 MiB
+
+struct PendingResponse {
+    /// Everything after the first chunk
+    data: Vec<u8>,
+    offset: usize,
+    total_size: usize,
+    /// When the response was last stored or fetched from, for eviction
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct PendingResponses {
+    responses: HashMap<ContinuationToken, PendingResponse>,
+    /// The bytes held by `responses`
+    size: usize,
+    clock: u64,
+}
+
+impl PendingResponses {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn insert(&mut self, token: ContinuationToken, data: Vec<u8>, total_size: usize) {
+        while self.size + data.len() > MAX_PENDING_BYTES {
+            let oldest = match self
+                .responses
+                .iter()
+                .min_by_key(|(_, response)| response.last_used)
+            {
+                Some((token, _)) => *token,
+                None => break,
+            };
+            warn!("too many pending query responses, dropping the least recently fetched");
+            self.remove(&oldest);
+        }
+
+        let last_used = self.tick();
+        self.size += data.len();
+        self.responses.insert(
+            token,
+            PendingResponse {
+                data,
+                offset: 0,
+                total_size,
+                last_used,
+            },
+        );
+    }
+
+    fn remove(&mut self, token: &ContinuationToken) {
+        if let Some(response) = self.responses.remove(token) {
+            self.size -= response.data.len();
+        }
+    }
+}
+
+lazy_static! {
+    static ref PENDING_RESPONSES: SgxMutex<PendingResponses> =
+        SgxMutex::new(PendingResponses::default());
+}
+
+pub struct QueryChunk {
+    pub data: Vec<u8>,
+    pub total_size: usize,
+    pub continuation_token: ContinuationToken,
+}
+
+/// Split a query response, keeping everything after the first chunk in the enclave
+pub fn first_chunk(mut output: Vec<u8>) -> Result<QueryChunk, EnclaveError> {
+    let total_size = output.len();
+    if total_size <= QUERY_CHUNK_SIZE {
+        return Ok(QueryChunk {
+            data: output,
+            total_size,
+            continuation_token: NO_CONTINUATION,
+        });
+    }
+
+    if total_size > MAX_CHUNKED_RESPONSE_SIZE {
+        warn!("query response of {} bytes is too large", total_size);
+        return Err(EnclaveError::FailedToSerialize);
+    }
+
+    let mut token = NO_CONTINUATION;
+    enclave_crypto::rand_slice(&mut token).map_err(|err| {
+        error!("failed to generate a continuation token: {:?}", err);
+        EnclaveError::InternalError
+    })?;
+
+    let rest = output.split_off(QUERY_CHUNK_SIZE);
+    PENDING_RESPONSES
+        .lock()
+        .unwrap()
+        .insert(token, rest, total_size);
+
+    trace!(
+        "query response of {} bytes will be sent in chunks",
+        total_size
+    );
+
+    Ok(QueryChunk {
+        data: output,
+        total_size,
+        continuation_token: token,
+    })
+}
+
+/// Fetch the next chunk of a pending response. The returned token is `NO_CONTINUATION` once the
+/// last chunk was fetched.
+pub fn next_chunk(token: &ContinuationToken) -> Result<QueryChunk, EnclaveError> {
+    let mut pending = PENDING_RESPONSES.lock().unwrap();
+
+    let last_used = pending.tick();
+    let response = pending.responses.get_mut(token).ok_or_else(|| {
+        warn!("got an unknown continuation token");
+        EnclaveError::ValidationFailure
+    })?;
+    response.last_used = last_used;
+
+    let end = std::cmp::min(response.offset + QUERY_CHUNK_SIZE, response.data.len());
+    let data = response.data[response.offset..end].to_vec();
+    response.offset = end;
+    let total_size = response.total_size;
+
+    let continuation_token = if end == response.data.len() {
+        pending.remove(token);
+        NO_CONTINUATION
+    } else {
+        *token
+    };
+
+    Ok(QueryChunk {
+        data,
+        total_size,
+        continuation_token,
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_query_chunks_small_response() {
+        let chunk = first_chunk(vec![1u8; 10]).unwrap();
+        assert_eq!(chunk.data.len(), 10);
+        assert_eq!(chunk.total_size, 10);
+        assert_eq!(chunk.continuation_token, NO_CONTINUATION);
+    }
+
+    pub fn test_query_chunks_reassemble() {
+        let output: Vec<u8> = (0..(2 * QUERY_CHUNK_SIZE + 17))
+            .map(|i| (i % 251) as u8)
+            .collect();
+
+        let first = first_chunk(output.clone()).unwrap();
+        assert_eq!(first.total_size, output.len());
+        assert_ne!(first.continuation_token, NO_CONTINUATION);
+
+        let mut assembled = first.data;
+        let mut token = first.continuation_token;
+        while token != NO_CONTINUATION {
+            let chunk = next_chunk(&token).unwrap();
+            assembled.extend_from_slice(&chunk.data);
+            token = chunk.continuation_token;
+        }
+
+        assert_eq!(assembled, output);
+        // the response is dropped after the last chunk
+        assert!(next_chunk(&first.continuation_token).is_err());
+    }
+
+    pub fn test_query_chunks_eviction() {
+        let response = || vec![7u8; QUERY_CHUNK_SIZE + MAX_CHUNKED_RESPONSE_SIZE / 2];
+        let held = |n: usize| n * (MAX_CHUNKED_RESPONSE_SIZE / 2);
+
+        let mut pending = PendingResponses::default();
+        for i in 0..4u8 {
+            pending.insert([i + 1; 32], response().split_off(QUERY_CHUNK_SIZE), 0);
+        }
+        assert_eq!(pending.responses.len(), 4);
+        assert_eq!(pending.size, held(4));
+
+        // fetching from the first response makes the second the least recently used
+        pending.responses.get_mut(&[1; 32]).unwrap().last_used = pending.tick();
+
+        pending.insert([5; 32], response().split_off(QUERY_CHUNK_SIZE), 0);
+        assert_eq!(pending.responses.len(), 4);
+        assert_eq!(pending.size, held(4));
+        assert!(pending.responses.contains_key(&[1; 32]));
+        assert!(!pending.responses.contains_key(&[2; 32]));
+        assert!(pending.responses.contains_key(&[5; 32]));
+
+        pending.remove(&[1; 32]);
+        assert_eq!(pending.size, held(3));
+    }
+}
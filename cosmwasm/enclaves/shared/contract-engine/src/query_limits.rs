@@ -0,0 +1,147 @@
+//! Limits the node operator sets for queries from outside the chain, on top of the limits that
+//! apply to every contract call.
+//!
+//! Queries over gRPC/REST run on the same enclave as the blocks a validator executes, so a
+//! stream of expensive queries can slow the validator down. Operators can set a lower gas limit,
+//! a lower memory ceiling and a wall-clock time limit for these queries, without changing what a
+//! contract can do in a tx. A limit of 0 leaves that resource at the default.
+//!
+//! The limits only apply to queries the untrusted side marks as coming from outside the chain,
+//! the same ones `query_cache` caches. Queries that contracts make while a tx executes must give
+//! the same result on every node, so they never see these limits. Nested queries of an
+//! off-chain query run in their own engine with the default memory ceiling, but they are bounded
+//! by the gas left to the outer query, and the time they take counts against its deadline.
+//!
+//! The time limit is checked whenever the contract calls into the enclave, and the gas limit
+//! still bounds a contract that loops without calling out. The clock comes from the host, which
+//! is fine for a limit that protects the host itself.
+
+use std::sync::SgxRwLock;
+use std::time::{Duration, Instant};
+use std::untrusted::time::InstantEx;
+
+use lazy_static::lazy_static;
+use log::*;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueryLimits {
+    /// The most wasm pages (64 KiB each) the contract's memory can grow to
+    pub memory_pages: u32,
+    pub gas: u64,
+    pub time_ms: u64,
+}
+
+lazy_static! {
+    static ref QUERY_LIMITS: SgxRwLock<QueryLimits> = SgxRwLock::new(QueryLimits::default());
+}
+
+pub fn configure_query_limits(limits: QueryLimits) {
+    debug!("configuring query limits: {:?}", limits);
+    *QUERY_LIMITS.write().unwrap() = limits;
+}
+
+/// The limits of a query, which are only set for queries from outside the chain
+pub fn query_limits(from_outside_chain: bool) -> QueryLimits {
+    if from_outside_chain {
+        *QUERY_LIMITS.read().unwrap()
+    } else {
+        QueryLimits::default()
+    }
+}
+
+impl QueryLimits {
+    pub fn gas_limit(&self, gas_limit: u64) -> u64 {
+        match self.gas {
+            0 => gas_limit,
+            limit => gas_limit.min(limit),
+        }
+    }
+
+    pub fn memory_limit_pages(&self, memory_limit_pages: u32) -> u32 {
+        match self.memory_pages {
+            0 => memory_limit_pages,
+            limit => memory_limit_pages.min(limit),
+        }
+    }
+
+    /// The deadline of a query that starts now, if there's a time limit
+    pub fn deadline(&self) -> Option<QueryDeadline> {
+        match self.time_ms {
+            0 => None,
+            limit => Some(QueryDeadline {
+                started: Instant::now(),
+                limit: Duration::from_millis(limit),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct QueryDeadline {
+    started: Instant,
+    limit: Duration,
+}
+
+impl QueryDeadline {
+    pub fn has_passed(&self) -> bool {
+        self.exceeded_by(self.started.elapsed())
+    }
+
+    fn exceeded_by(&self, elapsed: Duration) -> bool {
+        elapsed > self.limit
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_query_limits_defaults() {
+        let none = QueryLimits::default();
+        assert_eq!(none.gas_limit(1_000_000), 1_000_000);
+        assert_eq!(none.memory_limit_pages(192), 192);
+        assert!(none.deadline().is_none());
+
+        let limits = QueryLimits {
+            memory_pages: 64,
+            gas: 500_000,
+            time_ms: 0,
+        };
+        assert_eq!(limits.gas_limit(1_000_000), 500_000);
+        assert_eq!(limits.gas_limit(100_000), 100_000);
+        assert_eq!(limits.memory_limit_pages(192), 64);
+        // a limit above the default doesn't raise it
+        assert_eq!(
+            QueryLimits {
+                memory_pages: 1024,
+                ..limits
+            }
+            .memory_limit_pages(192),
+            192
+        );
+    }
+
+    pub fn test_query_limits_only_off_chain() {
+        let limits = QueryLimits {
+            memory_pages: 64,
+            gas: 500_000,
+            time_ms: 100,
+        };
+        configure_query_limits(limits);
+        assert_eq!(query_limits(true), limits);
+        assert_eq!(query_limits(false), QueryLimits::default());
+        configure_query_limits(QueryLimits::default());
+    }
+
+    pub fn test_query_deadline() {
+        let deadline = QueryLimits {
+            time_ms: 60_000,
+            ..Default::default()
+        }
+        .deadline()
+        .unwrap();
+        assert!(!deadline.has_passed());
+        assert!(!deadline.exceeded_by(Duration::from_millis(60_000)));
+        assert!(deadline.exceeded_by(Duration::from_millis(60_001)));
+    }
+}
@@ -0,0 +1,286 @@
+//! Replay protection for the queries of contracts that opt into it.
+//!
+//! Anyone who sees an encrypted query, e.g. on its way to a public node, can send it to that
+//! node again later and watch how the response changes. A contract that exports
+//! `requires_query_replay_protection` only has each of its queries from outside the chain
+//! answered once: the enclave remembers every query message the contract was sent within the
+//! last `REPLAY_WINDOW_EPOCHS` epochs of `REPLAY_EPOCH_BLOCKS` blocks, and rejects those it
+//! already saw.
+//!
+//! Each epoch is a bitmap, in which a message sets `BITS_PER_MESSAGE` bits picked by its hash.
+//! A message is known when all of its bits are set in one of the epochs, so a new message is
+//! rejected by mistake once in a while, more often the more queries an epoch sees. The epochs
+//! slide by the height of the last block the enclave verified, which the node can't choose, and
+//! the window of every contract is sealed to its own file after each query.
+
+use std::path;
+use std::sync::SgxMutex;
+use std::untrusted::path::PathEx;
+
+use lazy_static::lazy_static;
+use log::*;
+
+use enclave_crypto::consts::{make_sgx_secret_path, SEALED_FILE_QUERY_REPLAY_PREFIX};
+use enclave_crypto::sha_256;
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::storage::{seal, unseal};
+
+#[cfg(feature = "light-client-validation")]
+use block_verifier::VERIFIED_BLOCK_MESSAGES;
+
+/// Roughly 10 minutes at 6 seconds per block
+pub const REPLAY_EPOCH_BLOCKS: u64 = 100;
+/// A message is remembered for at least `REPLAY_WINDOW_EPOCHS - 1` full epochs
+pub const REPLAY_WINDOW_EPOCHS: u64 = 6;
+
+const EPOCH_BITS: usize = 1 << 17;
+const EPOCH_BYTES: usize = EPOCH_BITS / 8;
+const BITS_PER_MESSAGE: usize = 4;
+
+/// `epoch || bitmap`
+const ENTRY_LEN: usize = 8 + EPOCH_BYTES;
+
+pub type MessageId = [u8; 32];
+
+lazy_static! {
+    /// A query mustn't load a window while another query is sealing it
+    static ref QUERY_REPLAY_LOCK: SgxMutex<()> = SgxMutex::new(());
+}
+
+/// The id of a query message is the hash of the whole encrypted message, so queries of a session,
+/// which share a nonce, are still told apart
+pub fn message_id(msg: &[u8]) -> MessageId {
+    sha_256(msg)
+}
+
+fn bit_positions(id: &MessageId) -> [usize; BITS_PER_MESSAGE] {
+    let mut positions = [0usize; BITS_PER_MESSAGE];
+    for (position, chunk) in positions.iter_mut().zip(id.chunks(4)) {
+        let word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        *position = word as usize % EPOCH_BITS;
+    }
+    positions
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Epoch {
+    number: u64,
+    bits: Vec<u8>,
+}
+
+impl Epoch {
+    fn new(number: u64) -> Self {
+        Self {
+            number,
+            bits: vec![0u8; EPOCH_BYTES],
+        }
+    }
+
+    fn contains(&self, positions: &[usize]) -> bool {
+        positions
+            .iter()
+            .all(|position| self.bits[position / 8] & (1 << (position % 8)) != 0)
+    }
+
+    fn insert(&mut self, positions: &[usize]) {
+        for position in positions {
+            self.bits[position / 8] |= 1 << (position % 8);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayWindow {
+    /// Oldest first
+    epochs: Vec<Epoch>,
+}
+
+impl ReplayWindow {
+    /// Records the message at `height`, unless it's already in the window. Returns whether it was
+    /// new.
+    pub fn check_and_record(&mut self, id: &MessageId, height: u64) -> bool {
+        self.slide(height);
+
+        let positions = bit_positions(id);
+        if self.epochs.iter().any(|epoch| epoch.contains(&positions)) {
+            return false;
+        }
+
+        if let Some(newest) = self.epochs.last_mut() {
+            newest.insert(&positions);
+        }
+        true
+    }
+
+    /// Starts the epoch of `height`, and drops the epochs that slide out of the window. A height
+    /// before the newest epoch, e.g. while a restarted enclave hasn't verified a block yet, is
+    /// recorded in the newest epoch.
+    fn slide(&mut self, height: u64) {
+        let number = height / REPLAY_EPOCH_BLOCKS;
+        if let Some(newest) = self.epochs.last() {
+            if newest.number >= number {
+                return;
+            }
+        }
+
+        self.epochs
+            .retain(|epoch| epoch.number + REPLAY_WINDOW_EPOCHS > number);
+        self.epochs.push(Epoch::new(number));
+    }
+
+    /// The epochs, each as `epoch || bitmap`, with little endian epoch numbers
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.epochs.len() * ENTRY_LEN);
+        for epoch in &self.epochs {
+            out.extend_from_slice(&epoch.number.to_le_bytes());
+            out.extend_from_slice(&epoch.bits);
+        }
+        out
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() % ENTRY_LEN != 0 || bytes.len() / ENTRY_LEN > REPLAY_WINDOW_EPOCHS as usize {
+            return None;
+        }
+
+        let mut epochs: Vec<Epoch> = vec![];
+        for entry in bytes.chunks(ENTRY_LEN) {
+            let mut number = [0u8; 8];
+            number.copy_from_slice(&entry[..8]);
+            let number = u64::from_le_bytes(number);
+
+            if epochs
+                .last()
+                .map_or(false, |previous| previous.number >= number)
+            {
+                return None;
+            }
+            epochs.push(Epoch {
+                number,
+                bits: entry[8..].to_vec(),
+            });
+        }
+        Some(Self { epochs })
+    }
+
+    fn load(sealing_path: &str) -> Self {
+        if !path::Path::new(sealing_path).exists() {
+            return Self::default();
+        }
+
+        match unseal(sealing_path)
+            .ok()
+            .and_then(|sealed| Self::deserialize(&sealed))
+        {
+            Some(window) => window,
+            None => {
+                warn!("Failed to load a query replay window, starting from an empty one");
+                Self::default()
+            }
+        }
+    }
+}
+
+fn sealing_path(contract_address: &[u8]) -> String {
+    make_sgx_secret_path(&format!(
+        "{}{}.sealed",
+        SEALED_FILE_QUERY_REPLAY_PREFIX,
+        hex::encode(contract_address)
+    ))
+}
+
+#[cfg(feature = "light-client-validation")]
+fn current_height() -> Result<u64, EnclaveError> {
+    Ok(VERIFIED_BLOCK_MESSAGES.lock().unwrap().height())
+}
+
+// Without the light client, the only height the enclave knows is the one the node says
+#[cfg(not(feature = "light-client-validation"))]
+fn current_height() -> Result<u64, EnclaveError> {
+    warn!("can't protect queries from replays without light client validation");
+    Err(EnclaveError::ValidationFailure)
+}
+
+/// Rejects a query message that the contract was already sent within the replay window, and
+/// records it otherwise
+pub fn check_query_replay(contract_address: &[u8], msg: &[u8]) -> Result<(), EnclaveError> {
+    let height = current_height()?;
+    let sealing_path = sealing_path(contract_address);
+
+    let _guard = QUERY_REPLAY_LOCK.lock().unwrap();
+    let mut window = ReplayWindow::load(&sealing_path);
+
+    if !window.check_and_record(&message_id(msg), height) {
+        warn!(
+            "query to contract {} was already answered within the replay window",
+            hex::encode(contract_address)
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    // A message that isn't sealed would be forgotten as soon as it's answered
+    seal(&window.serialize(), &sealing_path).map_err(|err| {
+        error!("Error sealing a query replay window: {}", err);
+        EnclaveError::FailedSeal
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_query_replay_rejects_replays() {
+        let mut window = ReplayWindow::default();
+        let first = message_id(b"first");
+        let second = message_id(b"second");
+
+        assert!(window.check_and_record(&first, 1000));
+        assert!(!window.check_and_record(&first, 1000));
+        assert!(window.check_and_record(&second, 1000));
+
+        // still rejected in later epochs of the window
+        let last_height = 1000 + (REPLAY_WINDOW_EPOCHS - 1) * REPLAY_EPOCH_BLOCKS;
+        assert!(!window.check_and_record(&first, last_height));
+        assert!(!window.check_and_record(&second, 1050));
+    }
+
+    pub fn test_query_replay_window_slides() {
+        let mut window = ReplayWindow::default();
+        let id = message_id(b"message");
+
+        assert!(window.check_and_record(&id, 1000));
+
+        // the epoch of height 1000 slid out of the window
+        let height = 1000 + REPLAY_WINDOW_EPOCHS * REPLAY_EPOCH_BLOCKS;
+        assert!(window.check_and_record(&id, height));
+        assert_eq!(window.epochs.len(), 1);
+
+        // an earlier height doesn't bring older epochs back
+        assert!(!window.check_and_record(&id, 0));
+        assert_eq!(window.epochs.len(), 1);
+    }
+
+    pub fn test_query_replay_window_serialization() {
+        let mut window = ReplayWindow::default();
+        assert_eq!(window.serialize(), Vec::<u8>::new());
+        assert_eq!(ReplayWindow::deserialize(&[]), Some(window.clone()));
+
+        window.check_and_record(&message_id(b"first"), 100);
+        window.check_and_record(&message_id(b"second"), 350);
+
+        let serialized = window.serialize();
+        assert_eq!(serialized.len(), 2 * ENTRY_LEN);
+        assert_eq!(ReplayWindow::deserialize(&serialized), Some(window));
+
+        // truncated
+        assert_eq!(
+            ReplayWindow::deserialize(&serialized[..serialized.len() - 1]),
+            None
+        );
+
+        // epochs out of order
+        let mut swapped = serialized[ENTRY_LEN..].to_vec();
+        swapped.extend_from_slice(&serialized[..ENTRY_LEN]);
+        assert_eq!(ReplayWindow::deserialize(&swapped), None);
+    }
+}
@@ -0,0 +1,210 @@
+//! Short-lived symmetric sessions for query streams.
+//!
+//! A regular query carries `nonce || user_public_key || ciphertext`, and every one of them costs
+//! an x25519 key exchange and an HKDF before anything can be decrypted. A front-end that issues
+//! many queries can instead open a session once, with the `OpenQuerySession` gRPC query of
+//! x/compute, which calls `ecall_open_query_session` with the same nonce and public key it would
+//! use for a query. From then on it sends
+//!
+//! ```text
+//! session_id (32) || [0; 32] || ciphertext
+//! ```
+//!
+//! and keeps encrypting with the key it derived for that nonce. An all-zero x25519 public key is
+//! a low order point that can never be used in a regular query, so it marks a session query.
+//!
+//! Sessions live in the memory of this enclave only, and expire at a block height chosen by the
+//! client, bounded by `MAX_SESSION_BLOCKS`.
+
+use std::collections::HashMap;
+use std::sync::SgxMutex;
+
+use lazy_static::lazy_static;
+use log::*;
+
+use enclave_crypto::{sha_256, AESKey, Ed25519PublicKey};
+use enclave_ffi_types::EnclaveError;
+
+use crate::io::derive_encryption_key;
+use crate::types::{IoNonce, SecretMessage};
+
+pub type SessionId = [u8; 32];
+
+/// Roughly an hour at 6 seconds per block
+pub const MAX_SESSION_BLOCKS: u64 = 600;
+pub const MAX_SESSIONS: usize = 10_000;
+
+const SESSION_MARKER: Ed25519PublicKey = [0u8; 32];
+
+struct QuerySession {
+    nonce: IoNonce,
+    user_public_key: Ed25519PublicKey,
+    key: AESKey,
+    expiry_height: u64,
+}
+
+lazy_static! {
+    static ref QUERY_SESSIONS: SgxMutex<HashMap<SessionId, QuerySession>> =
+        SgxMutex::new(HashMap::new());
+}
+
+pub fn session_id(nonce: &IoNonce, user_public_key: &Ed25519PublicKey) -> SessionId {
+    let mut data = nonce.to_vec();
+    data.extend_from_slice(user_public_key);
+
+    sha_256(&data)
+}
+
+pub fn open_session(
+    nonce: &IoNonce,
+    user_public_key: &Ed25519PublicKey,
+    current_height: u64,
+    expiry_height: u64,
+) -> Result<SessionId, EnclaveError> {
+    if *user_public_key == SESSION_MARKER {
+        warn!("tried to open a query session without a public key");
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    if expiry_height <= current_height || expiry_height - current_height > MAX_SESSION_BLOCKS {
+        warn!(
+            "query session expiry {} is out of range at height {}",
+            expiry_height, current_height
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    let id = session_id(nonce, user_public_key);
+    let key = derive_encryption_key(nonce, user_public_key);
+
+    let mut sessions = QUERY_SESSIONS.lock().unwrap();
+    sessions.retain(|_, session| session.expiry_height > current_height);
+
+    if sessions.len() >= MAX_SESSIONS && !sessions.contains_key(&id) {
+        warn!("too many open query sessions");
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    sessions.insert(
+        id,
+        QuerySession {
+            nonce: *nonce,
+            user_public_key: *user_public_key,
+            key,
+            expiry_height,
+        },
+    );
+
+    trace!(
+        "opened query session {} until height {}",
+        hex::encode(id),
+        expiry_height
+    );
+
+    Ok(id)
+}
+
+/// The key of a live session, used to skip the key exchange for messages of that session
+pub fn cached_key(nonce: &IoNonce, user_public_key: &Ed25519PublicKey) -> Option<AESKey> {
+    let sessions = QUERY_SESSIONS.lock().unwrap();
+    if sessions.is_empty() {
+        return None;
+    }
+
+    sessions
+        .get(&session_id(nonce, user_public_key))
+        .map(|session| session.key)
+}
+
+/// Turn a session query back into the `SecretMessage` it stands for. Returns `Ok(None)` for
+/// regular queries.
+pub fn resolve_session_message(
+    msg: &SecretMessage,
+    current_height: u64,
+) -> Result<Option<SecretMessage>, EnclaveError> {
+    if msg.user_public_key != SESSION_MARKER {
+        return Ok(None);
+    }
+
+    let sessions = QUERY_SESSIONS.lock().unwrap();
+    let session = match sessions.get(&msg.nonce) {
+        Some(session) if session.expiry_height > current_height => session,
+        Some(_) => {
+            debug!("query session {} has expired", hex::encode(msg.nonce));
+            return Err(EnclaveError::DecryptionError);
+        }
+        None => {
+            debug!("unknown query session {}", hex::encode(msg.nonce));
+            return Err(EnclaveError::DecryptionError);
+        }
+    };
+
+    Ok(Some(SecretMessage {
+        nonce: session.nonce,
+        user_public_key: session.user_public_key,
+        msg: msg.msg.clone(),
+    }))
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn insert_session(nonce: IoNonce, user_public_key: Ed25519PublicKey, expiry_height: u64) {
+        QUERY_SESSIONS.lock().unwrap().insert(
+            session_id(&nonce, &user_public_key),
+            QuerySession {
+                nonce,
+                user_public_key,
+                key: AESKey::new_from_slice(&[7u8; 32]),
+                expiry_height,
+            },
+        );
+    }
+
+    pub fn test_query_session_resolve() {
+        let nonce = [1u8; 32];
+        let user_public_key = [2u8; 32];
+        insert_session(nonce, user_public_key, 100);
+
+        let session_msg = SecretMessage {
+            nonce: session_id(&nonce, &user_public_key),
+            user_public_key: SESSION_MARKER,
+            msg: vec![3u8; 20],
+        };
+
+        let resolved = resolve_session_message(&session_msg, 50).unwrap().unwrap();
+        assert_eq!(resolved.nonce, nonce);
+        assert_eq!(resolved.user_public_key, user_public_key);
+        assert_eq!(resolved.msg, session_msg.msg);
+        assert!(cached_key(&nonce, &user_public_key).is_some());
+
+        // expired
+        assert!(resolve_session_message(&session_msg, 100).is_err());
+    }
+
+    pub fn test_query_session_regular_message() {
+        let msg = SecretMessage {
+            nonce: [1u8; 32],
+            user_public_key: [9u8; 32],
+            msg: vec![3u8; 20],
+        };
+        assert!(resolve_session_message(&msg, 1).unwrap().is_none());
+
+        let unknown = SecretMessage {
+            nonce: [4u8; 32],
+            user_public_key: SESSION_MARKER,
+            msg: vec![3u8; 20],
+        };
+        assert!(resolve_session_message(&unknown, 1).is_err());
+    }
+
+    pub fn test_query_session_expiry_bounds() {
+        let nonce = [5u8; 32];
+        let user_public_key = [6u8; 32];
+
+        assert!(open_session(&nonce, &user_public_key, 10, 10).is_err());
+        assert!(open_session(&nonce, &user_public_key, 10, 11 + MAX_SESSION_BLOCKS).is_err());
+        assert!(open_session(&nonce, &SESSION_MARKER, 10, 20).is_err());
+    }
+}
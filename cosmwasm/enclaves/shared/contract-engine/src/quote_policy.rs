@@ -0,0 +1,248 @@
+//! Policies of the DCAP quotes contracts verify with `verify_sgx_quote`, so a contract can admit
+//! its own off-chain enclave agents without them registering with the network.
+//!
+//! A policy is a JSON object, e.g.
+//!
+//! ```json
+//! {"mr_enclave":["<hex>"],"isv_prod_id":1,"min_isv_svn":2}
+//! ```
+//!
+//! * `mr_enclave` and `mr_signer` are the measurements and signers the quote's enclave may have.
+//!   A policy needs at least one of them, and a quote has to match every list that isn't empty.
+//! * `isv_prod_id` is the product ID the enclave must have, and `min_isv_svn` the lowest
+//!   security version it may have.
+//! * `allow_debug` admits debug enclaves, whose memory the host can read. It's meant for
+//!   testnets only.
+//!
+//! Unknown and duplicate keys are rejected, so a typo can't loosen the policy.
+
+use serde::Deserialize;
+use sgx_types::{sgx_ql_qv_result_t, sgx_report_body_t};
+
+/// The longest policy, enough for a few hundred measurements
+pub const MAX_POLICY_LENGTH: usize = 32 * 1024;
+
+/// `SGX_FLAGS_DEBUG` of the enclave's attributes
+const SGX_FLAGS_DEBUG: u64 = 0x2;
+
+/// Error codes returned to contracts by `verify_sgx_quote`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum QuotePolicyError {
+    /// The policy isn't valid JSON of a policy, has no measurements or signers, or is too long
+    InvalidPolicy = 1,
+    /// The quote or its collateral don't verify, or the collateral expired
+    InvalidQuote = 2,
+    /// The enclave's MRENCLAVE or MRSIGNER isn't in the policy
+    MeasurementMismatch = 3,
+    /// The enclave's product ID isn't the policy's
+    ProductMismatch = 4,
+    /// The enclave's security version is below the policy's
+    SvnTooLow = 5,
+    /// The enclave is a debug enclave, and the policy doesn't allow them
+    DebugEnclave = 6,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct RawQuotePolicy {
+    #[serde(default)]
+    mr_enclave: Vec<String>,
+    #[serde(default)]
+    mr_signer: Vec<String>,
+    #[serde(default)]
+    isv_prod_id: Option<u16>,
+    #[serde(default)]
+    min_isv_svn: u16,
+    #[serde(default)]
+    allow_debug: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotePolicy {
+    pub mr_enclave: Vec<[u8; 32]>,
+    pub mr_signer: Vec<[u8; 32]>,
+    pub isv_prod_id: Option<u16>,
+    pub min_isv_svn: u16,
+    pub allow_debug: bool,
+}
+
+fn decode_measurements(measurements: &[String]) -> Result<Vec<[u8; 32]>, QuotePolicyError> {
+    measurements
+        .iter()
+        .map(|measurement| {
+            let mut decoded = [0u8; 32];
+            hex::decode_to_slice(measurement, &mut decoded)
+                .map_err(|_| QuotePolicyError::InvalidPolicy)?;
+            Ok(decoded)
+        })
+        .collect()
+}
+
+impl QuotePolicy {
+    pub fn parse(policy: &[u8]) -> Result<Self, QuotePolicyError> {
+        if policy.len() > MAX_POLICY_LENGTH {
+            return Err(QuotePolicyError::InvalidPolicy);
+        }
+
+        let raw: RawQuotePolicy =
+            serde_json::from_slice(policy).map_err(|_| QuotePolicyError::InvalidPolicy)?;
+        if raw.mr_enclave.is_empty() && raw.mr_signer.is_empty() {
+            return Err(QuotePolicyError::InvalidPolicy);
+        }
+
+        Ok(QuotePolicy {
+            mr_enclave: decode_measurements(&raw.mr_enclave)?,
+            mr_signer: decode_measurements(&raw.mr_signer)?,
+            isv_prod_id: raw.isv_prod_id,
+            min_isv_svn: raw.min_isv_svn,
+            allow_debug: raw.allow_debug,
+        })
+    }
+
+    /// Checks the report body of a verified quote against the policy
+    pub fn check(&self, report_body: &sgx_report_body_t) -> Result<(), QuotePolicyError> {
+        if (!self.mr_enclave.is_empty() && !self.mr_enclave.contains(&report_body.mr_enclave.m))
+            || (!self.mr_signer.is_empty() && !self.mr_signer.contains(&report_body.mr_signer.m))
+        {
+            return Err(QuotePolicyError::MeasurementMismatch);
+        }
+        if let Some(isv_prod_id) = self.isv_prod_id {
+            if report_body.isv_prod_id != isv_prod_id {
+                return Err(QuotePolicyError::ProductMismatch);
+            }
+        }
+        if report_body.isv_svn < self.min_isv_svn {
+            return Err(QuotePolicyError::SvnTooLow);
+        }
+        if !self.allow_debug && report_body.attributes.flags & SGX_FLAGS_DEBUG != 0 {
+            return Err(QuotePolicyError::DebugEnclave);
+        }
+
+        Ok(())
+    }
+}
+
+/// What `verify_sgx_quote` returns about the enclave of a quote that matches the policy:
+/// MRENCLAVE, MRSIGNER, product ID and security version as u16 big endian, the 64 bytes of report
+/// data, and a byte that is 1 if the platform needs software hardening, and 0 if it's up to date
+pub fn quote_summary(report_body: &sgx_report_body_t, qv_result: sgx_ql_qv_result_t) -> Vec<u8> {
+    let mut summary = Vec::with_capacity(32 + 32 + 2 + 2 + 64 + 1);
+    summary.extend_from_slice(&report_body.mr_enclave.m);
+    summary.extend_from_slice(&report_body.mr_signer.m);
+    summary.extend_from_slice(&report_body.isv_prod_id.to_be_bytes());
+    summary.extend_from_slice(&report_body.isv_svn.to_be_bytes());
+    summary.extend_from_slice(&report_body.report_data.d);
+    summary.push((qv_result == sgx_ql_qv_result_t::SGX_QL_QV_RESULT_SW_HARDENING_NEEDED) as u8);
+
+    summary
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn report_body() -> sgx_report_body_t {
+        let mut report_body = sgx_report_body_t::default();
+        report_body.mr_enclave.m = [1; 32];
+        report_body.mr_signer.m = [2; 32];
+        report_body.isv_prod_id = 7;
+        report_body.isv_svn = 3;
+        report_body
+    }
+
+    pub fn test_quote_policy_parse() {
+        let policy = QuotePolicy::parse(
+            format!(
+                r#"{{"mr_enclave":["{}"],"isv_prod_id":7,"min_isv_svn":2}}"#,
+                hex::encode([1u8; 32])
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(policy.mr_enclave, vec![[1u8; 32]]);
+        assert!(policy.mr_signer.is_empty());
+        assert_eq!(policy.isv_prod_id, Some(7));
+        assert_eq!(policy.min_isv_svn, 2);
+        assert!(!policy.allow_debug);
+
+        let signer = hex::encode([2u8; 32]);
+        for invalid in [
+            // Admits every enclave
+            "{}".to_string(),
+            r#"{"mr_enclave":[],"mr_signer":[]}"#.to_string(),
+            format!(r#"{{"mr_signer":["{}"],"min_isvsvn":2}}"#, signer),
+            format!(r#"{{"mr_signer":["{}"],"mr_signer":[]}}"#, signer),
+            format!(r#"{{"mr_signer":["{}"]}}"#, &signer[2..]),
+            "[]".to_string(),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                QuotePolicy::parse(invalid.as_bytes()),
+                Err(QuotePolicyError::InvalidPolicy)
+            );
+        }
+    }
+
+    pub fn test_quote_policy_check() {
+        let policy = QuotePolicy {
+            mr_enclave: vec![[9; 32], [1; 32]],
+            mr_signer: vec![[2; 32]],
+            isv_prod_id: Some(7),
+            min_isv_svn: 3,
+            allow_debug: false,
+        };
+        assert_eq!(policy.check(&report_body()), Ok(()));
+
+        let mut other = report_body();
+        other.mr_enclave.m = [3; 32];
+        assert_eq!(
+            policy.check(&other),
+            Err(QuotePolicyError::MeasurementMismatch)
+        );
+
+        let mut other = report_body();
+        other.mr_signer.m = [3; 32];
+        assert_eq!(
+            policy.check(&other),
+            Err(QuotePolicyError::MeasurementMismatch)
+        );
+
+        let mut other = report_body();
+        other.isv_prod_id = 8;
+        assert_eq!(policy.check(&other), Err(QuotePolicyError::ProductMismatch));
+
+        let mut other = report_body();
+        other.isv_svn = 2;
+        assert_eq!(policy.check(&other), Err(QuotePolicyError::SvnTooLow));
+
+        let mut debug = report_body();
+        debug.attributes.flags |= SGX_FLAGS_DEBUG;
+        assert_eq!(policy.check(&debug), Err(QuotePolicyError::DebugEnclave));
+        let allow_debug = QuotePolicy {
+            allow_debug: true,
+            ..policy
+        };
+        assert_eq!(allow_debug.check(&debug), Ok(()));
+    }
+
+    pub fn test_quote_summary() {
+        let mut report_body = report_body();
+        report_body.report_data.d[0] = 5;
+
+        let summary = quote_summary(&report_body, sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OK);
+        assert_eq!(summary.len(), 133);
+        assert_eq!(&summary[..32], &[1; 32]);
+        assert_eq!(&summary[32..64], &[2; 32]);
+        assert_eq!(&summary[64..68], &[0, 7, 0, 3]);
+        assert_eq!(summary[68], 5);
+        assert_eq!(summary[132], 0);
+
+        let summary = quote_summary(
+            &report_body,
+            sgx_ql_qv_result_t::SGX_QL_QV_RESULT_SW_HARDENING_NEEDED,
+        );
+        assert_eq!(summary[132], 1);
+    }
+}
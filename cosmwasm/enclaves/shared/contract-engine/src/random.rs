@@ -15,6 +15,26 @@ pub struct MsgCounter {
     pub counter: u64,
 }
 
+#[cfg(feature = "random")]
+const SIMULATION_RANDOM_DOMAIN: &[u8] = b"simulation_random";
+#[cfg(all(feature = "random", feature = "light-client-validation"))]
+use crate::query_chain::query_block_random;
+#[cfg(all(feature = "random", feature = "light-client-validation"))]
+use enclave_ffi_types::Ctx;
+
+#[cfg(all(feature = "random", feature = "light-client-validation"))]
+const PAST_RANDOM_DOMAIN: &[u8] = b"past_random";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PastRandomError {
+    /// The height is the one of the current block, or later
+    NotPast = 1,
+    /// The height is more than `PAST_RANDOM_WINDOW` blocks back
+    Expired = 2,
+    /// x/compute didn't store a random for the block, or the enclave doesn't support randomness
+    Unavailable = 3,
+}
+
 lazy_static! {
     pub static ref MSG_COUNTER: SgxMutex<MsgCounter> = SgxMutex::new(MsgCounter::default());
 }
@@ -49,6 +69,25 @@ pub fn derive_random(seed: &Binary, contract_key: &ContractKey, height: u64) ->
     )
 }
 
+/// The random of a simulated execution. It's derived under its own domain, and without the msg
+/// counter, so simulations can't reveal the random of a real execution, or change the random of
+/// the next one.
+#[cfg(feature = "random")]
+pub fn derive_simulation_random(seed: &Binary, contract_key: &ContractKey, height: u64) -> Binary {
+    let height_bytes = height.to_be_bytes();
+    let data = vec![
+        SIMULATION_RANDOM_DOMAIN,
+        height_bytes.as_slice(),
+        contract_key.as_slice(),
+    ];
+
+    Binary(
+        enclave_crypto::hkdf_sha_256(seed.0.as_slice(), data.as_slice())
+            .get()
+            .to_vec(),
+    )
+}
+
 pub fn update_msg_counter(height: u64) {
     let mut counter = MSG_COUNTER.lock().unwrap();
 
@@ -61,3 +100,101 @@ pub fn update_msg_counter(height: u64) {
 
     trace!("counter incremented to: {:?}", counter);
 }
+
+/// How far back contracts can ask for the random of a block
+#[cfg(any(
+    all(feature = "random", feature = "light-client-validation"),
+    feature = "test"
+))]
+const PAST_RANDOM_WINDOW: u64 = 999;
+
+#[cfg(any(
+    all(feature = "random", feature = "light-client-validation"),
+    feature = "test"
+))]
+fn check_past_height(height: u64, current_height: u64) -> Result<(), PastRandomError> {
+    if height >= current_height {
+        return Err(PastRandomError::NotPast);
+    }
+    if current_height - height > PAST_RANDOM_WINDOW {
+        return Err(PastRandomError::Expired);
+    }
+    Ok(())
+}
+
+/// The random of the block at `height`, derived for this contract under its own domain, so it
+/// doesn't reveal the random any contract got during that block. `height` must be before the
+/// current block, and at most `PAST_RANDOM_WINDOW` blocks back. The random is the one x/compute
+/// stored for the block in its state, like the random of the current block in the env.
+#[cfg(all(feature = "random", feature = "light-client-validation"))]
+pub fn past_random(
+    context: &Ctx,
+    query_depth: u32,
+    contract_key: &ContractKey,
+    height: u64,
+    gas_used: &mut u64,
+    gas_limit: u64,
+) -> Result<Binary, PastRandomError> {
+    let current_height = block_verifier::VERIFIED_BLOCK_MESSAGES
+        .lock()
+        .unwrap()
+        .height();
+    check_past_height(height, current_height)?;
+
+    let random = query_block_random(context, height, query_depth, gas_used, gas_limit)
+        .ok()
+        .flatten()
+        .ok_or(PastRandomError::Unavailable)?;
+
+    let height_bytes = height.to_be_bytes();
+    let data = vec![
+        PAST_RANDOM_DOMAIN,
+        height_bytes.as_slice(),
+        contract_key.as_slice(),
+    ];
+
+    Ok(Binary(
+        enclave_crypto::hkdf_sha_256(random.as_slice(), data.as_slice())
+            .get()
+            .to_vec(),
+    ))
+}
+
+#[cfg(not(all(feature = "random", feature = "light-client-validation")))]
+pub fn past_random(
+    _context: &enclave_ffi_types::Ctx,
+    _query_depth: u32,
+    _contract_key: &crate::contract_validation::ContractKey,
+    _height: u64,
+    _gas_used: &mut u64,
+    _gas_limit: u64,
+) -> Result<cw_types_v010::encoding::Binary, PastRandomError> {
+    Err(PastRandomError::Unavailable)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_past_random_window() {
+        let current_height = 1099;
+        assert_eq!(
+            check_past_height(current_height - 1, current_height),
+            Ok(())
+        );
+        assert_eq!(check_past_height(100, current_height), Ok(()));
+
+        assert_eq!(
+            check_past_height(99, current_height),
+            Err(PastRandomError::Expired)
+        );
+        assert_eq!(
+            check_past_height(current_height, current_height),
+            Err(PastRandomError::NotPast)
+        );
+        assert_eq!(
+            check_past_height(current_height + 1, current_height),
+            Err(PastRandomError::NotPast)
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::submsg_errors::SubMsgError;
 use crate::types::{ParsedMessage, SecretMessage};
 use cw_types_v010::encoding::Binary;
 use cw_types_v1::results::{
@@ -206,17 +207,18 @@ fn parse_encrypted_error_reply(
 
     let decrypted_error = secret_msg.decrypt()?;
 
-    // Now we need to create synthetic SecretMessage to fit the API in "handle"
-    let result = SubMsgResult::Err(
-        String::from_utf8(decrypted_error[HEX_ENCODED_HASH_SIZE..].to_vec()).map_err(|err| {
+    let contract_error = String::from_utf8(decrypted_error[HEX_ENCODED_HASH_SIZE..].to_vec())
+        .map_err(|err| {
             warn!(
                 "Failed to parse error as string {:?}: {}",
                 decrypted_error[HEX_ENCODED_HASH_SIZE..].to_vec(),
                 err
             );
             EnclaveError::FailedToDeserialize
-        })?,
-    );
+        })?;
+
+    // Now we need to create synthetic SecretMessage to fit the API in "handle"
+    let result = SubMsgResult::Err(SubMsgError::custom(contract_error).encode());
 
     wrap_results_as_parsed_message(input_msg, id, result, parsed_reply, data_for_validation)
 }
@@ -254,9 +256,13 @@ fn parse_plaintext_reply_message(
         }
     };
 
+    let result = match &parsed_reply.result {
+        SubMsgResult::Ok(response) => SubMsgResult::Ok(response.clone()),
+        SubMsgResult::Err(error) => SubMsgResult::Err(SubMsgError::from_node_error(error).encode()),
+    };
     let decrypted_reply = DecryptedReply {
         id: msg_id_as_num,
-        result: parsed_reply.result.clone(),
+        result,
     };
 
     redact_custom_events(parsed_reply);
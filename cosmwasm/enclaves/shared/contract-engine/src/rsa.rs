@@ -0,0 +1,337 @@
+//! RSA signature verification, for contracts that verify JWTs or x509 certificates, e.g. of
+//! attested web data or of enterprise identity providers.
+//!
+//! Public keys are passed as their modulus and public exponent, big-endian and without leading
+//! zeros, the way JWKs carry them. Only 2048, 3072 and 4096-bit moduli are accepted, and public
+//! exponents must fit in 32 bits, so the cost of a verification only depends on the size of the
+//! key. The hash algorithm is given by the length of the message hash: SHA-256, SHA-384 or
+//! SHA-512.
+//!
+//! * PKCS#1 v1.5 signatures (e.g. JWT's `RS256`) are checked by encoding the expected message and
+//!   comparing all of it, rather than by parsing the signed one, as RFC 8017 recommends. The
+//!   `DigestInfo` must have NULL parameters.
+//! * PSS signatures (e.g. JWT's `PS256`) must use MGF1 with the same hash as the message, and a
+//!   salt as long as the hash, as RFC 7518 requires.
+
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RsaError {
+    /// The message hash isn't as long as a SHA-256, SHA-384 or SHA-512 hash
+    InvalidHashFormat,
+    /// The signature isn't as long as the modulus, or isn't smaller than it
+    InvalidSignatureFormat,
+    /// The modulus isn't 2048, 3072 or 4096 bits long, or the exponent is invalid
+    InvalidPubkeyFormat,
+    /// The signature is well-formed, but isn't a signature of the message by the key
+    VerificationFailed,
+}
+
+/// The lengths of the moduli that are accepted, in bytes
+pub const RSA_MODULUS_LENGTHS: [usize; 3] = [256, 384, 512];
+
+const MAX_EXPONENT_LENGTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Hash {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Hash {
+    fn of_length(len: usize) -> Result<Self, RsaError> {
+        match len {
+            32 => Ok(Hash::Sha256),
+            48 => Ok(Hash::Sha384),
+            64 => Ok(Hash::Sha512),
+            _ => Err(RsaError::InvalidHashFormat),
+        }
+    }
+
+    fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Hash::Sha256 => Sha256::digest(data).to_vec(),
+            Hash::Sha384 => Sha384::digest(data).to_vec(),
+            Hash::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    /// The DER encoding of the `DigestInfo` of a hash, up to the hash itself
+    fn digest_info_prefix(self) -> &'static [u8] {
+        match self {
+            Hash::Sha256 => &[
+                0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x01, 0x05, 0x00, 0x04, 0x20,
+            ],
+            Hash::Sha384 => &[
+                0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x02, 0x05, 0x00, 0x04, 0x30,
+            ],
+            Hash::Sha512 => &[
+                0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x03, 0x05, 0x00, 0x04, 0x40,
+            ],
+        }
+    }
+}
+
+/// Checks the encoding of the inputs, and returns the encoded message that was signed
+fn recover_encoded_message(
+    signature: &[u8],
+    modulus: &[u8],
+    exponent: &[u8],
+) -> Result<Vec<u8>, RsaError> {
+    // The top bit of the modulus must be set, so its length is exactly one of the key sizes
+    let modulus_is_valid = RSA_MODULUS_LENGTHS.contains(&modulus.len())
+        && modulus[0] & 0x80 != 0
+        && modulus[modulus.len() - 1] & 1 == 1;
+    let exponent_is_valid = !exponent.is_empty()
+        && exponent.len() <= MAX_EXPONENT_LENGTH
+        && exponent[0] != 0
+        && exponent[exponent.len() - 1] & 1 == 1
+        && exponent != [1];
+    if !modulus_is_valid || !exponent_is_valid {
+        return Err(RsaError::InvalidPubkeyFormat);
+    }
+
+    if signature.len() != modulus.len() || signature >= modulus {
+        return Err(RsaError::InvalidSignatureFormat);
+    }
+
+    let message = BigUint::from_bytes_be(signature)
+        .modpow(
+            &BigUint::from_bytes_be(exponent),
+            &BigUint::from_bytes_be(modulus),
+        )
+        .to_bytes_be();
+
+    let mut encoded_message = vec![0u8; modulus.len() - message.len()];
+    encoded_message.extend_from_slice(&message);
+    Ok(encoded_message)
+}
+
+/// Verifies an RSASSA-PKCS1-v1_5 signature of a message hash
+pub fn verify_pkcs1v15(
+    message_hash: &[u8],
+    signature: &[u8],
+    modulus: &[u8],
+    exponent: &[u8],
+) -> Result<(), RsaError> {
+    let hash = Hash::of_length(message_hash.len())?;
+    let encoded_message = recover_encoded_message(signature, modulus, exponent)?;
+
+    // 0x00 || 0x01 || 0xff... || 0x00 || DigestInfo
+    let digest_info_prefix = hash.digest_info_prefix();
+    let padding_len = modulus.len() - 3 - digest_info_prefix.len() - message_hash.len();
+    let mut expected = vec![0x00, 0x01];
+    expected.resize(2 + padding_len, 0xff);
+    expected.push(0x00);
+    expected.extend_from_slice(digest_info_prefix);
+    expected.extend_from_slice(message_hash);
+
+    if encoded_message != expected {
+        return Err(RsaError::VerificationFailed);
+    }
+    Ok(())
+}
+
+/// MGF1, as defined by RFC 8017 appendix B.2.1
+fn mgf1(hash: Hash, seed: &[u8], len: usize) -> Vec<u8> {
+    let mut mask = Vec::with_capacity(len);
+    let mut counter: u32 = 0;
+    while mask.len() < len {
+        let mut block = seed.to_vec();
+        block.extend_from_slice(&counter.to_be_bytes());
+        mask.extend_from_slice(&hash.digest(&block));
+        counter += 1;
+    }
+    mask.truncate(len);
+    mask
+}
+
+/// Verifies an RSASSA-PSS signature of a message hash
+pub fn verify_pss(
+    message_hash: &[u8],
+    signature: &[u8],
+    modulus: &[u8],
+    exponent: &[u8],
+) -> Result<(), RsaError> {
+    let hash = Hash::of_length(message_hash.len())?;
+    let encoded_message = recover_encoded_message(signature, modulus, exponent)?;
+
+    let hash_len = message_hash.len();
+    let salt_len = hash_len;
+
+    // maskedDB || H || 0xbc, of which the leftmost bit is zero, since the modulus is a whole
+    // number of bytes and the encoded message has one bit less
+    if encoded_message[0] & 0x80 != 0 || encoded_message[encoded_message.len() - 1] != 0xbc {
+        return Err(RsaError::VerificationFailed);
+    }
+    let db_len = encoded_message.len() - hash_len - 1;
+    let (masked_db, h) = encoded_message[..encoded_message.len() - 1].split_at(db_len);
+
+    let mut db: Vec<u8> = masked_db
+        .iter()
+        .zip(mgf1(hash, h, db_len))
+        .map(|(masked, mask)| masked ^ mask)
+        .collect();
+    db[0] &= 0x7f;
+
+    // DB = 0x00... || 0x01 || salt
+    let padding_len = db_len - salt_len - 1;
+    if db[..padding_len].iter().any(|byte| *byte != 0) || db[padding_len] != 0x01 {
+        return Err(RsaError::VerificationFailed);
+    }
+    let salt = &db[padding_len + 1..];
+
+    let mut m_prime = vec![0u8; 8];
+    m_prime.extend_from_slice(message_hash);
+    m_prime.extend_from_slice(salt);
+    if hash.digest(&m_prime) != h {
+        return Err(RsaError::VerificationFailed);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    /// A JWT's signing input, signed with `RS256` and `PS256`
+    const MESSAGE: &[u8] = b"eyJhbGciOiJSUzI1NiJ9.eyJzdWIiOiJzZWNyZXQifQ";
+    const MODULUS: &str = concat!(
+        "b70171548ee0f77c2cfe1a9d03c8c0d9f4160a3ffa6bbafd73a1627f0977e3b5a3411c8256b59d2c",
+        "b69334aa20d89b5abf6235dadce9fa6ff34c8fb80a1a1c2b34ee43c8ae362282de56cb4344e4c8d1",
+        "6556d458dc32f5592c1d820e4ae2a14db97ecad4667444eda47be823d14fe4a364971ccfa1aa91e9",
+        "f9f265a63baa86d109b7f1bd4e22f86750a2ccb57a43a8e0e25ddd3e334455de7747822fc88a0d7c",
+        "d494ec18363258dadec4e689e66a03272c5709c4188704f53e8b9a7e27ab7d7607274f370def5abb",
+        "cf6564aacf69f5789142aae6c75ffec65cebd47aac8d2d521c8a3947e88d7ae0be424470f2a75bd3",
+        "32083c89d7e599555582848b1ffba7ab",
+    );
+
+    const EXPONENT: &[u8] = &[0x01, 0x00, 0x01];
+    const PKCS1V15_SIGNATURE: &str = concat!(
+        "6d19dbb8c1bba9382ae0512a72c7e4e3278a63f24089000036c24ae94c890aacf143fe9cac613cdf",
+        "3fa865d531d0a694aab09038bb390e980f7fa235a9737da92ef616906d6eacaafc8bf4c7f1673280",
+        "236dbf5430500d9064b60f78058062a1ff03e1aeb1cb24a0675f2d411831fb2cccfb694de933dc08",
+        "e171830b5fb0abccf79dc9729d766af4aa73e07ea8956195ce10affbff2d55313baab9b27f5f0435",
+        "9225576bee170fa3a5a9649c65b8b8ee980502529e353d907ee0ff18b06864c3f8e01dd370e6fa8d",
+        "6576eb4137363fea86349fec4086f678499c44f66445e744018f3e0e795a5d04e244c8b3d23aa5f5",
+        "b0903ed4a39c3d8b620aaa29e389d709",
+    );
+
+    const PSS_SIGNATURE: &str = concat!(
+        "208b61f1202a51f619c80130b45a96a169fe30887b510767761ae5c8a6c4ba21eae86e3a2c428cdd",
+        "ea9797ef6cca1de5ec60ebc362e82b268ed83fa563398cedf428e32c12f33c000897a29f22b7fa05",
+        "e23584b57b6a0228b5d6c23cdfcb9bc261b48039894f43b3bb7b4a164f35d8e5dda926c4e2837cc6",
+        "78eb0696b2add9ff2d98d8685e59c868843cee7b1ece656b53cb51a166a573d48d142fd2e3197254",
+        "e2ed6bbaa50f5099266f7d4fbbf5c49435da781b077802572ec581c71101a65d0fc556355135495f",
+        "7f5a7b8768e1b00c2b780e5f4fc910678e1af25f1cd9dd41eae8122b136b3bd21d3db6c3d94a1de4",
+        "0343017db1eb203f704b006f793cf5a1",
+    );
+
+    fn hex(value: &str) -> Vec<u8> {
+        hex::decode(value).unwrap()
+    }
+
+    pub fn test_rsa_pkcs1v15_verify() {
+        let message_hash = Sha256::digest(MESSAGE).to_vec();
+        let (signature, modulus) = (hex(PKCS1V15_SIGNATURE), hex(MODULUS));
+        assert_eq!(
+            verify_pkcs1v15(&message_hash, &signature, &modulus, EXPONENT),
+            Ok(())
+        );
+
+        let mut other_hash = message_hash.clone();
+        other_hash[0] ^= 1;
+        assert_eq!(
+            verify_pkcs1v15(&other_hash, &signature, &modulus, EXPONENT),
+            Err(RsaError::VerificationFailed)
+        );
+        assert_eq!(
+            verify_pkcs1v15(&message_hash, &hex(PSS_SIGNATURE), &modulus, EXPONENT),
+            Err(RsaError::VerificationFailed)
+        );
+        // The same hash bytes, parsed as the start of a SHA-512 hash
+        let mut longer_hash = message_hash.clone();
+        longer_hash.resize(64, 0);
+        assert_eq!(
+            verify_pkcs1v15(&longer_hash, &signature, &modulus, EXPONENT),
+            Err(RsaError::VerificationFailed)
+        );
+    }
+
+    pub fn test_rsa_pss_verify() {
+        let message_hash = Sha256::digest(MESSAGE).to_vec();
+        let (signature, modulus) = (hex(PSS_SIGNATURE), hex(MODULUS));
+        assert_eq!(
+            verify_pss(&message_hash, &signature, &modulus, EXPONENT),
+            Ok(())
+        );
+
+        let mut other_hash = message_hash.clone();
+        other_hash[31] ^= 1;
+        assert_eq!(
+            verify_pss(&other_hash, &signature, &modulus, EXPONENT),
+            Err(RsaError::VerificationFailed)
+        );
+        let mut other_signature = signature.clone();
+        other_signature[100] ^= 1;
+        assert_eq!(
+            verify_pss(&message_hash, &other_signature, &modulus, EXPONENT),
+            Err(RsaError::VerificationFailed)
+        );
+        assert_eq!(
+            verify_pss(&message_hash, &hex(PKCS1V15_SIGNATURE), &modulus, EXPONENT),
+            Err(RsaError::VerificationFailed)
+        );
+    }
+
+    pub fn test_rsa_rejects_encodings() {
+        let message_hash = Sha256::digest(MESSAGE).to_vec();
+        let (signature, modulus) = (hex(PKCS1V15_SIGNATURE), hex(MODULUS));
+
+        assert_eq!(
+            verify_pkcs1v15(&message_hash[1..], &signature, &modulus, EXPONENT),
+            Err(RsaError::InvalidHashFormat)
+        );
+
+        // Leading zeros, and keys of other sizes
+        let mut padded_modulus = vec![0];
+        padded_modulus.extend_from_slice(&modulus);
+        assert_eq!(
+            verify_pkcs1v15(&message_hash, &signature, &padded_modulus, EXPONENT),
+            Err(RsaError::InvalidPubkeyFormat)
+        );
+        assert_eq!(
+            verify_pkcs1v15(&message_hash, &signature[1..], &modulus[1..], EXPONENT),
+            Err(RsaError::InvalidPubkeyFormat)
+        );
+        // Leading zeros, even, 1, empty and too long
+        for exponent in [
+            &[0x00, 0x01, 0x00, 0x01][..],
+            &[0x01, 0x00, 0x00],
+            &[0x01],
+            &[],
+            &[0x01; 5],
+        ] {
+            assert_eq!(
+                verify_pkcs1v15(&message_hash, &signature, &modulus, exponent),
+                Err(RsaError::InvalidPubkeyFormat)
+            );
+        }
+
+        let mut padded_signature = vec![0];
+        padded_signature.extend_from_slice(&signature);
+        assert_eq!(
+            verify_pkcs1v15(&message_hash, &padded_signature, &modulus, EXPONENT),
+            Err(RsaError::InvalidSignatureFormat)
+        );
+        assert_eq!(
+            verify_pss(&message_hash, &modulus, &modulus, EXPONENT),
+            Err(RsaError::InvalidSignatureFormat)
+        );
+    }
+}
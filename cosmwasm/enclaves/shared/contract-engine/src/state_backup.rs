@@ -0,0 +1,247 @@
+//! Encrypted backups of a contract's state, for its admin.
+//!
+//! The admin exports the state of a contract in chunks: the node passes the pairs as they're
+//! stored, and the enclave decrypts them and wraps them again under a backup key. The backup key
+//! is derived from the genesis consensus seed, the contract's code hash and its admin, so a
+//! backup can only be imported into an instance of the same code with the same admin, where the
+//! pairs are encrypted again under that instance's state key. Both directions must be signed by
+//! the admin:
+//!
+//! ```text
+//! message StateBackupAuthorization {
+//!   string contract = 1;
+//!   uint64 expires_at_height = 2;
+//!   bytes admin_public_key = 3;  // secp256k1, compressed
+//!   bytes admin_signature = 4;   // over `sign_bytes`
+//! }
+//! ```
+//!
+//! Pairs are passed in and out of the enclave as `len(key) || key || len(value) || value`, with
+//! 4 byte big endian lengths.
+
+use log::*;
+
+use cw_types_v010::types::CanonicalAddr;
+use enclave_crypto::{AESKey, Kdf, SIVEncryptable, HASH_SIZE};
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::KEY_MANAGER;
+use enclave_wire::{Endian, Framed};
+
+use crate::gov_messages::{
+    read_len_delimited, read_string, read_varint, WIRE_TYPE_LEN, WIRE_TYPE_VARINT,
+};
+use crate::key_disclosure::verify_admin_signature;
+
+const BACKUP_SIGN_PREFIX: &[u8] = b"secret_contract_state_backup";
+const BACKUP_KEY_DOMAIN: &[u8] = b"contract_state_backup";
+/// Bumped whenever the format of a wrapped chunk changes
+const BACKUP_VERSION: &[u8] = &[1];
+
+/// Shared with state commitments and re-encryption, which pass pairs the same way
+const STATE_PAIRS: Framed = Framed(Endian::Big);
+
+pub type StatePairs = Vec<(Vec<u8>, Vec<u8>)>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BackupOperation {
+    Export,
+    Import,
+}
+
+impl BackupOperation {
+    fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            BackupOperation::Export => b"export",
+            BackupOperation::Import => b"import",
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct StateBackupAuthorization {
+    pub contract: String,
+    pub expires_at_height: u64,
+    pub admin_public_key: Vec<u8>,
+    pub admin_signature: Vec<u8>,
+}
+
+impl StateBackupAuthorization {
+    pub fn parse(mut msg: &[u8]) -> Result<Self, EnclaveError> {
+        let mut authorization = Self::default();
+
+        while !msg.is_empty() {
+            let key = read_varint(&mut msg)?;
+            match (key >> 3, key & 0x7) {
+                (1, WIRE_TYPE_LEN) => authorization.contract = read_string(&mut msg)?,
+                (2, WIRE_TYPE_VARINT) => authorization.expires_at_height = read_varint(&mut msg)?,
+                (3, WIRE_TYPE_LEN) => {
+                    authorization.admin_public_key = read_len_delimited(&mut msg)?.to_vec();
+                }
+                (4, WIRE_TYPE_LEN) => {
+                    authorization.admin_signature = read_len_delimited(&mut msg)?.to_vec();
+                }
+                (field, wire_type) => {
+                    warn!(
+                        "unexpected field {} of wire type {} in state backup authorization",
+                        field, wire_type
+                    );
+                    return Err(EnclaveError::FailedToDeserialize);
+                }
+            }
+        }
+
+        Ok(authorization)
+    }
+
+    /// The bytes the admin signs to authorize `operation`
+    pub fn sign_bytes(&self, operation: BackupOperation) -> Vec<u8> {
+        let mut bytes = BACKUP_SIGN_PREFIX.to_vec();
+        bytes.extend_from_slice(operation.as_bytes());
+        bytes.extend_from_slice(&(self.contract.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(self.contract.as_bytes());
+        bytes.extend_from_slice(&self.expires_at_height.to_be_bytes());
+        bytes
+    }
+
+    /// Checks that `admin` authorized `operation`, and that the authorization didn't expire
+    pub fn verify(
+        &self,
+        operation: BackupOperation,
+        admin: &CanonicalAddr,
+        block_height: u64,
+    ) -> Result<(), EnclaveError> {
+        if block_height > self.expires_at_height {
+            warn!(
+                "state backup authorization expired at height {}",
+                self.expires_at_height
+            );
+            return Err(EnclaveError::ValidationFailure);
+        }
+
+        verify_admin_signature(
+            &self.admin_public_key,
+            &self.admin_signature,
+            &self.sign_bytes(operation),
+            admin,
+        )
+    }
+}
+
+pub fn encode_pairs(pairs: &[(Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+    STATE_PAIRS.encode_pairs(pairs.iter().map(|(key, value)| (key, value)))
+}
+
+pub fn decode_pairs(bytes: &[u8]) -> Result<StatePairs, EnclaveError> {
+    let pairs = STATE_PAIRS.decode_pairs(bytes).map_err(|err| {
+        warn!("failed to decode state pairs: {}", err);
+        EnclaveError::FailedToDeserialize
+    })?;
+
+    Ok(pairs
+        .into_iter()
+        .map(|(key, value)| (key.to_vec(), value.to_vec()))
+        .collect())
+}
+
+/// The key backups of a contract are wrapped with, shared by every instance of `code_hash` that
+/// has `admin` as its admin
+pub fn backup_key(code_hash: &[u8; HASH_SIZE], admin: &CanonicalAddr) -> AESKey {
+    let consensus_state_ikm = KEY_MANAGER.get_consensus_state_ikm().unwrap();
+
+    let mut derivation_data = BACKUP_KEY_DOMAIN.to_vec();
+    derivation_data.extend_from_slice(code_hash);
+    derivation_data.extend_from_slice(admin.as_slice());
+    consensus_state_ikm
+        .genesis
+        .derive_key_from_this(&derivation_data)
+}
+
+pub fn wrap_chunk(pairs: &[(Vec<u8>, Vec<u8>)], key: &AESKey) -> Result<Vec<u8>, EnclaveError> {
+    key.encrypt_siv(&encode_pairs(pairs), Some(&[BACKUP_VERSION]))
+        .map_err(|err| {
+            error!("failed to wrap a state backup chunk: {:?}", err);
+            EnclaveError::EncryptionError
+        })
+}
+
+pub fn unwrap_chunk(chunk: &[u8], key: &AESKey) -> Result<StatePairs, EnclaveError> {
+    let pairs = key
+        .decrypt_siv(chunk, Some(&[BACKUP_VERSION]))
+        .map_err(|err| {
+            warn!(
+                "failed to unwrap a state backup chunk, it belongs to another code or admin: {:?}",
+                err
+            );
+            EnclaveError::DecryptionError
+        })?;
+
+    decode_pairs(&pairs)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use enclave_crypto::sha_256;
+
+    fn encode_field(out: &mut Vec<u8>, field: u8, value: &[u8]) {
+        out.push(field << 3 | WIRE_TYPE_LEN as u8);
+        out.push(value.len() as u8);
+        out.extend_from_slice(value);
+    }
+
+    pub fn test_state_backup_authorization_parse() {
+        let mut msg = vec![];
+        encode_field(&mut msg, 1, b"secret1contract");
+        msg.extend_from_slice(&[2 << 3 | WIRE_TYPE_VARINT as u8, 0xac, 0x02]);
+        encode_field(&mut msg, 3, &[2u8; 33]);
+        encode_field(&mut msg, 4, &[9u8; 64]);
+
+        let authorization = StateBackupAuthorization::parse(&msg).unwrap();
+        assert_eq!(authorization.contract, "secret1contract");
+        assert_eq!(authorization.expires_at_height, 300);
+        assert_eq!(authorization.admin_public_key, vec![2u8; 33]);
+        assert_eq!(authorization.admin_signature, vec![9u8; 64]);
+
+        // an export authorization can't be used for an import
+        assert_ne!(
+            authorization.sign_bytes(BackupOperation::Export),
+            authorization.sign_bytes(BackupOperation::Import)
+        );
+
+        let expired = authorization.verify(
+            BackupOperation::Export,
+            &CanonicalAddr::from_vec(vec![1u8; 20]),
+            301,
+        );
+        assert!(matches!(expired, Err(EnclaveError::ValidationFailure)));
+    }
+
+    pub fn test_state_backup_pairs_encoding() {
+        let pairs = vec![
+            (b"key".to_vec(), b"value".to_vec()),
+            (vec![], vec![]),
+            (vec![0u8; 300], vec![1u8; 70000]),
+        ];
+
+        let encoded = encode_pairs(&pairs);
+        assert_eq!(decode_pairs(&encoded).unwrap(), pairs);
+        assert!(decode_pairs(&[]).unwrap().is_empty());
+
+        assert!(decode_pairs(&encoded[..encoded.len() - 1]).is_err());
+        assert!(decode_pairs(&[0, 0, 0]).is_err());
+    }
+
+    pub fn test_state_backup_chunk_bound_to_key() {
+        let key = AESKey::new_from_slice(&sha_256(b"backup key"));
+        let other_key = AESKey::new_from_slice(&sha_256(b"other backup key"));
+        let pairs = vec![(b"key".to_vec(), b"value".to_vec())];
+
+        let chunk = wrap_chunk(&pairs, &key).unwrap();
+        assert_eq!(unwrap_chunk(&chunk, &key).unwrap(), pairs);
+        assert!(matches!(
+            unwrap_chunk(&chunk, &other_key),
+            Err(EnclaveError::DecryptionError)
+        ));
+    }
+}
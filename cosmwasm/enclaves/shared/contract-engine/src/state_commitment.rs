@@ -0,0 +1,305 @@
+//! Signed commitments to the state of a contract, e.g. to compare the state before and after a
+//! migration without decrypting it.
+//!
+//! The commitment is the root of an RFC 6962 Merkle tree over the contract's pairs. The leaf of
+//! a pair is an HMAC of it under a key derived from the contract's state key, so leaves don't
+//! reveal values that could be guessed, and an auditor the state key was disclosed to can
+//! recompute them. Leaves are sorted, which makes the root depend only on the pairs, but the node
+//! can only sort what it can see, so the commitment takes two passes over the stored pairs:
+//! 1. The node passes the pairs in chunks, and gets their leaves back.
+//! 2. The node passes the pairs again in the order of their leaves. The enclave computes the
+//!    leaves again, checks that they're ascending, and folds them into the tree. Between chunks
+//!    the unfinished tree is handed to the node, sealed. After the last chunk the enclave signs
+//!    the root.
+//!
+//! Commitments are signed with a secp256k1 key derived from the consensus seed, so every node
+//! signs the same commitment for the same state.
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use cw_types_v010::encoding::Binary;
+use cw_types_v010::types::HumanAddr;
+use cw_types_v1::math::Uint64;
+use enclave_crypto::{sha_256, AESKey, Hmac, Kdf, SIVEncryptable, HASH_SIZE};
+use enclave_ffi_types::EnclaveError;
+use enclave_utils::KEY_MANAGER;
+
+use crate::state_backup::encode_pairs;
+
+pub const STATE_COMMITMENT_DOMAIN: &[u8] = b"secret-state-commitment-v1";
+const LEAF_KEY_DOMAIN: &[u8] = b"contract_state_commitment_leaf";
+const PROGRESS_KEY_DOMAIN: &[u8] = b"contract_state_commitment_progress";
+const SIGNING_KEY_DOMAIN: &[u8] = b"contract_state_commitment_signing";
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+pub type Leaf = [u8; HASH_SIZE];
+
+/// The key the leaves of a contract are computed with
+pub fn leaf_key(state_key: &AESKey) -> AESKey {
+    state_key.derive_key_from_this(LEAF_KEY_DOMAIN)
+}
+
+pub fn leaf_hash(leaf_key: &AESKey, key: &[u8], value: &[u8]) -> Leaf {
+    let mut data = LEAF_PREFIX.to_vec();
+    data.extend_from_slice(&encode_pairs(&[(key.to_vec(), value.to_vec())]));
+    leaf_key.sign_sha_256(&data)
+}
+
+fn node_hash(left: &[u8], right: &[u8]) -> Leaf {
+    let mut data = NODE_PREFIX.to_vec();
+    data.extend_from_slice(left);
+    data.extend_from_slice(right);
+    sha_256(&data)
+}
+
+/// A tree that leaves are appended to, keeping only the roots of its perfect subtrees
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct CommitmentProgress {
+    pub height: u64,
+    pub leaf_count: u64,
+    last_leaf: Option<Leaf>,
+    /// Sizes and roots of the perfect subtrees, largest first
+    subtrees: Vec<(u64, Leaf)>,
+}
+
+impl CommitmentProgress {
+    pub fn new(height: u64) -> Self {
+        Self {
+            height,
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, leaf: Leaf) -> Result<(), EnclaveError> {
+        if let Some(last_leaf) = self.last_leaf {
+            if leaf <= last_leaf {
+                warn!("state commitment leaves aren't ascending");
+                return Err(EnclaveError::ValidationFailure);
+            }
+        }
+        self.last_leaf = Some(leaf);
+        self.leaf_count += 1;
+
+        let mut subtree = (1, leaf);
+        while let Some(&(size, left)) = self.subtrees.last() {
+            if size != subtree.0 {
+                break;
+            }
+            self.subtrees.pop();
+            subtree = (size * 2, node_hash(&left, &subtree.1));
+        }
+        self.subtrees.push(subtree);
+
+        Ok(())
+    }
+
+    /// The Merkle tree hash of the leaves pushed so far, as defined by RFC 6962
+    pub fn root(&self) -> Leaf {
+        let mut subtrees = self.subtrees.iter().rev();
+        match subtrees.next() {
+            None => sha_256(&[]),
+            Some(&(_, smallest)) => {
+                subtrees.fold(smallest, |right, (_, left)| node_hash(left, &right))
+            }
+        }
+    }
+
+    pub fn seal(&self, state_key: &AESKey) -> Result<Vec<u8>, EnclaveError> {
+        let encoded = serde_json::to_vec(self).map_err(|err| {
+            error!("failed to encode a state commitment progress: {:?}", err);
+            EnclaveError::InternalError
+        })?;
+
+        state_key
+            .derive_key_from_this(PROGRESS_KEY_DOMAIN)
+            .encrypt_siv(&encoded, Some(&[]))
+            .map_err(|err| {
+                error!("failed to seal a state commitment progress: {:?}", err);
+                EnclaveError::EncryptionError
+            })
+    }
+
+    pub fn unseal(sealed: &[u8], state_key: &AESKey) -> Result<Self, EnclaveError> {
+        let encoded = state_key
+            .derive_key_from_this(PROGRESS_KEY_DOMAIN)
+            .decrypt_siv(sealed, Some(&[]))
+            .map_err(|err| {
+                warn!(
+                    "got a state commitment progress of another contract: {:?}",
+                    err
+                );
+                EnclaveError::DecryptionError
+            })?;
+
+        serde_json::from_slice(&encoded).map_err(|err| {
+            warn!("failed to decode a state commitment progress: {:?}", err);
+            EnclaveError::FailedToDeserialize
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SignedStateCommitment {
+    pub contract_address: HumanAddr,
+    pub code_hash: Binary,
+    pub height: Uint64,
+    pub leaf_count: Uint64,
+    pub root: Binary,
+    /// Compressed secp256k1 public key, the same on every node
+    pub public_key: Binary,
+    /// Compact secp256k1 signature over `sha256(sign_bytes)`
+    pub signature: Binary,
+}
+
+impl SignedStateCommitment {
+    pub fn sign_bytes(&self) -> Vec<u8> {
+        let contract_address = self.contract_address.0.as_bytes();
+
+        let mut bytes = vec![];
+        bytes.extend_from_slice(STATE_COMMITMENT_DOMAIN);
+        bytes.extend_from_slice(&(contract_address.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(contract_address);
+        bytes.extend_from_slice(self.code_hash.as_slice());
+        bytes.extend_from_slice(&self.height.u64().to_be_bytes());
+        bytes.extend_from_slice(&self.leaf_count.u64().to_be_bytes());
+        bytes.extend_from_slice(self.root.as_slice());
+
+        bytes
+    }
+
+    pub fn sign(
+        contract_address: &str,
+        code_hash: &[u8; HASH_SIZE],
+        progress: &CommitmentProgress,
+        signing_key: &[u8],
+    ) -> Result<Self, EnclaveError> {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let sk = secp256k1::SecretKey::from_slice(signing_key).map_err(|err| {
+            error!("state commitment signing key is invalid: {:?}", err);
+            EnclaveError::InternalError
+        })?;
+
+        let mut commitment = Self {
+            contract_address: HumanAddr(contract_address.to_string()),
+            code_hash: Binary(code_hash.to_vec()),
+            height: Uint64::new(progress.height),
+            leaf_count: Uint64::new(progress.leaf_count),
+            root: Binary(progress.root().to_vec()),
+            public_key: Binary(
+                secp256k1::PublicKey::from_secret_key(&secp, &sk)
+                    .serialize()
+                    .to_vec(),
+            ),
+            signature: Binary(vec![]),
+        };
+
+        let msg = secp256k1::Message::from_slice(&sha_256(&commitment.sign_bytes()))
+            .map_err(|_| EnclaveError::InternalError)?;
+        commitment.signature = Binary(secp.sign_ecdsa(&msg, &sk).serialize_compact().to_vec());
+
+        Ok(commitment)
+    }
+}
+
+/// The key commitments are signed with, derived from the genesis consensus seed
+pub fn signing_key() -> [u8; 32] {
+    let consensus_state_ikm = KEY_MANAGER.get_consensus_state_ikm().unwrap();
+    *consensus_state_ikm
+        .genesis
+        .derive_key_from_this(SIGNING_KEY_DOMAIN)
+        .get()
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    /// RFC 6962 section 2.1, computed recursively
+    fn merkle_tree_hash(leaves: &[Leaf]) -> Leaf {
+        match leaves.len() {
+            0 => sha_256(&[]),
+            1 => leaves[0],
+            n => {
+                let k = n.next_power_of_two() / 2;
+                node_hash(
+                    &merkle_tree_hash(&leaves[..k]),
+                    &merkle_tree_hash(&leaves[k..]),
+                )
+            }
+        }
+    }
+
+    pub fn test_state_commitment_root() {
+        let leaves: Vec<Leaf> = (0u8..20).map(|i| sha_256(&[i])).collect();
+        let mut sorted = leaves.clone();
+        sorted.sort();
+
+        for n in 0..=sorted.len() {
+            let mut progress = CommitmentProgress::new(100);
+            for leaf in &sorted[..n] {
+                progress.push(*leaf).unwrap();
+            }
+            assert_eq!(progress.root(), merkle_tree_hash(&sorted[..n]));
+            assert_eq!(progress.leaf_count, n as u64);
+        }
+    }
+
+    pub fn test_state_commitment_leaves_ascending() {
+        let mut progress = CommitmentProgress::new(100);
+        progress.push([2u8; HASH_SIZE]).unwrap();
+
+        assert!(progress.push([2u8; HASH_SIZE]).is_err());
+        assert!(progress.push([1u8; HASH_SIZE]).is_err());
+        assert!(progress.push([3u8; HASH_SIZE]).is_ok());
+
+        let key = leaf_key(&AESKey::new_from_slice(&sha_256(b"state key")));
+        let other_key = leaf_key(&AESKey::new_from_slice(&sha_256(b"other state key")));
+        assert_ne!(
+            leaf_hash(&key, b"key", b"value"),
+            leaf_hash(&other_key, b"key", b"value")
+        );
+        // pairs are length prefixed, so moving a byte from the key to the value changes the leaf
+        assert_ne!(
+            leaf_hash(&key, b"key", b"value"),
+            leaf_hash(&key, b"ke", b"yvalue")
+        );
+    }
+
+    pub fn test_state_commitment_progress_sealing() {
+        let state_key = AESKey::new_from_slice(&sha_256(b"state key"));
+        let other_state_key = AESKey::new_from_slice(&sha_256(b"other state key"));
+
+        let mut progress = CommitmentProgress::new(100);
+        progress.push([1u8; HASH_SIZE]).unwrap();
+        progress.push([2u8; HASH_SIZE]).unwrap();
+        progress.push([3u8; HASH_SIZE]).unwrap();
+
+        let sealed = progress.seal(&state_key).unwrap();
+        assert_eq!(
+            CommitmentProgress::unseal(&sealed, &state_key).unwrap(),
+            progress
+        );
+        assert!(CommitmentProgress::unseal(&sealed, &other_state_key).is_err());
+
+        let commitment = SignedStateCommitment::sign(
+            "secret1contract",
+            &[7u8; HASH_SIZE],
+            &progress,
+            &sha_256(b"signing key"),
+        )
+        .unwrap();
+        assert_eq!(commitment.root.as_slice(), progress.root());
+        assert_eq!(commitment.leaf_count.u64(), 3);
+
+        let secp = secp256k1::Secp256k1::verification_only();
+        let msg = secp256k1::Message::from_slice(&sha_256(&commitment.sign_bytes())).unwrap();
+        let signature =
+            secp256k1::ecdsa::Signature::from_compact(commitment.signature.as_slice()).unwrap();
+        let public_key =
+            secp256k1::PublicKey::from_slice(commitment.public_key.as_slice()).unwrap();
+        assert!(secp.verify_ecdsa(&msg, &signature, &public_key).is_ok());
+    }
+}
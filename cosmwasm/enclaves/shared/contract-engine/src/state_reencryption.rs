@@ -0,0 +1,235 @@
+//! Re-encryption of the values a contract still has under the genesis seed, so that the seed can
+//! eventually be retired instead of lingering in the state of contracts nobody touches.
+//!
+//! Values are only moved to the current seed when a contract reads them, and only if it can
+//! write, so values of idle contracts stay under the genesis seed. The node sweeps the state of
+//! contracts at the end of each block instead:
+//! 1. It passes a chunk of at most `MAX_REENCRYPTION_PAIRS` of a contract's stored pairs, in the
+//!    order of their keys, with the progress of the sweep, sealed.
+//! 2. The enclave re-encrypts the pairs that are under the genesis seed and returns them to be
+//!    stored, with the progress after the chunk.
+//!
+//! Legacy values are stored under the digest of their field name, which the field name can't be
+//! recovered from, so they stay under the same digest and are re-encrypted in place:
+//!
+//! ```text
+//! value = "secret-rekeyed" || seed version (2) || AES-SIV ciphertext
+//! ```
+//!
+//! Re-encryption is deterministic, so every node stores the same pairs and the same progress.
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use cw_types_v010::encoding::Binary;
+use cw_types_v1::math::Uint64;
+use enclave_crypto::{AESKey, Kdf, SIVEncryptable};
+use enclave_ffi_types::EnclaveError;
+
+use crate::contract_validation::ContractKey;
+use crate::db::{rekey_legacy_value, stored_pair_epoch, StoredPairEpoch};
+use crate::public_state::PUBLIC_KEY_PREFIX;
+use crate::state_backup::{encode_pairs, StatePairs};
+
+/// The most pairs a chunk can have, which bounds the work of a single call
+pub const MAX_REENCRYPTION_PAIRS: usize = 256;
+
+const PROGRESS_KEY_DOMAIN: &[u8] = b"contract_state_reencryption_progress";
+
+/// How far the sweep of a contract's state got
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct ReencryptionProgress {
+    /// The stored key of the last pair the sweep went over
+    last_key: Option<Vec<u8>>,
+    /// Pairs that were re-encrypted with the current seed
+    pub reencrypted: u64,
+    /// Pairs under a seed the enclave doesn't have, which are left as they are
+    pub retired: u64,
+    pub complete: bool,
+}
+
+/// What the node stores after a chunk
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReencryptionOutput {
+    /// The sealed progress to pass with the next chunk
+    pub progress: Binary,
+    /// The re-encrypted pairs, encoded like the chunks of a state backup
+    pub pairs: Binary,
+    pub reencrypted: Uint64,
+    pub complete: bool,
+}
+
+impl ReencryptionProgress {
+    /// Moves the sweep past `key`, which must come after every key it went over
+    fn advance(&mut self, key: &[u8]) -> Result<(), EnclaveError> {
+        if self.complete {
+            warn!("got more pairs after the state re-encryption of a contract completed");
+            return Err(EnclaveError::ValidationFailure);
+        }
+        if let Some(last_key) = &self.last_key {
+            if key <= last_key.as_slice() {
+                warn!("state re-encryption pairs aren't in the order of their keys");
+                return Err(EnclaveError::ValidationFailure);
+            }
+        }
+
+        self.last_key = Some(key.to_vec());
+        Ok(())
+    }
+
+    pub fn seal(&self, state_key: &AESKey) -> Result<Vec<u8>, EnclaveError> {
+        let encoded = serde_json::to_vec(self).map_err(|err| {
+            error!("failed to encode a state re-encryption progress: {:?}", err);
+            EnclaveError::InternalError
+        })?;
+
+        state_key
+            .derive_key_from_this(PROGRESS_KEY_DOMAIN)
+            .encrypt_siv(&encoded, Some(&[]))
+            .map_err(|err| {
+                error!("failed to seal a state re-encryption progress: {:?}", err);
+                EnclaveError::EncryptionError
+            })
+    }
+
+    pub fn unseal(sealed: &[u8], state_key: &AESKey) -> Result<Self, EnclaveError> {
+        let encoded = state_key
+            .derive_key_from_this(PROGRESS_KEY_DOMAIN)
+            .decrypt_siv(sealed, Some(&[]))
+            .map_err(|err| {
+                warn!(
+                    "got a state re-encryption progress of another contract: {:?}",
+                    err
+                );
+                EnclaveError::DecryptionError
+            })?;
+
+        serde_json::from_slice(&encoded).map_err(|err| {
+            warn!("failed to decode a state re-encryption progress: {:?}", err);
+            EnclaveError::FailedToDeserialize
+        })
+    }
+}
+
+/// Re-encrypts the pairs of a chunk that are under the genesis seed, and returns them
+pub fn reencrypt_chunk(
+    progress: &mut ReencryptionProgress,
+    stored_pairs: &[(Vec<u8>, Vec<u8>)],
+    og_contract_key: &ContractKey,
+    last_chunk: bool,
+) -> Result<StatePairs, EnclaveError> {
+    if stored_pairs.len() > MAX_REENCRYPTION_PAIRS {
+        warn!(
+            "got {} pairs to re-encrypt, at most {} are allowed",
+            stored_pairs.len(),
+            MAX_REENCRYPTION_PAIRS
+        );
+        return Err(EnclaveError::ValidationFailure);
+    }
+
+    let mut reencrypted = vec![];
+    for (key, value) in stored_pairs {
+        progress.advance(key)?;
+
+        // Public values aren't encrypted
+        if key.starts_with(PUBLIC_KEY_PREFIX) {
+            continue;
+        }
+
+        match stored_pair_epoch(key, value) {
+            StoredPairEpoch::Current => {}
+            StoredPairEpoch::Genesis => match rekey_legacy_value(key, value, og_contract_key) {
+                Ok(rekeyed_value) => {
+                    reencrypted.push((key.clone(), rekeyed_value));
+                    progress.reencrypted += 1;
+                }
+                // Left as it is, like the contract would fail to read it
+                Err(_) => progress.retired += 1,
+            },
+            StoredPairEpoch::Retired => progress.retired += 1,
+        }
+    }
+
+    progress.complete = last_chunk;
+    Ok(reencrypted)
+}
+
+pub fn encode_output(
+    progress: &ReencryptionProgress,
+    reencrypted: &[(Vec<u8>, Vec<u8>)],
+    state_key: &AESKey,
+) -> Result<Vec<u8>, EnclaveError> {
+    let output = ReencryptionOutput {
+        progress: Binary(progress.seal(state_key)?),
+        pairs: Binary(encode_pairs(reencrypted)),
+        reencrypted: Uint64::new(reencrypted.len() as u64),
+        complete: progress.complete,
+    };
+
+    serde_json::to_vec(&output).map_err(|err| {
+        error!("failed to encode a state re-encryption output: {:?}", err);
+        EnclaveError::InternalError
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use enclave_crypto::sha_256;
+
+    pub fn test_state_reencryption_progress() {
+        let contract = [1u8; 64];
+        let mut progress = ReencryptionProgress::default();
+
+        // pairs the enclave can't read are left as they are, and public values are skipped, even
+        // when their keys are as long as a legacy key
+        let mut public_key = PUBLIC_KEY_PREFIX.to_vec();
+        public_key.extend_from_slice(&[b'a'; 24]);
+        let chunk = vec![
+            (b"\x00\x01".to_vec(), b"unknown".to_vec()),
+            (public_key, b"100".to_vec()),
+        ];
+        assert!(reencrypt_chunk(&mut progress, &chunk, &contract, false)
+            .unwrap()
+            .is_empty());
+        assert_eq!(progress.retired, 1);
+        assert!(!progress.complete);
+
+        // keys must be ascending across chunks
+        let chunk = vec![(b"\x00\x01".to_vec(), vec![])];
+        assert!(reencrypt_chunk(&mut progress, &chunk, &contract, false).is_err());
+
+        let chunk = vec![(b"\xff".to_vec(), vec![])];
+        reencrypt_chunk(&mut progress, &chunk, &contract, true).unwrap();
+        assert!(progress.complete);
+        let chunk = vec![(b"\xff\xff".to_vec(), vec![])];
+        assert!(reencrypt_chunk(&mut progress, &chunk, &contract, true).is_err());
+
+        let too_many = vec![(vec![], vec![]); MAX_REENCRYPTION_PAIRS + 1];
+        assert!(reencrypt_chunk(
+            &mut ReencryptionProgress::default(),
+            &too_many,
+            &contract,
+            false
+        )
+        .is_err());
+    }
+
+    pub fn test_state_reencryption_progress_sealing() {
+        let state_key = AESKey::new_from_slice(&sha_256(b"state key"));
+        let other_state_key = AESKey::new_from_slice(&sha_256(b"other state key"));
+
+        let mut progress = ReencryptionProgress::default();
+        progress.advance(b"key").unwrap();
+        progress.reencrypted = 3;
+
+        let sealed = progress.seal(&state_key).unwrap();
+        assert_eq!(sealed, progress.seal(&state_key).unwrap());
+        assert_eq!(
+            ReencryptionProgress::unseal(&sealed, &state_key).unwrap(),
+            progress
+        );
+        assert!(ReencryptionProgress::unseal(&sealed, &other_state_key).is_err());
+    }
+}
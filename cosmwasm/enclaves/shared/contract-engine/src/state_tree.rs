@@ -0,0 +1,618 @@
+//! Sparse Merkle commitments to the state of contracts that opt into them.
+//!
+//! Contracts that export `requires_state_commitment` get a sparse Merkle tree over their state,
+//! which the engine updates on every `db_write` and `db_remove`. Proofs of the tree show that a
+//! key has a value, or that it has none, with hashes only, so they reveal neither the other
+//! entries nor the values themselves:
+//!
+//! * The path of an entry is the SHA-256 of its plaintext key, and its leaf commits to the
+//!   SHA-256 of the key and of its value: `sha256(0x00 || key_hash || value_hash)`.
+//! * Internal nodes are `sha256(0x01 || left || right)`, and empty subtrees are 32 zero bytes.
+//! * Subtrees with a single leaf are that leaf, so paths only go as deep as the keys of the
+//!   entries differ, and updates take about `log2(entries)` reads and writes.
+//!
+//! The tree is stored in the contract's own encrypted state, under `STATE_TREE_PREFIX`, which the
+//! contract can't write to itself. Keys of oblivious namespaces are left out of the tree, since
+//! updating it would reveal which of their keys were accessed.
+
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+
+use enclave_crypto::sha_256;
+
+use crate::errors::{WasmEngineError, WasmEngineResult};
+use crate::merkle_accumulator::MerkleStorage;
+
+/// Error codes returned to contracts by the state commitment host functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum StateTreeError {
+    /// The contract's code doesn't require a state commitment
+    NotEnabled = 1,
+    /// The root isn't 32 bytes, or the proof is malformed
+    InvalidInput = 2,
+    InvalidProof = 3,
+}
+
+pub const HASH_SIZE: usize = 32;
+
+type Hash = [u8; HASH_SIZE];
+
+const EMPTY: Hash = [0u8; HASH_SIZE];
+
+/// The siblings along a path, starting at the root, and the key and value hashes of the leaf the
+/// path ends in, if it doesn't end in an empty subtree
+type Descent = (Vec<Hash>, Option<(Hash, Hash)>);
+
+/// Paths are the bits of key hashes
+const MAX_DEPTH: usize = HASH_SIZE * 8;
+
+/// The tree is stored under this key, and its nodes under keys that start with it
+pub const STATE_TREE_PREFIX: &[u8] = b"\xffstate_tree";
+
+/// The length of a stored tree: its root, its number of entries, its generation and its flags
+const TREE_SIZE: usize = HASH_SIZE + 8 + 4 + 1;
+
+const COMPLETE: u8 = 1;
+const ACTIVE: u8 = 2;
+
+const LEAF: u8 = 0;
+const INTERNAL: u8 = 1;
+
+/// Proofs start with one of these, by what the path of the key ends in
+const PROOF_EMPTY: u8 = 0;
+const PROOF_LEAF: u8 = 1;
+
+/// Whether a key is reserved for the tree
+pub fn is_reserved(key: &[u8]) -> bool {
+    key.starts_with(STATE_TREE_PREFIX)
+}
+
+fn bit(path: &Hash, depth: usize) -> bool {
+    (path[depth / 8] >> (7 - depth % 8)) & 1 == 1
+}
+
+/// The path with the bit at a depth flipped, which leads to the sibling at that depth
+fn flip(path: &Hash, depth: usize) -> Hash {
+    let mut flipped = *path;
+    flipped[depth / 8] ^= 1 << (7 - depth % 8);
+    flipped
+}
+
+/// The number of leading bits two paths have in common
+fn common_prefix(a: &Hash, b: &Hash) -> usize {
+    a.iter()
+        .zip(b.iter())
+        .position(|(x, y)| x != y)
+        .map_or(MAX_DEPTH, |index| {
+            index * 8 + (a[index] ^ b[index]).leading_zeros() as usize
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Node {
+    Leaf { key: Hash, value: Hash },
+    Internal { left: Hash, right: Hash },
+}
+
+impl Node {
+    /// The node of which the child on the side of `bit` is `child`, and the other one `sibling`
+    fn internal(bit: bool, child: Hash, sibling: Hash) -> Self {
+        if bit {
+            Node::Internal {
+                left: sibling,
+                right: child,
+            }
+        } else {
+            Node::Internal {
+                left: child,
+                right: sibling,
+            }
+        }
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let (tag, first, second) = match self {
+            Node::Leaf { key, value } => (LEAF, key, value),
+            Node::Internal { left, right } => (INTERNAL, left, right),
+        };
+        [&[tag][..], &first, &second].concat()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> WasmEngineResult<Self> {
+        if bytes.len() != 1 + 2 * HASH_SIZE {
+            return Err(WasmEngineError::DeserializationError);
+        }
+        let first = bytes[1..1 + HASH_SIZE].try_into().unwrap();
+        let second = bytes[1 + HASH_SIZE..].try_into().unwrap();
+        match bytes[0] {
+            LEAF => Ok(Node::Leaf {
+                key: first,
+                value: second,
+            }),
+            INTERNAL => Ok(Node::Internal {
+                left: first,
+                right: second,
+            }),
+            _ => Err(WasmEngineError::DeserializationError),
+        }
+    }
+
+    /// Nodes are hashed as they're stored, so leaves and internal nodes are domain separated
+    fn hash(self) -> Hash {
+        sha_256(&self.to_bytes())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateTree {
+    pub root: Hash,
+    pub entries: u64,
+    /// Whether the tree has committed to the state since the contract was instantiated. Trees of
+    /// contracts that migrated to code that requires them only commit to the entries written since.
+    pub complete: bool,
+    /// Whether the contract's code still requires the tree
+    active: bool,
+    /// Incremented when the tree starts again, so the nodes of the previous tree are ignored
+    generation: u32,
+}
+
+impl StateTree {
+    fn empty(generation: u32, complete: bool) -> Self {
+        StateTree {
+            root: EMPTY,
+            entries: 0,
+            complete,
+            active: true,
+            generation,
+        }
+    }
+
+    fn load<S: MerkleStorage>(storage: &mut S) -> WasmEngineResult<Option<Self>> {
+        let bytes = match storage.read(STATE_TREE_PREFIX)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        if bytes.len() != TREE_SIZE {
+            return Err(WasmEngineError::DeserializationError);
+        }
+
+        let flags = bytes[TREE_SIZE - 1];
+        Ok(Some(StateTree {
+            root: bytes[..HASH_SIZE].try_into().unwrap(),
+            entries: u64::from_be_bytes(bytes[HASH_SIZE..HASH_SIZE + 8].try_into().unwrap()),
+            complete: flags & COMPLETE != 0,
+            active: flags & ACTIVE != 0,
+            generation: u32::from_be_bytes(
+                bytes[HASH_SIZE + 8..HASH_SIZE + 12].try_into().unwrap(),
+            ),
+        }))
+    }
+
+    fn save<S: MerkleStorage>(&self, storage: &mut S) {
+        let mut flags = 0;
+        if self.complete {
+            flags |= COMPLETE;
+        }
+        if self.active {
+            flags |= ACTIVE;
+        }
+
+        let mut bytes = self.root.to_vec();
+        bytes.extend_from_slice(&self.entries.to_be_bytes());
+        bytes.extend_from_slice(&self.generation.to_be_bytes());
+        bytes.push(flags);
+        storage.write(STATE_TREE_PREFIX, &bytes);
+    }
+
+    /// Returns the tree of a contract whose code requires one, and starts it if it wasn't yet.
+    /// A tree only starts complete when the contract is instantiated, since the state of a
+    /// contract that already exists may have entries the tree doesn't have.
+    pub fn start<S: MerkleStorage>(storage: &mut S, instantiated: bool) -> WasmEngineResult<Self> {
+        let previous = StateTree::load(storage)?;
+        if let Some(tree) = previous {
+            if tree.active {
+                return Ok(tree);
+            }
+        }
+
+        let generation = previous.map_or(0, |tree| tree.generation.wrapping_add(1));
+        let tree = StateTree::empty(generation, instantiated && previous.is_none());
+        tree.save(storage);
+        Ok(tree)
+    }
+
+    /// Stops the tree of a contract that migrated to code that doesn't require one. The tree is
+    /// started again from scratch if the contract migrates back, since its state may change
+    /// in between.
+    pub fn stop<S: MerkleStorage>(storage: &mut S) -> WasmEngineResult<()> {
+        if let Some(mut tree) = StateTree::load(storage)? {
+            if tree.active {
+                tree.active = false;
+                tree.complete = false;
+                tree.save(storage);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the tree of a contract whose code requires one, without starting it, for calls
+    /// that can't write. A tree that wasn't started yet is empty and incomplete.
+    pub fn current<S: MerkleStorage>(storage: &mut S) -> WasmEngineResult<Self> {
+        match StateTree::load(storage)? {
+            Some(tree) if tree.active => Ok(tree),
+            _ => Ok(StateTree::empty(0, false)),
+        }
+    }
+
+    /// Nodes are stored under their generation, their depth, and the bits of their path
+    fn node_key(&self, depth: usize, path: &Hash) -> Vec<u8> {
+        let mut key = STATE_TREE_PREFIX.to_vec();
+        key.extend_from_slice(&self.generation.to_be_bytes());
+        key.extend_from_slice(&(depth as u16).to_be_bytes());
+
+        let length = (depth + 7) / 8;
+        key.extend_from_slice(&path[..length]);
+        if depth % 8 != 0 {
+            key[STATE_TREE_PREFIX.len() + 6 + length - 1] &= 0xff << (8 - depth % 8);
+        }
+        key
+    }
+
+    fn read_node<S: MerkleStorage>(
+        &self,
+        storage: &mut S,
+        depth: usize,
+        path: &Hash,
+    ) -> WasmEngineResult<Node> {
+        let bytes = storage
+            .read(&self.node_key(depth, path))?
+            .ok_or(WasmEngineError::DeserializationError)?;
+        Node::from_bytes(&bytes)
+    }
+
+    /// Follows a path from the root until it ends in an empty subtree or a leaf
+    fn descend<S: MerkleStorage>(&self, storage: &mut S, path: &Hash) -> WasmEngineResult<Descent> {
+        let mut siblings = vec![];
+        let mut hash = self.root;
+        while hash != EMPTY {
+            let depth = siblings.len();
+            match self.read_node(storage, depth, path)? {
+                Node::Leaf { key, value } => return Ok((siblings, Some((key, value)))),
+                Node::Internal { .. } if depth == MAX_DEPTH => {
+                    return Err(WasmEngineError::DeserializationError)
+                }
+                Node::Internal { left, right } => {
+                    let (next, sibling) = if bit(path, depth) {
+                        (right, left)
+                    } else {
+                        (left, right)
+                    };
+                    siblings.push(sibling);
+                    hash = next;
+                }
+            }
+        }
+        Ok((siblings, None))
+    }
+
+    /// Sets the value of a key, or removes the key if the value is None. Returns the number of
+    /// nodes that were hashed.
+    pub fn update<S: MerkleStorage>(
+        &mut self,
+        storage: &mut S,
+        key: &[u8],
+        value: Option<&[u8]>,
+    ) -> WasmEngineResult<u64> {
+        let path = sha_256(key);
+        let (siblings, end) = self.descend(storage, &path)?;
+        let depth = siblings.len();
+
+        // Nodes to write, or to remove if they're None, by their keys
+        let mut changes: BTreeMap<Vec<u8>, Option<Node>> = BTreeMap::new();
+        let mut subtree = match (value, end) {
+            (Some(value), None) => {
+                self.entries += 1;
+                let leaf = Node::Leaf {
+                    key: path,
+                    value: sha_256(value),
+                };
+                changes.insert(self.node_key(depth, &path), Some(leaf));
+                Some(leaf)
+            }
+            (Some(value), Some((other, other_value))) if other == path => {
+                let value = sha_256(value);
+                if value == other_value {
+                    return Ok(0);
+                }
+                let leaf = Node::Leaf { key: path, value };
+                changes.insert(self.node_key(depth, &path), Some(leaf));
+                Some(leaf)
+            }
+            (Some(value), Some((other, other_value))) => {
+                self.entries += 1;
+                // Both leaves move down below the depth where their paths diverge
+                let split = common_prefix(&path, &other);
+                let leaf = Node::Leaf {
+                    key: path,
+                    value: sha_256(value),
+                };
+                let other_leaf = Node::Leaf {
+                    key: other,
+                    value: other_value,
+                };
+                changes.insert(self.node_key(split + 1, &path), Some(leaf));
+                changes.insert(self.node_key(split + 1, &other), Some(other_leaf));
+
+                let mut node = Node::internal(bit(&path, split), leaf.hash(), other_leaf.hash());
+                changes.insert(self.node_key(split, &path), Some(node));
+                for level in (depth..split).rev() {
+                    node = Node::internal(bit(&path, level), node.hash(), EMPTY);
+                    changes.insert(self.node_key(level, &path), Some(node));
+                }
+                Some(node)
+            }
+            (None, Some((other, _))) if other == path => {
+                self.entries -= 1;
+                changes.insert(self.node_key(depth, &path), None);
+                None
+            }
+            // The key has no value already
+            (None, _) => return Ok(0),
+        };
+
+        // After a removal, a single leaf left in a subtree moves up for as long as it has no
+        // sibling, since subtrees with a single leaf are that leaf
+        let mut merging = value.is_none();
+        for level in (0..depth).rev() {
+            let sibling = siblings[level];
+            if merging {
+                let leaf = match subtree {
+                    None if sibling != EMPTY => {
+                        match self.read_node(storage, level + 1, &flip(&path, level))? {
+                            leaf @ Node::Leaf { .. } => Some(leaf),
+                            Node::Internal { .. } => None,
+                        }
+                    }
+                    Some(leaf @ Node::Leaf { .. }) if sibling == EMPTY => Some(leaf),
+                    _ => None,
+                };
+                if let Some(leaf @ Node::Leaf { key, .. }) = leaf {
+                    changes.insert(self.node_key(level + 1, &key), None);
+                    changes.insert(self.node_key(level, &key), Some(leaf));
+                    subtree = Some(leaf);
+                    continue;
+                }
+                merging = false;
+            }
+
+            let child = subtree.map_or(EMPTY, Node::hash);
+            let node = Node::internal(bit(&path, level), child, sibling);
+            changes.insert(self.node_key(level, &path), Some(node));
+            subtree = Some(node);
+        }
+        self.root = subtree.map_or(EMPTY, Node::hash);
+
+        let mut hashed = 0;
+        for (key, node) in changes {
+            match node {
+                Some(node) => {
+                    storage.write(&key, &node.to_bytes());
+                    hashed += 1;
+                }
+                None => storage.remove(&key)?,
+            }
+        }
+        self.save(storage);
+
+        Ok(hashed)
+    }
+
+    /// Returns the proof of the value of a key, or that it has none: what the path of the key ends
+    /// in, followed by the siblings along the path, starting at its end
+    pub fn prove<S: MerkleStorage>(
+        &self,
+        storage: &mut S,
+        key: &[u8],
+    ) -> WasmEngineResult<Vec<u8>> {
+        let (siblings, end) = self.descend(storage, &sha_256(key))?;
+
+        let mut proof = match end {
+            Some((key, value)) => [&[PROOF_LEAF][..], &key, &value].concat(),
+            None => vec![PROOF_EMPTY],
+        };
+        for sibling in siblings.iter().rev() {
+            proof.extend_from_slice(sibling);
+        }
+        Ok(proof)
+    }
+}
+
+/// The number of nodes hashed to verify a proof
+pub fn proof_hashes(proof: &[u8]) -> u64 {
+    (proof.len() / HASH_SIZE) as u64
+}
+
+/// Checks a proof of a key against a root. Returns the hash of the value of the key, or None if
+/// the proof shows that the key has no value.
+pub fn verify(root: &[u8], key: &[u8], proof: &[u8]) -> Result<Option<Hash>, StateTreeError> {
+    if root.len() != HASH_SIZE || proof.is_empty() {
+        return Err(StateTreeError::InvalidInput);
+    }
+
+    let (end, siblings) = match proof[0] {
+        PROOF_EMPTY => (None, &proof[1..]),
+        PROOF_LEAF if proof.len() > 2 * HASH_SIZE => {
+            let key = proof[1..1 + HASH_SIZE].try_into().unwrap();
+            let value = proof[1 + HASH_SIZE..1 + 2 * HASH_SIZE].try_into().unwrap();
+            (Some((key, value)), &proof[1 + 2 * HASH_SIZE..])
+        }
+        _ => return Err(StateTreeError::InvalidInput),
+    };
+    if siblings.len() % HASH_SIZE != 0 || siblings.len() / HASH_SIZE > MAX_DEPTH {
+        return Err(StateTreeError::InvalidInput);
+    }
+
+    let path = sha_256(key);
+    let depth = siblings.len() / HASH_SIZE;
+    let mut hash = match end {
+        // The leaf has to be on the path of the key, where the path of its own key led
+        Some((other, _)) if common_prefix(&path, &other) < depth => {
+            return Err(StateTreeError::InvalidProof)
+        }
+        Some((key, value)) => Node::Leaf { key, value }.hash(),
+        None => EMPTY,
+    };
+    for (index, sibling) in siblings.chunks(HASH_SIZE).enumerate() {
+        let level = depth - 1 - index;
+        hash = Node::internal(bit(&path, level), hash, sibling.try_into().unwrap()).hash();
+    }
+
+    if hash != root[..] {
+        return Err(StateTreeError::InvalidProof);
+    }
+    match end {
+        Some((other, value)) if other == path => Ok(Some(value)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    use crate::merkle_accumulator::tests::TestStorage;
+
+    /// The root and the number of nodes of the tree of some entries, computed from scratch
+    fn naive_tree(entries: &[(Hash, Hash)], depth: usize) -> (Hash, usize) {
+        match entries {
+            [] => (EMPTY, 0),
+            [(key, value)] => (
+                Node::Leaf {
+                    key: *key,
+                    value: *value,
+                }
+                .hash(),
+                1,
+            ),
+            _ => {
+                let (right, left): (Vec<_>, Vec<_>) = entries
+                    .iter()
+                    .cloned()
+                    .partition(|(key, _)| bit(key, depth));
+                let (left, left_nodes) = naive_tree(&left, depth + 1);
+                let (right, right_nodes) = naive_tree(&right, depth + 1);
+                (
+                    Node::Internal { left, right }.hash(),
+                    left_nodes + right_nodes + 1,
+                )
+            }
+        }
+    }
+
+    pub fn test_state_tree_updates_and_proofs() {
+        let mut storage = TestStorage::default();
+        let mut tree = StateTree::start(&mut storage, true).unwrap();
+        assert!(tree.complete);
+
+        let mut state: BTreeMap<Vec<u8>, Vec<u8>> = BTreeMap::new();
+        for step in 0u32..200 {
+            // Writes, overwrites and removes keys from a small set, so paths collide and merge
+            let key = (step.wrapping_mul(7919) % 61).to_be_bytes().to_vec();
+            if step % 3 == 2 {
+                tree.update(&mut storage, &key, None).unwrap();
+                state.remove(&key);
+            } else {
+                let value = step.to_be_bytes().to_vec();
+                tree.update(&mut storage, &key, Some(&value)).unwrap();
+                state.insert(key, value);
+            }
+
+            let entries: Vec<(Hash, Hash)> = state
+                .iter()
+                .map(|(key, value)| (sha_256(key), sha_256(value)))
+                .collect();
+            let (root, nodes) = naive_tree(&entries, 0);
+            assert_eq!(tree.root, root);
+            assert_eq!(tree.entries, state.len() as u64);
+            // No nodes are left behind, besides the tree itself
+            assert_eq!(storage.values.len(), nodes + 1);
+        }
+
+        for key in 0u32..70 {
+            let key = key.to_be_bytes();
+            let proof = tree.prove(&mut storage, &key).unwrap();
+            let expected = state.get(&key[..]).map(|value| sha_256(value));
+            assert_eq!(verify(&tree.root, &key, &proof), Ok(expected));
+        }
+    }
+
+    pub fn test_state_tree_rejects_invalid_proofs() {
+        let mut storage = TestStorage::default();
+        let mut tree = StateTree::start(&mut storage, true).unwrap();
+        for key in 0u32..10 {
+            tree.update(&mut storage, &key.to_be_bytes(), Some(b"value"))
+                .unwrap();
+        }
+
+        let key = 3u32.to_be_bytes();
+        let proof = tree.prove(&mut storage, &key).unwrap();
+        assert_eq!(
+            verify(&tree.root, &key, &proof),
+            Ok(Some(sha_256(b"value")))
+        );
+
+        // The proof of one key doesn't prove anything about another
+        assert_eq!(
+            verify(&tree.root, &4u32.to_be_bytes(), &proof),
+            Err(StateTreeError::InvalidProof)
+        );
+        let mut tampered = proof.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            verify(&tree.root, &key, &tampered),
+            Err(StateTreeError::InvalidProof)
+        );
+        // An inclusion proof can't be turned into an exclusion proof
+        let mut excluded = vec![PROOF_EMPTY];
+        excluded.extend_from_slice(&proof[1 + 2 * HASH_SIZE..]);
+        assert_eq!(
+            verify(&tree.root, &key, &excluded),
+            Err(StateTreeError::InvalidProof)
+        );
+
+        assert_eq!(
+            verify(&tree.root[1..], &key, &proof),
+            Err(StateTreeError::InvalidInput)
+        );
+        assert_eq!(
+            verify(&tree.root, &key, &proof[..proof.len() - 1]),
+            Err(StateTreeError::InvalidInput)
+        );
+        assert_eq!(
+            verify(&tree.root, &key, &[]),
+            Err(StateTreeError::InvalidInput)
+        );
+    }
+
+    pub fn test_state_tree_restarts_after_migrations() {
+        let mut storage = TestStorage::default();
+        let mut tree = StateTree::start(&mut storage, true).unwrap();
+        tree.update(&mut storage, b"key", Some(b"value")).unwrap();
+        assert_eq!(StateTree::start(&mut storage, false).unwrap(), tree);
+
+        StateTree::stop(&mut storage).unwrap();
+        assert_eq!(StateTree::current(&mut storage).unwrap().root, EMPTY);
+
+        // The state may have changed while the tree was stopped
+        let mut tree = StateTree::start(&mut storage, false).unwrap();
+        assert!(!tree.complete);
+        assert_eq!((tree.root, tree.entries), (EMPTY, 0));
+        tree.update(&mut storage, b"other", Some(b"value")).unwrap();
+        let proof = tree.prove(&mut storage, b"key").unwrap();
+        assert_eq!(verify(&tree.root, b"key", &proof), Ok(None));
+
+        // Contracts that are migrated to code that requires a tree start incomplete
+        let mut storage = TestStorage::default();
+        assert!(!StateTree::start(&mut storage, false).unwrap().complete);
+    }
+}
@@ -0,0 +1,171 @@
+//! Stable error codes of failed submessages, for contracts that handle the errors in `reply`.
+//!
+//! The error a contract sees in `SubMsgResult::Err` is always a json object with a code:
+//!
+//! ```json
+//! {"code":"out_of_gas"}
+//! {"code":"custom","msg":"<the error the called contract returned>"}
+//! ```
+//!
+//! The codes don't change between enclave or node versions, and the human readable error only
+//! goes to the `submsg_error` event the node emits. Only `custom` errors carry a message, since
+//! the message of a contract's own error is chosen by the contract, and is the same on every node.
+//!
+//! The node passes the errors of plaintext replies as json too, either the error of the called
+//! contract or the ABCI code of any other error:
+//!
+//! ```json
+//! {"contract_error":"..."}
+//! {"codespace":"sdk","code":11}
+//! ```
+//!
+//! Errors of encrypted replies are always the called contract's, since the node fails the whole
+//! tx on any other error of an encrypted submessage.
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubMsgErrorCode {
+    OutOfGas,
+    Unauthorized,
+    NotFound,
+    /// The called contract returned an error
+    Custom,
+    /// Any other error, or one the enclave can't classify
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SubMsgError {
+    pub code: SubMsgErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub msg: Option<String>,
+}
+
+/// A failed submessage, as the node reports it
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum NodeError {
+    Contract { contract_error: String },
+    Abci { codespace: String, code: u32 },
+}
+
+impl SubMsgError {
+    pub fn custom(msg: String) -> Self {
+        Self {
+            code: SubMsgErrorCode::Custom,
+            msg: Some(msg),
+        }
+    }
+
+    fn of_code(code: SubMsgErrorCode) -> Self {
+        Self { code, msg: None }
+    }
+
+    /// Classifies the error the node reported for a plaintext reply
+    pub fn from_node_error(error: &str) -> Self {
+        match serde_json::from_str::<NodeError>(error) {
+            Ok(NodeError::Contract { contract_error }) => Self::custom(contract_error),
+            Ok(NodeError::Abci { codespace, code }) => {
+                Self::of_code(abci_error_code(&codespace, code))
+            }
+            Err(err) => {
+                warn!("failed to parse a submessage error from the node: {}", err);
+                Self::of_code(SubMsgErrorCode::Unknown)
+            }
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        // Serializing a struct of strings can't fail
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// The codes of the ABCI errors, which are registered once and never change
+fn abci_error_code(codespace: &str, code: u32) -> SubMsgErrorCode {
+    match (codespace, code) {
+        // ErrOutOfGas
+        ("sdk", 11) => SubMsgErrorCode::OutOfGas,
+        // ErrGasLimit
+        ("compute", 7) => SubMsgErrorCode::OutOfGas,
+        // ErrUnauthorized
+        ("sdk", 4) => SubMsgErrorCode::Unauthorized,
+        // ErrUnknownAddress, ErrNotFound
+        ("sdk", 9) | ("sdk", 38) => SubMsgErrorCode::NotFound,
+        // ErrNotFound
+        ("compute", 9) => SubMsgErrorCode::NotFound,
+        _ => SubMsgErrorCode::Unknown,
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_submsg_error_classification() {
+        let code = |error: &str| SubMsgError::from_node_error(error).code;
+
+        assert_eq!(
+            code(r#"{"codespace":"sdk","code":11}"#),
+            SubMsgErrorCode::OutOfGas
+        );
+        assert_eq!(
+            code(r#"{"codespace":"compute","code":7}"#),
+            SubMsgErrorCode::OutOfGas
+        );
+        assert_eq!(
+            code(r#"{"codespace":"sdk","code":4}"#),
+            SubMsgErrorCode::Unauthorized
+        );
+        assert_eq!(
+            code(r#"{"codespace":"sdk","code":9}"#),
+            SubMsgErrorCode::NotFound
+        );
+        assert_eq!(
+            code(r#"{"codespace":"compute","code":9}"#),
+            SubMsgErrorCode::NotFound
+        );
+        assert_eq!(
+            code(r#"{"codespace":"sdk","code":5}"#),
+            SubMsgErrorCode::Unknown
+        );
+        assert_eq!(
+            code(r#"{"codespace":"bank","code":11}"#),
+            SubMsgErrorCode::Unknown
+        );
+
+        assert_eq!(
+            SubMsgError::from_node_error(r#"{"contract_error":"insufficient balance"}"#),
+            SubMsgError::custom("insufficient balance".to_string())
+        );
+
+        // the strings of older nodes
+        assert_eq!(
+            code("the error was redacted (codespace: sdk, code: 11)"),
+            SubMsgErrorCode::Unknown
+        );
+    }
+
+    pub fn test_submsg_error_encoding() {
+        assert_eq!(
+            SubMsgError::of_code(SubMsgErrorCode::OutOfGas).encode(),
+            r#"{"code":"out_of_gas"}"#
+        );
+        assert_eq!(
+            SubMsgError::of_code(SubMsgErrorCode::NotFound).encode(),
+            r#"{"code":"not_found"}"#
+        );
+        assert_eq!(
+            SubMsgError::custom("bad \"input\"".to_string()).encode(),
+            r#"{"code":"custom","msg":"bad \"input\""}"#
+        );
+
+        // a contract can't make its error look like another code
+        let forged =
+            SubMsgError::from_node_error(r#"{"contract_error":"{\"code\":\"out_of_gas\"}"}"#);
+        assert_eq!(forged.code, SubMsgErrorCode::Custom);
+    }
+}
@@ -0,0 +1,566 @@
+//! Private token metadata that the enclave only releases to authorized queriers, so NFT contracts
+//! don't each have to get SNIP-721 style access checks right in wasm.
+//!
+//! Every token has its own key, derived from the genesis state key, the og contract key and the
+//! token id. Contracts never see it: they seal metadata with `token_metadata_seal` when a token
+//! is minted or updated, keep the ciphertext in their state, and open it with
+//! `token_metadata_open`, which only decrypts it after checking the querier's authorization
+//! against the token's ACL:
+//!
+//! ```json
+//! {"contract_address":"secret1...","chain_id":"secret-4",
+//!  "viewers":[{"address":"secret1...","viewing_key_hash":"<base64>","revoked_permits":["..."]}]}
+//! ```
+//!
+//! The authorization is passed on from the querier, and is either a SNIP-24 query permit signed
+//! by a viewer, for this contract and chain, with the `owner` permission, or a viewing key whose
+//! sha256 is the `viewing_key_hash` of the viewer:
+//!
+//! ```json
+//! {"permit":{"params":{...},"signature":{...}}}
+//! {"viewing_key":{"address":"secret1...","key":"..."}}
+//! ```
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use bech32::ToBase32;
+use cosmos_proto::tx::signing::SignMode;
+use cw_types_v010::consts::BECH32_PREFIX_ACC_ADDR;
+use cw_types_v010::encoding::Binary;
+use enclave_cosmos_types::traits::CosmosAminoPubkey;
+use enclave_crypto::secp256k1::Secp256k1PubKey;
+use enclave_crypto::traits::VerifyingKey;
+use enclave_crypto::{hkdf_sha_256, sha_256, AESKey, SIVEncryptable};
+
+use crate::contract_validation::ContractKey;
+
+pub const MAX_TOKEN_ID_LENGTH: usize = 256;
+pub const MAX_TOKEN_METADATA_LENGTH: usize = 64 * 1024;
+
+const TOKEN_METADATA_KEY_DOMAIN: &[u8] = b"token_metadata";
+const SIV_TAG_LENGTH: usize = 16;
+const PERMIT_PUB_KEY_TYPE: &str = "tendermint/PubKeySecp256k1";
+const PERMIT_PERMISSION: &str = "owner";
+
+/// Error codes returned to contracts by `token_metadata_seal` and `token_metadata_open`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum TokenMetadataError {
+    /// The token id or the metadata is over its limit, or the ACL is malformed
+    InvalidInput = 1,
+    /// The authorization is malformed, or doesn't authorize any of the token's viewers
+    Unauthorized = 2,
+    /// The ciphertext wasn't sealed for the token, or was tampered with
+    DecryptionFailed = 3,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct TokenAcl {
+    /// `env.contract.address`, which permits have to allow
+    contract_address: String,
+    /// `env.block.chain_id`, which permits have to be signed for
+    chain_id: String,
+    viewers: Vec<Viewer>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Viewer {
+    address: String,
+    /// The sha256 of the viewer's viewing key, if they have one
+    #[serde(default)]
+    viewing_key_hash: Option<Binary>,
+    /// The names of the permits the viewer revoked
+    #[serde(default)]
+    revoked_permits: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Authorization {
+    Permit(Permit),
+    ViewingKey { address: String, key: String },
+}
+
+#[derive(Deserialize, Debug)]
+struct Permit {
+    params: PermitParams,
+    signature: PermitSignature,
+}
+
+#[derive(Deserialize, Debug)]
+struct PermitParams {
+    permit_name: String,
+    allowed_tokens: Vec<String>,
+    chain_id: String,
+    permissions: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PermitSignature {
+    pub_key: PermitPubKey,
+    signature: Binary,
+}
+
+#[derive(Deserialize, Debug)]
+struct PermitPubKey {
+    #[serde(rename = "type")]
+    key_type: String,
+    value: Binary,
+}
+
+/// The amino JSON sign doc of a SNIP-24 permit. Fields are declared in the sorted order amino
+/// JSON requires.
+#[derive(Serialize)]
+struct PermitSignDoc<'a> {
+    account_number: &'static str,
+    chain_id: &'a str,
+    fee: PermitFee,
+    memo: &'static str,
+    msgs: [PermitMsg<'a>; 1],
+    sequence: &'static str,
+}
+
+#[derive(Serialize)]
+struct PermitFee {
+    amount: [PermitCoin; 1],
+    gas: &'static str,
+}
+
+#[derive(Serialize)]
+struct PermitCoin {
+    amount: &'static str,
+    denom: &'static str,
+}
+
+#[derive(Serialize)]
+struct PermitMsg<'a> {
+    #[serde(rename = "type")]
+    msg_type: &'static str,
+    value: PermitMsgValue<'a>,
+}
+
+#[derive(Serialize)]
+struct PermitMsgValue<'a> {
+    allowed_tokens: &'a [String],
+    permissions: &'a [String],
+    permit_name: &'a str,
+}
+
+impl PermitParams {
+    fn sign_bytes(&self) -> Vec<u8> {
+        let sign_doc = PermitSignDoc {
+            account_number: "0",
+            chain_id: &self.chain_id,
+            fee: PermitFee {
+                amount: [PermitCoin {
+                    amount: "0",
+                    denom: "uscrt",
+                }],
+                gas: "1",
+            },
+            memo: "",
+            msgs: [PermitMsg {
+                msg_type: "query_permit",
+                value: PermitMsgValue {
+                    allowed_tokens: &self.allowed_tokens,
+                    permissions: &self.permissions,
+                    permit_name: &self.permit_name,
+                },
+            }],
+            sequence: "0",
+        };
+
+        // serializing a struct of strings can't fail
+        serde_json::to_vec(&sign_doc).unwrap_or_default()
+    }
+}
+
+impl Permit {
+    /// The address of the permit's signer, if the permit is valid for `acl`
+    fn signer(&self, acl: &TokenAcl) -> Result<String, TokenMetadataError> {
+        if self.params.chain_id != acl.chain_id
+            || !self.params.allowed_tokens.contains(&acl.contract_address)
+            || !self
+                .params
+                .permissions
+                .iter()
+                .any(|permission| permission == PERMIT_PERMISSION)
+        {
+            debug!("permit {:?} isn't valid here", self.params.permit_name);
+            return Err(TokenMetadataError::Unauthorized);
+        }
+
+        if self.signature.pub_key.key_type != PERMIT_PUB_KEY_TYPE
+            || self.signature.pub_key.value.len() != 33
+        {
+            debug!("permit isn't signed with a compressed secp256k1 key");
+            return Err(TokenMetadataError::Unauthorized);
+        }
+
+        let public_key = Secp256k1PubKey::new(self.signature.pub_key.value.0.clone());
+        public_key
+            .verify_bytes(
+                &self.params.sign_bytes(),
+                self.signature.signature.as_slice(),
+                SignMode::SIGN_MODE_DIRECT,
+            )
+            .map_err(|err| {
+                debug!("permit signature verification failed: {:?}", err);
+                TokenMetadataError::Unauthorized
+            })?;
+
+        bech32::encode(
+            BECH32_PREFIX_ACC_ADDR,
+            public_key.get_address().as_slice().to_base32(),
+        )
+        .map_err(|_| TokenMetadataError::Unauthorized)
+    }
+}
+
+/// Compares two byte strings in time that only depends on their lengths
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Checks that `authorization` authorizes one of the viewers of `acl`
+fn authorize(acl: &TokenAcl, authorization: &[u8]) -> Result<(), TokenMetadataError> {
+    let authorization: Authorization = serde_json::from_slice(authorization).map_err(|err| {
+        debug!("token metadata authorization is malformed: {}", err);
+        TokenMetadataError::Unauthorized
+    })?;
+
+    let viewer_of = |address: &str| {
+        acl.viewers
+            .iter()
+            .find(|viewer| viewer.address == address)
+            .ok_or_else(|| {
+                debug!("{} isn't a viewer of the token", address);
+                TokenMetadataError::Unauthorized
+            })
+    };
+
+    match authorization {
+        Authorization::Permit(permit) => {
+            let viewer = viewer_of(&permit.signer(acl)?)?;
+            if viewer.revoked_permits.contains(&permit.params.permit_name) {
+                debug!("permit {:?} was revoked", permit.params.permit_name);
+                return Err(TokenMetadataError::Unauthorized);
+            }
+        }
+        Authorization::ViewingKey { address, key } => {
+            let viewer = viewer_of(&address)?;
+            let matches = match &viewer.viewing_key_hash {
+                Some(hash) => constant_time_eq(&sha_256(key.as_bytes()), hash.as_slice()),
+                None => false,
+            };
+            if !matches {
+                debug!("wrong viewing key for {}", address);
+                return Err(TokenMetadataError::Unauthorized);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn token_key(
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    token_id: &[u8],
+) -> Result<AESKey, TokenMetadataError> {
+    if token_id.len() > MAX_TOKEN_ID_LENGTH {
+        return Err(TokenMetadataError::InvalidInput);
+    }
+
+    let info: Vec<&[u8]> = vec![
+        TOKEN_METADATA_KEY_DOMAIN,
+        og_contract_key.as_slice(),
+        token_id,
+    ];
+
+    Ok(hkdf_sha_256(genesis_state_key.get(), info.as_slice()))
+}
+
+/// Encrypt the metadata of a token with the token's key. AES-SIV is deterministic, so every node
+/// writes the same ciphertext to the contract's state.
+pub fn seal_token_metadata(
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    token_id: &[u8],
+    metadata: &[u8],
+) -> Result<Vec<u8>, TokenMetadataError> {
+    let key = token_key(genesis_state_key, og_contract_key, token_id)?;
+    if metadata.len() > MAX_TOKEN_METADATA_LENGTH {
+        return Err(TokenMetadataError::InvalidInput);
+    }
+
+    key.encrypt_siv(metadata, Some(&[token_id])).map_err(|err| {
+        error!("failed to seal token metadata: {:?}", err);
+        TokenMetadataError::InvalidInput
+    })
+}
+
+/// Decrypt the metadata of a token, if `authorization` authorizes one of the viewers in `acl`
+pub fn open_token_metadata(
+    genesis_state_key: &AESKey,
+    og_contract_key: &ContractKey,
+    token_id: &[u8],
+    ciphertext: &[u8],
+    acl: &[u8],
+    authorization: &[u8],
+) -> Result<Vec<u8>, TokenMetadataError> {
+    let key = token_key(genesis_state_key, og_contract_key, token_id)?;
+    if ciphertext.len() > MAX_TOKEN_METADATA_LENGTH + SIV_TAG_LENGTH {
+        return Err(TokenMetadataError::InvalidInput);
+    }
+
+    let acl: TokenAcl = serde_json::from_slice(acl).map_err(|err| {
+        debug!("token metadata acl is malformed: {}", err);
+        TokenMetadataError::InvalidInput
+    })?;
+    authorize(&acl, authorization)?;
+
+    key.decrypt_siv(ciphertext, Some(&[token_id]))
+        .map_err(|err| {
+            debug!("failed to open token metadata: {:?}", err);
+            TokenMetadataError::DecryptionFailed
+        })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    const CONTRACT: &str = "secret1contract";
+    const CHAIN_ID: &str = "secret-4";
+
+    fn permit_json(secret_key: &[u8; 32], name: &str, contract: &str, chain_id: &str) -> String {
+        let params = PermitParams {
+            permit_name: name.to_string(),
+            allowed_tokens: vec![contract.to_string()],
+            chain_id: chain_id.to_string(),
+            permissions: vec!["owner".to_string()],
+        };
+
+        let secp = secp256k1::Secp256k1::signing_only();
+        let sk = secp256k1::SecretKey::from_slice(secret_key).unwrap();
+        let msg = secp256k1::Message::from_slice(&sha_256(&params.sign_bytes())).unwrap();
+        let signature = secp.sign_ecdsa(&msg, &sk).serialize_compact();
+        let public_key = secp256k1::PublicKey::from_secret_key(&secp, &sk).serialize();
+
+        serde_json::json!({
+            "permit": {
+                "params": {
+                    "permit_name": name,
+                    "allowed_tokens": [contract],
+                    "chain_id": chain_id,
+                    "permissions": ["owner"],
+                },
+                "signature": {
+                    "pub_key": {
+                        "type": PERMIT_PUB_KEY_TYPE,
+                        "value": base64::encode(&public_key[..]),
+                    },
+                    "signature": base64::encode(&signature[..]),
+                },
+            }
+        })
+        .to_string()
+    }
+
+    fn address_of(secret_key: &[u8; 32]) -> String {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let sk = secp256k1::SecretKey::from_slice(secret_key).unwrap();
+        let public_key = secp256k1::PublicKey::from_secret_key(&secp, &sk).serialize();
+        let address = Secp256k1PubKey::new(public_key.to_vec()).get_address();
+
+        bech32::encode(BECH32_PREFIX_ACC_ADDR, address.as_slice().to_base32()).unwrap()
+    }
+
+    fn acl(viewers: serde_json::Value) -> Vec<u8> {
+        serde_json::json!({
+            "contract_address": CONTRACT,
+            "chain_id": CHAIN_ID,
+            "viewers": viewers,
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    pub fn test_token_metadata_permit_sign_bytes() {
+        let params = PermitParams {
+            permit_name: "name".to_string(),
+            allowed_tokens: vec![CONTRACT.to_string()],
+            chain_id: CHAIN_ID.to_string(),
+            permissions: vec!["owner".to_string()],
+        };
+
+        assert_eq!(
+            String::from_utf8(params.sign_bytes()).unwrap(),
+            r#"{"account_number":"0","chain_id":"secret-4","fee":{"amount":[{"amount":"0","denom":"uscrt"}],"gas":"1"},"memo":"","msgs":[{"type":"query_permit","value":{"allowed_tokens":["secret1contract"],"permissions":["owner"],"permit_name":"name"}}],"sequence":"0"}"#
+        );
+    }
+
+    pub fn test_token_metadata_viewing_key() {
+        let state_key = AESKey::new_from_slice(&sha_256(b"state key"));
+        let contract = [1u8; 64];
+
+        let sealed = seal_token_metadata(&state_key, &contract, b"token 1", b"private").unwrap();
+        let viewers = acl(serde_json::json!([
+            {"address": "secret1owner", "viewing_key_hash": base64::encode(sha_256(b"key"))},
+            {"address": "secret1nokey"},
+        ]));
+        let authorization = |address: &str, key: &str| -> Vec<u8> {
+            serde_json::json!({"viewing_key": {"address": address, "key": key}})
+                .to_string()
+                .into_bytes()
+        };
+
+        let open = |token_id: &[u8], sealed: &[u8], authorization: &[u8]| {
+            open_token_metadata(
+                &state_key,
+                &contract,
+                token_id,
+                sealed,
+                &viewers,
+                authorization,
+            )
+        };
+
+        assert_eq!(
+            open(b"token 1", &sealed, &authorization("secret1owner", "key")).unwrap(),
+            b"private"
+        );
+        assert_eq!(
+            open(b"token 1", &sealed, &authorization("secret1owner", "other")),
+            Err(TokenMetadataError::Unauthorized)
+        );
+        assert_eq!(
+            open(b"token 1", &sealed, &authorization("secret1nokey", "")),
+            Err(TokenMetadataError::Unauthorized)
+        );
+        assert_eq!(
+            open(b"token 1", &sealed, &authorization("secret1other", "key")),
+            Err(TokenMetadataError::Unauthorized)
+        );
+        assert_eq!(
+            open(b"token 1", &sealed, b"{}"),
+            Err(TokenMetadataError::Unauthorized)
+        );
+
+        // the ciphertext is bound to its token and contract
+        assert_eq!(
+            open(b"token 2", &sealed, &authorization("secret1owner", "key")),
+            Err(TokenMetadataError::DecryptionFailed)
+        );
+        let other_contract = seal_token_metadata(&state_key, &[2u8; 64], b"token 1", b"private");
+        assert_eq!(
+            open(
+                b"token 1",
+                &other_contract.unwrap(),
+                &authorization("secret1owner", "key")
+            ),
+            Err(TokenMetadataError::DecryptionFailed)
+        );
+
+        assert_eq!(
+            open_token_metadata(
+                &state_key,
+                &contract,
+                b"token 1",
+                &sealed,
+                b"[]",
+                &authorization("secret1owner", "key"),
+            ),
+            Err(TokenMetadataError::InvalidInput)
+        );
+        assert_eq!(
+            seal_token_metadata(
+                &state_key,
+                &contract,
+                &[0u8; MAX_TOKEN_ID_LENGTH + 1],
+                b"private"
+            ),
+            Err(TokenMetadataError::InvalidInput)
+        );
+        assert_eq!(
+            seal_token_metadata(
+                &state_key,
+                &contract,
+                b"token 1",
+                &[0u8; MAX_TOKEN_METADATA_LENGTH + 1]
+            ),
+            Err(TokenMetadataError::InvalidInput)
+        );
+    }
+
+    pub fn test_token_metadata_permit() {
+        let state_key = AESKey::new_from_slice(&sha_256(b"state key"));
+        let contract = [1u8; 64];
+        let owner_key = sha_256(b"owner");
+        let owner = address_of(&owner_key);
+
+        let sealed = seal_token_metadata(&state_key, &contract, b"token 1", b"private").unwrap();
+        let open = |acl: &[u8], authorization: &str| {
+            open_token_metadata(
+                &state_key,
+                &contract,
+                b"token 1",
+                &sealed,
+                acl,
+                authorization.as_bytes(),
+            )
+        };
+        let viewers = acl(serde_json::json!([{ "address": owner }]));
+
+        assert_eq!(
+            open(&viewers, &permit_json(&owner_key, "p", CONTRACT, CHAIN_ID)).unwrap(),
+            b"private"
+        );
+
+        // signed by someone who isn't a viewer
+        let stranger = permit_json(&sha_256(b"stranger"), "p", CONTRACT, CHAIN_ID);
+        assert_eq!(
+            open(&viewers, &stranger),
+            Err(TokenMetadataError::Unauthorized)
+        );
+
+        // for another contract or chain
+        let other_contract = permit_json(&owner_key, "p", "secret1other", CHAIN_ID);
+        assert_eq!(
+            open(&viewers, &other_contract),
+            Err(TokenMetadataError::Unauthorized)
+        );
+        let other_chain = permit_json(&owner_key, "p", CONTRACT, "pulsar-3");
+        assert_eq!(
+            open(&viewers, &other_chain),
+            Err(TokenMetadataError::Unauthorized)
+        );
+
+        // with params that weren't signed
+        let tampered = permit_json(&owner_key, "p", CONTRACT, CHAIN_ID)
+            .replace(r#""permit_name":"p""#, r#""permit_name":"q""#);
+        assert_eq!(
+            open(&viewers, &tampered),
+            Err(TokenMetadataError::Unauthorized)
+        );
+
+        // revoked
+        let revoked = acl(serde_json::json!([{"address": owner, "revoked_permits": ["p"]}]));
+        assert_eq!(
+            open(&revoked, &permit_json(&owner_key, "p", CONTRACT, CHAIN_ID)),
+            Err(TokenMetadataError::Unauthorized)
+        );
+        assert!(open(&revoked, &permit_json(&owner_key, "p2", CONTRACT, CHAIN_ID)).is_ok());
+    }
+
+    pub fn test_token_metadata_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+        assert!(constant_time_eq(b"", b""));
+    }
+}
@@ -1,10 +1,12 @@
 use log::*;
 use serde::{Deserialize, Serialize};
 
-use enclave_crypto::{AESKey, Ed25519PublicKey, SIVEncryptable};
+use enclave_crypto::{AESKey, Ed25519PublicKey};
 use enclave_ffi_types::EnclaveError;
+use enclave_wire::Reader;
 
 use super::io::calc_encryption_key;
+use super::padding::{decrypt_message, encrypt_message};
 
 pub type IoNonce = [u8; 32];
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -32,28 +34,36 @@ pub struct DecryptedSecretMessage {
 
 impl SecretMessage {
     pub fn encrypt_in_place(&mut self) -> Result<(), EnclaveError> {
-        self.msg = self
-            .encryption_key()
-            .encrypt_siv(self.msg.as_slice(), None)
-            .map_err(|err| {
-                error!("got an error while trying to encrypt the msg: {:?}", err);
-                EnclaveError::EncryptionError
-            })?;
+        self.msg = encrypt_message(&self.encryption_key(), &self.msg, true).map_err(|err| {
+            error!("got an error while trying to encrypt the msg: {:?}", err);
+            EnclaveError::EncryptionError
+        })?;
 
         Ok(())
     }
 
+    /// Decrypt the message, stripping its padding if the sender marked it as padded
     pub fn try_decrypt(&self) -> Option<Vec<u8>> {
+        self.try_decrypt_padded().map(|(msg, _)| msg)
+    }
+
+    /// Whether the sender padded the message, in which case we pad the response as well
+    pub fn is_padded(&self) -> bool {
+        self.try_decrypt_padded()
+            .map_or(false, |(_, is_padded)| is_padded)
+    }
+
+    fn try_decrypt_padded(&self) -> Option<(Vec<u8>, bool)> {
         trace!("input before decryption: {:?}", base64::encode(&self.msg));
         let key = self.encryption_key();
 
-        if let Ok(msg) = key.decrypt_siv(self.msg.as_slice(), None) {
+        if let Some((msg, is_padded)) = decrypt_message(&key, self.msg.as_slice()) {
             trace!(
                 "input after decryption: {:?}",
                 String::from_utf8_lossy(&msg)
             );
 
-            return Some(msg.to_vec());
+            return Some((msg, is_padded));
         }
 
         None
@@ -106,11 +116,11 @@ impl SecretMessage {
             return Err(EnclaveError::DecryptionError);
         };
 
-        let mut nonce = [0u8; 32];
-        nonce.copy_from_slice(&msg[0..32]);
-
-        let mut user_pubkey = [0u8; 32];
-        user_pubkey.copy_from_slice(&msg[32..64]);
+        let mut reader = Reader::new(msg);
+        let (nonce, user_pubkey) = match (reader.array(), reader.array()) {
+            (Ok(nonce), Ok(user_pubkey)) => (nonce, user_pubkey),
+            _ => return Err(EnclaveError::DecryptionError),
+        };
 
         debug!(
             "SecretMessage::from_slice nonce = {:?} pubkey = {:?}",
@@ -121,7 +131,7 @@ impl SecretMessage {
         Ok(SecretMessage {
             nonce,
             user_public_key: user_pubkey,
-            msg: msg[64..].to_vec(),
+            msg: reader.rest().to_vec(),
         })
     }
 
@@ -0,0 +1,535 @@
+//! Limits on the size and complexity of contract code, checked when the code is stored.
+//!
+//! A module that breaks one of these limits couldn't run, or would only fail on the call that
+//! reaches its offending part. `validate_code` checks the whole module up front, so the chain can
+//! reject it at upload instead, with a report of everything that's wrong with it.
+//!
+//! A module can also declare the enclave features it needs, in a custom section named
+//! `secret_requires` that lists their names, separated by commas or whitespace. Contracts built
+//! by several crates can each add their own, since the linker concatenates them:
+//!
+//! ```ignore
+//! #[link_section = "secret_requires"]
+//! static REQUIRES: [u8; 7] = *b"random\n";
+//! ```
+//!
+//! A module that requires a feature this enclave doesn't have, or one the chain withholds with
+//! `WITHHELD_CONTRACT_FEATURES`, is rejected, instead of failing on its first call to it. Until the
+//! params are proven, every feature is withheld.
+
+use log::*;
+use serde::Serialize;
+
+use enclave_cosmos_types::types::ContractCode;
+use enclave_ffi_types::{EnclaveError, MAX_WASM_LENGTH};
+use enclave_utils::verified_params::WITHHELD_CONTRACT_FEATURES;
+
+use crate::cosmwasm_config::ContractOperation;
+use crate::enclave_features::{has_feature, known_features};
+use crate::gas::WasmCosts;
+
+use super::module_cache::analyze_module;
+
+/// The largest module the enclave accepts, in bytes
+pub const MAX_MODULE_SIZE: usize = MAX_WASM_LENGTH;
+/// The most functions a module can define, not counting its imports
+pub const MAX_FUNCTIONS: usize = 20_000;
+/// Every call reserves a slot of the engine's stack for each local of the function, so a function
+/// with more locals than this would overflow it on its first call
+pub const MAX_FUNCTION_LOCALS: u64 = 10_000;
+
+/// The functions `Engine::link_host_functions` links. Keep in sync with it.
+const HOST_FUNCTIONS: &[&str] = &[
+    "db_read",
+    "db_write",
+    "db_remove",
+    "db_oblivious_namespace",
+    "db_public_namespace",
+    "db_declassify",
+    "db_integrity_namespace",
+    "canonicalize_address",
+    "humanize_address",
+    "query_chain",
+    "addr_canonicalize",
+    "addr_humanize",
+    "addr_validate",
+    "debug_print",
+    "debug",
+    "secp256k1_verify",
+    "secp256k1_recover_pubkey",
+    "ed25519_verify",
+    "ed25519_batch_verify",
+    "secp256k1_sign",
+    "ed25519_sign",
+    "rsa_pkcs1v15_verify",
+    "rsa_pss_verify",
+    "musig2_key_agg",
+    "secp256k1_schnorr_verify",
+    "dcap_quote_verify",
+    "drand_verify",
+    "drand_randomness",
+    "oracle_attestation_verify",
+    "decrypt_disclosed_attribute",
+    "job_seal_input",
+    "derive_contract_keypair",
+    "contract_decrypt",
+    "token_metadata_seal",
+    "token_metadata_open",
+    "foreign_app_hash_verify",
+    "block_random_at",
+    "check_gas",
+    "gas_evaporate",
+    "gas_uniform",
+    "canonicalize_json",
+    "fixed_point_mul",
+    "fixed_point_div",
+    "fixed_point_ln",
+    "fixed_point_exp",
+    "fixed_point_pow",
+    "timestamp_add_months",
+    "timestamp_truncate",
+    "timestamp_weekday",
+    "uint512_op",
+    "int512_op",
+    "modexp",
+    "x509_verify_chain",
+    "jwt_verify",
+    "poseidon_hash",
+    "merkle_create",
+    "merkle_append",
+    "merkle_root",
+    "merkle_prove",
+    "merkle_verify",
+    "state_commitment_root",
+    "state_commitment_prove",
+    "state_commitment_verify",
+    "hkdf_sha256",
+    "hmac_sha256",
+    "aead_seal",
+    "aead_open",
+    "compress",
+    "decompress",
+    "verify_sgx_quote",
+    "query_enclave_features",
+    "ibc_client_status",
+];
+
+/// Functions of the CosmWasm APIs that the engine doesn't link. The standard library of contracts
+/// imports them, and a contract only fails if it calls one, so they're allowed.
+const UNLINKED_FUNCTIONS: &[&str] = &["db_scan", "db_next", "read_db", "write_db", "abort"];
+
+const CUSTOM_SECTION_ID: u8 = 0;
+const CODE_SECTION_ID: u8 = 10;
+/// The custom section modules declare the enclave features they need in
+pub const REQUIRES_SECTION: &str = "secret_requires";
+
+/// What `validate_code` found in a module. The code is valid if there are no violations.
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct CodeValidationReport {
+    pub valid: bool,
+    pub module_size: usize,
+    pub functions: usize,
+    /// The most locals any function of the module declares
+    pub max_locals: u64,
+    /// Every import of the module, as `module.name`
+    pub imports: Vec<String>,
+    /// The enclave features the module declares it needs, sorted
+    pub required_features: Vec<String>,
+    pub violations: Vec<String>,
+}
+
+fn read_leb128_u32(bytes: &mut &[u8]) -> Option<u32> {
+    let mut result = 0u32;
+    for shift in (0..35).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        result |= u32::from(byte & 0x7f).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(result);
+        }
+    }
+    None
+}
+
+fn split_off<'a>(bytes: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if bytes.len() < len {
+        return None;
+    }
+    let (head, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Some(head)
+}
+
+/// The id and content of each section of the module, or None if the module isn't well-formed
+fn sections(code: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    let mut bytes = code;
+    // magic and version
+    if split_off(&mut bytes, 8)? != b"\0asm\x01\0\0\0" {
+        return None;
+    }
+
+    let mut sections = vec![];
+    while !bytes.is_empty() {
+        let id = split_off(&mut bytes, 1)?[0];
+        let size = read_leb128_u32(&mut bytes)? as usize;
+        sections.push((id, split_off(&mut bytes, size)?));
+    }
+
+    Some(sections)
+}
+
+/// The number of locals each function of the module declares, not counting its params, or None
+/// if the module isn't well-formed
+fn function_locals(code: &[u8]) -> Option<Vec<u64>> {
+    let mut locals = vec![];
+    for (_, mut section) in sections(code)?
+        .into_iter()
+        .filter(|(id, _)| *id == CODE_SECTION_ID)
+    {
+        let count = read_leb128_u32(&mut section)?;
+        for _ in 0..count {
+            let body_size = read_leb128_u32(&mut section)? as usize;
+            let mut body = split_off(&mut section, body_size)?;
+
+            let mut declared = 0u64;
+            for _ in 0..read_leb128_u32(&mut body)? {
+                declared += u64::from(read_leb128_u32(&mut body)?);
+                // the type of the locals
+                split_off(&mut body, 1)?;
+            }
+            locals.push(declared);
+        }
+    }
+
+    Some(locals)
+}
+
+/// The contents of the module's `secret_requires` sections, in order
+fn requires_sections(code: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut contents = vec![];
+    for (_, mut section) in sections(code)?
+        .into_iter()
+        .filter(|(id, _)| *id == CUSTOM_SECTION_ID)
+    {
+        let name_len = read_leb128_u32(&mut section)? as usize;
+        if split_off(&mut section, name_len)? == REQUIRES_SECTION.as_bytes() {
+            contents.push(section);
+        }
+    }
+
+    Some(contents)
+}
+
+/// The names listed in `list`, separated by commas or whitespace, sorted and without duplicates
+fn feature_names(list: &str) -> Vec<String> {
+    let mut names: Vec<String> = list
+        .split(|c: char| c == ',' || c.is_ascii_whitespace())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+/// Check the features the module requires against this enclave, and against the features
+/// `withheld` by the chain
+fn check_required_features(code: &[u8], withheld: &[String], report: &mut CodeValidationReport) {
+    let mut declared = String::new();
+    for content in requires_sections(code).unwrap_or_default() {
+        match std::str::from_utf8(content) {
+            Ok(list) => {
+                declared.push_str(list);
+                declared.push('\n');
+            }
+            Err(_) => report.violations.push(format!(
+                "the {} section isn't a list of feature names",
+                REQUIRES_SECTION
+            )),
+        }
+    }
+
+    report.required_features = feature_names(&declared);
+    for feature in &report.required_features {
+        let violation = match has_feature(feature) {
+            None => "which this enclave doesn't know",
+            Some(false) => "which this enclave wasn't built with",
+            Some(true) if withheld.contains(feature) => "which the chain withholds",
+            Some(true) => continue,
+        };
+        report.violations.push(format!(
+            "the module requires the feature {}, {}",
+            feature, violation
+        ));
+    }
+}
+
+/// Check `code` against the limits of the enclave, and against everything the engine checks when
+/// it instantiates a module
+pub fn validate_code(code: &[u8]) -> CodeValidationReport {
+    // Until the params are proven, uploads may not require any feature
+    let withheld = feature_names(
+        &WITHHELD_CONTRACT_FEATURES.get_or(String::new(), known_features().join(",")),
+    );
+    validate_code_with_withheld(code, &withheld)
+}
+
+fn validate_code_with_withheld(code: &[u8], withheld: &[String]) -> CodeValidationReport {
+    let mut report = CodeValidationReport {
+        module_size: code.len(),
+        ..Default::default()
+    };
+
+    // Larger modules aren't parsed at all
+    if code.len() > MAX_MODULE_SIZE {
+        report.violations.push(format!(
+            "the module is {} bytes, more than {}",
+            code.len(),
+            MAX_MODULE_SIZE
+        ));
+        return report;
+    }
+
+    let locals = match function_locals(code) {
+        Some(locals) => locals,
+        None => {
+            report
+                .violations
+                .push("the module isn't valid wasm".to_string());
+            return report;
+        }
+    };
+
+    report.functions = locals.len();
+    if locals.len() > MAX_FUNCTIONS {
+        report.violations.push(format!(
+            "the module defines {} functions, more than {}",
+            locals.len(),
+            MAX_FUNCTIONS
+        ));
+    }
+
+    report.max_locals = locals.iter().copied().max().unwrap_or(0);
+    for (index, &function_locals) in locals.iter().enumerate() {
+        if function_locals > MAX_FUNCTION_LOCALS {
+            report.violations.push(format!(
+                "function {} declares {} locals, more than {}",
+                index, function_locals, MAX_FUNCTION_LOCALS
+            ));
+        }
+    }
+
+    check_required_features(code, withheld, &mut report);
+
+    let module = match walrus::ModuleConfig::new().parse(code) {
+        Ok(module) => module,
+        Err(err) => {
+            debug!("failed to parse a module being validated: {}", err);
+            report
+                .violations
+                .push("the module isn't valid wasm".to_string());
+            return report;
+        }
+    };
+
+    for import in module.imports.iter() {
+        let name = format!("{}.{}", import.module, import.name);
+        let is_function = matches!(import.kind, walrus::ImportKind::Function(_));
+        let is_known =
+            HOST_FUNCTIONS.contains(&&*import.name) || UNLINKED_FUNCTIONS.contains(&&*import.name);
+        if import.module != "env" || !is_function || !is_known {
+            report
+                .violations
+                .push(format!("the import {} isn't a function of the host", name));
+        }
+        report.imports.push(name);
+    }
+
+    // Floating point operations are only rejected on init, so code that has them can still be
+    // migrated to
+    if let Err(err) = analyze_module(
+        &ContractCode::new(code),
+        &WasmCosts::default(),
+        ContractOperation::Handle,
+    ) {
+        report
+            .violations
+            .push(format!("the engine can't instantiate the module: {}", err));
+    }
+
+    report.valid = report.violations.is_empty();
+    report
+}
+
+/// Validate `code`, and encode the report as json
+pub fn encoded_validation_report(code: &[u8]) -> Result<Vec<u8>, EnclaveError> {
+    serde_json::to_vec(&validate_code(code)).map_err(|err| {
+        error!("Failed to encode a code validation report: {}", err);
+        EnclaveError::FailedToSerialize
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn leb128(mut value: u32) -> Vec<u8> {
+        let mut encoded = vec![];
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                encoded.push(byte);
+                return encoded;
+            }
+            encoded.push(byte | 0x80);
+        }
+    }
+
+    fn name(name: &str) -> Vec<u8> {
+        let mut encoded = leb128(name.len() as u32);
+        encoded.extend_from_slice(name.as_bytes());
+        encoded
+    }
+
+    fn section(id: u8, content: Vec<u8>) -> Vec<u8> {
+        let mut encoded = vec![id];
+        encoded.extend(leb128(content.len() as u32));
+        encoded.extend(content);
+        encoded
+    }
+
+    /// A v1 contract that imports `env.<import>`, and defines one function with `locals` locals
+    fn module(import: &str, locals: u32) -> Vec<u8> {
+        let mut code = b"\0asm\x01\0\0\0".to_vec();
+        // type 0: () -> ()
+        code.extend(section(1, vec![1, 0x60, 0, 0]));
+
+        let mut imports = vec![1];
+        imports.extend(name("env"));
+        imports.extend(name(import));
+        imports.extend(vec![0x00, 0]);
+        code.extend(section(2, imports));
+
+        code.extend(section(3, vec![1, 0]));
+        // one memory of one page
+        code.extend(section(5, vec![1, 0x00, 1]));
+
+        let mut exports = vec![2];
+        exports.extend(name("interface_version_8"));
+        exports.extend(vec![0x00, 1]);
+        exports.extend(name("memory"));
+        exports.extend(vec![0x02, 0]);
+        code.extend(section(7, exports));
+
+        let mut body = vec![1];
+        body.extend(leb128(locals));
+        body.extend(vec![0x7f, 0x0b]);
+        let mut bodies = vec![1];
+        bodies.extend(leb128(body.len() as u32));
+        bodies.extend(body);
+        code.extend(section(CODE_SECTION_ID, bodies));
+
+        code
+    }
+
+    /// `code` with a custom section appended
+    fn with_custom_section(mut code: Vec<u8>, section_name: &str, content: &[u8]) -> Vec<u8> {
+        let mut custom = name(section_name);
+        custom.extend_from_slice(content);
+        code.extend(section(CUSTOM_SECTION_ID, custom));
+        code
+    }
+
+    pub fn test_code_limits_valid() {
+        let report = validate_code(&module("db_read", 3));
+        assert_eq!(report.violations, Vec::<String>::new());
+        assert!(report.valid);
+        assert_eq!(report.functions, 1);
+        assert_eq!(report.max_locals, 3);
+        assert_eq!(report.imports, vec!["env.db_read".to_string()]);
+        assert_eq!(report.required_features, Vec::<String>::new());
+    }
+
+    pub fn test_code_limits_required_features() {
+        let code = with_custom_section(
+            module("db_read", 3),
+            REQUIRES_SECTION,
+            b"storage_iterators,",
+        );
+        let code = with_custom_section(code, REQUIRES_SECTION, b"aead\nstorage_iterators\n");
+        // other custom sections aren't read
+        let code = with_custom_section(code, "name", b"teleportation");
+        let report = validate_code_with_withheld(&code, &[]);
+        assert_eq!(report.violations, Vec::<String>::new());
+        assert_eq!(
+            report.required_features,
+            vec!["aead".to_string(), "storage_iterators".to_string()]
+        );
+
+        let report = validate_code_with_withheld(&code, &["aead".to_string()]);
+        assert_eq!(
+            report.violations,
+            vec!["the module requires the feature aead, which the chain withholds".to_string()]
+        );
+
+        let code = with_custom_section(module("db_read", 3), REQUIRES_SECTION, b"teleportation");
+        let report = validate_code_with_withheld(&code, &[]);
+        assert!(!report.valid);
+        assert_eq!(
+            report.violations,
+            vec![
+                "the module requires the feature teleportation, which this enclave doesn't know"
+                    .to_string()
+            ]
+        );
+
+        let code = with_custom_section(module("db_read", 3), REQUIRES_SECTION, &[0xff]);
+        let report = validate_code_with_withheld(&code, &[]);
+        assert_eq!(
+            report.violations,
+            vec!["the secret_requires section isn't a list of feature names".to_string()]
+        );
+
+        assert_eq!(
+            feature_names(" random, ,aead random"),
+            vec!["aead".to_string(), "random".to_string()]
+        );
+    }
+
+    pub fn test_code_limits_violations() {
+        let report = validate_code(&module("db_read", MAX_FUNCTION_LOCALS as u32 + 1));
+        assert!(!report.valid);
+        assert_eq!(
+            report.violations,
+            vec![format!(
+                "function 0 declares {} locals, more than {}",
+                MAX_FUNCTION_LOCALS + 1,
+                MAX_FUNCTION_LOCALS
+            )]
+        );
+
+        let report = validate_code(&module("db_delete", 0));
+        assert!(!report.valid);
+        assert_eq!(
+            report.violations,
+            vec!["the import env.db_delete isn't a function of the host".to_string()]
+        );
+    }
+
+    pub fn test_code_limits_malformed() {
+        let report = validate_code(&vec![0u8; MAX_MODULE_SIZE + 1]);
+        assert!(!report.valid);
+        assert_eq!(report.module_size, MAX_MODULE_SIZE + 1);
+        assert_eq!(report.violations.len(), 1);
+
+        let mut code = module("db_read", 3);
+        code.truncate(code.len() - 1);
+        let report = validate_code(&code);
+        assert!(!report.valid);
+        assert_eq!(
+            report.violations,
+            vec!["the module isn't valid wasm".to_string()]
+        );
+    }
+}
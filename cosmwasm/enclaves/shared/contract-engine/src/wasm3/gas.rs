@@ -14,6 +14,35 @@ use enclave_ffi_types::EnclaveError;
 pub const EXPORT_GAS_LIMIT: &str = "gas_limit";
 /// Name of the exported global that holds the gas limit exhausted flag.
 pub const EXPORT_GAS_LIMIT_EXHAUSTED: &str = "gas_limit_exhausted";
+/// Name of the exported global that holds the execution ticks that are left.
+pub const EXPORT_EXECUTION_TICKS: &str = "execution_ticks";
+/// Name of the exported global that holds the execution ticks exhausted flag.
+pub const EXPORT_EXECUTION_TICKS_EXHAUSTED: &str = "execution_ticks_exhausted";
+
+/// The execution ticks of a single execution, unless governance set `max_execution_ticks`. It's
+/// only reached by executions with a gas limit of hundreds of millions, that spend most of it on
+/// memory accesses.
+pub const DEFAULT_MAX_EXECUTION_TICKS: u64 = 2_000_000_000;
+
+/// Ticks of an instruction that loads from or stores to linear memory. It costs the same gas as
+/// any other instruction, but takes far longer when it misses the cache, or pages the enclave's
+/// memory.
+const MEMORY_ACCESS_TICKS: u64 = 8;
+/// Ticks of a call, which sets up a frame of the interpreter
+const CALL_TICKS: u64 = 4;
+/// Ticks of any other instruction
+const INSTRUCTION_TICKS: u64 = 1;
+/// Ticks of growing memory by a page, which zeroes 64 KiB
+const GROW_MEM_TICKS_PER_PAGE: u64 = 16_384;
+
+/// The globals the injected instrumentation counts down
+#[derive(Clone, Copy)]
+struct Meters {
+    gas_limit: GlobalId,
+    gas_limit_exhausted: GlobalId,
+    ticks: GlobalId,
+    ticks_exhausted: GlobalId,
+}
 
 /// Configures the gas limit on the given instance.
 pub fn set_gas_limit<C>(instance: &wasm3::Instance<C>, gas_limit: u64) -> Result<(), EnclaveError> {
@@ -34,6 +63,30 @@ pub fn get_exhausted_amount<C>(instance: &wasm3::Instance<C>) -> u64 {
         .unwrap_or_default()
 }
 
+/// Configures the execution ticks the instance may use before it's aborted.
+pub fn set_execution_ticks<C>(
+    instance: &wasm3::Instance<C>,
+    ticks: u64,
+) -> Result<(), EnclaveError> {
+    instance
+        .set_global(EXPORT_EXECUTION_TICKS, ticks)
+        .map_err(|_err| EnclaveError::FailedGasMeteringInjection)
+}
+
+/// Returns the execution ticks that are left.
+pub fn get_remaining_ticks<C>(instance: &wasm3::Instance<C>) -> u64 {
+    instance
+        .get_global(EXPORT_EXECUTION_TICKS)
+        .unwrap_or_default()
+}
+
+/// Returns the amount of ticks requested that was over the limit.
+pub fn get_exhausted_ticks<C>(instance: &wasm3::Instance<C>) -> u64 {
+    instance
+        .get_global(EXPORT_EXECUTION_TICKS_EXHAUSTED)
+        .unwrap_or_default()
+}
+
 /// Attempts to use the given amount of gas.
 pub fn use_gas<C>(instance: &wasm3::Instance<C>, amount: u64) -> WasmEngineResult<()> {
     debug!("external service used gas: {}", amount);
@@ -51,35 +104,30 @@ pub fn use_gas<C>(instance: &wasm3::Instance<C>, amount: u64) -> WasmEngineResul
 }
 
 /// Inject gas metering instrumentation into the module.
+///
+/// Besides gas, the instrumentation counts down execution ticks, which weigh instructions by how
+/// long they take rather than by what they cost. Gas charges every instruction alike, so a
+/// contract can use its gas on the slowest ones. The ticks of an execution are capped
+/// independently of its gas limit, so they cut it off at the same instruction on every node.
 pub fn add_metering(module: &mut Module, gas_costs: &WasmCosts) {
-    let gas_limit_global =
-        module
-            .globals
-            .add_local(ValType::I64, true, InitExpr::Value(Value::I64(0)));
-    let gas_limit_exhausted_global =
-        module
+    let mut add_meter = |name: &str| {
+        let global = module
             .globals
             .add_local(ValType::I64, true, InitExpr::Value(Value::I64(0)));
-    module.exports.add(EXPORT_GAS_LIMIT, gas_limit_global);
-    module
-        .exports
-        .add(EXPORT_GAS_LIMIT_EXHAUSTED, gas_limit_exhausted_global);
-
-    let memory_grow_meter = create_memory_grow_meter(
-        module,
-        gas_costs,
-        gas_limit_global,
-        gas_limit_exhausted_global,
-    );
+        module.exports.add(name, global);
+        global
+    };
+    let meters = Meters {
+        gas_limit: add_meter(EXPORT_GAS_LIMIT),
+        gas_limit_exhausted: add_meter(EXPORT_GAS_LIMIT_EXHAUSTED),
+        ticks: add_meter(EXPORT_EXECUTION_TICKS),
+        ticks_exhausted: add_meter(EXPORT_EXECUTION_TICKS_EXHAUSTED),
+    };
+
+    let memory_grow_meter = create_memory_grow_meter(module, gas_costs, meters);
 
     for (_, func) in module.funcs.iter_local_mut() {
-        transform_function(
-            func,
-            gas_costs,
-            gas_limit_global,
-            gas_limit_exhausted_global,
-            memory_grow_meter,
-        );
+        transform_function(func, gas_costs, meters, memory_grow_meter);
     }
 }
 
@@ -90,37 +138,37 @@ fn instruction_cost(_instr: &Instr, _gas_costs: &WasmCosts) -> u64 {
     2
 }
 
+/// Execution ticks of an instruction
+fn instruction_ticks(instr: &Instr) -> u64 {
+    match instr {
+        Instr::Load { .. } | Instr::Store { .. } => MEMORY_ACCESS_TICKS,
+        Instr::Call { .. } | Instr::CallIndirect { .. } => CALL_TICKS,
+        _ => INSTRUCTION_TICKS,
+    }
+}
+
 fn transform_function(
     func: &mut LocalFunction,
     gas_costs: &WasmCosts,
-    gas_limit_global: GlobalId,
-    gas_limit_exhausted_global: GlobalId,
+    meters: Meters,
     memory_grow_meter: FunctionId,
 ) {
     // get the list of "original" blocks before we start adding more.
     let block_ids: Vec<_> = func.blocks().map(|(block_id, _block)| block_id).collect();
     // for each block, prepend it with metering instructions
     for block_id in block_ids {
-        inject_metering(
-            func,
-            block_id,
-            gas_costs,
-            gas_limit_global,
-            gas_limit_exhausted_global,
-            memory_grow_meter,
-        );
+        inject_metering(func, block_id, gas_costs, meters, memory_grow_meter);
     }
 }
 
 /// Number of injected metering instructions (needed to calculate final instruction size).
-const METERING_INSTRUCTION_COUNT: usize = 8;
+const METERING_INSTRUCTION_COUNT: usize = 16;
 
 fn inject_metering(
     func: &mut LocalFunction,
     block_id: InstrSeqId,
     gas_costs: &WasmCosts,
-    gas_limit_global: GlobalId,
-    gas_limit_exhausted_global: GlobalId,
+    meters: Meters,
     memory_grow_meter: FunctionId,
 ) {
     let block = func.block_mut(block_id);
@@ -131,6 +179,11 @@ fn inject_metering(
         .map(|(inst, _instr_loc)| instruction_cost(inst, gas_costs))
         .sum();
     let block_cost = block_cost as i64;
+    let block_ticks: u64 = block_instrs
+        .iter()
+        .map(|(inst, _instr_loc)| instruction_ticks(inst))
+        .sum();
+    let block_ticks = block_ticks as i64;
 
     // find all location in the block that use Instr::MemoryGrow
     let mut grow_locations = vec![];
@@ -156,23 +209,41 @@ fn inject_metering(
     let mut builder = builder.dangling_instr_seq(None);
     let seq = builder
         // if unsigned(globals[gas_limit]) < unsigned(block_cost) { throw(); }
-        .global_get(gas_limit_global)
+        .global_get(meters.gas_limit)
         .i64_const(block_cost)
         .binop(BinaryOp::I64LtU)
         .if_else(
             None,
             |then| {
                 then.i64_const(block_cost)
-                    .global_set(gas_limit_exhausted_global)
+                    .global_set(meters.gas_limit_exhausted)
                     .unreachable();
             },
             |_else| {},
         )
         // globals[gas_limit] -= block_cost;
-        .global_get(gas_limit_global)
+        .global_get(meters.gas_limit)
         .i64_const(block_cost)
         .binop(BinaryOp::I64Sub)
-        .global_set(gas_limit_global);
+        .global_set(meters.gas_limit)
+        // if unsigned(globals[execution_ticks]) < unsigned(block_ticks) { throw(); }
+        .global_get(meters.ticks)
+        .i64_const(block_ticks)
+        .binop(BinaryOp::I64LtU)
+        .if_else(
+            None,
+            |then| {
+                then.i64_const(block_ticks)
+                    .global_set(meters.ticks_exhausted)
+                    .unreachable();
+            },
+            |_else| {},
+        )
+        // globals[execution_ticks] -= block_ticks;
+        .global_get(meters.ticks)
+        .i64_const(block_ticks)
+        .binop(BinaryOp::I64Sub)
+        .global_set(meters.ticks);
 
     let mut new_instrs = Vec::with_capacity(block_len + METERING_INSTRUCTION_COUNT);
     new_instrs.append(seq.instrs_mut());
@@ -185,13 +256,14 @@ fn inject_metering(
 fn create_memory_grow_meter(
     module: &mut Module,
     gas_costs: &WasmCosts,
-    gas_limit_global: GlobalId,
-    gas_limit_exhausted_global: GlobalId,
+    meters: Meters,
 ) -> FunctionId {
     // function input
     let num_pages = module.locals.add(ValType::I32);
     // cache cost of memory grow
     let grow_cost = module.locals.add(ValType::I64);
+    // cache ticks of memory grow
+    let grow_ticks = module.locals.add(ValType::I64);
 
     let mut func = FunctionBuilder::new(&mut module.types, &[ValType::I32], &[ValType::I32]);
 
@@ -206,23 +278,45 @@ fn create_memory_grow_meter(
         .local_set(grow_cost)
         // from here it's very similar to the code in `fn inject_metering()`.
         // if unsigned(globals[gas_limit]) < unsigned(grow_cost) { throw(); }
-        .global_get(gas_limit_global)
+        .global_get(meters.gas_limit)
         .local_get(grow_cost)
         .binop(BinaryOp::I64LtU)
         .if_else(
             None,
             |then| {
                 then.local_get(grow_cost)
-                    .global_set(gas_limit_exhausted_global)
+                    .global_set(meters.gas_limit_exhausted)
                     .unreachable();
             },
             |_else| {},
         )
         // globals[gas_limit] -= grow_cost;
-        .global_get(gas_limit_global)
+        .global_get(meters.gas_limit)
         .local_get(grow_cost)
         .binop(BinaryOp::I64Sub)
-        .global_set(gas_limit_global)
+        .global_set(meters.gas_limit)
+        // the same for the ticks of the grow
+        .local_get(num_pages)
+        .unop(UnaryOp::I64ExtendSI32)
+        .i64_const(GROW_MEM_TICKS_PER_PAGE as i64)
+        .binop(BinaryOp::I64Mul)
+        .local_set(grow_ticks)
+        .global_get(meters.ticks)
+        .local_get(grow_ticks)
+        .binop(BinaryOp::I64LtU)
+        .if_else(
+            None,
+            |then| {
+                then.local_get(grow_ticks)
+                    .global_set(meters.ticks_exhausted)
+                    .unreachable();
+            },
+            |_else| {},
+        )
+        .global_get(meters.ticks)
+        .local_get(grow_ticks)
+        .binop(BinaryOp::I64Sub)
+        .global_set(meters.ticks)
         // return the original number of pages for the MemoryGrow instruction
         // right after this function call.
         .local_get(num_pages);
@@ -14,23 +14,61 @@ use wasm3::{Instance, Memory, Trap};
 use cw_types_v010::consts::BECH32_PREFIX_ACC_ADDR;
 use cw_types_v010::encoding::Binary;
 use enclave_cosmos_types::types::{ContractCode, HandleType};
-use enclave_crypto::dcap::verify_quote_any;
+use enclave_crypto::dcap::{quote_report_body, verify_quote_any};
+use enclave_crypto::drand::{verify_drand_beacon, DrandBeacon};
+use enclave_crypto::oracle::verify_oracle_attestation;
+use enclave_crypto::symmetric::{self, AeadAlgorithm, SymmetricError};
+use enclave_crypto::x509::{self, CertPurpose, X509Error};
 use enclave_crypto::{sha_256, Ed25519PublicKey, WasmApiCryptoError};
 use enclave_ffi_types::{Ctx, EnclaveError};
-
+use enclave_utils::verified_params::MAX_EXECUTION_TICKS;
+use enclave_utils::KEY_MANAGER;
+
+use crate::big_int::{self, BigIntError};
+use crate::calendar::{self, CalendarError};
+use crate::canonical_json::canonicalize;
+use crate::compression::{self, CompressionError, CompressionFormat};
+use crate::contract_subkeys::{
+    genesis_state_key, subkey_decrypt, subkey_public_key, ContractSubkeyError,
+};
 use crate::contract_validation::ContractKey;
 use crate::cosmwasm_config::ContractOperation;
 use crate::db::read_from_encrypted_state;
 use crate::db::{remove_from_encrypted_state, write_multiple_keys};
+use crate::enclave_features::encoded_enclave_features;
 use crate::errors::{ToEnclaveError, ToEnclaveResult, WasmEngineError, WasmEngineResult};
+use crate::execution_report::{ExecutionReport, HostGasMeter, StorageOps};
+use crate::fixed_point::{self, FixedPointError};
+use crate::foreign_clients::foreign_app_hash_at;
 use crate::gas::{WasmCosts, READ_BASE_GAS, WRITE_BASE_GAS};
-use crate::query_chain::encrypt_and_query_chain;
-use crate::random::MSG_COUNTER;
+use crate::ibc_client_status::{verified_client_status, IbcClientStatusError};
+use crate::io::decrypt_disclosed_attribute;
+use crate::job_message::{delivered_job_key, seal_job_input};
+use crate::jwt::{self, Jwk, JwkKey};
+use crate::merkle_accumulator::{MerkleHash, MerkleStore, MerkleTree};
+use crate::musig2::{self, MuSig2Error};
+use crate::oblivious_storage::{ObliviousNamespaces, ObliviousStore, OBLIVIOUS_GAS_MULTIPLIER};
+use crate::poseidon::{self, Curve};
+use crate::public_state::{
+    read_public_state, remove_public_state, write_public_state, NamespaceMode, PublicNamespaces,
+};
+use crate::query_cancellation::QueryCancellation;
+use crate::query_chain::{encrypt_and_query_chain, queries_unavailable_response};
+use crate::query_limits::{QueryDeadline, QueryLimits};
+use crate::quote_policy::{quote_summary, QuotePolicy, QuotePolicyError};
+use crate::random::{past_random, MSG_COUNTER};
+use crate::rsa::{self, RsaError};
+use crate::state_tree::{self, StateTree, StateTreeError};
+use crate::token_metadata::{open_token_metadata, seal_token_metadata, TokenMetadataError};
 use crate::types::IoNonce;
 
-use gas::{get_exhausted_amount, get_remaining_gas, use_gas};
-use module_cache::create_module_instance;
+use gas::{
+    get_exhausted_amount, get_exhausted_ticks, get_remaining_gas, get_remaining_ticks, use_gas,
+    DEFAULT_MAX_EXECUTION_TICKS,
+};
+use module_cache::{analyze_module, create_module_instance, is_cached};
 
+pub mod code_limits;
 mod gas;
 pub mod module_cache;
 mod validation;
@@ -119,8 +157,25 @@ pub struct Context {
     user_nonce: IoNonce,
     user_public_key: Ed25519PublicKey,
     kv_cache: KvCache,
+    oblivious_namespaces: ObliviousNamespaces,
+    /// How many oblivious accesses this call made, to derive the decoys of the next one
+    oblivious_accesses: u32,
+    public_namespaces: PublicNamespaces,
+    /// The total gas declared with `gas_uniform`, if the contract opted into uniform gas
+    uniform_gas: Option<u64>,
+    /// Whether the contract's code requires a commitment to its state, see `state_tree`
+    state_commitment: bool,
     last_error: Option<WasmEngineError>,
     timestamp: u64,
+    /// Set for job handlers, which run off-chain where the node could answer storage reads and
+    /// chain queries with anything
+    isolated: bool,
+    /// Set for queries from outside the chain when the node limits their time, see `query_limits`
+    query_deadline: Option<QueryDeadline>,
+    /// Set for queries from outside the chain the node may cancel, see `query_cancellation`
+    cancellation: Option<QueryCancellation>,
+    host_gas: HostGasMeter,
+    storage_ops: StorageOps,
 }
 
 impl Context {
@@ -139,6 +194,50 @@ impl Context {
     pub fn set_last_error(&mut self, error: WasmEngineError) {
         self.last_error = Some(error);
     }
+
+    fn check_query_interrupted(&self) -> WasmEngineResult<()> {
+        if let Some(cancellation) = &self.cancellation {
+            if cancellation.is_cancelled() {
+                debug!("query was cancelled");
+                return Err(WasmEngineError::QueryCancelled);
+            }
+        }
+
+        match &self.query_deadline {
+            Some(deadline) if deadline.has_passed() => {
+                debug!("query ran past its time limit");
+                Err(WasmEngineError::QueryTimeLimitExceeded)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn oblivious_store(&mut self) -> ObliviousStore<'_> {
+        let msg_counter = *MSG_COUNTER.lock().unwrap();
+
+        ObliviousStore {
+            context: &self.context,
+            contract_key: &self.og_contract_key,
+            kv_cache: &mut self.kv_cache,
+            has_write_permissions: !self.operation.is_query(),
+            encryption_salt: &[],
+            height: msg_counter.height,
+            msg_counter: msg_counter.counter,
+            accesses: &mut self.oblivious_accesses,
+        }
+    }
+
+    fn merkle_store(&mut self) -> MerkleStore<'_> {
+        MerkleStore {
+            context: &self.context,
+            contract_key: &self.og_contract_key,
+            kv_cache: &mut self.kv_cache,
+            has_write_permissions: !self.operation.is_query(),
+            encryption_salt: &[],
+            gas_used: 0,
+            gas_used_externally: 0,
+        }
+    }
 }
 
 /// Wrap the hook function such that we expect the context to be passed in,
@@ -162,9 +261,66 @@ where
     }
 }
 
+/// Records every call of `func` in the audit transcript
+#[cfg(feature = "audit")]
+fn audited<F, A, R>(
+    name: &str,
+    mut func: F,
+) -> impl FnMut(&mut Context, &wasm3::Instance<Context>, A) -> Result<R, WasmEngineError> + 'static
+where
+    F: FnMut(&mut Context, &wasm3::Instance<Context>, A) -> Result<R, WasmEngineError> + 'static,
+{
+    let name = name.to_string();
+    move |context, instance, args| {
+        crate::audit::record_host_call(&name, get_remaining_gas(instance));
+        let result = func(context, instance, args);
+        if let Err(err) = &result {
+            crate::audit::record_host_error(&name, err);
+        }
+        result
+    }
+}
+
+/// Records the gas of every call of `func` for the execution report
+fn metered<F, A, R>(
+    name: &'static str,
+    mut func: F,
+) -> impl FnMut(&mut Context, &wasm3::Instance<Context>, A) -> Result<R, WasmEngineError> + 'static
+where
+    F: FnMut(&mut Context, &wasm3::Instance<Context>, A) -> Result<R, WasmEngineError> + 'static,
+{
+    move |context, instance, args| {
+        let remaining_gas = get_remaining_gas(instance);
+        let gas_used_externally = context.get_gas_used_externally();
+        let result = func(context, instance, args);
+        context.host_gas.record(
+            name,
+            remaining_gas.saturating_sub(get_remaining_gas(instance)),
+            context
+                .get_gas_used_externally()
+                .saturating_sub(gas_used_externally),
+        );
+        result
+    }
+}
+
+/// Stops a query that ran past its deadline or was cancelled the next time it calls into the
+/// enclave
+fn interruptible<F, A, R>(
+    mut func: F,
+) -> impl FnMut(&mut Context, &wasm3::Instance<Context>, A) -> Result<R, WasmEngineError> + 'static
+where
+    F: FnMut(&mut Context, &wasm3::Instance<Context>, A) -> Result<R, WasmEngineError> + 'static,
+{
+    move |context, instance, args| {
+        context.check_query_interrupted()?;
+        func(context, instance, args)
+    }
+}
+
 fn link_fn_no_args<F, R>(
     instance: &mut Instance<Context>,
-    name: &str,
+    name: &'static str,
     mut func: F,
 ) -> Wasm3RsResult<()>
 where
@@ -174,19 +330,29 @@ where
     let wrapped_func =
         move |ctx: &mut Context, instance: &wasm3::Instance<Context>, _: ()| func(ctx, instance);
 
-    let wrapped_func = expect_context(wrapped_func);
+    #[cfg(feature = "audit")]
+    let wrapped_func = audited(name, wrapped_func);
+
+    let wrapped_func = expect_context(interruptible(metered(name, wrapped_func)));
     instance
         .link_function("env", name, wrapped_func)
         .allow_missing_import()
 }
 
-fn link_fn<F, A, R>(instance: &mut Instance<Context>, name: &str, func: F) -> Wasm3RsResult<()>
+fn link_fn<F, A, R>(
+    instance: &mut Instance<Context>,
+    name: &'static str,
+    func: F,
+) -> Wasm3RsResult<()>
 where
     F: FnMut(&mut Context, &wasm3::Instance<Context>, A) -> Result<R, WasmEngineError> + 'static,
     A: wasm3::Arg + 'static,
     R: wasm3::Arg + 'static,
 {
-    let func = expect_context(func);
+    #[cfg(feature = "audit")]
+    let func = audited(name, func);
+
+    let func = expect_context(interruptible(metered(name, func)));
     instance
         .link_function("env", name, func)
         .allow_missing_import()
@@ -208,6 +374,14 @@ fn check_execution_result<T>(
             );
             EnclaveError::OutOfGas
         }
+        wasm3::Error::UnreachableExecuted if get_exhausted_ticks(instance) != 0 => {
+            debug!(
+                "Detected execution ticks exhausted! Remaining: {}, Exhausted: {}",
+                get_remaining_ticks(instance),
+                get_exhausted_ticks(instance)
+            );
+            EnclaveError::ExecutionTicksExhausted
+        }
         // Otherwise, check if a hook set an error, in which case we propagate it.
         err => match context.take_last_error() {
             Some(err) => err.into(),
@@ -216,10 +390,30 @@ fn check_execution_result<T>(
     })
 }
 
+/// The most wasm pages a contract's memory can grow to, 12 MiB
+const MEMORY_LIMIT_PAGES: u32 = 192;
+
+/// Which backend an engine runs contracts with. Consensus always uses `Current`. An upgrade of
+/// the engine stages its changes behind `Candidate`, where `differential` compares them with
+/// `Current` on live traffic before they're switched on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EngineBackend {
+    Current,
+    /// Until an upgrade stages changes here, the candidate only differs in instrumenting the
+    /// module from scratch, rather than taking it from the module cache
+    Candidate,
+}
+
 pub struct Engine {
     context: Context,
     gas_limit: u64,
     used_gas: u64,
+    /// The part of `used_gas` that the contract's own instructions used
+    wasm_gas: u64,
+    /// The execution ticks an execution may use, independently of its gas
+    max_execution_ticks: u64,
+    module_cache_hit: bool,
+    memory_limit_pages: u32,
     environment: wasm3::Environment,
     code: Vec<u8>,
     api_version: CosmWasmApiVersion,
@@ -240,8 +434,20 @@ impl Engine {
         user_public_key: Ed25519PublicKey,
         query_depth: u32,
         timestamp: u64,
+        backend: EngineBackend,
     ) -> Result<Engine, EnclaveError> {
-        let versioned_code = create_module_instance(contract_code, &gas_costs, operation)?;
+        let (module_cache_hit, versioned_code) = match backend {
+            EngineBackend::Current => (
+                is_cached(contract_code),
+                create_module_instance(contract_code, &gas_costs, operation)?,
+            ),
+            EngineBackend::Candidate => {
+                (false, analyze_module(contract_code, &gas_costs, operation)?)
+            }
+        };
+        let state_commitment = versioned_code
+            .features
+            .contains(&ContractFeature::StateCommitment);
         let kv_cache = KvCache::new();
         let context = Context {
             context,
@@ -254,8 +460,18 @@ impl Engine {
             user_nonce,
             user_public_key,
             kv_cache,
+            oblivious_namespaces: ObliviousNamespaces::default(),
+            oblivious_accesses: 0,
+            public_namespaces: PublicNamespaces::default(),
+            uniform_gas: None,
+            state_commitment,
             last_error: None,
             timestamp,
+            isolated: false,
+            query_deadline: None,
+            cancellation: None,
+            host_gas: HostGasMeter::default(),
+            storage_ops: StorageOps::default(),
         };
 
         debug!("setting up runtime");
@@ -270,6 +486,11 @@ impl Engine {
             context,
             gas_limit,
             used_gas: 0,
+            wasm_gas: 0,
+            max_execution_ticks: MAX_EXECUTION_TICKS
+                .get_or(DEFAULT_MAX_EXECUTION_TICKS, DEFAULT_MAX_EXECUTION_TICKS),
+            module_cache_hit,
+            memory_limit_pages: MEMORY_LIMIT_PAGES,
             environment,
             code: versioned_code.code,
             api_version: versioned_code.version,
@@ -284,7 +505,7 @@ impl Engine {
         // let start = Instant::now();
         let runtime = self
             .environment
-            .new_runtime::<Context>(1024 * 60, Some(192 /* 12 MiB */))
+            .new_runtime::<Context>(1024 * 60, Some(self.memory_limit_pages))
             .to_enclave_result()?;
         // let duration = start.elapsed();
         // trace!("Time elapsed in environment.new_runtime is: {:?}", duration);
@@ -310,6 +531,7 @@ impl Engine {
 
         // let start = Instant::now();
         gas::set_gas_limit(&instance, self.gas_limit)?;
+        gas::set_execution_ticks(&instance, self.max_execution_ticks)?;
         // let duration = start.elapsed();
         // trace!("Time elapsed in set_gas_limit is: {:?}", duration);
         trace!("set gas limit");
@@ -331,14 +553,26 @@ impl Engine {
             .saturating_sub(get_remaining_gas(&instance))
             .saturating_sub(self.context.get_gas_used_externally())
             .saturating_add(get_exhausted_amount(&instance));
+        self.wasm_gas = self
+            .gas_limit
+            .saturating_sub(get_remaining_gas(&instance))
+            .saturating_sub(self.context.host_gas.instance_gas());
+
+        #[cfg(feature = "audit")]
+        crate::audit::record_end(self.used_gas, &result);
 
         result
     }
 
+    /// Keep `code_limits::HOST_FUNCTIONS` in sync with the functions linked here
     fn link_host_functions(instance: &mut wasm3::Instance<Context>) -> Wasm3RsResult<()> {
         link_fn(instance, "db_read", host_read_db)?;
         link_fn(instance, "db_write", host_write_db)?;
         link_fn(instance, "db_remove", host_remove_db)?;
+        link_fn(instance, "db_oblivious_namespace", host_oblivious_namespace)?;
+        link_fn(instance, "db_public_namespace", host_public_namespace)?;
+        link_fn_no_args(instance, "db_declassify", host_declassify)?;
+        link_fn(instance, "db_integrity_namespace", host_integrity_namespace)?;
         link_fn(instance, "canonicalize_address", host_canonicalize_address)?;
         link_fn(instance, "humanize_address", host_humanize_address)?;
         link_fn(instance, "query_chain", host_query_chain)?;
@@ -357,9 +591,75 @@ impl Engine {
         link_fn(instance, "ed25519_batch_verify", host_ed25519_batch_verify)?;
         link_fn(instance, "secp256k1_sign", host_secp256k1_sign)?;
         link_fn(instance, "ed25519_sign", host_ed25519_sign)?;
+        link_fn(instance, "rsa_pkcs1v15_verify", host_rsa_pkcs1v15_verify)?;
+        link_fn(instance, "rsa_pss_verify", host_rsa_pss_verify)?;
+        link_fn(instance, "musig2_key_agg", host_musig2_key_agg)?;
+        #[rustfmt::skip]
+        link_fn(instance, "secp256k1_schnorr_verify", host_secp256k1_schnorr_verify)?;
         link_fn(instance, "dcap_quote_verify", host_dcap_quote_verify)?;
+        link_fn(instance, "drand_verify", host_drand_verify)?;
+        link_fn(instance, "drand_randomness", host_drand_randomness)?;
+        link_fn(
+            instance,
+            "oracle_attestation_verify",
+            host_oracle_attestation_verify,
+        )?;
+        link_fn(instance, "job_seal_input", host_job_seal_input)?;
+        #[rustfmt::skip]
+        link_fn(instance, "decrypt_disclosed_attribute", host_decrypt_disclosed_attribute)?;
+        link_fn(
+            instance,
+            "derive_contract_keypair",
+            host_derive_contract_keypair,
+        )?;
+        link_fn(instance, "contract_decrypt", host_contract_decrypt)?;
+        link_fn(instance, "token_metadata_seal", host_token_metadata_seal)?;
+        link_fn(instance, "token_metadata_open", host_token_metadata_open)?;
+        link_fn(
+            instance,
+            "foreign_app_hash_verify",
+            host_foreign_app_hash_verify,
+        )?;
+        link_fn(instance, "block_random_at", host_block_random_at)?;
         link_fn_no_args(instance, "check_gas", host_check_gas_used)?;
         link_fn(instance, "gas_evaporate", host_gas_evaporate)?;
+        link_fn(instance, "gas_uniform", host_gas_uniform)?;
+        link_fn(instance, "canonicalize_json", host_canonicalize_json)?;
+        link_fn(instance, "fixed_point_mul", host_fixed_point_mul)?;
+        link_fn(instance, "fixed_point_div", host_fixed_point_div)?;
+        link_fn(instance, "fixed_point_ln", host_fixed_point_ln)?;
+        link_fn(instance, "fixed_point_exp", host_fixed_point_exp)?;
+        link_fn(instance, "fixed_point_pow", host_fixed_point_pow)?;
+        link_fn(instance, "timestamp_add_months", host_timestamp_add_months)?;
+        link_fn(instance, "timestamp_truncate", host_timestamp_truncate)?;
+        link_fn(instance, "timestamp_weekday", host_timestamp_weekday)?;
+        link_fn(instance, "uint512_op", host_uint512_op)?;
+        link_fn(instance, "int512_op", host_int512_op)?;
+        link_fn(instance, "modexp", host_modexp)?;
+        link_fn(instance, "x509_verify_chain", host_x509_verify_chain)?;
+        link_fn(instance, "jwt_verify", host_jwt_verify)?;
+        link_fn(instance, "poseidon_hash", host_poseidon_hash)?;
+        link_fn(instance, "merkle_create", host_merkle_create)?;
+        link_fn(instance, "merkle_append", host_merkle_append)?;
+        link_fn(instance, "merkle_root", host_merkle_root)?;
+        link_fn(instance, "merkle_prove", host_merkle_prove)?;
+        link_fn(instance, "merkle_verify", host_merkle_verify)?;
+        #[rustfmt::skip]
+        link_fn_no_args(instance, "state_commitment_root", host_state_commitment_root)?;
+        #[rustfmt::skip]
+        link_fn(instance, "state_commitment_prove", host_state_commitment_prove)?;
+        #[rustfmt::skip]
+        link_fn(instance, "state_commitment_verify", host_state_commitment_verify)?;
+        link_fn(instance, "hkdf_sha256", host_hkdf_sha256)?;
+        link_fn(instance, "hmac_sha256", host_hmac_sha256)?;
+        link_fn(instance, "aead_seal", host_aead_seal)?;
+        link_fn(instance, "aead_open", host_aead_open)?;
+        link_fn(instance, "compress", host_compress)?;
+        link_fn(instance, "decompress", host_decompress)?;
+        link_fn(instance, "verify_sgx_quote", host_verify_sgx_quote)?;
+        #[rustfmt::skip]
+        link_fn_no_args(instance, "query_enclave_features", host_query_enclave_features)?;
+        link_fn(instance, "ibc_client_status", host_ibc_client_status)?;
 
         //    DbReadIndex = 0,
         //     DbWriteIndex = 1,
@@ -378,17 +678,120 @@ impl Engine {
         //     Secp256k1SignIndex = 14,
         //     Ed25519SignIndex = 15,
         //     DebugIndex = 16,
+        //     DrandVerifyIndex = 17,
+        //     DrandRandomnessIndex = 18,
+        //     OracleAttestationVerifyIndex = 19,
+        //     DecryptDisclosedAttributeIndex = 20,
+        //     DbObliviousNamespaceIndex = 21,
+        //     GasUniformIndex = 22,
+        //     CanonicalizeJsonIndex = 23,
         //     DebugPrintIndex = 254,
         //     Unknown,
 
         Ok(())
     }
 
+    /// Cut the contract off from the node: storage reads find nothing and chain queries fail
+    pub fn isolate(&mut self) {
+        self.context.isolated = true;
+    }
+
+    /// Records in the contract's state that the result of job `job_id` was delivered, and fails
+    /// if it already was. The record is flushed with the contract's own writes, so it's dropped
+    /// if the contract fails to handle the result.
+    pub fn record_delivered_job(&mut self, job_id: u64) -> Result<(), EnclaveError> {
+        let key = delivered_job_key(job_id);
+        let (value, used_gas) = read_from_encrypted_state(
+            &key,
+            &self.context.context,
+            &self.context.og_contract_key,
+            true,
+            &mut self.context.kv_cache,
+            &get_encryption_salt(self.context.timestamp),
+        )?;
+        self.context.use_gas_externally(used_gas);
+
+        if value.is_some() {
+            warn!("the result of job {} was already delivered", job_id);
+            return Err(EnclaveError::ValidationFailure);
+        }
+
+        self.context.kv_cache.write(&key, &[1]);
+        Ok(())
+    }
+
+    /// Applies the node's limits for queries from outside the chain. The time limit starts now.
+    pub fn limit_query(&mut self, limits: &QueryLimits) {
+        self.gas_limit = limits.gas_limit(self.gas_limit);
+        self.context.gas_limit = self.gas_limit;
+        self.memory_limit_pages = limits.memory_limit_pages(self.memory_limit_pages);
+        self.context.query_deadline = limits.deadline();
+    }
+
+    /// Lets the node cancel the query with `ecall_cancel_query`
+    pub fn allow_cancellation(&mut self, cancellation: QueryCancellation) {
+        self.context.cancellation = Some(cancellation);
+    }
+
+    /// What the last contract execution spent its gas on, unless the contract hides its gas with
+    /// `gas_uniform`. `call_depth` is the depth of its call stack.
+    pub fn execution_report(&self, call_depth: u32) -> Option<ExecutionReport> {
+        if self.context.uniform_gas.is_some() {
+            return None;
+        }
+
+        Some(ExecutionReport {
+            wasm_gas: self.wasm_gas,
+            host_functions: self.context.host_gas.report(),
+            storage: self.context.storage_ops.clone(),
+            module_cache_hit: self.module_cache_hit,
+            call_depth,
+        })
+    }
+
     /// get the amount of gas used by the last contract execution
     pub fn gas_used(&self) -> u64 {
         self.used_gas
     }
 
+    /// The writes the last contract execution left in the cache, before it's flushed
+    #[cfg(feature = "differential-execution")]
+    pub fn write_set(&self) -> &std::collections::BTreeMap<Vec<u8>, Vec<u8>> {
+        self.context.kv_cache.writes()
+    }
+
+    /// If the contract declared a uniform gas amount, pad `used_gas` so that together with the
+    /// gas charged externally (storage, sub-queries) the call costs exactly that amount.
+    /// Must be called after the cache was flushed, so the writes are accounted for.
+    pub fn apply_uniform_gas(&self, used_gas: &mut u64) -> Result<(), EnclaveError> {
+        let declared = match self.context.uniform_gas {
+            Some(declared) => declared,
+            None => return Ok(()),
+        };
+
+        let external = self.context.get_gas_used_externally();
+        if used_gas.saturating_add(external) > declared {
+            warn!(
+                "call used {} gas, more than the {} declared for uniform gas",
+                used_gas.saturating_add(external),
+                declared
+            );
+            *used_gas = declared.saturating_sub(external);
+            return Err(EnclaveError::OutOfGas);
+        }
+
+        *used_gas = declared - external;
+        Ok(())
+    }
+
+    /// Pad `used_gas` of a call that failed like `apply_uniform_gas` does, so a failure reports
+    /// the same gas as a success. The writes of a failed call are dropped, so there's no cache to
+    /// flush first.
+    pub fn apply_uniform_gas_on_failure(&self, used_gas: &mut u64) {
+        // A call that needed more than it declared fails with its own error, not out of gas
+        let _ = self.apply_uniform_gas(used_gas);
+    }
+
     pub fn get_api_version(&self) -> CosmWasmApiVersion {
         self.api_version
     }
@@ -401,9 +804,23 @@ impl Engine {
     pub fn migrate(&mut self, env: &CwEnv, msg: Vec<u8>) -> Result<Vec<u8>, EnclaveError> {
         let api_version = self.get_api_version();
 
+        #[cfg(feature = "audit")]
+        crate::audit::record_start("migrate", &msg);
+
         self.with_instance(|instance, context| {
             debug!("starting migrate, api version: {:?}", api_version);
 
+            // The tree is stopped when migrating to code that doesn't require it, so it can't
+            // be left behind by writes the engine doesn't commit to
+            let state_commitment = context.state_commitment;
+            with_merkle_store(context, instance, |store| {
+                if state_commitment {
+                    StateTree::start(store, false).map(drop)
+                } else {
+                    StateTree::stop(store)
+                }
+            })?;
+
             let (env_bytes, _msg_info_bytes) = env.get_wasm_ptrs()?;
 
             // let start = Instant::now();
@@ -464,9 +881,18 @@ impl Engine {
     pub fn init(&mut self, env: &CwEnv, msg: Vec<u8>) -> Result<Vec<u8>, EnclaveError> {
         let api_version = self.get_api_version();
 
+        #[cfg(feature = "audit")]
+        crate::audit::record_start("init", &msg);
+
         self.with_instance(|instance, context| {
             debug!("starting init, api version: {:?}", api_version);
 
+            if context.state_commitment {
+                with_merkle_store(context, instance, |store| {
+                    StateTree::start(store, true).map(drop)
+                })?;
+            }
+
             let (env_bytes, msg_info_bytes) = env.get_wasm_ptrs()?;
 
             // let start = Instant::now();
@@ -534,6 +960,9 @@ impl Engine {
     ) -> Result<Vec<u8>, EnclaveError> {
         let api_version = self.get_api_version();
 
+        #[cfg(feature = "audit")]
+        crate::audit::record_start(HandleType::get_export_name(handle_type), &msg);
+
         self.with_instance(|instance, context| {
             trace!("starting handle");
             let (env_bytes, msg_info_bytes) = env.get_wasm_ptrs()?;
@@ -595,6 +1024,9 @@ impl Engine {
     pub fn query(&mut self, env: &CwEnv, msg: Vec<u8>) -> Result<Vec<u8>, EnclaveError> {
         let api_version = self.get_api_version();
 
+        #[cfg(feature = "audit")]
+        crate::audit::record_start("query", &msg);
+
         self.with_instance(|instance, context| {
             let msg_ptr = write_to_memory(instance, &msg)?;
 
@@ -666,8 +1098,9 @@ impl Engine {
         if let Some(random_unwraped) = random {
             shuffle_cache(&mut keys, random_unwraped);
         }
+        self.context.storage_ops.writes += keys.len() as u64;
 
-        write_multiple_keys(&self.context.context, keys).map_err(|err| {
+        let used_gas = write_multiple_keys(&self.context.context, keys).map_err(|err| {
             debug!(
                 "write_db() error while trying to write the value to state: {:?}",
                 err
@@ -675,6 +1108,7 @@ impl Engine {
 
             EnclaveError::from(err)
         })?;
+        self.context.use_gas_externally(used_gas);
 
         Ok(total_gas_to_refund)
     }
@@ -912,11 +1346,66 @@ fn host_read_db(
     )?;
 
     debug!("db_read reading key {}", show_bytes(&state_key_name));
+    context.storage_ops.reads += 1;
+
+    if let Some(namespace) = context.oblivious_namespaces.namespace_of(&state_key_name) {
+        use_gas(instance, READ_BASE_GAS * (OBLIVIOUS_GAS_MULTIPLIER - 1))?;
+
+        let (value, used_gas) = context
+            .oblivious_store()
+            .read(&namespace, &state_key_name)
+            .map_err(debug_err!(
+                "db_read failed to read key from oblivious storage"
+            ))?;
+        context.use_gas_externally(used_gas);
+
+        #[cfg(feature = "conformance")]
+        crate::conformance::record_read(&state_key_name, value.as_deref());
+        #[cfg(feature = "audit")]
+        crate::audit::record_read(&state_key_name, value.as_deref());
+
+        return match value {
+            Some(value) => {
+                context.public_namespaces.taint();
+                Ok(write_to_memory(instance, &value)? as i32)
+            }
+            None => Ok(0),
+        };
+    }
+
+    if context.public_namespaces.is_public(&state_key_name) {
+        let state_key = genesis_state_key().map_err(|_| WasmEngineError::DecryptionError)?;
+        let (value, used_gas) = read_public_state(
+            &context.context,
+            &state_key,
+            &context.og_contract_key,
+            &state_key_name,
+        )
+        .map_err(debug_err!("db_read failed to read key from public storage"))?;
+        context.use_gas_externally(used_gas);
+
+        #[cfg(feature = "conformance")]
+        crate::conformance::record_read(&state_key_name, value.as_deref());
+        #[cfg(feature = "audit")]
+        crate::audit::record_read(&state_key_name, value.as_deref());
+
+        return match value {
+            Some(value) => Ok(write_to_memory(instance, &value)? as i32),
+            None => Ok(0),
+        };
+    }
 
     let value = context.kv_cache.read(&state_key_name);
 
     if let Some(unwrapped) = value {
         debug!("Got value from cache");
+        context.storage_ops.cache_hits += 1;
+        context.public_namespaces.taint();
+        #[cfg(feature = "conformance")]
+        crate::conformance::record_read(&state_key_name, Some(&unwrapped));
+        #[cfg(feature = "audit")]
+        crate::audit::record_read(&state_key_name, Some(&unwrapped));
+
         let ptr_to_region_in_wasm_vm = write_to_memory(instance, &unwrapped).map_err(|err| {
             debug!(
                 "read_db() error while trying to allocate {} bytes for the value",
@@ -928,33 +1417,55 @@ fn host_read_db(
         return Ok(ptr_to_region_in_wasm_vm as i32);
     }
 
-    debug!("Missed value in cache");
-    let (value, used_gas) = read_from_encrypted_state(
-        &state_key_name,
-        &context.context,
-        &context.og_contract_key,
-        match context.operation {
-            ContractOperation::Init => true,
-            ContractOperation::Handle => true,
-            ContractOperation::Query => false,
-            ContractOperation::Migrate => true,
-        },
-        &mut context.kv_cache,
-        &get_encryption_salt(context.timestamp),
-    )
-    .map_err(debug_err!("db_read failed to read key from storage"))?;
-    context.use_gas_externally(used_gas);
+    if context.isolated {
+        debug!("db_read of an isolated contract, returning nothing");
+        return Ok(0);
+    }
+
+    let value = if let Some((value, used_gas)) = context.kv_cache.storage_read(&state_key_name) {
+        debug!("Got value from an earlier read of this call");
+        // Costs what the read from storage did, so not leaving the enclave doesn't change the gas
+        use_gas(instance, used_gas)?;
+        value
+    } else {
+        debug!("Missed value in cache");
+        let (value, used_gas) = read_from_encrypted_state(
+            &state_key_name,
+            &context.context,
+            &context.og_contract_key,
+            match context.operation {
+                ContractOperation::Init => true,
+                ContractOperation::Handle => true,
+                ContractOperation::Query => false,
+                ContractOperation::Migrate => true,
+            },
+            &mut context.kv_cache,
+            &get_encryption_salt(context.timestamp),
+        )
+        .map_err(debug_err!("db_read failed to read key from storage"))?;
+        context.use_gas_externally(used_gas);
+        context
+            .kv_cache
+            .store_storage_read(&state_key_name, value.as_deref(), used_gas);
+        value
+    };
 
     debug!(
         "db_read received value {:?}",
         value.as_ref().map(|v| show_bytes(v))
     );
 
+    #[cfg(feature = "conformance")]
+    crate::conformance::record_read(&state_key_name, value.as_deref());
+    #[cfg(feature = "audit")]
+    crate::audit::record_read(&state_key_name, value.as_deref());
+
     let value = match value {
         // Return 0 (null ponter) if value is empty
         Some(value) => value,
         None => return Ok(0),
     };
+    context.public_namespaces.taint();
 
     let region_ptr = write_to_memory(instance, &value)?;
 
@@ -976,6 +1487,47 @@ fn host_remove_db(
     )?;
 
     debug!("db_remove removing key {}", show_bytes(&state_key_name));
+    context.storage_ops.removes += 1;
+
+    #[cfg(feature = "conformance")]
+    crate::conformance::record_remove(&state_key_name);
+    #[cfg(feature = "audit")]
+    crate::audit::record_remove(&state_key_name);
+
+    if context.state_commitment && state_tree::is_reserved(&state_key_name) {
+        debug!("db_remove was called with a key reserved for the state commitment");
+        return Err(WasmEngineError::InvalidNamespace);
+    }
+
+    if let Some(namespace) = context.oblivious_namespaces.namespace_of(&state_key_name) {
+        use_gas(instance, WRITE_BASE_GAS * OBLIVIOUS_GAS_MULTIPLIER)?;
+
+        let (used_gas, pseudo_cost_for_write) = context
+            .oblivious_store()
+            .write(&namespace, &state_key_name, None)
+            .map_err(debug_err!(
+                "db_remove failed to remove key from oblivious storage"
+            ))?;
+        context.use_gas_externally(used_gas);
+        use_gas(instance, pseudo_cost_for_write)?; // Use gas now, refund later
+
+        return Ok(());
+    }
+
+    if context.public_namespaces.is_public(&state_key_name) {
+        context.public_namespaces.check_write(&state_key_name)?;
+
+        let used_gas = remove_public_state(&context.context, &state_key_name).map_err(
+            debug_err!("db_remove failed to remove key from public storage"),
+        )?;
+        context.use_gas_externally(used_gas);
+
+        if context.state_commitment {
+            update_state_tree(context, instance, &state_key_name, None)?;
+        }
+
+        return Ok(());
+    }
 
     // Also remove the key from the cache to avoid rewriting it
     context.kv_cache.remove(&state_key_name);
@@ -984,6 +1536,10 @@ fn host_remove_db(
         remove_from_encrypted_state(&state_key_name, &context.context, &context.og_contract_key)?;
     context.use_gas_externally(used_gas);
 
+    if context.state_commitment {
+        update_state_tree(context, instance, &state_key_name, None)?;
+    }
+
     Ok(())
 }
 
@@ -1012,9 +1568,149 @@ fn host_write_db(
         show_bytes(&value)
     );
 
+    #[cfg(feature = "conformance")]
+    crate::conformance::record_write(&state_key_name, &value);
+    #[cfg(feature = "audit")]
+    crate::audit::record_write(&state_key_name, &value);
+
+    if context.state_commitment && state_tree::is_reserved(&state_key_name) {
+        debug!("db_write was called with a key reserved for the state commitment");
+        return Err(WasmEngineError::InvalidNamespace);
+    }
+
+    if let Some(namespace) = context.oblivious_namespaces.namespace_of(&state_key_name) {
+        use_gas(instance, WRITE_BASE_GAS * (OBLIVIOUS_GAS_MULTIPLIER - 1))?;
+
+        let (used_gas, pseudo_cost_for_write) = context
+            .oblivious_store()
+            .write(&namespace, &state_key_name, Some(&value))
+            .map_err(debug_err!(
+                "db_write failed to write key to oblivious storage"
+            ))?;
+        context.use_gas_externally(used_gas);
+        use_gas(instance, pseudo_cost_for_write)?; // Use gas now, refund later
+
+        return Ok(());
+    }
+
+    if context.public_namespaces.is_public(&state_key_name) {
+        context.public_namespaces.check_write(&state_key_name)?;
+
+        let state_key = genesis_state_key().map_err(|_| WasmEngineError::EncryptionError)?;
+        let used_gas = write_public_state(
+            &context.context,
+            &state_key,
+            &context.og_contract_key,
+            &state_key_name,
+            &value,
+        )
+        .map_err(debug_err!("db_write failed to write key to public storage"))?;
+        context.use_gas_externally(used_gas);
+
+        if context.state_commitment {
+            update_state_tree(context, instance, &state_key_name, Some(&value))?;
+        }
+
+        return Ok(());
+    }
+
     let (_, pseudo_cost_for_write) = context.kv_cache.write(&state_key_name, &value);
     use_gas(instance, pseudo_cost_for_write)?; // Use gas now, refund later
 
+    if context.state_commitment {
+        update_state_tree(context, instance, &state_key_name, Some(&value))?;
+    }
+
+    Ok(())
+}
+
+/// Commits a write or remove of a contract whose code requires a state commitment
+fn update_state_tree(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    key: &[u8],
+    value: Option<&[u8]>,
+) -> WasmEngineResult<()> {
+    let node_gas = context.gas_costs.external_merkle_sha256_per_node as u64;
+    let hashed = with_merkle_store(context, instance, |store| {
+        StateTree::start(store, false)?.update(store, key, value)
+    })?;
+    use_gas(instance, hashed.saturating_mul(node_gas))
+}
+
+/// Opts a storage namespace into oblivious access for the rest of this call.
+/// See `oblivious_storage` for how accesses are hidden and what they cost.
+fn host_oblivious_namespace(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    namespace_region_ptr: i32,
+) -> WasmEngineResult<()> {
+    use_gas(instance, READ_BASE_GAS)?;
+
+    let namespace = read_from_memory(instance, namespace_region_ptr as u32).map_err(
+        debug_err!(err => "db_oblivious_namespace failed to extract vector from namespace_region_ptr: {err}"),
+    )?;
+
+    debug!(
+        "db_oblivious_namespace registering {}",
+        show_bytes(&namespace)
+    );
+
+    context.oblivious_namespaces.register(&namespace)
+}
+
+/// Stores a namespace in the clear for the rest of this call.
+/// See `public_state` for how public values are protected, and which writes are refused.
+fn host_public_namespace(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    namespace_region_ptr: i32,
+) -> WasmEngineResult<()> {
+    use_gas(instance, READ_BASE_GAS)?;
+
+    let namespace = read_from_memory(instance, namespace_region_ptr as u32).map_err(
+        debug_err!(err => "db_public_namespace failed to extract vector from namespace_region_ptr: {err}"),
+    )?;
+
+    debug!("db_public_namespace registering {}", show_bytes(&namespace));
+
+    context
+        .public_namespaces
+        .register(&namespace, NamespaceMode::Public)
+}
+
+/// Stores a namespace in the clear for the rest of this call, guarded by its tags only, so the
+/// call can write to it even after reading secret data
+fn host_integrity_namespace(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    namespace_region_ptr: i32,
+) -> WasmEngineResult<()> {
+    use_gas(instance, READ_BASE_GAS)?;
+
+    let namespace = read_from_memory(instance, namespace_region_ptr as u32).map_err(
+        debug_err!(err => "db_integrity_namespace failed to extract vector from namespace_region_ptr: {err}"),
+    )?;
+
+    debug!(
+        "db_integrity_namespace registering {}",
+        show_bytes(&namespace)
+    );
+
+    context
+        .public_namespaces
+        .register(&namespace, NamespaceMode::IntegrityOnly)
+}
+
+/// States that what the contract writes to its public namespaces next is meant to be public, even
+/// though it read secret data before
+fn host_declassify(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+) -> WasmEngineResult<()> {
+    use_gas(instance, READ_BASE_GAS)?;
+
+    context.public_namespaces.declassify();
     Ok(())
 }
 
@@ -1273,6 +1969,12 @@ fn host_query_chain(
         debug_err!(err => "query_chain failed to extract vector from query_region_ptr: {err}"),
     )?;
 
+    if context.isolated {
+        debug!("query_chain of an isolated contract, returning an error");
+        let answer = queries_unavailable_response()?;
+        return write_to_memory(instance, &answer).map(|region_ptr| region_ptr as i32);
+    }
+
     let mut used_gas: u64 = 0;
     let answer = encrypt_and_query_chain(
         &query_buffer,
@@ -1285,6 +1987,7 @@ fn host_query_chain(
     )?;
 
     context.use_gas_externally(used_gas);
+    context.public_namespaces.taint();
 
     write_to_memory(instance, &answer).map(|region_ptr| region_ptr as i32)
 }
@@ -1865,45 +2568,1832 @@ fn host_ed25519_sign(
     Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
 }
 
-fn host_dcap_quote_verify(
-    context: &mut Context,
-    instance: &wasm3::Instance<Context>,
-    (quote_ptr, collateral_ptr): (i32, i32),
-) -> WasmEngineResult<i64> {
-    let used_gas = context.gas_costs.external_dcap_quote_verify as u64;
-    use_gas(instance, used_gas)?;
+type RsaVerifyFn = fn(&[u8], &[u8], &[u8], &[u8]) -> Result<(), RsaError>;
 
-    let quote_data = read_from_memory(instance, quote_ptr as u32).map_err(
-        debug_err!(err => "dcap_quote_verify error while trying to read message_hash from wasm memory: {err}")
-    )?;
-    let collateral_data = read_from_memory(instance, collateral_ptr as u32).map_err(
-        debug_err!(err => "dcap_quote_verify error while trying to read private key from wasm memory: {err}")
-    )?;
+/// The cost only depends on the size of the key, since the exponent is at most 32 bits
+fn rsa_verify_gas(gas_costs: &WasmCosts, modulus: &[u8]) -> u64 {
+    (match modulus.len() {
+        512 => gas_costs.external_rsa_verify_4096,
+        384 => gas_costs.external_rsa_verify_3072,
+        _ => gas_costs.external_rsa_verify_2048,
+    }) as u64
+}
 
-    trace!("dcap_quote_verify() was called from WASM code");
+fn rsa_verify(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    name: &str,
+    (message_hash_ptr, signature_ptr, modulus_ptr, exponent_ptr): (i32, i32, i32, i32),
+    verify: RsaVerifyFn,
+) -> WasmEngineResult<i32> {
+    let message_hash = read_operand(instance, name, message_hash_ptr)?;
+    let signature = read_operand(instance, name, signature_ptr)?;
+    let modulus = read_operand(instance, name, modulus_ptr)?;
+    let exponent = read_operand(instance, name, exponent_ptr)?;
 
-    // check private_key input
-    if collateral_data.is_empty() {
-        debug!("dcap_quote_verify called with empty collateral");
-        return Ok(to_high_half(WasmApiCryptoError::GenericErr as u32) as i64);
-    }
+    let used_gas = rsa_verify_gas(&context.gas_costs, &modulus);
+    use_gas(instance, used_gas)?;
 
-    let tm_ns = context.timestamp;
-    let tm_s = (tm_ns / 1000000000) as i64;
+    trace!(
+        "{}() was called from WASM code with a {} bit modulus",
+        name,
+        modulus.len() * 8
+    );
 
-    match verify_quote_any(&quote_data, &collateral_data, tm_s) {
-        Ok(val) => Ok(to_low_half(val as u32) as i64),
+    match verify(&message_hash, &signature, &modulus, &exponent) {
+        Ok(()) => {
+            // return 0 == success, valid signature
+            Ok(0)
+        }
+        Err(RsaError::VerificationFailed) => {
+            debug!("{}() failed to verify signature", name);
+            // return 1 == failed, invalid signature
+            Ok(1)
+        }
         Err(err) => {
-            debug!("dcap_quote_verify error {}", err);
-            Ok(to_high_half(err as u32) as i64)
+            debug!("{}() malformed input: {:?}", name, err);
+            Ok(match err {
+                RsaError::InvalidHashFormat => WasmApiCryptoError::InvalidHashFormat,
+                RsaError::InvalidSignatureFormat => WasmApiCryptoError::InvalidSignatureFormat,
+                _ => WasmApiCryptoError::InvalidPubkeyFormat,
+            } as i32)
         }
     }
 }
 
-fn get_encryption_salt(timestamp: u64) -> Vec<u8> {
-    let mut encryption_salt: Vec<u8> = vec![];
+/// Verifies an RSASSA-PKCS1-v1_5 signature of a SHA-256, SHA-384 or SHA-512 message hash
+fn host_rsa_pkcs1v15_verify(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    ptrs: (i32, i32, i32, i32),
+) -> WasmEngineResult<i32> {
+    rsa_verify(
+        context,
+        instance,
+        "rsa_pkcs1v15_verify",
+        ptrs,
+        rsa::verify_pkcs1v15,
+    )
+}
 
-    encryption_salt.extend(timestamp.to_be_bytes());
+/// Verifies an RSASSA-PSS signature of a SHA-256, SHA-384 or SHA-512 message hash
+fn host_rsa_pss_verify(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    ptrs: (i32, i32, i32, i32),
+) -> WasmEngineResult<i32> {
+    rsa_verify(context, instance, "rsa_pss_verify", ptrs, rsa::verify_pss)
+}
+
+fn musig2_error_code(err: MuSig2Error) -> WasmApiCryptoError {
+    match err {
+        MuSig2Error::InvalidHashFormat => WasmApiCryptoError::InvalidHashFormat,
+        MuSig2Error::InvalidSignatureFormat => WasmApiCryptoError::InvalidSignatureFormat,
+        MuSig2Error::InvalidPubkeyFormat => WasmApiCryptoError::InvalidPubkeyFormat,
+        _ => WasmApiCryptoError::GenericErr,
+    }
+}
+
+/// Aggregates a list of compressed secp256k1 keys with MuSig2, into an x-only key
+fn host_musig2_key_agg(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    public_keys_ptr: i32,
+) -> WasmEngineResult<i64> {
+    let public_keys = decode_sections_from_memory(instance, public_keys_ptr as u32).map_err(
+        debug_err!(err => "musig2_key_agg error while trying to read public_keys from wasm memory: {err}")
+    )?;
+
+    let used_gas =
+        context.gas_costs.external_musig2_key_agg_per_key as u64 * public_keys.len() as u64;
+    use_gas(instance, used_gas)?;
+
+    trace!(
+        "musig2_key_agg() was called from WASM code with {} keys",
+        public_keys.len()
+    );
+
+    let public_keys: Vec<&[u8]> = public_keys.iter().map(Vec::as_slice).collect();
+    let aggregate_key = match musig2::key_agg(&public_keys) {
+        Ok(key) => key,
+        Err(err) => {
+            debug!("musig2_key_agg() failed: {:?}", err);
+            return Ok(to_high_half(musig2_error_code(err) as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &aggregate_key).map_err(|err| {
+        debug!(
+            "musig2_key_agg() error while trying to allocate and write the result to the WASM VM"
+        );
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Verifies a BIP-340 Schnorr signature of a 32-byte message by an x-only key
+fn host_secp256k1_schnorr_verify(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (message_hash_ptr, signature_ptr, public_key_ptr): (i32, i32, i32),
+) -> WasmEngineResult<i32> {
+    let name = "secp256k1_schnorr_verify";
+    let used_gas = context.gas_costs.external_secp256k1_schnorr_verify as u64;
+    use_gas(instance, used_gas)?;
+
+    let message_hash = read_operand(instance, name, message_hash_ptr)?;
+    let signature = read_operand(instance, name, signature_ptr)?;
+    let public_key = read_operand(instance, name, public_key_ptr)?;
+
+    trace!("{}() was called from WASM code", name);
+
+    match musig2::schnorr_verify(&message_hash, &signature, &public_key) {
+        Ok(()) => {
+            // return 0 == success, valid signature
+            Ok(0)
+        }
+        Err(MuSig2Error::VerificationFailed) => {
+            debug!("{}() failed to verify signature", name);
+            // return 1 == failed, invalid signature
+            Ok(1)
+        }
+        Err(err) => {
+            debug!("{}() malformed input: {:?}", name, err);
+            Ok(musig2_error_code(err) as i32)
+        }
+    }
+}
+
+fn host_dcap_quote_verify(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (quote_ptr, collateral_ptr): (i32, i32),
+) -> WasmEngineResult<i64> {
+    let used_gas = context.gas_costs.external_dcap_quote_verify as u64;
+    use_gas(instance, used_gas)?;
+
+    let quote_data = read_from_memory(instance, quote_ptr as u32).map_err(
+        debug_err!(err => "dcap_quote_verify error while trying to read message_hash from wasm memory: {err}")
+    )?;
+    let collateral_data = read_from_memory(instance, collateral_ptr as u32).map_err(
+        debug_err!(err => "dcap_quote_verify error while trying to read private key from wasm memory: {err}")
+    )?;
+
+    trace!("dcap_quote_verify() was called from WASM code");
+
+    // check private_key input
+    if collateral_data.is_empty() {
+        debug!("dcap_quote_verify called with empty collateral");
+        return Ok(to_high_half(WasmApiCryptoError::GenericErr as u32) as i64);
+    }
+
+    let tm_ns = context.timestamp;
+    let tm_s = (tm_ns / 1000000000) as i64;
+
+    match verify_quote_any(&quote_data, &collateral_data, tm_s) {
+        Ok(val) => Ok(to_low_half(val as u32) as i64),
+        Err(err) => {
+            debug!("dcap_quote_verify error {}", err);
+            Ok(to_high_half(err as u32) as i64)
+        }
+    }
+}
+
+fn read_drand_beacon(
+    instance: &wasm3::Instance<Context>,
+    signature_ptr: i32,
+    previous_signature_ptr: i32,
+) -> WasmEngineResult<(Vec<u8>, Vec<u8>)> {
+    let signature = read_from_memory(instance, signature_ptr as u32).map_err(
+        debug_err!(err => "drand error while trying to read signature from wasm memory: {err}"),
+    )?;
+
+    // unchained beacons don't have a previous signature, so a null pointer is allowed here
+    let previous_signature = if previous_signature_ptr == 0 {
+        vec![]
+    } else {
+        read_from_memory(instance, previous_signature_ptr as u32).map_err(
+            debug_err!(err => "drand error while trying to read previous_signature from wasm memory: {err}"),
+        )?
+    };
+
+    Ok((signature, previous_signature))
+}
+
+fn host_drand_verify(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (round, signature_ptr, previous_signature_ptr): (i64, i32, i32),
+) -> WasmEngineResult<i32> {
+    let used_gas = context.gas_costs.external_drand_verify as u64;
+    use_gas(instance, used_gas)?;
+
+    let (signature, previous_signature) =
+        read_drand_beacon(instance, signature_ptr, previous_signature_ptr)?;
+
+    trace!(
+        "drand_verify() was called from WASM code for round {}",
+        round
+    );
+
+    let beacon = DrandBeacon {
+        round: round as u64,
+        signature: &signature,
+        previous_signature: &previous_signature,
+    };
+
+    match verify_drand_beacon(&beacon) {
+        Err(err) => {
+            debug!("drand_verify() failed to verify beacon: {:?}", err);
+            // return 1 == failed, invalid beacon
+            Ok(1)
+        }
+        // return 0 == success, valid beacon
+        Ok(()) => Ok(0),
+    }
+}
+
+/// Verifies a drand beacon and returns its randomness mixed with the enclave's private
+/// randomness seed. The result is bound to the calling contract, so it's unpredictable
+/// to anyone who only sees the public beacon, but deterministic across nodes.
+fn host_drand_randomness(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (round, signature_ptr, previous_signature_ptr): (i64, i32, i32),
+) -> WasmEngineResult<i64> {
+    let used_gas = context.gas_costs.external_drand_verify as u64;
+    use_gas(instance, used_gas)?;
+
+    let (signature, previous_signature) =
+        read_drand_beacon(instance, signature_ptr, previous_signature_ptr)?;
+
+    trace!(
+        "drand_randomness() was called from WASM code for round {}",
+        round
+    );
+
+    let beacon = DrandBeacon {
+        round: round as u64,
+        signature: &signature,
+        previous_signature: &previous_signature,
+    };
+
+    if let Err(err) = verify_drand_beacon(&beacon) {
+        debug!("drand_randomness() failed to verify beacon: {:?}", err);
+        return Ok(to_high_half(WasmApiCryptoError::InvalidSignatureFormat as u32) as i64);
+    }
+
+    let enclave_seed = match KEY_MANAGER.initial_randomness_seed {
+        Some(seed) => seed,
+        None => {
+            error!("drand_randomness() called before the randomness seed was initialized");
+            return Ok(to_high_half(WasmApiCryptoError::GenericErr as u32) as i64);
+        }
+    };
+
+    let round_bytes = (round as u64).to_be_bytes();
+    let info: Vec<&[u8]> = vec![
+        enclave_seed.get(),
+        context.og_contract_key.as_slice(),
+        round_bytes.as_slice(),
+    ];
+    let mixed = enclave_crypto::hkdf_sha_256(&beacon.randomness(), info.as_slice());
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, mixed.get()).map_err(|err| {
+        debug!("drand_randomness() error while trying to allocate and write the randomness to the WASM VM");
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Verifies an HTTPS fetch attestation signed by an oracle fetcher enclave. The fetcher's
+/// quote must verify at the current block time and carry the same MRSIGNER as this enclave.
+fn host_oracle_attestation_verify(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (attestation_ptr, signature_ptr, quote_ptr, collateral_ptr): (i32, i32, i32, i32),
+) -> WasmEngineResult<i32> {
+    let used_gas = context.gas_costs.external_oracle_attestation_verify as u64;
+    use_gas(instance, used_gas)?;
+
+    let attestation = read_from_memory(instance, attestation_ptr as u32).map_err(
+        debug_err!(err => "oracle_attestation_verify error while trying to read attestation from wasm memory: {err}")
+    )?;
+    let signature = read_from_memory(instance, signature_ptr as u32).map_err(
+        debug_err!(err => "oracle_attestation_verify error while trying to read signature from wasm memory: {err}")
+    )?;
+    let quote = read_from_memory(instance, quote_ptr as u32).map_err(
+        debug_err!(err => "oracle_attestation_verify error while trying to read quote from wasm memory: {err}")
+    )?;
+    let collateral = read_from_memory(instance, collateral_ptr as u32).map_err(
+        debug_err!(err => "oracle_attestation_verify error while trying to read collateral from wasm memory: {err}")
+    )?;
+
+    trace!("oracle_attestation_verify() was called from WASM code");
+
+    if collateral.is_empty() {
+        debug!("oracle_attestation_verify called with empty collateral");
+        return Ok(1);
+    }
+
+    let tm_s = (context.timestamp / 1000000000) as i64;
+
+    match verify_oracle_attestation(&attestation, &signature, &quote, &collateral, tm_s) {
+        Err(err) => {
+            debug!("oracle_attestation_verify() failed: {:?}", err);
+            // return 1 == failed, invalid attestation
+            Ok(1)
+        }
+        Ok(fetch) => {
+            trace!(
+                "oracle_attestation_verify() verified fetch of {:?} at {}",
+                String::from_utf8_lossy(&fetch.url),
+                fetch.timestamp
+            );
+            // return 0 == success, valid attestation
+            Ok(0)
+        }
+    }
+}
+
+/// Seals the input of an off-chain job for the worker enclave that runs its handler, see
+/// `job_message`. Returns a `JobSealError` code in the high half if the input is rejected.
+fn host_job_seal_input(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (handler_code_hash_ptr, input_ptr): (i32, i32),
+) -> WasmEngineResult<i64> {
+    let handler_code_hash = read_from_memory(instance, handler_code_hash_ptr as u32).map_err(
+        debug_err!(err => "job_seal_input error while trying to read handler_code_hash from wasm memory: {err}")
+    )?;
+    let input = read_from_memory(instance, input_ptr as u32).map_err(
+        debug_err!(err => "job_seal_input error while trying to read input from wasm memory: {err}")
+    )?;
+
+    let used_gas = (context.gas_costs.external_job_seal_input_base as u64).saturating_add(
+        (context.gas_costs.external_job_seal_input_per_byte as u64)
+            .saturating_mul(input.len() as u64),
+    );
+    use_gas(instance, used_gas)?;
+
+    trace!("job_seal_input() was called from WASM code");
+
+    let state_ikm = KEY_MANAGER
+        .get_consensus_state_ikm()
+        .map_err(|_| WasmEngineError::EncryptionError)?;
+    let sealed = match seal_job_input(
+        &state_ikm.genesis,
+        &context.og_contract_key,
+        &handler_code_hash,
+        &input,
+    ) {
+        Ok(sealed) => sealed,
+        Err(err) => {
+            debug!("job_seal_input() rejected the input: {:?}", err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &sealed).map_err(|err| {
+        debug!(
+            "job_seal_input() error while trying to allocate and write the result to the WASM VM"
+        );
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Decrypts an attribute this contract emitted to a disclosure group. The contract is
+/// responsible for checking that the caller is allowed to see the group's attributes,
+/// typically with a viewing key, before calling this.
+fn host_decrypt_disclosed_attribute(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (group_ptr, ciphertext_ptr): (i32, i32),
+) -> WasmEngineResult<i64> {
+    let used_gas = context.gas_costs.external_decrypt_disclosed_attribute as u64;
+    use_gas(instance, used_gas)?;
+
+    let group = read_from_memory(instance, group_ptr as u32).map_err(
+        debug_err!(err => "decrypt_disclosed_attribute error while trying to read group from wasm memory: {err}")
+    )?;
+    let ciphertext = read_from_memory(instance, ciphertext_ptr as u32).map_err(
+        debug_err!(err => "decrypt_disclosed_attribute error while trying to read ciphertext from wasm memory: {err}")
+    )?;
+
+    trace!("decrypt_disclosed_attribute() was called from WASM code");
+
+    let group = match String::from_utf8(group) {
+        Ok(group) => group,
+        Err(_) => {
+            debug!("decrypt_disclosed_attribute() got a group that is not valid utf8");
+            return Ok(to_high_half(WasmApiCryptoError::GenericErr as u32) as i64);
+        }
+    };
+
+    let plaintext = match decrypt_disclosed_attribute(&context.og_contract_key, &group, &ciphertext)
+    {
+        Ok(plaintext) => plaintext,
+        Err(err) => {
+            debug!("decrypt_disclosed_attribute() failed: {:?}", err);
+            return Ok(to_high_half(WasmApiCryptoError::GenericErr as u32) as i64);
+        }
+    };
+    context.public_namespaces.taint();
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &plaintext).map_err(|err| {
+        debug!("decrypt_disclosed_attribute() error while trying to allocate and write the plaintext to the WASM VM");
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Writes the result of a contract sub-key host function, or returns a `ContractSubkeyError`
+/// code in the high half if it failed.
+fn write_subkey_result(
+    instance: &wasm3::Instance<Context>,
+    name: &str,
+    result: Result<Vec<u8>, ContractSubkeyError>,
+) -> WasmEngineResult<i64> {
+    let result = match result {
+        Ok(result) => result,
+        Err(err) => {
+            debug!("{}() failed: {:?}", name, err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &result).map_err(|err| {
+        debug!(
+            "{}() error while trying to allocate and write the result to the WASM VM",
+            name
+        );
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Writes the x25519 public key of the contract's sub-key at a path. Only the contract can
+/// decrypt what's encrypted to it, with `contract_decrypt`.
+fn host_derive_contract_keypair(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    path_ptr: i32,
+) -> WasmEngineResult<i64> {
+    use_gas(instance, context.gas_costs.external_contract_subkey as u64)?;
+
+    let path = read_from_memory(instance, path_ptr as u32).map_err(
+        debug_err!(err => "derive_contract_keypair error while trying to read path from wasm memory: {err}")
+    )?;
+
+    trace!("derive_contract_keypair() was called from WASM code");
+
+    let state_key = genesis_state_key().map_err(|_| WasmEngineError::EncryptionError)?;
+    let result = subkey_public_key(&state_key, &context.og_contract_key, &path)
+        .map(|public_key| public_key.to_vec());
+    write_subkey_result(instance, "derive_contract_keypair", result)
+}
+
+/// Decrypts a ciphertext that was encrypted to the contract's sub-key at a path
+fn host_contract_decrypt(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (path_ptr, ciphertext_ptr): (i32, i32),
+) -> WasmEngineResult<i64> {
+    let path = read_from_memory(instance, path_ptr as u32).map_err(
+        debug_err!(err => "contract_decrypt error while trying to read path from wasm memory: {err}")
+    )?;
+    let ciphertext = read_from_memory(instance, ciphertext_ptr as u32).map_err(
+        debug_err!(err => "contract_decrypt error while trying to read ciphertext from wasm memory: {err}")
+    )?;
+
+    let used_gas = (context.gas_costs.external_contract_subkey as u64).saturating_add(
+        (context.gas_costs.external_symmetric_crypto_per_byte as u64)
+            .saturating_mul(ciphertext.len() as u64),
+    );
+    use_gas(instance, used_gas)?;
+
+    trace!("contract_decrypt() was called from WASM code");
+
+    let state_key = genesis_state_key().map_err(|_| WasmEngineError::DecryptionError)?;
+    let result = subkey_decrypt(&state_key, &context.og_contract_key, &path, &ciphertext);
+    if result.is_ok() {
+        context.public_namespaces.taint();
+    }
+    write_subkey_result(instance, "contract_decrypt", result)
+}
+
+/// Writes the result of a token metadata host function, or returns a `TokenMetadataError` code
+/// in the high half if it failed.
+fn write_token_metadata_result(
+    instance: &wasm3::Instance<Context>,
+    name: &str,
+    result: Result<Vec<u8>, TokenMetadataError>,
+) -> WasmEngineResult<i64> {
+    let result = match result {
+        Ok(result) => result,
+        Err(err) => {
+            debug!("{}() failed: {:?}", name, err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &result).map_err(|err| {
+        debug!(
+            "{}() error while trying to allocate and write the result to the WASM VM",
+            name
+        );
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+fn token_metadata_gas(gas_costs: &WasmCosts, bytes: usize) -> u64 {
+    (gas_costs.external_token_metadata_base as u64).saturating_add(
+        (gas_costs.external_symmetric_crypto_per_byte as u64).saturating_mul(bytes as u64),
+    )
+}
+
+/// Encrypts the private metadata of a token with the token's key, which the contract never sees
+fn host_token_metadata_seal(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (token_id_ptr, metadata_ptr): (i32, i32),
+) -> WasmEngineResult<i64> {
+    let token_id = read_from_memory(instance, token_id_ptr as u32).map_err(
+        debug_err!(err => "token_metadata_seal error while trying to read token_id from wasm memory: {err}")
+    )?;
+    let metadata = read_from_memory(instance, metadata_ptr as u32).map_err(
+        debug_err!(err => "token_metadata_seal error while trying to read metadata from wasm memory: {err}")
+    )?;
+
+    let used_gas = token_metadata_gas(&context.gas_costs, token_id.len() + metadata.len());
+    use_gas(instance, used_gas)?;
+
+    trace!("token_metadata_seal() was called from WASM code");
+
+    let state_key = genesis_state_key().map_err(|_| WasmEngineError::EncryptionError)?;
+    let result = seal_token_metadata(&state_key, &context.og_contract_key, &token_id, &metadata);
+    write_token_metadata_result(instance, "token_metadata_seal", result)
+}
+
+/// Decrypts the private metadata of a token, if the querier's authorization, a permit or a
+/// viewing key, authorizes one of the viewers in the token's ACL
+fn host_token_metadata_open(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (token_id_ptr, ciphertext_ptr, acl_ptr, authorization_ptr): (i32, i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    let token_id = read_from_memory(instance, token_id_ptr as u32).map_err(
+        debug_err!(err => "token_metadata_open error while trying to read token_id from wasm memory: {err}")
+    )?;
+    let ciphertext = read_from_memory(instance, ciphertext_ptr as u32).map_err(
+        debug_err!(err => "token_metadata_open error while trying to read ciphertext from wasm memory: {err}")
+    )?;
+    let acl = read_from_memory(instance, acl_ptr as u32).map_err(
+        debug_err!(err => "token_metadata_open error while trying to read acl from wasm memory: {err}")
+    )?;
+    let authorization = read_from_memory(instance, authorization_ptr as u32).map_err(
+        debug_err!(err => "token_metadata_open error while trying to read authorization from wasm memory: {err}")
+    )?;
+
+    let bytes = token_id.len() + ciphertext.len() + acl.len() + authorization.len();
+    let used_gas = token_metadata_gas(&context.gas_costs, bytes)
+        .saturating_add(context.gas_costs.external_secp256k1_verify as u64);
+    use_gas(instance, used_gas)?;
+
+    trace!("token_metadata_open() was called from WASM code");
+
+    let state_key = genesis_state_key().map_err(|_| WasmEngineError::DecryptionError)?;
+    let result = open_token_metadata(
+        &state_key,
+        &context.og_contract_key,
+        &token_id,
+        &ciphertext,
+        &acl,
+        &authorization,
+    );
+    if result.is_ok() {
+        context.public_namespaces.taint();
+    }
+    write_token_metadata_result(instance, "token_metadata_open", result)
+}
+
+/// Checks that `app_hash` is the app hash in the header of another chain at `height`, as
+/// verified by the enclave's light client of that chain. Contracts can then verify proofs of the
+/// chain's state against it, without trusting the relayer.
+fn host_foreign_app_hash_verify(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (chain_id_ptr, height, app_hash_ptr): (i32, i64, i32),
+) -> WasmEngineResult<i32> {
+    let used_gas = context.gas_costs.external_foreign_app_hash_verify as u64;
+    use_gas(instance, used_gas)?;
+
+    let chain_id = read_from_memory(instance, chain_id_ptr as u32).map_err(
+        debug_err!(err => "foreign_app_hash_verify error while trying to read chain_id from wasm memory: {err}")
+    )?;
+    let app_hash = read_from_memory(instance, app_hash_ptr as u32).map_err(
+        debug_err!(err => "foreign_app_hash_verify error while trying to read app_hash from wasm memory: {err}")
+    )?;
+
+    trace!(
+        "foreign_app_hash_verify() was called from WASM code for height {}",
+        height
+    );
+
+    let chain_id = match String::from_utf8(chain_id) {
+        Ok(chain_id) => chain_id,
+        Err(_) => {
+            debug!("foreign_app_hash_verify() got a chain id that is not valid utf8");
+            return Ok(1);
+        }
+    };
+
+    match foreign_app_hash_at(&chain_id, height as u64) {
+        // return 0 == success, the header is trusted and has this app hash
+        Some(trusted) if trusted == app_hash => Ok(0),
+        Some(_) => {
+            debug!("foreign_app_hash_verify() got a different app hash than the trusted header");
+            // return 1 == failed
+            Ok(1)
+        }
+        None => {
+            debug!(
+                "foreign_app_hash_verify() has no trusted header of {} at {}",
+                chain_id, height
+            );
+            // return 1 == failed
+            Ok(1)
+        }
+    }
+}
+
+/// Writes the random of an earlier block, derived for this contract. Returns a `PastRandomError`
+/// code in the high half if the block is too recent, too old, or has no random in x/compute.
+fn host_block_random_at(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    height: i64,
+) -> WasmEngineResult<i64> {
+    let used_gas = context.gas_costs.external_block_random_at as u64;
+    use_gas(instance, used_gas)?;
+
+    trace!(
+        "block_random_at() was called from WASM code for height {}",
+        height
+    );
+
+    let mut query_used_gas: u64 = 0;
+    let result = past_random(
+        &context.context,
+        context.query_depth,
+        &context.og_contract_key,
+        height as u64,
+        &mut query_used_gas,
+        get_remaining_gas(instance),
+    );
+    context.use_gas_externally(query_used_gas);
+
+    let random = match result {
+        Ok(random) => random,
+        Err(err) => {
+            debug!("block_random_at() has no random for {}: {:?}", height, err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, random.as_slice()).map_err(|err| {
+        debug!(
+            "block_random_at() error while trying to allocate and write the result to the WASM VM"
+        );
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Writes the RFC 8785 canonical form of a JSON document, so contracts can hash or compare
+/// JSON regardless of how the sender serialized it. Returns a `CanonicalJsonError` code in the
+/// high half if the document is rejected.
+fn host_canonicalize_json(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    json_ptr: i32,
+) -> WasmEngineResult<i64> {
+    let json = read_from_memory(instance, json_ptr as u32).map_err(
+        debug_err!(err => "canonicalize_json error while trying to read json from wasm memory: {err}")
+    )?;
+
+    let used_gas = context.gas_costs.external_canonicalize_json_base as u64
+        + context.gas_costs.external_canonicalize_json_per_byte as u64 * json.len() as u64;
+    use_gas(instance, used_gas)?;
+
+    trace!("canonicalize_json() was called from WASM code");
+
+    let canonical = match canonicalize(&json) {
+        Ok(canonical) => canonical,
+        Err(err) => {
+            debug!("canonicalize_json() rejected the document: {:?}", err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &canonical).map_err(|err| {
+        debug!("canonicalize_json() error while trying to allocate and write the result to the WASM VM");
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+fn read_operand(
+    instance: &wasm3::Instance<Context>,
+    name: &str,
+    ptr: i32,
+) -> WasmEngineResult<Vec<u8>> {
+    read_from_memory(instance, ptr as u32).map_err(|err| {
+        debug!(
+            "{}() error while trying to read an operand from wasm memory: {:?}",
+            name, err
+        );
+        err
+    })
+}
+
+/// Writes the result of a fixed-point host function, or returns a `FixedPointError` code in the
+/// high half if the operation failed.
+fn write_fixed_point_result(
+    instance: &wasm3::Instance<Context>,
+    name: &str,
+    result: Result<[u8; 32], FixedPointError>,
+) -> WasmEngineResult<i64> {
+    let result = match result {
+        Ok(result) => result,
+        Err(err) => {
+            debug!("{}() failed: {:?}", name, err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &result).map_err(|err| {
+        debug!(
+            "{}() error while trying to allocate and write the result to the WASM VM",
+            name
+        );
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Multiplies two numbers with 18 decimals. `rounding` is 0 for floor, 1 for ceil and 2 for
+/// nearest.
+fn host_fixed_point_mul(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (a_ptr, b_ptr, rounding): (i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    use_gas(
+        instance,
+        context.gas_costs.external_fixed_point_arithmetic as u64,
+    )?;
+
+    let a = read_operand(instance, "fixed_point_mul", a_ptr)?;
+    let b = read_operand(instance, "fixed_point_mul", b_ptr)?;
+
+    trace!("fixed_point_mul() was called from WASM code");
+
+    let result = fixed_point::mul(&a, &b, rounding as u32);
+    write_fixed_point_result(instance, "fixed_point_mul", result)
+}
+
+/// Divides two numbers with 18 decimals, rounded like `fixed_point_mul`
+fn host_fixed_point_div(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (a_ptr, b_ptr, rounding): (i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    use_gas(
+        instance,
+        context.gas_costs.external_fixed_point_arithmetic as u64,
+    )?;
+
+    let a = read_operand(instance, "fixed_point_div", a_ptr)?;
+    let b = read_operand(instance, "fixed_point_div", b_ptr)?;
+
+    trace!("fixed_point_div() was called from WASM code");
+
+    let result = fixed_point::div(&a, &b, rounding as u32);
+    write_fixed_point_result(instance, "fixed_point_div", result)
+}
+
+fn host_fixed_point_ln(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    x_ptr: i32,
+) -> WasmEngineResult<i64> {
+    use_gas(
+        instance,
+        context.gas_costs.external_fixed_point_transcendental as u64,
+    )?;
+
+    let x = read_operand(instance, "fixed_point_ln", x_ptr)?;
+
+    trace!("fixed_point_ln() was called from WASM code");
+
+    write_fixed_point_result(instance, "fixed_point_ln", fixed_point::ln(&x))
+}
+
+fn host_fixed_point_exp(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    x_ptr: i32,
+) -> WasmEngineResult<i64> {
+    use_gas(
+        instance,
+        context.gas_costs.external_fixed_point_transcendental as u64,
+    )?;
+
+    let x = read_operand(instance, "fixed_point_exp", x_ptr)?;
+
+    trace!("fixed_point_exp() was called from WASM code");
+
+    write_fixed_point_result(instance, "fixed_point_exp", fixed_point::exp(&x))
+}
+
+fn host_fixed_point_pow(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (base_ptr, exponent_ptr): (i32, i32),
+) -> WasmEngineResult<i64> {
+    use_gas(
+        instance,
+        context.gas_costs.external_fixed_point_transcendental as u64,
+    )?;
+
+    let base = read_operand(instance, "fixed_point_pow", base_ptr)?;
+    let exponent = read_operand(instance, "fixed_point_pow", exponent_ptr)?;
+
+    trace!("fixed_point_pow() was called from WASM code");
+
+    let result = fixed_point::pow(&base, &exponent);
+    write_fixed_point_result(instance, "fixed_point_pow", result)
+}
+
+/// Writes the result of a big integer host function, or returns a `BigIntError` code in the
+/// high half if the operation failed.
+fn write_big_int_result(
+    instance: &wasm3::Instance<Context>,
+    name: &str,
+    result: Result<Vec<u8>, BigIntError>,
+) -> WasmEngineResult<i64> {
+    let result = match result {
+        Ok(result) => result,
+        Err(err) => {
+            debug!("{}() failed: {:?}", name, err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &result).map_err(|err| {
+        debug!(
+            "{}() error while trying to allocate and write the result to the WASM VM",
+            name
+        );
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Writes the 8 byte big-endian timestamp a calendar host function computed, or returns a
+/// `CalendarError` code in the high half if it failed.
+fn write_timestamp_result(
+    instance: &wasm3::Instance<Context>,
+    name: &str,
+    result: Result<u64, CalendarError>,
+) -> WasmEngineResult<i64> {
+    let result = match result {
+        Ok(result) => result,
+        Err(err) => {
+            debug!("{}() failed: {:?}", name, err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm =
+        write_to_memory(instance, &result.to_be_bytes()).map_err(|err| {
+            debug!(
+                "{}() error while trying to allocate and write the result to the WASM VM",
+                name
+            );
+            err
+        })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Moves a timestamp, in nanoseconds, by a number of calendar months, clamping the day to the
+/// end of the target month
+fn host_timestamp_add_months(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (timestamp, months): (i64, i32),
+) -> WasmEngineResult<i64> {
+    use_gas(
+        instance,
+        context.gas_costs.external_timestamp_arithmetic as u64,
+    )?;
+
+    trace!("timestamp_add_months() was called from WASM code");
+
+    let result = calendar::add_months(timestamp as u64, months);
+    write_timestamp_result(instance, "timestamp_add_months", result)
+}
+
+/// Rounds a timestamp down to the start of its day (0), ISO week (1), month (2) or year (3)
+fn host_timestamp_truncate(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (timestamp, unit): (i64, i32),
+) -> WasmEngineResult<i64> {
+    use_gas(
+        instance,
+        context.gas_costs.external_timestamp_arithmetic as u64,
+    )?;
+
+    trace!("timestamp_truncate() was called from WASM code");
+
+    let result = calendar::truncate(timestamp as u64, unit as u32);
+    write_timestamp_result(instance, "timestamp_truncate", result)
+}
+
+/// Returns the ISO weekday of a timestamp, 1 for Monday to 7 for Sunday
+fn host_timestamp_weekday(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    timestamp: i64,
+) -> WasmEngineResult<i32> {
+    use_gas(
+        instance,
+        context.gas_costs.external_timestamp_arithmetic as u64,
+    )?;
+
+    trace!("timestamp_weekday() was called from WASM code");
+
+    Ok(calendar::weekday(timestamp as u64) as i32)
+}
+
+/// Adds, subtracts, multiplies, divides or takes the remainder of two unsigned 512-bit integers,
+/// for `op` from 0 to 4
+fn host_uint512_op(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (op, a_ptr, b_ptr): (i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    use_gas(instance, context.gas_costs.external_int512_op as u64)?;
+
+    let a = read_operand(instance, "uint512_op", a_ptr)?;
+    let b = read_operand(instance, "uint512_op", b_ptr)?;
+
+    trace!("uint512_op() was called from WASM code with op {}", op);
+
+    let result = big_int::uint512_op(op as u32, &a, &b);
+    write_big_int_result(instance, "uint512_op", result)
+}
+
+/// Like `uint512_op`, for two's complement 512-bit integers
+fn host_int512_op(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (op, a_ptr, b_ptr): (i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    use_gas(instance, context.gas_costs.external_int512_op as u64)?;
+
+    let a = read_operand(instance, "int512_op", a_ptr)?;
+    let b = read_operand(instance, "int512_op", b_ptr)?;
+
+    trace!("int512_op() was called from WASM code with op {}", op);
+
+    let result = big_int::int512_op(op as u32, &a, &b);
+    write_big_int_result(instance, "int512_op", result)
+}
+
+/// Computes base^exponent % modulus like the EVM's MODEXP precompile, for the same price
+/// relative to the EVM's other precompiles
+fn host_modexp(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (base_ptr, exponent_ptr, modulus_ptr): (i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    let base = read_operand(instance, "modexp", base_ptr)?;
+    let exponent = read_operand(instance, "modexp", exponent_ptr)?;
+    let modulus = read_operand(instance, "modexp", modulus_ptr)?;
+
+    let evm_gas = big_int::modexp_evm_gas(base.len(), &exponent, modulus.len());
+    use_gas(
+        instance,
+        evm_gas.saturating_mul(context.gas_costs.external_modexp_per_evm_gas as u64),
+    )?;
+
+    trace!(
+        "modexp() was called from WASM code with {} byte operands",
+        max(base.len(), max(exponent.len(), modulus.len()))
+    );
+
+    let result = big_int::modexp(&base, &exponent, &modulus);
+    write_big_int_result(instance, "modexp", result)
+}
+
+/// Validates an X.509 chain against roots pinned by the contract, at the block time, and returns
+/// the leaf's public key and the extensions the contract selected as JSON
+fn host_x509_verify_chain(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (chain_ptr, roots_ptr, extensions_ptr, purpose): (i32, i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    let chain = decode_sections_from_memory(instance, chain_ptr as u32).map_err(
+        debug_err!(err => "x509_verify_chain error while trying to read chain from wasm memory: {err}")
+    )?;
+    let roots = decode_sections_from_memory(instance, roots_ptr as u32).map_err(
+        debug_err!(err => "x509_verify_chain error while trying to read roots from wasm memory: {err}")
+    )?;
+    let extensions = decode_sections_from_memory(instance, extensions_ptr as u32).map_err(
+        debug_err!(err => "x509_verify_chain error while trying to read extensions from wasm memory: {err}")
+    )?;
+
+    let used_gas =
+        context.gas_costs.external_x509_verify_chain_per_cert as u64 * chain.len().max(1) as u64;
+    use_gas(instance, used_gas)?;
+
+    trace!(
+        "x509_verify_chain() was called from WASM code with {} certificates and {} roots",
+        chain.len(),
+        roots.len()
+    );
+
+    let result = CertPurpose::from_u32(purpose as u32).and_then(|purpose| {
+        let extensions = extensions
+            .into_iter()
+            .map(String::from_utf8)
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|_| X509Error::InvalidInput)?;
+        let tm_s = context.timestamp / 1000000000;
+
+        x509::verify_cert_chain(&chain, &roots, purpose, tm_s, &extensions)
+    });
+    let leaf = match result {
+        Ok(leaf) => leaf,
+        Err(err) => {
+            debug!("x509_verify_chain() failed: {:?}", err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let extensions: Vec<serde_json::Value> = leaf
+        .extensions
+        .into_iter()
+        .map(|extension| {
+            serde_json::json!({
+                "oid": extension.oid,
+                "critical": extension.critical,
+                "value": Binary(extension.value),
+            })
+        })
+        .collect();
+    let leaf = serde_json::json!({
+        "public_key": Binary(leaf.public_key),
+        "extensions": extensions,
+    });
+    // serializing a json value can't fail
+    let leaf = serde_json::to_vec(&leaf).unwrap();
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &leaf).map_err(|err| {
+        debug!("x509_verify_chain() error while trying to allocate and write the result to the WASM VM");
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Verifies an ES256 or RS256 JWT with a JWK, at the block time, and returns its claims
+fn host_jwt_verify(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (token_ptr, jwk_ptr): (i32, i32),
+) -> WasmEngineResult<i64> {
+    let token = read_operand(instance, "jwt_verify", token_ptr)?;
+    let jwk = read_operand(instance, "jwt_verify", jwk_ptr)?;
+
+    // Parsing is priced like canonicalizing the token and key, and the signature like the
+    // corresponding host function
+    let json_len = (token.len() + jwk.len()) as u64;
+    let mut used_gas = context.gas_costs.external_canonicalize_json_base as u64
+        + context.gas_costs.external_canonicalize_json_per_byte as u64 * json_len;
+    let result = Jwk::parse(&jwk);
+    if let Ok(jwk) = &result {
+        used_gas += match &jwk.key {
+            JwkKey::P256(_) => context.gas_costs.external_secp256k1_verify as u64,
+            JwkKey::Rsa { modulus, .. } => rsa_verify_gas(&context.gas_costs, modulus),
+        };
+    }
+    use_gas(instance, used_gas)?;
+
+    trace!(
+        "jwt_verify() was called from WASM code with a {} byte token",
+        token.len()
+    );
+
+    let tm_s = context.timestamp / 1000000000;
+    let claims = match result.and_then(|jwk| jwt::verify_jwt(&token, &jwk, tm_s)) {
+        Ok(claims) => claims,
+        Err(err) => {
+            debug!("jwt_verify() failed: {:?}", err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &claims).map_err(|err| {
+        debug!("jwt_verify() error while trying to allocate and write the result to the WASM VM");
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Hashes field elements of BN254 or BLS12-381 with Poseidon
+fn host_poseidon_hash(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (curve, inputs_ptr): (i32, i32),
+) -> WasmEngineResult<i64> {
+    let inputs = decode_sections_from_memory(instance, inputs_ptr as u32).map_err(
+        debug_err!(err => "poseidon_hash error while trying to read inputs from wasm memory: {err}")
+    )?;
+
+    let used_gas = context.gas_costs.external_poseidon_hash_per_multiplication as u64
+        * poseidon::multiplications(inputs.len());
+    use_gas(instance, used_gas)?;
+
+    trace!(
+        "poseidon_hash() was called from WASM code with {} inputs",
+        inputs.len()
+    );
+
+    let hash = match Curve::from_u32(curve as u32)
+        .and_then(|curve| poseidon::poseidon_hash(curve, &inputs))
+    {
+        Ok(hash) => hash,
+        Err(err) => {
+            debug!("poseidon_hash() failed: {:?}", err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &hash).map_err(|err| {
+        debug!(
+            "poseidon_hash() error while trying to allocate and write the result to the WASM VM"
+        );
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Runs an accumulator operation, and charges the gas of the storage accesses it made
+fn with_merkle_store<T, F>(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    operation: F,
+) -> WasmEngineResult<T>
+where
+    F: FnOnce(&mut MerkleStore) -> WasmEngineResult<T>,
+{
+    let mut store = context.merkle_store();
+    let result = operation(&mut store);
+    let (used_gas, used_gas_externally) = (store.gas_used, store.gas_used_externally);
+
+    context.use_gas_externally(used_gas_externally);
+    use_gas(instance, used_gas)?; // Use gas now, refund the pseudo gas of the writes later
+    result
+}
+
+/// The gas of hashing a node of an accumulator, by its hash
+fn merkle_hash_gas(gas_costs: &WasmCosts) -> impl Fn(MerkleHash) -> u64 {
+    let sha256_gas = gas_costs.external_merkle_sha256_per_node as u64;
+    let poseidon_gas =
+        gas_costs.external_poseidon_hash_per_multiplication as u64 * poseidon::multiplications(2);
+    move |hash| match hash {
+        MerkleHash::Sha256 => sha256_gas,
+        MerkleHash::PoseidonBn254 => poseidon_gas,
+    }
+}
+
+/// Creates an empty accumulator of the given depth and hash, under a name in the contract's state
+fn host_merkle_create(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (name_ptr, depth, hash): (i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    if context.operation.is_query() {
+        debug!("merkle_create was called while in query mode");
+        return Err(WasmEngineError::UnauthorizedWrite);
+    }
+
+    let name = read_from_memory(instance, name_ptr as u32).map_err(
+        debug_err!(err => "merkle_create failed to extract vector from name_ptr: {err}"),
+    )?;
+
+    debug!("merkle_create creating {}", show_bytes(&name));
+
+    let result = with_merkle_store(context, instance, |store| {
+        MerkleTree::create(store, &name, depth as u32, hash as u32)
+    })?;
+    match result {
+        Ok(_) => Ok(0),
+        Err(err) => {
+            debug!("merkle_create() failed: {:?}", err);
+            Ok(to_high_half(err as u32) as i64)
+        }
+    }
+}
+
+/// Appends a leaf to an accumulator, and returns its index
+fn host_merkle_append(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (name_ptr, leaf_ptr): (i32, i32),
+) -> WasmEngineResult<i64> {
+    if context.operation.is_query() {
+        debug!("merkle_append was called while in query mode");
+        return Err(WasmEngineError::UnauthorizedWrite);
+    }
+
+    let name = read_from_memory(instance, name_ptr as u32).map_err(
+        debug_err!(err => "merkle_append failed to extract vector from name_ptr: {err}"),
+    )?;
+    let leaf = read_from_memory(instance, leaf_ptr as u32).map_err(
+        debug_err!(err => "merkle_append failed to extract vector from leaf_ptr: {err}"),
+    )?;
+
+    debug!("merkle_append appending to {}", show_bytes(&name));
+
+    let hash_gas = merkle_hash_gas(&context.gas_costs);
+    let result = with_merkle_store(context, instance, |store| {
+        let mut tree = match MerkleTree::load(store, &name)? {
+            Ok(tree) => tree,
+            Err(err) => return Ok(Err(err)),
+        };
+        // Every level of the path is hashed
+        use_gas(instance, tree.depth as u64 * hash_gas(tree.hash))?;
+        tree.append(store, &name, &leaf)
+    })?;
+    match result {
+        // Trees have at most 2^32 leaves
+        Ok(index) => Ok(to_low_half(index as u32) as i64),
+        Err(err) => {
+            debug!("merkle_append() failed: {:?}", err);
+            Ok(to_high_half(err as u32) as i64)
+        }
+    }
+}
+
+/// Returns the root of an accumulator
+fn host_merkle_root(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    name_ptr: i32,
+) -> WasmEngineResult<i64> {
+    let name = read_from_memory(instance, name_ptr as u32)
+        .map_err(debug_err!(err => "merkle_root failed to extract vector from name_ptr: {err}"))?;
+
+    let result = with_merkle_store(context, instance, |store| {
+        match MerkleTree::load(store, &name)? {
+            Ok(tree) => tree.root(store, &name).map(Ok),
+            Err(err) => Ok(Err(err)),
+        }
+    })?;
+    let root = match result {
+        Ok(root) => root,
+        Err(err) => {
+            debug!("merkle_root() failed: {:?}", err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &root).map_err(|err| {
+        debug!("merkle_root() error while trying to allocate and write the result to the WASM VM");
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Returns the proof of the leaf at an index of an accumulator
+fn host_merkle_prove(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (name_ptr, index): (i32, i32),
+) -> WasmEngineResult<i64> {
+    let name = read_from_memory(instance, name_ptr as u32)
+        .map_err(debug_err!(err => "merkle_prove failed to extract vector from name_ptr: {err}"))?;
+
+    let result = with_merkle_store(context, instance, |store| {
+        match MerkleTree::load(store, &name)? {
+            Ok(tree) => tree.prove(store, &name, index as u32 as u64),
+            Err(err) => Ok(Err(err)),
+        }
+    })?;
+    let proof = match result {
+        Ok(proof) => proof,
+        Err(err) => {
+            debug!("merkle_prove() failed: {:?}", err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &proof).map_err(|err| {
+        debug!("merkle_prove() error while trying to allocate and write the result to the WASM VM");
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Checks the proof of a leaf at an index against the current root of an accumulator
+fn host_merkle_verify(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (name_ptr, leaf_ptr, index, proof_ptr): (i32, i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    let name = read_from_memory(instance, name_ptr as u32).map_err(
+        debug_err!(err => "merkle_verify failed to extract vector from name_ptr: {err}"),
+    )?;
+    let leaf = read_from_memory(instance, leaf_ptr as u32).map_err(
+        debug_err!(err => "merkle_verify failed to extract vector from leaf_ptr: {err}"),
+    )?;
+    let proof = read_from_memory(instance, proof_ptr as u32).map_err(
+        debug_err!(err => "merkle_verify failed to extract vector from proof_ptr: {err}"),
+    )?;
+
+    let hash_gas = merkle_hash_gas(&context.gas_costs);
+    let result = with_merkle_store(context, instance, |store| {
+        let tree = match MerkleTree::load(store, &name)? {
+            Ok(tree) => tree,
+            Err(err) => return Ok(Err(err)),
+        };
+        use_gas(instance, tree.depth as u64 * hash_gas(tree.hash))?;
+        tree.verify(store, &name, &leaf, index as u32 as u64, &proof)
+    })?;
+    match result {
+        Ok(()) => Ok(0),
+        Err(err) => {
+            debug!("merkle_verify() failed: {:?}", err);
+            Ok(to_high_half(err as u32) as i64)
+        }
+    }
+}
+
+/// Returns the root of the contract's state commitment, followed by its number of entries and
+/// whether it's complete
+fn host_state_commitment_root(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+) -> WasmEngineResult<i64> {
+    if !context.state_commitment {
+        debug!("state_commitment_root() was called by a contract that doesn't require it");
+        return Ok(to_high_half(StateTreeError::NotEnabled as u32) as i64);
+    }
+
+    let tree = with_merkle_store(context, instance, StateTree::current)?;
+    let mut result = tree.root.to_vec();
+    result.extend_from_slice(&tree.entries.to_be_bytes());
+    result.push(tree.complete as u8);
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &result).map_err(|err| {
+        debug!("state_commitment_root() error while trying to allocate and write the result to the WASM VM");
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Returns the proof of the value of a key in the contract's state commitment, or that it has none
+fn host_state_commitment_prove(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    key_ptr: i32,
+) -> WasmEngineResult<i64> {
+    if !context.state_commitment {
+        debug!("state_commitment_prove() was called by a contract that doesn't require it");
+        return Ok(to_high_half(StateTreeError::NotEnabled as u32) as i64);
+    }
+
+    let key = read_from_memory(instance, key_ptr as u32).map_err(
+        debug_err!(err => "state_commitment_prove failed to extract vector from key_ptr: {err}"),
+    )?;
+
+    let proof = with_merkle_store(context, instance, |store| {
+        StateTree::current(store)?.prove(store, &key)
+    })?;
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &proof).map_err(|err| {
+        debug!("state_commitment_prove() error while trying to allocate and write the result to the WASM VM");
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Checks a proof of a key against the root of any contract's state commitment, and returns the
+/// hash of the value of the key, or nothing if the proof shows it has none
+fn host_state_commitment_verify(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (root_ptr, key_ptr, proof_ptr): (i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    let root = read_from_memory(instance, root_ptr as u32).map_err(
+        debug_err!(err => "state_commitment_verify failed to extract vector from root_ptr: {err}"),
+    )?;
+    let key = read_from_memory(instance, key_ptr as u32).map_err(
+        debug_err!(err => "state_commitment_verify failed to extract vector from key_ptr: {err}"),
+    )?;
+    let proof = read_from_memory(instance, proof_ptr as u32).map_err(
+        debug_err!(err => "state_commitment_verify failed to extract vector from proof_ptr: {err}"),
+    )?;
+
+    let node_gas = context.gas_costs.external_merkle_sha256_per_node as u64;
+    use_gas(
+        instance,
+        state_tree::proof_hashes(&proof).saturating_mul(node_gas),
+    )?;
+
+    let value_hash = match state_tree::verify(&root, &key, &proof) {
+        Ok(value_hash) => value_hash,
+        Err(err) => {
+            debug!("state_commitment_verify() failed: {:?}", err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let result = value_hash.map_or(vec![], |value_hash| value_hash.to_vec());
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &result).map_err(|err| {
+        debug!("state_commitment_verify() error while trying to allocate and write the result to the WASM VM");
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// The gas of an HKDF, HMAC or AEAD call, by the bytes it reads and writes
+fn symmetric_crypto_gas(gas_costs: &WasmCosts, bytes: usize) -> u64 {
+    (gas_costs.external_symmetric_crypto_base as u64).saturating_add(
+        (gas_costs.external_symmetric_crypto_per_byte as u64).saturating_mul(bytes as u64),
+    )
+}
+
+/// Writes the result of an HKDF, HMAC or AEAD call to the contract
+fn write_symmetric_result(
+    instance: &wasm3::Instance<Context>,
+    name: &str,
+    result: Result<Vec<u8>, SymmetricError>,
+) -> WasmEngineResult<i64> {
+    let output = match result {
+        Ok(output) => output,
+        Err(err) => {
+            debug!("{}() failed: {:?}", name, err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &output).map_err(|err| {
+        debug!(
+            "{}() error while trying to allocate and write the result to the WASM VM",
+            name
+        );
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Derives `length` bytes with HKDF-SHA256 from input key material the contract supplies
+fn host_hkdf_sha256(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (ikm_ptr, salt_ptr, info_ptr, length): (i32, i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    let ikm = read_from_memory(instance, ikm_ptr as u32)
+        .map_err(debug_err!(err => "hkdf_sha256 failed to extract vector from ikm_ptr: {err}"))?;
+    let salt = read_from_memory(instance, salt_ptr as u32)
+        .map_err(debug_err!(err => "hkdf_sha256 failed to extract vector from salt_ptr: {err}"))?;
+    let info = read_from_memory(instance, info_ptr as u32)
+        .map_err(debug_err!(err => "hkdf_sha256 failed to extract vector from info_ptr: {err}"))?;
+    let length = length as u32 as usize;
+
+    // The length is capped before it's charged for, so the gas can't overflow
+    let output_length = length.min(symmetric::MAX_HKDF_OUTPUT_LENGTH);
+    use_gas(
+        instance,
+        symmetric_crypto_gas(
+            &context.gas_costs,
+            ikm.len() + salt.len() + info.len() + output_length,
+        ),
+    )?;
+
+    let result = symmetric::hkdf_sha256(&ikm, &salt, &info, length);
+    write_symmetric_result(instance, "hkdf_sha256", result)
+}
+
+/// Computes the HMAC-SHA256 of a message with a key the contract supplies
+fn host_hmac_sha256(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (key_ptr, message_ptr): (i32, i32),
+) -> WasmEngineResult<i64> {
+    let key = read_from_memory(instance, key_ptr as u32)
+        .map_err(debug_err!(err => "hmac_sha256 failed to extract vector from key_ptr: {err}"))?;
+    let message = read_from_memory(instance, message_ptr as u32).map_err(
+        debug_err!(err => "hmac_sha256 failed to extract vector from message_ptr: {err}"),
+    )?;
+
+    use_gas(
+        instance,
+        symmetric_crypto_gas(&context.gas_costs, key.len() + message.len()),
+    )?;
+
+    let result = symmetric::hmac_sha256(&key, &message);
+    write_symmetric_result(instance, "hmac_sha256", result)
+}
+
+struct AeadInputs {
+    key: Vec<u8>,
+    nonce: Vec<u8>,
+    aad: Vec<u8>,
+    /// The plaintext of `aead_seal`, or the ciphertext of `aead_open`
+    message: Vec<u8>,
+}
+
+/// Reads the inputs of `aead_seal` and `aead_open`, and charges for them. The associated data and
+/// the message are passed as a list, encoded like the arguments of `ed25519_batch_verify`.
+fn read_aead_inputs(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    name: &str,
+    (key_ptr, nonce_ptr, message_ptr): (i32, i32, i32),
+) -> WasmEngineResult<Result<AeadInputs, SymmetricError>> {
+    let key = read_from_memory(instance, key_ptr as u32)
+        .map_err(debug_err!(err => "{name} failed to extract vector from key_ptr: {err}"))?;
+    let nonce = read_from_memory(instance, nonce_ptr as u32)
+        .map_err(debug_err!(err => "{name} failed to extract vector from nonce_ptr: {err}"))?;
+    let mut sections = decode_sections_from_memory(instance, message_ptr as u32).map_err(
+        debug_err!(err => "{name} error while trying to read message from wasm memory: {err}"),
+    )?;
+
+    let bytes = key.len() + nonce.len() + sections.iter().map(Vec::len).sum::<usize>();
+    use_gas(instance, symmetric_crypto_gas(&context.gas_costs, bytes))?;
+
+    if sections.len() != 2 {
+        return Ok(Err(SymmetricError::InvalidInput));
+    }
+    let message = sections.pop().unwrap();
+    let aad = sections.pop().unwrap();
+    Ok(Ok(AeadInputs {
+        key,
+        nonce,
+        aad,
+        message,
+    }))
+}
+
+/// Encrypts a plaintext with an AEAD, under a key and nonce the contract supplies
+fn host_aead_seal(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (algorithm, key_ptr, nonce_ptr, message_ptr): (i32, i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    let inputs = read_aead_inputs(
+        context,
+        instance,
+        "aead_seal",
+        (key_ptr, nonce_ptr, message_ptr),
+    )?;
+
+    let result = inputs.and_then(|inputs| {
+        let algorithm = AeadAlgorithm::from_u32(algorithm as u32)?;
+        symmetric::aead_seal(
+            algorithm,
+            &inputs.key,
+            &inputs.nonce,
+            &inputs.aad,
+            &inputs.message,
+        )
+    });
+    write_symmetric_result(instance, "aead_seal", result)
+}
+
+/// Decrypts a ciphertext sealed with `aead_seal`, or with the same AEAD elsewhere
+fn host_aead_open(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (algorithm, key_ptr, nonce_ptr, message_ptr): (i32, i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    let inputs = read_aead_inputs(
+        context,
+        instance,
+        "aead_open",
+        (key_ptr, nonce_ptr, message_ptr),
+    )?;
+
+    let result = inputs.and_then(|inputs| {
+        let algorithm = AeadAlgorithm::from_u32(algorithm as u32)?;
+        symmetric::aead_open(
+            algorithm,
+            &inputs.key,
+            &inputs.nonce,
+            &inputs.aad,
+            &inputs.message,
+        )
+    });
+    write_symmetric_result(instance, "aead_open", result)
+}
+
+/// The gas of a compress or decompress call, by the bytes it reads or writes
+fn compression_gas(gas_costs: &WasmCosts, bytes: usize) -> u64 {
+    (gas_costs.external_compression_per_byte as u64).saturating_mul(bytes as u64)
+}
+
+/// Charges for the input of a compress or decompress call, and reads it
+fn read_compression_input(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    name: &str,
+    input_ptr: i32,
+) -> WasmEngineResult<Vec<u8>> {
+    let input = read_from_memory(instance, input_ptr as u32)
+        .map_err(debug_err!(err => "{name} failed to extract vector from input_ptr: {err}"))?;
+
+    use_gas(
+        instance,
+        (context.gas_costs.external_compression_base as u64)
+            .saturating_add(compression_gas(&context.gas_costs, input.len())),
+    )?;
+    Ok(input)
+}
+
+/// Writes the result of a compress or decompress call to the contract
+fn write_compression_result(
+    instance: &wasm3::Instance<Context>,
+    name: &str,
+    result: Result<Vec<u8>, CompressionError>,
+) -> WasmEngineResult<i64> {
+    let output = match result {
+        Ok(output) => output,
+        Err(err) => {
+            debug!("{}() failed: {:?}", name, err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &output).map_err(|err| {
+        debug!(
+            "{}() error while trying to allocate and write the result to the WASM VM",
+            name
+        );
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Compresses an input with deflate or zlib, the same way on every node
+fn host_compress(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (format, input_ptr): (i32, i32),
+) -> WasmEngineResult<i64> {
+    let input = read_compression_input(context, instance, "compress", input_ptr)?;
+
+    let result = CompressionFormat::from_u32(format as u32)
+        .and_then(|format| compression::compress(format, &input));
+    if let Ok(output) = &result {
+        use_gas(instance, compression_gas(&context.gas_costs, output.len()))?;
+    }
+    write_compression_result(instance, "compress", result)
+}
+
+/// Decompresses an input, and fails as soon as the output would be longer than the contract's
+/// maximum
+fn host_decompress(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (format, input_ptr, max_output_length): (i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    let input = read_compression_input(context, instance, "decompress", input_ptr)?;
+    let max_output_length = max_output_length as u32 as usize;
+
+    let result = CompressionFormat::from_u32(format as u32)
+        .and_then(|format| compression::decompress(format, &input, max_output_length));
+    // A failure can come after the whole maximum was decompressed, so it costs as much
+    let output_length = match &result {
+        Ok(output) => output.len(),
+        Err(CompressionError::InvalidInput) => 0,
+        Err(_) => max_output_length,
+    };
+    use_gas(instance, compression_gas(&context.gas_costs, output_length))?;
+    write_compression_result(instance, "decompress", result)
+}
+
+/// Verifies a DCAP quote, and checks its enclave against a policy of the contract
+fn host_verify_sgx_quote(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    (quote_ptr, collateral_ptr, policy_ptr): (i32, i32, i32),
+) -> WasmEngineResult<i64> {
+    use_gas(instance, context.gas_costs.external_verify_sgx_quote as u64)?;
+
+    let quote = read_from_memory(instance, quote_ptr as u32).map_err(
+        debug_err!(err => "verify_sgx_quote failed to extract vector from quote_ptr: {err}"),
+    )?;
+    let collateral = read_from_memory(instance, collateral_ptr as u32).map_err(
+        debug_err!(err => "verify_sgx_quote failed to extract vector from collateral_ptr: {err}"),
+    )?;
+    let policy = read_from_memory(instance, policy_ptr as u32).map_err(
+        debug_err!(err => "verify_sgx_quote failed to extract vector from policy_ptr: {err}"),
+    )?;
+
+    let policy = match QuotePolicy::parse(&policy) {
+        Ok(policy) => policy,
+        Err(err) => {
+            debug!("verify_sgx_quote() got an invalid policy");
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    // The block time, so every node verifies the collateral at the same time
+    let tm_s = (context.timestamp / 1000000000) as i64;
+    let verified = verify_quote_any(&quote, &collateral, tm_s)
+        .map_err(|err| {
+            debug!("verify_sgx_quote() failed to verify the quote: {}", err);
+            QuotePolicyError::InvalidQuote
+        })
+        .and_then(|qv_result| {
+            let report_body =
+                quote_report_body(&quote).map_err(|_| QuotePolicyError::InvalidQuote)?;
+            policy.check(&report_body)?;
+            Ok(quote_summary(&report_body, qv_result))
+        });
+    let summary = match verified {
+        Ok(summary) => summary,
+        Err(err) => {
+            debug!("verify_sgx_quote() failed: {:?}", err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &summary).map_err(|err| {
+        debug!(
+            "verify_sgx_quote() error while trying to allocate and write the result to the WASM VM"
+        );
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+pub(crate) fn get_encryption_salt(timestamp: u64) -> Vec<u8> {
+    let mut encryption_salt: Vec<u8> = vec![];
+
+    encryption_salt.extend(timestamp.to_be_bytes());
 
     let msg_counter = MSG_COUNTER.lock().unwrap();
 
@@ -1933,6 +4423,118 @@ fn host_gas_evaporate(
     Ok(0)
 }
 
+/// Opts the current call into uniform gas: whichever branches the contract takes, the call
+/// reports exactly `declared` gas (in the same units as `gas_evaporate`), or fails with out of
+/// gas if it needs more. Should be called at the start of the entry point.
+fn host_gas_uniform(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    declared: i32,
+) -> WasmEngineResult<i32> {
+    const GAS_MULTIPLIER: u64 = 1000; // (cosmwasm gas : sdk gas)
+
+    use_gas(instance, context.gas_costs.external_gas_uniform as u64)?;
+
+    let declared = (declared as u32 as u64) * GAS_MULTIPLIER;
+    if declared > context.gas_limit {
+        debug!(
+            "gas_uniform declared {} gas, more than the limit of {}",
+            declared, context.gas_limit
+        );
+        return Err(WasmEngineError::OutOfGas);
+    }
+
+    match context.uniform_gas {
+        Some(previous) if previous != declared => {
+            debug!("gas_uniform was called twice with different amounts");
+            // return 1 == failure
+            Ok(1)
+        }
+        _ => {
+            context.uniform_gas = Some(declared);
+            // return 0 == success
+            Ok(0)
+        }
+    }
+}
+
+/// Writes the features of this enclave as json, see `enclave_features`
+fn host_query_enclave_features(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+) -> WasmEngineResult<i64> {
+    let used_gas = context.gas_costs.external_query_enclave_features as u64;
+    use_gas(instance, used_gas)?;
+
+    trace!("query_enclave_features() was called from WASM code");
+
+    let ptr_to_region_in_wasm_vm =
+        write_to_memory(instance, &encoded_enclave_features()).map_err(|err| {
+            debug!("query_enclave_features() error while trying to allocate and write the result to the WASM VM");
+            err
+        })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
+/// Writes the status of an IBC client of this chain as json, verified against the app hash of the
+/// current block. Returns an `IbcClientStatusError` code in the high half if it can't be verified.
+fn host_ibc_client_status(
+    context: &mut Context,
+    instance: &wasm3::Instance<Context>,
+    client_id_ptr: i32,
+) -> WasmEngineResult<i64> {
+    use_gas(
+        instance,
+        context.gas_costs.external_ibc_client_status as u64,
+    )?;
+
+    let client_id = read_from_memory(instance, client_id_ptr as u32).map_err(
+        debug_err!(err => "ibc_client_status failed to extract vector from client_id_ptr: {err}"),
+    )?;
+    let client_id = match String::from_utf8(client_id) {
+        Ok(client_id) => client_id,
+        Err(_) => return Ok(to_high_half(IbcClientStatusError::Malformed as u32) as i64),
+    };
+
+    trace!(
+        "ibc_client_status() was called from WASM code for client {}",
+        client_id
+    );
+
+    let mut query_used_gas: u64 = 0;
+    let result = verified_client_status(
+        &context.context,
+        &client_id,
+        context.query_depth,
+        &mut query_used_gas,
+        get_remaining_gas(instance),
+    );
+    context.use_gas_externally(query_used_gas);
+
+    let status = match result {
+        Ok(status) => serde_json::to_vec(&status).map_err(|err| {
+            debug!("ibc_client_status() failed to encode the status: {}", err);
+            WasmEngineError::SerializationError
+        })?,
+        Err(err) => {
+            debug!("ibc_client_status() failed for {}: {:?}", client_id, err);
+            return Ok(to_high_half(err as u32) as i64);
+        }
+    };
+
+    let ptr_to_region_in_wasm_vm = write_to_memory(instance, &status).map_err(|err| {
+        debug!(
+            "ibc_client_status() error while trying to allocate and write the result to the WASM VM"
+        );
+        err
+    })?;
+
+    // Return pointer to the allocated buffer with the value written to it
+    Ok(to_low_half(ptr_to_region_in_wasm_vm) as i64)
+}
+
 fn host_check_gas_used(
     context: &mut Context,
     instance: &wasm3::Instance<Context>,
@@ -1957,6 +4559,7 @@ fn host_check_gas_used(
 
 #[cfg(feature = "test")]
 pub mod tests {
+    use super::code_limits;
     use super::shuffle_cache;
     use crate::count_failures;
     use crate::wasm3::Binary;
@@ -1967,6 +4570,10 @@ pub mod tests {
 
         count_failures!(failures, {
             cache_shuffle_works();
+            code_limits::tests::test_code_limits_valid();
+            code_limits::tests::test_code_limits_violations();
+            code_limits::tests::test_code_limits_required_features();
+            code_limits::tests::test_code_limits_malformed();
         });
 
         // The test doesn't work for some reason
@@ -9,7 +9,7 @@ use cw_types_generic::{ContractFeature, CosmWasmApiVersion};
 use enclave_ffi_types::EnclaveError;
 
 use enclave_cosmos_types::types::ContractCode;
-use enclave_crypto::HASH_SIZE;
+use enclave_crypto::{sha_256, HASH_SIZE};
 
 use super::{gas, validation};
 use crate::cosmwasm_config::ContractOperation;
@@ -32,6 +32,10 @@ impl VersionedCode {
     }
 }
 
+/// The version of how `analyze_module` builds the artifact of a module. Bump it with every change
+/// to the instrumentation, so artifacts are only compared between enclaves that build them alike.
+pub const ENGINE_VERSION: u32 = 1;
+
 lazy_static! {
     static ref MODULE_CACHE: SgxRwLock<LruCache<[u8; HASH_SIZE], VersionedCode>> =
         SgxRwLock::new(LruCache::new(0));
@@ -42,6 +46,28 @@ pub fn configure_module_cache(cap: usize) {
     MODULE_CACHE.write().unwrap().resize(cap)
 }
 
+/// Whether the module of `contract_code` is in the cache, without touching its LRU value
+pub fn is_cached(contract_code: &ContractCode) -> bool {
+    is_code_hash_cached(&contract_code.hash())
+}
+
+/// Whether the module with `code_hash` is in the cache, without touching its LRU value
+pub fn is_code_hash_cached(code_hash: &[u8; HASH_SIZE]) -> bool {
+    MODULE_CACHE.read().unwrap().peek(code_hash).is_some()
+}
+
+/// The code hash and the hash of the instrumented artifact of every cached module, sorted by code
+/// hash, so two caches with the same modules list them the same way whatever their LRU order
+pub fn cached_artifacts() -> Vec<([u8; HASH_SIZE], [u8; HASH_SIZE])> {
+    let cache = MODULE_CACHE.read().unwrap();
+    let mut artifacts: Vec<([u8; HASH_SIZE], [u8; HASH_SIZE])> = cache
+        .iter()
+        .map(|(code_hash, versioned_code)| (*code_hash, sha_256(&versioned_code.code)))
+        .collect();
+    artifacts.sort();
+    artifacts
+}
+
 pub fn create_module_instance(
     contract_code: &ContractCode,
     gas_costs: &WasmCosts,
@@ -107,6 +133,35 @@ pub fn create_module_instance(
     Ok(VersionedCode::new(code, api_version, features))
 }
 
+/// Analyze a contract ahead of the block it's likely to be executed in, and cache it if the cache
+/// has room. Cached modules are never evicted for it, so a wrong hint can't slow the block down.
+/// Returns whether the module was added.
+pub fn prewarm_module(
+    contract_code: &ContractCode,
+    gas_costs: &WasmCosts,
+) -> Result<bool, EnclaveError> {
+    let has_room = |cache: &LruCache<[u8; HASH_SIZE], VersionedCode>| {
+        cache.len() < cache.cap() && !cache.contains(&contract_code.hash())
+    };
+
+    if !has_room(&MODULE_CACHE.read().unwrap()) {
+        return Ok(false);
+    }
+
+    // Analyzed as for an init, so that floating point modules, which only an init rejects, are
+    // never cached ahead of one
+    let versioned_code = analyze_module(contract_code, gas_costs, ContractOperation::Init)?;
+
+    let mut cache = MODULE_CACHE.write().unwrap();
+    if !has_room(&cache) {
+        return Ok(false);
+    }
+
+    trace!("prewarmed module {:x?}", contract_code.hash());
+    cache.put(contract_code.hash(), versioned_code);
+    Ok(true)
+}
+
 pub fn analyze_module(
     contract_code: &ContractCode,
     gas_costs: &WasmCosts,
@@ -138,17 +193,39 @@ pub fn analyze_module(
     };
 
     // features
-    let random_enabled = module
+    let mut supported_features = vec![];
+    if module
         .exports
         .iter()
-        .any(|exp| exp.name == features::RANDOM);
-
-    let features = if random_enabled {
+        .any(|exp| exp.name == features::RANDOM)
+    {
         debug!("Found supported features: random");
-        vec![ContractFeature::Random]
-    } else {
-        vec![]
-    };
+        supported_features.push(ContractFeature::Random);
+    }
+    if module
+        .exports
+        .iter()
+        .any(|exp| exp.name == features::REENTRANCY_GUARD)
+    {
+        debug!("Found supported features: reentrancy guard");
+        supported_features.push(ContractFeature::ReentrancyGuard);
+    }
+    if module
+        .exports
+        .iter()
+        .any(|exp| exp.name == features::STATE_COMMITMENT)
+    {
+        debug!("Found supported features: state commitment");
+        supported_features.push(ContractFeature::StateCommitment);
+    }
+    if module
+        .exports
+        .iter()
+        .any(|exp| exp.name == features::QUERY_REPLAY_PROTECTION)
+    {
+        debug!("Found supported features: query replay protection");
+        supported_features.push(ContractFeature::QueryReplayProtection);
+    }
     drop(exports);
 
     validation::validate_memory(&mut module)?;
@@ -164,5 +241,9 @@ pub fn analyze_module(
 
     let code = module.emit_wasm();
 
-    Ok(VersionedCode::new(code, cosmwasm_api_version, features))
+    Ok(VersionedCode::new(
+        code,
+        cosmwasm_api_version,
+        supported_features,
+    ))
 }
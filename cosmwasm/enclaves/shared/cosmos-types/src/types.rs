@@ -162,6 +162,8 @@ pub enum HandleType {
     HANDLE_TYPE_IBC_WASM_HOOKS_INCOMING_TRANSFER = 8,
     HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_ACK = 9,
     HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_TIMEOUT = 10,
+    HANDLE_TYPE_JOB_RESULT = 11,
+    HANDLE_TYPE_CRON = 12,
 }
 
 impl HandleType {
@@ -178,6 +180,8 @@ impl HandleType {
             8 => Ok(HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_INCOMING_TRANSFER),
             9 => Ok(HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_ACK),
             10 => Ok(HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_TIMEOUT),
+            11 => Ok(HandleType::HANDLE_TYPE_JOB_RESULT),
+            12 => Ok(HandleType::HANDLE_TYPE_CRON),
             _ => {
                 error!("unrecognized handle type: {}", value);
                 Err(EnclaveError::FailedToDeserialize)
@@ -198,6 +202,8 @@ impl HandleType {
             HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_INCOMING_TRANSFER => "execute",
             HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_ACK => "sudo",
             HandleType::HANDLE_TYPE_IBC_WASM_HOOKS_OUTGOING_TRANSFER_TIMEOUT => "sudo",
+            HandleType::HANDLE_TYPE_JOB_RESULT => "job_result",
+            HandleType::HANDLE_TYPE_CRON => "cron",
         }
     }
 }
@@ -223,6 +229,15 @@ pub struct SigInfo {
     pub public_key: Binary,
     pub signature: Binary,
     pub callback_sig: Option<Binary>,
+    pub interchain_account: Option<InterchainAccountInfo>,
+}
+
+/// The controller of the interchain account that executes a contract, as the ICA host module
+/// stores it
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct InterchainAccountInfo {
+    pub controller_port: String,
+    pub host_channel: String,
 }
 
 // Should be in sync with https://github.com/cosmos/cosmos-sdk/blob/v0.38.3/x/auth/types/stdtx.go#L216
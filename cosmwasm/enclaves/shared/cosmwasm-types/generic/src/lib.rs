@@ -28,7 +28,10 @@ pub enum CosmWasmApiVersion {
 /// features that a contract requires
 #[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
 pub enum ContractFeature {
+    QueryReplayProtection,
     Random,
+    ReentrancyGuard,
+    StateCommitment,
 }
 
 pub type BaseAddr = HumanAddr;
@@ -379,6 +379,12 @@ pub struct LogAttribute {
     #[serde(default = "bool_true")]
     #[serde(skip_serializing)]
     pub encrypted: bool,
+    /// Name of a disclosure group. When set, an encrypted attribute is encrypted to the
+    /// contract's key for that group instead of to the tx sender, so the contract can
+    /// later decrypt it for whoever it authorizes. Never serialized, same as `encrypted`.
+    #[serde(default)]
+    #[serde(skip_serializing)]
+    pub disclose_to: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -408,6 +414,7 @@ pub fn log<K: ToString, V: ToString>(key: K, value: V) -> LogAttribute {
         key: key.to_string(),
         value: value.to_string(),
         encrypted: true,
+        disclose_to: None,
     }
 }
 
@@ -417,5 +424,6 @@ pub fn plaintext_log<K: ToString, V: ToString>(key: K, value: V) -> LogAttribute
         key: key.to_string(),
         value: value.to_string(),
         encrypted: false,
+        disclose_to: None,
     }
 }
@@ -62,6 +62,17 @@ pub const FILE_MIGRATION_CERT_LOCAL: &str = "migration_report_local.bin";
 pub const FILE_MIGRATION_CERT_REMOTE: &str = "migration_report_remote.bin";
 pub const FILE_MIGRATION_TARGET_INFO: &str = "migration_target_info.bin";
 pub const FILE_MIGRATION_DATA: &str = "migration_data.bin";
+pub const FILE_BACKUP_ESCROW_CERT_LOCAL: &str = "backup_escrow_report_local.bin";
+pub const FILE_BACKUP_ESCROW_CERT_REMOTE: &str = "backup_escrow_report_remote.bin";
+pub const FILE_BACKUP_RESTORE_CERT_LOCAL: &str = "backup_restore_report_local.bin";
+pub const FILE_BACKUP_RESTORE_CERT_REMOTE: &str = "backup_restore_report_remote.bin";
+pub const FILE_BACKUP_DATA: &str = "backup_data.bin";
+pub const FILE_BACKUP_RESTORE_DATA: &str = "backup_restore_data.bin";
+pub const FILE_CEREMONY_COMMIT: &str = "seed_ceremony_commit.bin";
+pub const FILE_CEREMONY_COMMITS: &str = "seed_ceremony_commits.bin";
+pub const FILE_CEREMONY_REVEAL: &str = "seed_ceremony_reveal.bin";
+pub const FILE_CEREMONY_REVEALS: &str = "seed_ceremony_reveals.bin";
+pub const FILE_CEREMONY_TRANSCRIPT: &str = "seed_ceremony_transcript.json";
 pub const PUBKEY_SAVE_PATH: &str = "pubkey.bin";
 
 pub const SEED_EXCH_KEY_SAVE_PATH: &str = "node-master-key.txt";
@@ -75,6 +86,18 @@ pub const SEALED_FILE_REGISTRATION_KEY: &str = "new_node_seed_exchange_keypair.s
 pub const SEALED_FILE_REK: &str = "rek.sealed";
 pub const SEALED_FILE_IRS: &str = "irs.sealed";
 pub const SEALED_FILE_VALIDATOR_SET: &str = "validator_set.sealed";
+pub const SEALED_FILE_AUDIT_LOG: &str = "audit_log.sealed";
+pub const SEALED_FILE_CONSENSUS_KEY: &str = "consensus_key.sealed";
+pub const SEALED_FILE_TIME_WATERMARK: &str = "time_watermark.sealed";
+pub const SEALED_FILE_CEREMONY_STATE: &str = "seed_ceremony_state.sealed";
+pub const SEALED_FILE_CEREMONY_SEED: &str = "seed_ceremony_seed.sealed";
+pub const SEALED_FILE_SELF_TEST: &str = "self_test.sealed";
+pub const SEALED_FILE_COLLATERAL_CACHE: &str = "collateral_cache.sealed";
+pub const SEALED_FILE_CODE_HASH_REGISTRY: &str = "code_hash_registry.sealed";
+pub const SEALED_FILE_PLATFORM_CENSUS: &str = "platform_census.sealed";
+pub const SEALED_FILE_CRASH_BREADCRUMBS: &str = "crash_breadcrumbs.sealed";
+/// Followed by the hex of the contract's canonical address, and `.sealed`
+pub const SEALED_FILE_QUERY_REPLAY_PREFIX: &str = "query_replay_";
 
 pub const MIGRATION_CONSENSUS_SAVE_PATH: &str = "migration_consensus.json";
 
@@ -139,6 +162,14 @@ pub const RANDOMNESS_ENCRYPTION_KEY_SECRET_DERIVE_ORDER: u32 = 5;
 pub const INITIAL_RANDOMNESS_SEED_SECRET_DERIVE_ORDER: u32 = 6;
 pub const ADMIN_PROOF_SECRET_DERIVE_ORDER: u32 = 7;
 pub const CONTRACT_KEY_PROOF_SECRET_DERIVE_ORDER: u32 = 8;
+pub const ORACLE_CAPABILITY_KEY_DERIVE_ORDER: u32 = 9;
+pub const JOB_WORKER_CAPABILITY_KEY_DERIVE_ORDER: u32 = 10;
+pub const ESCROW_CAPABILITY_KEY_DERIVE_ORDER: u32 = 11;
+pub const MEMPOOL_KEY_SECRET_DERIVE_ORDER: u32 = 12;
+pub const SEED_HEARTBEAT_KEY_SECRET_DERIVE_ORDER: u32 = 13;
+
+/// Bumped by an upgrade after which the network must not share keys with the one before it
+pub const KEY_CONTEXT_NETWORK_VERSION: u32 = 1;
 
 pub const ENCRYPTED_KEY_MAGIC_BYTES: &[u8; 6] = b"secret";
 pub const CONSENSUS_SEED_VERSION: u16 = 2;
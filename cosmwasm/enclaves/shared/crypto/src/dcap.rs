@@ -1,27 +1,32 @@
 use log::*;
+use std::mem;
+
 use sgx_types::{
     sgx_isv_svn_t, sgx_ql_qe_report_info_t, sgx_quote3_error_t, sgx_self_target, sgx_target_info_t,
     sgx_tvl_verify_qve_report_and_identity,
 };
-use sgx_types::{sgx_ql_qv_result_t, sgx_status_t};
-
-extern "C" {
-    pub fn ocall_verify_quote_ecdsa(
-        ret_val: *mut sgx_status_t,
-        p_quote: *const u8,
-        n_quote: u32,
-        p_col: *const u8,
-        n_col: u32,
-        p_target_info: *const sgx_target_info_t,
-        time_s: i64,
-        p_qve_report_info: *mut sgx_ql_qe_report_info_t,
-        p_supp_data: *mut u8,
-        n_supp_data: u32,
-        p_supp_data_size: *mut u32,
-        p_time_s: *mut i64,
-        p_collateral_expiration_status: *mut u32,
-        p_qv_result: *mut sgx_ql_qv_result_t,
-    ) -> sgx_status_t;
+use sgx_types::{sgx_ql_qv_result_t, sgx_quote_t, sgx_report_body_t, sgx_status_t};
+
+use crate::consts::SELF_REPORT_BODY;
+use crate::errors::CryptoError;
+use crate::ocalls::ocall_verify_quote_ecdsa;
+
+/// Offset of `earliest_expiration_date` in the packed `sgx_ql_qv_supplemental_t`, after the
+/// version and the earliest and latest issue dates
+const SUPP_EARLIEST_EXPIRATION_OFFSET: usize = 20;
+
+/// Read the earliest expiration date (the soonest `nextUpdate` of the collateral) out of the QvE
+/// supplemental data, or 0 if the data is too short to hold it
+fn supp_earliest_expiration(supp: &[u8]) -> i64 {
+    let field = supp.get(SUPP_EARLIEST_EXPIRATION_OFFSET..SUPP_EARLIEST_EXPIRATION_OFFSET + 8);
+    match field {
+        Some(bytes) => {
+            let mut date = [0u8; 8];
+            date.copy_from_slice(bytes);
+            i64::from_le_bytes(date)
+        }
+        None => 0,
+    }
 }
 
 pub fn verify_quote_any(
@@ -29,6 +34,16 @@ pub fn verify_quote_any(
     vec_coll: &[u8],
     time_s: i64,
 ) -> Result<sgx_ql_qv_result_t, sgx_status_t> {
+    verify_quote_collateral(vec_quote, vec_coll, time_s).map(|(qv_result, _)| qv_result)
+}
+
+/// Verify a DCAP quote, and return the verification result along with the time (unix seconds)
+/// at which the earliest of its collateral expires
+pub fn verify_quote_collateral(
+    vec_quote: &[u8],
+    vec_coll: &[u8],
+    time_s: i64,
+) -> Result<(sgx_ql_qv_result_t, i64), sgx_status_t> {
     let mut qe_report: sgx_ql_qe_report_info_t = sgx_ql_qe_report_info_t::default();
     let mut p_supp: [u8; 5000] = [0; 5000];
     let mut n_supp: u32 = 0;
@@ -110,5 +125,71 @@ pub fn verify_quote_any(
         return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
     }
 
-    Ok(qv_result)
+    let n_supp = (n_supp as usize).min(p_supp.len());
+    let collateral_expiration = supp_earliest_expiration(&p_supp[..n_supp]);
+    trace!("collateral_expiration = {}", collateral_expiration);
+
+    Ok((qv_result, collateral_expiration))
+}
+
+/// Extract the report body of a DCAP quote without verifying it
+pub fn quote_report_body(quote: &[u8]) -> Result<sgx_report_body_t, CryptoError> {
+    if quote.len() < mem::size_of::<sgx_quote_t>() {
+        debug!("quote too small: {}", quote.len());
+        return Err(CryptoError::ParsingError);
+    }
+
+    let p_quote = quote.as_ptr() as *const sgx_quote_t;
+    // read_unaligned since the quote may come straight out of wasm memory
+    let quote = unsafe { std::ptr::read_unaligned(p_quote) };
+
+    Ok(quote.report_body)
+}
+
+/// Verify the quote of an auxiliary enclave (oracle fetcher, job worker, ...) and return its
+/// report body.
+///
+/// The enclave must be signed by the same MRSIGNER as this enclave. Its MRENCLAVE is not
+/// checked, so auxiliary enclaves can be upgraded independently of the network enclave.
+pub fn verify_quote_same_signer(
+    quote: &[u8],
+    collateral: &[u8],
+    time_s: i64,
+) -> Result<sgx_report_body_t, CryptoError> {
+    verify_quote_any(quote, collateral, time_s).map_err(|err| {
+        debug!("quote failed to verify: {}", err);
+        CryptoError::VerificationError
+    })?;
+
+    let report_body = quote_report_body(quote)?;
+
+    if report_body.mr_signer.m != SELF_REPORT_BODY.mr_signer.m {
+        debug!(
+            "quote has the wrong mr_signer: {}",
+            hex::encode(report_body.mr_signer.m)
+        );
+        return Err(CryptoError::VerificationError);
+    }
+
+    Ok(report_body)
+}
+
+/// Verify the quote of another instance of this enclave, such as a job worker, and return its
+/// report body. Unlike `verify_quote_same_signer`, its MRENCLAVE must be ours.
+pub fn verify_quote_same_enclave(
+    quote: &[u8],
+    collateral: &[u8],
+    time_s: i64,
+) -> Result<sgx_report_body_t, CryptoError> {
+    let report_body = verify_quote_same_signer(quote, collateral, time_s)?;
+
+    if report_body.mr_enclave.m != SELF_REPORT_BODY.mr_enclave.m {
+        debug!(
+            "quote has the wrong mr_enclave: {}",
+            hex::encode(report_body.mr_enclave.m)
+        );
+        return Err(CryptoError::VerificationError);
+    }
+
+    Ok(report_body)
 }
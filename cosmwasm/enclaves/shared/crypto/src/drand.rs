@@ -0,0 +1,170 @@
+//! Verification of drand randomness beacons.
+//!
+//! drand publishes a BLS threshold signature (on BLS12-381) for every round. For the League of
+//! Entropy mainnet chain the group public key lives in G1 and the round signatures live in G2.
+//! The signed message of a chained beacon is `sha256(previous_signature || round)`, and the
+//! randomness of a round is `sha256(signature)`.
+//!
+//! See https://drand.love/docs/specification/#beacon-signature
+
+use std::convert::TryInto;
+
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{multi_miller_loop, G1Affine, G2Affine, G2Prepared, G2Projective, Gt};
+use log::*;
+
+use crate::errors::CryptoError;
+use crate::hash::sha::{sha_256, HASH_SIZE};
+
+pub const DRAND_PUBKEY_SIZE: usize = 48;
+pub const DRAND_SIGNATURE_SIZE: usize = 96;
+
+/// Domain separation tag used by drand for signatures in G2
+const DRAND_DST_G2: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// Group public key of the League of Entropy drand mainnet (chain hash
+/// 8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce)
+pub const DRAND_MAINNET_PUBKEY: [u8; DRAND_PUBKEY_SIZE] = [
+    0x86, 0x8f, 0x00, 0x5e, 0xb8, 0xe6, 0xe4, 0xca, 0x0a, 0x47, 0xc8, 0xa7, 0x7c, 0xea, 0xa5, 0x30,
+    0x9a, 0x47, 0x97, 0x8a, 0x7c, 0x71, 0xbc, 0x5c, 0xce, 0x96, 0x36, 0x6b, 0x5d, 0x7a, 0x56, 0x99,
+    0x37, 0xc5, 0x29, 0xee, 0xda, 0x66, 0xc7, 0x29, 0x37, 0x84, 0xa9, 0x40, 0x28, 0x01, 0xaf, 0x31,
+];
+
+/// A single drand round as published by the beacon network
+pub struct DrandBeacon<'a> {
+    pub round: u64,
+    pub signature: &'a [u8],
+    /// Empty for unchained beacons
+    pub previous_signature: &'a [u8],
+}
+
+impl<'a> DrandBeacon<'a> {
+    /// The message that the drand group signed for this round
+    pub fn message(&self) -> [u8; HASH_SIZE] {
+        let mut data: Vec<u8> = Vec::with_capacity(self.previous_signature.len() + 8);
+        data.extend_from_slice(self.previous_signature);
+        data.extend_from_slice(&self.round.to_be_bytes());
+
+        sha_256(&data)
+    }
+
+    /// The public randomness of this round. Only meaningful after the beacon was verified.
+    pub fn randomness(&self) -> [u8; HASH_SIZE] {
+        sha_256(self.signature)
+    }
+}
+
+/// Verify a beacon against the drand mainnet group key
+pub fn verify_drand_beacon(beacon: &DrandBeacon) -> Result<(), CryptoError> {
+    verify_drand_beacon_with_pubkey(beacon, &DRAND_MAINNET_PUBKEY)
+}
+
+pub fn verify_drand_beacon_with_pubkey(
+    beacon: &DrandBeacon,
+    pubkey: &[u8],
+) -> Result<(), CryptoError> {
+    let pubkey = parse_g1(pubkey)?;
+    let signature = parse_g2(beacon.signature)?;
+
+    let msg_on_curve: G2Affine =
+        <G2Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(
+            beacon.message(),
+            DRAND_DST_G2,
+        )
+        .into();
+
+    // e(g1, signature) == e(pubkey, H(m))  <=>  e(-g1, signature) * e(pubkey, H(m)) == 1
+    let result = multi_miller_loop(&[
+        (&-G1Affine::generator(), &G2Prepared::from(signature)),
+        (&pubkey, &G2Prepared::from(msg_on_curve)),
+    ])
+    .final_exponentiation();
+
+    if result == Gt::identity() {
+        Ok(())
+    } else {
+        debug!("drand beacon for round {} failed to verify", beacon.round);
+        Err(CryptoError::VerificationError)
+    }
+}
+
+fn parse_g1(bytes: &[u8]) -> Result<G1Affine, CryptoError> {
+    let bytes: &[u8; DRAND_PUBKEY_SIZE] = bytes.try_into().map_err(|_| {
+        debug!("drand public key has wrong length: {}", bytes.len());
+        CryptoError::KeyError
+    })?;
+
+    Option::from(G1Affine::from_compressed(bytes)).ok_or(CryptoError::KeyError)
+}
+
+fn parse_g2(bytes: &[u8]) -> Result<G2Affine, CryptoError> {
+    let bytes: &[u8; DRAND_SIGNATURE_SIZE] = bytes.try_into().map_err(|_| {
+        debug!("drand signature has wrong length: {}", bytes.len());
+        CryptoError::ParsingError
+    })?;
+
+    Option::from(G2Affine::from_compressed(bytes)).ok_or(CryptoError::ParsingError)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    use bls12_381::{G1Projective, Scalar};
+
+    fn sign(sk: &Scalar, beacon: &DrandBeacon) -> [u8; DRAND_SIGNATURE_SIZE] {
+        let h = <G2Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(
+            beacon.message(),
+            DRAND_DST_G2,
+        );
+
+        G2Affine::from(h * sk).to_compressed()
+    }
+
+    pub fn test_drand_verify_chained_beacon() {
+        let sk = Scalar::from(0x1337_u64);
+        let pk = G1Affine::from(G1Projective::generator() * sk).to_compressed();
+
+        let previous_signature = [7u8; DRAND_SIGNATURE_SIZE];
+        let mut beacon = DrandBeacon {
+            round: 1234,
+            signature: &[],
+            previous_signature: &previous_signature,
+        };
+        let signature = sign(&sk, &beacon);
+        beacon.signature = &signature;
+
+        assert!(verify_drand_beacon_with_pubkey(&beacon, &pk).is_ok());
+        assert_eq!(beacon.randomness(), sha_256(&signature));
+    }
+
+    pub fn test_drand_verify_wrong_round() {
+        let sk = Scalar::from(0x1337_u64);
+        let pk = G1Affine::from(G1Projective::generator() * sk).to_compressed();
+
+        let beacon = DrandBeacon {
+            round: 1,
+            signature: &[],
+            previous_signature: &[],
+        };
+        let signature = sign(&sk, &beacon);
+
+        let forged = DrandBeacon {
+            round: 2,
+            signature: &signature,
+            previous_signature: &[],
+        };
+
+        assert!(verify_drand_beacon_with_pubkey(&forged, &pk).is_err());
+    }
+
+    pub fn test_drand_verify_malformed_inputs() {
+        let beacon = DrandBeacon {
+            round: 1,
+            signature: &[1u8; 10],
+            previous_signature: &[],
+        };
+
+        assert!(verify_drand_beacon(&beacon).is_err());
+        assert!(verify_drand_beacon_with_pubkey(&beacon, &[0u8; 3]).is_err());
+    }
+}
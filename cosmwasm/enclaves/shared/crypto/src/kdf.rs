@@ -1,3 +1,7 @@
+use log::*;
+
+use crate::consts::KEY_CONTEXT_NETWORK_VERSION;
+use crate::errors::CryptoError;
 use crate::traits::Kdf;
 use crate::{AESKey, Seed, SECRET_KEY_SIZE};
 
@@ -9,27 +13,113 @@ const KDF_SALT: [u8; 32] = [
     0x08, 0x52, 0xc2, 0x02, 0xdb, 0x0e, 0x00, 0x97, 0xc1, 0xa1, 0x2e, 0xa6, 0x37, 0xd7, 0xe9, 0x6d,
 ];
 
+/// The algorithms keys can be derived with.
+///
+/// The id of each algorithm is stored alongside the keys derived with it, so it must never
+/// change. Keys derived before algorithms were versioned all used `HkdfSha256`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum KdfAlgorithm {
+    HkdfSha256 = 1,
+    HkdfSha512 = 2,
+}
+
+impl Default for KdfAlgorithm {
+    fn default() -> Self {
+        KdfAlgorithm::HkdfSha256
+    }
+}
+
+impl KdfAlgorithm {
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, CryptoError> {
+        match id {
+            1 => Ok(KdfAlgorithm::HkdfSha256),
+            2 => Ok(KdfAlgorithm::HkdfSha512),
+            _ => {
+                error!("unknown kdf algorithm id {}", id);
+                Err(CryptoError::ParsingError)
+            }
+        }
+    }
+
+    fn hkdf_algorithm(self) -> hkdf::Algorithm {
+        match self {
+            KdfAlgorithm::HkdfSha256 => hkdf::HKDF_SHA256,
+            KdfAlgorithm::HkdfSha512 => hkdf::HKDF_SHA512,
+        }
+    }
+}
+
+/// Separates the derive data of keys bound to a network from that of unbound keys
+const KEY_CONTEXT_DOMAIN: &[u8] = b"secret key context";
+
+/// The network keys are derived for.
+///
+/// Keys derived from a seed that is bound to a context are unique to its chain id and network
+/// version, so two networks that share a seed lineage, e.g. a testnet forked from mainnet state,
+/// don't share keys. Seeds created before contexts were added aren't bound to any, and their keys
+/// are derived from the derive order alone.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyContext {
+    pub chain_id: String,
+    pub network_version: u32,
+}
+
+impl KeyContext {
+    /// The context of the chain `chain_id` at this enclave's network version
+    pub fn new(chain_id: &str) -> Self {
+        KeyContext {
+            chain_id: chain_id.to_string(),
+            network_version: KEY_CONTEXT_NETWORK_VERSION,
+        }
+    }
+}
+
+/// The data the key with `derive_order` is derived with, from a seed bound to `context`.
+///
+/// `derive order || domain || chain id length (4) || chain id || network version (4)`, all
+/// numbers big endian, or just the derive order for unbound seeds.
+pub fn derive_data(context: Option<&KeyContext>, derive_order: u32) -> Vec<u8> {
+    let mut data = derive_order.to_be_bytes().to_vec();
+    if let Some(context) = context {
+        data.extend_from_slice(KEY_CONTEXT_DOMAIN);
+        data.extend_from_slice(&(context.chain_id.len() as u32).to_be_bytes());
+        data.extend_from_slice(context.chain_id.as_bytes());
+        data.extend_from_slice(&context.network_version.to_be_bytes());
+    }
+    data
+}
+
 impl Kdf<AESKey> for AESKey {
-    fn derive_key_from_this(&self, data: &[u8]) -> Self {
+    fn derive_key_with(&self, algorithm: KdfAlgorithm, data: &[u8]) -> Self {
         let mut input_bytes: Vec<u8> = self.get().to_vec();
         input_bytes.extend_from_slice(data);
 
-        hkdf_sha_256(&input_bytes, &[])
+        hkdf(algorithm, &input_bytes, &[])
     }
 }
 
 impl Kdf<AESKey> for Seed {
     //
-    fn derive_key_from_this(&self, data: &[u8]) -> AESKey {
+    fn derive_key_with(&self, algorithm: KdfAlgorithm, data: &[u8]) -> AESKey {
         let mut input_bytes: Vec<u8> = self.as_slice().to_vec();
         input_bytes.extend_from_slice(data);
 
-        hkdf_sha_256(&input_bytes, &[b"seed"])
+        hkdf(algorithm, &input_bytes, &[b"seed"])
     }
 }
 
 pub fn hkdf_sha_256(input_bytes: &[u8], info: &[&[u8]]) -> AESKey {
-    let salt = hkdf::Salt::new(hkdf::HKDF_SHA256, &KDF_SALT);
+    hkdf(KdfAlgorithm::HkdfSha256, input_bytes, info)
+}
+
+/// HKDF with `algorithm`'s hash, expanded to a single 32 byte key
+pub fn hkdf(algorithm: KdfAlgorithm, input_bytes: &[u8], info: &[&[u8]]) -> AESKey {
+    let salt = hkdf::Salt::new(algorithm.hkdf_algorithm(), &KDF_SALT);
 
     let prk = salt.extract(input_bytes);
 
@@ -46,7 +136,7 @@ pub fn hkdf_sha_256(input_bytes: &[u8], info: &[&[u8]]) -> AESKey {
 /// Generic newtype wrapper that lets us implement traits for externally-defined
 /// types.
 #[derive(Debug, PartialEq)]
-struct My<T: std::fmt::Debug + PartialEq>(T);
+pub(crate) struct My<T: std::fmt::Debug + PartialEq>(pub(crate) T);
 
 impl hkdf::KeyType for My<usize> {
     fn len(&self) -> usize {
@@ -64,6 +154,8 @@ impl From<hkdf::Okm<'_, My<usize>>> for My<Vec<u8>> {
 
 #[cfg(feature = "test")]
 pub mod tests {
+    use super::*;
+
     // use crate::crypto::CryptoError;
     // use crate::crypto::{Kdf, KeyPair, Seed};
 
@@ -78,4 +170,78 @@ pub mod tests {
     //     assert_eq!(kdf1, b"SOME VALUE");
     //     assert_eq!(kdf2, b"SOME VALUE");
     // }
+
+    pub fn test_kdf_algorithm_ids() {
+        for algorithm in [KdfAlgorithm::HkdfSha256, KdfAlgorithm::HkdfSha512].iter() {
+            assert_eq!(KdfAlgorithm::from_id(algorithm.id()).unwrap(), *algorithm);
+        }
+
+        // ids are stored alongside derived keys, so they must not change
+        assert_eq!(KdfAlgorithm::HkdfSha256.id(), 1);
+        assert_eq!(KdfAlgorithm::HkdfSha512.id(), 2);
+        assert_eq!(KdfAlgorithm::default(), KdfAlgorithm::HkdfSha256);
+
+        assert!(KdfAlgorithm::from_id(0).is_err());
+        assert!(KdfAlgorithm::from_id(3).is_err());
+    }
+
+    pub fn test_kdf_algorithm_agility() {
+        let mut seed = Seed::default();
+        seed.as_mut().copy_from_slice(&[10u8; 32]);
+        let data = 1u32.to_be_bytes();
+
+        // keys derived before algorithms were versioned must not change
+        let legacy = seed.derive_key_from_this(&data);
+        assert_eq!(
+            legacy.get(),
+            seed.derive_key_with(KdfAlgorithm::HkdfSha256, &data).get()
+        );
+        let mut input_bytes = seed.as_slice().to_vec();
+        input_bytes.extend_from_slice(&data);
+        assert_eq!(legacy.get(), hkdf_sha_256(&input_bytes, &[b"seed"]).get());
+
+        let sha512 = seed.derive_key_with(KdfAlgorithm::HkdfSha512, &data);
+        assert_ne!(legacy.get(), sha512.get());
+        assert_eq!(
+            sha512.get(),
+            seed.derive_key_with(KdfAlgorithm::HkdfSha512, &data).get()
+        );
+    }
+
+    pub fn test_key_context_binding() {
+        let mut seed = Seed::default();
+        seed.as_mut().copy_from_slice(&[10u8; 32]);
+        let mainnet = KeyContext {
+            chain_id: "secret-4".to_string(),
+            network_version: 1,
+        };
+
+        // unbound seeds derive the keys they derived before contexts were added
+        assert_eq!(derive_data(None, 3), 3u32.to_be_bytes().to_vec());
+
+        let derive = |context: Option<&KeyContext>| {
+            seed.derive_key_with(KdfAlgorithm::HkdfSha256, &derive_data(context, 3))
+                .get()
+                .to_vec()
+        };
+        let testnet = KeyContext {
+            chain_id: "pulsar-3".to_string(),
+            ..mainnet.clone()
+        };
+        let upgraded = KeyContext {
+            network_version: 2,
+            ..mainnet.clone()
+        };
+
+        let keys = [
+            derive(None),
+            derive(Some(&mainnet)),
+            derive(Some(&testnet)),
+            derive(Some(&upgraded)),
+        ];
+        for (i, key) in keys.iter().enumerate() {
+            assert!(keys[i + 1..].iter().all(|other| other != key));
+        }
+        assert_eq!(keys[1], derive(Some(&mainnet)));
+    }
 }
@@ -21,8 +21,15 @@ mod aes_siv;
 pub mod ed25519;
 mod hmac;
 pub mod secp256k1;
+pub mod secp256r1;
+pub mod self_test;
+pub mod symmetric;
 
 pub mod dcap;
+pub mod drand;
+pub mod ocalls;
+pub mod oracle;
+pub mod x509;
 
 mod rng;
 
@@ -36,14 +43,22 @@ pub use ed25519::{Ed25519PublicKey, KeyPair, PUBLIC_KEY_SIZE, SECRET_KEY_SIZE};
 pub use hash::sha::{sha_256, HASH_SIZE};
 pub use traits::{Encryptable, Hmac, Kdf, SIVEncryptable, SealedKey, HMAC_SIGNATURE_SIZE};
 
-pub use kdf::hkdf_sha_256;
+pub use kdf::{derive_data, hkdf, hkdf_sha_256, KdfAlgorithm, KeyContext};
+pub use rng::rand_slice;
 
 #[cfg(feature = "test")]
 pub mod tests {
     use crate::aes_siv;
+    use crate::drand;
     use crate::ed25519;
     use crate::hash;
     use crate::hmac;
+    use crate::kdf;
+    use crate::ocalls;
+    use crate::oracle;
+    use crate::self_test;
+    use crate::symmetric;
+    use crate::x509;
 
     /// Catch failures like the standard test runner, and print similar information per test.
     /// Tests can only fail by panicking, not by returning a `Result` type.
@@ -78,10 +93,44 @@ pub mod tests {
             
             // HMAC tests
             hmac::tests::test_hmac_sha256;
+
+            // KDF tests
+            kdf::tests::test_kdf_algorithm_ids();
+            kdf::tests::test_kdf_algorithm_agility();
+            kdf::tests::test_key_context_binding();
             
             // Ed25519 tests
             ed25519::tests::test_keypair_generation;
             ed25519::tests::test_signing_and_verification;
+
+            // drand beacon tests
+            drand::tests::test_drand_verify_chained_beacon();
+            drand::tests::test_drand_verify_wrong_round();
+            drand::tests::test_drand_verify_malformed_inputs();
+
+            // ocall dispatcher tests
+            ocalls::tests::test_ocall_table_order();
+            ocalls::tests::test_ocall_validation();
+            ocalls::tests::test_ocall_rejections_are_counted();
+
+            // oracle attestation tests
+            oracle::tests::test_oracle_attestation_roundtrip();
+            oracle::tests::test_oracle_attestation_signature();
+            oracle::tests::test_oracle_quote_too_small();
+            oracle::tests::test_oracle_fetcher_report();
+
+            // x509 chain validation tests
+            x509::tests::test_x509_verify_chain();
+            x509::tests::test_x509_verify_chain_rejects_invalid_chains();
+            x509::tests::test_x509_oid_to_string();
+
+            // HKDF, HMAC and AEAD tests
+            symmetric::tests::test_hkdf_sha256();
+            symmetric::tests::test_hmac_sha256();
+            symmetric::tests::test_aead_seal_and_open();
+
+            // startup known-answer tests
+            self_test::tests::test_known_answers();
         });
 
         if failures != 0 {
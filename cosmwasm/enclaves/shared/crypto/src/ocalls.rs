@@ -0,0 +1,949 @@
+//! The ocalls of the enclave, and the only place they're called from.
+//!
+//! Every call to the untrusted host goes through `dispatch`, which checks the arguments of the
+//! ocall against its entry in `OCALLS`, and counts it. An ocall with a pointer the host would
+//! dereference set to null, or an input or output buffer larger than its limit, is rejected
+//! with `SGX_ERROR_INVALID_PARAMETER` before it leaves the enclave. The wrappers below have the
+//! same signatures as the ocalls declared in the EDL, so a new ocall isn't callable until it's
+//! added to the table.
+//!
+//! This lives in `enclave_crypto` because it's the lowest of the enclave crates, and the DCAP
+//! quote verification here makes an ocall too.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use log::*;
+
+use sgx_types::{
+    sgx_epid_group_id_t, sgx_platform_info_t, sgx_ql_qe_report_info_t, sgx_ql_qv_result_t,
+    sgx_quote_nonce_t, sgx_quote_sign_type_t, sgx_report_t, sgx_spid_t, sgx_status_t,
+    sgx_target_info_t, sgx_update_info_bit_t,
+};
+
+use enclave_ffi_types::{Ctx, EnclaveBuffer, OcallReturn, UntrustedVmError, UserSpaceBuffer};
+
+/// Contract storage keys are short, this only stops runaway ones
+const MAX_DB_KEY_LENGTH: usize = 1024 * 1024; // 1 MiB
+const MAX_PAYLOAD_LENGTH: usize = 16 * 1024 * 1024; // 16 MiB
+/// Quotes, their collateral, sigrls and the QvE's supplemental data
+const MAX_ATTESTATION_LENGTH: usize = 1024 * 1024; // 1 MiB
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ocall {
+    Allocate,
+    ReadDb,
+    ReadDbFirst,
+    QueryChain,
+    RemoveDb,
+    WriteDb,
+    MultipleWriteDb,
+    ReportEngineDivergence,
+    SgxInitQuote,
+    GetIasSocket,
+    GetSnTssSocket,
+    GetQuote,
+    GetQuoteEcdsaParams,
+    GetQuoteEcdsa,
+    GetQuoteEcdsaCollateral,
+    VerifyQuoteEcdsa,
+    GetUpdateInfo,
+}
+
+pub struct OcallSpec {
+    pub ocall: Ocall,
+    pub name: &'static str,
+    /// Largest total length of the buffers the enclave sends to the host
+    pub max_input_len: usize,
+    /// Largest buffer the host can write back to
+    pub max_output_len: usize,
+}
+
+const OCALL_COUNT: usize = 17;
+
+/// Everything the enclave calls on the host, in the order of `Ocall`
+pub const OCALLS: [OcallSpec; OCALL_COUNT] = [
+    OcallSpec {
+        ocall: Ocall::Allocate,
+        name: "ocall_allocate",
+        max_input_len: MAX_PAYLOAD_LENGTH,
+        max_output_len: 0,
+    },
+    OcallSpec {
+        ocall: Ocall::ReadDb,
+        name: "ocall_read_db",
+        max_input_len: MAX_DB_KEY_LENGTH,
+        max_output_len: 0,
+    },
+    OcallSpec {
+        ocall: Ocall::ReadDbFirst,
+        name: "ocall_read_db_first",
+        // the keys, serialized together
+        max_input_len: MAX_PAYLOAD_LENGTH,
+        max_output_len: 0,
+    },
+    OcallSpec {
+        ocall: Ocall::QueryChain,
+        name: "ocall_query_chain",
+        max_input_len: MAX_PAYLOAD_LENGTH,
+        max_output_len: 0,
+    },
+    OcallSpec {
+        ocall: Ocall::RemoveDb,
+        name: "ocall_remove_db",
+        max_input_len: MAX_DB_KEY_LENGTH,
+        max_output_len: 0,
+    },
+    OcallSpec {
+        ocall: Ocall::WriteDb,
+        name: "ocall_write_db",
+        max_input_len: MAX_DB_KEY_LENGTH + MAX_PAYLOAD_LENGTH,
+        max_output_len: 0,
+    },
+    OcallSpec {
+        ocall: Ocall::MultipleWriteDb,
+        name: "ocall_multiple_write_db",
+        max_input_len: MAX_PAYLOAD_LENGTH,
+        max_output_len: 0,
+    },
+    OcallSpec {
+        ocall: Ocall::ReportEngineDivergence,
+        name: "ocall_report_engine_divergence",
+        max_input_len: MAX_PAYLOAD_LENGTH,
+        max_output_len: 0,
+    },
+    OcallSpec {
+        ocall: Ocall::SgxInitQuote,
+        name: "ocall_sgx_init_quote",
+        max_input_len: 0,
+        max_output_len: 0,
+    },
+    OcallSpec {
+        ocall: Ocall::GetIasSocket,
+        name: "ocall_get_ias_socket",
+        max_input_len: 0,
+        max_output_len: 0,
+    },
+    OcallSpec {
+        ocall: Ocall::GetSnTssSocket,
+        name: "ocall_get_sn_tss_socket",
+        max_input_len: 0,
+        max_output_len: 0,
+    },
+    OcallSpec {
+        ocall: Ocall::GetQuote,
+        name: "ocall_get_quote",
+        max_input_len: MAX_ATTESTATION_LENGTH,
+        max_output_len: MAX_ATTESTATION_LENGTH,
+    },
+    OcallSpec {
+        ocall: Ocall::GetQuoteEcdsaParams,
+        name: "ocall_get_quote_ecdsa_params",
+        max_input_len: 0,
+        max_output_len: 0,
+    },
+    OcallSpec {
+        ocall: Ocall::GetQuoteEcdsa,
+        name: "ocall_get_quote_ecdsa",
+        max_input_len: 0,
+        max_output_len: MAX_ATTESTATION_LENGTH,
+    },
+    OcallSpec {
+        ocall: Ocall::GetQuoteEcdsaCollateral,
+        name: "ocall_get_quote_ecdsa_collateral",
+        max_input_len: MAX_ATTESTATION_LENGTH,
+        max_output_len: MAX_ATTESTATION_LENGTH,
+    },
+    OcallSpec {
+        ocall: Ocall::VerifyQuoteEcdsa,
+        name: "ocall_verify_quote_ecdsa",
+        max_input_len: 2 * MAX_ATTESTATION_LENGTH,
+        max_output_len: MAX_ATTESTATION_LENGTH,
+    },
+    OcallSpec {
+        ocall: Ocall::GetUpdateInfo,
+        name: "ocall_get_update_info",
+        max_input_len: 0,
+        max_output_len: 0,
+    },
+];
+
+struct OcallCounters {
+    calls: AtomicU64,
+    rejected: AtomicU64,
+    failed: AtomicU64,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_CALLS: OcallCounters = OcallCounters {
+    calls: AtomicU64::new(0),
+    rejected: AtomicU64::new(0),
+    failed: AtomicU64::new(0),
+};
+
+static COUNTERS: [OcallCounters; OCALL_COUNT] = [NO_CALLS; OCALL_COUNT];
+
+/// How often an ocall was made since the enclave started
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OcallStats {
+    pub name: &'static str,
+    pub calls: u64,
+    /// Calls whose arguments didn't pass validation, and never reached the host
+    pub rejected: u64,
+    /// Calls that reached the host, but returned an SGX error
+    pub failed: u64,
+}
+
+pub fn ocall_stats() -> Vec<OcallStats> {
+    OCALLS
+        .iter()
+        .zip(COUNTERS.iter())
+        .map(|(spec, counters)| OcallStats {
+            name: spec.name,
+            calls: counters.calls.load(Ordering::Relaxed),
+            rejected: counters.rejected.load(Ordering::Relaxed),
+            failed: counters.failed.load(Ordering::Relaxed),
+        })
+        .collect()
+}
+
+/// The arguments of an ocall that are validated
+struct OcallArgs<'a> {
+    /// Pointers the host dereferences
+    pointers: &'a [*const u8],
+    /// Lengths of the buffers sent to the host
+    inputs: &'a [usize],
+    /// Capacities of the buffers the host writes to
+    outputs: &'a [usize],
+}
+
+fn validate(spec: &OcallSpec, args: &OcallArgs) -> Result<(), &'static str> {
+    if args.pointers.iter().any(|pointer| pointer.is_null()) {
+        return Err("null pointer");
+    }
+
+    let input_len = args
+        .inputs
+        .iter()
+        .try_fold(0usize, |total, len| total.checked_add(*len));
+    match input_len {
+        Some(len) if len <= spec.max_input_len => {}
+        _ => return Err("input too large"),
+    }
+
+    if args.outputs.iter().any(|len| *len > spec.max_output_len) {
+        return Err("output buffer too large");
+    }
+
+    Ok(())
+}
+
+fn dispatch<F>(ocall: Ocall, args: OcallArgs, call: F) -> sgx_status_t
+where
+    F: FnOnce() -> sgx_status_t,
+{
+    let spec = &OCALLS[ocall as usize];
+    let counters = &COUNTERS[ocall as usize];
+    counters.calls.fetch_add(1, Ordering::Relaxed);
+
+    if let Err(reason) = validate(spec, &args) {
+        counters.rejected.fetch_add(1, Ordering::Relaxed);
+        warn!("Rejected {}: {}", spec.name, reason);
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+
+    let status = call();
+    if status != sgx_status_t::SGX_SUCCESS {
+        counters.failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    status
+}
+
+/// A buffer the host reads or writes, which may only be null when it's empty
+fn buffer(pointer: *const u8, len: usize) -> *const u8 {
+    if len == 0 {
+        // Not dereferenced
+        return core::ptr::NonNull::dangling().as_ptr();
+    }
+    pointer
+}
+
+mod raw {
+    use super::*;
+
+    extern "C" {
+        pub fn ocall_allocate(
+            retval: *mut UserSpaceBuffer,
+            buffer: *const u8,
+            length: usize,
+        ) -> sgx_status_t;
+
+        pub fn ocall_read_db(
+            retval: *mut OcallReturn,
+            context: Ctx,
+            vm_error: *mut UntrustedVmError,
+            gas_used: *mut u64,
+            value: *mut EnclaveBuffer,
+            key: *const u8,
+            key_len: usize,
+        ) -> sgx_status_t;
+
+        pub fn ocall_read_db_first(
+            retval: *mut OcallReturn,
+            context: Ctx,
+            vm_error: *mut UntrustedVmError,
+            gas_used: *mut u64,
+            value: *mut EnclaveBuffer,
+            key_index: *mut u32,
+            keys: *const u8,
+            keys_len: usize,
+        ) -> sgx_status_t;
+
+        pub fn ocall_query_chain(
+            retval: *mut OcallReturn,
+            context: Ctx,
+            vm_error: *mut UntrustedVmError,
+            gas_used: *mut u64,
+            gas_limit: u64,
+            value: *mut EnclaveBuffer,
+            query: *const u8,
+            query_len: usize,
+            query_depth: u32,
+        ) -> sgx_status_t;
+
+        pub fn ocall_remove_db(
+            retval: *mut OcallReturn,
+            context: Ctx,
+            vm_error: *mut UntrustedVmError,
+            gas_used: *mut u64,
+            key: *const u8,
+            key_len: usize,
+        ) -> sgx_status_t;
+
+        pub fn ocall_write_db(
+            retval: *mut OcallReturn,
+            context: Ctx,
+            vm_error: *mut UntrustedVmError,
+            gas_used: *mut u64,
+            key: *const u8,
+            key_len: usize,
+            value: *const u8,
+            value_len: usize,
+        ) -> sgx_status_t;
+
+        pub fn ocall_multiple_write_db(
+            retval: *mut OcallReturn,
+            context: Ctx,
+            vm_error: *mut UntrustedVmError,
+            gas_used: *mut u64,
+            keys: *const u8,
+            keys_len: usize,
+        ) -> sgx_status_t;
+
+        pub fn ocall_report_engine_divergence(report: *const u8, report_len: usize)
+            -> sgx_status_t;
+
+        pub fn ocall_sgx_init_quote(
+            ret_val: *mut sgx_status_t,
+            ret_ti: *mut sgx_target_info_t,
+            ret_gid: *mut sgx_epid_group_id_t,
+        ) -> sgx_status_t;
+
+        pub fn ocall_get_ias_socket(ret_val: *mut sgx_status_t, ret_fd: *mut i32) -> sgx_status_t;
+
+        pub fn ocall_get_sn_tss_socket(
+            ret_val: *mut sgx_status_t,
+            ret_fd: *mut i32,
+        ) -> sgx_status_t;
+
+        pub fn ocall_get_quote(
+            ret_val: *mut sgx_status_t,
+            p_sigrl: *const u8,
+            sigrl_len: u32,
+            p_report: *const sgx_report_t,
+            quote_type: sgx_quote_sign_type_t,
+            p_spid: *const sgx_spid_t,
+            p_nonce: *const sgx_quote_nonce_t,
+            p_qe_report: *mut sgx_report_t,
+            p_quote: *mut u8,
+            maxlen: u32,
+            p_quote_len: *mut u32,
+        ) -> sgx_status_t;
+
+        pub fn ocall_get_quote_ecdsa_params(
+            ret_val: *mut sgx_status_t,
+            p_qe_info: *mut sgx_target_info_t,
+            p_quote_size: *mut u32,
+        ) -> sgx_status_t;
+
+        pub fn ocall_get_quote_ecdsa(
+            ret_val: *mut sgx_status_t,
+            p_report: *const sgx_report_t,
+            p_quote: *mut u8,
+            n_quote: u32,
+        ) -> sgx_status_t;
+
+        pub fn ocall_get_quote_ecdsa_collateral(
+            ret_val: *mut sgx_status_t,
+            p_quote: *const u8,
+            n_quote: u32,
+            p_col: *mut u8,
+            n_col: u32,
+            p_col_out: *mut u32,
+        ) -> sgx_status_t;
+
+        pub fn ocall_verify_quote_ecdsa(
+            ret_val: *mut sgx_status_t,
+            p_quote: *const u8,
+            n_quote: u32,
+            p_col: *const u8,
+            n_col: u32,
+            p_target_info: *const sgx_target_info_t,
+            time_s: i64,
+            p_qve_report_info: *mut sgx_ql_qe_report_info_t,
+            p_supp_data: *mut u8,
+            n_supp_data: u32,
+            p_supp_data_size: *mut u32,
+            p_time_s: *mut i64,
+            p_collateral_expiration_status: *mut u32,
+            p_qv_result: *mut sgx_ql_qv_result_t,
+        ) -> sgx_status_t;
+
+        pub fn ocall_get_update_info(
+            ret_val: *mut sgx_status_t,
+            platformBlob: *const sgx_platform_info_t,
+            enclaveTrusted: i32,
+            update_info: *mut sgx_update_info_bit_t,
+        ) -> sgx_status_t;
+    }
+}
+
+/// # Safety
+/// Like calling `ocall_allocate` directly, the buffer must be valid for `length` bytes
+pub unsafe fn ocall_allocate(
+    retval: *mut UserSpaceBuffer,
+    buffer_ptr: *const u8,
+    length: usize,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[retval as *const u8, buffer(buffer_ptr, length)],
+        inputs: &[length],
+        outputs: &[],
+    };
+    dispatch(Ocall::Allocate, args, || {
+        raw::ocall_allocate(retval, buffer_ptr, length)
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_read_db` directly
+pub unsafe fn ocall_read_db(
+    retval: *mut OcallReturn,
+    context: Ctx,
+    vm_error: *mut UntrustedVmError,
+    gas_used: *mut u64,
+    value: *mut EnclaveBuffer,
+    key: *const u8,
+    key_len: usize,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            retval as *const u8,
+            vm_error as *const u8,
+            gas_used as *const u8,
+            value as *const u8,
+            buffer(key, key_len),
+        ],
+        inputs: &[key_len],
+        outputs: &[],
+    };
+    dispatch(Ocall::ReadDb, args, || {
+        raw::ocall_read_db(retval, context, vm_error, gas_used, value, key, key_len)
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_read_db_first` directly
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn ocall_read_db_first(
+    retval: *mut OcallReturn,
+    context: Ctx,
+    vm_error: *mut UntrustedVmError,
+    gas_used: *mut u64,
+    value: *mut EnclaveBuffer,
+    key_index: *mut u32,
+    keys: *const u8,
+    keys_len: usize,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            retval as *const u8,
+            vm_error as *const u8,
+            gas_used as *const u8,
+            value as *const u8,
+            key_index as *const u8,
+            buffer(keys, keys_len),
+        ],
+        inputs: &[keys_len],
+        outputs: &[],
+    };
+    dispatch(Ocall::ReadDbFirst, args, || {
+        raw::ocall_read_db_first(
+            retval, context, vm_error, gas_used, value, key_index, keys, keys_len,
+        )
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_query_chain` directly
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn ocall_query_chain(
+    retval: *mut OcallReturn,
+    context: Ctx,
+    vm_error: *mut UntrustedVmError,
+    gas_used: *mut u64,
+    gas_limit: u64,
+    value: *mut EnclaveBuffer,
+    query: *const u8,
+    query_len: usize,
+    query_depth: u32,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            retval as *const u8,
+            vm_error as *const u8,
+            gas_used as *const u8,
+            value as *const u8,
+            buffer(query, query_len),
+        ],
+        inputs: &[query_len],
+        outputs: &[],
+    };
+    dispatch(Ocall::QueryChain, args, || {
+        raw::ocall_query_chain(
+            retval,
+            context,
+            vm_error,
+            gas_used,
+            gas_limit,
+            value,
+            query,
+            query_len,
+            query_depth,
+        )
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_remove_db` directly
+pub unsafe fn ocall_remove_db(
+    retval: *mut OcallReturn,
+    context: Ctx,
+    vm_error: *mut UntrustedVmError,
+    gas_used: *mut u64,
+    key: *const u8,
+    key_len: usize,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            retval as *const u8,
+            vm_error as *const u8,
+            gas_used as *const u8,
+            buffer(key, key_len),
+        ],
+        inputs: &[key_len],
+        outputs: &[],
+    };
+    dispatch(Ocall::RemoveDb, args, || {
+        raw::ocall_remove_db(retval, context, vm_error, gas_used, key, key_len)
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_write_db` directly
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn ocall_write_db(
+    retval: *mut OcallReturn,
+    context: Ctx,
+    vm_error: *mut UntrustedVmError,
+    gas_used: *mut u64,
+    key: *const u8,
+    key_len: usize,
+    value: *const u8,
+    value_len: usize,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            retval as *const u8,
+            vm_error as *const u8,
+            gas_used as *const u8,
+            buffer(key, key_len),
+            buffer(value, value_len),
+        ],
+        inputs: &[key_len, value_len],
+        outputs: &[],
+    };
+    dispatch(Ocall::WriteDb, args, || {
+        raw::ocall_write_db(
+            retval, context, vm_error, gas_used, key, key_len, value, value_len,
+        )
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_multiple_write_db` directly
+pub unsafe fn ocall_multiple_write_db(
+    retval: *mut OcallReturn,
+    context: Ctx,
+    vm_error: *mut UntrustedVmError,
+    gas_used: *mut u64,
+    keys: *const u8,
+    keys_len: usize,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            retval as *const u8,
+            vm_error as *const u8,
+            gas_used as *const u8,
+            buffer(keys, keys_len),
+        ],
+        inputs: &[keys_len],
+        outputs: &[],
+    };
+    dispatch(Ocall::MultipleWriteDb, args, || {
+        raw::ocall_multiple_write_db(retval, context, vm_error, gas_used, keys, keys_len)
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_report_engine_divergence` directly
+pub unsafe fn ocall_report_engine_divergence(report: *const u8, report_len: usize) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[buffer(report, report_len)],
+        inputs: &[report_len],
+        outputs: &[],
+    };
+    dispatch(Ocall::ReportEngineDivergence, args, || {
+        raw::ocall_report_engine_divergence(report, report_len)
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_sgx_init_quote` directly
+pub unsafe fn ocall_sgx_init_quote(
+    ret_val: *mut sgx_status_t,
+    ret_ti: *mut sgx_target_info_t,
+    ret_gid: *mut sgx_epid_group_id_t,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            ret_val as *const u8,
+            ret_ti as *const u8,
+            ret_gid as *const u8,
+        ],
+        inputs: &[],
+        outputs: &[],
+    };
+    dispatch(Ocall::SgxInitQuote, args, || {
+        raw::ocall_sgx_init_quote(ret_val, ret_ti, ret_gid)
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_get_ias_socket` directly
+pub unsafe fn ocall_get_ias_socket(ret_val: *mut sgx_status_t, ret_fd: *mut i32) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[ret_val as *const u8, ret_fd as *const u8],
+        inputs: &[],
+        outputs: &[],
+    };
+    dispatch(Ocall::GetIasSocket, args, || {
+        raw::ocall_get_ias_socket(ret_val, ret_fd)
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_get_sn_tss_socket` directly
+pub unsafe fn ocall_get_sn_tss_socket(
+    ret_val: *mut sgx_status_t,
+    ret_fd: *mut i32,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[ret_val as *const u8, ret_fd as *const u8],
+        inputs: &[],
+        outputs: &[],
+    };
+    dispatch(Ocall::GetSnTssSocket, args, || {
+        raw::ocall_get_sn_tss_socket(ret_val, ret_fd)
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_get_quote` directly
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn ocall_get_quote(
+    ret_val: *mut sgx_status_t,
+    p_sigrl: *const u8,
+    sigrl_len: u32,
+    p_report: *const sgx_report_t,
+    quote_type: sgx_quote_sign_type_t,
+    p_spid: *const sgx_spid_t,
+    p_nonce: *const sgx_quote_nonce_t,
+    p_qe_report: *mut sgx_report_t,
+    p_quote: *mut u8,
+    maxlen: u32,
+    p_quote_len: *mut u32,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            ret_val as *const u8,
+            buffer(p_sigrl, sigrl_len as usize),
+            p_report as *const u8,
+            p_spid as *const u8,
+            p_nonce as *const u8,
+            p_qe_report as *const u8,
+            buffer(p_quote, maxlen as usize),
+            p_quote_len as *const u8,
+        ],
+        inputs: &[sigrl_len as usize],
+        outputs: &[maxlen as usize],
+    };
+    dispatch(Ocall::GetQuote, args, || {
+        raw::ocall_get_quote(
+            ret_val,
+            p_sigrl,
+            sigrl_len,
+            p_report,
+            quote_type,
+            p_spid,
+            p_nonce,
+            p_qe_report,
+            p_quote,
+            maxlen,
+            p_quote_len,
+        )
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_get_quote_ecdsa_params` directly
+pub unsafe fn ocall_get_quote_ecdsa_params(
+    ret_val: *mut sgx_status_t,
+    p_qe_info: *mut sgx_target_info_t,
+    p_quote_size: *mut u32,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            ret_val as *const u8,
+            p_qe_info as *const u8,
+            p_quote_size as *const u8,
+        ],
+        inputs: &[],
+        outputs: &[],
+    };
+    dispatch(Ocall::GetQuoteEcdsaParams, args, || {
+        raw::ocall_get_quote_ecdsa_params(ret_val, p_qe_info, p_quote_size)
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_get_quote_ecdsa` directly
+pub unsafe fn ocall_get_quote_ecdsa(
+    ret_val: *mut sgx_status_t,
+    p_report: *const sgx_report_t,
+    p_quote: *mut u8,
+    n_quote: u32,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            ret_val as *const u8,
+            p_report as *const u8,
+            buffer(p_quote, n_quote as usize),
+        ],
+        inputs: &[],
+        outputs: &[n_quote as usize],
+    };
+    dispatch(Ocall::GetQuoteEcdsa, args, || {
+        raw::ocall_get_quote_ecdsa(ret_val, p_report, p_quote, n_quote)
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_get_quote_ecdsa_collateral` directly
+pub unsafe fn ocall_get_quote_ecdsa_collateral(
+    ret_val: *mut sgx_status_t,
+    p_quote: *const u8,
+    n_quote: u32,
+    p_col: *mut u8,
+    n_col: u32,
+    p_col_out: *mut u32,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            ret_val as *const u8,
+            buffer(p_quote, n_quote as usize),
+            buffer(p_col, n_col as usize),
+            p_col_out as *const u8,
+        ],
+        inputs: &[n_quote as usize],
+        outputs: &[n_col as usize],
+    };
+    dispatch(Ocall::GetQuoteEcdsaCollateral, args, || {
+        raw::ocall_get_quote_ecdsa_collateral(ret_val, p_quote, n_quote, p_col, n_col, p_col_out)
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_verify_quote_ecdsa` directly
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn ocall_verify_quote_ecdsa(
+    ret_val: *mut sgx_status_t,
+    p_quote: *const u8,
+    n_quote: u32,
+    p_col: *const u8,
+    n_col: u32,
+    p_target_info: *const sgx_target_info_t,
+    time_s: i64,
+    p_qve_report_info: *mut sgx_ql_qe_report_info_t,
+    p_supp_data: *mut u8,
+    n_supp_data: u32,
+    p_supp_data_size: *mut u32,
+    p_time_s: *mut i64,
+    p_collateral_expiration_status: *mut u32,
+    p_qv_result: *mut sgx_ql_qv_result_t,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            ret_val as *const u8,
+            buffer(p_quote, n_quote as usize),
+            buffer(p_col, n_col as usize),
+            p_target_info as *const u8,
+            p_qve_report_info as *const u8,
+            buffer(p_supp_data, n_supp_data as usize),
+            p_supp_data_size as *const u8,
+            p_time_s as *const u8,
+            p_collateral_expiration_status as *const u8,
+            p_qv_result as *const u8,
+        ],
+        inputs: &[n_quote as usize, n_col as usize],
+        outputs: &[n_supp_data as usize],
+    };
+    dispatch(Ocall::VerifyQuoteEcdsa, args, || {
+        raw::ocall_verify_quote_ecdsa(
+            ret_val,
+            p_quote,
+            n_quote,
+            p_col,
+            n_col,
+            p_target_info,
+            time_s,
+            p_qve_report_info,
+            p_supp_data,
+            n_supp_data,
+            p_supp_data_size,
+            p_time_s,
+            p_collateral_expiration_status,
+            p_qv_result,
+        )
+    })
+}
+
+/// # Safety
+/// Like calling `ocall_get_update_info` directly
+#[allow(non_snake_case)]
+pub unsafe fn ocall_get_update_info(
+    ret_val: *mut sgx_status_t,
+    platformBlob: *const sgx_platform_info_t,
+    enclaveTrusted: i32,
+    update_info: *mut sgx_update_info_bit_t,
+) -> sgx_status_t {
+    let args = OcallArgs {
+        pointers: &[
+            ret_val as *const u8,
+            platformBlob as *const u8,
+            update_info as *const u8,
+        ],
+        inputs: &[],
+        outputs: &[],
+    };
+    dispatch(Ocall::GetUpdateInfo, args, || {
+        raw::ocall_get_update_info(ret_val, platformBlob, enclaveTrusted, update_info)
+    })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_ocall_table_order() {
+        for (index, spec) in OCALLS.iter().enumerate() {
+            assert_eq!(spec.ocall as usize, index, "{}", spec.name);
+        }
+    }
+
+    pub fn test_ocall_validation() {
+        let spec = &OCALLS[Ocall::ReadDb as usize];
+        let key = [1u8; 4];
+
+        let valid = OcallArgs {
+            pointers: &[key.as_ptr()],
+            inputs: &[key.len()],
+            outputs: &[],
+        };
+        assert!(validate(spec, &valid).is_ok());
+
+        let null = OcallArgs {
+            pointers: &[core::ptr::null()],
+            inputs: &[key.len()],
+            outputs: &[],
+        };
+        assert_eq!(validate(spec, &null), Err("null pointer"));
+
+        // An empty buffer may be null, the host doesn't read it
+        let empty = OcallArgs {
+            pointers: &[buffer(core::ptr::null(), 0)],
+            inputs: &[0],
+            outputs: &[],
+        };
+        assert!(validate(spec, &empty).is_ok());
+
+        let too_large = OcallArgs {
+            pointers: &[key.as_ptr()],
+            inputs: &[MAX_DB_KEY_LENGTH + 1],
+            outputs: &[],
+        };
+        assert_eq!(validate(spec, &too_large), Err("input too large"));
+
+        let overflow = OcallArgs {
+            pointers: &[],
+            inputs: &[usize::MAX, 1],
+            outputs: &[],
+        };
+        assert_eq!(validate(spec, &overflow), Err("input too large"));
+
+        let collateral = &OCALLS[Ocall::GetQuoteEcdsaCollateral as usize];
+        let huge_output = OcallArgs {
+            pointers: &[],
+            inputs: &[],
+            outputs: &[MAX_ATTESTATION_LENGTH + 1],
+        };
+        assert_eq!(
+            validate(collateral, &huge_output),
+            Err("output buffer too large")
+        );
+    }
+
+    pub fn test_ocall_rejections_are_counted() {
+        let before = ocall_stats()[Ocall::ReportEngineDivergence as usize];
+
+        // Never reaches the host, so this is safe to call outside of an enclave
+        let status = unsafe { ocall_report_engine_divergence(core::ptr::null(), 1) };
+        assert_eq!(status, sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+
+        let after = ocall_stats()[Ocall::ReportEngineDivergence as usize];
+        assert_eq!(after.name, "ocall_report_engine_divergence");
+        assert_eq!(after.calls, before.calls + 1);
+        assert_eq!(after.rejected, before.rejected + 1);
+        assert_eq!(after.failed, before.failed);
+    }
+}
@@ -0,0 +1,280 @@
+//! Attestations of HTTPS fetches made by an off-chain oracle fetcher enclave.
+//!
+//! The fetcher is an enclave signed with the same MRSIGNER as the network's enclaves, with the
+//! product ID `ORACLE_FETCHER_PROD_ID`. It produces a DCAP quote whose report data carries its
+//! secp256k1 public key followed by `ORACLE_FETCHER_REPORT_DOMAIN`, then signs every fetch it
+//! performs with the matching secret key. On-chain, a contract hands the attestation, the
+//! signature and the fetcher's quote + collateral to the `oracle_attestation_verify` host
+//! function, which checks the whole chain: quote -> MRSIGNER and product ID -> public key ->
+//! signature.
+//!
+//! The product ID and the domain keep any other enclave of the signer from passing as a fetcher,
+//! e.g. the network's own enclaves, which are product 0 and put other data in their reports.
+//!
+//! The fetcher enclave itself is built and run outside of this repository. This module only
+//! verifies what it produces.
+//!
+//! Wire format of an attestation (all integers are big endian):
+//!
+//! ```text
+//! ORACLE_ATTESTATION_DOMAIN || timestamp (u64) || response_hash (32) || tls_chain_hash (32)
+//!     || url_len (u16) || url
+//! ```
+
+use std::convert::TryInto;
+
+use log::*;
+use sgx_types::sgx_report_body_t;
+
+use crate::dcap::verify_quote_same_signer;
+use crate::errors::CryptoError;
+use crate::hash::sha::{sha_256, HASH_SIZE};
+
+pub const ORACLE_ATTESTATION_DOMAIN: &[u8] = b"secret-oracle-fetch-v1";
+pub const ORACLE_PUBKEY_SIZE: usize = 33;
+pub const ORACLE_SIGNATURE_SIZE: usize = 64;
+pub const MAX_ORACLE_URL_LENGTH: usize = 2048;
+
+/// The ISV product ID the fetcher enclave is signed with
+pub const ORACLE_FETCHER_PROD_ID: u16 = 1;
+/// What follows the public key in the fetcher's report data, padded with zeros
+pub const ORACLE_FETCHER_REPORT_DOMAIN: &[u8] = b"secret-oracle-fetcher-v1";
+
+/// A single HTTPS fetch as observed by the fetcher enclave
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchAttestation {
+    pub url: Vec<u8>,
+    /// Unix time in seconds at which the response was received
+    pub timestamp: u64,
+    /// sha256 of the response body
+    pub response_hash: [u8; HASH_SIZE],
+    /// sha256 of the DER-encoded certificate chain presented by the server
+    pub tls_chain_hash: [u8; HASH_SIZE],
+}
+
+impl FetchAttestation {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            ORACLE_ATTESTATION_DOMAIN.len() + 8 + 2 * HASH_SIZE + 2 + self.url.len(),
+        );
+        bytes.extend_from_slice(ORACLE_ATTESTATION_DOMAIN);
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.extend_from_slice(&self.response_hash);
+        bytes.extend_from_slice(&self.tls_chain_hash);
+        bytes.extend_from_slice(&(self.url.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&self.url);
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let body = bytes
+            .strip_prefix(ORACLE_ATTESTATION_DOMAIN)
+            .ok_or_else(|| {
+                debug!("oracle attestation has the wrong domain");
+                CryptoError::ParsingError
+            })?;
+
+        let header_len = 8 + 2 * HASH_SIZE + 2;
+        if body.len() < header_len {
+            debug!("oracle attestation too short: {}", bytes.len());
+            return Err(CryptoError::ParsingError);
+        }
+
+        let (timestamp, rest) = body.split_at(8);
+        let (response_hash, rest) = rest.split_at(HASH_SIZE);
+        let (tls_chain_hash, rest) = rest.split_at(HASH_SIZE);
+        let (url_len, url) = rest.split_at(2);
+
+        let url_len = u16::from_be_bytes(url_len.try_into().unwrap()) as usize;
+        if url_len != url.len() || url_len > MAX_ORACLE_URL_LENGTH {
+            debug!(
+                "oracle attestation has a malformed url: declared {} got {}",
+                url_len,
+                url.len()
+            );
+            return Err(CryptoError::ParsingError);
+        }
+
+        Ok(Self {
+            url: url.to_vec(),
+            timestamp: u64::from_be_bytes(timestamp.try_into().unwrap()),
+            response_hash: response_hash.try_into().unwrap(),
+            tls_chain_hash: tls_chain_hash.try_into().unwrap(),
+        })
+    }
+
+    /// Used by the fetcher enclave to sign a fetch it performed
+    pub fn sign(&self, secret_key: &[u8]) -> Result<[u8; ORACLE_SIGNATURE_SIZE], CryptoError> {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let sk = secp256k1::SecretKey::from_slice(secret_key).map_err(|err| {
+            warn!("oracle fetcher got an invalid secret key: {:?}", err);
+            CryptoError::KeyError
+        })?;
+        let msg = secp256k1::Message::from_slice(&sha_256(&self.to_bytes()))
+            .map_err(|_| CryptoError::SigningError)?;
+
+        Ok(secp.sign_ecdsa(&msg, &sk).serialize_compact())
+    }
+}
+
+/// Verify the signature of an attestation against the fetcher public key
+pub fn verify_fetch_attestation_signature(
+    attestation: &[u8],
+    signature: &[u8],
+    pubkey: &[u8],
+) -> Result<FetchAttestation, CryptoError> {
+    let parsed = FetchAttestation::from_bytes(attestation)?;
+
+    let secp = secp256k1::Secp256k1::verification_only();
+    let pubkey = secp256k1::PublicKey::from_slice(pubkey).map_err(|err| {
+        debug!("oracle fetcher public key is invalid: {:?}", err);
+        CryptoError::KeyError
+    })?;
+    let signature = secp256k1::ecdsa::Signature::from_compact(signature).map_err(|err| {
+        debug!("oracle attestation signature is malformed: {:?}", err);
+        CryptoError::ParsingError
+    })?;
+    let msg = secp256k1::Message::from_slice(&sha_256(attestation))
+        .map_err(|_| CryptoError::VerificationError)?;
+
+    secp.verify_ecdsa(&msg, &signature, &pubkey).map_err(|_| {
+        debug!("oracle attestation signature failed to verify");
+        CryptoError::VerificationError
+    })?;
+
+    Ok(parsed)
+}
+
+/// Verify the DCAP quote of a fetcher enclave and return the public key it committed to.
+///
+/// The fetcher must be signed by the same MRSIGNER as this enclave, as the fetcher product. Its
+/// MRENCLAVE is not checked, so the fetcher can be upgraded independently of the network enclave.
+pub fn verify_fetcher_quote(
+    quote: &[u8],
+    collateral: &[u8],
+    time_s: i64,
+) -> Result<[u8; ORACLE_PUBKEY_SIZE], CryptoError> {
+    let report_body = verify_quote_same_signer(quote, collateral, time_s)?;
+
+    fetcher_pubkey(&report_body)
+}
+
+/// The public key in the report of a fetcher, if the report is one
+fn fetcher_pubkey(
+    report_body: &sgx_report_body_t,
+) -> Result<[u8; ORACLE_PUBKEY_SIZE], CryptoError> {
+    if report_body.isv_prod_id != ORACLE_FETCHER_PROD_ID {
+        debug!(
+            "oracle fetcher has the wrong product id: {}",
+            report_body.isv_prod_id
+        );
+        return Err(CryptoError::VerificationError);
+    }
+
+    let (pubkey, domain) = report_body.report_data.d.split_at(ORACLE_PUBKEY_SIZE);
+    let (domain, padding) = domain.split_at(ORACLE_FETCHER_REPORT_DOMAIN.len());
+    if domain != ORACLE_FETCHER_REPORT_DOMAIN || padding.iter().any(|byte| *byte != 0) {
+        debug!("oracle fetcher quote isn't a quote of a fetch key");
+        return Err(CryptoError::VerificationError);
+    }
+
+    Ok(pubkey.try_into().unwrap())
+}
+
+/// Full verification of an attestation produced by an oracle fetcher enclave
+pub fn verify_oracle_attestation(
+    attestation: &[u8],
+    signature: &[u8],
+    quote: &[u8],
+    collateral: &[u8],
+    time_s: i64,
+) -> Result<FetchAttestation, CryptoError> {
+    let pubkey = verify_fetcher_quote(quote, collateral, time_s)?;
+    verify_fetch_attestation_signature(attestation, signature, &pubkey)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    const TEST_SECRET_KEY: [u8; 32] = [0x11; 32];
+
+    fn test_attestation() -> FetchAttestation {
+        FetchAttestation {
+            url: b"https://api.example.com/v1/price?pair=SCRT-USD".to_vec(),
+            timestamp: 1_700_000_000,
+            response_hash: sha_256(b"{\"price\":\"0.42\"}"),
+            tls_chain_hash: sha_256(b"certificate chain"),
+        }
+    }
+
+    fn test_pubkey() -> Vec<u8> {
+        let secp = secp256k1::Secp256k1::new();
+        let sk = secp256k1::SecretKey::from_slice(&TEST_SECRET_KEY).unwrap();
+        secp256k1::PublicKey::from_secret_key(&secp, &sk)
+            .serialize()
+            .to_vec()
+    }
+
+    pub fn test_oracle_attestation_roundtrip() {
+        let attestation = test_attestation();
+        let bytes = attestation.to_bytes();
+
+        assert_eq!(FetchAttestation::from_bytes(&bytes).unwrap(), attestation);
+
+        // trailing garbage must not be accepted
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(FetchAttestation::from_bytes(&extended).is_err());
+        assert!(FetchAttestation::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(FetchAttestation::from_bytes(&bytes[1..]).is_err());
+    }
+
+    pub fn test_oracle_attestation_signature() {
+        let attestation = test_attestation();
+        let bytes = attestation.to_bytes();
+        let signature = attestation.sign(&TEST_SECRET_KEY).unwrap();
+
+        let verified =
+            verify_fetch_attestation_signature(&bytes, &signature, &test_pubkey()).unwrap();
+        assert_eq!(verified, attestation);
+
+        let mut tampered = attestation;
+        tampered.timestamp += 1;
+        assert!(verify_fetch_attestation_signature(
+            &tampered.to_bytes(),
+            &signature,
+            &test_pubkey()
+        )
+        .is_err());
+    }
+
+    pub fn test_oracle_quote_too_small() {
+        assert!(crate::dcap::quote_report_body(&[0u8; 16]).is_err());
+    }
+
+    pub fn test_oracle_fetcher_report() {
+        let pubkey = test_pubkey();
+        let mut report_body = sgx_report_body_t::default();
+        report_body.isv_prod_id = ORACLE_FETCHER_PROD_ID;
+        report_body.report_data.d[..ORACLE_PUBKEY_SIZE].copy_from_slice(&pubkey);
+        report_body.report_data.d
+            [ORACLE_PUBKEY_SIZE..ORACLE_PUBKEY_SIZE + ORACLE_FETCHER_REPORT_DOMAIN.len()]
+            .copy_from_slice(ORACLE_FETCHER_REPORT_DOMAIN);
+        assert_eq!(fetcher_pubkey(&report_body).unwrap().to_vec(), pubkey);
+
+        // another product of the signer, e.g. the network's enclave
+        let mut other_product = report_body;
+        other_product.isv_prod_id = 0;
+        assert!(fetcher_pubkey(&other_product).is_err());
+
+        // a report of other data
+        let mut other_data = report_body;
+        other_data.report_data.d[ORACLE_PUBKEY_SIZE] ^= 1;
+        assert!(fetcher_pubkey(&other_data).is_err());
+        let mut trailing_data = report_body;
+        trailing_data.report_data.d[63] = 1;
+        assert!(fetcher_pubkey(&trailing_data).is_err());
+    }
+}
@@ -0,0 +1,32 @@
+//! ECDSA over NIST P-256 (secp256r1), which `ES256` JWTs and most hardware keys sign with.
+
+use log::*;
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+
+use crate::CryptoError;
+
+/// An uncompressed SEC1 public key: 0x04 || x || y
+pub const SECP256R1_PUBKEY_SIZE: usize = 65;
+/// A fixed-size signature: r || s
+pub const SECP256R1_SIGNATURE_SIZE: usize = 64;
+
+/// Verifies a fixed-size ECDSA signature of the SHA-256 hash of `message`. ring checks that the
+/// public key is on the curve, and that r and s are in range.
+pub fn verify_sha256(
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), CryptoError> {
+    if public_key.len() != SECP256R1_PUBKEY_SIZE || signature.len() != SECP256R1_SIGNATURE_SIZE {
+        debug!(
+            "secp256r1 public key or signature has the wrong length: {} {}",
+            public_key.len(),
+            signature.len()
+        );
+        return Err(CryptoError::ParsingError);
+    }
+
+    UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, public_key)
+        .verify(message, signature)
+        .map_err(|_| CryptoError::VerificationError)
+}
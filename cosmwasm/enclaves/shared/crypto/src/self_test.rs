@@ -0,0 +1,144 @@
+//! Known-answer tests of the primitives the enclave's state and signatures depend on.
+//!
+//! An enclave that was miscompiled, or runs on a CPU with an erratum, can compute wrong results
+//! without failing outright, e.g. derive different keys than every other node. The node runs
+//! these tests at startup through `ecall_self_test`, and the enclave refuses consensus ecalls
+//! until they pass. Every vector comes from an RFC or was computed with an independent
+//! implementation, and a test also checks that a corrupted input is rejected where it can be.
+
+use log::*;
+
+use cosmos_proto::tx::signing::SignMode;
+
+use crate::ed25519::{Ed25519PrivateKey, KeyPair};
+use crate::kdf::hkdf_sha_256;
+use crate::keys::AESKey;
+use crate::secp256k1::Secp256k1PubKey;
+use crate::traits::{SIVEncryptable, VerifyingKey};
+
+/// RFC 5297, A.1
+const AES_SIV_KEY: &str = "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff";
+const AES_SIV_AD: &str = "101112131415161718191a1b1c1d1e1f2021222324252627";
+const AES_SIV_PLAINTEXT: &str = "112233445566778899aabbccddee";
+const AES_SIV_CIPHERTEXT: &str = "85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c";
+
+/// HKDF-SHA256 with the enclave's salt, of the bytes 0..32 with the info `seed`
+const HKDF_OUTPUT: &str = "1d19db6d4e27bc7d5a127535e4e2193552a6ecfd2944594a655a0bf43e8e4c71";
+
+/// A compressed public key and a low-S signature of `SECP256K1_MESSAGE` with SHA-256
+const SECP256K1_PUBKEY: &str = "02e87337783a584816a898efa8fb8aedacd89ad039a0c8a22b940aeaed06f1150f";
+const SECP256K1_SIGNATURE: &str = "0541c8a32ae825b677732163f7d8a76e17c93a64e45fcb2602c9538a5d37998e\
+                                   2a2c89379a176c3abcf5299d0437f8a0f10f0bb1472554ec39aa2a92bd8cc941";
+const SECP256K1_MESSAGE: &[u8] = b"secret network enclave self-test";
+
+/// RFC 7748, 6.1
+const X25519_ALICE_PRIVKEY: &str =
+    "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a";
+const X25519_ALICE_PUBKEY: &str =
+    "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a";
+const X25519_BOB_PUBKEY: &str = "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f";
+const X25519_SHARED_SECRET: &str =
+    "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742";
+
+fn decode<const N: usize>(vector: &str) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    bytes.copy_from_slice(&hex::decode(vector).unwrap());
+    bytes
+}
+
+fn known_answer(primitive: &str, result: &[u8], expected: &str) -> bool {
+    let matches = hex::encode(result) == expected;
+    if !matches {
+        error!(
+            "{} returned {} instead of {}",
+            primitive,
+            hex::encode(result),
+            expected
+        );
+    }
+    matches
+}
+
+pub fn aes_siv() -> bool {
+    let key = AESKey::new_from_slice(&decode(AES_SIV_KEY));
+    let ad_bytes = hex::decode(AES_SIV_AD).unwrap();
+    let ad: &[&[u8]] = &[&ad_bytes];
+    let plaintext = hex::decode(AES_SIV_PLAINTEXT).unwrap();
+
+    let ciphertext = match key.encrypt_siv(&plaintext, Some(ad)) {
+        Ok(ciphertext) => ciphertext,
+        Err(err) => {
+            error!("aes-siv failed to encrypt: {:?}", err);
+            return false;
+        }
+    };
+    if !known_answer("aes-siv", &ciphertext, AES_SIV_CIPHERTEXT) {
+        return false;
+    }
+
+    let mut tampered = ciphertext;
+    tampered[0] ^= 1;
+    match key.decrypt_siv(&tampered, Some(ad)) {
+        Err(_) => true,
+        Ok(_) => {
+            error!("aes-siv decrypted a tampered ciphertext");
+            false
+        }
+    }
+}
+
+pub fn hkdf() -> bool {
+    let input: Vec<u8> = (0..32).collect();
+    let key = hkdf_sha_256(&input, &[b"seed"]);
+
+    known_answer("hkdf", key.get(), HKDF_OUTPUT)
+}
+
+pub fn secp256k1() -> bool {
+    let pubkey = Secp256k1PubKey::new(hex::decode(SECP256K1_PUBKEY).unwrap());
+    let signature = hex::decode(SECP256K1_SIGNATURE).unwrap();
+
+    if let Err(err) = pubkey.verify_bytes(SECP256K1_MESSAGE, &signature, SignMode::SIGN_MODE_DIRECT)
+    {
+        error!("secp256k1 rejected a valid signature: {:?}", err);
+        return false;
+    }
+
+    let mut tampered = SECP256K1_MESSAGE.to_vec();
+    tampered[0] ^= 1;
+    match pubkey.verify_bytes(&tampered, &signature, SignMode::SIGN_MODE_DIRECT) {
+        Err(_) => true,
+        Ok(_) => {
+            error!("secp256k1 accepted the signature of another message");
+            false
+        }
+    }
+}
+
+/// The Diffie-Hellman of the node's tx keys, which `ed25519::KeyPair` implements over X25519
+pub fn x25519() -> bool {
+    let mut privkey = Ed25519PrivateKey::default();
+    privkey
+        .get_mut()
+        .copy_from_slice(&decode::<32>(X25519_ALICE_PRIVKEY));
+    let keypair = KeyPair::from_sk(privkey);
+
+    known_answer("x25519", &keypair.get_pubkey(), X25519_ALICE_PUBKEY)
+        && known_answer(
+            "x25519",
+            &keypair.diffie_hellman(&decode(X25519_BOB_PUBKEY)),
+            X25519_SHARED_SECRET,
+        )
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_known_answers() {
+        assert!(aes_siv());
+        assert!(hkdf());
+        assert!(secp256k1());
+        assert!(x25519());
+    }
+}
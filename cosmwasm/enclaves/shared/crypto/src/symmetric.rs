@@ -0,0 +1,248 @@
+//! HKDF, HMAC and AEAD for contracts, over keys the contracts supply.
+//!
+//! Contracts that implement their own envelope encryption otherwise re-implement these in wasm.
+//! None of the enclave's own keys are involved: every key comes from the contract, and every
+//! result goes back to it. Inputs are capped, so the cost of a call is bounded by its gas.
+//!
+//! * HKDF and HMAC use SHA-256, as RFC 5869 and RFC 2104 define them.
+//! * AEADs are AES-256-GCM and ChaCha20-Poly1305, with 32 byte keys, 12 byte nonces and 16 byte
+//!   tags appended to the ciphertext. Nonces are the contract's responsibility, and reusing one
+//!   with the same key breaks both algorithms.
+
+use ring::{aead, hkdf, hmac};
+
+use crate::kdf::My;
+
+/// Inputs that may be keys: the input key material and salt of HKDF, and the key of HMAC
+pub const MAX_KEY_MATERIAL_LENGTH: usize = 256;
+pub const MAX_INFO_LENGTH: usize = 1024;
+/// The longest output of HKDF-SHA256, 255 times the length of a hash, as RFC 5869 requires
+pub const MAX_HKDF_OUTPUT_LENGTH: usize = 255 * 32;
+/// Messages of HMAC, and the plaintexts, ciphertexts and associated data of AEADs
+pub const MAX_MESSAGE_LENGTH: usize = 64 * 1024;
+
+pub const AEAD_KEY_LENGTH: usize = 32;
+pub const AEAD_TAG_LENGTH: usize = 16;
+
+/// Error codes returned to contracts by the HKDF, HMAC and AEAD host functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum SymmetricError {
+    /// An input is longer than its cap, a key or nonce has the wrong length, the output length of
+    /// HKDF is 0 or too long, or the algorithm is unknown
+    InvalidInput = 1,
+    /// The ciphertext wasn't sealed with the key, nonce and associated data
+    DecryptionFailed = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AeadAlgorithm {
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl AeadAlgorithm {
+    pub fn from_u32(algorithm: u32) -> Result<Self, SymmetricError> {
+        match algorithm {
+            0 => Ok(AeadAlgorithm::Aes256Gcm),
+            1 => Ok(AeadAlgorithm::ChaCha20Poly1305),
+            _ => Err(SymmetricError::InvalidInput),
+        }
+    }
+
+    fn ring_algorithm(self) -> &'static aead::Algorithm {
+        match self {
+            AeadAlgorithm::Aes256Gcm => &aead::AES_256_GCM,
+            AeadAlgorithm::ChaCha20Poly1305 => &aead::CHACHA20_POLY1305,
+        }
+    }
+}
+
+pub fn hkdf_sha256(
+    ikm: &[u8],
+    salt: &[u8],
+    info: &[u8],
+    length: usize,
+) -> Result<Vec<u8>, SymmetricError> {
+    if ikm.len() > MAX_KEY_MATERIAL_LENGTH
+        || salt.len() > MAX_KEY_MATERIAL_LENGTH
+        || info.len() > MAX_INFO_LENGTH
+        || length == 0
+        || length > MAX_HKDF_OUTPUT_LENGTH
+    {
+        return Err(SymmetricError::InvalidInput);
+    }
+
+    let prk = hkdf::Salt::new(hkdf::HKDF_SHA256, salt).extract(ikm);
+    let okm: My<Vec<u8>> = prk
+        .expand(&[info], My(length))
+        .map_err(|_| SymmetricError::InvalidInput)?
+        .into();
+    Ok(okm.0)
+}
+
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Result<Vec<u8>, SymmetricError> {
+    if key.len() > MAX_KEY_MATERIAL_LENGTH || message.len() > MAX_MESSAGE_LENGTH {
+        return Err(SymmetricError::InvalidInput);
+    }
+
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    Ok(hmac::sign(&key, message).as_ref().to_vec())
+}
+
+fn aead_key(
+    algorithm: AeadAlgorithm,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+) -> Result<(aead::LessSafeKey, aead::Nonce), SymmetricError> {
+    if key.len() != AEAD_KEY_LENGTH || aad.len() > MAX_MESSAGE_LENGTH {
+        return Err(SymmetricError::InvalidInput);
+    }
+
+    let key = aead::UnboundKey::new(algorithm.ring_algorithm(), key)
+        .map_err(|_| SymmetricError::InvalidInput)?;
+    let nonce =
+        aead::Nonce::try_assume_unique_for_key(nonce).map_err(|_| SymmetricError::InvalidInput)?;
+    Ok((aead::LessSafeKey::new(key), nonce))
+}
+
+/// Encrypts a plaintext, and returns the ciphertext followed by the tag
+pub fn aead_seal(
+    algorithm: AeadAlgorithm,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, SymmetricError> {
+    if plaintext.len() > MAX_MESSAGE_LENGTH {
+        return Err(SymmetricError::InvalidInput);
+    }
+    let (key, nonce) = aead_key(algorithm, key, nonce, aad)?;
+
+    let mut in_out = plaintext.to_vec();
+    key.seal_in_place_append_tag(nonce, aead::Aad::from(aad), &mut in_out)
+        .map_err(|_| SymmetricError::InvalidInput)?;
+    Ok(in_out)
+}
+
+/// Decrypts a ciphertext followed by its tag, as `aead_seal` returns it
+pub fn aead_open(
+    algorithm: AeadAlgorithm,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, SymmetricError> {
+    if ciphertext.len() > MAX_MESSAGE_LENGTH + AEAD_TAG_LENGTH {
+        return Err(SymmetricError::InvalidInput);
+    }
+    let (key, nonce) = aead_key(algorithm, key, nonce, aad)?;
+
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, aead::Aad::from(aad), &mut in_out)
+        .map_err(|_| SymmetricError::DecryptionFailed)?;
+    Ok(plaintext.to_vec())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_hkdf_sha256() {
+        // RFC 5869, test case 1
+        let okm = hkdf_sha256(
+            &[0x0b; 22],
+            &hex::decode("000102030405060708090a0b0c").unwrap(),
+            &hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap(),
+            42,
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(okm),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+
+        assert!(hkdf_sha256(b"ikm", b"", b"", MAX_HKDF_OUTPUT_LENGTH).is_ok());
+        assert_eq!(
+            hkdf_sha256(b"ikm", b"", b"", MAX_HKDF_OUTPUT_LENGTH + 1),
+            Err(SymmetricError::InvalidInput)
+        );
+        assert_eq!(
+            hkdf_sha256(b"ikm", b"", b"", 0),
+            Err(SymmetricError::InvalidInput)
+        );
+        assert_eq!(
+            hkdf_sha256(&[0; MAX_KEY_MATERIAL_LENGTH + 1], b"", b"", 32),
+            Err(SymmetricError::InvalidInput)
+        );
+    }
+
+    pub fn test_hmac_sha256() {
+        // RFC 4231, test case 2
+        let tag = hmac_sha256(b"Jefe", b"what do ya want for nothing?").unwrap();
+        assert_eq!(
+            hex::encode(tag),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        assert_eq!(
+            hmac_sha256(b"key", &vec![0; MAX_MESSAGE_LENGTH + 1]),
+            Err(SymmetricError::InvalidInput)
+        );
+    }
+
+    pub fn test_aead_seal_and_open() {
+        // The GCM specification, test case 14
+        let sealed =
+            aead_seal(AeadAlgorithm::Aes256Gcm, &[0; 32], &[0; 12], b"", &[0; 16]).unwrap();
+        assert_eq!(
+            hex::encode(&sealed),
+            "cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919"
+        );
+
+        for &algorithm in [AeadAlgorithm::Aes256Gcm, AeadAlgorithm::ChaCha20Poly1305].iter() {
+            let (key, nonce) = ([7u8; 32], [9u8; 12]);
+            let sealed = aead_seal(algorithm, &key, &nonce, b"header", b"plaintext").unwrap();
+            assert_eq!(sealed.len(), b"plaintext".len() + AEAD_TAG_LENGTH);
+            assert_eq!(
+                aead_open(algorithm, &key, &nonce, b"header", &sealed).unwrap(),
+                b"plaintext"
+            );
+
+            // Anything else fails to open
+            assert_eq!(
+                aead_open(algorithm, &key, &nonce, b"other", &sealed),
+                Err(SymmetricError::DecryptionFailed)
+            );
+            assert_eq!(
+                aead_open(algorithm, &key, &[0; 12], b"header", &sealed),
+                Err(SymmetricError::DecryptionFailed)
+            );
+            let mut tampered = sealed.clone();
+            tampered[0] ^= 1;
+            assert_eq!(
+                aead_open(algorithm, &key, &nonce, b"header", &tampered),
+                Err(SymmetricError::DecryptionFailed)
+            );
+            assert_eq!(
+                aead_open(algorithm, &key, &nonce, b"header", &sealed[..8]),
+                Err(SymmetricError::DecryptionFailed)
+            );
+
+            assert_eq!(
+                aead_seal(algorithm, &key[..16], &nonce, b"", b""),
+                Err(SymmetricError::InvalidInput)
+            );
+            assert_eq!(
+                aead_seal(algorithm, &key, &nonce[..8], b"", b""),
+                Err(SymmetricError::InvalidInput)
+            );
+        }
+        assert_eq!(
+            AeadAlgorithm::from_u32(2),
+            Err(SymmetricError::InvalidInput)
+        );
+    }
+}
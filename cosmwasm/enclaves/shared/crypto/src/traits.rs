@@ -2,6 +2,7 @@ use cosmos_proto::tx::signing::SignMode;
 use enclave_ffi_types::EnclaveError;
 
 use crate::errors::CryptoError;
+use crate::kdf::KdfAlgorithm;
 
 pub const HMAC_SIGNATURE_SIZE: usize = 32;
 pub const EC_256_PRIVATE_KEY_SIZE: usize = 32;
@@ -29,7 +30,14 @@ pub trait Rng {
 }
 
 pub trait Kdf<T> {
-    fn derive_key_from_this(&self, data: &[u8]) -> T;
+    /// Derive a key for `data` with `algorithm`
+    fn derive_key_with(&self, algorithm: KdfAlgorithm, data: &[u8]) -> T;
+
+    /// Derive a key for `data` with HKDF-SHA-256, the algorithm used before derivation was
+    /// versioned
+    fn derive_key_from_this(&self, data: &[u8]) -> T {
+        self.derive_key_with(KdfAlgorithm::HkdfSha256, data)
+    }
 }
 
 pub trait Hmac {
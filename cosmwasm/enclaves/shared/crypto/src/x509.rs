@@ -0,0 +1,439 @@
+//! X.509 certificate chain validation against pinned roots.
+//!
+//! Path building and signature checks are left to webpki, the same verifier the registration
+//! flow uses for Intel's attestation signing certificate, so chains are held to the same rules:
+//! v3 certificates only, strict DER, basic and name constraints, and no unknown critical
+//! extensions. There is no system store: a chain is only valid if it leads to one of the roots
+//! the caller pins.
+//!
+//! Once webpki has accepted the leaf, its public key and the extensions the caller asked for are
+//! read back out of it, since webpki doesn't expose them.
+
+use log::*;
+
+/// The signature algorithms accepted in certificate chains
+pub type SignatureAlgorithms = &'static [&'static webpki::SignatureAlgorithm];
+pub static SUPPORTED_SIG_ALGS: SignatureAlgorithms = &[
+    &webpki::ECDSA_P256_SHA256,
+    &webpki::ECDSA_P256_SHA384,
+    &webpki::ECDSA_P384_SHA256,
+    &webpki::ECDSA_P384_SHA384,
+    &webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA384_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA512_LEGACY_KEY,
+    &webpki::RSA_PKCS1_2048_8192_SHA256,
+    &webpki::RSA_PKCS1_2048_8192_SHA384,
+    &webpki::RSA_PKCS1_2048_8192_SHA512,
+    &webpki::RSA_PKCS1_3072_8192_SHA384,
+];
+
+/// The leaf and at most 3 intermediates. Path building tries every order of the intermediates,
+/// so this bounds the number of signatures a single call can verify.
+pub const MAX_CHAIN_LENGTH: usize = 4;
+pub const MAX_PINNED_ROOTS: usize = 8;
+pub const MAX_SELECTED_EXTENSIONS: usize = 16;
+
+/// Error codes returned to contracts by `x509_verify_chain`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum X509Error {
+    /// The chain is empty or too long, there are no roots or too many of them, too many
+    /// extensions were selected, or the purpose is unknown
+    InvalidInput = 1,
+    /// A certificate or root isn't a DER-encoded X.509 v3 certificate
+    BadDer = 2,
+    /// There's no valid path from the leaf to one of the pinned roots. webpki doesn't tell why an
+    /// issuer was rejected, so this covers a missing issuer as well as an issuer whose signature,
+    /// validity or constraints don't check out.
+    UntrustedChain = 3,
+    /// The leaf isn't valid at the given time
+    Expired = 4,
+    /// The leaf violates a constraint, e.g. it's a CA, it isn't allowed for the purpose, or it has
+    /// an unknown critical extension
+    ConstraintViolation = 5,
+}
+
+impl From<webpki::Error> for X509Error {
+    fn from(err: webpki::Error) -> Self {
+        match err {
+            webpki::Error::BadDER
+            | webpki::Error::BadDERTime
+            | webpki::Error::InvalidCertValidity
+            | webpki::Error::UnsupportedCertVersion
+            | webpki::Error::ExtensionValueInvalid => X509Error::BadDer,
+            webpki::Error::UnknownIssuer
+            | webpki::Error::InvalidSignatureForPublicKey
+            | webpki::Error::SignatureAlgorithmMismatch
+            | webpki::Error::UnsupportedSignatureAlgorithm
+            | webpki::Error::UnsupportedSignatureAlgorithmForPublicKey => X509Error::UntrustedChain,
+            webpki::Error::CertExpired | webpki::Error::CertNotValidYet => X509Error::Expired,
+            _ => X509Error::ConstraintViolation,
+        }
+    }
+}
+
+/// What the leaf will be used for. The leaf must allow it in its extended key usage, if it has
+/// one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertPurpose {
+    TlsServer,
+    TlsClient,
+}
+
+impl CertPurpose {
+    pub fn from_u32(purpose: u32) -> Result<Self, X509Error> {
+        match purpose {
+            0 => Ok(CertPurpose::TlsServer),
+            1 => Ok(CertPurpose::TlsClient),
+            _ => Err(X509Error::InvalidInput),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extension {
+    /// In dotted notation, e.g. "2.5.29.17" for the subject alternative name
+    pub oid: String,
+    pub critical: bool,
+    /// The DER-encoded value, without the `OCTET STRING` that wraps it
+    pub value: Vec<u8>,
+}
+
+/// What a contract learns about the leaf of a valid chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedLeaf {
+    /// The DER-encoded `SubjectPublicKeyInfo`
+    pub public_key: Vec<u8>,
+    /// The selected extensions the leaf has, in the order they appear in it
+    pub extensions: Vec<Extension>,
+}
+
+/// Validates `chain`, the leaf followed by its intermediates in any order, against the pinned
+/// `roots` at `time` (unix seconds), and returns the leaf's public key and the extensions it has
+/// out of `selected_extensions`.
+pub fn verify_cert_chain(
+    chain: &[Vec<u8>],
+    roots: &[Vec<u8>],
+    purpose: CertPurpose,
+    time: u64,
+    selected_extensions: &[String],
+) -> Result<VerifiedLeaf, X509Error> {
+    if chain.is_empty()
+        || chain.len() > MAX_CHAIN_LENGTH
+        || roots.is_empty()
+        || roots.len() > MAX_PINNED_ROOTS
+        || selected_extensions.len() > MAX_SELECTED_EXTENSIONS
+    {
+        debug!(
+            "x509 chain of {} certificates, {} roots and {} extensions is out of bounds",
+            chain.len(),
+            roots.len(),
+            selected_extensions.len()
+        );
+        return Err(X509Error::InvalidInput);
+    }
+
+    let trust_anchors = roots
+        .iter()
+        .map(|root| webpki::trust_anchor_util::cert_der_as_trust_anchor(root))
+        .collect::<Result<Vec<webpki::TrustAnchor>, _>>()
+        .map_err(|err| {
+            debug!("failed to parse pinned x509 root: {:?}", err);
+            X509Error::BadDer
+        })?;
+
+    let leaf = webpki::EndEntityCert::from(&chain[0]).map_err(|err| {
+        debug!("failed to parse x509 leaf: {:?}", err);
+        X509Error::from(err)
+    })?;
+    let intermediates: Vec<&[u8]> = chain[1..].iter().map(|cert| cert.as_slice()).collect();
+    let time = webpki::Time::from_seconds_since_unix_epoch(time);
+
+    match purpose {
+        CertPurpose::TlsServer => leaf.verify_is_valid_tls_server_cert(
+            SUPPORTED_SIG_ALGS,
+            &webpki::TLSServerTrustAnchors(&trust_anchors),
+            &intermediates,
+            time,
+        ),
+        CertPurpose::TlsClient => leaf.verify_is_valid_tls_client_cert(
+            SUPPORTED_SIG_ALGS,
+            &webpki::TLSClientTrustAnchors(&trust_anchors),
+            &intermediates,
+            time,
+        ),
+    }
+    .map_err(|err| {
+        debug!("x509 chain verification failed: {:?}", err);
+        X509Error::from(err)
+    })?;
+
+    // webpki accepted the leaf, so it's well formed and the walk below is not expected to fail
+    read_leaf(&chain[0], selected_extensions).ok_or(X509Error::BadDer)
+}
+
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_VERSION: u8 = 0xa0;
+const TAG_EXTENSIONS: u8 = 0xa3;
+
+/// Splits the DER element with `expected_tag` off the start of `input`, and returns its value
+fn read_element<'a>(input: &mut &'a [u8], expected_tag: u8) -> Option<&'a [u8]> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, mut rest) = rest.split_first()?;
+    if tag != expected_tag {
+        return None;
+    }
+
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let len_bytes = (first & 0x7f) as usize;
+        if len_bytes == 0 || len_bytes > 3 || rest.len() < len_bytes {
+            return None;
+        }
+        let (len, value) = rest.split_at(len_bytes);
+        rest = value;
+        len.iter().fold(0, |len, &byte| (len << 8) | byte as usize)
+    };
+
+    if rest.len() < len {
+        return None;
+    }
+    let (value, rest) = rest.split_at(len);
+    *input = rest;
+    Some(value)
+}
+
+/// Reads the public key and selected extensions of a certificate webpki already parsed
+fn read_leaf(mut cert: &[u8], selected_extensions: &[String]) -> Option<VerifiedLeaf> {
+    let mut cert = read_element(&mut cert, TAG_SEQUENCE)?;
+    let mut tbs = read_element(&mut cert, TAG_SEQUENCE)?;
+
+    read_element(&mut tbs, TAG_VERSION)?;
+    read_element(&mut tbs, TAG_INTEGER)?;
+    // signature algorithm, issuer, validity and subject
+    for _ in 0..4 {
+        read_element(&mut tbs, TAG_SEQUENCE)?;
+    }
+    let public_key_start = tbs;
+    read_element(&mut tbs, TAG_SEQUENCE)?;
+    let public_key = &public_key_start[..public_key_start.len() - tbs.len()];
+
+    // webpki doesn't accept unique identifiers, so the extensions follow, and are mandatory
+    let mut tagged = read_element(&mut tbs, TAG_EXTENSIONS)?;
+    let mut extensions_der = read_element(&mut tagged, TAG_SEQUENCE)?;
+
+    let mut extensions = vec![];
+    while !extensions_der.is_empty() {
+        let mut extension = read_element(&mut extensions_der, TAG_SEQUENCE)?;
+        let oid = read_element(&mut extension, TAG_OID)?;
+        let critical = match extension.first() {
+            Some(&TAG_BOOLEAN) => read_element(&mut extension, TAG_BOOLEAN)? == [0xff],
+            _ => false,
+        };
+        let value = read_element(&mut extension, TAG_OCTET_STRING)?;
+
+        let oid = oid_to_string(oid)?;
+        if selected_extensions.contains(&oid) {
+            extensions.push(Extension {
+                oid,
+                critical,
+                value: value.to_vec(),
+            });
+        }
+    }
+
+    Some(VerifiedLeaf {
+        public_key: public_key.to_vec(),
+        extensions,
+    })
+}
+
+/// Formats the value of a DER-encoded OID in dotted notation
+fn oid_to_string(oid: &[u8]) -> Option<String> {
+    // The value is a list of base 128 numbers, most significant group first and without padding
+    let mut subidentifiers: Vec<u64> = vec![];
+    let mut current: Option<u64> = None;
+    for &byte in oid {
+        let value = match current {
+            None if byte == 0x80 => return None,
+            None => 0,
+            Some(value) if value > u64::MAX >> 7 => return None,
+            Some(value) => value,
+        };
+        let value = (value << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            subidentifiers.push(value);
+            current = None;
+        } else {
+            current = Some(value);
+        }
+    }
+    if current.is_some() || subidentifiers.is_empty() {
+        return None;
+    }
+
+    // The first number encodes the first two arcs
+    let first = (subidentifiers[0] / 40).min(2);
+    let mut arcs = vec![first, subidentifiers[0] - 40 * first];
+    arcs.extend_from_slice(&subidentifiers[1..]);
+
+    Some(
+        arcs.iter()
+            .map(|arc| arc.to_string())
+            .collect::<Vec<String>>()
+            .join("."),
+    )
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    const ROOT: &[u8] = include_bytes!("fixtures/x509_root.der");
+    const OTHER_ROOT: &[u8] = include_bytes!("fixtures/x509_other_root.der");
+    const INTERMEDIATE: &[u8] = include_bytes!("fixtures/x509_intermediate.der");
+    const LEAF: &[u8] = include_bytes!("fixtures/x509_leaf.der");
+    const LEAF_PUBLIC_KEY: &[u8] = include_bytes!("fixtures/x509_leaf_spki.der");
+
+    /// 2025-01-01, when the whole chain is valid
+    const TIME: u64 = 1_735_689_600;
+
+    const SUBJECT_ALT_NAME: &str = "2.5.29.17";
+    const PRIVATE_EXTENSION: &str = "1.3.6.1.4.1.99999.1";
+
+    fn chain() -> Vec<Vec<u8>> {
+        vec![LEAF.to_vec(), INTERMEDIATE.to_vec()]
+    }
+
+    pub fn test_x509_verify_chain() {
+        let roots = vec![OTHER_ROOT.to_vec(), ROOT.to_vec()];
+        let selected = vec![
+            PRIVATE_EXTENSION.to_string(),
+            SUBJECT_ALT_NAME.to_string(),
+            "2.5.29.19".to_string(),
+        ];
+
+        let leaf =
+            verify_cert_chain(&chain(), &roots, CertPurpose::TlsClient, TIME, &selected).unwrap();
+        assert_eq!(leaf.public_key, LEAF_PUBLIC_KEY);
+        // in the order of the certificate rather than of the selection, and without the basic
+        // constraints the leaf doesn't have
+        assert_eq!(
+            leaf.extensions,
+            vec![
+                Extension {
+                    oid: SUBJECT_ALT_NAME.to_string(),
+                    critical: false,
+                    value: b"\x30\x19\x82\x17device-1234.example.com".to_vec(),
+                },
+                Extension {
+                    oid: PRIVATE_EXTENSION.to_string(),
+                    critical: false,
+                    value: b"\x04\x05hello".to_vec(),
+                },
+            ]
+        );
+
+        let leaf = verify_cert_chain(&chain(), &roots, CertPurpose::TlsClient, TIME, &[]).unwrap();
+        assert!(leaf.extensions.is_empty());
+    }
+
+    pub fn test_x509_verify_chain_rejects_invalid_chains() {
+        let roots = vec![ROOT.to_vec()];
+        let verify = |chain: &[Vec<u8>], roots: &[Vec<u8>], purpose, time| {
+            verify_cert_chain(chain, roots, purpose, time, &[]).map(|_| ())
+        };
+
+        assert_eq!(
+            verify(
+                &chain(),
+                &[OTHER_ROOT.to_vec()],
+                CertPurpose::TlsClient,
+                TIME
+            ),
+            Err(X509Error::UntrustedChain)
+        );
+        assert_eq!(
+            verify(&chain()[..1], &roots, CertPurpose::TlsClient, TIME),
+            Err(X509Error::UntrustedChain)
+        );
+        // the leaf expires in 2034, before the rest of the chain
+        assert_eq!(
+            verify(&chain(), &roots, CertPurpose::TlsClient, 2_100_000_000),
+            Err(X509Error::Expired)
+        );
+        // the leaf only allows client authentication
+        assert_eq!(
+            verify(&chain(), &roots, CertPurpose::TlsServer, TIME),
+            Err(X509Error::ConstraintViolation)
+        );
+        // the intermediate isn't an end entity
+        assert_eq!(
+            verify(
+                &[INTERMEDIATE.to_vec()],
+                &roots,
+                CertPurpose::TlsClient,
+                TIME
+            ),
+            Err(X509Error::ConstraintViolation)
+        );
+
+        let mut forged = chain();
+        let last = forged[0].len() - 1;
+        forged[0][last] ^= 1;
+        assert_eq!(
+            verify(&forged, &roots, CertPurpose::TlsClient, TIME),
+            Err(X509Error::UntrustedChain)
+        );
+
+        let mut truncated = chain();
+        truncated[0].pop();
+        assert_eq!(
+            verify(&truncated, &roots, CertPurpose::TlsClient, TIME),
+            Err(X509Error::BadDer)
+        );
+        assert_eq!(
+            verify(&chain(), &[vec![0x30, 0x00]], CertPurpose::TlsClient, TIME),
+            Err(X509Error::BadDer)
+        );
+
+        assert_eq!(
+            verify(&[], &roots, CertPurpose::TlsClient, TIME),
+            Err(X509Error::InvalidInput)
+        );
+        assert_eq!(
+            verify(&chain(), &[], CertPurpose::TlsClient, TIME),
+            Err(X509Error::InvalidInput)
+        );
+        assert_eq!(
+            verify(
+                &vec![INTERMEDIATE.to_vec(); MAX_CHAIN_LENGTH + 1],
+                &roots,
+                CertPurpose::TlsClient,
+                TIME
+            ),
+            Err(X509Error::InvalidInput)
+        );
+        assert_eq!(CertPurpose::from_u32(2), Err(X509Error::InvalidInput));
+    }
+
+    pub fn test_x509_oid_to_string() {
+        assert_eq!(
+            oid_to_string(&[0x55, 0x1d, 0x11]),
+            Some("2.5.29.17".to_string())
+        );
+        assert_eq!(
+            oid_to_string(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b]),
+            Some("1.2.840.113549.1.1.11".to_string())
+        );
+        assert_eq!(oid_to_string(&[0x88, 0x37]), Some("2.999".to_string()));
+        assert_eq!(oid_to_string(&[]), None);
+        assert_eq!(oid_to_string(&[0x55, 0x86]), None);
+        assert_eq!(oid_to_string(&[0x55, 0x80, 0x01]), None);
+    }
+}
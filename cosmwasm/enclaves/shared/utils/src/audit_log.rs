@@ -0,0 +1,63 @@
+use crate::storage::{seal, unseal};
+use enclave_crypto::consts::{make_sgx_secret_path, SEALED_FILE_AUDIT_LOG};
+use log::error;
+use serde::{Deserialize, Serialize};
+use sgx_types::{sgx_status_t, SgxResult};
+use std::path;
+use std::untrusted::path::PathEx;
+
+lazy_static::lazy_static! {
+    pub static ref AUDIT_LOG_SEALING_PATH: String = make_sgx_secret_path(SEALED_FILE_AUDIT_LOG);
+}
+
+/// Sensitive operations the enclave performed on behalf of governance
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum AuditEntry {
+    ContractKeyDisclosed {
+        height: u64,
+        contract_address: String,
+        /// hex encoded canonical address
+        admin: String,
+        /// hex encoded
+        auditor_public_key: String,
+        /// hex encoded sha256 of the governance message that authorized the disclosure
+        authorization_hash: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AuditLog {
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    /// Returns an empty log if nothing was recorded yet
+    pub fn unseal() -> SgxResult<Self> {
+        if !path::Path::new(AUDIT_LOG_SEALING_PATH.as_str()).exists() {
+            return Ok(Self::default());
+        }
+
+        let log: Self = serde_json::from_slice(unseal(&AUDIT_LOG_SEALING_PATH)?.as_slice())
+            .map_err(|e| {
+                error!("Error decoding audit log from json {:?}", e);
+                sgx_status_t::SGX_ERROR_UNEXPECTED
+            })?;
+
+        Ok(log)
+    }
+
+    pub fn seal(&self) -> SgxResult<()> {
+        let encoded = serde_json::to_vec(&self).map_err(|e| {
+            error!("Error encoding audit log to json: {:?}", e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })?;
+
+        seal(encoded.as_slice(), &AUDIT_LOG_SEALING_PATH)
+    }
+
+    pub fn append(entry: AuditEntry) -> SgxResult<()> {
+        let mut log = Self::unseal()?;
+        log.entries.push(entry);
+        log.seal()
+    }
+}
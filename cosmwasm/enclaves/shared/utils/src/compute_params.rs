@@ -0,0 +1,224 @@
+//! The params of the compute module that change what the enclave does.
+//!
+//! The node pushes the params with every block, with a proof of them against the app hash of the
+//! block, which the block verifier replaces with every block it verifies. Until the params of the
+//! current block are proven, there are none, and whatever uses them falls back to its strictest
+//! behavior, so a node can't unlock anything by withholding or forging them.
+
+use std::collections::BTreeMap;
+use std::string::String;
+use std::sync::SgxRwLock;
+use std::vec::Vec;
+
+use lazy_static::lazy_static;
+use log::*;
+use sgx_types::sgx_status_t;
+
+use enclave_ffi_types::EnclaveError;
+
+use crate::proto_fields::{fields, read_varint, varint_value, WIRE_TYPE_LEN, WIRE_TYPE_VARINT};
+use crate::{validate_const_ptr, validate_input_length};
+
+/// The store and key the compute module keeps its `Params` at
+pub const COMPUTE_STORE_KEY: &[u8] = b"compute";
+pub const PARAMS_KEY: &[u8] = &[0x0B];
+
+pub const MAX_PARAMS_LENGTH: u32 = 100_000;
+
+// Fields of `secret.compute.v1beta1.Params`
+const NATIVE_SNIP20_CODE_HASHES: u64 = 3;
+const MAX_MSG_LENGTH: u64 = 4;
+const MAX_WASM_LENGTH: u64 = 5;
+const MAX_CERT_LENGTH: u64 = 6;
+const STATE_VALUE_BUCKETS: u64 = 7;
+const IO_MESSAGE_BUCKETS: u64 = 8;
+const INSTANTIATE_ALLOW_LIST_ENABLED: u64 = 9;
+const INSTANTIATE_ALLOWED_CODE_HASHES: u64 = 10;
+const INSTANTIATE_DENIED_CODE_HASHES: u64 = 11;
+const ENCLAVE_PARAMS: u64 = 12;
+
+// Fields of `secret.compute.v1beta1.EnclaveParam`
+const ENCLAVE_PARAM_NAME: u64 = 1;
+const ENCLAVE_PARAM_VALUE: u64 = 2;
+
+/// The largest padding bucket the params can set
+const MAX_PADDING_BUCKET: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComputeParams {
+    /// Code hashes approved for the native SNIP-20 fast-path
+    pub native_snip20_code_hashes: Vec<[u8; 32]>,
+    /// Limits on the sizes of ecall inputs, 0 where the chain keeps the enclave's own
+    pub max_msg_length: u64,
+    pub max_wasm_length: u64,
+    pub max_cert_length: u64,
+    /// The sizes state values and messages are padded to, empty where the chain keeps the
+    /// enclave's own
+    pub state_value_buckets: Vec<usize>,
+    pub io_message_buckets: Vec<usize>,
+    /// Code hashes governance restricted instantiating, see `instantiate_restrictions` in the
+    /// contract engine
+    pub instantiate_allow_list_enabled: bool,
+    pub instantiate_allowed_code_hashes: Vec<[u8; 32]>,
+    pub instantiate_denied_code_hashes: Vec<[u8; 32]>,
+    /// The encoded values of the params in `verified_params`, by name
+    pub enclave_params: BTreeMap<String, Vec<u8>>,
+}
+
+impl ComputeParams {
+    /// Decodes the params as the compute module stores them. Fields the enclave doesn't use are
+    /// skipped.
+    pub fn parse(params: &[u8]) -> Result<Self, EnclaveError> {
+        let mut parsed = Self::default();
+        for (field, wire_type, value) in fields(params)? {
+            match (field, wire_type) {
+                (NATIVE_SNIP20_CODE_HASHES, WIRE_TYPE_LEN) => {
+                    parsed.native_snip20_code_hashes.push(code_hash(value)?)
+                }
+                (MAX_MSG_LENGTH, WIRE_TYPE_VARINT) => parsed.max_msg_length = varint_value(value)?,
+                (MAX_WASM_LENGTH, WIRE_TYPE_VARINT) => {
+                    parsed.max_wasm_length = varint_value(value)?
+                }
+                (MAX_CERT_LENGTH, WIRE_TYPE_VARINT) => {
+                    parsed.max_cert_length = varint_value(value)?
+                }
+                (STATE_VALUE_BUCKETS, _) => {
+                    push_buckets(&mut parsed.state_value_buckets, wire_type, value)?
+                }
+                (IO_MESSAGE_BUCKETS, _) => {
+                    push_buckets(&mut parsed.io_message_buckets, wire_type, value)?
+                }
+                (INSTANTIATE_ALLOW_LIST_ENABLED, WIRE_TYPE_VARINT) => {
+                    parsed.instantiate_allow_list_enabled = varint_value(value)? != 0
+                }
+                (INSTANTIATE_ALLOWED_CODE_HASHES, WIRE_TYPE_LEN) => parsed
+                    .instantiate_allowed_code_hashes
+                    .push(code_hash(value)?),
+                (INSTANTIATE_DENIED_CODE_HASHES, WIRE_TYPE_LEN) => parsed
+                    .instantiate_denied_code_hashes
+                    .push(code_hash(value)?),
+                (ENCLAVE_PARAMS, WIRE_TYPE_LEN) => {
+                    let (name, value) = enclave_param(value)?;
+                    parsed.enclave_params.insert(name, value);
+                }
+                _ => {}
+            }
+        }
+
+        for buckets in &[&parsed.state_value_buckets, &parsed.io_message_buckets] {
+            if !valid_buckets(buckets) {
+                warn!("invalid padding buckets {:?}", buckets);
+                return Err(EnclaveError::FailedToDeserialize);
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+fn code_hash(value: &[u8]) -> Result<[u8; 32], EnclaveError> {
+    let mut code_hash = [0u8; 32];
+    if value.len() != code_hash.len() {
+        warn!("code hash of {} bytes in the compute params", value.len());
+        return Err(EnclaveError::FailedToDeserialize);
+    }
+    code_hash.copy_from_slice(value);
+    Ok(code_hash)
+}
+
+fn enclave_param(param: &[u8]) -> Result<(String, Vec<u8>), EnclaveError> {
+    let mut name = vec![];
+    let mut value = vec![];
+    for (field, wire_type, field_value) in fields(param)? {
+        match (field, wire_type) {
+            (ENCLAVE_PARAM_NAME, WIRE_TYPE_LEN) => name = field_value.to_vec(),
+            (ENCLAVE_PARAM_VALUE, WIRE_TYPE_LEN) => value = field_value.to_vec(),
+            _ => {}
+        }
+    }
+
+    let name = String::from_utf8(name).map_err(|_| EnclaveError::FailedToDeserialize)?;
+    Ok((name, value))
+}
+
+/// Appends the sizes of a repeated uint64 field, which may or may not be packed
+fn push_buckets(
+    buckets: &mut Vec<usize>,
+    wire_type: u64,
+    value: &[u8],
+) -> Result<(), EnclaveError> {
+    match wire_type {
+        WIRE_TYPE_VARINT => buckets.push(varint_value(value)? as usize),
+        _ => {
+            let mut packed = value;
+            while !packed.is_empty() {
+                buckets.push(read_varint(&mut packed)? as usize);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Buckets must be ascending sizes, of at most `MAX_PADDING_BUCKET`. No buckets keep the
+/// enclave's own.
+fn valid_buckets(buckets: &[usize]) -> bool {
+    if buckets.is_empty() {
+        return true;
+    }
+    buckets[0] != 0
+        && buckets[buckets.len() - 1] <= MAX_PADDING_BUCKET
+        && buckets.windows(2).all(|pair| pair[0] < pair[1])
+}
+
+lazy_static! {
+    static ref COMPUTE_PARAMS: SgxRwLock<Option<ComputeParams>> = SgxRwLock::new(None);
+}
+
+/// The params of the current block, if they were proven
+pub fn compute_params() -> Option<ComputeParams> {
+    COMPUTE_PARAMS.read().unwrap().clone()
+}
+
+pub fn set_compute_params(params: ComputeParams) {
+    *COMPUTE_PARAMS.write().unwrap() = Some(params);
+}
+
+/// Forgets the params, when a new block is verified
+pub fn clear_compute_params() {
+    *COMPUTE_PARAMS.write().unwrap() = None;
+}
+
+/// Takes the params as they are, where there's no app hash to prove them against
+///
+/// # Safety
+/// `in_params` must point to `in_params_len` readable bytes
+pub unsafe fn submit_unproven_compute_params(
+    in_params: *const u8,
+    in_params_len: u32,
+) -> sgx_status_t {
+    validate_input_length!(
+        in_params_len,
+        "params",
+        MAX_PARAMS_LENGTH,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    if in_params_len != 0 {
+        validate_const_ptr!(
+            in_params,
+            in_params_len as usize,
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        );
+    }
+
+    let params = if in_params_len != 0 {
+        std::slice::from_raw_parts(in_params, in_params_len as usize)
+    } else {
+        &[]
+    };
+    match ComputeParams::parse(params) {
+        Ok(params) => {
+            set_compute_params(params);
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(_) => sgx_status_t::SGX_ERROR_INVALID_PARAMETER,
+    }
+}
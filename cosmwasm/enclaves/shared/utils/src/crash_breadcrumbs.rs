@@ -0,0 +1,439 @@
+//! A sealed ring buffer of the last errors the enclave stopped on, so operators have something to
+//! triage after a panic or an abort took the node down.
+//!
+//! The panic hook, and the fatal error paths that don't panic, record a breadcrumb with the
+//! subsystem the error came from, the ecall that was running, the height of the block it ran for
+//! and the error's message. The buffer is sealed right away, before the enclave gets a chance to
+//! abort, and `ecall_get_crash_breadcrumbs` returns it after the node restarted.
+//!
+//! Messages can hold whatever the failing code formatted into them, e.g. a key it printed with
+//! `{:?}`, so they're sanitized before they're sealed, see `sanitize_message`.
+
+use std::cell::RefCell;
+use std::panic::{self, PanicInfo};
+use std::path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::SgxMutex;
+use std::untrusted::path::PathEx;
+
+use enclave_crypto::consts::{make_sgx_secret_path, SEALED_FILE_CRASH_BREADCRUMBS};
+use log::*;
+use serde::{Deserialize, Serialize};
+use sgx_types::{sgx_status_t, SgxResult};
+
+use crate::oom_handler;
+use crate::storage::{seal, unseal};
+
+/// How many breadcrumbs are kept, older ones are dropped
+pub const MAX_BREADCRUMBS: usize = 16;
+pub const MAX_MESSAGE_LENGTH: usize = 256;
+
+/// Runs of hex or base64 characters at least this long are redacted, and so are lists of numbers
+const MIN_REDACTED_LENGTH: usize = 16;
+const REDACTED: &str = "<redacted>";
+
+lazy_static::lazy_static! {
+    pub static ref CRASH_BREADCRUMBS_SEALING_PATH: String =
+        make_sgx_secret_path(SEALED_FILE_CRASH_BREADCRUMBS);
+
+    /// Held while a breadcrumb is added, so threads that fail together don't lose each other's
+    static ref SEALING_LOCK: SgxMutex<()> = SgxMutex::new(());
+}
+
+static PANIC_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, Default)]
+struct EcallContext {
+    ecall: Option<&'static str>,
+    height: Option<u64>,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<EcallContext> = RefCell::new(EcallContext::default());
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BreadcrumbKind {
+    Panic,
+    /// The panic of the OOM handler
+    OutOfMemory,
+    /// An error the enclave can't go on after, that didn't panic
+    Fatal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Breadcrumb {
+    /// Increases with every breadcrumb, across restarts
+    pub sequence: u64,
+    pub kind: BreadcrumbKind,
+    /// The crate the error came from, e.g. `contract-engine`
+    pub subsystem: String,
+    /// The file and line in the crate, for panics, e.g. `src/wasm3/mod.rs:120`
+    pub location: Option<String>,
+    /// The ecall that was running, if it's one that tags its breadcrumbs
+    pub ecall: Option<String>,
+    /// The height of the block the ecall ran for, once it parsed its env
+    pub height: Option<u64>,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CrashBreadcrumbs {
+    next_sequence: u64,
+    /// Oldest first
+    pub breadcrumbs: Vec<Breadcrumb>,
+}
+
+impl CrashBreadcrumbs {
+    /// Returns an empty buffer if nothing was recorded yet
+    pub fn unseal() -> SgxResult<Self> {
+        if !path::Path::new(CRASH_BREADCRUMBS_SEALING_PATH.as_str()).exists() {
+            return Ok(Self::default());
+        }
+
+        let buffer: Self = serde_json::from_slice(
+            unseal(&CRASH_BREADCRUMBS_SEALING_PATH)?.as_slice(),
+        )
+        .map_err(|e| {
+            error!("Error decoding crash breadcrumbs from json {:?}", e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })?;
+
+        Ok(buffer)
+    }
+
+    pub fn seal(&self) -> SgxResult<()> {
+        let encoded = serde_json::to_vec(&self).map_err(|e| {
+            error!("Error encoding crash breadcrumbs to json: {:?}", e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        })?;
+
+        seal(encoded.as_slice(), &CRASH_BREADCRUMBS_SEALING_PATH)
+    }
+
+    /// Adds a breadcrumb with the next sequence number, and drops the oldest one if the buffer is
+    /// full
+    pub fn push(&mut self, mut breadcrumb: Breadcrumb) {
+        breadcrumb.sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        if self.breadcrumbs.len() >= MAX_BREADCRUMBS {
+            self.breadcrumbs.remove(0);
+        }
+        self.breadcrumbs.push(breadcrumb);
+    }
+}
+
+/// Tags the breadcrumbs of the current thread with an ecall, until it's dropped
+pub struct EcallScope {
+    previous: EcallContext,
+}
+
+impl Drop for EcallScope {
+    fn drop(&mut self) {
+        let previous = self.previous;
+        let _ = CONTEXT.try_with(|context| *context.borrow_mut() = previous);
+    }
+}
+
+/// Ecalls can nest, e.g. a query a contract makes during a handle, so the scope of the outer ecall
+/// is restored when the inner one ends
+pub fn enter_ecall(ecall: &'static str) -> EcallScope {
+    let previous = CONTEXT.with(|context| {
+        context.replace(EcallContext {
+            ecall: Some(ecall),
+            height: None,
+        })
+    });
+
+    EcallScope { previous }
+}
+
+/// Tags the breadcrumbs of the current ecall with the height of the block it runs for
+pub fn set_height(height: u64) {
+    CONTEXT.with(|context| context.borrow_mut().height = Some(height));
+}
+
+fn current_context() -> EcallContext {
+    CONTEXT
+        .try_with(|context| {
+            context
+                .try_borrow()
+                .map(|context| *context)
+                .unwrap_or_default()
+        })
+        .unwrap_or_default()
+}
+
+/// Installs a panic hook that records every panic, before the default hook prints it. Only the
+/// first call installs it.
+pub fn install_panic_hook() {
+    if PANIC_HOOK_INSTALLED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        record_panic(info);
+        default_hook(info);
+    }));
+}
+
+fn record_panic(info: &PanicInfo) {
+    let payload = info.payload();
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "panicked without a message"
+    };
+
+    let (subsystem, location) = match info.location() {
+        Some(location) => {
+            let (subsystem, file) = split_source_path(location.file());
+            (subsystem, Some(format!("{}:{}", file, location.line())))
+        }
+        None => ("unknown".to_string(), None),
+    };
+
+    let kind = if oom_handler::oom_happened() {
+        BreadcrumbKind::OutOfMemory
+    } else {
+        BreadcrumbKind::Panic
+    };
+
+    record(kind, subsystem, location, message);
+}
+
+/// Records an error the enclave can't go on after, that doesn't panic
+pub fn record_fatal(subsystem: &str, message: &str) {
+    record(BreadcrumbKind::Fatal, subsystem.to_string(), None, message);
+}
+
+fn record(kind: BreadcrumbKind, subsystem: String, location: Option<String>, message: &str) {
+    let context = current_context();
+    let breadcrumb = Breadcrumb {
+        sequence: 0,
+        kind,
+        subsystem,
+        location,
+        ecall: context.ecall.map(String::from),
+        height: context.height,
+        message: sanitize_message(message),
+    };
+
+    // The hook doesn't panic while it holds the lock, so a poisoned lock is still consistent
+    let _lock = SEALING_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let mut buffer = CrashBreadcrumbs::unseal().unwrap_or_else(|_| {
+        warn!("starting over with the crash breadcrumbs, the sealed ones can't be read");
+        CrashBreadcrumbs::default()
+    });
+    buffer.push(breadcrumb);
+    if buffer.seal().is_err() {
+        error!("failed to seal a crash breadcrumb");
+    }
+}
+
+/// The breadcrumbs, oldest first, as a json array
+pub fn encoded_crash_breadcrumbs() -> SgxResult<Vec<u8>> {
+    let buffer = {
+        let _lock = SEALING_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        CrashBreadcrumbs::unseal()?
+    };
+
+    serde_json::to_vec(&buffer.breadcrumbs).map_err(|e| {
+        error!("Error encoding crash breadcrumbs to json: {:?}", e);
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })
+}
+
+/// Splits the path of a source file into the crate it's in and the path in the crate, e.g.
+/// `shared/contract-engine/src/wasm3/mod.rs` into `contract-engine` and `src/wasm3/mod.rs`
+fn split_source_path(file: &str) -> (String, String) {
+    let components: Vec<&str> = file.split('/').collect();
+    match components.iter().rposition(|component| *component == "src") {
+        Some(src) if src > 0 => (components[src - 1].to_string(), components[src..].join("/")),
+        _ => (
+            "unknown".to_string(),
+            components.last().copied().unwrap_or_default().to_string(),
+        ),
+    }
+}
+
+/// Makes a message safe to keep after the enclave stopped. It's trimmed, characters other than
+/// printable ASCII are replaced with `?`, and lists of numbers and runs of hex or base64
+/// characters with a digit in them are redacted, when they're at least `MIN_REDACTED_LENGTH`
+/// long, since that's how keys and ciphertexts end up in messages. What's left is cut to
+/// `MAX_MESSAGE_LENGTH`.
+///
+/// Paths and versions can look like base64 as well, and are redacted with the rest. The message
+/// is only context for the location, so redacting too much is the cheaper mistake.
+pub fn sanitize_message(message: &str) -> String {
+    let printable: String = message
+        .trim()
+        .chars()
+        .map(|c| {
+            if c == ' ' || c.is_ascii_graphic() {
+                c
+            } else {
+                '?'
+            }
+        })
+        .collect();
+
+    let mut sanitized = redact_runs(&redact_number_lists(&printable));
+    // Only ASCII is left, so any length is on a character boundary
+    sanitized.truncate(MAX_MESSAGE_LENGTH);
+    sanitized
+}
+
+/// Redacts `[1, 2, 3, ...]`, which is how byte arrays are formatted with `{:?}`
+fn redact_number_lists(message: &str) -> String {
+    let mut redacted = String::with_capacity(message.len());
+    let mut rest = message;
+
+    while let Some(start) = rest.find('[') {
+        redacted.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find(']') {
+            Some(end) if is_number_list(&after[..end]) => {
+                redacted.push('[');
+                redacted.push_str(REDACTED);
+                redacted.push(']');
+                rest = &after[end + 1..];
+            }
+            _ => {
+                redacted.push('[');
+                rest = after;
+            }
+        }
+    }
+
+    redacted.push_str(rest);
+    redacted
+}
+
+fn is_number_list(list: &str) -> bool {
+    list.len() >= MIN_REDACTED_LENGTH
+        && list
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == ',' || c == ' ')
+}
+
+fn redact_runs(message: &str) -> String {
+    fn flush(redacted: &mut String, run: &mut String) {
+        if run.len() >= MIN_REDACTED_LENGTH && run.chars().any(|c| c.is_ascii_digit()) {
+            redacted.push_str(REDACTED);
+        } else {
+            redacted.push_str(run);
+        }
+        run.clear();
+    }
+
+    let mut redacted = String::with_capacity(message.len());
+    let mut run = String::new();
+    for c in message.chars() {
+        if c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '=' {
+            run.push(c);
+        } else {
+            flush(&mut redacted, &mut run);
+            redacted.push(c);
+        }
+    }
+    flush(&mut redacted, &mut run);
+
+    redacted
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_crash_breadcrumbs_sanitize_message() {
+        assert_eq!(
+            sanitize_message("called `Option::unwrap()` on a `None` value"),
+            "called `Option::unwrap()` on a `None` value"
+        );
+        assert_eq!(
+            sanitize_message("seed too long: [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]"),
+            "seed too long: [<redacted>]"
+        );
+        // short lists are kept
+        assert_eq!(sanitize_message("index [1, 2]"), "index [1, 2]");
+        assert_eq!(
+            sanitize_message("bad key 9d61b19deffd5a60ba844af492ec2cc4 for 2 bytes"),
+            "bad key <redacted> for 2 bytes"
+        );
+        assert_eq!(
+            sanitize_message("bad ciphertext ZGVhZGJlZWYxMjM0NTY3OA== from host"),
+            "bad ciphertext <redacted> from host"
+        );
+        // long words without a digit are kept
+        assert_eq!(
+            sanitize_message("MemorySafetyAllocationError"),
+            "MemorySafetyAllocationError"
+        );
+        assert_eq!(sanitize_message("line\nbreak \u{1F600}"), "line?break ?");
+
+        let long = "a ".repeat(MAX_MESSAGE_LENGTH);
+        assert_eq!(sanitize_message(&long).len(), MAX_MESSAGE_LENGTH);
+    }
+
+    pub fn test_crash_breadcrumbs_ring_buffer() {
+        let breadcrumb = Breadcrumb {
+            sequence: 0,
+            kind: BreadcrumbKind::Panic,
+            subsystem: "contract-engine".to_string(),
+            location: None,
+            ecall: Some("ecall_handle".to_string()),
+            height: Some(100),
+            message: "failed".to_string(),
+        };
+
+        let mut buffer = CrashBreadcrumbs::default();
+        for _ in 0..MAX_BREADCRUMBS + 2 {
+            buffer.push(breadcrumb.clone());
+        }
+
+        assert_eq!(buffer.breadcrumbs.len(), MAX_BREADCRUMBS);
+        assert_eq!(buffer.breadcrumbs[0].sequence, 2);
+        assert_eq!(
+            buffer.breadcrumbs.last().unwrap().sequence,
+            MAX_BREADCRUMBS as u64 + 1
+        );
+
+        assert_eq!(
+            split_source_path("shared/contract-engine/src/wasm3/mod.rs"),
+            (
+                "contract-engine".to_string(),
+                "src/wasm3/mod.rs".to_string()
+            )
+        );
+        assert_eq!(
+            split_source_path("lib.rs"),
+            ("unknown".to_string(), "lib.rs".to_string())
+        );
+    }
+
+    pub fn test_crash_breadcrumbs_ecall_scope() {
+        {
+            let _handle = enter_ecall("ecall_handle");
+            set_height(100);
+            {
+                let _query = enter_ecall("ecall_query");
+                assert_eq!(current_context().ecall, Some("ecall_query"));
+                assert_eq!(current_context().height, None);
+            }
+            assert_eq!(current_context().ecall, Some("ecall_handle"));
+            assert_eq!(current_context().height, Some(100));
+        }
+        assert_eq!(current_context().ecall, None);
+    }
+}
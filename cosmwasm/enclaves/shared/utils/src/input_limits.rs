@@ -0,0 +1,28 @@
+//! The limits on the sizes of ecall inputs that the chain can change.
+//!
+//! The compute params can lower the msg, wasm and cert limits that the enclave is built with, but
+//! never raise them, since those are what the enclave is known to handle. Until the params of the
+//! current block are proven, the built-in limits hold.
+
+use enclave_ffi_types::{MAX_CERT_LENGTH, MAX_MSG_LENGTH, MAX_WASM_LENGTH};
+
+use crate::compute_params::{compute_params, ComputeParams};
+
+pub fn max_msg_length() -> usize {
+    limit(MAX_MSG_LENGTH, |params| params.max_msg_length)
+}
+
+pub fn max_wasm_length() -> usize {
+    limit(MAX_WASM_LENGTH, |params| params.max_wasm_length)
+}
+
+pub fn max_cert_length() -> usize {
+    limit(MAX_CERT_LENGTH, |params| params.max_cert_length)
+}
+
+fn limit(built_in: usize, param: impl Fn(&ComputeParams) -> u64) -> usize {
+    match compute_params().map(|params| param(&params)) {
+        Some(limit) if limit != 0 && limit < built_in as u64 => limit as usize,
+        _ => built_in,
+    }
+}
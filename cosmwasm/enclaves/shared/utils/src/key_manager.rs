@@ -5,7 +5,9 @@ use enclave_crypto::consts::*;
 use enclave_crypto::ed25519::Ed25519PrivateKey;
 use enclave_crypto::traits::{Kdf, SealedKey};
 use enclave_crypto::CryptoError;
-use enclave_crypto::{AESKey, KeyPair, Seed};
+use enclave_crypto::{
+    derive_data, AESKey, KdfAlgorithm, KeyContext, KeyPair, Seed, PUBLIC_KEY_SIZE,
+};
 use enclave_ffi_types::EnclaveError;
 use lazy_static::lazy_static;
 use log::*;
@@ -62,6 +64,10 @@ impl KeychainMutableData {
 pub struct Keychain {
     consensus_seed_id: u16,
     consensus_seed: Option<SeedsHolder<Seed>>,
+    /// The algorithms keys are derived from each consensus seed with
+    consensus_seed_kdf: SeedsHolder<KdfAlgorithm>,
+    /// The network each consensus seed's keys are bound to, see `KeyContext`
+    consensus_seed_context: SeedsHolder<Option<KeyContext>>,
     consensus_state_ikm: Option<SeedsHolder<AESKey>>,
     consensus_seed_exchange_keypair: Option<SeedsHolder<KeyPair>>,
     consensus_io_exchange_keypair: Option<SeedsHolder<KeyPair>>,
@@ -71,6 +77,8 @@ pub struct Keychain {
     registration_key: Option<KeyPair>,
     admin_proof_secret: Option<AESKey>,
     contract_key_proof_secret: Option<AESKey>,
+    mempool_key_secret: Option<AESKey>,
+    seed_heartbeat_key_secret: Option<AESKey>,
     pub extra_data: SgxMutex<KeychainMutableData>,
 }
 
@@ -88,6 +96,41 @@ lazy_static! {
 
 const KEYCHAIN_DATA_VER: u32 = 1;
 
+/// A secret anyone can derive, for the well-known keys of audit builds
+#[cfg(feature = "audit")]
+fn audit_secret(label: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"secret audit build ");
+    hasher.update(label.as_bytes());
+
+    let mut ret = [0u8; 32];
+    ret.copy_from_slice(&hasher.finalize());
+    ret
+}
+
+/// The genesis and current seeds of audit builds, so that auditors can decrypt everything the
+/// enclave encrypts, and runs are the same on every machine
+#[cfg(feature = "audit")]
+pub fn audit_seeds() -> (Seed, Seed) {
+    let mut genesis = Seed::default();
+    genesis
+        .as_mut()
+        .copy_from_slice(&audit_secret("genesis seed"));
+    let mut current = Seed::default();
+    current
+        .as_mut()
+        .copy_from_slice(&audit_secret("current seed"));
+    (genesis, current)
+}
+
+#[cfg(feature = "audit")]
+pub fn audit_registration_key() -> KeyPair {
+    let mut sk = Ed25519PrivateKey::default();
+    sk.get_mut()
+        .copy_from_slice(&audit_secret("registration key"));
+    KeyPair::from_sk(sk)
+}
+
 #[allow(clippy::new_without_default)]
 impl Keychain {
     pub fn serialize(&self, writer: &mut dyn Write) -> std::io::Result<()> {
@@ -122,6 +165,27 @@ impl Keychain {
             writer.write_all(&[0_u8])?;
         }
 
+        writer.write_all(&[
+            self.consensus_seed_kdf.genesis.id(),
+            self.consensus_seed_kdf.current.id(),
+        ])?;
+
+        for context in [
+            &self.consensus_seed_context.genesis,
+            &self.consensus_seed_context.current,
+        ]
+        .iter()
+        {
+            if let Some(context) = context {
+                writer.write_all(&[1_u8])?;
+                writer.write_all(&(context.chain_id.len() as u64).to_le_bytes())?;
+                writer.write_all(context.chain_id.as_bytes())?;
+                writer.write_all(&context.network_version.to_le_bytes())?;
+            } else {
+                writer.write_all(&[0_u8])?;
+            }
+        }
+
         Ok(())
     }
 
@@ -186,9 +250,57 @@ impl Keychain {
             extra.next_mr_enclave = None;
         }
 
+        // Files sealed before kdf algorithms were versioned end here, and all their keys were
+        // derived with the default algorithm
+        let mut kdf_ids = [0u8; 2];
+        self.consensus_seed_kdf = match reader.read_exact(&mut kdf_ids) {
+            Ok(()) => {
+                let from_id = |id| {
+                    KdfAlgorithm::from_id(id).map_err(|_| {
+                        std::io::Error::new(std::io::ErrorKind::Other, "unsupported kdf algorithm")
+                    })
+                };
+                SeedsHolder {
+                    genesis: from_id(kdf_ids[0])?,
+                    current: from_id(kdf_ids[1])?,
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => SeedsHolder::default(),
+            Err(err) => return Err(err),
+        };
+
+        // Files sealed before keys were bound to a network end here. Their seeds stay unbound, so
+        // the keys of existing networks don't change.
+        self.consensus_seed_context = match reader.read_exact(&mut flag_bytes) {
+            Ok(()) => {
+                let genesis = Self::read_key_context(reader, flag_bytes[0])?;
+                reader.read_exact(&mut flag_bytes)?;
+                let current = Self::read_key_context(reader, flag_bytes[0])?;
+                SeedsHolder { genesis, current }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => SeedsHolder::default(),
+            Err(err) => return Err(err),
+        };
+
         Ok(())
     }
 
+    fn read_key_context(reader: &mut dyn Read, flag: u8) -> std::io::Result<Option<KeyContext>> {
+        if flag == 0 {
+            return Ok(None);
+        }
+
+        let mut chain_id = vec![0u8; Self::read_u64(reader)? as usize];
+        reader.read_exact(&mut chain_id)?;
+        let chain_id = String::from_utf8(chain_id)
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "invalid chain id"))?;
+
+        Ok(Some(KeyContext {
+            chain_id,
+            network_version: Self::read_u32(reader)?,
+        }))
+    }
+
     pub fn save(&self) {
         let path: &str = &SEALED_DATA_PATH;
         let mut file = SgxFile::create_ex(path, &SEALING_KDK).unwrap();
@@ -234,6 +346,8 @@ impl Keychain {
         Keychain {
             consensus_seed_id: CONSENSUS_SEED_VERSION,
             consensus_seed: None,
+            consensus_seed_kdf: SeedsHolder::default(),
+            consensus_seed_context: SeedsHolder::default(),
             registration_key: None,
             consensus_state_ikm: None,
             consensus_seed_exchange_keypair: None,
@@ -245,6 +359,8 @@ impl Keychain {
             random_encryption_key: None,
             admin_proof_secret: None,
             contract_key_proof_secret: None,
+            mempool_key_secret: None,
+            seed_heartbeat_key_secret: None,
             extra_data: SgxMutex::new(KeychainMutableData {
                 height: 0,
                 validator_set_serialized: Vec::new(),
@@ -294,6 +410,13 @@ impl Keychain {
     pub fn new() -> Self {
         let mut x = Self::new_empty();
         x.load();
+        // audit builds must never run with the network's keys, even if they can unseal them
+        #[cfg(feature = "audit")]
+        {
+            let (genesis, current) = audit_seeds();
+            x.registration_key = Some(audit_registration_key());
+            x.consensus_seed = Some(SeedsHolder { genesis, current });
+        }
         let _ = x.generate_consensus_master_keys();
         x
     }
@@ -311,6 +434,14 @@ impl Keychain {
     }
 
     pub fn create_consensus_seed(&mut self) -> Result<(), CryptoError> {
+        #[cfg(feature = "audit")]
+        {
+            let (genesis, current) = audit_seeds();
+            self.set_consensus_seed(genesis, current);
+            return Ok(());
+        }
+
+        #[allow(unreachable_code)]
         match (Seed::new(), Seed::new()) {
             (Ok(genesis), Ok(current)) => {
                 self.set_consensus_seed(genesis, current);
@@ -322,6 +453,13 @@ impl Keychain {
     }
 
     pub fn create_registration_key(&mut self) -> Result<(), CryptoError> {
+        #[cfg(feature = "audit")]
+        {
+            self.set_registration_key(audit_registration_key());
+            return Ok(());
+        }
+
+        #[allow(unreachable_code)]
         match KeyPair::new() {
             Ok(key) => self.set_registration_key(key),
             Err(err) => return Err(err),
@@ -348,6 +486,14 @@ impl Keychain {
         self.consensus_seed_id += 1;
     }
 
+    pub fn get_consensus_seed_kdf(&self) -> SeedsHolder<KdfAlgorithm> {
+        self.consensus_seed_kdf
+    }
+
+    pub fn get_consensus_seed_context(&self) -> SeedsHolder<Option<KeyContext>> {
+        self.consensus_seed_context.clone()
+    }
+
     pub fn get_consensus_seed(&self) -> Result<SeedsHolder<Seed>, CryptoError> {
         self.consensus_seed.ok_or_else(|| {
             error!("Error accessing consensus_seed (does not exist, or was not initialized)");
@@ -397,6 +543,20 @@ impl Keychain {
         })
     }
 
+    pub fn get_mempool_key_secret(&self) -> Result<AESKey, CryptoError> {
+        self.mempool_key_secret.ok_or_else(|| {
+            error!("Error accessing mempool_key_secret (does not exist, or was not initialized)");
+            CryptoError::ParsingError
+        })
+    }
+
+    pub fn get_seed_heartbeat_key_secret(&self) -> Result<AESKey, CryptoError> {
+        self.seed_heartbeat_key_secret.ok_or_else(|| {
+            error!("Error accessing seed_heartbeat_key_secret (does not exist, or was not initialized)");
+            CryptoError::ParsingError
+        })
+    }
+
     pub fn set_registration_key(&mut self, kp: KeyPair) {
         self.registration_key = Some(kp);
         self.save();
@@ -425,31 +585,107 @@ impl Keychain {
 
     pub fn set_consensus_seed(&mut self, genesis: Seed, current: Seed) {
         self.consensus_seed = Some(SeedsHolder { genesis, current });
+        // Only the default algorithm is in use so far. A seed rotation that moves to another
+        // algorithm should set it for the new current seed here.
+        self.consensus_seed_kdf = SeedsHolder::default();
+        // Seeds are unbound until they're bound to the network that created them, or found to
+        // be bound to the network they were received from
+        self.consensus_seed_context = SeedsHolder::default();
         self.save();
         trace!("Consensus seeds set");
     }
 
+    /// Binds the keys of both consensus seeds to `context`. Binding changes every key derived
+    /// from the seeds, so only seeds that no keys were derived from yet, i.e. those of a new
+    /// network, can be bound. The keys of an existing network, and the state encrypted under them,
+    /// can't be migrated to bound seeds, and binding them is rejected.
+    pub fn bind_consensus_seed_context(&mut self, context: KeyContext) -> Result<(), CryptoError> {
+        if self.consensus_state_ikm.is_some() {
+            error!(
+                "Keys were already derived from the consensus seeds, they can't be bound to {}",
+                context.chain_id
+            );
+            return Err(CryptoError::KeyError);
+        }
+        if context.chain_id.is_empty() {
+            error!("Consensus seeds can't be bound to an empty chain id");
+            return Err(CryptoError::KeyError);
+        }
+
+        info!(
+            "Binding the consensus seeds to {} version {}",
+            context.chain_id, context.network_version
+        );
+        self.consensus_seed_context = SeedsHolder {
+            genesis: Some(context.clone()),
+            current: Some(context),
+        };
+        self.save();
+        Ok(())
+    }
+
+    /// Finds whether the consensus seeds received from the network are bound to `context`.
+    /// Networks bind both of their seeds when they're created, so the seeds are bound if the seed
+    /// exchange key derived with the context is the one the network's seeds were encrypted with.
+    /// Seeds of networks created before keys were bound stay unbound.
+    pub fn detect_consensus_seed_context(
+        &mut self,
+        master_pk: &[u8; PUBLIC_KEY_SIZE],
+        context: KeyContext,
+    ) -> Result<(), CryptoError> {
+        let seeds = self.get_consensus_seed()?;
+
+        let data = derive_data(Some(&context), CONSENSUS_SEED_EXCHANGE_KEYPAIR_DERIVE_ORDER);
+        let is_bound = [
+            (seeds.genesis, self.consensus_seed_kdf.genesis),
+            (seeds.current, self.consensus_seed_kdf.current),
+        ]
+        .iter()
+        .any(|(seed, kdf)| {
+            KeyPair::from(seed.derive_key_with(*kdf, &data)).get_pubkey() == *master_pk
+        });
+
+        if is_bound {
+            self.bind_consensus_seed_context(context)
+        } else {
+            warn!(
+                "The consensus seeds aren't bound to {}, the network was created before keys were bound",
+                context.chain_id
+            );
+            Ok(())
+        }
+    }
+
     pub fn generate_consensus_master_keys(&mut self) -> Result<(), EnclaveError> {
         if !self.is_consensus_seed_set() {
             trace!("Seed not initialized, skipping derivation of enclave keys");
             return Ok(());
         }
 
+        let kdf = self.consensus_seed_kdf;
+        let context = self.consensus_seed_context.clone();
+
         // consensus_seed_exchange_keypair
 
-        let consensus_seed_exchange_keypair_genesis_bytes = self
-            .consensus_seed
-            .unwrap()
-            .genesis
-            .derive_key_from_this(&CONSENSUS_SEED_EXCHANGE_KEYPAIR_DERIVE_ORDER.to_be_bytes());
+        let consensus_seed_exchange_keypair_genesis_bytes =
+            self.consensus_seed.unwrap().genesis.derive_key_with(
+                kdf.genesis,
+                &derive_data(
+                    context.genesis.as_ref(),
+                    CONSENSUS_SEED_EXCHANGE_KEYPAIR_DERIVE_ORDER,
+                ),
+            );
         let consensus_seed_exchange_keypair_genesis =
             KeyPair::from(consensus_seed_exchange_keypair_genesis_bytes);
 
-        let consensus_seed_exchange_keypair_current_bytes = self
-            .consensus_seed
-            .unwrap()
-            .current
-            .derive_key_from_this(&CONSENSUS_SEED_EXCHANGE_KEYPAIR_DERIVE_ORDER.to_be_bytes());
+        let consensus_seed_exchange_keypair_current_bytes =
+            self.consensus_seed.unwrap().current.derive_key_with(
+                kdf.current,
+                &derive_data(
+                    context.current.as_ref(),
+                    CONSENSUS_SEED_EXCHANGE_KEYPAIR_DERIVE_ORDER,
+                ),
+            );
         let consensus_seed_exchange_keypair_current =
             KeyPair::from(consensus_seed_exchange_keypair_current_bytes);
 
@@ -460,19 +696,25 @@ impl Keychain {
 
         // consensus_io_exchange_keypair
 
-        let consensus_io_exchange_keypair_genesis_bytes = self
-            .consensus_seed
-            .unwrap()
-            .genesis
-            .derive_key_from_this(&CONSENSUS_IO_EXCHANGE_KEYPAIR_DERIVE_ORDER.to_be_bytes());
+        let consensus_io_exchange_keypair_genesis_bytes =
+            self.consensus_seed.unwrap().genesis.derive_key_with(
+                kdf.genesis,
+                &derive_data(
+                    context.genesis.as_ref(),
+                    CONSENSUS_IO_EXCHANGE_KEYPAIR_DERIVE_ORDER,
+                ),
+            );
         let consensus_io_exchange_keypair_genesis =
             KeyPair::from(consensus_io_exchange_keypair_genesis_bytes);
 
-        let consensus_io_exchange_keypair_current_bytes = self
-            .consensus_seed
-            .unwrap()
-            .current
-            .derive_key_from_this(&CONSENSUS_IO_EXCHANGE_KEYPAIR_DERIVE_ORDER.to_be_bytes());
+        let consensus_io_exchange_keypair_current_bytes =
+            self.consensus_seed.unwrap().current.derive_key_with(
+                kdf.current,
+                &derive_data(
+                    context.current.as_ref(),
+                    CONSENSUS_IO_EXCHANGE_KEYPAIR_DERIVE_ORDER,
+                ),
+            );
         let consensus_io_exchange_keypair_current =
             KeyPair::from(consensus_io_exchange_keypair_current_bytes);
 
@@ -483,33 +725,37 @@ impl Keychain {
 
         // consensus_state_ikm
 
-        let consensus_state_ikm_genesis = self
-            .consensus_seed
-            .unwrap()
-            .genesis
-            .derive_key_from_this(&CONSENSUS_STATE_IKM_DERIVE_ORDER.to_be_bytes());
+        let consensus_state_ikm_genesis = self.consensus_seed.unwrap().genesis.derive_key_with(
+            kdf.genesis,
+            &derive_data(context.genesis.as_ref(), CONSENSUS_STATE_IKM_DERIVE_ORDER),
+        );
 
-        let consensus_state_ikm_current = self
-            .consensus_seed
-            .unwrap()
-            .current
-            .derive_key_from_this(&CONSENSUS_STATE_IKM_DERIVE_ORDER.to_be_bytes());
+        let consensus_state_ikm_current = self.consensus_seed.unwrap().current.derive_key_with(
+            kdf.current,
+            &derive_data(context.current.as_ref(), CONSENSUS_STATE_IKM_DERIVE_ORDER),
+        );
 
         self.set_consensus_state_ikm(consensus_state_ikm_genesis, consensus_state_ikm_current);
 
         // consensus_state_ikm
 
-        let consensus_callback_secret_genesis = self
-            .consensus_seed
-            .unwrap()
-            .genesis
-            .derive_key_from_this(&CONSENSUS_CALLBACK_SECRET_DERIVE_ORDER.to_be_bytes());
-
-        let consensus_callback_secret_current = self
-            .consensus_seed
-            .unwrap()
-            .current
-            .derive_key_from_this(&CONSENSUS_CALLBACK_SECRET_DERIVE_ORDER.to_be_bytes());
+        let consensus_callback_secret_genesis =
+            self.consensus_seed.unwrap().genesis.derive_key_with(
+                kdf.genesis,
+                &derive_data(
+                    context.genesis.as_ref(),
+                    CONSENSUS_CALLBACK_SECRET_DERIVE_ORDER,
+                ),
+            );
+
+        let consensus_callback_secret_current =
+            self.consensus_seed.unwrap().current.derive_key_with(
+                kdf.current,
+                &derive_data(
+                    context.current.as_ref(),
+                    CONSENSUS_CALLBACK_SECRET_DERIVE_ORDER,
+                ),
+            );
 
         self.set_consensus_callback_secret(
             consensus_callback_secret_genesis,
@@ -518,36 +764,60 @@ impl Keychain {
 
         //#[cfg(feature = "random")]
         {
-            let rek =
-                self.consensus_seed.unwrap().current.derive_key_from_this(
-                    &RANDOMNESS_ENCRYPTION_KEY_SECRET_DERIVE_ORDER.to_be_bytes(),
-                );
-
-            let irs =
-                self.consensus_seed.unwrap().current.derive_key_from_this(
-                    &INITIAL_RANDOMNESS_SEED_SECRET_DERIVE_ORDER.to_be_bytes(),
-                );
+            let rek = self.consensus_seed.unwrap().current.derive_key_with(
+                kdf.current,
+                &derive_data(
+                    context.current.as_ref(),
+                    RANDOMNESS_ENCRYPTION_KEY_SECRET_DERIVE_ORDER,
+                ),
+            );
+
+            let irs = self.consensus_seed.unwrap().current.derive_key_with(
+                kdf.current,
+                &derive_data(
+                    context.current.as_ref(),
+                    INITIAL_RANDOMNESS_SEED_SECRET_DERIVE_ORDER,
+                ),
+            );
 
             self.initial_randomness_seed = Some(irs);
             self.random_encryption_key = Some(rek);
         }
 
-        let admin_proof_secret = self
-            .consensus_seed
-            .unwrap()
-            .current
-            .derive_key_from_this(&ADMIN_PROOF_SECRET_DERIVE_ORDER.to_be_bytes());
+        let admin_proof_secret = self.consensus_seed.unwrap().current.derive_key_with(
+            kdf.current,
+            &derive_data(context.current.as_ref(), ADMIN_PROOF_SECRET_DERIVE_ORDER),
+        );
 
         self.admin_proof_secret = Some(admin_proof_secret);
 
-        let contract_key_proof_secret = self
-            .consensus_seed
-            .unwrap()
-            .current
-            .derive_key_from_this(&CONTRACT_KEY_PROOF_SECRET_DERIVE_ORDER.to_be_bytes());
+        let contract_key_proof_secret = self.consensus_seed.unwrap().current.derive_key_with(
+            kdf.current,
+            &derive_data(
+                context.current.as_ref(),
+                CONTRACT_KEY_PROOF_SECRET_DERIVE_ORDER,
+            ),
+        );
 
         self.contract_key_proof_secret = Some(contract_key_proof_secret);
 
+        let mempool_key_secret = self.consensus_seed.unwrap().current.derive_key_with(
+            kdf.current,
+            &derive_data(context.current.as_ref(), MEMPOOL_KEY_SECRET_DERIVE_ORDER),
+        );
+
+        self.mempool_key_secret = Some(mempool_key_secret);
+
+        let seed_heartbeat_key_secret = self.consensus_seed.unwrap().current.derive_key_with(
+            kdf.current,
+            &derive_data(
+                context.current.as_ref(),
+                SEED_HEARTBEAT_KEY_SECRET_DERIVE_ORDER,
+            ),
+        );
+
+        self.seed_heartbeat_key_secret = Some(seed_heartbeat_key_secret);
+
         Ok(())
     }
 }
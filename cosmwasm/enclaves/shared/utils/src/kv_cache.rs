@@ -7,6 +7,8 @@ const PSEUDO_GAS_STORE_PER_BYTE: u64 = 5_000;
 pub struct KvCache {
     writeable_cache: BTreeMap<Vec<u8>, Vec<u8>>,
     readable_cache: BTreeMap<Vec<u8>, Vec<u8>>,
+    /// values read from chain state in this call, with the gas the read cost
+    storage_reads: BTreeMap<Vec<u8>, (Option<Vec<u8>>, u64)>,
     /// used to track pseudo gas for inserts - this helps avoid situations where the write cache gets
     /// so big that the flush to chain state goes OOM instead of out of gas
     gas_tracker: u64,
@@ -31,6 +33,19 @@ impl KvCache {
     pub fn store_in_ro_cache(&mut self, k: &[u8], v: &[u8]) -> Option<Vec<u8>> {
         self.readable_cache.insert(k.to_vec(), v.to_vec())
     }
+
+    /// this is used to remember what a read from chain state returned, so reading the same key
+    /// again in this call doesn't leave the enclave. `gas` is what the read cost, which a cached
+    /// read must still cost.
+    pub fn store_storage_read(&mut self, k: &[u8], v: Option<&[u8]>, gas: u64) {
+        self.storage_reads
+            .insert(k.to_vec(), (v.map(|v| v.to_vec()), gas));
+    }
+
+    pub fn storage_read(&self, key: &[u8]) -> Option<(Option<Vec<u8>>, u64)> {
+        self.storage_reads.get(key).cloned()
+    }
+
     pub fn read(&self, key: &[u8]) -> Option<Vec<u8>> {
         // first to to read from the writeable cache - this will be more updated
         if let Some(value) = self.writeable_cache.get(key) {
@@ -42,9 +57,15 @@ impl KvCache {
         }
     }
 
+    /// The writes that the next flush will make
+    pub fn writes(&self) -> &BTreeMap<Vec<u8>, Vec<u8>> {
+        &self.writeable_cache
+    }
+
     pub fn remove(&mut self, key: &[u8]) {
         self.writeable_cache.remove(key);
         self.readable_cache.remove(key);
+        self.storage_reads.remove(key);
     }
 
     pub fn drain_gas_tracker(&mut self) -> u64 {
@@ -58,6 +79,7 @@ impl KvCache {
             self.writeable_cache.drain_filter(|_k, _v| true).collect();
 
         self.readable_cache.clear();
+        self.storage_reads.clear();
 
         items
     }
@@ -8,20 +8,31 @@ extern crate core;
 #[cfg(not(target_env = "sgx"))]
 extern crate sgx_tstd as std;
 
+pub mod audit_log;
+pub mod compute_params;
+pub mod crash_breadcrumbs;
+pub mod input_limits;
 pub mod key_manager;
 pub mod kv_cache;
 pub mod logger;
 pub mod macros;
 pub mod oom_handler;
+pub mod output_buffer;
 pub mod pointers;
+pub mod proto_fields;
 pub mod recursion_depth;
 mod results;
+pub mod self_test;
 pub mod storage;
 pub mod tx_bytes;
 pub mod validator_set;
+pub mod verified_params;
 
 pub use key_manager::Keychain;
 pub use key_manager::KEY_MANAGER;
 
 #[cfg(feature = "random")]
 pub mod random;
+
+#[cfg(all(feature = "audit", feature = "production"))]
+compile_error!("Cannot use 'audit' & 'production' features together.");
@@ -120,6 +120,11 @@ pub fn register_oom_handler() -> Result<(), EnclaveError> {
     Ok(())
 }
 
+/// Whether the current thread ran out of memory, without clearing it
+pub fn oom_happened() -> bool {
+    OOM_HAPPENED.with(|oom_happened| oom_happened.load(Ordering::SeqCst))
+}
+
 pub fn get_then_clear_oom_happened() -> bool {
     OOM_HAPPENED.with(|oom_happened| oom_happened.swap(false, Ordering::SeqCst))
 }
@@ -0,0 +1,34 @@
+//! Outputs of ecalls, written to a buffer of the caller's capacity.
+//!
+//! Ecalls with outputs take the buffer, its capacity, and where to write the output's length.
+//! The length is always set, so when the output doesn't fit, the caller learns how large a
+//! buffer it needs and calls the ecall again. Nothing is written to a buffer that's too small.
+//! That way the enclave can change the size of an output without changing the ABI of its ecall.
+
+/// The output didn't fit in the caller's buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferTooSmall {
+    pub needed: usize,
+}
+
+/// Copy `bytes` to the caller's buffer, and set `output_len` to their length
+///
+/// # Safety
+/// `output` must point to `output_capacity` writable bytes, validated with `validate_mut_ptr!`
+pub unsafe fn write_output(
+    bytes: &[u8],
+    output: *mut u8,
+    output_capacity: u32,
+    output_len: &mut u32,
+) -> Result<(), BufferTooSmall> {
+    *output_len = bytes.len() as u32;
+    if bytes.len() > output_capacity as usize {
+        return Err(BufferTooSmall {
+            needed: bytes.len(),
+        });
+    }
+
+    let output_slice = std::slice::from_raw_parts_mut(output, bytes.len());
+    output_slice.copy_from_slice(bytes);
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! A minimal reader of protobuf fields, for the few messages the enclave reads from chain state
+//! as raw bytes.
+
+use std::vec::Vec;
+
+use enclave_ffi_types::EnclaveError;
+
+pub const WIRE_TYPE_VARINT: u64 = 0;
+pub const WIRE_TYPE_LEN: u64 = 2;
+
+pub fn read_varint(bytes: &mut &[u8]) -> Result<u64, EnclaveError> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = bytes
+            .split_first()
+            .ok_or(EnclaveError::FailedToDeserialize)?;
+        *bytes = rest;
+
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(EnclaveError::FailedToDeserialize)
+}
+
+pub fn read_len_delimited<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8], EnclaveError> {
+    let len = read_varint(bytes)? as usize;
+    if bytes.len() < len {
+        return Err(EnclaveError::FailedToDeserialize);
+    }
+
+    let (value, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(value)
+}
+
+/// The fields of a message, as `(field number, wire type, value)`. Varints are returned as their
+/// encoding, and fields of other wire types are rejected.
+pub fn fields(mut msg: &[u8]) -> Result<Vec<(u64, u64, &[u8])>, EnclaveError> {
+    let mut fields = vec![];
+    while !msg.is_empty() {
+        let key = read_varint(&mut msg)?;
+        let value = match key & 0x7 {
+            WIRE_TYPE_VARINT => {
+                let rest = msg;
+                read_varint(&mut msg)?;
+                &rest[..rest.len() - msg.len()]
+            }
+            WIRE_TYPE_LEN => read_len_delimited(&mut msg)?,
+            _ => return Err(EnclaveError::FailedToDeserialize),
+        };
+        fields.push((key >> 3, key & 0x7, value));
+    }
+    Ok(fields)
+}
+
+/// The value of a varint that `fields` returned
+pub fn varint_value(mut value: &[u8]) -> Result<u64, EnclaveError> {
+    read_varint(&mut value)
+}
+
+pub fn varint(mut value: u64) -> Vec<u8> {
+    let mut encoded = vec![];
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            encoded.push(byte);
+            return encoded;
+        }
+        encoded.push(byte | 0x80);
+    }
+}
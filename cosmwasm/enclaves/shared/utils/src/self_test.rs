@@ -0,0 +1,40 @@
+//! Whether the enclave passed its startup self-test, see `ecall_self_test`.
+//!
+//! Consensus ecalls are refused until the self-test passed, so an enclave that computes wrong
+//! results never changes the state or signs anything. A failure is final: running the self-test
+//! again, until it happens to pass on a flaky CPU, doesn't unlock the enclave.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use log::*;
+
+const NOT_RUN: u8 = 0;
+const PASSED: u8 = 1;
+const FAILED: u8 = 2;
+
+static SELF_TEST_STATE: AtomicU8 = AtomicU8::new(NOT_RUN);
+
+pub fn record_self_test(passed: bool) {
+    if passed {
+        // Don't overwrite an earlier failure
+        let _ =
+            SELF_TEST_STATE.compare_exchange(NOT_RUN, PASSED, Ordering::SeqCst, Ordering::SeqCst);
+    } else {
+        SELF_TEST_STATE.store(FAILED, Ordering::SeqCst);
+    }
+}
+
+/// Whether the enclave may serve consensus ecalls
+pub fn self_test_passed() -> bool {
+    match SELF_TEST_STATE.load(Ordering::SeqCst) {
+        PASSED => true,
+        FAILED => {
+            error!("refusing a consensus ecall, the enclave failed its self-test");
+            false
+        }
+        _ => {
+            error!("refusing a consensus ecall, the enclave didn't run its self-test");
+            false
+        }
+    }
+}
@@ -0,0 +1,365 @@
+//! Values governance sets inside the enclave, e.g. advisory lists, gas tables or whitelists.
+//!
+//! Every param has a name and a type, and is declared once here as a `VerifiedParam`, so the
+//! contract engine, the block verifier and registration all read it the same way:
+//!
+//! ```ignore
+//! pub const MAX_WIDGETS: VerifiedParam<u64> = VerifiedParam::new("max_widgets");
+//!
+//! let max_widgets = MAX_WIDGETS.get_or(DEFAULT_MAX_WIDGETS, STRICT_MAX_WIDGETS);
+//! ```
+//!
+//! and registered in `registered_params`, so governance can set it. The values are part of the
+//! compute module params, which governance changes with a `MsgUpdateParams`:
+//!
+//! ```text
+//! message Params {
+//!   ...
+//!   repeated EnclaveParam enclave_params = 12;
+//! }
+//!
+//! message EnclaveParam {
+//!   string name = 1;
+//!   bytes value = 2;
+//! }
+//! ```
+//!
+//! The enclave only takes the params once they're proven against the app hash of the block it's
+//! executing, see `compute_params`, so every node reads the values of the chain, and a node can't
+//! give its enclave others. A param that isn't set has its default. Until the params of the block
+//! are proven, or when a stored value doesn't decode, e.g. after an upgrade changed the param's
+//! type, the param has its strict value, so a node can't loosen anything by withholding them.
+
+use std::convert::TryInto;
+use std::marker::PhantomData;
+use std::string::String;
+use std::vec::Vec;
+
+use log::*;
+use serde::Deserialize;
+
+use enclave_ffi_types::EnclaveError;
+
+use crate::compute_params::{compute_params, ComputeParams};
+
+/// How values of a type are encoded in the params
+pub trait ParamValue: Sized {
+    fn decode(value: &[u8]) -> Option<Self>;
+}
+
+/// 8 bytes, big endian
+impl ParamValue for u64 {
+    fn decode(value: &[u8]) -> Option<Self> {
+        value.try_into().ok().map(u64::from_be_bytes)
+    }
+}
+
+/// A single byte, 0 or 1
+impl ParamValue for bool {
+    fn decode(value: &[u8]) -> Option<Self> {
+        match value {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+}
+
+/// UTF-8
+impl ParamValue for String {
+    fn decode(value: &[u8]) -> Option<Self> {
+        String::from_utf8(value.to_vec()).ok()
+    }
+}
+
+/// A list of hashes, concatenated
+impl ParamValue for Vec<[u8; 32]> {
+    fn decode(value: &[u8]) -> Option<Self> {
+        if value.len() % 32 != 0 {
+            return None;
+        }
+        Some(
+            value
+                .chunks(32)
+                .map(|hash| hash.try_into().unwrap())
+                .collect(),
+        )
+    }
+}
+
+/// What registration does with a public key it already issued seeds to, see
+/// `DUPLICATE_REGISTRATION_POLICY`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateRegistrationPolicy {
+    /// Issue the seeds again
+    Allow,
+    Deny,
+    /// Issue them again only if the cert proves the node holds the private key
+    RequireProof,
+}
+
+/// UTF-8, `allow`, `deny` or `require_proof`
+impl ParamValue for DuplicateRegistrationPolicy {
+    fn decode(value: &[u8]) -> Option<Self> {
+        match value {
+            b"allow" => Some(DuplicateRegistrationPolicy::Allow),
+            b"deny" => Some(DuplicateRegistrationPolicy::Deny),
+            b"require_proof" => Some(DuplicateRegistrationPolicy::RequireProof),
+            _ => None,
+        }
+    }
+}
+
+/// Which enclaves registration accepts, see `ATTESTATION_POLICY`. Every MRENCLAVE and MRSIGNER
+/// in it is a rule of its own, an enclave has to match one of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttestationPolicy {
+    pub mr_enclave: Vec<[u8; 32]>,
+    pub mr_signer: Vec<[u8; 32]>,
+    /// The product IDs, i.e. node roles, that may register. Any role when it's empty.
+    pub isv_prod_id: Vec<u16>,
+    /// The lowest security version that may register
+    pub min_isv_svn: u16,
+}
+
+impl AttestationPolicy {
+    /// Whether the policy lists the enclaves it accepts, instead of leaving it to the enclave
+    pub fn names_measurements(&self) -> bool {
+        !self.mr_enclave.is_empty() || !self.mr_signer.is_empty()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAttestationPolicy {
+    #[serde(default)]
+    mr_enclave: Vec<String>,
+    #[serde(default)]
+    mr_signer: Vec<String>,
+    #[serde(default)]
+    isv_prod_id: Vec<u16>,
+    #[serde(default)]
+    min_isv_svn: u16,
+}
+
+fn decode_measurements(measurements: &[String]) -> Option<Vec<[u8; 32]>> {
+    measurements
+        .iter()
+        .map(|measurement| {
+            let mut decoded = [0u8; 32];
+            hex::decode_to_slice(measurement, &mut decoded).ok()?;
+            Some(decoded)
+        })
+        .collect()
+}
+
+/// JSON, e.g. `{"mr_signer":["<hex>"],"isv_prod_id":[0],"min_isv_svn":2}`, where every field may
+/// be left out. Unknown fields are rejected, so a typo can't loosen the policy.
+impl ParamValue for AttestationPolicy {
+    fn decode(value: &[u8]) -> Option<Self> {
+        let raw: RawAttestationPolicy = serde_json::from_slice(value).ok()?;
+        Some(AttestationPolicy {
+            mr_enclave: decode_measurements(&raw.mr_enclave)?,
+            mr_signer: decode_measurements(&raw.mr_signer)?,
+            isv_prod_id: raw.isv_prod_id,
+            min_isv_svn: raw.min_isv_svn,
+        })
+    }
+}
+
+/// A step of `IsvSvnRatchet`
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct IsvSvnStep {
+    pub height: u64,
+    pub min_isv_svn: u16,
+}
+
+/// The minimum ISV SVN of enclaves that are given secrets, from the heights governance scheduled,
+/// see `ISV_SVN_RATCHET`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IsvSvnRatchet {
+    /// By height, and every step raises the minimum
+    pub steps: Vec<IsvSvnStep>,
+}
+
+impl IsvSvnRatchet {
+    /// The minimum at `height`, 0 before the first step. A height of 0 is no verified height, at
+    /// which no minimum is active.
+    pub fn min_isv_svn_at(&self, height: u64) -> u16 {
+        if height == 0 {
+            return 0;
+        }
+        self.steps
+            .iter()
+            .take_while(|step| step.height <= height)
+            .last()
+            .map_or(0, |step| step.min_isv_svn)
+    }
+}
+
+/// JSON, a list of steps, e.g. `[{"height":1000000,"min_isv_svn":3}]`. The list can't be empty,
+/// and every step must come at a higher height and with a higher minimum than the one before it.
+impl ParamValue for IsvSvnRatchet {
+    fn decode(value: &[u8]) -> Option<Self> {
+        let steps: Vec<IsvSvnStep> = serde_json::from_slice(value).ok()?;
+        if steps.is_empty() || steps[0].height == 0 {
+            return None;
+        }
+        let rises = steps.windows(2).all(|pair| {
+            pair[0].height < pair[1].height && pair[0].min_isv_svn < pair[1].min_isv_svn
+        });
+        if !rises {
+            return None;
+        }
+        Some(IsvSvnRatchet { steps })
+    }
+}
+
+pub struct VerifiedParam<T> {
+    pub name: &'static str,
+    value_type: PhantomData<fn() -> T>,
+}
+
+impl<T: ParamValue> VerifiedParam<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            value_type: PhantomData,
+        }
+    }
+
+    /// The value in the params of the current block. `default` when they don't set it, and
+    /// `strict` when they aren't proven.
+    pub fn get_or(&self, default: T, strict: T) -> T {
+        match compute_params() {
+            Some(params) => self.value_in(&params, default, strict),
+            None => strict,
+        }
+    }
+
+    /// The value in `params`. `default` when they don't set it, and `strict` when its value
+    /// doesn't decode.
+    pub fn value_in(&self, params: &ComputeParams, default: T, strict: T) -> T {
+        match params.enclave_params.get(self.name) {
+            None => default,
+            Some(value) => T::decode(value).unwrap_or_else(|| {
+                warn!("the value of param {} doesn't decode", self.name);
+                strict
+            }),
+        }
+    }
+
+    pub fn spec(&self) -> ParamSpec {
+        ParamSpec {
+            name: self.name,
+            is_valid: |value| T::decode(value).is_some(),
+        }
+    }
+}
+
+/// What the value of a param is checked against
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub is_valid: fn(&[u8]) -> bool,
+}
+
+/// Whether the contract engine signs a receipt of every encrypted execution, see
+/// `execution_receipt` there. Off by default, and on until the params are proven.
+pub const EXECUTION_RECEIPTS: VerifiedParam<bool> = VerifiedParam::new("execution_receipts");
+
+/// The execution ticks a single contract execution may use, see `wasm3::gas` in the contract
+/// engine. `DEFAULT_MAX_EXECUTION_TICKS` there when it's not set or not proven.
+pub const MAX_EXECUTION_TICKS: VerifiedParam<u64> = VerifiedParam::new("max_execution_ticks");
+
+/// The height from which registration rejects EPID attestations, see `onchain` in the execute
+/// enclave. Until then, EPID registrations are flagged as deprecated. EPID is accepted without a
+/// flag when it's not set, and rejected until the params are proven.
+pub const EPID_CUTOFF_HEIGHT: VerifiedParam<u64> = VerifiedParam::new("epid_cutoff_height");
+
+/// The height from which the seed rotation is active, and registration stops issuing the genesis
+/// seed to new nodes, see `onchain` in the execute enclave. The genesis seed is issued as before
+/// when it's not set.
+pub const SEED_ROTATION_HEIGHT: VerifiedParam<u64> = VerifiedParam::new("seed_rotation_height");
+
+/// Whether registration issues the genesis seed along with the current one, whatever
+/// `SEED_ROTATION_HEIGHT` says. Lets governance hold back the genesis seed before the rotation, or
+/// keep issuing it after, e.g. while contract state is still under it.
+pub const ISSUE_GENESIS_SEED: VerifiedParam<bool> = VerifiedParam::new("issue_genesis_seed");
+
+/// What registration does with a public key that x/registration says was already issued seeds,
+/// see `issued_keys` in the execute enclave. Seeds are issued again when it's not set, and
+/// duplicates are denied until the params are proven.
+pub const DUPLICATE_REGISTRATION_POLICY: VerifiedParam<DuplicateRegistrationPolicy> =
+    VerifiedParam::new("duplicate_registration_policy");
+
+/// The height from which every registration must prove the node holds its registration key, with
+/// a proof that refers to a recent block, see `possession` in the execute enclave. Only duplicate
+/// registrations may need a proof when it's not set, and every registration needs one until the
+/// params are proven.
+pub const POSSESSION_PROOF_HEIGHT: VerifiedParam<u64> =
+    VerifiedParam::new("possession_proof_height");
+
+/// The height from which seeds are only issued to nodes whose cert binds them to a validator in
+/// the set the light client verified, see `operator` in the execute enclave. Any attested node is
+/// issued seeds when it's not set, and only bound nodes until the params are proven.
+pub const OPERATOR_BINDING_HEIGHT: VerifiedParam<u64> =
+    VerifiedParam::new("operator_binding_height");
+
+/// Which enclaves registration accepts, see `verify_ra_report` in the execute enclave. When it
+/// names no MRENCLAVE or MRSIGNER, or isn't set, an enclave has to be this one or have its signer,
+/// depending on the attestation. Its product IDs and minimum ISV SVN apply either way. Until the
+/// params are proven, only enclaves of the same build and version are accepted.
+pub const ATTESTATION_POLICY: VerifiedParam<AttestationPolicy> =
+    VerifiedParam::new("attestation_policy");
+
+/// The minimum ISV SVN of enclaves that registration issues seeds to, and that peer enclaves
+/// hand secrets to, from the heights governance scheduled, see `svn_ratchet` in the execute
+/// enclave. No minimum is enforced when it's not set, and until the params are proven, no enclave
+/// older than the one checking is given secrets.
+pub const ISV_SVN_RATCHET: VerifiedParam<IsvSvnRatchet> = VerifiedParam::new("isv_svn_ratchet");
+
+/// Enclave features that uploads may not require, as a comma separated list of names, see
+/// `wasm3::code_limits` in the contract engine. Lets governance hold back a feature until it's
+/// available on every node. Nothing is withheld when it's not set, and every feature until the
+/// params are proven.
+pub const WITHHELD_CONTRACT_FEATURES: VerifiedParam<String> =
+    VerifiedParam::new("withheld_contract_features");
+
+/// The params governance can set. Params that aren't in it are rejected, since their values
+/// can't be checked.
+pub fn registered_params() -> Vec<ParamSpec> {
+    vec![
+        EXECUTION_RECEIPTS.spec(),
+        MAX_EXECUTION_TICKS.spec(),
+        EPID_CUTOFF_HEIGHT.spec(),
+        SEED_ROTATION_HEIGHT.spec(),
+        ISSUE_GENESIS_SEED.spec(),
+        DUPLICATE_REGISTRATION_POLICY.spec(),
+        POSSESSION_PROOF_HEIGHT.spec(),
+        OPERATOR_BINDING_HEIGHT.spec(),
+        ATTESTATION_POLICY.spec(),
+        ISV_SVN_RATCHET.spec(),
+        WITHHELD_CONTRACT_FEATURES.spec(),
+    ]
+}
+
+/// Checks the enclave params of `params` against `specs`, before governance sets them
+pub fn validate_params(params: &ComputeParams, specs: &[ParamSpec]) -> Result<(), EnclaveError> {
+    for (name, value) in &params.enclave_params {
+        let spec = match specs.iter().find(|spec| spec.name == name.as_str()) {
+            Some(spec) => spec,
+            None => {
+                warn!("governance tried to set the unknown param {}", name);
+                return Err(EnclaveError::ValidationFailure);
+            }
+        };
+
+        if !(spec.is_valid)(value) {
+            warn!("governance tried to set param {} to an invalid value", name);
+            return Err(EnclaveError::ValidationFailure);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+//! Decodes arbitrary inputs with every layout. A decoder must never panic, and whatever it
+//! accepts must encode back to the same bytes, or two inputs would decode to the same fields.
+
+#![no_main]
+
+use enclave_wire::{CombinedCert, Endian, Framed, Headed, Reader};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for endian in &[Endian::Little, Endian::Big] {
+        let endian = *endian;
+
+        if let Ok(fields) = Framed(endian).decode(data) {
+            assert_eq!(Framed(endian).encode(fields), data);
+        }
+        if let Ok(pairs) = Framed(endian).decode_pairs(data) {
+            assert_eq!(Framed(endian).encode_pairs(pairs), data);
+        }
+        if let Ok(fields) = Headed::<3>(endian).decode(data) {
+            assert_eq!(Headed::<3>(endian).encode(fields), data);
+        }
+
+        let mut reader = Reader::new(data);
+        if let Ok(fields) = Headed::<3>(endian).read(&mut reader) {
+            let read = data.len() - reader.remaining();
+            assert_eq!(Headed::<3>(endian).encode(fields), &data[..read]);
+        }
+    }
+
+    let mut reader = Reader::new(data);
+    if let Ok(head) = reader.array::<33>() {
+        assert_eq!([&head[..], reader.rest()].concat(), data);
+    }
+
+    if let Ok(cert) = CombinedCert::parse(data) {
+        let encoded = cert.encode();
+        assert_eq!(encoded, &data[..encoded.len()]);
+    }
+});
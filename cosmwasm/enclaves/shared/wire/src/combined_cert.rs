@@ -0,0 +1,392 @@
+//! The combined attestation certificate that nodes register with.
+//!
+//! A node attests with an EPID certificate, a DCAP quote and its collateral, or both, and submits
+//! all three fields as one blob. The enclave writes it in `save_attestation_combined` and splits it
+//! in `split_combined_cert`, both through `CombinedCert`, and operators and tests on the untrusted
+//! side build theirs with `CombinedCertBuilder`.
+//!
+//! A node that proves it holds its registration key appends the proof after the fields, as one
+//! more length and field, a node whose keys follow another scheme than a single X25519 key
+//! appends the id of its `KeyScheme` after that, and a node that binds itself to the validator it
+//! serves appends its `OperatorBinding` last. Enclaves and nodes that don't know about them
+//! ignore them, as they ignore anything after the fields.
+
+use alloc::vec::Vec;
+
+use crate::{Endian, Framed, Headed, Reader, WireResult};
+
+/// `len(EPID cert) || len(DCAP quote) || len(DCAP collateral) || EPID cert || DCAP quote ||
+/// DCAP collateral`
+pub const COMBINED_CERT: Headed<3> = Headed(Endian::Little);
+
+/// `len(proof) || proof`, after the fields of `COMBINED_CERT`
+pub const COMBINED_CERT_PROOF: Framed = Framed(Endian::Little);
+
+/// `len(key scheme) || key scheme`, after the proof, which is then encoded even if it's empty
+pub const COMBINED_CERT_KEY_SCHEME: Framed = Framed(Endian::Little);
+
+/// `len(operator binding) || operator binding`, after the key scheme, which is then encoded even
+/// if it's empty
+pub const COMBINED_CERT_OPERATOR: Framed = Framed(Endian::Little);
+
+/// `len(consensus key) || len(signature) || consensus key || signature`
+pub const OPERATOR_BINDING: Headed<2> = Headed(Endian::Little);
+
+/// How the keys of a registering node are laid out in the report data of its attestation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyScheme {
+    /// A single X25519 key, which the seeds are encrypted to. That's every cert without a scheme.
+    X25519,
+    /// The X25519 key, followed by an Ed25519 key that signs for the node
+    Ed25519X25519,
+}
+
+impl KeyScheme {
+    /// The id in a cert. `X25519` is never written, a cert without a scheme encodes as before.
+    pub fn id(self) -> u8 {
+        match self {
+            KeyScheme::X25519 => 0,
+            KeyScheme::Ed25519X25519 => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(KeyScheme::X25519),
+            1 => Some(KeyScheme::Ed25519X25519),
+            _ => None,
+        }
+    }
+
+    fn encoded(self) -> &'static [u8] {
+        match self {
+            KeyScheme::X25519 => &[],
+            KeyScheme::Ed25519X25519 => &[1],
+        }
+    }
+}
+
+/// The consensus key of the validator a node registers for, and its signature that binds the
+/// node's key to it. The enclave defines what's signed, the layout only carries it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OperatorBinding<'a> {
+    pub consensus_key: &'a [u8],
+    pub signature: &'a [u8],
+}
+
+impl<'a> OperatorBinding<'a> {
+    pub fn parse(binding: &'a [u8]) -> WireResult<Self> {
+        let [consensus_key, signature] = OPERATOR_BINDING.decode(binding)?;
+        Ok(Self {
+            consensus_key,
+            signature,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        OPERATOR_BINDING.encode([self.consensus_key, self.signature])
+    }
+}
+
+/// The fields of a combined cert. A field the node didn't attest with is empty.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CombinedCert<'a> {
+    pub epid_cert: &'a [u8],
+    pub dcap_quote: &'a [u8],
+    pub dcap_collateral: &'a [u8],
+    /// The proof that the node holds its registration key, empty if it has none
+    pub proof: &'a [u8],
+    /// The id of the node's `KeyScheme`, empty for `X25519`
+    pub key_scheme: &'a [u8],
+    /// The encoded `OperatorBinding` of the node, empty if it has none
+    pub operator: &'a [u8],
+}
+
+impl<'a> CombinedCert<'a> {
+    /// Whatever comes after the fields is ignored, as the enclave always has, so that a cert that
+    /// registered once still splits the same way. That includes a proof that's truncated, which
+    /// reads as no proof, a truncated key scheme, which reads as `X25519`, and a truncated operator
+    /// binding, which reads as none.
+    pub fn parse(cert: &'a [u8]) -> WireResult<Self> {
+        let mut reader = Reader::new(cert);
+        let [epid_cert, dcap_quote, dcap_collateral] = COMBINED_CERT.read(&mut reader)?;
+        let proof = reader.prefixed(COMBINED_CERT_PROOF.0).unwrap_or_default();
+        let key_scheme = reader
+            .prefixed(COMBINED_CERT_KEY_SCHEME.0)
+            .unwrap_or_default();
+        let operator = reader
+            .prefixed(COMBINED_CERT_OPERATOR.0)
+            .unwrap_or_default();
+
+        Ok(Self {
+            epid_cert,
+            dcap_quote,
+            dcap_collateral,
+            proof,
+            key_scheme,
+            operator,
+        })
+    }
+
+    /// The proof, the key scheme and the operator binding are only appended if there are any, or
+    /// a field after them, so a cert without them encodes as before
+    pub fn encode(&self) -> Vec<u8> {
+        let mut cert = self.attestation();
+        if self.has_proof() || !self.key_scheme.is_empty() || self.has_operator() {
+            cert.extend_from_slice(&COMBINED_CERT_PROOF.encode([self.proof]));
+        }
+        if !self.key_scheme.is_empty() || self.has_operator() {
+            cert.extend_from_slice(&COMBINED_CERT_KEY_SCHEME.encode([self.key_scheme]));
+        }
+        if self.has_operator() {
+            cert.extend_from_slice(&COMBINED_CERT_OPERATOR.encode([self.operator]));
+        }
+        cert
+    }
+
+    /// The scheme of the node's keys, or `None` if it's one this version doesn't know
+    pub fn scheme(&self) -> Option<KeyScheme> {
+        match self.key_scheme {
+            [] => Some(KeyScheme::X25519),
+            [id] => KeyScheme::from_id(*id),
+            _ => None,
+        }
+    }
+
+    /// The operator binding of the node, or `None` if it has none or it doesn't decode
+    pub fn operator_binding(&self) -> Option<OperatorBinding<'a>> {
+        if !self.has_operator() {
+            return None;
+        }
+        OperatorBinding::parse(self.operator).ok()
+    }
+
+    /// The fields without the proof, the key scheme and the operator binding, which is what the
+    /// proof is over
+    pub fn attestation(&self) -> Vec<u8> {
+        COMBINED_CERT.encode([self.epid_cert, self.dcap_quote, self.dcap_collateral])
+    }
+
+    pub fn has_epid(&self) -> bool {
+        !self.epid_cert.is_empty()
+    }
+
+    pub fn has_dcap(&self) -> bool {
+        !self.dcap_quote.is_empty()
+    }
+
+    pub fn has_proof(&self) -> bool {
+        !self.proof.is_empty()
+    }
+
+    pub fn has_operator(&self) -> bool {
+        !self.operator.is_empty()
+    }
+}
+
+/// Builds a combined cert from the attestations a node has, e.g.
+/// `CombinedCertBuilder::new().dcap(&quote, &collateral).build()`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CombinedCertBuilder<'a> {
+    cert: CombinedCert<'a>,
+}
+
+impl<'a> CombinedCertBuilder<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn epid_cert(mut self, cert: &'a [u8]) -> Self {
+        self.cert.epid_cert = cert;
+        self
+    }
+
+    pub fn dcap(mut self, quote: &'a [u8], collateral: &'a [u8]) -> Self {
+        self.cert.dcap_quote = quote;
+        self.cert.dcap_collateral = collateral;
+        self
+    }
+
+    pub fn proof(mut self, proof: &'a [u8]) -> Self {
+        self.cert.proof = proof;
+        self
+    }
+
+    pub fn key_scheme(mut self, scheme: KeyScheme) -> Self {
+        self.cert.key_scheme = scheme.encoded();
+        self
+    }
+
+    /// An encoded `OperatorBinding`
+    pub fn operator(mut self, binding: &'a [u8]) -> Self {
+        self.cert.operator = binding;
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        self.cert.encode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::WireError;
+
+    #[test]
+    fn combined_cert_layout() {
+        let cert = CombinedCertBuilder::new()
+            .epid_cert(b"epid")
+            .dcap(b"quote", b"coll")
+            .build();
+        assert_eq!(
+            cert,
+            &b"\x04\x00\x00\x00\x05\x00\x00\x00\x04\x00\x00\x00epidquotecoll"[..]
+        );
+
+        let parsed = CombinedCert::parse(&cert).unwrap();
+        assert_eq!(parsed.epid_cert, b"epid");
+        assert_eq!(parsed.dcap_quote, b"quote");
+        assert_eq!(parsed.dcap_collateral, b"coll");
+        assert_eq!(parsed.encode(), cert);
+    }
+
+    #[test]
+    fn combined_cert_with_one_attestation() {
+        let dcap = CombinedCertBuilder::new().dcap(b"quote", b"coll").build();
+        let parsed = CombinedCert::parse(&dcap).unwrap();
+        assert!(!parsed.has_epid() && parsed.has_dcap());
+        assert_eq!(&dcap[..4], b"\x00\x00\x00\x00");
+
+        let epid = CombinedCertBuilder::new().epid_cert(b"epid").build();
+        let parsed = CombinedCert::parse(&epid).unwrap();
+        assert!(parsed.has_epid() && !parsed.has_dcap());
+        assert_eq!(parsed.dcap_collateral, b"");
+    }
+
+    #[test]
+    fn combined_cert_parse_is_lenient_about_trailing_bytes() {
+        let mut cert = CombinedCertBuilder::new().dcap(b"quote", b"coll").build();
+        cert.extend_from_slice(b"junk");
+        let parsed = CombinedCert::parse(&cert).unwrap();
+        assert_eq!(parsed.dcap_collateral, b"coll");
+
+        assert_eq!(
+            CombinedCert::parse(&cert[..20]),
+            Err(WireError::Truncated { needed: 1 })
+        );
+    }
+
+    #[test]
+    fn combined_cert_with_proof() {
+        let builder = CombinedCertBuilder::new().dcap(b"quote", b"coll");
+        let without = builder.build();
+        let cert = builder.proof(b"proof").build();
+        assert_eq!(&cert[..without.len()], &without[..]);
+        assert_eq!(&cert[without.len()..], b"\x05\x00\x00\x00proof");
+
+        let parsed = CombinedCert::parse(&cert).unwrap();
+        assert_eq!(parsed.proof, b"proof");
+        assert_eq!(parsed.attestation(), without);
+        assert_eq!(parsed.encode(), cert);
+
+        // a truncated proof is no proof
+        let parsed = CombinedCert::parse(&cert[..cert.len() - 1]).unwrap();
+        assert!(!parsed.has_proof());
+        assert_eq!(parsed.dcap_collateral, b"coll");
+    }
+
+    #[test]
+    fn combined_cert_with_key_scheme() {
+        let builder = CombinedCertBuilder::new().dcap(b"quote", b"coll");
+        let without = builder.build();
+        assert_eq!(
+            CombinedCert::parse(&without).unwrap().scheme(),
+            Some(KeyScheme::X25519)
+        );
+        assert_eq!(builder.key_scheme(KeyScheme::X25519).build(), without);
+
+        // the proof is encoded before the scheme, even if it's empty
+        let cert = builder.key_scheme(KeyScheme::Ed25519X25519).build();
+        assert_eq!(
+            &cert[without.len()..],
+            b"\x00\x00\x00\x00\x01\x00\x00\x00\x01"
+        );
+        let parsed = CombinedCert::parse(&cert).unwrap();
+        assert!(!parsed.has_proof());
+        assert_eq!(parsed.scheme(), Some(KeyScheme::Ed25519X25519));
+        assert_eq!(parsed.attestation(), without);
+        assert_eq!(parsed.encode(), cert);
+
+        let proven = builder
+            .proof(b"proof")
+            .key_scheme(KeyScheme::Ed25519X25519)
+            .build();
+        let parsed = CombinedCert::parse(&proven).unwrap();
+        assert_eq!(parsed.proof, b"proof");
+        assert_eq!(parsed.scheme(), Some(KeyScheme::Ed25519X25519));
+
+        // a scheme this version doesn't know
+        let unknown = CombinedCert {
+            key_scheme: &[9],
+            ..parsed
+        };
+        assert_eq!(unknown.scheme(), None);
+        assert_eq!(
+            KeyScheme::from_id(KeyScheme::Ed25519X25519.id()),
+            Some(KeyScheme::Ed25519X25519)
+        );
+    }
+
+    #[test]
+    fn combined_cert_with_operator_binding() {
+        let binding = OperatorBinding {
+            consensus_key: &[1; 32],
+            signature: &[2; 64],
+        }
+        .encode();
+        assert_eq!(
+            OperatorBinding::parse(&binding).unwrap().consensus_key,
+            &[1; 32]
+        );
+
+        let builder = CombinedCertBuilder::new().dcap(b"quote", b"coll");
+        let without = builder.build();
+        assert_eq!(
+            CombinedCert::parse(&without).unwrap().operator_binding(),
+            None
+        );
+
+        // the proof and the scheme are encoded before the binding, even if they're empty
+        let cert = builder.operator(&binding).build();
+        assert_eq!(
+            &cert[without.len()..without.len() + 8],
+            b"\x00\x00\x00\x00\x00\x00\x00\x00"
+        );
+        let parsed = CombinedCert::parse(&cert).unwrap();
+        assert!(!parsed.has_proof());
+        assert_eq!(parsed.scheme(), Some(KeyScheme::X25519));
+        assert_eq!(parsed.operator_binding().unwrap().signature, &[2; 64]);
+        assert_eq!(parsed.attestation(), without);
+        assert_eq!(parsed.encode(), cert);
+
+        let all = builder
+            .proof(b"proof")
+            .key_scheme(KeyScheme::Ed25519X25519)
+            .operator(&binding)
+            .build();
+        let parsed = CombinedCert::parse(&all).unwrap();
+        assert_eq!(parsed.proof, b"proof");
+        assert_eq!(parsed.scheme(), Some(KeyScheme::Ed25519X25519));
+        assert_eq!(parsed.operator, &binding[..]);
+
+        // a binding that doesn't decode is none
+        let malformed = CombinedCert {
+            operator: &binding[..binding.len() - 1],
+            ..parsed
+        };
+        assert!(malformed.has_operator());
+        assert_eq!(malformed.operator_binding(), None);
+    }
+}
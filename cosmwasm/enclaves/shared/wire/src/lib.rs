@@ -0,0 +1,365 @@
+//! Encoders and decoders of the length-prefixed layouts that cross the enclave's boundary.
+//!
+//! Several ecalls take inputs that are a few byte strings glued together, e.g. a combined
+//! attestation certificate or the pairs of a state backup. The untrusted side builds them, so a
+//! decoder must check every length against what's left before it slices, and must not panic or
+//! read past its input whatever the lengths say. A layout is declared once as a value, such as
+//! `Framed(Endian::Big)`, and the same value encodes and decodes it, so the two sides can't drift
+//! apart.
+//!
+//! ```text
+//! Framed:    len(a) || a || len(b) || b || ...
+//! Headed<N>: len(a) || len(b) || ... || a || b || ...
+//! ```
+//!
+//! Every length is a u32, in the byte order of the layout. Fields whose size is fixed, like the
+//! nonce and public key in front of an encrypted message, have no length, and are read with
+//! `Reader::array`. The crate has no dependencies, so it builds for the enclave, for the untrusted
+//! side, and for the fuzzer in `fuzz/`, which runs with `cargo fuzz run decode` from this
+//! directory.
+
+#![no_std]
+
+extern crate alloc;
+
+mod combined_cert;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+pub use combined_cert::{
+    CombinedCert, CombinedCertBuilder, KeyScheme, OperatorBinding, COMBINED_CERT,
+    COMBINED_CERT_KEY_SCHEME, COMBINED_CERT_OPERATOR, COMBINED_CERT_PROOF, OPERATOR_BINDING,
+};
+
+pub type WireResult<T> = Result<T, WireError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireError {
+    /// The input ended `needed` bytes before the end of a length or a field
+    Truncated { needed: usize },
+    /// The input has `len` bytes after its last field
+    TrailingBytes { len: usize },
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::Truncated { needed } => write!(f, "input is truncated by {} bytes", needed),
+            WireError::TrailingBytes { len } => write!(f, "input has {} trailing bytes", len),
+        }
+    }
+}
+
+/// The byte order of the lengths of a layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    /// Panics if `len` doesn't fit in a u32. Only ever called on fields the caller holds in
+    /// memory, never on lengths read from an input.
+    fn encode_len(self, len: usize) -> [u8; 4] {
+        assert!(
+            len <= u32::MAX as usize,
+            "field of {} bytes is too long",
+            len
+        );
+        match self {
+            Endian::Little => (len as u32).to_le_bytes(),
+            Endian::Big => (len as u32).to_be_bytes(),
+        }
+    }
+
+    fn decode_len(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        }
+    }
+}
+
+/// Reads an input front to back. Every read either returns what it asked for or fails without
+/// consuming anything.
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn take(&mut self, len: usize) -> WireResult<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(WireError::Truncated {
+                needed: len - self.bytes.len(),
+            });
+        }
+
+        let (field, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(field)
+    }
+
+    /// A field of `N` bytes
+    pub fn array<const N: usize>(&mut self) -> WireResult<[u8; N]> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    pub fn u32(&mut self, endian: Endian) -> WireResult<u32> {
+        Ok(endian.decode_len(self.array()?))
+    }
+
+    /// Everything that's left, e.g. a ciphertext after its fixed size header
+    pub fn rest(self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// A field that comes after its length
+    pub fn prefixed(&mut self, endian: Endian) -> WireResult<&'a [u8]> {
+        let mut ahead = self.clone();
+        let len = ahead.u32(endian)? as usize;
+        let field = ahead.take(len)?;
+
+        *self = ahead;
+        Ok(field)
+    }
+
+    /// Fails if anything is left
+    pub fn finish(self) -> WireResult<()> {
+        match self.bytes.len() {
+            0 => Ok(()),
+            len => Err(WireError::TrailingBytes { len }),
+        }
+    }
+}
+
+/// Fields that each come after their length, as many as fit in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Framed(pub Endian);
+
+impl Framed {
+    pub fn encode<'f, I>(self, fields: I) -> Vec<u8>
+    where
+        I: IntoIterator<Item = &'f [u8]>,
+    {
+        let mut bytes = Vec::new();
+        for field in fields {
+            bytes.extend_from_slice(&self.0.encode_len(field.len()));
+            bytes.extend_from_slice(field);
+        }
+        bytes
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> WireResult<Vec<&[u8]>> {
+        let mut reader = Reader::new(bytes);
+        let mut fields = Vec::new();
+        while !reader.is_empty() {
+            fields.push(reader.prefixed(self.0)?);
+        }
+        Ok(fields)
+    }
+
+    /// Keys and values, each framed as a field of their own
+    pub fn encode_pairs<K, V, I>(self, pairs: I) -> Vec<u8>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut bytes = Vec::new();
+        for (key, value) in pairs {
+            for field in [key.as_ref(), value.as_ref()].iter() {
+                bytes.extend_from_slice(&self.0.encode_len(field.len()));
+                bytes.extend_from_slice(field);
+            }
+        }
+        bytes
+    }
+
+    /// Decodes pairs encoded with `encode_pairs`. A key without a value is truncated.
+    pub fn decode_pairs(self, bytes: &[u8]) -> WireResult<Vec<(&[u8], &[u8])>> {
+        let mut reader = Reader::new(bytes);
+        let mut pairs = Vec::new();
+        while !reader.is_empty() {
+            let key = reader.prefixed(self.0)?;
+            let value = reader.prefixed(self.0)?;
+            pairs.push((key, value));
+        }
+        Ok(pairs)
+    }
+}
+
+/// `N` fields, after all of their lengths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Headed<const N: usize>(pub Endian);
+
+impl<const N: usize> Headed<N> {
+    pub fn encode(self, fields: [&[u8]; N]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(4 * N + fields.iter().map(|f| f.len()).sum::<usize>());
+        for field in fields.iter() {
+            bytes.extend_from_slice(&self.0.encode_len(field.len()));
+        }
+        for field in fields.iter() {
+            bytes.extend_from_slice(field);
+        }
+        bytes
+    }
+
+    /// Reads the fields from the front of `reader`, and leaves the rest of it
+    pub fn read<'a>(self, reader: &mut Reader<'a>) -> WireResult<[&'a [u8]; N]> {
+        let mut ahead = reader.clone();
+
+        let mut lens = [0usize; N];
+        for len in lens.iter_mut() {
+            *len = ahead.u32(self.0)? as usize;
+        }
+        let mut fields: [&'a [u8]; N] = [&[]; N];
+        for (field, len) in fields.iter_mut().zip(lens.iter()) {
+            *field = ahead.take(*len)?;
+        }
+
+        *reader = ahead;
+        Ok(fields)
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> WireResult<[&[u8]; N]> {
+        let mut reader = Reader::new(bytes);
+        let fields = self.read(&mut reader)?;
+        reader.finish()?;
+        Ok(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    #[test]
+    fn framed_roundtrip() {
+        let fields: [&[u8]; 3] = [b"ab", b"", b"c"];
+        let bytes = Framed(Endian::Little).encode(fields.iter().copied());
+        assert_eq!(
+            bytes,
+            b"\x02\x00\x00\x00ab\x00\x00\x00\x00\x01\x00\x00\x00c"
+        );
+        assert_eq!(Framed(Endian::Little).decode(&bytes).unwrap(), fields);
+        assert_eq!(
+            Framed(Endian::Big).decode(b"").unwrap(),
+            Vec::<&[u8]>::new()
+        );
+
+        let big = Framed(Endian::Big).encode(fields.iter().copied());
+        assert_eq!(&big[..6], b"\x00\x00\x00\x02ab");
+        assert!(Framed(Endian::Little).decode(&big).is_err());
+    }
+
+    #[test]
+    fn framed_pairs_roundtrip() {
+        let pairs = [(b"key".to_vec(), b"value".to_vec()), (vec![], vec![0u8])];
+        let bytes = Framed(Endian::Big).encode_pairs(pairs.iter().map(|(k, v)| (k, v)));
+        let decoded = Framed(Endian::Big).decode_pairs(&bytes).unwrap();
+        assert_eq!(
+            decoded,
+            vec![(&b"key"[..], &b"value"[..]), (&[][..], &[0u8][..])]
+        );
+
+        // the same bytes as framing every key and value as a field
+        let fields: [&[u8]; 4] = [b"key", b"value", b"", b"\x00"];
+        assert_eq!(Framed(Endian::Big).encode(fields.iter().copied()), bytes);
+
+        // a key without its value
+        let key_only = Framed(Endian::Big).encode([&b"key"[..]].iter().copied());
+        assert_eq!(
+            Framed(Endian::Big).decode_pairs(&key_only),
+            Err(WireError::Truncated { needed: 4 })
+        );
+    }
+
+    #[test]
+    fn headed_roundtrip() {
+        let bytes = Headed::<3>(Endian::Little).encode([&b""[..], &b"quote"[..], &b"coll"[..]]);
+        assert_eq!(
+            &bytes[..12],
+            b"\x00\x00\x00\x00\x05\x00\x00\x00\x04\x00\x00\x00"
+        );
+        assert_eq!(
+            Headed::<3>(Endian::Little).decode(&bytes).unwrap(),
+            [&b""[..], &b"quote"[..], &b"coll"[..]]
+        );
+
+        // `read` leaves what comes after the fields, `decode` rejects it
+        let mut trailing = bytes.clone();
+        trailing.push(7);
+        let mut reader = Reader::new(&trailing);
+        Headed::<3>(Endian::Little).read(&mut reader).unwrap();
+        assert_eq!(reader.remaining(), 1);
+        assert_eq!(
+            Headed::<3>(Endian::Little).decode(&trailing),
+            Err(WireError::TrailingBytes { len: 1 })
+        );
+    }
+
+    #[test]
+    fn fixed_fields() {
+        let mut reader = Reader::new(b"\x01\x02\x03rest");
+        assert_eq!(reader.array::<1>(), Ok([1u8]));
+        assert_eq!(reader.array::<2>(), Ok([2u8, 3]));
+        assert_eq!(reader.clone().rest(), b"rest");
+
+        // a failed read consumes nothing
+        assert_eq!(reader.array::<5>(), Err(WireError::Truncated { needed: 1 }));
+        assert_eq!(reader.array::<4>(), Ok(*b"rest"));
+        assert!(reader.finish().is_ok());
+    }
+
+    #[test]
+    fn truncated_inputs_are_rejected() {
+        let framed = Framed(Endian::Little).encode([&b"abc"[..], b"de"].iter().copied());
+        let headed = Headed::<2>(Endian::Little).encode([&b"abc"[..], &b"de"[..]]);
+        for len in 0..framed.len() {
+            // cutting a framed input on a field boundary leaves a valid input
+            if len != 0 && len != 7 {
+                assert!(Framed(Endian::Little).decode(&framed[..len]).is_err());
+            }
+        }
+        for len in 0..headed.len() {
+            assert!(Headed::<2>(Endian::Little).decode(&headed[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn lengths_past_the_input_are_rejected() {
+        let huge = [0xffu8, 0xff, 0xff, 0xff, 1, 2];
+        assert_eq!(
+            Framed(Endian::Big).decode(&huge),
+            Err(WireError::Truncated {
+                needed: u32::MAX as usize - 2
+            })
+        );
+        assert!(Headed::<1>(Endian::Big).decode(&huge).is_err());
+
+        // a failed read consumes nothing
+        let mut reader = Reader::new(&huge);
+        assert!(reader.prefixed(Endian::Big).is_err());
+        assert_eq!(reader.remaining(), huge.len());
+        assert!(Headed::<2>(Endian::Big).read(&mut reader).is_err());
+        assert_eq!(reader.remaining(), huge.len());
+    }
+}
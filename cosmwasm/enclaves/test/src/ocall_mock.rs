@@ -132,6 +132,19 @@ pub extern "C" fn ocall_read_db(
     unimplemented!()
 }
 
+#[no_mangle]
+pub extern "C" fn ocall_read_db_first(
+    _context: Ctx,
+    _vm_error: *mut UntrustedVmError,
+    _gas_used: *mut u64,
+    _value: *mut EnclaveBuffer,
+    _key_index: *mut u32,
+    _keys: *const u8,
+    _keys_len: usize,
+) -> OcallReturn {
+    unimplemented!()
+}
+
 #[no_mangle]
 pub extern "C" fn ocall_allocate(_buffer: *const u8, _length: usize) -> UserSpaceBuffer {
     unimplemented!()
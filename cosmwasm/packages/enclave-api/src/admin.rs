@@ -0,0 +1,186 @@
+//! Ecalls on the state of a contract, authorized by its admin
+
+use cosmwasm_sgx_vm::{
+    untrusted_disclose_contract_key, untrusted_export_state, untrusted_fold_state_commitment,
+    untrusted_import_state, untrusted_reencrypt_state, untrusted_state_commitment_leaves,
+};
+
+use crate::error::{fixed_size, EcallResult, EnclaveApiResult};
+
+/// A call the admin of a contract authorized, with the proof that it's the admin
+#[derive(Debug, Clone, Copy)]
+pub struct AdminCall<'a> {
+    env: &'a [u8],
+    code_hash: &'a [u8],
+    admin: &'a [u8],
+    admin_proof: &'a [u8],
+}
+
+impl<'a> AdminCall<'a> {
+    /// `env` is the env of the current block and contract, and `code_hash` the hash of the
+    /// contract's code
+    pub fn new(env: &'a [u8], code_hash: &'a [u8]) -> Self {
+        Self {
+            env,
+            code_hash,
+            admin: &[],
+            admin_proof: &[],
+        }
+    }
+
+    pub fn admin(mut self, admin: &'a [u8], admin_proof: &'a [u8]) -> Self {
+        self.admin = admin;
+        self.admin_proof = admin_proof;
+        self
+    }
+
+    fn code_hash(&self, ecall: &'static str) -> EnclaveApiResult<&'a [u8; 32]> {
+        fixed_size::<32>(ecall, "code_hash", self.code_hash)
+    }
+
+    /// Discloses the state key of the contract to an auditor, as authorized by a
+    /// `MsgDiscloseContractKey` that governance passed. `msg_proof` proves the msg against the
+    /// app hash of the current block.
+    pub fn disclose_contract_key(self, msg: &[u8], msg_proof: &[u8]) -> EnclaveApiResult<Vec<u8>> {
+        const ECALL: &str = "ecall_disclose_contract_key";
+
+        let code_hash = self.code_hash(ECALL)?;
+        untrusted_disclose_contract_key(
+            self.env,
+            code_hash,
+            self.admin,
+            self.admin_proof,
+            msg,
+            msg_proof,
+        )
+        .ecall(ECALL)
+    }
+
+    /// Wraps a chunk of the contract's pairs, as they're stored, for a backup of its state
+    pub fn export_state(self, authorization: &[u8], pairs: &[u8]) -> EnclaveApiResult<Vec<u8>> {
+        const ECALL: &str = "ecall_export_state";
+
+        let code_hash = self.code_hash(ECALL)?;
+        untrusted_export_state(
+            self.env,
+            code_hash,
+            self.admin,
+            self.admin_proof,
+            authorization,
+            pairs,
+        )
+        .ecall(ECALL)
+    }
+
+    /// Unwraps a chunk of a backup of the contract's state, and returns its pairs encrypted to
+    /// be stored by the contract
+    pub fn import_state(
+        self,
+        authorization: &[u8],
+        backup_chunk: &[u8],
+    ) -> EnclaveApiResult<Vec<u8>> {
+        const ECALL: &str = "ecall_import_state";
+
+        let code_hash = self.code_hash(ECALL)?;
+        untrusted_import_state(
+            self.env,
+            code_hash,
+            self.admin,
+            self.admin_proof,
+            authorization,
+            backup_chunk,
+        )
+        .ecall(ECALL)
+    }
+}
+
+/// The commitment to the state of a contract, built from chunks of its pairs
+#[derive(Debug, Clone, Copy)]
+pub struct StateCommitment<'a> {
+    env: &'a [u8],
+    code_hash: &'a [u8],
+}
+
+impl<'a> StateCommitment<'a> {
+    pub fn new(env: &'a [u8], code_hash: &'a [u8]) -> Self {
+        Self { env, code_hash }
+    }
+
+    /// The leaves of a chunk of the contract's pairs, in the order of the pairs
+    pub fn leaves(self, pairs: &[u8]) -> EnclaveApiResult<Vec<u8>> {
+        const ECALL: &str = "ecall_state_commitment_leaves";
+
+        let code_hash = fixed_size::<32>(ECALL, "code_hash", self.code_hash)?;
+        untrusted_state_commitment_leaves(self.env, code_hash, pairs).ecall(ECALL)
+    }
+
+    /// Folds a chunk of pairs, sorted by their leaves, into the commitment. `progress` is empty
+    /// for the first chunk, and the output of the previous chunk otherwise. After the last chunk
+    /// the output is the signed commitment, as JSON.
+    pub fn fold(
+        self,
+        progress: &[u8],
+        pairs: &[u8],
+        last_chunk: bool,
+    ) -> EnclaveApiResult<Vec<u8>> {
+        const ECALL: &str = "ecall_fold_state_commitment";
+
+        let code_hash = fixed_size::<32>(ECALL, "code_hash", self.code_hash)?;
+        untrusted_fold_state_commitment(self.env, code_hash, progress, pairs, last_chunk)
+            .ecall(ECALL)
+    }
+}
+
+/// The sweep that re-encrypts the pairs of a contract that are still under the genesis seed
+#[derive(Debug, Clone, Copy)]
+pub struct StateReencryption<'a> {
+    env: &'a [u8],
+    code_hash: &'a [u8],
+}
+
+impl<'a> StateReencryption<'a> {
+    pub fn new(env: &'a [u8], code_hash: &'a [u8]) -> Self {
+        Self { env, code_hash }
+    }
+
+    /// Re-encrypts a chunk of the contract's pairs, in the order of their keys. `progress` is
+    /// empty for the first chunk, and the progress of the previous chunk otherwise.
+    pub fn reencrypt(
+        self,
+        progress: &[u8],
+        pairs: &[u8],
+        last_chunk: bool,
+    ) -> EnclaveApiResult<Vec<u8>> {
+        const ECALL: &str = "ecall_reencrypt_state";
+
+        let code_hash = fixed_size::<32>(ECALL, "code_hash", self.code_hash)?;
+        untrusted_reencrypt_state(self.env, code_hash, progress, pairs, last_chunk).ecall(ECALL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admin_calls_need_a_code_hash() {
+        let call = AdminCall::new(b"{}", &[0u8; 20]).admin(b"admin", b"proof");
+
+        let err = call.disclose_contract_key(b"{}", &[]).unwrap_err();
+        assert_eq!(err.ecall(), "ecall_disclose_contract_key");
+        assert!(err
+            .to_string()
+            .contains("code_hash must be 32 bytes, got 20"));
+
+        let err = call.export_state(&[], &[]).unwrap_err();
+        assert_eq!(err.ecall(), "ecall_export_state");
+
+        let err = StateCommitment::new(b"{}", &[]).leaves(&[]).unwrap_err();
+        assert_eq!(err.ecall(), "ecall_state_commitment_leaves");
+
+        let err = StateReencryption::new(b"{}", &[])
+            .reencrypt(&[], &[], true)
+            .unwrap_err();
+        assert_eq!(err.ecall(), "ecall_reencrypt_state");
+    }
+}
@@ -0,0 +1,233 @@
+//! Ecalls the node makes for every block, and the keys that come with them
+
+use cosmwasm_sgx_vm::{
+    untrusted_cancel_query, untrusted_consensus_key_init, untrusted_consensus_sign,
+    untrusted_decrypt_encrypted_tx, untrusted_get_mempool_key, untrusted_get_seed_heartbeat_key,
+    untrusted_open_query_session, untrusted_seed_heartbeat, untrusted_submit_block_signatures,
+    untrusted_submit_validator_set_evidence,
+};
+
+use crate::error::{fixed_size, EcallResult, EnclaveApiError, EnclaveApiResult};
+
+/// Submits a block to the light client of the enclave, with the txs its messages are verified
+/// against
+#[derive(Debug, Clone, Copy)]
+pub struct BlockSignatures<'a> {
+    header: &'a [u8],
+    commit: &'a [u8],
+    txs: &'a [u8],
+    encrypted_random: &'a [u8],
+}
+
+/// What the enclave returns for a block it verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockSignaturesOutput {
+    /// The random of the block, decrypted
+    pub decrypted_random: [u8; 32],
+    /// The hash that commits to the validator set of the next block
+    pub next_validator_set_evidence: [u8; 32],
+}
+
+impl<'a> BlockSignatures<'a> {
+    const ECALL: &'static str = "ecall_submit_block_signatures";
+
+    pub fn new(header: &'a [u8], commit: &'a [u8]) -> Self {
+        Self {
+            header,
+            commit,
+            txs: &[],
+            encrypted_random: &[],
+        }
+    }
+
+    /// The txs of the block, encoded the way `submit_block_signatures` expects them
+    pub fn txs(mut self, txs: &'a [u8]) -> Self {
+        self.txs = txs;
+        self
+    }
+
+    /// The encrypted random the block's proposer included
+    pub fn encrypted_random(mut self, encrypted_random: &'a [u8]) -> Self {
+        self.encrypted_random = encrypted_random;
+        self
+    }
+
+    pub fn submit(self) -> EnclaveApiResult<BlockSignaturesOutput> {
+        if self.header.is_empty() || self.commit.is_empty() {
+            return Err(EnclaveApiError::invalid_input(
+                Self::ECALL,
+                "the header and the commit are required",
+            ));
+        }
+
+        let (decrypted_random, next_validator_set_evidence) = untrusted_submit_block_signatures(
+            self.header,
+            self.commit,
+            self.txs,
+            self.encrypted_random,
+        )
+        .ecall(Self::ECALL)?;
+
+        Ok(BlockSignaturesOutput {
+            decrypted_random,
+            next_validator_set_evidence,
+        })
+    }
+}
+
+/// Passes the evidence of the validator set the next block is verified with
+pub fn submit_validator_set_evidence(evidence: &[u8]) -> EnclaveApiResult<()> {
+    const ECALL: &str = "ecall_submit_validator_set_evidence";
+
+    let evidence = fixed_size::<32>(ECALL, "evidence", evidence)?;
+    untrusted_submit_validator_set_evidence(*evidence).ecall(ECALL)
+}
+
+/// Loads the consensus key held by the enclave, generating it on first use, and returns its
+/// Ed25519 public key
+pub fn consensus_key_init() -> EnclaveApiResult<[u8; 32]> {
+    untrusted_consensus_key_init().ecall("ecall_consensus_key_init")
+}
+
+/// Answers a protobuf encoded `privval.Message` with the enclave's consensus key
+pub fn consensus_sign(request: &[u8]) -> EnclaveApiResult<Vec<u8>> {
+    untrusted_consensus_sign(request).ecall("ecall_consensus_sign")
+}
+
+/// The public key seed heartbeats are verified against
+pub fn seed_heartbeat_key() -> EnclaveApiResult<[u8; 32]> {
+    untrusted_get_seed_heartbeat_key().ecall("ecall_get_seed_heartbeat_key")
+}
+
+/// A seed heartbeat, signed by the enclave
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedHeartbeat {
+    /// The registration key of the node
+    pub node_public_key: [u8; 32],
+    pub signature: [u8; 64],
+}
+
+pub fn seed_heartbeat(height: u64) -> EnclaveApiResult<SeedHeartbeat> {
+    let (node_public_key, signature) =
+        untrusted_seed_heartbeat(height).ecall("ecall_seed_heartbeat")?;
+    Ok(SeedHeartbeat {
+        node_public_key,
+        signature,
+    })
+}
+
+/// The public key users encrypt the txs they want included at `height` to
+pub fn mempool_key(height: u64) -> EnclaveApiResult<[u8; 32]> {
+    untrusted_get_mempool_key(height).ecall("ecall_get_mempool_key")
+}
+
+/// Decrypts the tx of a `MsgExecuteEncryptedTx` that was stored in the previous block.
+/// `msg_proof` proves the msg against the app hash of the current block.
+pub fn decrypt_encrypted_tx(msg: &[u8], msg_proof: &[u8]) -> EnclaveApiResult<Vec<u8>> {
+    untrusted_decrypt_encrypted_tx(msg, msg_proof).ecall("ecall_decrypt_encrypted_tx")
+}
+
+/// Opens a query session, that queries of the user can reference until `expiry_height`
+#[derive(Debug, Clone, Copy)]
+pub struct QuerySession<'a> {
+    nonce: &'a [u8],
+    user_public_key: &'a [u8],
+    current_height: u64,
+    expiry_height: u64,
+}
+
+impl<'a> QuerySession<'a> {
+    const ECALL: &'static str = "ecall_open_query_session";
+
+    pub fn new(nonce: &'a [u8], user_public_key: &'a [u8]) -> Self {
+        Self {
+            nonce,
+            user_public_key,
+            current_height: 0,
+            expiry_height: 0,
+        }
+    }
+
+    pub fn heights(mut self, current_height: u64, expiry_height: u64) -> Self {
+        self.current_height = current_height;
+        self.expiry_height = expiry_height;
+        self
+    }
+
+    /// Returns the id of the session
+    pub fn open(self) -> EnclaveApiResult<[u8; 32]> {
+        let nonce = fixed_size::<32>(Self::ECALL, "nonce", self.nonce)?;
+        let user_public_key =
+            fixed_size::<32>(Self::ECALL, "user_public_key", self.user_public_key)?;
+        if self.expiry_height <= self.current_height {
+            return Err(EnclaveApiError::invalid_input(
+                Self::ECALL,
+                format!(
+                    "the session expires at {}, which isn't after {}",
+                    self.expiry_height, self.current_height
+                ),
+            ));
+        }
+
+        untrusted_open_query_session(
+            nonce,
+            user_public_key,
+            self.current_height,
+            self.expiry_height,
+        )
+        .ecall(Self::ECALL)
+    }
+}
+
+/// Opens a query session, see `QuerySession`
+pub fn open_query_session(
+    nonce: &[u8],
+    user_public_key: &[u8],
+    current_height: u64,
+    expiry_height: u64,
+) -> EnclaveApiResult<[u8; 32]> {
+    QuerySession::new(nonce, user_public_key)
+        .heights(current_height, expiry_height)
+        .open()
+}
+
+/// Cancels the query from outside the chain that was given `cancellation_id` in its env. Doesn't
+/// wait for a free thread of the enclave, since the queries to cancel may hold all of them.
+pub fn cancel_query(cancellation_id: u64) -> EnclaveApiResult<()> {
+    untrusted_cancel_query(cancellation_id).ecall("ecall_cancel_query")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_signatures_need_a_header_and_a_commit() {
+        let err = BlockSignatures::new(&[], &[1]).submit().unwrap_err();
+        assert_eq!(err.ecall(), "ecall_submit_block_signatures");
+
+        let err = BlockSignatures::new(&[1], &[])
+            .txs(&[1])
+            .submit()
+            .unwrap_err();
+        assert_eq!(err.status(), None);
+    }
+
+    #[test]
+    fn query_sessions_check_their_inputs() {
+        let err = open_query_session(&[0u8; 31], &[0u8; 32], 10, 20).unwrap_err();
+        assert!(err.to_string().contains("nonce must be 32 bytes"));
+
+        let err = open_query_session(&[0u8; 32], &[0u8; 33], 10, 20).unwrap_err();
+        assert!(err.to_string().contains("user_public_key must be 32 bytes"));
+
+        let err = open_query_session(&[0u8; 32], &[0u8; 32], 10, 10).unwrap_err();
+        assert!(err.to_string().contains("isn't after 10"));
+    }
+
+    #[test]
+    fn validator_set_evidence_is_a_hash() {
+        let err = submit_validator_set_evidence(&[0u8; 16]).unwrap_err();
+        assert_eq!(err.ecall(), "ecall_submit_validator_set_evidence");
+    }
+}
@@ -0,0 +1,88 @@
+//! Ecalls that report on the enclave, or help it ahead of time
+
+use cosmwasm_sgx_vm::{
+    untrusted_get_crash_breadcrumbs, untrusted_get_ocall_stats, untrusted_module_cache_snapshot,
+    untrusted_prewarm_module, untrusted_self_test, untrusted_take_audit_transcript,
+    untrusted_take_conformance_trace, untrusted_take_engine_divergences, untrusted_validate_code,
+};
+
+use crate::error::{EcallResult, EnclaveApiResult};
+
+/// The outcome of the known-answer tests of the enclave's crypto
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// The names of the tests that failed
+    pub failed: Vec<String>,
+}
+
+impl SelfTestReport {
+    /// The enclave refuses consensus ecalls until every test passed
+    pub fn passed(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+pub fn self_test() -> EnclaveApiResult<SelfTestReport> {
+    let failed = untrusted_self_test().ecall("ecall_self_test")?;
+    Ok(SelfTestReport { failed })
+}
+
+/// How often the enclave made each ocall since it started, as json
+pub fn ocall_stats() -> EnclaveApiResult<Vec<u8>> {
+    untrusted_get_ocall_stats().ecall("ecall_get_ocall_stats")
+}
+
+/// The breadcrumbs of the last panics and fatal errors of the enclave, oldest first, as json.
+/// They're sealed, so they survive the crash they describe.
+pub fn crash_breadcrumbs() -> EnclaveApiResult<Vec<u8>> {
+    untrusted_get_crash_breadcrumbs().ecall("ecall_get_crash_breadcrumbs")
+}
+
+/// The transcript of the executions since the last call, as json, in `audit` builds
+pub fn take_audit_transcript() -> EnclaveApiResult<Vec<u8>> {
+    untrusted_take_audit_transcript().ecall("ecall_take_audit_transcript")
+}
+
+/// The storage accesses recorded since the last call, as json, in `conformance` builds
+pub fn take_conformance_trace() -> EnclaveApiResult<Vec<u8>> {
+    untrusted_take_conformance_trace().ecall("ecall_take_conformance_trace")
+}
+
+/// The engine divergences reported since the last call, as a json array. They're reported
+/// through an ocall, so taking them doesn't enter the enclave.
+pub fn take_engine_divergences() -> Vec<u8> {
+    untrusted_take_engine_divergences()
+}
+
+/// Checks a contract against the enclave's limits on code size and complexity, and returns the
+/// validation report, as json
+pub fn validate_code(code: &[u8]) -> EnclaveApiResult<Vec<u8>> {
+    untrusted_validate_code(code).ecall("ecall_validate_code")
+}
+
+/// The modules the enclave has cached, with the hashes of their artifacts, signed by the enclave,
+/// as json. Nodes with the same modules have the same `digest`.
+pub fn module_cache_snapshot() -> EnclaveApiResult<Vec<u8>> {
+    untrusted_module_cache_snapshot().ecall("ecall_module_cache_snapshot")
+}
+
+/// Hints the enclave that a contract is likely to be executed in the next block. Only an
+/// optimization, the enclave may ignore it.
+pub fn prewarm_module(code: &[u8]) -> EnclaveApiResult<()> {
+    untrusted_prewarm_module(code).ecall("ecall_prewarm_module")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_test_report() {
+        assert!(SelfTestReport::default().passed());
+
+        let report = SelfTestReport {
+            failed: vec!["hkdf".to_string()],
+        };
+        assert!(!report.passed());
+    }
+}
@@ -0,0 +1,146 @@
+use enclave_ffi_types::{NodeAuthResult, SeedBlobStatus};
+use sgx_types::sgx_status_t;
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum EnclaveApiError {
+    /// The ecall failed in the SGX SDK, or the enclave returned an error status
+    #[snafu(display("{} failed: {}", ecall, status))]
+    Sgx {
+        ecall: &'static str,
+        status: sgx_status_t,
+    },
+    /// The enclave refused to authenticate a registering node
+    #[snafu(display("{} failed: {}", ecall, result))]
+    NodeAuth {
+        ecall: &'static str,
+        result: NodeAuthResult,
+    },
+    /// The enclave couldn't load a node's encrypted seed
+    #[snafu(display("{} failed: {}", ecall, seed_status))]
+    SeedBlob {
+        ecall: &'static str,
+        seed_status: SeedBlobStatus,
+    },
+    /// The input was rejected before the ecall was made
+    #[snafu(display("invalid input to {}: {}", ecall, msg))]
+    InvalidInput { ecall: &'static str, msg: String },
+}
+
+impl EnclaveApiError {
+    pub(crate) fn sgx(ecall: &'static str, status: sgx_status_t) -> Self {
+        Sgx { ecall, status }.build()
+    }
+
+    pub(crate) fn node_auth(ecall: &'static str, result: NodeAuthResult) -> Self {
+        NodeAuth { ecall, result }.build()
+    }
+
+    pub(crate) fn seed_blob(ecall: &'static str, seed_status: SeedBlobStatus) -> Self {
+        SeedBlob { ecall, seed_status }.build()
+    }
+
+    pub(crate) fn invalid_input<S: Into<String>>(ecall: &'static str, msg: S) -> Self {
+        InvalidInput {
+            ecall,
+            msg: msg.into(),
+        }
+        .build()
+    }
+
+    /// The name of the ecall that failed
+    pub fn ecall(&self) -> &'static str {
+        match self {
+            EnclaveApiError::Sgx { ecall, .. }
+            | EnclaveApiError::NodeAuth { ecall, .. }
+            | EnclaveApiError::SeedBlob { ecall, .. }
+            | EnclaveApiError::InvalidInput { ecall, .. } => ecall,
+        }
+    }
+
+    /// The SGX status the ecall failed with, if it got as far as the enclave
+    pub fn status(&self) -> Option<sgx_status_t> {
+        match self {
+            EnclaveApiError::Sgx { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+pub type EnclaveApiResult<T> = core::result::Result<T, EnclaveApiError>;
+
+/// Names the ecall an `SgxResult` came from
+pub(crate) trait EcallResult<T> {
+    fn ecall(self, ecall: &'static str) -> EnclaveApiResult<T>;
+}
+
+impl<T> EcallResult<T> for Result<T, sgx_status_t> {
+    fn ecall(self, ecall: &'static str) -> EnclaveApiResult<T> {
+        self.map_err(|status| EnclaveApiError::sgx(ecall, status))
+    }
+}
+
+/// Checks that `input` is exactly `N` bytes long
+pub(crate) fn fixed_size<'a, const N: usize>(
+    ecall: &'static str,
+    name: &str,
+    input: &'a [u8],
+) -> EnclaveApiResult<&'a [u8; N]> {
+    input.try_into().map_err(|_| {
+        EnclaveApiError::invalid_input(
+            ecall,
+            format!("{} must be {} bytes, got {}", name, N, input.len()),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_name_the_ecall() {
+        let err = Err::<(), _>(sgx_status_t::SGX_ERROR_BUSY)
+            .ecall("ecall_key_gen")
+            .unwrap_err();
+        assert_eq!(err.ecall(), "ecall_key_gen");
+        assert_eq!(err.status(), Some(sgx_status_t::SGX_ERROR_BUSY));
+        assert!(err.to_string().starts_with("ecall_key_gen failed: "));
+
+        let err = EnclaveApiError::invalid_input("ecall_init_node", "master_key is empty");
+        assert_eq!(err.ecall(), "ecall_init_node");
+        assert_eq!(err.status(), None);
+        assert_eq!(
+            err.to_string(),
+            "invalid input to ecall_init_node: master_key is empty"
+        );
+    }
+
+    #[test]
+    fn seed_blob_errors_say_what_was_wrong() {
+        let err =
+            EnclaveApiError::seed_blob("ecall_init_node", SeedBlobStatus::UnexpectedCurrentSeed);
+        assert_eq!(err.ecall(), "ecall_init_node");
+        assert_eq!(err.status(), None);
+        assert_eq!(
+            err.to_string(),
+            "ecall_init_node failed: corrupt encrypted seed: its length byte says genesis only, but \
+             a current seed follows"
+        );
+    }
+
+    #[test]
+    fn fixed_size_checks_the_length() {
+        assert_eq!(
+            fixed_size::<4>("ecall", "input", &[1, 2, 3, 4]).unwrap(),
+            &[1, 2, 3, 4]
+        );
+
+        let err = fixed_size::<4>("ecall", "input", &[1, 2, 3]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid input to ecall: input must be 4 bytes, got 3"
+        );
+    }
+}
@@ -0,0 +1,43 @@
+//! The state of governance and of other chains that the enclave reads, proven against the app
+//! hash of the current block
+
+use cosmwasm_sgx_vm::{
+    untrusted_create_foreign_client, untrusted_submit_compute_params,
+    untrusted_submit_foreign_client, untrusted_update_foreign_client,
+    untrusted_validate_enclave_params,
+};
+
+use crate::error::{EcallResult, EnclaveApiResult};
+
+/// Passes the params of x/compute, with their proof, for the enclave to read in the current
+/// block. Both are empty when the node can't prove them, and the enclave reads strict values.
+pub fn submit_compute_params(params: &[u8], proof: &[u8]) -> EnclaveApiResult<()> {
+    untrusted_submit_compute_params(params, proof).ecall("ecall_submit_compute_params")
+}
+
+/// Checks the enclave params of a `MsgUpdateParams`, before governance stores them
+pub fn validate_enclave_params(params: &[u8]) -> EnclaveApiResult<()> {
+    untrusted_validate_enclave_params(params).ecall("ecall_validate_enclave_params")
+}
+
+/// Creates the light client of another chain from a `MsgCreateForeignClient` that governance
+/// passed, and returns the client for x/compute to store
+pub fn create_foreign_client(msg: &[u8], msg_proof: &[u8]) -> EnclaveApiResult<Vec<u8>> {
+    untrusted_create_foreign_client(msg, msg_proof).ecall("ecall_create_foreign_client")
+}
+
+/// Updates a stored light client of another chain from a `MsgUpdateForeignClient`, and returns
+/// the updated client
+pub fn update_foreign_client(
+    client: &[u8],
+    client_proof: &[u8],
+    msg: &[u8],
+) -> EnclaveApiResult<Vec<u8>> {
+    untrusted_update_foreign_client(client, client_proof, msg).ecall("ecall_update_foreign_client")
+}
+
+/// Passes a stored light client of another chain, with its proof, for contracts to verify the
+/// state of that chain against in the current block
+pub fn submit_foreign_client(chain_id: &[u8], client: &[u8], proof: &[u8]) -> EnclaveApiResult<()> {
+    untrusted_submit_foreign_client(chain_id, client, proof).ecall("ecall_submit_foreign_client")
+}
@@ -0,0 +1,40 @@
+//! Typed wrappers over the ecalls of the enclave, for the untrusted side.
+//!
+//! `cosmwasm-sgx-vm` declares the ecalls and owns the enclave. This crate puts one interface in
+//! front of the ecalls that aren't contract calls: inputs are checked before they cross into the
+//! enclave, ecalls that take many inputs are built step by step, outputs are owned, and every
+//! error says which ecall failed and why. Contract calls go through `CosmCache` and `Instance`.
+
+mod admin;
+mod consensus;
+mod diagnostics;
+mod error;
+mod governance;
+mod node;
+
+pub use admin::{AdminCall, StateCommitment, StateReencryption};
+pub use consensus::{
+    cancel_query, consensus_key_init, consensus_sign, decrypt_encrypted_tx, mempool_key,
+    open_query_session, seed_heartbeat, seed_heartbeat_key, submit_validator_set_evidence,
+    BlockSignatures, BlockSignaturesOutput, QuerySession, SeedHeartbeat,
+};
+pub use diagnostics::{
+    crash_breadcrumbs, module_cache_snapshot, ocall_stats, prewarm_module, self_test,
+    take_audit_transcript, take_conformance_trace, take_engine_divergences, validate_code,
+    SelfTestReport,
+};
+pub use error::{EnclaveApiError, EnclaveApiResult};
+pub use governance::{
+    create_foreign_client, submit_compute_params, submit_foreign_client, update_foreign_client,
+    validate_enclave_params,
+};
+pub use node::{
+    approve_upgrade, attest_code_hash, authenticate_new_node, bind_operator, configure,
+    create_attestation_report, create_registration_package, encrypted_genesis_seed, health_check,
+    key_gen, migration_op, platform_census, prove_key_possession, rotate_node_keys,
+    verify_registration_package, Bootstrap, EncryptedSeed, InitNode, MigrationOp,
+};
+
+pub use cosmwasm_sgx_vm::EnclaveRuntimeConfig;
+/// The enclave splits the cert `authenticate_new_node` takes with the same parser
+pub use enclave_wire::{CombinedCert, CombinedCertBuilder, KeyScheme};
@@ -0,0 +1,431 @@
+//! Registration and upkeep of the node's enclave
+
+use cosmwasm_sgx_vm::{
+    configure_enclave, create_attestation_report_u, untrusted_approve_upgrade,
+    untrusted_attest_code_hash, untrusted_bind_operator, untrusted_create_registration_package,
+    untrusted_get_encrypted_genesis_seed, untrusted_get_encrypted_seed, untrusted_health_check,
+    untrusted_init_bootstrap, untrusted_init_node, untrusted_key_gen, untrusted_migration_op,
+    untrusted_platform_census, untrusted_prove_key_possession, untrusted_rotate_node_keys,
+    untrusted_verify_registration_package, EnclaveRuntimeConfig,
+};
+use enclave_ffi_types::{
+    HealthCheckResult, SeedBlobStatus, AUTH_FLAG_CURRENT_SEED_ONLY, AUTH_FLAG_EPID_DEPRECATED,
+    INPUT_ENCRYPTED_SEED_SIZE, PUBLIC_KEY_SIZE,
+};
+use enclave_wire::KeyScheme;
+
+use crate::error::{fixed_size, EcallResult, EnclaveApiError, EnclaveApiResult};
+
+/// Passes the runtime limits to the enclave. Only the first configuration is applied.
+pub fn configure(config: EnclaveRuntimeConfig) -> EnclaveApiResult<()> {
+    configure_enclave(config).ecall("ecall_configure_runtime")
+}
+
+pub fn health_check() -> EnclaveApiResult<HealthCheckResult> {
+    untrusted_health_check().ecall("ecall_health_check")
+}
+
+/// Creates the consensus seeds of a new network, and returns its master public key
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Bootstrap<'a> {
+    spid: &'a [u8],
+    api_key: &'a [u8],
+    chain_id: &'a str,
+}
+
+impl<'a> Bootstrap<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The SPID of EPID attestations
+    pub fn spid(mut self, spid: &'a [u8]) -> Self {
+        self.spid = spid;
+        self
+    }
+
+    /// The API key of the attestation service, and of the seed service
+    pub fn api_key(mut self, api_key: &'a [u8]) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// The chain id of the network, which its consensus seeds are bound to
+    pub fn chain_id(mut self, chain_id: &'a str) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    pub fn run(self) -> EnclaveApiResult<[u8; 32]> {
+        const ECALL: &str = "ecall_init_bootstrap";
+
+        if self.chain_id.is_empty() {
+            return Err(EnclaveApiError::invalid_input(ECALL, "chain_id is empty"));
+        }
+        untrusted_init_bootstrap(self.spid, self.api_key, self.chain_id).ecall(ECALL)
+    }
+}
+
+/// Starts a registered node with the seeds the network encrypted to it
+#[derive(Debug, Clone, Copy)]
+pub struct InitNode<'a> {
+    master_key: &'a [u8],
+    encrypted_seed: &'a [u8],
+    api_key: &'a [u8],
+    chain_id: &'a str,
+}
+
+impl<'a> InitNode<'a> {
+    const ECALL: &'static str = "ecall_init_node";
+
+    /// `master_key` is the public key the seeds were encrypted with, and `encrypted_seed` the
+    /// seeds as the node's registration returned them
+    pub fn new(master_key: &'a [u8], encrypted_seed: &'a [u8]) -> Self {
+        Self {
+            master_key,
+            encrypted_seed,
+            api_key: &[],
+            chain_id: "",
+        }
+    }
+
+    /// The API key of the seed service, for seeds that aren't part of the registration
+    pub fn api_key(mut self, api_key: &'a [u8]) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// The chain id of the network, to find whether its consensus seeds are bound to it
+    pub fn chain_id(mut self, chain_id: &'a str) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// Returns whether the encrypted seed had only the genesis seed or both seeds. An encrypted
+    /// seed the enclave rejects fails with `EnclaveApiError::SeedBlob`, which says why.
+    pub fn run(self) -> EnclaveApiResult<SeedBlobStatus> {
+        fixed_size::<PUBLIC_KEY_SIZE>(Self::ECALL, "master_key", self.master_key)?;
+        if self.encrypted_seed.len() != INPUT_ENCRYPTED_SEED_SIZE as usize {
+            return Err(EnclaveApiError::invalid_input(
+                Self::ECALL,
+                format!(
+                    "encrypted_seed must be {} bytes, got {}",
+                    INPUT_ENCRYPTED_SEED_SIZE,
+                    self.encrypted_seed.len()
+                ),
+            ));
+        }
+
+        if self.chain_id.is_empty() {
+            return Err(EnclaveApiError::invalid_input(
+                Self::ECALL,
+                "chain_id is empty",
+            ));
+        }
+
+        untrusted_init_node(
+            self.master_key,
+            self.encrypted_seed,
+            self.api_key,
+            self.chain_id,
+        )
+        .ecall(Self::ECALL)?
+        .map_err(|status| EnclaveApiError::seed_blob(Self::ECALL, status))
+    }
+}
+
+/// The seeds of a node that registered, encrypted to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedSeed {
+    pub seed: Vec<u8>,
+    /// When the earliest of the node's attestation collateral expires, in unix seconds, or 0 if
+    /// unknown
+    pub collateral_expiration: i64,
+    /// The `AUTH_FLAG_*` flags the enclave set on the registration
+    pub auth_flags: u32,
+}
+
+impl EncryptedSeed {
+    /// The node registered with an EPID attestation, which governance scheduled to retire
+    pub fn epid_deprecated(&self) -> bool {
+        self.auth_flags & AUTH_FLAG_EPID_DEPRECATED != 0
+    }
+
+    /// The network no longer issues the genesis seed, so `seed` only holds the current one
+    pub fn current_seed_only(&self) -> bool {
+        self.auth_flags & AUTH_FLAG_CURRENT_SEED_ONLY != 0
+    }
+}
+
+/// Authenticates a registering node by its attestation certificate. `issued_height` is the height
+/// the chain first issued seeds to the node's key at, or 0 if it never did.
+pub fn authenticate_new_node(cert: &[u8], issued_height: u64) -> EnclaveApiResult<EncryptedSeed> {
+    const ECALL: &str = "ecall_authenticate_new_node";
+
+    match untrusted_get_encrypted_seed(cert, issued_height).ecall(ECALL)? {
+        Ok((seed, collateral_expiration, auth_flags)) => Ok(EncryptedSeed {
+            seed,
+            collateral_expiration,
+            auth_flags,
+        }),
+        Err(result) => Err(EnclaveApiError::node_auth(ECALL, result)),
+    }
+}
+
+/// How many of the platforms that registered through this node's enclave are at each TCB status,
+/// TCB date and advisory, signed by the enclave, as json
+pub fn platform_census() -> EnclaveApiResult<Vec<u8>> {
+    untrusted_platform_census().ecall("ecall_platform_census")
+}
+
+/// A DCAP quote that attests the code with `code_hash` runs under this node's enclave on
+/// `chain_id` at `height`, with the statement it attests, as json
+pub fn attest_code_hash(
+    code_hash: &[u8; 32],
+    chain_id: &str,
+    height: u64,
+) -> EnclaveApiResult<Vec<u8>> {
+    untrusted_attest_code_hash(code_hash, chain_id, height).ecall("ecall_attest_code_hash")
+}
+
+/// A registration package of the node's registration key, attested in `scheme`: the combined cert,
+/// the key and what the enclave read from its report, as json
+pub fn create_registration_package(scheme: KeyScheme) -> EnclaveApiResult<Vec<u8>> {
+    untrusted_create_registration_package(scheme.id()).ecall("ecall_create_registration_package")
+}
+
+/// Verifies a registration package the way a registration of its cert would be, without
+/// registering it, and returns the time at which the earliest of its collateral expires
+pub fn verify_registration_package(package: &[u8]) -> EnclaveApiResult<i64> {
+    const ECALL: &str = "ecall_verify_registration_package";
+
+    match untrusted_verify_registration_package(package).ecall(ECALL)? {
+        Ok(collateral_expiration) => Ok(collateral_expiration),
+        Err(result) => Err(EnclaveApiError::node_auth(ECALL, result)),
+    }
+}
+
+/// The genesis seed, encrypted to the node with `public_key`, for nodes that sync from genesis
+pub fn encrypted_genesis_seed(public_key: &[u8]) -> EnclaveApiResult<Vec<u8>> {
+    const ECALL: &str = "ecall_get_genesis_seed";
+
+    fixed_size::<PUBLIC_KEY_SIZE>(ECALL, "public_key", public_key)?;
+    untrusted_get_encrypted_genesis_seed(public_key).ecall(ECALL)
+}
+
+/// Creates the registration key of the node, or returns the one it has
+pub fn key_gen() -> EnclaveApiResult<[u8; 32]> {
+    untrusted_key_gen().ecall("ecall_key_gen")
+}
+
+/// Replaces the registration key of the node, and returns the new one
+pub fn rotate_node_keys() -> EnclaveApiResult<[u8; 32]> {
+    untrusted_rotate_node_keys().ecall("ecall_rotate_node_keys")
+}
+
+/// Creates the attestation report of the node's registration key. `flags` picks the kind of
+/// attestation.
+pub fn create_attestation_report(api_key: &[u8], flags: u32) -> EnclaveApiResult<()> {
+    create_attestation_report_u(api_key, flags).ecall("ecall_get_attestation_report")
+}
+
+/// Appends a proof that the node holds its registration key to the attestation certificate
+/// `create_attestation_report` wrote. `master_key` is the node exchange key of the network, and
+/// the proof is made for the block with the protobuf encoded `header` and `commit`.
+pub fn prove_key_possession(
+    master_key: &[u8],
+    header: &[u8],
+    commit: &[u8],
+) -> EnclaveApiResult<()> {
+    const ECALL: &str = "ecall_prove_key_possession";
+
+    let master_key = fixed_size::<PUBLIC_KEY_SIZE>(ECALL, "master_key", master_key)?;
+    if header.is_empty() || commit.is_empty() {
+        return Err(EnclaveApiError::invalid_input(
+            ECALL,
+            "the proof needs the header and commit of a block",
+        ));
+    }
+    untrusted_prove_key_possession(master_key, header, commit).ecall(ECALL)
+}
+
+/// Appends the binding of the node to the validator with `consensus_key` to the attestation
+/// certificate `create_attestation_report` wrote. `signature` is the validator's `consensus_key`
+/// signing the node's registration key on `chain_id`.
+pub fn bind_operator(
+    consensus_key: &[u8],
+    signature: &[u8],
+    chain_id: &str,
+) -> EnclaveApiResult<()> {
+    const ECALL: &str = "ecall_bind_operator";
+
+    let consensus_key = fixed_size::<32>(ECALL, "consensus_key", consensus_key)?;
+    let signature = fixed_size::<64>(ECALL, "signature", signature)?;
+    untrusted_bind_operator(consensus_key, signature, chain_id).ecall(ECALL)
+}
+
+/// Approves the upgrade to the enclave in a `MsgUpgradeProposalPassed` of the current block
+pub fn approve_upgrade(msg: &[u8]) -> EnclaveApiResult<()> {
+    untrusted_approve_upgrade(msg).ecall("ecall_onchain_approve_upgrade")
+}
+
+/// The steps of moving the sealed data of an enclave, see `ecall_migration_op`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationOp {
+    ConvertLegacyFiles,
+    CreateSelfMigrationReport,
+    ExportSealedData,
+    ImportSealedData,
+    ImportLegacySealing,
+    ExportSelfTargetInfo,
+    BackUpRegistrationKey,
+    ReleaseRegistrationBackup,
+    RestoreRegistrationKey,
+    CommitSeedShare,
+    RevealSeedShare,
+    CombineSeedShares,
+}
+
+impl MigrationOp {
+    const ALL: [MigrationOp; 12] = [
+        MigrationOp::ConvertLegacyFiles,
+        MigrationOp::CreateSelfMigrationReport,
+        MigrationOp::ExportSealedData,
+        MigrationOp::ImportSealedData,
+        MigrationOp::ImportLegacySealing,
+        MigrationOp::ExportSelfTargetInfo,
+        MigrationOp::BackUpRegistrationKey,
+        MigrationOp::ReleaseRegistrationBackup,
+        MigrationOp::RestoreRegistrationKey,
+        MigrationOp::CommitSeedShare,
+        MigrationOp::RevealSeedShare,
+        MigrationOp::CombineSeedShares,
+    ];
+
+    /// The opcode the enclave knows the step by
+    pub fn opcode(self) -> u32 {
+        self as u32
+    }
+
+    pub fn from_opcode(opcode: u32) -> Option<Self> {
+        Self::ALL.get(opcode as usize).copied()
+    }
+}
+
+pub fn migration_op(op: MigrationOp) -> EnclaveApiResult<()> {
+    untrusted_migration_op(op.opcode()).ecall("ecall_migration_op")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migration_opcodes_match_the_enclave() {
+        assert_eq!(MigrationOp::ConvertLegacyFiles.opcode(), 0);
+        assert_eq!(MigrationOp::ExportSelfTargetInfo.opcode(), 5);
+        assert_eq!(MigrationOp::CombineSeedShares.opcode(), 11);
+
+        for (opcode, op) in MigrationOp::ALL.iter().enumerate() {
+            assert_eq!(op.opcode(), opcode as u32);
+            assert_eq!(MigrationOp::from_opcode(opcode as u32), Some(*op));
+        }
+        assert_eq!(MigrationOp::from_opcode(12), None);
+    }
+
+    #[test]
+    fn init_node_checks_its_inputs() {
+        let seed = [0u8; INPUT_ENCRYPTED_SEED_SIZE as usize];
+
+        let err = InitNode::new(&[0u8; 31], &seed).run().unwrap_err();
+        assert_eq!(err.ecall(), "ecall_init_node");
+        assert!(err.to_string().contains("master_key must be 32 bytes"));
+
+        let err = InitNode::new(&[0u8; 32], &seed[1..]).run().unwrap_err();
+        assert!(err.to_string().contains("encrypted_seed must be 97 bytes"));
+
+        let err = InitNode::new(&[0u8; 32], &seed).run().unwrap_err();
+        assert!(err.to_string().contains("chain_id is empty"));
+    }
+
+    #[test]
+    fn bootstrap_needs_a_chain_id() {
+        let err = Bootstrap::new().run().unwrap_err();
+        assert_eq!(err.ecall(), "ecall_init_bootstrap");
+        assert!(err.to_string().contains("chain_id is empty"));
+    }
+
+    #[test]
+    fn genesis_seed_needs_a_public_key() {
+        let err = encrypted_genesis_seed(&[]).unwrap_err();
+        assert_eq!(err.ecall(), "ecall_get_genesis_seed");
+        assert_eq!(err.status(), None);
+    }
+
+    #[test]
+    fn key_possession_needs_a_master_key_and_a_block() {
+        let err = prove_key_possession(&[0u8; 16], b"header", b"commit").unwrap_err();
+        assert_eq!(err.ecall(), "ecall_prove_key_possession");
+        assert_eq!(
+            err.to_string(),
+            "invalid input to ecall_prove_key_possession: master_key must be 32 bytes, got 16"
+        );
+
+        let err = prove_key_possession(&[0u8; 32], b"header", b"").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid input to ecall_prove_key_possession: the proof needs the header and commit of a block"
+        );
+    }
+
+    #[test]
+    fn operator_binding_needs_an_ed25519_key_and_signature() {
+        let err = bind_operator(&[0u8; 33], &[0u8; 64], "secret-4").unwrap_err();
+        assert_eq!(err.ecall(), "ecall_bind_operator");
+        assert_eq!(
+            err.to_string(),
+            "invalid input to ecall_bind_operator: consensus_key must be 32 bytes, got 33"
+        );
+
+        let err = bind_operator(&[0u8; 32], &[0u8; 32], "secret-4").unwrap_err();
+        assert!(err.to_string().contains("signature must be 64 bytes"));
+    }
+
+    #[test]
+    fn registration_package_is_limited_before_the_enclave() {
+        use enclave_ffi_types::MAX_REGISTRATION_PACKAGE_LENGTH;
+
+        let package = vec![b' '; MAX_REGISTRATION_PACKAGE_LENGTH + 1];
+        let err = verify_registration_package(&package).unwrap_err();
+        assert_eq!(err.ecall(), "ecall_verify_registration_package");
+        assert_eq!(err.status(), None);
+        assert_eq!(
+            err.to_string(),
+            "ecall_verify_registration_package failed: The provided certificate is larger than the \
+             allowed maximum"
+        );
+    }
+
+    #[test]
+    fn combined_cert_roundtrip() {
+        use crate::{CombinedCert, CombinedCertBuilder};
+
+        let cert = CombinedCertBuilder::new()
+            .epid_cert(b"cert")
+            .dcap(b"quote", b"collateral")
+            .build();
+        // hand-built the way operators used to, little endian lengths before the fields
+        let mut manual = Vec::new();
+        for field in [&b"cert"[..], b"quote", b"collateral"] {
+            manual.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        }
+        manual.extend_from_slice(b"certquotecollateral");
+        assert_eq!(cert, manual);
+
+        let parsed = CombinedCert::parse(&cert).unwrap();
+        assert_eq!(parsed.epid_cert, b"cert");
+        assert_eq!(parsed.dcap_quote, b"quote");
+        assert_eq!(parsed.dcap_collateral, b"collateral");
+        assert!(CombinedCert::parse(&cert[..11]).is_err());
+    }
+}
@@ -7,9 +7,12 @@ use log::*;
 use sgx_types::*;
 use sgx_types::{sgx_status_t, SgxResult};
 
-use enclave_ffi_types::{NodeAuthResult, OUTPUT_ENCRYPTED_SEED_SIZE, SINGLE_ENCRYPTED_SEED_SIZE};
+use enclave_ffi_types::{
+    NodeAuthResult, MAX_CERT_LENGTH, OUTPUT_ENCRYPTED_SEED_SIZE, SINGLE_ENCRYPTED_SEED_SIZE,
+};
 
 use crate::enclave::ENCLAVE_DOORBELL;
+use crate::output_buffer::{call_with_output, call_with_output_as};
 
 extern "C" {
     pub fn ecall_get_attestation_report(
@@ -19,19 +22,43 @@ extern "C" {
         api_key_len: u32,
         flags: u32,
     ) -> sgx_status_t;
+    pub fn ecall_prove_key_possession(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        master_key: &[u8; 32],
+        header: *const u8,
+        header_len: usize,
+        commit: *const u8,
+        commit_len: usize,
+    ) -> sgx_status_t;
+    pub fn ecall_bind_operator(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        consensus_key: &[u8; 32],
+        signature: &[u8; 64],
+        chain_id: *const u8,
+        chain_id_len: usize,
+    ) -> sgx_status_t;
     pub fn ecall_authenticate_new_node(
         eid: sgx_enclave_id_t,
         retval: *mut NodeAuthResult,
         cert: *const u8,
         cert_len: u32,
-        seed: &mut [u8; OUTPUT_ENCRYPTED_SEED_SIZE as usize],
+        issued_height: u64,
+        seed: *mut u8,
+        seed_capacity: u32,
+        seed_len: *mut u32,
+        collateral_expiration: *mut i64,
+        auth_flags: *mut u32,
     ) -> sgx_status_t;
     pub fn ecall_get_genesis_seed(
         eid: sgx_enclave_id_t,
         retval: *mut sgx_status_t,
         pk: *const u8,
         pk_len: u32,
-        seed: &mut [u8; SINGLE_ENCRYPTED_SEED_SIZE as usize],
+        seed: *mut u8,
+        seed_capacity: u32,
+        seed_len: *mut u32,
     ) -> sgx_status_t;
 }
 
@@ -189,76 +216,162 @@ pub fn create_attestation_report_u(api_key: &[u8], flags: u32) -> SgxResult<()>
     Ok(())
 }
 
-pub fn untrusted_get_encrypted_seed(
-    cert: &[u8],
-) -> SgxResult<Result<[u8; OUTPUT_ENCRYPTED_SEED_SIZE as usize], NodeAuthResult>> {
+/// Append a proof that the node holds its registration key to the attestation certificate
+/// `create_attestation_report_u` wrote. `master_key` is the node exchange key of the network, and
+/// the proof is made for the block with the protobuf encoded `header` and `commit`.
+pub fn untrusted_prove_key_possession(
+    master_key: &[u8; 32],
+    header: &[u8],
+    commit: &[u8],
+) -> SgxResult<()> {
     // Bind the token to a local variable to ensure its
     // destructor runs in the end of the function
     let enclave_access_token = ENCLAVE_DOORBELL
         .get_access(1) // This can never be recursive
         .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
     let enclave = (*enclave_access_token)?;
-    let eid = enclave.geteid();
-    let mut retval = NodeAuthResult::Success;
 
-    let mut seed = [0u8; OUTPUT_ENCRYPTED_SEED_SIZE as usize];
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
     let status = unsafe {
-        ecall_authenticate_new_node(
+        ecall_prove_key_possession(
             eid,
             &mut retval,
-            cert.as_ptr(),
-            cert.len() as u32,
-            &mut seed,
+            master_key,
+            header.as_ptr(),
+            header.len(),
+            commit.as_ptr(),
+            commit.len(),
         )
     };
 
     if status != sgx_status_t::SGX_SUCCESS {
-        debug!("Error from authenticate new node");
         return Err(status);
     }
 
-    if retval != NodeAuthResult::Success {
-        debug!("Error from authenticate new node, bad NodeAuthResult");
-        return Ok(Err(retval));
-    }
-
-    debug!("Done auth, got seed: {:?}", seed);
-
-    if seed.is_empty() {
-        error!("Got empty seed from encryption");
-        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
     }
 
-    Ok(Ok(seed))
+    Ok(())
 }
 
-pub fn untrusted_get_encrypted_genesis_seed(
-    pk: &[u8],
-) -> SgxResult<[u8; SINGLE_ENCRYPTED_SEED_SIZE as usize]> {
+/// Append the binding of the node to the validator with `consensus_key` to the attestation
+/// certificate `create_attestation_report_u` wrote. `signature` is the validator's
+/// `consensus_key` signing the node's registration key on `chain_id`.
+pub fn untrusted_bind_operator(
+    consensus_key: &[u8; 32],
+    signature: &[u8; 64],
+    chain_id: &str,
+) -> SgxResult<()> {
     // Bind the token to a local variable to ensure its
     // destructor runs in the end of the function
     let enclave_access_token = ENCLAVE_DOORBELL
         .get_access(1) // This can never be recursive
         .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
     let enclave = (*enclave_access_token)?;
+
     let eid = enclave.geteid();
     let mut retval = sgx_status_t::SGX_SUCCESS;
-
-    let mut seed = [0u8; SINGLE_ENCRYPTED_SEED_SIZE as usize];
     let status = unsafe {
-        ecall_get_genesis_seed(eid, &mut retval, pk.as_ptr(), pk.len() as u32, &mut seed)
+        ecall_bind_operator(
+            eid,
+            &mut retval,
+            consensus_key,
+            signature,
+            chain_id.as_ptr(),
+            chain_id.len(),
+        )
     };
 
     if status != sgx_status_t::SGX_SUCCESS {
-        debug!("Error from get genesis seed");
         return Err(status);
     }
 
     if retval != sgx_status_t::SGX_SUCCESS {
-        debug!("Error from get genesis seed, bad NodeAuthResult");
         return Err(retval);
     }
 
+    Ok(())
+}
+
+/// Authenticate a registering node and return its encrypted seed, along with the time (unix
+/// seconds) at which the earliest of its attestation collateral expires, or 0 if unknown, and the
+/// `AUTH_FLAG_*` flags the enclave set on the registration. `issued_height` is the height the
+/// chain first issued seeds to the node's key at, or 0 if it never did.
+pub fn untrusted_get_encrypted_seed(
+    cert: &[u8],
+    issued_height: u64,
+) -> SgxResult<Result<(Vec<u8>, i64, u32), NodeAuthResult>> {
+    if cert.len() > MAX_CERT_LENGTH {
+        warn!("Rejecting a certificate of {} bytes", cert.len());
+        return Ok(Err(NodeAuthResult::InputTooLarge));
+    }
+
+    let mut collateral_expiration: i64 = 0;
+    let mut auth_flags: u32 = 0;
+    let result = call_with_output_as(
+        OUTPUT_ENCRYPTED_SEED_SIZE as usize,
+        NodeAuthResult::Success,
+        NodeAuthResult::BufferTooSmall,
+        |eid, retval, seed, seed_len| unsafe {
+            ecall_authenticate_new_node(
+                eid,
+                retval,
+                cert.as_ptr(),
+                cert.len() as u32,
+                issued_height,
+                seed.as_mut_ptr(),
+                seed.len() as u32,
+                seed_len,
+                &mut collateral_expiration,
+                &mut auth_flags,
+            )
+        },
+    );
+
+    let seed = match result {
+        Err(status) => {
+            debug!("Error from authenticate new node");
+            return Err(status);
+        }
+        Ok(Err(retval)) => {
+            debug!("Error from authenticate new node, bad NodeAuthResult");
+            return Ok(Err(retval));
+        }
+        Ok(Ok(seed)) => seed,
+    };
+
+    debug!("Done auth, got seed: {:?}", seed);
+
+    if seed.is_empty() {
+        error!("Got empty seed from encryption");
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    Ok(Ok((seed, collateral_expiration, auth_flags)))
+}
+
+pub fn untrusted_get_encrypted_genesis_seed(pk: &[u8]) -> SgxResult<Vec<u8>> {
+    let seed = call_with_output(
+        SINGLE_ENCRYPTED_SEED_SIZE,
+        |eid, retval, seed, seed_len| unsafe {
+            ecall_get_genesis_seed(
+                eid,
+                retval,
+                pk.as_ptr(),
+                pk.len() as u32,
+                seed.as_mut_ptr(),
+                seed.len() as u32,
+                seed_len,
+            )
+        },
+    )
+    .map_err(|status| {
+        debug!("Error from get genesis seed");
+        status
+    })?;
+
     debug!("Done getting genesis seed, got seed: {:?}", seed);
 
     if seed.is_empty() {
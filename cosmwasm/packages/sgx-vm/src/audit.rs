@@ -0,0 +1,34 @@
+use sgx_types::*;
+
+use crate::output_buffer::call_with_output;
+
+/// Enough for a few small txs, larger transcripts take a second ecall
+const INITIAL_TRANSCRIPT_CAPACITY: usize = 64 * 1024;
+
+extern "C" {
+    pub fn ecall_take_audit_transcript(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        transcript: *mut u8,
+        transcript_capacity: u32,
+        transcript_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// Take the transcript of the executions since the last call, as json. Only available in
+/// enclaves built with the `audit` feature.
+pub fn untrusted_take_audit_transcript() -> SgxResult<Vec<u8>> {
+    // The enclave keeps the transcript when it doesn't fit
+    call_with_output(
+        INITIAL_TRANSCRIPT_CAPACITY,
+        |eid, retval, transcript, transcript_len| unsafe {
+            ecall_take_audit_transcript(
+                eid,
+                retval,
+                transcript.as_mut_ptr(),
+                transcript.len() as u32,
+                transcript_len,
+            )
+        },
+    )
+}
@@ -138,6 +138,19 @@ pub fn call_handle_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'sta
     instance.call_handle(env, msg, sig_info, handle_type)
 }
 
+/// Simulates an execute of a tx that isn't part of any block, and returns raw data from
+/// the contract. The writes of the simulation go to the instance's storage, which should be a
+/// branch of the state that is discarded after the simulation.
+pub fn call_simulate_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'static>(
+    instance: &mut Instance<S, A, Q>,
+    env: &[u8],
+    msg: &[u8],
+    sig_info: &[u8],
+) -> VmResult<Vec<u8>> {
+    instance.set_storage_readonly(false);
+    instance.call_simulate(env, msg, sig_info)
+}
+
 /// Calls Wasm export "query" and returns raw data from the contract.
 /// The result is length limited to prevent abuse but otherwise unchecked.
 pub fn call_query_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'static>(
@@ -152,6 +165,18 @@ pub fn call_query_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'stat
     instance.call_query(env, msg)
 }
 
+/// Runs the instance as the handler of an off-chain job, see `Instance::call_run_job`.
+/// The handler runs against the instance's storage, which is never written back.
+pub fn call_run_job_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'static>(
+    instance: &mut Instance<S, A, Q>,
+    contract_code_hash: &[u8; 32],
+    env: &[u8],
+    job: &[u8],
+) -> VmResult<Vec<u8>> {
+    instance.set_storage_readonly(true);
+    instance.call_run_job(contract_code_hash, env, job)
+}
+
 #[cfg(not(feature = "default-enclave"))]
 fn call_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'static>(
     instance: &mut Instance<S, A, Q>,
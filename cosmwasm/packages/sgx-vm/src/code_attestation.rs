@@ -0,0 +1,45 @@
+use sgx_types::*;
+
+use crate::output_buffer::call_with_output;
+
+/// A DCAP quote and the json of the statement around it
+const INITIAL_ATTESTATION_CAPACITY: usize = 8 * 1024;
+
+extern "C" {
+    pub fn ecall_attest_code_hash(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        code_hash: &[u8; 32],
+        chain_id: *const u8,
+        chain_id_len: usize,
+        height: u64,
+        attestation: *mut u8,
+        attestation_capacity: u32,
+        attestation_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// A DCAP quote whose report data binds `code_hash`, `chain_id` and `height`, with the statement
+/// it attests, as json. The enclave refuses code it hasn't loaded.
+pub fn untrusted_attest_code_hash(
+    code_hash: &[u8; 32],
+    chain_id: &str,
+    height: u64,
+) -> SgxResult<Vec<u8>> {
+    call_with_output(
+        INITIAL_ATTESTATION_CAPACITY,
+        |eid, retval, attestation, attestation_len| unsafe {
+            ecall_attest_code_hash(
+                eid,
+                retval,
+                code_hash,
+                chain_id.as_ptr(),
+                chain_id.len(),
+                height,
+                attestation.as_mut_ptr(),
+                attestation.len() as u32,
+                attestation_len,
+            )
+        },
+    )
+}
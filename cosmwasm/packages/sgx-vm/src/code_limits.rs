@@ -0,0 +1,37 @@
+use sgx_types::*;
+
+use crate::output_buffer::call_with_output;
+
+/// Enough for the report of most modules, larger ones take a second ecall
+const INITIAL_REPORT_CAPACITY: usize = 4 * 1024;
+
+extern "C" {
+    pub fn ecall_validate_code(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        contract: *const u8,
+        contract_len: u32,
+        report: *mut u8,
+        report_capacity: u32,
+        report_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// Check a contract against the enclave's limits on code size and complexity, and return the
+/// validation report, as json. Rejected code has `"valid":false`, and the reasons in `violations`.
+pub fn untrusted_validate_code(contract: &[u8]) -> SgxResult<Vec<u8>> {
+    call_with_output(
+        INITIAL_REPORT_CAPACITY,
+        |eid, retval, report, report_len| unsafe {
+            ecall_validate_code(
+                eid,
+                retval,
+                contract.as_ptr(),
+                contract.len() as u32,
+                report.as_mut_ptr(),
+                report.len() as u32,
+                report_len,
+            )
+        },
+    )
+}
@@ -35,6 +35,10 @@ const SUPPORTED_IMPORTS_V1: &[&str] = &[
     "env.db_read",
     "env.db_write",
     "env.db_remove",
+    "env.db_oblivious_namespace",
+    "env.db_public_namespace",
+    "env.db_declassify",
+    "env.db_integrity_namespace",
     "env.addr_validate",
     "env.addr_canonicalize",
     "env.addr_humanize",
@@ -44,7 +48,54 @@ const SUPPORTED_IMPORTS_V1: &[&str] = &[
     "env.ed25519_verify",
     "env.ed25519_batch_verify",
     "env.ed25519_sign",
+    "env.rsa_pkcs1v15_verify",
+    "env.rsa_pss_verify",
+    "env.musig2_key_agg",
+    "env.secp256k1_schnorr_verify",
     "env.dcap_quote_verify",
+    "env.drand_verify",
+    "env.drand_randomness",
+    "env.oracle_attestation_verify",
+    "env.job_seal_input",
+    "env.decrypt_disclosed_attribute",
+    "env.derive_contract_keypair",
+    "env.contract_decrypt",
+    "env.token_metadata_seal",
+    "env.token_metadata_open",
+    "env.foreign_app_hash_verify",
+    "env.block_random_at",
+    "env.canonicalize_json",
+    "env.fixed_point_mul",
+    "env.fixed_point_div",
+    "env.fixed_point_ln",
+    "env.fixed_point_exp",
+    "env.fixed_point_pow",
+    "env.timestamp_add_months",
+    "env.timestamp_truncate",
+    "env.timestamp_weekday",
+    "env.uint512_op",
+    "env.int512_op",
+    "env.modexp",
+    "env.x509_verify_chain",
+    "env.jwt_verify",
+    "env.poseidon_hash",
+    "env.merkle_create",
+    "env.merkle_append",
+    "env.merkle_root",
+    "env.merkle_prove",
+    "env.merkle_verify",
+    "env.state_commitment_root",
+    "env.state_commitment_prove",
+    "env.state_commitment_verify",
+    "env.hkdf_sha256",
+    "env.hmac_sha256",
+    "env.aead_seal",
+    "env.aead_open",
+    "env.compress",
+    "env.decompress",
+    "env.verify_sgx_quote",
+    "env.query_enclave_features",
+    "env.ibc_client_status",
     "env.debug",
     "env.query_chain",
     #[cfg(feature = "iterator")]
@@ -52,6 +103,7 @@ const SUPPORTED_IMPORTS_V1: &[&str] = &[
     #[cfg(feature = "iterator")]
     "env.db_next",
     "env.gas_evaporate",
+    "env.gas_uniform",
     "env.check_gas"
 ];
 
@@ -0,0 +1,87 @@
+use sgx_types::*;
+
+use log::debug;
+
+use crate::enclave::ENCLAVE_DOORBELL;
+
+extern "C" {
+    pub fn ecall_submit_compute_params(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        in_params: *const u8,
+        in_params_len: u32,
+        in_proof: *const u8,
+        in_proof_len: u32,
+    ) -> sgx_status_t;
+
+    pub fn ecall_validate_enclave_params(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        params: *const u8,
+        params_len: u32,
+    ) -> sgx_status_t;
+}
+
+/// Hands the enclave the params of the compute module, and the proof of them against the app
+/// hash of the current block
+pub fn untrusted_submit_compute_params(params: &[u8], proof: &[u8]) -> SgxResult<()> {
+    debug!("Hello from just before - untrusted_submit_compute_params");
+
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let status = unsafe {
+        ecall_submit_compute_params(
+            eid,
+            &mut retval,
+            params.as_ptr(),
+            params.len() as u32,
+            proof.as_ptr(),
+            proof.len() as u32,
+        )
+    };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok(())
+}
+
+/// Checks the enclave params of compute module params, before governance sets them
+pub fn untrusted_validate_enclave_params(params: &[u8]) -> SgxResult<()> {
+    debug!("Hello from just before - untrusted_validate_enclave_params");
+
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let status = unsafe {
+        ecall_validate_enclave_params(eid, &mut retval, params.as_ptr(), params.len() as u32)
+    };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use sgx_types::*;
+
+use crate::output_buffer::call_with_output;
+
+/// Enough for the calls of the reference contracts, larger traces take a second ecall
+const INITIAL_TRACE_CAPACITY: usize = 64 * 1024;
+
+extern "C" {
+    pub fn ecall_take_conformance_trace(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        trace: *mut u8,
+        trace_capacity: u32,
+        trace_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// Take the storage accesses recorded since the last call, as a json array. Only available in
+/// enclaves built with the `conformance` feature.
+pub fn untrusted_take_conformance_trace() -> SgxResult<Vec<u8>> {
+    // The enclave keeps the trace when it doesn't fit
+    call_with_output(
+        INITIAL_TRACE_CAPACITY,
+        |eid, retval, trace, trace_len| unsafe {
+            ecall_take_conformance_trace(
+                eid,
+                retval,
+                trace.as_mut_ptr(),
+                trace.len() as u32,
+                trace_len,
+            )
+        },
+    )
+}
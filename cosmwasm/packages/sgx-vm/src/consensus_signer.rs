@@ -0,0 +1,70 @@
+use sgx_types::*;
+
+use crate::enclave::ENCLAVE_DOORBELL;
+use crate::output_buffer::call_with_output;
+
+/// Room for the signatures a response adds to the request, larger responses take a second ecall
+const PRIVVAL_RESPONSE_OVERHEAD: usize = 256;
+
+extern "C" {
+    pub fn ecall_consensus_key_init(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        public_key: &mut [u8; 32],
+    ) -> sgx_status_t;
+
+    pub fn ecall_consensus_sign(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        request: *const u8,
+        request_len: u32,
+        response: *mut u8,
+        response_capacity: u32,
+        response_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// Load the consensus key held by the enclave, generating it on first use, and return its
+/// Ed25519 public key
+pub fn untrusted_consensus_key_init() -> SgxResult<[u8; 32]> {
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let mut public_key = [0u8; 32];
+
+    let status = unsafe { ecall_consensus_key_init(eid, &mut retval, &mut public_key) };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok(public_key)
+}
+
+/// Answer a protobuf encoded `privval.Message` with the enclave's consensus key
+pub fn untrusted_consensus_sign(request: &[u8]) -> SgxResult<Vec<u8>> {
+    call_with_output(
+        request.len() + PRIVVAL_RESPONSE_OVERHEAD,
+        |eid, retval, response, response_len| unsafe {
+            ecall_consensus_sign(
+                eid,
+                retval,
+                request.as_ptr(),
+                request.len() as u32,
+                response.as_mut_ptr(),
+                response.len() as u32,
+                response_len,
+            )
+        },
+    )
+}
@@ -0,0 +1,33 @@
+use sgx_types::*;
+
+use crate::output_buffer::call_with_output;
+
+/// Enough for a full buffer of breadcrumbs
+const INITIAL_BREADCRUMBS_CAPACITY: usize = 16 * 1024;
+
+extern "C" {
+    pub fn ecall_get_crash_breadcrumbs(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        breadcrumbs: *mut u8,
+        breadcrumbs_capacity: u32,
+        breadcrumbs_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// Return the breadcrumbs the enclave sealed when it last panicked or hit a fatal error, oldest
+/// first, as json. They're kept across restarts of the node.
+pub fn untrusted_get_crash_breadcrumbs() -> SgxResult<Vec<u8>> {
+    call_with_output(
+        INITIAL_BREADCRUMBS_CAPACITY,
+        |eid, retval, breadcrumbs, breadcrumbs_len| unsafe {
+            ecall_get_crash_breadcrumbs(
+                eid,
+                retval,
+                breadcrumbs.as_mut_ptr(),
+                breadcrumbs.len() as u32,
+                breadcrumbs_len,
+            )
+        },
+    )
+}
@@ -0,0 +1,45 @@
+use lazy_static::lazy_static;
+use log::*;
+
+use parking_lot::Mutex;
+
+/// Reports beyond this are dropped until the node takes them
+const MAX_PENDING_DIVERGENCES: usize = 1024;
+
+lazy_static! {
+    /// The engine divergences the enclave reported since they were last taken, as json
+    static ref ENGINE_DIVERGENCES: Mutex<Vec<Vec<u8>>> = Mutex::new(vec![]);
+}
+
+/// Called by enclaves built with the `differential-execution` feature, when the candidate engine
+/// backend ran a contract differently than the current one
+#[no_mangle]
+pub extern "C" fn ocall_report_engine_divergence(report: *const u8, report_len: usize) {
+    let report = unsafe { std::slice::from_raw_parts(report, report_len) }.to_vec();
+    warn!(
+        "The enclave's engines diverged: {}",
+        String::from_utf8_lossy(&report)
+    );
+
+    let mut divergences = ENGINE_DIVERGENCES.lock();
+    if divergences.len() < MAX_PENDING_DIVERGENCES {
+        divergences.push(report);
+    } else {
+        error!("Dropped an engine divergence, too many weren't taken yet");
+    }
+}
+
+/// Take the engine divergences reported since the last call, as a json array
+pub fn untrusted_take_engine_divergences() -> Vec<u8> {
+    let divergences = std::mem::take(&mut *ENGINE_DIVERGENCES.lock());
+
+    let mut encoded = b"[".to_vec();
+    for (i, divergence) in divergences.iter().enumerate() {
+        if i > 0 {
+            encoded.push(b',');
+        }
+        encoded.extend_from_slice(divergence);
+    }
+    encoded.push(b']');
+    encoded
+}
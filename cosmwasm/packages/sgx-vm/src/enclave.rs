@@ -71,6 +71,8 @@ static ENCLAVE_FILE: &str = "librust_cosmwasm_enclave.signed.so";
 /// This const determines how many seconds we wait when trying to get access to the enclave
 /// before giving up.
 const ENCLAVE_LOCK_TIMEOUT: u64 = 6 * 5;
+/// The enclave has one more TCS than this, which is kept for `ecall_cancel_query`, so that stuck
+/// queries can be cancelled while they hold every other one.
 const TCS_NUM: u8 = 8;
 lazy_static! {
     pub static ref ENCLAVE_DOORBELL: EnclaveDoorbell = EnclaveDoorbell::new(ENCLAVE_FILE, TCS_NUM);
@@ -120,6 +122,12 @@ impl EnclaveDoorbell {
     pub fn get_access(&'static self, query_depth: u32) -> Option<EnclaveAccessToken> {
         self.wait_for(Duration::from_secs(ENCLAVE_LOCK_TIMEOUT), query_depth)
     }
+
+    /// The enclave, without waiting for a slot. Only for an ecall that runs on the TCS that isn't
+    /// counted in `TCS_NUM`, one call at a time.
+    pub fn get_reserved_access(&'static self) -> SgxResult<&'static SgxEnclave> {
+        self.enclave.as_ref().map_err(|status| *status)
+    }
 }
 
 // NEVER add Clone or Copy
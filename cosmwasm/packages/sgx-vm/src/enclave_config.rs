@@ -22,12 +22,18 @@ extern "C" {
 
 pub struct EnclaveRuntimeConfig {
     pub module_cache_size: u32,
+    pub query_memory_limit_pages: u32,
+    pub query_gas_limit: u64,
+    pub query_time_limit_ms: u64,
 }
 
 impl EnclaveRuntimeConfig {
     fn to_ffi_type(&self) -> RuntimeConfiguration {
         RuntimeConfiguration {
             module_cache_size: self.module_cache_size,
+            query_memory_limit_pages: self.query_memory_limit_pages,
+            query_gas_limit: self.query_gas_limit,
+            query_time_limit_ms: self.query_time_limit_ms,
         }
     }
 }
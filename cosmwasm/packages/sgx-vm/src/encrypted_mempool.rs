@@ -0,0 +1,70 @@
+use sgx_types::*;
+
+use crate::enclave::ENCLAVE_DOORBELL;
+use crate::output_buffer::call_with_output;
+
+extern "C" {
+    pub fn ecall_get_mempool_key(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        height: u64,
+        public_key: &mut [u8; 32],
+    ) -> sgx_status_t;
+
+    pub fn ecall_decrypt_encrypted_tx(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        msg: *const u8,
+        msg_len: usize,
+        msg_proof: *const u8,
+        msg_proof_len: usize,
+        output: *mut u8,
+        output_capacity: u32,
+        output_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// Returns the public key users encrypt the txs they want included at `height` to
+pub fn untrusted_get_mempool_key(height: u64) -> SgxResult<[u8; 32]> {
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let mut public_key = [0u8; 32];
+
+    let status = unsafe { ecall_get_mempool_key(eid, &mut retval, height, &mut public_key) };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok(public_key)
+}
+
+/// Decrypt the tx of a `MsgExecuteEncryptedTx` that was included in the last block, with the
+/// proof that x/compute stored it, and return the tx
+pub fn untrusted_decrypt_encrypted_tx(msg: &[u8], msg_proof: &[u8]) -> SgxResult<Vec<u8>> {
+    // The tx is shorter than its ciphertext, which is shorter than the msg
+    call_with_output(msg.len(), |eid, retval, output, output_len| unsafe {
+        ecall_decrypt_encrypted_tx(
+            eid,
+            retval,
+            msg.as_ptr(),
+            msg.len(),
+            msg_proof.as_ptr(),
+            msg_proof.len(),
+            output.as_mut_ptr(),
+            output.len() as u32,
+            output_len,
+        )
+    })
+}
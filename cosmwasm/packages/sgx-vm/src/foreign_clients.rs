@@ -0,0 +1,135 @@
+use sgx_types::*;
+
+use crate::enclave::ENCLAVE_DOORBELL;
+use crate::output_buffer::call_with_output;
+
+/// A light client with a small validator set. Only the initial capacity, the enclave says so when
+/// its output is larger.
+pub const FOREIGN_CLIENT_LENGTH: usize = 16 * 1024;
+
+extern "C" {
+    pub fn ecall_create_foreign_client(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        msg: *const u8,
+        msg_len: usize,
+        msg_proof: *const u8,
+        msg_proof_len: usize,
+        output: *mut u8,
+        output_capacity: u32,
+        output_len: *mut u32,
+    ) -> sgx_status_t;
+
+    pub fn ecall_update_foreign_client(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        client: *const u8,
+        client_len: usize,
+        client_proof: *const u8,
+        client_proof_len: usize,
+        msg: *const u8,
+        msg_len: usize,
+        output: *mut u8,
+        output_capacity: u32,
+        output_len: *mut u32,
+    ) -> sgx_status_t;
+
+    pub fn ecall_submit_foreign_client(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        chain_id: *const u8,
+        chain_id_len: usize,
+        client: *const u8,
+        client_len: usize,
+        proof: *const u8,
+        proof_len: usize,
+    ) -> sgx_status_t;
+}
+
+/// Create the light client of another chain from a `MsgCreateForeignClient` that governance
+/// passed, with the proof that x/compute stored it, and return the client
+pub fn untrusted_create_foreign_client(msg: &[u8], msg_proof: &[u8]) -> SgxResult<Vec<u8>> {
+    call_with_output(
+        FOREIGN_CLIENT_LENGTH,
+        |eid, retval, output, output_len| unsafe {
+            ecall_create_foreign_client(
+                eid,
+                retval,
+                msg.as_ptr(),
+                msg.len(),
+                msg_proof.as_ptr(),
+                msg_proof.len(),
+                output.as_mut_ptr(),
+                output.len() as u32,
+                output_len,
+            )
+        },
+    )
+}
+
+/// Verify the header of a `MsgUpdateForeignClient` against the stored light client, with the
+/// proof that x/compute stores it, and return the updated client
+pub fn untrusted_update_foreign_client(
+    client: &[u8],
+    client_proof: &[u8],
+    msg: &[u8],
+) -> SgxResult<Vec<u8>> {
+    call_with_output(
+        client.len().max(FOREIGN_CLIENT_LENGTH),
+        |eid, retval, output, output_len| unsafe {
+            ecall_update_foreign_client(
+                eid,
+                retval,
+                client.as_ptr(),
+                client.len(),
+                client_proof.as_ptr(),
+                client_proof.len(),
+                msg.as_ptr(),
+                msg.len(),
+                output.as_mut_ptr(),
+                output.len() as u32,
+                output_len,
+            )
+        },
+    )
+}
+
+/// Pass the enclave the light client x/compute stores for `chain_id`, with the proof of it, so
+/// contracts are answered from it in the current block
+pub fn untrusted_submit_foreign_client(
+    chain_id: &[u8],
+    client: &[u8],
+    proof: &[u8],
+) -> SgxResult<()> {
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let status = unsafe {
+        ecall_submit_foreign_client(
+            eid,
+            &mut retval,
+            chain_id.as_ptr(),
+            chain_id.len(),
+            client.as_ptr(),
+            client.len(),
+            proof.as_ptr(),
+            proof.len(),
+        )
+    };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok(())
+}
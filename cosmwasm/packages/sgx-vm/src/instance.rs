@@ -378,10 +378,24 @@ where
         Ok(result.into_output())
     }
 
+    pub fn call_simulate(&mut self, env: &[u8], msg: &[u8], sig_info: &[u8]) -> VmResult<Vec<u8>> {
+        let result = self.inner.simulate(env, msg, sig_info)?;
+        Ok(result.into_output())
+    }
+
     pub fn call_query(&mut self, env: &[u8], msg: &[u8]) -> VmResult<Vec<u8>> {
         let result = self.inner.query(env, msg)?;
         Ok(result.into_output())
     }
+
+    pub fn call_run_job(
+        &mut self,
+        contract_code_hash: &[u8; 32],
+        env: &[u8],
+        job: &[u8],
+    ) -> VmResult<Vec<u8>> {
+        self.inner.run_job(contract_code_hash, env, job)
+    }
 }
 
 #[cfg(test)]
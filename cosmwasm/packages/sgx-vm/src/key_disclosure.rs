@@ -0,0 +1,63 @@
+use sgx_types::*;
+
+use crate::output_buffer::call_with_output;
+
+/// The ephemeral public key of the enclave, followed by the wrapped key. Only the initial
+/// capacity, the enclave says so when its output is larger.
+pub const ENCRYPTED_CONTRACT_KEY_LENGTH: usize = 80;
+
+extern "C" {
+    pub fn ecall_disclose_contract_key(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        env: *const u8,
+        env_len: usize,
+        code_hash: &[u8; 32],
+        admin: *const u8,
+        admin_len: usize,
+        admin_proof: *const u8,
+        admin_proof_len: usize,
+        msg: *const u8,
+        msg_len: usize,
+        msg_proof: *const u8,
+        msg_proof_len: usize,
+        encrypted_key: *mut u8,
+        encrypted_key_capacity: u32,
+        encrypted_key_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// Disclose the state key of a contract to an auditor, as authorized by a
+/// `MsgDiscloseContractKey` that governance passed, with the proof that x/compute stored it
+pub fn untrusted_disclose_contract_key(
+    env: &[u8],
+    code_hash: &[u8; 32],
+    admin: &[u8],
+    admin_proof: &[u8],
+    msg: &[u8],
+    msg_proof: &[u8],
+) -> SgxResult<Vec<u8>> {
+    call_with_output(
+        ENCRYPTED_CONTRACT_KEY_LENGTH,
+        |eid, retval, encrypted_key, encrypted_key_len| unsafe {
+            ecall_disclose_contract_key(
+                eid,
+                retval,
+                env.as_ptr(),
+                env.len(),
+                code_hash,
+                admin.as_ptr(),
+                admin.len(),
+                admin_proof.as_ptr(),
+                admin_proof.len(),
+                msg.as_ptr(),
+                msg.len(),
+                msg_proof.as_ptr(),
+                msg_proof.len(),
+                encrypted_key.as_mut_ptr(),
+                encrypted_key.len() as u32,
+                encrypted_key_len,
+            )
+        },
+    )
+}
@@ -20,16 +20,41 @@ mod traits;
 // Secret Network specific modules
 mod attestation;
 mod attestation_dcap;
+mod compute_params;
+mod audit;
+mod code_attestation;
+mod code_limits;
+mod conformance;
+mod consensus_signer;
+mod crash_breadcrumbs;
+mod differential;
 mod enclave;
 mod enclave_config;
+mod encrypted_mempool;
+mod foreign_clients;
+mod key_disclosure;
+mod module_cache_snapshot;
+mod ocall_stats;
+mod output_buffer;
+mod platform_census;
+mod prewarm;
+mod query_cancellation;
+mod query_session;
+mod registration_package;
 mod seed;
+mod seed_heartbeat;
+mod self_test;
+mod state_backup;
+mod state_commitment;
+mod state_reencryption;
 mod wasmi;
 
 mod random;
 
 pub use crate::cache::CosmCache;
 pub use crate::calls::{
-    call_handle_raw, call_init_raw, call_migrate_raw, call_query_raw, call_update_admin_raw,
+    call_handle_raw, call_init_raw, call_migrate_raw, call_query_raw, call_run_job_raw, call_simulate_raw,
+    call_update_admin_raw,
 };
 pub use crate::checksum::Checksum;
 pub use crate::errors::{
@@ -51,12 +76,45 @@ pub use crate::traits::StorageIterator;
 
 // Secret Network specific exports
 pub use crate::attestation::{
-    create_attestation_report_u, untrusted_get_encrypted_genesis_seed, untrusted_get_encrypted_seed,
+    create_attestation_report_u, untrusted_bind_operator, untrusted_get_encrypted_genesis_seed,
+    untrusted_get_encrypted_seed, untrusted_prove_key_possession,
 };
 pub use crate::seed::{
     untrusted_approve_upgrade, untrusted_health_check, untrusted_init_bootstrap,
-    untrusted_init_node, untrusted_key_gen, untrusted_migration_op,
+    untrusted_init_node, untrusted_key_gen, untrusted_migration_op, untrusted_rotate_node_keys,
     untrusted_submit_validator_set_evidence,
 };
 
+pub use crate::compute_params::{
+    untrusted_submit_compute_params, untrusted_validate_enclave_params,
+};
+pub use crate::audit::untrusted_take_audit_transcript;
+pub use crate::code_attestation::untrusted_attest_code_hash;
+pub use crate::code_limits::untrusted_validate_code;
+pub use crate::conformance::untrusted_take_conformance_trace;
+pub use crate::consensus_signer::{untrusted_consensus_key_init, untrusted_consensus_sign};
+pub use crate::crash_breadcrumbs::untrusted_get_crash_breadcrumbs;
+pub use crate::differential::untrusted_take_engine_divergences;
+pub use crate::encrypted_mempool::{untrusted_decrypt_encrypted_tx, untrusted_get_mempool_key};
+pub use crate::foreign_clients::{
+    untrusted_create_foreign_client, untrusted_submit_foreign_client,
+    untrusted_update_foreign_client,
+};
+pub use crate::key_disclosure::untrusted_disclose_contract_key;
+pub use crate::module_cache_snapshot::untrusted_module_cache_snapshot;
+pub use crate::ocall_stats::untrusted_get_ocall_stats;
+pub use crate::platform_census::untrusted_platform_census;
+pub use crate::prewarm::untrusted_prewarm_module;
+pub use crate::query_cancellation::untrusted_cancel_query;
+pub use crate::query_session::untrusted_open_query_session;
 pub use crate::random::untrusted_submit_block_signatures;
+pub use crate::registration_package::{
+    untrusted_create_registration_package, untrusted_verify_registration_package,
+};
+pub use crate::seed_heartbeat::{untrusted_get_seed_heartbeat_key, untrusted_seed_heartbeat};
+pub use crate::self_test::untrusted_self_test;
+pub use crate::state_backup::{untrusted_export_state, untrusted_import_state};
+pub use crate::state_commitment::{
+    untrusted_fold_state_commitment, untrusted_state_commitment_leaves,
+};
+pub use crate::state_reencryption::untrusted_reencrypt_state;
@@ -0,0 +1,33 @@
+use sgx_types::*;
+
+use crate::output_buffer::call_with_output;
+
+/// Enough for a cache of about 30 modules, larger ones take a second ecall
+const INITIAL_SNAPSHOT_CAPACITY: usize = 8 * 1024;
+
+extern "C" {
+    pub fn ecall_module_cache_snapshot(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        snapshot: *mut u8,
+        snapshot_capacity: u32,
+        snapshot_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// A snapshot of the enclave's module cache, as json: the code hash, artifact hash and engine
+/// version of every cached module, and a digest of them signed by the enclave
+pub fn untrusted_module_cache_snapshot() -> SgxResult<Vec<u8>> {
+    call_with_output(
+        INITIAL_SNAPSHOT_CAPACITY,
+        |eid, retval, snapshot, snapshot_len| unsafe {
+            ecall_module_cache_snapshot(
+                eid,
+                retval,
+                snapshot.as_mut_ptr(),
+                snapshot.len() as u32,
+                snapshot_len,
+            )
+        },
+    )
+}
@@ -0,0 +1,33 @@
+use sgx_types::*;
+
+use crate::output_buffer::call_with_output;
+
+/// Enough for the counters of every ocall the enclave has
+const INITIAL_STATS_CAPACITY: usize = 4 * 1024;
+
+extern "C" {
+    pub fn ecall_get_ocall_stats(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        stats: *mut u8,
+        stats_capacity: u32,
+        stats_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// Return how often the enclave made each ocall since it started, as json. Calls that didn't
+/// pass the enclave's validation are counted as `rejected`, and never reached the host.
+pub fn untrusted_get_ocall_stats() -> SgxResult<Vec<u8>> {
+    call_with_output(
+        INITIAL_STATS_CAPACITY,
+        |eid, retval, stats, stats_len| unsafe {
+            ecall_get_ocall_stats(
+                eid,
+                retval,
+                stats.as_mut_ptr(),
+                stats.len() as u32,
+                stats_len,
+            )
+        },
+    )
+}
@@ -0,0 +1,71 @@
+//! Ecalls that write their output to a buffer of the caller's capacity.
+//!
+//! The enclave always sets the output length to the length of its output. When the output
+//! doesn't fit, it writes nothing and returns its "buffer too small" result instead, so the
+//! caller can grow the buffer to the length it was told and call the ecall again. The initial
+//! capacity is only a guess, and the enclave can change the size of its outputs without
+//! changing the ABI of the ecall.
+
+use sgx_types::*;
+
+use crate::enclave::ENCLAVE_DOORBELL;
+
+/// Call an ecall that returns `SGX_ERROR_INVALID_PARAMETER` when its output doesn't fit, starting
+/// with a buffer of `capacity` bytes
+pub fn call_with_output<F>(capacity: usize, ecall: F) -> SgxResult<Vec<u8>>
+where
+    F: FnMut(sgx_enclave_id_t, &mut sgx_status_t, &mut [u8], &mut u32) -> sgx_status_t,
+{
+    call_with_output_as(
+        capacity,
+        sgx_status_t::SGX_SUCCESS,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER,
+        ecall,
+    )?
+}
+
+/// Call an ecall with its own result type, which returns `buffer_too_small` when its output
+/// doesn't fit. Results other than `success` are returned as errors.
+pub fn call_with_output_as<R, F>(
+    capacity: usize,
+    success: R,
+    buffer_too_small: R,
+    mut ecall: F,
+) -> SgxResult<Result<Vec<u8>, R>>
+where
+    R: Copy + PartialEq,
+    F: FnMut(sgx_enclave_id_t, &mut R, &mut [u8], &mut u32) -> sgx_status_t,
+{
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    let eid = enclave.geteid();
+    let mut output = vec![0u8; capacity];
+
+    loop {
+        let mut retval = success;
+        let mut output_len: u32 = 0;
+
+        let status = ecall(eid, &mut retval, &mut output, &mut output_len);
+
+        if status != sgx_status_t::SGX_SUCCESS {
+            return Err(status);
+        }
+
+        if retval == buffer_too_small && output_len as usize > output.len() {
+            output.resize(output_len as usize, 0);
+            continue;
+        }
+
+        if retval != success {
+            return Ok(Err(retval));
+        }
+
+        output.truncate(output_len as usize);
+        return Ok(Ok(output));
+    }
+}
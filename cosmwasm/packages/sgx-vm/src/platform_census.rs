@@ -0,0 +1,33 @@
+use sgx_types::*;
+
+use crate::output_buffer::call_with_output;
+
+/// Enough for a fleet spread over a few dozen TCB levels and advisories
+const INITIAL_CENSUS_CAPACITY: usize = 4 * 1024;
+
+extern "C" {
+    pub fn ecall_platform_census(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        census: *mut u8,
+        census_capacity: u32,
+        census_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// How many of the platforms that registered are at each TCB status, TCB date and advisory, and
+/// a digest of the counts signed by the enclave, as json
+pub fn untrusted_platform_census() -> SgxResult<Vec<u8>> {
+    call_with_output(
+        INITIAL_CENSUS_CAPACITY,
+        |eid, retval, census, census_len| unsafe {
+            ecall_platform_census(
+                eid,
+                retval,
+                census.as_mut_ptr(),
+                census.len() as u32,
+                census_len,
+            )
+        },
+    )
+}
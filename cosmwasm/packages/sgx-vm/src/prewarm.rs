@@ -0,0 +1,38 @@
+use sgx_types::*;
+
+use crate::enclave::ENCLAVE_DOORBELL;
+
+extern "C" {
+    pub fn ecall_prewarm_module(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        contract: *const u8,
+        contract_len: u32,
+    ) -> sgx_status_t;
+}
+
+/// Hint the enclave that a contract is likely to be executed in the next block, so it can analyze
+/// it ahead of time. Only an optimization, the enclave may ignore it.
+pub fn untrusted_prewarm_module(contract: &[u8]) -> SgxResult<()> {
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let status =
+        unsafe { ecall_prewarm_module(eid, &mut retval, contract.as_ptr(), contract.len() as u32) };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use sgx_types::*;
+
+use crate::enclave::ENCLAVE_DOORBELL;
+
+extern "C" {
+    pub fn ecall_cancel_query(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        cancellation_id: u64,
+    ) -> sgx_status_t;
+}
+
+lazy_static! {
+    /// The reserved TCS can only run one cancellation at a time
+    static ref RESERVED_TCS: Mutex<()> = Mutex::new(());
+}
+
+/// Cancel the query from outside the chain that was given `cancellation_id` in its env. It stops
+/// the next time it calls into the enclave. Cancelling a query that already returned does nothing.
+pub fn untrusted_cancel_query(cancellation_id: u64) -> SgxResult<()> {
+    // Queries hold the doorbell's slots, and they may be the ones stuck
+    let _reserved = RESERVED_TCS.lock();
+    let enclave = ENCLAVE_DOORBELL.get_reserved_access()?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let status = unsafe { ecall_cancel_query(eid, &mut retval, cancellation_id) };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok(())
+}
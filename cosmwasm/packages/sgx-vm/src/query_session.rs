@@ -0,0 +1,56 @@
+use sgx_types::*;
+
+use crate::enclave::ENCLAVE_DOORBELL;
+
+extern "C" {
+    pub fn ecall_open_query_session(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        nonce: &[u8; 32],
+        user_public_key: &[u8; 32],
+        current_height: u64,
+        expiry_height: u64,
+        session_id: &mut [u8; 32],
+    ) -> sgx_status_t;
+}
+
+/// Open a query session, returning the session id that subsequent queries should reference
+pub fn untrusted_open_query_session(
+    nonce: &[u8; 32],
+    user_public_key: &[u8; 32],
+    current_height: u64,
+    expiry_height: u64,
+) -> SgxResult<[u8; 32]> {
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let mut session_id = [0u8; 32];
+
+    let status = unsafe {
+        ecall_open_query_session(
+            eid,
+            &mut retval,
+            nonce,
+            user_public_key,
+            current_height,
+            expiry_height,
+            &mut session_id,
+        )
+    };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok(session_id)
+}
@@ -0,0 +1,90 @@
+use log::*;
+use sgx_types::*;
+
+use enclave_ffi_types::{NodeAuthResult, MAX_REGISTRATION_PACKAGE_LENGTH};
+
+use crate::enclave::ENCLAVE_DOORBELL;
+use crate::output_buffer::call_with_output;
+
+/// A DCAP quote and its collateral in base64, with the json around them
+const INITIAL_PACKAGE_CAPACITY: usize = 64 * 1024;
+
+extern "C" {
+    pub fn ecall_create_registration_package(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        key_scheme: u8,
+        package: *mut u8,
+        package_capacity: u32,
+        package_len: *mut u32,
+    ) -> sgx_status_t;
+    pub fn ecall_verify_registration_package(
+        eid: sgx_enclave_id_t,
+        retval: *mut NodeAuthResult,
+        package: *const u8,
+        package_len: u32,
+        collateral_expiration: *mut i64,
+    ) -> sgx_status_t;
+}
+
+/// A registration package of the node's registration key, attested in the key scheme with the id
+/// `key_scheme`, as json
+pub fn untrusted_create_registration_package(key_scheme: u8) -> SgxResult<Vec<u8>> {
+    call_with_output(
+        INITIAL_PACKAGE_CAPACITY,
+        |eid, retval, package, package_len| unsafe {
+            ecall_create_registration_package(
+                eid,
+                retval,
+                key_scheme,
+                package.as_mut_ptr(),
+                package.len() as u32,
+                package_len,
+            )
+        },
+    )
+}
+
+/// Verifies a registration package the way a registration of its cert would be, without
+/// registering it. Returns the time (unix seconds) at which the earliest of its collateral expires.
+pub fn untrusted_verify_registration_package(
+    package: &[u8],
+) -> SgxResult<Result<i64, NodeAuthResult>> {
+    if package.len() > MAX_REGISTRATION_PACKAGE_LENGTH {
+        warn!(
+            "Rejecting a registration package of {} bytes",
+            package.len()
+        );
+        return Ok(Err(NodeAuthResult::InputTooLarge));
+    }
+
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    let eid = enclave.geteid();
+    let mut retval = NodeAuthResult::Success;
+    let mut collateral_expiration: i64 = 0;
+    let status = unsafe {
+        ecall_verify_registration_package(
+            eid,
+            &mut retval,
+            package.as_ptr(),
+            package.len() as u32,
+            &mut collateral_expiration,
+        )
+    };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != NodeAuthResult::Success {
+        return Ok(Err(retval));
+    }
+
+    Ok(Ok(collateral_expiration))
+}
@@ -1,6 +1,6 @@
 use enclave_ffi_types::{
-    HealthCheckResult, INPUT_ENCRYPTED_SEED_SIZE, NEWLY_FORMED_DOUBLE_ENCRYPTED_SEED_SIZE,
-    NEWLY_FORMED_SINGLE_ENCRYPTED_SEED_SIZE,
+    HealthCheckResult, SeedBlobStatus, INPUT_ENCRYPTED_SEED_SIZE,
+    NEWLY_FORMED_DOUBLE_ENCRYPTED_SEED_SIZE, NEWLY_FORMED_SINGLE_ENCRYPTED_SEED_SIZE,
 };
 use sgx_types::*;
 
@@ -18,6 +18,9 @@ extern "C" {
         encrypted_seed_len: u32,
         api_key: *const u8,
         api_key_len: u32,
+        chain_id: *const u8,
+        chain_id_len: usize,
+        seed_status: *mut SeedBlobStatus,
     ) -> sgx_status_t;
 
     pub fn ecall_init_bootstrap(
@@ -28,6 +31,8 @@ extern "C" {
         spid_len: u32,
         api_key: *const u8,
         api_key_len: u32,
+        chain_id: *const u8,
+        chain_id_len: usize,
     ) -> sgx_status_t;
 
     pub fn ecall_key_gen(
@@ -36,6 +41,12 @@ extern "C" {
         public_key: &mut [u8; 32],
     ) -> sgx_status_t;
 
+    pub fn ecall_rotate_node_keys(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        public_key: &mut [u8; 32],
+    ) -> sgx_status_t;
+
     pub fn ecall_migration_op(
         eid: sgx_enclave_id_t,
         retval: *mut sgx_status_t,
@@ -80,11 +91,14 @@ pub fn untrusted_health_check() -> SgxResult<HealthCheckResult> {
     Ok(ret)
 }
 
+/// Loads the seeds of a registered node into the enclave. Returns whether the node had only the
+/// genesis seed or both seeds, or, if the enclave rejected the encrypted seed, why.
 pub fn untrusted_init_node(
     master_key: &[u8],
     encrypted_seed: &[u8],
     api_key: &[u8],
-) -> SgxResult<()> {
+    chain_id: &str,
+) -> SgxResult<Result<SeedBlobStatus, SeedBlobStatus>> {
     info!("Initializing enclave..");
 
     // Bind the token to a local variable to ensure its
@@ -98,6 +112,7 @@ pub fn untrusted_init_node(
 
     let eid = enclave.geteid();
     let mut ret = sgx_status_t::SGX_SUCCESS;
+    let mut seed_status = SeedBlobStatus::default();
 
     let mut seed_to_enclave = [0u8; INPUT_ENCRYPTED_SEED_SIZE as usize];
 
@@ -129,6 +144,9 @@ pub fn untrusted_init_node(
             seed_to_enclave.len() as u32,
             api_key.as_ptr(),
             api_key.len() as u32,
+            chain_id.as_ptr(),
+            chain_id.len(),
+            &mut seed_status,
         )
     };
 
@@ -137,10 +155,16 @@ pub fn untrusted_init_node(
     }
 
     if ret != sgx_status_t::SGX_SUCCESS {
-        return Err(ret);
+        // A seed that was loaded, or never looked at, isn't why the ecall failed
+        if seed_status.is_loaded() || seed_status == SeedBlobStatus::NotDecoded {
+            return Err(ret);
+        }
+        error!("Enclave rejected the encrypted seed: {}", seed_status);
+        return Ok(Err(seed_status));
     }
 
-    Ok(())
+    info!("Loaded {}", seed_status);
+    Ok(Ok(seed_status))
 }
 
 pub fn untrusted_submit_validator_set_evidence(evidence: [u8; 32]) -> SgxResult<()> {
@@ -245,7 +269,39 @@ pub fn untrusted_key_gen() -> SgxResult<[u8; 32]> {
     Ok(public_key)
 }
 
-pub fn untrusted_init_bootstrap(spid: &[u8], api_key: &[u8]) -> SgxResult<[u8; 32]> {
+pub fn untrusted_rotate_node_keys() -> SgxResult<[u8; 32]> {
+    info!("Initializing enclave..");
+
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    info!("Initialized enclave successfully!");
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let mut public_key = [0u8; 32];
+    let status = unsafe { ecall_rotate_node_keys(eid, &mut retval, &mut public_key) };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok(public_key)
+}
+
+pub fn untrusted_init_bootstrap(
+    spid: &[u8],
+    api_key: &[u8],
+    chain_id: &str,
+) -> SgxResult<[u8; 32]> {
     info!("Hello from just before initializing - untrusted_init_bootstrap");
 
     // Bind the token to a local variable to ensure its
@@ -270,6 +326,8 @@ pub fn untrusted_init_bootstrap(spid: &[u8], api_key: &[u8]) -> SgxResult<[u8; 3
             spid.len() as u32,
             api_key.as_ptr(),
             api_key.len() as u32,
+            chain_id.as_ptr(),
+            chain_id.len(),
         )
     };
 
@@ -0,0 +1,82 @@
+use sgx_types::*;
+
+use crate::enclave::ENCLAVE_DOORBELL;
+
+extern "C" {
+    pub fn ecall_get_seed_heartbeat_key(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        public_key: &mut [u8; 32],
+    ) -> sgx_status_t;
+
+    pub fn ecall_seed_heartbeat(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        height: u64,
+        node_public_key: &mut [u8; 32],
+        signature: &mut [u8; 64],
+    ) -> sgx_status_t;
+}
+
+/// Returns the public key seed heartbeats are verified against, which is the same for every
+/// enclave that holds the consensus seed
+pub fn untrusted_get_seed_heartbeat_key() -> SgxResult<[u8; 32]> {
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let mut public_key = [0u8; 32];
+
+    let status = unsafe { ecall_get_seed_heartbeat_key(eid, &mut retval, &mut public_key) };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok(public_key)
+}
+
+/// Sign a seed heartbeat at `height`, and return the registration key of the node along with the
+/// signature
+pub fn untrusted_seed_heartbeat(height: u64) -> SgxResult<([u8; 32], [u8; 64])> {
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let mut node_public_key = [0u8; 32];
+    let mut signature = [0u8; 64];
+
+    let status = unsafe {
+        ecall_seed_heartbeat(
+            eid,
+            &mut retval,
+            height,
+            &mut node_public_key,
+            &mut signature,
+        )
+    };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok((node_public_key, signature))
+}
@@ -0,0 +1,54 @@
+use sgx_types::*;
+
+use crate::enclave::ENCLAVE_DOORBELL;
+
+extern "C" {
+    pub fn ecall_self_test(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        failures: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// The names of the enclave's self-tests, in the order of the bits `ecall_self_test` returns
+const SELF_TESTS: &[&str] = &[
+    "aes_siv",
+    "hkdf",
+    "secp256k1",
+    "ed25519",
+    "x25519",
+    "sealing",
+    "quote_parsing",
+];
+
+/// Run the known-answer tests of the enclave's crypto, and return the names of the tests that
+/// failed. The enclave refuses consensus ecalls until every test passed.
+pub fn untrusted_self_test() -> SgxResult<Vec<String>> {
+    // Bind the token to a local variable to ensure its
+    // destructor runs in the end of the function
+    let enclave_access_token = ENCLAVE_DOORBELL
+        .get_access(1) // This can never be recursive
+        .ok_or(sgx_status_t::SGX_ERROR_BUSY)?;
+    let enclave = (*enclave_access_token)?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let mut failures = 0u32;
+    let status = unsafe { ecall_self_test(eid, &mut retval, &mut failures) };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok((0..32)
+        .filter(|bit| failures & (1 << bit) != 0)
+        .map(|bit| match SELF_TESTS.get(bit) {
+            Some(name) => name.to_string(),
+            None => format!("test {}", bit),
+        })
+        .collect())
+}
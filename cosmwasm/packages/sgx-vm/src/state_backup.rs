@@ -0,0 +1,141 @@
+use sgx_types::*;
+
+use crate::output_buffer::call_with_output;
+
+/// The output is about as large as the input, larger outputs take a second ecall
+const OUTPUT_CAPACITY_MARGIN: usize = 4 * 1024;
+
+type StateBackupEcall = unsafe extern "C" fn(
+    eid: sgx_enclave_id_t,
+    retval: *mut sgx_status_t,
+    env: *const u8,
+    env_len: usize,
+    code_hash: &[u8; 32],
+    admin: *const u8,
+    admin_len: usize,
+    admin_proof: *const u8,
+    admin_proof_len: usize,
+    authorization: *const u8,
+    authorization_len: usize,
+    chunk: *const u8,
+    chunk_len: usize,
+    output: *mut u8,
+    output_capacity: u32,
+    output_len: *mut u32,
+) -> sgx_status_t;
+
+extern "C" {
+    pub fn ecall_export_state(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        env: *const u8,
+        env_len: usize,
+        code_hash: &[u8; 32],
+        admin: *const u8,
+        admin_len: usize,
+        admin_proof: *const u8,
+        admin_proof_len: usize,
+        authorization: *const u8,
+        authorization_len: usize,
+        pairs: *const u8,
+        pairs_len: usize,
+        output: *mut u8,
+        output_capacity: u32,
+        output_len: *mut u32,
+    ) -> sgx_status_t;
+
+    pub fn ecall_import_state(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        env: *const u8,
+        env_len: usize,
+        code_hash: &[u8; 32],
+        admin: *const u8,
+        admin_len: usize,
+        admin_proof: *const u8,
+        admin_proof_len: usize,
+        authorization: *const u8,
+        authorization_len: usize,
+        backup_chunk: *const u8,
+        backup_chunk_len: usize,
+        output: *mut u8,
+        output_capacity: u32,
+        output_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// Wrap a chunk of a contract's pairs, as they're stored, for a backup of its state. The export
+/// must be authorized by the contract's admin.
+pub fn untrusted_export_state(
+    env: &[u8],
+    code_hash: &[u8; 32],
+    admin: &[u8],
+    admin_proof: &[u8],
+    authorization: &[u8],
+    pairs: &[u8],
+) -> SgxResult<Vec<u8>> {
+    call_state_backup_ecall(
+        ecall_export_state,
+        env,
+        code_hash,
+        admin,
+        admin_proof,
+        authorization,
+        pairs,
+    )
+}
+
+/// Unwrap a chunk of a backup of a contract's state, and return its pairs encrypted to be stored
+/// by the contract. The import must be authorized by the contract's admin.
+pub fn untrusted_import_state(
+    env: &[u8],
+    code_hash: &[u8; 32],
+    admin: &[u8],
+    admin_proof: &[u8],
+    authorization: &[u8],
+    backup_chunk: &[u8],
+) -> SgxResult<Vec<u8>> {
+    call_state_backup_ecall(
+        ecall_import_state,
+        env,
+        code_hash,
+        admin,
+        admin_proof,
+        authorization,
+        backup_chunk,
+    )
+}
+
+fn call_state_backup_ecall(
+    ecall: StateBackupEcall,
+    env: &[u8],
+    code_hash: &[u8; 32],
+    admin: &[u8],
+    admin_proof: &[u8],
+    authorization: &[u8],
+    chunk: &[u8],
+) -> SgxResult<Vec<u8>> {
+    call_with_output(
+        chunk.len() + OUTPUT_CAPACITY_MARGIN,
+        |eid, retval, output, output_len| unsafe {
+            ecall(
+                eid,
+                retval,
+                env.as_ptr(),
+                env.len(),
+                code_hash,
+                admin.as_ptr(),
+                admin.len(),
+                admin_proof.as_ptr(),
+                admin_proof.len(),
+                authorization.as_ptr(),
+                authorization.len(),
+                chunk.as_ptr(),
+                chunk.len(),
+                output.as_mut_ptr(),
+                output.len() as u32,
+                output_len,
+            )
+        },
+    )
+}
@@ -0,0 +1,98 @@
+use sgx_types::*;
+
+use crate::output_buffer::call_with_output;
+
+/// Each pair has a leaf of 32 bytes
+const LEAF_SIZE: usize = 32;
+/// Large enough for the sealed progress of a commitment and for a signed commitment, larger
+/// outputs take a second ecall
+const FOLD_OUTPUT_CAPACITY: usize = 16 * 1024;
+
+extern "C" {
+    pub fn ecall_state_commitment_leaves(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        env: *const u8,
+        env_len: usize,
+        code_hash: &[u8; 32],
+        pairs: *const u8,
+        pairs_len: usize,
+        output: *mut u8,
+        output_capacity: u32,
+        output_len: *mut u32,
+    ) -> sgx_status_t;
+
+    pub fn ecall_fold_state_commitment(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        env: *const u8,
+        env_len: usize,
+        code_hash: &[u8; 32],
+        progress: *const u8,
+        progress_len: usize,
+        pairs: *const u8,
+        pairs_len: usize,
+        last_chunk: u8,
+        output: *mut u8,
+        output_capacity: u32,
+        output_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// Returns the leaves of a chunk of a contract's pairs, as they're stored, in the order of the
+/// pairs. The chunk is encoded like the chunks of a state backup.
+pub fn untrusted_state_commitment_leaves(
+    env: &[u8],
+    code_hash: &[u8; 32],
+    pairs: &[u8],
+) -> SgxResult<Vec<u8>> {
+    // A chunk can't have more pairs than it has lengths
+    let capacity = pairs.len() / 8 * LEAF_SIZE;
+
+    call_with_output(capacity, |eid, retval, output, output_len| unsafe {
+        ecall_state_commitment_leaves(
+            eid,
+            retval,
+            env.as_ptr(),
+            env.len(),
+            code_hash,
+            pairs.as_ptr(),
+            pairs.len(),
+            output.as_mut_ptr(),
+            output.len() as u32,
+            output_len,
+        )
+    })
+}
+
+/// Fold a chunk of a contract's pairs, sorted by their leaves, into the commitment to its state.
+/// `progress` is empty for the first chunk, and the output of the previous call otherwise. After
+/// the last chunk the output is the signed commitment, as JSON.
+pub fn untrusted_fold_state_commitment(
+    env: &[u8],
+    code_hash: &[u8; 32],
+    progress: &[u8],
+    pairs: &[u8],
+    last_chunk: bool,
+) -> SgxResult<Vec<u8>> {
+    call_with_output(
+        FOLD_OUTPUT_CAPACITY,
+        |eid, retval, output, output_len| unsafe {
+            ecall_fold_state_commitment(
+                eid,
+                retval,
+                env.as_ptr(),
+                env.len(),
+                code_hash,
+                progress.as_ptr(),
+                progress.len(),
+                pairs.as_ptr(),
+                pairs.len(),
+                last_chunk as u8,
+                output.as_mut_ptr(),
+                output.len() as u32,
+                output_len,
+            )
+        },
+    )
+}
@@ -0,0 +1,56 @@
+use sgx_types::*;
+
+use crate::output_buffer::call_with_output;
+
+/// The re-encrypted pairs are about as large as the pairs of the chunk, and grow by a third in
+/// base64, larger outputs take a second ecall
+const REENCRYPTION_OUTPUT_OVERHEAD: usize = 16 * 1024;
+
+extern "C" {
+    pub fn ecall_reencrypt_state(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        env: *const u8,
+        env_len: usize,
+        code_hash: &[u8; 32],
+        progress: *const u8,
+        progress_len: usize,
+        pairs: *const u8,
+        pairs_len: usize,
+        last_chunk: u8,
+        output: *mut u8,
+        output_capacity: u32,
+        output_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// Re-encrypts the pairs of a chunk of a contract's pairs, in the order of their keys, that are
+/// still under the genesis seed. `progress` is empty for the first chunk, and the progress of the
+/// previous call otherwise. The output is JSON, with the pairs to store and the next progress.
+pub fn untrusted_reencrypt_state(
+    env: &[u8],
+    code_hash: &[u8; 32],
+    progress: &[u8],
+    pairs: &[u8],
+    last_chunk: bool,
+) -> SgxResult<Vec<u8>> {
+    let capacity = pairs.len() / 3 * 4 + REENCRYPTION_OUTPUT_OVERHEAD;
+
+    call_with_output(capacity, |eid, retval, output, output_len| unsafe {
+        ecall_reencrypt_state(
+            eid,
+            retval,
+            env.as_ptr(),
+            env.len(),
+            code_hash,
+            progress.as_ptr(),
+            progress.len(),
+            pairs.as_ptr(),
+            pairs.len(),
+            last_chunk as u8,
+            output.as_mut_ptr(),
+            output.len() as u32,
+            output_len,
+        )
+    })
+}
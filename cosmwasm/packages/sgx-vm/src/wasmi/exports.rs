@@ -97,6 +97,86 @@ fn ocall_read_db_concrete(
         .unwrap_or(OcallReturn::Panic)
 }
 
+#[no_mangle]
+pub extern "C" fn ocall_read_db_first(
+    context: Ctx,
+    vm_error: *mut UntrustedVmError,
+    gas_used: *mut u64,
+    value: *mut EnclaveBuffer,
+    key_index: *mut u32,
+    keys: *const u8,
+    keys_len: usize,
+) -> OcallReturn {
+    ocall_read_db_first_concrete(
+        super::allocate_enclave_buffer,
+        context,
+        vm_error,
+        gas_used,
+        value,
+        key_index,
+        keys,
+        keys_len,
+    )
+}
+
+/// Read keys from the contracts key-value store in order, until one exists. The gas is that of
+/// all the keys that were read.
+#[allow(clippy::too_many_arguments)]
+fn ocall_read_db_first_concrete(
+    alloc_impl: fn(&[u8]) -> SgxResult<EnclaveBuffer>,
+    context: Ctx,
+    vm_error: *mut UntrustedVmError,
+    gas_used: *mut u64,
+    value: *mut EnclaveBuffer,
+    key_index: *mut u32,
+    keys: *const u8,
+    keys_len: usize,
+) -> OcallReturn {
+    let keys = unsafe { std::slice::from_raw_parts(keys, keys_len) };
+    let keys: Vec<Vec<u8>> = match serde_json::from_slice(keys) {
+        Ok(keys) => keys,
+        Err(_) => return OcallReturn::Failure,
+    };
+
+    let implementation = unsafe { get_implementations_from_context(&context).read_db };
+
+    std::panic::catch_unwind(|| {
+        let mut total_gas = 0;
+        for (index, key) in keys.iter().enumerate() {
+            match implementation(unsafe { context.unsafe_clone() }, key) {
+                Ok((Some(val), gas_cost)) => {
+                    total_gas += gas_cost;
+                    unsafe {
+                        *gas_used = total_gas;
+                        *key_index = index as u32;
+                    }
+                    return match alloc_impl(&val) {
+                        Ok(enclave_buffer) => {
+                            unsafe { *value = enclave_buffer };
+                            OcallReturn::Success
+                        }
+                        Err(_) => OcallReturn::Failure,
+                    };
+                }
+                Ok((None, gas_cost)) => total_gas += gas_cost,
+                Err(err) => {
+                    unsafe { store_vm_error(err, vm_error) };
+                    return OcallReturn::Failure;
+                }
+            }
+        }
+
+        unsafe {
+            *gas_used = total_gas;
+            *key_index = keys.len() as u32;
+            *value = EnclaveBuffer::default();
+        }
+        OcallReturn::Success
+    })
+    // This will happen only when `catch_unwind` returns `Err`, which indicates a caught panic
+    .unwrap_or(OcallReturn::Panic)
+}
+
 #[no_mangle]
 pub extern "C" fn ocall_query_chain(
     context: Ctx,
@@ -90,6 +90,23 @@ extern "C" {
         sig_info_len: usize,
         handle_type: u8,
     ) -> sgx_status_t;
+
+    /// Simulate an execute of a wasm contract, without changing the enclave's state
+    pub fn ecall_simulate(
+        eid: sgx_enclave_id_t,
+        retval: *mut HandleResult,
+        context: Ctx,
+        gas_limit: u64,
+        used_gas: *mut u64,
+        contract: *const u8,
+        contract_len: usize,
+        env: *const u8,
+        env_len: usize,
+        msg: *const u8,
+        msg_len: usize,
+        sig_info: *const u8,
+        sig_info_len: usize,
+    ) -> sgx_status_t;
 }
 
 extern "C" {
@@ -109,6 +126,36 @@ extern "C" {
     ) -> sgx_status_t;
 }
 
+extern "C" {
+    /// Run the handler of an off-chain job, and attest its result
+    pub fn ecall_run_job(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        context: Ctx,
+        gas_limit: u64,
+        used_gas: *mut u64,
+        handler: *const u8,
+        handler_len: usize,
+        contract_code_hash: &[u8; 32],
+        env: *const u8,
+        env_len: usize,
+        job: *const u8,
+        job_len: usize,
+        result: *mut u8,
+        result_capacity: u32,
+        result_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+extern "C" {
+    /// Fetch the next chunk of a large query output
+    pub fn ecall_query_chunk(
+        eid: sgx_enclave_id_t,
+        retval: *mut QueryResult,
+        continuation_token: &[u8; 32],
+    ) -> sgx_status_t;
+}
+
 /// This is a safe wrapper for allocating buffers inside the enclave.
 pub(super) fn allocate_enclave_buffer(buffer: &[u8]) -> SgxResult<EnclaveBuffer> {
     let ptr = buffer.as_ptr();
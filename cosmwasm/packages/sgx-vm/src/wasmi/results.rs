@@ -1,6 +1,11 @@
 use super::exports;
 use crate::VmResult;
-use enclave_ffi_types::{HandleResult, InitResult, MigrateResult, QueryResult, UpdateAdminResult};
+use enclave_ffi_types::{
+    EnclaveError, HandleResult, InitResult, MigrateResult, QueryResult, UpdateAdminResult,
+};
+
+/// Continuation token of the last chunk of a query output
+const NO_CONTINUATION: [u8; 32] = [0u8; 32];
 
 /// This struct is returned from module initialization.
 pub struct InitSuccess {
@@ -120,11 +125,50 @@ impl QuerySuccess {
     }
 }
 
-pub fn query_result_to_vm_result(other: QueryResult) -> VmResult<QuerySuccess> {
-    match other {
-        QueryResult::Success { output } => Ok(QuerySuccess {
-            output: unsafe { exports::recover_buffer(output) }.unwrap_or_else(Vec::new),
-        }),
-        QueryResult::Failure { err } => Err(err.into()),
+/// Large query outputs are returned in chunks. `fetch_chunk` is called with the continuation
+/// token of the previous chunk until the whole output was received.
+pub fn query_result_to_vm_result<F>(
+    other: QueryResult,
+    mut fetch_chunk: F,
+) -> VmResult<QuerySuccess>
+where
+    F: FnMut(&[u8; 32]) -> VmResult<QueryResult>,
+{
+    let (mut output, total_size, mut continuation_token) = match other {
+        QueryResult::Success {
+            output,
+            total_size,
+            continuation_token,
+        } => (
+            unsafe { exports::recover_buffer(output) }.unwrap_or_else(Vec::new),
+            total_size,
+            continuation_token,
+        ),
+        QueryResult::Failure { err } => return Err(err.into()),
+    };
+
+    while continuation_token != NO_CONTINUATION {
+        match fetch_chunk(&continuation_token)? {
+            QueryResult::Success {
+                output: chunk,
+                continuation_token: next_token,
+                ..
+            } => {
+                output.extend(unsafe { exports::recover_buffer(chunk) }.unwrap_or_else(Vec::new));
+                continuation_token = next_token;
+            }
+            QueryResult::Failure { err } => return Err(err.into()),
+        }
+
+        // don't keep fetching if the enclave sends more than it announced
+        if output.len() > total_size {
+            break;
+        }
     }
+
+    if output.len() != total_size {
+        return Err(EnclaveError::FailedToDeserialize.into());
+    }
+
+    Ok(QuerySuccess { output })
 }
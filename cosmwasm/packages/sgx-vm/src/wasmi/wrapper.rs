@@ -10,6 +10,8 @@ use crate::{Querier, Storage, VmError};
 
 use enclave_ffi_types::{
     Ctx, HandleResult, InitResult, MigrateResult, QueryResult, UpdateAdminResult,
+    MAX_ADDRESS_LENGTH, MAX_ENV_LENGTH, MAX_MSG_LENGTH, MAX_PROOF_LENGTH, MAX_SIG_INFO_LENGTH,
+    MAX_WASM_LENGTH,
 };
 
 use sgx_types::sgx_status_t;
@@ -27,6 +29,9 @@ use super::results::{
     InitSuccess, QuerySuccess, UpdateAdminSuccess,
 };
 
+/// A job result, with a DCAP quote and its collateral
+const INITIAL_JOB_RESULT_CAPACITY: usize = 32 * 1024;
+
 pub struct Module<S, Q>
 where
     S: Storage,
@@ -110,6 +115,13 @@ where
             self.gas_left()
         );
 
+        check_input_length(&self.bytecode, MAX_WASM_LENGTH)?;
+        check_input_length(env, MAX_ENV_LENGTH)?;
+        check_input_length(msg, MAX_MSG_LENGTH)?;
+        check_input_length(sig_info, MAX_SIG_INFO_LENGTH)?;
+        check_input_length(admin, MAX_ADDRESS_LENGTH)?;
+        check_input_length(admin_proof, MAX_ENV_LENGTH)?;
+
         let mut migrate_result = MaybeUninit::<MigrateResult>::uninit();
         let mut used_gas = 0_u64;
 
@@ -171,6 +183,12 @@ where
             String::from_utf8_lossy(env),
         );
 
+        check_input_length(env, MAX_ENV_LENGTH)?;
+        check_input_length(sig_info, MAX_SIG_INFO_LENGTH)?;
+        check_input_length(current_admin, MAX_ADDRESS_LENGTH)?;
+        check_input_length(current_admin_proof, MAX_PROOF_LENGTH)?;
+        check_input_length(new_admin, MAX_ADDRESS_LENGTH)?;
+
         let mut update_admin_result = MaybeUninit::<UpdateAdminResult>::uninit();
 
         // Bind the token to a local variable to ensure its
@@ -222,6 +240,12 @@ where
             self.gas_left()
         );
 
+        check_input_length(&self.bytecode, MAX_WASM_LENGTH)?;
+        check_input_length(env, MAX_ENV_LENGTH)?;
+        check_input_length(msg, MAX_MSG_LENGTH)?;
+        check_input_length(sig_info, MAX_SIG_INFO_LENGTH)?;
+        check_input_length(admin, MAX_ADDRESS_LENGTH)?;
+
         let mut init_result = MaybeUninit::<InitResult>::uninit();
         let mut used_gas = 0_u64;
 
@@ -282,6 +306,11 @@ where
             self.gas_left()
         );
 
+        check_input_length(&self.bytecode, MAX_WASM_LENGTH)?;
+        check_input_length(env, MAX_ENV_LENGTH)?;
+        check_input_length(msg, MAX_MSG_LENGTH)?;
+        check_input_length(sig_info, MAX_SIG_INFO_LENGTH)?;
+
         let mut handle_result = MaybeUninit::<HandleResult>::uninit();
         let mut used_gas = 0_u64;
 
@@ -327,6 +356,63 @@ where
         }
     }
 
+    pub fn simulate(&mut self, env: &[u8], msg: &[u8], sig_info: &[u8]) -> VmResult<HandleSuccess> {
+        trace!(
+            "simulate() called with env: {:?} msg: {:?} gas_left: {}",
+            String::from_utf8_lossy(env),
+            String::from_utf8_lossy(msg),
+            self.gas_left()
+        );
+
+        check_input_length(&self.bytecode, MAX_WASM_LENGTH)?;
+        check_input_length(env, MAX_ENV_LENGTH)?;
+        check_input_length(msg, MAX_MSG_LENGTH)?;
+        check_input_length(sig_info, MAX_SIG_INFO_LENGTH)?;
+
+        let mut handle_result = MaybeUninit::<HandleResult>::uninit();
+        let mut used_gas = 0_u64;
+
+        // Bind the token to a local variable to ensure its
+        // destructor runs in the end of the function
+        let enclave_access_token = ENCLAVE_DOORBELL
+            .get_access(1) // This can never be recursive
+            .ok_or_else(Self::busy_enclave_err)?;
+        let enclave = enclave_access_token.map_err(EnclaveError::sdk_err)?;
+
+        let status = unsafe {
+            imports::ecall_simulate(
+                enclave.geteid(),
+                handle_result.as_mut_ptr(),
+                self.ctx.unsafe_clone(),
+                self.gas_left(),
+                &mut used_gas,
+                self.bytecode.as_ptr(),
+                self.bytecode.len(),
+                env.as_ptr(),
+                env.len(),
+                msg.as_ptr(),
+                msg.len(),
+                sig_info.as_ptr(),
+                sig_info.len(),
+            )
+        };
+
+        trace!(
+            "simulate() returned with gas_used: {} (gas_limit: {})",
+            used_gas,
+            self.gas_limit
+        );
+        self.consume_gas(used_gas);
+
+        match status {
+            sgx_status_t::SGX_SUCCESS => {
+                let handle_result = unsafe { handle_result.assume_init() };
+                handle_result_to_vm_result(handle_result)
+            }
+            failure_status => Err(EnclaveError::sdk_err(failure_status).into()),
+        }
+    }
+
     pub fn query(&mut self, env: &[u8], msg: &[u8]) -> VmResult<QuerySuccess> {
         trace!(
             "query() called with env: {:?} msg: {:?}",
@@ -334,6 +420,10 @@ where
             String::from_utf8_lossy(msg),
         );
 
+        check_input_length(&self.bytecode, MAX_WASM_LENGTH)?;
+        check_input_length(env, MAX_ENV_LENGTH)?;
+        check_input_length(msg, MAX_MSG_LENGTH)?;
+
         let mut query_result = MaybeUninit::<QueryResult>::uninit();
         let mut used_gas = 0_u64;
 
@@ -373,12 +463,103 @@ where
         match status {
             sgx_status_t::SGX_SUCCESS => {
                 let query_result = unsafe { query_result.assume_init() };
-                query_result_to_vm_result(query_result)
+                query_result_to_vm_result(query_result, |continuation_token| {
+                    let mut chunk_result = MaybeUninit::<QueryResult>::uninit();
+                    let status = unsafe {
+                        imports::ecall_query_chunk(
+                            enclave.geteid(),
+                            chunk_result.as_mut_ptr(),
+                            continuation_token,
+                        )
+                    };
+
+                    match status {
+                        sgx_status_t::SGX_SUCCESS => Ok(unsafe { chunk_result.assume_init() }),
+                        failure_status => Err(EnclaveError::sdk_err(failure_status).into()),
+                    }
+                })
             }
             failure_status => Err(EnclaveError::sdk_err(failure_status).into()),
         }
     }
 
+    /// Runs this module as the handler of `job`, which the contract in `env`, of
+    /// `contract_code_hash`, enqueued. Returns the signed and attested result, as json.
+    pub fn run_job(
+        &mut self,
+        contract_code_hash: &[u8; 32],
+        env: &[u8],
+        job: &[u8],
+    ) -> VmResult<Vec<u8>> {
+        trace!(
+            "run_job() called with env: {:?} job: {:?}",
+            String::from_utf8_lossy(env),
+            String::from_utf8_lossy(job),
+        );
+
+        check_input_length(&self.bytecode, MAX_WASM_LENGTH)?;
+        check_input_length(env, MAX_ENV_LENGTH)?;
+        check_input_length(job, MAX_MSG_LENGTH)?;
+
+        let doorbell = &ENCLAVE_DOORBELL;
+
+        // Bind the token to a local variable to ensure its
+        // destructor runs in the end of the function
+        let enclave_access_token = doorbell
+            .get_access(1) // Jobs run off-chain, outside of any query
+            .ok_or_else(Self::busy_enclave_err)?;
+        let enclave = enclave_access_token.map_err(EnclaveError::sdk_err)?;
+
+        let mut result = vec![0u8; INITIAL_JOB_RESULT_CAPACITY];
+        loop {
+            let mut retval = sgx_status_t::SGX_SUCCESS;
+            let mut used_gas = 0_u64;
+            let mut result_len = 0_u32;
+
+            let status = unsafe {
+                imports::ecall_run_job(
+                    enclave.geteid(),
+                    &mut retval,
+                    self.ctx.unsafe_clone(),
+                    self.gas_left(),
+                    &mut used_gas,
+                    self.bytecode.as_ptr(),
+                    self.bytecode.len(),
+                    contract_code_hash,
+                    env.as_ptr(),
+                    env.len(),
+                    job.as_ptr(),
+                    job.len(),
+                    result.as_mut_ptr(),
+                    result.len() as u32,
+                    &mut result_len,
+                )
+            };
+
+            trace!(
+                "run_job() returned with gas_used: {} (gas_limit: {})",
+                used_gas,
+                self.gas_limit
+            );
+            self.consume_gas(used_gas);
+
+            if status != sgx_status_t::SGX_SUCCESS {
+                return Err(EnclaveError::sdk_err(status).into());
+            }
+            match retval {
+                sgx_status_t::SGX_SUCCESS => {
+                    result.truncate(result_len as usize);
+                    return Ok(result);
+                }
+                // The enclave tells us the length of the result when it doesn't fit
+                sgx_status_t::SGX_ERROR_INVALID_PARAMETER if result_len as usize > result.len() => {
+                    result.resize(result_len as usize, 0);
+                }
+                failure_status => return Err(EnclaveError::sdk_err(failure_status).into()),
+            }
+        }
+    }
+
     fn consume_gas(&mut self, used_gas: u64) {
         self.used_gas = self.used_gas.saturating_add(used_gas);
     }
@@ -395,6 +576,15 @@ where
     }
 }
 
+/// Reject inputs that the enclave would reject anyway, before they are copied into it
+fn check_input_length(input: &[u8], max: usize) -> VmResult<()> {
+    if input.len() > max {
+        return Err(enclave_ffi_types::EnclaveError::InputTooLarge.into());
+    }
+
+    Ok(())
+}
+
 /// This type is used to extract the `query_depth` field which starts out at 1
 /// and is incremented every time a recursive query is called.
 /// We do not include the other fields of the Env here
@@ -64,6 +64,115 @@ func SubmitBlockSignatures(header []byte, commit []byte, txs []byte, encRandom [
 	return receiveVector(res.buf1), receiveVector(res.buf2), nil
 }
 
+// SubmitComputeParams hands the enclave the encoded params of the compute module, with the proof
+// of them against the app hash of the current block
+func SubmitComputeParams(params []byte, proof []byte) error {
+	errmsg := C.Buffer{}
+	paramsSlice := sendSlice(params)
+	defer freeAfterSend(paramsSlice)
+	proofSlice := sendSlice(proof)
+	defer freeAfterSend(proofSlice)
+
+	_, err := C.submit_compute_params(paramsSlice, proofSlice, &errmsg)
+	if err != nil {
+		return errorWithMessage(err, errmsg)
+	}
+	return nil
+}
+
+// ValidateEnclaveParams has the enclave check the enclave params of the encoded params of the
+// compute module, before governance sets them
+func ValidateEnclaveParams(params []byte) error {
+	errmsg := C.Buffer{}
+	paramsSlice := sendSlice(params)
+	defer freeAfterSend(paramsSlice)
+
+	_, err := C.validate_enclave_params(paramsSlice, &errmsg)
+	if err != nil {
+		return errorWithMessage(err, errmsg)
+	}
+	return nil
+}
+
+// CreateForeignClient has the enclave create the light client of another chain from a
+// MsgCreateForeignClient that governance passed, with the proof that the module stored it, and
+// returns the client to store
+func CreateForeignClient(msg []byte, msgProof []byte) ([]byte, error) {
+	errmsg := C.Buffer{}
+	msgSlice := sendSlice(msg)
+	defer freeAfterSend(msgSlice)
+	msgProofSlice := sendSlice(msgProof)
+	defer freeAfterSend(msgProofSlice)
+
+	res, err := C.create_foreign_client(msgSlice, msgProofSlice, &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// UpdateForeignClient has the enclave verify the header of a MsgUpdateForeignClient against the
+// stored light client, with the proof of it, and returns the updated client to store
+func UpdateForeignClient(client []byte, clientProof []byte, msg []byte) ([]byte, error) {
+	errmsg := C.Buffer{}
+	clientSlice := sendSlice(client)
+	defer freeAfterSend(clientSlice)
+	clientProofSlice := sendSlice(clientProof)
+	defer freeAfterSend(clientProofSlice)
+	msgSlice := sendSlice(msg)
+	defer freeAfterSend(msgSlice)
+
+	res, err := C.update_foreign_client(clientSlice, clientProofSlice, msgSlice, &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// SubmitForeignClient hands the enclave the stored light client of a chain with a proof of it, so
+// contracts are answered from it in the current block
+func SubmitForeignClient(chainID []byte, client []byte, proof []byte) error {
+	errmsg := C.Buffer{}
+	chainIDSlice := sendSlice(chainID)
+	defer freeAfterSend(chainIDSlice)
+	clientSlice := sendSlice(client)
+	defer freeAfterSend(clientSlice)
+	proofSlice := sendSlice(proof)
+	defer freeAfterSend(proofSlice)
+
+	_, err := C.submit_foreign_client(chainIDSlice, clientSlice, proofSlice, &errmsg)
+	if err != nil {
+		return errorWithMessage(err, errmsg)
+	}
+	return nil
+}
+
+// GetMempoolKey returns the public key users encrypt the txs they want included at height to
+func GetMempoolKey(height uint64) ([]byte, error) {
+	errmsg := C.Buffer{}
+	res, err := C.get_mempool_key(u64(height), &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// DecryptEncryptedTx has the enclave decrypt the tx of a MsgExecuteEncryptedTx that was included
+// in the last block, with the proof that the module stored it, and returns the tx
+func DecryptEncryptedTx(msg []byte, msgProof []byte) ([]byte, error) {
+	errmsg := C.Buffer{}
+	msgSlice := sendSlice(msg)
+	defer freeAfterSend(msgSlice)
+	msgProofSlice := sendSlice(msgProof)
+	defer freeAfterSend(msgProofSlice)
+
+	res, err := C.decrypt_encrypted_tx(msgSlice, msgProofSlice, &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
 func SubmitValidatorSetEvidence(evidence []byte) error {
 	errmsg := C.Buffer{}
 	evidenceSlice := sendSlice(evidence)
@@ -72,30 +181,37 @@ func SubmitValidatorSetEvidence(evidence []byte) error {
 	return nil
 }
 
-func InitBootstrap(spid []byte, apiKey []byte) ([]byte, error) {
+// InitBootstrap creates the consensus seeds of a new network, bound to chainID, and returns its
+// master public key
+func InitBootstrap(spid []byte, apiKey []byte, chainID string) ([]byte, error) {
 	errmsg := C.Buffer{}
 	spidSlice := sendSlice(spid)
 	defer freeAfterSend(spidSlice)
 	apiKeySlice := sendSlice(apiKey)
 	defer freeAfterSend(apiKeySlice)
+	chainIDSlice := sendSlice([]byte(chainID))
+	defer freeAfterSend(chainIDSlice)
 
-	res, err := C.init_bootstrap(spidSlice, apiKeySlice, &errmsg)
+	res, err := C.init_bootstrap(spidSlice, apiKeySlice, chainIDSlice, &errmsg)
 	if err != nil {
 		return nil, errorWithMessage(err, errmsg)
 	}
 	return receiveVector(res), nil
 }
 
-func LoadSeedToEnclave(masterKey []byte, seed []byte, apiKey []byte) (bool, error) {
+// LoadSeedToEnclave loads the seeds of a registered node of chainID into the enclave
+func LoadSeedToEnclave(masterKey []byte, seed []byte, apiKey []byte, chainID string) (bool, error) {
 	pkSlice := sendSlice(masterKey)
 	defer freeAfterSend(pkSlice)
 	seedSlice := sendSlice(seed)
 	defer freeAfterSend(seedSlice)
 	apiKeySlice := sendSlice(apiKey)
 	defer freeAfterSend(apiKeySlice)
+	chainIDSlice := sendSlice([]byte(chainID))
+	defer freeAfterSend(chainIDSlice)
 	errmsg := C.Buffer{}
 
-	_, err := C.init_node(pkSlice, seedSlice, apiKeySlice, &errmsg)
+	_, err := C.init_node(pkSlice, seedSlice, apiKeySlice, chainIDSlice, &errmsg)
 	if err != nil {
 		return false, errorWithMessage(err, errmsg)
 	}
@@ -150,11 +266,14 @@ func ReleaseCache(cache Cache) {
 	C.release_cache(cache.ptr)
 }
 
-func InitEnclaveRuntime(moduleCacheSize uint16) error {
+func InitEnclaveRuntime(moduleCacheSize uint16, queryLimits types.QueryLimits) error {
 	errmsg := C.Buffer{}
 
 	config := C.EnclaveRuntimeConfig{
-		module_cache_size: u32(moduleCacheSize),
+		module_cache_size:        u32(moduleCacheSize),
+		query_memory_limit_pages: u32(queryLimits.MemoryPages),
+		query_gas_limit:          u64(queryLimits.Gas),
+		query_time_limit_ms:      u64(queryLimits.TimeMs),
 	}
 	_, err := C.configure_enclave_runtime(config, &errmsg)
 	if err != nil {
@@ -164,6 +283,17 @@ func InitEnclaveRuntime(moduleCacheSize uint16) error {
 	return nil
 }
 
+// RunSelfTest runs the known-answer tests of the enclave's crypto. The enclave refuses consensus
+// calls until every test passed, and the error names the tests that failed.
+func RunSelfTest() error {
+	errmsg := C.Buffer{}
+	_, err := C.run_self_test(&errmsg)
+	if err != nil {
+		return errorWithMessage(err, errmsg)
+	}
+	return nil
+}
+
 func OnUpgradeProposalPassed(mrEnclaveHash []byte) error {
 	msgBuf := sendSlice(mrEnclaveHash)
 	defer freeAfterSend(msgBuf)
@@ -179,6 +309,275 @@ func OnUpgradeProposalPassed(mrEnclaveHash []byte) error {
 	return nil
 }
 
+// DiscloseContractKey re-encrypts the state key of a contract to an auditor, as authorized by a
+// MsgDiscloseContractKey that governance passed, with the proof that x/compute stored it
+func DiscloseContractKey(env []byte, codeHash []byte, admin []byte, adminProof []byte, msg []byte, msgProof []byte) ([]byte, error) {
+	errmsg := C.Buffer{}
+	envSlice := sendSlice(env)
+	defer freeAfterSend(envSlice)
+	codeHashSlice := sendSlice(codeHash)
+	defer freeAfterSend(codeHashSlice)
+	adminSlice := sendSlice(admin)
+	defer freeAfterSend(adminSlice)
+	adminProofSlice := sendSlice(adminProof)
+	defer freeAfterSend(adminProofSlice)
+	msgSlice := sendSlice(msg)
+	defer freeAfterSend(msgSlice)
+	msgProofSlice := sendSlice(msgProof)
+	defer freeAfterSend(msgProofSlice)
+	res, err := C.disclose_contract_key(envSlice, codeHashSlice, adminSlice, adminProofSlice, msgSlice, msgProofSlice, &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// ExportState wraps a chunk of a contract's pairs, as they're stored, for a backup of its state.
+// Pairs are encoded as len(key) || key || len(value) || value, with 4 byte big endian lengths.
+func ExportState(env []byte, codeHash []byte, admin []byte, adminProof []byte, authorization []byte, pairs []byte) ([]byte, error) {
+	errmsg := C.Buffer{}
+	envSlice := sendSlice(env)
+	defer freeAfterSend(envSlice)
+	codeHashSlice := sendSlice(codeHash)
+	defer freeAfterSend(codeHashSlice)
+	adminSlice := sendSlice(admin)
+	defer freeAfterSend(adminSlice)
+	adminProofSlice := sendSlice(adminProof)
+	defer freeAfterSend(adminProofSlice)
+	authorizationSlice := sendSlice(authorization)
+	defer freeAfterSend(authorizationSlice)
+	pairsSlice := sendSlice(pairs)
+	defer freeAfterSend(pairsSlice)
+	res, err := C.export_state(envSlice, codeHashSlice, adminSlice, adminProofSlice, authorizationSlice, pairsSlice, &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// ImportState unwraps a chunk of a backup of a contract's state, and returns its pairs encrypted
+// to be stored by the contract, encoded the same way as the pairs passed to ExportState
+func ImportState(env []byte, codeHash []byte, admin []byte, adminProof []byte, authorization []byte, backupChunk []byte) ([]byte, error) {
+	errmsg := C.Buffer{}
+	envSlice := sendSlice(env)
+	defer freeAfterSend(envSlice)
+	codeHashSlice := sendSlice(codeHash)
+	defer freeAfterSend(codeHashSlice)
+	adminSlice := sendSlice(admin)
+	defer freeAfterSend(adminSlice)
+	adminProofSlice := sendSlice(adminProof)
+	defer freeAfterSend(adminProofSlice)
+	authorizationSlice := sendSlice(authorization)
+	defer freeAfterSend(authorizationSlice)
+	backupChunkSlice := sendSlice(backupChunk)
+	defer freeAfterSend(backupChunkSlice)
+	res, err := C.import_state(envSlice, codeHashSlice, adminSlice, adminProofSlice, authorizationSlice, backupChunkSlice, &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// StateCommitmentLeaves returns the leaves of a chunk of a contract's pairs, as they're stored, 32
+// bytes per pair in the order of the pairs. Pairs are encoded the same way as for ExportState.
+func StateCommitmentLeaves(env []byte, codeHash []byte, pairs []byte) ([]byte, error) {
+	errmsg := C.Buffer{}
+	envSlice := sendSlice(env)
+	defer freeAfterSend(envSlice)
+	codeHashSlice := sendSlice(codeHash)
+	defer freeAfterSend(codeHashSlice)
+	pairsSlice := sendSlice(pairs)
+	defer freeAfterSend(pairsSlice)
+	res, err := C.state_commitment_leaves(envSlice, codeHashSlice, pairsSlice, &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// FoldStateCommitment folds a chunk of a contract's pairs, sorted by their leaves, into the
+// commitment to its state. progress is nil for the first chunk, and the output of the previous
+// call otherwise. After the last chunk it returns the signed commitment, as JSON.
+func FoldStateCommitment(env []byte, codeHash []byte, progress []byte, pairs []byte, lastChunk bool) ([]byte, error) {
+	errmsg := C.Buffer{}
+	envSlice := sendSlice(env)
+	defer freeAfterSend(envSlice)
+	codeHashSlice := sendSlice(codeHash)
+	defer freeAfterSend(codeHashSlice)
+	progressSlice := sendSlice(progress)
+	defer freeAfterSend(progressSlice)
+	pairsSlice := sendSlice(pairs)
+	defer freeAfterSend(pairsSlice)
+	res, err := C.fold_state_commitment(envSlice, codeHashSlice, progressSlice, pairsSlice, cbool(lastChunk), &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// ReencryptState re-encrypts the pairs of a chunk of a contract's pairs, as they're stored and in
+// the order of their keys, that are still under the genesis seed. progress is nil for the first
+// chunk, and the progress of the previous call otherwise. It returns JSON with the pairs to store
+// and the next progress.
+func ReencryptState(env []byte, codeHash []byte, progress []byte, pairs []byte, lastChunk bool) ([]byte, error) {
+	errmsg := C.Buffer{}
+	envSlice := sendSlice(env)
+	defer freeAfterSend(envSlice)
+	codeHashSlice := sendSlice(codeHash)
+	defer freeAfterSend(codeHashSlice)
+	progressSlice := sendSlice(progress)
+	defer freeAfterSend(progressSlice)
+	pairsSlice := sendSlice(pairs)
+	defer freeAfterSend(pairsSlice)
+	res, err := C.reencrypt_state(envSlice, codeHashSlice, progressSlice, pairsSlice, cbool(lastChunk), &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// ConsensusKeyInit loads the consensus key held by the enclave, generating it on first use,
+// and returns its Ed25519 public key
+func ConsensusKeyInit() ([]byte, error) {
+	errmsg := C.Buffer{}
+	res, err := C.consensus_key_init(&errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// ConsensusSign answers a protobuf encoded privval.Message with the enclave's consensus key,
+// and returns the encoded privval.Message response
+func ConsensusSign(request []byte) ([]byte, error) {
+	errmsg := C.Buffer{}
+	requestSlice := sendSlice(request)
+	defer freeAfterSend(requestSlice)
+	res, err := C.consensus_sign(requestSlice, &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// GetOcallStats returns how often the enclave made each ocall since it started, as json
+func GetOcallStats() ([]byte, error) {
+	errmsg := C.Buffer{}
+	res, err := C.get_ocall_stats(&errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// GetCrashBreadcrumbs returns the breadcrumbs the enclave sealed when it last panicked or hit a
+// fatal error, oldest first, as json
+func GetCrashBreadcrumbs() ([]byte, error) {
+	errmsg := C.Buffer{}
+	res, err := C.get_crash_breadcrumbs(&errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// GetSeedHeartbeatKey returns the public key seed heartbeats are verified against, which is the
+// same for every enclave that holds the consensus seed
+func GetSeedHeartbeatKey() ([]byte, error) {
+	errmsg := C.Buffer{}
+	res, err := C.get_seed_heartbeat_key(&errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// SeedHeartbeat signs a seed heartbeat at height, and returns the registration key of the node
+// and the signature
+func SeedHeartbeat(height uint64) ([]byte, []byte, error) {
+	errmsg := C.Buffer{}
+	res, err := C.seed_heartbeat(u64(height), &errmsg)
+	if err != nil {
+		return nil, nil, errorWithMessage(err, errmsg)
+	}
+	heartbeat := receiveVector(res)
+	if len(heartbeat) != 32+64 {
+		return nil, nil, fmt.Errorf("unexpected seed heartbeat length %d", len(heartbeat))
+	}
+	return heartbeat[:32], heartbeat[32:], nil
+}
+
+// TakeConformanceTrace returns the storage accesses the enclave recorded since the last call, as
+// a json array. Only enclaves built with the conformance feature record them.
+func TakeConformanceTrace() ([]byte, error) {
+	errmsg := C.Buffer{}
+	res, err := C.take_conformance_trace(&errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// TakeAuditTranscript returns the transcript of the contract executions since the last call, as
+// json. Only enclaves built with the audit feature record it, see docs/audit-builds.md.
+func TakeAuditTranscript() ([]byte, error) {
+	errmsg := C.Buffer{}
+	res, err := C.take_audit_transcript(&errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// TakeEngineDivergences returns the engine divergences the enclave reported since the last call,
+// as a json array. Only enclaves built with the differential-execution feature report them, see
+// docs/differential-execution.md.
+func TakeEngineDivergences() []byte {
+	return receiveVector(C.take_engine_divergences())
+}
+
+// ValidateCode checks a contract against the enclave's limits on code size and complexity, and
+// returns the validation report, as json. See docs/code-limits.md.
+func ValidateCode(code []byte) ([]byte, error) {
+	errmsg := C.Buffer{}
+	codeBuf := sendSlice(code)
+	defer freeAfterSend(codeBuf)
+	res, err := C.validate_code(codeBuf, &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// ModuleCacheSnapshot returns the modules the enclave has cached, with the hashes of their
+// instrumented artifacts and a digest of them signed by the enclave, as json. See
+// docs/module-cache-snapshots.md.
+func ModuleCacheSnapshot() ([]byte, error) {
+	errmsg := C.Buffer{}
+	res, err := C.module_cache_snapshot(&errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// PrewarmModule hints the enclave that a contract is likely to be executed in the next block, so
+// it can analyze the code ahead of time. It's only an optimization, and doesn't affect results.
+func PrewarmModule(code []byte) error {
+	codeBuf := sendSlice(code)
+	defer freeAfterSend(codeBuf)
+
+	ret, err := C.prewarm_module(codeBuf)
+	if err != nil {
+		return err
+	}
+	if !ret {
+		return errors.New("prewarm_module failed")
+	}
+
+	return nil
+}
+
 func Create(cache Cache, wasm []byte) ([]byte, error) {
 	code := sendSlice(wasm)
 	defer freeAfterSend(code)
@@ -404,6 +803,54 @@ func Handle(
 	return receiveVector(res), uint64(gasUsed), nil
 }
 
+// Simulate executes a tx the same way Handle does, but outside of any block and without
+// changing the enclave's state, so the node can meter its gas exactly. The store should be a
+// branch of the state that is discarded after the simulation.
+func Simulate(
+	cache Cache,
+	code_id []byte,
+	params []byte,
+	msg []byte,
+	gasMeter *GasMeter,
+	store KVStore,
+	api *GoAPI,
+	querier *Querier,
+	gasLimit uint64,
+	sigInfo []byte,
+) ([]byte, uint64, error) {
+	id := sendSlice(code_id)
+	defer freeAfterSend(id)
+	p := sendSlice(params)
+	defer freeAfterSend(p)
+	m := sendSlice(msg)
+	defer freeAfterSend(m)
+
+	// set up a new stack frame to handle iterators
+	counter := startContract()
+	defer endContract(counter)
+
+	dbState := buildDBState(store, counter)
+	db := buildDB(&dbState, gasMeter)
+	s := sendSlice(sigInfo)
+	defer freeAfterSend(s)
+	a := buildAPI(api)
+	q := buildQuerier(querier)
+	var gasUsed u64
+	errmsg := C.Buffer{}
+
+	//// This is done in order to ensure that goroutines don't
+	//// swap threads between recursive calls to the enclave.
+	//runtime.LockOSThread()
+	//defer runtime.UnlockOSThread()
+
+	res, err := C.simulate(cache.ptr, id, p, m, db, a, q, u64(gasLimit), &gasUsed, &errmsg, s)
+	if err != nil && err.(syscall.Errno) != C.ErrnoValue_Success {
+		// Depending on the nature of the error, `gasUsed` will either have a meaningful value, or just 0.
+		return nil, uint64(gasUsed), errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), uint64(gasUsed), nil
+}
+
 func Query(
 	cache Cache,
 	code_id []byte,
@@ -446,6 +893,47 @@ func Query(
 	return receiveVector(res), uint64(gasUsed), nil
 }
 
+// RunJob runs the code of `code_id` as the handler of an off-chain job, and returns the
+// result the enclave signed and attested, as json
+func RunJob(
+	cache Cache,
+	code_id []byte,
+	contractCodeHash []byte,
+	params []byte,
+	job []byte,
+	gasMeter *GasMeter,
+	store KVStore,
+	api *GoAPI,
+	querier *Querier,
+	gasLimit uint64,
+) ([]byte, uint64, error) {
+	id := sendSlice(code_id)
+	defer freeAfterSend(id)
+	h := sendSlice(contractCodeHash)
+	defer freeAfterSend(h)
+	p := sendSlice(params)
+	defer freeAfterSend(p)
+	j := sendSlice(job)
+	defer freeAfterSend(j)
+
+	// set up a new stack frame to handle iterators
+	counter := startContract()
+	defer endContract(counter)
+
+	dbState := buildDBState(store, counter)
+	db := buildDB(&dbState, gasMeter)
+	a := buildAPI(api)
+	q := buildQuerier(querier)
+	var gasUsed u64
+	errmsg := C.Buffer{}
+
+	res, err := C.run_job(cache.ptr, id, h, p, j, db, a, q, u64(gasLimit), &gasUsed, &errmsg)
+	if err != nil && err.(syscall.Errno) != C.ErrnoValue_Success {
+		return nil, uint64(gasUsed), errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), uint64(gasUsed), nil
+}
+
 func AnalyzeCode(
 	cache Cache,
 	codeHash []byte,
@@ -474,8 +962,19 @@ func KeyGen() ([]byte, error) {
 	return receiveVector(res), nil
 }
 
-// CreateAttestationReport Send CreateAttestationReport request to enclave
-func CreateAttestationReport(apiKey []byte, no_epid bool, no_dcap bool, is_migration_report bool) (bool, error) {
+// RotateNodeKeys replaces the registration key of the enclave, and returns the new public key
+func RotateNodeKeys() ([]byte, error) {
+	errmsg := C.Buffer{}
+	res, err := C.rotate_node_keys(&errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// CreateAttestationReport Send CreateAttestationReport request to enclave. With ed25519, the DCAP
+// quote attests an Ed25519 key along with the registration key, and there's no EPID report.
+func CreateAttestationReport(apiKey []byte, no_epid bool, no_dcap bool, is_migration_report bool, ed25519 bool) (bool, error) {
 	errmsg := C.Buffer{}
 	apiKeySlice := sendSlice(apiKey)
 	defer freeAfterSend(apiKeySlice)
@@ -490,6 +989,9 @@ func CreateAttestationReport(apiKey []byte, no_epid bool, no_dcap bool, is_migra
 	if is_migration_report {
 		flags |= u32(0x10)
 	}
+	if ed25519 {
+		flags |= u32(0x20)
+	}
 
 	_, err := C.create_attestation_report(apiKeySlice, flags, &errmsg)
 	if err != nil {
@@ -498,15 +1000,59 @@ func CreateAttestationReport(apiKey []byte, no_epid bool, no_dcap bool, is_migra
 	return true, nil
 }
 
-func GetEncryptedSeed(cert []byte) ([]byte, error) {
+// ProveKeyPossession appends a proof that the node holds its registration key to the attestation
+// certificate CreateAttestationReport wrote. masterKey is the node exchange key of the network, and
+// the proof is made for the block with the protobuf encoded header and commit, which it carries.
+func ProveKeyPossession(masterKey []byte, header []byte, commit []byte) error {
+	errmsg := C.Buffer{}
+	masterKeySlice := sendSlice(masterKey)
+	defer freeAfterSend(masterKeySlice)
+	headerSlice := sendSlice(header)
+	defer freeAfterSend(headerSlice)
+	commitSlice := sendSlice(commit)
+	defer freeAfterSend(commitSlice)
+
+	_, err := C.prove_key_possession(masterKeySlice, headerSlice, commitSlice, &errmsg)
+	if err != nil {
+		return errorWithMessage(err, errmsg)
+	}
+	return nil
+}
+
+// BindOperator appends the binding of the node to the validator with consensusKey to the
+// attestation certificate CreateAttestationReport wrote. signature is the validator's Ed25519
+// consensusKey signing the node's registration key on chainID.
+func BindOperator(consensusKey []byte, signature []byte, chainID string) error {
+	errmsg := C.Buffer{}
+	consensusKeySlice := sendSlice(consensusKey)
+	defer freeAfterSend(consensusKeySlice)
+	signatureSlice := sendSlice(signature)
+	defer freeAfterSend(signatureSlice)
+	chainIDSlice := sendSlice([]byte(chainID))
+	defer freeAfterSend(chainIDSlice)
+
+	_, err := C.bind_operator(consensusKeySlice, signatureSlice, chainIDSlice, &errmsg)
+	if err != nil {
+		return errorWithMessage(err, errmsg)
+	}
+	return nil
+}
+
+// GetEncryptedSeed authenticates a registering node and returns its encrypted seed, along with
+// the time (unix seconds) at which the earliest of its attestation collateral expires, or 0 if
+// unknown, and the AUTH_FLAG_* flags the enclave set on the registration. issuedHeight is the
+// height the chain first issued seeds to the node's key at, or 0 if it never did.
+func GetEncryptedSeed(cert []byte, issuedHeight uint64) ([]byte, int64, uint32, error) {
+	var collateralExpiration i64
+	var authFlags u32
 	errmsg := C.Buffer{}
 	certSlice := sendSlice(cert)
 	defer freeAfterSend(certSlice)
-	res, err := C.get_encrypted_seed(certSlice, &errmsg)
+	res, err := C.get_encrypted_seed(certSlice, u64(issuedHeight), &collateralExpiration, &authFlags, &errmsg)
 	if err != nil {
-		return nil, errorWithMessage(err, errmsg)
+		return nil, 0, 0, errorWithMessage(err, errmsg)
 	}
-	return receiveVector(res), nil
+	return receiveVector(res), int64(collateralExpiration), uint32(authFlags), nil
 }
 
 func GetEncryptedGenesisSeed(pk []byte) ([]byte, error) {
@@ -520,6 +1066,86 @@ func GetEncryptedGenesisSeed(pk []byte) ([]byte, error) {
 	return receiveVector(res), nil
 }
 
+// PlatformCensus returns how many of the platforms that registered are at each TCB status, TCB
+// date and advisory, signed by the enclave, as json. See docs/platform-census.md.
+func PlatformCensus() ([]byte, error) {
+	errmsg := C.Buffer{}
+	res, err := C.platform_census(&errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// AttestCodeHash returns a DCAP quote that attests the code with codeHash runs under this node's
+// enclave on chainID at height, with the statement it attests, as json. See
+// docs/code-attestation.md.
+func AttestCodeHash(codeHash []byte, chainID string, height uint64) ([]byte, error) {
+	errmsg := C.Buffer{}
+	codeHashSlice := sendSlice(codeHash)
+	defer freeAfterSend(codeHashSlice)
+	chainIDSlice := sendSlice([]byte(chainID))
+	defer freeAfterSend(chainIDSlice)
+	res, err := C.attest_code_hash(codeHashSlice, chainIDSlice, u64(height), &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// CreateRegistrationPackage returns a registration package of the node's registration key: the
+// combined certificate, the key and what the enclave read from its report, as json. With ed25519,
+// an Ed25519 key is attested along with the registration key. See docs/registration-packages.md.
+func CreateRegistrationPackage(ed25519 bool) ([]byte, error) {
+	errmsg := C.Buffer{}
+	res, err := C.create_registration_package(cbool(ed25519), &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// VerifyRegistrationPackage verifies a registration package the way a registration of its
+// certificate would be, without registering it, and returns the time (unix seconds) at which the
+// earliest of its collateral expires
+func VerifyRegistrationPackage(pkg []byte) (int64, error) {
+	var collateralExpiration i64
+	errmsg := C.Buffer{}
+	pkgSlice := sendSlice(pkg)
+	defer freeAfterSend(pkgSlice)
+	_, err := C.verify_registration_package(pkgSlice, &collateralExpiration, &errmsg)
+	if err != nil {
+		return 0, errorWithMessage(err, errmsg)
+	}
+	return int64(collateralExpiration), nil
+}
+
+// OpenQuerySession opens a short-lived query session in the enclave, valid until expiryHeight,
+// and returns its id
+func OpenQuerySession(nonce []byte, userPublicKey []byte, currentHeight uint64, expiryHeight uint64) ([]byte, error) {
+	errmsg := C.Buffer{}
+	nonceSlice := sendSlice(nonce)
+	defer freeAfterSend(nonceSlice)
+	pkSlice := sendSlice(userPublicKey)
+	defer freeAfterSend(pkSlice)
+	res, err := C.open_query_session(nonceSlice, pkSlice, u64(currentHeight), u64(expiryHeight), &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// CancelQuery cancels the query that was given cancellationID in its env, which stops the next
+// time it calls into the enclave. Cancelling a query that already returned does nothing.
+func CancelQuery(cancellationID uint64) error {
+	errmsg := C.Buffer{}
+	_, err := C.cancel_query(u64(cancellationID), &errmsg)
+	if err != nil {
+		return errorWithMessage(err, errmsg)
+	}
+	return nil
+}
+
 /**** To error module ***/
 
 func errorWithMessage(err error, b C.Buffer) error {
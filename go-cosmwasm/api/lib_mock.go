@@ -35,7 +35,7 @@ func HealthCheck() ([]byte, error) {
 	return nil, nil
 }
 
-func InitBootstrap(spid []byte, apiKey []byte) ([]byte, error) {
+func InitBootstrap(spid []byte, apiKey []byte, chainID string) ([]byte, error) {
 	return nil, nil
 }
 
@@ -43,11 +43,39 @@ func SubmitBlockSignatures(header []byte, commit []byte, txs []byte, random []by
 	return nil, nil, nil
 }
 
+func SubmitComputeParams(params []byte, proof []byte) error {
+	return nil
+}
+
+func ValidateEnclaveParams(params []byte) error {
+	return nil
+}
+
+func CreateForeignClient(msg []byte, msgProof []byte) ([]byte, error) {
+	return nil, nil
+}
+
+func UpdateForeignClient(client []byte, clientProof []byte, msg []byte) ([]byte, error) {
+	return nil, nil
+}
+
+func SubmitForeignClient(chainID []byte, client []byte, proof []byte) error {
+	return nil
+}
+
+func GetMempoolKey(height uint64) ([]byte, error) {
+	return nil, nil
+}
+
+func DecryptEncryptedTx(msg []byte, msgProof []byte) ([]byte, error) {
+	return nil, nil
+}
+
 func SubmitValidatorSetEvidence(evidence []byte) error {
 	return nil
 }
 
-func LoadSeedToEnclave(masterKey []byte, seed []byte, apiKey []byte) (bool, error) {
+func LoadSeedToEnclave(masterKey []byte, seed []byte, apiKey []byte, chainID string) (bool, error) {
 	return true, nil
 }
 
@@ -79,7 +107,11 @@ func ReleaseCache(cache Cache) {
 	// C.release_cache(cache.ptr)
 }
 
-func InitEnclaveRuntime(ModuleCacheSize uint16) error {
+func InitEnclaveRuntime(ModuleCacheSize uint16, QueryLimits types.QueryLimits) error {
+	return nil
+}
+
+func RunSelfTest() error {
 	return nil
 }
 
@@ -207,6 +239,21 @@ func Handle(
 	return nil, 0, nil
 }
 
+func Simulate(
+	cache Cache,
+	code_id []byte,
+	params []byte,
+	msg []byte,
+	gasMeter *GasMeter,
+	store KVStore,
+	api *GoAPI,
+	querier *Querier,
+	gasLimit uint64,
+	sigInfo []byte,
+) ([]byte, uint64, error) {
+	return nil, 0, nil
+}
+
 func Query(
 	cache Cache,
 	code_id []byte,
@@ -236,6 +283,21 @@ func Query(
 	return nil, 0, nil
 }
 
+func RunJob(
+	cache Cache,
+	code_id []byte,
+	contractCodeHash []byte,
+	params []byte,
+	job []byte,
+	gasMeter *GasMeter,
+	store KVStore,
+	api *GoAPI,
+	querier *Querier,
+	gasLimit uint64,
+) ([]byte, uint64, error) {
+	return nil, 0, nil
+}
+
 func AnalyzeCode(
 	cache Cache,
 	codeHash []byte,
@@ -267,8 +329,18 @@ func KeyGen() ([]byte, error) {
 	return nil, nil
 }
 
+func RotateNodeKeys() ([]byte, error) {
+	//errmsg := C.Buffer{}
+	//res, err := C.rotate_node_keys(&errmsg)
+	//if err != nil {
+	//	return nil, errorWithMessage(err, errmsg)
+	//}
+	//return receiveVector(res), nil
+	return nil, nil
+}
+
 // KeyGen Seng KeyGen request to enclave
-func CreateAttestationReport(apiKey []byte, no_epid bool, no_dcap bool, is_migration_report bool) (bool, error) {
+func CreateAttestationReport(apiKey []byte, no_epid bool, no_dcap bool, is_migration_report bool, ed25519 bool) (bool, error) {
 	//errmsg := C.Buffer{}
 	//_, err := C.create_attestation_report(&errmsg)
 	//if err != nil {
@@ -277,22 +349,56 @@ func CreateAttestationReport(apiKey []byte, no_epid bool, no_dcap bool, is_migra
 	return true, nil
 }
 
-func GetEncryptedSeed(cert []byte) ([]byte, error) {
+func ProveKeyPossession(masterKey []byte, header []byte, commit []byte) error {
+	return nil
+}
+
+func BindOperator(consensusKey []byte, signature []byte, chainID string) error {
+	return nil
+}
+
+func GetEncryptedSeed(cert []byte, issuedHeight uint64) ([]byte, int64, uint32, error) {
+	//var collateralExpiration i64
+	//var authFlags u32
 	//errmsg := C.Buffer{}
 	//certSlice := sendSlice(cert)
 	//defer freeAfterSend(certSlice)
-	//res, err := C.get_encrypted_seed(certSlice, &errmsg)
+	//res, err := C.get_encrypted_seed(certSlice, u64(issuedHeight), &collateralExpiration, &authFlags, &errmsg)
 	//if err != nil {
-	//	return nil, errorWithMessage(err, errmsg)
+	//	return nil, 0, 0, errorWithMessage(err, errmsg)
 	//}
-	//return receiveVector(res), nil
-	return nil, nil
+	//return receiveVector(res), int64(collateralExpiration), uint32(authFlags), nil
+	return nil, 0, 0, nil
 }
 
 func GetEncryptedGenesisSeed(cert []byte) ([]byte, error) {
 	return nil, nil
 }
 
+func PlatformCensus() ([]byte, error) {
+	return nil, nil
+}
+
+func AttestCodeHash(codeHash []byte, chainID string, height uint64) ([]byte, error) {
+	return nil, nil
+}
+
+func CreateRegistrationPackage(ed25519 bool) ([]byte, error) {
+	return nil, nil
+}
+
+func VerifyRegistrationPackage(pkg []byte) (int64, error) {
+	return 0, nil
+}
+
+func OpenQuerySession(nonce []byte, userPublicKey []byte, currentHeight uint64, expiryHeight uint64) ([]byte, error) {
+	return nil, nil
+}
+
+func CancelQuery(cancellationID uint64) error {
+	return nil
+}
+
 /**** To error module ***/
 
 //func errorWithMessage(err error, b C.Buffer) error {
@@ -307,3 +413,75 @@ func GetEncryptedGenesisSeed(cert []byte) ([]byte, error) {
 func OnUpgradeProposalPassed(mrEnclaveHash []byte) error {
 	return nil
 }
+
+func DiscloseContractKey(env []byte, codeHash []byte, admin []byte, adminProof []byte, msg []byte, msgProof []byte) ([]byte, error) {
+	return nil, nil
+}
+
+func ConsensusKeyInit() ([]byte, error) {
+	return nil, nil
+}
+
+func ConsensusSign(request []byte) ([]byte, error) {
+	return nil, nil
+}
+
+func GetOcallStats() ([]byte, error) {
+	return nil, nil
+}
+
+func GetCrashBreadcrumbs() ([]byte, error) {
+	return nil, nil
+}
+
+func GetSeedHeartbeatKey() ([]byte, error) {
+	return nil, nil
+}
+
+func SeedHeartbeat(height uint64) ([]byte, []byte, error) {
+	return nil, nil, nil
+}
+
+func TakeConformanceTrace() ([]byte, error) {
+	return nil, nil
+}
+
+func TakeAuditTranscript() ([]byte, error) {
+	return nil, nil
+}
+
+func TakeEngineDivergences() []byte {
+	return nil
+}
+
+func ExportState(env []byte, codeHash []byte, admin []byte, adminProof []byte, authorization []byte, pairs []byte) ([]byte, error) {
+	return nil, nil
+}
+
+func ImportState(env []byte, codeHash []byte, admin []byte, adminProof []byte, authorization []byte, backupChunk []byte) ([]byte, error) {
+	return nil, nil
+}
+
+func StateCommitmentLeaves(env []byte, codeHash []byte, pairs []byte) ([]byte, error) {
+	return nil, nil
+}
+
+func FoldStateCommitment(env []byte, codeHash []byte, progress []byte, pairs []byte, lastChunk bool) ([]byte, error) {
+	return nil, nil
+}
+
+func ReencryptState(env []byte, codeHash []byte, progress []byte, pairs []byte, lastChunk bool) ([]byte, error) {
+	return nil, nil
+}
+
+func ValidateCode(code []byte) ([]byte, error) {
+	return []byte(`{"valid":true,"violations":[]}`), nil
+}
+
+func ModuleCacheSnapshot() ([]byte, error) {
+	return nil, nil
+}
+
+func PrewarmModule(code []byte) error {
+	return nil
+}
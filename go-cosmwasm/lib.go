@@ -25,6 +25,9 @@ type GoAPI = api.GoAPI
 // Querier lets us make read-only queries on other modules
 type Querier = types.Querier
 
+// QueryLimits are the limits of queries from outside the chain
+type QueryLimits = types.QueryLimits
+
 // GasMeter is a read-only version of the sdk gas meter
 type GasMeter = api.GasMeter
 
@@ -32,7 +35,8 @@ type GasMeter = api.GasMeter
 // You should create an instance with it's own subdirectory to manage state inside,
 // and call it for all cosmwasm code related actions.
 type Wasmer struct {
-	cache api.Cache
+	cache         api.Cache
+	reportHandler func(code CodeHash, report types.ExecutionReport)
 }
 
 // NewWasmer creates a new binding, with the given dataDir where
@@ -40,13 +44,19 @@ type Wasmer struct {
 // cacheSize sets the size of an optional in-memory LRU cache for prepared VMs.
 // They allow popular contracts to be executed very rapidly (no loading overhead),
 // but require ~32-64MB each in memory usage.
-func NewWasmer(dataDir string, supportedFeatures string, cacheSize uint64, moduleCacheSize uint16, initEnclave bool) (*Wasmer, error) {
+// queryLimits are the limits of queries from outside the chain, enforced in the enclave.
+func NewWasmer(dataDir string, supportedFeatures string, cacheSize uint64, moduleCacheSize uint16, queryLimits QueryLimits, initEnclave bool) (*Wasmer, error) {
 	cache, err := api.InitCache(dataDir, supportedFeatures, cacheSize)
 	if err != nil {
 		return nil, err
 	}
 	if initEnclave {
-		err = api.InitEnclaveRuntime(moduleCacheSize)
+		err = api.InitEnclaveRuntime(moduleCacheSize, queryLimits)
+		if err != nil {
+			return nil, err
+		}
+		// The enclave refuses to execute contracts unless its self-test passed
+		err = api.RunSelfTest()
 		if err != nil {
 			return nil, err
 		}
@@ -55,6 +65,12 @@ func NewWasmer(dataDir string, supportedFeatures string, cacheSize uint64, modul
 	return &Wasmer{cache: cache}, nil
 }
 
+// SetExecutionReportHandler sets a function that gets the report of every execution the enclave
+// returns one for. It's called synchronously, before Execute returns.
+func (w *Wasmer) SetExecutionReportHandler(handler func(code CodeHash, report types.ExecutionReport)) {
+	w.reportHandler = handler
+}
+
 // Cleanup should be called when no longer using this to free resources on the rust-side
 func (w *Wasmer) Cleanup() {
 	api.ReleaseCache(w.cache)
@@ -94,6 +110,7 @@ type ContractExecResponse struct {
 	IBCBasic               *v1types.IBCBasicResult       `json:"ibc_basic,omitempty"`
 	IBCPacketReceive       *v1types.IBCReceiveResult     `json:"ibc_packet_receive,omitempty"`
 	IBCChannelOpen         *v1types.IBCOpenChannelResult `json:"ibc_open_channel,omitempty"`
+	ExecutionReport        *types.ExecutionReport        `json:"execution_report,omitempty"`
 }
 
 type V010ContractExecResponse struct {
@@ -268,6 +285,10 @@ func (w *Wasmer) Execute(
 		return nil, gasUsed, fmt.Errorf("handle: cannot parse response from json: %w", err)
 	}
 
+	if resp.ExecutionReport != nil && w.reportHandler != nil {
+		w.reportHandler(code, *resp.ExecutionReport)
+	}
+
 	isOutputAddressedToReply := len(resp.InternaReplyEnclaveSig) > 0 && len(resp.InternalMsgId) > 0
 
 	// handle v0.10 response
@@ -341,6 +362,50 @@ func (w *Wasmer) Execute(
 	return nil, gasUsed, fmt.Errorf("handle: cannot detect response type (v0.10 or v1)")
 }
 
+// Simulate executes a tx against a contract outside of any block, and returns the raw
+// (encrypted) response of the contract and the gas it used. The enclave's state isn't changed, but the
+// writes of the contract go to `store`, so the caller should pass a branch of the state that is
+// discarded afterwards.
+func (w *Wasmer) Simulate(
+	code CodeHash,
+	env types.Env,
+	executeMsg []byte,
+	store KVStore,
+	goapi GoAPI,
+	querier Querier,
+	gasMeter GasMeter,
+	gasLimit uint64,
+	sigInfo types.SigInfo,
+) ([]byte, uint64, error) {
+	paramBin, err := json.Marshal(env)
+	if err != nil {
+		return nil, 0, err
+	}
+	sigInfoBin, err := json.Marshal(sigInfo)
+	if err != nil {
+		return nil, 0, err
+	}
+
+	data, gasUsed, err := api.Simulate(w.cache, code, paramBin, executeMsg, &gasMeter, store, &goapi, &querier, gasLimit, sigInfoBin)
+	if err != nil {
+		return nil, gasUsed, err
+	}
+
+	var resp ContractExecResponse
+	err = json.Unmarshal(data, &resp)
+	if err != nil {
+		return nil, gasUsed, fmt.Errorf("simulate: cannot parse response from json: %w", err)
+	}
+	if resp.V010 != nil && resp.V010.Err != nil {
+		return nil, gasUsed, fmt.Errorf("%+v", resp.V010.Err)
+	}
+	if resp.V1 != nil && resp.V1.Err != nil {
+		return nil, gasUsed, fmt.Errorf("%+v", resp.V1.Err)
+	}
+
+	return data, gasUsed, nil
+}
+
 // Query allows a client to execute a contract-specific query. If the result is not empty, it should be
 // valid json-encoded data to return to the client.
 // The meaning of path and data can be determined by the code. Path is the suffix of the abci.QueryRequest.Path
@@ -384,6 +449,27 @@ func (w *Wasmer) Query(
 	return nil, gasUsed, fmt.Errorf("query: cannot detect response type")
 }
 
+// RunJob runs the code of `handler` as the handler of an off-chain job the contract in `env`
+// enqueued, against `store`, which the caller should throw away. It returns the result the
+// enclave signed and attested, as the json to submit in a MsgSubmitJobResult.
+func (w *Wasmer) RunJob(
+	handler CodeHash,
+	contractCodeHash []byte,
+	env types.Env,
+	job []byte,
+	store KVStore,
+	goapi GoAPI,
+	querier Querier,
+	gasMeter GasMeter,
+	gasLimit uint64,
+) ([]byte, uint64, error) {
+	paramBin, err := json.Marshal(env)
+	if err != nil {
+		return nil, 0, err
+	}
+	return api.RunJob(w.cache, handler, contractCodeHash, paramBin, job, &gasMeter, store, &goapi, &querier, gasLimit)
+}
+
 // AnalyzeCode returns a report of static analysis of the wasm contract (uncompiled).
 // This contract must have been stored in the cache previously (via Create).
 // Only info currently returned is if it exposes all ibc entry points, but this may grow later
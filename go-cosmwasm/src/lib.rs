@@ -11,15 +11,16 @@ use crate::error::{clear_error, handle_c_error, handle_c_error_default, set_erro
 pub use api::GoApi;
 use base64;
 use cosmwasm_sgx_vm::{
-    call_handle_raw, call_init_raw, call_migrate_raw, call_query_raw, call_update_admin_raw,
-    create_attestation_report_u, features_from_csv, untrusted_approve_upgrade,
-    untrusted_get_encrypted_genesis_seed, untrusted_get_encrypted_seed, untrusted_health_check,
-    untrusted_init_bootstrap, untrusted_init_node, untrusted_key_gen, untrusted_migration_op,
-    untrusted_submit_validator_set_evidence, Checksum, CosmCache, Extern,
+    call_handle_raw, call_init_raw, call_migrate_raw, call_query_raw, call_run_job_raw,
+    call_simulate_raw, call_update_admin_raw, features_from_csv, Checksum, CosmCache, Extern,
 };
 use ctor::ctor;
 pub use db::{db_t, DB};
 use ed25519_dalek::{Keypair, Signature, Signer};
+use enclave_api::{
+    AdminCall, BlockSignatures, InitNode, KeyScheme, MigrationOp, QuerySession, StateCommitment,
+    StateReencryption,
+};
 use hex;
 use log::*;
 use logger::get_log_level;
@@ -54,7 +55,7 @@ fn to_cache(ptr: *mut cache_t) -> Option<&'static mut CosmCache<DB, GoApi, GoQue
 
 #[no_mangle]
 pub extern "C" fn get_health_check(err: Option<&mut Buffer>) -> Buffer {
-    match untrusted_health_check() {
+    match enclave_api::health_check() {
         Err(e) => {
             set_error(Error::enclave_err(e.to_string()), err);
             Buffer::default()
@@ -67,7 +68,13 @@ pub extern "C" fn get_health_check(err: Option<&mut Buffer>) -> Buffer {
 }
 
 #[no_mangle]
-pub extern "C" fn get_encrypted_seed(cert: Buffer, err: Option<&mut Buffer>) -> Buffer {
+pub extern "C" fn get_encrypted_seed(
+    cert: Buffer,
+    issued_height: u64,
+    collateral_expiration: Option<&mut i64>,
+    auth_flags: Option<&mut u32>,
+    err: Option<&mut Buffer>,
+) -> Buffer {
     trace!("Called get_encrypted_seed");
     let cert_slice = match unsafe { cert.read() } {
         None => {
@@ -76,21 +83,22 @@ pub extern "C" fn get_encrypted_seed(cert: Buffer, err: Option<&mut Buffer>) ->
         }
         Some(r) => r,
     };
-    trace!("Hello from right before untrusted_get_encrypted_seed");
-    match untrusted_get_encrypted_seed(cert_slice) {
+    trace!("Hello from right before authenticate_new_node");
+    match enclave_api::authenticate_new_node(cert_slice, issued_height) {
         Err(e) => {
-            // An error happened in the SGX sdk.
+            // An error happened in the SGX sdk, or the enclave rejected the node
             set_error(Error::enclave_err(e.to_string()), err);
             Buffer::default()
         }
-        Ok(Err(e)) => {
-            // An error was returned from the enclave.
-            set_error(Error::enclave_err(e.to_string()), err);
-            Buffer::default()
-        }
-        Ok(Ok(seed)) => {
+        Ok(encrypted) => {
+            if let Some(collateral_expiration) = collateral_expiration {
+                *collateral_expiration = encrypted.collateral_expiration;
+            }
+            if let Some(auth_flags) = auth_flags {
+                *auth_flags = encrypted.auth_flags;
+            }
             clear_error();
-            Buffer::from_vec(seed.to_vec())
+            Buffer::from_vec(encrypted.seed)
         }
     }
 }
@@ -105,8 +113,8 @@ pub extern "C" fn get_encrypted_genesis_seed(pk: Buffer, err: Option<&mut Buffer
         }
         Some(r) => r,
     };
-    trace!("Hello from right before untrusted_get_encrypted_genesis_seed");
-    match untrusted_get_encrypted_genesis_seed(pk_slice) {
+    trace!("Hello from right before encrypted_genesis_seed");
+    match enclave_api::encrypted_genesis_seed(pk_slice) {
         Err(e) => {
             // An error happened in the SGX sdk.
             set_error(Error::enclave_err(e.to_string()), err);
@@ -114,15 +122,182 @@ pub extern "C" fn get_encrypted_genesis_seed(pk: Buffer, err: Option<&mut Buffer
         }
         Ok(seed) => {
             clear_error();
-            Buffer::from_vec(seed.to_vec())
+            Buffer::from_vec(seed)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn platform_census(err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called platform_census");
+    match enclave_api::platform_census() {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(census) => {
+            clear_error();
+            Buffer::from_vec(census)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn attest_code_hash(
+    code_hash: Buffer,
+    chain_id: Buffer,
+    height: u64,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    trace!("Called attest_code_hash");
+    let code_hash: &[u8; 32] = match unsafe { code_hash.read() }.map(|r| r.try_into()) {
+        None => {
+            set_error(Error::empty_arg("code_hash"), err);
+            return Buffer::default();
+        }
+        Some(Err(_)) => {
+            set_error(Error::vm_err("code_hash must be 32 bytes"), err);
+            return Buffer::default();
+        }
+        Some(Ok(r)) => r,
+    };
+    let chain_id = match unsafe { chain_id.read() }.map(from_utf8) {
+        None => {
+            set_error(Error::empty_arg("chain_id"), err);
+            return Buffer::default();
+        }
+        Some(Err(_)) => {
+            set_error(Error::vm_err("chain_id must be utf-8"), err);
+            return Buffer::default();
+        }
+        Some(Ok(r)) => r,
+    };
+
+    match enclave_api::attest_code_hash(code_hash, chain_id, height) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(attestation) => {
+            clear_error();
+            Buffer::from_vec(attestation)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn create_registration_package(ed25519: bool, err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called create_registration_package");
+    let scheme = if ed25519 {
+        KeyScheme::Ed25519X25519
+    } else {
+        KeyScheme::X25519
+    };
+
+    match enclave_api::create_registration_package(scheme) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(package) => {
+            clear_error();
+            Buffer::from_vec(package)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn verify_registration_package(
+    package: Buffer,
+    collateral_expiration: Option<&mut i64>,
+    err: Option<&mut Buffer>,
+) -> bool {
+    trace!("Called verify_registration_package");
+    let package_slice = match unsafe { package.read() } {
+        None => {
+            set_error(Error::empty_arg("package"), err);
+            return false;
+        }
+        Some(r) => r,
+    };
+
+    match enclave_api::verify_registration_package(package_slice) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            false
+        }
+        Ok(expiration) => {
+            if let Some(collateral_expiration) = collateral_expiration {
+                *collateral_expiration = expiration;
+            }
+            clear_error();
+            true
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn open_query_session(
+    nonce: Buffer,
+    user_public_key: Buffer,
+    current_height: u64,
+    expiry_height: u64,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    trace!("Called open_query_session");
+    let nonce: &[u8; 32] = match unsafe { nonce.read() }.map(|r| r.try_into()) {
+        None => {
+            set_error(Error::empty_arg("nonce"), err);
+            return Buffer::default();
+        }
+        Some(Err(_)) => {
+            set_error(Error::vm_err("nonce must be 32 bytes"), err);
+            return Buffer::default();
+        }
+        Some(Ok(r)) => r,
+    };
+    let user_public_key: &[u8; 32] = match unsafe { user_public_key.read() }.map(|r| r.try_into())
+    {
+        None => {
+            set_error(Error::empty_arg("user_public_key"), err);
+            return Buffer::default();
+        }
+        Some(Err(_)) => {
+            set_error(Error::vm_err("user_public_key must be 32 bytes"), err);
+            return Buffer::default();
+        }
+        Some(Ok(r)) => r,
+    };
+
+    match QuerySession::new(nonce, user_public_key)
+        .heights(current_height, expiry_height)
+        .open()
+    {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(session_id) => {
+            clear_error();
+            Buffer::from_vec(session_id.to_vec())
         }
     }
 }
 
+#[no_mangle]
+pub extern "C" fn cancel_query(cancellation_id: u64, err: Option<&mut Buffer>) {
+    trace!("Called cancel_query");
+    match enclave_api::cancel_query(cancellation_id) {
+        Err(e) => set_error(Error::enclave_err(e.to_string()), err),
+        Ok(()) => clear_error(),
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn init_bootstrap(
     spid: Buffer,
     api_key: Buffer,
+    chain_id: Buffer,
     err: Option<&mut Buffer>,
 ) -> Buffer {
     trace!("Hello from right before init_bootstrap");
@@ -143,7 +318,24 @@ pub extern "C" fn init_bootstrap(
         Some(r) => r,
     };
 
-    match untrusted_init_bootstrap(spid_slice, api_key_slice) {
+    let chain_id = match unsafe { chain_id.read() }.map(from_utf8) {
+        None => {
+            set_error(Error::empty_arg("chain_id"), err);
+            return Buffer::default();
+        }
+        Some(Err(_)) => {
+            set_error(Error::vm_err("chain_id must be utf-8"), err);
+            return Buffer::default();
+        }
+        Some(Ok(r)) => r,
+    };
+
+    match enclave_api::Bootstrap::new()
+        .spid(spid_slice)
+        .api_key(api_key_slice)
+        .chain_id(chain_id)
+        .run()
+    {
         Err(e) => {
             set_error(Error::enclave_err(e.to_string()), err);
             Buffer::default()
@@ -160,6 +352,7 @@ pub extern "C" fn init_node(
     master_key: Buffer,
     encrypted_seed: Buffer,
     api_key: Buffer,
+    chain_id: Buffer,
     err: Option<&mut Buffer>,
 ) -> bool {
     let pk_slice = match unsafe { master_key.read() } {
@@ -183,9 +376,25 @@ pub extern "C" fn init_node(
         }
         Some(r) => r,
     };
+    let chain_id = match unsafe { chain_id.read() }.map(from_utf8) {
+        None => {
+            set_error(Error::empty_arg("chain_id"), err);
+            return false;
+        }
+        Some(Err(_)) => {
+            set_error(Error::vm_err("chain_id must be utf-8"), err);
+            return false;
+        }
+        Some(Ok(r)) => r,
+    };
 
-    match untrusted_init_node(pk_slice, encrypted_seed_slice, api_key_slice) {
-        Ok(()) => {
+    match InitNode::new(pk_slice, encrypted_seed_slice)
+        .api_key(api_key_slice)
+        .chain_id(chain_id)
+        .run()
+    {
+        // cosmwasm-sgx-vm logs which seeds were loaded
+        Ok(_seed_status) => {
             clear_error();
             true
         }
@@ -210,7 +419,7 @@ pub extern "C" fn create_attestation_report(
         Some(r) => r,
     };
 
-    if let Err(status) = create_attestation_report_u(api_key_slice, flags) {
+    if let Err(status) = enclave_api::create_attestation_report(api_key_slice, flags) {
         set_error(Error::enclave_err(status.to_string()), err);
         return false;
     }
@@ -218,6 +427,85 @@ pub extern "C" fn create_attestation_report(
     true
 }
 
+#[no_mangle]
+pub extern "C" fn prove_key_possession(
+    master_key: Buffer,
+    header: Buffer,
+    commit: Buffer,
+    err: Option<&mut Buffer>,
+) -> bool {
+    let master_key_slice = match unsafe { master_key.read() } {
+        None => {
+            set_error(Error::empty_arg("master_key"), err);
+            return false;
+        }
+        Some(r) => r,
+    };
+    let header_slice = match unsafe { header.read() } {
+        None => {
+            set_error(Error::empty_arg("header"), err);
+            return false;
+        }
+        Some(r) => r,
+    };
+    let commit_slice = match unsafe { commit.read() } {
+        None => {
+            set_error(Error::empty_arg("commit"), err);
+            return false;
+        }
+        Some(r) => r,
+    };
+
+    if let Err(e) = enclave_api::prove_key_possession(master_key_slice, header_slice, commit_slice)
+    {
+        set_error(Error::enclave_err(e.to_string()), err);
+        return false;
+    }
+    clear_error();
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn bind_operator(
+    consensus_key: Buffer,
+    signature: Buffer,
+    chain_id: Buffer,
+    err: Option<&mut Buffer>,
+) -> bool {
+    let consensus_key_slice = match unsafe { consensus_key.read() } {
+        None => {
+            set_error(Error::empty_arg("consensus_key"), err);
+            return false;
+        }
+        Some(r) => r,
+    };
+    let signature_slice = match unsafe { signature.read() } {
+        None => {
+            set_error(Error::empty_arg("signature"), err);
+            return false;
+        }
+        Some(r) => r,
+    };
+    let chain_id = match unsafe { chain_id.read() }.map(from_utf8) {
+        None => {
+            set_error(Error::empty_arg("chain_id"), err);
+            return false;
+        }
+        Some(Err(_)) => {
+            set_error(Error::vm_err("chain_id must be utf-8"), err);
+            return false;
+        }
+        Some(Ok(r)) => r,
+    };
+
+    if let Err(e) = enclave_api::bind_operator(consensus_key_slice, signature_slice, chain_id) {
+        set_error(Error::enclave_err(e.to_string()), err);
+        return false;
+    }
+    clear_error();
+    true
+}
+
 fn to_extern(storage: DB, api: GoApi, querier: GoQuerier) -> Extern<DB, GoApi, GoQuerier> {
     Extern {
         storage,
@@ -258,7 +546,10 @@ pub extern "C" fn submit_validator_set_evidence(evidence: Buffer, err: Option<&m
         Some(r) => r,
     };
 
-    untrusted_submit_validator_set_evidence(evidence_slice.try_into().unwrap()).unwrap();
+    match enclave_api::submit_validator_set_evidence(evidence_slice) {
+        Err(e) => set_error(Error::enclave_err(e.to_string()), err),
+        Ok(()) => clear_error(),
+    }
 }
 
 #[no_mangle]
@@ -320,110 +611,291 @@ pub extern "C" fn submit_block_signatures(
     //     Some(r) => r,
     // };
 
-    match cosmwasm_sgx_vm::untrusted_submit_block_signatures(
-        header_slice,
-        commit_slice,
-        txs_slice,
-        random_slice,
-        // val_set_slice,
-        // next_val_set_slice,
-    ) {
+    match BlockSignatures::new(header_slice, commit_slice)
+        .txs(txs_slice)
+        .encrypted_random(random_slice)
+        .submit()
+    {
         Err(e) => {
             set_error(Error::enclave_err(e.to_string()), err);
             return TwoBuffers::default();
         }
-        Ok((r1, r2)) => {
+        Ok(output) => {
             clear_error();
             TwoBuffers {
-                buf1: Buffer::from_vec(r1.to_vec()),
-                buf2: Buffer::from_vec(r2.to_vec()),
+                buf1: Buffer::from_vec(output.decrypted_random.to_vec()),
+                buf2: Buffer::from_vec(output.next_validator_set_evidence.to_vec()),
             }
         }
     }
 }
 
-// store some common string for argument names
-static DATA_DIR_ARG: &str = "data_dir";
-static FEATURES_ARG: &str = "supported_features";
-static CACHE_ARG: &str = "cache";
-static WASM_ARG: &str = "wasm";
-static CODE_ID_ARG: &str = "code_id";
-static MSG_ARG: &str = "msg";
-static PARAMS_ARG: &str = "params";
-static GAS_USED_ARG: &str = "gas_used";
-static SIG_INFO_ARG: &str = "sig_info";
-static CURRENT_ADMIN_ARG: &str = "current_admin";
-static CURRENT_ADMIN_PROOF_ARG: &str = "current_admin_proof";
-
-fn do_init_cache(
-    data_dir: Buffer,
-    supported_features: Buffer,
-) -> Result<*mut CosmCache<DB, GoApi, GoQuerier>, Error> {
-    let dir = unsafe { data_dir.read() }.ok_or_else(|| Error::empty_arg(DATA_DIR_ARG))?;
-    let dir_str = from_utf8(dir)?;
-    // parse the supported features
-    let features_bin =
-        unsafe { supported_features.read() }.ok_or_else(|| Error::empty_arg(FEATURES_ARG))?;
-    let features_str = from_utf8(features_bin)?;
-    let features = features_from_csv(features_str);
-    let cache = unsafe { CosmCache::new(dir_str, features) }?;
-    let out = Box::new(cache);
-    Ok(Box::into_raw(out))
-}
-
-/// frees a cache reference
-///
-/// # Safety
-///
-/// This must be called exactly once for any `*cache_t` returned by `init_cache`
-/// and cannot be called on any other pointer.
 #[no_mangle]
-pub extern "C" fn release_cache(cache: *mut cache_t) {
-    if !cache.is_null() {
-        // this will free cache when it goes out of scope
-        let _ = unsafe { Box::from_raw(cache as *mut CosmCache<DB, GoApi, GoQuerier>) };
+pub extern "C" fn submit_compute_params(params: Buffer, proof: Buffer, err: Option<&mut Buffer>) {
+    // both are empty when the node has nothing to push
+    let params_slice = unsafe { params.read() }.unwrap_or_default();
+    let proof_slice = unsafe { proof.read() }.unwrap_or_default();
+
+    match enclave_api::submit_compute_params(params_slice, proof_slice) {
+        Err(e) => set_error(Error::enclave_err(e.to_string()), err),
+        Ok(()) => clear_error(),
     }
 }
 
-#[repr(C)]
-pub struct EnclaveRuntimeConfig {
-    pub module_cache_size: u32,
-}
+#[no_mangle]
+pub extern "C" fn validate_enclave_params(params: Buffer, err: Option<&mut Buffer>) {
+    let params_slice = unsafe { params.read() }.unwrap_or_default();
 
-impl EnclaveRuntimeConfig {
-    fn to_sgx_vm(&self) -> cosmwasm_sgx_vm::EnclaveRuntimeConfig {
-        cosmwasm_sgx_vm::EnclaveRuntimeConfig {
-            module_cache_size: self.module_cache_size,
-        }
+    match enclave_api::validate_enclave_params(params_slice) {
+        Err(e) => set_error(Error::enclave_err(e.to_string()), err),
+        Ok(()) => clear_error(),
     }
 }
 
 #[no_mangle]
-pub extern "C" fn configure_enclave_runtime(
-    config: EnclaveRuntimeConfig,
+pub extern "C" fn create_foreign_client(
+    msg: Buffer,
+    msg_proof: Buffer,
     err: Option<&mut Buffer>,
-) {
-    let r = cosmwasm_sgx_vm::configure_enclave(config.to_sgx_vm())
-        .map_err(|err| Error::enclave_err(err.to_string()));
+) -> Buffer {
+    let msg = match unsafe { msg.read() } {
+        None => {
+            set_error(Error::empty_arg(MSG_ARG), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+    // empty where the node can't prove its state
+    let msg_proof = unsafe { msg_proof.read() }.unwrap_or_default();
 
-    if let Err(e) = r {
-        set_error(e, err);
-    } else {
-        clear_error();
+    match enclave_api::create_foreign_client(msg, msg_proof) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(client) => {
+            clear_error();
+            Buffer::from_vec(client)
+        }
     }
 }
 
 #[no_mangle]
-pub extern "C" fn create(cache: *mut cache_t, wasm: Buffer, err: Option<&mut Buffer>) -> Buffer {
-    let r = match to_cache(cache) {
-        Some(c) => catch_unwind(AssertUnwindSafe(move || do_create(c, wasm)))
-            .unwrap_or_else(|_| Err(Error::panic())),
-        None => Err(Error::empty_arg(CACHE_ARG)),
-    };
-    let data = handle_c_error(r, err);
-    Buffer::from_vec(data)
-}
-
+pub extern "C" fn update_foreign_client(
+    client: Buffer,
+    client_proof: Buffer,
+    msg: Buffer,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    let client = match unsafe { client.read() } {
+        None => {
+            set_error(Error::empty_arg("client"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+    // empty where the node can't prove its state
+    let client_proof = unsafe { client_proof.read() }.unwrap_or_default();
+    let msg = match unsafe { msg.read() } {
+        None => {
+            set_error(Error::empty_arg(MSG_ARG), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+
+    match enclave_api::update_foreign_client(client, client_proof, msg) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(client) => {
+            clear_error();
+            Buffer::from_vec(client)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn submit_foreign_client(
+    chain_id: Buffer,
+    client: Buffer,
+    proof: Buffer,
+    err: Option<&mut Buffer>,
+) {
+    let chain_id = match unsafe { chain_id.read() } {
+        None => {
+            set_error(Error::empty_arg("chain_id"), err);
+            return;
+        }
+        Some(r) => r,
+    };
+    let client = match unsafe { client.read() } {
+        None => {
+            set_error(Error::empty_arg("client"), err);
+            return;
+        }
+        Some(r) => r,
+    };
+    // empty where the node can't prove its state
+    let proof = unsafe { proof.read() }.unwrap_or_default();
+
+    match enclave_api::submit_foreign_client(chain_id, client, proof) {
+        Err(e) => set_error(Error::enclave_err(e.to_string()), err),
+        Ok(()) => clear_error(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn get_mempool_key(height: u64, err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called get_mempool_key");
+    match enclave_api::mempool_key(height) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(public_key) => {
+            clear_error();
+            Buffer::from_vec(public_key.to_vec())
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn decrypt_encrypted_tx(
+    msg: Buffer,
+    msg_proof: Buffer,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    trace!("Called decrypt_encrypted_tx");
+    let msg = match unsafe { msg.read() } {
+        None => {
+            set_error(Error::empty_arg(MSG_ARG), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+    // empty where the node can't prove its state
+    let msg_proof = unsafe { msg_proof.read() }.unwrap_or_default();
+
+    match enclave_api::decrypt_encrypted_tx(msg, msg_proof) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(tx) => {
+            clear_error();
+            Buffer::from_vec(tx)
+        }
+    }
+}
+
+// store some common string for argument names
+static DATA_DIR_ARG: &str = "data_dir";
+static FEATURES_ARG: &str = "supported_features";
+static CACHE_ARG: &str = "cache";
+static WASM_ARG: &str = "wasm";
+static CODE_ID_ARG: &str = "code_id";
+static MSG_ARG: &str = "msg";
+static PARAMS_ARG: &str = "params";
+static GAS_USED_ARG: &str = "gas_used";
+static SIG_INFO_ARG: &str = "sig_info";
+static CURRENT_ADMIN_ARG: &str = "current_admin";
+static CURRENT_ADMIN_PROOF_ARG: &str = "current_admin_proof";
+
+fn do_init_cache(
+    data_dir: Buffer,
+    supported_features: Buffer,
+) -> Result<*mut CosmCache<DB, GoApi, GoQuerier>, Error> {
+    let dir = unsafe { data_dir.read() }.ok_or_else(|| Error::empty_arg(DATA_DIR_ARG))?;
+    let dir_str = from_utf8(dir)?;
+    // parse the supported features
+    let features_bin =
+        unsafe { supported_features.read() }.ok_or_else(|| Error::empty_arg(FEATURES_ARG))?;
+    let features_str = from_utf8(features_bin)?;
+    let features = features_from_csv(features_str);
+    let cache = unsafe { CosmCache::new(dir_str, features) }?;
+    let out = Box::new(cache);
+    Ok(Box::into_raw(out))
+}
+
+/// frees a cache reference
+///
+/// # Safety
+///
+/// This must be called exactly once for any `*cache_t` returned by `init_cache`
+/// and cannot be called on any other pointer.
+#[no_mangle]
+pub extern "C" fn release_cache(cache: *mut cache_t) {
+    if !cache.is_null() {
+        // this will free cache when it goes out of scope
+        let _ = unsafe { Box::from_raw(cache as *mut CosmCache<DB, GoApi, GoQuerier>) };
+    }
+}
+
+#[repr(C)]
+pub struct EnclaveRuntimeConfig {
+    pub module_cache_size: u32,
+    pub query_memory_limit_pages: u32,
+    pub query_gas_limit: u64,
+    pub query_time_limit_ms: u64,
+}
+
+impl EnclaveRuntimeConfig {
+    fn to_sgx_vm(&self) -> enclave_api::EnclaveRuntimeConfig {
+        enclave_api::EnclaveRuntimeConfig {
+            module_cache_size: self.module_cache_size,
+            query_memory_limit_pages: self.query_memory_limit_pages,
+            query_gas_limit: self.query_gas_limit,
+            query_time_limit_ms: self.query_time_limit_ms,
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn configure_enclave_runtime(
+    config: EnclaveRuntimeConfig,
+    err: Option<&mut Buffer>,
+) {
+    let r = enclave_api::configure(config.to_sgx_vm())
+        .map_err(|err| Error::enclave_err(err.to_string()));
+
+    if let Err(e) = r {
+        set_error(e, err);
+    } else {
+        clear_error();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn run_self_test(err: Option<&mut Buffer>) {
+    let r = match enclave_api::self_test() {
+        Err(e) => Err(Error::enclave_err(e.to_string())),
+        Ok(report) if !report.passed() => Err(Error::enclave_err(format!(
+            "enclave self-test failed: {}",
+            report.failed.join(", ")
+        ))),
+        Ok(_) => Ok(()),
+    };
+
+    if let Err(e) = r {
+        set_error(e, err);
+    } else {
+        clear_error();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn create(cache: *mut cache_t, wasm: Buffer, err: Option<&mut Buffer>) -> Buffer {
+    let r = match to_cache(cache) {
+        Some(c) => catch_unwind(AssertUnwindSafe(move || do_create(c, wasm)))
+            .unwrap_or_else(|_| Err(Error::panic())),
+        None => Err(Error::empty_arg(CACHE_ARG)),
+    };
+    let data = handle_c_error(r, err);
+    Buffer::from_vec(data)
+}
+
 fn do_create(cache: &mut CosmCache<DB, GoApi, GoQuerier>, wasm: Buffer) -> Result<Checksum, Error> {
     let wasm = unsafe { wasm.read() }.ok_or_else(|| Error::empty_arg(WASM_ARG))?;
     let checksum = cache.save_wasm(wasm)?;
@@ -742,7 +1214,7 @@ fn do_handle(
 }
 
 #[no_mangle]
-pub extern "C" fn query(
+pub extern "C" fn simulate(
     cache: *mut cache_t,
     code_id: Buffer,
     params: Buffer,
@@ -753,11 +1225,12 @@ pub extern "C" fn query(
     gas_limit: u64,
     gas_used: Option<&mut u64>,
     err: Option<&mut Buffer>,
+    sig_info: Buffer,
 ) -> Buffer {
     let r = match to_cache(cache) {
         Some(c) => catch_unwind(AssertUnwindSafe(move || {
-            do_query(
-                c, code_id, params, msg, db, api, querier, gas_limit, gas_used,
+            do_simulate(
+                c, code_id, params, msg, db, api, querier, gas_limit, gas_used, sig_info,
             )
         }))
         .unwrap_or_else(|_| Err(Error::panic())),
@@ -767,7 +1240,8 @@ pub extern "C" fn query(
     Buffer::from_vec(data)
 }
 
-fn do_query(
+#[allow(clippy::too_many_arguments)]
+fn do_simulate(
     cache: &mut CosmCache<DB, GoApi, GoQuerier>,
     code_id: Buffer,
     params: Buffer,
@@ -777,6 +1251,7 @@ fn do_query(
     querier: GoQuerier,
     gas_limit: u64,
     gas_used: Option<&mut u64>,
+    sig_info: Buffer,
 ) -> Result<Vec<u8>, Error> {
     let gas_used = gas_used.ok_or_else(|| Error::empty_arg(GAS_USED_ARG))?;
     let code_id: Checksum = unsafe { code_id.read() }
@@ -784,85 +1259,229 @@ fn do_query(
         .try_into()?;
     let params = unsafe { params.read() }.ok_or_else(|| Error::empty_arg(PARAMS_ARG))?;
     let msg = unsafe { msg.read() }.ok_or_else(|| Error::empty_arg(MSG_ARG))?;
+    let sig_info = unsafe { sig_info.read() }.ok_or_else(|| Error::empty_arg(SIG_INFO_ARG))?;
 
     let deps = to_extern(db, api, querier);
     let mut instance = cache.get_instance(&code_id, deps, gas_limit)?;
     // We only check this result after reporting gas usage and returning the instance into the cache.
-    let res = call_query_raw(&mut instance, params, msg);
+    let res = call_simulate_raw(&mut instance, params, msg, sig_info);
     *gas_used = instance.create_gas_report().used_internally;
     instance.recycle();
     Ok(res?)
 }
 
-/// The result type of the FFI function analyze_code.
-///
-/// Please note that the unmanaged vector in `required_features`
-/// has to be destroyed exactly once. When calling `analyze_code`
-/// from Go this is done via `C.destroy_unmanaged_vector`.
-#[repr(C)]
-#[derive(Clone, Default, Debug, PartialEq)]
-pub struct AnalysisReport {
-    pub has_ibc_entry_points: bool,
-    /// An UTF-8 encoded comma separated list of required features.
-    /// This is never None/nil.
-    pub required_features: Buffer,
-}
-
 #[no_mangle]
-pub extern "C" fn analyze_code(
+pub extern "C" fn query(
     cache: *mut cache_t,
-    checksum: Buffer,
-    error_msg: Option<&mut Buffer>,
-) -> AnalysisReport {
+    code_id: Buffer,
+    params: Buffer,
+    msg: Buffer,
+    db: DB,
+    api: GoApi,
+    querier: GoQuerier,
+    gas_limit: u64,
+    gas_used: Option<&mut u64>,
+    err: Option<&mut Buffer>,
+) -> Buffer {
     let r = match to_cache(cache) {
-        Some(c) => catch_unwind(AssertUnwindSafe(move || do_analyze_code(c, checksum)))
-            .unwrap_or_else(|_| Err(Error::panic())),
+        Some(c) => catch_unwind(AssertUnwindSafe(move || {
+            do_query(
+                c, code_id, params, msg, db, api, querier, gas_limit, gas_used,
+            )
+        }))
+        .unwrap_or_else(|_| Err(Error::panic())),
         None => Err(Error::empty_arg(CACHE_ARG)),
     };
-
-    handle_c_error_default(r, error_msg)
+    let data = handle_c_error(r, err);
+    Buffer::from_vec(data)
 }
 
-fn do_analyze_code(
+fn do_query(
     cache: &mut CosmCache<DB, GoApi, GoQuerier>,
-    checksum: Buffer,
-) -> Result<AnalysisReport, Error> {
-    let checksum: Checksum = unsafe { checksum.read() }
+    code_id: Buffer,
+    params: Buffer,
+    msg: Buffer,
+    db: DB,
+    api: GoApi,
+    querier: GoQuerier,
+    gas_limit: u64,
+    gas_used: Option<&mut u64>,
+) -> Result<Vec<u8>, Error> {
+    let gas_used = gas_used.ok_or_else(|| Error::empty_arg(GAS_USED_ARG))?;
+    let code_id: Checksum = unsafe { code_id.read() }
         .ok_or_else(|| Error::empty_arg(CODE_ID_ARG))?
         .try_into()?;
-    let report = cache.analyze(&checksum)?;
-    let mut features_vec: Vec<u8> = vec![];
-    for feature in &report.required_features {
-        if features_vec.len() > 0 {
-            features_vec.append(&mut (",".as_bytes().to_vec()))
-        }
-
-        features_vec.append(&mut feature.as_bytes().to_vec())
-    }
+    let params = unsafe { params.read() }.ok_or_else(|| Error::empty_arg(PARAMS_ARG))?;
+    let msg = unsafe { msg.read() }.ok_or_else(|| Error::empty_arg(MSG_ARG))?;
 
-    Ok(AnalysisReport {
-        has_ibc_entry_points: report.has_ibc_entry_points,
-        required_features: Buffer::from_vec(features_vec),
-    })
+    let deps = to_extern(db, api, querier);
+    let mut instance = cache.get_instance(&code_id, deps, gas_limit)?;
+    // We only check this result after reporting gas usage and returning the instance into the cache.
+    let res = call_query_raw(&mut instance, params, msg);
+    *gas_used = instance.create_gas_report().used_internally;
+    instance.recycle();
+    Ok(res?)
 }
 
 #[no_mangle]
-pub extern "C" fn key_gen(err: Option<&mut Buffer>) -> Buffer {
-    match untrusted_key_gen() {
-        Err(e) => {
-            set_error(Error::enclave_err(e.to_string()), err);
-            Buffer::default()
-        }
-        Ok(r) => {
-            clear_error();
-            Buffer::from_vec(r.to_vec())
-        }
-    }
+pub extern "C" fn run_job(
+    cache: *mut cache_t,
+    code_id: Buffer,
+    contract_code_hash: Buffer,
+    params: Buffer,
+    job: Buffer,
+    db: DB,
+    api: GoApi,
+    querier: GoQuerier,
+    gas_limit: u64,
+    gas_used: Option<&mut u64>,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    let r = match to_cache(cache) {
+        Some(c) => catch_unwind(AssertUnwindSafe(move || {
+            do_run_job(
+                c,
+                code_id,
+                contract_code_hash,
+                params,
+                job,
+                db,
+                api,
+                querier,
+                gas_limit,
+                gas_used,
+            )
+        }))
+        .unwrap_or_else(|_| Err(Error::panic())),
+        None => Err(Error::empty_arg(CACHE_ARG)),
+    };
+    let data = handle_c_error(r, err);
+    Buffer::from_vec(data)
+}
+
+fn do_run_job(
+    cache: &mut CosmCache<DB, GoApi, GoQuerier>,
+    code_id: Buffer,
+    contract_code_hash: Buffer,
+    params: Buffer,
+    job: Buffer,
+    db: DB,
+    api: GoApi,
+    querier: GoQuerier,
+    gas_limit: u64,
+    gas_used: Option<&mut u64>,
+) -> Result<Vec<u8>, Error> {
+    let gas_used = gas_used.ok_or_else(|| Error::empty_arg(GAS_USED_ARG))?;
+    let code_id: Checksum = unsafe { code_id.read() }
+        .ok_or_else(|| Error::empty_arg(CODE_ID_ARG))?
+        .try_into()?;
+    let contract_code_hash: &[u8; 32] = unsafe { contract_code_hash.read() }
+        .ok_or_else(|| Error::empty_arg("contract_code_hash"))?
+        .try_into()
+        .map_err(|_| Error::vm_err("contract_code_hash must be 32 bytes"))?;
+    let params = unsafe { params.read() }.ok_or_else(|| Error::empty_arg(PARAMS_ARG))?;
+    let job = unsafe { job.read() }.ok_or_else(|| Error::empty_arg("job"))?;
+
+    let deps = to_extern(db, api, querier);
+    let mut instance = cache.get_instance(&code_id, deps, gas_limit)?;
+    // We only check this result after reporting gas usage and returning the instance into the cache.
+    let res = call_run_job_raw(&mut instance, contract_code_hash, params, job);
+    *gas_used = instance.create_gas_report().used_internally;
+    instance.recycle();
+    Ok(res?)
+}
+
+/// The result type of the FFI function analyze_code.
+///
+/// Please note that the unmanaged vector in `required_features`
+/// has to be destroyed exactly once. When calling `analyze_code`
+/// from Go this is done via `C.destroy_unmanaged_vector`.
+#[repr(C)]
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct AnalysisReport {
+    pub has_ibc_entry_points: bool,
+    /// An UTF-8 encoded comma separated list of required features.
+    /// This is never None/nil.
+    pub required_features: Buffer,
+}
+
+#[no_mangle]
+pub extern "C" fn analyze_code(
+    cache: *mut cache_t,
+    checksum: Buffer,
+    error_msg: Option<&mut Buffer>,
+) -> AnalysisReport {
+    let r = match to_cache(cache) {
+        Some(c) => catch_unwind(AssertUnwindSafe(move || do_analyze_code(c, checksum)))
+            .unwrap_or_else(|_| Err(Error::panic())),
+        None => Err(Error::empty_arg(CACHE_ARG)),
+    };
+
+    handle_c_error_default(r, error_msg)
+}
+
+fn do_analyze_code(
+    cache: &mut CosmCache<DB, GoApi, GoQuerier>,
+    checksum: Buffer,
+) -> Result<AnalysisReport, Error> {
+    let checksum: Checksum = unsafe { checksum.read() }
+        .ok_or_else(|| Error::empty_arg(CODE_ID_ARG))?
+        .try_into()?;
+    let report = cache.analyze(&checksum)?;
+    let mut features_vec: Vec<u8> = vec![];
+    for feature in &report.required_features {
+        if features_vec.len() > 0 {
+            features_vec.append(&mut (",".as_bytes().to_vec()))
+        }
+
+        features_vec.append(&mut feature.as_bytes().to_vec())
+    }
+
+    Ok(AnalysisReport {
+        has_ibc_entry_points: report.has_ibc_entry_points,
+        required_features: Buffer::from_vec(features_vec),
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn key_gen(err: Option<&mut Buffer>) -> Buffer {
+    match enclave_api::key_gen() {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(r) => {
+            clear_error();
+            Buffer::from_vec(r.to_vec())
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rotate_node_keys(err: Option<&mut Buffer>) -> Buffer {
+    match enclave_api::rotate_node_keys() {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(r) => {
+            clear_error();
+            Buffer::from_vec(r.to_vec())
+        }
+    }
 }
 
 #[no_mangle]
 pub extern "C" fn migration_op(opcode: u32) -> bool {
-    if let Err(e) = untrusted_migration_op(opcode) {
+    let op = match MigrationOp::from_opcode(opcode) {
+        Some(op) => op,
+        None => {
+            error!("unknown migration opcode: {}", opcode);
+            return false;
+        }
+    };
+
+    if let Err(e) = enclave_api::migration_op(op) {
         error!("migration op error: {}", e);
         return false;
     }
@@ -961,7 +1580,492 @@ pub extern "C" fn onchain_approve_upgrade(msg: Buffer) -> bool {
         Some(r) => r,
     };
 
-    match untrusted_approve_upgrade(&msg_slice) {
+    match enclave_api::approve_upgrade(&msg_slice) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), None);
+            false
+        }
+        Ok(_) => {
+            clear_error();
+            true
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn disclose_contract_key(
+    env: Buffer,
+    code_hash: Buffer,
+    admin: Buffer,
+    admin_proof: Buffer,
+    msg: Buffer,
+    msg_proof: Buffer,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    trace!("Called disclose_contract_key");
+    let env = match unsafe { env.read() } {
+        None => {
+            set_error(Error::empty_arg("env"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+    let code_hash: &[u8; 32] = match unsafe { code_hash.read() }.map(|r| r.try_into()) {
+        None => {
+            set_error(Error::empty_arg("code_hash"), err);
+            return Buffer::default();
+        }
+        Some(Err(_)) => {
+            set_error(Error::vm_err("code_hash must be 32 bytes"), err);
+            return Buffer::default();
+        }
+        Some(Ok(r)) => r,
+    };
+    let admin = match unsafe { admin.read() } {
+        None => {
+            set_error(Error::empty_arg("admin"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+    let admin_proof = match unsafe { admin_proof.read() } {
+        None => {
+            set_error(Error::empty_arg("admin_proof"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+    let msg = match unsafe { msg.read() } {
+        None => {
+            set_error(Error::empty_arg("msg"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+
+    // empty where the node can't prove the msg, e.g. in tests
+    let msg_proof = unsafe { msg_proof.read() }.unwrap_or_default();
+
+    match AdminCall::new(env, code_hash)
+        .admin(admin, admin_proof)
+        .disclose_contract_key(msg, msg_proof)
+    {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(encrypted_key) => {
+            clear_error();
+            Buffer::from_vec(encrypted_key)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn export_state(
+    env: Buffer,
+    code_hash: Buffer,
+    admin: Buffer,
+    admin_proof: Buffer,
+    authorization: Buffer,
+    pairs: Buffer,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    trace!("Called export_state");
+    do_state_backup(
+        |call, authorization, pairs| call.export_state(authorization, pairs),
+        env,
+        code_hash,
+        admin,
+        admin_proof,
+        authorization,
+        pairs,
+        err,
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn import_state(
+    env: Buffer,
+    code_hash: Buffer,
+    admin: Buffer,
+    admin_proof: Buffer,
+    authorization: Buffer,
+    backup_chunk: Buffer,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    trace!("Called import_state");
+    do_state_backup(
+        |call, authorization, chunk| call.import_state(authorization, chunk),
+        env,
+        code_hash,
+        admin,
+        admin_proof,
+        authorization,
+        backup_chunk,
+        err,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn do_state_backup(
+    operation: impl FnOnce(AdminCall, &[u8], &[u8]) -> enclave_api::EnclaveApiResult<Vec<u8>>,
+    env: Buffer,
+    code_hash: Buffer,
+    admin: Buffer,
+    admin_proof: Buffer,
+    authorization: Buffer,
+    chunk: Buffer,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    let code_hash = match read_code_hash(&code_hash) {
+        Err(e) => {
+            set_error(e, err);
+            return Buffer::default();
+        }
+        Ok(r) => r,
+    };
+
+    let mut args = vec![];
+    for (name, arg) in [
+        ("env", env),
+        ("admin", admin),
+        ("admin_proof", admin_proof),
+        ("authorization", authorization),
+    ] {
+        match unsafe { arg.read() } {
+            None => {
+                set_error(Error::empty_arg(name), err);
+                return Buffer::default();
+            }
+            Some(r) => args.push(r),
+        }
+    }
+    // An empty chunk is valid, it's the last chunk of an empty state
+    let chunk = unsafe { chunk.read() }.unwrap_or_default();
+
+    let call = AdminCall::new(args[0], code_hash).admin(args[1], args[2]);
+    match operation(call, args[3], chunk) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(output) => {
+            clear_error();
+            Buffer::from_vec(output)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn state_commitment_leaves(
+    env: Buffer,
+    code_hash: Buffer,
+    pairs: Buffer,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    trace!("Called state_commitment_leaves");
+    let code_hash = match read_code_hash(&code_hash) {
+        Err(e) => {
+            set_error(e, err);
+            return Buffer::default();
+        }
+        Ok(r) => r,
+    };
+    let env = match unsafe { env.read() } {
+        None => {
+            set_error(Error::empty_arg("env"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+    let pairs = unsafe { pairs.read() }.unwrap_or_default();
+
+    match StateCommitment::new(env, code_hash).leaves(pairs) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(leaves) => {
+            clear_error();
+            Buffer::from_vec(leaves)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn fold_state_commitment(
+    env: Buffer,
+    code_hash: Buffer,
+    progress: Buffer,
+    pairs: Buffer,
+    last_chunk: bool,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    trace!("Called fold_state_commitment");
+    let code_hash = match read_code_hash(&code_hash) {
+        Err(e) => {
+            set_error(e, err);
+            return Buffer::default();
+        }
+        Ok(r) => r,
+    };
+    let env = match unsafe { env.read() } {
+        None => {
+            set_error(Error::empty_arg("env"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+    // The progress is empty for the first chunk
+    let progress = unsafe { progress.read() }.unwrap_or_default();
+    let pairs = unsafe { pairs.read() }.unwrap_or_default();
+
+    match StateCommitment::new(env, code_hash).fold(progress, pairs, last_chunk) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(output) => {
+            clear_error();
+            Buffer::from_vec(output)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn reencrypt_state(
+    env: Buffer,
+    code_hash: Buffer,
+    progress: Buffer,
+    pairs: Buffer,
+    last_chunk: bool,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    trace!("Called reencrypt_state");
+    let code_hash = match read_code_hash(&code_hash) {
+        Err(e) => {
+            set_error(e, err);
+            return Buffer::default();
+        }
+        Ok(r) => r,
+    };
+    let env = match unsafe { env.read() } {
+        None => {
+            set_error(Error::empty_arg("env"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+    // The progress is empty for the first chunk
+    let progress = unsafe { progress.read() }.unwrap_or_default();
+    let pairs = unsafe { pairs.read() }.unwrap_or_default();
+
+    match StateReencryption::new(env, code_hash).reencrypt(progress, pairs, last_chunk) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(output) => {
+            clear_error();
+            Buffer::from_vec(output)
+        }
+    }
+}
+
+fn read_code_hash(code_hash: &Buffer) -> Result<&[u8; 32], Error> {
+    match unsafe { code_hash.read() }.map(|r| r.try_into()) {
+        None => Err(Error::empty_arg("code_hash")),
+        Some(Err(_)) => Err(Error::vm_err("code_hash must be 32 bytes")),
+        Some(Ok(r)) => Ok(r),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn consensus_key_init(err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called consensus_key_init");
+    match enclave_api::consensus_key_init() {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(public_key) => {
+            clear_error();
+            Buffer::from_vec(public_key.to_vec())
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn consensus_sign(request: Buffer, err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called consensus_sign");
+    let request = match unsafe { request.read() } {
+        None => {
+            set_error(Error::empty_arg("request"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+
+    match enclave_api::consensus_sign(request) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(response) => {
+            clear_error();
+            Buffer::from_vec(response)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn get_ocall_stats(err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called get_ocall_stats");
+    match enclave_api::ocall_stats() {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(stats) => {
+            clear_error();
+            Buffer::from_vec(stats)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn get_crash_breadcrumbs(err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called get_crash_breadcrumbs");
+    match enclave_api::crash_breadcrumbs() {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(breadcrumbs) => {
+            clear_error();
+            Buffer::from_vec(breadcrumbs)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn get_seed_heartbeat_key(err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called get_seed_heartbeat_key");
+    match enclave_api::seed_heartbeat_key() {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(public_key) => {
+            clear_error();
+            Buffer::from_vec(public_key.to_vec())
+        }
+    }
+}
+
+/// Returns the registration key of the node, followed by the signature of the heartbeat
+#[no_mangle]
+pub extern "C" fn seed_heartbeat(height: u64, err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called seed_heartbeat");
+    match enclave_api::seed_heartbeat(height) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(heartbeat) => {
+            clear_error();
+            let mut out = heartbeat.node_public_key.to_vec();
+            out.extend_from_slice(&heartbeat.signature);
+            Buffer::from_vec(out)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn take_conformance_trace(err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called take_conformance_trace");
+    match enclave_api::take_conformance_trace() {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(trace) => {
+            clear_error();
+            Buffer::from_vec(trace)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn take_audit_transcript(err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called take_audit_transcript");
+    match enclave_api::take_audit_transcript() {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(transcript) => {
+            clear_error();
+            Buffer::from_vec(transcript)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn take_engine_divergences() -> Buffer {
+    trace!("Called take_engine_divergences");
+    Buffer::from_vec(enclave_api::take_engine_divergences())
+}
+
+#[no_mangle]
+pub extern "C" fn validate_code(code: Buffer, err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called validate_code");
+    let code = match unsafe { code.read() } {
+        None => {
+            set_error(Error::empty_arg(WASM_ARG), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+
+    match enclave_api::validate_code(code) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(report) => {
+            clear_error();
+            Buffer::from_vec(report)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn module_cache_snapshot(err: Option<&mut Buffer>) -> Buffer {
+    trace!("Called module_cache_snapshot");
+    match enclave_api::module_cache_snapshot() {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(snapshot) => {
+            clear_error();
+            Buffer::from_vec(snapshot)
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn prewarm_module(code: Buffer) -> bool {
+    let code_slice = match unsafe { code.read() } {
+        None => {
+            return false;
+        }
+        Some(r) => r,
+    };
+
+    match enclave_api::prewarm_module(code_slice) {
         Err(e) => {
             set_error(Error::enclave_err(e.to_string()), None);
             false
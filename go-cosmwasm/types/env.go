@@ -14,6 +14,14 @@ type Env struct {
 	Key         ContractKey      `json:"contract_key"`
 	QueryDepth  uint32           `json:"query_depth"`
 	Transaction *TransactionInfo `json:"transaction,omitempty"`
+
+	// QueryCacheable lets the enclave cache the response to a query from outside the chain,
+	// whose state can't change at its height. It isn't passed on to the contract.
+	QueryCacheable bool `json:"query_cacheable,omitempty"`
+
+	// QueryCancellationID lets the node cancel a query from outside the chain with
+	// api.CancelQuery. The enclave ignores it for other queries.
+	QueryCancellationID uint64 `json:"query_cancellation_id,omitempty,string"`
 }
 
 type ContractKey struct {
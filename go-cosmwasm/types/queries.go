@@ -73,6 +73,31 @@ type QueryRequest struct {
 	Gov      *GovQuery       `json:"gov,omitempty"`
 	IBC      *IBCQuery       `json:"ibc,omitempty"`
 	Stargate *StargateQuery  `json:"stargate,omitempty"`
+
+	// BlockRandom is only sent by the enclave itself, contracts can't send it
+	BlockRandom *BlockRandomQuery `json:"block_random,omitempty"`
+	// IbcClientState is only sent by the enclave itself, contracts can't send it
+	IbcClientState *IbcClientStateQuery `json:"ibc_client_state,omitempty"`
+}
+
+// BlockRandomQuery asks for the random x/compute stored for the block at Height. The response is
+// the random, or nothing if there's none.
+type BlockRandomQuery struct {
+	Height uint64 `json:"height,string"`
+}
+
+// IbcClientStateQuery asks for the state the IBC module stores for the client ClientID as of the
+// last block. The response is a ProvenState, or nothing if there's no such client or the node
+// can't prove it.
+type IbcClientStateQuery struct {
+	ClientID string `json:"client_id"`
+}
+
+// ProvenState is a value of the chain's state as of the last block, with an ICS-23 proof of it
+// against the app hash of the current block
+type ProvenState struct {
+	Value []byte `json:"value"`
+	Proof []byte `json:"proof"`
 }
 
 type BankQuery struct {
@@ -50,6 +50,87 @@ func (c *Coins) UnmarshalJSON(data []byte) error {
 	return nil
 }
 
+// QueryLimits are the limits of queries from outside the chain, on top of the limits of
+// every contract call. A limit of 0 leaves that resource at the default.
+type QueryLimits struct {
+	// MemoryPages is the most wasm pages (64 KiB each) a query's memory can grow to
+	MemoryPages uint32
+	Gas         uint64
+	TimeMs      uint64
+}
+
+// ExecutionReport is what an execution spent its gas on, as measured by the enclave. It isn't
+// part of consensus: ModuleCacheHit depends on the node, so it must only be used for analytics.
+type ExecutionReport struct {
+	// WasmGas is the gas of the contract's own instructions
+	WasmGas uint64 `json:"wasm_gas"`
+	// HostFunctions is sorted by name
+	HostFunctions  []HostFunctionGas `json:"host_functions"`
+	Storage        StorageOps        `json:"storage"`
+	ModuleCacheHit bool              `json:"module_cache_hit"`
+	// CallDepth is how many contracts are waiting for the execution to return
+	CallDepth uint32 `json:"call_depth"`
+}
+
+type HostFunctionGas struct {
+	Name  string `json:"name"`
+	Calls uint64 `json:"calls"`
+	Gas   uint64 `json:"gas"`
+}
+
+type StorageOps struct {
+	Reads uint64 `json:"reads"`
+	// CacheHits are reads served from values the execution already read or wrote
+	CacheHits uint64 `json:"cache_hits"`
+	Writes    uint64 `json:"writes"`
+	Removes   uint64 `json:"removes"`
+}
+
+// CodeValidationReport is what the enclave found in a contract when it was stored. Code is only
+// stored if it's Valid, which it is when there are no Violations.
+type CodeValidationReport struct {
+	Valid      bool   `json:"valid"`
+	ModuleSize uint64 `json:"module_size"`
+	Functions  uint64 `json:"functions"`
+	// MaxLocals is the most locals any function of the contract declares
+	MaxLocals uint64 `json:"max_locals"`
+	// Imports are the functions the contract imports, as module.name
+	Imports []string `json:"imports"`
+	// RequiredFeatures are the enclave features the contract declares it needs
+	RequiredFeatures []string `json:"required_features"`
+	Violations       []string `json:"violations"`
+}
+
+// OcallStats is how often the enclave called an ocall of the host since it started. Rejected
+// calls didn't pass the enclave's validation of their arguments, and never reached the host.
+type OcallStats struct {
+	Name     string `json:"name"`
+	Calls    uint64 `json:"calls"`
+	Rejected uint64 `json:"rejected"`
+	// Failed calls reached the host, but returned an SGX error
+	Failed uint64 `json:"failed"`
+}
+
+// ModuleCacheSnapshot is the list of modules in the enclave's cache, signed by the enclave. Nodes
+// with the same modules, built by the same engine, have the same Digest.
+type ModuleCacheSnapshot struct {
+	// Entries are sorted by code hash
+	Entries       []CachedModule `json:"entries"`
+	Digest        []byte         `json:"digest"`
+	NodePublicKey []byte         `json:"node_public_key"`
+	// PublicKey is the same on every node with the consensus seed
+	PublicKey []byte `json:"public_key"`
+	Signature []byte `json:"signature"`
+}
+
+// CachedModule is a module in the enclave's cache
+type CachedModule struct {
+	CodeHash []byte `json:"code_hash"`
+	// ArtifactHash is the hash of the module after the enclave validated and instrumented it
+	ArtifactHash  []byte `json:"artifact_hash"`
+	EngineVersion uint32 `json:"engine_version"`
+}
+
 type OutOfGasError struct{}
 
 var _ error = OutOfGasError{}
@@ -66,6 +147,15 @@ type SigInfo struct {
 	PublicKey         []byte `json:"public_key"`
 	Signature         []byte `json:"signature"`
 	CallbackSignature []byte `json:"callback_sig"` // Optional
+
+	// InterchainAccount is set when the sender is an interchain account hosted on this chain
+	InterchainAccount *InterchainAccount `json:"interchain_account"` // Optional
+}
+
+// InterchainAccount is the controller of an interchain account, as the ICA host module stores it
+type InterchainAccount struct {
+	ControllerPort string `json:"controller_port"`
+	HostChannel    string `json:"host_channel"`
 }
 
 type HandleType int
@@ -82,6 +172,8 @@ const (
 	HandleTypeIbcWasmHooksIncomingTransfer
 	HandleTypeIbcWasmHooksOutgoingTransferAck
 	HandleTypeIbcWasmHooksOutgoingTransferTimeout
+	HandleTypeJobResult
+	HandleTypeCron
 )
 
 type CosmosMsgVersion int
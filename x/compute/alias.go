@@ -69,6 +69,7 @@ var (
 	NewWasmSnapshotter        = keeper.NewWasmSnapshotter
 	ContractFromPortID        = keeper.ContractFromPortID
 	NewCountTXDecorator       = keeper.NewCountTXDecorator
+	NewPrewarmDecorator       = keeper.NewPrewarmDecorator
 	NewMsgServerImpl          = keeper.NewMsgServerImpl
 
 	// variable aliases
@@ -52,6 +52,10 @@ func GetQueryCmd() *cobra.Command {
 		GetCmdCodeHashByCodeID(),
 		GetCmdDecryptText(),
 		GetCmdGetContractHistory(),
+		GetCmdJob(),
+		GetCmdRunJob(),
+		GetCmdExportContractState(),
+		GetCmdMempoolKey(),
 	)
 	return queryCmd
 }
@@ -733,3 +737,137 @@ func addPaginationFlags(cmd *cobra.Command, query string) {
 	cmd.Flags().Uint64(flags.FlagLimit, 100, fmt.Sprintf("pagination limit of %s to query for", query))
 	cmd.Flags().Bool(flags.FlagReverse, false, "results are sorted in descending order")
 }
+
+// GetCmdJob prints an off-chain job that's waiting for its result
+func GetCmdJob() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "job [job_id]",
+		Short: "Prints out an off-chain job that's waiting for its result",
+		Args:  cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			clientCtx, err := client.GetClientQueryContext(cmd)
+			if err != nil {
+				return err
+			}
+
+			jobID, err := strconv.ParseUint(args[0], 10, 64)
+			if err != nil {
+				return err
+			}
+
+			queryClient := types.NewQueryClient(clientCtx)
+			res, err := queryClient.Job(cmd.Context(), &types.QueryJobRequest{JobId: jobID})
+			if err != nil {
+				return err
+			}
+
+			return clientCtx.PrintProto(res)
+		},
+	}
+
+	flags.AddQueryFlagsToCmd(cmd)
+	return cmd
+}
+
+// GetCmdRunJob runs the handler of an off-chain job on the node's enclave, and prints the
+// attested result to submit with submit-job-result
+func GetCmdRunJob() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "run-job [job_id]",
+		Short: "Runs the handler of an off-chain job on the node's enclave, and prints its attested result",
+		Args:  cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			clientCtx, err := client.GetClientQueryContext(cmd)
+			if err != nil {
+				return err
+			}
+
+			jobID, err := strconv.ParseUint(args[0], 10, 64)
+			if err != nil {
+				return err
+			}
+
+			queryClient := types.NewQueryClient(clientCtx)
+			res, err := queryClient.RunJob(cmd.Context(), &types.QueryJobRequest{JobId: jobID})
+			if err != nil {
+				return err
+			}
+
+			fmt.Println(string(res.Result))
+			return nil
+		},
+	}
+
+	flags.AddQueryFlagsToCmd(cmd)
+	return cmd
+}
+
+// GetCmdExportContractState exports the state of a contract to an encrypted backup, and prints it
+// to import with import-state
+func GetCmdExportContractState() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "export-state [contract_addr_bech32] [authorization json file]",
+		Short: "Exports the state of a contract to an encrypted backup, as its admin authorized with sign-state-backup",
+		Args:  cobra.ExactArgs(2),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			clientCtx, err := client.GetClientQueryContext(cmd)
+			if err != nil {
+				return err
+			}
+
+			bz, err := os.ReadFile(args[1])
+			if err != nil {
+				return err
+			}
+			var authorization types.StateBackupAuthorization
+			if err := clientCtx.Codec.UnmarshalJSON(bz, &authorization); err != nil {
+				return fmt.Errorf("authorization: %w", err)
+			}
+
+			queryClient := types.NewQueryClient(clientCtx)
+			res, err := queryClient.ExportContractState(cmd.Context(), &types.QueryExportContractStateRequest{
+				ContractAddress: args[0],
+				Authorization:   authorization,
+			})
+			if err != nil {
+				return err
+			}
+
+			return clientCtx.PrintProto(res)
+		},
+	}
+
+	flags.AddQueryFlagsToCmd(cmd)
+	return cmd
+}
+
+// GetCmdMempoolKey prints the public key txs are encrypted to for execute-encrypted
+func GetCmdMempoolKey() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "mempool-key [height]",
+		Short: "Prints the public key users encrypt the txs they want included at a height to",
+		Args:  cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			clientCtx, err := client.GetClientQueryContext(cmd)
+			if err != nil {
+				return err
+			}
+
+			height, err := strconv.ParseUint(args[0], 10, 64)
+			if err != nil {
+				return err
+			}
+
+			queryClient := types.NewQueryClient(clientCtx)
+			res, err := queryClient.MempoolKey(cmd.Context(), &types.QueryMempoolKeyRequest{Height: height})
+			if err != nil {
+				return err
+			}
+
+			return clientCtx.PrintProto(res)
+		},
+	}
+
+	flags.AddQueryFlagsToCmd(cmd)
+	return cmd
+}
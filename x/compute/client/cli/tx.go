@@ -1,7 +1,9 @@
 package cli
 
 import (
+	"bytes"
 	"context"
+	"encoding/binary"
 	"encoding/hex"
 	"fmt"
 	"os"
@@ -14,6 +16,8 @@ import (
 	"github.com/cosmos/cosmos-sdk/client/flags"
 	sdk "github.com/cosmos/cosmos-sdk/types"
 	sdkerrors "github.com/cosmos/cosmos-sdk/types/errors"
+	"github.com/cosmos/cosmos-sdk/types/tx/signing"
+	"github.com/cosmos/gogoproto/proto"
 	wasmUtils "github.com/scrtlabs/SecretNetwork/x/compute/client/utils"
 	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
 	"github.com/spf13/cobra"
@@ -51,7 +55,13 @@ func GetTxCmd() *cobra.Command {
 		MigrateContractCmd(),
 		UpdateContractAdminCmd(),
 		ClearContractAdminCmd(),
+		SetContractCronCmd(),
 		UpgradeProposalPassedCmd(),
+		SubmitJobResultCmd(),
+		SignStateBackupCmd(),
+		ImportContractStateCmd(),
+		UpdateForeignClientCmd(),
+		ExecuteEncryptedTxCmd(),
 	)
 	return txCmd
 }
@@ -569,6 +579,47 @@ func ClearContractAdminCmd() *cobra.Command {
 	return cmd
 }
 
+// SetContractCronCmd schedules the block callbacks of a contract
+func SetContractCronCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "set-contract-cron [contract_addr_bech32] [interval] [gas_budget]",
+		Short: "Schedule the block callbacks of a contract, as its admin",
+		Long: `Schedule the callbacks of a contract every [interval] blocks, each with [gas_budget] gas, or
+remove its schedule with an interval of 0. The tx must be signed by the contract's admin. See
+docs/contract-cron.md.`,
+		Args: cobra.ExactArgs(3),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			clientCtx, err := client.GetClientTxContext(cmd)
+			if err != nil {
+				return err
+			}
+
+			interval, err := strconv.ParseUint(args[1], 10, 64)
+			if err != nil {
+				return errorsmod.Wrap(err, "interval")
+			}
+			gasBudget, err := strconv.ParseUint(args[2], 10, 64)
+			if err != nil {
+				return errorsmod.Wrap(err, "gas budget")
+			}
+
+			msg := types.MsgSetContractCron{
+				Sender:    clientCtx.GetFromAddress().String(),
+				Contract:  args[0],
+				Interval:  interval,
+				GasBudget: gasBudget,
+			}
+			if err := msg.ValidateBasic(); err != nil {
+				return err
+			}
+			return tx.GenerateOrBroadcastTxCLI(clientCtx, cmd.Flags(), &msg)
+		},
+		SilenceUsage: true,
+	}
+	flags.AddTxFlagsToCmd(cmd)
+	return cmd
+}
+
 // UpgradeProposalPassedCmd
 func UpgradeProposalPassedCmd() *cobra.Command {
 	cmd := &cobra.Command{
@@ -605,3 +656,233 @@ func UpgradeProposalPassedCmd() *cobra.Command {
 
 	return cmd
 }
+
+// SubmitJobResultCmd submits the attested result of an off-chain job, as run-job printed it
+func SubmitJobResultCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "submit-job-result [result json file]",
+		Short: "Deliver the attested result of an off-chain job to the contract that enqueued it",
+		Args:  cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			clientCtx, err := client.GetClientTxContext(cmd)
+			if err != nil {
+				return err
+			}
+
+			result, err := os.ReadFile(args[0])
+			if err != nil {
+				return err
+			}
+
+			msg := types.MsgSubmitJobResult{
+				Sender: clientCtx.GetFromAddress().String(),
+				Result: bytes.TrimSpace(result),
+			}
+			if err = msg.ValidateBasic(); err != nil {
+				return err
+			}
+
+			return tx.GenerateOrBroadcastTxCLI(clientCtx, cmd.Flags(), &msg)
+		},
+	}
+	flags.AddTxFlagsToCmd(cmd)
+
+	return cmd
+}
+
+// SignStateBackupCmd signs the admin's authorization to export or import the state of a contract
+func SignStateBackupCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "sign-state-backup [contract_addr_bech32] [export|import] [expires_at_height]",
+		Short: "Authorize the export or import of the state of a contract, as its admin",
+		Long: `Sign the authorization to export the state of a contract with export-state, or to import a
+backup into it with import-state. The key of --from must be the contract's admin. The
+authorization is printed as json, and can't be used after expires_at_height.`,
+		Args: cobra.ExactArgs(3),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			clientCtx, err := client.GetClientTxContext(cmd)
+			if err != nil {
+				return err
+			}
+
+			if _, err := sdk.AccAddressFromBech32(args[0]); err != nil {
+				return errorsmod.Wrap(err, "contract")
+			}
+			if args[1] != "export" && args[1] != "import" {
+				return fmt.Errorf("unknown operation %s, expected export or import", args[1])
+			}
+			expiresAtHeight, err := strconv.ParseUint(args[2], 10, 64)
+			if err != nil {
+				return errorsmod.Wrap(err, "expires at height")
+			}
+
+			signature, pubKey, err := clientCtx.Keyring.Sign(
+				clientCtx.FromName,
+				stateBackupSignBytes(args[0], args[1], expiresAtHeight),
+				signing.SignMode_SIGN_MODE_DIRECT,
+			)
+			if err != nil {
+				return err
+			}
+
+			return clientCtx.PrintProto(&types.StateBackupAuthorization{
+				Contract:        args[0],
+				ExpiresAtHeight: expiresAtHeight,
+				AdminPublicKey:  pubKey.Bytes(),
+				AdminSignature:  signature,
+			})
+		},
+	}
+	flags.AddTxFlagsToCmd(cmd)
+
+	return cmd
+}
+
+// stateBackupSignBytes are the bytes the admin signs to authorize a state backup operation. They
+// must match `StateBackupAuthorization::sign_bytes` in the enclave's state_backup.rs.
+func stateBackupSignBytes(contract string, operation string, expiresAtHeight uint64) []byte {
+	bz := []byte("secret_contract_state_backup" + operation)
+	bz = binary.BigEndian.AppendUint32(bz, uint32(len(contract)))
+	bz = append(bz, contract...)
+	return binary.BigEndian.AppendUint64(bz, expiresAtHeight)
+}
+
+// ImportContractStateCmd imports a backup that export-state printed into a contract
+func ImportContractStateCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "import-state [contract_addr_bech32] [authorization json file] [backup json file]",
+		Short: "Import a backup of a contract's state into a contract of the same code and admin",
+		Args:  cobra.ExactArgs(3),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			clientCtx, err := client.GetClientTxContext(cmd)
+			if err != nil {
+				return err
+			}
+
+			var authorization types.StateBackupAuthorization
+			if err := readProtoJSONFile(clientCtx, args[1], &authorization); err != nil {
+				return errorsmod.Wrap(err, "authorization")
+			}
+			var backup types.QueryExportContractStateResponse
+			if err := readProtoJSONFile(clientCtx, args[2], &backup); err != nil {
+				return errorsmod.Wrap(err, "backup")
+			}
+
+			msg := types.MsgImportContractState{
+				Sender:        clientCtx.GetFromAddress().String(),
+				Contract:      args[0],
+				Authorization: authorization,
+				Chunks:        backup.Chunks,
+			}
+			if err = msg.ValidateBasic(); err != nil {
+				return err
+			}
+
+			return tx.GenerateOrBroadcastTxCLI(clientCtx, cmd.Flags(), &msg)
+		},
+	}
+	flags.AddTxFlagsToCmd(cmd)
+
+	return cmd
+}
+
+// UpdateForeignClientCmd relays a header of another chain to its light client
+func UpdateForeignClientCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "update-foreign-client [update json file]",
+		Short: "Relay a newer header of another chain to its light client",
+		Long: `Relay a newer header of another chain to its light client. The file is the json of a
+MsgUpdateForeignClient, whose signer is set to the --from account. See docs/foreign-light-clients.md.`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			clientCtx, err := client.GetClientTxContext(cmd)
+			if err != nil {
+				return err
+			}
+
+			var msg types.MsgUpdateForeignClient
+			if err := readProtoJSONFile(clientCtx, args[0], &msg); err != nil {
+				return errorsmod.Wrap(err, "update")
+			}
+			msg.Signer = clientCtx.GetFromAddress().String()
+			if err = msg.ValidateBasic(); err != nil {
+				return err
+			}
+
+			return tx.GenerateOrBroadcastTxCLI(clientCtx, cmd.Flags(), &msg)
+		},
+	}
+	flags.AddTxFlagsToCmd(cmd)
+
+	return cmd
+}
+
+func ExecuteEncryptedTxCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "execute-encrypted [height] [body json file] [gas_limit]",
+		Short: "Send msgs encrypted to the mempool key of a height, to be executed in the block after the one that includes them",
+		Long: `Send msgs encrypted to the mempool key of a height, so they stay encrypted until the block
+that includes them is final, which must be at most 9 blocks after the height. The file is the json of
+an EncryptedTxBody, whose msgs must all be signed by the --from account. They're executed at the
+start of the next block with [gas_limit] gas, which the tx pays for. See docs/encrypted-mempool.md.`,
+		Args: cobra.ExactArgs(3),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			clientCtx, err := client.GetClientTxContext(cmd)
+			if err != nil {
+				return err
+			}
+
+			height, err := strconv.ParseUint(args[0], 10, 64)
+			if err != nil {
+				return err
+			}
+			var body types.EncryptedTxBody
+			if err := readProtoJSONFile(clientCtx, args[1], &body); err != nil {
+				return errorsmod.Wrap(err, "body")
+			}
+			gasLimit, err := strconv.ParseUint(args[2], 10, 64)
+			if err != nil {
+				return errorsmod.Wrap(err, "gas limit")
+			}
+			plaintext, err := clientCtx.Codec.Marshal(&body)
+			if err != nil {
+				return err
+			}
+
+			queryClient := types.NewQueryClient(clientCtx)
+			res, err := queryClient.MempoolKey(cmd.Context(), &types.QueryMempoolKeyRequest{Height: height})
+			if err != nil {
+				return err
+			}
+
+			sender := clientCtx.GetFromAddress().String()
+			wasmCtx := wasmUtils.WASMContext{CLIContext: clientCtx}
+			encryptedTx, err := wasmCtx.EncryptForMempool(res.PublicKey, height, sender, plaintext)
+			if err != nil {
+				return err
+			}
+
+			msg := types.MsgExecuteEncryptedTx{
+				Sender:      sender,
+				EncryptedTx: encryptedTx,
+				GasLimit:    gasLimit,
+			}
+			if err = msg.ValidateBasic(); err != nil {
+				return err
+			}
+
+			return tx.GenerateOrBroadcastTxCLI(clientCtx, cmd.Flags(), &msg)
+		},
+	}
+	flags.AddTxFlagsToCmd(cmd)
+
+	return cmd
+}
+
+func readProtoJSONFile(clientCtx client.Context, path string, msg proto.Message) error {
+	bz, err := os.ReadFile(path)
+	if err != nil {
+		return err
+	}
+	return clientCtx.Codec.UnmarshalJSON(bz, msg)
+}
@@ -6,6 +6,7 @@ import (
 	"crypto/rand"
 	"crypto/sha256"
 	"encoding/base64"
+	"encoding/binary"
 	"encoding/hex"
 	"encoding/json"
 	"fmt"
@@ -300,6 +301,40 @@ func encryptData(aesEncryptionKey []byte, txSenderPubKey []byte, plaintext []byt
 	return ciphertext, nil
 }
 
+// EncryptForMempool encrypts the body of a tx to the mempool key of height, which only the sender
+// can execute. See docs/encrypted-mempool.md.
+func (ctx WASMContext) EncryptForMempool(mempoolKey []byte, height uint64, sender string, plaintext []byte) ([]byte, error) {
+	txSenderPrivKey, txSenderPubKey, err := ctx.GetTxSenderKeyPair()
+	if err != nil {
+		return nil, err
+	}
+
+	nonce := make([]byte, 32)
+	if _, err := rand.Read(nonce); err != nil {
+		return nil, err
+	}
+
+	txEncryptionKey, err := GetTxEncryptionKeyOffline(mempoolKey, txSenderPrivKey, nonce)
+	if err != nil {
+		return nil, err
+	}
+
+	cipher, err := miscreant.NewAESCMACSIV(txEncryptionKey)
+	if err != nil {
+		return nil, err
+	}
+	ciphertext, err := cipher.Seal(nil, plaintext, []byte(sender))
+	if err != nil {
+		return nil, err
+	}
+
+	// encrypted tx = height(8, big endian) || wallet_pubkey(32) || nonce(32) || ciphertext
+	encryptedTx := binary.BigEndian.AppendUint64(nil, height)
+	encryptedTx = append(encryptedTx, txSenderPubKey...)
+	encryptedTx = append(encryptedTx, nonce...)
+	return append(encryptedTx, ciphertext...), nil
+}
+
 func GetTxEncryptionKeyOffline(pubkey []byte, txSenderPrivKey []byte, nonce []byte) ([]byte, error) {
 	txEncryptionIkm, err := curve25519.X25519(txSenderPrivKey, pubkey)
 	if err != nil {
@@ -0,0 +1,49 @@
+package keeper
+
+import (
+	"encoding/json"
+	"fmt"
+	"os"
+	"path/filepath"
+
+	sdk "github.com/cosmos/cosmos-sdk/types"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+)
+
+// AuditTranscriptDir is the directory of the home dir audit builds write their transcripts to
+const AuditTranscriptDir = "audit"
+
+type auditTranscript struct {
+	Steps []json.RawMessage `json:"steps"`
+}
+
+// RecordAuditTranscript writes the transcript of the contract executions of the block to
+// audit/<height>.json in the home dir, when the enclave is an audit build. Other builds don't
+// record a transcript, and nothing is written. See docs/audit-builds.md.
+func (k Keeper) RecordAuditTranscript(ctx sdk.Context) {
+	res, err := api.TakeAuditTranscript()
+	if err != nil || res == nil {
+		return
+	}
+
+	var transcript auditTranscript
+	if err := json.Unmarshal(res, &transcript); err != nil {
+		ctx.Logger().Error("invalid audit transcript", "error", err)
+		return
+	}
+	if len(transcript.Steps) == 0 {
+		return
+	}
+
+	dir := filepath.Join(k.HomeDir, AuditTranscriptDir)
+	if err := os.MkdirAll(dir, 0o700); err != nil {
+		ctx.Logger().Error("failed to write the audit transcript", "error", err)
+		return
+	}
+	path := filepath.Join(dir, fmt.Sprintf("%d.json", ctx.BlockHeight()))
+	if err := os.WriteFile(path, res, 0o600); err != nil {
+		ctx.Logger().Error("failed to write the audit transcript", "error", err)
+		return
+	}
+}
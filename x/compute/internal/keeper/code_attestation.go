@@ -0,0 +1,25 @@
+package keeper
+
+import (
+	errorsmod "cosmossdk.io/errors"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+// AttestCodeHash returns a DCAP quote that attests the code with codeHash runs under this node's
+// enclave, on the chain and at the height of ctx, with the statement it attests, as json. The
+// enclave only attests code whose module it has loaded, so the quote is local to the node and
+// never reaches the state.
+func (k Keeper) AttestCodeHash(ctx sdk.Context, codeHash []byte) ([]byte, error) {
+	if len(codeHash) != 32 {
+		return nil, errorsmod.Wrap(types.ErrInvalid, "code hash")
+	}
+
+	res, err := api.AttestCodeHash(codeHash, ctx.ChainID(), uint64(ctx.BlockHeight()))
+	if err != nil {
+		return nil, errorsmod.Wrap(types.ErrInvalid, err.Error())
+	}
+	return res, nil
+}
@@ -0,0 +1,32 @@
+package keeper
+
+import (
+	"encoding/json"
+	"strings"
+
+	errorsmod "cosmossdk.io/errors"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	wasmTypes "github.com/scrtlabs/SecretNetwork/go-cosmwasm/types"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+// validateCode rejects a contract that breaks the enclave's limits on code size and complexity,
+// or that the engine couldn't instantiate, before it's stored. Code that was already stored, or
+// that's imported from a genesis, isn't checked. See docs/code-limits.md.
+func validateCode(wasmCode []byte) error {
+	res, err := api.ValidateCode(wasmCode)
+	if err != nil {
+		return errorsmod.Wrap(types.ErrCreateFailed, err.Error())
+	}
+
+	var report wasmTypes.CodeValidationReport
+	if err := json.Unmarshal(res, &report); err != nil {
+		return errorsmod.Wrap(types.ErrCreateFailed, err.Error())
+	}
+	if !report.Valid {
+		return errorsmod.Wrap(types.ErrCodeRejected, strings.Join(report.Violations, "; "))
+	}
+
+	return nil
+}
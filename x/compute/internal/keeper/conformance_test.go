@@ -0,0 +1,141 @@
+package keeper
+
+import (
+	"encoding/json"
+	"os"
+	"path/filepath"
+	"testing"
+
+	sdk "github.com/cosmos/cosmos-sdk/types"
+	"github.com/stretchr/testify/require"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	cosmwasm "github.com/scrtlabs/SecretNetwork/go-cosmwasm/types"
+)
+
+// The conformance vectors record, for every call of the reference contracts, the gas it used,
+// its output and the storage accesses the enclave made. Generate them with an enclave built with
+// the conformance feature (make conformance-vectors), and replay them in SW and HW mode with
+// make go-conformance-tests and make go-conformance-tests-hw.
+const conformanceVectorsPath = "testdata/conformance/vectors.json"
+
+type ConformanceStep struct {
+	Call       string          `json:"call"`
+	Msg        string          `json:"msg"`
+	GasUsed    uint64          `json:"gas_used"`
+	Output     string          `json:"output"`
+	Error      string          `json:"error,omitempty"`
+	StorageOps json.RawMessage `json:"storage_ops"`
+}
+
+type ConformanceVector struct {
+	Contract string            `json:"contract"`
+	Steps    []ConformanceStep `json:"steps"`
+}
+
+// The calls only use fixed keys and values, so the vectors don't depend on the random wallets
+var conformanceExecMsgs = []string{
+	`{"get_state":{"key":"banana"}}`,
+	`{"set_state":{"key":"banana","value":"🍌"}}`,
+	`{"get_state":{"key":"banana"}}`,
+	`{"set_state":{"key":"banana","value":"🍎"}}`,
+	`{"get_state":{"key":"banana"}}`,
+	`{"remove_state":{"key":"banana"}}`,
+	`{"get_state":{"key":"banana"}}`,
+	`{"nop":{}}`,
+}
+
+func takeConformanceTrace(t *testing.T) json.RawMessage {
+	trace, err := api.TakeConformanceTrace()
+	require.NoError(t, err)
+	return trace
+}
+
+func stdErrorString(err cosmwasm.StdError) string {
+	if err.IsEmpty() {
+		return ""
+	}
+	return err.Error()
+}
+
+func runConformanceCorpus(t *testing.T) []ConformanceVector {
+	var vectors []ConformanceVector
+
+	for _, testContract := range testContracts {
+		ctx, keeper, codeID, _, walletA, privKeyA, _, _ := setupTest(t, testContract.WasmFilePath, sdk.NewCoins())
+		vector := ConformanceVector{Contract: testContract.CosmWasmVersion}
+
+		// drop whatever the setup recorded
+		takeConformanceTrace(t)
+
+		initMsg := `{"nop":{}}`
+		_, initCtx, contractAddress, _, initErr := initHelper(t, keeper, ctx, codeID, walletA, nil, privKeyA, initMsg, true, testContract.IsCosmWasmV1, defaultGasForTests)
+		require.Empty(t, initErr)
+		vector.Steps = append(vector.Steps, ConformanceStep{
+			Call:       "init",
+			Msg:        initMsg,
+			GasUsed:    initCtx.GasMeter().GasConsumed(),
+			StorageOps: takeConformanceTrace(t),
+		})
+
+		for _, msg := range conformanceExecMsgs {
+			_, _, data, _, gasUsed, execErr := execHelper(t, keeper, ctx, contractAddress, walletA, privKeyA, msg, true, testContract.IsCosmWasmV1, defaultGasForTests, 0)
+			vector.Steps = append(vector.Steps, ConformanceStep{
+				Call:       "execute",
+				Msg:        msg,
+				GasUsed:    gasUsed,
+				Output:     string(data),
+				Error:      stdErrorString(execErr),
+				StorageOps: takeConformanceTrace(t),
+			})
+		}
+
+		vectors = append(vectors, vector)
+	}
+
+	return vectors
+}
+
+// TestConformance replays the conformance vectors, or writes them when CONFORMANCE_GENERATE is
+// set. It needs an enclave built with the conformance feature.
+func TestConformance(t *testing.T) {
+	if _, err := api.TakeConformanceTrace(); err != nil {
+		t.Skip("the enclave wasn't built with the conformance feature")
+	}
+
+	generate := os.Getenv("CONFORMANCE_GENERATE") == "true"
+
+	var expected []ConformanceVector
+	if !generate {
+		bz, err := os.ReadFile(conformanceVectorsPath)
+		if os.IsNotExist(err) {
+			t.Skipf("%s not found, generate it with make conformance-vectors", conformanceVectorsPath)
+		}
+		require.NoError(t, err)
+		require.NoError(t, json.Unmarshal(bz, &expected))
+	}
+
+	vectors := runConformanceCorpus(t)
+
+	if generate {
+		bz, err := json.MarshalIndent(vectors, "", "  ")
+		require.NoError(t, err)
+		require.NoError(t, os.MkdirAll(filepath.Dir(conformanceVectorsPath), 0o755))
+		require.NoError(t, os.WriteFile(conformanceVectorsPath, append(bz, '\n'), 0o644))
+		return
+	}
+
+	require.Equal(t, len(expected), len(vectors))
+	for i, vector := range vectors {
+		require.Equal(t, expected[i].Contract, vector.Contract)
+		require.Equal(t, len(expected[i].Steps), len(vector.Steps), vector.Contract)
+		for j, step := range vector.Steps {
+			want := expected[i].Steps[j]
+			require.Equal(t, want.Msg, step.Msg, "%s step %d", vector.Contract, j)
+			require.Equal(t, want.GasUsed, step.GasUsed, "%s step %d: %s", vector.Contract, j, step.Msg)
+			require.Equal(t, want.Output, step.Output, "%s step %d: %s", vector.Contract, j, step.Msg)
+			require.Equal(t, want.Error, step.Error, "%s step %d: %s", vector.Contract, j, step.Msg)
+			require.JSONEq(t, string(want.StorageOps), string(step.StorageOps), "%s step %d: %s", vector.Contract, j, step.Msg)
+		}
+	}
+}
@@ -0,0 +1,196 @@
+package keeper
+
+import (
+	"encoding/binary"
+	"encoding/json"
+	"fmt"
+
+	errorsmod "cosmossdk.io/errors"
+	"cosmossdk.io/store/prefix"
+	storetypes "cosmossdk.io/store/types"
+	"github.com/cosmos/cosmos-sdk/runtime"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+	sdkerrors "github.com/cosmos/cosmos-sdk/types/errors"
+
+	wasmTypes "github.com/scrtlabs/SecretNetwork/go-cosmwasm/types"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+// cronScheduleSize is the size of a stored schedule: interval, gas budget and start height
+const cronScheduleSize = 24
+
+// cronSchedule is the schedule of a contract's block callbacks, due every interval blocks after
+// the start height. The enclave checks the schedule of each callback against the chain state.
+type cronSchedule struct {
+	interval    uint64
+	gasBudget   uint64
+	startHeight uint64
+}
+
+func (s cronSchedule) isDue(height uint64) bool {
+	return height > s.startHeight && (height-s.startHeight)%s.interval == 0
+}
+
+func (s cronSchedule) marshal() []byte {
+	bz := make([]byte, cronScheduleSize)
+	binary.BigEndian.PutUint64(bz[0:8], s.interval)
+	binary.BigEndian.PutUint64(bz[8:16], s.gasBudget)
+	binary.BigEndian.PutUint64(bz[16:24], s.startHeight)
+	return bz
+}
+
+func unmarshalCronSchedule(bz []byte) (cronSchedule, error) {
+	if len(bz) != cronScheduleSize {
+		return cronSchedule{}, fmt.Errorf("stored cron schedule is %d bytes", len(bz))
+	}
+	schedule := cronSchedule{
+		interval:    binary.BigEndian.Uint64(bz[0:8]),
+		gasBudget:   binary.BigEndian.Uint64(bz[8:16]),
+		startHeight: binary.BigEndian.Uint64(bz[16:24]),
+	}
+	if schedule.interval == 0 {
+		return cronSchedule{}, fmt.Errorf("stored cron schedule has no interval")
+	}
+	return schedule, nil
+}
+
+// cronCallbackMsg is what the enclave receives for a callback. It checks the schedule against the
+// chain state with the proof, and the contract only receives the height in its cron entry point.
+type cronCallbackMsg struct {
+	Height   uint64 `json:"height,string"`
+	Schedule []byte `json:"schedule"`
+	Proof    []byte `json:"proof"`
+}
+
+// SetContractCron schedules the block callbacks of a contract, or removes its schedule, as its
+// admin asked in msg. See docs/contract-cron.md.
+func (k Keeper) SetContractCron(ctx sdk.Context, msg *types.MsgSetContractCron) error {
+	contractAddress, err := sdk.AccAddressFromBech32(msg.Contract)
+	if err != nil {
+		return errorsmod.Wrap(err, "contract")
+	}
+	admin, err := sdk.AccAddressFromBech32(msg.Sender)
+	if err != nil {
+		return errorsmod.Wrap(err, "sender")
+	}
+
+	contractInfo := k.GetContractInfo(ctx, contractAddress)
+	if contractInfo == nil {
+		return errorsmod.Wrap(types.ErrNotFound, "contract")
+	}
+	if contractInfo.Admin != admin.String() {
+		return errorsmod.Wrap(sdkerrors.ErrUnauthorized, "only the admin can schedule the callbacks of a contract")
+	}
+
+	store := k.storeService.OpenKVStore(ctx)
+	cronKey := types.GetContractCronKey(contractAddress)
+	if msg.Interval == 0 {
+		return store.Delete(cronKey)
+	}
+
+	existing, err := store.Has(cronKey)
+	if err != nil {
+		return err
+	}
+	if !existing && k.countCronSchedules(ctx) >= types.MaxCronSchedules {
+		return errorsmod.Wrapf(types.ErrInvalid, "there are already %d contract crons", types.MaxCronSchedules)
+	}
+
+	schedule := cronSchedule{
+		interval:    msg.Interval,
+		gasBudget:   msg.GasBudget,
+		startHeight: uint64(ctx.BlockHeight()),
+	}
+	return store.Set(cronKey, schedule.marshal())
+}
+
+func (k Keeper) countCronSchedules(ctx sdk.Context) int {
+	prefixStore := prefix.NewStore(runtime.KVStoreAdapter(k.storeService.OpenKVStore(ctx)), types.ContractCronPrefix)
+	iter := prefixStore.Iterator(nil, nil)
+	defer iter.Close()
+
+	count := 0
+	for ; iter.Valid(); iter.Next() {
+		count++
+	}
+	return count
+}
+
+// ExecuteCronCallbacks runs the callbacks that are due at the current height, in the order of
+// the contracts' addresses. Each callback runs in its own cache context with its own gas budget, so
+// a failing callback is reverted without affecting the others or the block, and isn't retried.
+func (k Keeper) ExecuteCronCallbacks(ctx sdk.Context) {
+	height := uint64(ctx.BlockHeight())
+
+	var due []sdk.AccAddress
+	var schedules []cronSchedule
+
+	prefixStore := prefix.NewStore(runtime.KVStoreAdapter(k.storeService.OpenKVStore(ctx)), types.ContractCronPrefix)
+	iter := prefixStore.Iterator(nil, nil)
+	for ; iter.Valid(); iter.Next() {
+		schedule, err := unmarshalCronSchedule(iter.Value())
+		if err != nil {
+			panic(errorsmod.Wrapf(err, "failed to read the cron schedule of %s", sdk.AccAddress(iter.Key()).String()))
+		}
+		if schedule.isDue(height) {
+			due = append(due, append(sdk.AccAddress{}, iter.Key()...))
+			schedules = append(schedules, schedule)
+		}
+	}
+	iter.Close()
+
+	for i, contractAddress := range due {
+		err := k.executeCronCallback(ctx, contractAddress, schedules[i])
+
+		attributes := []sdk.Attribute{
+			sdk.NewAttribute(types.AttributeKeyContractAddr, contractAddress.String()),
+			sdk.NewAttribute(types.AttributeKeyHeight, fmt.Sprintf("%d", height)),
+		}
+		if err != nil {
+			ctx.Logger().Info("cron callback failed", "contract", contractAddress.String(), "error", err.Error())
+			attributes = append(attributes, sdk.NewAttribute(types.AttributeKeyError, err.Error()))
+		}
+		ctx.EventManager().EmitEvent(sdk.NewEvent(types.EventTypeCron, attributes...))
+	}
+}
+
+func (k Keeper) executeCronCallback(ctx sdk.Context, contractAddress sdk.AccAddress, schedule cronSchedule) (err error) {
+	cacheCtx, commit := ctx.CacheContext()
+	em := sdk.NewEventManager()
+	cacheCtx = cacheCtx.WithEventManager(em).WithGasMeter(storetypes.NewGasMeter(schedule.gasBudget))
+
+	defer func() {
+		if r := recover(); r != nil {
+			outOfGas, ok := r.(storetypes.ErrorOutOfGas)
+			if !ok {
+				panic(r)
+			}
+			err = errorsmod.Wrap(sdkerrors.ErrOutOfGas, outOfGas.Descriptor)
+		}
+	}()
+
+	// A due schedule wasn't set in this block, so it's also the schedule in the state of the last
+	// block, which the enclave checks it against
+	scheduleBz, proof, err := k.proveKey(ctx, types.GetContractCronKey(contractAddress))
+	if err != nil {
+		return err
+	}
+	msg, err := json.Marshal(cronCallbackMsg{
+		Height:   uint64(ctx.BlockHeight()),
+		Schedule: scheduleBz,
+		Proof:    proof,
+	})
+	if err != nil {
+		return err
+	}
+
+	_, err = k.Execute(cacheCtx, contractAddress, sdk.AccAddress{}, msg, sdk.NewCoins(), nil, wasmTypes.HandleTypeCron)
+	if err != nil {
+		return err
+	}
+
+	// on failure, the state and events of the callback are dropped with the cache context
+	commit()
+	ctx.EventManager().EmitEvents(em.Events())
+	return nil
+}
@@ -0,0 +1,46 @@
+package keeper
+
+import (
+	"encoding/json"
+	"fmt"
+	"os"
+	"path/filepath"
+
+	sdk "github.com/cosmos/cosmos-sdk/types"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+)
+
+// EngineDivergenceDir is the directory of the home dir differential builds write the engine
+// divergences they reported to
+const EngineDivergenceDir = "engine-divergences"
+
+// RecordEngineDivergences writes the engine divergences the enclave reported during the block to
+// engine-divergences/<height>.json in the home dir, as a json array. Only enclaves built with the
+// differential-execution feature report them. See docs/differential-execution.md.
+func (k Keeper) RecordEngineDivergences(ctx sdk.Context) {
+	res := api.TakeEngineDivergences()
+	if len(res) == 0 {
+		return
+	}
+
+	var divergences []json.RawMessage
+	if err := json.Unmarshal(res, &divergences); err != nil {
+		ctx.Logger().Error("invalid engine divergences", "error", err)
+		return
+	}
+	if len(divergences) == 0 {
+		return
+	}
+
+	dir := filepath.Join(k.HomeDir, EngineDivergenceDir)
+	if err := os.MkdirAll(dir, 0o700); err != nil {
+		ctx.Logger().Error("failed to write the engine divergences", "error", err)
+		return
+	}
+	path := filepath.Join(dir, fmt.Sprintf("%d.json", ctx.BlockHeight()))
+	if err := os.WriteFile(path, res, 0o600); err != nil {
+		ctx.Logger().Error("failed to write the engine divergences", "error", err)
+		return
+	}
+}
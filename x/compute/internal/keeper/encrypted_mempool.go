@@ -0,0 +1,166 @@
+package keeper
+
+import (
+	errorsmod "cosmossdk.io/errors"
+	"cosmossdk.io/store/prefix"
+	storetypes "cosmossdk.io/store/types"
+	"github.com/cosmos/cosmos-sdk/runtime"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+	sdkerrors "github.com/cosmos/cosmos-sdk/types/errors"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+// MempoolKey returns the public key users encrypt the txs they want included at height to
+func (k Keeper) MempoolKey(height uint64) ([]byte, error) {
+	publicKey, err := api.GetMempoolKey(height)
+	if err != nil {
+		return nil, errorsmod.Wrap(types.ErrInvalid, err.Error())
+	}
+	return publicKey, nil
+}
+
+// StoreEncryptedTx stores a MsgExecuteEncryptedTx to be decrypted and executed in the next block,
+// once the block that includes it is final. The sender pays for the gas limit of the encrypted
+// msgs here, since they're executed at the start of the next block. See docs/encrypted-mempool.md.
+func (k Keeper) StoreEncryptedTx(ctx sdk.Context, msg *types.MsgExecuteEncryptedTx) error {
+	ctx.GasMeter().ConsumeGas(msg.GasLimit, "encrypted tx")
+
+	bz, err := msg.Marshal()
+	if err != nil {
+		return err
+	}
+
+	store := k.storeService.OpenKVStore(ctx)
+	key := types.GetEncryptedTxKey(bz)
+	existing, err := store.Has(key)
+	if err != nil {
+		return err
+	}
+	if existing {
+		return errorsmod.Wrap(types.ErrInvalid, "the encrypted tx is already waiting to be executed")
+	}
+	return store.Set(key, bz)
+}
+
+// ExecutePendingEncryptedTxs decrypts and executes the encrypted txs stored until the last block,
+// in execute_encrypted_tx events. The enclave only decrypts a tx with a proof that it's in the
+// state of the last block, which the light client verified. It must be called once a block, after
+// the block is submitted to the enclave.
+func (k Keeper) ExecutePendingEncryptedTxs(ctx sdk.Context) {
+	pendingStore := prefix.NewStore(runtime.KVStoreAdapter(k.storeService.OpenKVStore(ctx)), types.EncryptedTxPrefix)
+
+	var hashes, pending [][]byte
+	iter := pendingStore.Iterator(nil, nil)
+	for ; iter.Valid(); iter.Next() {
+		hashes = append(hashes, append([]byte{}, iter.Key()...))
+		pending = append(pending, append([]byte{}, iter.Value()...))
+	}
+	iter.Close()
+
+	for i, msgBz := range pending {
+		var msg types.MsgExecuteEncryptedTx
+		if err := k.cdc.Unmarshal(msgBz, &msg); err != nil {
+			panic(errorsmod.Wrap(err, "failed to read a stored encrypted tx"))
+		}
+
+		err := k.executeEncryptedTx(ctx, &msg, msgBz)
+		// The tx is executed once, even if it failed
+		pendingStore.Delete(hashes[i])
+
+		attributes := []sdk.Attribute{
+			sdk.NewAttribute(sdk.AttributeKeySender, msg.Sender),
+		}
+		if err != nil {
+			ctx.Logger().Info("encrypted tx failed", "sender", msg.Sender, "error", err.Error())
+			attributes = append(attributes, sdk.NewAttribute(types.AttributeKeyError, err.Error()))
+		}
+		ctx.EventManager().EmitEvent(sdk.NewEvent(types.EventTypeExecuteEncryptedTx, attributes...))
+	}
+}
+
+// executeEncryptedTx has the enclave decrypt the tx of msg, and executes its msgs in order, in a
+// cache context with the gas limit of the msg. Every msg must be signed by the sender of the
+// MsgExecuteEncryptedTx, who signed the tx that carried it.
+func (k Keeper) executeEncryptedTx(ctx sdk.Context, msg *types.MsgExecuteEncryptedTx, msgBz []byte) (err error) {
+	sender, err := sdk.AccAddressFromBech32(msg.Sender)
+	if err != nil {
+		return errorsmod.Wrap(err, "sender")
+	}
+
+	provenBz, proof, err := k.proveKey(ctx, types.GetEncryptedTxKey(msgBz))
+	if err != nil {
+		return err
+	}
+	if provenBz == nil {
+		return errorsmod.Wrap(types.ErrNotFound, "encrypted tx")
+	}
+	plaintext, err := api.DecryptEncryptedTx(provenBz, proof)
+	if err != nil {
+		return errorsmod.Wrap(types.ErrInvalid, "encrypted tx: "+err.Error())
+	}
+
+	var body types.EncryptedTxBody
+	if err := k.cdc.Unmarshal(plaintext, &body); err != nil {
+		return errorsmod.Wrap(types.ErrInvalid, "encrypted tx: "+err.Error())
+	}
+	if len(body.Messages) == 0 {
+		return errorsmod.Wrap(types.ErrEmpty, "encrypted tx messages")
+	}
+
+	cacheCtx, commit := ctx.CacheContext()
+	em := sdk.NewEventManager()
+	cacheCtx = cacheCtx.WithEventManager(em).WithGasMeter(storetypes.NewGasMeter(msg.GasLimit))
+
+	defer func() {
+		if r := recover(); r != nil {
+			outOfGas, ok := r.(storetypes.ErrorOutOfGas)
+			if !ok {
+				panic(r)
+			}
+			err = errorsmod.Wrap(sdkerrors.ErrOutOfGas, outOfGas.Descriptor)
+		}
+	}()
+
+	for i, anyMsg := range body.Messages {
+		var sdkMsg sdk.Msg
+		if err := k.cdc.UnpackAny(anyMsg, &sdkMsg); err != nil {
+			return errorsmod.Wrapf(err, "encrypted tx message %d", i)
+		}
+
+		signers, _, err := k.cdc.GetMsgV1Signers(sdkMsg)
+		if err != nil {
+			return errorsmod.Wrapf(err, "encrypted tx message %d", i)
+		}
+		if len(signers) != 1 || !sender.Equals(sdk.AccAddress(signers[0])) {
+			return errorsmod.Wrapf(sdkerrors.ErrUnauthorized, "encrypted tx message %d isn't signed by the sender", i)
+		}
+		if m, ok := sdkMsg.(sdk.HasValidateBasic); ok {
+			if err := m.ValidateBasic(); err != nil {
+				return errorsmod.Wrapf(err, "encrypted tx message %d", i)
+			}
+		}
+
+		handler := k.msgRouter.Handler(sdkMsg)
+		if handler == nil {
+			return sdkerrors.ErrUnknownRequest.Wrapf("can't route encrypted tx message %d", i)
+		}
+		res, err := handler(cacheCtx, sdkMsg)
+		if err != nil {
+			return errorsmod.Wrapf(err, "encrypted tx message %d", i)
+		}
+
+		// The router runs every msg with its own event manager
+		events := make([]sdk.Event, len(res.Events))
+		for j := range res.Events {
+			events[j] = sdk.Event(res.Events[j])
+		}
+		em.EmitEvents(events)
+	}
+
+	// on failure, the state and events of the msgs are dropped with the cache context
+	commit()
+	ctx.EventManager().EmitEvents(em.Events())
+	return nil
+}
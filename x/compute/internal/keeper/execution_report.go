@@ -0,0 +1,31 @@
+package keeper
+
+import (
+	"github.com/cosmos/cosmos-sdk/telemetry"
+
+	wasm "github.com/scrtlabs/SecretNetwork/go-cosmwasm"
+	wasmTypes "github.com/scrtlabs/SecretNetwork/go-cosmwasm/types"
+)
+
+// recordExecutionReport exports the report the enclave returned for an execution as telemetry
+// metrics, for tuning fees and for chain analytics. Reports aren't part of consensus, so they never
+// reach the state or the events of the tx.
+func recordExecutionReport(_ wasm.CodeHash, report wasmTypes.ExecutionReport) {
+	telemetry.IncrCounter(float32(report.WasmGas), "compute", "execute", "wasm_gas")
+	for _, host := range report.HostFunctions {
+		telemetry.IncrCounter(float32(host.Calls), "compute", "execute", "host_calls", host.Name)
+		telemetry.IncrCounter(float32(host.Gas), "compute", "execute", "host_gas", host.Name)
+	}
+
+	telemetry.IncrCounter(float32(report.Storage.Reads), "compute", "execute", "storage_reads")
+	telemetry.IncrCounter(float32(report.Storage.CacheHits), "compute", "execute", "storage_cache_hits")
+	telemetry.IncrCounter(float32(report.Storage.Writes), "compute", "execute", "storage_writes")
+	telemetry.IncrCounter(float32(report.Storage.Removes), "compute", "execute", "storage_removes")
+
+	if report.ModuleCacheHit {
+		telemetry.IncrCounter(1, "compute", "execute", "module_cache_hits")
+	} else {
+		telemetry.IncrCounter(1, "compute", "execute", "module_cache_misses")
+	}
+	telemetry.SetGauge(float32(report.CallDepth), "compute", "execute", "call_depth")
+}
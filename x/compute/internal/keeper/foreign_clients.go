@@ -0,0 +1,179 @@
+package keeper
+
+import (
+	"bytes"
+
+	errorsmod "cosmossdk.io/errors"
+	"cosmossdk.io/store/prefix"
+	"github.com/cosmos/cosmos-sdk/runtime"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+// AuthorizeForeignClient stores a MsgCreateForeignClient that governance passed, and marks the
+// chain's client to be created in the next block. The enclave only creates the client with a
+// proof that the msg is in the module's state, which only governance can put it in. A client that
+// exists for the chain is replaced. See docs/foreign-light-clients.md.
+func (k Keeper) AuthorizeForeignClient(ctx sdk.Context, msg *types.MsgCreateForeignClient) error {
+	store := k.storeService.OpenKVStore(ctx)
+	existing, err := store.Get(types.GetForeignClientCreationKey(msg.ChainID))
+	if err != nil {
+		return err
+	}
+	if existing == nil && k.countForeignClients(ctx) >= types.MaxForeignClients {
+		return errorsmod.Wrapf(types.ErrLimit, "at most %d foreign clients", types.MaxForeignClients)
+	}
+
+	bz, err := msg.Marshal()
+	if err != nil {
+		return err
+	}
+	if err := store.Set(types.GetForeignClientCreationKey(msg.ChainID), bz); err != nil {
+		return err
+	}
+	return store.Set(types.GetPendingForeignClientKey(msg.ChainID), []byte{1})
+}
+
+// countForeignClients counts the chains that have a client, or will have one in the next block
+func (k Keeper) countForeignClients(ctx sdk.Context) int {
+	prefixStore := prefix.NewStore(runtime.KVStoreAdapter(k.storeService.OpenKVStore(ctx)), types.ForeignClientCreationPrefix)
+	iter := prefixStore.Iterator(nil, nil)
+	defer iter.Close()
+
+	count := 0
+	for ; iter.Valid(); iter.Next() {
+		count++
+	}
+	return count
+}
+
+// CreatePendingForeignClients creates the clients governance authorized until the last block. It
+// must be called once a block, after the block is submitted to the enclave.
+func (k Keeper) CreatePendingForeignClients(ctx sdk.Context) {
+	pendingStore := prefix.NewStore(runtime.KVStoreAdapter(k.storeService.OpenKVStore(ctx)), types.PendingForeignClientPrefix)
+
+	var pending []string
+	iter := pendingStore.Iterator(nil, nil)
+	for ; iter.Valid(); iter.Next() {
+		pending = append(pending, string(iter.Key()))
+	}
+	iter.Close()
+
+	for _, chainID := range pending {
+		pendingStore.Delete([]byte(chainID))
+		if err := k.createForeignClient(ctx, chainID); err != nil {
+			ctx.Logger().Error("Failed to create a foreign client", "chain_id", chainID, "error", err)
+			k.dropFailedForeignClient(ctx, chainID)
+		}
+	}
+}
+
+// dropFailedForeignClient forgets a creation the enclave rejected, so it doesn't take one of the
+// chains that can have a client
+func (k Keeper) dropFailedForeignClient(ctx sdk.Context, chainID string) {
+	store := k.storeService.OpenKVStore(ctx)
+	client, err := store.Get(types.GetForeignClientKey(chainID))
+	if err != nil || client != nil {
+		// the chain keeps the client it had
+		return
+	}
+	if err := store.Delete(types.GetForeignClientCreationKey(chainID)); err != nil {
+		ctx.Logger().Error("Failed to drop a foreign client creation", "chain_id", chainID, "error", err)
+	}
+}
+
+func (k Keeper) createForeignClient(ctx sdk.Context, chainID string) error {
+	msgBz, proof, err := k.proveKey(ctx, types.GetForeignClientCreationKey(chainID))
+	if err != nil {
+		return err
+	}
+	if msgBz == nil {
+		return errorsmod.Wrap(types.ErrNotFound, "foreign client creation")
+	}
+
+	client, err := api.CreateForeignClient(msgBz, proof)
+	if err != nil {
+		return errorsmod.Wrap(types.ErrInvalid, err.Error())
+	}
+	if err := k.storeService.OpenKVStore(ctx).Set(types.GetForeignClientKey(chainID), client); err != nil {
+		return err
+	}
+
+	ctx.EventManager().EmitEvent(sdk.NewEvent(
+		types.EventTypeCreateForeignClient,
+		sdk.NewAttribute(types.AttributeKeyChainID, chainID),
+	))
+	return nil
+}
+
+// UpdateForeignClient stores the light client of another chain once the enclave verified the
+// header of a MsgUpdateForeignClient with it. The enclave only updates the client with a proof
+// that it's the chain's client as of the last block, so a client can only be updated once a block.
+func (k Keeper) UpdateForeignClient(ctx sdk.Context, msg *types.MsgUpdateForeignClient) error {
+	key := types.GetForeignClientKey(msg.ChainID)
+	store := k.storeService.OpenKVStore(ctx)
+	current, err := store.Get(key)
+	if err != nil {
+		return err
+	}
+	if current == nil {
+		return errorsmod.Wrapf(types.ErrNotFound, "foreign client of %s", msg.ChainID)
+	}
+
+	client, proof, err := k.proveKey(ctx, key)
+	if err != nil {
+		return err
+	}
+	if !bytes.Equal(client, current) {
+		return errorsmod.Wrapf(types.ErrInvalid, "the foreign client of %s changed in this block, update it in the next", msg.ChainID)
+	}
+
+	bz, err := msg.Marshal()
+	if err != nil {
+		return err
+	}
+	updated, err := api.UpdateForeignClient(client, proof, bz)
+	if err != nil {
+		return errorsmod.Wrap(types.ErrInvalid, "foreign client: "+err.Error())
+	}
+	if err := store.Set(key, updated); err != nil {
+		return err
+	}
+
+	ctx.EventManager().EmitEvent(sdk.NewEvent(
+		types.EventTypeUpdateForeignClient,
+		sdk.NewAttribute(types.AttributeKeyChainID, msg.ChainID),
+	))
+	return nil
+}
+
+// PushForeignClients hands the enclave every stored light client with a proof of it. The enclave
+// answers contracts from the clients it was handed until the next block. It must be called once a
+// block, after the block is submitted to the enclave.
+func (k Keeper) PushForeignClients(ctx sdk.Context) {
+	prefixStore := prefix.NewStore(runtime.KVStoreAdapter(k.storeService.OpenKVStore(ctx)), types.ForeignClientPrefix)
+
+	var chainIDs []string
+	iter := prefixStore.Iterator(nil, nil)
+	for ; iter.Valid(); iter.Next() {
+		chainIDs = append(chainIDs, string(iter.Key()))
+	}
+	iter.Close()
+
+	for _, chainID := range chainIDs {
+		client, proof, err := k.proveKey(ctx, types.GetForeignClientKey(chainID))
+		if err != nil {
+			ctx.Logger().Error("Failed to prove a foreign client", "chain_id", chainID, "error", err)
+			continue
+		}
+		if client == nil {
+			// created in this block, so it can only be proven in the next
+			continue
+		}
+		if err := api.SubmitForeignClient([]byte(chainID), client, proof); err != nil {
+			ctx.Logger().Error("Failed to push a foreign client", "chain_id", chainID, "error", err)
+		}
+	}
+}
@@ -0,0 +1,32 @@
+package keeper
+
+import (
+	sdk "github.com/cosmos/cosmos-sdk/types"
+
+	wasmTypes "github.com/scrtlabs/SecretNetwork/go-cosmwasm/types"
+)
+
+// interchainAccount returns the controller of caller, if caller is an interchain account hosted
+// on this chain with an open channel. An interchain account doesn't sign the tx that executes it,
+// so the enclave checks the ICA packet in the tx against its controller instead. See
+// docs/interchain-accounts.md.
+func (k Keeper) interchainAccount(ctx sdk.Context, caller sdk.AccAddress) *wasmTypes.InterchainAccount {
+	if k.icaHostKeeper == nil {
+		return nil
+	}
+
+	for _, account := range k.icaHostKeeper.GetAllInterchainAccounts(ctx) {
+		if account.AccountAddress != caller.String() {
+			continue
+		}
+		channelID, found := k.icaHostKeeper.GetActiveChannelID(ctx, account.ConnectionId, account.PortId)
+		if !found {
+			return nil
+		}
+		return &wasmTypes.InterchainAccount{
+			ControllerPort: account.PortId,
+			HostChannel:    channelID,
+		}
+	}
+	return nil
+}
@@ -0,0 +1,203 @@
+package keeper
+
+import (
+	"bytes"
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"fmt"
+	"strconv"
+
+	errorsmod "cosmossdk.io/errors"
+	"cosmossdk.io/store/dbadapter"
+	storetypes "cosmossdk.io/store/types"
+	dbm "github.com/cosmos/cosmos-db"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+	sdkerrors "github.com/cosmos/cosmos-sdk/types/errors"
+
+	wasmTypes "github.com/scrtlabs/SecretNetwork/go-cosmwasm/types"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+// enqueuedJob is a job as it's stored, and handed to the enclave of a worker. It must match
+// `EnqueuedJob` in the enclave's job_message.rs.
+type enqueuedJob struct {
+	JobID           uint64 `json:"job_id,string"`
+	ContractAddress string `json:"contract_address"`
+	HandlerCodeHash []byte `json:"handler_code_hash"`
+	SealedInput     []byte `json:"sealed_input"`
+}
+
+// jobResultHeader is the part of a `SignedJobResult` the node reads before the enclave checks it.
+// The enclave checks the worker signed all of these.
+type jobResultHeader struct {
+	JobID           uint64 `json:"job_id,string"`
+	ContractAddress string `json:"contract_address"`
+	HandlerCodeHash []byte `json:"handler_code_hash"`
+	SealedInputHash []byte `json:"sealed_input_hash"`
+}
+
+// EnqueueJob stores a job the contract sender enqueued, with the input it sealed for the handler
+// of handlerCodeHash, until a worker submits its result. See docs/private-jobs.md.
+func (k Keeper) EnqueueJob(ctx sdk.Context, sender sdk.AccAddress, handlerCodeHash []byte, sealedInput []byte) (uint64, error) {
+	if !k.containsContractInfo(ctx, sender) {
+		return 0, errorsmod.Wrap(sdkerrors.ErrUnauthorized, "only contracts can enqueue jobs")
+	}
+
+	jobID := k.autoIncrementID(ctx, types.KeyLastJobID)
+	bz, err := json.Marshal(enqueuedJob{
+		JobID:           jobID,
+		ContractAddress: sender.String(),
+		HandlerCodeHash: handlerCodeHash,
+		SealedInput:     sealedInput,
+	})
+	if err != nil {
+		return 0, err
+	}
+
+	store := k.storeService.OpenKVStore(ctx)
+	if err := store.Set(types.GetJobKey(jobID), bz); err != nil {
+		return 0, err
+	}
+
+	ctx.EventManager().EmitEvent(sdk.NewEvent(
+		types.EventTypeEnqueueJob,
+		sdk.NewAttribute(types.AttributeKeyContractAddr, sender.String()),
+		sdk.NewAttribute(types.AttributeKeyJobID, strconv.FormatUint(jobID, 10)),
+	))
+	return jobID, nil
+}
+
+func (k Keeper) getJob(ctx sdk.Context, jobID uint64) (*enqueuedJob, []byte, error) {
+	store := k.storeService.OpenKVStore(ctx)
+	bz, err := store.Get(types.GetJobKey(jobID))
+	if err != nil {
+		return nil, nil, err
+	}
+	if bz == nil {
+		return nil, nil, errorsmod.Wrapf(types.ErrNotFound, "job %d", jobID)
+	}
+
+	var job enqueuedJob
+	if err := json.Unmarshal(bz, &job); err != nil {
+		return nil, nil, err
+	}
+	return &job, bz, nil
+}
+
+// GetJob returns a job that's waiting for its result
+func (k Keeper) GetJob(ctx sdk.Context, jobID uint64) (*types.QueryJobResponse, error) {
+	job, _, err := k.getJob(ctx, jobID)
+	if err != nil {
+		return nil, err
+	}
+	return &types.QueryJobResponse{
+		JobId:           job.JobID,
+		ContractAddress: job.ContractAddress,
+		HandlerCodeHash: hex.EncodeToString(job.HandlerCodeHash),
+		SealedInput:     job.SealedInput,
+	}, nil
+}
+
+// RunJob runs the handler of a job on the node's enclave, and returns the result it signed and
+// attested, for a worker to submit in a MsgSubmitJobResult. The enclave isolates the handler: its
+// storage reads find nothing and its queries fail, and the block in env is replaced with the last
+// block the enclave verified. The store is thrown away, so nothing the handler does is written to
+// the chain, and the node's state is only read to find the job, its contract and its handler.
+func (k Keeper) RunJob(ctx sdk.Context, jobID uint64) ([]byte, error) {
+	job, bz, err := k.getJob(ctx, jobID)
+	if err != nil {
+		return nil, err
+	}
+
+	contractAddress, err := sdk.AccAddressFromBech32(job.ContractAddress)
+	if err != nil {
+		return nil, err
+	}
+	_, codeInfo, _, err := k.contractInstance(ctx, contractAddress)
+	if err != nil {
+		return nil, err
+	}
+
+	var handler *types.CodeInfo
+	k.IterateCodeInfos(ctx, func(_ uint64, info types.CodeInfo) bool {
+		if bytes.Equal(info.CodeHash, job.HandlerCodeHash) {
+			handler = &info
+			return true
+		}
+		return false
+	})
+	if handler == nil {
+		return nil, errorsmod.Wrapf(types.ErrNotFound, "handler %s of job %d", hex.EncodeToString(job.HandlerCodeHash), jobID)
+	}
+
+	contractKey, err := k.GetContractKey(ctx, contractAddress)
+	if err != nil {
+		return nil, err
+	}
+	env := types.NewEnv(
+		ctx,
+		sdk.AccAddress{}, /* empty because it's unused in jobs */
+		sdk.NewCoins(),   /* empty because it's unused in jobs */
+		contractAddress,
+		contractKey,
+		[]byte{0}, /* empty because it's unused in jobs */
+	)
+
+	ctx = ctx.WithGasMeter(storetypes.NewGasMeter(k.queryGasLimit))
+	querier := QueryHandler{
+		Ctx:     ctx,
+		Plugins: k.queryPlugins,
+		Caller:  contractAddress,
+	}
+	store := dbadapter.Store{DB: dbm.NewMemDB()}
+
+	result, gasUsed, err := k.wasmer.RunJob(handler.CodeHash, codeInfo.CodeHash, env, bz, store, cosmwasmAPI, querier, gasMeter(ctx), gasForContract(ctx))
+	consumeGas(ctx, gasUsed)
+	if err != nil {
+		return nil, errorsmod.Wrap(types.ErrQueryFailed, err.Error())
+	}
+	return result, nil
+}
+
+// SubmitJobResult delivers the result of a job to the `job_result` entry point of the contract
+// that enqueued it, and removes the job once the contract accepted the result. The enclave checks
+// the worker's attestation and signature, and that the job wasn't delivered before. The node checks
+// the job is still waiting, and that the result is for the handler and input the job was enqueued
+// with.
+func (k Keeper) SubmitJobResult(ctx sdk.Context, sender sdk.AccAddress, result []byte) (*sdk.Result, error) {
+	var header jobResultHeader
+	if err := json.Unmarshal(result, &header); err != nil {
+		return nil, errorsmod.Wrap(types.ErrInvalid, fmt.Sprintf("job result: %s", err))
+	}
+
+	job, _, err := k.getJob(ctx, header.JobID)
+	if err != nil {
+		return nil, err
+	}
+	if job.ContractAddress != header.ContractAddress {
+		return nil, errorsmod.Wrapf(types.ErrInvalid, "job %d was enqueued by %s", header.JobID, job.ContractAddress)
+	}
+	if !bytes.Equal(job.HandlerCodeHash, header.HandlerCodeHash) {
+		return nil, errorsmod.Wrapf(types.ErrInvalid, "job %d was enqueued for handler %s", header.JobID, hex.EncodeToString(job.HandlerCodeHash))
+	}
+	sealedInputHash := sha256.Sum256(job.SealedInput)
+	if !bytes.Equal(sealedInputHash[:], header.SealedInputHash) {
+		return nil, errorsmod.Wrapf(types.ErrInvalid, "job %d was enqueued with a different input", header.JobID)
+	}
+	contractAddress, err := sdk.AccAddressFromBech32(job.ContractAddress)
+	if err != nil {
+		return nil, err
+	}
+
+	res, err := k.Execute(ctx, contractAddress, sender, result, sdk.NewCoins(), nil, wasmTypes.HandleTypeJobResult)
+	if err != nil {
+		return nil, err
+	}
+
+	store := k.storeService.OpenKVStore(ctx)
+	if err := store.Delete(types.GetJobKey(header.JobID)); err != nil {
+		return nil, err
+	}
+	return res, nil
+}
@@ -2,6 +2,7 @@ package keeper
 
 import (
 	"bytes"
+	"context"
 	"crypto/sha256"
 	"encoding/binary"
 	"encoding/hex"
@@ -10,6 +11,7 @@ import (
 	"path/filepath"
 	"strconv"
 	"strings"
+	"sync/atomic"
 	"time"
 
 	capabilitykeeper "github.com/cosmos/ibc-go/modules/capability/keeper"
@@ -81,6 +83,10 @@ type Keeper struct {
 	wasmer           wasm.Wasmer
 	queryPlugins     QueryPlugins
 	messenger        Messenger
+	// icaHostKeeper finds the controllers of the interchain accounts that execute contracts
+	icaHostKeeper types.ICAHostKeeper
+	// msgRouter executes the msgs of encrypted txs
+	msgRouter MessageRouter
 	// queryGasLimit is the max wasm gas that can be spent on executing a query with a contract
 	queryGasLimit uint64
 	maxCallDepth  uint32
@@ -89,6 +95,8 @@ type Keeper struct {
 	// paramSpace    subspace.Subspace
 	LastMsgManager *baseapp.LastMsgMarkerContainer
 	authority      string
+	// cms is the app's multistore, to prove the module's state to the enclave
+	cms storetypes.CommitMultiStore
 }
 
 func moduleLogger(ctx sdk.Context) log.Logger {
@@ -115,6 +123,7 @@ func NewKeeper(
 	portSource types.ICS20TransferPortSource,
 	channelKeeper channelkeeper.Keeper,
 	ics4Wrapper porttypes.ICS4Wrapper,
+	icaHostKeeper types.ICAHostKeeper,
 	msgRouter MessageRouter,
 	queryRouter GRPCQueryRouter,
 	homeDir string,
@@ -125,10 +134,16 @@ func NewKeeper(
 	lastMsgManager *baseapp.LastMsgMarkerContainer,
 	authority string,
 ) Keeper {
-	wasmer, err := wasm.NewWasmer(filepath.Join(homeDir, "wasm"), supportedFeatures, wasmConfig.CacheSize, wasmConfig.EnclaveCacheSize, wasmConfig.InitEnclave)
+	queryLimits := wasm.QueryLimits{
+		MemoryPages: wasmConfig.QueryMemoryLimitPages,
+		Gas:         wasmConfig.QueryGasLimit,
+		TimeMs:      wasmConfig.QueryTimeLimitMs,
+	}
+	wasmer, err := wasm.NewWasmer(filepath.Join(homeDir, "wasm"), supportedFeatures, wasmConfig.CacheSize, wasmConfig.EnclaveCacheSize, queryLimits, wasmConfig.InitEnclave)
 	if err != nil {
 		panic(err)
 	}
+	wasmer.SetExecutionReportHandler(recordExecutionReport)
 
 	keeper := Keeper{
 		storeService:     storeService,
@@ -148,6 +163,8 @@ func NewKeeper(
 			portSource,
 			cdc,
 		),
+		icaHostKeeper:  icaHostKeeper,
+		msgRouter:      msgRouter,
 		queryGasLimit:  wasmConfig.SmartQueryGasLimit,
 		maxCallDepth:   types.DefaultMaxCallDepth,
 		HomeDir:        homeDir,
@@ -190,6 +207,9 @@ func (k Keeper) Create(ctx sdk.Context, creator sdk.AccAddress, wasmCode []byte,
 	if err != nil {
 		return 0, errorsmod.Wrap(types.ErrCreateFailed, err.Error())
 	}
+	if err := validateCode(wasmCode); err != nil {
+		return 0, err
+	}
 	store := k.storeService.OpenKVStore(ctx)
 	codeID = k.autoIncrementID(ctx, types.KeyLastCodeID)
 
@@ -676,17 +696,29 @@ func (k Keeper) Execute(ctx sdk.Context, contractAddress sdk.AccAddress, caller
 	modeInfoBytes := []byte{}
 	pkBytes := []byte{}
 	signerSig := []byte{}
+	var interchainAccount *wasmTypes.InterchainAccount
 	var err error
 
-	// If no callback signature - we should send the actual msg sender sign bytes and signature
-	if callbackSig == nil {
+	// If no callback signature - we should send the actual msg sender sign bytes and signature.
+	// Cron callbacks are sent by the chain at the end of the block, without a tx.
+	if callbackSig == nil && handleType != wasmTypes.HandleTypeCron {
 		signBytes, signMode, modeInfoBytes, pkBytes, signerSig, err = k.GetTxInfo(ctx, caller)
 		if err != nil {
-			return nil, err
+			// An interchain account isn't a signer of the relayer's tx that executes it, so the
+			// relayer's signature is passed, and the enclave checks the ICA packet in the tx
+			interchainAccount = k.interchainAccount(ctx, caller)
+			if interchainAccount == nil {
+				return nil, err
+			}
+			signBytes, signMode, modeInfoBytes, pkBytes, signerSig, err = k.GetTxInfo(ctx, nil)
+			if err != nil {
+				return nil, err
+			}
 		}
 	}
 
 	sigInfo := types.NewSigInfo(ctx.TxBytes(), signBytes, signMode, modeInfoBytes, pkBytes, signerSig, callbackSig)
+	sigInfo.InterchainAccount = interchainAccount
 
 	contractInfo, codeInfo, prefixStore, err := k.contractInstance(ctx, contractAddress)
 	if err != nil {
@@ -817,6 +849,11 @@ func (k Keeper) querySmartImpl(ctx sdk.Context, contractAddress sdk.AccAddress,
 		[]byte{0}, /* empty because it's unused in queries */
 	)
 	params.QueryDepth = queryDepth
+	params.QueryCacheable = isQueryCacheable(ctx, queryDepth)
+	if params.QueryCacheable {
+		stop := cancelQueryWhenDone(ctx, &params)
+		defer stop()
+	}
 
 	queryResult, gasUsed, qErr := k.wasmer.Query(codeInfo.CodeHash, params, req, prefixStore, cosmwasmAPI, querier, gasMeter(ctx), gasForContract(ctx))
 	consumeGas(ctx, gasUsed)
@@ -829,6 +866,34 @@ func (k Keeper) querySmartImpl(ctx sdk.Context, contractAddress sdk.AccAddress,
 	return queryResult, nil
 }
 
+// isQueryCacheable is true for queries from outside the chain, e.g. over gRPC, which see the
+// committed state of their height. Queries made while a block or a tx is executed see state that
+// is still changing, so their responses must not be cached.
+func isQueryCacheable(ctx sdk.Context, queryDepth uint32) bool {
+	return queryDepth == 1 && ctx.ExecMode() == sdk.ExecModeCheck && len(ctx.TxBytes()) == 0
+}
+
+// queryCancellationIDs numbers the queries the enclave can be asked to cancel
+var queryCancellationIDs atomic.Uint64
+
+// cancelQueryWhenDone gives a query from outside the chain an id to cancel it with, and cancels it
+// in the enclave once ctx is done, e.g. because the client's deadline passed, so a stuck query
+// doesn't hold a thread of the enclave. See docs/query-cancellation.md.
+func cancelQueryWhenDone(ctx sdk.Context, params *wasmTypes.Env) (stop func() bool) {
+	goCtx := ctx.Context()
+	if goCtx == nil || goCtx.Done() == nil {
+		return func() bool { return false }
+	}
+
+	id := queryCancellationIDs.Add(1)
+	params.QueryCancellationID = id
+	return context.AfterFunc(goCtx, func() {
+		if err := api.CancelQuery(id); err != nil {
+			ctx.Logger().Error("failed to cancel a query", "error", err.Error())
+		}
+	})
+}
+
 func checkAndIncreaseCallDepth(ctx sdk.Context, maxCallDepth uint32) (sdk.Context, error) {
 	var callDepth uint32
 	if size, ok := types.CallDepth(ctx); ok {
@@ -45,7 +45,7 @@ func init() {
 	fmt.Printf("This IS spid: %v\n", spid)
 	fmt.Printf("This IS api key: %v\n", apiKey)
 
-	_, err = api.InitBootstrap(spid, apiKey)
+	_, err = api.InitBootstrap(spid, apiKey, TestConfig.ChainID)
 	if err != nil {
 		panic(fmt.Sprintf("Error initializing the enclave: %v", err))
 	}
@@ -194,6 +194,40 @@ func TestIsSimulationMode(t *testing.T) {
 	}
 }
 
+func TestIsQueryCacheable(t *testing.T) {
+	specs := map[string]struct {
+		ctx        sdk.Context
+		queryDepth uint32
+		exp        bool
+	}{
+		"grpc query": {
+			ctx:        sdk.Context{}.WithBlockHeight(1),
+			queryDepth: 1,
+			exp:        true,
+		},
+		"query from a contract": {
+			ctx:        sdk.Context{}.WithBlockHeight(1),
+			queryDepth: 2,
+			exp:        false,
+		},
+		"check tx": {
+			ctx:        sdk.Context{}.WithBlockHeight(1).WithTxBytes([]byte("tx")),
+			queryDepth: 1,
+			exp:        false,
+		},
+		"finalize block": {
+			ctx:        sdk.Context{}.WithBlockHeight(1).WithExecMode(sdk.ExecModeFinalize),
+			queryDepth: 1,
+			exp:        false,
+		},
+	}
+	for msg, spec := range specs {
+		t.Run(msg, func(t *testing.T) {
+			require.Equal(t, spec.exp, isQueryCacheable(spec.ctx, spec.queryDepth))
+		})
+	}
+}
+
 func TestCreateWithGzippedPayload(t *testing.T) {
 	encodingConfig := MakeEncodingConfig()
 	var transferPortSource types.ICS20TransferPortSource
@@ -0,0 +1,135 @@
+package keeper
+
+import (
+	"encoding/hex"
+	"encoding/json"
+
+	errorsmod "cosmossdk.io/errors"
+	"cosmossdk.io/store/prefix"
+	"github.com/cosmos/cosmos-sdk/runtime"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+	sdkerrors "github.com/cosmos/cosmos-sdk/types/errors"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+// AuthorizeContractKeyDisclosure stores a MsgDiscloseContractKey that governance passed, and marks
+// the contract's key to be disclosed in the next block. The enclave only discloses the key with a
+// proof that the msg is in the module's state, which only governance can put it in. The nonce must
+// be above the one of the contract's last disclosure, so the admin's signature can't be replayed.
+// See docs/contract-key-disclosure.md.
+func (k Keeper) AuthorizeContractKeyDisclosure(ctx sdk.Context, msg *types.MsgDiscloseContractKey) error {
+	contractAddress, err := sdk.AccAddressFromBech32(msg.Contract)
+	if err != nil {
+		return errorsmod.Wrap(err, "contract")
+	}
+	contractInfo, _, _, err := k.contractInstance(ctx, contractAddress)
+	if err != nil {
+		return err
+	}
+	if contractInfo.Admin == "" {
+		return errorsmod.Wrap(sdkerrors.ErrUnauthorized, "contracts without an admin can't be disclosed")
+	}
+
+	store := k.storeService.OpenKVStore(ctx)
+	disclosureKey := types.GetContractDisclosureKey(contractAddress)
+	lastBz, err := store.Get(disclosureKey)
+	if err != nil {
+		return err
+	}
+	if lastBz != nil {
+		var last types.MsgDiscloseContractKey
+		if err := k.cdc.Unmarshal(lastBz, &last); err != nil {
+			return err
+		}
+		if msg.Nonce <= last.Nonce {
+			return errorsmod.Wrapf(types.ErrInvalid, "nonce must be above %d", last.Nonce)
+		}
+	}
+
+	bz, err := msg.Marshal()
+	if err != nil {
+		return err
+	}
+	if err := store.Set(disclosureKey, bz); err != nil {
+		return err
+	}
+	return store.Set(types.GetPendingContractDisclosureKey(contractAddress), []byte{1})
+}
+
+// DisclosePendingContractKeys discloses the keys governance authorized until the last block, in
+// disclose_contract_key events. It must be called once a block, after the block is submitted to
+// the enclave.
+func (k Keeper) DisclosePendingContractKeys(ctx sdk.Context) {
+	pendingStore := prefix.NewStore(runtime.KVStoreAdapter(k.storeService.OpenKVStore(ctx)), types.PendingContractDisclosurePrefix)
+
+	var pending []sdk.AccAddress
+	iter := pendingStore.Iterator(nil, nil)
+	for ; iter.Valid(); iter.Next() {
+		pending = append(pending, append(sdk.AccAddress{}, iter.Key()...))
+	}
+	iter.Close()
+
+	for _, contractAddress := range pending {
+		pendingStore.Delete(contractAddress)
+		if err := k.discloseContractKey(ctx, contractAddress); err != nil {
+			ctx.Logger().Error("Failed to disclose a contract key", "contract", contractAddress.String(), "error", err)
+		}
+	}
+}
+
+func (k Keeper) discloseContractKey(ctx sdk.Context, contractAddress sdk.AccAddress) error {
+	contractInfo, codeInfo, _, err := k.contractInstance(ctx, contractAddress)
+	if err != nil {
+		return err
+	}
+	if contractInfo.Admin == "" {
+		return errorsmod.Wrap(sdkerrors.ErrUnauthorized, "contracts without an admin can't be disclosed")
+	}
+	admin, err := sdk.AccAddressFromBech32(contractInfo.Admin)
+	if err != nil {
+		return errorsmod.Wrap(err, "admin")
+	}
+
+	contractKey, err := k.GetContractKey(ctx, contractAddress)
+	if err != nil {
+		return err
+	}
+	env, err := json.Marshal(types.NewEnv(
+		ctx,
+		admin,
+		sdk.NewCoins(), /* empty because it's unused in disclosures */
+		contractAddress,
+		contractKey,
+		[]byte{0}, /* empty because it's unused in disclosures */
+	))
+	if err != nil {
+		return err
+	}
+
+	msgBz, proof, err := k.proveKey(ctx, types.GetContractDisclosureKey(contractAddress))
+	if err != nil {
+		return err
+	}
+	if msgBz == nil {
+		return errorsmod.Wrap(types.ErrNotFound, "key disclosure")
+	}
+	var msg types.MsgDiscloseContractKey
+	if err := k.cdc.Unmarshal(msgBz, &msg); err != nil {
+		return err
+	}
+
+	encryptedKey, err := api.DiscloseContractKey(env, codeInfo.CodeHash, admin, contractInfo.AdminProof, msgBz, proof)
+	if err != nil {
+		return errorsmod.Wrap(types.ErrInvalid, err.Error())
+	}
+
+	ctx.EventManager().EmitEvent(sdk.NewEvent(
+		types.EventTypeDiscloseContractKey,
+		sdk.NewAttribute(types.AttributeKeyContractAddr, contractAddress.String()),
+		sdk.NewAttribute(types.AttributeKeyAuditor, hex.EncodeToString(msg.AuditorPublicKey)),
+		sdk.NewAttribute(types.AttributeKeyEncryptedKey, hex.EncodeToString(encryptedKey)),
+	))
+	return nil
+}
@@ -0,0 +1,60 @@
+package keeper
+
+import (
+	"encoding/hex"
+	"encoding/json"
+	"os"
+	"path/filepath"
+
+	sdk "github.com/cosmos/cosmos-sdk/types"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	wasmTypes "github.com/scrtlabs/SecretNetwork/go-cosmwasm/types"
+)
+
+const (
+	// moduleCacheSnapshotInterval is how many blocks apart the enclave's module cache is
+	// snapshotted. Every node snapshots at the same heights, so their snapshots can be compared.
+	moduleCacheSnapshotInterval = 1000
+	// ModuleCacheSnapshotFile is where the latest snapshot is kept, in the wasm directory of the node
+	ModuleCacheSnapshotFile = "module_cache_snapshot.json"
+)
+
+// RecordModuleCacheSnapshot writes a signed snapshot of the enclave's module cache to the wasm
+// directory every moduleCacheSnapshotInterval blocks, and logs its digest, so operators can tell
+// whether nodes that disagree ran contracts with the same artifacts. The snapshot is local to the
+// node, so it never reaches the state.
+func (k Keeper) RecordModuleCacheSnapshot(ctx sdk.Context) {
+	if ctx.BlockHeight()%moduleCacheSnapshotInterval != 0 {
+		return
+	}
+
+	res, err := api.ModuleCacheSnapshot()
+	if err != nil || res == nil {
+		ctx.Logger().Debug("failed to snapshot the enclave's module cache", "error", err)
+		return
+	}
+
+	var snapshot wasmTypes.ModuleCacheSnapshot
+	if err := json.Unmarshal(res, &snapshot); err != nil {
+		ctx.Logger().Error("invalid module cache snapshot", "error", err)
+		return
+	}
+
+	// Replaced in one step, so a reader never sees half a snapshot
+	path := filepath.Join(k.HomeDir, "wasm", ModuleCacheSnapshotFile)
+	if err := os.WriteFile(path+".tmp", res, 0o600); err != nil {
+		ctx.Logger().Error("failed to write the module cache snapshot", "error", err)
+		return
+	}
+	if err := os.Rename(path+".tmp", path); err != nil {
+		ctx.Logger().Error("failed to write the module cache snapshot", "error", err)
+		return
+	}
+
+	ctx.Logger().Info("snapshotted the enclave's module cache",
+		"height", ctx.BlockHeight(),
+		"modules", len(snapshot.Entries),
+		"digest", hex.EncodeToString(snapshot.Digest),
+	)
+}
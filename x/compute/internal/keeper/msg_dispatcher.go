@@ -187,6 +187,43 @@ func redactError(err error) (bool, error) {
 	return true, fmt.Errorf("the error was redacted (codespace: %s, code: %d). For more info use latest localsecret and reproduce the issue", codespace, code)
 }
 
+// subMsgError is how a failed submessage is passed to the enclave, which turns it into a stable error code
+// for the contract. See cosmwasm/enclaves/shared/contract-engine/src/submsg_errors.rs
+type subMsgError struct {
+	ContractError *string `json:"contract_error,omitempty"`
+	Codespace     string  `json:"codespace,omitempty"`
+	Code          uint32  `json:"code,omitempty"`
+}
+
+func subMsgResultErr(err error, isSdkError bool, redactedErr error, isEncrypted bool) string {
+	// The enclave decrypts the error of an encrypted reply, which is always the called contract's
+	if isEncrypted {
+		return redactedErr.Error()
+	}
+
+	var subMsgErr subMsgError
+	switch {
+	case !isSdkError && wasmTypes.ToSystemError(err) == nil:
+		contractError := redactedErr.Error()
+		subMsgErr.ContractError = &contractError
+	case wasmTypes.ToSystemError(err) != nil && wasmTypes.ToSystemError(err).NoSuchContract != nil:
+		subMsgErr.Codespace, subMsgErr.Code = types.ErrNotFound.Codespace(), types.ErrNotFound.ABCICode()
+	default:
+		codespace, code, _ := errorsmod.ABCIInfo(err, false)
+		// The enclave's out of gas error reaches here as the message of an ErrExecuteFailed
+		if codespace == types.DefaultCodespace && strings.Contains(err.Error(), wasmTypes.OutOfGasError{}.Error()) {
+			codespace, code = sdkerrors.ErrOutOfGas.Codespace(), sdkerrors.ErrOutOfGas.ABCICode()
+		}
+		subMsgErr.Codespace, subMsgErr.Code = codespace, code
+	}
+
+	bz, marshalErr := json.Marshal(subMsgErr)
+	if marshalErr != nil {
+		return redactedErr.Error()
+	}
+	return string(bz)
+}
+
 // DispatchSubmessages builds a sandbox to execute these messages and returns the execution result to the contract
 // that dispatched them, both on success as well as failure
 func (d MessageDispatcher) DispatchSubmessages(ctx sdk.Context, contractAddr sdk.AccAddress, ibcPort string, msgs []v1wasmTypes.SubMsg, ogTx []byte, ogSigInfo wasmTypes.SigInfo) ([]byte, error) {
@@ -284,8 +321,15 @@ func (d MessageDispatcher) DispatchSubmessages(ctx sdk.Context, contractAddr sdk
 			moduleLogger(ctx).Info("Redacting submessage error", "cause", err)
 			isSdkError, redactedErr = redactError(err)
 			result = v1wasmTypes.SubMsgResult{
-				Err: redactedErr.Error(),
+				Err: subMsgResultErr(err, isSdkError, redactedErr, isReplyEncrypted(msg)),
 			}
+
+			// The contract only gets the code of the error, the human readable error goes to the events
+			ctx.EventManager().EmitEvent(sdk.NewEvent(
+				types.EventTypeSubMsgError,
+				sdk.NewAttribute(types.AttributeKeySubMsgID, fmt.Sprint(msg.ID)),
+				sdk.NewAttribute(types.AttributeKeyError, redactedErr.Error()),
+			))
 		}
 
 		msg_id := []byte(fmt.Sprint(msg.ID))
@@ -2,6 +2,7 @@ package keeper
 
 import (
 	"context"
+	"encoding/hex"
 	"fmt"
 
 	errorsmod "cosmossdk.io/errors"
@@ -88,6 +89,18 @@ func (m msgServer) ExecuteContract(goCtx context.Context, msg *types.MsgExecuteC
 		sdk.NewAttribute(types.AttributeKeyContractAddr, msg.Contract.String()),
 	))
 
+	// Simulated txs are executed by the enclave's simulation, which meters them exactly without
+	// requiring them to be in a block
+	if ctx.ExecMode() == sdk.ExecModeSimulate && msg.CallbackSig == nil {
+		data, err := m.keeper.Simulate(ctx, msg.Contract, msg.Sender, msg.Msg, msg.SentFunds)
+		if err != nil {
+			return nil, err
+		}
+		return &types.MsgExecuteContractResponse{
+			Data: data,
+		}, nil
+	}
+
 	data, err := m.keeper.Execute(ctx, msg.Contract, msg.Sender, msg.Msg, msg.SentFunds, msg.CallbackSig, wasmtypes.HandleTypeExecute)
 
 	if data == nil {
@@ -197,6 +210,9 @@ func (m msgServer) UpdateParams(goCtx context.Context, req *types.MsgUpdateParam
 	}
 
 	ctx := sdk.UnwrapSDKContext(goCtx)
+	if err := m.keeper.ValidateEnclaveParams(req.Params); err != nil {
+		return nil, err
+	}
 	if err := m.keeper.SetParams(ctx, req.Params); err != nil {
 		return nil, err
 	}
@@ -204,6 +220,19 @@ func (m msgServer) UpdateParams(goCtx context.Context, req *types.MsgUpdateParam
 	return &types.MsgUpdateParamsResponse{}, nil
 }
 
+func (m msgServer) DiscloseContractKey(goCtx context.Context, req *types.MsgDiscloseContractKey) (*types.MsgDiscloseContractKeyResponse, error) {
+	if m.keeper.authority != req.Authority {
+		return nil, errorsmod.Wrapf(govtypes.ErrInvalidSigner, "invalid authority; expected %s, got %s", m.keeper.authority, req.Authority)
+	}
+
+	ctx := sdk.UnwrapSDKContext(goCtx)
+	if err := m.keeper.AuthorizeContractKeyDisclosure(ctx, req); err != nil {
+		return nil, err
+	}
+
+	return &types.MsgDiscloseContractKeyResponse{}, nil
+}
+
 func (m msgServer) UpgradeProposalPassed(goCtx context.Context, msg *types.MsgUpgradeProposalPassed) (*types.MsgUpgradeProposalPassedResponse, error) {
 	ctx := sdk.UnwrapSDKContext(goCtx)
 
@@ -223,3 +252,150 @@ func (m msgServer) UpgradeProposalPassed(goCtx context.Context, msg *types.MsgUp
 
 	return &types.MsgUpgradeProposalPassedResponse{}, nil
 }
+
+func (m msgServer) EnqueueJob(goCtx context.Context, msg *types.MsgEnqueueJob) (*types.MsgEnqueueJobResponse, error) {
+	if err := msg.ValidateBasic(); err != nil {
+		return nil, err
+	}
+
+	ctx := sdk.UnwrapSDKContext(goCtx)
+	senderAddr, err := sdk.AccAddressFromBech32(msg.Sender)
+	if err != nil {
+		return nil, errorsmod.Wrap(err, "sender")
+	}
+	handlerCodeHash, err := hex.DecodeString(msg.HandlerCodeHash)
+	if err != nil {
+		return nil, errorsmod.Wrap(err, "handler code hash")
+	}
+
+	ctx.EventManager().EmitEvent(sdk.NewEvent(
+		sdk.EventTypeMessage,
+		sdk.NewAttribute(sdk.AttributeKeyModule, types.ModuleName),
+		sdk.NewAttribute(sdk.AttributeKeySender, msg.Sender),
+	))
+
+	jobID, err := m.keeper.EnqueueJob(ctx, senderAddr, handlerCodeHash, msg.SealedInput)
+	if err != nil {
+		return nil, err
+	}
+
+	return &types.MsgEnqueueJobResponse{
+		JobID: jobID,
+	}, nil
+}
+
+func (m msgServer) SubmitJobResult(goCtx context.Context, msg *types.MsgSubmitJobResult) (*types.MsgSubmitJobResultResponse, error) {
+	if err := msg.ValidateBasic(); err != nil {
+		return nil, err
+	}
+
+	ctx := sdk.UnwrapSDKContext(goCtx)
+	senderAddr, err := sdk.AccAddressFromBech32(msg.Sender)
+	if err != nil {
+		return nil, errorsmod.Wrap(err, "sender")
+	}
+
+	ctx.EventManager().EmitEvent(sdk.NewEvent(
+		sdk.EventTypeMessage,
+		sdk.NewAttribute(sdk.AttributeKeyModule, types.ModuleName),
+		sdk.NewAttribute(sdk.AttributeKeySender, msg.Sender),
+	))
+
+	res, err := m.keeper.SubmitJobResult(ctx, senderAddr, msg.Result)
+	if err != nil {
+		return nil, err
+	}
+
+	return &types.MsgSubmitJobResultResponse{
+		Data: res.Data,
+	}, nil
+}
+
+func (m msgServer) ImportContractState(goCtx context.Context, msg *types.MsgImportContractState) (*types.MsgImportContractStateResponse, error) {
+	if err := msg.ValidateBasic(); err != nil {
+		return nil, err
+	}
+
+	ctx := sdk.UnwrapSDKContext(goCtx)
+	ctx.EventManager().EmitEvent(sdk.NewEvent(
+		sdk.EventTypeMessage,
+		sdk.NewAttribute(sdk.AttributeKeyModule, types.ModuleName),
+		sdk.NewAttribute(sdk.AttributeKeySender, msg.Sender),
+	))
+
+	if err := m.keeper.ImportContractState(ctx, msg); err != nil {
+		return nil, err
+	}
+
+	return &types.MsgImportContractStateResponse{}, nil
+}
+
+func (m msgServer) SetContractCron(goCtx context.Context, msg *types.MsgSetContractCron) (*types.MsgSetContractCronResponse, error) {
+	if err := msg.ValidateBasic(); err != nil {
+		return nil, err
+	}
+
+	ctx := sdk.UnwrapSDKContext(goCtx)
+	ctx.EventManager().EmitEvent(sdk.NewEvent(
+		sdk.EventTypeMessage,
+		sdk.NewAttribute(sdk.AttributeKeyModule, types.ModuleName),
+		sdk.NewAttribute(sdk.AttributeKeySender, msg.Sender),
+	))
+
+	if err := m.keeper.SetContractCron(ctx, msg); err != nil {
+		return nil, err
+	}
+
+	return &types.MsgSetContractCronResponse{}, nil
+}
+
+func (m msgServer) CreateForeignClient(goCtx context.Context, req *types.MsgCreateForeignClient) (*types.MsgCreateForeignClientResponse, error) {
+	if m.keeper.authority != req.Authority {
+		return nil, errorsmod.Wrapf(govtypes.ErrInvalidSigner, "invalid authority; expected %s, got %s", m.keeper.authority, req.Authority)
+	}
+
+	ctx := sdk.UnwrapSDKContext(goCtx)
+	if err := m.keeper.AuthorizeForeignClient(ctx, req); err != nil {
+		return nil, err
+	}
+
+	return &types.MsgCreateForeignClientResponse{}, nil
+}
+
+func (m msgServer) UpdateForeignClient(goCtx context.Context, msg *types.MsgUpdateForeignClient) (*types.MsgUpdateForeignClientResponse, error) {
+	if err := msg.ValidateBasic(); err != nil {
+		return nil, err
+	}
+
+	ctx := sdk.UnwrapSDKContext(goCtx)
+	ctx.EventManager().EmitEvent(sdk.NewEvent(
+		sdk.EventTypeMessage,
+		sdk.NewAttribute(sdk.AttributeKeyModule, types.ModuleName),
+		sdk.NewAttribute(sdk.AttributeKeySender, msg.Signer),
+	))
+
+	if err := m.keeper.UpdateForeignClient(ctx, msg); err != nil {
+		return nil, err
+	}
+
+	return &types.MsgUpdateForeignClientResponse{}, nil
+}
+
+func (m msgServer) ExecuteEncryptedTx(goCtx context.Context, msg *types.MsgExecuteEncryptedTx) (*types.MsgExecuteEncryptedTxResponse, error) {
+	if err := msg.ValidateBasic(); err != nil {
+		return nil, err
+	}
+
+	ctx := sdk.UnwrapSDKContext(goCtx)
+	ctx.EventManager().EmitEvent(sdk.NewEvent(
+		sdk.EventTypeMessage,
+		sdk.NewAttribute(sdk.AttributeKeyModule, types.ModuleName),
+		sdk.NewAttribute(sdk.AttributeKeySender, msg.Sender),
+	))
+
+	if err := m.keeper.StoreEncryptedTx(ctx, msg); err != nil {
+		return nil, err
+	}
+
+	return &types.MsgExecuteEncryptedTxResponse{}, nil
+}
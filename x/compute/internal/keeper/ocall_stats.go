@@ -0,0 +1,35 @@
+package keeper
+
+import (
+	"encoding/json"
+
+	"github.com/cosmos/cosmos-sdk/telemetry"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	wasmTypes "github.com/scrtlabs/SecretNetwork/go-cosmwasm/types"
+)
+
+// RecordOcallStats exports the enclave's counters of the calls it made to the host as telemetry
+// gauges, so the metrics cover everything the enclave asks of the untrusted host. The counters
+// are local to the node, so they never reach the state.
+func (k Keeper) RecordOcallStats() {
+	if !telemetry.IsTelemetryEnabled() {
+		return
+	}
+
+	res, err := api.GetOcallStats()
+	if err != nil || res == nil {
+		return
+	}
+
+	var stats []wasmTypes.OcallStats
+	if err := json.Unmarshal(res, &stats); err != nil {
+		return
+	}
+
+	for _, ocall := range stats {
+		telemetry.SetGauge(float32(ocall.Calls), "compute", "enclave", "ocall_calls", ocall.Name)
+		telemetry.SetGauge(float32(ocall.Rejected), "compute", "enclave", "ocall_rejected", ocall.Name)
+		telemetry.SetGauge(float32(ocall.Failed), "compute", "enclave", "ocall_failed", ocall.Name)
+	}
+}
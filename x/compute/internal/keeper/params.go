@@ -1,7 +1,10 @@
 package keeper
 
 import (
+	errorsmod "cosmossdk.io/errors"
 	sdk "github.com/cosmos/cosmos-sdk/types"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
 	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
 )
 
@@ -27,3 +30,21 @@ func (k *Keeper) GetParams(ctx sdk.Context) (params types.Params) {
 	k.cdc.MustUnmarshal(bz, &params)
 	return params
 }
+
+// ValidateEnclaveParams has the enclave check that it knows every enclave param of p, and that
+// their values decode as the params' types. Governance can only set params that pass, since the
+// enclave reads a param whose value doesn't decode as its strict value. See
+// docs/verified-params.md.
+func (k *Keeper) ValidateEnclaveParams(p types.Params) error {
+	if len(p.EnclaveParams) == 0 {
+		return nil
+	}
+	bz, err := k.cdc.Marshal(&p)
+	if err != nil {
+		return err
+	}
+	if err := api.ValidateEnclaveParams(bz); err != nil {
+		return errorsmod.Wrap(types.ErrInvalid, "enclave params: "+err.Error())
+	}
+	return nil
+}
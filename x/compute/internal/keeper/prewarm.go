@@ -0,0 +1,114 @@
+package keeper
+
+import (
+	"encoding/hex"
+	"sync"
+
+	sdk "github.com/cosmos/cosmos-sdk/types"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+const (
+	// prewarmQueueSize is how many hints wait for the enclave, more are dropped
+	prewarmQueueSize = 64
+	// prewarmRecentSize is how many code hashes are remembered as already hinted
+	prewarmRecentSize = 1024
+)
+
+// PrewarmDecorator hints the enclave about the contracts of txs that enter the mempool, which
+// are likely to be executed in the next block, so the enclave can analyze their code before the
+// block is executed. Hints are passed to the enclave in the background, never slow CheckTx down,
+// and don't affect the results of the block.
+type PrewarmDecorator struct {
+	keeper *Keeper
+	hints  chan []byte
+
+	mu     sync.Mutex
+	recent map[string]struct{}
+}
+
+// NewPrewarmDecorator constructor. With the enclave's module cache disabled there's nothing to
+// prewarm, and the decorator does nothing.
+func NewPrewarmDecorator(keeper *Keeper, enclaveCacheSize uint16) *PrewarmDecorator {
+	d := &PrewarmDecorator{
+		keeper: keeper,
+		recent: make(map[string]struct{}),
+	}
+	if enclaveCacheSize > 0 {
+		d.hints = make(chan []byte, prewarmQueueSize)
+		go d.prewarm()
+	}
+	return d
+}
+
+func (d *PrewarmDecorator) AnteHandle(ctx sdk.Context, tx sdk.Tx, simulate bool, next sdk.AnteHandler) (sdk.Context, error) {
+	if d.hints != nil && ctx.IsCheckTx() && !ctx.IsReCheckTx() && !simulate {
+		for _, msg := range tx.GetMsgs() {
+			if codeHash := d.codeHashOf(ctx, msg); codeHash != nil {
+				d.hint(codeHash)
+			}
+		}
+	}
+
+	return next(ctx, tx, simulate)
+}
+
+func (d *PrewarmDecorator) codeHashOf(ctx sdk.Context, msg sdk.Msg) []byte {
+	var codeID uint64
+	switch msg := msg.(type) {
+	case *types.MsgInstantiateContract:
+		codeID = msg.CodeID
+	case *types.MsgExecuteContract:
+		contractInfo := d.keeper.GetContractInfo(ctx, msg.Contract)
+		if contractInfo == nil {
+			return nil
+		}
+		codeID = contractInfo.CodeID
+	default:
+		return nil
+	}
+
+	codeInfo, err := d.keeper.GetCodeInfo(ctx, codeID)
+	if err != nil {
+		return nil
+	}
+	return codeInfo.CodeHash
+}
+
+func (d *PrewarmDecorator) hint(codeHash []byte) {
+	key := hex.EncodeToString(codeHash)
+
+	d.mu.Lock()
+	_, seen := d.recent[key]
+	if !seen {
+		if len(d.recent) >= prewarmRecentSize {
+			d.recent = make(map[string]struct{})
+		}
+		d.recent[key] = struct{}{}
+	}
+	d.mu.Unlock()
+
+	if seen {
+		return
+	}
+
+	select {
+	case d.hints <- codeHash:
+	default:
+		// the enclave is behind, hints are only an optimization
+	}
+}
+
+func (d *PrewarmDecorator) prewarm() {
+	for codeHash := range d.hints {
+		code, err := d.keeper.wasmer.GetCode(codeHash)
+		if err != nil {
+			continue
+		}
+		// Fails for code the enclave can't cache ahead of time, which it then analyzes when it's
+		// executed, as usual
+		_ = api.PrewarmModule(code)
+	}
+}
@@ -92,7 +92,8 @@ func (q GrpcQuerier) QuerySecretContract(c context.Context, req *types.QuerySecr
 		return nil, err
 	}
 
-	ctx := sdk.UnwrapSDKContext(c).WithGasMeter(storetypes.NewGasMeter(q.keeper.queryGasLimit))
+	// The client's deadline comes with c, see cancelQueryWhenDone
+	ctx := sdk.UnwrapSDKContext(c).WithContext(c).WithGasMeter(storetypes.NewGasMeter(q.keeper.queryGasLimit))
 
 	response, err := q.keeper.QuerySmart(ctx, contractAddress, req.Query, false)
 	switch {
@@ -328,3 +329,56 @@ func queryCodeHashByCodeID(ctx sdk.Context, codeID uint64, keeper Keeper) ([]byt
 
 	return codeInfo.CodeHash, nil
 }
+
+func (q GrpcQuerier) Job(c context.Context, req *types.QueryJobRequest) (*types.QueryJobResponse, error) {
+	ctx := sdk.UnwrapSDKContext(c).WithGasMeter(storetypes.NewGasMeter(q.keeper.queryGasLimit))
+	return q.keeper.GetJob(ctx, req.JobId)
+}
+
+func (q GrpcQuerier) RunJob(c context.Context, req *types.QueryJobRequest) (*types.QueryRunJobResponse, error) {
+	ctx := sdk.UnwrapSDKContext(c)
+	result, err := q.keeper.RunJob(ctx, req.JobId)
+	if err != nil {
+		return nil, err
+	}
+	return &types.QueryRunJobResponse{
+		Result: result,
+	}, nil
+}
+
+func (q GrpcQuerier) OpenQuerySession(c context.Context, req *types.QueryOpenQuerySessionRequest) (*types.QueryOpenQuerySessionResponse, error) {
+	ctx := sdk.UnwrapSDKContext(c)
+	sessionID, err := q.keeper.OpenQuerySession(ctx, req.Nonce, req.UserPublicKey, req.ExpiryHeight)
+	if err != nil {
+		return nil, err
+	}
+	return &types.QueryOpenQuerySessionResponse{
+		SessionId: sessionID,
+	}, nil
+}
+
+func (q GrpcQuerier) ExportContractState(c context.Context, req *types.QueryExportContractStateRequest) (*types.QueryExportContractStateResponse, error) {
+	contractAddress, err := sdk.AccAddressFromBech32(req.ContractAddress)
+	if err != nil {
+		return nil, err
+	}
+
+	ctx := sdk.UnwrapSDKContext(c).WithGasMeter(storetypes.NewGasMeter(q.keeper.queryGasLimit))
+	chunks, err := q.keeper.ExportContractState(ctx, contractAddress, req.Authorization)
+	if err != nil {
+		return nil, err
+	}
+	return &types.QueryExportContractStateResponse{
+		Chunks: chunks,
+	}, nil
+}
+
+func (q GrpcQuerier) MempoolKey(_ context.Context, req *types.QueryMempoolKeyRequest) (*types.QueryMempoolKeyResponse, error) {
+	publicKey, err := q.keeper.MempoolKey(req.Height)
+	if err != nil {
+		return nil, err
+	}
+	return &types.QueryMempoolKeyResponse{
+		PublicKey: publicKey,
+	}, nil
+}
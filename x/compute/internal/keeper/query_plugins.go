@@ -3,9 +3,12 @@ package keeper
 import (
 	"encoding/json"
 	"fmt"
+	"math"
 	"strings"
 
 	channeltypes "github.com/cosmos/ibc-go/v8/modules/core/04-channel/types"
+	host "github.com/cosmos/ibc-go/v8/modules/core/24-host"
+	ibcexported "github.com/cosmos/ibc-go/v8/modules/core/exported"
 	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
 
 	storetypes "cosmossdk.io/store/types"
@@ -76,6 +79,12 @@ func (q QueryHandler) Query(request wasmTypes.QueryRequest, queryDepth uint32, g
 	if request.Stargate != nil {
 		return q.Plugins.Stargate(q.Ctx, request.Stargate)
 	}
+	if request.BlockRandom != nil {
+		return q.Plugins.BlockRandom(q.Ctx, request.BlockRandom)
+	}
+	if request.IbcClientState != nil {
+		return q.Plugins.IbcClientState(q.Ctx, request.IbcClientState)
+	}
 	return nil, wasmTypes.Unknown{}
 }
 
@@ -95,6 +104,9 @@ type QueryPlugins struct {
 	Gov      func(ctx sdk.Context, request *wasmTypes.GovQuery) ([]byte, error)
 	IBC      func(ctx sdk.Context, caller sdk.AccAddress, request *wasmTypes.IBCQuery) ([]byte, error)
 	Stargate func(ctx sdk.Context, request *wasmTypes.StargateQuery) ([]byte, error)
+
+	BlockRandom    func(ctx sdk.Context, request *wasmTypes.BlockRandomQuery) ([]byte, error)
+	IbcClientState func(ctx sdk.Context, request *wasmTypes.IbcClientStateQuery) ([]byte, error)
 }
 
 func DefaultQueryPlugins(gov govkeeper.Keeper, dist distrkeeper.Keeper, mint mintkeeper.Keeper, bank bankkeeper.Keeper, staking stakingkeeper.Keeper, stargateQueryRouter GRPCQueryRouter, wasm *Keeper, channelKeeper types.ChannelKeeper) QueryPlugins {
@@ -108,6 +120,9 @@ func DefaultQueryPlugins(gov govkeeper.Keeper, dist distrkeeper.Keeper, mint min
 		Gov:      GovQuerier(gov),
 		Stargate: StargateQuerier(stargateQueryRouter),
 		IBC:      IBCQuerier(wasm, channelKeeper),
+
+		BlockRandom:    BlockRandomQuerier(wasm),
+		IbcClientState: IbcClientStateQuerier(wasm),
 	}
 }
 
@@ -143,6 +158,12 @@ func (e QueryPlugins) Merge(o *QueryPlugins) QueryPlugins {
 	if o.Stargate != nil {
 		e.Stargate = o.Stargate
 	}
+	if o.BlockRandom != nil {
+		e.BlockRandom = o.BlockRandom
+	}
+	if o.IbcClientState != nil {
+		e.IbcClientState = o.IbcClientState
+	}
 	return e
 }
 
@@ -691,6 +712,33 @@ func getAccumulatedRewards(ctx sdk.Context, distKeeper distrkeeper.Keeper, deleg
 	return rewards, nil
 }
 
+// BlockRandomQuerier returns the random x/compute stored in BeginBlock for an earlier block, so
+// the enclave can derive past randomness for contracts. See docs/past-randomness.md.
+func BlockRandomQuerier(wasm *Keeper) func(ctx sdk.Context, request *wasmTypes.BlockRandomQuery) ([]byte, error) {
+	return func(ctx sdk.Context, request *wasmTypes.BlockRandomQuery) ([]byte, error) {
+		if request.Height > math.MaxInt64 {
+			return []byte{}, nil
+		}
+		return wasm.GetRandomSeed(ctx, int64(request.Height)), nil
+	}
+}
+
+// IbcClientStateQuerier returns the state the IBC module stores for a client as of the last block,
+// with a proof of it, so the enclave can verify the client's status for contracts. See
+// docs/ibc-client-status.md.
+func IbcClientStateQuerier(wasm *Keeper) func(ctx sdk.Context, request *wasmTypes.IbcClientStateQuery) ([]byte, error) {
+	return func(ctx sdk.Context, request *wasmTypes.IbcClientStateQuery) ([]byte, error) {
+		state, proof, err := wasm.proveStoreKey(ctx, ibcexported.StoreKey, host.FullClientStateKey(request.ClientID))
+		if err != nil {
+			return nil, err
+		}
+		if state == nil || proof == nil {
+			return []byte{}, nil
+		}
+		return json.Marshal(wasmTypes.ProvenState{Value: state, Proof: proof})
+	}
+}
+
 func WasmQuerier(wasm *Keeper) func(ctx sdk.Context, request *wasmTypes.WasmQuery, queryDepth uint32) ([]byte, error) {
 	return func(ctx sdk.Context, request *wasmTypes.WasmQuery, queryDepth uint32) ([]byte, error) {
 		if request.Smart != nil {
@@ -0,0 +1,24 @@
+package keeper
+
+import (
+	errorsmod "cosmossdk.io/errors"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+// OpenQuerySession opens a query session in this node's enclave for the nonce and public key a
+// client would send with a query, until expiryHeight, and returns its id. Sessions only live in
+// the memory of the node's enclave, so the client must send its session queries to the same node.
+func (k Keeper) OpenQuerySession(ctx sdk.Context, nonce []byte, userPublicKey []byte, expiryHeight uint64) ([]byte, error) {
+	if len(nonce) != 32 || len(userPublicKey) != 32 {
+		return nil, errorsmod.Wrap(types.ErrInvalid, "nonce and public key must be 32 bytes")
+	}
+
+	sessionID, err := api.OpenQuerySession(nonce, userPublicKey, uint64(ctx.BlockHeight()), expiryHeight)
+	if err != nil {
+		return nil, errorsmod.Wrap(types.ErrInvalid, err.Error())
+	}
+	return sessionID, nil
+}
@@ -515,12 +515,26 @@ func TestGasIsChargedForExecCallbackToInit(t *testing.T) {
 	}
 }
 
-func TestWasmTooHighInitialMemoryRuntimeFail(t *testing.T) {
-	ctx, keeper, codeID, _, walletA, privKeyA, _, _ := setupTest(t, TestContractPaths[tooHighMemoryContract], sdk.NewCoins())
+func TestWasmTooHighInitialMemoryEnclaveFail(t *testing.T) {
+	encodingConfig := MakeEncodingConfig()
+	var transferPortSource types.ICS20TransferPortSource
+	transferPortSource = MockIBCTransferKeeper{GetPortFn: func(ctx sdk.Context) string {
+		return "myTransferPort"
+	}}
+	encoders := DefaultEncoders(transferPortSource, encodingConfig.Codec)
+	ctx, keepers := CreateTestInput(t, false, SupportedFeatures, &encoders, nil)
+	accKeeper, keeper := keepers.AccountKeeper, keepers.WasmKeeper
+
+	walletA, _, _ := CreateFakeFundedAccount(ctx, accKeeper, keeper.bankKeeper, sdk.NewCoins(sdk.NewInt64Coin("denom", 1)))
 
-	_, _, _, _, err := initHelper(t, keeper, ctx, codeID, walletA, nil, privKeyA, `{"nop":{}}`, false, false, defaultGasForTests)
-	require.NotNil(t, err.GenericErr)
-	require.Contains(t, err.GenericErr.Msg, "failed to initialize wasm memory")
+	wasmCode, err := os.ReadFile(TestContractPaths[tooHighMemoryContract])
+	require.NoError(t, err)
+
+	// The enclave rejects the code when it's stored, instead of on the first instantiate
+	_, err = keeper.Create(ctx, walletA, wasmCode, "", "")
+	require.Error(t, err)
+	require.Contains(t, err.Error(), "contract code rejected")
+	require.Contains(t, err.Error(), "failed to initialize wasm memory")
 }
 
 func TestWasmTooHighInitialMemoryStaticFail(t *testing.T) {
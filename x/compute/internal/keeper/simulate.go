@@ -0,0 +1,68 @@
+package keeper
+
+import (
+	"time"
+
+	errorsmod "cosmossdk.io/errors"
+	"github.com/cosmos/cosmos-sdk/telemetry"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+	sdkerrors "github.com/cosmos/cosmos-sdk/types/errors"
+	sdktxsigning "github.com/cosmos/cosmos-sdk/types/tx/signing"
+
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+// Simulate executes a contract for a tx that is simulated rather than delivered, e.g. to estimate
+// its fee. The enclave executes the tx the same way it would in a block, so the gas is exact even
+// for encrypted msgs, but doesn't change its own state. Simulated txs needn't be signed, and the
+// enclave only simulates encrypted msgs that were encrypted for a simulation, see
+// docs/simulation.md. The writes of the contract go to ctx,
+// which the caller must discard. The returned data is the raw response of the contract, encrypted
+// to the sender, and its messages are not dispatched.
+func (k Keeper) Simulate(ctx sdk.Context, contractAddress sdk.AccAddress, caller sdk.AccAddress, msg []byte, coins sdk.Coins) ([]byte, error) {
+	defer telemetry.MeasureSince(time.Now(), "compute", "keeper", "simulate")
+
+	ctx.GasMeter().ConsumeGas(types.InstanceCost, "Loading Compute module: simulate")
+
+	sigInfo := types.NewSigInfo(ctx.TxBytes(), []byte{}, sdktxsigning.SignMode_SIGN_MODE_UNSPECIFIED, []byte{}, []byte{}, []byte{}, nil)
+
+	_, codeInfo, prefixStore, err := k.contractInstance(ctx, contractAddress)
+	if err != nil {
+		return nil, err
+	}
+
+	if !coins.IsZero() {
+		if k.bankKeeper.BlockedAddr(caller) {
+			return nil, sdkerrors.ErrInvalidAddress.Wrap("blocked address can not be used")
+		}
+
+		sdkerr := k.bankKeeper.SendCoins(ctx, caller, contractAddress, coins)
+		if sdkerr != nil {
+			return nil, sdkerr
+		}
+	}
+
+	random := k.GetRandomSeed(ctx, ctx.BlockHeight())
+
+	contractKey, err := k.GetContractKey(ctx, contractAddress)
+	if err != nil {
+		return nil, err
+	}
+
+	env := types.NewEnv(ctx, caller, coins, contractAddress, contractKey, random)
+
+	querier := QueryHandler{
+		Ctx:     ctx,
+		Plugins: k.queryPlugins,
+		Caller:  contractAddress,
+	}
+
+	data, gasUsed, simErr := k.wasmer.Simulate(codeInfo.CodeHash, env, msg, prefixStore, cosmwasmAPI, querier, gasMeter(ctx), gasForContract(ctx), sigInfo)
+	consumeGas(ctx, gasUsed)
+
+	if simErr != nil {
+		return nil, errorsmod.Wrap(types.ErrExecuteFailed, simErr.Error())
+	}
+
+	return data, nil
+}
@@ -0,0 +1,142 @@
+package keeper
+
+import (
+	"encoding/json"
+
+	errorsmod "cosmossdk.io/errors"
+	"cosmossdk.io/store/prefix"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+	sdkerrors "github.com/cosmos/cosmos-sdk/types/errors"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+// stateBackupContract is what the enclave needs to check a state backup authorization of a
+// contract, and the contract's store
+type stateBackupContract struct {
+	env        []byte
+	codeHash   []byte
+	admin      sdk.AccAddress
+	adminProof []byte
+	store      prefix.Store
+}
+
+func (k Keeper) stateBackupContract(ctx sdk.Context, contractAddress sdk.AccAddress) (*stateBackupContract, error) {
+	contractInfo, codeInfo, prefixStore, err := k.contractInstance(ctx, contractAddress)
+	if err != nil {
+		return nil, err
+	}
+	if contractInfo.Admin == "" {
+		return nil, errorsmod.Wrap(sdkerrors.ErrUnauthorized, "contracts without an admin can't be backed up")
+	}
+	admin, err := sdk.AccAddressFromBech32(contractInfo.Admin)
+	if err != nil {
+		return nil, errorsmod.Wrap(err, "admin")
+	}
+
+	contractKey, err := k.GetContractKey(ctx, contractAddress)
+	if err != nil {
+		return nil, err
+	}
+	env, err := json.Marshal(types.NewEnv(
+		ctx,
+		admin,
+		sdk.NewCoins(), /* empty because it's unused in state backups */
+		contractAddress,
+		contractKey,
+		[]byte{0}, /* empty because it's unused in state backups */
+	))
+	if err != nil {
+		return nil, err
+	}
+	return &stateBackupContract{
+		env:        env,
+		codeHash:   codeInfo.CodeHash,
+		admin:      admin,
+		adminProof: contractInfo.AdminProof,
+		store:      prefixStore,
+	}, nil
+}
+
+// ExportContractState exports the state of a contract to an encrypted backup, as its admin
+// authorized, and returns the wrapped chunks. The node passes the pairs as they're stored, and
+// only the enclave decrypts them. See docs/contract-state-backup.md.
+func (k Keeper) ExportContractState(ctx sdk.Context, contractAddress sdk.AccAddress, authorization types.StateBackupAuthorization) ([][]byte, error) {
+	contract, err := k.stateBackupContract(ctx, contractAddress)
+	if err != nil {
+		return nil, err
+	}
+	authorizationBz, err := authorization.Marshal()
+	if err != nil {
+		return nil, err
+	}
+
+	var chunks [][]byte
+	var chunk []byte
+	flushChunk := func() error {
+		res, err := api.ExportState(contract.env, contract.codeHash, contract.admin, contract.adminProof, authorizationBz, chunk)
+		if err != nil {
+			return errorsmod.Wrap(types.ErrQueryFailed, err.Error())
+		}
+		chunks = append(chunks, res)
+		chunk = nil
+		return nil
+	}
+
+	iter := contract.store.Iterator(nil, nil)
+	defer iter.Close()
+	for ; iter.Valid(); iter.Next() {
+		chunk = appendStatePair(chunk, iter.Key(), iter.Value())
+		if len(chunk) >= stateChunkSize {
+			if err := flushChunk(); err != nil {
+				return nil, err
+			}
+		}
+	}
+	// A contract without pairs still gets a chunk, so the admin's authorization is checked
+	if len(chunk) > 0 || len(chunks) == 0 {
+		if err := flushChunk(); err != nil {
+			return nil, err
+		}
+	}
+	return chunks, nil
+}
+
+// ImportContractState writes the pairs of chunks of a backup to the store of a contract, as its
+// admin authorized. The enclave encrypts the pairs under the contract's state key, and pairs the
+// contract already stored under the same keys are overwritten.
+func (k Keeper) ImportContractState(ctx sdk.Context, msg *types.MsgImportContractState) error {
+	contractAddress, err := sdk.AccAddressFromBech32(msg.Contract)
+	if err != nil {
+		return errorsmod.Wrap(err, "contract")
+	}
+	contract, err := k.stateBackupContract(ctx, contractAddress)
+	if err != nil {
+		return err
+	}
+	authorizationBz, err := msg.Authorization.Marshal()
+	if err != nil {
+		return err
+	}
+
+	for _, chunk := range msg.Chunks {
+		res, err := api.ImportState(contract.env, contract.codeHash, contract.admin, contract.adminProof, authorizationBz, chunk)
+		if err != nil {
+			return errorsmod.Wrap(types.ErrInvalid, err.Error())
+		}
+		pairs, err := decodeStatePairs(res)
+		if err != nil {
+			return errorsmod.Wrap(types.ErrInvalid, err.Error())
+		}
+		for _, pair := range pairs {
+			contract.store.Set(pair.key, pair.value)
+		}
+	}
+
+	ctx.EventManager().EmitEvent(sdk.NewEvent(
+		types.EventTypeImportContractState,
+		sdk.NewAttribute(types.AttributeKeyContractAddr, msg.Contract),
+	))
+	return nil
+}
@@ -0,0 +1,127 @@
+package keeper
+
+import (
+	"bytes"
+	"encoding/binary"
+	"encoding/json"
+	"sort"
+
+	errorsmod "cosmossdk.io/errors"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+const (
+	// stateChunkSize is how many bytes of pairs are passed to the enclave at once, well below the
+	// enclave's limit of 2 MB
+	stateChunkSize = 1024 * 1024
+	// stateCommitmentLeafSize is the size of the leaf of each pair
+	stateCommitmentLeafSize = 32
+)
+
+type stateCommitmentLeaf struct {
+	leaf []byte
+	key  []byte
+}
+
+// StateCommitment returns the enclave signed commitment to the state of a contract at the current
+// height, as JSON. See docs/contract-state-commitment.md.
+func (k Keeper) StateCommitment(ctx sdk.Context, contractAddress sdk.AccAddress) ([]byte, error) {
+	_, codeInfo, prefixStore, err := k.contractInstance(ctx, contractAddress)
+	if err != nil {
+		return nil, err
+	}
+
+	contractKey, err := k.GetContractKey(ctx, contractAddress)
+	if err != nil {
+		return nil, err
+	}
+
+	env, err := json.Marshal(types.NewEnv(
+		ctx,
+		sdk.AccAddress{}, /* empty because it's unused in commitments */
+		sdk.NewCoins(),   /* empty because it's unused in commitments */
+		contractAddress,
+		contractKey,
+		[]byte{0}, /* empty because it's unused in commitments */
+	))
+	if err != nil {
+		return nil, err
+	}
+
+	// The enclave only accepts the pairs in the order of their leaves, so the first pass gets the
+	// leaves of all the pairs
+	var leaves []stateCommitmentLeaf
+	var keys [][]byte
+	var chunk []byte
+
+	flushLeaves := func() error {
+		res, err := api.StateCommitmentLeaves(env, codeInfo.CodeHash, chunk)
+		if err != nil {
+			return errorsmod.Wrap(types.ErrQueryFailed, err.Error())
+		}
+		if len(res) != len(keys)*stateCommitmentLeafSize {
+			return errorsmod.Wrapf(types.ErrQueryFailed, "got %d bytes of leaves for %d pairs", len(res), len(keys))
+		}
+		for i, key := range keys {
+			leaves = append(leaves, stateCommitmentLeaf{
+				leaf: res[i*stateCommitmentLeafSize : (i+1)*stateCommitmentLeafSize],
+				key:  key,
+			})
+		}
+		keys, chunk = nil, nil
+		return nil
+	}
+
+	iter := prefixStore.Iterator(nil, nil)
+	for ; iter.Valid(); iter.Next() {
+		chunk = appendStatePair(chunk, iter.Key(), iter.Value())
+		keys = append(keys, iter.Key())
+		if len(chunk) >= stateChunkSize {
+			if err := flushLeaves(); err != nil {
+				iter.Close()
+				return nil, err
+			}
+		}
+	}
+	iter.Close()
+	if len(keys) > 0 {
+		if err := flushLeaves(); err != nil {
+			return nil, err
+		}
+	}
+
+	sort.Slice(leaves, func(i, j int) bool {
+		return bytes.Compare(leaves[i].leaf, leaves[j].leaf) < 0
+	})
+
+	// The second pass folds the pairs into the commitment, in the order of their leaves
+	var progress []byte
+	for i, leaf := range leaves {
+		chunk = appendStatePair(chunk, leaf.key, prefixStore.Get(leaf.key))
+		if len(chunk) < stateChunkSize && i+1 < len(leaves) {
+			continue
+		}
+		progress, err = api.FoldStateCommitment(env, codeInfo.CodeHash, progress, chunk, i+1 == len(leaves))
+		if err != nil {
+			return nil, errorsmod.Wrap(types.ErrQueryFailed, err.Error())
+		}
+		chunk = nil
+	}
+
+	if len(leaves) == 0 {
+		return api.FoldStateCommitment(env, codeInfo.CodeHash, nil, nil, true)
+	}
+	return progress, nil
+}
+
+// appendStatePair encodes a pair the way the enclave expects chunks of state:
+// len(key) || key || len(value) || value, with 4 byte big endian lengths
+func appendStatePair(chunk []byte, key []byte, value []byte) []byte {
+	chunk = binary.BigEndian.AppendUint32(chunk, uint32(len(key)))
+	chunk = append(chunk, key...)
+	chunk = binary.BigEndian.AppendUint32(chunk, uint32(len(value)))
+	return append(chunk, value...)
+}
@@ -0,0 +1,80 @@
+package keeper
+
+import (
+	errorsmod "cosmossdk.io/errors"
+	storetypes "cosmossdk.io/store/types"
+	commitmenttypes "github.com/cosmos/ibc-go/v8/modules/core/23-commitment/types"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+// SetCommitMultiStore gives the keeper the app's multistore, which it proves the module's state
+// to the enclave from
+func (k *Keeper) SetCommitMultiStore(cms storetypes.CommitMultiStore) {
+	k.cms = cms
+}
+
+// proveKey returns the value of key in the module's store as of the last block, with an ICS-23
+// proof of it against the app hash of the current block, which the enclave verified. The proof
+// is nil when the node can't prove the last block's state, e.g. in tests without a multistore,
+// and the value is then read from ctx.
+func (k Keeper) proveKey(ctx sdk.Context, key []byte) ([]byte, []byte, error) {
+	if !k.canProveLastBlock(ctx) {
+		value, err := k.storeService.OpenKVStore(ctx).Get(key)
+		return value, nil, err
+	}
+	return k.proveStoreKey(ctx, types.StoreKey, key)
+}
+
+// canProveLastBlock is whether the multistore still has the state of the last block
+func (k Keeper) canProveLastBlock(ctx sdk.Context) bool {
+	_, ok := k.cms.(storetypes.Queryable)
+	height := ctx.BlockHeight() - 1
+	return ok && height > 0 && k.cms.LastCommitID().Version == height
+}
+
+// proveStoreKey returns the value of key in the module store storeName as of the last block, with
+// an ICS-23 proof of it, like proveKey. Both are nil when the key is absent, or when the node
+// can't prove the last block's state.
+func (k Keeper) proveStoreKey(ctx sdk.Context, storeName string, key []byte) ([]byte, []byte, error) {
+	if !k.canProveLastBlock(ctx) {
+		return nil, nil, nil
+	}
+
+	res, err := k.cms.(storetypes.Queryable).Query(&storetypes.RequestQuery{
+		Path:   "/" + storeName + "/key",
+		Data:   key,
+		Height: ctx.BlockHeight() - 1,
+		Prove:  true,
+	})
+	if err != nil {
+		return nil, nil, err
+	}
+	if res.Value == nil {
+		// absent keys have no existence proof
+		return nil, nil, nil
+	}
+
+	proof, err := commitmenttypes.ConvertProofs(res.ProofOps)
+	if err != nil {
+		return nil, nil, errorsmod.Wrap(err, "state proof")
+	}
+	proofBz, err := proof.Marshal()
+	if err != nil {
+		return nil, nil, err
+	}
+	return res.Value, proofBz, nil
+}
+
+// PushComputeParams hands the enclave the module's params with a proof of them. It must be called
+// once a block, after the block is submitted to the enclave, which otherwise uses its strictest
+// defaults for them.
+func (k Keeper) PushComputeParams(ctx sdk.Context) error {
+	params, proof, err := k.proveKey(ctx, types.ParamsKey)
+	if err != nil {
+		return err
+	}
+	return api.SubmitComputeParams(params, proof)
+}
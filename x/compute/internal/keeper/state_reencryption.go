@@ -0,0 +1,228 @@
+package keeper
+
+import (
+	"encoding/binary"
+	"encoding/json"
+	"fmt"
+
+	errorsmod "cosmossdk.io/errors"
+	"cosmossdk.io/store/prefix"
+	"github.com/cosmos/cosmos-sdk/runtime"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+
+	"github.com/scrtlabs/SecretNetwork/go-cosmwasm/api"
+	"github.com/scrtlabs/SecretNetwork/x/compute/internal/types"
+)
+
+// reencryptionPairsPerBlock is how many stored pairs the sweep passes to the enclave in a block,
+// which is also the most the enclave takes in a single chunk
+const reencryptionPairsPerBlock = 256
+
+// stateReencryption is how far the sweep that re-encrypts the pairs still under the genesis seed
+// got. Contracts are swept in the order of their addresses, and the pairs of each contract in the
+// order of their keys. See docs/state-reencryption.md.
+type stateReencryption struct {
+	// Contract is the address of the contract being swept, empty before the first one
+	Contract []byte `json:"contract,omitempty"`
+
+	// LastKey is the last key of the contract that was passed to the enclave
+	LastKey []byte `json:"last_key,omitempty"`
+
+	// Progress is the enclave's sealed progress of the contract
+	Progress []byte `json:"progress,omitempty"`
+
+	// Done is set once every contract was swept
+	Done bool `json:"done,omitempty"`
+}
+
+// reencryptionOutput is what the enclave returns for a chunk
+type reencryptionOutput struct {
+	Progress    []byte `json:"progress"`
+	Pairs       []byte `json:"pairs"`
+	Reencrypted uint64 `json:"reencrypted,string"`
+	Complete    bool   `json:"complete"`
+}
+
+type statePair struct {
+	key   []byte
+	value []byte
+}
+
+// ReencryptState moves the sweep on by up to reencryptionPairsPerBlock pairs. It runs at the end
+// of every block, since the pairs it stores must be the same on every node. A contract the enclave
+// refuses a chunk of is skipped, so it can't stall the sweep.
+func (k Keeper) ReencryptState(ctx sdk.Context) {
+	store := k.storeService.OpenKVStore(ctx)
+
+	var sweep stateReencryption
+	bz, err := store.Get(types.StateReencryptionKey)
+	if err != nil {
+		panic(err)
+	}
+	if bz != nil {
+		if err := json.Unmarshal(bz, &sweep); err != nil {
+			panic(errorsmod.Wrap(err, "failed to read the state re-encryption sweep"))
+		}
+	}
+	if sweep.Done {
+		return
+	}
+
+	budget := reencryptionPairsPerBlock
+	for budget > 0 {
+		if sweep.Contract == nil {
+			sweep.Contract = k.contractAfter(ctx, nil)
+			if sweep.Contract == nil {
+				sweep.Done = true
+				break
+			}
+		}
+
+		used, complete, err := k.reencryptContractChunk(ctx, &sweep, budget)
+		budget -= used
+		if err != nil {
+			ctx.Logger().Info("state re-encryption failed", "contract", sdk.AccAddress(sweep.Contract).String(), "error", err.Error())
+			complete = true
+		}
+		if !complete {
+			continue
+		}
+
+		next := k.contractAfter(ctx, sweep.Contract)
+		sweep = stateReencryption{Contract: next, Done: next == nil}
+		if sweep.Done {
+			ctx.Logger().Info("state re-encryption of all contracts completed")
+			break
+		}
+	}
+
+	bz, err = json.Marshal(sweep)
+	if err != nil {
+		panic(err)
+	}
+	if err := store.Set(types.StateReencryptionKey, bz); err != nil {
+		panic(err)
+	}
+}
+
+// reencryptContractChunk passes the next chunk of at most budget pairs of the contract being swept
+// to the enclave, and stores the pairs it re-encrypted. It returns how much of the budget it used,
+// and whether the contract is complete.
+func (k Keeper) reencryptContractChunk(ctx sdk.Context, sweep *stateReencryption, budget int) (int, bool, error) {
+	contractAddress := sdk.AccAddress(sweep.Contract)
+
+	_, codeInfo, prefixStore, err := k.contractInstance(ctx, contractAddress)
+	if err != nil {
+		return 1, true, err
+	}
+
+	contractKey, err := k.GetContractKey(ctx, contractAddress)
+	if err != nil {
+		return 1, true, err
+	}
+
+	env, err := json.Marshal(types.NewEnv(
+		ctx,
+		sdk.AccAddress{}, /* empty because it's unused in re-encryption */
+		sdk.NewCoins(),   /* empty because it's unused in re-encryption */
+		contractAddress,
+		contractKey,
+		[]byte{0}, /* empty because it's unused in re-encryption */
+	))
+	if err != nil {
+		return 1, true, err
+	}
+
+	var start []byte
+	if sweep.LastKey != nil {
+		// the smallest key after the last one
+		start = append(append([]byte{}, sweep.LastKey...), 0)
+	}
+
+	var chunk []byte
+	lastKey := sweep.LastKey
+	pairs := 0
+	iter := prefixStore.Iterator(start, nil)
+	for ; iter.Valid() && pairs < budget; iter.Next() {
+		chunk = appendStatePair(chunk, iter.Key(), iter.Value())
+		lastKey = append([]byte{}, iter.Key()...)
+		pairs++
+	}
+	lastChunk := !iter.Valid()
+	iter.Close()
+
+	// A contract without pairs still costs an ecall
+	used := pairs
+	if used == 0 {
+		used = 1
+	}
+
+	res, err := api.ReencryptState(env, codeInfo.CodeHash, sweep.Progress, chunk, lastChunk)
+	if err != nil {
+		return used, true, err
+	}
+
+	var output reencryptionOutput
+	if err := json.Unmarshal(res, &output); err != nil {
+		return used, true, err
+	}
+	reencrypted, err := decodeStatePairs(output.Pairs)
+	if err != nil {
+		return used, true, err
+	}
+	for _, pair := range reencrypted {
+		prefixStore.Set(pair.key, pair.value)
+	}
+
+	sweep.LastKey = lastKey
+	sweep.Progress = output.Progress
+	return used, output.Complete, nil
+}
+
+// contractAfter returns the address of the first contract after address, or of the first contract
+// if address is nil, or nil if there's none
+func (k Keeper) contractAfter(ctx sdk.Context, address []byte) []byte {
+	var start []byte
+	if address != nil {
+		start = append(append([]byte{}, address...), 0)
+	}
+
+	prefixStore := prefix.NewStore(runtime.KVStoreAdapter(k.storeService.OpenKVStore(ctx)), types.ContractKeyPrefix)
+	iter := prefixStore.Iterator(start, nil)
+	defer iter.Close()
+	if !iter.Valid() {
+		return nil
+	}
+	return append([]byte{}, iter.Key()...)
+}
+
+// decodeStatePairs decodes pairs encoded like appendStatePair does
+func decodeStatePairs(chunk []byte) ([]statePair, error) {
+	readField := func() ([]byte, error) {
+		if len(chunk) < 4 {
+			return nil, fmt.Errorf("truncated length of a state pair")
+		}
+		length := binary.BigEndian.Uint32(chunk[:4])
+		chunk = chunk[4:]
+		if uint64(len(chunk)) < uint64(length) {
+			return nil, fmt.Errorf("truncated state pair")
+		}
+		field := chunk[:length]
+		chunk = chunk[length:]
+		return field, nil
+	}
+
+	var pairs []statePair
+	for len(chunk) > 0 {
+		key, err := readField()
+		if err != nil {
+			return nil, err
+		}
+		value, err := readField()
+		if err != nil {
+			return nil, err
+		}
+		pairs = append(pairs, statePair{key: key, value: value})
+	}
+	return pairs, nil
+}
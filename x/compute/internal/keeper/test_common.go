@@ -587,6 +587,7 @@ func CreateTestInput(t *testing.T, isCheckTx bool, supportedFeatures string, enc
 		MockIBCTransferKeeper{},
 		ibcKeeper.ChannelKeeper,
 		nil,
+		nil,
 		msgRouter,
 		queryRouter,
 		tempDir,
@@ -604,6 +605,8 @@ func CreateTestInput(t *testing.T, isCheckTx bool, supportedFeatures string, enc
 	_, _ = rand.Read(random)
 	keeper.SetRandomSeed(ctx, random, random)
 	_ = keeper.SetParams(ctx, wasmtypes.DefaultParams())
+	// the enclave doesn't instantiate anything until it has the params
+	require.NoError(t, keeper.PushComputeParams(ctx))
 
 	govSubSp, _ := paramsKeeper.GetSubspace(govtypes.ModuleName)
 
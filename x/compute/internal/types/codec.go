@@ -16,6 +16,14 @@ func RegisterLegacyAminoCodec(cdc *codec.LegacyAmino) {
 	cdc.RegisterConcrete(&MsgUpdateAdmin{}, "wasm/MsgUpdateAdmin", nil)
 	cdc.RegisterConcrete(&MsgClearAdmin{}, "wasm/MsgClearAdmin", nil)
 	cdc.RegisterConcrete(&MsgUpdateParams{}, "wasm/MsgUpdateParams", nil)
+	cdc.RegisterConcrete(&MsgDiscloseContractKey{}, "wasm/MsgDiscloseContractKey", nil)
+	cdc.RegisterConcrete(&MsgEnqueueJob{}, "wasm/MsgEnqueueJob", nil)
+	cdc.RegisterConcrete(&MsgSubmitJobResult{}, "wasm/MsgSubmitJobResult", nil)
+	cdc.RegisterConcrete(&MsgImportContractState{}, "wasm/MsgImportContractState", nil)
+	cdc.RegisterConcrete(&MsgCreateForeignClient{}, "wasm/MsgCreateForeignClient", nil)
+	cdc.RegisterConcrete(&MsgUpdateForeignClient{}, "wasm/MsgUpdateForeignClient", nil)
+	cdc.RegisterConcrete(&MsgSetContractCron{}, "wasm/MsgSetContractCron", nil)
+	cdc.RegisterConcrete(&MsgExecuteEncryptedTx{}, "wasm/MsgExecuteEncryptedTx", nil)
 }
 
 func RegisterInterfaces(registry types.InterfaceRegistry) {
@@ -28,6 +36,14 @@ func RegisterInterfaces(registry types.InterfaceRegistry) {
 		&MsgUpdateAdmin{},
 		&MsgClearAdmin{},
 		&MsgUpdateParams{},
+		&MsgDiscloseContractKey{},
+		&MsgEnqueueJob{},
+		&MsgSubmitJobResult{},
+		&MsgImportContractState{},
+		&MsgCreateForeignClient{},
+		&MsgUpdateForeignClient{},
+		&MsgSetContractCron{},
+		&MsgExecuteEncryptedTx{},
 	)
 	msgservice.RegisterMsgServiceDesc(registry, &_Msg_serviceDesc)
 }
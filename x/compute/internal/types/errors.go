@@ -78,6 +78,9 @@ var (
 
 	// ErrExceedMaxContractSize error if max contract size is exceeded
 	ErrExceedMaxContractSize = errors.Register(DefaultCodespace, 31, "max contract size exceeded")
+
+	// ErrCodeRejected error if the enclave rejects the code of a contract when it's stored
+	ErrCodeRejected = errors.Register(DefaultCodespace, 32, "contract code rejected")
 )
 
 func IsEncryptedErrorCode(code uint32) bool {
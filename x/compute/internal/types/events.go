@@ -16,6 +16,14 @@ const (
 	EventTypeReply                 = "reply"
 	EventTypeUpdateContractAdmin   = "update_contract_admin"
 	EventTypeUpgradeProposalPassed = "upgrade_proposal_passed"
+	EventTypeEnqueueJob            = "enqueue_job"
+	EventTypeDiscloseContractKey   = "disclose_contract_key"
+	EventTypeImportContractState   = "import_contract_state"
+	EventTypeCreateForeignClient   = "create_foreign_client"
+	EventTypeUpdateForeignClient   = "update_foreign_client"
+	EventTypeCron                  = "cron"
+	EventTypeExecuteEncryptedTx    = "execute_encrypted_tx"
+	EventTypeSubMsgError           = "submsg_error"
 )
 
 // event attributes returned from contract execution
@@ -26,4 +34,11 @@ const (
 	AttributeKeyCodeID       = "code_id"
 	AttributeKeySigner       = "signer"
 	AttributeKeyNewAdmin     = "new_admin_address"
+	AttributeKeyJobID        = "job_id"
+	AttributeKeyAuditor      = "auditor_public_key"
+	AttributeKeyEncryptedKey = "encrypted_key"
+	AttributeKeyChainID      = "chain_id"
+	AttributeKeyHeight       = "height"
+	AttributeKeyError        = "error"
+	AttributeKeySubMsgID     = "id"
 )
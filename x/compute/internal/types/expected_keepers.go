@@ -4,6 +4,7 @@ import (
 	sdk "github.com/cosmos/cosmos-sdk/types"
 
 	capabilitytypes "github.com/cosmos/ibc-go/modules/capability/types"
+	icagenesistypes "github.com/cosmos/ibc-go/v8/modules/apps/27-interchain-accounts/genesis/types"
 	clienttypes "github.com/cosmos/ibc-go/v8/modules/core/02-client/types"
 	channeltypes "github.com/cosmos/ibc-go/v8/modules/core/04-channel/types"
 )
@@ -23,3 +24,9 @@ type ChannelKeeper interface {
 	IterateChannels(ctx sdk.Context, cb func(channeltypes.IdentifiedChannel) bool)
 	SetChannel(ctx sdk.Context, portID, channelID string, channel channeltypes.Channel)
 }
+
+// ICAHostKeeper defines the expected interchain accounts host keeper
+type ICAHostKeeper interface {
+	GetAllInterchainAccounts(ctx sdk.Context) []icagenesistypes.RegisteredInterchainAccount
+	GetActiveChannelID(ctx sdk.Context, connectionID, portID string) (string, bool)
+}
@@ -1,6 +1,7 @@
 package types
 
 import (
+	"crypto/sha256"
 	"encoding/binary"
 
 	sdk "github.com/cosmos/cosmos-sdk/types"
@@ -34,11 +35,21 @@ var (
 	ContractCodeHistoryElementPrefix               = []byte{0x09}
 	ContractByCodeIDAndCreatedSecondaryIndexPrefix = []byte{0x0A}
 	ParamsKey                                      = []byte{0x0B}
+	ContractCronPrefix                             = []byte{0x0C}
+	StateReencryptionKey                           = []byte{0x0D}
+	JobPrefix                                      = []byte{0x0E}
+	EncryptedTxPrefix                              = []byte{0x0F}
+	ContractDisclosurePrefix                       = []byte{0x10}
+	PendingContractDisclosurePrefix                = []byte{0x11}
+	ForeignClientPrefix                            = []byte{0x12}
+	ForeignClientCreationPrefix                    = []byte{0x13}
+	PendingForeignClientPrefix                     = []byte{0x14}
 	RandomPrefix                                   = []byte{0xFF}
 	ValidatorSetEvidencePrefix                     = []byte{0xFE}
 
 	KeyLastCodeID     = append(SequenceKeyPrefix, []byte("lastCodeId")...)
 	KeyLastInstanceID = append(SequenceKeyPrefix, []byte("lastContractId")...)
+	KeyLastJobID      = append(SequenceKeyPrefix, []byte("lastJobId")...)
 )
 
 // GetCodeKey constructs the key for retreiving the ID for the WASM code
@@ -59,6 +70,53 @@ func GetRandomKey(height int64) []byte {
 	return append(RandomPrefix, b...)
 }
 
+// GetJobKey returns the key for an off-chain job that's waiting for its result
+func GetJobKey(jobID uint64) []byte {
+	return append(JobPrefix, sdk.Uint64ToBigEndian(jobID)...)
+}
+
+// GetContractDisclosureKey returns the key for the last MsgDiscloseContractKey of a contract,
+// which the enclave checks governance's approval of the disclosure against
+func GetContractDisclosureKey(addr sdk.AccAddress) []byte {
+	return append(ContractDisclosurePrefix, addr...)
+}
+
+// GetPendingContractDisclosureKey returns the key that marks a contract's key as waiting to be
+// disclosed in the next block
+func GetPendingContractDisclosureKey(addr sdk.AccAddress) []byte {
+	return append(PendingContractDisclosurePrefix, addr...)
+}
+
+// GetForeignClientKey returns the key for the light client of another chain, as the enclave
+// created or last updated it
+func GetForeignClientKey(chainID string) []byte {
+	return append(ForeignClientPrefix, []byte(chainID)...)
+}
+
+// GetForeignClientCreationKey returns the key for the last MsgCreateForeignClient of a chain,
+// which the enclave checks governance's approval of the client against
+func GetForeignClientCreationKey(chainID string) []byte {
+	return append(ForeignClientCreationPrefix, []byte(chainID)...)
+}
+
+// GetPendingForeignClientKey returns the key that marks the client of a chain as waiting to be
+// created in the next block
+func GetPendingForeignClientKey(chainID string) []byte {
+	return append(PendingForeignClientPrefix, []byte(chainID)...)
+}
+
+// GetEncryptedTxKey returns the key for a MsgExecuteEncryptedTx that's waiting to be decrypted in
+// the next block, by the hash of the msg
+func GetEncryptedTxKey(msgBz []byte) []byte {
+	hash := sha256.Sum256(msgBz)
+	return append(EncryptedTxPrefix, hash[:]...)
+}
+
+// GetContractCronKey returns the key for the schedule of a contract's block callbacks
+func GetContractCronKey(addr sdk.AccAddress) []byte {
+	return append(ContractCronPrefix, addr...)
+}
+
 // GetContractAddressKey returns the key for the WASM contract instance
 func GetContractEnclaveKey(addr sdk.AccAddress) []byte {
 	return append(ContractEnclaveIdPrefix, addr...)
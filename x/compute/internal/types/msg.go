@@ -1,6 +1,7 @@
 package types
 
 import (
+	"encoding/hex"
 	"strings"
 
 	errorsmod "cosmossdk.io/errors"
@@ -245,3 +246,284 @@ func (msg MsgUpgradeProposalPassed) GetSigners() []sdk.AccAddress {
 	}
 	return []sdk.AccAddress{senderAddr}
 }
+
+func (msg MsgDiscloseContractKey) ValidateBasic() error {
+	if _, err := sdk.AccAddressFromBech32(msg.Authority); err != nil {
+		return errorsmod.Wrap(err, "authority")
+	}
+	if _, err := sdk.AccAddressFromBech32(msg.Contract); err != nil {
+		return errorsmod.Wrap(err, "contract")
+	}
+	if len(msg.AuditorPublicKey) != 32 {
+		return errorsmod.Wrap(ErrInvalid, "auditor public key must be 32 bytes")
+	}
+	if len(msg.AdminPublicKey) == 0 || len(msg.AdminSignature) == 0 {
+		return errorsmod.Wrap(ErrEmpty, "admin signature")
+	}
+	if msg.Nonce == 0 {
+		return errorsmod.Wrap(ErrEmpty, "nonce")
+	}
+	return nil
+}
+
+func (msg MsgEnqueueJob) Route() string {
+	return RouterKey
+}
+
+func (msg MsgEnqueueJob) Type() string {
+	return "enqueue-job"
+}
+
+func (msg MsgEnqueueJob) ValidateBasic() error {
+	if _, err := sdk.AccAddressFromBech32(msg.Sender); err != nil {
+		return errorsmod.Wrap(err, "sender")
+	}
+	if codeHash, err := hex.DecodeString(msg.HandlerCodeHash); err != nil || len(codeHash) != 32 {
+		return errorsmod.Wrap(ErrInvalidMsg, "handler code hash must be 32 bytes in hex")
+	}
+	if len(msg.SealedInput) == 0 {
+		return errorsmod.Wrap(ErrEmpty, "sealed input")
+	}
+	return nil
+}
+
+func (msg MsgEnqueueJob) GetSignBytes() []byte {
+	return sdk.MustSortJSON(ModuleCdc.MustMarshalJSON(&msg))
+}
+
+func (msg MsgEnqueueJob) GetSigners() []sdk.AccAddress {
+	senderAddr, err := sdk.AccAddressFromBech32(msg.Sender)
+	if err != nil { // should never happen as valid basic rejects invalid addresses
+		panic(err.Error())
+	}
+	return []sdk.AccAddress{senderAddr}
+}
+
+func (msg MsgSubmitJobResult) Route() string {
+	return RouterKey
+}
+
+func (msg MsgSubmitJobResult) Type() string {
+	return "submit-job-result"
+}
+
+func (msg MsgSubmitJobResult) ValidateBasic() error {
+	if _, err := sdk.AccAddressFromBech32(msg.Sender); err != nil {
+		return errorsmod.Wrap(err, "sender")
+	}
+	if len(msg.Result) == 0 {
+		return errorsmod.Wrap(ErrEmpty, "result")
+	}
+	return nil
+}
+
+func (msg MsgSubmitJobResult) GetSignBytes() []byte {
+	return sdk.MustSortJSON(ModuleCdc.MustMarshalJSON(&msg))
+}
+
+func (msg MsgSubmitJobResult) GetSigners() []sdk.AccAddress {
+	senderAddr, err := sdk.AccAddressFromBech32(msg.Sender)
+	if err != nil { // should never happen as valid basic rejects invalid addresses
+		panic(err.Error())
+	}
+	return []sdk.AccAddress{senderAddr}
+}
+
+func (msg MsgImportContractState) Route() string {
+	return RouterKey
+}
+
+func (msg MsgImportContractState) Type() string {
+	return "import-contract-state"
+}
+
+func (msg MsgImportContractState) ValidateBasic() error {
+	if _, err := sdk.AccAddressFromBech32(msg.Sender); err != nil {
+		return errorsmod.Wrap(err, "sender")
+	}
+	if _, err := sdk.AccAddressFromBech32(msg.Contract); err != nil {
+		return errorsmod.Wrap(err, "contract")
+	}
+	if msg.Authorization.Contract != msg.Contract {
+		return errorsmod.Wrap(ErrInvalid, "authorization is for another contract")
+	}
+	if len(msg.Authorization.AdminPublicKey) == 0 || len(msg.Authorization.AdminSignature) == 0 {
+		return errorsmod.Wrap(ErrEmpty, "admin signature")
+	}
+	if len(msg.Chunks) == 0 {
+		return errorsmod.Wrap(ErrEmpty, "chunks")
+	}
+	return nil
+}
+
+func (msg MsgImportContractState) GetSignBytes() []byte {
+	return sdk.MustSortJSON(ModuleCdc.MustMarshalJSON(&msg))
+}
+
+func (msg MsgImportContractState) GetSigners() []sdk.AccAddress {
+	senderAddr, err := sdk.AccAddressFromBech32(msg.Sender)
+	if err != nil { // should never happen as valid basic rejects invalid addresses
+		panic(err.Error())
+	}
+	return []sdk.AccAddress{senderAddr}
+}
+
+// The limits of contract crons, MaxCronInterval and MaxCronGasBudget must match the enclave's cron.rs
+const (
+	MaxCronInterval  = 500_000
+	MaxCronGasBudget = 10_000_000
+	MaxCronSchedules = 10_000
+)
+
+func (msg MsgSetContractCron) Route() string {
+	return RouterKey
+}
+
+func (msg MsgSetContractCron) Type() string {
+	return "set-contract-cron"
+}
+
+func (msg MsgSetContractCron) ValidateBasic() error {
+	if _, err := sdk.AccAddressFromBech32(msg.Sender); err != nil {
+		return errorsmod.Wrap(err, "sender")
+	}
+	if _, err := sdk.AccAddressFromBech32(msg.Contract); err != nil {
+		return errorsmod.Wrap(err, "contract")
+	}
+	if msg.Interval == 0 {
+		return nil
+	}
+	if msg.Interval > MaxCronInterval {
+		return errorsmod.Wrapf(ErrInvalid, "interval must be at most %d blocks", MaxCronInterval)
+	}
+	if msg.GasBudget == 0 || msg.GasBudget > MaxCronGasBudget {
+		return errorsmod.Wrapf(ErrInvalid, "gas budget must be between 1 and %d", MaxCronGasBudget)
+	}
+	return nil
+}
+
+func (msg MsgSetContractCron) GetSignBytes() []byte {
+	return sdk.MustSortJSON(ModuleCdc.MustMarshalJSON(&msg))
+}
+
+func (msg MsgSetContractCron) GetSigners() []sdk.AccAddress {
+	senderAddr, err := sdk.AccAddressFromBech32(msg.Sender)
+	if err != nil { // should never happen as valid basic rejects invalid addresses
+		panic(err.Error())
+	}
+	return []sdk.AccAddress{senderAddr}
+}
+
+// MaxForeignClients is the number of chains the enclave keeps a light client of
+const MaxForeignClients = 32
+
+// MaxChainIDLength is the longest chain id Tendermint accepts
+const MaxChainIDLength = 50
+
+func validateChainID(chainID string) error {
+	if chainID == "" {
+		return errorsmod.Wrap(ErrEmpty, "chain id")
+	}
+	if len(chainID) > MaxChainIDLength {
+		return errorsmod.Wrapf(ErrLimit, "chain id longer than %d", MaxChainIDLength)
+	}
+	return nil
+}
+
+func (msg MsgCreateForeignClient) ValidateBasic() error {
+	if _, err := sdk.AccAddressFromBech32(msg.Authority); err != nil {
+		return errorsmod.Wrap(err, "authority")
+	}
+	if err := validateChainID(msg.ChainID); err != nil {
+		return err
+	}
+	if msg.TrustingPeriod == 0 {
+		return errorsmod.Wrap(ErrEmpty, "trusting period")
+	}
+	if msg.TrustThresholdDenominator == 0 || msg.TrustThresholdNumerator > msg.TrustThresholdDenominator {
+		return errorsmod.Wrap(ErrInvalid, "trust threshold must be a fraction of at most 1")
+	}
+	if len(msg.Header) == 0 {
+		return errorsmod.Wrap(ErrEmpty, "header")
+	}
+	if len(msg.NextValidators) == 0 {
+		return errorsmod.Wrap(ErrEmpty, "next validators")
+	}
+	return nil
+}
+
+func (msg MsgUpdateForeignClient) Route() string {
+	return RouterKey
+}
+
+func (msg MsgUpdateForeignClient) Type() string {
+	return "update-foreign-client"
+}
+
+func (msg MsgUpdateForeignClient) ValidateBasic() error {
+	if _, err := sdk.AccAddressFromBech32(msg.Signer); err != nil {
+		return errorsmod.Wrap(err, "signer")
+	}
+	if err := validateChainID(msg.ChainID); err != nil {
+		return err
+	}
+	if len(msg.Header) == 0 || len(msg.Commit) == 0 {
+		return errorsmod.Wrap(ErrEmpty, "header")
+	}
+	if len(msg.Validators) == 0 || len(msg.NextValidators) == 0 {
+		return errorsmod.Wrap(ErrEmpty, "validators")
+	}
+	return nil
+}
+
+func (msg MsgUpdateForeignClient) GetSignBytes() []byte {
+	return sdk.MustSortJSON(ModuleCdc.MustMarshalJSON(&msg))
+}
+
+func (msg MsgUpdateForeignClient) GetSigners() []sdk.AccAddress {
+	signerAddr, err := sdk.AccAddressFromBech32(msg.Signer)
+	if err != nil { // should never happen as valid basic rejects invalid addresses
+		panic(err.Error())
+	}
+	return []sdk.AccAddress{signerAddr}
+}
+
+const (
+	// encryptedTxHeaderLength is the height, the user's public key and the nonce an encrypted tx
+	// starts with
+	encryptedTxHeaderLength = 8 + 32 + 32
+	MaxEncryptedTxGasLimit  = 10_000_000
+)
+
+func (msg MsgExecuteEncryptedTx) Route() string {
+	return RouterKey
+}
+
+func (msg MsgExecuteEncryptedTx) Type() string {
+	return "execute-encrypted-tx"
+}
+
+func (msg MsgExecuteEncryptedTx) ValidateBasic() error {
+	if _, err := sdk.AccAddressFromBech32(msg.Sender); err != nil {
+		return errorsmod.Wrap(err, "sender")
+	}
+	if len(msg.EncryptedTx) <= encryptedTxHeaderLength {
+		return errorsmod.Wrap(ErrEmpty, "encrypted tx")
+	}
+	if msg.GasLimit == 0 || msg.GasLimit > MaxEncryptedTxGasLimit {
+		return errorsmod.Wrapf(ErrInvalid, "gas limit must be between 1 and %d", MaxEncryptedTxGasLimit)
+	}
+	return nil
+}
+
+func (msg MsgExecuteEncryptedTx) GetSignBytes() []byte {
+	return sdk.MustSortJSON(ModuleCdc.MustMarshalJSON(&msg))
+}
+
+func (msg MsgExecuteEncryptedTx) GetSigners() []sdk.AccAddress {
+	senderAddr, err := sdk.AccAddressFromBech32(msg.Sender)
+	if err != nil { // should never happen as valid basic rejects invalid addresses
+		panic(err.Error())
+	}
+	return []sdk.AccAddress{senderAddr}
+}
@@ -1,6 +1,8 @@
 package types
 
 import (
+	"fmt"
+
 	"cosmossdk.io/math"
 )
 
@@ -22,5 +24,63 @@ func DefaultParams() Params {
 
 // validate params.
 func (p Params) Validate() error {
+	if err := validateCodeHashes(p.NativeSnip20CodeHashes); err != nil {
+		return fmt.Errorf("native SNIP-20 code hashes: %w", err)
+	}
+	if err := validatePaddingBuckets(p.StateValueBuckets); err != nil {
+		return fmt.Errorf("state value buckets: %w", err)
+	}
+	if err := validatePaddingBuckets(p.IoMessageBuckets); err != nil {
+		return fmt.Errorf("io message buckets: %w", err)
+	}
+	if err := validateCodeHashes(p.InstantiateAllowedCodeHashes); err != nil {
+		return fmt.Errorf("instantiate allowed code hashes: %w", err)
+	}
+	if err := validateCodeHashes(p.InstantiateDeniedCodeHashes); err != nil {
+		return fmt.Errorf("instantiate denied code hashes: %w", err)
+	}
+	if err := validateEnclaveParams(p.EnclaveParams); err != nil {
+		return fmt.Errorf("enclave params: %w", err)
+	}
+	return nil
+}
+
+func validateCodeHashes(codeHashes [][]byte) error {
+	for _, codeHash := range codeHashes {
+		if len(codeHash) != 32 {
+			return fmt.Errorf("code hash of %d bytes, must be 32", len(codeHash))
+		}
+	}
+	return nil
+}
+
+// validateEnclaveParams only checks that every param is set once. The enclave checks their
+// values when governance sets them, see Keeper.ValidateEnclaveParams.
+func validateEnclaveParams(params []EnclaveParam) error {
+	names := make(map[string]bool, len(params))
+	for _, param := range params {
+		if param.Name == "" {
+			return fmt.Errorf("param without a name")
+		}
+		if names[param.Name] {
+			return fmt.Errorf("param %s is set more than once", param.Name)
+		}
+		names[param.Name] = true
+	}
+	return nil
+}
+
+// MaxPaddingBucket is the largest padding bucket the enclave accepts
+const MaxPaddingBucket = 1024 * 1024
+
+func validatePaddingBuckets(buckets []uint64) error {
+	for i, bucket := range buckets {
+		if bucket == 0 || bucket > MaxPaddingBucket {
+			return fmt.Errorf("bucket of %d bytes, must be between 1 and %d", bucket, MaxPaddingBucket)
+		}
+		if i > 0 && bucket <= buckets[i-1] {
+			return fmt.Errorf("buckets must be ascending")
+		}
+	}
 	return nil
 }
@@ -33,6 +33,30 @@ type Params struct {
 	CompileCost cosmossdk_io_math.LegacyDec `protobuf:"bytes,1,opt,name=compile_cost,json=compileCost,proto3,customtype=cosmossdk.io/math.LegacyDec" json:"compile_cost"`
 	// MaxContractSize is the maximum size of contract to store in bytes.
 	MaxContractSize uint64 `protobuf:"varint,2,opt,name=max_contract_size,json=maxContractSize,proto3" json:"max_contract_size,omitempty"`
+	// NativeSnip20CodeHashes are the code hashes of SNIP-20 reference
+	// implementations that the enclave can execute simple transfers of natively,
+	// 32 bytes each.
+	NativeSnip20CodeHashes [][]byte `protobuf:"bytes,3,rep,name=native_snip20_code_hashes,json=nativeSnip20CodeHashes,proto3" json:"native_snip20_code_hashes,omitempty"`
+	// MaxMsgLength, MaxWasmLength and MaxCertLength are the largest msg, contract
+	// and node registration certificate in bytes that the enclave accepts. They
+	// can only lower the limits the enclave is built with, and 0 keeps those.
+	MaxMsgLength  uint64 `protobuf:"varint,4,opt,name=max_msg_length,json=maxMsgLength,proto3" json:"max_msg_length,omitempty"`
+	MaxWasmLength uint64 `protobuf:"varint,5,opt,name=max_wasm_length,json=maxWasmLength,proto3" json:"max_wasm_length,omitempty"`
+	MaxCertLength uint64 `protobuf:"varint,6,opt,name=max_cert_length,json=maxCertLength,proto3" json:"max_cert_length,omitempty"`
+	// StateValueBuckets and IoMessageBuckets are the sizes in bytes that contract
+	// state values and encrypted messages are padded to, ascending and at most
+	// 1 MiB. Empty keeps the enclave's defaults.
+	StateValueBuckets []uint64 `protobuf:"varint,7,rep,packed,name=state_value_buckets,json=stateValueBuckets,proto3" json:"state_value_buckets,omitempty"`
+	IoMessageBuckets  []uint64 `protobuf:"varint,8,rep,packed,name=io_message_buckets,json=ioMessageBuckets,proto3" json:"io_message_buckets,omitempty"`
+	// InstantiateDeniedCodeHashes can't be instantiated, and when
+	// InstantiateAllowListEnabled only InstantiateAllowedCodeHashes can be, 32
+	// bytes each. Existing instances are not affected.
+	InstantiateAllowListEnabled  bool     `protobuf:"varint,9,opt,name=instantiate_allow_list_enabled,json=instantiateAllowListEnabled,proto3" json:"instantiate_allow_list_enabled,omitempty"`
+	InstantiateAllowedCodeHashes [][]byte `protobuf:"bytes,10,rep,name=instantiate_allowed_code_hashes,json=instantiateAllowedCodeHashes,proto3" json:"instantiate_allowed_code_hashes,omitempty"`
+	InstantiateDeniedCodeHashes  [][]byte `protobuf:"bytes,11,rep,name=instantiate_denied_code_hashes,json=instantiateDeniedCodeHashes,proto3" json:"instantiate_denied_code_hashes,omitempty"`
+	// EnclaveParams are params governance sets inside the enclave, by name, see
+	// docs/verified-params.md. A param that isn't in the list has its default.
+	EnclaveParams []EnclaveParam `protobuf:"bytes,12,rep,name=enclave_params,json=enclaveParams,proto3" json:"enclave_params"`
 }
 
 func (m *Params) Reset()         { *m = Params{} }
@@ -75,8 +99,134 @@ func (m *Params) GetMaxContractSize() uint64 {
 	return 0
 }
 
+func (m *Params) GetNativeSnip20CodeHashes() [][]byte {
+	if m != nil {
+		return m.NativeSnip20CodeHashes
+	}
+	return nil
+}
+
+func (m *Params) GetMaxMsgLength() uint64 {
+	if m != nil {
+		return m.MaxMsgLength
+	}
+	return 0
+}
+
+func (m *Params) GetMaxWasmLength() uint64 {
+	if m != nil {
+		return m.MaxWasmLength
+	}
+	return 0
+}
+
+func (m *Params) GetMaxCertLength() uint64 {
+	if m != nil {
+		return m.MaxCertLength
+	}
+	return 0
+}
+
+func (m *Params) GetStateValueBuckets() []uint64 {
+	if m != nil {
+		return m.StateValueBuckets
+	}
+	return nil
+}
+
+func (m *Params) GetIoMessageBuckets() []uint64 {
+	if m != nil {
+		return m.IoMessageBuckets
+	}
+	return nil
+}
+
+func (m *Params) GetInstantiateAllowListEnabled() bool {
+	if m != nil {
+		return m.InstantiateAllowListEnabled
+	}
+	return false
+}
+
+func (m *Params) GetInstantiateAllowedCodeHashes() [][]byte {
+	if m != nil {
+		return m.InstantiateAllowedCodeHashes
+	}
+	return nil
+}
+
+func (m *Params) GetInstantiateDeniedCodeHashes() [][]byte {
+	if m != nil {
+		return m.InstantiateDeniedCodeHashes
+	}
+	return nil
+}
+
+func (m *Params) GetEnclaveParams() []EnclaveParam {
+	if m != nil {
+		return m.EnclaveParams
+	}
+	return nil
+}
+
+// EnclaveParam is a param governance sets inside the enclave
+type EnclaveParam struct {
+	// Name is the name of the param
+	Name string `protobuf:"bytes,1,opt,name=name,proto3" json:"name,omitempty"`
+	// Value is the encoded value of the param
+	Value []byte `protobuf:"bytes,2,opt,name=value,proto3" json:"value,omitempty"`
+}
+
+func (m *EnclaveParam) Reset()         { *m = EnclaveParam{} }
+func (m *EnclaveParam) String() string { return proto.CompactTextString(m) }
+func (*EnclaveParam) ProtoMessage()    {}
+func (*EnclaveParam) Descriptor() ([]byte, []int) {
+	return fileDescriptor_631b2d12372d9a02, []int{1}
+}
+func (m *EnclaveParam) XXX_Unmarshal(b []byte) error {
+	return m.Unmarshal(b)
+}
+func (m *EnclaveParam) XXX_Marshal(b []byte, deterministic bool) ([]byte, error) {
+	if deterministic {
+		return xxx_messageInfo_EnclaveParam.Marshal(b, m, deterministic)
+	} else {
+		b = b[:cap(b)]
+		n, err := m.MarshalToSizedBuffer(b)
+		if err != nil {
+			return nil, err
+		}
+		return b[:n], nil
+	}
+}
+func (m *EnclaveParam) XXX_Merge(src proto.Message) {
+	xxx_messageInfo_EnclaveParam.Merge(m, src)
+}
+func (m *EnclaveParam) XXX_Size() int {
+	return m.Size()
+}
+func (m *EnclaveParam) XXX_DiscardUnknown() {
+	xxx_messageInfo_EnclaveParam.DiscardUnknown(m)
+}
+
+var xxx_messageInfo_EnclaveParam proto.InternalMessageInfo
+
+func (m *EnclaveParam) GetName() string {
+	if m != nil {
+		return m.Name
+	}
+	return ""
+}
+
+func (m *EnclaveParam) GetValue() []byte {
+	if m != nil {
+		return m.Value
+	}
+	return nil
+}
+
 func init() {
 	proto.RegisterType((*Params)(nil), "secret.compute.v1beta1.Params")
+	proto.RegisterType((*EnclaveParam)(nil), "secret.compute.v1beta1.EnclaveParam")
 }
 
 func init() {
@@ -84,27 +234,44 @@ func init() {
 }
 
 var fileDescriptor_631b2d12372d9a02 = []byte{
-	// 313 bytes of a gzipped FileDescriptorProto
-	0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0x3c, 0x90, 0xb1, 0x4a, 0x03, 0x41,
-	0x10, 0x86, 0x6f, 0x45, 0x03, 0x9e, 0x82, 0x24, 0x88, 0xc4, 0x08, 0x97, 0xa0, 0x4d, 0x10, 0xbc,
-	0x25, 0x08, 0x16, 0x96, 0x49, 0x4a, 0x11, 0x31, 0x36, 0xda, 0x1c, 0x7b, 0xeb, 0x70, 0x59, 0x92,
-	0xbd, 0x39, 0x76, 0x27, 0x9a, 0xe4, 0x29, 0xac, 0x7c, 0x06, 0x4b, 0x0b, 0x1f, 0x22, 0x65, 0xb0,
-	0x12, 0x8b, 0x20, 0x49, 0xe1, 0x6b, 0x48, 0x6e, 0x0f, 0x9b, 0x65, 0x67, 0xfe, 0x8f, 0xff, 0x67,
-	0x7e, 0xff, 0xc4, 0x82, 0x34, 0x40, 0x5c, 0xa2, 0xce, 0x46, 0x04, 0xfc, 0xa9, 0x15, 0x03, 0x89,
-	0x16, 0xcf, 0x84, 0x11, 0xda, 0x86, 0x99, 0x41, 0xc2, 0xca, 0x81, 0x83, 0xc2, 0x02, 0x0a, 0x0b,
-	0xa8, 0xb6, 0x9f, 0x60, 0x82, 0x39, 0xc2, 0xd7, 0x3f, 0x47, 0xd7, 0x0e, 0x25, 0x5a, 0x8d, 0x36,
-	0x72, 0x82, 0x1b, 0x0a, 0xa9, 0x2c, 0xb4, 0x4a, 0x91, 0xe7, 0xaf, 0x5b, 0x1d, 0xbf, 0x32, 0xbf,
-	0x74, 0x93, 0x87, 0x55, 0xee, 0xfd, 0xdd, 0x75, 0x82, 0x1a, 0x42, 0x24, 0xd1, 0x52, 0x95, 0x35,
-	0x58, 0x73, 0xbb, 0x7d, 0x31, 0x5b, 0xd4, 0xbd, 0xef, 0x45, 0xfd, 0xc8, 0x39, 0xd9, 0xc7, 0x41,
-	0xa8, 0x90, 0x6b, 0x41, 0xfd, 0xf0, 0x0a, 0x12, 0x21, 0x27, 0x5d, 0x90, 0x9f, 0x1f, 0x67, 0x7e,
-	0x11, 0xd4, 0x05, 0xf9, 0xf6, 0xfb, 0x7e, 0xca, 0x6e, 0x77, 0x0a, 0xaf, 0x0e, 0x5a, 0xaa, 0xb4,
-	0xfc, 0xb2, 0x16, 0xe3, 0x48, 0x62, 0x4a, 0x46, 0x48, 0x8a, 0xac, 0x9a, 0x42, 0x75, 0xa3, 0xc1,
-	0x9a, 0x9b, 0xed, 0x2d, 0x87, 0xef, 0x69, 0x31, 0xee, 0x14, 0x72, 0x4f, 0x4d, 0xa1, 0x7d, 0x37,
-	0x5b, 0x06, 0x6c, 0xbe, 0x0c, 0xd8, 0xcf, 0x32, 0x60, 0x2f, 0xab, 0xc0, 0x9b, 0xaf, 0x02, 0xef,
-	0x6b, 0x15, 0x78, 0x0f, 0x97, 0x89, 0xa2, 0xfe, 0x28, 0x5e, 0xd7, 0xc1, 0xad, 0x34, 0x34, 0x14,
-	0xb1, 0xe5, 0xbd, 0xbc, 0xa2, 0x6b, 0xa0, 0x67, 0x34, 0x03, 0x3e, 0xfe, 0x2f, 0x54, 0xa5, 0x04,
-	0x26, 0x15, 0x43, 0x4e, 0x93, 0x0c, 0x6c, 0x5c, 0xca, 0xaf, 0x3e, 0xff, 0x0b, 0x00, 0x00, 0xff,
-	0xff, 0x8c, 0x9d, 0xbb, 0x8e, 0x78, 0x01, 0x00, 0x00,
+	// 590 bytes of a gzipped FileDescriptorProto
+	0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0x6d, 0x93, 0x41, 0x6f, 0xd3, 0x30,
+	0x14, 0xc7, 0x57, 0xd6, 0x8e, 0xcd, 0xed, 0x06, 0x0d, 0xd3, 0x94, 0x6d, 0xa8, 0x9d, 0xc6, 0x84,
+	0x26, 0x04, 0x09, 0x1d, 0x12, 0x02, 0x6e, 0xb4, 0xab, 0xc4, 0xa1, 0x43, 0x90, 0x22, 0x10, 0x5c,
+	0x2c, 0x27, 0x79, 0x4a, 0xad, 0x26, 0x76, 0x15, 0xbb, 0xed, 0xc6, 0xa7, 0xe0, 0x63, 0x70, 0xe4,
+	0xc0, 0x87, 0x18, 0xb7, 0x89, 0x13, 0xe2, 0x30, 0x21, 0x38, 0xf0, 0x35, 0x70, 0xec, 0x74, 0x64,
+	0x85, 0xc3, 0x8b, 0xe2, 0xf7, 0xff, 0xf9, 0xff, 0x9e, 0xec, 0x67, 0x74, 0x4b, 0x40, 0x90, 0x82,
+	0x74, 0x03, 0x9e, 0x8c, 0xc6, 0x12, 0xdc, 0x49, 0xcb, 0x07, 0x49, 0x5a, 0xee, 0x88, 0xa4, 0x24,
+	0x11, 0xce, 0x28, 0xe5, 0x92, 0x5b, 0x1b, 0x06, 0x72, 0x72, 0xc8, 0xc9, 0xa1, 0xad, 0xf5, 0x88,
+	0x47, 0x5c, 0x23, 0x6e, 0xf6, 0x67, 0xe8, 0xad, 0xcd, 0x80, 0x8b, 0x84, 0x0b, 0x6c, 0x04, 0xb3,
+	0xc8, 0xa5, 0x3a, 0x49, 0x28, 0xe3, 0xae, 0xfe, 0x9a, 0xd4, 0xee, 0x97, 0x0a, 0x5a, 0x7a, 0xa1,
+	0x8b, 0x59, 0x6f, 0x51, 0x2d, 0xab, 0x40, 0x63, 0xc0, 0x6a, 0x97, 0xb4, 0x4b, 0x3b, 0xa5, 0xfd,
+	0x95, 0xf6, 0xc3, 0xd3, 0xf3, 0xe6, 0xc2, 0xf7, 0xf3, 0xe6, 0xb6, 0x71, 0x12, 0xe1, 0xd0, 0xa1,
+	0xdc, 0x4d, 0x88, 0x1c, 0x38, 0x3d, 0x88, 0x48, 0x70, 0x72, 0x08, 0xc1, 0xd7, 0xcf, 0xf7, 0x50,
+	0x5e, 0x48, 0xad, 0x3e, 0xfe, 0xfe, 0x74, 0xa7, 0xe4, 0x55, 0x73, 0xaf, 0x8e, 0xb2, 0xb2, 0x5a,
+	0xa8, 0x9e, 0x90, 0x63, 0x65, 0xcb, 0x64, 0x4a, 0x02, 0x89, 0x05, 0x7d, 0x0f, 0xf6, 0x15, 0xe5,
+	0x5f, 0x6e, 0x57, 0x0c, 0x7e, 0x4d, 0xe9, 0x9d, 0x5c, 0xee, 0x2b, 0xd5, 0x7a, 0x8c, 0x36, 0x19,
+	0x91, 0x74, 0x02, 0x58, 0x30, 0x3a, 0x3a, 0xb8, 0xaf, 0x36, 0x87, 0x80, 0x07, 0x44, 0x0c, 0x40,
+	0xd8, 0x8b, 0x3b, 0x8b, 0xfb, 0x35, 0x6f, 0xc3, 0x00, 0x7d, 0xad, 0x77, 0x94, 0xfc, 0x4c, 0xab,
+	0xd6, 0x1e, 0x5a, 0xcb, 0xaa, 0x25, 0x22, 0xc2, 0x31, 0xb0, 0x48, 0x0e, 0xec, 0x72, 0x56, 0xca,
+	0xab, 0xa9, 0xec, 0x91, 0x88, 0x7a, 0x3a, 0x67, 0xdd, 0x46, 0x59, 0x4d, 0x3c, 0x25, 0x22, 0x99,
+	0x61, 0x15, 0x8d, 0xad, 0xaa, 0xf4, 0x1b, 0x95, 0xbd, 0xcc, 0x05, 0x90, 0xca, 0x19, 0xb7, 0x74,
+	0xc1, 0x75, 0x54, 0x36, 0xe7, 0x1c, 0x74, 0x43, 0x48, 0x22, 0x01, 0x4f, 0x48, 0x3c, 0x06, 0xec,
+	0x8f, 0x83, 0x21, 0x48, 0x61, 0x5f, 0x55, 0xad, 0x96, 0xbd, 0xba, 0x96, 0x5e, 0x67, 0x4a, 0xdb,
+	0x08, 0xd6, 0x5d, 0x64, 0x51, 0x8e, 0x13, 0x10, 0x82, 0x44, 0x7f, 0xf1, 0x65, 0x8d, 0x5f, 0xa7,
+	0xfc, 0xc8, 0x08, 0x33, 0xba, 0x83, 0x1a, 0x94, 0x29, 0x13, 0x26, 0x69, 0x56, 0x83, 0xc4, 0x31,
+	0x9f, 0xe2, 0x98, 0x0a, 0x89, 0x81, 0x11, 0x3f, 0x86, 0xd0, 0x5e, 0x51, 0x4d, 0x2d, 0x7b, 0xdb,
+	0x05, 0xea, 0x69, 0x06, 0xf5, 0x14, 0xd3, 0x35, 0x88, 0xd5, 0x45, 0xcd, 0x7f, 0x4c, 0x20, 0xbc,
+	0x74, 0xb2, 0x48, 0x9f, 0xec, 0xcd, 0x79, 0x17, 0x08, 0x0b, 0xe7, 0x3b, 0xd7, 0x4b, 0x08, 0x8c,
+	0xce, 0xb9, 0x54, 0xb5, 0x4b, 0xb1, 0x97, 0x43, 0x0d, 0x15, 0x4c, 0x5e, 0xa2, 0x35, 0x60, 0x41,
+	0x4c, 0xd4, 0x05, 0x9b, 0x61, 0xb7, 0x6b, 0x6a, 0x53, 0xf5, 0x60, 0xcf, 0xf9, 0xff, 0xb4, 0x3b,
+	0x5d, 0x43, 0xeb, 0x61, 0x6d, 0x97, 0xb3, 0xa9, 0xf4, 0x56, 0xa1, 0x90, 0x13, 0xbb, 0x8f, 0x50,
+	0xad, 0x08, 0x59, 0x16, 0x2a, 0x33, 0x92, 0x80, 0x19, 0x64, 0x4f, 0xff, 0x5b, 0xeb, 0xa8, 0xa2,
+	0xef, 0x47, 0x4f, 0x5f, 0xcd, 0x33, 0x8b, 0xf6, 0xab, 0xd3, 0x9f, 0x8d, 0xd2, 0x99, 0x8a, 0x1f,
+	0x2a, 0x3e, 0xfc, 0x6a, 0x2c, 0x9c, 0xa9, 0xf8, 0xa6, 0xe2, 0xdd, 0x93, 0x88, 0xca, 0xc1, 0xd8,
+	0xcf, 0xba, 0x71, 0x45, 0x90, 0xca, 0x98, 0xf8, 0xc2, 0xed, 0xeb, 0x0e, 0x9f, 0x83, 0x9c, 0xf2,
+	0x74, 0xe8, 0x1e, 0x5f, 0xbc, 0x5e, 0xca, 0x24, 0xa4, 0x8c, 0xc4, 0xae, 0x3c, 0x19, 0x81, 0xf0,
+	0x97, 0xf4, 0x13, 0x7b, 0xf0, 0x07, 0xcd, 0x7e, 0x36, 0x65, 0xe5, 0x03, 0x00, 0x00,
 }
 
 func (m *Params) Marshal() (dAtA []byte, err error) {
@@ -127,6 +294,108 @@ func (m *Params) MarshalToSizedBuffer(dAtA []byte) (int, error) {
 	_ = i
 	var l int
 	_ = l
+	if len(m.EnclaveParams) > 0 {
+		for iNdEx := len(m.EnclaveParams) - 1; iNdEx >= 0; iNdEx-- {
+			{
+				size, err := m.EnclaveParams[iNdEx].MarshalToSizedBuffer(dAtA[:i])
+				if err != nil {
+					return 0, err
+				}
+				i -= size
+				i = encodeVarintParams(dAtA, i, uint64(size))
+			}
+			i--
+			dAtA[i] = 0x62
+		}
+	}
+	if len(m.InstantiateDeniedCodeHashes) > 0 {
+		for iNdEx := len(m.InstantiateDeniedCodeHashes) - 1; iNdEx >= 0; iNdEx-- {
+			i -= len(m.InstantiateDeniedCodeHashes[iNdEx])
+			copy(dAtA[i:], m.InstantiateDeniedCodeHashes[iNdEx])
+			i = encodeVarintParams(dAtA, i, uint64(len(m.InstantiateDeniedCodeHashes[iNdEx])))
+			i--
+			dAtA[i] = 0x5a
+		}
+	}
+	if len(m.InstantiateAllowedCodeHashes) > 0 {
+		for iNdEx := len(m.InstantiateAllowedCodeHashes) - 1; iNdEx >= 0; iNdEx-- {
+			i -= len(m.InstantiateAllowedCodeHashes[iNdEx])
+			copy(dAtA[i:], m.InstantiateAllowedCodeHashes[iNdEx])
+			i = encodeVarintParams(dAtA, i, uint64(len(m.InstantiateAllowedCodeHashes[iNdEx])))
+			i--
+			dAtA[i] = 0x52
+		}
+	}
+	if m.InstantiateAllowListEnabled {
+		i--
+		if m.InstantiateAllowListEnabled {
+			dAtA[i] = 1
+		} else {
+			dAtA[i] = 0
+		}
+		i--
+		dAtA[i] = 0x48
+	}
+	if len(m.IoMessageBuckets) > 0 {
+		dAtA2 := make([]byte, len(m.IoMessageBuckets)*10)
+		var j1 int
+		for _, num := range m.IoMessageBuckets {
+			for num >= 1<<7 {
+				dAtA2[j1] = uint8(uint64(num)&0x7f | 0x80)
+				num >>= 7
+				j1++
+			}
+			dAtA2[j1] = uint8(num)
+			j1++
+		}
+		i -= j1
+		copy(dAtA[i:], dAtA2[:j1])
+		i = encodeVarintParams(dAtA, i, uint64(j1))
+		i--
+		dAtA[i] = 0x42
+	}
+	if len(m.StateValueBuckets) > 0 {
+		dAtA4 := make([]byte, len(m.StateValueBuckets)*10)
+		var j3 int
+		for _, num := range m.StateValueBuckets {
+			for num >= 1<<7 {
+				dAtA4[j3] = uint8(uint64(num)&0x7f | 0x80)
+				num >>= 7
+				j3++
+			}
+			dAtA4[j3] = uint8(num)
+			j3++
+		}
+		i -= j3
+		copy(dAtA[i:], dAtA4[:j3])
+		i = encodeVarintParams(dAtA, i, uint64(j3))
+		i--
+		dAtA[i] = 0x3a
+	}
+	if m.MaxCertLength != 0 {
+		i = encodeVarintParams(dAtA, i, uint64(m.MaxCertLength))
+		i--
+		dAtA[i] = 0x30
+	}
+	if m.MaxWasmLength != 0 {
+		i = encodeVarintParams(dAtA, i, uint64(m.MaxWasmLength))
+		i--
+		dAtA[i] = 0x28
+	}
+	if m.MaxMsgLength != 0 {
+		i = encodeVarintParams(dAtA, i, uint64(m.MaxMsgLength))
+		i--
+		dAtA[i] = 0x20
+	}
+	if len(m.NativeSnip20CodeHashes) > 0 {
+		for iNdEx := len(m.NativeSnip20CodeHashes) - 1; iNdEx >= 0; iNdEx-- {
+			i -= len(m.NativeSnip20CodeHashes[iNdEx])
+			copy(dAtA[i:], m.NativeSnip20CodeHashes[iNdEx])
+			i = encodeVarintParams(dAtA, i, uint64(len(m.NativeSnip20CodeHashes[iNdEx])))
+			i--
+			dAtA[i] = 0x1a
+		}
+	}
 	if m.MaxContractSize != 0 {
 		i = encodeVarintParams(dAtA, i, uint64(m.MaxContractSize))
 		i--
@@ -145,6 +414,43 @@ func (m *Params) MarshalToSizedBuffer(dAtA []byte) (int, error) {
 	return len(dAtA) - i, nil
 }
 
+func (m *EnclaveParam) Marshal() (dAtA []byte, err error) {
+	size := m.Size()
+	dAtA = make([]byte, size)
+	n, err := m.MarshalToSizedBuffer(dAtA[:size])
+	if err != nil {
+		return nil, err
+	}
+	return dAtA[:n], nil
+}
+
+func (m *EnclaveParam) MarshalTo(dAtA []byte) (int, error) {
+	size := m.Size()
+	return m.MarshalToSizedBuffer(dAtA[:size])
+}
+
+func (m *EnclaveParam) MarshalToSizedBuffer(dAtA []byte) (int, error) {
+	i := len(dAtA)
+	_ = i
+	var l int
+	_ = l
+	if len(m.Value) > 0 {
+		i -= len(m.Value)
+		copy(dAtA[i:], m.Value)
+		i = encodeVarintParams(dAtA, i, uint64(len(m.Value)))
+		i--
+		dAtA[i] = 0x12
+	}
+	if len(m.Name) > 0 {
+		i -= len(m.Name)
+		copy(dAtA[i:], m.Name)
+		i = encodeVarintParams(dAtA, i, uint64(len(m.Name)))
+		i--
+		dAtA[i] = 0xa
+	}
+	return len(dAtA) - i, nil
+}
+
 func encodeVarintParams(dAtA []byte, offset int, v uint64) int {
 	offset -= sovParams(v)
 	base := offset
@@ -167,6 +473,73 @@ func (m *Params) Size() (n int) {
 	if m.MaxContractSize != 0 {
 		n += 1 + sovParams(uint64(m.MaxContractSize))
 	}
+	if len(m.NativeSnip20CodeHashes) > 0 {
+		for _, b := range m.NativeSnip20CodeHashes {
+			l = len(b)
+			n += 1 + l + sovParams(uint64(l))
+		}
+	}
+	if m.MaxMsgLength != 0 {
+		n += 1 + sovParams(uint64(m.MaxMsgLength))
+	}
+	if m.MaxWasmLength != 0 {
+		n += 1 + sovParams(uint64(m.MaxWasmLength))
+	}
+	if m.MaxCertLength != 0 {
+		n += 1 + sovParams(uint64(m.MaxCertLength))
+	}
+	if len(m.StateValueBuckets) > 0 {
+		l = 0
+		for _, e := range m.StateValueBuckets {
+			l += sovParams(uint64(e))
+		}
+		n += 1 + sovParams(uint64(l)) + l
+	}
+	if len(m.IoMessageBuckets) > 0 {
+		l = 0
+		for _, e := range m.IoMessageBuckets {
+			l += sovParams(uint64(e))
+		}
+		n += 1 + sovParams(uint64(l)) + l
+	}
+	if m.InstantiateAllowListEnabled {
+		n += 2
+	}
+	if len(m.InstantiateAllowedCodeHashes) > 0 {
+		for _, b := range m.InstantiateAllowedCodeHashes {
+			l = len(b)
+			n += 1 + l + sovParams(uint64(l))
+		}
+	}
+	if len(m.InstantiateDeniedCodeHashes) > 0 {
+		for _, b := range m.InstantiateDeniedCodeHashes {
+			l = len(b)
+			n += 1 + l + sovParams(uint64(l))
+		}
+	}
+	if len(m.EnclaveParams) > 0 {
+		for _, e := range m.EnclaveParams {
+			l = e.Size()
+			n += 1 + l + sovParams(uint64(l))
+		}
+	}
+	return n
+}
+
+func (m *EnclaveParam) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = len(m.Name)
+	if l > 0 {
+		n += 1 + l + sovParams(uint64(l))
+	}
+	l = len(m.Value)
+	if l > 0 {
+		n += 1 + l + sovParams(uint64(l))
+	}
 	return n
 }
 
@@ -258,6 +631,481 @@ func (m *Params) Unmarshal(dAtA []byte) error {
 					break
 				}
 			}
+		case 3:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field NativeSnip20CodeHashes", wireType)
+			}
+			var byteLen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowParams
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				byteLen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if byteLen < 0 {
+				return ErrInvalidLengthParams
+			}
+			postIndex := iNdEx + byteLen
+			if postIndex < 0 {
+				return ErrInvalidLengthParams
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.NativeSnip20CodeHashes = append(m.NativeSnip20CodeHashes, make([]byte, postIndex-iNdEx))
+			copy(m.NativeSnip20CodeHashes[len(m.NativeSnip20CodeHashes)-1], dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 4:
+			if wireType != 0 {
+				return fmt.Errorf("proto: wrong wireType = %d for field MaxMsgLength", wireType)
+			}
+			m.MaxMsgLength = 0
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowParams
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				m.MaxMsgLength |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+		case 5:
+			if wireType != 0 {
+				return fmt.Errorf("proto: wrong wireType = %d for field MaxWasmLength", wireType)
+			}
+			m.MaxWasmLength = 0
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowParams
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				m.MaxWasmLength |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+		case 6:
+			if wireType != 0 {
+				return fmt.Errorf("proto: wrong wireType = %d for field MaxCertLength", wireType)
+			}
+			m.MaxCertLength = 0
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowParams
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				m.MaxCertLength |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+		case 7:
+			if wireType == 0 {
+				var v uint64
+				for shift := uint(0); ; shift += 7 {
+					if shift >= 64 {
+						return ErrIntOverflowParams
+					}
+					if iNdEx >= l {
+						return io.ErrUnexpectedEOF
+					}
+					b := dAtA[iNdEx]
+					iNdEx++
+					v |= uint64(b&0x7F) << shift
+					if b < 0x80 {
+						break
+					}
+				}
+				m.StateValueBuckets = append(m.StateValueBuckets, v)
+			} else if wireType == 2 {
+				var packedLen int
+				for shift := uint(0); ; shift += 7 {
+					if shift >= 64 {
+						return ErrIntOverflowParams
+					}
+					if iNdEx >= l {
+						return io.ErrUnexpectedEOF
+					}
+					b := dAtA[iNdEx]
+					iNdEx++
+					packedLen |= int(b&0x7F) << shift
+					if b < 0x80 {
+						break
+					}
+				}
+				if packedLen < 0 {
+					return ErrInvalidLengthParams
+				}
+				postIndex := iNdEx + packedLen
+				if postIndex < 0 {
+					return ErrInvalidLengthParams
+				}
+				if postIndex > l {
+					return io.ErrUnexpectedEOF
+				}
+				var elementCount int
+				var count int
+				for _, integer := range dAtA[iNdEx:postIndex] {
+					if integer < 128 {
+						count++
+					}
+				}
+				elementCount = count
+				if elementCount != 0 && len(m.StateValueBuckets) == 0 {
+					m.StateValueBuckets = make([]uint64, 0, elementCount)
+				}
+				for iNdEx < postIndex {
+					var v uint64
+					for shift := uint(0); ; shift += 7 {
+						if shift >= 64 {
+							return ErrIntOverflowParams
+						}
+						if iNdEx >= l {
+							return io.ErrUnexpectedEOF
+						}
+						b := dAtA[iNdEx]
+						iNdEx++
+						v |= uint64(b&0x7F) << shift
+						if b < 0x80 {
+							break
+						}
+					}
+					m.StateValueBuckets = append(m.StateValueBuckets, v)
+				}
+			} else {
+				return fmt.Errorf("proto: wrong wireType = %d for field StateValueBuckets", wireType)
+			}
+		case 8:
+			if wireType == 0 {
+				var v uint64
+				for shift := uint(0); ; shift += 7 {
+					if shift >= 64 {
+						return ErrIntOverflowParams
+					}
+					if iNdEx >= l {
+						return io.ErrUnexpectedEOF
+					}
+					b := dAtA[iNdEx]
+					iNdEx++
+					v |= uint64(b&0x7F) << shift
+					if b < 0x80 {
+						break
+					}
+				}
+				m.IoMessageBuckets = append(m.IoMessageBuckets, v)
+			} else if wireType == 2 {
+				var packedLen int
+				for shift := uint(0); ; shift += 7 {
+					if shift >= 64 {
+						return ErrIntOverflowParams
+					}
+					if iNdEx >= l {
+						return io.ErrUnexpectedEOF
+					}
+					b := dAtA[iNdEx]
+					iNdEx++
+					packedLen |= int(b&0x7F) << shift
+					if b < 0x80 {
+						break
+					}
+				}
+				if packedLen < 0 {
+					return ErrInvalidLengthParams
+				}
+				postIndex := iNdEx + packedLen
+				if postIndex < 0 {
+					return ErrInvalidLengthParams
+				}
+				if postIndex > l {
+					return io.ErrUnexpectedEOF
+				}
+				var elementCount int
+				var count int
+				for _, integer := range dAtA[iNdEx:postIndex] {
+					if integer < 128 {
+						count++
+					}
+				}
+				elementCount = count
+				if elementCount != 0 && len(m.IoMessageBuckets) == 0 {
+					m.IoMessageBuckets = make([]uint64, 0, elementCount)
+				}
+				for iNdEx < postIndex {
+					var v uint64
+					for shift := uint(0); ; shift += 7 {
+						if shift >= 64 {
+							return ErrIntOverflowParams
+						}
+						if iNdEx >= l {
+							return io.ErrUnexpectedEOF
+						}
+						b := dAtA[iNdEx]
+						iNdEx++
+						v |= uint64(b&0x7F) << shift
+						if b < 0x80 {
+							break
+						}
+					}
+					m.IoMessageBuckets = append(m.IoMessageBuckets, v)
+				}
+			} else {
+				return fmt.Errorf("proto: wrong wireType = %d for field IoMessageBuckets", wireType)
+			}
+		case 9:
+			if wireType != 0 {
+				return fmt.Errorf("proto: wrong wireType = %d for field InstantiateAllowListEnabled", wireType)
+			}
+			var v int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowParams
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				v |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			m.InstantiateAllowListEnabled = bool(v != 0)
+		case 10:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field InstantiateAllowedCodeHashes", wireType)
+			}
+			var byteLen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowParams
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				byteLen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if byteLen < 0 {
+				return ErrInvalidLengthParams
+			}
+			postIndex := iNdEx + byteLen
+			if postIndex < 0 {
+				return ErrInvalidLengthParams
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.InstantiateAllowedCodeHashes = append(m.InstantiateAllowedCodeHashes, make([]byte, postIndex-iNdEx))
+			copy(m.InstantiateAllowedCodeHashes[len(m.InstantiateAllowedCodeHashes)-1], dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 11:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field InstantiateDeniedCodeHashes", wireType)
+			}
+			var byteLen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowParams
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				byteLen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if byteLen < 0 {
+				return ErrInvalidLengthParams
+			}
+			postIndex := iNdEx + byteLen
+			if postIndex < 0 {
+				return ErrInvalidLengthParams
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.InstantiateDeniedCodeHashes = append(m.InstantiateDeniedCodeHashes, make([]byte, postIndex-iNdEx))
+			copy(m.InstantiateDeniedCodeHashes[len(m.InstantiateDeniedCodeHashes)-1], dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 12:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field EnclaveParams", wireType)
+			}
+			var msglen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowParams
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				msglen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if msglen < 0 {
+				return ErrInvalidLengthParams
+			}
+			postIndex := iNdEx + msglen
+			if postIndex < 0 {
+				return ErrInvalidLengthParams
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.EnclaveParams = append(m.EnclaveParams, EnclaveParam{})
+			if err := m.EnclaveParams[len(m.EnclaveParams)-1].Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
+				return err
+			}
+			iNdEx = postIndex
+		default:
+			iNdEx = preIndex
+			skippy, err := skipParams(dAtA[iNdEx:])
+			if err != nil {
+				return err
+			}
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
+				return ErrInvalidLengthParams
+			}
+			if (iNdEx + skippy) > l {
+				return io.ErrUnexpectedEOF
+			}
+			iNdEx += skippy
+		}
+	}
+
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
+	}
+	return nil
+}
+func (m *EnclaveParam) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowParams
+			}
+			if iNdEx >= l {
+				return io.ErrUnexpectedEOF
+			}
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: EnclaveParam: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: EnclaveParam: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		case 1:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field Name", wireType)
+			}
+			var stringLen uint64
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowParams
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				stringLen |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthParams
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthParams
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.Name = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 2:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field Value", wireType)
+			}
+			var byteLen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowParams
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				byteLen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if byteLen < 0 {
+				return ErrInvalidLengthParams
+			}
+			postIndex := iNdEx + byteLen
+			if postIndex < 0 {
+				return ErrInvalidLengthParams
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.Value = append(m.Value[:0], dAtA[iNdEx:postIndex]...)
+			if m.Value == nil {
+				m.Value = []byte{}
+			}
+			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
 			skippy, err := skipParams(dAtA[iNdEx:])
@@ -0,0 +1,39 @@
+package types
+
+import (
+	"testing"
+
+	"github.com/stretchr/testify/require"
+)
+
+func TestParamsValidateEnclaveParams(t *testing.T) {
+	specs := map[string]struct {
+		params   []EnclaveParam
+		expError bool
+	}{
+		"none": {},
+		"all good": {
+			params: []EnclaveParam{{Name: "a", Value: []byte{1}}, {Name: "b"}},
+		},
+		"no name": {
+			params:   []EnclaveParam{{Value: []byte{1}}},
+			expError: true,
+		},
+		"set twice": {
+			params:   []EnclaveParam{{Name: "a", Value: []byte{1}}, {Name: "a", Value: []byte{0}}},
+			expError: true,
+		},
+	}
+	for msg, spec := range specs {
+		t.Run(msg, func(t *testing.T) {
+			params := DefaultParams()
+			params.EnclaveParams = spec.params
+			err := params.Validate()
+			if spec.expError {
+				require.Error(t, err)
+				return
+			}
+			require.NoError(t, err)
+		})
+	}
+}
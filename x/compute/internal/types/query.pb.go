@@ -805,6 +805,356 @@ func (m *QueryContractHistoryResponse) XXX_DiscardUnknown() {
 
 var xxx_messageInfo_QueryContractHistoryResponse proto.InternalMessageInfo
 
+type QueryJobRequest struct {
+	JobId uint64 `protobuf:"varint,1,opt,name=job_id,json=jobId,proto3" json:"job_id,omitempty"`
+}
+
+func (m *QueryJobRequest) Reset()         { *m = QueryJobRequest{} }
+func (m *QueryJobRequest) String() string { return proto.CompactTextString(m) }
+func (*QueryJobRequest) ProtoMessage()    {}
+func (*QueryJobRequest) Descriptor() ([]byte, []int) {
+	return fileDescriptor_7735281c5fa969d4, []int{20}
+}
+func (m *QueryJobRequest) XXX_Unmarshal(b []byte) error {
+	return m.Unmarshal(b)
+}
+func (m *QueryJobRequest) XXX_Marshal(b []byte, deterministic bool) ([]byte, error) {
+	if deterministic {
+		return xxx_messageInfo_QueryJobRequest.Marshal(b, m, deterministic)
+	} else {
+		b = b[:cap(b)]
+		n, err := m.MarshalToSizedBuffer(b)
+		if err != nil {
+			return nil, err
+		}
+		return b[:n], nil
+	}
+}
+func (m *QueryJobRequest) XXX_Merge(src proto.Message) {
+	xxx_messageInfo_QueryJobRequest.Merge(m, src)
+}
+func (m *QueryJobRequest) XXX_Size() int {
+	return m.Size()
+}
+func (m *QueryJobRequest) XXX_DiscardUnknown() {
+	xxx_messageInfo_QueryJobRequest.DiscardUnknown(m)
+}
+
+var xxx_messageInfo_QueryJobRequest proto.InternalMessageInfo
+
+type QueryJobResponse struct {
+	JobId uint64 `protobuf:"varint,1,opt,name=job_id,json=jobId,proto3" json:"job_id,omitempty"`
+	// contract_address is the contract that enqueued the job
+	ContractAddress string `protobuf:"bytes,2,opt,name=contract_address,json=contractAddress,proto3" json:"contract_address,omitempty"`
+	// handler_code_hash is the code hash of the job's handler, in hex
+	HandlerCodeHash string `protobuf:"bytes,3,opt,name=handler_code_hash,json=handlerCodeHash,proto3" json:"handler_code_hash,omitempty"`
+	SealedInput     []byte `protobuf:"bytes,4,opt,name=sealed_input,json=sealedInput,proto3" json:"sealed_input,omitempty"`
+}
+
+func (m *QueryJobResponse) Reset()         { *m = QueryJobResponse{} }
+func (m *QueryJobResponse) String() string { return proto.CompactTextString(m) }
+func (*QueryJobResponse) ProtoMessage()    {}
+func (*QueryJobResponse) Descriptor() ([]byte, []int) {
+	return fileDescriptor_7735281c5fa969d4, []int{21}
+}
+func (m *QueryJobResponse) XXX_Unmarshal(b []byte) error {
+	return m.Unmarshal(b)
+}
+func (m *QueryJobResponse) XXX_Marshal(b []byte, deterministic bool) ([]byte, error) {
+	if deterministic {
+		return xxx_messageInfo_QueryJobResponse.Marshal(b, m, deterministic)
+	} else {
+		b = b[:cap(b)]
+		n, err := m.MarshalToSizedBuffer(b)
+		if err != nil {
+			return nil, err
+		}
+		return b[:n], nil
+	}
+}
+func (m *QueryJobResponse) XXX_Merge(src proto.Message) {
+	xxx_messageInfo_QueryJobResponse.Merge(m, src)
+}
+func (m *QueryJobResponse) XXX_Size() int {
+	return m.Size()
+}
+func (m *QueryJobResponse) XXX_DiscardUnknown() {
+	xxx_messageInfo_QueryJobResponse.DiscardUnknown(m)
+}
+
+var xxx_messageInfo_QueryJobResponse proto.InternalMessageInfo
+
+type QueryRunJobResponse struct {
+	// result is the json of the signed and attested result
+	Result []byte `protobuf:"bytes,1,opt,name=result,proto3" json:"result,omitempty"`
+}
+
+func (m *QueryRunJobResponse) Reset()         { *m = QueryRunJobResponse{} }
+func (m *QueryRunJobResponse) String() string { return proto.CompactTextString(m) }
+func (*QueryRunJobResponse) ProtoMessage()    {}
+func (*QueryRunJobResponse) Descriptor() ([]byte, []int) {
+	return fileDescriptor_7735281c5fa969d4, []int{22}
+}
+func (m *QueryRunJobResponse) XXX_Unmarshal(b []byte) error {
+	return m.Unmarshal(b)
+}
+func (m *QueryRunJobResponse) XXX_Marshal(b []byte, deterministic bool) ([]byte, error) {
+	if deterministic {
+		return xxx_messageInfo_QueryRunJobResponse.Marshal(b, m, deterministic)
+	} else {
+		b = b[:cap(b)]
+		n, err := m.MarshalToSizedBuffer(b)
+		if err != nil {
+			return nil, err
+		}
+		return b[:n], nil
+	}
+}
+func (m *QueryRunJobResponse) XXX_Merge(src proto.Message) {
+	xxx_messageInfo_QueryRunJobResponse.Merge(m, src)
+}
+func (m *QueryRunJobResponse) XXX_Size() int {
+	return m.Size()
+}
+func (m *QueryRunJobResponse) XXX_DiscardUnknown() {
+	xxx_messageInfo_QueryRunJobResponse.DiscardUnknown(m)
+}
+
+var xxx_messageInfo_QueryRunJobResponse proto.InternalMessageInfo
+
+type QueryOpenQuerySessionRequest struct {
+	// nonce and user_public_key are the ones the client would send with a query
+	Nonce         []byte `protobuf:"bytes,1,opt,name=nonce,proto3" json:"nonce,omitempty"`
+	UserPublicKey []byte `protobuf:"bytes,2,opt,name=user_public_key,json=userPublicKey,proto3" json:"user_public_key,omitempty"`
+	// expiry_height is the height the session expires at, at most 600 blocks
+	// after the node's latest height
+	ExpiryHeight uint64 `protobuf:"varint,3,opt,name=expiry_height,json=expiryHeight,proto3" json:"expiry_height,omitempty"`
+}
+
+func (m *QueryOpenQuerySessionRequest) Reset()         { *m = QueryOpenQuerySessionRequest{} }
+func (m *QueryOpenQuerySessionRequest) String() string { return proto.CompactTextString(m) }
+func (*QueryOpenQuerySessionRequest) ProtoMessage()    {}
+func (*QueryOpenQuerySessionRequest) Descriptor() ([]byte, []int) {
+	return fileDescriptor_7735281c5fa969d4, []int{23}
+}
+func (m *QueryOpenQuerySessionRequest) XXX_Unmarshal(b []byte) error {
+	return m.Unmarshal(b)
+}
+func (m *QueryOpenQuerySessionRequest) XXX_Marshal(b []byte, deterministic bool) ([]byte, error) {
+	if deterministic {
+		return xxx_messageInfo_QueryOpenQuerySessionRequest.Marshal(b, m, deterministic)
+	} else {
+		b = b[:cap(b)]
+		n, err := m.MarshalToSizedBuffer(b)
+		if err != nil {
+			return nil, err
+		}
+		return b[:n], nil
+	}
+}
+func (m *QueryOpenQuerySessionRequest) XXX_Merge(src proto.Message) {
+	xxx_messageInfo_QueryOpenQuerySessionRequest.Merge(m, src)
+}
+func (m *QueryOpenQuerySessionRequest) XXX_Size() int {
+	return m.Size()
+}
+func (m *QueryOpenQuerySessionRequest) XXX_DiscardUnknown() {
+	xxx_messageInfo_QueryOpenQuerySessionRequest.DiscardUnknown(m)
+}
+
+var xxx_messageInfo_QueryOpenQuerySessionRequest proto.InternalMessageInfo
+
+type QueryOpenQuerySessionResponse struct {
+	SessionId []byte `protobuf:"bytes,1,opt,name=session_id,json=sessionId,proto3" json:"session_id,omitempty"`
+}
+
+func (m *QueryOpenQuerySessionResponse) Reset()         { *m = QueryOpenQuerySessionResponse{} }
+func (m *QueryOpenQuerySessionResponse) String() string { return proto.CompactTextString(m) }
+func (*QueryOpenQuerySessionResponse) ProtoMessage()    {}
+func (*QueryOpenQuerySessionResponse) Descriptor() ([]byte, []int) {
+	return fileDescriptor_7735281c5fa969d4, []int{24}
+}
+func (m *QueryOpenQuerySessionResponse) XXX_Unmarshal(b []byte) error {
+	return m.Unmarshal(b)
+}
+func (m *QueryOpenQuerySessionResponse) XXX_Marshal(b []byte, deterministic bool) ([]byte, error) {
+	if deterministic {
+		return xxx_messageInfo_QueryOpenQuerySessionResponse.Marshal(b, m, deterministic)
+	} else {
+		b = b[:cap(b)]
+		n, err := m.MarshalToSizedBuffer(b)
+		if err != nil {
+			return nil, err
+		}
+		return b[:n], nil
+	}
+}
+func (m *QueryOpenQuerySessionResponse) XXX_Merge(src proto.Message) {
+	xxx_messageInfo_QueryOpenQuerySessionResponse.Merge(m, src)
+}
+func (m *QueryOpenQuerySessionResponse) XXX_Size() int {
+	return m.Size()
+}
+func (m *QueryOpenQuerySessionResponse) XXX_DiscardUnknown() {
+	xxx_messageInfo_QueryOpenQuerySessionResponse.DiscardUnknown(m)
+}
+
+var xxx_messageInfo_QueryOpenQuerySessionResponse proto.InternalMessageInfo
+
+type QueryExportContractStateRequest struct {
+	// contract_address is the contract to export
+	ContractAddress string `protobuf:"bytes,1,opt,name=contract_address,json=contractAddress,proto3" json:"contract_address,omitempty"`
+	// authorization is the admin's authorization to export the contract
+	Authorization StateBackupAuthorization `protobuf:"bytes,2,opt,name=authorization,proto3" json:"authorization"`
+}
+
+func (m *QueryExportContractStateRequest) Reset()         { *m = QueryExportContractStateRequest{} }
+func (m *QueryExportContractStateRequest) String() string { return proto.CompactTextString(m) }
+func (*QueryExportContractStateRequest) ProtoMessage()    {}
+func (*QueryExportContractStateRequest) Descriptor() ([]byte, []int) {
+	return fileDescriptor_7735281c5fa969d4, []int{25}
+}
+func (m *QueryExportContractStateRequest) XXX_Unmarshal(b []byte) error {
+	return m.Unmarshal(b)
+}
+func (m *QueryExportContractStateRequest) XXX_Marshal(b []byte, deterministic bool) ([]byte, error) {
+	if deterministic {
+		return xxx_messageInfo_QueryExportContractStateRequest.Marshal(b, m, deterministic)
+	} else {
+		b = b[:cap(b)]
+		n, err := m.MarshalToSizedBuffer(b)
+		if err != nil {
+			return nil, err
+		}
+		return b[:n], nil
+	}
+}
+func (m *QueryExportContractStateRequest) XXX_Merge(src proto.Message) {
+	xxx_messageInfo_QueryExportContractStateRequest.Merge(m, src)
+}
+func (m *QueryExportContractStateRequest) XXX_Size() int {
+	return m.Size()
+}
+func (m *QueryExportContractStateRequest) XXX_DiscardUnknown() {
+	xxx_messageInfo_QueryExportContractStateRequest.DiscardUnknown(m)
+}
+
+var xxx_messageInfo_QueryExportContractStateRequest proto.InternalMessageInfo
+
+type QueryExportContractStateResponse struct {
+	// chunks are the wrapped chunks of the backup, to import with a
+	// MsgImportContractState
+	Chunks [][]byte `protobuf:"bytes,1,rep,name=chunks,proto3" json:"chunks,omitempty"`
+}
+
+func (m *QueryExportContractStateResponse) Reset()         { *m = QueryExportContractStateResponse{} }
+func (m *QueryExportContractStateResponse) String() string { return proto.CompactTextString(m) }
+func (*QueryExportContractStateResponse) ProtoMessage()    {}
+func (*QueryExportContractStateResponse) Descriptor() ([]byte, []int) {
+	return fileDescriptor_7735281c5fa969d4, []int{26}
+}
+func (m *QueryExportContractStateResponse) XXX_Unmarshal(b []byte) error {
+	return m.Unmarshal(b)
+}
+func (m *QueryExportContractStateResponse) XXX_Marshal(b []byte, deterministic bool) ([]byte, error) {
+	if deterministic {
+		return xxx_messageInfo_QueryExportContractStateResponse.Marshal(b, m, deterministic)
+	} else {
+		b = b[:cap(b)]
+		n, err := m.MarshalToSizedBuffer(b)
+		if err != nil {
+			return nil, err
+		}
+		return b[:n], nil
+	}
+}
+func (m *QueryExportContractStateResponse) XXX_Merge(src proto.Message) {
+	xxx_messageInfo_QueryExportContractStateResponse.Merge(m, src)
+}
+func (m *QueryExportContractStateResponse) XXX_Size() int {
+	return m.Size()
+}
+func (m *QueryExportContractStateResponse) XXX_DiscardUnknown() {
+	xxx_messageInfo_QueryExportContractStateResponse.DiscardUnknown(m)
+}
+
+var xxx_messageInfo_QueryExportContractStateResponse proto.InternalMessageInfo
+
+type QueryMempoolKeyRequest struct {
+	Height uint64 `protobuf:"varint,1,opt,name=height,proto3" json:"height,omitempty"`
+}
+
+func (m *QueryMempoolKeyRequest) Reset()         { *m = QueryMempoolKeyRequest{} }
+func (m *QueryMempoolKeyRequest) String() string { return proto.CompactTextString(m) }
+func (*QueryMempoolKeyRequest) ProtoMessage()    {}
+func (*QueryMempoolKeyRequest) Descriptor() ([]byte, []int) {
+	return fileDescriptor_7735281c5fa969d4, []int{27}
+}
+func (m *QueryMempoolKeyRequest) XXX_Unmarshal(b []byte) error {
+	return m.Unmarshal(b)
+}
+func (m *QueryMempoolKeyRequest) XXX_Marshal(b []byte, deterministic bool) ([]byte, error) {
+	if deterministic {
+		return xxx_messageInfo_QueryMempoolKeyRequest.Marshal(b, m, deterministic)
+	} else {
+		b = b[:cap(b)]
+		n, err := m.MarshalToSizedBuffer(b)
+		if err != nil {
+			return nil, err
+		}
+		return b[:n], nil
+	}
+}
+func (m *QueryMempoolKeyRequest) XXX_Merge(src proto.Message) {
+	xxx_messageInfo_QueryMempoolKeyRequest.Merge(m, src)
+}
+func (m *QueryMempoolKeyRequest) XXX_Size() int {
+	return m.Size()
+}
+func (m *QueryMempoolKeyRequest) XXX_DiscardUnknown() {
+	xxx_messageInfo_QueryMempoolKeyRequest.DiscardUnknown(m)
+}
+
+var xxx_messageInfo_QueryMempoolKeyRequest proto.InternalMessageInfo
+
+type QueryMempoolKeyResponse struct {
+	// public_key is the x25519 public key of the height
+	PublicKey []byte `protobuf:"bytes,1,opt,name=public_key,json=publicKey,proto3" json:"public_key,omitempty"`
+}
+
+func (m *QueryMempoolKeyResponse) Reset()         { *m = QueryMempoolKeyResponse{} }
+func (m *QueryMempoolKeyResponse) String() string { return proto.CompactTextString(m) }
+func (*QueryMempoolKeyResponse) ProtoMessage()    {}
+func (*QueryMempoolKeyResponse) Descriptor() ([]byte, []int) {
+	return fileDescriptor_7735281c5fa969d4, []int{28}
+}
+func (m *QueryMempoolKeyResponse) XXX_Unmarshal(b []byte) error {
+	return m.Unmarshal(b)
+}
+func (m *QueryMempoolKeyResponse) XXX_Marshal(b []byte, deterministic bool) ([]byte, error) {
+	if deterministic {
+		return xxx_messageInfo_QueryMempoolKeyResponse.Marshal(b, m, deterministic)
+	} else {
+		b = b[:cap(b)]
+		n, err := m.MarshalToSizedBuffer(b)
+		if err != nil {
+			return nil, err
+		}
+		return b[:n], nil
+	}
+}
+func (m *QueryMempoolKeyResponse) XXX_Merge(src proto.Message) {
+	xxx_messageInfo_QueryMempoolKeyResponse.Merge(m, src)
+}
+func (m *QueryMempoolKeyResponse) XXX_Size() int {
+	return m.Size()
+}
+func (m *QueryMempoolKeyResponse) XXX_DiscardUnknown() {
+	xxx_messageInfo_QueryMempoolKeyResponse.DiscardUnknown(m)
+}
+
+var xxx_messageInfo_QueryMempoolKeyResponse proto.InternalMessageInfo
+
 func init() {
 	proto.RegisterType((*ParamsRequest)(nil), "secret.compute.v1beta1.ParamsRequest")
 	proto.RegisterType((*ParamsResponse)(nil), "secret.compute.v1beta1.ParamsResponse")
@@ -826,6 +1176,15 @@ func init() {
 	proto.RegisterType((*DecryptedAnswers)(nil), "secret.compute.v1beta1.DecryptedAnswers")
 	proto.RegisterType((*QueryContractHistoryRequest)(nil), "secret.compute.v1beta1.QueryContractHistoryRequest")
 	proto.RegisterType((*QueryContractHistoryResponse)(nil), "secret.compute.v1beta1.QueryContractHistoryResponse")
+	proto.RegisterType((*QueryJobRequest)(nil), "secret.compute.v1beta1.QueryJobRequest")
+	proto.RegisterType((*QueryJobResponse)(nil), "secret.compute.v1beta1.QueryJobResponse")
+	proto.RegisterType((*QueryRunJobResponse)(nil), "secret.compute.v1beta1.QueryRunJobResponse")
+	proto.RegisterType((*QueryOpenQuerySessionRequest)(nil), "secret.compute.v1beta1.QueryOpenQuerySessionRequest")
+	proto.RegisterType((*QueryOpenQuerySessionResponse)(nil), "secret.compute.v1beta1.QueryOpenQuerySessionResponse")
+	proto.RegisterType((*QueryExportContractStateRequest)(nil), "secret.compute.v1beta1.QueryExportContractStateRequest")
+	proto.RegisterType((*QueryExportContractStateResponse)(nil), "secret.compute.v1beta1.QueryExportContractStateResponse")
+	proto.RegisterType((*QueryMempoolKeyRequest)(nil), "secret.compute.v1beta1.QueryMempoolKeyRequest")
+	proto.RegisterType((*QueryMempoolKeyResponse)(nil), "secret.compute.v1beta1.QueryMempoolKeyResponse")
 }
 
 func init() {
@@ -833,89 +1192,117 @@ func init() {
 }
 
 var fileDescriptor_7735281c5fa969d4 = []byte{
-	// 1311 bytes of a gzipped FileDescriptorProto
-	0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0xc4, 0x58, 0xcd, 0x6f, 0x1b, 0x45,
-	0x14, 0xf7, 0xb6, 0x4e, 0xd2, 0xbc, 0xa4, 0x4e, 0x3b, 0xa4, 0xa9, 0xeb, 0x16, 0xbb, 0x5d, 0xfa,
-	0x91, 0x8f, 0xe2, 0xad, 0x9d, 0x50, 0xa4, 0xaa, 0x97, 0x24, 0x8d, 0xd4, 0xa0, 0x50, 0x8a, 0x83,
-	0x84, 0x84, 0x8a, 0xac, 0xf1, 0x7a, 0x62, 0xaf, 0xea, 0xec, 0xb8, 0x3b, 0xe3, 0x24, 0x16, 0x0a,
-	0x07, 0x4e, 0x1c, 0x91, 0xa0, 0x07, 0xc4, 0x85, 0x13, 0x54, 0x1c, 0x90, 0xb8, 0xf2, 0x17, 0xe4,
-	0xc0, 0x21, 0x12, 0x17, 0x4e, 0x15, 0x24, 0x1c, 0x10, 0x77, 0xee, 0x68, 0xe7, 0x63, 0xb3, 0xb6,
-	0xd7, 0x5f, 0xe5, 0xc0, 0xcd, 0x33, 0xf3, 0xde, 0xfb, 0xfd, 0xe6, 0xf7, 0x66, 0xde, 0x9b, 0x35,
-	0x98, 0x8c, 0xd8, 0x1e, 0xe1, 0x96, 0x4d, 0xb7, 0xeb, 0x0d, 0x4e, 0xac, 0x9d, 0x5c, 0x89, 0x70,
-	0x9c, 0xb3, 0x9e, 0x35, 0x88, 0xd7, 0xcc, 0xd6, 0x3d, 0xca, 0x29, 0x9a, 0x91, 0x36, 0x59, 0x65,
-	0x93, 0x55, 0x36, 0xa9, 0xe9, 0x0a, 0xad, 0x50, 0x61, 0x62, 0xf9, 0xbf, 0xa4, 0x75, 0xaa, 0x5b,
-	0x44, 0xde, 0xac, 0x13, 0xa6, 0x6c, 0xde, 0xe8, 0x62, 0x53, 0xc7, 0x1e, 0xde, 0xd6, 0x46, 0x97,
-	0x2b, 0x94, 0x56, 0x6a, 0xc4, 0x12, 0xa3, 0x52, 0x63, 0xcb, 0x22, 0xdb, 0x75, 0xae, 0x38, 0xa5,
-	0xae, 0xa8, 0x45, 0x5c, 0x77, 0x2c, 0xec, 0xba, 0x94, 0x63, 0xee, 0x50, 0x37, 0x88, 0x6f, 0x53,
-	0xb6, 0x4d, 0x99, 0x55, 0xc2, 0x8c, 0x58, 0xb8, 0x64, 0x3b, 0x01, 0x82, 0x3f, 0x50, 0x46, 0xf3,
-	0x61, 0x23, 0xb1, 0xdf, 0x10, 0x8f, 0x8a, 0xe3, 0x8a, 0x88, 0xd2, 0xd6, 0x9c, 0x82, 0xb3, 0x8f,
-	0x05, 0xb7, 0x02, 0x79, 0xd6, 0x20, 0x8c, 0x9b, 0x1f, 0x40, 0x42, 0x4f, 0xb0, 0x3a, 0x75, 0x19,
-	0x41, 0xf7, 0x61, 0x54, 0xd2, 0x4f, 0x1a, 0x57, 0x8d, 0xd9, 0x89, 0x7c, 0x3a, 0x1b, 0x2d, 0x5b,
-	0x56, 0xfa, 0xad, 0xc4, 0x0f, 0x5e, 0x66, 0x62, 0x05, 0xe5, 0x73, 0x2f, 0xfe, 0xd7, 0xb7, 0x99,
-	0x98, 0xf9, 0x31, 0xa4, 0xde, 0xf7, 0x89, 0x6c, 0x0a, 0xcf, 0x55, 0xea, 0x72, 0x0f, 0xdb, 0x5c,
-	0x61, 0xa2, 0x39, 0x38, 0x67, 0xab, 0xa9, 0x22, 0x2e, 0x97, 0x3d, 0xc2, 0x24, 0xd6, 0x78, 0x61,
-	0x4a, 0xcf, 0x2f, 0xcb, 0x69, 0x34, 0x0d, 0x23, 0x62, 0x47, 0xc9, 0x53, 0x57, 0x8d, 0xd9, 0xc9,
-	0x82, 0x1c, 0x98, 0x0b, 0xf0, 0x9a, 0x08, 0xbf, 0xd2, 0xdc, 0xc0, 0x25, 0x52, 0xd3, 0x71, 0xa7,
-	0x61, 0xa4, 0xe6, 0x8f, 0x55, 0x30, 0x39, 0x30, 0xdf, 0x81, 0xd7, 0x95, 0xf1, 0x6a, 0x6b, 0xf0,
-	0xe1, 0xe9, 0x98, 0x16, 0x4c, 0x07, 0xb1, 0xca, 0x64, 0xbd, 0xac, 0x43, 0x5c, 0x84, 0x31, 0x9b,
-	0x96, 0x49, 0xd1, 0x29, 0x0b, 0xcf, 0x78, 0x61, 0xd4, 0x16, 0xeb, 0x66, 0x0e, 0x2e, 0x47, 0x0a,
-	0xa1, 0xb4, 0x46, 0x10, 0x2f, 0x63, 0x8e, 0x85, 0xd3, 0x64, 0x41, 0xfc, 0x36, 0xbf, 0x31, 0xe0,
-	0x92, 0xf0, 0xd1, 0xd6, 0xeb, 0xee, 0x16, 0x0d, 0x3c, 0x86, 0xd0, 0x6e, 0x13, 0xce, 0x06, 0xa6,
-	0x8e, 0xbb, 0x45, 0x85, 0x86, 0x13, 0xf9, 0xeb, 0xdd, 0xf2, 0x19, 0xc6, 0x5b, 0x39, 0x73, 0xf8,
-	0x32, 0x63, 0xfc, 0xed, 0x67, 0x76, 0xd2, 0x0e, 0xcd, 0x9b, 0x5f, 0x1b, 0x70, 0x31, 0x6c, 0xf8,
-	0xa1, 0xc3, 0xab, 0x1a, 0xf0, 0xff, 0xe6, 0xf6, 0x29, 0xa4, 0x5b, 0x84, 0x63, 0x27, 0x69, 0x52,
-	0xea, 0x3d, 0x81, 0x44, 0x0b, 0xac, 0xcf, 0xef, 0xf4, 0xec, 0x44, 0xde, 0x1a, 0x04, 0x37, 0xb4,
-	0x55, 0x75, 0xe8, 0xcf, 0x86, 0xe1, 0x99, 0xf9, 0x95, 0x01, 0xe7, 0x04, 0x60, 0x38, 0x61, 0xdd,
-	0x8e, 0x06, 0x4a, 0xc2, 0x98, 0xed, 0x11, 0xcc, 0xa9, 0x27, 0x36, 0x3f, 0x5e, 0xd0, 0x43, 0x74,
-	0x19, 0xc6, 0x85, 0x4b, 0x15, 0xb3, 0x6a, 0xf2, 0xb4, 0x58, 0x3b, 0xe3, 0x4f, 0x3c, 0xc4, 0xac,
-	0x8a, 0x66, 0x60, 0x94, 0xd1, 0x86, 0x67, 0x93, 0x64, 0x5c, 0xac, 0xa8, 0x91, 0x1f, 0xae, 0xd4,
-	0x70, 0x6a, 0x65, 0xe2, 0x25, 0x47, 0x64, 0x38, 0x35, 0x34, 0xf7, 0xe0, 0xbc, 0x92, 0xa5, 0x4c,
-	0x02, 0x5a, 0xef, 0x29, 0x0c, 0x21, 0xbe, 0xbc, 0xe8, 0xb3, 0xdd, 0x45, 0x68, 0xdd, 0x53, 0x28,
-	0x01, 0x82, 0x97, 0xbf, 0xe6, 0x1f, 0xe5, 0x5d, 0xcc, 0xb6, 0xd5, 0x45, 0x15, 0xbf, 0x4d, 0x1b,
-	0x50, 0x80, 0x7c, 0x52, 0x60, 0xde, 0x05, 0x08, 0xa0, 0x75, 0x02, 0x06, 0xc7, 0x96, 0xca, 0x8f,
-	0x6b, 0x5c, 0x66, 0xae, 0xc3, 0x95, 0x96, 0xac, 0x07, 0xb7, 0x7b, 0xe8, 0x1b, 0x63, 0xe6, 0x55,
-	0xd9, 0xd2, 0xa1, 0x54, 0x75, 0x51, 0x81, 0xa2, 0xcb, 0xcb, 0x12, 0x5c, 0x08, 0xf6, 0xe8, 0x27,
-	0x28, 0x30, 0x6f, 0xc9, 0xa2, 0xd1, 0x9a, 0x45, 0xf3, 0xb9, 0x01, 0x53, 0x0f, 0x88, 0xed, 0x35,
-	0xeb, 0x9c, 0x94, 0x97, 0x5d, 0xb6, 0x4b, 0x3c, 0x5f, 0x41, 0xbf, 0xb7, 0x28, 0x5b, 0xf1, 0xdb,
-	0xc7, 0x74, 0xdc, 0x7a, 0x83, 0xab, 0x23, 0x22, 0x07, 0x28, 0x03, 0x13, 0xb4, 0xc1, 0xeb, 0x0d,
-	0x5e, 0x14, 0xd5, 0x43, 0x1e, 0x11, 0x90, 0x53, 0x0f, 0x30, 0xc7, 0x28, 0x07, 0x17, 0x42, 0x06,
-	0x45, 0xcc, 0x8a, 0x8c, 0x7b, 0x8e, 0x5b, 0x51, 0x67, 0x06, 0x9d, 0x98, 0x2e, 0xb3, 0x4d, 0xb1,
-	0xa2, 0x0a, 0xf7, 0x3f, 0x06, 0x9c, 0x6b, 0xe3, 0xc5, 0xd0, 0x32, 0x8c, 0x61, 0xf9, 0x53, 0x65,
-	0xeb, 0x56, 0xb7, 0x6c, 0xb5, 0xb9, 0x16, 0xb4, 0x1f, 0xda, 0x08, 0x18, 0xd7, 0x68, 0x85, 0x25,
-	0x4f, 0x89, 0x30, 0x37, 0xb2, 0xb2, 0x73, 0x65, 0xfd, 0xce, 0x95, 0x15, 0x1d, 0x4d, 0x07, 0x92,
-	0xa4, 0xd6, 0x76, 0x88, 0xcb, 0x55, 0xc6, 0xd5, 0xf6, 0x36, 0x68, 0x85, 0xa1, 0x6b, 0x30, 0xa9,
-	0xa2, 0x11, 0xcf, 0xa3, 0x9e, 0x12, 0x40, 0x21, 0xac, 0xf9, 0x53, 0xe8, 0x16, 0x4c, 0xd5, 0x6b,
-	0xd8, 0x71, 0x39, 0xd9, 0xd3, 0x56, 0x72, 0xef, 0x89, 0x60, 0x5a, 0x18, 0xaa, 0x7d, 0x3f, 0x52,
-	0x75, 0x5a, 0x67, 0xfe, 0xa1, 0xc3, 0x38, 0xf5, 0x9a, 0xc3, 0xb7, 0x08, 0x15, 0x6f, 0xa7, 0xed,
-	0x50, 0x06, 0xf1, 0xd4, 0xe1, 0x78, 0x0c, 0x63, 0xc4, 0xe5, 0x9e, 0x43, 0xb4, 0xa4, 0x77, 0xfa,
-	0x55, 0x20, 0x71, 0xbe, 0x64, 0x94, 0x35, 0x97, 0x7b, 0x4d, 0x25, 0x8b, 0x0e, 0x23, 0x71, 0xf3,
-	0xcf, 0x13, 0x30, 0x22, 0x80, 0xd1, 0x0f, 0x06, 0x4c, 0x86, 0xab, 0x17, 0x7a, 0xab, 0x1b, 0x42,
-	0xcf, 0xee, 0x98, 0xca, 0xf5, 0x74, 0x8b, 0xea, 0x51, 0xe6, 0x9d, 0xcf, 0x7e, 0xfd, 0xf3, 0xcb,
-	0x53, 0xf3, 0x68, 0xb6, 0xe3, 0x5d, 0xe4, 0x5f, 0x79, 0xeb, 0x93, 0x76, 0x29, 0xf7, 0xd1, 0xf7,
-	0x06, 0x9c, 0xef, 0xa8, 0xda, 0xe8, 0x76, 0x5f, 0xc6, 0xa1, 0x1e, 0x9c, 0xba, 0x3b, 0x10, 0xd1,
-	0x8e, 0x9e, 0x60, 0xde, 0x16, 0x6c, 0x6f, 0xa2, 0xeb, 0x1d, 0x6c, 0x35, 0x4f, 0xe6, 0x53, 0x16,
-	0x25, 0x7c, 0x1f, 0xfd, 0x64, 0xa8, 0xb7, 0x47, 0x6b, 0x47, 0x47, 0xf9, 0x9e, 0xe8, 0x91, 0xef,
-	0xa0, 0xd4, 0xe2, 0x50, 0x3e, 0x8a, 0x6e, 0x4e, 0xd0, 0x5d, 0x40, 0x73, 0xd1, 0x4f, 0xdd, 0x28,
-	0x75, 0x3f, 0x37, 0x20, 0xee, 0x6f, 0x7a, 0x48, 0x41, 0xe7, 0xfa, 0x08, 0x7a, 0xd2, 0x4d, 0xcc,
-	0x5b, 0x82, 0xd4, 0x35, 0x94, 0x89, 0xd0, 0xb0, 0x4c, 0x42, 0xf2, 0x3d, 0x85, 0x11, 0xd1, 0x0c,
-	0xd0, 0x4c, 0x56, 0x3e, 0x7c, 0xb3, 0xfa, 0x55, 0x9c, 0x5d, 0xf3, 0x5f, 0xc5, 0xa9, 0xf9, 0xbe,
-	0xa0, 0x41, 0x65, 0x37, 0xd3, 0x02, 0x35, 0x89, 0x66, 0x22, 0x51, 0x19, 0xfa, 0xc5, 0x80, 0x4b,
-	0xba, 0x2c, 0x77, 0x9c, 0xef, 0x57, 0xbd, 0x0f, 0x6f, 0xf6, 0x25, 0x18, 0xee, 0x02, 0xe6, 0xba,
-	0xe0, 0xb8, 0x8a, 0x96, 0x23, 0x39, 0x8a, 0xe6, 0x60, 0x95, 0x9a, 0xc5, 0xf6, 0xa4, 0x45, 0xa5,
-	0xf1, 0x85, 0x7a, 0x5e, 0xe8, 0xed, 0xbc, 0xc2, 0x1d, 0x19, 0x92, 0xfc, 0xdb, 0x82, 0x7c, 0x0e,
-	0x59, 0xfd, 0xc8, 0x8b, 0xec, 0x86, 0xd2, 0xfc, 0xa3, 0x01, 0x09, 0xd1, 0x3c, 0x57, 0x9a, 0xff,
-	0x51, 0xee, 0xfc, 0x40, 0xb7, 0xba, 0xa5, 0x51, 0xf7, 0xb8, 0x22, 0xa2, 0x65, 0x47, 0x69, 0xfb,
-	0x9d, 0x01, 0x09, 0xfd, 0xb6, 0x93, 0x1f, 0x15, 0x68, 0xa1, 0x0f, 0xe1, 0xf0, 0xa7, 0x47, 0x6a,
-	0x69, 0x20, 0x9a, 0x6d, 0x4f, 0x93, 0x1e, 0x44, 0x3b, 0xcf, 0x83, 0xa0, 0xbe, 0x8f, 0x7e, 0x36,
-	0x60, 0xaa, 0xad, 0xa9, 0xa0, 0xc5, 0x81, 0xc0, 0x5b, 0x5b, 0xda, 0x80, 0x8c, 0xdb, 0xfa, 0x96,
-	0x79, 0x5f, 0x30, 0xbe, 0x8b, 0x96, 0xba, 0x33, 0xae, 0x4a, 0x97, 0x28, 0x95, 0xf7, 0x60, 0x54,
-	0x7e, 0x34, 0xa2, 0x1b, 0xbd, 0x3f, 0x2a, 0x35, 0xc9, 0x9b, 0xfd, 0xcc, 0x14, 0xad, 0x8c, 0xa0,
-	0x75, 0x09, 0x5d, 0xec, 0xf2, 0x25, 0xbe, 0xf2, 0xe4, 0xe0, 0x8f, 0x74, 0xec, 0xc5, 0x51, 0xda,
-	0x38, 0x38, 0x4a, 0x1b, 0x87, 0x47, 0x69, 0xe3, 0xf7, 0xa3, 0xb4, 0xf1, 0xc5, 0x71, 0x3a, 0x76,
-	0x78, 0x9c, 0x8e, 0xfd, 0x76, 0x9c, 0x8e, 0x7d, 0x74, 0xaf, 0xe2, 0xf0, 0x6a, 0xa3, 0xe4, 0x23,
-	0x59, 0xcc, 0xf6, 0x78, 0x0d, 0x97, 0x98, 0x25, 0x6b, 0xf0, 0x23, 0xc2, 0x77, 0xa9, 0xf7, 0xd4,
-	0xda, 0x0b, 0xa2, 0xfb, 0x0f, 0x08, 0xcf, 0xc5, 0x35, 0xf9, 0x67, 0x40, 0x69, 0x54, 0x14, 0xb1,
-	0xc5, 0x7f, 0x03, 0x00, 0x00, 0xff, 0xff, 0x0e, 0xbf, 0x66, 0x60, 0x85, 0x10, 0x00, 0x00,
+	// 1749 bytes of a gzipped FileDescriptorProto
+	0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0xc5, 0x58, 0x49, 0x6f, 0x1c, 0x45,
+	0x14, 0x76, 0x27, 0x63, 0x27, 0x2e, 0x6f, 0x49, 0x91, 0xd8, 0xce, 0x24, 0xf1, 0x24, 0x9d, 0x78,
+	0x4d, 0x32, 0x1d, 0x3b, 0x2b, 0x21, 0x42, 0xb2, 0x13, 0x4b, 0x71, 0xc8, 0xc6, 0x18, 0x09, 0x09,
+	0x05, 0x8d, 0x7a, 0x7a, 0x2a, 0x33, 0x8d, 0xc7, 0xdd, 0x4d, 0x2f, 0x89, 0x87, 0xc8, 0x20, 0x38,
+	0xc1, 0x0d, 0x09, 0x38, 0x20, 0x38, 0x20, 0x21, 0x41, 0xc4, 0x01, 0x89, 0x23, 0xfc, 0x82, 0x48,
+	0x70, 0x88, 0xc4, 0x85, 0x53, 0xc4, 0x76, 0x40, 0xdc, 0xb9, 0x53, 0xfd, 0xea, 0x55, 0x4f, 0xcf,
+	0x4c, 0xcf, 0x16, 0x0e, 0x1c, 0x5a, 0xea, 0x7a, 0xfd, 0x96, 0xaf, 0xde, 0xab, 0xaa, 0xf7, 0x55,
+	0x13, 0xd5, 0x63, 0x86, 0xcb, 0x7c, 0xcd, 0xb0, 0x37, 0x9d, 0xc0, 0x67, 0xda, 0xfd, 0xc5, 0x02,
+	0xf3, 0xf5, 0x45, 0xed, 0xcd, 0x80, 0xb9, 0xd5, 0xac, 0xe3, 0xda, 0xbe, 0x4d, 0xc7, 0x85, 0x4e,
+	0x16, 0x75, 0xb2, 0xa8, 0x93, 0xde, 0x57, 0xb2, 0x4b, 0x36, 0xa8, 0x68, 0xe1, 0x9b, 0xd0, 0x4e,
+	0xb7, 0xf2, 0xe8, 0x57, 0x1d, 0xe6, 0xa1, 0xce, 0xb1, 0x16, 0x3a, 0x8e, 0xee, 0xea, 0x9b, 0x52,
+	0xe9, 0x60, 0xc9, 0xb6, 0x4b, 0x15, 0xa6, 0xc1, 0xa8, 0x10, 0xdc, 0xd3, 0xd8, 0xa6, 0xe3, 0x23,
+	0xa6, 0xf4, 0x21, 0xfc, 0xa8, 0x3b, 0xa6, 0xa6, 0x5b, 0x96, 0xed, 0xeb, 0xbe, 0x69, 0x5b, 0x91,
+	0x7f, 0xc3, 0xf6, 0x36, 0x6d, 0x4f, 0x2b, 0xe8, 0x1e, 0x57, 0x29, 0x18, 0x66, 0x14, 0x21, 0x1c,
+	0xa0, 0xd2, 0x42, 0x5c, 0x09, 0xe6, 0x1b, 0xc3, 0x51, 0x32, 0x2d, 0xf0, 0x28, 0x74, 0xd5, 0x31,
+	0x32, 0x72, 0x07, 0xb0, 0xe5, 0x18, 0xd7, 0xf4, 0x7c, 0xf5, 0x15, 0x32, 0x2a, 0x05, 0x9e, 0xc3,
+	0x03, 0x33, 0x7a, 0x99, 0x0c, 0x08, 0xf8, 0x93, 0xca, 0x11, 0x65, 0x6e, 0x68, 0x69, 0x2a, 0x9b,
+	0x9c, 0xb6, 0xac, 0xb0, 0x5b, 0x49, 0x3d, 0x7e, 0x9a, 0xe9, 0xcb, 0xa1, 0xcd, 0xa5, 0xd4, 0x5f,
+	0x5f, 0x64, 0xfa, 0xd4, 0xd7, 0x49, 0xfa, 0xe5, 0x10, 0xc8, 0x3a, 0x58, 0x5e, 0xb1, 0x2d, 0xdf,
+	0xd5, 0x0d, 0x1f, 0x63, 0xd2, 0x79, 0xb2, 0xc7, 0x40, 0x51, 0x5e, 0x2f, 0x16, 0x5d, 0xe6, 0x89,
+	0x58, 0x83, 0xb9, 0x31, 0x29, 0x5f, 0x16, 0x62, 0xba, 0x8f, 0xf4, 0xc3, 0x8c, 0x26, 0x77, 0xf0,
+	0xef, 0xc3, 0x39, 0x31, 0x50, 0x4f, 0x90, 0xe7, 0xc0, 0xfd, 0x4a, 0xf5, 0x86, 0x5e, 0x60, 0x15,
+	0xe9, 0x97, 0x2b, 0x57, 0xc2, 0x31, 0x3a, 0x13, 0x03, 0xf5, 0x3a, 0x39, 0x8c, 0xca, 0x57, 0xea,
+	0x9d, 0xf7, 0x0e, 0x47, 0xd5, 0xc8, 0xbe, 0xc8, 0x57, 0x91, 0xad, 0x15, 0xa5, 0x8b, 0x09, 0xb2,
+	0xcb, 0xe0, 0x82, 0xbc, 0x59, 0x04, 0xcb, 0x54, 0x6e, 0xc0, 0x80, 0xef, 0xea, 0x22, 0x39, 0x98,
+	0x98, 0x08, 0xcc, 0x35, 0x25, 0xa9, 0xa2, 0xee, 0xeb, 0x60, 0x34, 0x9c, 0x83, 0x77, 0xf5, 0x33,
+	0x85, 0x1c, 0x00, 0x1b, 0xa9, 0xbd, 0x66, 0xdd, 0xb3, 0x23, 0x8b, 0x1e, 0x72, 0xb7, 0x4e, 0x46,
+	0x22, 0x55, 0x93, 0xfb, 0x80, 0x1c, 0x0e, 0x2d, 0x1d, 0x6f, 0x55, 0xcf, 0x78, 0xbc, 0x95, 0xdd,
+	0x4f, 0x9e, 0x66, 0x94, 0xbf, 0xc3, 0xca, 0x0e, 0x1b, 0x31, 0xb9, 0xfa, 0xa9, 0x42, 0x26, 0xe2,
+	0x8a, 0xaf, 0x9a, 0x7e, 0x59, 0x06, 0xfc, 0xbf, 0xb1, 0xbd, 0x4d, 0xa6, 0xea, 0x12, 0xe7, 0xd5,
+	0xca, 0x84, 0xd9, 0xbb, 0x4b, 0x46, 0xeb, 0xc2, 0x86, 0xf8, 0x76, 0xf2, 0xb8, 0x5a, 0x37, 0x71,
+	0x63, 0x53, 0xc5, 0x45, 0x3f, 0x12, 0x0f, 0xef, 0xa9, 0x1f, 0x2b, 0x64, 0x0f, 0x04, 0x8c, 0x17,
+	0xac, 0xd5, 0xd2, 0xa0, 0x93, 0xfc, 0x83, 0xcb, 0x74, 0xdf, 0x76, 0x61, 0xf2, 0x83, 0x39, 0x39,
+	0xa4, 0x07, 0xc9, 0x20, 0x98, 0x94, 0x75, 0xaf, 0x3c, 0xb9, 0x13, 0xbe, 0xed, 0x0e, 0x05, 0xd7,
+	0xf8, 0x98, 0x8e, 0x93, 0x01, 0xcf, 0x0e, 0x5c, 0x83, 0x4d, 0xa6, 0xe0, 0x0b, 0x8e, 0x42, 0x77,
+	0x85, 0xc0, 0xac, 0x14, 0x99, 0x3b, 0xd9, 0x2f, 0xdc, 0xe1, 0x50, 0xdd, 0x22, 0x7b, 0x31, 0x2d,
+	0x45, 0x16, 0xc1, 0xba, 0x8d, 0x31, 0x20, 0xf9, 0x62, 0xa3, 0xcf, 0xb5, 0x4e, 0x42, 0xfd, 0x9c,
+	0x62, 0x05, 0x00, 0x5c, 0xe1, 0xb7, 0x70, 0x29, 0x3f, 0xd0, 0xbd, 0x4d, 0xdc, 0xa8, 0xf0, 0xae,
+	0x1a, 0x84, 0x46, 0x91, 0x6b, 0x07, 0xcc, 0x4d, 0x42, 0xa2, 0xd0, 0xb2, 0x00, 0xdd, 0xc7, 0x16,
+	0x99, 0x1f, 0x94, 0x71, 0x3d, 0x75, 0x8d, 0x1c, 0xaa, 0xab, 0x7a, 0xb4, 0xbb, 0x7b, 0xde, 0x31,
+	0xea, 0x12, 0x1e, 0x5b, 0xd2, 0x15, 0x9e, 0x2e, 0xe8, 0x28, 0xf9, 0x78, 0x39, 0x4b, 0xf6, 0x47,
+	0x73, 0x0c, 0x0b, 0x14, 0xa9, 0xd7, 0x55, 0x51, 0xa9, 0xaf, 0xa2, 0xfa, 0x89, 0x42, 0xc6, 0xae,
+	0xf2, 0x19, 0x57, 0x1d, 0x9f, 0x15, 0x97, 0x2d, 0xef, 0x01, 0x73, 0xc3, 0x0c, 0x86, 0xbd, 0x05,
+	0x75, 0xe1, 0x3d, 0x8c, 0x69, 0x5a, 0x3c, 0x21, 0xb8, 0x44, 0xc4, 0x80, 0x66, 0xc8, 0x90, 0x1d,
+	0xf8, 0xfc, 0x2d, 0x0f, 0xa7, 0x87, 0x58, 0x22, 0x44, 0x88, 0xae, 0x72, 0x09, 0x5d, 0x24, 0xfb,
+	0x63, 0x0a, 0x79, 0xdd, 0xcb, 0x7b, 0xbe, 0x6b, 0x5a, 0x25, 0x5c, 0x33, 0xb4, 0xa6, 0xba, 0xec,
+	0xad, 0xc3, 0x17, 0x3c, 0xb8, 0xff, 0xe1, 0x4b, 0xb8, 0x01, 0x97, 0x47, 0x97, 0xc9, 0x2e, 0x5d,
+	0xbc, 0x62, 0xb5, 0x66, 0x5b, 0x55, 0xab, 0xc1, 0x34, 0x27, 0xed, 0xe8, 0x8d, 0x08, 0x71, 0xc5,
+	0x2e, 0x79, 0x7c, 0x36, 0xa1, 0x9b, 0xe9, 0xac, 0xe8, 0x5c, 0xd9, 0xb0, 0x73, 0x65, 0xa1, 0xa3,
+	0x49, 0x47, 0x02, 0xd4, 0xea, 0x7d, 0x66, 0xf9, 0x58, 0x71, 0x9c, 0xde, 0x0d, 0x6e, 0x4e, 0x8f,
+	0x92, 0x61, 0xf4, 0xc6, 0x5c, 0x97, 0xef, 0x1f, 0x91, 0x00, 0x8c, 0xb0, 0x1a, 0x8a, 0xe8, 0x2c,
+	0x19, 0x73, 0x2a, 0xba, 0x69, 0xf9, 0x6c, 0x4b, 0x6a, 0x89, 0xb9, 0x8f, 0x46, 0x62, 0x50, 0xc4,
+	0x79, 0xdf, 0xc2, 0x73, 0x5a, 0x56, 0xfe, 0x9a, 0xe9, 0xf1, 0x9d, 0x58, 0xed, 0xbd, 0x45, 0xa0,
+	0xbf, 0xfb, 0x0d, 0x8b, 0x32, 0xf2, 0x87, 0x8b, 0xe3, 0x0e, 0xd9, 0xc5, 0xa7, 0xe6, 0x9a, 0x4c,
+	0xa6, 0xf4, 0x74, 0xa7, 0x13, 0x08, 0xd6, 0x97, 0xf0, 0xb2, 0xca, 0x25, 0x55, 0x4c, 0x8b, 0x74,
+	0x83, 0x71, 0xe7, 0xc8, 0x18, 0xc4, 0xbd, 0x6e, 0x17, 0x24, 0xf6, 0xfd, 0x64, 0xe0, 0x0d, 0xbb,
+	0x50, 0x3b, 0x7f, 0xfa, 0xf9, 0x88, 0x77, 0xa6, 0x2f, 0x79, 0xa5, 0x6b, 0xaa, 0x08, 0x2b, 0x59,
+	0x37, 0x71, 0xfa, 0x3b, 0x92, 0x0f, 0xf6, 0x05, 0xb2, 0xb7, 0xac, 0x5b, 0xc5, 0x0a, 0x73, 0xf3,
+	0x8d, 0x67, 0xd8, 0x18, 0x7e, 0x90, 0x3b, 0x25, 0x2c, 0xa3, 0xc7, 0xf4, 0x0a, 0x2b, 0xe6, 0xc5,
+	0x1a, 0x4f, 0xc1, 0xd1, 0x31, 0x24, 0x64, 0x6b, 0xa1, 0x48, 0x3d, 0x85, 0x9d, 0x3e, 0x17, 0x58,
+	0x71, 0x9c, 0xfc, 0x10, 0xe4, 0xd1, 0x82, 0x8a, 0x8f, 0x9d, 0x13, 0x47, 0xea, 0xbb, 0x0a, 0xe6,
+	0xfd, 0xb6, 0xc3, 0x2c, 0x6c, 0xbc, 0x9e, 0xc7, 0xe9, 0x4f, 0x8c, 0x22, 0x58, 0xb6, 0x65, 0x30,
+	0xb4, 0x13, 0x03, 0x3a, 0x43, 0xc6, 0x02, 0x8f, 0x23, 0x76, 0x82, 0x42, 0xc5, 0x34, 0xf2, 0x1b,
+	0x4c, 0xf2, 0x8d, 0x91, 0x50, 0x7c, 0x07, 0xa4, 0x2f, 0xb1, 0x2a, 0x3d, 0x46, 0x46, 0xd8, 0x96,
+	0x63, 0xba, 0xd5, 0x7c, 0x99, 0x99, 0xa5, 0xb2, 0x0f, 0x13, 0x4b, 0xe5, 0x86, 0x85, 0xf0, 0x1a,
+	0xc8, 0xd4, 0x17, 0x91, 0x6f, 0x34, 0x43, 0x40, 0xf0, 0x87, 0x09, 0xf1, 0x84, 0x48, 0x26, 0x7a,
+	0x38, 0x37, 0x88, 0x12, 0x5e, 0x98, 0x6f, 0x14, 0x92, 0x01, 0xbb, 0xd5, 0x2d, 0xc7, 0x76, 0x23,
+	0xce, 0xb0, 0xce, 0x99, 0x21, 0x7b, 0x06, 0x06, 0x75, 0x97, 0x8c, 0xe8, 0x81, 0x5f, 0xb6, 0x5d,
+	0xf3, 0x2d, 0x20, 0x82, 0xd8, 0x69, 0x5b, 0xae, 0x37, 0x88, 0xb3, 0xa2, 0x1b, 0x1b, 0x81, 0xb3,
+	0x1c, 0xb7, 0x93, 0x2d, 0xaf, 0xce, 0x99, 0x7a, 0x89, 0x1c, 0x69, 0x8d, 0xb5, 0x56, 0x2c, 0xa3,
+	0x1c, 0x58, 0x1b, 0x62, 0xa9, 0xf3, 0x62, 0x89, 0x91, 0x7a, 0x9a, 0x8c, 0x83, 0xed, 0x4d, 0xce,
+	0x87, 0x6d, 0xbb, 0xc2, 0x13, 0x2c, 0xa7, 0xc7, 0x2d, 0x30, 0xc1, 0xd8, 0x32, 0xc5, 0x48, 0xbd,
+	0x48, 0x26, 0x9a, 0x2c, 0x6a, 0x49, 0x8d, 0x55, 0x0f, 0x93, 0xea, 0xc8, 0xca, 0x2d, 0x7d, 0xcf,
+	0x0b, 0x0f, 0xa6, 0x94, 0xa7, 0x77, 0x38, 0xde, 0xd5, 0xe9, 0xb9, 0x56, 0x99, 0x68, 0xcb, 0x1a,
+	0xd3, 0x8b, 0x6d, 0xcd, 0x92, 0xb8, 0x9b, 0x7a, 0xfa, 0xbd, 0x9f, 0xff, 0xfc, 0x68, 0xc7, 0x02,
+	0x9d, 0x6b, 0xba, 0x2f, 0x84, 0xad, 0x50, 0x7b, 0xd8, 0x58, 0xd2, 0x6d, 0xfa, 0xb5, 0x42, 0xf6,
+	0x36, 0xb1, 0x19, 0x7a, 0xb2, 0x23, 0xe2, 0x18, 0x37, 0x4d, 0x9f, 0xef, 0x0a, 0x68, 0x13, 0x57,
+	0x52, 0x4f, 0x02, 0xda, 0x19, 0x7a, 0xbc, 0x09, 0xad, 0xc4, 0xe9, 0x85, 0x90, 0x81, 0xda, 0x6c,
+	0xd3, 0xef, 0x14, 0xdc, 0xa9, 0xf5, 0x4c, 0x97, 0x2e, 0xb5, 0x8d, 0x9e, 0x78, 0x3f, 0x48, 0x9f,
+	0xe9, 0xc9, 0x06, 0xe1, 0x2e, 0x02, 0xdc, 0x13, 0x74, 0x3e, 0xf9, 0x0a, 0x98, 0x94, 0xdd, 0xf7,
+	0x15, 0x92, 0x0a, 0x27, 0xdd, 0x63, 0x42, 0xe7, 0x3b, 0x24, 0xb4, 0xc6, 0xb2, 0xd4, 0x59, 0x00,
+	0x75, 0x94, 0x66, 0x12, 0x72, 0x58, 0x64, 0xb1, 0xf4, 0x6d, 0x90, 0x7e, 0x20, 0x49, 0x74, 0x3c,
+	0x2b, 0x2e, 0x84, 0x59, 0x79, 0x5b, 0xcc, 0xae, 0x86, 0xb7, 0xc5, 0xf4, 0x42, 0xc7, 0xa0, 0x11,
+	0xe3, 0x51, 0xa7, 0x20, 0xea, 0x24, 0x1d, 0x4f, 0x8c, 0xea, 0xd1, 0x9f, 0xf8, 0x0d, 0x43, 0x1e,
+	0xc2, 0x4d, 0xeb, 0xfb, 0x59, 0xf7, 0xc3, 0xa9, 0x8e, 0x00, 0xe3, 0xec, 0x48, 0x5d, 0x03, 0x8c,
+	0x57, 0xe8, 0x72, 0x22, 0x46, 0x68, 0x1b, 0x5a, 0xa1, 0x9a, 0x6f, 0x2c, 0x5a, 0x52, 0x19, 0x1f,
+	0x21, 0xed, 0x96, 0xd3, 0x79, 0x86, 0x3d, 0xd2, 0x23, 0xf8, 0x0b, 0x00, 0x7e, 0x91, 0x6a, 0x9d,
+	0xc0, 0x43, 0x75, 0x63, 0x65, 0xfe, 0x56, 0x21, 0xa3, 0x40, 0x2a, 0x57, 0xaa, 0xff, 0x31, 0xdd,
+	0x4b, 0x5d, 0xed, 0xea, 0x3a, 0x02, 0xdb, 0x66, 0x8b, 0x00, 0x95, 0x4d, 0xca, 0xed, 0x57, 0x1c,
+	0xb0, 0xbc, 0xf3, 0x88, 0xcb, 0x36, 0x3d, 0xd1, 0x01, 0x70, 0xfc, 0x4a, 0x9e, 0x3e, 0xdb, 0x15,
+	0xcc, 0x06, 0xca, 0xde, 0x06, 0x68, 0xf3, 0x7a, 0x00, 0xe8, 0xdb, 0xf4, 0x07, 0x4e, 0xa8, 0x1b,
+	0xc8, 0x16, 0x3d, 0xd3, 0x55, 0xf0, 0x7a, 0xaa, 0xd7, 0x25, 0xe2, 0x06, 0x3e, 0xa7, 0x5e, 0x06,
+	0xc4, 0xe7, 0xe9, 0xd9, 0xd6, 0x88, 0xcb, 0xc2, 0x24, 0x29, 0xcb, 0x5b, 0x64, 0x40, 0xfc, 0x4c,
+	0xa1, 0xd3, 0xed, 0x7f, 0xb6, 0x48, 0x90, 0x33, 0x9d, 0xd4, 0x10, 0x56, 0x06, 0x60, 0x1d, 0xa0,
+	0x13, 0x2d, 0xfe, 0x50, 0xd1, 0x77, 0xc8, 0x4e, 0xce, 0xab, 0xe8, 0x6c, 0xdb, 0x49, 0xd7, 0xc8,
+	0x64, 0x7a, 0xae, 0xb3, 0x22, 0x86, 0x9e, 0x86, 0xd0, 0x19, 0x7a, 0xb8, 0x29, 0x34, 0xe7, 0x94,
+	0xda, 0x43, 0x41, 0x33, 0xb7, 0xe9, 0x07, 0x0a, 0x19, 0x10, 0xe4, 0xae, 0x7b, 0x10, 0xed, 0x57,
+	0x60, 0x3d, 0x55, 0x54, 0xe7, 0x01, 0xc7, 0x31, 0x7a, 0xb4, 0x2d, 0x0e, 0xcd, 0x0d, 0xac, 0xb0,
+	0xdb, 0xee, 0x69, 0x64, 0x6d, 0xb4, 0xfd, 0x7a, 0x68, 0xc1, 0x33, 0xd3, 0xe7, 0x7a, 0xb4, 0x42,
+	0xb0, 0x33, 0x00, 0xf6, 0x08, 0x9d, 0x4a, 0x6e, 0x62, 0x79, 0x64, 0x89, 0xf4, 0x47, 0xde, 0x6d,
+	0x13, 0x28, 0x17, 0xbd, 0xd0, 0x36, 0x6c, 0x6b, 0x42, 0x99, 0xbe, 0xd8, 0xbb, 0x21, 0x42, 0x5e,
+	0x06, 0xc8, 0x2f, 0xd0, 0xe7, 0x5b, 0xae, 0xfc, 0x84, 0x15, 0xaf, 0x31, 0xf0, 0xc8, 0x6f, 0xa5,
+	0x21, 0xea, 0xcf, 0x15, 0x42, 0x6a, 0x94, 0x8e, 0x66, 0xdb, 0x62, 0x69, 0x62, 0x8b, 0x69, 0xad,
+	0x6b, 0x7d, 0x84, 0x7c, 0x0a, 0x20, 0xcf, 0xd2, 0xe9, 0x26, 0xc8, 0x9b, 0x42, 0x39, 0xe4, 0x90,
+	0xda, 0x43, 0x41, 0x3a, 0xb7, 0x57, 0xee, 0x3e, 0xfe, 0x6d, 0xaa, 0xef, 0xd1, 0xef, 0x53, 0xca,
+	0x63, 0xfe, 0x3c, 0xe1, 0xcf, 0xaf, 0xfc, 0xf9, 0xf0, 0x8f, 0xa9, 0xbe, 0x27, 0xfc, 0xf9, 0x85,
+	0x3f, 0xaf, 0x5d, 0x2a, 0x99, 0x7e, 0x39, 0x28, 0x84, 0x00, 0x34, 0xcf, 0x70, 0x7d, 0x7e, 0x26,
+	0x79, 0x9a, 0xe0, 0x29, 0xb7, 0x98, 0xff, 0xc0, 0x76, 0x37, 0xb4, 0xad, 0x28, 0x56, 0x78, 0xf9,
+	0x74, 0x2d, 0xbd, 0x22, 0x7e, 0x24, 0x17, 0x06, 0xa0, 0xd1, 0x9f, 0xf9, 0x17, 0x18, 0xbb, 0x13,
+	0x8b, 0xc1, 0x16, 0x00, 0x00,
 }
 
 func (this *ParamsRequest) Equal(that interface{}) bool {
@@ -1309,66 +1696,319 @@ func (this *QueryCodeHashResponse) Equal(that interface{}) bool {
 	}
 	return true
 }
-
-// Reference imports to suppress errors if they are not otherwise used.
-var _ context.Context
-var _ grpc.ClientConn
-
-// This is a compile-time assertion to ensure that this generated file
-// is compatible with the grpc package it is being compiled against.
-const _ = grpc.SupportPackageIsVersion4
-
-// QueryClient is the client API for Query service.
-//
-// For semantics around ctx use and closing/ending streaming RPCs, please refer to https://godoc.org/google.golang.org/grpc#ClientConn.NewStream.
-type QueryClient interface {
-	// Query contract info by address
-	ContractInfo(ctx context.Context, in *QueryByContractAddressRequest, opts ...grpc.CallOption) (*QueryContractInfoResponse, error)
-	// Query code info by id
-	ContractsByCodeId(ctx context.Context, in *QueryByCodeIdRequest, opts ...grpc.CallOption) (*QueryContractsByCodeIdResponse, error)
-	// Query secret contract
-	QuerySecretContract(ctx context.Context, in *QuerySecretContractRequest, opts ...grpc.CallOption) (*QuerySecretContractResponse, error)
-	// Query a specific contract code by id
-	Code(ctx context.Context, in *QueryByCodeIdRequest, opts ...grpc.CallOption) (*QueryCodeResponse, error)
-	// Query all contract codes on-chain
-	Codes(ctx context.Context, in *emptypb.Empty, opts ...grpc.CallOption) (*QueryCodesResponse, error)
-	// Query code hash by contract address
-	CodeHashByContractAddress(ctx context.Context, in *QueryByContractAddressRequest, opts ...grpc.CallOption) (*QueryCodeHashResponse, error)
-	// Query code hash by code id
-	CodeHashByCodeId(ctx context.Context, in *QueryByCodeIdRequest, opts ...grpc.CallOption) (*QueryCodeHashResponse, error)
-	// Query contract label by address
-	LabelByAddress(ctx context.Context, in *QueryByContractAddressRequest, opts ...grpc.CallOption) (*QueryContractLabelResponse, error)
-	// Query contract address by label
-	AddressByLabel(ctx context.Context, in *QueryByLabelRequest, opts ...grpc.CallOption) (*QueryContractAddressResponse, error)
-	// ContractHistory gets the contract code history
-	ContractHistory(ctx context.Context, in *QueryContractHistoryRequest, opts ...grpc.CallOption) (*QueryContractHistoryResponse, error)
-	// Params defines a gRPC query method that returns the compute
-	// module's parameters.
-	Params(ctx context.Context, in *ParamsRequest, opts ...grpc.CallOption) (*ParamsResponse, error)
-}
-
-type queryClient struct {
-	cc grpc1.ClientConn
-}
-
-func NewQueryClient(cc grpc1.ClientConn) QueryClient {
-	return &queryClient{cc}
-}
-
-func (c *queryClient) ContractInfo(ctx context.Context, in *QueryByContractAddressRequest, opts ...grpc.CallOption) (*QueryContractInfoResponse, error) {
-	out := new(QueryContractInfoResponse)
-	err := c.cc.Invoke(ctx, "/secret.compute.v1beta1.Query/ContractInfo", in, out, opts...)
-	if err != nil {
-		return nil, err
+func (this *QueryJobRequest) Equal(that interface{}) bool {
+	if that == nil {
+		return this == nil
 	}
-	return out, nil
-}
 
-func (c *queryClient) ContractsByCodeId(ctx context.Context, in *QueryByCodeIdRequest, opts ...grpc.CallOption) (*QueryContractsByCodeIdResponse, error) {
-	out := new(QueryContractsByCodeIdResponse)
-	err := c.cc.Invoke(ctx, "/secret.compute.v1beta1.Query/ContractsByCodeId", in, out, opts...)
-	if err != nil {
-		return nil, err
+	that1, ok := that.(*QueryJobRequest)
+	if !ok {
+		that2, ok := that.(QueryJobRequest)
+		if ok {
+			that1 = &that2
+		} else {
+			return false
+		}
+	}
+	if that1 == nil {
+		return this == nil
+	} else if this == nil {
+		return false
+	}
+	if this.JobId != that1.JobId {
+		return false
+	}
+	return true
+}
+func (this *QueryJobResponse) Equal(that interface{}) bool {
+	if that == nil {
+		return this == nil
+	}
+
+	that1, ok := that.(*QueryJobResponse)
+	if !ok {
+		that2, ok := that.(QueryJobResponse)
+		if ok {
+			that1 = &that2
+		} else {
+			return false
+		}
+	}
+	if that1 == nil {
+		return this == nil
+	} else if this == nil {
+		return false
+	}
+	if this.JobId != that1.JobId {
+		return false
+	}
+	if this.ContractAddress != that1.ContractAddress {
+		return false
+	}
+	if this.HandlerCodeHash != that1.HandlerCodeHash {
+		return false
+	}
+	if !bytes.Equal(this.SealedInput, that1.SealedInput) {
+		return false
+	}
+	return true
+}
+func (this *QueryRunJobResponse) Equal(that interface{}) bool {
+	if that == nil {
+		return this == nil
+	}
+
+	that1, ok := that.(*QueryRunJobResponse)
+	if !ok {
+		that2, ok := that.(QueryRunJobResponse)
+		if ok {
+			that1 = &that2
+		} else {
+			return false
+		}
+	}
+	if that1 == nil {
+		return this == nil
+	} else if this == nil {
+		return false
+	}
+	if !bytes.Equal(this.Result, that1.Result) {
+		return false
+	}
+	return true
+}
+func (this *QueryOpenQuerySessionRequest) Equal(that interface{}) bool {
+	if that == nil {
+		return this == nil
+	}
+
+	that1, ok := that.(*QueryOpenQuerySessionRequest)
+	if !ok {
+		that2, ok := that.(QueryOpenQuerySessionRequest)
+		if ok {
+			that1 = &that2
+		} else {
+			return false
+		}
+	}
+	if that1 == nil {
+		return this == nil
+	} else if this == nil {
+		return false
+	}
+	if !bytes.Equal(this.Nonce, that1.Nonce) {
+		return false
+	}
+	if !bytes.Equal(this.UserPublicKey, that1.UserPublicKey) {
+		return false
+	}
+	if this.ExpiryHeight != that1.ExpiryHeight {
+		return false
+	}
+	return true
+}
+func (this *QueryOpenQuerySessionResponse) Equal(that interface{}) bool {
+	if that == nil {
+		return this == nil
+	}
+
+	that1, ok := that.(*QueryOpenQuerySessionResponse)
+	if !ok {
+		that2, ok := that.(QueryOpenQuerySessionResponse)
+		if ok {
+			that1 = &that2
+		} else {
+			return false
+		}
+	}
+	if that1 == nil {
+		return this == nil
+	} else if this == nil {
+		return false
+	}
+	if !bytes.Equal(this.SessionId, that1.SessionId) {
+		return false
+	}
+	return true
+}
+func (this *QueryExportContractStateRequest) Equal(that interface{}) bool {
+	if that == nil {
+		return this == nil
+	}
+
+	that1, ok := that.(*QueryExportContractStateRequest)
+	if !ok {
+		that2, ok := that.(QueryExportContractStateRequest)
+		if ok {
+			that1 = &that2
+		} else {
+			return false
+		}
+	}
+	if that1 == nil {
+		return this == nil
+	} else if this == nil {
+		return false
+	}
+	if this.ContractAddress != that1.ContractAddress {
+		return false
+	}
+	if !this.Authorization.Equal(&that1.Authorization) {
+		return false
+	}
+	return true
+}
+func (this *QueryExportContractStateResponse) Equal(that interface{}) bool {
+	if that == nil {
+		return this == nil
+	}
+
+	that1, ok := that.(*QueryExportContractStateResponse)
+	if !ok {
+		that2, ok := that.(QueryExportContractStateResponse)
+		if ok {
+			that1 = &that2
+		} else {
+			return false
+		}
+	}
+	if that1 == nil {
+		return this == nil
+	} else if this == nil {
+		return false
+	}
+	if len(this.Chunks) != len(that1.Chunks) {
+		return false
+	}
+	for i := range this.Chunks {
+		if !bytes.Equal(this.Chunks[i], that1.Chunks[i]) {
+			return false
+		}
+	}
+	return true
+}
+func (this *QueryMempoolKeyRequest) Equal(that interface{}) bool {
+	if that == nil {
+		return this == nil
+	}
+
+	that1, ok := that.(*QueryMempoolKeyRequest)
+	if !ok {
+		that2, ok := that.(QueryMempoolKeyRequest)
+		if ok {
+			that1 = &that2
+		} else {
+			return false
+		}
+	}
+	if that1 == nil {
+		return this == nil
+	} else if this == nil {
+		return false
+	}
+	if this.Height != that1.Height {
+		return false
+	}
+	return true
+}
+func (this *QueryMempoolKeyResponse) Equal(that interface{}) bool {
+	if that == nil {
+		return this == nil
+	}
+
+	that1, ok := that.(*QueryMempoolKeyResponse)
+	if !ok {
+		that2, ok := that.(QueryMempoolKeyResponse)
+		if ok {
+			that1 = &that2
+		} else {
+			return false
+		}
+	}
+	if that1 == nil {
+		return this == nil
+	} else if this == nil {
+		return false
+	}
+	if !bytes.Equal(this.PublicKey, that1.PublicKey) {
+		return false
+	}
+	return true
+}
+
+// Reference imports to suppress errors if they are not otherwise used.
+var _ context.Context
+var _ grpc.ClientConn
+
+// This is a compile-time assertion to ensure that this generated file
+// is compatible with the grpc package it is being compiled against.
+const _ = grpc.SupportPackageIsVersion4
+
+// QueryClient is the client API for Query service.
+//
+// For semantics around ctx use and closing/ending streaming RPCs, please refer to https://godoc.org/google.golang.org/grpc#ClientConn.NewStream.
+type QueryClient interface {
+	// Query contract info by address
+	ContractInfo(ctx context.Context, in *QueryByContractAddressRequest, opts ...grpc.CallOption) (*QueryContractInfoResponse, error)
+	// Query code info by id
+	ContractsByCodeId(ctx context.Context, in *QueryByCodeIdRequest, opts ...grpc.CallOption) (*QueryContractsByCodeIdResponse, error)
+	// Query secret contract
+	QuerySecretContract(ctx context.Context, in *QuerySecretContractRequest, opts ...grpc.CallOption) (*QuerySecretContractResponse, error)
+	// Query a specific contract code by id
+	Code(ctx context.Context, in *QueryByCodeIdRequest, opts ...grpc.CallOption) (*QueryCodeResponse, error)
+	// Query all contract codes on-chain
+	Codes(ctx context.Context, in *emptypb.Empty, opts ...grpc.CallOption) (*QueryCodesResponse, error)
+	// Query code hash by contract address
+	CodeHashByContractAddress(ctx context.Context, in *QueryByContractAddressRequest, opts ...grpc.CallOption) (*QueryCodeHashResponse, error)
+	// Query code hash by code id
+	CodeHashByCodeId(ctx context.Context, in *QueryByCodeIdRequest, opts ...grpc.CallOption) (*QueryCodeHashResponse, error)
+	// Query contract label by address
+	LabelByAddress(ctx context.Context, in *QueryByContractAddressRequest, opts ...grpc.CallOption) (*QueryContractLabelResponse, error)
+	// Query contract address by label
+	AddressByLabel(ctx context.Context, in *QueryByLabelRequest, opts ...grpc.CallOption) (*QueryContractAddressResponse, error)
+	// ContractHistory gets the contract code history
+	ContractHistory(ctx context.Context, in *QueryContractHistoryRequest, opts ...grpc.CallOption) (*QueryContractHistoryResponse, error)
+	// Params defines a gRPC query method that returns the compute
+	// module's parameters.
+	Params(ctx context.Context, in *ParamsRequest, opts ...grpc.CallOption) (*ParamsResponse, error)
+	// Job gets an off-chain job that's waiting for its result
+	Job(ctx context.Context, in *QueryJobRequest, opts ...grpc.CallOption) (*QueryJobResponse, error)
+	// RunJob runs the handler of a job on the node's enclave, and returns the
+	// result it signed and attested, to submit in a MsgSubmitJobResult
+	RunJob(ctx context.Context, in *QueryJobRequest, opts ...grpc.CallOption) (*QueryRunJobResponse, error)
+	// OpenQuerySession opens a query session in the node's enclave, so the
+	// client can send queries without a key exchange each until expiry_height
+	OpenQuerySession(ctx context.Context, in *QueryOpenQuerySessionRequest, opts ...grpc.CallOption) (*QueryOpenQuerySessionResponse, error)
+	// ExportContractState exports the state of a contract to an encrypted backup,
+	// as its admin authorized
+	ExportContractState(ctx context.Context, in *QueryExportContractStateRequest, opts ...grpc.CallOption) (*QueryExportContractStateResponse, error)
+	// MempoolKey gets the public key users encrypt the txs they want included at
+	// a height to, see docs/encrypted-mempool.md
+	MempoolKey(ctx context.Context, in *QueryMempoolKeyRequest, opts ...grpc.CallOption) (*QueryMempoolKeyResponse, error)
+}
+
+type queryClient struct {
+	cc grpc1.ClientConn
+}
+
+func NewQueryClient(cc grpc1.ClientConn) QueryClient {
+	return &queryClient{cc}
+}
+
+func (c *queryClient) ContractInfo(ctx context.Context, in *QueryByContractAddressRequest, opts ...grpc.CallOption) (*QueryContractInfoResponse, error) {
+	out := new(QueryContractInfoResponse)
+	err := c.cc.Invoke(ctx, "/secret.compute.v1beta1.Query/ContractInfo", in, out, opts...)
+	if err != nil {
+		return nil, err
+	}
+	return out, nil
+}
+
+func (c *queryClient) ContractsByCodeId(ctx context.Context, in *QueryByCodeIdRequest, opts ...grpc.CallOption) (*QueryContractsByCodeIdResponse, error) {
+	out := new(QueryContractsByCodeIdResponse)
+	err := c.cc.Invoke(ctx, "/secret.compute.v1beta1.Query/ContractsByCodeId", in, out, opts...)
+	if err != nil {
+		return nil, err
 	}
 	return out, nil
 }
@@ -1454,15 +2094,60 @@ func (c *queryClient) Params(ctx context.Context, in *ParamsRequest, opts ...grp
 	return out, nil
 }
 
-// QueryServer is the server API for Query service.
-type QueryServer interface {
-	// Query contract info by address
-	ContractInfo(context.Context, *QueryByContractAddressRequest) (*QueryContractInfoResponse, error)
-	// Query code info by id
-	ContractsByCodeId(context.Context, *QueryByCodeIdRequest) (*QueryContractsByCodeIdResponse, error)
-	// Query secret contract
-	QuerySecretContract(context.Context, *QuerySecretContractRequest) (*QuerySecretContractResponse, error)
-	// Query a specific contract code by id
+func (c *queryClient) Job(ctx context.Context, in *QueryJobRequest, opts ...grpc.CallOption) (*QueryJobResponse, error) {
+	out := new(QueryJobResponse)
+	err := c.cc.Invoke(ctx, "/secret.compute.v1beta1.Query/Job", in, out, opts...)
+	if err != nil {
+		return nil, err
+	}
+	return out, nil
+}
+
+func (c *queryClient) RunJob(ctx context.Context, in *QueryJobRequest, opts ...grpc.CallOption) (*QueryRunJobResponse, error) {
+	out := new(QueryRunJobResponse)
+	err := c.cc.Invoke(ctx, "/secret.compute.v1beta1.Query/RunJob", in, out, opts...)
+	if err != nil {
+		return nil, err
+	}
+	return out, nil
+}
+
+func (c *queryClient) OpenQuerySession(ctx context.Context, in *QueryOpenQuerySessionRequest, opts ...grpc.CallOption) (*QueryOpenQuerySessionResponse, error) {
+	out := new(QueryOpenQuerySessionResponse)
+	err := c.cc.Invoke(ctx, "/secret.compute.v1beta1.Query/OpenQuerySession", in, out, opts...)
+	if err != nil {
+		return nil, err
+	}
+	return out, nil
+}
+
+func (c *queryClient) ExportContractState(ctx context.Context, in *QueryExportContractStateRequest, opts ...grpc.CallOption) (*QueryExportContractStateResponse, error) {
+	out := new(QueryExportContractStateResponse)
+	err := c.cc.Invoke(ctx, "/secret.compute.v1beta1.Query/ExportContractState", in, out, opts...)
+	if err != nil {
+		return nil, err
+	}
+	return out, nil
+}
+
+func (c *queryClient) MempoolKey(ctx context.Context, in *QueryMempoolKeyRequest, opts ...grpc.CallOption) (*QueryMempoolKeyResponse, error) {
+	out := new(QueryMempoolKeyResponse)
+	err := c.cc.Invoke(ctx, "/secret.compute.v1beta1.Query/MempoolKey", in, out, opts...)
+	if err != nil {
+		return nil, err
+	}
+	return out, nil
+}
+
+// QueryServer is the server API for Query service.
+type QueryServer interface {
+	// Query contract info by address
+	ContractInfo(context.Context, *QueryByContractAddressRequest) (*QueryContractInfoResponse, error)
+	// Query code info by id
+	ContractsByCodeId(context.Context, *QueryByCodeIdRequest) (*QueryContractsByCodeIdResponse, error)
+	// Query secret contract
+	QuerySecretContract(context.Context, *QuerySecretContractRequest) (*QuerySecretContractResponse, error)
+	// Query a specific contract code by id
 	Code(context.Context, *QueryByCodeIdRequest) (*QueryCodeResponse, error)
 	// Query all contract codes on-chain
 	Codes(context.Context, *emptypb.Empty) (*QueryCodesResponse, error)
@@ -1479,6 +2164,20 @@ type QueryServer interface {
 	// Params defines a gRPC query method that returns the compute
 	// module's parameters.
 	Params(context.Context, *ParamsRequest) (*ParamsResponse, error)
+	// Job gets an off-chain job that's waiting for its result
+	Job(context.Context, *QueryJobRequest) (*QueryJobResponse, error)
+	// RunJob runs the handler of a job on the node's enclave, and returns the
+	// result it signed and attested, to submit in a MsgSubmitJobResult
+	RunJob(context.Context, *QueryJobRequest) (*QueryRunJobResponse, error)
+	// OpenQuerySession opens a query session in the node's enclave, so the
+	// client can send queries without a key exchange each until expiry_height
+	OpenQuerySession(context.Context, *QueryOpenQuerySessionRequest) (*QueryOpenQuerySessionResponse, error)
+	// ExportContractState exports the state of a contract to an encrypted backup,
+	// as its admin authorized
+	ExportContractState(context.Context, *QueryExportContractStateRequest) (*QueryExportContractStateResponse, error)
+	// MempoolKey gets the public key users encrypt the txs they want included at
+	// a height to, see docs/encrypted-mempool.md
+	MempoolKey(context.Context, *QueryMempoolKeyRequest) (*QueryMempoolKeyResponse, error)
 }
 
 // UnimplementedQueryServer can be embedded to have forward compatible implementations.
@@ -1518,6 +2217,21 @@ func (*UnimplementedQueryServer) ContractHistory(ctx context.Context, req *Query
 func (*UnimplementedQueryServer) Params(ctx context.Context, req *ParamsRequest) (*ParamsResponse, error) {
 	return nil, status.Errorf(codes.Unimplemented, "method Params not implemented")
 }
+func (*UnimplementedQueryServer) Job(ctx context.Context, req *QueryJobRequest) (*QueryJobResponse, error) {
+	return nil, status.Errorf(codes.Unimplemented, "method Job not implemented")
+}
+func (*UnimplementedQueryServer) RunJob(ctx context.Context, req *QueryJobRequest) (*QueryRunJobResponse, error) {
+	return nil, status.Errorf(codes.Unimplemented, "method RunJob not implemented")
+}
+func (*UnimplementedQueryServer) OpenQuerySession(ctx context.Context, req *QueryOpenQuerySessionRequest) (*QueryOpenQuerySessionResponse, error) {
+	return nil, status.Errorf(codes.Unimplemented, "method OpenQuerySession not implemented")
+}
+func (*UnimplementedQueryServer) ExportContractState(ctx context.Context, req *QueryExportContractStateRequest) (*QueryExportContractStateResponse, error) {
+	return nil, status.Errorf(codes.Unimplemented, "method ExportContractState not implemented")
+}
+func (*UnimplementedQueryServer) MempoolKey(ctx context.Context, req *QueryMempoolKeyRequest) (*QueryMempoolKeyResponse, error) {
+	return nil, status.Errorf(codes.Unimplemented, "method MempoolKey not implemented")
+}
 
 func RegisterQueryServer(s grpc1.Server, srv QueryServer) {
 	s.RegisterService(&_Query_serviceDesc, srv)
@@ -1721,6 +2435,96 @@ func _Query_Params_Handler(srv interface{}, ctx context.Context, dec func(interf
 	return interceptor(ctx, in, info, handler)
 }
 
+func _Query_Job_Handler(srv interface{}, ctx context.Context, dec func(interface{}) error, interceptor grpc.UnaryServerInterceptor) (interface{}, error) {
+	in := new(QueryJobRequest)
+	if err := dec(in); err != nil {
+		return nil, err
+	}
+	if interceptor == nil {
+		return srv.(QueryServer).Job(ctx, in)
+	}
+	info := &grpc.UnaryServerInfo{
+		Server:     srv,
+		FullMethod: "/secret.compute.v1beta1.Query/Job",
+	}
+	handler := func(ctx context.Context, req interface{}) (interface{}, error) {
+		return srv.(QueryServer).Job(ctx, req.(*QueryJobRequest))
+	}
+	return interceptor(ctx, in, info, handler)
+}
+
+func _Query_RunJob_Handler(srv interface{}, ctx context.Context, dec func(interface{}) error, interceptor grpc.UnaryServerInterceptor) (interface{}, error) {
+	in := new(QueryJobRequest)
+	if err := dec(in); err != nil {
+		return nil, err
+	}
+	if interceptor == nil {
+		return srv.(QueryServer).RunJob(ctx, in)
+	}
+	info := &grpc.UnaryServerInfo{
+		Server:     srv,
+		FullMethod: "/secret.compute.v1beta1.Query/RunJob",
+	}
+	handler := func(ctx context.Context, req interface{}) (interface{}, error) {
+		return srv.(QueryServer).RunJob(ctx, req.(*QueryJobRequest))
+	}
+	return interceptor(ctx, in, info, handler)
+}
+
+func _Query_OpenQuerySession_Handler(srv interface{}, ctx context.Context, dec func(interface{}) error, interceptor grpc.UnaryServerInterceptor) (interface{}, error) {
+	in := new(QueryOpenQuerySessionRequest)
+	if err := dec(in); err != nil {
+		return nil, err
+	}
+	if interceptor == nil {
+		return srv.(QueryServer).OpenQuerySession(ctx, in)
+	}
+	info := &grpc.UnaryServerInfo{
+		Server:     srv,
+		FullMethod: "/secret.compute.v1beta1.Query/OpenQuerySession",
+	}
+	handler := func(ctx context.Context, req interface{}) (interface{}, error) {
+		return srv.(QueryServer).OpenQuerySession(ctx, req.(*QueryOpenQuerySessionRequest))
+	}
+	return interceptor(ctx, in, info, handler)
+}
+
+func _Query_ExportContractState_Handler(srv interface{}, ctx context.Context, dec func(interface{}) error, interceptor grpc.UnaryServerInterceptor) (interface{}, error) {
+	in := new(QueryExportContractStateRequest)
+	if err := dec(in); err != nil {
+		return nil, err
+	}
+	if interceptor == nil {
+		return srv.(QueryServer).ExportContractState(ctx, in)
+	}
+	info := &grpc.UnaryServerInfo{
+		Server:     srv,
+		FullMethod: "/secret.compute.v1beta1.Query/ExportContractState",
+	}
+	handler := func(ctx context.Context, req interface{}) (interface{}, error) {
+		return srv.(QueryServer).ExportContractState(ctx, req.(*QueryExportContractStateRequest))
+	}
+	return interceptor(ctx, in, info, handler)
+}
+
+func _Query_MempoolKey_Handler(srv interface{}, ctx context.Context, dec func(interface{}) error, interceptor grpc.UnaryServerInterceptor) (interface{}, error) {
+	in := new(QueryMempoolKeyRequest)
+	if err := dec(in); err != nil {
+		return nil, err
+	}
+	if interceptor == nil {
+		return srv.(QueryServer).MempoolKey(ctx, in)
+	}
+	info := &grpc.UnaryServerInfo{
+		Server:     srv,
+		FullMethod: "/secret.compute.v1beta1.Query/MempoolKey",
+	}
+	handler := func(ctx context.Context, req interface{}) (interface{}, error) {
+		return srv.(QueryServer).MempoolKey(ctx, req.(*QueryMempoolKeyRequest))
+	}
+	return interceptor(ctx, in, info, handler)
+}
+
 var _Query_serviceDesc = grpc.ServiceDesc{
 	ServiceName: "secret.compute.v1beta1.Query",
 	HandlerType: (*QueryServer)(nil),
@@ -1769,6 +2573,26 @@ var _Query_serviceDesc = grpc.ServiceDesc{
 			MethodName: "Params",
 			Handler:    _Query_Params_Handler,
 		},
+		{
+			MethodName: "Job",
+			Handler:    _Query_Job_Handler,
+		},
+		{
+			MethodName: "RunJob",
+			Handler:    _Query_RunJob_Handler,
+		},
+		{
+			MethodName: "OpenQuerySession",
+			Handler:    _Query_OpenQuerySession_Handler,
+		},
+		{
+			MethodName: "ExportContractState",
+			Handler:    _Query_ExportContractState_Handler,
+		},
+		{
+			MethodName: "MempoolKey",
+			Handler:    _Query_MempoolKey_Handler,
+		},
 	},
 	Streams:  []grpc.StreamDesc{},
 	Metadata: "secret/compute/v1beta1/query.proto",
@@ -2514,111 +3338,420 @@ func (m *QueryContractHistoryResponse) MarshalToSizedBuffer(dAtA []byte) (int, e
 	return len(dAtA) - i, nil
 }
 
-func encodeVarintQuery(dAtA []byte, offset int, v uint64) int {
-	offset -= sovQuery(v)
-	base := offset
-	for v >= 1<<7 {
-		dAtA[offset] = uint8(v&0x7f | 0x80)
-		v >>= 7
-		offset++
+func (m *QueryJobRequest) Marshal() (dAtA []byte, err error) {
+	size := m.Size()
+	dAtA = make([]byte, size)
+	n, err := m.MarshalToSizedBuffer(dAtA[:size])
+	if err != nil {
+		return nil, err
 	}
-	dAtA[offset] = uint8(v)
-	return base
+	return dAtA[:n], nil
 }
-func (m *ParamsRequest) Size() (n int) {
-	if m == nil {
-		return 0
-	}
-	var l int
-	_ = l
-	return n
+
+func (m *QueryJobRequest) MarshalTo(dAtA []byte) (int, error) {
+	size := m.Size()
+	return m.MarshalToSizedBuffer(dAtA[:size])
 }
 
-func (m *ParamsResponse) Size() (n int) {
-	if m == nil {
-		return 0
-	}
+func (m *QueryJobRequest) MarshalToSizedBuffer(dAtA []byte) (int, error) {
+	i := len(dAtA)
+	_ = i
 	var l int
 	_ = l
-	l = m.Params.Size()
-	n += 1 + l + sovQuery(uint64(l))
-	return n
+	if m.JobId != 0 {
+		i = encodeVarintQuery(dAtA, i, uint64(m.JobId))
+		i--
+		dAtA[i] = 0x8
+	}
+	return len(dAtA) - i, nil
 }
 
-func (m *QuerySecretContractRequest) Size() (n int) {
-	if m == nil {
-		return 0
+func (m *QueryJobResponse) Marshal() (dAtA []byte, err error) {
+	size := m.Size()
+	dAtA = make([]byte, size)
+	n, err := m.MarshalToSizedBuffer(dAtA[:size])
+	if err != nil {
+		return nil, err
 	}
+	return dAtA[:n], nil
+}
+
+func (m *QueryJobResponse) MarshalTo(dAtA []byte) (int, error) {
+	size := m.Size()
+	return m.MarshalToSizedBuffer(dAtA[:size])
+}
+
+func (m *QueryJobResponse) MarshalToSizedBuffer(dAtA []byte) (int, error) {
+	i := len(dAtA)
+	_ = i
 	var l int
 	_ = l
-	l = len(m.ContractAddress)
-	if l > 0 {
-		n += 1 + l + sovQuery(uint64(l))
+	if len(m.SealedInput) > 0 {
+		i -= len(m.SealedInput)
+		copy(dAtA[i:], m.SealedInput)
+		i = encodeVarintQuery(dAtA, i, uint64(len(m.SealedInput)))
+		i--
+		dAtA[i] = 0x22
 	}
-	l = len(m.Query)
-	if l > 0 {
-		n += 1 + l + sovQuery(uint64(l))
+	if len(m.HandlerCodeHash) > 0 {
+		i -= len(m.HandlerCodeHash)
+		copy(dAtA[i:], m.HandlerCodeHash)
+		i = encodeVarintQuery(dAtA, i, uint64(len(m.HandlerCodeHash)))
+		i--
+		dAtA[i] = 0x1a
 	}
-	return n
-}
-
-func (m *QueryByLabelRequest) Size() (n int) {
-	if m == nil {
-		return 0
+	if len(m.ContractAddress) > 0 {
+		i -= len(m.ContractAddress)
+		copy(dAtA[i:], m.ContractAddress)
+		i = encodeVarintQuery(dAtA, i, uint64(len(m.ContractAddress)))
+		i--
+		dAtA[i] = 0x12
 	}
-	var l int
-	_ = l
-	l = len(m.Label)
-	if l > 0 {
-		n += 1 + l + sovQuery(uint64(l))
+	if m.JobId != 0 {
+		i = encodeVarintQuery(dAtA, i, uint64(m.JobId))
+		i--
+		dAtA[i] = 0x8
 	}
-	return n
+	return len(dAtA) - i, nil
 }
 
-func (m *QueryByContractAddressRequest) Size() (n int) {
-	if m == nil {
-		return 0
-	}
-	var l int
-	_ = l
-	l = len(m.ContractAddress)
-	if l > 0 {
-		n += 1 + l + sovQuery(uint64(l))
+func (m *QueryRunJobResponse) Marshal() (dAtA []byte, err error) {
+	size := m.Size()
+	dAtA = make([]byte, size)
+	n, err := m.MarshalToSizedBuffer(dAtA[:size])
+	if err != nil {
+		return nil, err
 	}
-	return n
+	return dAtA[:n], nil
 }
 
-func (m *QueryByCodeIdRequest) Size() (n int) {
-	if m == nil {
-		return 0
-	}
-	var l int
-	_ = l
-	if m.CodeId != 0 {
-		n += 1 + sovQuery(uint64(m.CodeId))
-	}
-	return n
+func (m *QueryRunJobResponse) MarshalTo(dAtA []byte) (int, error) {
+	size := m.Size()
+	return m.MarshalToSizedBuffer(dAtA[:size])
 }
 
-func (m *QuerySecretContractResponse) Size() (n int) {
-	if m == nil {
-		return 0
-	}
+func (m *QueryRunJobResponse) MarshalToSizedBuffer(dAtA []byte) (int, error) {
+	i := len(dAtA)
+	_ = i
 	var l int
 	_ = l
-	l = len(m.Data)
-	if l > 0 {
-		n += 1 + l + sovQuery(uint64(l))
+	if len(m.Result) > 0 {
+		i -= len(m.Result)
+		copy(dAtA[i:], m.Result)
+		i = encodeVarintQuery(dAtA, i, uint64(len(m.Result)))
+		i--
+		dAtA[i] = 0xa
 	}
-	return n
+	return len(dAtA) - i, nil
 }
 
-func (m *QueryContractInfoResponse) Size() (n int) {
-	if m == nil {
-		return 0
+func (m *QueryOpenQuerySessionRequest) Marshal() (dAtA []byte, err error) {
+	size := m.Size()
+	dAtA = make([]byte, size)
+	n, err := m.MarshalToSizedBuffer(dAtA[:size])
+	if err != nil {
+		return nil, err
 	}
-	var l int
-	_ = l
+	return dAtA[:n], nil
+}
+
+func (m *QueryOpenQuerySessionRequest) MarshalTo(dAtA []byte) (int, error) {
+	size := m.Size()
+	return m.MarshalToSizedBuffer(dAtA[:size])
+}
+
+func (m *QueryOpenQuerySessionRequest) MarshalToSizedBuffer(dAtA []byte) (int, error) {
+	i := len(dAtA)
+	_ = i
+	var l int
+	_ = l
+	if m.ExpiryHeight != 0 {
+		i = encodeVarintQuery(dAtA, i, uint64(m.ExpiryHeight))
+		i--
+		dAtA[i] = 0x18
+	}
+	if len(m.UserPublicKey) > 0 {
+		i -= len(m.UserPublicKey)
+		copy(dAtA[i:], m.UserPublicKey)
+		i = encodeVarintQuery(dAtA, i, uint64(len(m.UserPublicKey)))
+		i--
+		dAtA[i] = 0x12
+	}
+	if len(m.Nonce) > 0 {
+		i -= len(m.Nonce)
+		copy(dAtA[i:], m.Nonce)
+		i = encodeVarintQuery(dAtA, i, uint64(len(m.Nonce)))
+		i--
+		dAtA[i] = 0xa
+	}
+	return len(dAtA) - i, nil
+}
+
+func (m *QueryOpenQuerySessionResponse) Marshal() (dAtA []byte, err error) {
+	size := m.Size()
+	dAtA = make([]byte, size)
+	n, err := m.MarshalToSizedBuffer(dAtA[:size])
+	if err != nil {
+		return nil, err
+	}
+	return dAtA[:n], nil
+}
+
+func (m *QueryOpenQuerySessionResponse) MarshalTo(dAtA []byte) (int, error) {
+	size := m.Size()
+	return m.MarshalToSizedBuffer(dAtA[:size])
+}
+
+func (m *QueryOpenQuerySessionResponse) MarshalToSizedBuffer(dAtA []byte) (int, error) {
+	i := len(dAtA)
+	_ = i
+	var l int
+	_ = l
+	if len(m.SessionId) > 0 {
+		i -= len(m.SessionId)
+		copy(dAtA[i:], m.SessionId)
+		i = encodeVarintQuery(dAtA, i, uint64(len(m.SessionId)))
+		i--
+		dAtA[i] = 0xa
+	}
+	return len(dAtA) - i, nil
+}
+
+func (m *QueryExportContractStateRequest) Marshal() (dAtA []byte, err error) {
+	size := m.Size()
+	dAtA = make([]byte, size)
+	n, err := m.MarshalToSizedBuffer(dAtA[:size])
+	if err != nil {
+		return nil, err
+	}
+	return dAtA[:n], nil
+}
+
+func (m *QueryExportContractStateRequest) MarshalTo(dAtA []byte) (int, error) {
+	size := m.Size()
+	return m.MarshalToSizedBuffer(dAtA[:size])
+}
+
+func (m *QueryExportContractStateRequest) MarshalToSizedBuffer(dAtA []byte) (int, error) {
+	i := len(dAtA)
+	_ = i
+	var l int
+	_ = l
+	{
+		size, err := m.Authorization.MarshalToSizedBuffer(dAtA[:i])
+		if err != nil {
+			return 0, err
+		}
+		i -= size
+		i = encodeVarintQuery(dAtA, i, uint64(size))
+	}
+	i--
+	dAtA[i] = 0x12
+	if len(m.ContractAddress) > 0 {
+		i -= len(m.ContractAddress)
+		copy(dAtA[i:], m.ContractAddress)
+		i = encodeVarintQuery(dAtA, i, uint64(len(m.ContractAddress)))
+		i--
+		dAtA[i] = 0xa
+	}
+	return len(dAtA) - i, nil
+}
+
+func (m *QueryExportContractStateResponse) Marshal() (dAtA []byte, err error) {
+	size := m.Size()
+	dAtA = make([]byte, size)
+	n, err := m.MarshalToSizedBuffer(dAtA[:size])
+	if err != nil {
+		return nil, err
+	}
+	return dAtA[:n], nil
+}
+
+func (m *QueryExportContractStateResponse) MarshalTo(dAtA []byte) (int, error) {
+	size := m.Size()
+	return m.MarshalToSizedBuffer(dAtA[:size])
+}
+
+func (m *QueryExportContractStateResponse) MarshalToSizedBuffer(dAtA []byte) (int, error) {
+	i := len(dAtA)
+	_ = i
+	var l int
+	_ = l
+	if len(m.Chunks) > 0 {
+		for iNdEx := len(m.Chunks) - 1; iNdEx >= 0; iNdEx-- {
+			i -= len(m.Chunks[iNdEx])
+			copy(dAtA[i:], m.Chunks[iNdEx])
+			i = encodeVarintQuery(dAtA, i, uint64(len(m.Chunks[iNdEx])))
+			i--
+			dAtA[i] = 0xa
+		}
+	}
+	return len(dAtA) - i, nil
+}
+
+func (m *QueryMempoolKeyRequest) Marshal() (dAtA []byte, err error) {
+	size := m.Size()
+	dAtA = make([]byte, size)
+	n, err := m.MarshalToSizedBuffer(dAtA[:size])
+	if err != nil {
+		return nil, err
+	}
+	return dAtA[:n], nil
+}
+
+func (m *QueryMempoolKeyRequest) MarshalTo(dAtA []byte) (int, error) {
+	size := m.Size()
+	return m.MarshalToSizedBuffer(dAtA[:size])
+}
+
+func (m *QueryMempoolKeyRequest) MarshalToSizedBuffer(dAtA []byte) (int, error) {
+	i := len(dAtA)
+	_ = i
+	var l int
+	_ = l
+	if m.Height != 0 {
+		i = encodeVarintQuery(dAtA, i, uint64(m.Height))
+		i--
+		dAtA[i] = 0x8
+	}
+	return len(dAtA) - i, nil
+}
+
+func (m *QueryMempoolKeyResponse) Marshal() (dAtA []byte, err error) {
+	size := m.Size()
+	dAtA = make([]byte, size)
+	n, err := m.MarshalToSizedBuffer(dAtA[:size])
+	if err != nil {
+		return nil, err
+	}
+	return dAtA[:n], nil
+}
+
+func (m *QueryMempoolKeyResponse) MarshalTo(dAtA []byte) (int, error) {
+	size := m.Size()
+	return m.MarshalToSizedBuffer(dAtA[:size])
+}
+
+func (m *QueryMempoolKeyResponse) MarshalToSizedBuffer(dAtA []byte) (int, error) {
+	i := len(dAtA)
+	_ = i
+	var l int
+	_ = l
+	if len(m.PublicKey) > 0 {
+		i -= len(m.PublicKey)
+		copy(dAtA[i:], m.PublicKey)
+		i = encodeVarintQuery(dAtA, i, uint64(len(m.PublicKey)))
+		i--
+		dAtA[i] = 0xa
+	}
+	return len(dAtA) - i, nil
+}
+
+func encodeVarintQuery(dAtA []byte, offset int, v uint64) int {
+	offset -= sovQuery(v)
+	base := offset
+	for v >= 1<<7 {
+		dAtA[offset] = uint8(v&0x7f | 0x80)
+		v >>= 7
+		offset++
+	}
+	dAtA[offset] = uint8(v)
+	return base
+}
+func (m *ParamsRequest) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	return n
+}
+
+func (m *ParamsResponse) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = m.Params.Size()
+	n += 1 + l + sovQuery(uint64(l))
+	return n
+}
+
+func (m *QuerySecretContractRequest) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = len(m.ContractAddress)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	l = len(m.Query)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	return n
+}
+
+func (m *QueryByLabelRequest) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = len(m.Label)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	return n
+}
+
+func (m *QueryByContractAddressRequest) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = len(m.ContractAddress)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	return n
+}
+
+func (m *QueryByCodeIdRequest) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	if m.CodeId != 0 {
+		n += 1 + sovQuery(uint64(m.CodeId))
+	}
+	return n
+}
+
+func (m *QuerySecretContractResponse) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = len(m.Data)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	return n
+}
+
+func (m *QueryContractInfoResponse) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
 	l = len(m.ContractAddress)
 	if l > 0 {
 		n += 1 + l + sovQuery(uint64(l))
@@ -2804,52 +3937,991 @@ func (m *DecryptedAnswers) Size() (n int) {
 			n += 1 + l + sovQuery(uint64(l))
 		}
 	}
-	l = len(m.OutputError)
-	if l > 0 {
-		n += 1 + l + sovQuery(uint64(l))
-	}
-	l = len(m.PlaintextError)
-	if l > 0 {
-		n += 1 + l + sovQuery(uint64(l))
-	}
-	return n
-}
-
-func (m *QueryContractHistoryRequest) Size() (n int) {
-	if m == nil {
-		return 0
-	}
-	var l int
-	_ = l
-	l = len(m.ContractAddress)
-	if l > 0 {
-		n += 1 + l + sovQuery(uint64(l))
-	}
-	return n
-}
+	l = len(m.OutputError)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	l = len(m.PlaintextError)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	return n
+}
+
+func (m *QueryContractHistoryRequest) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = len(m.ContractAddress)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	return n
+}
+
+func (m *QueryContractHistoryResponse) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	if len(m.Entries) > 0 {
+		for _, e := range m.Entries {
+			l = e.Size()
+			n += 1 + l + sovQuery(uint64(l))
+		}
+	}
+	return n
+}
+
+func (m *QueryJobRequest) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	if m.JobId != 0 {
+		n += 1 + sovQuery(uint64(m.JobId))
+	}
+	return n
+}
+
+func (m *QueryJobResponse) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	if m.JobId != 0 {
+		n += 1 + sovQuery(uint64(m.JobId))
+	}
+	l = len(m.ContractAddress)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	l = len(m.HandlerCodeHash)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	l = len(m.SealedInput)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	return n
+}
+
+func (m *QueryRunJobResponse) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = len(m.Result)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	return n
+}
+
+func (m *QueryOpenQuerySessionRequest) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = len(m.Nonce)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	l = len(m.UserPublicKey)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	if m.ExpiryHeight != 0 {
+		n += 1 + sovQuery(uint64(m.ExpiryHeight))
+	}
+	return n
+}
+
+func (m *QueryOpenQuerySessionResponse) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = len(m.SessionId)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	return n
+}
+
+func (m *QueryExportContractStateRequest) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = len(m.ContractAddress)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	l = m.Authorization.Size()
+	n += 1 + l + sovQuery(uint64(l))
+	return n
+}
+
+func (m *QueryExportContractStateResponse) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	if len(m.Chunks) > 0 {
+		for _, b := range m.Chunks {
+			l = len(b)
+			n += 1 + l + sovQuery(uint64(l))
+		}
+	}
+	return n
+}
+
+func (m *QueryMempoolKeyRequest) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	if m.Height != 0 {
+		n += 1 + sovQuery(uint64(m.Height))
+	}
+	return n
+}
+
+func (m *QueryMempoolKeyResponse) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = len(m.PublicKey)
+	if l > 0 {
+		n += 1 + l + sovQuery(uint64(l))
+	}
+	return n
+}
+
+func sovQuery(x uint64) (n int) {
+	return (math_bits.Len64(x|1) + 6) / 7
+}
+func sozQuery(x uint64) (n int) {
+	return sovQuery(uint64((x << 1) ^ uint64((int64(x) >> 63))))
+}
+func (m *ParamsRequest) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowQuery
+			}
+			if iNdEx >= l {
+				return io.ErrUnexpectedEOF
+			}
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: ParamsRequest: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: ParamsRequest: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		default:
+			iNdEx = preIndex
+			skippy, err := skipQuery(dAtA[iNdEx:])
+			if err != nil {
+				return err
+			}
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if (iNdEx + skippy) > l {
+				return io.ErrUnexpectedEOF
+			}
+			iNdEx += skippy
+		}
+	}
+
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
+	}
+	return nil
+}
+func (m *ParamsResponse) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowQuery
+			}
+			if iNdEx >= l {
+				return io.ErrUnexpectedEOF
+			}
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: ParamsResponse: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: ParamsResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		case 1:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field Params", wireType)
+			}
+			var msglen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				msglen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if msglen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + msglen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			if err := m.Params.Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
+				return err
+			}
+			iNdEx = postIndex
+		default:
+			iNdEx = preIndex
+			skippy, err := skipQuery(dAtA[iNdEx:])
+			if err != nil {
+				return err
+			}
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if (iNdEx + skippy) > l {
+				return io.ErrUnexpectedEOF
+			}
+			iNdEx += skippy
+		}
+	}
+
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
+	}
+	return nil
+}
+func (m *QuerySecretContractRequest) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowQuery
+			}
+			if iNdEx >= l {
+				return io.ErrUnexpectedEOF
+			}
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: QuerySecretContractRequest: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: QuerySecretContractRequest: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		case 1:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field ContractAddress", wireType)
+			}
+			var stringLen uint64
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				stringLen |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.ContractAddress = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 2:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field Query", wireType)
+			}
+			var byteLen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				byteLen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if byteLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + byteLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.Query = append(m.Query[:0], dAtA[iNdEx:postIndex]...)
+			if m.Query == nil {
+				m.Query = []byte{}
+			}
+			iNdEx = postIndex
+		default:
+			iNdEx = preIndex
+			skippy, err := skipQuery(dAtA[iNdEx:])
+			if err != nil {
+				return err
+			}
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if (iNdEx + skippy) > l {
+				return io.ErrUnexpectedEOF
+			}
+			iNdEx += skippy
+		}
+	}
+
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
+	}
+	return nil
+}
+func (m *QueryByLabelRequest) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowQuery
+			}
+			if iNdEx >= l {
+				return io.ErrUnexpectedEOF
+			}
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: QueryByLabelRequest: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: QueryByLabelRequest: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		case 1:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field Label", wireType)
+			}
+			var stringLen uint64
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				stringLen |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.Label = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		default:
+			iNdEx = preIndex
+			skippy, err := skipQuery(dAtA[iNdEx:])
+			if err != nil {
+				return err
+			}
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if (iNdEx + skippy) > l {
+				return io.ErrUnexpectedEOF
+			}
+			iNdEx += skippy
+		}
+	}
+
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
+	}
+	return nil
+}
+func (m *QueryByContractAddressRequest) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowQuery
+			}
+			if iNdEx >= l {
+				return io.ErrUnexpectedEOF
+			}
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: QueryByContractAddressRequest: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: QueryByContractAddressRequest: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		case 1:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field ContractAddress", wireType)
+			}
+			var stringLen uint64
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				stringLen |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.ContractAddress = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		default:
+			iNdEx = preIndex
+			skippy, err := skipQuery(dAtA[iNdEx:])
+			if err != nil {
+				return err
+			}
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if (iNdEx + skippy) > l {
+				return io.ErrUnexpectedEOF
+			}
+			iNdEx += skippy
+		}
+	}
+
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
+	}
+	return nil
+}
+func (m *QueryByCodeIdRequest) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowQuery
+			}
+			if iNdEx >= l {
+				return io.ErrUnexpectedEOF
+			}
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: QueryByCodeIdRequest: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: QueryByCodeIdRequest: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		case 1:
+			if wireType != 0 {
+				return fmt.Errorf("proto: wrong wireType = %d for field CodeId", wireType)
+			}
+			m.CodeId = 0
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				m.CodeId |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+		default:
+			iNdEx = preIndex
+			skippy, err := skipQuery(dAtA[iNdEx:])
+			if err != nil {
+				return err
+			}
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if (iNdEx + skippy) > l {
+				return io.ErrUnexpectedEOF
+			}
+			iNdEx += skippy
+		}
+	}
+
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
+	}
+	return nil
+}
+func (m *QuerySecretContractResponse) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowQuery
+			}
+			if iNdEx >= l {
+				return io.ErrUnexpectedEOF
+			}
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: QuerySecretContractResponse: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: QuerySecretContractResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		case 1:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field Data", wireType)
+			}
+			var byteLen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				byteLen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if byteLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + byteLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.Data = append(m.Data[:0], dAtA[iNdEx:postIndex]...)
+			if m.Data == nil {
+				m.Data = []byte{}
+			}
+			iNdEx = postIndex
+		default:
+			iNdEx = preIndex
+			skippy, err := skipQuery(dAtA[iNdEx:])
+			if err != nil {
+				return err
+			}
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if (iNdEx + skippy) > l {
+				return io.ErrUnexpectedEOF
+			}
+			iNdEx += skippy
+		}
+	}
+
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
+	}
+	return nil
+}
+func (m *QueryContractInfoResponse) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowQuery
+			}
+			if iNdEx >= l {
+				return io.ErrUnexpectedEOF
+			}
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: QueryContractInfoResponse: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: QueryContractInfoResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		case 1:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field ContractAddress", wireType)
+			}
+			var stringLen uint64
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				stringLen |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.ContractAddress = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 2:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field ContractInfo", wireType)
+			}
+			var msglen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				msglen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if msglen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + msglen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			if m.ContractInfo == nil {
+				m.ContractInfo = &ContractInfo{}
+			}
+			if err := m.ContractInfo.Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
+				return err
+			}
+			iNdEx = postIndex
+		default:
+			iNdEx = preIndex
+			skippy, err := skipQuery(dAtA[iNdEx:])
+			if err != nil {
+				return err
+			}
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if (iNdEx + skippy) > l {
+				return io.ErrUnexpectedEOF
+			}
+			iNdEx += skippy
+		}
+	}
+
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
+	}
+	return nil
+}
+func (m *ContractInfoWithAddress) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowQuery
+			}
+			if iNdEx >= l {
+				return io.ErrUnexpectedEOF
+			}
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: ContractInfoWithAddress: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: ContractInfoWithAddress: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		case 1:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field ContractAddress", wireType)
+			}
+			var stringLen uint64
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				stringLen |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.ContractAddress = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 2:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field ContractInfo", wireType)
+			}
+			var msglen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				msglen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if msglen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + msglen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			if m.ContractInfo == nil {
+				m.ContractInfo = &ContractInfo{}
+			}
+			if err := m.ContractInfo.Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
+				return err
+			}
+			iNdEx = postIndex
+		default:
+			iNdEx = preIndex
+			skippy, err := skipQuery(dAtA[iNdEx:])
+			if err != nil {
+				return err
+			}
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if (iNdEx + skippy) > l {
+				return io.ErrUnexpectedEOF
+			}
+			iNdEx += skippy
+		}
+	}
 
-func (m *QueryContractHistoryResponse) Size() (n int) {
-	if m == nil {
-		return 0
-	}
-	var l int
-	_ = l
-	if len(m.Entries) > 0 {
-		for _, e := range m.Entries {
-			l = e.Size()
-			n += 1 + l + sovQuery(uint64(l))
-		}
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
 	}
-	return n
-}
-
-func sovQuery(x uint64) (n int) {
-	return (math_bits.Len64(x|1) + 6) / 7
-}
-func sozQuery(x uint64) (n int) {
-	return sovQuery(uint64((x << 1) ^ uint64((int64(x) >> 63))))
+	return nil
 }
-func (m *ParamsRequest) Unmarshal(dAtA []byte) error {
+func (m *QueryContractsByCodeIdResponse) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -2872,12 +4944,46 @@ func (m *ParamsRequest) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: ParamsRequest: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryContractsByCodeIdResponse: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: ParamsRequest: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryContractsByCodeIdResponse: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
+		case 1:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field ContractInfos", wireType)
+			}
+			var msglen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				msglen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if msglen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + msglen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.ContractInfos = append(m.ContractInfos, ContractInfoWithAddress{})
+			if err := m.ContractInfos[len(m.ContractInfos)-1].Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
+				return err
+			}
+			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
 			skippy, err := skipQuery(dAtA[iNdEx:])
@@ -2899,7 +5005,7 @@ func (m *ParamsRequest) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *ParamsResponse) Unmarshal(dAtA []byte) error {
+func (m *CodeInfoResponse) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -2922,17 +5028,36 @@ func (m *ParamsResponse) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: ParamsResponse: wiretype end group for non-group")
+			return fmt.Errorf("proto: CodeInfoResponse: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: ParamsResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: CodeInfoResponse: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
+			if wireType != 0 {
+				return fmt.Errorf("proto: wrong wireType = %d for field CodeId", wireType)
+			}
+			m.CodeId = 0
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				m.CodeId |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+		case 2:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Params", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field Creator", wireType)
 			}
-			var msglen int
+			var stringLen uint64
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -2942,24 +5067,119 @@ func (m *ParamsResponse) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				msglen |= int(b&0x7F) << shift
+				stringLen |= uint64(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			if msglen < 0 {
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + msglen
+			postIndex := iNdEx + intStringLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			if err := m.Params.Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
-				return err
+			m.Creator = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 3:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field CodeHash", wireType)
+			}
+			var stringLen uint64
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				stringLen |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.CodeHash = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 4:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field Source", wireType)
+			}
+			var stringLen uint64
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				stringLen |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.Source = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 5:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field Builder", wireType)
+			}
+			var stringLen uint64
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				stringLen |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
 			}
+			m.Builder = string(dAtA[iNdEx:postIndex])
 			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
@@ -2982,7 +5202,7 @@ func (m *ParamsResponse) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QuerySecretContractRequest) Unmarshal(dAtA []byte) error {
+func (m *QueryCodeResponse) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -3005,17 +5225,17 @@ func (m *QuerySecretContractRequest) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QuerySecretContractRequest: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryCodeResponse: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QuerySecretContractRequest: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryCodeResponse: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field ContractAddress", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field CodeInfoResponse", wireType)
 			}
-			var stringLen uint64
+			var msglen int
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -3025,27 +5245,31 @@ func (m *QuerySecretContractRequest) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
+				msglen |= int(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
+			if msglen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + intStringLen
+			postIndex := iNdEx + msglen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.ContractAddress = string(dAtA[iNdEx:postIndex])
+			if m.CodeInfoResponse == nil {
+				m.CodeInfoResponse = &CodeInfoResponse{}
+			}
+			if err := m.CodeInfoResponse.Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
+				return err
+			}
 			iNdEx = postIndex
 		case 2:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Query", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field Wasm", wireType)
 			}
 			var byteLen int
 			for shift := uint(0); ; shift += 7 {
@@ -3072,9 +5296,9 @@ func (m *QuerySecretContractRequest) Unmarshal(dAtA []byte) error {
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.Query = append(m.Query[:0], dAtA[iNdEx:postIndex]...)
-			if m.Query == nil {
-				m.Query = []byte{}
+			m.Wasm = append(m.Wasm[:0], dAtA[iNdEx:postIndex]...)
+			if m.Wasm == nil {
+				m.Wasm = []byte{}
 			}
 			iNdEx = postIndex
 		default:
@@ -3098,7 +5322,7 @@ func (m *QuerySecretContractRequest) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QueryByLabelRequest) Unmarshal(dAtA []byte) error {
+func (m *QueryCodesResponse) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -3121,17 +5345,17 @@ func (m *QueryByLabelRequest) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QueryByLabelRequest: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryCodesResponse: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QueryByLabelRequest: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryCodesResponse: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Label", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field CodeInfos", wireType)
 			}
-			var stringLen uint64
+			var msglen int
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -3141,23 +5365,25 @@ func (m *QueryByLabelRequest) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
+				msglen |= int(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
+			if msglen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + intStringLen
+			postIndex := iNdEx + msglen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.Label = string(dAtA[iNdEx:postIndex])
+			m.CodeInfos = append(m.CodeInfos, CodeInfoResponse{})
+			if err := m.CodeInfos[len(m.CodeInfos)-1].Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
+				return err
+			}
 			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
@@ -3180,7 +5406,7 @@ func (m *QueryByLabelRequest) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QueryByContractAddressRequest) Unmarshal(dAtA []byte) error {
+func (m *QueryContractAddressResponse) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -3203,10 +5429,10 @@ func (m *QueryByContractAddressRequest) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QueryByContractAddressRequest: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryContractAddressResponse: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QueryByContractAddressRequest: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryContractAddressResponse: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
@@ -3262,7 +5488,7 @@ func (m *QueryByContractAddressRequest) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QueryByCodeIdRequest) Unmarshal(dAtA []byte) error {
+func (m *QueryContractLabelResponse) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -3285,17 +5511,17 @@ func (m *QueryByCodeIdRequest) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QueryByCodeIdRequest: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryContractLabelResponse: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QueryByCodeIdRequest: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryContractLabelResponse: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
-			if wireType != 0 {
-				return fmt.Errorf("proto: wrong wireType = %d for field CodeId", wireType)
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field Label", wireType)
 			}
-			m.CodeId = 0
+			var stringLen uint64
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -3305,11 +5531,24 @@ func (m *QueryByCodeIdRequest) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				m.CodeId |= uint64(b&0x7F) << shift
+				stringLen |= uint64(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.Label = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
 			skippy, err := skipQuery(dAtA[iNdEx:])
@@ -3331,7 +5570,7 @@ func (m *QueryByCodeIdRequest) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QuerySecretContractResponse) Unmarshal(dAtA []byte) error {
+func (m *QueryCodeHashResponse) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -3354,17 +5593,17 @@ func (m *QuerySecretContractResponse) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QuerySecretContractResponse: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryCodeHashResponse: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QuerySecretContractResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryCodeHashResponse: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Data", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field CodeHash", wireType)
 			}
-			var byteLen int
+			var stringLen uint64
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -3374,25 +5613,23 @@ func (m *QuerySecretContractResponse) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				byteLen |= int(b&0x7F) << shift
+				stringLen |= uint64(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			if byteLen < 0 {
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + byteLen
+			postIndex := iNdEx + intStringLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.Data = append(m.Data[:0], dAtA[iNdEx:postIndex]...)
-			if m.Data == nil {
-				m.Data = []byte{}
-			}
+			m.CodeHash = string(dAtA[iNdEx:postIndex])
 			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
@@ -3415,7 +5652,7 @@ func (m *QuerySecretContractResponse) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QueryContractInfoResponse) Unmarshal(dAtA []byte) error {
+func (m *DecryptedAnswer) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -3438,15 +5675,15 @@ func (m *QueryContractInfoResponse) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QueryContractInfoResponse: wiretype end group for non-group")
+			return fmt.Errorf("proto: DecryptedAnswer: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QueryContractInfoResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: DecryptedAnswer: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field ContractAddress", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field Type", wireType)
 			}
 			var stringLen uint64
 			for shift := uint(0); ; shift += 7 {
@@ -3474,97 +5711,11 @@ func (m *QueryContractInfoResponse) Unmarshal(dAtA []byte) error {
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.ContractAddress = string(dAtA[iNdEx:postIndex])
+			m.Type = string(dAtA[iNdEx:postIndex])
 			iNdEx = postIndex
 		case 2:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field ContractInfo", wireType)
-			}
-			var msglen int
-			for shift := uint(0); ; shift += 7 {
-				if shift >= 64 {
-					return ErrIntOverflowQuery
-				}
-				if iNdEx >= l {
-					return io.ErrUnexpectedEOF
-				}
-				b := dAtA[iNdEx]
-				iNdEx++
-				msglen |= int(b&0x7F) << shift
-				if b < 0x80 {
-					break
-				}
-			}
-			if msglen < 0 {
-				return ErrInvalidLengthQuery
-			}
-			postIndex := iNdEx + msglen
-			if postIndex < 0 {
-				return ErrInvalidLengthQuery
-			}
-			if postIndex > l {
-				return io.ErrUnexpectedEOF
-			}
-			if m.ContractInfo == nil {
-				m.ContractInfo = &ContractInfo{}
-			}
-			if err := m.ContractInfo.Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
-				return err
-			}
-			iNdEx = postIndex
-		default:
-			iNdEx = preIndex
-			skippy, err := skipQuery(dAtA[iNdEx:])
-			if err != nil {
-				return err
-			}
-			if (skippy < 0) || (iNdEx+skippy) < 0 {
-				return ErrInvalidLengthQuery
-			}
-			if (iNdEx + skippy) > l {
-				return io.ErrUnexpectedEOF
-			}
-			iNdEx += skippy
-		}
-	}
-
-	if iNdEx > l {
-		return io.ErrUnexpectedEOF
-	}
-	return nil
-}
-func (m *ContractInfoWithAddress) Unmarshal(dAtA []byte) error {
-	l := len(dAtA)
-	iNdEx := 0
-	for iNdEx < l {
-		preIndex := iNdEx
-		var wire uint64
-		for shift := uint(0); ; shift += 7 {
-			if shift >= 64 {
-				return ErrIntOverflowQuery
-			}
-			if iNdEx >= l {
-				return io.ErrUnexpectedEOF
-			}
-			b := dAtA[iNdEx]
-			iNdEx++
-			wire |= uint64(b&0x7F) << shift
-			if b < 0x80 {
-				break
-			}
-		}
-		fieldNum := int32(wire >> 3)
-		wireType := int(wire & 0x7)
-		if wireType == 4 {
-			return fmt.Errorf("proto: ContractInfoWithAddress: wiretype end group for non-group")
-		}
-		if fieldNum <= 0 {
-			return fmt.Errorf("proto: ContractInfoWithAddress: illegal tag %d (wire type %d)", fieldNum, wire)
-		}
-		switch fieldNum {
-		case 1:
-			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field ContractAddress", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field Input", wireType)
 			}
 			var stringLen uint64
 			for shift := uint(0); ; shift += 7 {
@@ -3592,13 +5743,13 @@ func (m *ContractInfoWithAddress) Unmarshal(dAtA []byte) error {
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.ContractAddress = string(dAtA[iNdEx:postIndex])
+			m.Input = string(dAtA[iNdEx:postIndex])
 			iNdEx = postIndex
-		case 2:
+		case 3:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field ContractInfo", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field OutputData", wireType)
 			}
-			var msglen int
+			var stringLen uint64
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -3608,83 +5759,29 @@ func (m *ContractInfoWithAddress) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				msglen |= int(b&0x7F) << shift
+				stringLen |= uint64(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			if msglen < 0 {
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + msglen
+			postIndex := iNdEx + intStringLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			if m.ContractInfo == nil {
-				m.ContractInfo = &ContractInfo{}
-			}
-			if err := m.ContractInfo.Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
-				return err
-			}
+			m.OutputData = string(dAtA[iNdEx:postIndex])
 			iNdEx = postIndex
-		default:
-			iNdEx = preIndex
-			skippy, err := skipQuery(dAtA[iNdEx:])
-			if err != nil {
-				return err
-			}
-			if (skippy < 0) || (iNdEx+skippy) < 0 {
-				return ErrInvalidLengthQuery
-			}
-			if (iNdEx + skippy) > l {
-				return io.ErrUnexpectedEOF
-			}
-			iNdEx += skippy
-		}
-	}
-
-	if iNdEx > l {
-		return io.ErrUnexpectedEOF
-	}
-	return nil
-}
-func (m *QueryContractsByCodeIdResponse) Unmarshal(dAtA []byte) error {
-	l := len(dAtA)
-	iNdEx := 0
-	for iNdEx < l {
-		preIndex := iNdEx
-		var wire uint64
-		for shift := uint(0); ; shift += 7 {
-			if shift >= 64 {
-				return ErrIntOverflowQuery
-			}
-			if iNdEx >= l {
-				return io.ErrUnexpectedEOF
-			}
-			b := dAtA[iNdEx]
-			iNdEx++
-			wire |= uint64(b&0x7F) << shift
-			if b < 0x80 {
-				break
-			}
-		}
-		fieldNum := int32(wire >> 3)
-		wireType := int(wire & 0x7)
-		if wireType == 4 {
-			return fmt.Errorf("proto: QueryContractsByCodeIdResponse: wiretype end group for non-group")
-		}
-		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QueryContractsByCodeIdResponse: illegal tag %d (wire type %d)", fieldNum, wire)
-		}
-		switch fieldNum {
-		case 1:
+		case 4:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field ContractInfos", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field OutputDataAsString", wireType)
 			}
-			var msglen int
+			var stringLen uint64
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -3694,25 +5791,23 @@ func (m *QueryContractsByCodeIdResponse) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				msglen |= int(b&0x7F) << shift
+				stringLen |= uint64(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			if msglen < 0 {
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + msglen
+			postIndex := iNdEx + intStringLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.ContractInfos = append(m.ContractInfos, ContractInfoWithAddress{})
-			if err := m.ContractInfos[len(m.ContractInfos)-1].Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
-				return err
-			}
+			m.OutputDataAsString = string(dAtA[iNdEx:postIndex])
 			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
@@ -3735,7 +5830,7 @@ func (m *QueryContractsByCodeIdResponse) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *CodeInfoResponse) Unmarshal(dAtA []byte) error {
+func (m *DecryptedAnswers) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -3758,36 +5853,17 @@ func (m *CodeInfoResponse) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: CodeInfoResponse: wiretype end group for non-group")
+			return fmt.Errorf("proto: DecryptedAnswers: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: CodeInfoResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: DecryptedAnswers: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
-			if wireType != 0 {
-				return fmt.Errorf("proto: wrong wireType = %d for field CodeId", wireType)
-			}
-			m.CodeId = 0
-			for shift := uint(0); ; shift += 7 {
-				if shift >= 64 {
-					return ErrIntOverflowQuery
-				}
-				if iNdEx >= l {
-					return io.ErrUnexpectedEOF
-				}
-				b := dAtA[iNdEx]
-				iNdEx++
-				m.CodeId |= uint64(b&0x7F) << shift
-				if b < 0x80 {
-					break
-				}
-			}
-		case 2:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Creator", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field Answers", wireType)
 			}
-			var stringLen uint64
+			var msglen int
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -3797,29 +5873,31 @@ func (m *CodeInfoResponse) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
+				msglen |= int(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
+			if msglen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + intStringLen
+			postIndex := iNdEx + msglen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.Creator = string(dAtA[iNdEx:postIndex])
+			m.Answers = append(m.Answers, &DecryptedAnswer{})
+			if err := m.Answers[len(m.Answers)-1].Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
+				return err
+			}
 			iNdEx = postIndex
-		case 3:
+		case 2:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field CodeHash", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field OutputLogs", wireType)
 			}
-			var stringLen uint64
+			var msglen int
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -3829,27 +5907,29 @@ func (m *CodeInfoResponse) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
+				msglen |= int(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
+			if msglen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + intStringLen
+			postIndex := iNdEx + msglen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.CodeHash = string(dAtA[iNdEx:postIndex])
+			m.OutputLogs = append(m.OutputLogs, types.StringEvent{})
+			if err := m.OutputLogs[len(m.OutputLogs)-1].Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
+				return err
+			}
 			iNdEx = postIndex
-		case 4:
+		case 3:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Source", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field OutputError", wireType)
 			}
 			var stringLen uint64
 			for shift := uint(0); ; shift += 7 {
@@ -3877,11 +5957,11 @@ func (m *CodeInfoResponse) Unmarshal(dAtA []byte) error {
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.Source = string(dAtA[iNdEx:postIndex])
+			m.OutputError = string(dAtA[iNdEx:postIndex])
 			iNdEx = postIndex
-		case 5:
+		case 4:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Builder", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field PlaintextError", wireType)
 			}
 			var stringLen uint64
 			for shift := uint(0); ; shift += 7 {
@@ -3909,7 +5989,7 @@ func (m *CodeInfoResponse) Unmarshal(dAtA []byte) error {
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.Builder = string(dAtA[iNdEx:postIndex])
+			m.PlaintextError = string(dAtA[iNdEx:postIndex])
 			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
@@ -3932,7 +6012,7 @@ func (m *CodeInfoResponse) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QueryCodeResponse) Unmarshal(dAtA []byte) error {
+func (m *QueryContractHistoryRequest) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -3955,53 +6035,17 @@ func (m *QueryCodeResponse) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QueryCodeResponse: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryContractHistoryRequest: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QueryCodeResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryContractHistoryRequest: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field CodeInfoResponse", wireType)
-			}
-			var msglen int
-			for shift := uint(0); ; shift += 7 {
-				if shift >= 64 {
-					return ErrIntOverflowQuery
-				}
-				if iNdEx >= l {
-					return io.ErrUnexpectedEOF
-				}
-				b := dAtA[iNdEx]
-				iNdEx++
-				msglen |= int(b&0x7F) << shift
-				if b < 0x80 {
-					break
-				}
-			}
-			if msglen < 0 {
-				return ErrInvalidLengthQuery
-			}
-			postIndex := iNdEx + msglen
-			if postIndex < 0 {
-				return ErrInvalidLengthQuery
-			}
-			if postIndex > l {
-				return io.ErrUnexpectedEOF
-			}
-			if m.CodeInfoResponse == nil {
-				m.CodeInfoResponse = &CodeInfoResponse{}
-			}
-			if err := m.CodeInfoResponse.Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
-				return err
-			}
-			iNdEx = postIndex
-		case 2:
-			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Wasm", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field ContractAddress", wireType)
 			}
-			var byteLen int
+			var stringLen uint64
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -4011,25 +6055,23 @@ func (m *QueryCodeResponse) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				byteLen |= int(b&0x7F) << shift
+				stringLen |= uint64(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			if byteLen < 0 {
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + byteLen
+			postIndex := iNdEx + intStringLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.Wasm = append(m.Wasm[:0], dAtA[iNdEx:postIndex]...)
-			if m.Wasm == nil {
-				m.Wasm = []byte{}
-			}
+			m.ContractAddress = string(dAtA[iNdEx:postIndex])
 			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
@@ -4052,7 +6094,7 @@ func (m *QueryCodeResponse) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QueryCodesResponse) Unmarshal(dAtA []byte) error {
+func (m *QueryContractHistoryResponse) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -4075,15 +6117,15 @@ func (m *QueryCodesResponse) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QueryCodesResponse: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryContractHistoryResponse: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QueryCodesResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryContractHistoryResponse: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field CodeInfos", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field Entries", wireType)
 			}
 			var msglen int
 			for shift := uint(0); ; shift += 7 {
@@ -4110,8 +6152,8 @@ func (m *QueryCodesResponse) Unmarshal(dAtA []byte) error {
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.CodeInfos = append(m.CodeInfos, CodeInfoResponse{})
-			if err := m.CodeInfos[len(m.CodeInfos)-1].Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
+			m.Entries = append(m.Entries, ContractCodeHistoryEntry{})
+			if err := m.Entries[len(m.Entries)-1].Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
 				return err
 			}
 			iNdEx = postIndex
@@ -4136,7 +6178,7 @@ func (m *QueryCodesResponse) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QueryContractAddressResponse) Unmarshal(dAtA []byte) error {
+func (m *QueryJobRequest) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -4159,17 +6201,17 @@ func (m *QueryContractAddressResponse) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QueryContractAddressResponse: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryJobRequest: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QueryContractAddressResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryJobRequest: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
-			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field ContractAddress", wireType)
+			if wireType != 0 {
+				return fmt.Errorf("proto: wrong wireType = %d for field JobId", wireType)
 			}
-			var stringLen uint64
+			m.JobId = 0
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -4179,24 +6221,11 @@ func (m *QueryContractAddressResponse) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
+				m.JobId |= uint64(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
-				return ErrInvalidLengthQuery
-			}
-			postIndex := iNdEx + intStringLen
-			if postIndex < 0 {
-				return ErrInvalidLengthQuery
-			}
-			if postIndex > l {
-				return io.ErrUnexpectedEOF
-			}
-			m.ContractAddress = string(dAtA[iNdEx:postIndex])
-			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
 			skippy, err := skipQuery(dAtA[iNdEx:])
@@ -4218,7 +6247,7 @@ func (m *QueryContractAddressResponse) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QueryContractLabelResponse) Unmarshal(dAtA []byte) error {
+func (m *QueryJobResponse) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -4241,15 +6270,66 @@ func (m *QueryContractLabelResponse) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QueryContractLabelResponse: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryJobResponse: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QueryContractLabelResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryJobResponse: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
+			if wireType != 0 {
+				return fmt.Errorf("proto: wrong wireType = %d for field JobId", wireType)
+			}
+			m.JobId = 0
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				m.JobId |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+		case 2:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Label", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field ContractAddress", wireType)
+			}
+			var stringLen uint64
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				stringLen |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.ContractAddress = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 3:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field HandlerCodeHash", wireType)
 			}
 			var stringLen uint64
 			for shift := uint(0); ; shift += 7 {
@@ -4266,18 +6346,52 @@ func (m *QueryContractLabelResponse) Unmarshal(dAtA []byte) error {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthQuery
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthQuery
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.HandlerCodeHash = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 4:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field SealedInput", wireType)
+			}
+			var byteLen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowQuery
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				byteLen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if byteLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + intStringLen
+			postIndex := iNdEx + byteLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.Label = string(dAtA[iNdEx:postIndex])
+			m.SealedInput = append(m.SealedInput[:0], dAtA[iNdEx:postIndex]...)
+			if m.SealedInput == nil {
+				m.SealedInput = []byte{}
+			}
 			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
@@ -4300,7 +6414,7 @@ func (m *QueryContractLabelResponse) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QueryCodeHashResponse) Unmarshal(dAtA []byte) error {
+func (m *QueryRunJobResponse) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -4323,17 +6437,17 @@ func (m *QueryCodeHashResponse) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QueryCodeHashResponse: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryRunJobResponse: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QueryCodeHashResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryRunJobResponse: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field CodeHash", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field Result", wireType)
 			}
-			var stringLen uint64
+			var byteLen int
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -4343,23 +6457,25 @@ func (m *QueryCodeHashResponse) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
+				byteLen |= int(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
+			if byteLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + intStringLen
+			postIndex := iNdEx + byteLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.CodeHash = string(dAtA[iNdEx:postIndex])
+			m.Result = append(m.Result[:0], dAtA[iNdEx:postIndex]...)
+			if m.Result == nil {
+				m.Result = []byte{}
+			}
 			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
@@ -4382,7 +6498,7 @@ func (m *QueryCodeHashResponse) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *DecryptedAnswer) Unmarshal(dAtA []byte) error {
+func (m *QueryOpenQuerySessionRequest) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -4405,17 +6521,17 @@ func (m *DecryptedAnswer) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: DecryptedAnswer: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryOpenQuerySessionRequest: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: DecryptedAnswer: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryOpenQuerySessionRequest: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Type", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field Nonce", wireType)
 			}
-			var stringLen uint64
+			var byteLen int
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -4425,29 +6541,31 @@ func (m *DecryptedAnswer) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
+				byteLen |= int(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
+			if byteLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + intStringLen
+			postIndex := iNdEx + byteLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.Type = string(dAtA[iNdEx:postIndex])
+			m.Nonce = append(m.Nonce[:0], dAtA[iNdEx:postIndex]...)
+			if m.Nonce == nil {
+				m.Nonce = []byte{}
+			}
 			iNdEx = postIndex
 		case 2:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Input", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field UserPublicKey", wireType)
 			}
-			var stringLen uint64
+			var byteLen int
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -4457,29 +6575,31 @@ func (m *DecryptedAnswer) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
+				byteLen |= int(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
+			if byteLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + intStringLen
+			postIndex := iNdEx + byteLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.Input = string(dAtA[iNdEx:postIndex])
+			m.UserPublicKey = append(m.UserPublicKey[:0], dAtA[iNdEx:postIndex]...)
+			if m.UserPublicKey == nil {
+				m.UserPublicKey = []byte{}
+			}
 			iNdEx = postIndex
 		case 3:
-			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field OutputData", wireType)
+			if wireType != 0 {
+				return fmt.Errorf("proto: wrong wireType = %d for field ExpiryHeight", wireType)
 			}
-			var stringLen uint64
+			m.ExpiryHeight = 0
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -4489,29 +6609,66 @@ func (m *DecryptedAnswer) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
+				m.ExpiryHeight |= uint64(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
-				return ErrInvalidLengthQuery
+		default:
+			iNdEx = preIndex
+			skippy, err := skipQuery(dAtA[iNdEx:])
+			if err != nil {
+				return err
 			}
-			postIndex := iNdEx + intStringLen
-			if postIndex < 0 {
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
 				return ErrInvalidLengthQuery
 			}
-			if postIndex > l {
+			if (iNdEx + skippy) > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.OutputData = string(dAtA[iNdEx:postIndex])
-			iNdEx = postIndex
-		case 4:
+			iNdEx += skippy
+		}
+	}
+
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
+	}
+	return nil
+}
+func (m *QueryOpenQuerySessionResponse) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowQuery
+			}
+			if iNdEx >= l {
+				return io.ErrUnexpectedEOF
+			}
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: QueryOpenQuerySessionResponse: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: QueryOpenQuerySessionResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		case 1:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field OutputDataAsString", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field SessionId", wireType)
 			}
-			var stringLen uint64
+			var byteLen int
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -4521,23 +6678,25 @@ func (m *DecryptedAnswer) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
+				byteLen |= int(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
+			if byteLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + intStringLen
+			postIndex := iNdEx + byteLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.OutputDataAsString = string(dAtA[iNdEx:postIndex])
+			m.SessionId = append(m.SessionId[:0], dAtA[iNdEx:postIndex]...)
+			if m.SessionId == nil {
+				m.SessionId = []byte{}
+			}
 			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
@@ -4560,7 +6719,7 @@ func (m *DecryptedAnswer) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *DecryptedAnswers) Unmarshal(dAtA []byte) error {
+func (m *QueryExportContractStateRequest) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -4583,17 +6742,17 @@ func (m *DecryptedAnswers) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: DecryptedAnswers: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryExportContractStateRequest: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: DecryptedAnswers: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryExportContractStateRequest: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Answers", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field ContractAddress", wireType)
 			}
-			var msglen int
+			var stringLen uint64
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -4603,29 +6762,27 @@ func (m *DecryptedAnswers) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				msglen |= int(b&0x7F) << shift
+				stringLen |= uint64(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			if msglen < 0 {
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + msglen
+			postIndex := iNdEx + intStringLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.Answers = append(m.Answers, &DecryptedAnswer{})
-			if err := m.Answers[len(m.Answers)-1].Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
-				return err
-			}
+			m.ContractAddress = string(dAtA[iNdEx:postIndex])
 			iNdEx = postIndex
 		case 2:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field OutputLogs", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field Authorization", wireType)
 			}
 			var msglen int
 			for shift := uint(0); ; shift += 7 {
@@ -4652,48 +6809,65 @@ func (m *DecryptedAnswers) Unmarshal(dAtA []byte) error {
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.OutputLogs = append(m.OutputLogs, types.StringEvent{})
-			if err := m.OutputLogs[len(m.OutputLogs)-1].Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
+			if err := m.Authorization.Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
 				return err
 			}
 			iNdEx = postIndex
-		case 3:
-			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field OutputError", wireType)
-			}
-			var stringLen uint64
-			for shift := uint(0); ; shift += 7 {
-				if shift >= 64 {
-					return ErrIntOverflowQuery
-				}
-				if iNdEx >= l {
-					return io.ErrUnexpectedEOF
-				}
-				b := dAtA[iNdEx]
-				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
-				if b < 0x80 {
-					break
-				}
+		default:
+			iNdEx = preIndex
+			skippy, err := skipQuery(dAtA[iNdEx:])
+			if err != nil {
+				return err
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + intStringLen
-			if postIndex < 0 {
-				return ErrInvalidLengthQuery
+			if (iNdEx + skippy) > l {
+				return io.ErrUnexpectedEOF
 			}
-			if postIndex > l {
+			iNdEx += skippy
+		}
+	}
+
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
+	}
+	return nil
+}
+func (m *QueryExportContractStateResponse) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowQuery
+			}
+			if iNdEx >= l {
 				return io.ErrUnexpectedEOF
 			}
-			m.OutputError = string(dAtA[iNdEx:postIndex])
-			iNdEx = postIndex
-		case 4:
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: QueryExportContractStateResponse: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: QueryExportContractStateResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		case 1:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field PlaintextError", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field Chunks", wireType)
 			}
-			var stringLen uint64
+			var byteLen int
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -4703,23 +6877,23 @@ func (m *DecryptedAnswers) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
+				byteLen |= int(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
+			if byteLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + intStringLen
+			postIndex := iNdEx + byteLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.PlaintextError = string(dAtA[iNdEx:postIndex])
+			m.Chunks = append(m.Chunks, make([]byte, postIndex-iNdEx))
+			copy(m.Chunks[len(m.Chunks)-1], dAtA[iNdEx:postIndex])
 			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
@@ -4742,7 +6916,7 @@ func (m *DecryptedAnswers) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QueryContractHistoryRequest) Unmarshal(dAtA []byte) error {
+func (m *QueryMempoolKeyRequest) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -4765,17 +6939,17 @@ func (m *QueryContractHistoryRequest) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QueryContractHistoryRequest: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryMempoolKeyRequest: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QueryContractHistoryRequest: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryMempoolKeyRequest: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
-			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field ContractAddress", wireType)
+			if wireType != 0 {
+				return fmt.Errorf("proto: wrong wireType = %d for field Height", wireType)
 			}
-			var stringLen uint64
+			m.Height = 0
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -4785,24 +6959,11 @@ func (m *QueryContractHistoryRequest) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				stringLen |= uint64(b&0x7F) << shift
+				m.Height |= uint64(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			intStringLen := int(stringLen)
-			if intStringLen < 0 {
-				return ErrInvalidLengthQuery
-			}
-			postIndex := iNdEx + intStringLen
-			if postIndex < 0 {
-				return ErrInvalidLengthQuery
-			}
-			if postIndex > l {
-				return io.ErrUnexpectedEOF
-			}
-			m.ContractAddress = string(dAtA[iNdEx:postIndex])
-			iNdEx = postIndex
 		default:
 			iNdEx = preIndex
 			skippy, err := skipQuery(dAtA[iNdEx:])
@@ -4824,7 +6985,7 @@ func (m *QueryContractHistoryRequest) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
-func (m *QueryContractHistoryResponse) Unmarshal(dAtA []byte) error {
+func (m *QueryMempoolKeyResponse) Unmarshal(dAtA []byte) error {
 	l := len(dAtA)
 	iNdEx := 0
 	for iNdEx < l {
@@ -4847,17 +7008,17 @@ func (m *QueryContractHistoryResponse) Unmarshal(dAtA []byte) error {
 		fieldNum := int32(wire >> 3)
 		wireType := int(wire & 0x7)
 		if wireType == 4 {
-			return fmt.Errorf("proto: QueryContractHistoryResponse: wiretype end group for non-group")
+			return fmt.Errorf("proto: QueryMempoolKeyResponse: wiretype end group for non-group")
 		}
 		if fieldNum <= 0 {
-			return fmt.Errorf("proto: QueryContractHistoryResponse: illegal tag %d (wire type %d)", fieldNum, wire)
+			return fmt.Errorf("proto: QueryMempoolKeyResponse: illegal tag %d (wire type %d)", fieldNum, wire)
 		}
 		switch fieldNum {
 		case 1:
 			if wireType != 2 {
-				return fmt.Errorf("proto: wrong wireType = %d for field Entries", wireType)
+				return fmt.Errorf("proto: wrong wireType = %d for field PublicKey", wireType)
 			}
-			var msglen int
+			var byteLen int
 			for shift := uint(0); ; shift += 7 {
 				if shift >= 64 {
 					return ErrIntOverflowQuery
@@ -4867,24 +7028,24 @@ func (m *QueryContractHistoryResponse) Unmarshal(dAtA []byte) error {
 				}
 				b := dAtA[iNdEx]
 				iNdEx++
-				msglen |= int(b&0x7F) << shift
+				byteLen |= int(b&0x7F) << shift
 				if b < 0x80 {
 					break
 				}
 			}
-			if msglen < 0 {
+			if byteLen < 0 {
 				return ErrInvalidLengthQuery
 			}
-			postIndex := iNdEx + msglen
+			postIndex := iNdEx + byteLen
 			if postIndex < 0 {
 				return ErrInvalidLengthQuery
 			}
 			if postIndex > l {
 				return io.ErrUnexpectedEOF
 			}
-			m.Entries = append(m.Entries, ContractCodeHistoryEntry{})
-			if err := m.Entries[len(m.Entries)-1].Unmarshal(dAtA[iNdEx:postIndex]); err != nil {
-				return err
+			m.PublicKey = append(m.PublicKey[:0], dAtA[iNdEx:postIndex]...)
+			if m.PublicKey == nil {
+				m.PublicKey = []byte{}
 			}
 			iNdEx = postIndex
 		default:
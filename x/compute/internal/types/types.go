@@ -245,6 +245,10 @@ type WasmConfig struct {
 	SmartQueryGasLimit uint64
 	CacheSize          uint64
 	EnclaveCacheSize   uint16
+	// Limits of queries from outside the chain, enforced in the enclave. 0 leaves the default.
+	QueryMemoryLimitPages uint32
+	QueryGasLimit         uint64
+	QueryTimeLimitMs      uint64
 	// It must always be true except the case when we create temporary app to
 	// extract autoCLIOpts from it
 	InitEnclave bool
@@ -310,6 +314,10 @@ func GetConfig(appOpts servertypes.AppOptions) *WasmConfig {
 		config.EnclaveCacheSize = enclaveCacheSize
 	}
 
+	config.QueryMemoryLimitPages = cast.ToUint32(appOpts.Get("wasm.query-memory-limit-pages"))
+	config.QueryGasLimit = cast.ToUint64(appOpts.Get("wasm.query-enclave-gas-limit"))
+	config.QueryTimeLimitMs = cast.ToUint64(appOpts.Get("wasm.query-time-limit-ms"))
+
 	return config
 }
 
@@ -326,6 +334,16 @@ contract-memory-cache-size = "{{ .WASMConfig.CacheSize }}"
 
 # The WASM VM memory cache size in number of cached modules. Can safely go up to 15, but not recommended for validators
 contract-memory-enclave-cache-size = "{{ .WASMConfig.EnclaveCacheSize }}"
+
+# Limits of queries from outside the chain (gRPC, REST), enforced inside the enclave.
+# They don't apply to queries that contracts make during txs, so they can be set
+# conservatively on validators without affecting block execution. 0 means no extra limit.
+# The most wasm pages (64 KiB each) a query can grow the contract's memory to (default 192)
+query-memory-limit-pages = "{{ .WASMConfig.QueryMemoryLimitPages }}"
+# The most gas a query can use inside the enclave
+query-enclave-gas-limit = "{{ .WASMConfig.QueryGasLimit }}"
+# How long a query can run, in milliseconds
+query-time-limit-ms = "{{ .WASMConfig.QueryTimeLimitMs }}"
 `
 
 // ZeroSender is a valid 20 byte canonical address that's used to bypass the x/compute checks
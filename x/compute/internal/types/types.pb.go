@@ -420,6 +420,52 @@ func (m *ContractCodeHistoryEntry) XXX_DiscardUnknown() {
 
 var xxx_messageInfo_ContractCodeHistoryEntry proto.InternalMessageInfo
 
+// StateBackupAuthorization is the admin's authorization to export or import the state of a
+// contract, see docs/contract-state-backup.md
+type StateBackupAuthorization struct {
+	// Contract is the contract exported from, or imported into
+	Contract string `protobuf:"bytes,1,opt,name=contract,proto3" json:"contract,omitempty"`
+	// ExpiresAtHeight is the last height the authorization can be used at
+	ExpiresAtHeight uint64 `protobuf:"varint,2,opt,name=expires_at_height,json=expiresAtHeight,proto3" json:"expires_at_height,omitempty"`
+	// AdminPublicKey is the compressed secp256k1 key of the contract's admin
+	AdminPublicKey []byte `protobuf:"bytes,3,opt,name=admin_public_key,json=adminPublicKey,proto3" json:"admin_public_key,omitempty"`
+	// AdminSignature is the admin's signature of the operation
+	AdminSignature []byte `protobuf:"bytes,4,opt,name=admin_signature,json=adminSignature,proto3" json:"admin_signature,omitempty"`
+}
+
+func (m *StateBackupAuthorization) Reset()         { *m = StateBackupAuthorization{} }
+func (m *StateBackupAuthorization) String() string { return proto.CompactTextString(m) }
+func (*StateBackupAuthorization) ProtoMessage()    {}
+func (*StateBackupAuthorization) Descriptor() ([]byte, []int) {
+	return fileDescriptor_8ba7f40a6d1951b3, []int{8}
+}
+func (m *StateBackupAuthorization) XXX_Unmarshal(b []byte) error {
+	return m.Unmarshal(b)
+}
+func (m *StateBackupAuthorization) XXX_Marshal(b []byte, deterministic bool) ([]byte, error) {
+	if deterministic {
+		return xxx_messageInfo_StateBackupAuthorization.Marshal(b, m, deterministic)
+	} else {
+		b = b[:cap(b)]
+		n, err := m.MarshalToSizedBuffer(b)
+		if err != nil {
+			return nil, err
+		}
+		return b[:n], nil
+	}
+}
+func (m *StateBackupAuthorization) XXX_Merge(src proto.Message) {
+	xxx_messageInfo_StateBackupAuthorization.Merge(m, src)
+}
+func (m *StateBackupAuthorization) XXX_Size() int {
+	return m.Size()
+}
+func (m *StateBackupAuthorization) XXX_DiscardUnknown() {
+	xxx_messageInfo_StateBackupAuthorization.DiscardUnknown(m)
+}
+
+var xxx_messageInfo_StateBackupAuthorization proto.InternalMessageInfo
+
 func init() {
 	proto.RegisterEnum("secret.compute.v1beta1.AccessType", AccessType_name, AccessType_value)
 	proto.RegisterEnum("secret.compute.v1beta1.ContractCodeHistoryOperationType", ContractCodeHistoryOperationType_name, ContractCodeHistoryOperationType_value)
@@ -431,6 +477,7 @@ func init() {
 	proto.RegisterType((*AbsoluteTxPosition)(nil), "secret.compute.v1beta1.AbsoluteTxPosition")
 	proto.RegisterType((*Model)(nil), "secret.compute.v1beta1.Model")
 	proto.RegisterType((*ContractCodeHistoryEntry)(nil), "secret.compute.v1beta1.ContractCodeHistoryEntry")
+	proto.RegisterType((*StateBackupAuthorization)(nil), "secret.compute.v1beta1.StateBackupAuthorization")
 }
 
 func init() {
@@ -438,73 +485,77 @@ func init() {
 }
 
 var fileDescriptor_8ba7f40a6d1951b3 = []byte{
-	// 1048 bytes of a gzipped FileDescriptorProto
-	0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0xac, 0x56, 0x4b, 0x6f, 0x1b, 0x45,
-	0x1c, 0xf7, 0xc6, 0x8e, 0x1d, 0x8f, 0xdd, 0xd6, 0x1a, 0x42, 0xeb, 0x1a, 0xc9, 0x36, 0x5b, 0x54,
-	0x4a, 0x43, 0xec, 0xa4, 0x70, 0x40, 0xe1, 0xe4, 0xc7, 0x26, 0x59, 0x42, 0xd6, 0xd6, 0xd8, 0x09,
-	0x0a, 0x02, 0xad, 0xf6, 0x31, 0xb1, 0x57, 0x59, 0xef, 0x58, 0x33, 0xe3, 0xe0, 0xbd, 0x71, 0x44,
-	0x3e, 0x71, 0xe4, 0x62, 0x09, 0x89, 0xaa, 0xea, 0x17, 0xe0, 0x0b, 0x70, 0xea, 0xb1, 0x47, 0x4e,
-	0x16, 0x38, 0x1f, 0x00, 0x89, 0x63, 0x4f, 0x68, 0xc7, 0xeb, 0xd8, 0xa5, 0x8d, 0x12, 0x24, 0x4e,
-	0xf9, 0x3f, 0x7f, 0xff, 0xd7, 0x6f, 0x27, 0x06, 0x32, 0xc3, 0x16, 0xc5, 0xbc, 0x6c, 0x91, 0x5e,
-	0x7f, 0xc0, 0x71, 0xf9, 0x7c, 0xdb, 0xc4, 0xdc, 0xd8, 0x2e, 0x73, 0xbf, 0x8f, 0x59, 0xa9, 0x4f,
-	0x09, 0x27, 0xf0, 0xee, 0x2c, 0xa6, 0x14, 0xc6, 0x94, 0xc2, 0x98, 0xdc, 0x7a, 0x87, 0x74, 0x88,
-	0x08, 0x29, 0x07, 0xd2, 0x2c, 0x5a, 0xb6, 0xc0, 0x9d, 0x8a, 0x65, 0x61, 0xc6, 0xda, 0x7e, 0x1f,
-	0x37, 0x0d, 0x6a, 0xf4, 0xe0, 0x17, 0x60, 0xf5, 0xdc, 0x70, 0x07, 0x38, 0x2b, 0x15, 0xa5, 0x47,
-	0xb7, 0x9f, 0xc8, 0xa5, 0xb7, 0x03, 0x96, 0x16, 0x79, 0xd5, 0xcc, 0xdf, 0x93, 0x42, 0xda, 0x37,
-	0x7a, 0xee, 0x8e, 0x2c, 0x52, 0x65, 0x34, 0x83, 0xd8, 0x89, 0xfd, 0xf4, 0x73, 0x41, 0x92, 0x9f,
-	0x49, 0x60, 0xad, 0x46, 0x6c, 0xac, 0x7a, 0xa7, 0x04, 0xbe, 0x07, 0x92, 0x16, 0xb1, 0xb1, 0xde,
-	0x35, 0x58, 0x57, 0x94, 0x48, 0xa3, 0xb5, 0xc0, 0xb0, 0x6f, 0xb0, 0x2e, 0x3c, 0x00, 0x09, 0x8b,
-	0x62, 0x83, 0x13, 0x9a, 0x5d, 0x09, 0x5c, 0xd5, 0xed, 0x57, 0x93, 0xc2, 0x66, 0xc7, 0xe1, 0xdd,
-	0x81, 0x19, 0x34, 0x50, 0xb6, 0x08, 0xeb, 0x11, 0x16, 0xfe, 0xd9, 0x64, 0xf6, 0x59, 0x38, 0x7b,
-	0xc5, 0xb2, 0x2a, 0xb6, 0x4d, 0x31, 0x63, 0x68, 0x8e, 0x00, 0xef, 0x82, 0x38, 0x23, 0x03, 0x6a,
-	0xe1, 0x6c, 0xb4, 0x28, 0x3d, 0x4a, 0xa2, 0x50, 0x83, 0x59, 0x90, 0x30, 0x07, 0x8e, 0x6b, 0x63,
-	0x9a, 0x8d, 0x09, 0xc7, 0x5c, 0x95, 0x9f, 0x4a, 0x20, 0x55, 0x23, 0x1e, 0xa7, 0x86, 0xc5, 0x0f,
-	0xb0, 0x0f, 0x1f, 0x82, 0x3b, 0xa4, 0xa3, 0x5b, 0xa1, 0x45, 0x3f, 0xc3, 0x7e, 0xd8, 0xf1, 0x2d,
-	0xd2, 0x59, 0x8e, 0xdb, 0x02, 0xeb, 0xd6, 0x80, 0x52, 0xec, 0xf1, 0xd7, 0x83, 0xc5, 0x0c, 0x08,
-	0x86, 0xbe, 0xe5, 0x8c, 0xcf, 0x41, 0xee, 0x6d, 0x19, 0x7a, 0x9f, 0x12, 0x72, 0x2a, 0xfa, 0x4d,
-	0xa3, 0x7b, 0x6f, 0xe6, 0x35, 0x03, 0xb7, 0xfc, 0xbd, 0x04, 0xe0, 0xdc, 0x58, 0x1b, 0x30, 0x4e,
-	0x7a, 0x62, 0xb3, 0x6d, 0x90, 0xc2, 0x9e, 0xe5, 0x1a, 0xe7, 0xf8, 0xb2, 0xd3, 0xd4, 0x93, 0x07,
-	0x57, 0x9d, 0x6f, 0x09, 0xb5, 0x7a, 0x7b, 0x3a, 0x29, 0x00, 0x65, 0x96, 0x7b, 0x80, 0x7d, 0x04,
-	0xf0, 0xa5, 0x0c, 0xd7, 0xc1, 0xaa, 0x6b, 0x98, 0xd8, 0x15, 0xc3, 0x24, 0xd1, 0x4c, 0x91, 0x7f,
-	0x5b, 0x01, 0xe9, 0x39, 0x82, 0x28, 0xfe, 0x00, 0x24, 0xc4, 0x59, 0x1d, 0x5b, 0x14, 0x8e, 0x55,
-	0xc1, 0x74, 0x52, 0x88, 0x8b, 0xab, 0xd7, 0x51, 0x3c, 0x70, 0xa9, 0xf6, 0xff, 0x7b, 0xde, 0xcb,
-	0xc6, 0x62, 0x4b, 0x8d, 0xc1, 0x7a, 0x58, 0x02, 0xdb, 0xd9, 0x55, 0xb1, 0x80, 0xc7, 0x57, 0xf2,
-	0xd7, 0x64, 0xc4, 0x1d, 0x70, 0xdc, 0x1e, 0x36, 0x09, 0x73, 0xb8, 0x43, 0x3c, 0x34, 0x4f, 0x85,
-	0x9b, 0x20, 0xe5, 0x98, 0x96, 0xde, 0x27, 0x94, 0x07, 0x13, 0xc5, 0x83, 0x0a, 0xd5, 0x5b, 0xd3,
-	0x49, 0x21, 0xa9, 0x56, 0x6b, 0x4d, 0x42, 0xb9, 0x5a, 0x47, 0x49, 0xc7, 0xb4, 0x84, 0x68, 0x07,
-	0xad, 0x18, 0x76, 0xcf, 0xf1, 0xb2, 0x89, 0x59, 0x2b, 0x42, 0x81, 0x05, 0x90, 0x12, 0x42, 0x78,
-	0xd4, 0x35, 0x71, 0x54, 0x20, 0x4c, 0xb3, 0x3b, 0x22, 0x00, 0xdf, 0x6c, 0x02, 0xbe, 0x0f, 0xd2,
-	0xa6, 0x4b, 0xac, 0x33, 0xbd, 0x8b, 0x9d, 0x4e, 0x97, 0x8b, 0x75, 0x46, 0x51, 0x4a, 0xd8, 0xf6,
-	0x85, 0x09, 0xde, 0x07, 0x6b, 0x7c, 0xa8, 0x3b, 0x9e, 0x8d, 0x87, 0x62, 0x91, 0x31, 0x94, 0xe0,
-	0x43, 0x35, 0x50, 0x65, 0x0c, 0x56, 0x0f, 0x89, 0x8d, 0x5d, 0xb8, 0x0b, 0xa2, 0x07, 0x73, 0xbe,
-	0x56, 0x3f, 0x7d, 0x35, 0x29, 0x6c, 0xbd, 0xb6, 0xe7, 0x1e, 0xe6, 0xe6, 0x29, 0x5f, 0x08, 0xae,
-	0x63, 0xb2, 0xb2, 0xe9, 0x73, 0xcc, 0x4a, 0xfb, 0x78, 0x58, 0x0d, 0x04, 0x14, 0x0d, 0xef, 0x7f,
-	0x2c, 0x9e, 0x83, 0x19, 0x99, 0x67, 0x8a, 0xfc, 0x97, 0x04, 0xb2, 0x97, 0x14, 0x0c, 0xbe, 0x5e,
-	0x87, 0x71, 0x42, 0x7d, 0xc5, 0xe3, 0xd4, 0x87, 0xc7, 0x20, 0x49, 0xfa, 0x98, 0x1a, 0xc1, 0x38,
-	0xe1, 0x2b, 0xf2, 0xd9, 0x75, 0x34, 0x5c, 0x02, 0x69, 0xcc, 0x73, 0x83, 0xb7, 0x05, 0x2d, 0xa0,
-	0x96, 0x39, 0xb6, 0x72, 0x25, 0xc7, 0xea, 0x20, 0x31, 0xe8, 0xdb, 0x82, 0x00, 0xd1, 0xff, 0x4e,
-	0x80, 0x30, 0x15, 0x66, 0x40, 0xb4, 0xc7, 0x3a, 0x82, 0x5a, 0x69, 0x14, 0x88, 0x8f, 0x7f, 0x95,
-	0x00, 0x58, 0x3c, 0x79, 0xf0, 0x21, 0x48, 0x1e, 0x69, 0x75, 0x65, 0x57, 0xd5, 0x94, 0x7a, 0x26,
-	0x92, 0xbb, 0x37, 0x1a, 0x17, 0xdf, 0x59, 0xb8, 0x8f, 0x3c, 0x1b, 0x9f, 0x3a, 0x1e, 0xb6, 0x61,
-	0x11, 0xc4, 0xb5, 0x46, 0xb5, 0x51, 0x3f, 0xc9, 0x48, 0xb9, 0xf5, 0xd1, 0xb8, 0x98, 0x59, 0x04,
-	0x69, 0xc4, 0x24, 0xb6, 0x0f, 0x37, 0x40, 0xba, 0xa1, 0x7d, 0x79, 0xa2, 0x57, 0xea, 0x75, 0xa4,
-	0xb4, 0x5a, 0x99, 0x95, 0xdc, 0xfd, 0xd1, 0xb8, 0xf8, 0xee, 0x22, 0xae, 0xe1, 0xb9, 0x7e, 0xc8,
-	0xfe, 0xa0, 0xac, 0x72, 0xac, 0xa0, 0x13, 0x81, 0x18, 0xfd, 0x77, 0x59, 0xe5, 0x1c, 0x53, 0x3f,
-	0x00, 0xcd, 0xad, 0xfd, 0xf0, 0x4b, 0x3e, 0xf2, 0xfc, 0x69, 0x3e, 0xf2, 0xf8, 0x59, 0x14, 0x14,
-	0xaf, 0x5b, 0x32, 0xc4, 0x60, 0xab, 0xd6, 0xd0, 0xda, 0xa8, 0x52, 0x6b, 0xeb, 0xb5, 0x46, 0x5d,
-	0xd1, 0xf7, 0xd5, 0x56, 0xbb, 0x81, 0x4e, 0xf4, 0x46, 0x53, 0x41, 0x95, 0xb6, 0xda, 0xd0, 0xf4,
-	0xf6, 0x49, 0x53, 0xd1, 0x8f, 0xb4, 0x56, 0x53, 0xa9, 0xa9, 0xbb, 0xaa, 0x18, 0xba, 0x3c, 0x1a,
-	0x17, 0x37, 0xae, 0xc3, 0x3e, 0xf2, 0x58, 0x1f, 0x5b, 0xce, 0xa9, 0x83, 0x6d, 0xf8, 0x15, 0xf8,
-	0xe8, 0x46, 0x65, 0x54, 0x4d, 0x6d, 0x67, 0xa4, 0xdc, 0xa3, 0xd1, 0xb8, 0xf8, 0xc1, 0x75, 0xf8,
-	0xaa, 0xe7, 0x70, 0xf8, 0x2d, 0xf8, 0xf8, 0x46, 0xc0, 0x87, 0xea, 0x1e, 0xaa, 0xb4, 0x95, 0xcc,
-	0x4a, 0x6e, 0x63, 0x34, 0x2e, 0x7e, 0x78, 0x1d, 0xf6, 0xa1, 0xd3, 0xa1, 0x06, 0xc7, 0x37, 0x86,
-	0xdf, 0x53, 0x34, 0xa5, 0xa5, 0xb6, 0x32, 0xd1, 0x9b, 0xc1, 0xef, 0x61, 0x0f, 0x33, 0x87, 0xe5,
-	0x62, 0xc1, 0xb1, 0xaa, 0xdf, 0xbc, 0xf8, 0x33, 0x1f, 0x79, 0x3e, 0xcd, 0x4b, 0x2f, 0xa6, 0x79,
-	0xe9, 0xe5, 0x34, 0x2f, 0xfd, 0x31, 0xcd, 0x4b, 0x3f, 0x5e, 0xe4, 0x23, 0x2f, 0x2f, 0xf2, 0x91,
-	0xdf, 0x2f, 0xf2, 0x91, 0xaf, 0x77, 0x96, 0xbe, 0x60, 0x66, 0x51, 0xee, 0x1a, 0x26, 0x2b, 0xb7,
-	0x04, 0xb9, 0x35, 0xcc, 0xbf, 0x23, 0xf4, 0xac, 0x3c, 0xbc, 0xfc, 0x6d, 0xe0, 0x78, 0x1c, 0x53,
-	0xcf, 0x70, 0x67, 0x2f, 0xa8, 0x19, 0x17, 0xff, 0xef, 0x3f, 0xf9, 0x27, 0x00, 0x00, 0xff, 0xff,
-	0x7f, 0x72, 0x40, 0x8b, 0x43, 0x08, 0x00, 0x00,
+	// 1113 bytes of a gzipped FileDescriptorProto
+	0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0xad, 0x56, 0xcd, 0x6f, 0x1b, 0x45,
+	0x14, 0x8f, 0x63, 0xc7, 0xb1, 0xc7, 0x6e, 0x62, 0x86, 0xd0, 0xba, 0x46, 0x4a, 0xc2, 0x16, 0xb5,
+	0x21, 0x21, 0x71, 0x52, 0x38, 0xa0, 0x70, 0xf2, 0xc7, 0x26, 0x59, 0x42, 0xd6, 0xd6, 0xd8, 0x49,
+	0x15, 0x04, 0x5a, 0xed, 0xc7, 0xc4, 0x5e, 0x65, 0xbd, 0x63, 0xed, 0xce, 0x06, 0x9b, 0x13, 0x47,
+	0xc4, 0x89, 0x23, 0x17, 0x24, 0x24, 0xaa, 0x8a, 0x7f, 0x80, 0x7f, 0x80, 0x53, 0x8f, 0x3d, 0x72,
+	0x8a, 0xa0, 0xfc, 0x01, 0x48, 0x1c, 0x7b, 0xe2, 0xed, 0xec, 0xfa, 0xa3, 0xb4, 0x51, 0x82, 0xc4,
+	0x61, 0xe5, 0x79, 0x6f, 0xde, 0xfb, 0xbd, 0xaf, 0xdf, 0x3c, 0x19, 0x49, 0x3e, 0x35, 0x3d, 0xca,
+	0xcb, 0x26, 0xeb, 0xf5, 0x03, 0x4e, 0xcb, 0x17, 0x3b, 0x06, 0xe5, 0xfa, 0x4e, 0x99, 0x0f, 0xfb,
+	0xd4, 0xdf, 0xea, 0x7b, 0x8c, 0x33, 0x7c, 0x3b, 0xb2, 0xd9, 0x8a, 0x6d, 0xb6, 0x62, 0x9b, 0xd2,
+	0x52, 0x87, 0x75, 0x98, 0x30, 0x29, 0x87, 0xa7, 0xc8, 0x5a, 0x32, 0xd1, 0x62, 0xc5, 0x34, 0xa9,
+	0xef, 0xb7, 0x01, 0xa2, 0xa9, 0x7b, 0x7a, 0x0f, 0x7f, 0x82, 0xe6, 0x2e, 0x74, 0x27, 0xa0, 0xc5,
+	0xc4, 0x6a, 0x62, 0x6d, 0xe1, 0xa1, 0xb4, 0xf5, 0x7a, 0xc0, 0xad, 0x89, 0x5f, 0xb5, 0xf0, 0xf7,
+	0xe5, 0x4a, 0x7e, 0xa8, 0xf7, 0x9c, 0x5d, 0x49, 0xb8, 0x4a, 0x24, 0x82, 0xd8, 0x4d, 0x7d, 0xff,
+	0xe3, 0x4a, 0x42, 0x7a, 0x92, 0x40, 0x99, 0x1a, 0xb3, 0xa8, 0xe2, 0x9e, 0x31, 0xfc, 0x36, 0xca,
+	0x9a, 0x70, 0xd6, 0xba, 0xba, 0xdf, 0x15, 0x21, 0xf2, 0x24, 0x13, 0x2a, 0x0e, 0x40, 0xc6, 0x87,
+	0x68, 0x1e, 0x62, 0xe9, 0x9c, 0x79, 0xc5, 0xd9, 0xf0, 0xaa, 0xba, 0xf3, 0xe2, 0x72, 0x65, 0xb3,
+	0x63, 0xf3, 0x6e, 0x60, 0x84, 0x09, 0x40, 0xe5, 0x7e, 0x8f, 0xf9, 0xf1, 0xcf, 0xa6, 0x6f, 0x9d,
+	0xc7, 0xb5, 0x43, 0x32, 0x15, 0xcb, 0xf2, 0x20, 0x21, 0x32, 0x42, 0xc0, 0xb7, 0x51, 0xda, 0x67,
+	0x81, 0x67, 0xd2, 0x62, 0x12, 0xb0, 0xb2, 0x24, 0x96, 0x70, 0x11, 0xcd, 0x1b, 0x81, 0xed, 0x58,
+	0xd4, 0x2b, 0xa6, 0xc4, 0xc5, 0x48, 0x94, 0x1e, 0x27, 0x50, 0xae, 0xc6, 0x5c, 0xee, 0xe9, 0x26,
+	0x3f, 0xa4, 0x43, 0x7c, 0x1f, 0x2d, 0xb2, 0x8e, 0x66, 0xc6, 0x1a, 0xed, 0x9c, 0x0e, 0xe3, 0x8c,
+	0x6f, 0xb1, 0xce, 0xb4, 0xdd, 0x36, 0x5a, 0x32, 0x03, 0xcf, 0xa3, 0x2e, 0x7f, 0xd9, 0x58, 0xd4,
+	0x40, 0x70, 0x7c, 0x37, 0xed, 0xf1, 0x31, 0x2a, 0xbd, 0xce, 0x43, 0x83, 0xa9, 0xb0, 0x33, 0x91,
+	0x6f, 0x9e, 0xdc, 0x79, 0xd5, 0xaf, 0x19, 0x5e, 0x4b, 0x5f, 0x27, 0x10, 0x1e, 0x29, 0x6b, 0x81,
+	0xcf, 0x59, 0x4f, 0x74, 0xb6, 0x8d, 0x72, 0xd4, 0x35, 0x1d, 0xfd, 0x82, 0x8e, 0x33, 0xcd, 0x3d,
+	0xbc, 0x77, 0xd5, 0xf8, 0xa6, 0x50, 0xab, 0x0b, 0xcf, 0x2f, 0x57, 0x90, 0x1c, 0xf9, 0x82, 0x4c,
+	0x10, 0x1d, 0x9f, 0xf1, 0x12, 0x9a, 0x73, 0x74, 0x83, 0x3a, 0xa2, 0x98, 0x2c, 0x89, 0x04, 0xe9,
+	0xd7, 0x59, 0x94, 0x1f, 0x21, 0x88, 0xe0, 0xf7, 0x60, 0x72, 0xe1, 0x58, 0x6d, 0x4b, 0x04, 0x4e,
+	0x55, 0x11, 0x60, 0xa6, 0xc5, 0xd4, 0xeb, 0x24, 0x1d, 0x5e, 0x29, 0xd6, 0xff, 0x3b, 0xde, 0x71,
+	0x62, 0xa9, 0xa9, 0xc4, 0x70, 0x3d, 0x0e, 0x41, 0xad, 0xe2, 0x9c, 0x68, 0xc0, 0xfa, 0x95, 0xfc,
+	0x35, 0x7c, 0xe6, 0x80, 0xa2, 0x3d, 0x68, 0x32, 0xdf, 0xe6, 0x36, 0x73, 0xc9, 0xc8, 0x15, 0x6f,
+	0xa2, 0x9c, 0x6d, 0x98, 0x5a, 0x9f, 0x79, 0x3c, 0xac, 0x28, 0x1d, 0x46, 0xa8, 0xde, 0x82, 0x8a,
+	0xb2, 0x4a, 0xb5, 0xd6, 0x04, 0x2d, 0x14, 0x95, 0x05, 0x0b, 0x71, 0xb4, 0xc2, 0x54, 0x74, 0xab,
+	0x67, 0xbb, 0xc5, 0xf9, 0x28, 0x15, 0x21, 0xe0, 0x15, 0x94, 0x13, 0x87, 0x78, 0xa8, 0x19, 0x31,
+	0x54, 0x24, 0x54, 0xd1, 0x1c, 0x09, 0xc2, 0xaf, 0x26, 0x81, 0xdf, 0x41, 0x79, 0xc3, 0x61, 0xe6,
+	0xb9, 0xd6, 0xa5, 0x76, 0xa7, 0xcb, 0x45, 0x3b, 0x93, 0x24, 0x27, 0x74, 0x07, 0x42, 0x85, 0xef,
+	0xa2, 0x0c, 0x1f, 0x68, 0xb6, 0x6b, 0xd1, 0x81, 0x68, 0x64, 0x8a, 0xcc, 0xf3, 0x81, 0x12, 0x8a,
+	0x12, 0x45, 0x73, 0x47, 0xd0, 0x6c, 0x07, 0xef, 0xa1, 0xe4, 0xe1, 0x88, 0xaf, 0xd5, 0x0f, 0xa1,
+	0xcf, 0xdb, 0x2f, 0xf5, 0xb9, 0x47, 0xb9, 0x71, 0xc6, 0x27, 0x07, 0xc7, 0x36, 0xfc, 0xb2, 0x31,
+	0xe4, 0xd0, 0xec, 0x03, 0x3a, 0xa8, 0x86, 0x07, 0x92, 0x8c, 0xe7, 0x7f, 0x22, 0xd6, 0x41, 0x44,
+	0xe6, 0x48, 0x90, 0xfe, 0x4a, 0xa0, 0xe2, 0x98, 0x82, 0xe1, 0xeb, 0xb5, 0x81, 0x86, 0xde, 0x50,
+	0x06, 0xcd, 0x10, 0x9f, 0xa0, 0x2c, 0xeb, 0x53, 0x4f, 0x0f, 0xcb, 0x89, 0xb7, 0xc8, 0x47, 0xd7,
+	0xd1, 0x70, 0x0a, 0xa4, 0x31, 0xf2, 0x0d, 0x77, 0x0b, 0x99, 0x40, 0x4d, 0x73, 0x6c, 0xf6, 0x4a,
+	0x8e, 0x01, 0x01, 0x82, 0xbe, 0x25, 0x08, 0x90, 0xfc, 0xef, 0x04, 0x88, 0x5d, 0x71, 0x01, 0x25,
+	0x7b, 0x7e, 0x47, 0x50, 0x2b, 0x4f, 0xc2, 0xa3, 0xf4, 0x0b, 0x54, 0xdc, 0xe2, 0x70, 0x59, 0xd5,
+	0xcd, 0xf3, 0xa0, 0x5f, 0x09, 0x78, 0x97, 0x79, 0xf6, 0x57, 0x51, 0x66, 0x25, 0x94, 0x19, 0x3d,
+	0x63, 0x51, 0x70, 0x96, 0x8c, 0x65, 0xbc, 0x8e, 0xde, 0xa0, 0x83, 0xbe, 0x0d, 0xe4, 0xd5, 0x74,
+	0x3e, 0x1a, 0x6a, 0x34, 0xb5, 0xc5, 0xf8, 0xa2, 0xc2, 0xe3, 0xc1, 0xae, 0xa1, 0x42, 0x4c, 0x99,
+	0xc0, 0x70, 0x6c, 0x53, 0xbc, 0xe3, 0x68, 0x19, 0x2c, 0x44, 0xbc, 0x11, 0xea, 0x70, 0x2c, 0x0f,
+	0xd0, 0x62, 0x64, 0xe9, 0xdb, 0x1d, 0x57, 0xe7, 0x81, 0x47, 0xe3, 0x64, 0x23, 0xc3, 0xd6, 0x48,
+	0xbb, 0x0e, 0x79, 0xa3, 0xc9, 0xaa, 0x86, 0x95, 0x96, 0x3d, 0x56, 0xeb, 0xf2, 0x9e, 0xa2, 0xca,
+	0xf5, 0xc2, 0x4c, 0xe9, 0xce, 0xb7, 0x3f, 0xac, 0xbe, 0x39, 0xb9, 0x3e, 0x06, 0x0e, 0x9d, 0xd9,
+	0x2e, 0x34, 0x60, 0x15, 0xa5, 0xd5, 0x46, 0xb5, 0x51, 0x3f, 0x2d, 0x24, 0x4a, 0x4b, 0x60, 0x54,
+	0x98, 0x18, 0xa9, 0xcc, 0x60, 0xd6, 0x10, 0x6f, 0xa0, 0x7c, 0x43, 0xfd, 0xf4, 0x54, 0xab, 0xd4,
+	0xeb, 0x44, 0x6e, 0xb5, 0x0a, 0xb3, 0xa5, 0xbb, 0x60, 0xf7, 0xd6, 0xc4, 0xae, 0xe1, 0x3a, 0xc3,
+	0xf8, 0xd5, 0x86, 0x61, 0xe5, 0x13, 0x99, 0x9c, 0x0a, 0xc4, 0xe4, 0xbf, 0xc3, 0xca, 0x17, 0xd4,
+	0x1b, 0x86, 0xa0, 0xa5, 0xcc, 0x37, 0x3f, 0x2d, 0xcf, 0xfc, 0xfc, 0x78, 0x79, 0x66, 0xfd, 0x49,
+	0x12, 0xad, 0x5e, 0x47, 0x0e, 0x4c, 0xd1, 0x76, 0xad, 0xa1, 0xb6, 0x49, 0xa5, 0xd6, 0xd6, 0x6a,
+	0x8d, 0xba, 0xac, 0x1d, 0x28, 0xad, 0x76, 0x83, 0x9c, 0x6a, 0x8d, 0xa6, 0x4c, 0x2a, 0x6d, 0xa5,
+	0xa1, 0x6a, 0xed, 0xd3, 0xa6, 0xac, 0x1d, 0xab, 0xad, 0xa6, 0x5c, 0x53, 0xf6, 0x14, 0x51, 0x74,
+	0x19, 0xa2, 0x6f, 0x5c, 0x87, 0x7d, 0xec, 0xfa, 0x7d, 0x6a, 0xda, 0x67, 0x36, 0x34, 0xe3, 0x11,
+	0x7a, 0xef, 0x46, 0x61, 0x14, 0x55, 0x69, 0x43, 0xbf, 0xd6, 0x00, 0xff, 0xdd, 0xeb, 0xf0, 0x15,
+	0xd7, 0xe6, 0xf8, 0x0b, 0xf4, 0xfe, 0x8d, 0x80, 0x8f, 0x94, 0x7d, 0x10, 0x65, 0xe8, 0xf1, 0x06,
+	0x60, 0x3f, 0xb8, 0x0e, 0xfb, 0xc8, 0xee, 0x80, 0x40, 0x6f, 0x0c, 0xbf, 0x2f, 0xab, 0x72, 0x4b,
+	0x69, 0xc1, 0x60, 0x6e, 0x04, 0xbf, 0x4f, 0x5d, 0xea, 0xdb, 0x7e, 0x29, 0x15, 0x0e, 0xab, 0xfa,
+	0xf9, 0xd3, 0x3f, 0x60, 0x64, 0xcf, 0x97, 0x13, 0x4f, 0xe1, 0x7b, 0x06, 0xdf, 0xef, 0xf0, 0x7d,
+	0xf7, 0xe7, 0xf2, 0xcc, 0x33, 0xf8, 0x7e, 0x83, 0xef, 0xb3, 0xdd, 0xa9, 0xcd, 0xe3, 0x9b, 0x1e,
+	0x87, 0x4d, 0xed, 0x97, 0x5b, 0xe2, 0x51, 0xaa, 0x94, 0x7f, 0xc9, 0xbc, 0xf3, 0xf2, 0x60, 0xfc,
+	0x9f, 0xc6, 0x76, 0x39, 0xf5, 0x5c, 0xdd, 0x89, 0x36, 0xbf, 0x91, 0x16, 0xff, 0x53, 0x3e, 0xf8,
+	0x07, 0x2a, 0x8f, 0x8f, 0xb8, 0xfb, 0x08, 0x00, 0x00,
 }
 
 func (this *AccessTypeParam) Equal(that interface{}) bool {
@@ -750,6 +801,39 @@ func (this *ContractCodeHistoryEntry) Equal(that interface{}) bool {
 	}
 	return true
 }
+func (this *StateBackupAuthorization) Equal(that interface{}) bool {
+	if that == nil {
+		return this == nil
+	}
+
+	that1, ok := that.(*StateBackupAuthorization)
+	if !ok {
+		that2, ok := that.(StateBackupAuthorization)
+		if ok {
+			that1 = &that2
+		} else {
+			return false
+		}
+	}
+	if that1 == nil {
+		return this == nil
+	} else if this == nil {
+		return false
+	}
+	if this.Contract != that1.Contract {
+		return false
+	}
+	if this.ExpiresAtHeight != that1.ExpiresAtHeight {
+		return false
+	}
+	if !bytes.Equal(this.AdminPublicKey, that1.AdminPublicKey) {
+		return false
+	}
+	if !bytes.Equal(this.AdminSignature, that1.AdminSignature) {
+		return false
+	}
+	return true
+}
 func (m *AccessTypeParam) Marshal() (dAtA []byte, err error) {
 	size := m.Size()
 	dAtA = make([]byte, size)
@@ -1112,6 +1196,55 @@ func (m *ContractCodeHistoryEntry) MarshalToSizedBuffer(dAtA []byte) (int, error
 	return len(dAtA) - i, nil
 }
 
+func (m *StateBackupAuthorization) Marshal() (dAtA []byte, err error) {
+	size := m.Size()
+	dAtA = make([]byte, size)
+	n, err := m.MarshalToSizedBuffer(dAtA[:size])
+	if err != nil {
+		return nil, err
+	}
+	return dAtA[:n], nil
+}
+
+func (m *StateBackupAuthorization) MarshalTo(dAtA []byte) (int, error) {
+	size := m.Size()
+	return m.MarshalToSizedBuffer(dAtA[:size])
+}
+
+func (m *StateBackupAuthorization) MarshalToSizedBuffer(dAtA []byte) (int, error) {
+	i := len(dAtA)
+	_ = i
+	var l int
+	_ = l
+	if len(m.AdminSignature) > 0 {
+		i -= len(m.AdminSignature)
+		copy(dAtA[i:], m.AdminSignature)
+		i = encodeVarintTypes(dAtA, i, uint64(len(m.AdminSignature)))
+		i--
+		dAtA[i] = 0x22
+	}
+	if len(m.AdminPublicKey) > 0 {
+		i -= len(m.AdminPublicKey)
+		copy(dAtA[i:], m.AdminPublicKey)
+		i = encodeVarintTypes(dAtA, i, uint64(len(m.AdminPublicKey)))
+		i--
+		dAtA[i] = 0x1a
+	}
+	if m.ExpiresAtHeight != 0 {
+		i = encodeVarintTypes(dAtA, i, uint64(m.ExpiresAtHeight))
+		i--
+		dAtA[i] = 0x10
+	}
+	if len(m.Contract) > 0 {
+		i -= len(m.Contract)
+		copy(dAtA[i:], m.Contract)
+		i = encodeVarintTypes(dAtA, i, uint64(len(m.Contract)))
+		i--
+		dAtA[i] = 0xa
+	}
+	return len(dAtA) - i, nil
+}
+
 func encodeVarintTypes(dAtA []byte, offset int, v uint64) int {
 	offset -= sovTypes(v)
 	base := offset
@@ -1289,6 +1422,30 @@ func (m *ContractCodeHistoryEntry) Size() (n int) {
 	return n
 }
 
+func (m *StateBackupAuthorization) Size() (n int) {
+	if m == nil {
+		return 0
+	}
+	var l int
+	_ = l
+	l = len(m.Contract)
+	if l > 0 {
+		n += 1 + l + sovTypes(uint64(l))
+	}
+	if m.ExpiresAtHeight != 0 {
+		n += 1 + sovTypes(uint64(m.ExpiresAtHeight))
+	}
+	l = len(m.AdminPublicKey)
+	if l > 0 {
+		n += 1 + l + sovTypes(uint64(l))
+	}
+	l = len(m.AdminSignature)
+	if l > 0 {
+		n += 1 + l + sovTypes(uint64(l))
+	}
+	return n
+}
+
 func sovTypes(x uint64) (n int) {
 	return (math_bits.Len64(x|1) + 6) / 7
 }
@@ -2449,6 +2606,175 @@ func (m *ContractCodeHistoryEntry) Unmarshal(dAtA []byte) error {
 	}
 	return nil
 }
+func (m *StateBackupAuthorization) Unmarshal(dAtA []byte) error {
+	l := len(dAtA)
+	iNdEx := 0
+	for iNdEx < l {
+		preIndex := iNdEx
+		var wire uint64
+		for shift := uint(0); ; shift += 7 {
+			if shift >= 64 {
+				return ErrIntOverflowTypes
+			}
+			if iNdEx >= l {
+				return io.ErrUnexpectedEOF
+			}
+			b := dAtA[iNdEx]
+			iNdEx++
+			wire |= uint64(b&0x7F) << shift
+			if b < 0x80 {
+				break
+			}
+		}
+		fieldNum := int32(wire >> 3)
+		wireType := int(wire & 0x7)
+		if wireType == 4 {
+			return fmt.Errorf("proto: StateBackupAuthorization: wiretype end group for non-group")
+		}
+		if fieldNum <= 0 {
+			return fmt.Errorf("proto: StateBackupAuthorization: illegal tag %d (wire type %d)", fieldNum, wire)
+		}
+		switch fieldNum {
+		case 1:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field Contract", wireType)
+			}
+			var stringLen uint64
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowTypes
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				stringLen |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			intStringLen := int(stringLen)
+			if intStringLen < 0 {
+				return ErrInvalidLengthTypes
+			}
+			postIndex := iNdEx + intStringLen
+			if postIndex < 0 {
+				return ErrInvalidLengthTypes
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.Contract = string(dAtA[iNdEx:postIndex])
+			iNdEx = postIndex
+		case 2:
+			if wireType != 0 {
+				return fmt.Errorf("proto: wrong wireType = %d for field ExpiresAtHeight", wireType)
+			}
+			m.ExpiresAtHeight = 0
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowTypes
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				m.ExpiresAtHeight |= uint64(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+		case 3:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field AdminPublicKey", wireType)
+			}
+			var byteLen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowTypes
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				byteLen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if byteLen < 0 {
+				return ErrInvalidLengthTypes
+			}
+			postIndex := iNdEx + byteLen
+			if postIndex < 0 {
+				return ErrInvalidLengthTypes
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.AdminPublicKey = append(m.AdminPublicKey[:0], dAtA[iNdEx:postIndex]...)
+			if m.AdminPublicKey == nil {
+				m.AdminPublicKey = []byte{}
+			}
+			iNdEx = postIndex
+		case 4:
+			if wireType != 2 {
+				return fmt.Errorf("proto: wrong wireType = %d for field AdminSignature", wireType)
+			}
+			var byteLen int
+			for shift := uint(0); ; shift += 7 {
+				if shift >= 64 {
+					return ErrIntOverflowTypes
+				}
+				if iNdEx >= l {
+					return io.ErrUnexpectedEOF
+				}
+				b := dAtA[iNdEx]
+				iNdEx++
+				byteLen |= int(b&0x7F) << shift
+				if b < 0x80 {
+					break
+				}
+			}
+			if byteLen < 0 {
+				return ErrInvalidLengthTypes
+			}
+			postIndex := iNdEx + byteLen
+			if postIndex < 0 {
+				return ErrInvalidLengthTypes
+			}
+			if postIndex > l {
+				return io.ErrUnexpectedEOF
+			}
+			m.AdminSignature = append(m.AdminSignature[:0], dAtA[iNdEx:postIndex]...)
+			if m.AdminSignature == nil {
+				m.AdminSignature = []byte{}
+			}
+			iNdEx = postIndex
+		default:
+			iNdEx = preIndex
+			skippy, err := skipTypes(dAtA[iNdEx:])
+			if err != nil {
+				return err
+			}
+			if (skippy < 0) || (iNdEx+skippy) < 0 {
+				return ErrInvalidLengthTypes
+			}
+			if (iNdEx + skippy) > l {
+				return io.ErrUnexpectedEOF
+			}
+			iNdEx += skippy
+		}
+	}
+
+	if iNdEx > l {
+		return io.ErrUnexpectedEOF
+	}
+	return nil
+}
 func skipTypes(dAtA []byte) (n int, err error) {
 	l := len(dAtA)
 	iNdEx := 0
@@ -175,12 +175,38 @@ func (am AppModule) BeginBlock(c context.Context) error {
 		}
 
 		am.keeper.SetRandomSeed(ctx, random, validator_set_evidence)
+
+		// params the enclave can't verify fall back to its strictest defaults
+		if err := am.keeper.PushComputeParams(ctx); err != nil {
+			ctx.Logger().Error("Failed to push compute params", "error", err)
+		}
+
+		am.keeper.CreatePendingForeignClients(ctx)
+		am.keeper.PushForeignClients(ctx)
+
+		am.keeper.DisclosePendingContractKeys(ctx)
+		am.keeper.ExecutePendingEncryptedTxs(ctx)
 	} else {
 		ctx.Logger().Debug("Non-encrypted block", "Block_hash", block_header.LastBlockId.Hash, "Height", ctx.BlockHeight(), "Txs", len(x2_data))
 	}
 	return nil
 }
 
+// EndBlock runs the block callbacks of contracts that are due at the end of the block, moves the
+// re-encryption of old state on, exports the enclave's ocall counters, writes the transcript of
+// audit builds and the engine divergences of differential builds, and snapshots the enclave's
+// module cache every few blocks.
+func (am AppModule) EndBlock(c context.Context) error {
+	ctx := c.(sdk.Context)
+	am.keeper.ExecuteCronCallbacks(ctx)
+	am.keeper.ReencryptState(ctx)
+	am.keeper.RecordOcallStats()
+	am.keeper.RecordAuditTranscript(ctx)
+	am.keeper.RecordEngineDivergences(ctx)
+	am.keeper.RecordModuleCacheSnapshot(ctx)
+	return nil
+}
+
 // IsAppModule implements the appmodule.AppModule interface.
 func (AppModule) IsAppModule() {}
 
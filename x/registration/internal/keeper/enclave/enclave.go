@@ -6,14 +6,18 @@ import (
 
 type Api struct{}
 
-func (Api) LoadSeed(masterKey []byte, seed []byte, apiKey []byte) (bool, error) {
-	return api.LoadSeedToEnclave(masterKey, seed, apiKey)
+func (Api) LoadSeed(masterKey []byte, seed []byte, apiKey []byte, chainID string) (bool, error) {
+	return api.LoadSeedToEnclave(masterKey, seed, apiKey, chainID)
 }
 
-func (Api) GetEncryptedSeed(masterCert []byte) ([]byte, error) {
-	return api.GetEncryptedSeed(masterCert)
+func (Api) GetEncryptedSeed(masterCert []byte, issuedHeight uint64) ([]byte, int64, uint32, error) {
+	return api.GetEncryptedSeed(masterCert, issuedHeight)
 }
 
 func (Api) GetEncryptedGenesisSeed(pk []byte) ([]byte, error) {
 	return api.GetEncryptedGenesisSeed(pk)
 }
+
+func (Api) GetSeedHeartbeatKey() ([]byte, error) {
+	return api.GetSeedHeartbeatKey()
+}
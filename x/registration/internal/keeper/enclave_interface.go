@@ -1,7 +1,8 @@
 package keeper
 
 type EnclaveInterface interface {
-	LoadSeed(masterKey []byte, seed []byte, apiKey []byte) (bool, error)
-	GetEncryptedSeed(masterCert []byte) ([]byte, error)
+	LoadSeed(masterKey []byte, seed []byte, apiKey []byte, chainID string) (bool, error)
+	GetEncryptedSeed(masterCert []byte, issuedHeight uint64) ([]byte, int64, uint32, error)
 	GetEncryptedGenesisSeed(pk []byte) ([]byte, error)
+	GetSeedHeartbeatKey() ([]byte, error)
 }
@@ -7,11 +7,13 @@ import (
 	"fmt"
 	"os"
 	"path/filepath"
+	"strconv"
 
 	"cosmossdk.io/core/store"
 	errorsmod "cosmossdk.io/errors"
 	"github.com/cosmos/cosmos-sdk/baseapp"
 	"github.com/cosmos/cosmos-sdk/codec"
+	"github.com/cosmos/cosmos-sdk/telemetry"
 	sdk "github.com/cosmos/cosmos-sdk/types"
 	"github.com/scrtlabs/SecretNetwork/x/registration/internal/types"
 	ra "github.com/scrtlabs/SecretNetwork/x/registration/remote_attestation"
@@ -25,10 +27,11 @@ type Keeper struct {
 	router       baseapp.MessageRouter
 }
 
-// NewKeeper creates a new contract Keeper instance
-func NewKeeper(cdc codec.Codec, storeService store.KVStoreService, router baseapp.MessageRouter, enclave EnclaveInterface, homeDir string, bootstrap bool) Keeper {
+// NewKeeper creates a new contract Keeper instance. chainID is the chain id of the node's
+// genesis, which is passed to the enclave with the node's seeds.
+func NewKeeper(cdc codec.Codec, storeService store.KVStoreService, router baseapp.MessageRouter, enclave EnclaveInterface, homeDir string, chainID string, bootstrap bool) Keeper {
 	if !bootstrap {
-		InitializeNode(homeDir, enclave)
+		InitializeNode(homeDir, chainID, enclave)
 	}
 
 	return Keeper{
@@ -122,7 +125,7 @@ func createOldSecret(key []byte, seedFilePath string, enclave EnclaveInterface)
 	return nil
 }
 
-func InitializeNode(homeDir string, enclave EnclaveInterface) {
+func InitializeNode(homeDir string, chainID string, enclave EnclaveInterface) {
 	apiKey, err := types.GetApiKey()
 	if err != nil {
 		panic(errorsmod.Wrap(types.ErrSeedInitFailed, err.Error()))
@@ -156,7 +159,7 @@ func InitializeNode(homeDir string, enclave EnclaveInterface) {
 
 	// On upgrade LoadSeed will write the new seed to "SeedPath -- seed.txt" which then will be parsed by the upgrade handler to create new_seed.json
 	// On registration both seed.jsםn and new_seed.json will be created by 'secretd q register secret-network-params' on manual flow or by auto-registration flow"
-	_, err = enclave.LoadSeed(pk, sizedEndSeed, apiKey)
+	_, err = enclave.LoadSeed(pk, sizedEndSeed, apiKey, chainID)
 	if err != nil {
 		panic(errorsmod.Wrap(types.ErrSeedInitFailed, err.Error()))
 	}
@@ -207,6 +210,10 @@ func (k Keeper) RegisterNode(ctx sdk.Context, certificate ra.Certificate) ([]byt
 
 		publicKey = publicKey_
 
+		if k.isNodeRevoked(ctx, publicKey) {
+			return nil, errorsmod.Wrap(types.ErrNodeRevoked, hex.EncodeToString(publicKey))
+		}
+
 		isAuth, err := k.isNodeAuthenticated(ctx, publicKey)
 		if err != nil {
 			return nil, errorsmod.Wrap(types.ErrAuthenticateFailed, err.Error())
@@ -215,11 +222,37 @@ func (k Keeper) RegisterNode(ctx sdk.Context, certificate ra.Certificate) ([]byt
 			return k.getRegistrationInfo(ctx, publicKey).EncryptedSeed, nil
 		}
 
-		encSeed, err = k.enclave.GetEncryptedSeed(certificate)
+		var collateralExpiration int64
+		var authFlags uint32
+		encSeed, collateralExpiration, authFlags, err = k.enclave.GetEncryptedSeed(certificate, k.getIssuedHeight(ctx, publicKey))
 		if err != nil {
 			// return 0, errorsmod.Wrap(err, "cosmwasm create")
 			return nil, errorsmod.Wrap(types.ErrAuthenticateFailed, err.Error())
 		}
+
+		err = k.recordIssued(ctx, publicKey)
+		if err != nil {
+			return nil, err
+		}
+
+		reportCollateralExpiration(ctx, publicKey, collateralExpiration)
+		if authFlags&types.AuthFlagEpidDeprecated != 0 {
+			reportEpidDeprecation(ctx, publicKey)
+		}
+		if authFlags&types.AuthFlagCurrentSeedOnly != 0 {
+			ctx.Logger().Info("[+] Registered node received the current seed only", "node_id", fmt.Sprintf("0x%s", hex.EncodeToString(publicKey)))
+		}
+
+		rotatedFrom, err := ra.GetRotatedFromKey(certificate)
+		if err != nil {
+			return nil, errorsmod.Wrap(types.ErrAuthenticateFailed, err.Error())
+		}
+		if rotatedFrom != nil {
+			err = k.rotateNodeKey(ctx, rotatedFrom, publicKey)
+			if err != nil {
+				return nil, err
+			}
+		}
 	}
 
 	regInfo := types.RegistrationNodeInfo{
@@ -243,6 +276,76 @@ func (k Keeper) RegisterNode(ctx sdk.Context, certificate ra.Certificate) ([]byt
 	return encSeed, nil
 }
 
+// reportCollateralExpiration records when the attestation collateral of a newly registered node
+// expires, and emits an attestation_expiring event if that's within CollateralExpiryWarningPeriod
+// of the block time. EPID attestations have no collateral, and report an expiration of 0.
+func reportCollateralExpiration(ctx sdk.Context, publicKey []byte, collateralExpiration int64) {
+	if collateralExpiration == 0 {
+		return
+	}
+
+	nodeID := fmt.Sprintf("0x%s", hex.EncodeToString(publicKey))
+	remaining := collateralExpiration - ctx.BlockTime().Unix()
+
+	telemetry.SetGauge(float32(remaining), types.ModuleName, "collateral", nodeID, "seconds_remaining")
+
+	if remaining > int64(types.CollateralExpiryWarningPeriod.Seconds()) {
+		return
+	}
+
+	ctx.Logger().Info("[!] Registered node relies on soon to expire collateral", "node_id", nodeID, "collateral_expiration", collateralExpiration)
+
+	ctx.EventManager().EmitEvent(
+		sdk.NewEvent(
+			EventTypeAttestationExpiring,
+			sdk.NewAttribute(sdk.AttributeKeyModule, types.ModuleName),
+			sdk.NewAttribute(AttributeNodeID, nodeID),
+			sdk.NewAttribute(AttributeCollateralExpiration, strconv.FormatInt(collateralExpiration, 10)),
+		),
+	)
+}
+
+// reportEpidDeprecation emits an epid_deprecated event for a node that registered with an EPID
+// attestation, which the enclave will reject from the cutoff height governance scheduled
+func reportEpidDeprecation(ctx sdk.Context, publicKey []byte) {
+	nodeID := fmt.Sprintf("0x%s", hex.EncodeToString(publicKey))
+
+	ctx.Logger().Info("[!] Registered node uses a deprecated EPID attestation, it has to move to DCAP before the cutoff", "node_id", nodeID)
+
+	ctx.EventManager().EmitEvent(
+		sdk.NewEvent(
+			EventTypeEpidDeprecated,
+			sdk.NewAttribute(sdk.AttributeKeyModule, types.ModuleName),
+			sdk.NewAttribute(AttributeNodeID, nodeID),
+		),
+	)
+}
+
+// rotateNodeKey revokes the previous key of a node that registered a rotated key. The enclave only
+// attests a rotation with the previous key when it holds that key, so this can't revoke the key of
+// another node.
+func (k Keeper) rotateNodeKey(ctx sdk.Context, previousKey []byte, publicKey []byte) error {
+	err := k.revokeNode(ctx, previousKey, publicKey)
+	if err != nil {
+		return err
+	}
+
+	previousNodeID := fmt.Sprintf("0x%s", hex.EncodeToString(previousKey))
+	nodeID := fmt.Sprintf("0x%s", hex.EncodeToString(publicKey))
+
+	ctx.Logger().Info("[+] Node key rotated", "previous_node_id", previousNodeID, "node_id", nodeID)
+
+	ctx.EventManager().EmitEvent(
+		sdk.NewEvent(
+			EventTypeNodeKeyRotated,
+			sdk.NewAttribute(sdk.AttributeKeyModule, types.ModuleName),
+			sdk.NewAttribute(AttributeNodeID, nodeID),
+			sdk.NewAttribute(AttributePreviousNodeID, previousNodeID),
+		),
+	)
+	return nil
+}
+
 // returns true when simulation mode used by gas=auto queries
 func isSimulationMode(ctx sdk.Context) bool {
 	return ctx.GasMeter().Limit() == 0 && ctx.BlockHeight() != 0
@@ -90,3 +90,51 @@ func TestKeeper_RegisterNode(t *testing.T) {
 	_, err = regKeeper.RegisterNode(ctx, cert)
 	require.NoError(t, err)
 }
+
+func TestKeeper_RevokeNode(t *testing.T) {
+	tempDir, err := os.MkdirTemp("", "wasm")
+	require.NoError(t, err)
+	defer os.RemoveAll(tempDir)
+	ctx, regKeeper := CreateTestInput(t, false, tempDir, true)
+
+	cert, err := os.ReadFile("../../testdata/attestation_cert_sw")
+	require.NoError(t, err)
+
+	regInfo := types.RegistrationNodeInfo{
+		Certificate:   cert,
+		EncryptedSeed: []byte("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"),
+	}
+
+	err = regKeeper.SetRegistrationInfo(ctx, regInfo)
+	require.NoError(t, err)
+
+	publicKey, err := ra.VerifyRaCert(regInfo.Certificate)
+	require.NoError(t, err)
+	require.False(t, regKeeper.isNodeRevoked(ctx, publicKey))
+
+	err = regKeeper.revokeNode(ctx, publicKey, make([]byte, 32))
+	require.NoError(t, err)
+
+	require.Nil(t, regKeeper.getRegistrationInfo(ctx, publicKey))
+	require.True(t, regKeeper.isNodeRevoked(ctx, publicKey))
+}
+
+func TestKeeper_RecordIssued(t *testing.T) {
+	tempDir, err := os.MkdirTemp("", "wasm")
+	require.NoError(t, err)
+	defer os.RemoveAll(tempDir)
+	ctx, regKeeper := CreateTestInput(t, false, tempDir, true)
+
+	publicKey := types.NodeID(make([]byte, 32))
+	require.Equal(t, uint64(0), regKeeper.getIssuedHeight(ctx, publicKey))
+
+	// CheckTx and simulations don't record anything
+	require.NoError(t, regKeeper.recordIssued(ctx.WithBlockHeight(100).WithIsCheckTx(true), publicKey))
+	require.NoError(t, regKeeper.recordIssued(ctx.WithBlockHeight(100).WithExecMode(sdk.ExecModeSimulate), publicKey))
+	require.Equal(t, uint64(0), regKeeper.getIssuedHeight(ctx, publicKey))
+
+	// only the first height is kept
+	require.NoError(t, regKeeper.recordIssued(ctx.WithBlockHeight(100), publicKey))
+	require.NoError(t, regKeeper.recordIssued(ctx.WithBlockHeight(200), publicKey))
+	require.Equal(t, uint64(100), regKeeper.getIssuedHeight(ctx, publicKey))
+}
@@ -1,17 +1,29 @@
 package mock
 
+import (
+	"bytes"
+	"crypto/ed25519"
+)
+
+// SeedHeartbeatKey stands for the key the enclave derives from the seed to sign heartbeats
+var SeedHeartbeatKey = ed25519.NewKeyFromSeed(bytes.Repeat([]byte{7}, ed25519.SeedSize))
+
 // To be able to run unit tests without needing the enclave
 
 type MockEnclaveApi struct{} 
 
-func (MockEnclaveApi) LoadSeed(_ []byte, _ []byte, _ []byte) (bool, error) {
+func (MockEnclaveApi) LoadSeed(_ []byte, _ []byte, _ []byte, _ string) (bool, error) {
 	return true, nil
 }
 
-func (MockEnclaveApi) GetEncryptedSeed(_ []byte) ([]byte, error) {
-	return []byte(""), nil
+func (MockEnclaveApi) GetEncryptedSeed(_ []byte, _ uint64) ([]byte, int64, uint32, error) {
+	return []byte(""), 0, 0, nil
 }
 
 func (MockEnclaveApi) GetEncryptedGenesisSeed(_ []byte) ([]byte, error) {
 	return []byte(""), nil
 }
+
+func (MockEnclaveApi) GetSeedHeartbeatKey() ([]byte, error) {
+	return SeedHeartbeatKey.Public().(ed25519.PublicKey), nil
+}
@@ -12,9 +12,16 @@ import (
 )
 
 const (
-	AttributeSigner        = "signer"
-	AttributeEncryptedSeed = "encrypted_seed"
-	AttributeNodeID        = "node_id"
+	AttributeSigner               = "signer"
+	AttributeEncryptedSeed        = "encrypted_seed"
+	AttributeNodeID               = "node_id"
+	AttributeCollateralExpiration = "collateral_expiration"
+	AttributePreviousNodeID       = "previous_node_id"
+	AttributeNodeRole             = "node_role"
+
+	EventTypeAttestationExpiring = "attestation_expiring"
+	EventTypeNodeKeyRotated      = "node_key_rotated"
+	EventTypeEpidDeprecated      = "epid_deprecated"
 )
 
 var _ types.MsgServer = msgServer{}
@@ -41,6 +48,11 @@ func (m msgServer) RegisterAuth(goCtx context.Context, msg *types.RaAuthenticate
 		return nil, err
 	}
 
+	role, err := ra.GetNodeRole(msg.Certificate)
+	if err != nil {
+		return nil, err
+	}
+
 	encSeed, err := m.keeper.RegisterNode(ctx, msg.Certificate)
 	if err != nil {
 		return nil, err
@@ -53,6 +65,7 @@ func (m msgServer) RegisterAuth(goCtx context.Context, msg *types.RaAuthenticate
 			sdk.NewAttribute(AttributeSigner, msg.Sender.String()),
 			sdk.NewAttribute(AttributeEncryptedSeed, fmt.Sprintf("0x%02x", encSeed)),
 			sdk.NewAttribute(AttributeNodeID, fmt.Sprintf("0x%s", hex.EncodeToString(pubkey))),
+			sdk.NewAttribute(AttributeNodeRole, role.String()),
 		),
 	})
 
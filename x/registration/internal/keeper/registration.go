@@ -75,6 +75,63 @@ func (k Keeper) SetRegistrationInfo_Verified(ctx sdk.Context, certificate types.
 	return nil
 }
 
+// revokeNode removes the registration of a node that rotated its key to replacedBy, and records
+// the key as revoked so it can't register again
+func (k Keeper) revokeNode(ctx sdk.Context, publicKey types.NodeID, replacedBy types.NodeID) error {
+	store := k.storeService.OpenKVStore(ctx)
+
+	err := store.Delete(types.RegistrationKeyPrefix(publicKey))
+	if err != nil {
+		ctx.Logger().Error("delete registration info", "store", err.Error())
+		return err
+	}
+
+	err = store.Set(types.RevokedNodeKeyPrefix(publicKey), replacedBy)
+	if err != nil {
+		ctx.Logger().Error("set revoked node", "store", err.Error())
+		return err
+	}
+	return nil
+}
+
+// getIssuedHeight returns the height the enclave first issued seeds to a node's key at, or 0 if
+// it never did. The enclave applies the duplicate registration policy to it, see
+// docs/duplicate-registrations.md.
+func (k Keeper) getIssuedHeight(ctx sdk.Context, publicKey types.NodeID) uint64 {
+	store := k.storeService.OpenKVStore(ctx)
+	bz, _ := store.Get(types.IssuedKeyPrefix(publicKey))
+	if bz == nil {
+		return 0
+	}
+	return sdk.BigEndianToUint64(bz)
+}
+
+// recordIssued records that the enclave issued seeds to a node's key at the current height, unless
+// it did before. Only registrations in a block are recorded, not those in CheckTx or simulations,
+// so every node passes the enclave the same heights.
+func (k Keeper) recordIssued(ctx sdk.Context, publicKey types.NodeID) error {
+	if ctx.IsCheckTx() || ctx.IsReCheckTx() || ctx.ExecMode() == sdk.ExecModeSimulate {
+		return nil
+	}
+	if k.getIssuedHeight(ctx, publicKey) != 0 {
+		return nil
+	}
+
+	store := k.storeService.OpenKVStore(ctx)
+	err := store.Set(types.IssuedKeyPrefix(publicKey), sdk.Uint64ToBigEndian(uint64(ctx.BlockHeight())))
+	if err != nil {
+		ctx.Logger().Error("set issued key", "store", err.Error())
+		return err
+	}
+	return nil
+}
+
+func (k Keeper) isNodeRevoked(ctx sdk.Context, publicKey types.NodeID) bool {
+	store := k.storeService.OpenKVStore(ctx)
+	revoked, _ := store.Has(types.RevokedNodeKeyPrefix(publicKey))
+	return revoked
+}
+
 func (k Keeper) isNodeAuthenticated(ctx sdk.Context, publicKey types.NodeID) (bool, error) {
 	regInfo := k.getRegistrationInfo(ctx, publicKey)
 	if regInfo == nil {
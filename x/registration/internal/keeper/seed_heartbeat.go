@@ -0,0 +1,102 @@
+package keeper
+
+import (
+	"crypto/ed25519"
+	"encoding/hex"
+	"fmt"
+	"strconv"
+
+	errorsmod "cosmossdk.io/errors"
+	"cosmossdk.io/store/prefix"
+	"github.com/cosmos/cosmos-sdk/runtime"
+	sdk "github.com/cosmos/cosmos-sdk/types"
+	"github.com/scrtlabs/SecretNetwork/x/registration/internal/types"
+)
+
+const (
+	AttributeHeartbeatHeight = "heartbeat_height"
+
+	EventTypeSeedHeartbeat = "seed_heartbeat"
+)
+
+// seedHeartbeatDomain separates seed heartbeats from anything else signed with keys of the seed
+var seedHeartbeatDomain = []byte("secret-seed-heartbeat")
+
+// SeedHeartbeatSignBytes returns the bytes a seed heartbeat of the node at height signs
+func SeedHeartbeatSignBytes(height int64, publicKey types.NodeID) []byte {
+	signBytes := append([]byte{}, seedHeartbeatDomain...)
+	signBytes = append(signBytes, sdk.Uint64ToBigEndian(uint64(height))...)
+	return append(signBytes, publicKey...)
+}
+
+// RecordSeedHeartbeat verifies a seed heartbeat of a registered node, and records its height as
+// the last one the node proved it holds the seed at. The heartbeat is verified against the key the
+// local enclave derives from the seed, which every node with the seed derives alike.
+func (k Keeper) RecordSeedHeartbeat(ctx sdk.Context, publicKey types.NodeID, height int64, signature []byte) error {
+	nodeID := fmt.Sprintf("0x%s", hex.EncodeToString(publicKey))
+
+	if k.getRegistrationInfo(ctx, publicKey) == nil {
+		return errorsmod.Wrapf(types.ErrNotFound, "node %s isn't registered", nodeID)
+	}
+
+	if height > ctx.BlockHeight() || height <= ctx.BlockHeight()-types.SeedHeartbeatMaxAge {
+		return errorsmod.Wrapf(types.ErrSeedHeartbeatInvalid, "height %d isn't within %d blocks of %d", height, types.SeedHeartbeatMaxAge, ctx.BlockHeight())
+	}
+
+	if last, found := k.GetLastSeedHeartbeat(ctx, publicKey); found && height <= last {
+		return errorsmod.Wrapf(types.ErrSeedHeartbeatInvalid, "node %s already sent a heartbeat at height %d", nodeID, last)
+	}
+
+	heartbeatKey, err := k.enclave.GetSeedHeartbeatKey()
+	if err != nil {
+		return errorsmod.Wrap(types.ErrSeedHeartbeatInvalid, err.Error())
+	}
+
+	if len(heartbeatKey) != ed25519.PublicKeySize || !ed25519.Verify(heartbeatKey, SeedHeartbeatSignBytes(height, publicKey), signature) {
+		return errorsmod.Wrapf(types.ErrSeedHeartbeatInvalid, "the signature of node %s doesn't verify", nodeID)
+	}
+
+	store := k.storeService.OpenKVStore(ctx)
+	err = store.Set(types.SeedHeartbeatKeyPrefix(publicKey), sdk.Uint64ToBigEndian(uint64(height)))
+	if err != nil {
+		ctx.Logger().Error("set seed heartbeat", "store", err.Error())
+		return err
+	}
+
+	ctx.EventManager().EmitEvent(
+		sdk.NewEvent(
+			EventTypeSeedHeartbeat,
+			sdk.NewAttribute(sdk.AttributeKeyModule, types.ModuleName),
+			sdk.NewAttribute(AttributeNodeID, nodeID),
+			sdk.NewAttribute(AttributeHeartbeatHeight, strconv.FormatInt(height, 10)),
+		),
+	)
+	return nil
+}
+
+// GetLastSeedHeartbeat returns the height of the last seed heartbeat recorded for the node
+func (k Keeper) GetLastSeedHeartbeat(ctx sdk.Context, publicKey types.NodeID) (int64, bool) {
+	store := k.storeService.OpenKVStore(ctx)
+	bz, _ := store.Get(types.SeedHeartbeatKeyPrefix(publicKey))
+	if bz == nil {
+		return 0, false
+	}
+	return int64(sdk.BigEndianToUint64(bz)), true
+}
+
+// ListStaleRegistrations returns the registered nodes that haven't sent a seed heartbeat since
+// minHeight, including those that never sent one. They're the candidates for being pruned.
+func (k Keeper) ListStaleRegistrations(ctx sdk.Context, minHeight int64) []types.NodeID {
+	var stale []types.NodeID
+	prefixStore := prefix.NewStore(runtime.KVStoreAdapter(k.storeService.OpenKVStore(ctx)), types.RegistrationStorePrefix)
+	iter := prefixStore.Iterator(nil, nil)
+	defer iter.Close()
+	for ; iter.Valid(); iter.Next() {
+		publicKey := append(types.NodeID{}, iter.Key()...)
+		last, found := k.GetLastSeedHeartbeat(ctx, publicKey)
+		if !found || last < minHeight {
+			stale = append(stale, publicKey)
+		}
+	}
+	return stale
+}
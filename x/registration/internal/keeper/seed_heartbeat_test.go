@@ -0,0 +1,67 @@
+package keeper
+
+import (
+	"crypto/ed25519"
+	"os"
+	"testing"
+
+	registrationmock "github.com/scrtlabs/SecretNetwork/x/registration/internal/keeper/mock"
+	"github.com/scrtlabs/SecretNetwork/x/registration/internal/types"
+	ra "github.com/scrtlabs/SecretNetwork/x/registration/remote_attestation"
+	"github.com/stretchr/testify/require"
+)
+
+func TestKeeper_SeedHeartbeat(t *testing.T) {
+	tempDir, err := os.MkdirTemp("", "wasm")
+	require.NoError(t, err)
+	defer os.RemoveAll(tempDir)
+	ctx, regKeeper := CreateTestInput(t, false, tempDir, true)
+	ctx = ctx.WithBlockHeight(500)
+
+	cert, err := os.ReadFile("../../testdata/attestation_cert_sw")
+	require.NoError(t, err)
+
+	err = regKeeper.SetRegistrationInfo(ctx, types.RegistrationNodeInfo{
+		Certificate:   cert,
+		EncryptedSeed: []byte("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"),
+	})
+	require.NoError(t, err)
+
+	publicKey, err := ra.VerifyRaCert(cert)
+	require.NoError(t, err)
+
+	sign := func(height int64) []byte {
+		return ed25519.Sign(registrationmock.SeedHeartbeatKey, SeedHeartbeatSignBytes(height, publicKey))
+	}
+
+	require.Equal(t, []types.NodeID{publicKey}, regKeeper.ListStaleRegistrations(ctx, 0))
+
+	err = regKeeper.RecordSeedHeartbeat(ctx, publicKey, 450, sign(450))
+	require.NoError(t, err)
+	last, found := regKeeper.GetLastSeedHeartbeat(ctx, publicKey)
+	require.True(t, found)
+	require.Equal(t, int64(450), last)
+	require.Empty(t, regKeeper.ListStaleRegistrations(ctx, 400))
+	require.Equal(t, []types.NodeID{publicKey}, regKeeper.ListStaleRegistrations(ctx, 451))
+
+	// A heartbeat only holds for the height it signed
+	err = regKeeper.RecordSeedHeartbeat(ctx, publicKey, 460, sign(459))
+	require.ErrorIs(t, err, types.ErrSeedHeartbeatInvalid)
+
+	// Heartbeats can't go back, be too old, or come from the future
+	err = regKeeper.RecordSeedHeartbeat(ctx, publicKey, 450, sign(450))
+	require.ErrorIs(t, err, types.ErrSeedHeartbeatInvalid)
+	err = regKeeper.RecordSeedHeartbeat(ctx, publicKey, 400, sign(400))
+	require.ErrorIs(t, err, types.ErrSeedHeartbeatInvalid)
+	err = regKeeper.RecordSeedHeartbeat(ctx, publicKey, 501, sign(501))
+	require.ErrorIs(t, err, types.ErrSeedHeartbeatInvalid)
+
+	// A key that wasn't derived from the seed doesn't verify
+	staleKey := ed25519.NewKeyFromSeed(make([]byte, ed25519.SeedSize))
+	err = regKeeper.RecordSeedHeartbeat(ctx, publicKey, 490, ed25519.Sign(staleKey, SeedHeartbeatSignBytes(490, publicKey)))
+	require.ErrorIs(t, err, types.ErrSeedHeartbeatInvalid)
+
+	// Only registered nodes send heartbeats
+	err = regKeeper.RecordSeedHeartbeat(ctx, make([]byte, 32), 490, sign(490))
+	require.ErrorIs(t, err, types.ErrNotFound)
+}
@@ -26,4 +26,10 @@ var (
 	ErrNotFound = errors.Register(DefaultCodespace, 7, "not found")
 
 	ErrInvalid = errors.Register(DefaultCodespace, 8, "invalid")
+
+	// ErrNodeRevoked error when a node registers with a key that was rotated out
+	ErrNodeRevoked = errors.Register(DefaultCodespace, 9, "Node key was revoked")
+
+	// ErrSeedHeartbeatInvalid error when a seed heartbeat can't be verified
+	ErrSeedHeartbeatInvalid = errors.Register(DefaultCodespace, 10, "Invalid seed heartbeat")
 )
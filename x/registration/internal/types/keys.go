@@ -20,12 +20,27 @@ const (
 var (
 	RegistrationStorePrefix     = []byte{0x01}
 	RegistrationMasterKeyPrefix = []byte{0x02}
+	RevokedNodeStorePrefix      = []byte{0x03}
+	SeedHeartbeatStorePrefix    = []byte{0x04}
+	IssuedKeyStorePrefix        = []byte{0x05}
 )
 
 func RegistrationKeyPrefix(key []byte) []byte {
 	return append(RegistrationStorePrefix, key...)
 }
 
+func RevokedNodeKeyPrefix(key []byte) []byte {
+	return append(RevokedNodeStorePrefix, key...)
+}
+
+func SeedHeartbeatKeyPrefix(key []byte) []byte {
+	return append(SeedHeartbeatStorePrefix, key...)
+}
+
+func IssuedKeyPrefix(key []byte) []byte {
+	return append(IssuedKeyStorePrefix, key...)
+}
+
 func MasterKeyPrefix(key string) []byte {
 	return append(RegistrationMasterKeyPrefix, []byte(key)...)
 }
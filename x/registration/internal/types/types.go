@@ -3,6 +3,7 @@ package types
 import (
 	"encoding/base64"
 	"encoding/hex"
+	"time"
 )
 
 const (
@@ -30,6 +31,21 @@ const (
 	AttestationCombinedPath = "attestation_combined.bin"
 )
 
+// CollateralExpiryWarningPeriod is how close to expiring the attestation collateral of a
+// registering node has to be for an attestation_expiring event to be emitted
+const CollateralExpiryWarningPeriod = 30 * 24 * time.Hour
+
+// AuthFlagEpidDeprecated is set by the enclave on registrations with an EPID attestation, after
+// governance scheduled the height from which EPID attestations are rejected
+const AuthFlagEpidDeprecated uint32 = 1
+
+// AuthFlagCurrentSeedOnly is set by the enclave on registrations that only received the current
+// seed, once the seed rotation is active. The genesis seed in the encrypted seed is all zeros.
+const AuthFlagCurrentSeedOnly uint32 = 2
+
+// SeedHeartbeatMaxAge is how many blocks old a seed heartbeat can be when it's recorded
+const SeedHeartbeatMaxAge = 100
+
 type NodeID []byte
 
 func (c SeedConfig) Decode() ([]byte, []byte, error) {
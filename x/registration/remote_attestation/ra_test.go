@@ -1,6 +1,8 @@
 package remote_attestation
 
 import (
+	"bytes"
+	"encoding/binary"
 	"os"
 	"testing"
 
@@ -71,3 +73,52 @@ func Test_FuzzCrashers(t *testing.T) {
 		_, _ = VerifyRaCert(f)
 	}
 }
+
+func combinedDcapCert(t *testing.T, quote DcapQuote) []byte {
+	var buf bytes.Buffer
+	hdr := CombinedHdr{M_CombinedSizes: [3]uint32{0, uint32(binary.Size(quote)), 0}}
+	require.NoError(t, binary.Write(&buf, binary.LittleEndian, hdr))
+	require.NoError(t, binary.Write(&buf, binary.LittleEndian, quote))
+	return buf.Bytes()
+}
+
+func Test_GetRotatedFromKey(t *testing.T) {
+	var quote DcapQuote
+	quote.M_PubKey[0] = 1
+
+	rotatedFrom, err := GetRotatedFromKey(combinedDcapCert(t, quote))
+	require.NoError(t, err)
+	require.Nil(t, rotatedFrom)
+
+	quote.M_RotatedFrom[0] = 2
+
+	rotatedFrom, err = GetRotatedFromKey(combinedDcapCert(t, quote))
+	require.NoError(t, err)
+	require.Equal(t, quote.M_RotatedFrom[:], rotatedFrom)
+
+	pk, err := VerifyCombinedCert(combinedDcapCert(t, quote))
+	require.NoError(t, err)
+	require.Equal(t, quote.M_PubKey[:], pk)
+}
+
+func Test_GetNodeRole(t *testing.T) {
+	var quote DcapQuote
+	quote.M_PubKey[0] = 1
+
+	role, err := GetNodeRole(combinedDcapCert(t, quote))
+	require.NoError(t, err)
+	require.Equal(t, NodeRoleValidator, role)
+
+	quote.M_IsvProdID = uint16(NodeRoleOracle)
+	quote.M_RotatedFrom[0] = 2
+
+	role, err = GetNodeRole(combinedDcapCert(t, quote))
+	require.NoError(t, err)
+	require.Equal(t, NodeRoleOracle, role)
+	require.Equal(t, "oracle", role.String())
+
+	// Service enclaves can't rotate keys
+	rotatedFrom, err := GetRotatedFromKey(combinedDcapCert(t, quote))
+	require.NoError(t, err)
+	require.Nil(t, rotatedFrom)
+}
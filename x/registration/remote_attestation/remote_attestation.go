@@ -20,25 +20,29 @@ type CombinedHdr struct {
 }
 
 type DcapQuote struct {
-	M_Opaque1 [48]byte  // sgx_quote_t up to report_body
-	M_Opaque2 [320]byte // sgx_report_body_t up to report_ata
-	M_PubKey  [32]byte
-	M_Opaque3 [32]byte // remaining 32 bytes of report_data
-	M_SigLen  uint32
+	M_Opaque1     [48]byte  // sgx_quote_t up to report_body
+	M_Opaque2     [256]byte // sgx_report_body_t up to isv_prod_id
+	M_IsvProdID   uint16    // the role of the enclave, see NodeRole
+	M_Opaque3     [62]byte  // sgx_report_body_t up to report_data
+	M_PubKey      [32]byte
+	M_RotatedFrom [32]byte // remaining 32 bytes of report_data, the previous key of a node that rotated its key
+	M_SigLen      uint32
 }
 
-func VerifyCombinedCert(blob []byte) ([]byte, error) {
+// splitCombinedCert returns the EPID certificate and the DCAP quote of a combined certificate.
+// Either one may be empty.
+func splitCombinedCert(blob []byte) ([]byte, []byte, error) {
 	var hdr CombinedHdr
 
 	if uintptr(len(blob)) < unsafe.Sizeof(hdr) {
-		return nil, errors.New("Combined hdr too small")
+		return nil, nil, errors.New("Combined hdr too small")
 	}
 
 	{
 		buf := bytes.NewReader(blob)
 		err := binary.Read(buf, binary.LittleEndian, &hdr)
 		if err != nil {
-			return nil, err
+			return nil, nil, err
 		}
 	}
 
@@ -48,22 +52,40 @@ func VerifyCombinedCert(blob []byte) ([]byte, error) {
 	idx3 := idx2 + uintptr(hdr.M_CombinedSizes[2])
 
 	if uintptr(len(blob)) < idx3 {
-		return nil, errors.New("combined hdr invalid")
+		return nil, nil, errors.New("combined hdr invalid")
+	}
+
+	return blob[idx0:idx1], blob[idx1:idx2], nil
+}
+
+func parseDcapQuote(rawQuote []byte) (*DcapQuote, error) {
+	var quote DcapQuote
+
+	buf := bytes.NewReader(rawQuote)
+	err := binary.Read(buf, binary.LittleEndian, &quote)
+	if err != nil {
+		return nil, err
+	}
+
+	return &quote, nil
+}
+
+func VerifyCombinedCert(blob []byte) ([]byte, error) {
+	epidCert, dcapQuote, err := splitCombinedCert(blob)
+	if err != nil {
+		return nil, err
 	}
 
-	if idx1 > idx0 {
-		ret_pk, ret_err := VerifyRaCert(blob[idx0:idx1])
+	if len(epidCert) > 0 {
+		ret_pk, ret_err := VerifyRaCert(epidCert)
 		if ret_pk != nil {
 			fmt.Println("EPID quote Extracted pk: ", hex.EncodeToString(ret_pk))
 		}
 		return ret_pk, ret_err
 	}
 
-	if idx2 > idx1 {
-		var quote DcapQuote
-
-		buf := bytes.NewReader(blob[idx1:idx2])
-		err := binary.Read(buf, binary.LittleEndian, &quote)
+	if len(dcapQuote) > 0 {
+		quote, err := parseDcapQuote(dcapQuote)
 		if err != nil {
 			return nil, err
 		}
@@ -75,6 +97,84 @@ func VerifyCombinedCert(blob []byte) ([]byte, error) {
 	return nil, errors.New("No valid attestatoin found")
 }
 
+// NodeRole is the role of a registered enclave. It's the ISV product ID the enclave was signed
+// with, so an enclave can only register with a role it was signed for.
+type NodeRole uint16
+
+const (
+	NodeRoleValidator NodeRole = 0
+	NodeRoleOracle    NodeRole = 1
+	NodeRoleJobWorker NodeRole = 2
+	NodeRoleEscrow    NodeRole = 3
+)
+
+func (r NodeRole) String() string {
+	switch r {
+	case NodeRoleValidator:
+		return "validator"
+	case NodeRoleOracle:
+		return "oracle"
+	case NodeRoleJobWorker:
+		return "job_worker"
+	case NodeRoleEscrow:
+		return "escrow"
+	default:
+		return fmt.Sprintf("unknown(%d)", uint16(r))
+	}
+}
+
+// GetNodeRole returns the role of the enclave of a combined certificate. EPID attestations are
+// always of validators. Service enclaves only receive the capability keys of their role, and never
+// the consensus seed.
+//
+// Like VerifyCombinedCert, this doesn't verify the quote, so it should only be trusted once the
+// enclave has authenticated the certificate.
+func GetNodeRole(blob []byte) (NodeRole, error) {
+	epidCert, dcapQuote, err := splitCombinedCert(blob)
+	if err != nil {
+		return 0, err
+	}
+
+	if len(epidCert) > 0 || len(dcapQuote) == 0 {
+		return NodeRoleValidator, nil
+	}
+
+	quote, err := parseDcapQuote(dcapQuote)
+	if err != nil {
+		return 0, err
+	}
+
+	return NodeRole(quote.M_IsvProdID), nil
+}
+
+// GetRotatedFromKey returns the previous public key of a node that rotated its registration key,
+// or nil if the certificate isn't a key rotation. Only DCAP attestations of validators can rotate
+// keys, since service enclaves may use the rest of the report data for something else.
+//
+// Like VerifyCombinedCert, this doesn't verify the quote, so it should only be trusted once the
+// enclave has authenticated the certificate.
+func GetRotatedFromKey(blob []byte) ([]byte, error) {
+	epidCert, dcapQuote, err := splitCombinedCert(blob)
+	if err != nil {
+		return nil, err
+	}
+
+	if len(epidCert) > 0 || len(dcapQuote) == 0 {
+		return nil, nil
+	}
+
+	quote, err := parseDcapQuote(dcapQuote)
+	if err != nil {
+		return nil, err
+	}
+
+	if NodeRole(quote.M_IsvProdID) != NodeRoleValidator || quote.M_RotatedFrom == [32]byte{} {
+		return nil, nil
+	}
+
+	return quote.M_RotatedFrom[:], nil
+}
+
 /*
 	 Verifies the remote attestation certificate, which is comprised of a the attestation report, intel signature, and enclave signature
 